@@ -1,6 +1,8 @@
+use arc_swap::ArcSwap;
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Arc;
 
 // --- Constants for Default Configuration ---
 pub const DEFAULT_STORAGE_COMMIT_INTERVAL_MS: u64 = 5000;
@@ -14,11 +16,29 @@ pub const DEFAULT_STORAGE_RECENT_OVERLAY_PER_USER_MAX_DOCS: usize = 1000;
 pub const DEFAULT_STORAGE_RECENT_OVERLAY_PER_USER_MAX_BYTES: usize = 8_388_608;
 pub const DEFAULT_STORAGE_RECENT_OVERLAY_GLOBAL_MAX_BYTES: usize = 134_217_728;
 pub const DEFAULT_STORAGE_RECENT_OVERLAY_QUERY_LIMIT: usize = 200;
+pub const DEFAULT_STORAGE_INDEX_MERGE_ENABLED: bool = true;
+pub const DEFAULT_STORAGE_INDEX_MERGE_MIN_NUM_SEGMENTS: usize = 8;
+pub const DEFAULT_STORAGE_INDEX_MERGE_MAX_DOCS_BEFORE_MERGE: usize = 10_000_000;
+pub const DEFAULT_STORAGE_INDEX_MERGE_MIN_LAYER_SIZE: u32 = 10_000;
+pub const DEFAULT_STORAGE_INDEX_MERGE_LEVEL_LOG_SIZE: f64 = 0.75;
+pub const DEFAULT_STORAGE_INDEX_MERGE_DEL_DOCS_RATIO: f32 = 1.0;
+pub const DEFAULT_STORAGE_INDEX_SYNC_MIN_INTERVAL_MS: u64 = 100;
+pub const DEFAULT_STORAGE_INDEX_USER_BUCKETS: usize = 1;
+pub const MAX_STORAGE_INDEX_USER_BUCKETS: usize = 256;
+pub const DEFAULT_STORAGE_POSTGRES_TABLE: &str = "memorose_kv";
+pub const DEFAULT_STORAGE_POSTGRES_POOL_SIZE: usize = 4;
+
+pub const DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES: u32 = 2;
+pub const DEFAULT_LLM_REQUESTS_PER_MINUTE: u32 = 0;
+pub const DEFAULT_LLM_MAX_CONCURRENCY: usize = 16;
+pub const DEFAULT_LLM_RATE_LIMIT_RETRIES: u32 = 3;
 
 pub const DEFAULT_RAFT_HEARTBEAT_INTERVAL_MS: u64 = 500;
 pub const DEFAULT_RAFT_ELECTION_TIMEOUT_MIN_MS: u64 = 1500;
 pub const DEFAULT_RAFT_ELECTION_TIMEOUT_MAX_MS: u64 = 3000;
 pub const DEFAULT_RAFT_SNAPSHOT_LOGS: u64 = 1000000;
+pub const DEFAULT_RAFT_READ_INDEX_WAIT_MS: u64 = 2000;
+pub const DEFAULT_RAFT_READY_MAX_LAG: u64 = 100;
 
 pub const DEFAULT_WORKER_LLM_CONCURRENCY: usize = 5;
 pub const DEFAULT_WORKER_DECAY_INTERVAL_SECS: u64 = 60;
@@ -48,6 +68,60 @@ pub const DEFAULT_WORKER_INSIGHT_MAX_L1_PER_BATCH: usize = 32;
 pub const DEFAULT_WORKER_INSIGHT_MAX_BATCHES_PER_CYCLE: usize = 4;
 pub const DEFAULT_AUTO_LINK_SIMILARITY_THRESHOLD: f32 = 0.6;
 pub const DEFAULT_WORKER_TICK_INTERVAL_MS: u64 = 100;
+pub const DEFAULT_WORKER_KEYWORD_EXTRACTION_ENABLED: bool = true;
+pub const DEFAULT_WORKER_KEYWORD_MAX_PER_MEMORY: usize = 8;
+pub const DEFAULT_WORKER_PROFILE_INTERVAL_MS: u64 = 300_000;
+pub const DEFAULT_WORKER_PROFILE_MAX_USERS_PER_CYCLE: usize = 20;
+pub const DEFAULT_WORKER_PROFILE_MAX_INSIGHTS: usize = 50;
+pub const DEFAULT_WORKER_PROFILE_MAX_MEMORIES: usize = 50;
+pub const DEFAULT_WORKER_TASK_DEADLINE_INTERVAL_MS: u64 = 60_000;
+pub const DEFAULT_WORKER_TASK_DEADLINE_WARNING_MS: u64 = 3_600_000;
+pub const DEFAULT_WORKER_AUTO_PLANNER_MAX_SUBTASKS: usize = 5;
+pub const DEFAULT_WORKER_AUTO_PLANNER_MAX_DEPTH: usize = 5;
+pub const DEFAULT_WORKER_AUTO_PLANNER_REQUIRE_APPROVAL: bool = false;
+pub const DEFAULT_WORKER_TRASH_RETENTION_DAYS: u32 = 30;
+pub const DEFAULT_WORKER_AUDIO_CHUNK_SECS: u64 = 300;
+pub const DEFAULT_WORKER_VIDEO_SEGMENT_SECS: u64 = 30;
+pub const DEFAULT_WORKER_COMMUNITY_RESUMMARIZE_THRESHOLD: f32 = 0.3;
+pub const DEFAULT_WORKER_L2_REFRESH_INTERVAL_MS: u64 = 3_600_000;
+pub const DEFAULT_WORKER_INSIGHT_MIN_REFLECT_L1: usize = 2;
+pub const DEFAULT_WORKER_INSIGHT_MIN_REFLECT_TOKENS: usize = 64;
+pub const DEFAULT_WORKER_INSIGHT_MAX_LLM_CALLS_PER_CYCLE: usize = 32;
+pub const DEFAULT_WORKER_CONSOLIDATION_USER_WORKERS: usize = 4;
+pub const DEFAULT_WORKER_SYNC_CONSOLIDATION_BUDGET_MS: u64 = 10_000;
+pub const DEFAULT_WORKER_CONSOLIDATION_MAX_EVENTS_PER_USER: usize = 64;
+pub const DEFAULT_WORKER_CACHE_WARMUP_USERS: usize = 32;
+pub const DEFAULT_WORKER_CACHE_WARMUP_NODES_PER_USER: usize = 64;
+pub const DEFAULT_WORKER_INTEGRITY_CHECK_INTERVAL_SECS: u64 = 3600;
+pub const DEFAULT_WORKER_INTEGRITY_BATCH_SIZE: usize = 500;
+pub const DEFAULT_WORKER_INTEGRITY_REPAIR: bool = true;
+pub const DEFAULT_WORKER_INTEGRITY_REPAIR_ORPHANED_EDGES: bool = false;
+pub const DEFAULT_WORKER_ORPHAN_GC_INTERVAL_SECS: u64 = 86400;
+pub const DEFAULT_WORKER_ORPHAN_GC_DRY_RUN: bool = false;
+pub const DEFAULT_WORKER_ORPHAN_GC_EDGES: bool = false;
+pub const DEFAULT_WORKER_EXPORT_INTERVAL_SECS: u64 = 3600;
+pub const DEFAULT_WORKER_EXPORT_MAX_ROWS_PER_FILE: usize = 100_000;
+pub const DEFAULT_WORKER_STREAM_SEGMENTATION_ENABLED: bool = false;
+pub const DEFAULT_WORKER_STREAM_SEGMENT_GAP_SECS: u64 = 1800;
+pub const DEFAULT_WORKER_STREAM_SEGMENT_TOPIC_OVERLAP: f32 = 0.05;
+pub const DEFAULT_WORKER_STREAM_SEGMENT_MIN_EVENTS: usize = 4;
+pub const DEFAULT_WORKER_LOCAL_SUMMARIZATION_ENABLED: bool = false;
+pub const DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_CHARS: usize = 1500;
+pub const DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_ENTROPY: f32 = 4.8;
+pub const DEFAULT_WORKER_CONSOLIDATION_CHUNK_OVERLAP_TOKENS: usize = 128;
+pub const DEFAULT_WORKER_SELF_EVAL_INTERVAL_SECS: u64 = 0;
+pub const DEFAULT_WORKER_SELF_EVAL_SAMPLE_SIZE: usize = 20;
+pub const DEFAULT_WORKER_SELF_EVAL_TOP_K: usize = 5;
+pub const DEFAULT_WORKER_STORAGE_USAGE_INTERVAL_SECS: u64 = 3600;
+pub const DEFAULT_WORKER_IMPORTANCE_SCORING_ENABLED: bool = true;
+pub const DEFAULT_WORKER_IMPORTANCE_FLOOR: f32 = 0.2;
+pub const DEFAULT_WORKER_EMOTION_TAGGING_ENABLED: bool = false;
+pub const DEFAULT_WORKER_JOB_LEASE_SECS: u64 = 300;
+pub const DEFAULT_WORKER_JOB_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_WORKER_JOB_RETRY_BACKOFF_SECS: u64 = 60;
+pub const DEFAULT_WORKER_RUN_HISTORY_MAX_RECORDS: usize = 1000;
+pub const DEFAULT_RERANKER_RECENCY_HALF_LIFE_HOURS: f64 = 24.0;
+pub const DEFAULT_RERANKER_RRF_K: f32 = 60.0;
 pub const DEFAULT_VECTOR_ENABLED: bool = true;
 pub const DEFAULT_VECTOR_DEGRADE_ON_STARTUP_FAILURE: bool = true;
 pub const DEFAULT_VECTOR_STARTUP_TIMEOUT_SECS: u64 = 10;
@@ -57,6 +131,41 @@ pub const DEFAULT_VECTOR_SCHEMA_VERSION: u32 = 2;
 pub const DEFAULT_VECTOR_IO_CORE_RESERVATION: u32 = 0;
 pub const DEFAULT_VECTOR_CPU_THREADS: u32 = 1;
 pub const DEFAULT_VECTOR_IO_THREADS: u32 = 1;
+pub const DEFAULT_VECTOR_PGVECTOR_TABLE_PREFIX: &str = "memorose_";
+pub const DEFAULT_ADMISSION_SHED_LINKING_PENDING: usize = 5_000;
+pub const DEFAULT_ADMISSION_THROTTLE_PENDING: usize = 20_000;
+pub const DEFAULT_ADMISSION_REJECT_PENDING: usize = 50_000;
+pub const DEFAULT_ADMISSION_RETRY_AFTER_SECS: u64 = 5;
+pub const DEFAULT_ADMISSION_SAMPLE_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 1_000;
+pub const DEFAULT_SLOW_QUERY_CAPACITY: usize = 200;
+pub const DEFAULT_TRAFFIC_RECORDING_SAMPLE_RATE: f64 = 0.0;
+pub const DEFAULT_TRAFFIC_RECORDING_MAX_RECORDS: usize = 10_000;
+pub const DEFAULT_DISK_WATCHDOG_CHECK_INTERVAL_SECS: u64 = 30;
+pub const DEFAULT_DISK_WATCHDOG_WARN_FREE_PERCENT: f64 = 10.0;
+pub const DEFAULT_DISK_WATCHDOG_READ_ONLY_FREE_PERCENT: f64 = 5.0;
+pub const DEFAULT_TEMPORAL_QUERY_ENABLED: bool = true;
+pub const DEFAULT_TEMPORAL_QUERY_UTC_OFFSET_MINUTES: i32 = 0;
+/// UTC offsets in use range from UTC-12:00 to UTC+14:00.
+pub const MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES: i32 = -12 * 60;
+pub const MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+pub const DEFAULT_EDGE_DECAY_ENABLED: bool = false;
+pub const DEFAULT_EDGE_DECAY_INTERVAL_SECS: u64 = 86_400;
+pub const DEFAULT_EDGE_DECAY_HALF_LIFE_DAYS: f64 = 30.0;
+pub const DEFAULT_EDGE_DECAY_PRUNE_BELOW: f32 = 0.3;
+pub const DEFAULT_EDGE_DECAY_MIN_IDLE_DAYS: u64 = 14;
+pub const DEFAULT_CONSOLIDATION_POOL_LEASE_SECS: u64 = 300;
+pub const DEFAULT_CONSOLIDATION_POOL_POLL_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_CACHE_REDIS_KEY_PREFIX: &str = "memorose:";
+pub const DEFAULT_CACHE_EMBEDDING_TTL_SECS: u64 = 86_400;
+pub const DEFAULT_CACHE_DASHBOARD_TTL_SECS: u64 = 300;
+pub const DEFAULT_CACHE_PERSIST_EMBEDDINGS: bool = true;
+pub const DEFAULT_CACHE_PERSISTED_EMBEDDING_MAX_ENTRIES: usize = 100_000;
+pub const DEFAULT_REPLICATION_BATCH_SIZE: usize = 256;
+pub const DEFAULT_REPLICATION_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_LINKING_SEMANTIC_ENABLED: bool = true;
+pub const DEFAULT_LINKING_MAX_RELATION_CALLS_PER_MINUTE: u32 = 0;
+pub const DEFAULT_LINKING_DEFERRED_BATCH_SIZE: usize = 64;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum LLMProvider {
@@ -80,12 +189,40 @@ pub struct LLMConfig {
     pub embedding_task_type: Option<String>,
     pub stt_provider: Option<LLMProvider>,
     pub stt_model: Option<String>,
+    /// Extra attempts when a JSON reply fails schema validation
+    #[serde(default = "default_structured_output_retries")]
+    pub structured_output_retries: u32,
+    /// Token-bucket rate shared by every call to this provider; 0 leaves it unlimited
+    #[serde(default = "default_llm_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Ceiling for the adaptive limit on calls in flight to this provider
+    #[serde(default = "default_llm_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Retries of a call the provider throttled (429 or 503)
+    #[serde(default = "default_llm_rate_limit_retries")]
+    pub rate_limit_retries: u32,
 }
 
 fn default_embedding_dim() -> i32 {
     3072 // default for gemini-embedding-2
 }
 
+fn default_structured_output_retries() -> u32 {
+    DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES
+}
+
+fn default_llm_requests_per_minute() -> u32 {
+    DEFAULT_LLM_REQUESTS_PER_MINUTE
+}
+
+fn default_llm_max_concurrency() -> usize {
+    DEFAULT_LLM_MAX_CONCURRENCY
+}
+
+fn default_llm_rate_limit_retries() -> u32 {
+    DEFAULT_LLM_RATE_LIMIT_RETRIES
+}
+
 impl LLMConfig {
     pub fn get_base_url(&self) -> Option<String> {
         if self.base_url.is_some() {
@@ -98,9 +235,36 @@ impl LLMConfig {
     }
 }
 
+/// Where the key-value metadata (events, units, markers, raft log) lives.
+/// LanceDB and Tantivy always stay under `root_dir`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    #[default]
+    Rocksdb,
+    Postgres,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub root_dir: String,
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Connection string used when `backend = "postgres"`,
+    /// e.g. `host=db.internal user=memorose dbname=memorose`. Its `sslmode` picks
+    /// plain TCP (`disable`) or TLS verified against the system roots (`prefer`, the
+    /// default, or `require`)
+    #[serde(default)]
+    pub postgres_url: Option<String>,
+    /// Prefix of this node's rows when `backend = "postgres"`; must be unique per node
+    /// sharing the table. Each store appends its shard and directory name
+    #[serde(default)]
+    pub postgres_namespace: Option<String>,
+    #[serde(default = "default_postgres_table")]
+    pub postgres_table: String,
+    /// Connections per store; each shard and node opens its own store
+    #[serde(default = "default_postgres_pool_size")]
+    pub postgres_pool_size: usize,
     #[serde(default = "default_commit_interval")]
     pub index_commit_interval_ms: u64,
     #[serde(default = "default_commit_min_interval")]
@@ -123,6 +287,35 @@ pub struct StorageConfig {
     pub recent_overlay_global_max_bytes: usize,
     #[serde(default = "default_recent_overlay_query_limit")]
     pub recent_overlay_query_limit: usize,
+    /// Commit interval per app id. Documents of these apps are committed within this
+    /// many ms, ahead of the thresholds above
+    #[serde(default)]
+    pub index_app_commit_interval_ms: std::collections::BTreeMap<String, u64>,
+    /// Let Tantivy merge segments in the background; off keeps every segment as written
+    #[serde(default = "default_index_merge_enabled")]
+    pub index_merge_enabled: bool,
+    /// Fewest segments of one size level merged together
+    #[serde(default = "default_index_merge_min_num_segments")]
+    pub index_merge_min_num_segments: usize,
+    /// Segments with more docs than this are never merged
+    #[serde(default = "default_index_merge_max_docs_before_merge")]
+    pub index_merge_max_docs_before_merge: usize,
+    /// Segments below this many docs all share the smallest level
+    #[serde(default = "default_index_merge_min_layer_size")]
+    pub index_merge_min_layer_size: u32,
+    /// Log size ratio between merge levels
+    #[serde(default = "default_index_merge_level_log_size")]
+    pub index_merge_level_log_size: f64,
+    /// Share of deleted docs in a segment that makes it a merge candidate on its own
+    #[serde(default = "default_index_merge_del_docs_ratio")]
+    pub index_merge_del_docs_ratio: f32,
+    /// Shortest gap between index commits forced by `consistency: "strong"` reads
+    #[serde(default = "default_index_sync_min_interval_ms")]
+    pub index_sync_min_interval_ms: u64,
+    /// Text indexes per shard, each holding the users hashed to it. A search only reads
+    /// its user's bucket. Changing this rebuilds the index on the next start.
+    #[serde(default = "default_index_user_buckets")]
+    pub index_user_buckets: usize,
 }
 
 fn default_commit_interval() -> u64 {
@@ -169,6 +362,46 @@ fn default_recent_overlay_query_limit() -> usize {
     DEFAULT_STORAGE_RECENT_OVERLAY_QUERY_LIMIT
 }
 
+fn default_index_merge_enabled() -> bool {
+    DEFAULT_STORAGE_INDEX_MERGE_ENABLED
+}
+
+fn default_index_merge_min_num_segments() -> usize {
+    DEFAULT_STORAGE_INDEX_MERGE_MIN_NUM_SEGMENTS
+}
+
+fn default_index_merge_max_docs_before_merge() -> usize {
+    DEFAULT_STORAGE_INDEX_MERGE_MAX_DOCS_BEFORE_MERGE
+}
+
+fn default_index_merge_min_layer_size() -> u32 {
+    DEFAULT_STORAGE_INDEX_MERGE_MIN_LAYER_SIZE
+}
+
+fn default_index_merge_level_log_size() -> f64 {
+    DEFAULT_STORAGE_INDEX_MERGE_LEVEL_LOG_SIZE
+}
+
+fn default_index_merge_del_docs_ratio() -> f32 {
+    DEFAULT_STORAGE_INDEX_MERGE_DEL_DOCS_RATIO
+}
+
+fn default_index_sync_min_interval_ms() -> u64 {
+    DEFAULT_STORAGE_INDEX_SYNC_MIN_INTERVAL_MS
+}
+
+fn default_index_user_buckets() -> usize {
+    DEFAULT_STORAGE_INDEX_USER_BUCKETS
+}
+
+fn default_postgres_table() -> String {
+    DEFAULT_STORAGE_POSTGRES_TABLE.to_string()
+}
+
+fn default_postgres_pool_size() -> usize {
+    DEFAULT_STORAGE_POSTGRES_POOL_SIZE
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaftConfig {
     pub node_id: u64,
@@ -181,12 +414,27 @@ pub struct RaftConfig {
     pub auto_initialize: bool,
     #[serde(default)]
     pub bootstrap_seed_node_id: Option<u32>,
+    /// Longest a read carrying `min_applied_index` waits for that log entry to apply
+    #[serde(default = "default_read_index_wait_ms")]
+    pub read_index_wait_ms: u64,
+    /// `/readyz` fails while any shard has more than this many committed log entries
+    /// left to apply, or is installing a snapshot
+    #[serde(default = "default_ready_max_lag")]
+    pub ready_max_lag: u64,
 }
 
 fn default_auto_initialize() -> bool {
     true
 }
 
+fn default_read_index_wait_ms() -> u64 {
+    DEFAULT_RAFT_READ_INDEX_WAIT_MS
+}
+
+fn default_ready_max_lag() -> u64 {
+    DEFAULT_RAFT_READY_MAX_LAG
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerConfig {
     pub llm_concurrency: usize,
@@ -220,6 +468,392 @@ pub struct WorkerConfig {
     pub enable_task_reflection: bool,
     pub auto_link_similarity_threshold: f32,
     pub tick_interval_ms: u64,
+    #[serde(default = "default_keyword_extraction_enabled")]
+    pub keyword_extraction_enabled: bool,
+    #[serde(default = "default_keyword_max_per_memory")]
+    pub keyword_max_per_memory: usize,
+    #[serde(default = "default_profile_interval_ms")]
+    pub profile_interval_ms: u64,
+    #[serde(default = "default_profile_max_users_per_cycle")]
+    pub profile_max_users_per_cycle: usize,
+    #[serde(default = "default_profile_max_insights")]
+    pub profile_max_insights: usize,
+    #[serde(default = "default_profile_max_memories")]
+    pub profile_max_memories: usize,
+    #[serde(default = "default_task_deadline_interval_ms")]
+    pub task_deadline_interval_ms: u64,
+    /// How far ahead of `due_at` an "approaching" notification is emitted
+    #[serde(default = "default_task_deadline_warning_ms")]
+    pub task_deadline_warning_ms: u64,
+    /// Optional endpoint that receives deadline notifications as JSON POSTs
+    #[serde(default)]
+    pub task_deadline_webhook_url: Option<String>,
+    /// Upper bound on milestones kept from a single goal decomposition
+    #[serde(default = "default_auto_planner_max_subtasks")]
+    pub auto_planner_max_subtasks: usize,
+    /// Goals stored deeper than this in a planning chain are not decomposed
+    #[serde(default = "default_auto_planner_max_depth")]
+    pub auto_planner_max_depth: usize,
+    /// Park generated milestones until the plan is approved through the API
+    #[serde(default = "default_auto_planner_require_approval")]
+    pub auto_planner_require_approval: bool,
+    /// Days a soft-deleted memory stays restorable before it is purged
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Length of the slices long recordings are cut into before transcription
+    #[serde(default = "default_audio_chunk_secs")]
+    pub audio_chunk_secs: u64,
+    /// Optional local transcriber (e.g. whisper.cpp); `{input}` is replaced by the chunk path
+    #[serde(default)]
+    pub audio_transcribe_command: Option<String>,
+    /// Length of the video segments that each get a keyframe description
+    #[serde(default = "default_video_segment_secs")]
+    pub video_segment_secs: u64,
+    /// Embed stored image assets into the joint text+image vector table on publish
+    #[serde(default)]
+    pub asset_embeddings_enabled: bool,
+    /// Fraction of a community's members that must change before its L2 summary is regenerated
+    #[serde(default = "default_community_resummarize_threshold")]
+    pub community_resummarize_threshold: f32,
+    /// How often L2 insights are checked for member drift and regenerated
+    #[serde(default = "default_l2_refresh_interval_ms")]
+    pub l2_refresh_interval_ms: u64,
+    /// A reflection whose delay expired is still skipped while it has fewer new L1 units
+    /// than this and fewer new tokens than `insight_min_reflect_tokens`
+    #[serde(default = "default_insight_min_reflect_l1")]
+    pub insight_min_reflect_l1: usize,
+    #[serde(default = "default_insight_min_reflect_tokens")]
+    pub insight_min_reflect_tokens: usize,
+    /// Topic-extraction LLM calls the insight cycle may make before deferring the rest
+    #[serde(default = "default_insight_max_llm_calls_per_cycle")]
+    pub insight_max_llm_calls_per_cycle: usize,
+    /// Users whose consolidation batches run at once; LLM calls stay capped by `llm_concurrency`
+    #[serde(default = "default_consolidation_user_workers")]
+    pub consolidation_user_workers: usize,
+    /// How long `?mode=sync` ingestion waits for inline consolidation before answering
+    /// and leaving the rest to the background cycle
+    #[serde(default = "default_sync_consolidation_budget_ms")]
+    pub sync_consolidation_budget_ms: u64,
+    /// Apps (agent ids) whose events are stored as written and only embedded, unless an
+    /// event sets `skip_compression` itself
+    #[serde(default)]
+    pub skip_compression_apps: Vec<String>,
+    /// Events a single user may contribute to one consolidation cycle
+    #[serde(default = "default_consolidation_max_events_per_user")]
+    pub consolidation_max_events_per_user: usize,
+    /// Most recently active users whose neighbourhoods are preloaded on startup and on
+    /// leader change; 0 disables cache warm-up
+    #[serde(default = "default_cache_warmup_users")]
+    pub cache_warmup_users: usize,
+    /// One-hop neighbourhoods preloaded per warmed user
+    #[serde(default = "default_cache_warmup_nodes_per_user")]
+    pub cache_warmup_nodes_per_user: usize,
+    /// Seconds between consistency-check batches across KV, vectors, text index and graph;
+    /// 0 disables the checker
+    #[serde(default = "default_integrity_check_interval_secs")]
+    pub integrity_check_interval_secs: u64,
+    /// Memory units verified per consistency-check batch
+    #[serde(default = "default_integrity_batch_size")]
+    pub integrity_batch_size: usize,
+    /// Re-index missing entries and drop orphaned vectors; when false drift is only reported
+    #[serde(default = "default_integrity_repair")]
+    pub integrity_repair: bool,
+    /// Also delete edges whose endpoints are not stored units. Off by default because the
+    /// graph API accepts edges to external ids
+    #[serde(default = "default_integrity_repair_orphaned_edges")]
+    pub integrity_repair_orphaned_edges: bool,
+    /// Seconds between sweeps that remove index, queue and dedup entries left behind by
+    /// deleted units; 0 disables garbage collection
+    #[serde(default = "default_orphan_gc_interval_secs")]
+    pub orphan_gc_interval_secs: u64,
+    /// Report what the sweep would remove without deleting anything
+    #[serde(default = "default_orphan_gc_dry_run")]
+    pub orphan_gc_dry_run: bool,
+    /// Also remove edges whose endpoints are not stored units. Off by default for the same
+    /// reason as `integrity_repair_orphaned_edges`
+    #[serde(default = "default_orphan_gc_edges")]
+    pub orphan_gc_edges: bool,
+    /// Destination for Parquet exports of new memory units and edges, e.g.
+    /// `s3://bucket/memorose`, `gs://bucket/memorose` or `file:///var/exports`;
+    /// unset disables exporting. Credentials come from the usual `AWS_*` / `GOOGLE_*` variables
+    #[serde(default)]
+    pub export_url: Option<String>,
+    /// Seconds between exports
+    #[serde(default = "default_export_interval_secs")]
+    pub export_interval_secs: u64,
+    /// Rows written to a single Parquet file before another is started
+    #[serde(default = "default_export_max_rows_per_file")]
+    pub export_max_rows_per_file: usize,
+    /// Split consolidation of a stream into sessions at long time gaps or topic shifts.
+    /// Boundaries are recorded on the stream record
+    #[serde(default = "default_stream_segmentation_enabled")]
+    pub stream_segmentation_enabled: bool,
+    /// Seconds of silence in a stream that start a new session
+    #[serde(default = "default_stream_segment_gap_secs")]
+    pub stream_segment_gap_secs: u64,
+    /// Keyword overlap with the current session below which an event starts a new one;
+    /// 0 disables topic detection
+    #[serde(default = "default_stream_segment_topic_overlap")]
+    pub stream_segment_topic_overlap: f32,
+    /// Events a session must hold before a topic shift can close it
+    #[serde(default = "default_stream_segment_min_events")]
+    pub stream_segment_min_events: usize,
+    /// Summarize routine batches locally, with `local_llm` or extractively, and send only
+    /// long, dense or multimodal batches to the main LLM
+    #[serde(default = "default_local_summarization_enabled")]
+    pub local_summarization_enabled: bool,
+    /// Batches longer than this many characters go to the main LLM
+    #[serde(default = "default_local_summarization_max_chars")]
+    pub local_summarization_max_chars: usize,
+    /// Batches whose character entropy (bits per character) exceeds this go to the main LLM
+    #[serde(default = "default_local_summarization_max_entropy")]
+    pub local_summarization_max_entropy: f32,
+    /// Tokens repeated between the chunks of a batch longer than
+    /// `consolidation_target_tokens`, which is compressed one chunk per call
+    #[serde(default = "default_consolidation_chunk_overlap_tokens")]
+    pub consolidation_chunk_overlap_tokens: usize,
+    /// Seconds between retrieval self-evaluation runs; 0 disables them
+    #[serde(default = "default_self_eval_interval_secs")]
+    pub self_eval_interval_secs: u64,
+    /// Recent memories a self-evaluation run writes questions for
+    #[serde(default = "default_self_eval_sample_size")]
+    pub self_eval_sample_size: usize,
+    /// A question hits when its memory ranks within this many results
+    #[serde(default = "default_self_eval_top_k")]
+    pub self_eval_top_k: usize,
+    /// Seconds between storage usage accounting runs; 0 disables them
+    #[serde(default = "default_storage_usage_interval_secs")]
+    pub storage_usage_interval_secs: u64,
+    /// Score a new memory's initial importance from its content and novelty instead of 1.0
+    #[serde(default = "default_importance_scoring_enabled")]
+    pub importance_scoring_enabled: bool,
+    /// Lowest initial importance the scorer assigns
+    #[serde(default = "default_importance_floor")]
+    pub importance_floor: f32,
+    /// Tag new memories with the emotions they express, for `emotions` retrieval filters
+    #[serde(default = "default_emotion_tagging_enabled")]
+    pub emotion_tagging_enabled: bool,
+    /// Seconds a claimed reflection, community or profile job stays leased to the worker
+    /// without a heartbeat before it may be claimed again. A new leader claims it sooner.
+    #[serde(default = "default_job_lease_secs")]
+    pub job_lease_secs: u64,
+    /// Failed attempts after which a background job is dropped
+    #[serde(default = "default_job_max_attempts")]
+    pub job_max_attempts: u32,
+    /// Seconds a failed background job waits before it is retried
+    #[serde(default = "default_job_retry_backoff_secs")]
+    pub job_retry_backoff_secs: u64,
+    /// Worker cycle run reports kept on each node; the oldest are dropped past this
+    #[serde(default = "default_run_history_max_records")]
+    pub run_history_max_records: usize,
+}
+
+fn default_keyword_extraction_enabled() -> bool {
+    DEFAULT_WORKER_KEYWORD_EXTRACTION_ENABLED
+}
+
+fn default_keyword_max_per_memory() -> usize {
+    DEFAULT_WORKER_KEYWORD_MAX_PER_MEMORY
+}
+
+fn default_profile_interval_ms() -> u64 {
+    DEFAULT_WORKER_PROFILE_INTERVAL_MS
+}
+
+fn default_profile_max_users_per_cycle() -> usize {
+    DEFAULT_WORKER_PROFILE_MAX_USERS_PER_CYCLE
+}
+
+fn default_profile_max_insights() -> usize {
+    DEFAULT_WORKER_PROFILE_MAX_INSIGHTS
+}
+
+fn default_profile_max_memories() -> usize {
+    DEFAULT_WORKER_PROFILE_MAX_MEMORIES
+}
+
+fn default_task_deadline_interval_ms() -> u64 {
+    DEFAULT_WORKER_TASK_DEADLINE_INTERVAL_MS
+}
+
+fn default_task_deadline_warning_ms() -> u64 {
+    DEFAULT_WORKER_TASK_DEADLINE_WARNING_MS
+}
+
+fn default_auto_planner_max_subtasks() -> usize {
+    DEFAULT_WORKER_AUTO_PLANNER_MAX_SUBTASKS
+}
+
+fn default_auto_planner_max_depth() -> usize {
+    DEFAULT_WORKER_AUTO_PLANNER_MAX_DEPTH
+}
+
+fn default_auto_planner_require_approval() -> bool {
+    DEFAULT_WORKER_AUTO_PLANNER_REQUIRE_APPROVAL
+}
+
+fn default_trash_retention_days() -> u32 {
+    DEFAULT_WORKER_TRASH_RETENTION_DAYS
+}
+
+fn default_audio_chunk_secs() -> u64 {
+    DEFAULT_WORKER_AUDIO_CHUNK_SECS
+}
+
+fn default_video_segment_secs() -> u64 {
+    DEFAULT_WORKER_VIDEO_SEGMENT_SECS
+}
+
+fn default_community_resummarize_threshold() -> f32 {
+    DEFAULT_WORKER_COMMUNITY_RESUMMARIZE_THRESHOLD
+}
+
+fn default_l2_refresh_interval_ms() -> u64 {
+    DEFAULT_WORKER_L2_REFRESH_INTERVAL_MS
+}
+
+fn default_insight_min_reflect_l1() -> usize {
+    DEFAULT_WORKER_INSIGHT_MIN_REFLECT_L1
+}
+
+fn default_insight_min_reflect_tokens() -> usize {
+    DEFAULT_WORKER_INSIGHT_MIN_REFLECT_TOKENS
+}
+
+fn default_insight_max_llm_calls_per_cycle() -> usize {
+    DEFAULT_WORKER_INSIGHT_MAX_LLM_CALLS_PER_CYCLE
+}
+
+fn default_consolidation_user_workers() -> usize {
+    DEFAULT_WORKER_CONSOLIDATION_USER_WORKERS
+}
+
+fn default_sync_consolidation_budget_ms() -> u64 {
+    DEFAULT_WORKER_SYNC_CONSOLIDATION_BUDGET_MS
+}
+
+fn default_consolidation_max_events_per_user() -> usize {
+    DEFAULT_WORKER_CONSOLIDATION_MAX_EVENTS_PER_USER
+}
+
+fn default_cache_warmup_users() -> usize {
+    DEFAULT_WORKER_CACHE_WARMUP_USERS
+}
+
+fn default_cache_warmup_nodes_per_user() -> usize {
+    DEFAULT_WORKER_CACHE_WARMUP_NODES_PER_USER
+}
+
+fn default_integrity_check_interval_secs() -> u64 {
+    DEFAULT_WORKER_INTEGRITY_CHECK_INTERVAL_SECS
+}
+
+fn default_integrity_batch_size() -> usize {
+    DEFAULT_WORKER_INTEGRITY_BATCH_SIZE
+}
+
+fn default_integrity_repair() -> bool {
+    DEFAULT_WORKER_INTEGRITY_REPAIR
+}
+
+fn default_integrity_repair_orphaned_edges() -> bool {
+    DEFAULT_WORKER_INTEGRITY_REPAIR_ORPHANED_EDGES
+}
+
+fn default_orphan_gc_interval_secs() -> u64 {
+    DEFAULT_WORKER_ORPHAN_GC_INTERVAL_SECS
+}
+
+fn default_orphan_gc_dry_run() -> bool {
+    DEFAULT_WORKER_ORPHAN_GC_DRY_RUN
+}
+
+fn default_orphan_gc_edges() -> bool {
+    DEFAULT_WORKER_ORPHAN_GC_EDGES
+}
+
+fn default_export_interval_secs() -> u64 {
+    DEFAULT_WORKER_EXPORT_INTERVAL_SECS
+}
+
+fn default_export_max_rows_per_file() -> usize {
+    DEFAULT_WORKER_EXPORT_MAX_ROWS_PER_FILE
+}
+
+fn default_stream_segmentation_enabled() -> bool {
+    DEFAULT_WORKER_STREAM_SEGMENTATION_ENABLED
+}
+
+fn default_stream_segment_gap_secs() -> u64 {
+    DEFAULT_WORKER_STREAM_SEGMENT_GAP_SECS
+}
+
+fn default_stream_segment_topic_overlap() -> f32 {
+    DEFAULT_WORKER_STREAM_SEGMENT_TOPIC_OVERLAP
+}
+
+fn default_stream_segment_min_events() -> usize {
+    DEFAULT_WORKER_STREAM_SEGMENT_MIN_EVENTS
+}
+
+fn default_local_summarization_enabled() -> bool {
+    DEFAULT_WORKER_LOCAL_SUMMARIZATION_ENABLED
+}
+
+fn default_local_summarization_max_chars() -> usize {
+    DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_CHARS
+}
+
+fn default_local_summarization_max_entropy() -> f32 {
+    DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_ENTROPY
+}
+
+fn default_consolidation_chunk_overlap_tokens() -> usize {
+    DEFAULT_WORKER_CONSOLIDATION_CHUNK_OVERLAP_TOKENS
+}
+
+fn default_self_eval_interval_secs() -> u64 {
+    DEFAULT_WORKER_SELF_EVAL_INTERVAL_SECS
+}
+
+fn default_self_eval_sample_size() -> usize {
+    DEFAULT_WORKER_SELF_EVAL_SAMPLE_SIZE
+}
+
+fn default_self_eval_top_k() -> usize {
+    DEFAULT_WORKER_SELF_EVAL_TOP_K
+}
+
+fn default_storage_usage_interval_secs() -> u64 {
+    DEFAULT_WORKER_STORAGE_USAGE_INTERVAL_SECS
+}
+
+fn default_importance_scoring_enabled() -> bool {
+    DEFAULT_WORKER_IMPORTANCE_SCORING_ENABLED
+}
+
+fn default_importance_floor() -> f32 {
+    DEFAULT_WORKER_IMPORTANCE_FLOOR
+}
+
+fn default_emotion_tagging_enabled() -> bool {
+    DEFAULT_WORKER_EMOTION_TAGGING_ENABLED
+}
+
+fn default_job_lease_secs() -> u64 {
+    DEFAULT_WORKER_JOB_LEASE_SECS
+}
+
+fn default_job_max_attempts() -> u32 {
+    DEFAULT_WORKER_JOB_MAX_ATTEMPTS
+}
+
+fn default_job_retry_backoff_secs() -> u64 {
+    DEFAULT_WORKER_JOB_RETRY_BACKOFF_SECS
+}
+
+fn default_run_history_max_records() -> usize {
+    DEFAULT_WORKER_RUN_HISTORY_MAX_RECORDS
 }
 
 fn default_shard_count() -> u32 {
@@ -239,6 +873,10 @@ pub struct ShardingConfig {
     pub physical_node_id: u32,
     #[serde(default)]
     pub nodes: Vec<ShardNodeConfig>,
+    /// Learner the gateway sends heavy dashboard, statistics and export reads to. A
+    /// learner never leads, so worker cycles never run on it.
+    #[serde(default)]
+    pub analytics_node_id: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -246,6 +884,20 @@ pub struct ShardNodeConfig {
     pub id: u32,
     pub http_addr: String,
     pub raft_base_port: u16,
+    #[serde(default)]
+    pub role: NodeRole,
+}
+
+/// How a physical node takes part in each shard's Raft group.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    /// Votes in elections and counts toward the commit quorum.
+    #[default]
+    Voter,
+    /// Replicates the log for backup or analytics but is never promoted, so it
+    /// neither votes nor stands for election.
+    Learner,
 }
 
 impl Default for ShardingConfig {
@@ -255,6 +907,7 @@ impl Default for ShardingConfig {
             shard_count: 1,
             physical_node_id: 1,
             nodes: Vec::new(),
+            analytics_node_id: None,
         }
     }
 }
@@ -277,6 +930,14 @@ impl Default for ForgettingConfig {
 pub struct VectorConfig {
     #[serde(default = "default_vector_enabled")]
     pub enabled: bool,
+    #[serde(default)]
+    pub backend: VectorBackendKind,
+    /// Connection string used when `backend = "pgvector"`
+    #[serde(default)]
+    pub pgvector_url: Option<String>,
+    /// Prepended to the vector table names (`memories`, `memory_assets`) in Postgres
+    #[serde(default = "default_vector_pgvector_table_prefix")]
+    pub pgvector_table_prefix: String,
     #[serde(default = "default_vector_degrade_on_startup_failure")]
     pub degrade_on_startup_failure: bool,
     #[serde(default = "default_vector_startup_timeout_secs")]
@@ -297,6 +958,20 @@ pub struct VectorConfig {
     pub io_threads: Option<u32>,
 }
 
+/// Where memory and asset embeddings are indexed. The graph store stays in the
+/// local LanceDB directory either way.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorBackendKind {
+    #[default]
+    Lancedb,
+    Pgvector,
+}
+
+fn default_vector_pgvector_table_prefix() -> String {
+    DEFAULT_VECTOR_PGVECTOR_TABLE_PREFIX.to_string()
+}
+
 fn default_vector_enabled() -> bool {
     DEFAULT_VECTOR_ENABLED
 }
@@ -337,6 +1012,9 @@ impl Default for VectorConfig {
     fn default() -> Self {
         Self {
             enabled: DEFAULT_VECTOR_ENABLED,
+            backend: VectorBackendKind::default(),
+            pgvector_url: None,
+            pgvector_table_prefix: DEFAULT_VECTOR_PGVECTOR_TABLE_PREFIX.to_string(),
             degrade_on_startup_failure: DEFAULT_VECTOR_DEGRADE_ON_STARTUP_FAILURE,
             startup_timeout_secs: DEFAULT_VECTOR_STARTUP_TIMEOUT_SECS,
             rebuild_on_missing: false,
@@ -353,6 +1031,10 @@ impl Default for VectorConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub llm: LLMConfig,
+    /// Cheap model that summarizes routine batches when `worker.local_summarization_enabled`
+    /// is set; without it those batches are summarized extractively
+    #[serde(default)]
+    pub local_llm: Option<LLMConfig>,
     pub storage: StorageConfig,
     pub raft: RaftConfig,
     pub worker: WorkerConfig,
@@ -364,6 +1046,35 @@ pub struct AppConfig {
     pub sharding: Option<ShardingConfig>,
     #[serde(default)]
     pub reranker: RerankerConfig,
+    #[serde(default)]
+    pub admission: AdmissionConfig,
+    #[serde(default)]
+    pub linking: LinkingConfig,
+    #[serde(default)]
+    pub slow_query: SlowQueryConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    #[serde(default)]
+    pub prompts: PromptsConfig,
+    /// Ranking experiment that splits retrieval traffic across variants
+    #[serde(default)]
+    pub experiment: Option<ExperimentConfig>,
+    #[serde(default)]
+    pub traffic_recording: TrafficRecordingConfig,
+    #[serde(default)]
+    pub disk_watchdog: DiskWatchdogConfig,
+    #[serde(default)]
+    pub temporal_query: TemporalQueryConfig,
+    #[serde(default)]
+    pub edge_decay: EdgeDecayConfig,
+    #[serde(default)]
+    pub consolidation_pool: ConsolidationPoolConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -379,12 +1090,854 @@ impl Default for RerankerType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RerankerConfig {
     #[serde(default)]
     pub r#type: RerankerType,
     #[serde(default)]
     pub endpoint: Option<String>,
+    /// Half-life of the time decay applied by recency-biased retrieval.
+    #[serde(default = "default_reranker_recency_half_life_hours")]
+    pub recency_half_life_hours: f64,
+    /// Rank offset of reciprocal rank fusion. Larger values flatten the gap between
+    /// top-ranked and lower-ranked candidates.
+    #[serde(default = "default_reranker_rrf_k")]
+    pub rrf_k: f32,
+}
+
+fn default_reranker_recency_half_life_hours() -> f64 {
+    DEFAULT_RERANKER_RECENCY_HALF_LIFE_HOURS
+}
+
+fn default_reranker_rrf_k() -> f32 {
+    DEFAULT_RERANKER_RRF_K
+}
+
+/// Ingest admission control driven by the depth of the pending-event queue.
+/// A threshold of 0 disables that stage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Above this many pending events, publish-time auto/semantic linking is skipped.
+    #[serde(default = "default_admission_shed_linking_pending")]
+    pub shed_linking_pending: usize,
+    /// Above this many pending events, ingest answers 429 with `Retry-After`.
+    #[serde(default = "default_admission_throttle_pending")]
+    pub throttle_pending: usize,
+    /// Above this many pending events, ingest answers 503 with `Retry-After`.
+    #[serde(default = "default_admission_reject_pending")]
+    pub reject_pending: usize,
+    #[serde(default = "default_admission_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// How long a pending-queue count is reused before it is taken again.
+    #[serde(default = "default_admission_sample_interval_ms")]
+    pub sample_interval_ms: u64,
+}
+
+fn default_admission_shed_linking_pending() -> usize {
+    DEFAULT_ADMISSION_SHED_LINKING_PENDING
+}
+
+fn default_admission_throttle_pending() -> usize {
+    DEFAULT_ADMISSION_THROTTLE_PENDING
+}
+
+fn default_admission_reject_pending() -> usize {
+    DEFAULT_ADMISSION_REJECT_PENDING
+}
+
+fn default_admission_retry_after_secs() -> u64 {
+    DEFAULT_ADMISSION_RETRY_AFTER_SECS
+}
+
+fn default_admission_sample_interval_ms() -> u64 {
+    DEFAULT_ADMISSION_SAMPLE_INTERVAL_MS
+}
+
+/// Retrievals slower than `threshold_ms` are logged with their stage timings and kept in
+/// a ring buffer of the latest `capacity` entries. A threshold of 0 disables the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowQueryConfig {
+    #[serde(default = "default_slow_query_threshold_ms")]
+    pub threshold_ms: u64,
+    #[serde(default = "default_slow_query_capacity")]
+    pub capacity: usize,
+}
+
+fn default_slow_query_threshold_ms() -> u64 {
+    DEFAULT_SLOW_QUERY_THRESHOLD_MS
+}
+
+fn default_slow_query_capacity() -> usize {
+    DEFAULT_SLOW_QUERY_CAPACITY
+}
+
+impl Default for SlowQueryConfig {
+    fn default() -> Self {
+        Self {
+            threshold_ms: DEFAULT_SLOW_QUERY_THRESHOLD_MS,
+            capacity: DEFAULT_SLOW_QUERY_CAPACITY,
+        }
+    }
+}
+
+/// Samples retrieve requests, with the results they got, into each shard's system
+/// keyspace so `memorose-server replay` can re-run them against a candidate build or
+/// config. A sample rate of 0 disables recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficRecordingConfig {
+    /// Share of retrievals recorded, from 0.0 to 1.0
+    #[serde(default = "default_traffic_recording_sample_rate")]
+    pub sample_rate: f64,
+    /// Recordings kept per shard; the oldest are dropped first
+    #[serde(default = "default_traffic_recording_max_records")]
+    pub max_records: usize,
+}
+
+fn default_traffic_recording_sample_rate() -> f64 {
+    DEFAULT_TRAFFIC_RECORDING_SAMPLE_RATE
+}
+
+fn default_traffic_recording_max_records() -> usize {
+    DEFAULT_TRAFFIC_RECORDING_MAX_RECORDS
+}
+
+impl Default for TrafficRecordingConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_TRAFFIC_RECORDING_SAMPLE_RATE,
+            max_records: DEFAULT_TRAFFIC_RECORDING_MAX_RECORDS,
+        }
+    }
+}
+
+/// Turns time expressions in retrieval queries, such as "last Tuesday" or "上周二", into
+/// a valid-time range when the request sets neither `start_time` nor `end_time`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporalQueryConfig {
+    #[serde(default = "default_temporal_query_enabled")]
+    pub enabled: bool,
+    /// Offset from UTC, in minutes, that days and weeks are counted in when the request
+    /// does not give one
+    #[serde(default = "default_temporal_query_utc_offset_minutes")]
+    pub utc_offset_minutes: i32,
+}
+
+fn default_temporal_query_enabled() -> bool {
+    DEFAULT_TEMPORAL_QUERY_ENABLED
+}
+
+fn default_temporal_query_utc_offset_minutes() -> i32 {
+    DEFAULT_TEMPORAL_QUERY_UTC_OFFSET_MINUTES
+}
+
+impl Default for TemporalQueryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_TEMPORAL_QUERY_ENABLED,
+            utc_offset_minutes: DEFAULT_TEMPORAL_QUERY_UTC_OFFSET_MINUTES,
+        }
+    }
+}
+
+/// Decay of graph edge weights while they go unreinforced, and pruning of edges that decay
+/// too far. Rules are keyed by relation name; relations without a rule never decay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeDecayConfig {
+    #[serde(default = "default_edge_decay_enabled")]
+    pub enabled: bool,
+    /// Seconds between pruning cycles
+    #[serde(default = "default_edge_decay_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_edge_decay_relations")]
+    pub relations: std::collections::BTreeMap<String, EdgeDecayRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EdgeDecayRule {
+    /// Days without reinforcement after which an edge carries half its stored weight
+    #[serde(default = "default_edge_decay_half_life_days")]
+    pub half_life_days: f64,
+    /// Edges whose decayed weight falls below this are pruned
+    #[serde(default = "default_edge_decay_prune_below")]
+    pub prune_below: f32,
+    /// Edges reinforced within this many days are never pruned
+    #[serde(default = "default_edge_decay_min_idle_days")]
+    pub min_idle_days: u64,
+}
+
+fn default_edge_decay_enabled() -> bool {
+    DEFAULT_EDGE_DECAY_ENABLED
+}
+
+fn default_edge_decay_interval_secs() -> u64 {
+    DEFAULT_EDGE_DECAY_INTERVAL_SECS
+}
+
+fn default_edge_decay_relations() -> std::collections::BTreeMap<String, EdgeDecayRule> {
+    std::collections::BTreeMap::from([("RelatedTo".to_string(), EdgeDecayRule::default())])
+}
+
+fn default_edge_decay_half_life_days() -> f64 {
+    DEFAULT_EDGE_DECAY_HALF_LIFE_DAYS
+}
+
+fn default_edge_decay_prune_below() -> f32 {
+    DEFAULT_EDGE_DECAY_PRUNE_BELOW
+}
+
+fn default_edge_decay_min_idle_days() -> u64 {
+    DEFAULT_EDGE_DECAY_MIN_IDLE_DAYS
+}
+
+impl Default for EdgeDecayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_EDGE_DECAY_ENABLED,
+            interval_secs: DEFAULT_EDGE_DECAY_INTERVAL_SECS,
+            relations: default_edge_decay_relations(),
+        }
+    }
+}
+
+impl Default for EdgeDecayRule {
+    fn default() -> Self {
+        Self {
+            half_life_days: DEFAULT_EDGE_DECAY_HALF_LIFE_DAYS,
+            prune_below: DEFAULT_EDGE_DECAY_PRUNE_BELOW,
+            min_idle_days: DEFAULT_EDGE_DECAY_MIN_IDLE_DAYS,
+        }
+    }
+}
+
+impl EdgeDecayConfig {
+    /// The rule for `relation`, when decay is enabled and the relation has one.
+    pub fn rule_for(&self, relation: &str) -> Option<&EdgeDecayRule> {
+        if !self.enabled {
+            return None;
+        }
+        self.relations.get(relation)
+    }
+}
+
+impl EdgeDecayRule {
+    /// `weight` after `idle` without reinforcement.
+    pub fn decayed_weight(&self, weight: f32, idle: chrono::Duration) -> f32 {
+        let idle_days = idle.num_seconds().max(0) as f64 / 86_400.0;
+        weight * 0.5f64.powf(idle_days / self.half_life_days) as f32
+    }
+
+    /// Whether an edge of `weight`, last reinforced `idle` ago, should be pruned.
+    pub fn should_prune(&self, weight: f32, idle: chrono::Duration) -> bool {
+        idle.num_days() >= self.min_idle_days as i64
+            && self.decayed_weight(weight, idle) < self.prune_below
+    }
+}
+
+/// Dedicated consolidation nodes. When `node_ids` is set, shard leaders stop compressing
+/// text events themselves and lease batches to these nodes, which send the results back
+/// to the leader to be stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationPoolConfig {
+    /// Physical node IDs that consolidate on behalf of the shard leaders
+    #[serde(default)]
+    pub node_ids: Vec<u32>,
+    /// Seconds a claimed batch stays reserved for its node before others may claim it
+    #[serde(default = "default_consolidation_pool_lease_secs")]
+    pub lease_secs: u64,
+    /// Pause between claims once a node finds nothing to consolidate
+    #[serde(default = "default_consolidation_pool_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// API key pool nodes send to the leader as `x-api-key`
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+fn default_consolidation_pool_lease_secs() -> u64 {
+    DEFAULT_CONSOLIDATION_POOL_LEASE_SECS
+}
+
+fn default_consolidation_pool_poll_interval_ms() -> u64 {
+    DEFAULT_CONSOLIDATION_POOL_POLL_INTERVAL_MS
+}
+
+impl Default for ConsolidationPoolConfig {
+    fn default() -> Self {
+        Self {
+            node_ids: Vec::new(),
+            lease_secs: DEFAULT_CONSOLIDATION_POOL_LEASE_SECS,
+            poll_interval_ms: DEFAULT_CONSOLIDATION_POOL_POLL_INTERVAL_MS,
+            api_key: None,
+        }
+    }
+}
+
+/// Watches free space on the volume holding `storage.root_dir`. Below
+/// `warn_free_percent` the node alerts; below `read_only_free_percent` it rejects ingest
+/// until free space is back above `warn_free_percent`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskWatchdogConfig {
+    /// Seconds between free-space checks; 0 disables the watchdog
+    #[serde(default = "default_disk_watchdog_check_interval_secs")]
+    pub check_interval_secs: u64,
+    #[serde(default = "default_disk_watchdog_warn_free_percent")]
+    pub warn_free_percent: f64,
+    #[serde(default = "default_disk_watchdog_read_only_free_percent")]
+    pub read_only_free_percent: f64,
+    /// Optional endpoint that receives a JSON POST whenever the disk level changes
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_disk_watchdog_check_interval_secs() -> u64 {
+    DEFAULT_DISK_WATCHDOG_CHECK_INTERVAL_SECS
+}
+
+fn default_disk_watchdog_warn_free_percent() -> f64 {
+    DEFAULT_DISK_WATCHDOG_WARN_FREE_PERCENT
+}
+
+fn default_disk_watchdog_read_only_free_percent() -> f64 {
+    DEFAULT_DISK_WATCHDOG_READ_ONLY_FREE_PERCENT
+}
+
+impl Default for DiskWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: DEFAULT_DISK_WATCHDOG_CHECK_INTERVAL_SECS,
+            warn_free_percent: DEFAULT_DISK_WATCHDOG_WARN_FREE_PERCENT,
+            read_only_free_percent: DEFAULT_DISK_WATCHDOG_READ_ONLY_FREE_PERCENT,
+            webhook_url: None,
+        }
+    }
+}
+
+/// Asynchronous shipping of applied writes to a Memorose cluster in another region.
+///
+/// Only cluster mode has a Raft log to ship from; the receiving side needs no settings
+/// beyond accepting `POST /v1/replication/apply`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Name of this region, recorded on every shipped batch
+    #[serde(default)]
+    pub region: String,
+    /// Base URL of the remote cluster, e.g. `https://memorose.eu-west.internal`
+    #[serde(default)]
+    pub sink_url: Option<String>,
+    /// API key of the remote cluster, sent as `x-api-key`
+    #[serde(default)]
+    pub sink_api_key: Option<String>,
+    /// Applied log entries shipped per request
+    #[serde(default = "default_replication_batch_size")]
+    pub batch_size: usize,
+    /// Pause between polls once the shipper has caught up with the local log
+    #[serde(default = "default_replication_interval_ms")]
+    pub interval_ms: u64,
+}
+
+fn default_replication_batch_size() -> usize {
+    DEFAULT_REPLICATION_BATCH_SIZE
+}
+
+fn default_replication_interval_ms() -> u64 {
+    DEFAULT_REPLICATION_INTERVAL_MS
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            region: String::new(),
+            sink_url: None,
+            sink_api_key: None,
+            batch_size: DEFAULT_REPLICATION_BATCH_SIZE,
+            interval_ms: DEFAULT_REPLICATION_INTERVAL_MS,
+        }
+    }
+}
+
+impl ReplicationConfig {
+    /// The sink URL when shipping is switched on and has somewhere to go.
+    pub fn active_sink(&self) -> Option<&str> {
+        self.sink_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| self.enabled && !url.is_empty())
+    }
+}
+
+/// Key management services that hold the per-user keys of bring-your-own-key encryption.
+///
+/// A user's key reference picks the service by its scheme: `vault:<key>` names a key in
+/// a Vault (or OpenBao) transit engine, `local:<name>` wraps data keys with
+/// `local_master_key` and is meant for development.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Base64 of a 32-byte key for `local:` references
+    #[serde(default)]
+    pub local_master_key: Option<String>,
+    /// Vault address, e.g. `https://vault.internal:8200`
+    #[serde(default)]
+    pub vault_addr: Option<String>,
+    #[serde(default)]
+    pub vault_token: Option<String>,
+    /// Mount path of the transit engine; `transit` when unset
+    #[serde(default)]
+    pub vault_transit_mount: Option<String>,
+}
+
+/// Service that decides whether content is unsafe. `none` turns moderation off.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationProvider {
+    #[default]
+    None,
+    /// Flag content matching any of `moderation.patterns`
+    Regex,
+    /// A moderation API speaking the OpenAI `/v1/moderations` format
+    Http,
+    /// Ask the configured LLM to classify the content
+    Llm,
+}
+
+/// What happens to an event whose content is flagged at ingest.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationIngestAction {
+    /// Store it without checking
+    #[default]
+    Allow,
+    /// Store it with the verdict under the event's `moderation` metadata
+    Tag,
+    /// Refuse to store it
+    Block,
+}
+
+/// How one app treats flagged content.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModerationPolicy {
+    #[serde(default)]
+    pub ingest: ModerationIngestAction,
+    /// Drop flagged memories from retrieval results returned to the app
+    #[serde(default)]
+    pub strip_from_retrieval: bool,
+}
+
+/// Content safety checks before storage and before retrieval output. Each app gets the
+/// policy under `apps.<app_id>`, falling back to `default_policy`; every blocked, tagged
+/// or stripped item leaves an audit record.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModerationConfig {
+    #[serde(default)]
+    pub provider: ModerationProvider,
+    /// Regex provider: category name to pattern, e.g. `credit_card = '\b(?:\d[ -]?){13,16}\b'`
+    #[serde(default)]
+    pub patterns: std::collections::BTreeMap<String, String>,
+    /// Http provider: moderation endpoint, e.g. `https://api.openai.com/v1/moderations`
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Http provider: sent as a bearer token
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Http provider: model named in each request; omitted when unset
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub default_policy: ModerationPolicy,
+    #[serde(default)]
+    pub apps: std::collections::BTreeMap<String, ModerationPolicy>,
+}
+
+impl ModerationConfig {
+    /// Policy for content of `app_id`; `None` (no app) uses the default policy.
+    pub fn policy_for(&self, app_id: Option<&str>) -> &ModerationPolicy {
+        app_id
+            .and_then(|app_id| self.apps.get(app_id))
+            .unwrap_or(&self.default_policy)
+    }
+}
+
+/// An LLM system prompt that can be replaced through `[prompts]`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptKind {
+    /// Compressing user-side events into an L1 memory
+    Compression,
+    /// Compressing an agent trajectory into an L1 memory
+    AgentCompression,
+    /// Extracting L2 topics from a session's memories
+    Reflection,
+    /// Splitting a goal into L3 milestones
+    Decomposition,
+    /// Summarizing a memory community into an L2 insight
+    CommunitySummary,
+    /// Answering in `/v1/users/:uid/apps/:app/chat`, ahead of the memory context
+    Chat,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 6] = [
+        PromptKind::Compression,
+        PromptKind::AgentCompression,
+        PromptKind::Reflection,
+        PromptKind::Decomposition,
+        PromptKind::CommunitySummary,
+        PromptKind::Chat,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromptKind::Compression => "compression",
+            PromptKind::AgentCompression => "agent_compression",
+            PromptKind::Reflection => "reflection",
+            PromptKind::Decomposition => "decomposition",
+            PromptKind::CommunitySummary => "community_summary",
+            PromptKind::Chat => "chat",
+        }
+    }
+
+    /// Variables a template of this kind may use.
+    pub fn variables(self) -> &'static [&'static str] {
+        match self {
+            PromptKind::Compression | PromptKind::AgentCompression => {
+                &["app_id", "language_instruction", "speakers"]
+            }
+            PromptKind::Reflection
+            | PromptKind::Decomposition
+            | PromptKind::CommunitySummary
+            | PromptKind::Chat => &["app_id", "language_instruction"],
+        }
+    }
+}
+
+/// System prompt overrides, one per [`PromptKind`]. Unset kinds keep the built-in prompt.
+/// Apart from `chat`, a template must still ask for the JSON the built-in prompt asks for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PromptTemplates {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_compression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflection: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decomposition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub community_summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat: Option<String>,
+}
+
+impl PromptTemplates {
+    pub fn get(&self, kind: PromptKind) -> Option<&str> {
+        match kind {
+            PromptKind::Compression => self.compression.as_deref(),
+            PromptKind::AgentCompression => self.agent_compression.as_deref(),
+            PromptKind::Reflection => self.reflection.as_deref(),
+            PromptKind::Decomposition => self.decomposition.as_deref(),
+            PromptKind::CommunitySummary => self.community_summary.as_deref(),
+            PromptKind::Chat => self.chat.as_deref(),
+        }
+    }
+}
+
+/// Prompt templates with `{{variable}}` placeholders. Each app gets the templates under
+/// `apps.<app_id>`, falling back to `default` kind by kind, then to the built-in prompt.
+/// Templates follow config reloads.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PromptsConfig {
+    #[serde(default)]
+    pub default: PromptTemplates,
+    #[serde(default)]
+    pub apps: std::collections::BTreeMap<String, PromptTemplates>,
+}
+
+impl PromptsConfig {
+    /// Template of `kind` for `app_id`; `None` means the built-in prompt.
+    pub fn template_for(&self, kind: PromptKind, app_id: Option<&str>) -> Option<&str> {
+        app_id
+            .and_then(|app_id| self.apps.get(app_id))
+            .and_then(|templates| templates.get(kind))
+            .or_else(|| self.default.get(kind))
+    }
+
+    /// Check every configured template, naming the first invalid one.
+    pub fn validate(&self) -> Result<(), String> {
+        let scopes = std::iter::once(("default".to_string(), &self.default)).chain(
+            self.apps
+                .iter()
+                .map(|(app_id, templates)| (format!("apps.{}", app_id), templates)),
+        );
+        for (scope, templates) in scopes {
+            for kind in PromptKind::ALL {
+                if let Some(template) = templates.get(kind) {
+                    crate::prompt_template::validate(template, kind.variables()).map_err(
+                        |error| format!("prompts.{}.{}: {}", scope, kind.as_str(), error),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How retrieval traffic is split across the variants of a ranking experiment.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentAssignment {
+    /// Each user keeps one variant for the life of the experiment
+    #[default]
+    User,
+    /// Every retrieval draws a variant afresh
+    Request,
+}
+
+/// One arm of a ranking experiment. Unset settings keep the configured ranking; set
+/// ones replace both the configured value and the request's own.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RankingVariantConfig {
+    pub name: String,
+    /// Relative share of traffic
+    #[serde(default = "default_ranking_variant_weight")]
+    pub weight: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrf_k: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recency_half_life_hours: Option<f64>,
+    /// Rerank with the HTTP reranker at this endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reranker_endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_depth: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recency_bias: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mmr_lambda: Option<f32>,
+}
+
+fn default_ranking_variant_weight() -> u32 {
+    1
+}
+
+/// A ranking experiment over retrieval. Follows config reloads; renaming the experiment
+/// starts its results afresh.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExperimentConfig {
+    pub name: String,
+    #[serde(default)]
+    pub assignment: ExperimentAssignment,
+    pub variants: Vec<RankingVariantConfig>,
+}
+
+impl ExperimentConfig {
+    /// The variant serving `user_id`'s next retrieval. Users hash to a stable bucket, so
+    /// a variant keeps its users while the weights stay the same.
+    pub fn assign(&self, user_id: &str) -> Option<&RankingVariantConfig> {
+        let key = match self.assignment {
+            ExperimentAssignment::User => format!("{}:{}", self.name, user_id),
+            ExperimentAssignment::Request => uuid::Uuid::new_v4().to_string(),
+        };
+        self.variant_for_key(&key)
+    }
+
+    fn variant_for_key(&self, key: &str) -> Option<&RankingVariantConfig> {
+        use sha2::{Digest, Sha256};
+
+        let total: u64 = self
+            .variants
+            .iter()
+            .map(|variant| variant.weight as u64)
+            .sum();
+        if total == 0 {
+            return None;
+        }
+        let hash = Sha256::digest(key.as_bytes());
+        let mut bucket = u64::from_le_bytes(hash[..8].try_into().ok()?) % total;
+        for variant in &self.variants {
+            if bucket < variant.weight as u64 {
+                return Some(variant);
+            }
+            bucket -= variant.weight as u64;
+        }
+        None
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&RankingVariantConfig> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.contains(':') {
+            return Err("experiment.name must be non-empty and free of ':'".to_string());
+        }
+        if self.variants.iter().all(|variant| variant.weight == 0) {
+            return Err("experiment needs a variant with a positive weight".to_string());
+        }
+        let mut names = std::collections::HashSet::new();
+        for variant in &self.variants {
+            if variant.name.trim().is_empty() || !names.insert(variant.name.as_str()) {
+                return Err(format!(
+                    "experiment variant names must be unique and non-empty (got {:?})",
+                    variant.name
+                ));
+            }
+            for (field, value) in [
+                ("recency_bias", variant.recency_bias),
+                ("mmr_lambda", variant.mmr_lambda),
+            ] {
+                if value.is_some_and(|value| !(0.0..=1.0).contains(&value)) {
+                    return Err(format!(
+                        "experiment variant {:?}: {} must be between 0.0 and 1.0",
+                        variant.name, field
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the query-embedding and dashboard caches live. `memory` keeps one per node;
+/// `redis` shares them across nodes so requests bounced by a gateway still hit.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheBackend {
+    #[default]
+    Memory,
+    Redis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    #[serde(default)]
+    pub backend: CacheBackend,
+    /// Used when `backend = "redis"`, e.g. `redis://cache.internal:6379/0`
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    #[serde(default = "default_cache_redis_key_prefix")]
+    pub redis_key_prefix: String,
+    #[serde(default = "default_cache_embedding_ttl_secs")]
+    pub embedding_ttl_secs: u64,
+    #[serde(default = "default_cache_dashboard_ttl_secs")]
+    pub dashboard_ttl_secs: u64,
+    /// Keep query embeddings in `root_dir/embedding_cache` so a restart does not
+    /// re-embed every query. Only applies to the `memory` backend.
+    #[serde(default = "default_cache_persist_embeddings")]
+    pub persist_embeddings: bool,
+    /// Oldest persisted embeddings are dropped beyond this many entries
+    #[serde(default = "default_cache_persisted_embedding_max_entries")]
+    pub persisted_embedding_max_entries: usize,
+}
+
+fn default_cache_redis_key_prefix() -> String {
+    DEFAULT_CACHE_REDIS_KEY_PREFIX.to_string()
+}
+
+fn default_cache_embedding_ttl_secs() -> u64 {
+    DEFAULT_CACHE_EMBEDDING_TTL_SECS
+}
+
+fn default_cache_dashboard_ttl_secs() -> u64 {
+    DEFAULT_CACHE_DASHBOARD_TTL_SECS
+}
+
+fn default_cache_persist_embeddings() -> bool {
+    DEFAULT_CACHE_PERSIST_EMBEDDINGS
+}
+
+fn default_cache_persisted_embedding_max_entries() -> usize {
+    DEFAULT_CACHE_PERSISTED_EMBEDDING_MAX_ENTRIES
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            backend: CacheBackend::default(),
+            redis_url: None,
+            redis_key_prefix: DEFAULT_CACHE_REDIS_KEY_PREFIX.to_string(),
+            embedding_ttl_secs: DEFAULT_CACHE_EMBEDDING_TTL_SECS,
+            dashboard_ttl_secs: DEFAULT_CACHE_DASHBOARD_TTL_SECS,
+            persist_embeddings: DEFAULT_CACHE_PERSIST_EMBEDDINGS,
+            persisted_embedding_max_entries: DEFAULT_CACHE_PERSISTED_EMBEDDING_MAX_ENTRIES,
+        }
+    }
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            shed_linking_pending: DEFAULT_ADMISSION_SHED_LINKING_PENDING,
+            throttle_pending: DEFAULT_ADMISSION_THROTTLE_PENDING,
+            reject_pending: DEFAULT_ADMISSION_REJECT_PENDING,
+            retry_after_secs: DEFAULT_ADMISSION_RETRY_AFTER_SECS,
+            sample_interval_ms: DEFAULT_ADMISSION_SAMPLE_INTERVAL_MS,
+        }
+    }
+}
+
+/// When publish-time linking (vector similarity plus LLM relation analysis) runs.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkingMode {
+    /// Inline, as part of storing the unit.
+    #[default]
+    Sync,
+    /// Queued and drained by a dedicated background worker loop.
+    Deferred,
+    /// Never; units are stored without automatic edges.
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkingConfig {
+    #[serde(default)]
+    pub mode: LinkingMode,
+    /// Run LLM relation analysis after vector-similarity linking.
+    #[serde(default = "default_linking_semantic_enabled")]
+    pub semantic_enabled: bool,
+    /// Cap on LLM relation-analysis calls per minute; 0 means unlimited.
+    #[serde(default = "default_linking_max_relation_calls_per_minute")]
+    pub max_relation_calls_per_minute: u32,
+    /// Queued units the deferred linking loop handles per tick.
+    #[serde(default = "default_linking_deferred_batch_size")]
+    pub deferred_batch_size: usize,
+}
+
+fn default_linking_semantic_enabled() -> bool {
+    DEFAULT_LINKING_SEMANTIC_ENABLED
+}
+
+fn default_linking_max_relation_calls_per_minute() -> u32 {
+    DEFAULT_LINKING_MAX_RELATION_CALLS_PER_MINUTE
+}
+
+fn default_linking_deferred_batch_size() -> usize {
+    DEFAULT_LINKING_DEFERRED_BATCH_SIZE
+}
+
+impl Default for LinkingConfig {
+    fn default() -> Self {
+        Self {
+            mode: LinkingMode::default(),
+            semantic_enabled: DEFAULT_LINKING_SEMANTIC_ENABLED,
+            max_relation_calls_per_minute: DEFAULT_LINKING_MAX_RELATION_CALLS_PER_MINUTE,
+            deferred_batch_size: DEFAULT_LINKING_DEFERRED_BATCH_SIZE,
+        }
+    }
+}
+
+impl Default for RerankerConfig {
+    fn default() -> Self {
+        Self {
+            r#type: RerankerType::default(),
+            endpoint: None,
+            recency_half_life_hours: DEFAULT_RERANKER_RECENCY_HALF_LIFE_HOURS,
+            rrf_k: DEFAULT_RERANKER_RRF_K,
+        }
+    }
 }
 
 impl Default for LLMConfig {
@@ -401,6 +1954,10 @@ impl Default for LLMConfig {
             embedding_task_type: None,
             stt_provider: None,
             stt_model: None,
+            structured_output_retries: DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES,
+            requests_per_minute: DEFAULT_LLM_REQUESTS_PER_MINUTE,
+            max_concurrency: DEFAULT_LLM_MAX_CONCURRENCY,
+            rate_limit_retries: DEFAULT_LLM_RATE_LIMIT_RETRIES,
         }
     }
 }
@@ -409,6 +1966,11 @@ impl Default for StorageConfig {
     fn default() -> Self {
         Self {
             root_dir: "./data".into(),
+            backend: StorageBackend::default(),
+            postgres_url: None,
+            postgres_namespace: None,
+            postgres_table: DEFAULT_STORAGE_POSTGRES_TABLE.to_string(),
+            postgres_pool_size: DEFAULT_STORAGE_POSTGRES_POOL_SIZE,
             index_commit_interval_ms: DEFAULT_STORAGE_COMMIT_INTERVAL_MS,
             index_commit_min_interval_ms: DEFAULT_STORAGE_COMMIT_MIN_INTERVAL_MS,
             index_commit_max_interval_ms: DEFAULT_STORAGE_COMMIT_MAX_INTERVAL_MS,
@@ -420,6 +1982,15 @@ impl Default for StorageConfig {
             recent_overlay_per_user_max_bytes: DEFAULT_STORAGE_RECENT_OVERLAY_PER_USER_MAX_BYTES,
             recent_overlay_global_max_bytes: DEFAULT_STORAGE_RECENT_OVERLAY_GLOBAL_MAX_BYTES,
             recent_overlay_query_limit: DEFAULT_STORAGE_RECENT_OVERLAY_QUERY_LIMIT,
+            index_app_commit_interval_ms: Default::default(),
+            index_merge_enabled: DEFAULT_STORAGE_INDEX_MERGE_ENABLED,
+            index_merge_min_num_segments: DEFAULT_STORAGE_INDEX_MERGE_MIN_NUM_SEGMENTS,
+            index_merge_max_docs_before_merge: DEFAULT_STORAGE_INDEX_MERGE_MAX_DOCS_BEFORE_MERGE,
+            index_merge_min_layer_size: DEFAULT_STORAGE_INDEX_MERGE_MIN_LAYER_SIZE,
+            index_merge_level_log_size: DEFAULT_STORAGE_INDEX_MERGE_LEVEL_LOG_SIZE,
+            index_merge_del_docs_ratio: DEFAULT_STORAGE_INDEX_MERGE_DEL_DOCS_RATIO,
+            index_sync_min_interval_ms: DEFAULT_STORAGE_INDEX_SYNC_MIN_INTERVAL_MS,
+            index_user_buckets: DEFAULT_STORAGE_INDEX_USER_BUCKETS,
         }
     }
 }
@@ -435,6 +2006,8 @@ impl Default for RaftConfig {
             snapshot_logs: DEFAULT_RAFT_SNAPSHOT_LOGS,
             auto_initialize: true,
             bootstrap_seed_node_id: None,
+            read_index_wait_ms: DEFAULT_RAFT_READ_INDEX_WAIT_MS,
+            ready_max_lag: DEFAULT_RAFT_READY_MAX_LAG,
         }
     }
 }
@@ -472,6 +2045,63 @@ impl Default for WorkerConfig {
             enable_task_reflection: true,
             auto_link_similarity_threshold: DEFAULT_AUTO_LINK_SIMILARITY_THRESHOLD,
             tick_interval_ms: DEFAULT_WORKER_TICK_INTERVAL_MS,
+            keyword_extraction_enabled: DEFAULT_WORKER_KEYWORD_EXTRACTION_ENABLED,
+            keyword_max_per_memory: DEFAULT_WORKER_KEYWORD_MAX_PER_MEMORY,
+            profile_interval_ms: DEFAULT_WORKER_PROFILE_INTERVAL_MS,
+            profile_max_users_per_cycle: DEFAULT_WORKER_PROFILE_MAX_USERS_PER_CYCLE,
+            profile_max_insights: DEFAULT_WORKER_PROFILE_MAX_INSIGHTS,
+            profile_max_memories: DEFAULT_WORKER_PROFILE_MAX_MEMORIES,
+            task_deadline_interval_ms: DEFAULT_WORKER_TASK_DEADLINE_INTERVAL_MS,
+            task_deadline_warning_ms: DEFAULT_WORKER_TASK_DEADLINE_WARNING_MS,
+            task_deadline_webhook_url: None,
+            auto_planner_max_subtasks: DEFAULT_WORKER_AUTO_PLANNER_MAX_SUBTASKS,
+            auto_planner_max_depth: DEFAULT_WORKER_AUTO_PLANNER_MAX_DEPTH,
+            auto_planner_require_approval: DEFAULT_WORKER_AUTO_PLANNER_REQUIRE_APPROVAL,
+            trash_retention_days: DEFAULT_WORKER_TRASH_RETENTION_DAYS,
+            audio_chunk_secs: DEFAULT_WORKER_AUDIO_CHUNK_SECS,
+            audio_transcribe_command: None,
+            video_segment_secs: DEFAULT_WORKER_VIDEO_SEGMENT_SECS,
+            asset_embeddings_enabled: false,
+            community_resummarize_threshold: DEFAULT_WORKER_COMMUNITY_RESUMMARIZE_THRESHOLD,
+            l2_refresh_interval_ms: DEFAULT_WORKER_L2_REFRESH_INTERVAL_MS,
+            insight_min_reflect_l1: DEFAULT_WORKER_INSIGHT_MIN_REFLECT_L1,
+            insight_min_reflect_tokens: DEFAULT_WORKER_INSIGHT_MIN_REFLECT_TOKENS,
+            insight_max_llm_calls_per_cycle: DEFAULT_WORKER_INSIGHT_MAX_LLM_CALLS_PER_CYCLE,
+            consolidation_user_workers: DEFAULT_WORKER_CONSOLIDATION_USER_WORKERS,
+            sync_consolidation_budget_ms: DEFAULT_WORKER_SYNC_CONSOLIDATION_BUDGET_MS,
+            skip_compression_apps: Vec::new(),
+            consolidation_max_events_per_user: DEFAULT_WORKER_CONSOLIDATION_MAX_EVENTS_PER_USER,
+            cache_warmup_users: DEFAULT_WORKER_CACHE_WARMUP_USERS,
+            cache_warmup_nodes_per_user: DEFAULT_WORKER_CACHE_WARMUP_NODES_PER_USER,
+            integrity_check_interval_secs: DEFAULT_WORKER_INTEGRITY_CHECK_INTERVAL_SECS,
+            integrity_batch_size: DEFAULT_WORKER_INTEGRITY_BATCH_SIZE,
+            integrity_repair: DEFAULT_WORKER_INTEGRITY_REPAIR,
+            integrity_repair_orphaned_edges: DEFAULT_WORKER_INTEGRITY_REPAIR_ORPHANED_EDGES,
+            orphan_gc_interval_secs: DEFAULT_WORKER_ORPHAN_GC_INTERVAL_SECS,
+            orphan_gc_dry_run: DEFAULT_WORKER_ORPHAN_GC_DRY_RUN,
+            orphan_gc_edges: DEFAULT_WORKER_ORPHAN_GC_EDGES,
+            export_url: None,
+            export_interval_secs: DEFAULT_WORKER_EXPORT_INTERVAL_SECS,
+            export_max_rows_per_file: DEFAULT_WORKER_EXPORT_MAX_ROWS_PER_FILE,
+            stream_segmentation_enabled: DEFAULT_WORKER_STREAM_SEGMENTATION_ENABLED,
+            stream_segment_gap_secs: DEFAULT_WORKER_STREAM_SEGMENT_GAP_SECS,
+            stream_segment_topic_overlap: DEFAULT_WORKER_STREAM_SEGMENT_TOPIC_OVERLAP,
+            stream_segment_min_events: DEFAULT_WORKER_STREAM_SEGMENT_MIN_EVENTS,
+            local_summarization_enabled: DEFAULT_WORKER_LOCAL_SUMMARIZATION_ENABLED,
+            local_summarization_max_chars: DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_CHARS,
+            local_summarization_max_entropy: DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_ENTROPY,
+            consolidation_chunk_overlap_tokens: DEFAULT_WORKER_CONSOLIDATION_CHUNK_OVERLAP_TOKENS,
+            self_eval_interval_secs: DEFAULT_WORKER_SELF_EVAL_INTERVAL_SECS,
+            self_eval_sample_size: DEFAULT_WORKER_SELF_EVAL_SAMPLE_SIZE,
+            self_eval_top_k: DEFAULT_WORKER_SELF_EVAL_TOP_K,
+            storage_usage_interval_secs: DEFAULT_WORKER_STORAGE_USAGE_INTERVAL_SECS,
+            importance_scoring_enabled: DEFAULT_WORKER_IMPORTANCE_SCORING_ENABLED,
+            importance_floor: DEFAULT_WORKER_IMPORTANCE_FLOOR,
+            emotion_tagging_enabled: DEFAULT_WORKER_EMOTION_TAGGING_ENABLED,
+            job_lease_secs: DEFAULT_WORKER_JOB_LEASE_SECS,
+            job_max_attempts: DEFAULT_WORKER_JOB_MAX_ATTEMPTS,
+            job_retry_backoff_secs: DEFAULT_WORKER_JOB_RETRY_BACKOFF_SECS,
+            run_history_max_records: DEFAULT_WORKER_RUN_HISTORY_MAX_RECORDS,
         }
     }
 }
@@ -480,6 +2110,7 @@ impl Default for AppConfig {
     fn default() -> Self {
         Self {
             llm: LLMConfig::default(),
+            local_llm: None,
             storage: StorageConfig::default(),
             raft: RaftConfig::default(),
             worker: WorkerConfig::default(),
@@ -487,6 +2118,20 @@ impl Default for AppConfig {
             vector: VectorConfig::default(),
             sharding: None,
             reranker: RerankerConfig::default(),
+            admission: AdmissionConfig::default(),
+            linking: LinkingConfig::default(),
+            slow_query: SlowQueryConfig::default(),
+            cache: CacheConfig::default(),
+            replication: ReplicationConfig::default(),
+            encryption: EncryptionConfig::default(),
+            moderation: ModerationConfig::default(),
+            prompts: PromptsConfig::default(),
+            experiment: None,
+            traffic_recording: TrafficRecordingConfig::default(),
+            disk_watchdog: DiskWatchdogConfig::default(),
+            temporal_query: TemporalQueryConfig::default(),
+            edge_decay: EdgeDecayConfig::default(),
+            consolidation_pool: ConsolidationPoolConfig::default(),
         }
     }
 }
@@ -542,9 +2187,60 @@ impl AppConfig {
                 "storage.recent_overlay_query_limit",
                 DEFAULT_STORAGE_RECENT_OVERLAY_QUERY_LIMIT as i64,
             )?
+            .set_default(
+                "storage.index_merge_enabled",
+                DEFAULT_STORAGE_INDEX_MERGE_ENABLED,
+            )?
+            .set_default(
+                "storage.index_merge_min_num_segments",
+                DEFAULT_STORAGE_INDEX_MERGE_MIN_NUM_SEGMENTS as i64,
+            )?
+            .set_default(
+                "storage.index_merge_max_docs_before_merge",
+                DEFAULT_STORAGE_INDEX_MERGE_MAX_DOCS_BEFORE_MERGE as i64,
+            )?
+            .set_default(
+                "storage.index_merge_min_layer_size",
+                DEFAULT_STORAGE_INDEX_MERGE_MIN_LAYER_SIZE as i64,
+            )?
+            .set_default(
+                "storage.index_merge_level_log_size",
+                DEFAULT_STORAGE_INDEX_MERGE_LEVEL_LOG_SIZE,
+            )?
+            .set_default(
+                "storage.index_merge_del_docs_ratio",
+                DEFAULT_STORAGE_INDEX_MERGE_DEL_DOCS_RATIO as f64,
+            )?
+            .set_default(
+                "storage.index_sync_min_interval_ms",
+                DEFAULT_STORAGE_INDEX_SYNC_MIN_INTERVAL_MS,
+            )?
+            .set_default(
+                "storage.index_user_buckets",
+                DEFAULT_STORAGE_INDEX_USER_BUCKETS as i64,
+            )?
+            .set_default("storage.backend", "rocksdb")?
+            .set_default("storage.postgres_table", DEFAULT_STORAGE_POSTGRES_TABLE)?
+            .set_default(
+                "storage.postgres_pool_size",
+                DEFAULT_STORAGE_POSTGRES_POOL_SIZE as i64,
+            )?
             .set_default("llm.provider", "gemini")?
             .set_default("llm.model", "")?
             .set_default("llm.embedding_model", "")?
+            .set_default(
+                "llm.structured_output_retries",
+                DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES as i64,
+            )?
+            .set_default(
+                "llm.requests_per_minute",
+                DEFAULT_LLM_REQUESTS_PER_MINUTE as i64,
+            )?
+            .set_default("llm.max_concurrency", DEFAULT_LLM_MAX_CONCURRENCY as i64)?
+            .set_default(
+                "llm.rate_limit_retries",
+                DEFAULT_LLM_RATE_LIMIT_RETRIES as i64,
+            )?
             .set_default("raft.node_id", node_id)?
             .set_default("raft.raft_addr", "127.0.0.1:5001")?
             .set_default(
@@ -561,6 +2257,8 @@ impl AppConfig {
             )?
             .set_default("raft.snapshot_logs", DEFAULT_RAFT_SNAPSHOT_LOGS)?
             .set_default("raft.auto_initialize", true)?
+            .set_default("raft.read_index_wait_ms", DEFAULT_RAFT_READ_INDEX_WAIT_MS)?
+            .set_default("raft.ready_max_lag", DEFAULT_RAFT_READY_MAX_LAG)?
             .set_default(
                 "worker.llm_concurrency",
                 DEFAULT_WORKER_LLM_CONCURRENCY as i64,
@@ -667,7 +2365,205 @@ impl AppConfig {
                 DEFAULT_AUTO_LINK_SIMILARITY_THRESHOLD as f64,
             )?
             .set_default("worker.tick_interval_ms", DEFAULT_WORKER_TICK_INTERVAL_MS)?
+            .set_default(
+                "worker.keyword_extraction_enabled",
+                DEFAULT_WORKER_KEYWORD_EXTRACTION_ENABLED,
+            )?
+            .set_default(
+                "worker.keyword_max_per_memory",
+                DEFAULT_WORKER_KEYWORD_MAX_PER_MEMORY as i64,
+            )?
+            .set_default(
+                "worker.profile_interval_ms",
+                DEFAULT_WORKER_PROFILE_INTERVAL_MS,
+            )?
+            .set_default(
+                "worker.profile_max_users_per_cycle",
+                DEFAULT_WORKER_PROFILE_MAX_USERS_PER_CYCLE as i64,
+            )?
+            .set_default(
+                "worker.profile_max_insights",
+                DEFAULT_WORKER_PROFILE_MAX_INSIGHTS as i64,
+            )?
+            .set_default(
+                "worker.profile_max_memories",
+                DEFAULT_WORKER_PROFILE_MAX_MEMORIES as i64,
+            )?
+            .set_default(
+                "worker.task_deadline_interval_ms",
+                DEFAULT_WORKER_TASK_DEADLINE_INTERVAL_MS,
+            )?
+            .set_default(
+                "worker.task_deadline_warning_ms",
+                DEFAULT_WORKER_TASK_DEADLINE_WARNING_MS,
+            )?
+            .set_default(
+                "worker.auto_planner_max_subtasks",
+                DEFAULT_WORKER_AUTO_PLANNER_MAX_SUBTASKS as i64,
+            )?
+            .set_default(
+                "worker.auto_planner_max_depth",
+                DEFAULT_WORKER_AUTO_PLANNER_MAX_DEPTH as i64,
+            )?
+            .set_default(
+                "worker.auto_planner_require_approval",
+                DEFAULT_WORKER_AUTO_PLANNER_REQUIRE_APPROVAL,
+            )?
+            .set_default(
+                "worker.trash_retention_days",
+                DEFAULT_WORKER_TRASH_RETENTION_DAYS,
+            )?
+            .set_default("worker.audio_chunk_secs", DEFAULT_WORKER_AUDIO_CHUNK_SECS)?
+            .set_default(
+                "worker.video_segment_secs",
+                DEFAULT_WORKER_VIDEO_SEGMENT_SECS,
+            )?
+            .set_default(
+                "worker.community_resummarize_threshold",
+                DEFAULT_WORKER_COMMUNITY_RESUMMARIZE_THRESHOLD as f64,
+            )?
+            .set_default(
+                "worker.l2_refresh_interval_ms",
+                DEFAULT_WORKER_L2_REFRESH_INTERVAL_MS,
+            )?
+            .set_default(
+                "worker.insight_min_reflect_l1",
+                DEFAULT_WORKER_INSIGHT_MIN_REFLECT_L1 as i64,
+            )?
+            .set_default(
+                "worker.insight_min_reflect_tokens",
+                DEFAULT_WORKER_INSIGHT_MIN_REFLECT_TOKENS as i64,
+            )?
+            .set_default(
+                "worker.insight_max_llm_calls_per_cycle",
+                DEFAULT_WORKER_INSIGHT_MAX_LLM_CALLS_PER_CYCLE as i64,
+            )?
+            .set_default(
+                "worker.consolidation_user_workers",
+                DEFAULT_WORKER_CONSOLIDATION_USER_WORKERS as i64,
+            )?
+            .set_default(
+                "worker.sync_consolidation_budget_ms",
+                DEFAULT_WORKER_SYNC_CONSOLIDATION_BUDGET_MS,
+            )?
+            .set_default(
+                "worker.consolidation_max_events_per_user",
+                DEFAULT_WORKER_CONSOLIDATION_MAX_EVENTS_PER_USER as i64,
+            )?
+            .set_default(
+                "worker.cache_warmup_users",
+                DEFAULT_WORKER_CACHE_WARMUP_USERS as i64,
+            )?
+            .set_default(
+                "worker.cache_warmup_nodes_per_user",
+                DEFAULT_WORKER_CACHE_WARMUP_NODES_PER_USER as i64,
+            )?
+            .set_default(
+                "worker.integrity_check_interval_secs",
+                DEFAULT_WORKER_INTEGRITY_CHECK_INTERVAL_SECS as i64,
+            )?
+            .set_default(
+                "worker.integrity_batch_size",
+                DEFAULT_WORKER_INTEGRITY_BATCH_SIZE as i64,
+            )?
+            .set_default("worker.integrity_repair", DEFAULT_WORKER_INTEGRITY_REPAIR)?
+            .set_default(
+                "worker.integrity_repair_orphaned_edges",
+                DEFAULT_WORKER_INTEGRITY_REPAIR_ORPHANED_EDGES,
+            )?
+            .set_default(
+                "worker.orphan_gc_interval_secs",
+                DEFAULT_WORKER_ORPHAN_GC_INTERVAL_SECS as i64,
+            )?
+            .set_default("worker.orphan_gc_dry_run", DEFAULT_WORKER_ORPHAN_GC_DRY_RUN)?
+            .set_default("worker.orphan_gc_edges", DEFAULT_WORKER_ORPHAN_GC_EDGES)?
+            .set_default(
+                "worker.export_interval_secs",
+                DEFAULT_WORKER_EXPORT_INTERVAL_SECS as i64,
+            )?
+            .set_default(
+                "worker.export_max_rows_per_file",
+                DEFAULT_WORKER_EXPORT_MAX_ROWS_PER_FILE as i64,
+            )?
+            .set_default(
+                "worker.stream_segmentation_enabled",
+                DEFAULT_WORKER_STREAM_SEGMENTATION_ENABLED,
+            )?
+            .set_default(
+                "worker.stream_segment_gap_secs",
+                DEFAULT_WORKER_STREAM_SEGMENT_GAP_SECS as i64,
+            )?
+            .set_default(
+                "worker.stream_segment_topic_overlap",
+                DEFAULT_WORKER_STREAM_SEGMENT_TOPIC_OVERLAP as f64,
+            )?
+            .set_default(
+                "worker.stream_segment_min_events",
+                DEFAULT_WORKER_STREAM_SEGMENT_MIN_EVENTS as i64,
+            )?
+            .set_default(
+                "worker.local_summarization_enabled",
+                DEFAULT_WORKER_LOCAL_SUMMARIZATION_ENABLED,
+            )?
+            .set_default(
+                "worker.local_summarization_max_chars",
+                DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_CHARS as i64,
+            )?
+            .set_default(
+                "worker.local_summarization_max_entropy",
+                DEFAULT_WORKER_LOCAL_SUMMARIZATION_MAX_ENTROPY as f64,
+            )?
+            .set_default(
+                "worker.consolidation_chunk_overlap_tokens",
+                DEFAULT_WORKER_CONSOLIDATION_CHUNK_OVERLAP_TOKENS as i64,
+            )?
+            .set_default(
+                "worker.self_eval_interval_secs",
+                DEFAULT_WORKER_SELF_EVAL_INTERVAL_SECS as i64,
+            )?
+            .set_default(
+                "worker.self_eval_sample_size",
+                DEFAULT_WORKER_SELF_EVAL_SAMPLE_SIZE as i64,
+            )?
+            .set_default(
+                "worker.self_eval_top_k",
+                DEFAULT_WORKER_SELF_EVAL_TOP_K as i64,
+            )?
+            .set_default(
+                "worker.storage_usage_interval_secs",
+                DEFAULT_WORKER_STORAGE_USAGE_INTERVAL_SECS as i64,
+            )?
+            .set_default(
+                "worker.importance_scoring_enabled",
+                DEFAULT_WORKER_IMPORTANCE_SCORING_ENABLED,
+            )?
+            .set_default(
+                "worker.importance_floor",
+                DEFAULT_WORKER_IMPORTANCE_FLOOR as f64,
+            )?
+            .set_default(
+                "worker.emotion_tagging_enabled",
+                DEFAULT_WORKER_EMOTION_TAGGING_ENABLED,
+            )?
+            .set_default("worker.job_lease_secs", DEFAULT_WORKER_JOB_LEASE_SECS)?
+            .set_default(
+                "worker.job_max_attempts",
+                DEFAULT_WORKER_JOB_MAX_ATTEMPTS as i64,
+            )?
+            .set_default(
+                "worker.job_retry_backoff_secs",
+                DEFAULT_WORKER_JOB_RETRY_BACKOFF_SECS,
+            )?
+            .set_default(
+                "worker.run_history_max_records",
+                DEFAULT_WORKER_RUN_HISTORY_MAX_RECORDS as i64,
+            )?
             .set_default("vector.enabled", DEFAULT_VECTOR_ENABLED)?
+            .set_default("vector.backend", "lancedb")?
+            .set_default(
+                "vector.pgvector_table_prefix",
+                DEFAULT_VECTOR_PGVECTOR_TABLE_PREFIX,
+            )?
             .set_default(
                 "vector.degrade_on_startup_failure",
                 DEFAULT_VECTOR_DEGRADE_ON_STARTUP_FAILURE,
@@ -730,6 +2626,116 @@ impl AppConfig {
                     config.raft.election_timeout_min_ms, config.raft.election_timeout_max_ms
                 )));
             }
+            config.prompts.validate().map_err(ConfigError::Message)?;
+            if let Some(experiment) = &config.experiment {
+                experiment.validate().map_err(ConfigError::Message)?;
+            }
+            if !(0.0..=1.0).contains(&config.worker.importance_floor) {
+                return Err(ConfigError::Message(format!(
+                    "worker.importance_floor ({}) must be between 0.0 and 1.0",
+                    config.worker.importance_floor
+                )));
+            }
+            if config.worker.job_lease_secs == 0 || config.worker.job_max_attempts == 0 {
+                return Err(ConfigError::Message(
+                    "worker.job_lease_secs and worker.job_max_attempts must be greater than 0"
+                        .to_string(),
+                ));
+            }
+            if !(0.0..=1.0).contains(&config.traffic_recording.sample_rate) {
+                return Err(ConfigError::Message(format!(
+                    "traffic_recording.sample_rate ({}) must be between 0.0 and 1.0",
+                    config.traffic_recording.sample_rate
+                )));
+            }
+            let storage = &config.storage;
+            if storage.index_merge_min_num_segments < 2
+                || storage.index_merge_level_log_size.is_nan()
+                || storage.index_merge_level_log_size <= 0.0
+                || !(storage.index_merge_del_docs_ratio > 0.0
+                    && storage.index_merge_del_docs_ratio <= 1.0)
+            {
+                return Err(ConfigError::Message(format!(
+                    "storage needs index_merge_min_num_segments ({}) >= 2, \
+                     index_merge_level_log_size ({}) > 0 and \
+                     0 < index_merge_del_docs_ratio ({}) <= 1",
+                    storage.index_merge_min_num_segments,
+                    storage.index_merge_level_log_size,
+                    storage.index_merge_del_docs_ratio
+                )));
+            }
+            if !(1..=MAX_STORAGE_INDEX_USER_BUCKETS).contains(&storage.index_user_buckets) {
+                return Err(ConfigError::Message(format!(
+                    "storage.index_user_buckets ({}) must be between 1 and {}",
+                    storage.index_user_buckets, MAX_STORAGE_INDEX_USER_BUCKETS
+                )));
+            }
+            let watchdog = &config.disk_watchdog;
+            if !(0.0..=100.0).contains(&watchdog.read_only_free_percent)
+                || !(watchdog.read_only_free_percent..=100.0).contains(&watchdog.warn_free_percent)
+            {
+                return Err(ConfigError::Message(format!(
+                    "disk_watchdog needs 0 <= read_only_free_percent ({}) <= \
+                     warn_free_percent ({}) <= 100",
+                    watchdog.read_only_free_percent, watchdog.warn_free_percent
+                )));
+            }
+            if !(MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES..=MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES)
+                .contains(&config.temporal_query.utc_offset_minutes)
+            {
+                return Err(ConfigError::Message(format!(
+                    "temporal_query.utc_offset_minutes ({}) must be between {} and {}",
+                    config.temporal_query.utc_offset_minutes,
+                    MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES,
+                    MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES
+                )));
+            }
+            if let Some(node_id) = config.analytics_node_id() {
+                if config.node_role(node_id) != NodeRole::Learner {
+                    return Err(ConfigError::Message(format!(
+                        "sharding.analytics_node_id ({}) must be a node listed in \
+                         sharding.nodes with role = \"learner\"",
+                        node_id
+                    )));
+                }
+            }
+            if config.consolidation_is_pooled() {
+                let pool = &config.consolidation_pool;
+                if pool.lease_secs == 0 {
+                    return Err(ConfigError::Message(
+                        "consolidation_pool.lease_secs must be greater than 0".to_string(),
+                    ));
+                }
+                let listed = |id: &u32| {
+                    config
+                        .sharding
+                        .as_ref()
+                        .is_some_and(|s| s.nodes.iter().any(|node| node.id == *id))
+                };
+                if let Some(id) = pool
+                    .node_ids
+                    .iter()
+                    .find(|id| !listed(id) || config.analytics_node_id() == Some(**id))
+                {
+                    return Err(ConfigError::Message(format!(
+                        "consolidation_pool.node_ids ({}) must be listed in sharding.nodes \
+                         and must not be the analytics node",
+                        id
+                    )));
+                }
+            }
+            for (relation, rule) in &config.edge_decay.relations {
+                if rule.half_life_days.is_nan()
+                    || rule.half_life_days <= 0.0
+                    || !(0.0..=1.0).contains(&rule.prune_below)
+                {
+                    return Err(ConfigError::Message(format!(
+                        "edge_decay.relations.{} needs half_life_days ({}) > 0 and \
+                         0 <= prune_below ({}) <= 1",
+                        relation, rule.half_life_days, rule.prune_below
+                    )));
+                }
+            }
             Ok(config)
         })
     }
@@ -781,6 +2787,76 @@ impl AppConfig {
             .map_or(self.raft.node_id as u32, |s| s.physical_node_id)
     }
 
+    /// Returns the configured HTTP address of the physical node behind a Raft node ID.
+    /// Multi-shard Raft IDs encode the physical node; single-shard IDs are the physical ID.
+    pub fn http_addr_for_raft_node(&self, raft_node_id: u64) -> Option<&str> {
+        let sharding = self.sharding.as_ref().filter(|s| s.enabled)?;
+        let physical_node_id = if self.is_sharded() {
+            crate::sharding::decode_raft_node_id(raft_node_id).1
+        } else {
+            u32::try_from(raft_node_id).ok()?
+        };
+        sharding
+            .nodes
+            .iter()
+            .find(|node| node.id == physical_node_id)
+            .map(|node| node.http_addr.as_str())
+    }
+
+    /// Returns the configured role of a physical node; nodes not listed in
+    /// `sharding.nodes` are voters.
+    pub fn node_role(&self, physical_node_id: u32) -> NodeRole {
+        self.sharding
+            .as_ref()
+            .filter(|s| s.enabled)
+            .and_then(|s| s.nodes.iter().find(|node| node.id == physical_node_id))
+            .map_or(NodeRole::Voter, |node| node.role)
+    }
+
+    /// Returns the physical node designated as the analytics replica, if any.
+    pub fn analytics_node_id(&self) -> Option<u32> {
+        self.sharding
+            .as_ref()
+            .filter(|s| s.enabled)
+            .and_then(|s| s.analytics_node_id)
+    }
+
+    /// Returns true when this node is the analytics replica.
+    pub fn is_analytics_replica(&self) -> bool {
+        self.analytics_node_id() == Some(self.physical_node_id())
+    }
+
+    /// Returns true when a consolidation pool takes text consolidation off the shard
+    /// leaders. Standalone nodes always consolidate locally.
+    pub fn consolidation_is_pooled(&self) -> bool {
+        self.is_cluster_mode() && !self.consolidation_pool.node_ids.is_empty()
+    }
+
+    /// Returns true when this node belongs to the consolidation pool.
+    pub fn is_consolidation_pool_member(&self) -> bool {
+        self.consolidation_is_pooled()
+            && self
+                .consolidation_pool
+                .node_ids
+                .contains(&self.physical_node_id())
+    }
+
+    /// Returns the HTTP address this node listens on, which it also advertises to
+    /// Raft peers. `None` when a sharded node is missing from `sharding.nodes`.
+    pub fn local_http_addr(&self) -> Option<String> {
+        if self.is_sharded() {
+            let sharding = self.sharding.as_ref()?;
+            sharding
+                .nodes
+                .iter()
+                .find(|node| node.id == sharding.physical_node_id)
+                .map(|node| node.http_addr.clone())
+        } else {
+            let http_port = 3000 + (self.raft.node_id as u16 - 1);
+            Some(format!("0.0.0.0:{}", http_port))
+        }
+    }
+
     /// Returns the number of physical nodes described by topology config.
     pub fn cluster_node_count(&self) -> u32 {
         self.sharding
@@ -810,9 +2886,12 @@ impl AppConfig {
         }
     }
 
-    /// Returns true when startup should auto-bootstrap local raft groups.
+    /// Returns true when startup should auto-bootstrap local raft groups. A learner
+    /// never bootstraps, since it would become the only voter of the new group.
     pub fn should_auto_initialize_raft(&self) -> bool {
-        self.raft.auto_initialize && self.is_bootstrap_seed_node()
+        self.raft.auto_initialize
+            && self.is_bootstrap_seed_node()
+            && self.node_role(self.physical_node_id()) == NodeRole::Voter
     }
 
     /// Returns true when auto-bootstrap is enabled but multi-node topology
@@ -824,6 +2903,112 @@ impl AppConfig {
     }
 }
 
+/// Settings that are only read while the process starts, so changing them on reload has no
+/// effect until the next restart. A whole section is listed by its name.
+const RESTART_REQUIRED_CONFIG_KEYS: &[&str] = &[
+    "llm",
+    "local_llm",
+    "storage",
+    "raft",
+    "vector",
+    "sharding",
+    "admission",
+    "linking",
+    "edge_decay",
+    "slow_query.capacity",
+    "cache",
+    "encryption",
+    "moderation",
+    "reranker.type",
+    "reranker.endpoint",
+    "worker.tick_interval_ms",
+    "worker.enable_auto_planner",
+    "worker.enable_task_reflection",
+    "worker.auto_link_similarity_threshold",
+    "worker.auto_planner_max_subtasks",
+    "worker.auto_planner_max_depth",
+    "worker.auto_planner_require_approval",
+];
+
+/// Outcome of [`LiveConfig::reload`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConfigReloadReport {
+    /// Every setting whose value changed, as `section.field`.
+    pub changed: Vec<String>,
+    /// The subset of `changed` that only takes effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+/// The process-wide configuration, swappable at runtime. Clones share the same value, so
+/// components holding a `LiveConfig` see a reload on their next [`LiveConfig::load`].
+#[derive(Debug, Clone)]
+pub struct LiveConfig(Arc<ArcSwap<AppConfig>>);
+
+impl LiveConfig {
+    pub fn new(config: AppConfig) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    /// The current configuration.
+    pub fn load(&self) -> Arc<AppConfig> {
+        self.0.load_full()
+    }
+
+    /// Replace the configuration and report what changed.
+    pub fn store(&self, config: AppConfig) -> ConfigReloadReport {
+        let report = Self::diff(&self.load(), &config);
+        self.0.store(Arc::new(config));
+        report
+    }
+
+    /// Re-read the configuration files and environment. An invalid configuration is
+    /// rejected and the current one stays in place.
+    pub fn reload(&self) -> Result<ConfigReloadReport, ConfigError> {
+        let config = AppConfig::load()?;
+        Ok(self.store(config))
+    }
+
+    fn diff(old: &AppConfig, new: &AppConfig) -> ConfigReloadReport {
+        let to_value = |config: &AppConfig| serde_json::to_value(config).unwrap_or_default();
+        let (old, new) = (to_value(old), to_value(new));
+        let mut changed = Vec::new();
+        if let (Some(old), Some(new)) = (old.as_object(), new.as_object()) {
+            for (section, new_value) in new {
+                let old_value = old.get(section).unwrap_or(&serde_json::Value::Null);
+                match (old_value.as_object(), new_value.as_object()) {
+                    (Some(old_fields), Some(new_fields)) => {
+                        for (field, value) in new_fields {
+                            if old_fields.get(field) != Some(value) {
+                                changed.push(format!("{}.{}", section, field));
+                            }
+                        }
+                    }
+                    _ if old_value != new_value => changed.push(section.clone()),
+                    _ => {}
+                }
+            }
+        }
+        changed.sort();
+
+        let restart_required = changed
+            .iter()
+            .filter(|key| {
+                RESTART_REQUIRED_CONFIG_KEYS.iter().any(|entry| {
+                    key.as_str() == *entry
+                        || key
+                            .strip_prefix(entry)
+                            .is_some_and(|rest| rest.starts_with('.'))
+                })
+            })
+            .cloned()
+            .collect();
+        ConfigReloadReport {
+            changed,
+            restart_required,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -881,11 +3066,58 @@ mod tests {
         assert_eq!(config.vector.schema_version, 3);
     }
 
+    #[test]
+    fn test_live_config_store_reports_changed_and_restart_required_keys() {
+        let live = LiveConfig::new(AppConfig::default());
+        let reader = live.clone();
+
+        let mut config = AppConfig::default();
+        config.worker.decay_interval_secs += 60;
+        config.worker.tick_interval_ms += 100;
+        config.reranker.recency_half_life_hours = 12.0;
+        config.storage.root_dir = "/tmp/elsewhere".to_string();
+        config.admission.throttle_pending += 1;
+        config.linking.deferred_batch_size += 1;
+        config.edge_decay.enabled = !config.edge_decay.enabled;
+        config.worker.auto_link_similarity_threshold += 0.01;
+        let report = live.store(config);
+
+        assert_eq!(
+            report.changed,
+            vec![
+                "admission.throttle_pending",
+                "edge_decay.enabled",
+                "linking.deferred_batch_size",
+                "reranker.recency_half_life_hours",
+                "storage.root_dir",
+                "worker.auto_link_similarity_threshold",
+                "worker.decay_interval_secs",
+                "worker.tick_interval_ms",
+            ]
+        );
+        assert_eq!(
+            report.restart_required,
+            vec![
+                "admission.throttle_pending",
+                "edge_decay.enabled",
+                "linking.deferred_batch_size",
+                "storage.root_dir",
+                "worker.auto_link_similarity_threshold",
+                "worker.tick_interval_ms",
+            ]
+        );
+        assert_eq!(reader.load().reranker.recency_half_life_hours, 12.0);
+
+        let unchanged = live.store((*live.load()).clone());
+        assert!(unchanged.changed.is_empty());
+    }
+
     #[test]
     fn test_multi_node_topology_requires_explicit_seed() {
         let mut config = AppConfig::default();
         config.sharding = Some(ShardingConfig {
             enabled: true,
+            analytics_node_id: None,
             shard_count: 4,
             physical_node_id: 1,
             nodes: vec![
@@ -893,11 +3125,13 @@ mod tests {
                     id: 1,
                     http_addr: "10.0.0.1:3000".into(),
                     raft_base_port: 5001,
+                    role: NodeRole::Voter,
                 },
                 ShardNodeConfig {
                     id: 2,
                     http_addr: "10.0.0.2:3000".into(),
                     raft_base_port: 5001,
+                    role: NodeRole::Voter,
                 },
             ],
         });
@@ -915,6 +3149,7 @@ mod tests {
         let mut config = AppConfig::default();
         config.sharding = Some(ShardingConfig {
             enabled: true,
+            analytics_node_id: None,
             shard_count: 4,
             physical_node_id: 2,
             nodes: vec![
@@ -922,11 +3157,13 @@ mod tests {
                     id: 1,
                     http_addr: "10.0.0.1:3000".into(),
                     raft_base_port: 5001,
+                    role: NodeRole::Voter,
                 },
                 ShardNodeConfig {
                     id: 2,
                     http_addr: "10.0.0.2:3000".into(),
                     raft_base_port: 5001,
+                    role: NodeRole::Voter,
                 },
             ],
         });
@@ -977,6 +3214,7 @@ mod tests {
 
         config.sharding = Some(ShardingConfig {
             enabled: true,
+            analytics_node_id: None,
             shard_count: 1,
             physical_node_id: 1,
             nodes: vec![],
@@ -1002,6 +3240,10 @@ mod tests {
             embedding_task_type: None,
             stt_provider: None,
             stt_model: None,
+            structured_output_retries: 0,
+            requests_per_minute: 0,
+            max_concurrency: 1,
+            rate_limit_retries: 0,
         };
         assert_eq!(
             config.get_base_url(),
@@ -1067,6 +3309,17 @@ mod tests {
             default_recent_overlay_query_limit(),
             DEFAULT_STORAGE_RECENT_OVERLAY_QUERY_LIMIT
         );
+        let storage: StorageConfig = serde_json::from_str(r#"{"root_dir": "./data"}"#).unwrap();
+        assert!(storage.index_merge_enabled);
+        assert_eq!(
+            storage.index_merge_min_num_segments,
+            DEFAULT_STORAGE_INDEX_MERGE_MIN_NUM_SEGMENTS
+        );
+        assert!(storage.index_app_commit_interval_ms.is_empty());
+        assert_eq!(
+            storage.index_user_buckets,
+            DEFAULT_STORAGE_INDEX_USER_BUCKETS
+        );
     }
 
     #[test]
@@ -1076,6 +3329,145 @@ mod tests {
         assert_eq!(default_physical_node_id(), 1);
     }
 
+    #[test]
+    fn test_replication_sink_requires_enabled_and_url() {
+        let mut replication = ReplicationConfig::default();
+        assert_eq!(replication.active_sink(), None);
+        replication.sink_url = Some(" https://remote.example ".to_string());
+        assert_eq!(replication.active_sink(), None);
+        replication.enabled = true;
+        assert_eq!(replication.active_sink(), Some("https://remote.example"));
+        replication.sink_url = Some("  ".to_string());
+        assert_eq!(replication.active_sink(), None);
+    }
+
+    #[test]
+    fn test_edge_decay_prunes_only_idle_weak_edges() {
+        let mut edge_decay = EdgeDecayConfig::default();
+        assert!(edge_decay.rule_for("RelatedTo").is_none());
+        edge_decay.enabled = true;
+        assert!(edge_decay.rule_for("DerivedFrom").is_none());
+
+        let rule = edge_decay.rule_for("RelatedTo").unwrap();
+        let half_life = chrono::Duration::days(30);
+        assert!((rule.decayed_weight(0.8, half_life) - 0.4).abs() < 1e-6);
+        assert!(!rule.should_prune(0.7, chrono::Duration::days(30)));
+        assert!(rule.should_prune(0.7, chrono::Duration::days(60)));
+        assert!(!rule.should_prune(0.1, chrono::Duration::days(7)));
+    }
+
+    #[test]
+    fn test_moderation_policy_falls_back_to_default() {
+        let moderation: ModerationConfig = toml::from_str(
+            r#"
+            provider = "regex"
+            patterns = { ssn = '\b\d{3}-\d{2}-\d{4}\b' }
+
+            [default_policy]
+            ingest = "tag"
+
+            [apps.kids-tutor]
+            ingest = "block"
+            strip_from_retrieval = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(moderation.provider, ModerationProvider::Regex);
+        assert_eq!(moderation.patterns["ssn"], r"\b\d{3}-\d{2}-\d{4}\b");
+        let strict = moderation.policy_for(Some("kids-tutor"));
+        assert_eq!(strict.ingest, ModerationIngestAction::Block);
+        assert!(strict.strip_from_retrieval);
+        for app_id in [Some("other-app"), None] {
+            let fallback = moderation.policy_for(app_id);
+            assert_eq!(fallback.ingest, ModerationIngestAction::Tag);
+            assert!(!fallback.strip_from_retrieval);
+        }
+        assert_eq!(
+            ModerationConfig::default().provider,
+            ModerationProvider::None
+        );
+    }
+
+    #[test]
+    fn test_prompt_templates_fall_back_per_kind_and_validate() {
+        let prompts: PromptsConfig = toml::from_str(
+            r#"
+            [default]
+            reflection = "Find topics. {{language_instruction}}"
+
+            [apps.support-bot]
+            compression = "Summarize the ticket for {{app_id}}. {{speakers}}"
+            chat = "You answer for {{app_id}}."
+            "#,
+        )
+        .unwrap();
+        assert!(prompts.validate().is_ok());
+
+        assert_eq!(
+            prompts.template_for(PromptKind::Compression, Some("support-bot")),
+            Some("Summarize the ticket for {{app_id}}. {{speakers}}")
+        );
+        assert_eq!(
+            prompts.template_for(PromptKind::Reflection, Some("support-bot")),
+            Some("Find topics. {{language_instruction}}")
+        );
+        assert_eq!(prompts.template_for(PromptKind::Compression, None), None);
+        assert_eq!(
+            prompts.template_for(PromptKind::Chat, Some("support-bot")),
+            Some("You answer for {{app_id}}.")
+        );
+
+        let mut invalid = prompts.clone();
+        invalid.apps.get_mut("support-bot").unwrap().decomposition =
+            Some("Plan {{speakers}}".into());
+        let error = invalid.validate().unwrap_err();
+        assert!(error.starts_with("prompts.apps.support-bot.decomposition:"));
+    }
+
+    #[test]
+    fn test_experiment_assigns_users_stably_by_weight() {
+        let experiment: ExperimentConfig = toml::from_str(
+            r#"
+            name = "rrf-k"
+
+            [[variants]]
+            name = "control"
+            weight = 3
+
+            [[variants]]
+            name = "flat"
+            rrf_k = 120.0
+            graph_depth = 2
+            "#,
+        )
+        .unwrap();
+        assert!(experiment.validate().is_ok());
+        assert_eq!(experiment.assignment, ExperimentAssignment::User);
+        assert_eq!(experiment.variant("flat").unwrap().rrf_k, Some(120.0));
+
+        let mut control = 0;
+        for index in 0..400 {
+            let user_id = format!("user-{}", index);
+            let variant = experiment.assign(&user_id).unwrap();
+            assert_eq!(experiment.assign(&user_id).unwrap().name, variant.name);
+            if variant.name == "control" {
+                control += 1;
+            }
+        }
+        assert!((240..=360).contains(&control), "control got {}", control);
+
+        let mut invalid = experiment.clone();
+        invalid.variants[1].name = "control".into();
+        assert!(invalid.validate().is_err());
+        invalid
+            .variants
+            .iter_mut()
+            .for_each(|variant| variant.weight = 0);
+        assert!(invalid.validate().is_err());
+        assert!(invalid.assign("user-1").is_none());
+    }
+
     #[test]
     fn test_app_config_accessors() {
         let mut config = AppConfig::default();
@@ -1111,6 +3503,7 @@ mod tests {
 
         config.sharding = Some(ShardingConfig {
             enabled: true,
+            analytics_node_id: None,
             shard_count: 5,
             physical_node_id: 10,
             nodes: vec![
@@ -1118,11 +3511,13 @@ mod tests {
                     id: 1,
                     http_addr: "".into(),
                     raft_base_port: 0,
+                    role: NodeRole::Voter,
                 },
                 ShardNodeConfig {
                     id: 2,
                     http_addr: "".into(),
                     raft_base_port: 0,
+                    role: NodeRole::Voter,
                 },
             ],
         });
@@ -1133,4 +3528,88 @@ mod tests {
         assert_eq!(config.cluster_node_count(), 2);
         assert_eq!(config.is_cluster_mode(), true);
     }
+
+    #[test]
+    fn test_http_addr_for_raft_node_resolves_physical_node() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.http_addr_for_raft_node(1), None);
+
+        let node = |id: u32, http_addr: &str| ShardNodeConfig {
+            id,
+            http_addr: http_addr.into(),
+            raft_base_port: 5000,
+            role: NodeRole::Voter,
+        };
+        config.sharding = Some(ShardingConfig {
+            enabled: true,
+            analytics_node_id: None,
+            shard_count: 1,
+            physical_node_id: 1,
+            nodes: vec![node(1, "10.0.0.1:3000"), node(2, "10.0.0.2:3000")],
+        });
+        assert_eq!(config.http_addr_for_raft_node(2), Some("10.0.0.2:3000"));
+        assert_eq!(config.http_addr_for_raft_node(3), None);
+
+        config.sharding.as_mut().unwrap().shard_count = 4;
+        assert_eq!(config.http_addr_for_raft_node(3002), Some("10.0.0.2:3000"));
+        assert_eq!(config.http_addr_for_raft_node(1001), Some("10.0.0.1:3000"));
+    }
+
+    #[test]
+    fn test_learner_nodes_never_auto_initialize() {
+        let sharding: ShardingConfig = toml::from_str(
+            r#"
+            enabled = true
+            shard_count = 2
+            physical_node_id = 3
+            nodes = [
+                { id = 1, http_addr = "10.0.0.1:3000", raft_base_port = 5001 },
+                { id = 3, http_addr = "10.0.0.3:3000", raft_base_port = 5001, role = "learner" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let mut config = AppConfig {
+            sharding: Some(sharding),
+            ..Default::default()
+        };
+        config.raft.bootstrap_seed_node_id = Some(3);
+
+        assert_eq!(config.node_role(1), NodeRole::Voter);
+        assert_eq!(config.node_role(3), NodeRole::Learner);
+        assert_eq!(config.node_role(9), NodeRole::Voter);
+        assert!(config.is_bootstrap_seed_node());
+        assert!(!config.should_auto_initialize_raft());
+        assert!(!config.is_analytics_replica());
+
+        config.sharding.as_mut().unwrap().analytics_node_id = Some(3);
+        assert_eq!(config.analytics_node_id(), Some(3));
+        assert!(config.is_analytics_replica());
+    }
+
+    #[test]
+    fn test_consolidation_pool_membership_needs_cluster_mode() {
+        let sharding: ShardingConfig = toml::from_str(
+            r#"
+            enabled = true
+            shard_count = 2
+            physical_node_id = 4
+            nodes = [
+                { id = 1, http_addr = "10.0.0.1:3000", raft_base_port = 5001 },
+                { id = 4, http_addr = "10.0.0.4:3000", raft_base_port = 5001 },
+            ]
+            "#,
+        )
+        .unwrap();
+        let mut config = AppConfig::default();
+        config.consolidation_pool.node_ids = vec![4];
+        assert!(!config.consolidation_is_pooled());
+        assert!(!config.is_consolidation_pool_member());
+
+        config.sharding = Some(sharding);
+        assert!(config.consolidation_is_pooled());
+        assert!(config.is_consolidation_pool_member());
+        config.sharding.as_mut().unwrap().physical_node_id = 1;
+        assert!(!config.is_consolidation_pool_member());
+    }
 }
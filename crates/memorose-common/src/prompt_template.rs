@@ -0,0 +1,112 @@
+//! Handlebars-style `{{variable}}` substitution for configurable LLM prompts.
+//!
+//! Only plain variables are supported: no helpers, blocks or partials. `\{{` writes a
+//! literal `{{`, which prompts need for JSON examples.
+
+/// Replace every `{{name}}` in `template` with its value from `vars`. Variables without a
+/// value render as empty text, so run [`validate`] on templates before use.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    // Validated templates never fail to parse; anything unparsable is kept as written.
+    let Ok(segments) = parse(template) else {
+        return template.to_string();
+    };
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Literal => out.push_str("{{"),
+            Segment::Variable(name) => {
+                if let Some((_, value)) = vars.iter().find(|(var, _)| *var == name) {
+                    out.push_str(value);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Check that `template` parses and only uses variables from `allowed`.
+pub fn validate(template: &str, allowed: &[&str]) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("template is empty".to_string());
+    }
+    for segment in parse(template)? {
+        if let Segment::Variable(name) = segment {
+            if !allowed.contains(&name) {
+                return Err(format!(
+                    "unknown variable `{{{{{}}}}}`; expected one of: {}",
+                    name,
+                    allowed.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Literal,
+    Variable(&'a str),
+}
+
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 && rest.as_bytes()[start - 1] == b'\\' {
+            segments.push(Segment::Text(&rest[..start - 1]));
+            segments.push(Segment::Literal);
+            rest = &rest[start + 2..];
+            continue;
+        }
+        segments.push(Segment::Text(&rest[..start]));
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            format!(
+                "unclosed `{{{{` at byte {}",
+                template.len() - rest.len() + start
+            )
+        })?;
+        let name = after[..end].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid variable `{{{{{}}}}}`", &after[..end]));
+        }
+        segments.push(Segment::Variable(name));
+        rest = &after[end + 2..];
+    }
+    segments.push(Segment::Text(rest));
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_substitutes_variables_and_escapes() {
+        let rendered = render(
+            "Summarize for {{ app_id }}. {{language_instruction}} Output \\{{\"content\": \"...\"}}",
+            &[("app_id", "support"), ("language_instruction", "Keep the language.")],
+        );
+        assert_eq!(
+            rendered,
+            "Summarize for support. Keep the language. Output {{\"content\": \"...\"}}"
+        );
+        assert_eq!(render("{{speakers}}done", &[]), "done");
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_and_malformed_variables() {
+        let allowed = ["app_id", "language_instruction"];
+        assert!(validate("Hello {{app_id}}", &allowed).is_ok());
+        assert!(validate("  ", &allowed).is_err());
+        assert!(validate("Hello {{user}}", &allowed)
+            .unwrap_err()
+            .contains("unknown variable `{{user}}`"));
+        assert!(validate("Hello {{app_id", &allowed)
+            .unwrap_err()
+            .contains("unclosed"));
+        assert!(validate("Hello {{app id}}", &allowed).is_err());
+    }
+}
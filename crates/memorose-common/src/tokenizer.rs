@@ -42,6 +42,57 @@ pub fn count_tokens(text: &str) -> usize {
     total
 }
 
+/// Split `text` into consecutive chunks of at most `max_tokens` tokens, each repeating
+/// roughly the last `overlap_tokens` of the one before so no sentence loses its context.
+/// Chunks break after whitespace or a CJK character; a single longer word forms its own
+/// chunk. Text within the budget is returned whole.
+pub fn split_by_tokens(text: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<&str> {
+    let max_tokens = max_tokens.max(1);
+    if count_tokens(text) <= max_tokens {
+        return vec![text];
+    }
+
+    // Byte ranges of the pieces a chunk may break between, with their token counts
+    let mut pieces: Vec<(usize, usize, usize)> = Vec::new();
+    let mut start = 0;
+    for (index, ch) in text.char_indices() {
+        let end = index + ch.len_utf8();
+        if ch.is_whitespace() || is_cjk(ch) {
+            pieces.push((start, end, count_tokens(&text[start..end])));
+            start = end;
+        }
+    }
+    if start < text.len() {
+        pieces.push((start, text.len(), count_tokens(&text[start..])));
+    }
+
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < pieces.len() {
+        let mut last = first;
+        let mut tokens = pieces[first].2;
+        while last + 1 < pieces.len() && tokens + pieces[last + 1].2 <= max_tokens {
+            last += 1;
+            tokens += pieces[last].2;
+        }
+        chunks.push(text[pieces[first].0..pieces[last].1].trim());
+        if last + 1 == pieces.len() {
+            break;
+        }
+
+        // Step back from the end of this chunk for the overlap, always moving forward
+        let mut next = last + 1;
+        let mut overlap = 0;
+        while next > first + 1 && overlap + pieces[next - 1].2 <= overlap_tokens {
+            next -= 1;
+            overlap += pieces[next].2;
+        }
+        first = next;
+    }
+    chunks.retain(|chunk| !chunk.is_empty());
+    chunks
+}
+
 fn is_cjk(ch: char) -> bool {
     matches!(
         ch as u32,
@@ -55,7 +106,7 @@ fn is_cjk(ch: char) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::count_tokens;
+    use super::{count_tokens, split_by_tokens};
 
     #[test]
     fn count_tokens_handles_plain_english() {
@@ -69,4 +120,21 @@ mod tests {
         assert_eq!(count_tokens("北京"), 2);
         assert!(count_tokens("我住在北京。") >= 6);
     }
+
+    #[test]
+    fn split_by_tokens_overlaps_chunks_within_budget() {
+        assert_eq!(split_by_tokens("short text", 10, 2), vec!["short text"]);
+
+        let text = "a b c d e f g h i j";
+        let chunks = split_by_tokens(text, 4, 1);
+        assert_eq!(chunks, vec!["a b c d", "d e f g", "g h i j"]);
+        assert!(chunks.iter().all(|chunk| count_tokens(chunk) <= 4));
+
+        // Without overlap every word appears once
+        assert_eq!(split_by_tokens(text, 3, 0).join(" "), text);
+
+        // CJK text breaks between characters
+        let chunks = split_by_tokens("我住在北京市朝阳区", 4, 1);
+        assert_eq!(chunks, vec!["我住在北", "北京市朝", "朝阳区"]);
+    }
 }
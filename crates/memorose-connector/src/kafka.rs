@@ -0,0 +1,62 @@
+use crate::Pipeline;
+use anyhow::{Context, Result};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::Message;
+
+pub struct KafkaSource {
+    pub brokers: String,
+    pub topic: String,
+    pub group_id: String,
+}
+
+impl KafkaSource {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            brokers: std::env::var("KAFKA_BROKERS").context("KAFKA_BROKERS must be set")?,
+            topic: std::env::var("KAFKA_TOPIC").context("KAFKA_TOPIC must be set")?,
+            group_id: std::env::var("KAFKA_GROUP_ID")
+                .unwrap_or_else(|_| "memorose-connector".to_string()),
+        })
+    }
+
+    /// Consume the topic in batches. Offsets are committed only after a batch has been
+    /// written, so a restart resumes from the first message not yet in Memorose.
+    pub async fn run(&self, pipeline: &Pipeline) -> Result<()> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .context("Failed to create Kafka consumer")?;
+        consumer.subscribe(&[&self.topic])?;
+        tracing::info!(
+            "Consuming Kafka topic {} from {} as group {}",
+            self.topic,
+            self.brokers,
+            self.group_id
+        );
+
+        loop {
+            let deadline = tokio::time::Instant::now() + pipeline.flush_interval;
+            let mut payloads = Vec::with_capacity(pipeline.batch_size);
+            while payloads.len() < pipeline.batch_size {
+                match tokio::time::timeout_at(deadline, consumer.recv()).await {
+                    Ok(Ok(message)) => {
+                        payloads.push(message.payload().unwrap_or_default().to_vec())
+                    }
+                    Ok(Err(e)) => tracing::warn!("Kafka error: {}", e),
+                    Err(_) => break,
+                }
+            }
+            if payloads.is_empty() {
+                continue;
+            }
+            pipeline.deliver(&payloads).await;
+            if let Err(e) = consumer.commit_consumer_state(CommitMode::Sync) {
+                tracing::warn!("Failed to commit Kafka offsets: {}", e);
+            }
+        }
+    }
+}
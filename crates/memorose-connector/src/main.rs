@@ -0,0 +1,91 @@
+//! Sidecar that consumes conversation events from Kafka or NATS JetStream and writes
+//! them to Memorose. Configured entirely through environment variables.
+
+#[cfg(feature = "kafka")]
+mod kafka;
+mod mapping;
+#[cfg(feature = "nats")]
+mod nats;
+mod sink;
+
+use anyhow::{anyhow, Result};
+use mapping::Mapping;
+use sink::{DeliveryTotals, MemoroseSink};
+use std::time::Duration;
+
+/// Everything a source needs to hand a consumed batch to Memorose.
+pub struct Pipeline {
+    mapping: Mapping,
+    sink: MemoroseSink,
+    totals: DeliveryTotals,
+    pub batch_size: usize,
+    /// Longest a partial batch waits for more messages before it is written
+    pub flush_interval: Duration,
+}
+
+impl Pipeline {
+    /// Write a batch; returns once it is safe to checkpoint past it.
+    pub async fn deliver(&self, payloads: &[Vec<u8>]) {
+        let report = self.sink.deliver(&self.mapping, payloads).await;
+        self.totals.record(&report);
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .map(|raw| {
+            raw.parse()
+                .unwrap_or_else(|_| panic!("{} must be a number", name))
+        })
+        .unwrap_or(default)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let mapping = match std::env::var("CONNECTOR_MAPPING") {
+        Ok(raw) => Mapping::from_json(&raw)?,
+        Err(_) => Mapping::default(),
+    };
+    let memorose_url =
+        std::env::var("MEMOROSE_URL").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string());
+    let pipeline = Pipeline {
+        mapping,
+        sink: MemoroseSink::new(&memorose_url, std::env::var("MEMOROSE_API_KEY").ok()),
+        totals: DeliveryTotals::default(),
+        batch_size: env_or("CONNECTOR_BATCH_SIZE", 500usize).max(1),
+        flush_interval: Duration::from_millis(env_or("CONNECTOR_FLUSH_INTERVAL_MS", 1_000u64)),
+    };
+    tracing::info!(
+        "Connector writing to {} in batches of up to {} every {:?}",
+        memorose_url,
+        pipeline.batch_size,
+        pipeline.flush_interval
+    );
+
+    let source = std::env::var("CONNECTOR_SOURCE").unwrap_or_default();
+    match source.as_str() {
+        #[cfg(feature = "kafka")]
+        "kafka" => kafka::KafkaSource::from_env()?.run(&pipeline).await,
+        #[cfg(feature = "nats")]
+        "nats" => nats::NatsSource::from_env()?.run(&pipeline).await,
+        other => Err(anyhow!(
+            "CONNECTOR_SOURCE {:?} is not supported by this build (enabled: {})",
+            other,
+            enabled_sources().join(", ")
+        )),
+    }
+}
+
+fn enabled_sources() -> Vec<&'static str> {
+    let mut sources = Vec::new();
+    if cfg!(feature = "kafka") {
+        sources.push("kafka");
+    }
+    if cfg!(feature = "nats") {
+        sources.push("nats");
+    }
+    sources
+}
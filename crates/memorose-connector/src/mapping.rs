@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Where each event field lives in a consumed message, as JSON pointers
+/// (e.g. `/user/id`). Read from `CONNECTOR_MAPPING`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Mapping {
+    #[serde(default = "default_user_id")]
+    pub user_id: String,
+    #[serde(default = "default_stream_id")]
+    pub stream_id: String,
+    #[serde(default = "default_content")]
+    pub content: String,
+    /// Pointer to the content type; messages without one use `default_content_type`
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default = "default_content_type")]
+    pub default_content_type: String,
+    /// Stream for messages that carry no stream id
+    #[serde(default)]
+    pub default_stream_id: Option<Uuid>,
+}
+
+fn default_user_id() -> String {
+    "/user_id".to_string()
+}
+
+fn default_stream_id() -> String {
+    "/stream_id".to_string()
+}
+
+fn default_content() -> String {
+    "/content".to_string()
+}
+
+fn default_content_type() -> String {
+    "text".to_string()
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            user_id: default_user_id(),
+            stream_id: default_stream_id(),
+            content: default_content(),
+            content_type: None,
+            org_id: None,
+            default_content_type: default_content_type(),
+            default_stream_id: None,
+        }
+    }
+}
+
+/// One message turned into the body of a Memorose ingest request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MappedEvent {
+    #[serde(skip)]
+    pub user_id: String,
+    #[serde(skip)]
+    pub stream_id: Uuid,
+    pub content: String,
+    pub content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+}
+
+impl Mapping {
+    pub fn from_json(raw: &str) -> Result<Self> {
+        serde_json::from_str(raw).context("CONNECTOR_MAPPING is not a valid mapping")
+    }
+
+    /// Map a raw message payload to an event.
+    pub fn map(&self, payload: &[u8]) -> Result<MappedEvent> {
+        let message: Value = serde_json::from_slice(payload).context("message is not JSON")?;
+        let user_id = string_at(&message, &self.user_id)
+            .ok_or_else(|| anyhow!("no user id at {}", self.user_id))?;
+        let stream_id = match string_at(&message, &self.stream_id) {
+            Some(raw) => Uuid::parse_str(&raw)
+                .with_context(|| format!("stream id {:?} is not a UUID", raw))?,
+            None => self
+                .default_stream_id
+                .ok_or_else(|| anyhow!("no stream id at {}", self.stream_id))?,
+        };
+        let content_type = self
+            .content_type
+            .as_deref()
+            .and_then(|pointer| string_at(&message, pointer))
+            .unwrap_or_else(|| self.default_content_type.clone());
+        // Structured content is forwarded as its JSON text.
+        let content = match message.pointer(&self.content) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => return Err(anyhow!("no content at {}", self.content)),
+            Some(value) => value.to_string(),
+        };
+        let org_id = self
+            .org_id
+            .as_deref()
+            .and_then(|pointer| string_at(&message, pointer));
+        Ok(MappedEvent {
+            user_id,
+            stream_id,
+            content,
+            content_type,
+            org_id,
+        })
+    }
+}
+
+/// The value at `pointer` as a string; numbers are accepted so numeric user ids map.
+fn string_at(message: &Value, pointer: &str) -> Option<String> {
+    match message.pointer(pointer)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_reads_nested_fields() {
+        let mapping = Mapping::from_json(
+            r#"{"user_id": "/user/id", "stream_id": "/conversation", "content": "/message/text", "org_id": "/tenant"}"#,
+        )
+        .unwrap();
+        let stream = Uuid::new_v4();
+        let payload = serde_json::json!({
+            "user": { "id": 42 },
+            "conversation": stream.to_string(),
+            "message": { "text": "hello" },
+            "tenant": "acme"
+        });
+        let event = mapping.map(payload.to_string().as_bytes()).unwrap();
+        assert_eq!(event.user_id, "42");
+        assert_eq!(event.stream_id, stream);
+        assert_eq!(event.content, "hello");
+        assert_eq!(event.content_type, "text");
+        assert_eq!(event.org_id.as_deref(), Some("acme"));
+    }
+
+    #[test]
+    fn test_map_falls_back_to_default_stream_and_serializes_structured_content() {
+        let stream = Uuid::new_v4();
+        let mapping = Mapping {
+            default_stream_id: Some(stream),
+            default_content_type: "json".to_string(),
+            ..Mapping::default()
+        };
+        let payload = serde_json::json!({ "user_id": "u1", "content": { "k": 1 } });
+        let event = mapping.map(payload.to_string().as_bytes()).unwrap();
+        assert_eq!(event.stream_id, stream);
+        assert_eq!(event.content, r#"{"k":1}"#);
+        assert_eq!(event.content_type, "json");
+    }
+
+    #[test]
+    fn test_map_rejects_messages_missing_required_fields() {
+        let mapping = Mapping::default();
+        assert!(mapping.map(b"not json").is_err());
+        assert!(mapping.map(br#"{"content": "hi"}"#).is_err());
+        let no_stream = serde_json::json!({ "user_id": "u1", "content": "hi" });
+        assert!(mapping.map(no_stream.to_string().as_bytes()).is_err());
+        let bad_stream = serde_json::json!({ "user_id": "u1", "stream_id": "x", "content": "hi" });
+        assert!(mapping.map(bad_stream.to_string().as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::Pipeline;
+use anyhow::{anyhow, Context, Result};
+use async_nats::jetstream::{self, consumer::pull};
+use futures::StreamExt;
+
+pub struct NatsSource {
+    pub url: String,
+    pub stream: String,
+    /// Only consume these subjects of the stream; all of them when unset
+    pub subject: Option<String>,
+    pub durable: String,
+}
+
+impl NatsSource {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            url: std::env::var("NATS_URL").unwrap_or_else(|_| "nats://127.0.0.1:4222".to_string()),
+            stream: std::env::var("NATS_STREAM").context("NATS_STREAM must be set")?,
+            subject: std::env::var("NATS_SUBJECT").ok(),
+            durable: std::env::var("NATS_DURABLE")
+                .unwrap_or_else(|_| "memorose-connector".to_string()),
+        })
+    }
+
+    /// Pull batches from a durable JetStream consumer. Messages are acknowledged only
+    /// after their batch has been written, so unacknowledged ones are redelivered.
+    pub async fn run(&self, pipeline: &Pipeline) -> Result<()> {
+        let client = async_nats::connect(&self.url)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", self.url))?;
+        let stream = jetstream::new(client)
+            .get_stream(&self.stream)
+            .await
+            .map_err(|e| anyhow!("Failed to open JetStream stream {}: {}", self.stream, e))?;
+        let consumer = stream
+            .get_or_create_consumer(
+                &self.durable,
+                pull::Config {
+                    durable_name: Some(self.durable.clone()),
+                    filter_subject: self.subject.clone().unwrap_or_default(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to create consumer {}: {}", self.durable, e))?;
+        tracing::info!(
+            "Consuming JetStream stream {} at {} as {}",
+            self.stream,
+            self.url,
+            self.durable
+        );
+
+        loop {
+            let mut batch = consumer
+                .fetch()
+                .max_messages(pipeline.batch_size)
+                .expires(pipeline.flush_interval)
+                .messages()
+                .await
+                .map_err(|e| anyhow!("Failed to fetch from JetStream: {}", e))?;
+            let mut messages = Vec::new();
+            while let Some(message) = batch.next().await {
+                match message {
+                    Ok(message) => messages.push(message),
+                    Err(e) => tracing::warn!("JetStream error: {}", e),
+                }
+            }
+            if messages.is_empty() {
+                continue;
+            }
+            let payloads: Vec<Vec<u8>> = messages.iter().map(|m| m.payload.to_vec()).collect();
+            pipeline.deliver(&payloads).await;
+            for message in messages {
+                if let Err(e) = message.ack().await {
+                    tracing::warn!("Failed to acknowledge JetStream message: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::mapping::{MappedEvent, Mapping};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Writes mapped events to Memorose through the batch ingest endpoint. Any node accepts
+/// the request and forwards it to the Raft leader of the user's shard.
+pub struct MemoroseSink {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+/// Outcome of one batch, for logging.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub written: usize,
+    /// Messages that could not be mapped to an event
+    pub unmapped: usize,
+    /// Events the server refused outright (4xx other than 429)
+    pub rejected: usize,
+}
+
+impl MemoroseSink {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to build connector HTTP client"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Map and write one batch of consumed messages. Returns once every event is either
+    /// stored or refused by the server, so the caller can checkpoint past the batch;
+    /// transient failures are retried until they succeed.
+    pub async fn deliver(&self, mapping: &Mapping, payloads: &[Vec<u8>]) -> DeliveryReport {
+        let mut report = DeliveryReport::default();
+        let mut events = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            match mapping.map(payload) {
+                Ok(event) => events.push(event),
+                Err(e) => {
+                    report.unmapped += 1;
+                    tracing::warn!("Skipping message that does not match the mapping: {:#}", e);
+                }
+            }
+        }
+        for ((user_id, stream_id), group) in group_by_stream(events) {
+            let count = group.len();
+            if self.write_with_retry(&user_id, stream_id, &group).await {
+                report.written += count;
+            } else {
+                report.rejected += count;
+            }
+        }
+        report
+    }
+
+    /// `false` when the server rejects the batch as invalid; retrying would not help.
+    async fn write_with_retry(
+        &self,
+        user_id: &str,
+        stream_id: Uuid,
+        events: &[MappedEvent],
+    ) -> bool {
+        let url = format!(
+            "{}/v1/users/{}/streams/{}/events/batch",
+            self.base_url, user_id, stream_id
+        );
+        let body = serde_json::json!({ "events": events });
+        let mut backoff = Duration::from_millis(200);
+        loop {
+            let mut request = self.client.post(&url).json(&body);
+            if let Some(api_key) = &self.api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            let wait = match request.send().await {
+                Ok(response) if response.status().is_success() => return true,
+                Ok(response) if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    tracing::info!("Memorose is throttling ingestion; backing off");
+                    retry_after.unwrap_or(backoff)
+                }
+                Ok(response) if response.status().is_client_error() => {
+                    let status = response.status();
+                    let detail = response.text().await.unwrap_or_default();
+                    tracing::error!(
+                        "Memorose rejected {} events for user {}: {} {}",
+                        events.len(),
+                        user_id,
+                        status,
+                        detail
+                    );
+                    return false;
+                }
+                Ok(response) => {
+                    tracing::warn!("Memorose answered {}; retrying", response.status());
+                    backoff
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to reach Memorose: {}; retrying", e);
+                    backoff
+                }
+            };
+            tokio::time::sleep(wait).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+/// Group events by the ingest endpoint they go to, keeping each stream's message order.
+fn group_by_stream(events: Vec<MappedEvent>) -> BTreeMap<(String, Uuid), Vec<MappedEvent>> {
+    let mut groups: BTreeMap<(String, Uuid), Vec<MappedEvent>> = BTreeMap::new();
+    for event in events {
+        groups
+            .entry((event.user_id.clone(), event.stream_id))
+            .or_default()
+            .push(event);
+    }
+    groups
+}
+
+/// Running totals since the connector started.
+#[derive(Debug, Default)]
+pub struct DeliveryTotals {
+    written: AtomicU64,
+    unmapped: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl DeliveryTotals {
+    pub fn record(&self, report: &DeliveryReport) {
+        self.written
+            .fetch_add(report.written as u64, Ordering::Relaxed);
+        self.unmapped
+            .fetch_add(report.unmapped as u64, Ordering::Relaxed);
+        self.rejected
+            .fetch_add(report.rejected as u64, Ordering::Relaxed);
+        tracing::info!(
+            "Delivered batch: {} written, {} unmapped, {} rejected (totals: {} / {} / {})",
+            report.written,
+            report.unmapped,
+            report.rejected,
+            self.written.load(Ordering::Relaxed),
+            self.unmapped.load(Ordering::Relaxed),
+            self.rejected.load(Ordering::Relaxed)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(user_id: &str, stream_id: Uuid, content: &str) -> MappedEvent {
+        MappedEvent {
+            user_id: user_id.to_string(),
+            stream_id,
+            content: content.to_string(),
+            content_type: "text".to_string(),
+            org_id: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by_stream_keeps_message_order() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let groups = group_by_stream(vec![
+            event("u1", a, "1"),
+            event("u2", b, "2"),
+            event("u1", a, "3"),
+        ]);
+        assert_eq!(groups.len(), 2);
+        let contents: Vec<_> = groups[&("u1".to_string(), a)]
+            .iter()
+            .map(|e| e.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["1", "3"]);
+    }
+
+    #[test]
+    fn test_mapped_event_serializes_as_ingest_request() {
+        let body = serde_json::to_value(event("u1", Uuid::new_v4(), "hi")).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "content": "hi", "content_type": "text" })
+        );
+    }
+}
@@ -2,8 +2,8 @@ use memorose_common::config::AppConfig;
 use memorose_common::{Event, EventContent};
 use memorose_core::raft::network::run_raft_server;
 use memorose_core::raft::start_raft_node;
+use memorose_core::raft::types::MemoroseNode;
 use memorose_core::MemoroseEngine;
-use openraft::BasicNode;
 use std::collections::BTreeMap;
 use tempfile::tempdir;
 use tokio::time::{sleep, Duration};
@@ -22,9 +22,10 @@ async fn main() -> anyhow::Result<()> {
         let port = 5000 + id;
         node_configs.insert(
             *id,
-            BasicNode {
-                addr: format!("127.0.0.1:{}", port),
-            },
+            MemoroseNode::new(
+                format!("127.0.0.1:{}", port),
+                format!("127.0.0.1:{}", 3000 + id),
+            ),
         );
     }
 
@@ -49,7 +50,7 @@ async fn main() -> anyhow::Result<()> {
 
         // Start gRPC server in background
         tokio::spawn(async move {
-            if let Err(e) = run_raft_server(addr, raft_server).await {
+            if let Err(e) = run_raft_server(addr, raft_server, Default::default()).await {
                 eprintln!("Node {} server error: {:?}", id, e);
             }
         });
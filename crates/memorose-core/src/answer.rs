@@ -0,0 +1,171 @@
+//! Single-question answers grounded in retrieved memories. Unlike chat, the model may
+//! only use the numbered memories it is given, cites them by number and rates how well
+//! they support the answer.
+
+use anyhow::Result;
+use memorose_common::MemoryUnit;
+use serde::{Deserialize, Serialize};
+
+use crate::llm::structured::{generate_structured, OutputSchema};
+use crate::llm::{LLMClient, LLMResponse, LANGUAGE_PRESERVATION_INSTRUCTION};
+
+/// Answer given without calling the model when retrieval found nothing.
+pub const NO_MEMORIES_ANSWER: &str = "I don't have any memories that answer this question.";
+/// Highest confidence an answer that cites no memory may claim.
+const UNCITED_CONFIDENCE_CAP: f32 = 0.2;
+
+const GROUNDED_ANSWER_PROMPT: &str = "You answer a question using only the numbered memories below. \
+Do not use outside knowledge or guess. Cite every memory a statement relies on inline as [1], [2] and so on. \
+If the memories do not answer the question, say so. \
+Reply with JSON: {\"answer\": string, \"citations\": [numbers of the memories cited], \
+\"confidence\": number from 0 to 1 for how fully the cited memories support the answer}.";
+
+/// The model's answer, with citations checked against the memories it was given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroundedAnswer {
+    pub answer: String,
+    /// 1-based positions of the cited memories, ascending
+    pub citations: Vec<usize>,
+    /// 0.0-1.0
+    pub confidence: f32,
+}
+
+/// Answer `question` strictly from `memories`, which are numbered from 1 in the prompt.
+pub async fn answer_from_memories(
+    client: &dyn LLMClient,
+    question: &str,
+    memories: &[&MemoryUnit],
+) -> Result<LLMResponse<GroundedAnswer>> {
+    if memories.is_empty() {
+        return Ok(LLMResponse {
+            data: GroundedAnswer {
+                answer: NO_MEMORIES_ANSWER.to_string(),
+                citations: Vec::new(),
+                confidence: 0.0,
+            },
+            usage: Default::default(),
+        });
+    }
+
+    let mut prompt = String::from("Memories:\n");
+    for (index, unit) in memories.iter().enumerate() {
+        let date = unit
+            .valid_time
+            .unwrap_or(unit.transaction_time)
+            .format("%Y-%m-%d");
+        prompt.push_str(&format!(
+            "[{}] ({}) {}\n",
+            index + 1,
+            date,
+            unit.content.trim()
+        ));
+    }
+    prompt.push_str(&format!("\nQuestion: {}", question));
+
+    let system_prompt = format!(
+        "{}\n{}",
+        GROUNDED_ANSWER_PROMPT, LANGUAGE_PRESERVATION_INSTRUCTION
+    );
+    let response: LLMResponse<GroundedAnswer> = generate_structured(
+        client,
+        "answer",
+        &system_prompt,
+        &prompt,
+        &OutputSchema::grounded_answer(),
+        client.structured_output_retries(),
+    )
+    .await?;
+    Ok(LLMResponse {
+        data: normalize(response.data, memories.len()),
+        usage: response.usage,
+    })
+}
+
+/// Drop citations of memories that were not given, and keep confidence in range and
+/// low when nothing is cited.
+fn normalize(mut answer: GroundedAnswer, memory_count: usize) -> GroundedAnswer {
+    answer
+        .citations
+        .retain(|&index| (1..=memory_count).contains(&index));
+    answer.citations.sort_unstable();
+    answer.citations.dedup();
+    let confidence = if answer.confidence.is_finite() {
+        answer.confidence.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    answer.confidence = if answer.citations.is_empty() {
+        confidence.min(UNCITED_CONFIDENCE_CAP)
+    } else {
+        confidence
+    };
+    answer.answer = answer.answer.trim().to_string();
+    answer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLLM;
+    use uuid::Uuid;
+
+    fn unit(content: &str) -> MemoryUnit {
+        MemoryUnit::new(
+            None,
+            "u1".into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_answer_keeps_only_citations_of_given_memories() {
+        let client = MockLLM {
+            generate_response: Some(
+                r#"{"answer": " Berlin [1][3] ", "citations": [3, 1, 1, 7], "confidence": 1.4}"#
+                    .into(),
+            ),
+            ..Default::default()
+        };
+        let memories = [
+            unit("Moving to Berlin in May"),
+            unit("Likes tea"),
+            unit("Signed a lease in Berlin"),
+        ];
+        let refs: Vec<&MemoryUnit> = memories.iter().collect();
+        let answer = answer_from_memories(&client, "Where am I moving?", &refs)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(answer.answer, "Berlin [1][3]");
+        assert_eq!(answer.citations, vec![1, 3]);
+        assert_eq!(answer.confidence, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_answer_without_memories_or_citations_has_low_confidence() {
+        let client = MockLLM {
+            generate_response: Some(
+                r#"{"answer": "Probably Paris", "citations": [], "confidence": 0.9}"#.into(),
+            ),
+            ..Default::default()
+        };
+        let answer = answer_from_memories(&client, "Where am I moving?", &[])
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(answer.answer, NO_MEMORIES_ANSWER);
+        assert_eq!(answer.confidence, 0.0);
+
+        let memory = unit("Likes tea");
+        let answer = answer_from_memories(&client, "Where am I moving?", &[&memory])
+            .await
+            .unwrap()
+            .data;
+        assert!(answer.citations.is_empty());
+        assert_eq!(answer.confidence, UNCITED_CONFIDENCE_CAP);
+    }
+}
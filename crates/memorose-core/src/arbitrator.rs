@@ -1,10 +1,20 @@
 use crate::fact_extraction::{self, MemoryFactDescriptor};
-use crate::llm::{LLMClient, LANGUAGE_PRESERVATION_INSTRUCTION};
+use crate::llm::structured::generate_structured;
+use crate::llm::{LLMClient, OutputSchema, LANGUAGE_PRESERVATION_INSTRUCTION};
 use anyhow::Result;
 use memorose_common::config::AppConfig;
-use memorose_common::{GraphEdge, MemoryUnit, RelationType};
+use memorose_common::{GraphEdge, MemoryUnit, ProfileValue, RelationType};
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Outcome of one LLM arbitration pass over a candidate set.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ArbitrationDecision {
+    pub retained_ids: Vec<uuid::Uuid>,
+    /// The model's explanation for the winners; only requested when explaining.
+    pub reasoning: Option<String>,
+}
+
 /// Approximate character budget for LLM prompts (~25k tokens at ~4 chars/token).
 /// Keeps batches within context window limits for all supported models.
 const MAX_CONTEXT_CHARS: usize = 100_000;
@@ -311,6 +321,165 @@ impl Arbitrator {
         Ok(self.extract_memory_facts(memory).await?.into_iter().next())
     }
 
+    /// Extract short search keywords for a consolidated memory.
+    /// Returns an empty list when no LLM is configured or the response cannot be parsed,
+    /// so callers can fall back to a deterministic extractor.
+    pub async fn extract_keywords(&self, memory: &MemoryUnit, limit: usize) -> Result<Vec<String>> {
+        let client = match &self.llm_client {
+            Some(c) => c,
+            None => return Ok(Vec::new()),
+        };
+
+        if limit == 0 || memory.content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let system_prompt = format!(
+            "You are a keyword extraction engine for a memory search index. \
+            Extract up to {} short keywords or key phrases (1-3 words each) that a person would search for to find this memory: \
+            named entities, topics, preferences, tools, places, and domain terms. \
+            Skip generic words and filler. \
+            {} \
+            Return ONLY a JSON array of strings, e.g. [\"keyword one\", \"keyword two\"].",
+            limit, LANGUAGE_PRESERVATION_INSTRUCTION
+        );
+
+        let result = match client
+            .generate(&format!("{}\n\nMemory:\n{}", system_prompt, memory.content))
+            .await
+        {
+            Ok(response) => response.data,
+            Err(error) => {
+                tracing::warn!(
+                    "Keyword extraction LLM call failed: {:?}. Skipping LLM keywords.",
+                    error
+                );
+                return Ok(Vec::new());
+            }
+        };
+
+        let clean_json = result
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim();
+
+        let keywords: Vec<String> = serde_json::from_str(clean_json).unwrap_or_default();
+        Ok(fact_extraction::normalize_memory_keywords(&keywords, limit))
+    }
+
+    /// Classify the emotions a memory expresses into [`crate::emotion::EMOTION_LABELS`].
+    /// Returns an empty list when no LLM is configured or the call fails.
+    pub async fn classify_emotions(&self, memory: &MemoryUnit) -> Result<Vec<String>> {
+        let client = match &self.llm_client {
+            Some(c) => c,
+            None => return Ok(Vec::new()),
+        };
+
+        if memory.content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let system_prompt = format!(
+            "You tag memories with the emotions the speaker expresses. \
+            Choose only from these labels: {}. \
+            Pick the labels clearly expressed in the text, usually zero to two. \
+            Return ONLY a JSON array of labels, e.g. [\"frustrated\"], or [] if none apply.",
+            crate::emotion::EMOTION_LABELS.join(", ")
+        );
+
+        let result = match client
+            .generate(&format!("{}\n\nMemory:\n{}", system_prompt, memory.content))
+            .await
+        {
+            Ok(response) => response.data,
+            Err(error) => {
+                tracing::warn!(
+                    "Emotion classification LLM call failed: {:?}. Skipping LLM emotions.",
+                    error
+                );
+                return Ok(Vec::new());
+            }
+        };
+
+        let clean_json = result
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim();
+
+        let labels: Vec<String> = serde_json::from_str(clean_json).unwrap_or_default();
+        Ok(crate::emotion::normalize_emotions(&labels))
+    }
+
+    /// Extract stable, typed profile attributes (language, timezone, dietary preferences, ...)
+    /// from a batch of memories. Keys are snake_case; values are strings, numbers, booleans
+    /// or string lists. Returns an empty map when no LLM is configured or parsing fails.
+    pub async fn extract_profile_attributes(
+        &self,
+        memories: Vec<String>,
+    ) -> Result<HashMap<String, ProfileValue>> {
+        let client = match &self.llm_client {
+            Some(c) => c,
+            None => return Ok(HashMap::new()),
+        };
+
+        if memories.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let (memory_block, included, total) =
+            build_bounded_context(memories.into_iter(), "\n---\n");
+        if included < total {
+            tracing::warn!("extract_profile_attributes: truncated context to {}/{} memories to stay within token budget", included, total);
+        }
+
+        let system_prompt = format!(
+            "You are a user profile extraction engine. \
+            From the memories below, extract stable attributes about the user such as \
+            language, timezone, location, occupation, dietary_preferences, communication_style. \
+            Only include attributes that are explicitly stated or strongly implied; skip one-off events. \
+            Use snake_case keys. Values must be a string, number, boolean, or array of strings. \
+            {} \
+            Return ONLY a JSON object, e.g. {{\"language\": \"English\", \"dietary_preferences\": [\"vegetarian\"]}}. \
+            Return {{}} if nothing applies.",
+            LANGUAGE_PRESERVATION_INSTRUCTION
+        );
+
+        let result = match client
+            .generate(&format!("{}\n\nMemories:\n{}", system_prompt, memory_block))
+            .await
+        {
+            Ok(response) => response.data,
+            Err(error) => {
+                tracing::warn!("Profile attribute extraction failed: {:?}", error);
+                return Ok(HashMap::new());
+            }
+        };
+
+        let clean_json = result
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim();
+
+        // Parse loosely so one malformed value does not discard the whole profile.
+        let raw: HashMap<String, serde_json::Value> =
+            serde_json::from_str(clean_json).unwrap_or_default();
+        Ok(raw
+            .into_iter()
+            .filter_map(|(key, value)| {
+                serde_json::from_value::<ProfileValue>(value)
+                    .ok()
+                    .map(|value| (key, value))
+            })
+            .collect())
+    }
+
+    /// Split `goal` into milestones. `system_prompt` replaces the built-in prompt.
     pub async fn decompose_goal(
         &self,
         org_id: Option<&str>,
@@ -318,29 +487,19 @@ impl Arbitrator {
         agent_id: Option<&str>,
         _stream_id: uuid::Uuid,
         goal: &str,
+        system_prompt: Option<&str>,
     ) -> Result<Vec<memorose_common::L3Task>> {
         let client = match &self.llm_client {
             Some(c) => c,
             None => return Ok(Vec::new()),
         };
 
-        let system_prompt = "You are a strategic AI planner. \
+        let system_prompt = system_prompt.unwrap_or("You are a strategic AI planner. \
             Decompose the following high-level Goal (L3) into a set of 3-5 actionable Milestones (L3Tasks). \
             For each milestone, identify its dependencies (which other milestones must be completed first). \
             \
             Output format (JSON): \
-            [{\"summary\": \"milestone title\", \"description\": \"Detailed action plan for this milestone\", \"dependencies\": [\"milestone_title_x\"]}]";
-
-        let combined_prompt = format!("{}\n\nGoal: {}", system_prompt, goal);
-        let result = client.generate(&combined_prompt).await?;
-
-        let clean_json = result
-            .data
-            .trim()
-            .trim_start_matches("```json")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim();
+            [{\"summary\": \"milestone title\", \"description\": \"Detailed action plan for this milestone\", \"dependencies\": [\"milestone_title_x\"]}]");
 
         #[derive(serde::Deserialize)]
         struct MilestoneDTO {
@@ -351,7 +510,16 @@ impl Arbitrator {
             dependencies: Vec<String>,
         }
 
-        let milestones: Vec<MilestoneDTO> = serde_json::from_str(clean_json).unwrap_or_default();
+        let milestones: Vec<MilestoneDTO> = generate_structured(
+            client.as_ref(),
+            "decompose_goal",
+            system_prompt,
+            &format!("Goal: {}", goal),
+            &OutputSchema::milestones(),
+            client.structured_output_retries(),
+        )
+        .await?
+        .data;
 
         let mut tasks = Vec::new();
         // Create tasks first to get their UUIDs
@@ -421,14 +589,32 @@ impl Arbitrator {
         memories: Vec<MemoryUnit>,
         query: Option<&str>,
     ) -> Result<Vec<MemoryUnit>> {
-        // ... (existing implementation)
+        let Some(decision) = self.decide_arbitration(&memories, query, false).await? else {
+            return Ok(memories);
+        };
+
+        Ok(memories
+            .into_iter()
+            .filter(|m| decision.retained_ids.contains(&m.id))
+            .collect())
+    }
+
+    /// Ask the LLM which memories survive conflict resolution. With `explain`, the model
+    /// also returns its reasoning for the winners. Returns `None` when arbitration passes
+    /// everything through (no LLM, a single candidate, or a failed call).
+    pub async fn decide_arbitration(
+        &self,
+        memories: &[MemoryUnit],
+        query: Option<&str>,
+        explain: bool,
+    ) -> Result<Option<ArbitrationDecision>> {
         let client = match &self.llm_client {
             Some(c) => c,
-            None => return Ok(memories),
+            None => return Ok(None),
         };
 
         if memories.len() <= 1 {
-            return Ok(memories);
+            return Ok(None);
         }
 
         // Prepare prompt with memories, IDs and timestamps
@@ -462,13 +648,18 @@ impl Arbitrator {
             3. If the User Query asks for a SPECIFIC fact/date/detail (e.g., 'When did I say X?', 'What about the 18th?'), YOU MUST RETAIN ONLY MEMORIES MATCHING THAT SPECIFIC DETAIL. Filter out other versions (even if newer) unless they directly reference the specific detail requested. \
             4. If the User Query asks for 'current', 'latest', 'now', 'final', or is neutral, FAVOR THE MOST RECENT INFORMATION (based on Timestamp) and filter out obsolete facts. \
             \
-            If no conflicts exist, keep all memories. \
-            Return ONLY the IDs of the memories that should be RETAINED, separated by commas. \
-            Do not explain.";
+            If no conflicts exist, keep all memories. ";
+        let output_instruction = if explain {
+            "Return ONLY a JSON object of the form \
+            {\"retained_ids\": [\"id\", ...], \"reasoning\": \"why the retained memories won over the others\"}."
+        } else {
+            "Return ONLY the IDs of the memories that should be RETAINED, separated by commas. \
+            Do not explain."
+        };
 
         let user_prompt = format!("{}\nMemories:\n{}", query_str, memory_context);
 
-        let combined_prompt = format!("{}\n\n{}", system_prompt, user_prompt);
+        let combined_prompt = format!("{}{}\n\n{}", system_prompt, output_instruction, user_prompt);
         let result = match client.generate(&combined_prompt).await {
             Ok(r) => r.data,
             Err(e) => {
@@ -476,20 +667,46 @@ impl Arbitrator {
                     "Arbitrator LLM call failed: {:?}. Falling back to pass-through.",
                     e
                 );
-                return Ok(memories);
+                return Ok(None);
             }
         };
 
-        // Parse IDs from result
-        let retained_ids: Vec<String> = result.split(',').map(|s| s.trim().to_string()).collect();
+        #[derive(serde::Deserialize)]
+        struct ExplainedDecisionDTO {
+            retained_ids: Vec<String>,
+            #[serde(default)]
+            reasoning: Option<String>,
+        }
 
-        // Filter original memories
-        let filtered: Vec<MemoryUnit> = memories
-            .into_iter()
-            .filter(|m| retained_ids.contains(&m.id.to_string()))
-            .collect();
+        let (retained_ids, reasoning) = match explain
+            .then(|| {
+                let clean_json = result
+                    .trim()
+                    .trim_start_matches("```json")
+                    .trim_start_matches("```")
+                    .trim_end_matches("```")
+                    .trim();
+                serde_json::from_str::<ExplainedDecisionDTO>(clean_json).ok()
+            })
+            .flatten()
+        {
+            Some(dto) => (dto.retained_ids, dto.reasoning),
+            // Parse IDs from a plain comma-separated answer
+            None => (
+                result.split(',').map(|s| s.trim().to_string()).collect(),
+                None,
+            ),
+        };
+        let retained_ids = retained_ids.iter().map(|id| id.trim()).collect::<Vec<_>>();
 
-        Ok(filtered)
+        Ok(Some(ArbitrationDecision {
+            retained_ids: memories
+                .iter()
+                .map(|m| m.id)
+                .filter(|id| retained_ids.contains(&id.to_string().as_str()))
+                .collect(),
+            reasoning,
+        }))
     }
 
     /// Synthesize a single coherent narrative from a set of memories, resolving conflicts and preserving history.
@@ -550,11 +767,13 @@ impl Arbitrator {
 
     /// Prospective Reflection: Analyze a set of memories (usually from a single session)
     /// and extract/summarize them into topic-based MemoryUnits (Level 2).
+    /// `system_prompt` replaces the built-in prompt.
     pub async fn extract_topics(
         &self,
         user_id: &str,
         stream_id: uuid::Uuid,
         memories: Vec<MemoryUnit>,
+        system_prompt: Option<&str>,
     ) -> Result<Vec<MemoryUnit>> {
         let client = match &self.llm_client {
             Some(c) => c,
@@ -579,8 +798,9 @@ impl Arbitrator {
             );
         }
 
-        let system_prompt = format!(
-            "You are a Memory Management System (Prospective Reflection). \
+        let system_prompt = system_prompt.map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "You are a Memory Management System (Prospective Reflection). \
             Analyze the following dialogue segments/memories from a recent session. \
             Your goal is to extract 'Topics' that summarize the key information. \
             \
@@ -594,24 +814,9 @@ impl Arbitrator {
             [{{\"summary\": \"topic summary\", \"source_ids\": [\"uuid1\", \"uuid2\"]}}] \
             \
             Focus on extracting facts, preferences, and long-term insights. Skip trivial chitchat.",
-            LANGUAGE_PRESERVATION_INSTRUCTION
-        );
-
-        let combined_prompt = format!("{}\n\n{}", system_prompt, memories_str);
-        let result = match client.generate(&combined_prompt).await {
-            Ok(res) => res.data,
-            Err(e) => {
-                tracing::error!("LLM generate failed for extract_topics: {:?}", e);
-                return Ok(Vec::new());
-            }
-        };
-
-        let clean_json = result
-            .trim()
-            .trim_start_matches("```json")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim();
+                LANGUAGE_PRESERVATION_INSTRUCTION
+            )
+        });
 
         #[derive(serde::Deserialize)]
         struct TopicDTO {
@@ -619,15 +824,20 @@ impl Arbitrator {
             source_ids: Vec<String>,
         }
 
-        let dtos: Vec<TopicDTO> = match serde_json::from_str(clean_json) {
-            Ok(d) => d,
+        let dtos: Vec<TopicDTO> = match generate_structured(
+            client.as_ref(),
+            "extract_topics",
+            &system_prompt,
+            &memories_str,
+            &OutputSchema::topics(),
+            client.structured_output_retries(),
+        )
+        .await
+        {
+            Ok(res) => res.data,
             Err(e) => {
-                tracing::error!(
-                    "Failed to parse topics JSON: {:?}. Raw response: {}",
-                    e,
-                    clean_json
-                );
-                Vec::new()
+                tracing::error!("LLM topic extraction failed: {:?}", e);
+                return Ok(Vec::new());
             }
         };
 
@@ -856,7 +1066,12 @@ impl Arbitrator {
     }
 
     /// Summarize a detected community of memories into a high-level insight.
-    pub async fn summarize_community(&self, memories: Vec<String>) -> Result<CommunityInsight> {
+    /// `system_prompt` replaces the built-in prompt.
+    pub async fn summarize_community(
+        &self,
+        memories: Vec<String>,
+        system_prompt: Option<&str>,
+    ) -> Result<CommunityInsight> {
         let client = match &self.llm_client {
             Some(c) => c,
             None => {
@@ -881,7 +1096,7 @@ impl Arbitrator {
         if included < total {
             tracing::warn!("summarize_community: truncated context to {}/{} memories to stay within token budget", included, total);
         }
-        let system_prompt = format!(
+        let system_prompt = system_prompt.map(str::to_string).unwrap_or_else(|| format!(
             "You are a memory abstraction engine. \
             Analyze the following group of related memories and extract the shared pattern. \
             The summary must be dense, concrete, and immediately useful. \
@@ -894,32 +1109,137 @@ impl Arbitrator {
             Output ONLY valid JSON: \
             {{\"name\": \"Short Title (3-5 words)\", \"summary\": \"Direct factual summary without filler\", \"keywords\": [\"k1\", \"k2\", \"k3\"]}}",
             LANGUAGE_PRESERVATION_INSTRUCTION
-        );
+        ));
 
         let user_prompt = format!("Community Memories:\n{}", memory_block);
 
-        let combined_prompt = format!("{}\n\n{}", system_prompt, user_prompt);
-        let result = client.generate(&combined_prompt).await?;
-
-        let clean_json = result
-            .data
-            .trim()
-            .trim_start_matches("```json")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim();
-
-        let mut insight: CommunityInsight =
-            serde_json::from_str(clean_json).unwrap_or_else(|_| CommunityInsight {
-                name: "Parsing Error".to_string(),
-                summary: result.data,
-                keywords: Vec::new(),
-            });
+        let mut insight: CommunityInsight = generate_structured(
+            client.as_ref(),
+            "summarize_community",
+            &system_prompt,
+            &user_prompt,
+            &OutputSchema::community_insight(),
+            client.structured_output_retries(),
+        )
+        .await?
+        .data;
 
         insight.summary = normalize_community_summary(&insight.summary);
 
         Ok(insight)
     }
+
+    /// Synthesize a stable L3 user profile from L2 insights, revising the previous profile if any.
+    /// Returns an empty string when no LLM is available or nothing durable can be extracted.
+    pub async fn synthesize_profile(
+        &self,
+        previous_profile: Option<&str>,
+        insights: Vec<String>,
+    ) -> Result<String> {
+        let client = match &self.llm_client {
+            Some(c) => c,
+            None => return Ok(String::new()),
+        };
+
+        if insights.is_empty() {
+            return Ok(previous_profile.unwrap_or_default().to_string());
+        }
+
+        let (insight_block, included, total) =
+            build_bounded_context(insights.into_iter(), "\n---\n");
+        if included < total {
+            tracing::warn!("synthesize_profile: truncated context to {}/{} insights to stay within token budget", included, total);
+        }
+
+        let prompt = format!(
+            "You maintain a long-lived profile of a single user. \
+            Using the insights below, write the user's stable preferences, facts, and traits \
+            as a short list of bullet points, one per line, each starting with '- '. \
+            Keep only information that is likely to stay true over time; drop one-off events. \
+            When an insight contradicts the previous profile, keep the newer information. \
+            Do not add framing language or commentary. \
+            If nothing durable can be extracted, output an empty string. \
+            {}\n\nPrevious Profile:\n{}\n\nInsights:\n{}",
+            LANGUAGE_PRESERVATION_INSTRUCTION,
+            previous_profile.unwrap_or("(none)"),
+            insight_block
+        );
+
+        match client.generate(&prompt).await {
+            Ok(response) => Ok(response
+                .data
+                .trim()
+                .trim_start_matches("```markdown")
+                .trim_start_matches("```")
+                .trim_end_matches("```")
+                .trim()
+                .to_string()),
+            Err(e) => {
+                tracing::warn!("Profile synthesis failed: {:?}", e);
+                Ok(String::new())
+            }
+        }
+    }
+
+    /// Write a digest of what happened in a time window from memories grouped by topic.
+    /// `topics` pairs each topic label with its memories, oldest first.
+    /// Returns an empty string when no LLM is available or generation fails.
+    pub async fn write_digest(&self, topics: Vec<(String, Vec<String>)>) -> Result<String> {
+        let client = match &self.llm_client {
+            Some(c) => c,
+            None => return Ok(String::new()),
+        };
+
+        if topics.is_empty() {
+            return Ok(String::new());
+        }
+
+        let sections = topics.into_iter().map(|(label, memories)| {
+            format!(
+                "Topic: {}\n{}",
+                label,
+                memories
+                    .iter()
+                    .map(|memory| format!("- {}", memory))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        });
+        let (topic_block, included, total) = build_bounded_context(sections, "\n\n");
+        if included < total {
+            tracing::warn!(
+                "write_digest: truncated context to {}/{} topics to stay within token budget",
+                included,
+                total
+            );
+        }
+
+        let prompt = format!(
+            "You write a short digest of what happened for a single user over a period of time. \
+            The memories below are grouped by topic, oldest first. \
+            Write one bullet point per topic, each starting with '- ', covering what happened, \
+            what was decided, and what is still open. \
+            Put the most significant topics first and merge topics that describe the same thing. \
+            Do not add framing language, commentary, or a closing summary. \
+            {}\n\nMemories by Topic:\n{}",
+            LANGUAGE_PRESERVATION_INSTRUCTION, topic_block
+        );
+
+        match client.generate(&prompt).await {
+            Ok(response) => Ok(response
+                .data
+                .trim()
+                .trim_start_matches("```markdown")
+                .trim_start_matches("```")
+                .trim_end_matches("```")
+                .trim()
+                .to_string()),
+            Err(e) => {
+                tracing::warn!("Digest generation failed: {:?}", e);
+                Ok(String::new())
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -928,7 +1248,7 @@ mod tests {
     use crate::fact_extraction::{
         MemoryFactAttribute, MemoryFactChangeType, MemoryFactSubject, MemoryFactValueKind,
     };
-    use crate::llm::{CompressionOutput, LLMClient};
+    use crate::llm::{CompressionContext, CompressionOutput, LLMClient};
     use async_trait::async_trait; // Import CompressionOutput
     use std::sync::Mutex;
 
@@ -960,7 +1280,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -1026,7 +1346,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -1088,7 +1408,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -1463,13 +1783,13 @@ mod tests {
         assert_eq!(consolidated, "first memory\nsecond memory");
 
         let tasks = arbitrator
-            .decompose_goal(None, "test-user", None, stream_id, "ship release")
+            .decompose_goal(None, "test-user", None, stream_id, "ship release", None)
             .await
             .unwrap();
         assert!(tasks.is_empty());
 
         let topics = arbitrator
-            .extract_topics("test-user", stream_id, memories.clone())
+            .extract_topics("test-user", stream_id, memories.clone(), None)
             .await
             .unwrap();
         assert!(topics.is_empty());
@@ -1486,7 +1806,7 @@ mod tests {
             .unwrap();
         assert!(corrections.is_empty());
 
-        let insight = arbitrator.summarize_community(vec![]).await.unwrap();
+        let insight = arbitrator.summarize_community(vec![], None).await.unwrap();
         assert_eq!(insight.name, "Unknown Community");
         assert_eq!(insight.summary, "LLM not available");
     }
@@ -1625,6 +1945,7 @@ mod tests {
                 Some("agent_x"),
                 uuid::Uuid::new_v4(),
                 "ship release",
+                None,
             )
             .await
             .unwrap();
@@ -1671,6 +1992,7 @@ mod tests {
             "test-user",
             stream_id,
             vec![source_a.clone(), source_b.clone()],
+            None,
         )
         .await
         .unwrap();
@@ -1682,7 +2004,7 @@ mod tests {
         let invalid_json = Arbitrator::with_client(Arc::new(MockLLM {
             response: "not-json".into(),
         }))
-        .extract_topics("test-user", stream_id, vec![source_a.clone()])
+        .extract_topics("test-user", stream_id, vec![source_a.clone()], None)
         .await
         .unwrap();
         assert!(invalid_json.is_empty());
@@ -1690,13 +2012,13 @@ mod tests {
         let empty_memories = Arbitrator::with_client(Arc::new(MockLLM {
             response: "[]".into(),
         }))
-        .extract_topics("test-user", stream_id, Vec::new())
+        .extract_topics("test-user", stream_id, Vec::new(), None)
         .await
         .unwrap();
         assert!(empty_memories.is_empty());
 
         let llm_error = Arbitrator::with_client(Arc::new(ErrorLLM))
-            .extract_topics("test-user", stream_id, vec![source_b])
+            .extract_topics("test-user", stream_id, vec![source_b], None)
             .await
             .unwrap();
         assert!(llm_error.is_empty());
@@ -1726,7 +2048,7 @@ mod tests {
         });
 
         Arbitrator::with_client(topic_client)
-            .extract_topics("test-user", stream_id, vec![source])
+            .extract_topics("test-user", stream_id, vec![source], None)
             .await
             .unwrap();
 
@@ -1737,7 +2059,7 @@ mod tests {
         });
 
         Arbitrator::with_client(community_client)
-            .summarize_community(vec!["用户主要使用中文讨论 memory 机制".into()])
+            .summarize_community(vec!["用户主要使用中文讨论 memory 机制".into()], None)
             .await
             .unwrap();
 
@@ -1829,7 +2151,10 @@ mod tests {
             "The borrow checker is tough but useful.".to_string(),
         ];
 
-        let insight = arbitrator.summarize_community(memories).await.unwrap();
+        let insight = arbitrator
+            .summarize_community(memories, None)
+            .await
+            .unwrap();
 
         assert_eq!(insight.name, "Rust Programming");
         assert!(insight.summary.contains("memory safety"));
@@ -2106,9 +2431,9 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_summarize_community_fallbacks_and_parsing_error_wrapper() {
+    async fn test_summarize_community_fallbacks_and_rejects_invalid_output() {
         let without_llm = Arbitrator { llm_client: None }
-            .summarize_community(vec!["memory".into()])
+            .summarize_community(vec!["memory".into()], None)
             .await
             .unwrap();
         assert_eq!(without_llm.name, "Unknown Community");
@@ -2117,21 +2442,21 @@ mod tests {
         let empty = Arbitrator::with_client(Arc::new(MockLLM {
             response: "unused".into(),
         }))
-        .summarize_community(Vec::new())
+        .summarize_community(Vec::new(), None)
         .await
         .unwrap();
         assert_eq!(empty.name, "Empty Community");
         assert_eq!(empty.summary, "No memories provided.");
 
+        // Plain text is rejected after the retries rather than stored as the summary
         let parse_error = Arbitrator::with_client(Arc::new(MockLLM {
             response: "plain text summary".into(),
         }))
-        .summarize_community(vec!["one".into(), "two".into()])
+        .summarize_community(vec!["one".into(), "two".into()], None)
         .await
-        .unwrap();
-        assert_eq!(parse_error.name, "Parsing Error");
-        assert_eq!(parse_error.summary, "plain text summary");
-        assert!(parse_error.keywords.is_empty());
+        .unwrap_err()
+        .to_string();
+        assert!(parse_error.contains("invalid community_insight output after 3 attempt(s)"));
     }
 
     #[tokio::test]
@@ -2144,10 +2469,13 @@ mod tests {
             }"#
             .into(),
         }))
-        .summarize_community(vec![
-            "Prefer concise technical writing.".into(),
-            "Examples should be direct.".into(),
-        ])
+        .summarize_community(
+            vec![
+                "Prefer concise technical writing.".into(),
+                "Examples should be direct.".into(),
+            ],
+            None,
+        )
         .await
         .unwrap();
 
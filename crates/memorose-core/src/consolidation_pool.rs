@@ -0,0 +1,186 @@
+//! Dedicated consolidation nodes.
+//!
+//! With `[consolidation_pool] node_ids` set, shard leaders stop compressing text events
+//! themselves. Each pool node asks the leader of every shard for a batch, which the leader
+//! leases to it in its system KV. The node compresses the batch against its own LLM budget
+//! and posts the groups back, and the leader stores them only while the lease is still the
+//! node's. A batch whose node stalls goes to the next claimant once its lease runs out.
+
+use crate::engine::{ConsolidationLease, PendingMaterializationInput};
+use crate::raft::{leader_base_url, MemoroseRaft};
+use crate::worker::BackgroundWorker;
+use anyhow::{anyhow, Result};
+use memorose_common::config::{AppConfig, LiveConfig};
+use memorose_common::{Asset, Event};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Path on the shard leader that leases a batch to a pool node.
+pub const CLAIM_PATH: &str = "/v1/consolidation/claim";
+/// Path on the shard leader that stores a batch compressed by a pool node.
+pub const COMMIT_PATH: &str = "/v1/consolidation/commit";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRequest {
+    pub shard_id: u32,
+    /// Physical node ID of the claiming pool node.
+    pub node_id: u32,
+}
+
+/// A leased batch: the lease and the pending events it covers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationClaim {
+    pub lease: ConsolidationLease,
+    pub events: Vec<Event>,
+}
+
+/// One compressed event group, which the leader turns into a memory unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidatedGroup {
+    pub event_ids: Vec<Uuid>,
+    pub user_id: String,
+    pub stream_id: Uuid,
+    pub summary: String,
+    pub valid_at: Option<String>,
+    pub assets: Vec<Asset>,
+    pub metadata: serde_json::Value,
+    pub embed_input: Option<PendingMaterializationInput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitRequest {
+    pub shard_id: u32,
+    pub node_id: u32,
+    pub lease_id: Uuid,
+    pub groups: Vec<ConsolidatedGroup>,
+}
+
+/// Claims, compresses and commits batches of one shard on a pool node, for as long as
+/// the node stays in the pool.
+pub struct ConsolidationPoolMember {
+    worker: BackgroundWorker,
+    raft: MemoroseRaft,
+    shard_id: u32,
+    live_config: LiveConfig,
+    client: reqwest::Client,
+}
+
+impl ConsolidationPoolMember {
+    pub fn new(
+        worker: BackgroundWorker,
+        raft: MemoroseRaft,
+        shard_id: u32,
+        live_config: LiveConfig,
+    ) -> Self {
+        Self {
+            worker,
+            raft,
+            shard_id,
+            live_config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn run(self) {
+        loop {
+            let config = self.live_config.load();
+            if config.is_consolidation_pool_member() {
+                match self.consolidate_once(&config).await {
+                    // More may be waiting; claim again without pausing.
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(
+                        "Pooled consolidation of shard {} failed: {:?}",
+                        self.shard_id,
+                        e
+                    ),
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(
+                config.consolidation_pool.poll_interval_ms.max(1),
+            ))
+            .await;
+        }
+    }
+
+    /// Claim one batch from the shard leader, compress it and commit it. Returns whether
+    /// there was a batch to claim.
+    async fn consolidate_once(&self, config: &AppConfig) -> Result<bool> {
+        let leader_url = {
+            let metrics = self.raft.metrics().borrow().clone();
+            metrics
+                .current_leader
+                .and_then(|leader_id| leader_base_url(config, &metrics, leader_id))
+        };
+        let Some(leader_url) = leader_url else {
+            return Ok(false);
+        };
+        let node_id = config.physical_node_id();
+
+        let claim: Option<ConsolidationClaim> = self
+            .post(
+                config,
+                &leader_url,
+                CLAIM_PATH,
+                &ClaimRequest {
+                    shard_id: self.shard_id,
+                    node_id,
+                },
+            )
+            .await?
+            .json()
+            .await?;
+        let Some(claim) = claim else {
+            return Ok(false);
+        };
+
+        let lease_id = claim.lease.lease_id;
+        let event_count = claim.events.len();
+        let groups = self.worker.compress_claimed_events(claim.events).await;
+        let group_count = groups.len();
+        self.post(
+            config,
+            &leader_url,
+            COMMIT_PATH,
+            &CommitRequest {
+                shard_id: self.shard_id,
+                node_id,
+                lease_id,
+                groups,
+            },
+        )
+        .await?;
+        tracing::info!(
+            "Consolidated {} events into {} groups for shard {} (lease {})",
+            event_count,
+            group_count,
+            self.shard_id,
+            lease_id
+        );
+        Ok(true)
+    }
+
+    async fn post<T: Serialize>(
+        &self,
+        config: &AppConfig,
+        leader_url: &str,
+        path: &str,
+        body: &T,
+    ) -> Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .post(format!("{}{}", leader_url, path))
+            .json(body);
+        if let Some(api_key) = &config.consolidation_pool.api_key {
+            request = request.header("x-api-key", api_key);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("leader answered {}: {}", status, body));
+        }
+        Ok(response)
+    }
+}
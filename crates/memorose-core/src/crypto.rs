@@ -0,0 +1,471 @@
+//! Bring-your-own-key encryption of memory content at rest.
+//!
+//! Each user in the key registry has a data key that their KMS key wraps. Content written
+//! for the user is sealed with the data key as `enc:v1:<key_id>:<base64(nonce || ciphertext)>`
+//! and opened again wherever units and events are decoded. Revoking the key drops the
+//! wrapped data key, after which nothing sealed with it can be read ("crypto-shredding").
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use memorose_common::config::EncryptionConfig;
+use memorose_common::{Event, EventContent, MemoryUnit};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+pub const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+const DATA_KEY_LEN: usize = 32;
+
+/// Data keys unlocked on this node, by key id. Sealed values are opened wherever units
+/// are decoded, including code that holds no engine, so the keyring is process-wide;
+/// key ids are random UUIDs, which keeps engines sharing a process apart.
+fn keyring() -> &'static RwLock<HashMap<String, Arc<Aes256Gcm>>> {
+    static KEYRING: OnceLock<RwLock<HashMap<String, Arc<Aes256Gcm>>>> = OnceLock::new();
+    KEYRING.get_or_init(Default::default)
+}
+
+/// Reading or writing content under a key that has been revoked, or that this node
+/// could not unlock from the KMS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyUnavailable {
+    pub key_id: String,
+}
+
+impl std::fmt::Display for KeyUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "encryption key {} has been revoked or is not unlocked on this node",
+            self.key_id
+        )
+    }
+}
+
+impl std::error::Error for KeyUnavailable {}
+
+/// True when `error` comes from content whose key is revoked or unavailable.
+pub fn is_key_unavailable(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<KeyUnavailable>().is_some()
+}
+
+pub fn unlock(key_id: &str, data_key: &[u8; DATA_KEY_LEN]) {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(data_key));
+    keyring()
+        .write()
+        .expect("keyring lock poisoned")
+        .insert(key_id.to_string(), Arc::new(cipher));
+}
+
+pub fn forget(key_id: &str) {
+    keyring()
+        .write()
+        .expect("keyring lock poisoned")
+        .remove(key_id);
+}
+
+pub fn is_unlocked(key_id: &str) -> bool {
+    keyring()
+        .read()
+        .expect("keyring lock poisoned")
+        .contains_key(key_id)
+}
+
+fn cipher(key_id: &str) -> Result<Arc<Aes256Gcm>> {
+    keyring()
+        .read()
+        .expect("keyring lock poisoned")
+        .get(key_id)
+        .cloned()
+        .ok_or_else(|| {
+            KeyUnavailable {
+                key_id: key_id.to_string(),
+            }
+            .into()
+        })
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// The key a sealed value was written with.
+pub fn sealed_key_id(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(SEALED_PREFIX)?
+        .split_once(':')
+        .map(|(key_id, _)| key_id)
+}
+
+pub fn seal(key_id: &str, plaintext: &[u8]) -> Result<String> {
+    let cipher = cipher(key_id)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("failed to encrypt content with key {}", key_id))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}:{}",
+        SEALED_PREFIX,
+        key_id,
+        BASE64.encode(sealed)
+    ))
+}
+
+pub fn open(value: &str) -> Result<Vec<u8>> {
+    let (key_id, body) = value
+        .strip_prefix(SEALED_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .ok_or_else(|| anyhow!("value is not sealed"))?;
+    let cipher = cipher(key_id)?;
+    let sealed = BASE64
+        .decode(body)
+        .context("sealed value is not valid base64")?;
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow!("sealed value is truncated"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("content sealed with key {} failed authentication", key_id))
+}
+
+pub fn seal_unit(unit: &mut MemoryUnit, key_id: &str) -> Result<()> {
+    if !is_sealed(&unit.content) {
+        unit.content = seal(key_id, unit.content.as_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn open_unit(unit: &mut MemoryUnit) -> Result<()> {
+    if is_sealed(&unit.content) {
+        unit.content = String::from_utf8(open(&unit.content)?)?;
+    }
+    Ok(())
+}
+
+/// Events keep their shape on disk: the sealed content is stored as text and holds the
+/// original content as JSON.
+pub fn seal_event(event: &mut Event, key_id: &str) -> Result<()> {
+    if matches!(&event.content, EventContent::Text(text) if is_sealed(text)) {
+        return Ok(());
+    }
+    let plaintext = serde_json::to_vec(&event.content)?;
+    event.content = EventContent::Text(seal(key_id, &plaintext)?);
+    Ok(())
+}
+
+pub fn open_event(event: &mut Event) -> Result<()> {
+    if let EventContent::Text(text) = &event.content {
+        if is_sealed(text) {
+            event.content = serde_json::from_slice(&open(text)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Wraps and unwraps data keys with the KMS key a user's `key_ref` names.
+#[derive(Clone)]
+pub struct KeyManager {
+    config: EncryptionConfig,
+    client: reqwest::Client,
+}
+
+enum KeyRef<'a> {
+    Local(&'a str),
+    Vault(&'a str),
+}
+
+impl<'a> KeyRef<'a> {
+    fn parse(key_ref: &'a str) -> Result<Self> {
+        let (scheme, name) = key_ref
+            .split_once(':')
+            .filter(|(_, name)| !name.is_empty())
+            .ok_or_else(|| anyhow!("key reference {:?} is not <scheme>:<key>", key_ref))?;
+        match scheme {
+            "local" => Ok(Self::Local(name)),
+            "vault" => Ok(Self::Vault(name)),
+            other => Err(anyhow!("unsupported key management scheme {:?}", other)),
+        }
+    }
+}
+
+impl KeyManager {
+    pub fn new(config: EncryptionConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build KMS HTTP client"),
+        }
+    }
+
+    /// Check that `key_ref` names a key this node can reach, without calling the KMS.
+    pub fn validate_key_ref(&self, key_ref: &str) -> Result<()> {
+        match KeyRef::parse(key_ref)? {
+            KeyRef::Local(_) => self.local_master_key().map(|_| ()),
+            KeyRef::Vault(_) => self.vault().map(|_| ()),
+        }
+    }
+
+    /// A fresh data key together with its wrapped form for the registry.
+    pub async fn generate_data_key(&self, key_ref: &str) -> Result<([u8; DATA_KEY_LEN], String)> {
+        match KeyRef::parse(key_ref)? {
+            KeyRef::Local(name) => {
+                let data_key: [u8; DATA_KEY_LEN] = Aes256Gcm::generate_key(&mut OsRng).into();
+                let master = self.local_master_key()?;
+                let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                let wrapped = master
+                    .encrypt(
+                        &nonce,
+                        Payload {
+                            msg: &data_key,
+                            aad: name.as_bytes(),
+                        },
+                    )
+                    .map_err(|_| anyhow!("failed to wrap data key"))?;
+                let mut sealed = nonce.to_vec();
+                sealed.extend_from_slice(&wrapped);
+                Ok((data_key, BASE64.encode(sealed)))
+            }
+            KeyRef::Vault(name) => {
+                let data = self
+                    .vault_call(
+                        &format!("datakey/plaintext/{}", name),
+                        serde_json::json!({}),
+                    )
+                    .await?;
+                let wrapped = data["ciphertext"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Vault returned no wrapped data key"))?
+                    .to_string();
+                Ok((decode_data_key(&data["plaintext"])?, wrapped))
+            }
+        }
+    }
+
+    pub async fn unwrap_data_key(
+        &self,
+        key_ref: &str,
+        wrapped: &str,
+    ) -> Result<[u8; DATA_KEY_LEN]> {
+        match KeyRef::parse(key_ref)? {
+            KeyRef::Local(name) => {
+                let sealed = BASE64
+                    .decode(wrapped)
+                    .context("wrapped data key is not valid base64")?;
+                if sealed.len() < NONCE_LEN {
+                    return Err(anyhow!("wrapped data key is truncated"));
+                }
+                let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+                let data_key = self
+                    .local_master_key()?
+                    .decrypt(
+                        Nonce::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: name.as_bytes(),
+                        },
+                    )
+                    .map_err(|_| anyhow!("local master key cannot unwrap this data key"))?;
+                data_key
+                    .try_into()
+                    .map_err(|_| anyhow!("unwrapped data key has the wrong length"))
+            }
+            KeyRef::Vault(name) => {
+                let data = self
+                    .vault_call(
+                        &format!("decrypt/{}", name),
+                        serde_json::json!({ "ciphertext": wrapped }),
+                    )
+                    .await?;
+                decode_data_key(&data["plaintext"])
+            }
+        }
+    }
+
+    fn local_master_key(&self) -> Result<Aes256Gcm> {
+        let encoded = self
+            .config
+            .local_master_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("encryption.local_master_key is not configured"))?;
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .context("encryption.local_master_key is not valid base64")?;
+        if bytes.len() != DATA_KEY_LEN {
+            return Err(anyhow!(
+                "encryption.local_master_key must be {} bytes",
+                DATA_KEY_LEN
+            ));
+        }
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)))
+    }
+
+    fn vault(&self) -> Result<(&str, &str)> {
+        let addr = self
+            .config
+            .vault_addr
+            .as_deref()
+            .ok_or_else(|| anyhow!("encryption.vault_addr is not configured"))?;
+        let token = self
+            .config
+            .vault_token
+            .as_deref()
+            .ok_or_else(|| anyhow!("encryption.vault_token is not configured"))?;
+        Ok((addr.trim_end_matches('/'), token))
+    }
+
+    /// POST to the transit engine and return the response's `data` object.
+    async fn vault_call(&self, path: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+        let (addr, token) = self.vault()?;
+        let mount = self
+            .config
+            .vault_transit_mount
+            .as_deref()
+            .unwrap_or("transit")
+            .trim_matches('/');
+        let response = self
+            .client
+            .post(format!("{}/v1/{}/{}", addr, mount, path))
+            .header("X-Vault-Token", token)
+            .json(&body)
+            .send()
+            .await
+            .context("failed to reach Vault")?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Vault answered {}: {}", status, detail));
+        }
+        let mut reply: serde_json::Value = response.json().await?;
+        Ok(reply["data"].take())
+    }
+}
+
+fn decode_data_key(plaintext: &serde_json::Value) -> Result<[u8; DATA_KEY_LEN]> {
+    let encoded = plaintext
+        .as_str()
+        .ok_or_else(|| anyhow!("KMS returned no data key"))?;
+    BASE64
+        .decode(encoded)
+        .context("KMS returned a data key that is not base64")?
+        .try_into()
+        .map_err(|_| anyhow!("KMS returned a data key of the wrong length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::MemoryType;
+    use uuid::Uuid;
+
+    fn local_manager() -> KeyManager {
+        KeyManager::new(EncryptionConfig {
+            local_master_key: Some(BASE64.encode([7u8; DATA_KEY_LEN])),
+            ..EncryptionConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_seal_round_trips_and_fails_once_the_key_is_forgotten() {
+        let key_id = Uuid::new_v4().to_string();
+        unlock(&key_id, &[1u8; DATA_KEY_LEN]);
+        let mut unit = MemoryUnit::new(
+            None,
+            "u1".to_string(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "likes green tea".to_string(),
+            None,
+        );
+        seal_unit(&mut unit, &key_id).unwrap();
+        assert!(is_sealed(&unit.content));
+        assert_eq!(sealed_key_id(&unit.content), Some(key_id.as_str()));
+        assert!(!unit.content.contains("green tea"));
+
+        let mut opened = unit.clone();
+        open_unit(&mut opened).unwrap();
+        assert_eq!(opened.content, "likes green tea");
+
+        forget(&key_id);
+        let error = open_unit(&mut unit).unwrap_err();
+        assert!(is_key_unavailable(&error));
+    }
+
+    #[test]
+    fn test_sealed_event_restores_structured_content() {
+        let key_id = Uuid::new_v4().to_string();
+        unlock(&key_id, &[2u8; DATA_KEY_LEN]);
+        let content = EventContent::Json(serde_json::json!({ "order": 42 }));
+        let mut event = Event::new(
+            None,
+            "u1".to_string(),
+            None,
+            Uuid::new_v4(),
+            content.clone(),
+        );
+        seal_event(&mut event, &key_id).unwrap();
+        assert!(matches!(&event.content, EventContent::Text(text) if is_sealed(text)));
+        open_event(&mut event).unwrap();
+        assert_eq!(
+            serde_json::to_value(&event.content).unwrap(),
+            serde_json::to_value(&content).unwrap()
+        );
+        forget(&key_id);
+    }
+
+    #[test]
+    fn test_tampered_content_fails_authentication() {
+        let key_id = Uuid::new_v4().to_string();
+        unlock(&key_id, &[3u8; DATA_KEY_LEN]);
+        let sealed = seal(&key_id, b"secret").unwrap();
+        let other = Uuid::new_v4().to_string();
+        unlock(&other, &[3u8; DATA_KEY_LEN]);
+        // Same key material under another id: the id is authenticated as well.
+        let relabeled = sealed.replacen(&key_id, &other, 1);
+        let error = open(&relabeled).unwrap_err();
+        assert!(!is_key_unavailable(&error));
+        forget(&key_id);
+        forget(&other);
+    }
+
+    #[tokio::test]
+    async fn test_local_key_ref_wraps_and_unwraps_data_keys() {
+        let manager = local_manager();
+        manager.validate_key_ref("local:tenant-a").unwrap();
+        assert!(manager.validate_key_ref("vault:tenant-a").is_err());
+        assert!(manager.validate_key_ref("aws:tenant-a").is_err());
+
+        let (data_key, wrapped) = manager.generate_data_key("local:tenant-a").await.unwrap();
+        assert_eq!(
+            manager
+                .unwrap_data_key("local:tenant-a", &wrapped)
+                .await
+                .unwrap(),
+            data_key
+        );
+        assert!(manager
+            .unwrap_data_key("local:tenant-b", &wrapped)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Emotion tags for memories: the fixed label set, a lexicon classifier used when no
+//! LLM is configured, and the sentiment each label counts towards.
+
+use serde::{Deserialize, Serialize};
+
+/// Labels a memory can be tagged with, sorted.
+pub const EMOTION_LABELS: &[&str] = &[
+    "angry",
+    "anxious",
+    "confused",
+    "disappointed",
+    "excited",
+    "frustrated",
+    "grateful",
+    "happy",
+    "sad",
+    "satisfied",
+];
+
+/// Cue words per label for the lexicon classifier. A word matches when it starts one of
+/// the text's words, so "frustrat" covers "frustrated" and "frustrating".
+const EMOTION_CUES: &[(&str, &[&str])] = &[
+    (
+        "angry",
+        &["angry", "furious", "outraged", "mad at", "pissed"],
+    ),
+    (
+        "anxious",
+        &[
+            "anxious", "worried", "nervous", "scared", "afraid", "stress",
+        ],
+    ),
+    (
+        "confused",
+        &[
+            "confus",
+            "unclear",
+            "don't understand",
+            "makes no sense",
+            "puzzl",
+        ],
+    ),
+    ("disappointed", &["disappoint", "let down", "letdown"]),
+    (
+        "excited",
+        &["excited", "thrilled", "can't wait", "looking forward"],
+    ),
+    (
+        "frustrated",
+        &[
+            "frustrat",
+            "annoy",
+            "fed up",
+            "keeps failing",
+            "still broken",
+        ],
+    ),
+    ("grateful", &["thank", "grateful", "appreciat"]),
+    (
+        "happy",
+        &["happy", "glad", "delighted", "love it", "great news"],
+    ),
+    (
+        "sad",
+        &[
+            "sad",
+            "unhappy",
+            "depress",
+            "heartbroken",
+            "lonely",
+            "miss ",
+        ],
+    ),
+    (
+        "satisfied",
+        &[
+            "satisfied",
+            "works now",
+            "works great",
+            "resolved",
+            "perfect",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sentiment {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+/// Sentiment an emotion label counts towards; unknown labels are neutral.
+pub fn sentiment_of(emotion: &str) -> Sentiment {
+    match emotion {
+        "excited" | "grateful" | "happy" | "satisfied" => Sentiment::Positive,
+        "angry" | "anxious" | "disappointed" | "frustrated" | "sad" => Sentiment::Negative,
+        _ => Sentiment::Neutral,
+    }
+}
+
+/// Lowercase, drop anything outside [`EMOTION_LABELS`], sort and dedupe.
+pub fn normalize_emotions(labels: &[String]) -> Vec<String> {
+    let mut emotions: Vec<String> = labels
+        .iter()
+        .map(|label| label.trim().to_lowercase())
+        .filter(|label| EMOTION_LABELS.contains(&label.as_str()))
+        .collect();
+    emotions.sort();
+    emotions.dedup();
+    emotions
+}
+
+/// Labels whose cue words occur in `text`.
+pub fn classify_emotions(text: &str) -> Vec<String> {
+    let text = format!(" {} ", text.to_lowercase());
+    EMOTION_CUES
+        .iter()
+        .filter(|(_, cues)| {
+            cues.iter().any(|cue| {
+                text.match_indices(cue).any(|(at, _)| {
+                    !text[..at]
+                        .chars()
+                        .next_back()
+                        .is_some_and(char::is_alphanumeric)
+                })
+            })
+        })
+        .map(|(label, _)| label.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_emotions_matches_cues_at_word_starts() {
+        assert_eq!(
+            classify_emotions("The export keeps failing and I'm so frustrated. Thanks anyway!"),
+            vec!["frustrated".to_string(), "grateful".to_string()]
+        );
+        // "sad" inside "crusade" is not a cue.
+        assert!(classify_emotions("The crusade ended on Tuesday").is_empty());
+        assert_eq!(
+            normalize_emotions(&[" Sad".into(), "bored".into(), "sad".into()]),
+            vec!["sad".to_string()]
+        );
+        assert_eq!(sentiment_of("frustrated"), Sentiment::Negative);
+        assert_eq!(sentiment_of("grateful"), Sentiment::Positive);
+    }
+}
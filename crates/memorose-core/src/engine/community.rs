@@ -1,5 +1,9 @@
-use super::types::PendingMaterializationJob;
+use super::types::{
+    CommunityRecord, CommunitySnapshot, CommunityState, CommunitySummaryState,
+    PendingMaterializationJob,
+};
 use anyhow::Result;
+use memorose_common::config::PromptKind;
 use memorose_common::{GraphEdge, MemoryUnit, RelationType};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
@@ -16,15 +20,22 @@ impl super::MemoroseEngine {
 
     /// Graph-driven L2 generation with configurable thresholds/limits.
     /// Returns number of L2 units created in this run.
+    ///
+    /// Recomputes the whole partition and stores it as the baseline for
+    /// [`Self::process_communities_incremental`].
     pub async fn process_communities_with_limits(
         &self,
         user_id: &str,
         min_members: usize,
         max_groups: usize,
     ) -> Result<usize> {
+        // Markers written after this point belong to the next run.
+        let dirty = self.dirty_community_nodes(user_id)?;
         let edges = self.graph.get_all_edges_for_user(user_id).await?;
 
         if edges.is_empty() {
+            self.save_community_state(user_id, &CommunityState::default())?;
+            self.clear_dirty_community_nodes(user_id, &dirty)?;
             return Ok(0);
         }
 
@@ -33,6 +44,10 @@ impl super::MemoroseEngine {
         })
         .await?;
 
+        let mut state = CommunityState {
+            node_to_community: communities.clone(),
+            summaries: HashMap::new(),
+        };
         let mut community_groups: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
         for (node_id, community_id) in communities {
             community_groups
@@ -44,7 +59,7 @@ impl super::MemoroseEngine {
         let min_members = min_members.max(1);
         let mut created = 0usize;
 
-        for (_comm_id, members) in community_groups {
+        for (comm_id, members) in community_groups {
             if created >= max_groups {
                 break;
             }
@@ -53,60 +68,404 @@ impl super::MemoroseEngine {
                 continue;
             }
 
-            let member_ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
-            let units = self.fetch_units(user_id, member_ids.clone()).await?;
+            if let Some(l2_id) = self
+                .enqueue_community_insight(user_id, &members, None)
+                .await?
+            {
+                state
+                    .summaries
+                    .insert(comm_id, CommunitySummaryState { l2_id, members });
+                created += 1;
+            }
+        }
 
-            if units.is_empty() {
+        self.save_community_state(user_id, &state)?;
+        self.clear_dirty_community_nodes(user_id, &dirty)?;
+        Ok(created)
+    }
+
+    /// Community detection limited to the nodes whose edges changed since the last run,
+    /// together with the rest of the communities they belonged to. L2 insights are only
+    /// regenerated for communities whose members drifted by more than
+    /// `resummarize_threshold` since they were summarized. Falls back to a full run when
+    /// no baseline partition is stored yet. Returns the number of L2 units created.
+    pub async fn process_communities_incremental(
+        &self,
+        user_id: &str,
+        min_members: usize,
+        max_groups: usize,
+        resummarize_threshold: f32,
+    ) -> Result<usize> {
+        let Some(mut state) = self.get_community_state(user_id)? else {
+            return self
+                .process_communities_with_limits(user_id, min_members, max_groups)
+                .await;
+        };
+        let dirty = self.dirty_community_nodes(user_id)?;
+        if dirty.is_empty() {
+            return Ok(0);
+        }
+
+        let affected = affected_community_nodes(&state, &dirty);
+        let affected_ids: Vec<Uuid> = affected.iter().copied().collect();
+        let outgoing = self
+            .graph
+            .batch_get_outgoing_edges(user_id, &affected_ids)
+            .await?;
+        let incoming = self
+            .graph
+            .batch_get_incoming_edges(user_id, &affected_ids)
+            .await?;
+        let mut seen = HashSet::new();
+        let edges: Vec<GraphEdge> = outgoing
+            .into_values()
+            .chain(incoming.into_values())
+            .flatten()
+            .filter(|edge| {
+                seen.insert((
+                    edge.source_id,
+                    edge.target_id,
+                    edge.relation.as_str().to_string(),
+                ))
+            })
+            .collect();
+
+        tracing::debug!(
+            "Incremental community update for user {}: {} dirty nodes, {} affected, {} edges",
+            user_id,
+            dirty.len(),
+            affected.len(),
+            edges.len()
+        );
+
+        let labels = tokio::task::spawn_blocking(move || {
+            crate::community::CommunityDetector::detect_communities(&edges)
+        })
+        .await?;
+        let plan = plan_community_update(
+            &state,
+            &affected,
+            &labels,
+            min_members.max(1),
+            resummarize_threshold,
+        );
+
+        for node in &affected {
+            state.node_to_community.remove(node);
+        }
+        for community_id in &plan.dropped {
+            state.summaries.remove(community_id);
+        }
+
+        let mut created = 0usize;
+        let mut deferred = Vec::new();
+        for group in plan.groups {
+            for member in &group.members {
+                state.node_to_community.insert(*member, group.community_id);
+            }
+            if !group.needs_summary {
                 continue;
             }
+            if created >= max_groups {
+                // Revisit on the next run instead of losing the pending summary.
+                deferred.extend(group.members);
+                continue;
+            }
+            let supersedes = state
+                .summaries
+                .get(&group.community_id)
+                .map(|summary| summary.l2_id);
+            if let Some(l2_id) = self
+                .enqueue_community_insight(user_id, &group.members, supersedes)
+                .await?
+            {
+                state.summaries.insert(
+                    group.community_id,
+                    CommunitySummaryState {
+                        l2_id,
+                        members: group.members,
+                    },
+                );
+                created += 1;
+            }
+        }
 
-            let texts: Vec<String> = units.iter().map(|u| u.content.clone()).collect();
+        self.save_community_state(user_id, &state)?;
+        self.clear_dirty_community_nodes(user_id, &dirty)?;
+        self.mark_community_nodes_dirty(user_id, &deferred)?;
+        Ok(created)
+    }
 
-            let insight = self.arbitrator.summarize_community(texts).await?;
+    /// Re-summarize communities whose L2 insight no longer matches their members: members
+    /// joined or left the community, or were forgotten, by more than `resummarize_threshold`
+    /// (as a Jaccard distance). The new insight supersedes the stale one. Returns the number
+    /// of insights regenerated.
+    pub async fn refresh_stale_community_summaries(
+        &self,
+        user_id: &str,
+        min_members: usize,
+        max_groups: usize,
+        resummarize_threshold: f32,
+    ) -> Result<usize> {
+        let Some(mut state) = self.get_community_state(user_id)? else {
+            return Ok(0);
+        };
+        let summary_ids: HashSet<Uuid> = state.summaries.values().map(|s| s.l2_id).collect();
+        let mut current: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (node, community_id) in &state.node_to_community {
+            if !summary_ids.contains(node) && state.summaries.contains_key(community_id) {
+                current.entry(*community_id).or_default().push(*node);
+            }
+        }
 
-            let mut l2_unit = MemoryUnit::new(
-                None,
-                user_id.to_string(),
-                None,
-                Uuid::new_v4(),
-                memorose_common::MemoryType::Factual,
-                insight.summary,
-                None,
-            );
-            l2_unit.level = 2;
-            l2_unit.keywords.push(insight.name.clone());
-            l2_unit.keywords.extend(insight.keywords);
-            l2_unit.references = members.clone();
-            let l2_id = l2_unit.id;
-            let uid2 = user_id.to_string();
-            let post_publish_edges = members
-                .iter()
-                .map(|member_id| {
-                    GraphEdge::new(
-                        uid2.clone(),
-                        l2_id,
-                        *member_id,
-                        RelationType::DerivedFrom,
-                        1.0,
-                    )
-                })
-                .collect::<Vec<_>>();
-            self.enqueue_materialization_jobs(vec![PendingMaterializationJob::new(
-                l2_unit,
-                post_publish_edges,
-                None,
-            )])?;
+        let mut community_ids: Vec<Uuid> = state.summaries.keys().copied().collect();
+        community_ids.sort();
+        let min_members = min_members.max(1);
+        let mut refreshed = 0usize;
+        for community_id in community_ids {
+            if refreshed >= max_groups {
+                break;
+            }
+            let members = current.remove(&community_id).unwrap_or_default();
+            let member_ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
+            let mut live: Vec<Uuid> = self
+                .fetch_units(user_id, member_ids)
+                .await?
+                .into_iter()
+                // Superseded insights stay in the graph but are not members.
+                .filter(|unit| unit.level < 2)
+                .map(|unit| unit.id)
+                .collect();
+            live.sort();
+            if live.len() < min_members {
+                continue;
+            }
+
+            let summary = &state.summaries[&community_id];
+            let live_set: HashSet<Uuid> = live.iter().copied().collect();
+            let drift = summary_drift(summary, &live_set, &summary_ids);
+            if drift <= f64::from(resummarize_threshold) {
+                continue;
+            }
 
-            created += 1;
+            let stale_id = summary.l2_id;
             tracing::info!(
-                "Created L2 Insight '{}' from {} members for user {}",
-                insight.name,
-                units.len(),
-                user_id
+                "L2 insight {} for user {} drifted by {:.2}; regenerating",
+                stale_id,
+                user_id,
+                drift
             );
+            if let Some(l2_id) = self
+                .enqueue_community_insight(user_id, &live, Some(stale_id))
+                .await?
+            {
+                state.summaries.insert(
+                    community_id,
+                    CommunitySummaryState {
+                        l2_id,
+                        members: live,
+                    },
+                );
+                refreshed += 1;
+            }
         }
 
-        Ok(created)
+        if refreshed > 0 {
+            self.save_community_state(user_id, &state)?;
+        }
+        Ok(refreshed)
+    }
+
+    /// Users that have a stored community partition on this engine.
+    pub fn list_community_state_users(&self) -> Result<Vec<String>> {
+        self.scan_key_suffixes("community_state:")
+    }
+
+    /// Lower the importance of an L2 insight that has been superseded, so retrieval
+    /// prefers its replacement.
+    async fn demote_superseded_insight(&self, user_id: &str, unit_id: Uuid) -> Result<()> {
+        let Some(mut unit) = self.get_memory_unit_raw(user_id, unit_id)? else {
+            return Ok(());
+        };
+        unit.importance *= SUPERSEDED_INSIGHT_IMPORTANCE_FACTOR;
+        let key = format!("u:{}:unit:{}", user_id, unit_id);
+        self.kv_store
+            .put(key.as_bytes(), &self.encode_memory_unit(&unit)?)?;
+        self.invalidate_query_cache(user_id).await;
+        Ok(())
+    }
+
+    /// Community summary prompt for `units`; an app's template applies only when every
+    /// member belongs to that app.
+    fn community_summary_prompt(&self, units: &[MemoryUnit]) -> Option<String> {
+        let app_id = units.first().and_then(|unit| unit.agent_id.as_deref());
+        let shared_app = app_id.filter(|app_id| {
+            units
+                .iter()
+                .all(|unit| unit.agent_id.as_deref() == Some(*app_id))
+        });
+        self.configured_prompt(PromptKind::CommunitySummary, shared_app)
+    }
+
+    /// Summarize `members` into an L2 insight and queue it for materialization. When the
+    /// insight replaces `supersedes`, the stale insight is linked to it with `EvolvedTo`
+    /// and demoted. Returns the id of the queued unit, or `None` when no member unit exists.
+    async fn enqueue_community_insight(
+        &self,
+        user_id: &str,
+        members: &[Uuid],
+        supersedes: Option<Uuid>,
+    ) -> Result<Option<Uuid>> {
+        let member_ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
+        let units = self.fetch_units(user_id, member_ids).await?;
+
+        if units.is_empty() {
+            return Ok(None);
+        }
+
+        let texts: Vec<String> = units.iter().map(|u| u.content.clone()).collect();
+        let system_prompt = self.community_summary_prompt(&units);
+
+        let insight = self
+            .arbitrator
+            .summarize_community(texts, system_prompt.as_deref())
+            .await?;
+
+        let mut l2_unit = MemoryUnit::new(
+            None,
+            user_id.to_string(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            insight.summary,
+            None,
+        );
+        l2_unit.level = 2;
+        l2_unit.keywords.push(insight.name.clone());
+        l2_unit.keywords.extend(insight.keywords);
+        l2_unit.references = members.to_vec();
+        let l2_id = l2_unit.id;
+        let uid2 = user_id.to_string();
+        let mut post_publish_edges = members
+            .iter()
+            .map(|member_id| {
+                GraphEdge::new(
+                    uid2.clone(),
+                    l2_id,
+                    *member_id,
+                    RelationType::DerivedFrom,
+                    1.0,
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(stale_id) = supersedes {
+            post_publish_edges.push(GraphEdge::new(
+                uid2.clone(),
+                stale_id,
+                l2_id,
+                RelationType::EvolvedTo,
+                1.0,
+            ));
+        }
+        self.enqueue_materialization_jobs(vec![PendingMaterializationJob::new(
+            l2_unit,
+            post_publish_edges,
+            None,
+        )])?;
+        if let Some(stale_id) = supersedes {
+            self.demote_superseded_insight(user_id, stale_id).await?;
+        }
+
+        tracing::info!(
+            "Created L2 Insight '{}' from {} members for user {}",
+            insight.name,
+            units.len(),
+            user_id
+        );
+        Ok(Some(l2_id))
+    }
+
+    // ── Community change tracking ───────────────────────────────────
+
+    fn community_state_key(user_id: &str) -> String {
+        format!("community_state:{}", user_id)
+    }
+
+    fn community_dirty_prefix(user_id: &str) -> String {
+        format!("community_dirty:{}:", user_id)
+    }
+
+    /// The partition stored by the last community run for `user_id`.
+    pub fn get_community_state(&self, user_id: &str) -> Result<Option<CommunityState>> {
+        let key = Self::community_state_key(user_id);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+    }
+
+    fn save_community_state(&self, user_id: &str, state: &CommunityState) -> Result<()> {
+        let key = Self::community_state_key(user_id);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(state)?)
+    }
+
+    /// Record that `node_ids` gained or lost edges so the next incremental community run
+    /// revisits them.
+    pub(crate) fn mark_community_nodes_dirty(
+        &self,
+        user_id: &str,
+        node_ids: &[Uuid],
+    ) -> Result<()> {
+        let prefix = Self::community_dirty_prefix(user_id);
+        let system_kv = self.system_kv();
+        for node_id in node_ids {
+            system_kv.put(format!("{}{}", prefix, node_id).as_bytes(), &[])?;
+        }
+        Ok(())
+    }
+
+    /// Nodes whose edges changed since the last community run for `user_id`.
+    pub fn dirty_community_nodes(&self, user_id: &str) -> Result<Vec<Uuid>> {
+        Ok(self
+            .scan_key_suffixes(&Self::community_dirty_prefix(user_id))?
+            .into_iter()
+            .filter_map(|id| Uuid::parse_str(&id).ok())
+            .collect())
+    }
+
+    fn clear_dirty_community_nodes(&self, user_id: &str, node_ids: &[Uuid]) -> Result<()> {
+        let prefix = Self::community_dirty_prefix(user_id);
+        let system_kv = self.system_kv();
+        for node_id in node_ids {
+            system_kv.delete(format!("{}{}", prefix, node_id).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Add an edge to the graph and mark both endpoints for the next community run.
+    pub async fn add_graph_edge(&self, edge: &GraphEdge) -> Result<()> {
+        self.graph.add_edge(edge).await?;
+        let nodes = [edge.source_id, edge.target_id];
+        self.invalidate_graph_cache(&edge.user_id, &nodes).await;
+        self.mark_community_nodes_dirty(&edge.user_id, &nodes)
+    }
+
+    /// Bulk variant of [`Self::add_graph_edge`]: one graph write for the whole slice.
+    pub async fn add_graph_edges(&self, edges: &[GraphEdge]) -> Result<()> {
+        self.graph.add_edges(edges).await?;
+        let mut touched: HashMap<&str, Vec<Uuid>> = HashMap::new();
+        for edge in edges {
+            touched
+                .entry(edge.user_id.as_str())
+                .or_default()
+                .extend([edge.source_id, edge.target_id]);
+        }
+        for (user_id, nodes) in touched {
+            self.invalidate_graph_cache(user_id, &nodes).await;
+            self.mark_community_nodes_dirty(user_id, &nodes)?;
+        }
+        Ok(())
     }
 
     /// 增强版社区检测（支持多种算法）
@@ -207,8 +566,11 @@ impl super::MemoroseEngine {
             user_id
         );
 
+        let modularity = result.modularity;
+        let mut records = Vec::new();
+
         // 为每个社区生成 L2 摘要
-        for (_comm_id, members) in result.community_to_nodes {
+        for (comm_id, members) in result.community_to_nodes {
             let member_ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
             let units = self.fetch_units(user_id, member_ids.clone()).await?;
 
@@ -217,7 +579,18 @@ impl super::MemoroseEngine {
             }
 
             let texts: Vec<String> = units.iter().map(|u| u.content.clone()).collect();
-            let insight = self.arbitrator.summarize_community(texts).await?;
+            let system_prompt = self.community_summary_prompt(&units);
+            let insight = match self
+                .arbitrator
+                .summarize_community(texts, system_prompt.as_deref())
+                .await
+            {
+                Ok(insight) => insight,
+                Err(e) => {
+                    tracing::warn!("Skipping community {} summary: {:?}", comm_id, e);
+                    continue;
+                }
+            };
 
             let mut l2_unit = MemoryUnit::new(
                 None,
@@ -233,6 +606,13 @@ impl super::MemoroseEngine {
             l2_unit.keywords.extend(insight.keywords);
             l2_unit.references = members.clone();
             let l2_id = l2_unit.id;
+            records.push(CommunityRecord {
+                community_id: comm_id,
+                member_ids: members.clone(),
+                summary_id: Some(l2_id),
+                summary: Some(l2_unit.content.clone()),
+                keywords: l2_unit.keywords.clone(),
+            });
             let uid2 = user_id.to_string();
             let post_publish_edges = members
                 .iter()
@@ -260,6 +640,271 @@ impl super::MemoroseEngine {
             );
         }
 
+        self.save_community_snapshot(user_id, modularity, records)?;
         Ok(())
     }
+
+    // ── Community snapshots ─────────────────────────────────────────
+
+    fn community_snapshot_key(user_id: &str) -> String {
+        format!("community_snapshot:{}", user_id)
+    }
+
+    /// The cached result of the last community detection run for `user_id`.
+    pub fn get_community_snapshot(&self, user_id: &str) -> Result<Option<CommunitySnapshot>> {
+        let key = Self::community_snapshot_key(user_id);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+    }
+
+    fn save_community_snapshot(
+        &self,
+        user_id: &str,
+        modularity: f64,
+        mut communities: Vec<CommunityRecord>,
+    ) -> Result<CommunitySnapshot> {
+        for community in &mut communities {
+            community.member_ids.sort();
+        }
+        communities.sort_by(|a, b| {
+            b.member_ids
+                .len()
+                .cmp(&a.member_ids.len())
+                .then(a.community_id.cmp(&b.community_id))
+        });
+        let snapshot = CommunitySnapshot {
+            user_id: user_id.to_string(),
+            computed_at: chrono::Utc::now(),
+            modularity,
+            communities,
+        };
+        let key = Self::community_snapshot_key(user_id);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(&snapshot)?)?;
+        Ok(snapshot)
+    }
+
+    /// Re-run enhanced community detection and cache the result without generating new
+    /// L2 insights. Each community is matched with the L2 unit already summarizing it,
+    /// which the `DerivedFrom` edges pull into the same community.
+    pub async fn refresh_community_snapshot(
+        &self,
+        user_id: &str,
+        config: crate::community::DetectionConfig,
+    ) -> Result<CommunitySnapshot> {
+        let min_members = config.min_community_size.max(1);
+        let result = self.detect_communities_enhanced(user_id, config).await?;
+
+        let mut records = Vec::new();
+        for (community_id, members) in result.community_to_nodes {
+            if members.len() < min_members {
+                continue;
+            }
+            let member_ids: Vec<String> = members.iter().map(|id| id.to_string()).collect();
+            let units = self.fetch_units(user_id, member_ids).await?;
+            records.push(community_record(community_id, members, &units));
+        }
+
+        self.save_community_snapshot(user_id, result.modularity, records)
+    }
+
+    /// Cached communities for `user_id`, detecting them first when nothing is cached
+    /// or `refresh` is set.
+    pub async fn list_communities(
+        &self,
+        user_id: &str,
+        refresh: bool,
+    ) -> Result<CommunitySnapshot> {
+        if !refresh {
+            if let Some(snapshot) = self.get_community_snapshot(user_id)? {
+                return Ok(snapshot);
+            }
+        }
+        self.refresh_community_snapshot(user_id, crate::community::DetectionConfig::default())
+            .await
+    }
+}
+
+const COMMUNITY_KEYWORD_LIMIT: usize = 10;
+/// Importance multiplier applied to an L2 insight once a newer one replaces it.
+const SUPERSEDED_INSIGHT_IMPORTANCE_FACTOR: f32 = 0.5;
+
+pub(super) fn community_record(
+    community_id: Uuid,
+    members: Vec<Uuid>,
+    units: &[MemoryUnit],
+) -> CommunityRecord {
+    let summary = units
+        .iter()
+        .filter(|unit| unit.level >= 2)
+        .max_by_key(|unit| unit.transaction_time);
+
+    let keywords = match summary {
+        Some(summary) => summary.keywords.clone(),
+        None => {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for keyword in units.iter().flat_map(|unit| unit.keywords.iter()) {
+                *counts.entry(keyword.as_str()).or_default() += 1;
+            }
+            let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+            ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            ranked
+                .into_iter()
+                .take(COMMUNITY_KEYWORD_LIMIT)
+                .map(|(keyword, _)| keyword.to_string())
+                .collect()
+        }
+    };
+    let summary_id = summary.map(|unit| unit.id);
+
+    CommunityRecord {
+        community_id,
+        member_ids: members
+            .into_iter()
+            .filter(|id| Some(*id) != summary_id)
+            .collect(),
+        summary_id,
+        summary: summary.map(|unit| unit.content.clone()),
+        keywords,
+    }
+}
+
+/// How an incremental community run regroups the nodes it revisited.
+#[derive(Debug, Default)]
+pub(super) struct CommunityUpdatePlan {
+    pub groups: Vec<CommunityGroupUpdate>,
+    /// Previous communities that no longer have any members.
+    pub dropped: Vec<Uuid>,
+}
+
+#[derive(Debug)]
+pub(super) struct CommunityGroupUpdate {
+    pub community_id: Uuid,
+    pub members: Vec<Uuid>,
+    pub needs_summary: bool,
+}
+
+/// The dirty nodes plus every member of the communities they belonged to.
+pub(super) fn affected_community_nodes(state: &CommunityState, dirty: &[Uuid]) -> HashSet<Uuid> {
+    let touched: HashSet<Uuid> = dirty
+        .iter()
+        .filter_map(|node| state.node_to_community.get(node))
+        .copied()
+        .collect();
+    let mut affected: HashSet<Uuid> = dirty.iter().copied().collect();
+    affected.extend(
+        state
+            .node_to_community
+            .iter()
+            .filter(|(_, community)| touched.contains(community))
+            .map(|(node, _)| *node),
+    );
+    affected
+}
+
+/// Match the communities found in the revisited subgraph with the previous ones. Each
+/// new group keeps the id of the unclaimed previous community it overlaps most, and
+/// needs a new summary when it has none or its members drifted by more than
+/// `resummarize_threshold` (as a Jaccard distance) since the last summary. L2 nodes
+/// are not counted as members when comparing.
+pub(super) fn plan_community_update(
+    state: &CommunityState,
+    affected: &HashSet<Uuid>,
+    labels: &HashMap<Uuid, Uuid>,
+    min_members: usize,
+    resummarize_threshold: f32,
+) -> CommunityUpdatePlan {
+    let summary_ids: HashSet<Uuid> = state.summaries.values().map(|s| s.l2_id).collect();
+
+    let mut previous: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+    for node in affected {
+        if let Some(community) = state.node_to_community.get(node) {
+            previous.entry(*community).or_default().insert(*node);
+        }
+    }
+
+    let mut found: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for node in affected {
+        if let Some(label) = labels.get(node) {
+            found.entry(*label).or_default().push(*node);
+        }
+    }
+    let mut groups: Vec<Vec<Uuid>> = found
+        .into_values()
+        .map(|mut members| {
+            members.sort();
+            members
+        })
+        .collect();
+    // Larger groups claim previous ids first, so a split keeps the id on its bigger half.
+    groups.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+
+    let mut claimed: HashSet<Uuid> = HashSet::new();
+    let mut plan = CommunityUpdatePlan::default();
+    for members in groups {
+        let member_set: HashSet<Uuid> = members.iter().copied().collect();
+        let best = previous
+            .iter()
+            .filter(|(id, _)| !claimed.contains(*id))
+            .map(|(id, old)| (*id, jaccard(&member_set, old)))
+            .filter(|(_, similarity)| *similarity > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+        let community_id = match best {
+            Some((id, _)) => {
+                claimed.insert(id);
+                id
+            }
+            None => Uuid::new_v4(),
+        };
+
+        let content: HashSet<Uuid> = member_set.difference(&summary_ids).copied().collect();
+        let needs_summary = content.len() >= min_members
+            && match state.summaries.get(&community_id) {
+                Some(summary) => {
+                    summary_drift(summary, &content, &summary_ids)
+                        > f64::from(resummarize_threshold)
+                }
+                None => true,
+            };
+        plan.groups.push(CommunityGroupUpdate {
+            community_id,
+            members,
+            needs_summary,
+        });
+    }
+
+    let mut dropped: Vec<Uuid> = previous
+        .into_keys()
+        .filter(|id| !claimed.contains(id))
+        .collect();
+    dropped.sort();
+    plan.dropped = dropped;
+    plan
+}
+
+/// Jaccard distance between a community's current members and the members its
+/// summary was generated from, ignoring L2 nodes.
+fn summary_drift(
+    summary: &CommunitySummaryState,
+    members: &HashSet<Uuid>,
+    summary_ids: &HashSet<Uuid>,
+) -> f64 {
+    let summarized: HashSet<Uuid> = summary
+        .members
+        .iter()
+        .filter(|id| !summary_ids.contains(id))
+        .copied()
+        .collect();
+    let members: HashSet<Uuid> = members.difference(summary_ids).copied().collect();
+    1.0 - jaccard(&members, &summarized)
+}
+
+fn jaccard(a: &HashSet<Uuid>, b: &HashSet<Uuid>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
 }
@@ -0,0 +1,45 @@
+use super::types::ConsolidationLease;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+impl super::MemoroseEngine {
+    // ── Consolidation pool leases ───────────────────────────────────
+
+    fn consolidation_lease_key(lease_id: Uuid) -> String {
+        format!("consolidation_lease:{}", lease_id)
+    }
+
+    pub fn put_consolidation_lease(&self, lease: &ConsolidationLease) -> Result<()> {
+        let key = Self::consolidation_lease_key(lease.lease_id);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(lease)?)
+    }
+
+    pub fn get_consolidation_lease(&self, lease_id: Uuid) -> Result<Option<ConsolidationLease>> {
+        let key = Self::consolidation_lease_key(lease_id);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    pub fn delete_consolidation_lease(&self, lease_id: Uuid) -> Result<()> {
+        let key = Self::consolidation_lease_key(lease_id);
+        self.system_kv().delete(key.as_bytes())
+    }
+
+    /// Events reserved by leases still running at `now`. Expired leases are dropped on
+    /// the way, which frees their events for the next claim.
+    pub fn leased_event_ids(&self, now: DateTime<Utc>) -> Result<HashSet<Uuid>> {
+        let mut leased = HashSet::new();
+        for (key, value) in self.system_kv().scan(b"consolidation_lease:")? {
+            match serde_json::from_slice::<ConsolidationLease>(&value) {
+                Ok(lease) if lease.expires_at > now => leased.extend(lease.event_ids),
+                _ => self.system_kv().delete(&key)?,
+            }
+        }
+        Ok(leased)
+    }
+}
@@ -938,7 +938,7 @@ impl super::MemoroseEngine {
                         relation.clone(),
                         action.confidence,
                     );
-                    self.graph.add_edge(&edge).await?;
+                    self.add_graph_edge(&edge).await?;
                     let _ = self.record_rac_decision_with_review(&RacDecisionRecord {
                         created_at: Utc::now(),
                         stage: stage.into(),
@@ -967,7 +967,7 @@ impl super::MemoroseEngine {
                         relation.clone(),
                         action.confidence,
                     );
-                    self.graph.add_edge(&edge).await?;
+                    self.add_graph_edge(&edge).await?;
                     let _ = self.record_rac_decision_with_review(&RacDecisionRecord {
                         created_at: Utc::now(),
                         stage: stage.into(),
@@ -0,0 +1,207 @@
+use super::types::{DigestTopic, MemoryDigest};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::MemoryUnit;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Memories per topic handed to the LLM, most important first.
+const DIGEST_MEMORIES_PER_TOPIC: usize = 20;
+const DIGEST_TOPIC_KEYWORD_LIMIT: usize = 5;
+const UNGROUPED_TOPIC_LABEL: &str = "other";
+
+impl super::MemoroseEngine {
+    // ── Memory Digest ───────────────────────────────────────────────
+
+    fn memory_digest_key(user_id: &str) -> String {
+        format!("digest:{}", user_id)
+    }
+
+    /// Digest of the user's L1 memories recorded in `[from, to)`, grouped by the cached
+    /// communities and, outside them, by shared keyword. The last digest per user is
+    /// cached and served again while the window and its memories are unchanged, unless
+    /// `refresh` is set. Returns the digest and whether it came from the cache.
+    pub async fn memory_digest(
+        &self,
+        user_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        refresh: bool,
+    ) -> Result<(MemoryDigest, bool)> {
+        let mut units = self
+            .list_memory_units_global(Some(user_id))
+            .await?
+            .into_iter()
+            .filter(|unit| {
+                unit.level == 1
+                    && Self::is_local_domain(&unit.domain)
+                    && unit.transaction_time >= from
+                    && unit.transaction_time < to
+            })
+            .collect::<Vec<_>>();
+        units.sort_by(|a, b| {
+            a.transaction_time
+                .cmp(&b.transaction_time)
+                .then(a.id.cmp(&b.id))
+        });
+        let source_fingerprint = digest_fingerprint(&units);
+
+        let key = Self::memory_digest_key(user_id);
+        if !refresh {
+            let cached: Option<MemoryDigest> = self
+                .system_kv()
+                .get(key.as_bytes())?
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+            if let Some(cached) = cached.filter(|cached| {
+                cached.from == from
+                    && cached.to == to
+                    && cached.source_fingerprint == source_fingerprint
+            }) {
+                return Ok((cached, true));
+            }
+        }
+
+        let communities = self
+            .get_community_snapshot(user_id)?
+            .map(|snapshot| snapshot.communities)
+            .unwrap_or_default();
+        let topics = group_digest_topics(&units, &communities);
+
+        let by_id: HashMap<Uuid, &MemoryUnit> = units.iter().map(|unit| (unit.id, unit)).collect();
+        let sections = topics
+            .iter()
+            .map(|topic| {
+                let mut members: Vec<&MemoryUnit> = topic
+                    .memory_ids
+                    .iter()
+                    .filter_map(|id| by_id.get(id).copied())
+                    .collect();
+                members.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+                members.truncate(DIGEST_MEMORIES_PER_TOPIC);
+                members.sort_by_key(|unit| unit.transaction_time);
+                (
+                    topic.label.clone(),
+                    members.iter().map(|unit| unit.content.clone()).collect(),
+                )
+            })
+            .collect::<Vec<(String, Vec<String>)>>();
+        let written = self.arbitrator.write_digest(sections).await?;
+
+        let digest = MemoryDigest {
+            user_id: user_id.to_string(),
+            from,
+            to,
+            generated_at: Utc::now(),
+            memory_count: units.len(),
+            digest: if written.is_empty() {
+                fallback_digest(&topics)
+            } else {
+                written.clone()
+            },
+            topics,
+            source_fingerprint,
+        };
+        // A digest the LLM did not write is not cached, so it is retried next time.
+        if !written.is_empty() {
+            self.system_kv()
+                .put(key.as_bytes(), &serde_json::to_vec(&digest)?)?;
+        }
+        Ok((digest, false))
+    }
+}
+
+fn digest_fingerprint(units: &[MemoryUnit]) -> String {
+    let mut hasher = Sha256::new();
+    for unit in units {
+        hasher.update(unit.id.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Group `units` (oldest first) into topics: members of a cached community share its
+/// topic, the rest are grouped by their first keyword. Largest topics come first.
+fn group_digest_topics(
+    units: &[MemoryUnit],
+    communities: &[super::types::CommunityRecord],
+) -> Vec<DigestTopic> {
+    let community_of: HashMap<Uuid, &super::types::CommunityRecord> = communities
+        .iter()
+        .flat_map(|community| community.member_ids.iter().map(move |id| (*id, community)))
+        .collect();
+
+    let mut topics: Vec<DigestTopic> = Vec::new();
+    let mut topic_index: HashMap<(Option<Uuid>, String), usize> = HashMap::new();
+    for unit in units {
+        let (community_id, label, keywords) = match community_of.get(&unit.id) {
+            Some(community) => (
+                Some(community.community_id),
+                community
+                    .keywords
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| UNGROUPED_TOPIC_LABEL.to_string()),
+                community.keywords.clone(),
+            ),
+            None => {
+                let label = unit
+                    .keywords
+                    .first()
+                    .map(|keyword| keyword.trim().to_lowercase())
+                    .filter(|keyword| !keyword.is_empty())
+                    .unwrap_or_else(|| UNGROUPED_TOPIC_LABEL.to_string());
+                (None, label, Vec::new())
+            }
+        };
+        let index = *topic_index
+            .entry((community_id, label.clone()))
+            .or_insert_with(|| {
+                topics.push(DigestTopic {
+                    community_id,
+                    label,
+                    keywords,
+                    memory_ids: Vec::new(),
+                });
+                topics.len() - 1
+            });
+        let topic = &mut topics[index];
+        topic.memory_ids.push(unit.id);
+        if topic.community_id.is_none() {
+            for keyword in &unit.keywords {
+                if topic.keywords.len() < DIGEST_TOPIC_KEYWORD_LIMIT
+                    && !topic.keywords.contains(keyword)
+                {
+                    topic.keywords.push(keyword.clone());
+                }
+            }
+        }
+    }
+
+    topics.sort_by(|a, b| {
+        b.memory_ids
+            .len()
+            .cmp(&a.memory_ids.len())
+            .then(a.label.cmp(&b.label))
+    });
+    topics
+}
+
+/// Digest written without an LLM: one line per topic with its memory count.
+fn fallback_digest(topics: &[DigestTopic]) -> String {
+    topics
+        .iter()
+        .map(|topic| {
+            format!(
+                "- {}: {} {}",
+                topic.label,
+                topic.memory_ids.len(),
+                if topic.memory_ids.len() == 1 {
+                    "memory"
+                } else {
+                    "memories"
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::GraphEdge;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+impl super::MemoroseEngine {
+    // ── Edge decay ──────────────────────────────────────────────────
+
+    /// `edge`'s weight at `now`, after decay since it was last written or reinforced.
+    /// Relations without a decay rule keep their stored weight.
+    pub(crate) fn decayed_edge_weight(&self, edge: &GraphEdge, now: DateTime<Utc>) -> f32 {
+        match self.edge_decay.rule_for(edge.relation.as_str()) {
+            Some(rule) => rule.decayed_weight(edge.weight, now - edge.transaction_time),
+            None => edge.weight,
+        }
+    }
+
+    /// Remove the user's edges that have decayed below their relation's `prune_below`
+    /// and have not been reinforced for `min_idle_days`. Returns how many were removed.
+    pub async fn prune_decayed_edges(&self, user_id: &str, now: DateTime<Utc>) -> Result<usize> {
+        if !self.edge_decay.enabled || self.edge_decay.relations.is_empty() {
+            return Ok(0);
+        }
+        let stale: Vec<GraphEdge> = self
+            .graph
+            .get_all_edges_for_user(user_id)
+            .await?
+            .into_iter()
+            .filter(|edge| {
+                self.edge_decay
+                    .rule_for(edge.relation.as_str())
+                    .is_some_and(|rule| rule.should_prune(edge.weight, now - edge.transaction_time))
+            })
+            .collect();
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let removed = self.graph.delete_edges(&stale).await?;
+        let nodes: Vec<Uuid> = stale
+            .iter()
+            .flat_map(|edge| [edge.source_id, edge.target_id])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        self.invalidate_graph_cache(user_id, &nodes).await;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,177 @@
+use super::helpers::validate_id;
+use crate::crypto::{self, KeyUnavailable};
+use anyhow::{anyhow, Result};
+use memorose_common::{MemoryUnit, UserKeyRecord};
+use uuid::Uuid;
+
+impl super::MemoroseEngine {
+    // ── Bring-your-own-key registry ─────────────────────────────────
+
+    fn user_key_key(user_id: &str) -> String {
+        format!("user_key:{}", user_id)
+    }
+
+    pub fn get_user_key(&self, user_id: &str) -> Result<Option<UserKeyRecord>> {
+        let key = Self::user_key_key(user_id);
+        match self.system_kv().get(key.as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_user_keys(&self) -> Result<Vec<UserKeyRecord>> {
+        Ok(self
+            .system_kv()
+            .scan(b"user_key:")?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+
+    /// Key that new content for `user_id` is sealed with; `None` leaves it in plaintext.
+    /// Writes for a user whose key has been revoked are refused.
+    pub(crate) fn sealing_key(&self, user_id: &str) -> Result<Option<String>> {
+        match self.get_user_key(user_id)? {
+            None => Ok(None),
+            Some(record) if record.is_revoked() => Err(KeyUnavailable {
+                key_id: record.key_id,
+            }
+            .into()),
+            Some(record) => Ok(Some(record.key_id)),
+        }
+    }
+
+    /// True when content sealed with `key_id` can never be read again: the key was revoked
+    /// or replaced in the registry.
+    pub(crate) fn is_key_shredded(&self, user_id: &str, key_id: &str) -> Result<bool> {
+        Ok(self
+            .get_user_key(user_id)?
+            .is_none_or(|record| record.key_id != key_id || record.is_revoked()))
+    }
+
+    /// Serialize a memory unit for storage, sealing its content with the user's key.
+    pub(crate) fn encode_memory_unit(&self, unit: &MemoryUnit) -> Result<Vec<u8>> {
+        match self.sealing_key(&unit.user_id)? {
+            Some(key_id) => {
+                let mut sealed = unit.clone();
+                crypto::seal_unit(&mut sealed, &key_id)?;
+                Ok(serde_json::to_vec(&sealed)?)
+            }
+            None => Ok(serde_json::to_vec(unit)?),
+        }
+    }
+
+    /// A new registry entry for `user_id` under the KMS key `key_ref`. The KMS generates
+    /// the data key, which is unlocked here right away; the entry takes effect once it is
+    /// stored with [`Self::put_user_key`].
+    pub async fn create_user_key(&self, user_id: &str, key_ref: &str) -> Result<UserKeyRecord> {
+        validate_id(user_id)?;
+        self.key_manager.validate_key_ref(key_ref)?;
+        if self
+            .get_user_key(user_id)?
+            .is_some_and(|record| !record.is_revoked())
+        {
+            return Err(anyhow!("user {} already has an active key", user_id));
+        }
+        let (data_key, wrapped) = self.key_manager.generate_data_key(key_ref).await?;
+        let key_id = Uuid::new_v4().to_string();
+        crypto::unlock(&key_id, &data_key);
+        Ok(UserKeyRecord {
+            user_id: user_id.to_string(),
+            key_id,
+            key_ref: key_ref.to_string(),
+            wrapped_key: Some(wrapped),
+            created_at: chrono::Utc::now(),
+            revoked_at: None,
+        })
+    }
+
+    /// Store a registry entry. An active key is unlocked through the KMS; if the KMS
+    /// refuses, the entry is still stored and the user's content stays unreadable on this
+    /// node until the key unlocks. A revoked key is dropped from the keyring, and the units
+    /// sealed with it leave the text index, which holds content in plaintext.
+    pub async fn put_user_key(&self, record: &UserKeyRecord) -> Result<()> {
+        validate_id(&record.user_id)?;
+        let key = Self::user_key_key(&record.user_id);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(record)?)?;
+
+        if record.is_revoked() {
+            crypto::forget(&record.key_id);
+            self.remove_shredded_units_from_index(&record.user_id, &record.key_id)
+                .await?;
+            self.invalidate_query_cache(&record.user_id).await;
+        } else if let Err(error) = self.unlock_user_key(record).await {
+            tracing::warn!(
+                "Failed to unlock encryption key of user {}: {:?}",
+                record.user_id,
+                error
+            );
+        }
+        Ok(())
+    }
+
+    /// Unlock the data key of every active registry entry, returning how many unlocked.
+    /// Keys the KMS refuses stay locked, which leaves that user's content unreadable
+    /// instead of failing startup.
+    pub async fn unlock_user_keys(&self) -> Result<usize> {
+        let mut unlocked = 0;
+        for record in self.list_user_keys()? {
+            if record.is_revoked() {
+                continue;
+            }
+            match self.unlock_user_key(&record).await {
+                Ok(()) => unlocked += 1,
+                Err(error) => tracing::warn!(
+                    "Failed to unlock encryption key of user {}: {:?}",
+                    record.user_id,
+                    error
+                ),
+            }
+        }
+        Ok(unlocked)
+    }
+
+    async fn unlock_user_key(&self, record: &UserKeyRecord) -> Result<()> {
+        if crypto::is_unlocked(&record.key_id) {
+            return Ok(());
+        }
+        let wrapped = record
+            .wrapped_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("key {} has been revoked", record.key_id))?;
+        let data_key = self
+            .key_manager
+            .unwrap_data_key(&record.key_ref, wrapped)
+            .await?;
+        crypto::unlock(&record.key_id, &data_key);
+        Ok(())
+    }
+
+    async fn remove_shredded_units_from_index(&self, user_id: &str, key_id: &str) -> Result<()> {
+        let prefix = format!("u:{}:unit:", user_id).into_bytes();
+        let kv = self.kv_store.clone();
+        let pairs = tokio::task::spawn_blocking(move || kv.scan(&prefix)).await??;
+        let ids: Vec<String> = pairs
+            .into_iter()
+            .filter_map(|(_, value)| {
+                let record: serde_json::Value = serde_json::from_slice(&value).ok()?;
+                let content = record.get("content")?.as_str()?;
+                if crypto::sealed_key_id(content) != Some(key_id) {
+                    return None;
+                }
+                record.get("id")?.as_str().map(str::to_string)
+            })
+            .collect();
+
+        let index = self.index.clone();
+        tokio::task::spawn_blocking(move || {
+            for id in &ids {
+                index.delete_unit(id)?;
+            }
+            Ok::<(), anyhow::Error>(())
+        })
+        .await??;
+        Ok(())
+    }
+}
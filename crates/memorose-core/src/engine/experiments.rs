@@ -0,0 +1,70 @@
+use anyhow::Result;
+use memorose_common::RetrievalFeedback;
+
+use super::types::ExperimentVariantOutcome;
+
+const EXPERIMENT_PREFIX: &str = "experiment:";
+
+impl super::MemoroseEngine {
+    // ── Retrieval feedback and ranking experiments ──────────────────
+
+    fn experiment_prefix(experiment: &str) -> String {
+        format!("{}{}:", EXPERIMENT_PREFIX, experiment)
+    }
+
+    /// Learn from which retrieved memories a client used, and count the outcome
+    /// towards the experiment variant that ranked them.
+    pub async fn apply_retrieval_feedback(&self, feedback: &RetrievalFeedback) -> Result<()> {
+        self.apply_reranker_feedback(
+            &feedback.user_id,
+            feedback.cited_ids.iter().map(ToString::to_string).collect(),
+            feedback
+                .retrieved_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        )
+        .await?;
+
+        let Some(arm) = &feedback.arm else {
+            return Ok(());
+        };
+        let _guard = self.experiment_outcomes_lock.lock().await;
+        let system_kv = self.system_kv();
+        let key = format!(
+            "{}{}",
+            Self::experiment_prefix(&arm.experiment),
+            arm.variant
+        );
+        let mut outcome = match system_kv.get(key.as_bytes())? {
+            Some(raw) => serde_json::from_slice::<ExperimentVariantOutcome>(&raw)?,
+            None => ExperimentVariantOutcome {
+                variant: arm.variant.clone(),
+                ..Default::default()
+            },
+        };
+        outcome.feedback += 1;
+        outcome.retrieved += feedback.retrieved_ids.len() as u64;
+        outcome.cited += feedback
+            .cited_ids
+            .iter()
+            .filter(|id| feedback.retrieved_ids.contains(id))
+            .count() as u64;
+        if let Some(rank) = feedback.first_cited_rank() {
+            outcome.useful += 1;
+            outcome.reciprocal_rank_sum += 1.0 / rank as f64;
+        }
+        system_kv.put(key.as_bytes(), &serde_json::to_vec(&outcome)?)?;
+        Ok(())
+    }
+
+    /// Feedback counted for each variant of `experiment`, by variant name.
+    pub fn experiment_outcomes(&self, experiment: &str) -> Result<Vec<ExperimentVariantOutcome>> {
+        Ok(self
+            .system_kv()
+            .scan(Self::experiment_prefix(experiment).as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+}
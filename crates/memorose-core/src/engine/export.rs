@@ -0,0 +1,425 @@
+//! Periodic export of new memory units and edges to a data warehouse as Parquet.
+//!
+//! Files are laid out as `{prefix}/{table}/date=YYYY-MM-DD/user_id={user}/part-*.parquet`,
+//! the Hive-style partitioning Spark, DuckDB, Athena and BigQuery pick up directly.
+
+use super::types::ExportReport;
+use super::MemoroseEngine;
+use anyhow::{Context, Result};
+use arrow_array::builder::{Float32Builder, ListBuilder, StringBuilder};
+use arrow_array::{
+    ArrayRef, Float32Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt64Array,
+    UInt8Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use memorose_common::{GraphEdge, MemoryDomain, MemoryType, MemoryUnit};
+use object_store::path::{Path, PathPart};
+use object_store::{ObjectStore, PutPayload};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const EXPORT_UNITS_WATERMARK_KEY: &[u8] = b"export:watermark:units";
+const EXPORT_EDGES_WATERMARK_KEY: &[u8] = b"export:watermark:edges";
+/// Keys read per page while collecting units.
+const EXPORT_PAGE_SIZE: usize = 1000;
+/// Rows newer than this may still be mid-write, so they wait for the next export.
+const EXPORT_SETTLE_SECS: i64 = 60;
+
+/// A `(date, user_id)` partition of one table.
+type Partition = (String, String);
+
+impl MemoroseEngine {
+    /// Write memory units and edges that appeared since the previous export to `url`
+    /// (`s3://`, `gs://`, `file://`, ...). Each table only advances its watermark once
+    /// all of its files are written, so a failed run is retried in full next time.
+    pub async fn export_to_warehouse(
+        &self,
+        url: &str,
+        max_rows_per_file: usize,
+    ) -> Result<ExportReport> {
+        let url = url::Url::parse(url).with_context(|| format!("invalid export url {}", url))?;
+        // Lower-cased so `AWS_ACCESS_KEY_ID` and friends match object_store's config keys.
+        let options = std::env::vars().map(|(k, v)| (k.to_ascii_lowercase(), v));
+        let (store, prefix) = object_store::parse_url_opts(&url, options)?;
+        let store: Arc<dyn ObjectStore> = Arc::from(store);
+        let cutoff = Utc::now() - chrono::Duration::seconds(EXPORT_SETTLE_SECS);
+        let run_id = Uuid::new_v4();
+        let mut report = ExportReport::default();
+
+        let since = self.export_watermark(EXPORT_UNITS_WATERMARK_KEY)?;
+        let units = self.units_published_between(since, cutoff).await?;
+        let mut partitions: BTreeMap<Partition, Vec<MemoryUnit>> = BTreeMap::new();
+        for unit in units {
+            partitions
+                .entry(partition_of(unit_export_time(&unit), &unit.user_id))
+                .or_default()
+                .push(unit);
+        }
+        for (partition, units) in partitions {
+            for (part, chunk) in units.chunks(max_rows_per_file.max(1)).enumerate() {
+                let path = export_path(&prefix, "memory_units", &partition, run_id, part);
+                write_parquet(&store, &path, units_to_record_batch(chunk)?).await?;
+                report.units += chunk.len();
+                report.files += 1;
+            }
+        }
+        self.set_export_watermark(EXPORT_UNITS_WATERMARK_KEY, cutoff)?;
+
+        let since = self.export_watermark(EXPORT_EDGES_WATERMARK_KEY)?;
+        let mut partitions: BTreeMap<Partition, Vec<GraphEdge>> = BTreeMap::new();
+        for edge in self.graph().scan_all_edges().await? {
+            if is_in_window(edge.transaction_time, since, cutoff) {
+                partitions
+                    .entry(partition_of(edge.transaction_time, &edge.user_id))
+                    .or_default()
+                    .push(edge);
+            }
+        }
+        for (partition, edges) in partitions {
+            for (part, chunk) in edges.chunks(max_rows_per_file.max(1)).enumerate() {
+                let path = export_path(&prefix, "edges", &partition, run_id, part);
+                write_parquet(&store, &path, edges_to_record_batch(chunk)?).await?;
+                report.edges += chunk.len();
+                report.files += 1;
+            }
+        }
+        self.set_export_watermark(EXPORT_EDGES_WATERMARK_KEY, cutoff)?;
+
+        Ok(report)
+    }
+
+    fn export_watermark(&self, key: &[u8]) -> Result<Option<DateTime<Utc>>> {
+        Ok(self
+            .system_kv()
+            .get(key)?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    fn set_export_watermark(&self, key: &[u8], at: DateTime<Utc>) -> Result<()> {
+        self.system_kv().put(key, &serde_json::to_vec(&at)?)
+    }
+
+    /// Visible user and agent units that became readable in `(since, until]`.
+    async fn units_published_between(
+        &self,
+        since: Option<DateTime<Utc>>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<MemoryUnit>> {
+        let kv = self.kv();
+        let mut units = Vec::new();
+        let mut after: Option<Vec<u8>> = None;
+        loop {
+            let page = {
+                let kv = kv.clone();
+                let after = after.clone();
+                tokio::task::spawn_blocking(move || {
+                    kv.scan_prefix_after(b"u:", after.as_deref(), EXPORT_PAGE_SIZE)
+                })
+                .await??
+            };
+            let Some((last_key, _)) = page.last() else {
+                break;
+            };
+            after = Some(last_key.clone());
+            for (key, val) in &page {
+                if !key.windows(6).any(|window| window == b":unit:") {
+                    continue;
+                }
+                let Ok(unit) = crate::migration::decode_sealed_memory_unit(val) else {
+                    continue;
+                };
+                if unit.domain == MemoryDomain::Organization
+                    || !is_in_window(unit_export_time(&unit), since, until)
+                    || !self.is_visible_memory_unit(&unit)?
+                {
+                    continue;
+                }
+                units.push(unit);
+            }
+        }
+        Ok(units)
+    }
+}
+
+/// When a unit became readable; units published by the materialization stage carry
+/// that time, older ones only their creation time.
+fn unit_export_time(unit: &MemoryUnit) -> DateTime<Utc> {
+    unit.materialized_at.unwrap_or(unit.transaction_time)
+}
+
+fn is_in_window(at: DateTime<Utc>, since: Option<DateTime<Utc>>, until: DateTime<Utc>) -> bool {
+    since.map_or(true, |since| at > since) && at <= until
+}
+
+fn partition_of(at: DateTime<Utc>, user_id: &str) -> Partition {
+    (at.format("%Y-%m-%d").to_string(), user_id.to_string())
+}
+
+fn export_path(
+    prefix: &Path,
+    table: &str,
+    partition: &Partition,
+    run_id: Uuid,
+    part: usize,
+) -> Path {
+    let (date, user_id) = partition;
+    // Each segment is encoded on its own, so user ids cannot escape their partition.
+    prefix
+        .child(table)
+        .child(PathPart::from(format!("date={}", date)))
+        .child(PathPart::from(format!("user_id={}", user_id)))
+        .child(format!("part-{}-{:05}.parquet", run_id, part))
+}
+
+async fn write_parquet(
+    store: &Arc<dyn ObjectStore>,
+    path: &Path,
+    batch: RecordBatch,
+) -> Result<()> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    store
+        .put(path, PutPayload::from(buffer))
+        .await
+        .with_context(|| format!("failed to write {}", path))?;
+    Ok(())
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+fn memory_type_str(memory_type: &MemoryType) -> &'static str {
+    match memory_type {
+        MemoryType::Factual => "factual",
+        MemoryType::Procedural => "procedural",
+    }
+}
+
+fn units_to_record_batch(units: &[MemoryUnit]) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("org_id", DataType::Utf8, true),
+        Field::new("user_id", DataType::Utf8, false),
+        Field::new("agent_id", DataType::Utf8, true),
+        Field::new("stream_id", DataType::Utf8, false),
+        Field::new("memory_type", DataType::Utf8, false),
+        Field::new("domain", DataType::Utf8, false),
+        Field::new("level", DataType::UInt8, false),
+        Field::new("content", DataType::Utf8, false),
+        Field::new(
+            "keywords",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+        Field::new("importance", DataType::Float32, false),
+        Field::new("access_count", DataType::UInt64, false),
+        timestamp_field("transaction_time", false),
+        timestamp_field("valid_time", true),
+        Field::new(
+            "references",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+        Field::new(
+            "embedding",
+            DataType::List(Arc::new(Field::new("item", DataType::Float32, true))),
+            true,
+        ),
+    ]));
+
+    let mut keywords = ListBuilder::new(StringBuilder::new());
+    let mut references = ListBuilder::new(StringBuilder::new());
+    let mut embeddings = ListBuilder::new(Float32Builder::new());
+    for unit in units {
+        for keyword in &unit.keywords {
+            keywords.values().append_value(keyword);
+        }
+        keywords.append(true);
+        for reference in &unit.references {
+            references.values().append_value(reference.to_string());
+        }
+        references.append(true);
+        match &unit.embedding {
+            Some(embedding) => {
+                embeddings.values().append_slice(embedding);
+                embeddings.append(true);
+            }
+            None => embeddings.append(false),
+        }
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| u.id.to_string()),
+        )),
+        Arc::new(StringArray::from_iter(
+            units.iter().map(|u| u.org_id.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| &u.user_id),
+        )),
+        Arc::new(StringArray::from_iter(
+            units.iter().map(|u| u.agent_id.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| u.stream_id.to_string()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| memory_type_str(&u.memory_type)),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| u.domain.as_str()),
+        )),
+        Arc::new(UInt8Array::from_iter_values(units.iter().map(|u| u.level))),
+        Arc::new(StringArray::from_iter_values(
+            units.iter().map(|u| &u.content),
+        )),
+        Arc::new(keywords.finish()),
+        Arc::new(Float32Array::from_iter_values(
+            units.iter().map(|u| u.importance),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            units.iter().map(|u| u.access_count),
+        )),
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                units.iter().map(|u| u.transaction_time.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(
+            TimestampMicrosecondArray::from_iter(
+                units
+                    .iter()
+                    .map(|u| u.valid_time.map(|t| t.timestamp_micros())),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(references.finish()),
+        Arc::new(embeddings.finish()),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+fn edges_to_record_batch(edges: &[GraphEdge]) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("user_id", DataType::Utf8, false),
+        Field::new("source_id", DataType::Utf8, false),
+        Field::new("target_id", DataType::Utf8, false),
+        Field::new("edge_kind", DataType::Utf8, false),
+        Field::new("relation", DataType::Utf8, false),
+        Field::new("weight", DataType::Float32, false),
+        timestamp_field("transaction_time", false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| &e.user_id),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| e.source_id.to_string()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| e.target_id.to_string()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| e.edge_kind.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| e.relation.as_str()),
+        )),
+        Arc::new(Float32Array::from_iter_values(
+            edges.iter().map(|e| e.weight),
+        )),
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                edges.iter().map(|e| e.transaction_time.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_path_is_partitioned_by_date_and_user() {
+        let at = DateTime::parse_from_rfc3339("2026-03-04T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let run_id = Uuid::nil();
+        let path = export_path(
+            &Path::from("warehouse"),
+            "memory_units",
+            &partition_of(at, "alice/../bob"),
+            run_id,
+            2,
+        );
+        let parts: Vec<_> = path.parts().map(|p| p.as_ref().to_string()).collect();
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[0], "warehouse");
+        assert_eq!(parts[2], "date=2026-03-04");
+        assert!(parts[3].starts_with("user_id=alice"));
+        assert!(!parts[3].contains('/'));
+        assert_eq!(parts[4], format!("part-{}-00002.parquet", run_id));
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_only_units_since_the_previous_run() -> Result<()> {
+        let data_dir = tempdir()?;
+        let export_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(data_dir.path(), 1000, true, true).await?;
+        let mut unit = MemoryUnit::new(
+            None,
+            "alice".to_string(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "Alice prefers tea".to_string(),
+            Some(vec![0.1; 4]),
+        );
+        unit.transaction_time = Utc::now() - chrono::Duration::hours(1);
+        unit.materialized_at = None;
+        engine.store_memory_unit(unit.clone()).await?;
+
+        let url = format!("file://{}", export_dir.path().display());
+        let report = engine.export_to_warehouse(&url, 100).await?;
+        assert_eq!(report.units, 1);
+
+        let written: Vec<_> = walkdir::WalkDir::new(export_dir.path())
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "parquet"))
+            .collect();
+        assert_eq!(written.len(), 1);
+        let file = std::fs::File::open(written[0].path())?;
+        let batches: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(file)?
+            .build()?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        let report = engine.export_to_warehouse(&url, 100).await?;
+        assert_eq!(report, ExportReport::default());
+        Ok(())
+    }
+}
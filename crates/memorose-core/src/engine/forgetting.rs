@@ -1,13 +1,19 @@
 use super::types::{PendingMaterializationJob, PendingMaterializationJobStatus};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
-use memorose_common::{ForgettingTombstone, MaterializationState, MemoryDomain, MemoryUnit};
+use memorose_common::{
+    ForgetMode, ForgetTargetKind, ForgettingTombstone, MaterializationState, MemoryDomain,
+    MemoryUnit, TrashedMemoryUnit,
+};
 use std::collections::HashSet;
 use uuid::Uuid;
 
 const PRUNE_SCAN_BATCH_SIZE: usize = 512;
 const PRUNE_DELETE_BATCH_SIZE: usize = 128;
 
+/// `reason_query` of the tombstone that hides a trashed unit.
+const TRASH_TOMBSTONE_REASON: &str = "trash";
+
 impl super::MemoroseEngine {
     // ── Forgetting ──────────────────────────────────────────────────
 
@@ -254,6 +260,34 @@ impl super::MemoroseEngine {
             .is_some())
     }
 
+    pub fn get_memory_unit_tombstone(
+        &self,
+        user_id: &str,
+        unit_id: Uuid,
+    ) -> Result<Option<ForgettingTombstone>> {
+        self.system_kv()
+            .get(Self::forgotten_memory_unit_key(user_id, unit_id).as_bytes())?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Record tombstones another node wrote for `user_id`'s units, e.g. those reconciliation
+    /// produced on the leader.
+    pub async fn apply_memory_unit_tombstones(
+        &self,
+        user_id: &str,
+        tombstones: &[ForgettingTombstone],
+    ) -> Result<()> {
+        for tombstone in tombstones {
+            let unit_id = Uuid::parse_str(&tombstone.target_id)?;
+            self.mark_memory_unit_forgotten(user_id, unit_id, tombstone)?;
+        }
+        if !tombstones.is_empty() {
+            self.invalidate_query_cache(user_id).await;
+        }
+        Ok(())
+    }
+
     pub fn is_event_forgotten(&self, user_id: &str, event_id: &str) -> Result<bool> {
         Ok(self
             .system_kv()
@@ -289,8 +323,11 @@ impl super::MemoroseEngine {
         self.delete_memory_unit_storage_by_key(unit_key, unit_id)
             .await?;
         let _ = self.graph.delete_edges_for_node(user_id, unit_id).await?;
+        self.mark_community_nodes_dirty(user_id, &[unit_id])?;
         self.invalidate_query_cache(user_id).await;
         self.clear_memory_unit_forgotten(user_id, unit_id)?;
+        self.system_kv()
+            .delete(Self::trash_key(user_id, unit_id).as_bytes())?;
 
         if let Some(unit) = unit {
             if unit.level == 1 {
@@ -302,8 +339,180 @@ impl super::MemoroseEngine {
         Ok(())
     }
 
-    /// Apply importance decay to memories for a specific user.
-    /// Updates only the KV store — does NOT re-index into LanceDB/Tantivy
+    // ── Trash ───────────────────────────────────────────────────────
+
+    fn trash_key(user_id: &str, unit_id: Uuid) -> String {
+        format!("trash:{}:{}", user_id, unit_id)
+    }
+
+    /// Move a memory unit to the trash. It is tombstoned, so every search path skips it,
+    /// but its storage is kept until `retention_days` have passed. A unit that was already
+    /// forgotten keeps its own tombstone, so restoring it does not bring it back.
+    /// Returns `None` when the unit does not exist.
+    pub async fn soft_delete_memory_unit(
+        &self,
+        user_id: &str,
+        unit_id: Uuid,
+        retention_days: u32,
+    ) -> Result<Option<TrashedMemoryUnit>> {
+        let Some(entry) = self.memory_unit_trash_entry(user_id, unit_id, retention_days)? else {
+            return Ok(None);
+        };
+        self.apply_memory_unit_trash(&entry).await?;
+        Ok(Some(entry))
+    }
+
+    /// The trash entry deleting `unit_id` now would write, or the one already written when
+    /// it is in the trash. In cluster mode the leader builds it and replicates it, so every
+    /// replica records the same restore window.
+    /// Returns `None` when the unit does not exist.
+    pub fn memory_unit_trash_entry(
+        &self,
+        user_id: &str,
+        unit_id: Uuid,
+        retention_days: u32,
+    ) -> Result<Option<TrashedMemoryUnit>> {
+        if self.get_memory_unit_raw(user_id, unit_id)?.is_none() {
+            return Ok(None);
+        }
+        if let Some(existing) = self.get_trashed_memory_unit(user_id, unit_id)? {
+            return Ok(Some(existing));
+        }
+        let deleted_at = Utc::now();
+        Ok(Some(TrashedMemoryUnit {
+            user_id: user_id.to_string(),
+            unit_id,
+            deleted_at,
+            purge_after: deleted_at + chrono::Duration::days(i64::from(retention_days)),
+        }))
+    }
+
+    /// Write a trash entry and the tombstone that hides its unit. Applying an entry for a
+    /// unit already in the trash keeps the first one. Returns false when the unit does not
+    /// exist.
+    pub async fn apply_memory_unit_trash(&self, entry: &TrashedMemoryUnit) -> Result<bool> {
+        let (user_id, unit_id) = (entry.user_id.as_str(), entry.unit_id);
+        let Some(unit) = self.get_memory_unit_raw(user_id, unit_id)? else {
+            return Ok(false);
+        };
+        if self.get_trashed_memory_unit(user_id, unit_id)?.is_some() {
+            return Ok(true);
+        }
+
+        if self.get_memory_unit_tombstone(user_id, unit_id)?.is_none() {
+            self.mark_memory_unit_forgotten(
+                user_id,
+                unit_id,
+                &ForgettingTombstone {
+                    user_id: user_id.to_string(),
+                    org_id: unit.org_id.clone(),
+                    target_kind: ForgetTargetKind::MemoryUnit,
+                    target_id: unit_id.to_string(),
+                    reason_query: TRASH_TOMBSTONE_REASON.to_string(),
+                    created_at: entry.deleted_at,
+                    preview_id: None,
+                    mode: ForgetMode::Logical,
+                },
+            )?;
+        }
+        self.system_kv().put(
+            Self::trash_key(user_id, unit_id).as_bytes(),
+            &serde_json::to_vec(entry)?,
+        )?;
+        self.invalidate_query_cache(user_id).await;
+        Ok(true)
+    }
+
+    /// Take a memory unit back out of the trash. Only the tombstone the trash wrote is
+    /// cleared; a unit forgotten before it was trashed stays forgotten.
+    /// Returns false when it is not in the trash.
+    pub async fn restore_memory_unit(&self, user_id: &str, unit_id: Uuid) -> Result<bool> {
+        let Some(entry) = self.get_trashed_memory_unit(user_id, unit_id)? else {
+            return Ok(false);
+        };
+        let written_by_trash = self
+            .get_memory_unit_tombstone(user_id, unit_id)?
+            .is_some_and(|tombstone| {
+                tombstone.reason_query == TRASH_TOMBSTONE_REASON
+                    && tombstone.created_at == entry.deleted_at
+            });
+        if written_by_trash {
+            self.clear_memory_unit_forgotten(user_id, unit_id)?;
+        }
+        self.system_kv()
+            .delete(Self::trash_key(user_id, unit_id).as_bytes())?;
+        self.invalidate_query_cache(user_id).await;
+        Ok(true)
+    }
+
+    pub fn get_trashed_memory_unit(
+        &self,
+        user_id: &str,
+        unit_id: Uuid,
+    ) -> Result<Option<TrashedMemoryUnit>> {
+        Ok(self
+            .system_kv()
+            .get(Self::trash_key(user_id, unit_id).as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    /// Memory units `user_id` has in the trash, most recently deleted first.
+    pub fn list_trashed_memory_units(&self, user_id: &str) -> Result<Vec<TrashedMemoryUnit>> {
+        let mut entries: Vec<TrashedMemoryUnit> = self
+            .system_kv()
+            .scan(format!("trash:{}:", user_id).as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect();
+        entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(entries)
+    }
+
+    /// Trashed memory units, of every user, whose restore window ended before `now`.
+    pub fn list_expired_trash(&self, now: chrono::DateTime<Utc>) -> Result<Vec<TrashedMemoryUnit>> {
+        Ok(self
+            .system_kv()
+            .scan(b"trash:")?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<TrashedMemoryUnit>(&value).ok())
+            .filter(|entry| entry.purge_after <= now)
+            .collect())
+    }
+
+    /// Permanently delete trashed memory units whose restore window ended before `now`.
+    /// The leader picks `now` and replicates it, so replicas purge the same units.
+    pub async fn purge_expired_trash(&self, now: chrono::DateTime<Utc>) -> Result<usize> {
+        let expired = self.list_expired_trash(now)?;
+        for entry in &expired {
+            self.delete_memory_unit_hard(&entry.user_id, entry.unit_id)
+                .await?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Pin or unpin a memory unit. Returns the updated unit, or `None` when `user_id` has
+    /// no visible unit `unit_id`.
+    pub async fn set_memory_unit_pinned(
+        &self,
+        user_id: &str,
+        unit_id: Uuid,
+        pinned: bool,
+    ) -> Result<Option<MemoryUnit>> {
+        let Some(mut unit) = self.get_memory_unit(user_id, unit_id).await? else {
+            return Ok(None);
+        };
+        if unit.pinned != pinned {
+            unit.pinned = pinned;
+            let key = format!("u:{}:unit:{}", user_id, unit_id);
+            self.kv_store
+                .put(key.as_bytes(), &self.encode_memory_unit(&unit)?)?;
+            self.invalidate_query_cache(user_id).await;
+        }
+        Ok(Some(unit))
+    }
+
+    /// Apply importance decay to memories for a specific user. Pinned memories keep
+    /// their importance. Updates only the KV store — does NOT re-index into LanceDB/Tantivy
     /// or trigger auto-linking/LLM calls.
     pub async fn decay_importance(&self, user_id: &str, factor: f32) -> Result<()> {
         let prefix = format!("u:{}:unit:", user_id);
@@ -315,7 +524,10 @@ impl super::MemoroseEngine {
         let kv = self.kv_store.clone();
         tokio::task::spawn_blocking(move || {
             for (key, val) in pairs {
-                if let Ok(mut unit) = serde_json::from_slice::<MemoryUnit>(&val) {
+                if let Ok(mut unit) = crate::migration::decode_sealed_memory_unit(&val) {
+                    if unit.pinned {
+                        continue;
+                    }
                     unit.importance *= factor;
                     if let Ok(new_val) = serde_json::to_vec(&unit) {
                         kv.put(&key, &new_val)?;
@@ -330,7 +542,8 @@ impl super::MemoroseEngine {
     }
 
     /// Remove memories with importance below the threshold for a specific user.
-    /// L1 units referenced by visible L2/L3 units are retained for provenance.
+    /// L1 units referenced by visible L2/L3 units are retained for provenance, and
+    /// pinned units are never pruned.
     /// Pruned units are deleted from KV, LanceDB vector store, and Tantivy text index.
     pub async fn prune_memories(&self, user_id: &str, threshold: f32) -> Result<usize> {
         let kv = self.kv_store.clone();
@@ -351,7 +564,7 @@ impl super::MemoroseEngine {
             }
 
             for (_, val) in &page {
-                if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(val) {
+                if let Ok(unit) = crate::migration::decode_memory_unit(val) {
                     if unit.level >= 2
                         && Self::is_local_domain(&unit.domain)
                         && self.is_visible_memory_unit(&unit).unwrap_or(false)
@@ -379,10 +592,10 @@ impl super::MemoroseEngine {
             }
 
             for (key, val) in &page {
-                let Ok(unit) = serde_json::from_slice::<MemoryUnit>(val) else {
+                let Ok(unit) = crate::migration::decode_memory_unit(val) else {
                     continue;
                 };
-                if unit.level == 1 && l2_referenced_l1_ids.contains(&unit.id) {
+                if unit.pinned || (unit.level == 1 && l2_referenced_l1_ids.contains(&unit.id)) {
                     continue;
                 }
                 if unit.importance < threshold {
@@ -399,7 +612,7 @@ impl super::MemoroseEngine {
         Ok(total_pruned)
     }
 
-    async fn delete_pruned_units(
+    pub(super) async fn delete_pruned_units(
         &self,
         user_id: &str,
         to_prune: &mut Vec<(Vec<u8>, MemoryUnit)>,
@@ -447,6 +660,19 @@ impl super::MemoroseEngine {
                         e
                     );
                 }
+                if let Err(e) = vector
+                    .delete_by_id(
+                        crate::storage::vector::ASSET_VECTOR_TABLE,
+                        &unit.id.to_string(),
+                    )
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to delete asset vectors of unit {} during pruning: {:?}",
+                        unit.id,
+                        e
+                    );
+                }
             }
         }
 
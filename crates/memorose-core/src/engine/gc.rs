@@ -0,0 +1,316 @@
+use super::types::{OrphanGcOptions, OrphanGcReport};
+use anyhow::Result;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+const ORPHAN_GC_LAST_REPORT_KEY: &[u8] = b"gc:last_report";
+const DEDUP_PREFIX: &[u8] = b"dedup:";
+/// Keys read per page while sweeping a key family.
+const GC_PAGE_SIZE: usize = 1000;
+/// Edges younger than this may belong to a unit that is still being written.
+const EDGE_GRACE_SECS: i64 = 300;
+/// How long a semantic dedup fingerprint suppresses re-compression of the same batch.
+pub(crate) const SEMANTIC_DEDUP_WINDOW_SECS: i64 = 3600;
+
+/// The unit a key points at. Some families only carry the unit id, so the owner has to be
+/// looked up through `idx:unit:`.
+enum UnitRef {
+    Owned(String, Uuid),
+    ById(Uuid),
+}
+
+/// A family of KV entries that exist only for the unit they reference.
+struct UnitKeyFamily {
+    name: &'static str,
+    prefix: &'static str,
+    parse: fn(&str, &[u8]) -> Option<UnitRef>,
+}
+
+/// `{user_id}:{unit_id}`
+fn parse_user_unit(rest: &str, _value: &[u8]) -> Option<UnitRef> {
+    let (user_id, id) = rest.rsplit_once(':')?;
+    Some(UnitRef::Owned(
+        user_id.to_string(),
+        Uuid::parse_str(id).ok()?,
+    ))
+}
+
+/// `{user_id}:{stream_id}:{unit_id}`
+fn parse_goal_index(rest: &str, _value: &[u8]) -> Option<UnitRef> {
+    let (scope, id) = rest.rsplit_once(':')?;
+    let (user_id, _stream_id) = scope.rsplit_once(':')?;
+    Some(UnitRef::Owned(
+        user_id.to_string(),
+        Uuid::parse_str(id).ok()?,
+    ))
+}
+
+/// `{due_at_micros}:{user_id}:{unit_id}`
+fn parse_task_due(rest: &str, value: &[u8]) -> Option<UnitRef> {
+    let (_due_at, rest) = rest.split_once(':')?;
+    parse_user_unit(rest, value)
+}
+
+/// `{unit_id}` with the owning user as the value.
+fn parse_unit_index(rest: &str, value: &[u8]) -> Option<UnitRef> {
+    Some(UnitRef::Owned(
+        String::from_utf8_lossy(value).to_string(),
+        Uuid::parse_str(rest).ok()?,
+    ))
+}
+
+/// `{unit_id}`
+fn parse_unit_id(rest: &str, _value: &[u8]) -> Option<UnitRef> {
+    Some(UnitRef::ById(Uuid::parse_str(rest).ok()?))
+}
+
+/// `idx:unit:` goes first so families keyed by bare unit id resolve against a clean index.
+const UNIT_KEY_FAMILIES: &[UnitKeyFamily] = &[
+    UnitKeyFamily {
+        name: "idx:unit",
+        prefix: "idx:unit:",
+        parse: parse_unit_index,
+    },
+    UnitKeyFamily {
+        name: "l1_idx",
+        prefix: "l1_idx:",
+        parse: parse_user_unit,
+    },
+    UnitKeyFamily {
+        name: "l3_idx",
+        prefix: "l3_idx:",
+        parse: parse_goal_index,
+    },
+    UnitKeyFamily {
+        name: "task_due",
+        prefix: "task_due:",
+        parse: parse_task_due,
+    },
+    UnitKeyFamily {
+        name: "task_due_notified",
+        prefix: "task_due_notified:",
+        parse: parse_unit_id,
+    },
+    UnitKeyFamily {
+        name: "link_queue",
+        prefix: "link_queue:",
+        parse: parse_user_unit,
+    },
+    UnitKeyFamily {
+        name: "trash",
+        prefix: "trash:",
+        parse: parse_user_unit,
+    },
+    UnitKeyFamily {
+        name: "materialize:hooks",
+        prefix: "materialize:hooks:",
+        parse: parse_unit_id,
+    },
+];
+
+impl super::MemoroseEngine {
+    /// Sweep every store for references to memory units that no longer exist: secondary
+    /// index and queue entries, expired dedup fingerprints and, with
+    /// `options.include_edges`, graph edges. A dry run only counts what would be removed.
+    ///
+    /// The report is kept for [`Self::last_orphan_gc_report`].
+    pub async fn run_orphan_gc(&self, options: OrphanGcOptions) -> Result<OrphanGcReport> {
+        let mut report = OrphanGcReport {
+            started_at: Some(chrono::Utc::now()),
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+
+        for family in UNIT_KEY_FAMILIES {
+            self.sweep_unit_key_family(family, options, &mut report)
+                .await?;
+        }
+        self.sweep_expired_dedup_fingerprints(options, &mut report)
+            .await?;
+        self.sweep_orphaned_edges(options, &mut report).await?;
+
+        report.completed_at = Some(chrono::Utc::now());
+        self.system_kv()
+            .put(ORPHAN_GC_LAST_REPORT_KEY, &serde_json::to_vec(&report)?)?;
+        Ok(report)
+    }
+
+    /// The most recent orphan garbage-collection sweep.
+    pub fn last_orphan_gc_report(&self) -> Result<Option<OrphanGcReport>> {
+        self.system_kv()
+            .get(ORPHAN_GC_LAST_REPORT_KEY)?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    fn unit_record_exists(&self, user_id: &str, unit_id: Uuid) -> Result<bool> {
+        Ok(self
+            .kv_store
+            .get(format!("u:{}:unit:{}", user_id, unit_id).as_bytes())?
+            .is_some())
+    }
+
+    fn unit_ref_exists(&self, unit: &UnitRef) -> Result<bool> {
+        match unit {
+            UnitRef::Owned(user_id, unit_id) => self.unit_record_exists(user_id, *unit_id),
+            UnitRef::ById(unit_id) => {
+                match self
+                    .kv_store
+                    .get(format!("idx:unit:{}", unit_id).as_bytes())?
+                {
+                    Some(user) => {
+                        self.unit_record_exists(&String::from_utf8_lossy(&user), *unit_id)
+                    }
+                    None => Ok(false),
+                }
+            }
+        }
+    }
+
+    async fn scan_gc_page(
+        &self,
+        prefix: &'static [u8],
+        after: Option<Vec<u8>>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let kv = self.kv_store.clone();
+        tokio::task::spawn_blocking(move || {
+            kv.scan_prefix_after(prefix, after.as_deref(), GC_PAGE_SIZE)
+        })
+        .await?
+    }
+
+    async fn delete_gc_keys(&self, keys: Vec<Vec<u8>>) -> Result<()> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let kv = self.kv_store.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut batch = rocksdb::WriteBatch::default();
+            for key in &keys {
+                batch.delete(key);
+            }
+            kv.write_batch(batch)
+        })
+        .await?
+    }
+
+    async fn sweep_unit_key_family(
+        &self,
+        family: &UnitKeyFamily,
+        options: OrphanGcOptions,
+        report: &mut OrphanGcReport,
+    ) -> Result<()> {
+        let mut after = None;
+        loop {
+            let page = self.scan_gc_page(family.prefix.as_bytes(), after).await?;
+            let mut dangling = Vec::new();
+            for (key, value) in &page {
+                report.scanned_keys += 1;
+                let Some(unit) = std::str::from_utf8(&key[family.prefix.len()..])
+                    .ok()
+                    .and_then(|rest| (family.parse)(rest, value))
+                else {
+                    continue;
+                };
+                if !self.unit_ref_exists(&unit)? {
+                    dangling.push(key.clone());
+                }
+            }
+            if !dangling.is_empty() {
+                *report
+                    .dangling_keys
+                    .entry(family.name.to_string())
+                    .or_default() += dangling.len();
+                if !options.dry_run {
+                    report.removed += dangling.len();
+                    self.delete_gc_keys(dangling).await?;
+                }
+            }
+            if page.len() < GC_PAGE_SIZE {
+                return Ok(());
+            }
+            after = page.last().map(|(key, _)| key.clone());
+        }
+    }
+
+    async fn sweep_expired_dedup_fingerprints(
+        &self,
+        options: OrphanGcOptions,
+        report: &mut OrphanGcReport,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut after = None;
+        loop {
+            let page = self.scan_gc_page(DEDUP_PREFIX, after).await?;
+            let mut expired = Vec::new();
+            for (key, value) in &page {
+                report.scanned_keys += 1;
+                let last_seen = std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok());
+                // Unparseable values can never suppress a batch either.
+                if last_seen.is_none_or(|ts| now.saturating_sub(ts) >= SEMANTIC_DEDUP_WINDOW_SECS) {
+                    expired.push(key.clone());
+                }
+            }
+            report.expired_dedup_fingerprints += expired.len();
+            if !options.dry_run {
+                report.removed += expired.len();
+                self.delete_gc_keys(expired).await?;
+            }
+            if page.len() < GC_PAGE_SIZE {
+                return Ok(());
+            }
+            after = page.last().map(|(key, _)| key.clone());
+        }
+    }
+
+    async fn sweep_orphaned_edges(
+        &self,
+        options: OrphanGcOptions,
+        report: &mut OrphanGcReport,
+    ) -> Result<()> {
+        let settled_before = chrono::Utc::now() - chrono::Duration::seconds(EDGE_GRACE_SECS);
+        let mut orphan_nodes: BTreeSet<(String, Uuid)> = BTreeSet::new();
+        for edge in self.graph.scan_all_edges().await? {
+            if edge.transaction_time >= settled_before {
+                continue;
+            }
+            let mut orphaned = false;
+            for node in [edge.source_id, edge.target_id] {
+                let node_key = (edge.user_id.clone(), node);
+                if orphan_nodes.contains(&node_key) {
+                    orphaned = true;
+                } else if !self.unit_record_exists(&edge.user_id, node)? {
+                    orphan_nodes.insert(node_key);
+                    orphaned = true;
+                }
+            }
+            if orphaned {
+                report.orphaned_edges += 1;
+            }
+        }
+
+        if options.dry_run || !options.include_edges {
+            return Ok(());
+        }
+        for (user_id, node) in orphan_nodes {
+            match self.graph.delete_edges_for_node(&user_id, node).await {
+                Ok(removed) => {
+                    report.removed += removed;
+                    self.mark_community_nodes_dirty(&user_id, &[node])?;
+                    self.invalidate_graph_cache(&user_id, &[node]).await;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to delete orphaned edges of {}:{}: {:?}",
+                        user_id,
+                        node,
+                        e
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
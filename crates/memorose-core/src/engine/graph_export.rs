@@ -0,0 +1,129 @@
+use super::types::{GraphExport, GraphExportEdge, GraphExportFilter, GraphExportNode};
+use anyhow::Result;
+use memorose_common::{GraphEdge, MemoryUnit};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Characters of content kept as a node's label.
+const GRAPH_EXPORT_LABEL_CHARS: usize = 80;
+
+impl super::MemoroseEngine {
+    // ── Graph export ────────────────────────────────────────────────
+
+    /// The user's memory graph, narrowed by `filter`. Nodes are the memories the kept
+    /// edges connect; edges to forgotten or filtered-out memories are dropped.
+    pub async fn export_graph(
+        &self,
+        user_id: &str,
+        filter: &GraphExportFilter,
+    ) -> Result<GraphExport> {
+        let community_of: HashMap<Uuid, Uuid> = self
+            .get_community_snapshot(user_id)?
+            .map(|snapshot| {
+                snapshot
+                    .communities
+                    .iter()
+                    .flat_map(|community| {
+                        community
+                            .member_ids
+                            .iter()
+                            .chain(community.summary_id.iter())
+                            .map(|id| (*id, community.community_id))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut edges: Vec<GraphEdge> = self
+            .graph
+            .get_all_edges_for_user(user_id)
+            .await?
+            .into_iter()
+            .filter(|edge| {
+                (filter.relations.is_empty() || filter.relations.contains(&edge.relation))
+                    && filter.min_weight.is_none_or(|min| edge.weight >= min)
+            })
+            .collect();
+        if let Some(community_id) = filter.community_id {
+            let in_community = |id: &Uuid| community_of.get(id) == Some(&community_id);
+            edges.retain(|edge| in_community(&edge.source_id) && in_community(&edge.target_id));
+        }
+
+        let node_ids: HashSet<Uuid> = edges
+            .iter()
+            .flat_map(|edge| [edge.source_id, edge.target_id])
+            .collect();
+        let mut nodes: HashMap<Uuid, GraphExportNode> = self
+            .fetch_units(user_id, node_ids.iter().map(Uuid::to_string).collect())
+            .await?
+            .into_iter()
+            .filter(|unit| filter.levels.is_empty() || filter.levels.contains(&unit.level))
+            .map(|unit| {
+                (
+                    unit.id,
+                    export_node(&unit, community_of.get(&unit.id).copied()),
+                )
+            })
+            .collect();
+
+        edges.retain(|edge| {
+            nodes.contains_key(&edge.source_id) && nodes.contains_key(&edge.target_id)
+        });
+        edges.sort_by(|a, b| {
+            b.weight
+                .total_cmp(&a.weight)
+                .then(a.source_id.cmp(&b.source_id))
+                .then(a.target_id.cmp(&b.target_id))
+        });
+        if filter.max_edges > 0 {
+            edges.truncate(filter.max_edges);
+        }
+        let connected: HashSet<Uuid> = edges
+            .iter()
+            .flat_map(|edge| [edge.source_id, edge.target_id])
+            .collect();
+        nodes.retain(|id, _| connected.contains(id));
+
+        let mut nodes: Vec<GraphExportNode> = nodes.into_values().collect();
+        nodes.sort_by(|a, b| {
+            a.transaction_time
+                .cmp(&b.transaction_time)
+                .then(a.id.cmp(&b.id))
+        });
+        Ok(GraphExport {
+            user_id: user_id.to_string(),
+            exported_at: chrono::Utc::now(),
+            nodes,
+            edges: edges
+                .into_iter()
+                .map(|edge| GraphExportEdge {
+                    source: edge.source_id,
+                    target: edge.target_id,
+                    relation: edge.relation,
+                    weight: edge.weight,
+                })
+                .collect(),
+        })
+    }
+}
+
+fn export_node(unit: &MemoryUnit, community_id: Option<Uuid>) -> GraphExportNode {
+    let content = unit
+        .content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let label = match content.char_indices().nth(GRAPH_EXPORT_LABEL_CHARS) {
+        Some((end, _)) => format!("{}…", &content[..end]),
+        None => content,
+    };
+    GraphExportNode {
+        id: unit.id,
+        label,
+        level: unit.level,
+        memory_type: unit.memory_type.clone(),
+        importance: unit.importance,
+        community_id,
+        transaction_time: unit.transaction_time,
+    }
+}
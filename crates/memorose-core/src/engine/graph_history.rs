@@ -0,0 +1,116 @@
+use super::types::{GraphDiff, GraphEdgeWeightChange};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::GraphEdge;
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+/// A version of an edge and when it stopped being current, if it has.
+type EdgeVersion = (GraphEdge, Option<DateTime<Utc>>);
+
+impl super::MemoroseEngine {
+    // ── Graph history ───────────────────────────────────────────────
+
+    /// How the user's graph changed between `from` and `to`. Both ends are rebuilt from
+    /// edge transaction times and the log of removed edge versions, so removals made
+    /// before the log existed are not seen.
+    pub async fn graph_diff(
+        &self,
+        user_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<GraphDiff> {
+        let mut versions: Vec<EdgeVersion> = self
+            .graph
+            .get_all_edges_for_user(user_id)
+            .await?
+            .into_iter()
+            .map(|edge| (edge, None))
+            .collect();
+        versions.extend(
+            self.graph
+                .removed_edges_since(user_id, from)?
+                .into_iter()
+                .map(|removed| (removed.edge, Some(removed.removed_at))),
+        );
+
+        let before = graph_at(&versions, from);
+        let after = graph_at(&versions, to);
+
+        let mut edges_added: Vec<GraphEdge> = after
+            .iter()
+            .filter(|(key, _)| !before.contains_key(*key))
+            .map(|(_, edge)| (*edge).clone())
+            .collect();
+        edges_added.sort_by_key(|edge| edge.transaction_time);
+        let mut edges_removed: Vec<GraphEdge> = before
+            .iter()
+            .filter(|(key, _)| !after.contains_key(*key))
+            .map(|(_, edge)| (*edge).clone())
+            .collect();
+        edges_removed.sort_by_key(|edge| edge.transaction_time);
+        let mut weight_changes: Vec<GraphEdgeWeightChange> = after
+            .iter()
+            .filter_map(|(key, edge)| {
+                let previous = before.get(key)?;
+                (previous.weight != edge.weight).then(|| GraphEdgeWeightChange {
+                    edge: (*edge).clone(),
+                    previous_weight: previous.weight,
+                })
+            })
+            .collect();
+        weight_changes.sort_by_key(|change| change.edge.transaction_time);
+
+        let nodes_before = endpoints(&before);
+        let nodes_after = endpoints(&after);
+        Ok(GraphDiff {
+            user_id: user_id.to_string(),
+            from,
+            to,
+            edges_added,
+            edges_removed,
+            weight_changes,
+            nodes_introduced: nodes_after.difference(&nodes_before).copied().collect(),
+            nodes_removed: nodes_before.difference(&nodes_after).copied().collect(),
+        })
+    }
+}
+
+fn edge_key(edge: &GraphEdge) -> (String, Uuid, Uuid, String) {
+    (
+        edge.namespace_key.clone(),
+        edge.source_id,
+        edge.target_id,
+        edge.relation.as_str().to_string(),
+    )
+}
+
+/// The current version of every edge as of `at`: written by then and not yet removed.
+fn graph_at(
+    versions: &[EdgeVersion],
+    at: DateTime<Utc>,
+) -> HashMap<(String, Uuid, Uuid, String), &GraphEdge> {
+    let mut graph: HashMap<_, &GraphEdge> = HashMap::new();
+    for (edge, removed_at) in versions {
+        let alive = edge.transaction_time <= at && removed_at.is_none_or(|removed| removed > at);
+        if !alive {
+            continue;
+        }
+        graph
+            .entry(edge_key(edge))
+            .and_modify(|current| {
+                if edge.transaction_time > current.transaction_time {
+                    *current = edge;
+                }
+            })
+            .or_insert(edge);
+    }
+    graph
+}
+
+fn endpoints<K>(graph: &HashMap<K, &GraphEdge>) -> BTreeSet<Uuid> {
+    graph
+        .values()
+        .flat_map(|edge| [edge.source_id, edge.target_id])
+        .collect()
+}
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 use memorose_common::tokenizer::count_tokens;
-use memorose_common::{MemoryDomain, MemoryUnit, SharePolicy, ShareTarget, TimeRange};
+use memorose_common::{GeoRadius, MemoryDomain, MemoryUnit, SharePolicy, ShareTarget, TimeRange};
 
 /// Escape a string value for use in LanceDB SQL filter expressions.
 pub(crate) fn escape_sql_string(s: &str) -> String {
@@ -61,6 +61,16 @@ impl super::MemoroseEngine {
         }
     }
 
+    /// Bounding-box prefilter over the `lat`/`lon` columns; callers check the exact
+    /// distance afterwards.
+    pub(crate) fn build_geo_filter(&self, near: &GeoRadius) -> String {
+        let (min_lat, max_lat, min_lon, max_lon) = near.bounding_box();
+        format!(
+            "lat >= {} AND lat <= {} AND lon >= {} AND lon <= {}",
+            min_lat, max_lat, min_lon, max_lon
+        )
+    }
+
     pub fn build_user_filter(&self, user_id: &str, extra: Option<String>) -> Option<String> {
         let mut conditions = vec![format!("user_id = '{}'", escape_sql_string(user_id))];
         if let Some(e) = extra {
@@ -1,6 +1,8 @@
 use super::helpers::validate_id;
+use super::types::IngestAdmission;
 use anyhow::Result;
 use memorose_common::Event;
+use std::collections::HashMap;
 
 impl super::MemoroseEngine {
     pub async fn ingest_event(&self, event: Event) -> Result<()> {
@@ -38,7 +40,6 @@ impl super::MemoroseEngine {
             return Ok(());
         }
 
-        let mut batch = rocksdb::WriteBatch::default();
         for event in &events {
             Self::validate_event_not_empty(event)?;
             validate_id(&event.user_id)?;
@@ -48,11 +49,30 @@ impl super::MemoroseEngine {
             if let Some(ref agent_id) = event.agent_id {
                 validate_id(agent_id)?;
             }
+        }
+        self.check_org_event_quota(&events)?;
 
+        let mut sealing_keys: HashMap<&str, Option<String>> = HashMap::new();
+        for event in &events {
+            if !sealing_keys.contains_key(event.user_id.as_str()) {
+                sealing_keys.insert(&event.user_id, self.sealing_key(&event.user_id)?);
+            }
+        }
+
+        let mut batch = rocksdb::WriteBatch::default();
+        let active_at = chrono::Utc::now().timestamp_millis().to_string();
+        for event in &events {
             let event_id = event.id.to_string();
             let user_id = event.user_id.clone();
             let key = format!("u:{}:event:{}", user_id, event_id);
-            let val = serde_json::to_vec(event)?;
+            let val = match &sealing_keys[user_id.as_str()] {
+                Some(key_id) => {
+                    let mut sealed = event.clone();
+                    crate::crypto::seal_event(&mut sealed, key_id)?;
+                    serde_json::to_vec(&sealed)?
+                }
+                None => serde_json::to_vec(event)?,
+            };
             batch.put(key.as_bytes(), &val);
 
             let pending_key = format!("pending:{}", event_id);
@@ -61,8 +81,15 @@ impl super::MemoroseEngine {
             }))?;
             batch.put(pending_key.as_bytes(), &pending_val);
 
+            // The marker value records when the user was last active (ms), so warm-up can
+            // prefer the hottest users.
             let active_key = format!("active_user:{}", event.user_id);
-            batch.put(active_key.as_bytes(), []);
+            batch.put(active_key.as_bytes(), active_at.as_bytes());
+
+            if let Some(ref org_id) = event.org_id {
+                batch.put(Self::org_event_key(org_id, &event_id).as_bytes(), []);
+                batch.put(Self::org_user_key(org_id, &event.user_id).as_bytes(), []);
+            }
         }
 
         self.kv_store.write_batch(batch)?;
@@ -80,6 +107,55 @@ impl super::MemoroseEngine {
         tokio::task::spawn_blocking(move || skv.count_prefix(b"pending:")).await?
     }
 
+    /// Pending-queue depth, sampled at most once per `admission.sample_interval_ms`.
+    pub async fn pending_events_gauge(&self) -> Result<usize> {
+        let ttl = std::time::Duration::from_millis(self.admission.sample_interval_ms);
+        let mut gauge = self.pending_gauge.lock().await;
+        if let Some((sampled_at, count)) = *gauge {
+            if sampled_at.elapsed() < ttl {
+                return Ok(count);
+            }
+        }
+        let count = self.count_pending_events().await?;
+        *gauge = Some((std::time::Instant::now(), count));
+        Ok(count)
+    }
+
+    pub fn admission_config(&self) -> &memorose_common::config::AdmissionConfig {
+        &self.admission
+    }
+
+    pub fn admission_for(
+        config: &memorose_common::config::AdmissionConfig,
+        pending: usize,
+    ) -> IngestAdmission {
+        let retry_after_secs = config.retry_after_secs;
+        if config.reject_pending > 0 && pending >= config.reject_pending {
+            IngestAdmission::Reject { retry_after_secs }
+        } else if config.throttle_pending > 0 && pending >= config.throttle_pending {
+            IngestAdmission::Throttle { retry_after_secs }
+        } else {
+            IngestAdmission::Accept
+        }
+    }
+
+    pub async fn check_ingest_admission(&self) -> Result<IngestAdmission> {
+        let pending = self.pending_events_gauge().await?;
+        Ok(Self::admission_for(&self.admission, pending))
+    }
+
+    /// Whether publish-time linking should be skipped to let consolidation catch up.
+    pub(crate) async fn should_shed_linking(&self) -> bool {
+        let threshold = self.admission.shed_linking_pending;
+        if threshold == 0 {
+            return false;
+        }
+        match self.pending_events_gauge().await {
+            Ok(pending) => pending >= threshold,
+            Err(_) => false,
+        }
+    }
+
     pub async fn fetch_pending_events_limited(&self, limit: usize) -> Result<Vec<Event>> {
         if limit == 0 {
             return Ok(Vec::new());
@@ -130,13 +206,22 @@ impl super::MemoroseEngine {
                     ));
                     continue;
                 };
-                if let Some(event) = self.get_event(&user_id, event_id).await? {
-                    events.push(event);
-                } else {
-                    invalid_pending_entries.push((
+                match self.get_event(&user_id, event_id).await {
+                    Ok(Some(event)) => events.push(event),
+                    Ok(None) => invalid_pending_entries.push((
                         event_id.to_string(),
                         format!("Pending entry missing source event for user {}", user_id),
-                    ));
+                    )),
+                    Err(e) => match e.downcast_ref::<crate::crypto::KeyUnavailable>() {
+                        Some(locked) if self.is_key_shredded(&user_id, &locked.key_id)? => {
+                            invalid_pending_entries.push((event_id.to_string(), e.to_string()))
+                        }
+                        Some(_) => tracing::warn!(
+                            "Leaving event {} pending until its encryption key unlocks",
+                            event_id
+                        ),
+                        None => return Err(e),
+                    },
                 }
             }
         }
@@ -216,7 +301,11 @@ impl super::MemoroseEngine {
         let key = format!("u:{}:event:{}", user_id, id);
         let val = self.kv_store.get(key.as_bytes())?;
         match val {
-            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Some(bytes) => {
+                let mut event: Event = serde_json::from_slice(&bytes)?;
+                crate::crypto::open_event(&mut event)?;
+                Ok(Some(event))
+            }
             None => Ok(None),
         }
     }
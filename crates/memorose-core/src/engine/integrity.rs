@@ -0,0 +1,302 @@
+use super::types::{IntegrityCheckOptions, IntegrityReport};
+use anyhow::Result;
+use memorose_common::{MaterializationState, MemoryUnit};
+use std::collections::{BTreeSet, HashSet};
+use uuid::Uuid;
+
+const UNIT_INDEX_PREFIX: &[u8] = b"idx:unit:";
+const INTEGRITY_CURSOR_KEY: &[u8] = b"integrity:cursor";
+const INTEGRITY_PASS_KEY: &[u8] = b"integrity:pass";
+const INTEGRITY_LAST_REPORT_KEY: &[u8] = b"integrity:last_report";
+const SUSPECT_VECTOR_PREFIX: &str = "integrity:suspect:vector:";
+const SUSPECT_EDGE_NODE_PREFIX: &str = "integrity:suspect:edge_node:";
+/// Default age before a unit is checked: younger units may still be mid-write.
+pub(crate) const INTEGRITY_SETTLE_SECS: i64 = 300;
+/// Ids per `id IN (...)` probe against the vector table.
+const VECTOR_PROBE_CHUNK: usize = 256;
+
+impl super::MemoroseEngine {
+    /// Verify the next `options.batch_size` units against the vector table and the text
+    /// index, re-indexing the ones that are missing. When the batch reaches the end of the
+    /// unit index the pass completes: vectors and edges that point at no stored unit are
+    /// counted, and deleted once they have been seen in two consecutive passes (so a write
+    /// in flight between stores is never mistaken for an orphan).
+    ///
+    /// Returns the report of the pass so far; completed passes are kept for
+    /// [`Self::last_integrity_report`].
+    pub async fn run_integrity_batch(
+        &self,
+        options: IntegrityCheckOptions,
+    ) -> Result<IntegrityReport> {
+        let system_kv = self.system_kv();
+        let mut pass: IntegrityReport = match system_kv.get(INTEGRITY_PASS_KEY)? {
+            Some(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            None => IntegrityReport::default(),
+        };
+        pass.started_at.get_or_insert_with(chrono::Utc::now);
+
+        let cursor = system_kv.get(INTEGRITY_CURSOR_KEY)?;
+        let kv = self.kv_store.clone();
+        let batch_size = options.batch_size.max(1);
+        let page = tokio::task::spawn_blocking(move || {
+            kv.scan_prefix_after(UNIT_INDEX_PREFIX, cursor.as_deref(), batch_size)
+        })
+        .await??;
+
+        let settled_before = chrono::Utc::now() - chrono::Duration::seconds(options.settle_secs);
+        let mut candidates = Vec::new();
+        for (key, user_bytes) in &page {
+            pass.scanned_units += 1;
+            let Some(unit_id) = std::str::from_utf8(&key[UNIT_INDEX_PREFIX.len()..])
+                .ok()
+                .and_then(|id| Uuid::parse_str(id).ok())
+            else {
+                continue;
+            };
+            let user_id = String::from_utf8_lossy(user_bytes).to_string();
+            match self.get_memory_unit_raw(&user_id, unit_id)? {
+                Some(unit) => {
+                    if unit.materialization_state == MaterializationState::Published
+                        && unit.transaction_time < settled_before
+                    {
+                        candidates.push(unit);
+                    }
+                }
+                None => {
+                    pass.dangling_unit_index += 1;
+                    if options.repair {
+                        self.kv_store.delete(key)?;
+                        pass.repaired += 1;
+                    }
+                }
+            }
+        }
+
+        let mut needs_reindex: BTreeSet<Uuid> = BTreeSet::new();
+        for id in self.units_missing_vectors(&candidates).await? {
+            pass.missing_vectors += 1;
+            needs_reindex.insert(id);
+        }
+        let index = self.index.clone();
+        let ids: Vec<(Uuid, String)> = candidates
+            .iter()
+            .map(|unit| (unit.id, unit.id.to_string()))
+            .collect();
+        let missing_text = tokio::task::spawn_blocking(move || {
+            let mut missing = Vec::new();
+            for (id, id_str) in ids {
+                if !index.contains_unit(&id_str)? {
+                    missing.push(id);
+                }
+            }
+            Ok::<_, anyhow::Error>(missing)
+        })
+        .await??;
+        pass.missing_text_index += missing_text.len();
+        needs_reindex.extend(missing_text);
+
+        if options.repair {
+            for unit in candidates.iter().filter(|u| needs_reindex.contains(&u.id)) {
+                match self.write_materialized_search_storage(unit).await {
+                    Ok(()) => pass.repaired += 1,
+                    Err(e) => {
+                        pass.repair_failures += 1;
+                        tracing::warn!("Failed to re-index unit {}: {:?}", unit.id, e);
+                    }
+                }
+            }
+        }
+
+        if page.len() < batch_size {
+            self.scan_orphaned_vectors(&mut pass, options).await?;
+            self.scan_orphaned_edges(&mut pass, options).await?;
+            pass.completed_at = Some(chrono::Utc::now());
+            system_kv.put(INTEGRITY_LAST_REPORT_KEY, &serde_json::to_vec(&pass)?)?;
+            system_kv.delete(INTEGRITY_PASS_KEY)?;
+            system_kv.delete(INTEGRITY_CURSOR_KEY)?;
+        } else {
+            if let Some((last_key, _)) = page.last() {
+                system_kv.put(INTEGRITY_CURSOR_KEY, last_key)?;
+            }
+            system_kv.put(INTEGRITY_PASS_KEY, &serde_json::to_vec(&pass)?)?;
+        }
+
+        Ok(pass)
+    }
+
+    /// The most recent completed consistency pass.
+    pub fn last_integrity_report(&self) -> Result<Option<IntegrityReport>> {
+        self.system_kv()
+            .get(INTEGRITY_LAST_REPORT_KEY)?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// The consistency pass currently being accumulated, if one is underway.
+    pub fn integrity_pass_in_progress(&self) -> Result<Option<IntegrityReport>> {
+        self.system_kv()
+            .get(INTEGRITY_PASS_KEY)?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn units_missing_vectors(&self, units: &[MemoryUnit]) -> Result<Vec<Uuid>> {
+        let Some(vector) = &self.vector else {
+            return Ok(Vec::new());
+        };
+        let embedded: Vec<&MemoryUnit> = units.iter().filter(|u| u.embedding.is_some()).collect();
+        let mut missing = Vec::new();
+        for chunk in embedded.chunks(VECTOR_PROBE_CHUNK) {
+            let filter = format!(
+                "id IN ({})",
+                chunk
+                    .iter()
+                    .map(|u| format!("'{}'", u.id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let present: HashSet<String> = vector
+                .scan_ids("memories", Some(filter))
+                .await?
+                .into_iter()
+                .collect();
+            missing.extend(
+                chunk
+                    .iter()
+                    .filter(|u| !present.contains(&u.id.to_string()))
+                    .map(|u| u.id),
+            );
+        }
+        Ok(missing)
+    }
+
+    fn unit_index_exists(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .kv_store
+            .get(format!("idx:unit:{}", id).as_bytes())?
+            .is_some())
+    }
+
+    /// Record `current` as this pass's suspects and return the ones that were already
+    /// suspect in the previous pass. Suspects not seen again are dropped.
+    fn confirm_suspects(
+        &self,
+        prefix: &str,
+        current: &BTreeSet<String>,
+    ) -> Result<BTreeSet<String>> {
+        let system_kv = self.system_kv();
+        let mut confirmed = BTreeSet::new();
+        for (key, _) in system_kv.scan(prefix.as_bytes())? {
+            let suspect = String::from_utf8_lossy(&key[prefix.len()..]).to_string();
+            if current.contains(&suspect) {
+                confirmed.insert(suspect);
+            } else {
+                system_kv.delete(&key)?;
+            }
+        }
+        for suspect in current {
+            system_kv.put(format!("{}{}", prefix, suspect).as_bytes(), b"")?;
+        }
+        Ok(confirmed)
+    }
+
+    fn clear_suspect(&self, prefix: &str, suspect: &str) -> Result<()> {
+        self.system_kv()
+            .delete(format!("{}{}", prefix, suspect).as_bytes())
+    }
+
+    async fn scan_orphaned_vectors(
+        &self,
+        pass: &mut IntegrityReport,
+        options: IntegrityCheckOptions,
+    ) -> Result<()> {
+        let Some(vector) = &self.vector else {
+            return Ok(());
+        };
+        let tables = ["memories", crate::storage::vector::ASSET_VECTOR_TABLE];
+        let mut orphans = BTreeSet::new();
+        for table in tables {
+            for id in vector.scan_ids(table, None).await? {
+                if !orphans.contains(&id) && !self.unit_index_exists(&id)? {
+                    orphans.insert(id);
+                }
+            }
+        }
+        pass.orphaned_vectors += orphans.len();
+
+        let confirmed = self.confirm_suspects(SUSPECT_VECTOR_PREFIX, &orphans)?;
+        if !options.repair {
+            return Ok(());
+        }
+        for id in confirmed {
+            let mut deleted = true;
+            for table in tables {
+                if let Err(e) = vector.delete_by_id(table, &id).await {
+                    deleted = false;
+                    tracing::warn!(
+                        "Failed to delete orphaned vector {} from {}: {:?}",
+                        id,
+                        table,
+                        e
+                    );
+                }
+            }
+            if deleted {
+                pass.repaired += 1;
+                self.clear_suspect(SUSPECT_VECTOR_PREFIX, &id)?;
+            } else {
+                pass.repair_failures += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn scan_orphaned_edges(
+        &self,
+        pass: &mut IntegrityReport,
+        options: IntegrityCheckOptions,
+    ) -> Result<()> {
+        let mut orphan_nodes = BTreeSet::new();
+        for edge in self.graph.scan_all_edges().await? {
+            let mut orphaned = false;
+            for node in [edge.source_id, edge.target_id] {
+                let node_key = format!("{}:{}", edge.user_id, node);
+                if orphan_nodes.contains(&node_key) {
+                    orphaned = true;
+                } else if !self.unit_index_exists(&node.to_string())? {
+                    orphan_nodes.insert(node_key);
+                    orphaned = true;
+                }
+            }
+            if orphaned {
+                pass.orphaned_edges += 1;
+            }
+        }
+
+        let confirmed = self.confirm_suspects(SUSPECT_EDGE_NODE_PREFIX, &orphan_nodes)?;
+        if !(options.repair && options.repair_orphaned_edges) {
+            return Ok(());
+        }
+        for node_key in confirmed {
+            let Some((user_id, node)) = node_key
+                .rsplit_once(':')
+                .and_then(|(user, id)| Some((user, Uuid::parse_str(id).ok()?)))
+            else {
+                continue;
+            };
+            match self.graph.delete_edges_for_node(user_id, node).await {
+                Ok(removed) => {
+                    pass.repaired += removed;
+                    self.mark_community_nodes_dirty(user_id, &[node])?;
+                    self.invalidate_graph_cache(user_id, &[node]).await;
+                    self.clear_suspect(SUSPECT_EDGE_NODE_PREFIX, &node_key)?;
+                }
+                Err(e) => {
+                    pass.repair_failures += 1;
+                    tracing::warn!("Failed to delete orphaned edges of {}: {:?}", node_key, e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use super::types::{BackgroundJob, BackgroundJobKind, JobLease, ReflectionMarker};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+/// Key prefixes of the per-user markers the job queue replaced.
+const LEGACY_MARKER_PREFIXES: [(BackgroundJobKind, &str); 3] = [
+    (BackgroundJobKind::Reflect, "needs_reflect:"),
+    (BackgroundJobKind::Community, "needs_community:"),
+    (BackgroundJobKind::Profile, "needs_profile:"),
+];
+
+impl super::MemoroseEngine {
+    // ── Background job queue ────────────────────────────────────────
+    //
+    // Jobs live in system KV and every change goes through the state machine: enqueues
+    // happen while applying the writes that create work, and the leader's worker claims
+    // and acks jobs with Raft commands. A lease belongs to the term it was claimed in, so
+    // after failover the new leader re-claims whatever its predecessor left in flight.
+
+    fn job_prefix(kind: BackgroundJobKind) -> String {
+        format!("job:{}:", kind.as_str())
+    }
+
+    fn job_key(kind: BackgroundJobKind, user_id: &str) -> String {
+        format!("job:{}:{}", kind.as_str(), user_id)
+    }
+
+    /// Read, change and write back one job record under the queue lock. Leaving `None`
+    /// in the slot deletes the job.
+    pub(crate) fn update_job<T>(
+        &self,
+        kind: BackgroundJobKind,
+        user_id: &str,
+        update: impl FnOnce(&mut Option<BackgroundJob>) -> T,
+    ) -> Result<T> {
+        let _guard = self
+            .job_queue_lock
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let key = Self::job_key(kind, user_id);
+        let mut job = self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice::<BackgroundJob>(&raw).ok());
+        let before = job.clone();
+        let result = update(&mut job);
+        if job != before {
+            match &job {
+                Some(job) => self
+                    .system_kv()
+                    .put(key.as_bytes(), &serde_json::to_vec(job)?)?,
+                None => self.system_kv().delete(key.as_bytes())?,
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn enqueue_job(&self, kind: BackgroundJobKind, user_id: &str) -> Result<()> {
+        self.enqueue_job_with(kind, user_id, |_| {})
+    }
+
+    /// Queue `kind` work for the user, or bump the generation of the job already queued,
+    /// and let `update` fold the new work into it.
+    pub(crate) fn enqueue_job_with(
+        &self,
+        kind: BackgroundJobKind,
+        user_id: &str,
+        update: impl FnOnce(&mut BackgroundJob),
+    ) -> Result<()> {
+        self.update_job(kind, user_id, |slot| {
+            let job = slot.get_or_insert_with(|| BackgroundJob {
+                kind,
+                user_id: user_id.to_string(),
+                enqueued_at: Utc::now(),
+                generation: 0,
+                attempts: 0,
+                lease: None,
+                retry_at: None,
+                last_error: None,
+                reflection: None,
+            });
+            job.generation = job.generation.wrapping_add(1);
+            update(job);
+        })
+    }
+
+    pub fn get_job(&self, kind: BackgroundJobKind, user_id: &str) -> Result<Option<BackgroundJob>> {
+        let key = Self::job_key(kind, user_id);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    /// Every queued job of `kind`, leased or not, ordered by user.
+    pub fn list_jobs(&self, kind: BackgroundJobKind) -> Result<Vec<BackgroundJob>> {
+        Ok(self
+            .system_kv()
+            .scan(Self::job_prefix(kind).as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+
+    pub fn delete_job(&self, kind: BackgroundJobKind, user_id: &str) -> Result<()> {
+        self.update_job(kind, user_id, |slot| *slot = None)
+    }
+
+    /// Lease the user's job to `holder` for leader `term` until `now + lease_for`. Returns
+    /// `None` when no job is queued, another holder's lease from this term is still
+    /// running, the job was claimed in a later term, or it is waiting out a retry backoff.
+    /// Leases from earlier terms are void. A job that has already used `max_attempts`
+    /// claims is dropped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn claim_job(
+        &self,
+        kind: BackgroundJobKind,
+        user_id: &str,
+        holder: &str,
+        term: u64,
+        lease_for: Duration,
+        max_attempts: u32,
+        now: DateTime<Utc>,
+    ) -> Result<Option<BackgroundJob>> {
+        self.update_job(kind, user_id, |slot| {
+            let job = slot.as_mut()?;
+            if job.lease.as_ref().is_some_and(|lease| {
+                lease.term > term
+                    || (lease.term == term && lease.holder != holder && lease.expires_at > now)
+            }) || job.retry_at.is_some_and(|retry_at| retry_at > now)
+            {
+                return None;
+            }
+            if job.attempts >= max_attempts.max(1) {
+                tracing::warn!(
+                    "Dropping {} job for user {} after {} attempts (last error: {})",
+                    kind.as_str(),
+                    user_id,
+                    job.attempts,
+                    job.last_error.as_deref().unwrap_or("lease expired")
+                );
+                *slot = None;
+                return None;
+            }
+            job.attempts += 1;
+            job.retry_at = None;
+            job.lease = Some(JobLease {
+                holder: holder.to_string(),
+                term,
+                claimed_at: now,
+                expires_at: now + lease_for,
+            });
+            Some(job.clone())
+        })
+    }
+
+    /// Extend `holder`'s lease on the job. Returns false when the lease was lost, in which
+    /// case the holder should stop working on it.
+    pub fn heartbeat_job(
+        &self,
+        kind: BackgroundJobKind,
+        user_id: &str,
+        holder: &str,
+        lease_for: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.update_job(kind, user_id, |slot| {
+            match slot
+                .as_mut()
+                .and_then(|job| job.lease.as_mut())
+                .filter(|lease| lease.holder == holder)
+            {
+                Some(lease) => {
+                    lease.expires_at = now + lease_for;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Finish `holder`'s claim of `claimed`. The job is removed unless more work was
+    /// enqueued after the claim, in which case it is released for the next claim. Returns
+    /// whether the job was removed.
+    pub fn complete_job(&self, claimed: &BackgroundJob, holder: &str) -> Result<bool> {
+        self.update_job(claimed.kind, &claimed.user_id, |slot| {
+            let Some(job) = slot.as_mut().filter(|job| Self::is_held_by(job, holder)) else {
+                return false;
+            };
+            if job.generation == claimed.generation {
+                *slot = None;
+                return true;
+            }
+            job.lease = None;
+            job.attempts = 0;
+            job.last_error = None;
+            false
+        })
+    }
+
+    /// Give up `holder`'s claim after an error. The job is retried after `retry_after`,
+    /// or dropped once it has used `max_attempts` claims.
+    pub fn fail_job(
+        &self,
+        claimed: &BackgroundJob,
+        holder: &str,
+        error: &str,
+        max_attempts: u32,
+        retry_after: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        self.update_job(claimed.kind, &claimed.user_id, |slot| {
+            let Some(job) = slot.as_mut().filter(|job| Self::is_held_by(job, holder)) else {
+                return;
+            };
+            if job.attempts >= max_attempts.max(1) {
+                tracing::warn!(
+                    "Dropping {} job for user {} after {} attempts: {}",
+                    claimed.kind.as_str(),
+                    claimed.user_id,
+                    job.attempts,
+                    error
+                );
+                *slot = None;
+                return;
+            }
+            job.lease = None;
+            job.retry_at = Some(now + retry_after);
+            job.last_error = Some(error.to_string());
+        })
+    }
+
+    /// Hand an unfinished claim back without counting it as an attempt, e.g. when the
+    /// cycle ran out of budget before the job was done.
+    pub fn release_job(&self, claimed: &BackgroundJob, holder: &str) -> Result<()> {
+        self.update_job(claimed.kind, &claimed.user_id, |slot| {
+            if let Some(job) = slot.as_mut().filter(|job| Self::is_held_by(job, holder)) {
+                job.lease = None;
+                job.attempts = job.attempts.saturating_sub(1);
+            }
+        })
+    }
+
+    fn is_held_by(job: &BackgroundJob, holder: &str) -> bool {
+        job.lease
+            .as_ref()
+            .is_some_and(|lease| lease.holder == holder)
+    }
+
+    /// Startup recovery for the queue: clear leases a previous standalone process left
+    /// behind, whose workers are gone, and move markers written before the queue existed
+    /// into jobs. Leases claimed through Raft (term 1 and up) are replicated state and are
+    /// left alone; they lapse once a later term claims the job. Returns how many legacy
+    /// markers were moved.
+    pub(crate) fn recover_background_jobs(&self) -> Result<usize> {
+        for kind in BackgroundJobKind::ALL {
+            for job in self.list_jobs(kind)? {
+                if job.lease.as_ref().is_some_and(|lease| lease.term == 0) {
+                    self.update_job(kind, &job.user_id, |slot| {
+                        if let Some(job) = slot.as_mut() {
+                            job.lease = None;
+                        }
+                    })?;
+                }
+            }
+        }
+
+        let mut migrated = 0;
+        for (kind, prefix) in LEGACY_MARKER_PREFIXES {
+            for (key, value) in self.system_kv().scan(prefix.as_bytes())? {
+                let key_str = String::from_utf8_lossy(&key).into_owned();
+                if let Some(user_id) = key_str.strip_prefix(prefix) {
+                    self.enqueue_job_with(kind, user_id, |job| {
+                        if kind == BackgroundJobKind::Reflect {
+                            job.reflection = Some(
+                                serde_json::from_slice::<ReflectionMarker>(&value)
+                                    .unwrap_or_default(),
+                            );
+                        }
+                    })?;
+                    migrated += 1;
+                }
+                self.system_kv().delete(&key)?;
+            }
+        }
+        Ok(migrated)
+    }
+}
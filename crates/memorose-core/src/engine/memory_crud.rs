@@ -1,4 +1,5 @@
-use super::types::SharedSearchHit;
+use super::types::{MemoryUnitFollowUp, SharedSearchHit};
+use crate::storage::vector::ASSET_VECTOR_TABLE;
 use anyhow::Result;
 use memorose_common::{tokenizer::count_tokens, GraphEdge, MemoryDomain, MemoryUnit, RelationType};
 use std::cmp::Reverse;
@@ -36,12 +37,12 @@ impl super::MemoroseEngine {
                     RelationType::IsSubTaskOf,
                     1.0,
                 );
-                self.graph.add_edge(&edge).await?;
+                self.add_graph_edge(&edge).await?;
             }
         }
 
         // Handle Auto-Planning for L3 Goals
-        if is_goal && self.auto_planner && depth < 5 {
+        if is_goal && self.auto_planner && depth < self.auto_planner_policy.max_depth {
             // Write a "pending" marker so callers can observe the in-flight planning state.
             // The task clears it to "done" or "failed" when it finishes.
             let planning_key = format!("planning:{}", unit_id);
@@ -74,10 +75,65 @@ impl super::MemoroseEngine {
         self.store_memory_units_internal(units, true).await
     }
 
+    /// Store units replicated through Raft. Only the storage writes run, so every replica
+    /// applies the entry the same way; the leader reconciles and links each unit once with
+    /// `follow_up_replicated_memory_unit` and replicates what that wrote.
+    pub async fn apply_replicated_memory_units(&self, units: Vec<MemoryUnit>) -> Result<()> {
+        self.store_memory_units_internal(units, false).await
+    }
+
+    /// Reconcile and link a unit stored by `apply_replicated_memory_units`, returning the
+    /// tombstones and edges this wrote so the caller can replicate them. Deferred linking
+    /// queues the unit for this node's linking loop instead.
+    pub async fn follow_up_replicated_memory_unit(
+        &self,
+        unit: &MemoryUnit,
+    ) -> Result<MemoryUnitFollowUp> {
+        let mut follow_up = MemoryUnitFollowUp::default();
+        if !Self::is_local_domain(&unit.domain) {
+            return Ok(follow_up);
+        }
+
+        let affected_ids = match self.reconcile_conflicting_memory_unit(unit).await {
+            Ok(affected_ids) => affected_ids,
+            Err(e) => {
+                tracing::error!("Memory reconciliation failed for unit {}: {:?}", unit.id, e);
+                Vec::new()
+            }
+        };
+        let source_id = unit.id.to_string();
+        for target_id in affected_ids {
+            if let Some(tombstone) = self.get_memory_unit_tombstone(&unit.user_id, target_id)? {
+                if tombstone.preview_id.as_deref() == Some(source_id.as_str()) {
+                    follow_up.tombstones.push(tombstone);
+                }
+            }
+        }
+
+        if self.is_visible_memory_unit(unit)? {
+            self.link_published_memory_unit(unit).await;
+        }
+
+        follow_up.edges = self
+            .graph
+            .get_outgoing_edges(&unit.user_id, unit.id)
+            .await?;
+        follow_up.edges.extend(
+            self.graph
+                .get_incoming_edges(&unit.user_id, unit.id)
+                .await?
+                .into_iter()
+                .filter(|edge| edge.source_id != unit.id),
+        );
+        Ok(follow_up)
+    }
+
+    /// `follow_up` runs reconciliation and linking after the storage writes; Raft replicas
+    /// skip it and receive the results as their own commands.
     pub(crate) async fn store_memory_units_internal(
         &self,
         units: Vec<MemoryUnit>,
-        run_reconciliation: bool,
+        follow_up: bool,
     ) -> Result<()> {
         if units.is_empty() {
             return Ok(());
@@ -92,12 +148,21 @@ impl super::MemoroseEngine {
             HashMap::new();
         for unit in &units {
             let key = format!("u:{}:unit:{}", unit.user_id, unit.id);
-            let val = serde_json::to_vec(unit)?;
+            let val = self.encode_memory_unit(unit)?;
             kv_batch.put(key.as_bytes(), &val);
 
             // Global index for dashboard lookups
             let idx_key = format!("idx:unit:{}", unit.id);
             kv_batch.put(idx_key.as_bytes(), unit.user_id.as_bytes());
+            if let Some(goal_key) = Self::goal_index_key(unit) {
+                kv_batch.put(
+                    goal_key.as_bytes(),
+                    unit.transaction_time.timestamp_micros().to_le_bytes(),
+                );
+            }
+            if let Some(due_key) = Self::task_due_index_key(unit) {
+                kv_batch.put(due_key.as_bytes(), []);
+            }
 
             if unit.level == 1 && Self::is_local_domain(&unit.domain) {
                 let tx_micros = unit.transaction_time.timestamp_micros();
@@ -196,14 +261,14 @@ impl super::MemoroseEngine {
 
         // 4. Automatic Semantic Linking (Parallelized)
         let units_for_org_publication = units.clone();
-        let mut join_set = tokio::task::JoinSet::new();
-        for unit in units {
-            let engine = self.clone();
-            join_set.spawn(async move {
-                if !Self::is_local_domain(&unit.domain) {
-                    return;
-                }
-                if run_reconciliation {
+        if follow_up {
+            let mut join_set = tokio::task::JoinSet::new();
+            for unit in units {
+                let engine = self.clone();
+                join_set.spawn(async move {
+                    if !Self::is_local_domain(&unit.domain) {
+                        return;
+                    }
                     if let Err(e) = engine.reconcile_conflicting_memory_unit(&unit).await {
                         tracing::error!(
                             "Memory reconciliation failed for unit {}: {:?}",
@@ -211,27 +276,26 @@ impl super::MemoroseEngine {
                             e
                         );
                     }
-                }
-                match engine.is_visible_memory_unit(&unit) {
-                    Ok(true) => {}
-                    Ok(false) => return,
-                    Err(e) => {
-                        tracing::error!("Failed to check visibility for unit {}: {:?}", unit.id, e);
-                        return;
+                    match engine.is_visible_memory_unit(&unit) {
+                        Ok(true) => {}
+                        Ok(false) => return,
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to check visibility for unit {}: {:?}",
+                                unit.id,
+                                e
+                            );
+                            return;
+                        }
                     }
-                }
-                if let Err(e) = engine.auto_link_memory(&unit).await {
-                    tracing::error!("Auto-linking failed for unit {}: {:?}", unit.id, e);
-                }
-                if let Err(e) = engine.semantic_link_memory(&unit).await {
-                    tracing::error!("Semantic linking failed for unit {}: {:?}", unit.id, e);
-                }
-            });
-        }
+                    engine.link_published_memory_unit(&unit).await;
+                });
+            }
 
-        while let Some(res) = join_set.join_next().await {
-            if let Err(e) = res {
-                tracing::error!("Parallel linking task panicked: {:?}", e);
+            while let Some(res) = join_set.join_next().await {
+                if let Err(e) = res {
+                    tracing::error!("Parallel linking task panicked: {:?}", e);
+                }
             }
         }
 
@@ -243,6 +307,31 @@ impl super::MemoroseEngine {
 
     // ── Memory Retrieval ────────────────────────────────────────────
 
+    /// Index joint text+image vectors for `unit`'s assets, replacing any indexed before.
+    pub async fn store_asset_vectors(
+        &self,
+        unit: &MemoryUnit,
+        vectors: Vec<Vec<f32>>,
+    ) -> Result<()> {
+        let Some(vector) = &self.vector else {
+            return Ok(());
+        };
+        vector.ensure_table(ASSET_VECTOR_TABLE).await?;
+        vector
+            .delete_by_id(ASSET_VECTOR_TABLE, &unit.id.to_string())
+            .await?;
+        vector
+            .add_vectors(
+                ASSET_VECTOR_TABLE,
+                vectors
+                    .into_iter()
+                    .filter(|vector| !vector.is_empty())
+                    .map(|vector| (unit.clone(), vector))
+                    .collect(),
+            )
+            .await
+    }
+
     pub(crate) fn get_memory_unit_raw(
         &self,
         user_id: &str,
@@ -251,7 +340,7 @@ impl super::MemoroseEngine {
         let key = format!("u:{}:unit:{}", user_id, id);
         let val = self.kv_store.get(key.as_bytes())?;
         match val {
-            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Some(bytes) => Ok(Some(crate::migration::decode_memory_unit(&bytes)?)),
             None => Ok(None),
         }
     }
@@ -406,7 +495,7 @@ impl super::MemoroseEngine {
 
         let results: Vec<MemoryUnit> = values
             .into_iter()
-            .filter_map(|v| v.and_then(|bytes| serde_json::from_slice::<MemoryUnit>(&bytes).ok()))
+            .filter_map(|v| v.and_then(|bytes| crate::migration::decode_memory_unit(&bytes).ok()))
             .filter(|unit: &MemoryUnit| {
                 Self::is_local_domain(&unit.domain)
                     && self.is_visible_memory_unit(unit).unwrap_or(false)
@@ -426,7 +515,7 @@ impl super::MemoroseEngine {
         let pairs = tokio::task::spawn_blocking(move || store.scan(&prefix_bytes)).await??;
         let mut results: Vec<MemoryUnit> = pairs
             .into_iter()
-            .filter_map(|(_, val)| serde_json::from_slice::<MemoryUnit>(&val).ok())
+            .filter_map(|(_, val)| crate::migration::decode_memory_unit(&val).ok())
             .filter(|u| {
                 u.level == 1
                     && Self::is_local_domain(&u.domain)
@@ -467,7 +556,7 @@ impl super::MemoroseEngine {
             let pairs = store.scan(&prefix_bytes)?;
             let count = pairs
                 .into_iter()
-                .filter_map(|(_, val)| serde_json::from_slice::<MemoryUnit>(&val).ok())
+                .filter_map(|(_, val)| crate::migration::decode_memory_unit(&val).ok())
                 .filter(|u| u.level == 1 && Self::is_local_domain(&u.domain))
                 .count();
             Ok::<usize, anyhow::Error>(count)
@@ -541,7 +630,7 @@ impl super::MemoroseEngine {
         let mut final_results = Vec::new();
         for (i, res) in db_results.into_iter().enumerate() {
             if let Some(bytes) = res {
-                if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(&bytes) {
+                if let Ok(unit) = crate::migration::decode_memory_unit(&bytes) {
                     if !self.is_visible_memory_unit(&unit)? {
                         continue;
                     }
@@ -595,7 +684,7 @@ impl super::MemoroseEngine {
         let mut units = Vec::new();
         for res in results {
             if let Some(bytes) = res {
-                if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(&bytes) {
+                if let Ok(unit) = crate::migration::decode_memory_unit(&bytes) {
                     if !self.is_visible_memory_unit(&unit)? {
                         continue;
                     }
@@ -628,7 +717,7 @@ impl super::MemoroseEngine {
             if !is_unit_key {
                 continue;
             }
-            let Ok(unit) = serde_json::from_slice::<MemoryUnit>(&val) else {
+            let Ok(unit) = crate::migration::decode_memory_unit(&val) else {
                 continue;
             };
             if unit.domain == MemoryDomain::Organization || !self.is_visible_memory_unit(&unit)? {
@@ -1,43 +1,78 @@
 mod community;
+mod consolidation_leases;
 mod correction;
+mod digest;
+mod edge_decay;
+mod encryption;
+mod experiments;
+mod export;
 mod forgetting;
+mod gc;
+mod graph_export;
+mod graph_history;
 pub(crate) mod helpers;
 mod ingest;
+mod integrity;
+mod job_queue;
 mod memory_crud;
+mod moderation;
+mod org_policy;
 mod organization;
+mod profile;
 mod query_cache;
+mod recovery;
 mod reflection;
+mod relations;
 mod search;
+mod self_eval;
+mod sharing;
 mod snapshot;
+mod streams;
 mod task;
+mod traffic;
 pub mod types;
+mod usage;
+mod worker_runs;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export public types
+pub(crate) use gc::SEMANTIC_DEDUP_WINDOW_SECS;
+pub(crate) use integrity::INTEGRITY_SETTLE_SECS;
 pub(crate) use types::ValidatedCorrectionDecision;
 pub use types::{
-    OrganizationAutomationCounterSnapshot, OrganizationKnowledgeContributionEntry,
-    OrganizationKnowledgeContributionRecord, OrganizationKnowledgeContributionStatus,
-    OrganizationKnowledgeDetailRecord, OrganizationKnowledgeMembershipEntry,
-    OrganizationKnowledgeMembershipRecord, OrganizationKnowledgeRecord,
-    OrganizationKnowledgeSearchHit, PendingMaterializationInput, PendingMaterializationJob,
+    ArbitrationExplanation, AutoPlannerPolicy, BackgroundJob, BackgroundJobKind, CommunityRecord,
+    CommunitySnapshot, CommunityState, CommunitySummaryState, ConsolidationLease, DigestTopic,
+    ExperimentVariantOutcome, ExportReport, GoalPlan, GoalPlanStatus, GraphDiff,
+    GraphEdgeWeightChange, GraphExport, GraphExportEdge, GraphExportFilter, GraphExportNode,
+    IngestAdmission, IntegrityCheckOptions, IntegrityReport, JobLease, L3TaskProgress,
+    MemoryDigest, MemoryUnitFollowUp, OrganizationAutomationCounterSnapshot,
+    OrganizationKnowledgeContributionEntry, OrganizationKnowledgeContributionRecord,
+    OrganizationKnowledgeContributionStatus, OrganizationKnowledgeDetailRecord,
+    OrganizationKnowledgeMembershipEntry, OrganizationKnowledgeMembershipRecord,
+    OrganizationKnowledgeRecord, OrganizationKnowledgeSearchHit, OrphanGcOptions, OrphanGcReport,
+    PackedContext, PendingMaterializationInput, PendingMaterializationJob,
     PendingMaterializationJobStatus, PendingMaterializationPart, PlannedMemoryCorrectionAction,
     RacDecisionEffect, RacDecisionRecord, RacMetricHistoryPoint, RacMetricSnapshot,
-    RacReviewRecord, RacReviewStatus, ReflectionBatchOutcome, ReflectionMarker, SharedSearchHit,
+    RacReviewRecord, RacReviewStatus, RankingOverrides, RecoveryReport, ReflectionBatchOutcome,
+    ReflectionMarker, RetrievalDiagnostics, RetrievalStageTimings, SearchExplainOptions,
+    SharedSearchHit, SharedSearchOutcome, SnapshotFileEntry, SnapshotManifest,
+    SnapshotVerification, StorageUsage, StorageUsageReport, StoreDiskUsage, TextIndexCommitReport,
+    VectorSearchMode, WorkerRun,
 };
 
 use crate::arbitrator::Arbitrator;
 use crate::reranker::Reranker;
+use crate::storage::bucketed_index::BucketedTextIndex;
 use crate::storage::graph::GraphStore;
-use crate::storage::index::TextIndex;
 use crate::storage::kv::KvStore;
+use crate::storage::pgvector::PgVectorStore;
 use crate::storage::system_kv::SystemKvStore;
 use crate::storage::vector::VectorStore;
 use anyhow::Result;
 use dashmap::DashMap;
-use memorose_common::config::VectorConfig;
+use memorose_common::config::{VectorBackendKind, VectorConfig};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -55,7 +90,7 @@ pub struct MemoroseEngine {
     pub(crate) kv_store: KvStore,
     pub(crate) vector: Option<VectorStore>,
     pub(crate) vector_status: DerivedIndexStatus,
-    pub(crate) index: TextIndex,
+    pub(crate) index: BucketedTextIndex,
     pub(crate) graph: GraphStore,
     pub(crate) arbitrator: Arbitrator,
     pub(crate) reranker: std::sync::Arc<dyn Reranker>,
@@ -63,12 +98,37 @@ pub struct MemoroseEngine {
     pub(crate) commit_interval_ms: u64,
     pub(crate) storage_config: memorose_common::config::StorageConfig,
     pub auto_planner: bool,
+    pub(crate) auto_planner_policy: AutoPlannerPolicy,
+    pub(crate) recency_half_life_hours: f64,
+    pub(crate) rrf_k: f32,
+    pub(crate) ranking_overrides: RankingOverrides,
+    /// Hot-reloadable settings; takes precedence over the values captured at startup.
+    pub(crate) live_config: Option<memorose_common::config::LiveConfig>,
+    pub(crate) admission: memorose_common::config::AdmissionConfig,
+    /// Last sampled pending-queue depth, reused for `admission.sample_interval_ms`.
+    pub(crate) pending_gauge: Arc<Mutex<Option<(std::time::Instant, usize)>>>,
+    pub(crate) linking: memorose_common::config::LinkingConfig,
+    pub(crate) edge_decay: memorose_common::config::EdgeDecayConfig,
+    /// Wraps and unwraps the data keys of the bring-your-own-key registry.
+    pub(crate) key_manager: crate::crypto::KeyManager,
+    /// Start of the current one-minute window and the relation-analysis calls made in it.
+    pub(crate) relation_call_window: Arc<Mutex<(std::time::Instant, u32)>>,
+    /// Serializes updates to the per-variant experiment outcome counters.
+    pub(crate) experiment_outcomes_lock: Arc<Mutex<()>>,
+    /// Serializes read-modify-write updates of background job records, which are enqueued
+    /// from the apply path while the worker claims and completes them.
+    pub(crate) job_queue_lock: Arc<std::sync::Mutex<()>>,
     pub task_reflection: bool,
     pub task_locks: Arc<DashMap<Uuid, Arc<Mutex<()>>>>,
     pub auto_link_similarity_threshold: f32,
     // New: Query optimization components
     pub(crate) query_cache: Arc<crate::graph::QueryCache>,
     pub(crate) batch_executor: Arc<crate::graph::BatchExecutor>,
+    pub(crate) task_deadline_events:
+        tokio::sync::broadcast::Sender<memorose_common::TaskDeadlineEvent>,
+    /// Backing directory of an engine from `new_in_memory`, removed with the last clone.
+    #[cfg(any(test, feature = "test-util"))]
+    pub(crate) temp_dir: Option<Arc<tempfile::TempDir>>,
 }
 
 impl MemoroseEngine {
@@ -131,21 +191,98 @@ impl MemoroseEngine {
         task_reflection: bool,
         auto_link_similarity_threshold: f32,
         embedding_dim: i32,
+    ) -> Result<Self> {
+        Self::open(
+            path,
+            storage_config,
+            auto_planner,
+            task_reflection,
+            auto_link_similarity_threshold,
+            embedding_dim,
+            memorose_common::config::AppConfig::load().ok(),
+        )
+        .await
+    }
+
+    /// An engine on a temporary directory that is deleted once the engine is dropped,
+    /// with [`crate::llm::MockLLM`] as its LLM. It ignores config files and the
+    /// environment, and no background worker runs, so tests get the same behaviour
+    /// everywhere without API keys.
+    #[cfg(any(test, feature = "test-util"))]
+    pub async fn new_in_memory() -> Result<Self> {
+        let temp_dir = tempfile::tempdir()?;
+        let mut engine = Self::open(
+            temp_dir.path(),
+            memorose_common::config::StorageConfig::default(),
+            false,
+            false,
+            memorose_common::config::DEFAULT_AUTO_LINK_SIMILARITY_THRESHOLD,
+            crate::llm::MOCK_EMBEDDING_DIM as i32,
+            Some(memorose_common::config::AppConfig::default()),
+        )
+        .await?
+        .with_arbitrator(Arbitrator::with_client(
+            Arc::new(crate::llm::MockLLM::new()),
+        ));
+        engine.temp_dir = Some(Arc::new(temp_dir));
+        Ok(engine)
+    }
+
+    async fn open(
+        path: impl Into<PathBuf>,
+        storage_config: memorose_common::config::StorageConfig,
+        auto_planner: bool,
+        task_reflection: bool,
+        auto_link_similarity_threshold: f32,
+        embedding_dim: i32,
+        app_config: Option<memorose_common::config::AppConfig>,
     ) -> Result<Self> {
         use crate::storage::index::TextIndexConfig;
         use lancedb::connect;
 
-        let app_config = memorose_common::config::AppConfig::load().ok();
         let vector_config = app_config
             .as_ref()
             .map(|config| config.vector.clone())
             .unwrap_or_default();
+        let auto_planner_policy = app_config
+            .as_ref()
+            .map(|config| AutoPlannerPolicy::from_worker_config(&config.worker))
+            .unwrap_or_default();
+        let recency_half_life_hours = app_config
+            .as_ref()
+            .map(|config| config.reranker.recency_half_life_hours)
+            .unwrap_or(memorose_common::config::DEFAULT_RERANKER_RECENCY_HALF_LIFE_HOURS);
+        let rrf_k = app_config
+            .as_ref()
+            .map(|config| config.reranker.rrf_k)
+            .unwrap_or(memorose_common::config::DEFAULT_RERANKER_RRF_K);
+        let admission = app_config
+            .as_ref()
+            .map(|config| config.admission.clone())
+            .unwrap_or_default();
+        let linking = app_config
+            .as_ref()
+            .map(|config| config.linking.clone())
+            .unwrap_or_default();
+        let edge_decay = app_config
+            .as_ref()
+            .map(|config| config.edge_decay.clone())
+            .unwrap_or_default();
+        let key_manager = crate::crypto::KeyManager::new(
+            app_config
+                .as_ref()
+                .map(|config| config.encryption.clone())
+                .unwrap_or_default(),
+        );
         let root_path = path.into();
         std::fs::create_dir_all(&root_path)?;
         let root_path = root_path.canonicalize()?;
 
         let kv_path = root_path.join("rocksdb");
-        let kv = tokio::task::spawn_blocking(move || KvStore::open(kv_path)).await??;
+        let kv_config = storage_config.clone();
+        let kv =
+            tokio::task::spawn_blocking(move || KvStore::open_with_config(kv_path, &kv_config))
+                .await??;
 
         let vector_path = root_path.join("lancedb");
         let vector_uri = vector_path.to_str().unwrap().to_string();
@@ -154,10 +291,27 @@ impl MemoroseEngine {
             let startup_timeout =
                 std::time::Duration::from_secs(vector_config.startup_timeout_secs.max(1));
             let vector_uri_for_open = vector_uri.clone();
+            let kv_for_graph = kv.clone();
+            let backend_config = vector_config.clone();
             let open_result = tokio::time::timeout(startup_timeout, async move {
-                let vector = VectorStore::new(&vector_uri_for_open, embedding_dim).await?;
+                let vector = match backend_config.backend {
+                    VectorBackendKind::Lancedb => {
+                        VectorStore::new(&vector_uri_for_open, embedding_dim).await?
+                    }
+                    VectorBackendKind::Pgvector => VectorStore::from_backend(
+                        PgVectorStore::connect(
+                            &backend_config,
+                            &vector_uri_for_open,
+                            embedding_dim,
+                        )
+                        .await?,
+                    ),
+                };
                 let db = Arc::new(connect(&vector_uri_for_open).execute().await?);
-                let graph = GraphStore::new(db).await?;
+                let graph = GraphStore::new(db)
+                    .await?
+                    .with_adjacency_index(kv_for_graph)
+                    .await?;
                 Ok::<_, anyhow::Error>((vector, graph))
             })
             .await;
@@ -199,9 +353,11 @@ impl MemoroseEngine {
 
         let index_path = root_path.join("tantivy");
         let index_config = TextIndexConfig::from_storage_config(&storage_config);
-        let index =
-            tokio::task::spawn_blocking(move || TextIndex::with_config(index_path, index_config))
-                .await??;
+        let index_buckets = storage_config.index_user_buckets;
+        let index = tokio::task::spawn_blocking(move || {
+            BucketedTextIndex::open(index_path, index_config, index_buckets)
+        })
+        .await??;
 
         let arbitrator = Arbitrator::new();
         let reranker: Arc<dyn crate::reranker::Reranker> = if let Some(config) = app_config.as_ref()
@@ -239,11 +395,27 @@ impl MemoroseEngine {
             commit_interval_ms: storage_config.index_commit_max_interval_ms,
             storage_config,
             auto_planner,
+            auto_planner_policy,
+            recency_half_life_hours,
+            rrf_k,
+            ranking_overrides: RankingOverrides::default(),
+            live_config: None,
+            admission,
+            pending_gauge: Arc::new(Mutex::new(None)),
+            linking,
+            edge_decay,
+            key_manager,
+            relation_call_window: Arc::new(Mutex::new((std::time::Instant::now(), 0))),
+            experiment_outcomes_lock: Arc::new(Mutex::new(())),
+            job_queue_lock: Arc::new(std::sync::Mutex::new(())),
             task_reflection,
             task_locks: Arc::new(DashMap::new()),
             auto_link_similarity_threshold,
             query_cache,
             batch_executor,
+            task_deadline_events: tokio::sync::broadcast::channel(256).0,
+            #[cfg(any(test, feature = "test-util"))]
+            temp_dir: None,
         };
 
         let reconciliation = engine.reconcile_organization_storage().await?;
@@ -261,6 +433,28 @@ impl MemoroseEngine {
             );
         }
 
+        let unlocked_keys = engine.unlock_user_keys().await?;
+        if unlocked_keys > 0 {
+            tracing::info!("Unlocked {} user encryption keys", unlocked_keys);
+        }
+
+        let migrated_markers = engine.recover_background_jobs()?;
+        if migrated_markers > 0 {
+            tracing::info!(
+                "Moved {} background work markers into the job queue",
+                migrated_markers
+            );
+        }
+
+        if engine.index.rebuild_required() {
+            let indexed = engine.rebuild_text_index().await?;
+            tracing::info!(
+                "Rebuilt text index into {} user buckets ({} units)",
+                engine.index.bucket_count(),
+                indexed
+            );
+        }
+
         Ok(engine)
     }
 
@@ -274,6 +468,100 @@ impl MemoroseEngine {
         self
     }
 
+    pub fn with_key_manager(mut self, key_manager: crate::crypto::KeyManager) -> Self {
+        self.key_manager = key_manager;
+        self
+    }
+
+    pub fn with_auto_planner_policy(mut self, policy: AutoPlannerPolicy) -> Self {
+        self.auto_planner_policy = policy;
+        self
+    }
+
+    pub fn with_recency_half_life_hours(mut self, hours: f64) -> Self {
+        self.recency_half_life_hours = hours;
+        self
+    }
+
+    pub fn with_rrf_k(mut self, rrf_k: f32) -> Self {
+        self.rrf_k = rrf_k;
+        self
+    }
+
+    pub fn with_ranking_overrides(mut self, overrides: RankingOverrides) -> Self {
+        self.ranking_overrides = overrides;
+        self
+    }
+
+    pub fn with_live_config(mut self, live_config: memorose_common::config::LiveConfig) -> Self {
+        self.live_config = Some(live_config);
+        self
+    }
+
+    /// The `[prompts]` template of `kind` for `app_id`, rendered, following config
+    /// reloads. `None` keeps the built-in prompt.
+    pub(crate) fn configured_prompt(
+        &self,
+        kind: memorose_common::config::PromptKind,
+        app_id: Option<&str>,
+    ) -> Option<String> {
+        let config = self.live_config.as_ref()?.load();
+        let template = config.prompts.template_for(kind, app_id)?;
+        Some(memorose_common::prompt_template::render(
+            template,
+            &[
+                ("app_id", app_id.unwrap_or_default()),
+                (
+                    "language_instruction",
+                    crate::llm::LANGUAGE_PRESERVATION_INSTRUCTION,
+                ),
+            ],
+        ))
+    }
+
+    /// Half-life of recency-biased retrieval, following config reloads when live.
+    pub fn recency_half_life_hours(&self) -> f64 {
+        if let Some(hours) = self.ranking_overrides.recency_half_life_hours {
+            return hours;
+        }
+        match &self.live_config {
+            Some(live_config) => live_config.load().reranker.recency_half_life_hours,
+            None => self.recency_half_life_hours,
+        }
+    }
+
+    /// Rank offset of reciprocal rank fusion, following config reloads when live.
+    pub fn rrf_k(&self) -> f32 {
+        if let Some(rrf_k) = self.ranking_overrides.rrf_k {
+            return rrf_k;
+        }
+        match &self.live_config {
+            Some(live_config) => live_config.load().reranker.rrf_k,
+            None => self.rrf_k,
+        }
+    }
+
+    pub fn with_admission_config(
+        mut self,
+        admission: memorose_common::config::AdmissionConfig,
+    ) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    pub fn with_linking_config(mut self, linking: memorose_common::config::LinkingConfig) -> Self {
+        self.linking = linking;
+        self
+    }
+
+    pub fn with_edge_decay_config(
+        mut self,
+        edge_decay: memorose_common::config::EdgeDecayConfig,
+    ) -> Self {
+        self.edge_decay = edge_decay;
+        self
+    }
+
     pub fn kv(&self) -> KvStore {
         self.kv_store.clone()
     }
@@ -286,6 +574,11 @@ impl MemoroseEngine {
         self.root_path.clone()
     }
 
+    /// Directory ingested media (images and their thumbnails) is stored under.
+    pub fn asset_dir(&self) -> PathBuf {
+        self.root_path.join("assets")
+    }
+
     pub fn commit_interval_ms(&self) -> u64 {
         self.commit_interval_ms
     }
@@ -298,6 +591,10 @@ impl MemoroseEngine {
         self.auto_planner
     }
 
+    pub fn auto_planner_policy(&self) -> AutoPlannerPolicy {
+        self.auto_planner_policy
+    }
+
     pub fn task_reflection(&self) -> bool {
         self.task_reflection
     }
@@ -317,6 +614,41 @@ impl MemoroseEngine {
         Ok(())
     }
 
+    /// Commit pending text index writes and make them searchable now, instead of on the
+    /// next background commit. With `merge`, also merge all segments into one.
+    pub async fn commit_text_index(&self, merge: bool) -> Result<TextIndexCommitReport> {
+        let index = self.index.clone();
+        tokio::task::spawn_blocking(move || {
+            let started_at = std::time::Instant::now();
+            let committed_docs = index.metrics_snapshot().dirty_docs;
+            index.commit()?;
+            index.reload()?;
+            let segments_before = index.segment_count();
+            if merge {
+                index.merge_segments()?;
+            }
+            Ok(TextIndexCommitReport {
+                committed_docs,
+                segments_before,
+                segments_after: index.segment_count(),
+                elapsed_ms: started_at.elapsed().as_millis() as u64,
+            })
+        })
+        .await?
+    }
+
+    /// Make every memory of `user_id` indexed so far searchable before a strong-consistency
+    /// read, forcing a commit of the user's text index bucket at most every
+    /// `storage.index_sync_min_interval_ms`.
+    pub async fn sync_text_index(&self, user_id: &str) -> Result<bool> {
+        let index = self.index.clone();
+        let user_id = user_id.to_string();
+        let min_interval =
+            std::time::Duration::from_millis(self.storage_config.index_sync_min_interval_ms);
+        tokio::task::spawn_blocking(move || index.sync_pending(Some(&user_id), min_interval))
+            .await?
+    }
+
     fn apply_lance_runtime_env(config: &VectorConfig) {
         if let Some(value) = config.io_core_reservation {
             std::env::set_var("LANCE_IO_CORE_RESERVATION", value.to_string());
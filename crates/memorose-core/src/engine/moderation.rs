@@ -0,0 +1,40 @@
+use anyhow::Result;
+use memorose_common::ModerationAuditRecord;
+
+impl super::MemoroseEngine {
+    // ── Content moderation audit ────────────────────────────────────
+
+    /// Records sort newest first: the key carries the time counted down from `u64::MAX`.
+    fn moderation_audit_key(record: &ModerationAuditRecord) -> String {
+        let millis = record.recorded_at.timestamp_millis().max(0) as u64;
+        format!(
+            "moderation_audit:{}:{:020}:{}",
+            record.user_id,
+            u64::MAX - millis,
+            record.id
+        )
+    }
+
+    /// Store an audit record of moderation blocking, tagging or stripping an item. The
+    /// record stays on the node that moderated the item.
+    pub fn record_moderation(&self, record: &ModerationAuditRecord) -> Result<()> {
+        let key = Self::moderation_audit_key(record);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(record)?)
+    }
+
+    /// The user's most recent moderation audit records, newest first.
+    pub fn list_moderation_audit(
+        &self,
+        user_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ModerationAuditRecord>> {
+        let prefix = format!("moderation_audit:{}:", user_id);
+        Ok(self
+            .system_kv()
+            .scan_limited(prefix.as_bytes(), limit)?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use memorose_common::{Event, MemoryUnit, OrgPolicy};
+use std::collections::HashMap;
+
+impl super::MemoroseEngine {
+    // ── Organization policy, quotas and retention ───────────────────
+
+    fn org_policy_key(org_id: &str) -> String {
+        format!("org_policy:{}", org_id)
+    }
+
+    pub(crate) fn org_event_key(org_id: &str, event_id: &str) -> String {
+        format!("org:{}:event:{}", org_id, event_id)
+    }
+
+    pub(crate) fn org_user_key(org_id: &str, user_id: &str) -> String {
+        format!("org:{}:user:{}", org_id, user_id)
+    }
+
+    pub fn get_org_policy(&self, org_id: &str) -> Result<OrgPolicy> {
+        let key = Self::org_policy_key(org_id);
+        match self.system_kv().get(key.as_bytes())? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_default()),
+            None => Ok(OrgPolicy::default()),
+        }
+    }
+
+    pub fn set_org_policy(&self, org_id: &str, policy: &OrgPolicy) -> Result<()> {
+        let key = Self::org_policy_key(org_id);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(policy)?)
+    }
+
+    /// Organizations that have a policy stored on this engine.
+    pub fn list_org_policies(&self) -> Result<Vec<(String, OrgPolicy)>> {
+        Ok(self
+            .system_kv()
+            .scan(b"org_policy:")?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let org_id = String::from_utf8(key)
+                    .ok()?
+                    .strip_prefix("org_policy:")?
+                    .to_string();
+                Some((org_id, serde_json::from_slice(&value).ok()?))
+            })
+            .collect())
+    }
+
+    /// Number of events ingested for `org_id` on this engine.
+    pub fn count_org_events(&self, org_id: &str) -> Result<usize> {
+        self.system_kv()
+            .count_prefix(format!("org:{}:event:", org_id).as_bytes())
+    }
+
+    /// Users that have ingested at least one event under `org_id` on this engine.
+    pub fn list_org_users(&self, org_id: &str) -> Result<Vec<String>> {
+        self.scan_key_suffixes(&format!("org:{}:user:", org_id))
+    }
+
+    /// Reject `events` when they would push an organization past its event quota.
+    pub(crate) fn check_org_event_quota(&self, events: &[Event]) -> Result<()> {
+        let mut incoming: HashMap<&str, u64> = HashMap::new();
+        for event in events {
+            if let Some(org_id) = event.org_id.as_deref() {
+                *incoming.entry(org_id).or_default() += 1;
+            }
+        }
+        for (org_id, count) in incoming {
+            let Some(max_events) = self.get_org_policy(org_id)?.max_events else {
+                continue;
+            };
+            let used = self.count_org_events(org_id)? as u64;
+            if used + count > max_events {
+                return Err(anyhow::anyhow!(
+                    "organization {} exceeded its event quota ({} of {} used)",
+                    org_id,
+                    used,
+                    max_events
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the organization's memory units that are older than its retention window.
+    /// Returns the number of units removed.
+    pub async fn apply_org_retention(&self, org_id: &str) -> Result<usize> {
+        let Some(retention_days) = self.get_org_policy(org_id)?.retention_days else {
+            return Ok(0);
+        };
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(retention_days));
+
+        let mut removed = 0;
+        for user_id in self.list_org_users(org_id)? {
+            let prefix = format!("u:{}:unit:", user_id).into_bytes();
+            let kv = self.kv_store.clone();
+            let pairs = tokio::task::spawn_blocking(move || kv.scan(&prefix)).await??;
+
+            let mut expired: Vec<(Vec<u8>, MemoryUnit)> = pairs
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let unit = crate::migration::decode_memory_unit(&value).ok()?;
+                    (unit.org_id.as_deref() == Some(org_id) && unit.transaction_time < cutoff)
+                        .then_some((key, unit))
+                })
+                .collect();
+            if expired.is_empty() {
+                continue;
+            }
+            removed += self.delete_pruned_units(&user_id, &mut expired).await?;
+            self.invalidate_query_cache(&user_id).await;
+        }
+        Ok(removed)
+    }
+}
@@ -2,6 +2,7 @@ use super::types::*;
 use crate::fact_extraction;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use memorose_common::config::LinkingMode;
 use memorose_common::{
     tokenizer::count_tokens, GraphEdge, MemoryDomain, MemoryUnit, RelationType, TimeRange,
 };
@@ -666,7 +667,7 @@ impl super::MemoroseEngine {
 
         let native_units: Vec<MemoryUnit> = pairs
             .into_iter()
-            .filter_map(|(_, val)| serde_json::from_slice::<MemoryUnit>(&val).ok())
+            .filter_map(|(_, val)| crate::migration::decode_memory_unit(&val).ok())
             .filter(|unit| Self::is_local_domain(&unit.domain))
             .filter(|unit| unit.level <= 2)
             .filter(|unit| match domain {
@@ -1287,7 +1288,7 @@ impl super::MemoroseEngine {
 
         let source_units: Vec<MemoryUnit> = pairs
             .into_iter()
-            .filter_map(|(_, val)| serde_json::from_slice::<MemoryUnit>(&val).ok())
+            .filter_map(|(_, val)| crate::migration::decode_memory_unit(&val).ok())
             .collect();
 
         let mut knowledge_by_id: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
@@ -1367,7 +1368,7 @@ impl super::MemoroseEngine {
             .into_iter()
             .filter(|(key, _)| key.windows(6).any(|window| window == b":unit:"))
             .filter_map(|(key, val)| {
-                serde_json::from_slice::<MemoryUnit>(&val)
+                crate::migration::decode_memory_unit(&val)
                     .ok()
                     .map(|unit| (key, unit))
             })
@@ -1400,6 +1401,19 @@ impl super::MemoroseEngine {
                     error
                 );
             }
+            if let Err(error) = vector
+                .delete_by_id(
+                    crate::storage::vector::ASSET_VECTOR_TABLE,
+                    &unit_id.to_string(),
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Failed to delete asset vectors of unit {}: {:?}",
+                    unit_id,
+                    error
+                );
+            }
         }
 
         let index = self.index.clone();
@@ -1819,12 +1833,25 @@ impl super::MemoroseEngine {
     ) -> Result<()> {
         let kv = self.kv_store.clone();
         let unit_to_store = unit.clone();
+        let value = self.encode_memory_unit(unit)?;
         tokio::task::spawn_blocking(move || {
             let mut batch = rocksdb::WriteBatch::default();
             let key = format!("u:{}:unit:{}", unit_to_store.user_id, unit_to_store.id);
             let idx_key = format!("idx:unit:{}", unit_to_store.id);
-            batch.put(key.as_bytes(), &serde_json::to_vec(&unit_to_store)?);
+            batch.put(key.as_bytes(), &value);
             batch.put(idx_key.as_bytes(), unit_to_store.user_id.as_bytes());
+            if let Some(goal_key) = Self::goal_index_key(&unit_to_store) {
+                batch.put(
+                    goal_key.as_bytes(),
+                    unit_to_store
+                        .transaction_time
+                        .timestamp_micros()
+                        .to_le_bytes(),
+                );
+            }
+            if let Some(due_key) = Self::task_due_index_key(&unit_to_store) {
+                batch.put(due_key.as_bytes(), []);
+            }
 
             if unit_to_store.level == 1 && Self::is_local_domain(&unit_to_store.domain) {
                 let l1_key = format!("l1_idx:{}:{}", unit_to_store.user_id, unit_to_store.id);
@@ -1871,19 +1898,111 @@ impl super::MemoroseEngine {
             return Ok(());
         }
 
-        if let Err(error) = self.auto_link_memory(unit).await {
-            tracing::error!("Auto-linking failed for unit {}: {:?}", unit.id, error);
-        }
-        if let Err(error) = self.semantic_link_memory(unit).await {
-            tracing::error!("Semantic linking failed for unit {}: {:?}", unit.id, error);
-        }
+        self.link_published_memory_unit(unit).await;
 
         self.publish_native_shared_knowledge(std::slice::from_ref(unit))
             .await?;
         Ok(())
     }
 
+    pub fn linking_config(&self) -> &memorose_common::config::LinkingConfig {
+        &self.linking
+    }
+
+    /// Link a freshly published unit according to `linking.mode`: inline, queued for the
+    /// deferred linking loop, or not at all.
+    pub(crate) async fn link_published_memory_unit(&self, unit: &MemoryUnit) {
+        match self.linking.mode {
+            LinkingMode::Sync => self.link_memory_unit(unit).await,
+            LinkingMode::Deferred => {
+                if let Err(e) = self.enqueue_linking_job(unit) {
+                    tracing::error!("Failed to queue linking for unit {}: {:?}", unit.id, e);
+                }
+            }
+            LinkingMode::Disabled => {}
+        }
+    }
+
+    async fn link_memory_unit(&self, unit: &MemoryUnit) {
+        if let Err(e) = self.auto_link_memory(unit).await {
+            tracing::error!("Auto-linking failed for unit {}: {:?}", unit.id, e);
+        }
+        if let Err(e) = self.semantic_link_memory(unit).await {
+            tracing::error!("Semantic linking failed for unit {}: {:?}", unit.id, e);
+        }
+    }
+
+    fn linking_job_key(user_id: &str, unit_id: Uuid) -> String {
+        format!("link_queue:{}:{}", user_id, unit_id)
+    }
+
+    pub(crate) fn enqueue_linking_job(&self, unit: &MemoryUnit) -> Result<()> {
+        let key = Self::linking_job_key(&unit.user_id, unit.id);
+        self.system_kv().put(
+            key.as_bytes(),
+            Utc::now().timestamp_millis().to_string().as_bytes(),
+        )
+    }
+
+    pub async fn count_pending_linking_jobs(&self) -> Result<usize> {
+        let skv = self.system_kv();
+        tokio::task::spawn_blocking(move || skv.count_prefix(b"link_queue:")).await?
+    }
+
+    /// Link up to `limit` queued units. Jobs whose unit is gone are dropped.
+    pub async fn process_linking_queue(&self, limit: usize) -> Result<usize> {
+        if limit == 0 {
+            return Ok(0);
+        }
+        let skv = self.system_kv();
+        let jobs =
+            tokio::task::spawn_blocking(move || skv.scan_limited(b"link_queue:", limit)).await??;
+
+        let mut linked = 0;
+        for (key, _) in jobs {
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            let parsed = key_str
+                .strip_prefix("link_queue:")
+                .and_then(|rest| rest.rsplit_once(':'))
+                .and_then(|(user_id, id)| Uuid::parse_str(id).ok().map(|id| (user_id, id)));
+            if let Some((user_id, unit_id)) = parsed {
+                if let Some(unit) = self.get_memory_unit(user_id, unit_id).await? {
+                    if self.is_visible_memory_unit(&unit)? {
+                        self.link_memory_unit(&unit).await;
+                        linked += 1;
+                    }
+                }
+            }
+            self.system_kv().delete(&key)?;
+        }
+        Ok(linked)
+    }
+
+    /// Take one relation-analysis call from the per-minute budget.
+    pub(crate) async fn try_acquire_relation_call(&self) -> bool {
+        let limit = self.linking.max_relation_calls_per_minute;
+        if limit == 0 {
+            return true;
+        }
+        let mut window = self.relation_call_window.lock().await;
+        if window.0.elapsed() >= std::time::Duration::from_secs(60) {
+            *window = (std::time::Instant::now(), 0);
+        }
+        if window.1 >= limit {
+            return false;
+        }
+        window.1 += 1;
+        true
+    }
+
     pub(crate) async fn auto_link_memory(&self, unit: &MemoryUnit) -> Result<()> {
+        if self.should_shed_linking().await {
+            tracing::debug!(
+                "Pending queue is deep; skipping auto-linking for {}",
+                unit.id
+            );
+            return Ok(());
+        }
         if let Some(ref embedding) = unit.embedding {
             let filter = self.build_user_filter(
                 &unit.user_id,
@@ -1903,7 +2022,7 @@ impl super::MemoroseEngine {
                         RelationType::RelatedTo,
                         score,
                     );
-                    self.graph.add_edge(&edge).await?;
+                    self.add_graph_edge(&edge).await?;
 
                     // Set community marker since graph changed
                     self.set_needs_community(&unit.user_id)?;
@@ -1914,6 +2033,16 @@ impl super::MemoroseEngine {
     }
 
     pub(crate) async fn semantic_link_memory(&self, unit: &MemoryUnit) -> Result<()> {
+        if !self.linking.semantic_enabled {
+            return Ok(());
+        }
+        if self.should_shed_linking().await {
+            tracing::debug!(
+                "Pending queue is deep; skipping semantic linking for {}",
+                unit.id
+            );
+            return Ok(());
+        }
         let context = self.fetch_recent_l1_units(&unit.user_id, 25).await?;
 
         let context: Vec<MemoryUnit> = context
@@ -1925,12 +2054,19 @@ impl super::MemoroseEngine {
         if context.is_empty() {
             return Ok(());
         }
+        if !self.try_acquire_relation_call().await {
+            tracing::debug!(
+                "Relation analysis budget exhausted; skipping semantic linking for {}",
+                unit.id
+            );
+            return Ok(());
+        }
 
         let edges = self.arbitrator.analyze_relations(unit, &context).await?;
 
         if !edges.is_empty() {
             for edge in edges {
-                self.graph.add_edge(&edge).await?;
+                self.add_graph_edge(&edge).await?;
             }
         }
         Ok(())
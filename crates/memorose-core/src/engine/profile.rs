@@ -0,0 +1,289 @@
+use super::types::PendingMaterializationJob;
+use anyhow::Result;
+use memorose_common::{
+    GraphEdge, MemoryUnit, ProfileAttribute, ProfileValue, ProfileValueSource, RelationType,
+    UserProfile,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub(crate) const PROFILE_KEYWORD: &str = "profile";
+
+/// Points at the latest synthesized profile. `previous` keeps the last published
+/// profile resolvable while the new one is still waiting to be materialized.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfilePointer {
+    current: Option<Uuid>,
+    #[serde(default)]
+    previous: Option<Uuid>,
+}
+
+impl super::MemoroseEngine {
+    // ── L3 Profile ──────────────────────────────────────────────────
+
+    /// Profile units share level 3 with goals; callers listing goals should skip them.
+    pub fn is_profile_unit(unit: &MemoryUnit) -> bool {
+        unit.level == 3 && unit.keywords.iter().any(|k| k == PROFILE_KEYWORD)
+    }
+
+    fn profile_pointer_key(user_id: &str) -> String {
+        format!("l3:profile:{}", user_id)
+    }
+
+    fn load_profile_pointer(&self, user_id: &str) -> Result<ProfilePointer> {
+        let key = Self::profile_pointer_key(user_id);
+        Ok(self
+            .kv_store
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default())
+    }
+
+    /// The user's latest synthesized L3 profile and the one it replaced.
+    pub fn user_profile_pointer(&self, user_id: &str) -> Result<(Option<Uuid>, Option<Uuid>)> {
+        let pointer = self.load_profile_pointer(user_id)?;
+        Ok((pointer.current, pointer.previous))
+    }
+
+    /// Point the user's L3 profile at `current`, keeping `previous` resolvable until
+    /// `current` is materialized.
+    pub fn set_user_profile_pointer(
+        &self,
+        user_id: &str,
+        current: Uuid,
+        previous: Option<Uuid>,
+    ) -> Result<()> {
+        let pointer = ProfilePointer {
+            current: Some(current),
+            previous,
+        };
+        let key = Self::profile_pointer_key(user_id);
+        self.kv_store
+            .put(key.as_bytes(), &serde_json::to_vec(&pointer)?)
+    }
+
+    /// Return the user's most recent published L3 profile, if one has been synthesized.
+    pub async fn get_user_profile(&self, user_id: &str) -> Result<Option<MemoryUnit>> {
+        let pointer = self.load_profile_pointer(user_id)?;
+        let ids = [pointer.current, pointer.previous]
+            .into_iter()
+            .flatten()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        let units = self.fetch_units(user_id, ids.clone()).await?;
+        Ok(ids
+            .iter()
+            .find_map(|id| units.iter().find(|unit| unit.id.to_string() == *id))
+            .cloned())
+    }
+
+    /// Synthesize a new L3 profile from the user's most recent L2 insights.
+    /// The new profile is linked to the previous one with an `EvolvedTo` edge and
+    /// to its source insights with `DerivedFrom` edges once materialized.
+    /// Returns the id of the enqueued profile, or `None` when nothing was produced.
+    pub async fn refresh_user_profile(
+        &self,
+        user_id: &str,
+        max_insights: usize,
+    ) -> Result<Option<Uuid>> {
+        let mut insights = self
+            .list_memory_units_global(Some(user_id))
+            .await?
+            .into_iter()
+            .filter(|unit| unit.level == 2 && Self::is_local_domain(&unit.domain))
+            .collect::<Vec<_>>();
+        if insights.is_empty() {
+            return Ok(None);
+        }
+        insights.sort_by(|a, b| b.transaction_time.cmp(&a.transaction_time));
+        insights.truncate(max_insights.max(1));
+
+        let previous = self.get_user_profile(user_id).await?;
+        let content = self
+            .arbitrator
+            .synthesize_profile(
+                previous.as_ref().map(|unit| unit.content.as_str()),
+                insights.iter().map(|unit| unit.content.clone()).collect(),
+            )
+            .await?;
+        if content.trim().is_empty()
+            || previous
+                .as_ref()
+                .is_some_and(|unit| unit.content.trim() == content.trim())
+        {
+            return Ok(None);
+        }
+
+        let mut profile = MemoryUnit::new(
+            None,
+            user_id.to_string(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content,
+            None,
+        );
+        profile.level = 3;
+        profile.keywords.push(PROFILE_KEYWORD.to_string());
+        let profile_id = profile.id;
+
+        let mut post_publish_edges = insights
+            .iter()
+            .map(|insight| {
+                GraphEdge::new(
+                    user_id.to_string(),
+                    profile_id,
+                    insight.id,
+                    RelationType::DerivedFrom,
+                    1.0,
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(previous) = &previous {
+            post_publish_edges.push(GraphEdge::new(
+                user_id.to_string(),
+                previous.id,
+                profile_id,
+                RelationType::EvolvedTo,
+                1.0,
+            ));
+        }
+
+        self.enqueue_materialization_jobs(vec![PendingMaterializationJob::new(
+            profile,
+            post_publish_edges,
+            None,
+        )])?;
+
+        self.set_user_profile_pointer(user_id, profile_id, previous.map(|unit| unit.id))?;
+
+        tracing::info!(
+            "Enqueued L3 profile {} for user {} from {} insights",
+            profile_id,
+            user_id,
+            insights.len()
+        );
+        Ok(Some(profile_id))
+    }
+
+    // ── Structured Profile ──────────────────────────────────────────
+
+    fn structured_profile_key(user_id: &str) -> String {
+        format!("profile:{}", user_id)
+    }
+
+    pub fn get_structured_profile(&self, user_id: &str) -> Result<Option<UserProfile>> {
+        let key = Self::structured_profile_key(user_id);
+        match self.kv_store.get(key.as_bytes())? {
+            Some(raw) => Ok(Some(serde_json::from_slice(&raw)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn store_structured_profile(&self, profile: &UserProfile) -> Result<()> {
+        let key = Self::structured_profile_key(&profile.user_id);
+        self.kv_store
+            .put(key.as_bytes(), &serde_json::to_vec(profile)?)
+    }
+
+    /// Apply manual profile edits. `None` removes the attribute; set values are marked
+    /// as manual so later extraction never overwrites them.
+    pub fn patch_structured_profile(
+        &self,
+        user_id: &str,
+        updates: HashMap<String, Option<ProfileValue>>,
+    ) -> Result<UserProfile> {
+        self.apply_structured_profile_patch(user_id, updates, chrono::Utc::now())
+    }
+
+    /// Apply manual profile edits made at `now`. In cluster mode the leader picks `now`
+    /// and replicates the patch, so every replica stores the same profile.
+    pub fn apply_structured_profile_patch(
+        &self,
+        user_id: &str,
+        updates: HashMap<String, Option<ProfileValue>>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<UserProfile> {
+        let mut profile = self
+            .get_structured_profile(user_id)?
+            .unwrap_or_else(|| UserProfile::new(user_id.to_string()));
+        for (key, value) in updates {
+            let Some(key) = UserProfile::normalize_key(&key) else {
+                return Err(anyhow::anyhow!(
+                    "profile keys must contain alphanumeric characters"
+                ));
+            };
+            match value {
+                Some(value) => {
+                    profile.attributes.insert(
+                        key,
+                        ProfileAttribute {
+                            value,
+                            source: ProfileValueSource::Manual,
+                            updated_at: now,
+                        },
+                    );
+                }
+                None => {
+                    profile.attributes.remove(&key);
+                }
+            }
+        }
+        profile.updated_at = now;
+        self.store_structured_profile(&profile)?;
+        Ok(profile)
+    }
+
+    /// Extract typed profile attributes from the user's most recent L1 memories and
+    /// merge them into the stored profile. Returns true when the profile changed.
+    pub async fn refresh_structured_profile(
+        &self,
+        user_id: &str,
+        max_memories: usize,
+    ) -> Result<bool> {
+        let extracted = self
+            .extract_structured_profile(user_id, max_memories)
+            .await?;
+        self.merge_structured_profile(user_id, extracted, chrono::Utc::now())
+    }
+
+    /// Extract typed profile attributes from the user's most recent L1 memories, without
+    /// storing them.
+    pub async fn extract_structured_profile(
+        &self,
+        user_id: &str,
+        max_memories: usize,
+    ) -> Result<HashMap<String, ProfileValue>> {
+        let memories = self
+            .fetch_recent_l1_units(user_id, max_memories.max(1))
+            .await?;
+        if memories.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.arbitrator
+            .extract_profile_attributes(memories.into_iter().map(|unit| unit.content).collect())
+            .await
+    }
+
+    /// Merge extracted attributes into the stored profile as of `now`. Manual values are
+    /// kept. Returns true when the profile changed.
+    pub fn merge_structured_profile(
+        &self,
+        user_id: &str,
+        extracted: HashMap<String, ProfileValue>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool> {
+        if extracted.is_empty() {
+            return Ok(false);
+        }
+        let mut profile = self
+            .get_structured_profile(user_id)?
+            .unwrap_or_else(|| UserProfile::new(user_id.to_string()));
+        if !profile.merge_extracted(extracted, now) {
+            return Ok(false);
+        }
+        self.store_structured_profile(&profile)?;
+        Ok(true)
+    }
+}
@@ -3,6 +3,9 @@ use memorose_common::GraphEdge;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Start nodes whose edges are sampled to estimate fanout for EXPLAIN
+const EXPLAIN_FANOUT_SAMPLE: usize = 64;
+
 impl super::MemoroseEngine {
     pub async fn batch_get_neighbors(
         &self,
@@ -55,11 +58,167 @@ impl super::MemoroseEngine {
             .await
     }
 
+    /// Users with an `active_user:` marker, most recently active first. Markers written
+    /// before activity timestamps were recorded sort last.
+    pub fn recently_active_users(&self, limit: usize) -> Result<Vec<String>> {
+        let mut users: Vec<(i64, String)> = self
+            .system_kv()
+            .scan(b"active_user:")?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let user_id = String::from_utf8(key)
+                    .ok()?
+                    .strip_prefix("active_user:")?
+                    .to_string();
+                let active_at = std::str::from_utf8(&value)
+                    .ok()
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .unwrap_or(0);
+                Some((active_at, user_id))
+            })
+            .collect();
+        users.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        users.truncate(limit);
+        Ok(users.into_iter().map(|(_, user_id)| user_id).collect())
+    }
+
+    /// 预热查询缓存：为最近活跃的用户预加载一跳邻居（重启或切换 leader 后调用）
+    ///
+    /// Returns the number of neighbourhoods loaded.
+    pub async fn warm_query_cache(&self, max_users: usize, nodes_per_user: usize) -> Result<usize> {
+        use crate::graph::CacheKey;
+
+        if max_users == 0 || nodes_per_user == 0 {
+            return Ok(0);
+        }
+
+        let mut warmed = 0;
+        for user_id in self.recently_active_users(max_users)? {
+            let mut node_ids = Vec::new();
+            for edge in self
+                .graph
+                .get_edges_for_user_limited(&user_id, nodes_per_user * 4)
+                .await?
+            {
+                if !node_ids.contains(&edge.source_id) {
+                    node_ids.push(edge.source_id);
+                }
+                if node_ids.len() >= nodes_per_user {
+                    break;
+                }
+            }
+            if node_ids.is_empty() {
+                continue;
+            }
+
+            let neighbours = self.batch_get_neighbors(&user_id, &node_ids).await?;
+            for node_id in node_ids {
+                let edges = neighbours.get(&node_id).cloned().unwrap_or_default();
+                let cache_key = CacheKey::OneHopNeighbors {
+                    user_id: user_id.clone(),
+                    node_id,
+                    direction: crate::graph::cache::Direction::Outgoing,
+                };
+                self.query_cache.put_edges(cache_key, edges).await;
+                warmed += 1;
+            }
+        }
+
+        Ok(warmed)
+    }
+
+    /// EXPLAIN a graph traversal without running it: the optimizer's plan, cardinalities
+    /// estimated from fanout sampled at the start nodes, and which execution paths apply.
+    pub async fn explain_graph_query(
+        &self,
+        query: &crate::graph::GraphQueryBuilder,
+    ) -> Result<crate::graph::optimizer::PlanExplanation> {
+        use crate::graph::{CacheKey, PlanExplainer, QueryOptimizer, TraversalDirection};
+
+        let user_id = query.user_id();
+        let direction = query
+            .traversals()
+            .first()
+            .map(|t| t.direction)
+            .unwrap_or_default();
+        let sample: Vec<Uuid> = query
+            .start_nodes()
+            .iter()
+            .take(EXPLAIN_FANOUT_SAMPLE)
+            .copied()
+            .collect();
+
+        let mut sampled_edges = Vec::new();
+        if !sample.is_empty() && !query.traversals().is_empty() {
+            if direction != TraversalDirection::Incoming {
+                let outgoing = self.batch_get_neighbors(user_id, &sample).await?;
+                sampled_edges.extend(outgoing.into_values().flatten());
+            }
+            if direction != TraversalDirection::Outgoing {
+                let incoming = self
+                    .batch_executor
+                    .batch_get_incoming_edges(user_id, &sample)
+                    .await?;
+                sampled_edges.extend(incoming.into_values().flatten());
+            }
+        }
+        let mut sampled_fanout: HashMap<String, f32> = HashMap::new();
+        for edge in &sampled_edges {
+            *sampled_fanout
+                .entry(edge.relation.as_str().to_string())
+                .or_default() += 1.0;
+        }
+        for fanout in sampled_fanout.values_mut() {
+            *fanout /= sample.len().max(1) as f32;
+        }
+
+        let optimizer = QueryOptimizer::new().with_fanout_stats(sampled_fanout.clone());
+        let plan = optimizer.optimize(query.to_execution_plan());
+        let steps = optimizer.estimate(&plan);
+
+        // A bare one-hop outgoing lookup from a single node is what `get_neighbors_cached`
+        // serves; everything else expands the frontier through the batch executor.
+        let uses_query_cache = match (query.start_nodes(), query.traversals()) {
+            ([_], [spec]) => {
+                spec.direction == TraversalDirection::Outgoing
+                    && spec.relation_types.is_empty()
+                    && spec.weight_threshold.is_none()
+            }
+            _ => false,
+        };
+        let cache_hit = if uses_query_cache {
+            let cache_key = CacheKey::OneHopNeighbors {
+                user_id: user_id.to_string(),
+                node_id: query.start_nodes()[0],
+                direction: crate::graph::cache::Direction::Outgoing,
+            };
+            self.query_cache.get_edges(&cache_key).await.is_some()
+        } else {
+            false
+        };
+
+        Ok(crate::graph::optimizer::PlanExplanation {
+            plan: PlanExplainer::explain(&plan),
+            estimated_result_rows: steps.first().map(|s| s.estimated_rows).unwrap_or(0),
+            steps,
+            sampled_fanout,
+            uses_query_cache,
+            cache_hit,
+            uses_batch_expansion: !uses_query_cache && !query.traversals().is_empty(),
+            uses_adjacency_index: self.graph.has_adjacency_index(),
+        })
+    }
+
     /// 失效用户的查询缓存（在写入边时调用）
     pub async fn invalidate_query_cache(&self, user_id: &str) {
         self.query_cache.invalidate_user(user_id).await;
     }
 
+    /// 失效与指定节点相关的图查询缓存（图写入后调用，包括 Raft 回放的写入）
+    pub async fn invalidate_graph_cache(&self, user_id: &str, node_ids: &[Uuid]) {
+        self.query_cache.invalidate_nodes(user_id, node_ids).await;
+    }
+
     /// Get cache statistics
     pub async fn query_cache_stats(&self) -> crate::graph::cache::CacheStats {
         self.query_cache.stats().await
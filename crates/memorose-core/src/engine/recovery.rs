@@ -0,0 +1,138 @@
+use super::types::{IntegrityCheckOptions, IntegrityReport};
+use anyhow::Result;
+use memorose_common::MaterializationState;
+use uuid::Uuid;
+
+const SESSION_OPEN_KEY: &[u8] = b"recovery:session_open";
+const LAST_APPLIED_KEY: &[u8] = b"raft:last_applied";
+/// Copy of `raft:last_applied` taken once the graph buffer and text index were flushed.
+const DURABLE_APPLIED_KEY: &[u8] = b"raft:durable_applied";
+/// Units checked per batch while converging the stores at startup.
+const RECOVERY_INTEGRITY_BATCH: usize = 500;
+const UNIT_INDEX_PREFIX: &[u8] = b"idx:unit:";
+/// Units read per page while rebuilding the text index.
+const TEXT_INDEX_REBUILD_BATCH: usize = 1000;
+
+impl super::MemoroseEngine {
+    /// Record that a process has the stores open. Returns true when the previous process
+    /// never closed its session, i.e. it did not shut down cleanly.
+    pub fn mark_session_open(&self) -> Result<bool> {
+        let system_kv = self.system_kv();
+        let unclean = system_kv.get(SESSION_OPEN_KEY)?.is_some();
+        system_kv.put(
+            SESSION_OPEN_KEY,
+            chrono::Utc::now().timestamp_millis().to_string().as_bytes(),
+        )?;
+        Ok(unclean)
+    }
+
+    /// Flush the graph buffer and commit the text index, then advance the durable applied
+    /// watermark to the Raft entry that was applied before the flush started.
+    pub async fn checkpoint_derived_stores(&self) -> Result<()> {
+        let applied = self.system_kv().get(LAST_APPLIED_KEY)?;
+        self.graph.flush().await?;
+        let index = self.index.clone();
+        tokio::task::spawn_blocking(move || index.commit()).await??;
+        if let Some(applied) = applied {
+            self.system_kv().put(DURABLE_APPLIED_KEY, &applied)?;
+        }
+        Ok(())
+    }
+
+    /// Checkpoint the derived stores and close the session, so the next start skips recovery.
+    pub async fn close_session(&self) -> Result<()> {
+        self.checkpoint_derived_stores().await?;
+        self.system_kv().delete(SESSION_OPEN_KEY)
+    }
+
+    /// Run a complete consistency pass with repair, checking units regardless of age (no
+    /// writer is running yet), then checkpoint. Used at startup after an unclean shutdown.
+    pub async fn converge_derived_stores(&self) -> Result<IntegrityReport> {
+        let options = IntegrityCheckOptions {
+            batch_size: RECOVERY_INTEGRITY_BATCH,
+            repair: true,
+            repair_orphaned_edges: false,
+            settle_secs: 0,
+        };
+        let report = loop {
+            let report = self.run_integrity_batch(options).await?;
+            if report.completed_at.is_some() {
+                break report;
+            }
+            tracing::info!(
+                "Recovery consistency pass: {} units checked so far",
+                report.scanned_units
+            );
+        };
+        if report.repair_failures > 0 {
+            anyhow::bail!(
+                "{} derived-store entries could not be repaired during recovery",
+                report.repair_failures
+            );
+        }
+        self.checkpoint_derived_stores().await?;
+        Ok(report)
+    }
+
+    /// Index every published, unforgotten unit into the (empty) text index, commit, and only
+    /// then record the bucket layout. Used at startup when the index had no layout marker
+    /// or `storage.index_user_buckets` changed. Returns the units indexed.
+    pub(crate) async fn rebuild_text_index(&self) -> Result<usize> {
+        let mut cursor: Option<Vec<u8>> = None;
+        let mut indexed = 0;
+        loop {
+            let kv = self.kv_store.clone();
+            let after = cursor.clone();
+            let page = tokio::task::spawn_blocking(move || {
+                kv.scan_prefix_after(
+                    UNIT_INDEX_PREFIX,
+                    after.as_deref(),
+                    TEXT_INDEX_REBUILD_BATCH,
+                )
+            })
+            .await??;
+
+            let mut units = Vec::new();
+            for (key, user_bytes) in &page {
+                let Some(unit_id) = std::str::from_utf8(&key[UNIT_INDEX_PREFIX.len()..])
+                    .ok()
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                else {
+                    continue;
+                };
+                let user_id = String::from_utf8_lossy(user_bytes).to_string();
+                if let Some(unit) = self.get_memory_unit_raw(&user_id, unit_id)? {
+                    if unit.materialization_state == MaterializationState::Published
+                        && !self.is_memory_unit_forgotten(&user_id, unit_id)?
+                    {
+                        units.push(unit);
+                    }
+                }
+            }
+
+            indexed += units.len();
+            let index = self.index.clone();
+            tokio::task::spawn_blocking(move || {
+                for unit in &units {
+                    index.index_unit(unit)?;
+                }
+                Ok::<(), anyhow::Error>(())
+            })
+            .await??;
+
+            if page.len() < TEXT_INDEX_REBUILD_BATCH {
+                break;
+            }
+            cursor = page.last().map(|(key, _)| key.clone());
+        }
+
+        let index = self.index.clone();
+        tokio::task::spawn_blocking(move || {
+            index.commit()?;
+            index.reload()?;
+            index.mark_rebuilt()
+        })
+        .await??;
+        Ok(indexed)
+    }
+}
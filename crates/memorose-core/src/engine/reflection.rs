@@ -1,7 +1,11 @@
-use super::types::{PendingMaterializationJob, ReflectionBatchOutcome, ReflectionMarker};
+use super::types::{
+    BackgroundJobKind, PendingMaterializationJob, ReflectionBatchOutcome, ReflectionMarker,
+};
 use anyhow::Result;
+use memorose_common::config::PromptKind;
 use memorose_common::tokenizer::count_tokens;
 use memorose_common::{GraphEdge, MemoryUnit, RelationType};
+use std::hash::{Hash, Hasher};
 use uuid::Uuid;
 
 impl super::MemoroseEngine {
@@ -34,61 +38,52 @@ impl super::MemoroseEngine {
         last_event_tx_micros: Option<i64>,
         first_event_id: Option<String>,
     ) -> Result<()> {
-        let key = format!("needs_reflect:{}", user_id);
         let now = chrono::Utc::now().timestamp_millis();
-        let next = match self.system_kv().get(key.as_bytes())? {
-            Some(raw) => {
-                let mut marker =
-                    serde_json::from_slice::<ReflectionMarker>(&raw).unwrap_or_else(|_| {
-                        ReflectionMarker {
-                            first_event_at_ts: now,
-                            last_event_at_ts: now,
-                            pending_units: 0,
-                            pending_tokens: 0,
-                            first_event_tx_micros: 0,
-                            last_event_tx_micros: 0,
-                            first_event_id: None,
+        self.enqueue_job_with(BackgroundJobKind::Reflect, user_id, |job| {
+            let next = match job.reflection.take() {
+                Some(mut marker) => {
+                    if marker.first_event_at_ts <= 0 {
+                        marker.first_event_at_ts = now;
+                    }
+                    marker.last_event_at_ts = now;
+                    marker.pending_units = marker.pending_units.saturating_add(pending_units_delta);
+                    marker.pending_tokens =
+                        marker.pending_tokens.saturating_add(pending_tokens_delta);
+                    if let Some(first_tx) = first_event_tx_micros {
+                        let should_update_cursor = marker.first_event_tx_micros <= 0
+                            || first_tx < marker.first_event_tx_micros
+                            || (first_tx == marker.first_event_tx_micros
+                                && first_event_id
+                                    .as_ref()
+                                    .zip(marker.first_event_id.as_ref())
+                                    .map(|(new_id, old_id)| new_id < old_id)
+                                    .unwrap_or(marker.first_event_id.is_none()));
+                        if should_update_cursor {
+                            marker.first_event_tx_micros = first_tx;
+                            marker.first_event_id = first_event_id.clone();
                         }
-                    });
-                if marker.first_event_at_ts <= 0 {
-                    marker.first_event_at_ts = now;
-                }
-                marker.last_event_at_ts = now;
-                marker.pending_units = marker.pending_units.saturating_add(pending_units_delta);
-                marker.pending_tokens = marker.pending_tokens.saturating_add(pending_tokens_delta);
-                if let Some(first_tx) = first_event_tx_micros {
-                    let should_update_cursor = marker.first_event_tx_micros <= 0
-                        || first_tx < marker.first_event_tx_micros
-                        || (first_tx == marker.first_event_tx_micros
-                            && first_event_id
-                                .as_ref()
-                                .zip(marker.first_event_id.as_ref())
-                                .map(|(new_id, old_id)| new_id < old_id)
-                                .unwrap_or(marker.first_event_id.is_none()));
-                    if should_update_cursor {
-                        marker.first_event_tx_micros = first_tx;
-                        marker.first_event_id = first_event_id.clone();
                     }
+                    if let Some(last_tx) = last_event_tx_micros {
+                        marker.last_event_tx_micros = marker.last_event_tx_micros.max(last_tx);
+                    }
+                    marker
                 }
-                if let Some(last_tx) = last_event_tx_micros {
-                    marker.last_event_tx_micros = marker.last_event_tx_micros.max(last_tx);
-                }
-                marker
-            }
-            None => ReflectionMarker {
-                first_event_at_ts: now,
-                last_event_at_ts: now,
-                pending_units: pending_units_delta,
-                pending_tokens: pending_tokens_delta,
-                first_event_tx_micros: first_event_tx_micros.unwrap_or_default(),
-                last_event_tx_micros: last_event_tx_micros.unwrap_or_default(),
-                first_event_id,
-            },
-        };
-        self.system_kv()
-            .put(key.as_bytes(), &serde_json::to_vec(&next)?)
+                None => ReflectionMarker {
+                    first_event_at_ts: now,
+                    last_event_at_ts: now,
+                    pending_units: pending_units_delta,
+                    pending_tokens: pending_tokens_delta,
+                    first_event_tx_micros: first_event_tx_micros.unwrap_or_default(),
+                    last_event_tx_micros: last_event_tx_micros.unwrap_or_default(),
+                    first_event_id,
+                },
+            };
+            job.reflection = Some(next);
+        })
     }
 
+    /// Record a reflected batch on the user's reflect job. Once nothing is left pending
+    /// the backlog is emptied, and the job itself is left for the worker to complete.
     pub fn consume_reflection_marker_batch(
         &self,
         user_id: &str,
@@ -97,28 +92,24 @@ impl super::MemoroseEngine {
         next_first_event_tx_micros: Option<i64>,
         next_first_event_id: Option<String>,
     ) -> Result<()> {
-        let key = format!("needs_reflect:{}", user_id);
-        let Some(raw) = self.system_kv().get(key.as_bytes())? else {
-            return Ok(());
-        };
-        let mut marker = serde_json::from_slice::<ReflectionMarker>(&raw).unwrap_or_default();
-        marker.pending_units = marker.pending_units.saturating_sub(consumed_units);
-        marker.pending_tokens = marker.pending_tokens.saturating_sub(consumed_tokens);
-
-        if marker.pending_units == 0 || next_first_event_tx_micros.is_none() {
-            return self.clear_reflection_marker(user_id);
-        }
-
-        marker.first_event_tx_micros = next_first_event_tx_micros.unwrap_or_default();
-        marker.first_event_id = next_first_event_id;
-        self.system_kv()
-            .put(key.as_bytes(), &serde_json::to_vec(&marker)?)
+        self.update_job(BackgroundJobKind::Reflect, user_id, |slot| {
+            let Some(job) = slot.as_mut() else {
+                return;
+            };
+            let marker = job.reflection.get_or_insert_with(ReflectionMarker::default);
+            marker.pending_units = marker.pending_units.saturating_sub(consumed_units);
+            marker.pending_tokens = marker.pending_tokens.saturating_sub(consumed_tokens);
+            if marker.pending_units == 0 || next_first_event_tx_micros.is_none() {
+                *marker = ReflectionMarker::default();
+                return;
+            }
+            marker.first_event_tx_micros = next_first_event_tx_micros.unwrap_or_default();
+            marker.first_event_id = next_first_event_id;
+        })
     }
 
     pub fn set_needs_community(&self, user_id: &str) -> Result<()> {
-        let key = format!("needs_community:{}", user_id);
-        let ts = chrono::Utc::now().timestamp().to_string();
-        self.system_kv().put(key.as_bytes(), ts.as_bytes())
+        self.enqueue_job(BackgroundJobKind::Community, user_id)
     }
 
     pub fn get_pending_reflections(&self) -> Result<Vec<String>> {
@@ -130,38 +121,43 @@ impl super::MemoroseEngine {
     }
 
     pub fn get_pending_reflection_markers(&self) -> Result<Vec<(String, ReflectionMarker)>> {
-        let pairs = self.system_kv().scan(b"needs_reflect:")?;
-        let mut markers = Vec::new();
-        for (key, value) in pairs {
-            let key_str = String::from_utf8(key)?;
-            if let Some(uid) = key_str.strip_prefix("needs_reflect:") {
-                let marker = serde_json::from_slice::<ReflectionMarker>(&value).unwrap_or_default();
-                markers.push((uid.to_string(), marker));
-            }
-        }
-        Ok(markers)
+        Ok(self
+            .list_jobs(BackgroundJobKind::Reflect)?
+            .into_iter()
+            .map(|job| (job.user_id, job.reflection.unwrap_or_default()))
+            .collect())
     }
 
     pub fn clear_reflection_marker(&self, user_id: &str) -> Result<()> {
-        let key = format!("needs_reflect:{}", user_id);
-        self.system_kv().delete(key.as_bytes())
+        self.delete_job(BackgroundJobKind::Reflect, user_id)
     }
 
     pub fn get_pending_communities(&self) -> Result<Vec<String>> {
-        let pairs = self.system_kv().scan(b"needs_community:")?;
-        let mut user_ids = Vec::new();
-        for (key, _) in pairs {
-            let key_str = String::from_utf8(key)?;
-            if let Some(uid) = key_str.strip_prefix("needs_community:") {
-                user_ids.push(uid.to_string());
-            }
-        }
-        Ok(user_ids)
+        self.pending_job_users(BackgroundJobKind::Community)
     }
 
     pub fn clear_community_marker(&self, user_id: &str) -> Result<()> {
-        let key = format!("needs_community:{}", user_id);
-        self.system_kv().delete(key.as_bytes())
+        self.delete_job(BackgroundJobKind::Community, user_id)
+    }
+
+    pub fn set_needs_profile(&self, user_id: &str) -> Result<()> {
+        self.enqueue_job(BackgroundJobKind::Profile, user_id)
+    }
+
+    pub fn get_pending_profiles(&self) -> Result<Vec<String>> {
+        self.pending_job_users(BackgroundJobKind::Profile)
+    }
+
+    pub fn clear_profile_marker(&self, user_id: &str) -> Result<()> {
+        self.delete_job(BackgroundJobKind::Profile, user_id)
+    }
+
+    fn pending_job_users(&self, kind: BackgroundJobKind) -> Result<Vec<String>> {
+        Ok(self
+            .list_jobs(kind)?
+            .into_iter()
+            .map(|job| job.user_id)
+            .collect())
     }
 
     // ── Reflection ──────────────────────────────────────────────────
@@ -218,9 +214,11 @@ impl super::MemoroseEngine {
             return Ok(0);
         }
 
+        let app_id = source_units.iter().find_map(|unit| unit.agent_id.clone());
+        let system_prompt = self.configured_prompt(PromptKind::Reflection, app_id.as_deref());
         let topic_units = self
             .arbitrator
-            .extract_topics(user_id, stream_id, source_units)
+            .extract_topics(user_id, stream_id, source_units, system_prompt.as_deref())
             .await?;
 
         if topic_units.is_empty() {
@@ -284,7 +282,7 @@ impl super::MemoroseEngine {
 
             let mut ordered_units: Vec<(String, i64, MemoryUnit)> = pairs
                 .into_iter()
-                .filter_map(|(_, value)| serde_json::from_slice::<MemoryUnit>(&value).ok())
+                .filter_map(|(_, value)| crate::migration::decode_memory_unit(&value).ok())
                 .filter(|unit| unit.level == 1 && Self::is_local_domain(&unit.domain))
                 .filter_map(|unit| {
                     let id = unit.id.to_string();
@@ -350,7 +348,7 @@ impl super::MemoroseEngine {
             .zip(values.into_iter())
             .filter_map(|((id, ts), value)| {
                 let unit =
-                    value.and_then(|bytes| serde_json::from_slice::<MemoryUnit>(&bytes).ok())?;
+                    value.and_then(|bytes| crate::migration::decode_memory_unit(&bytes).ok())?;
                 (unit.level == 1 && Self::is_local_domain(&unit.domain)).then_some((id, ts, unit))
             })
             .collect();
@@ -457,18 +455,51 @@ impl super::MemoroseEngine {
             return Ok(ReflectionBatchOutcome::default());
         };
 
-        let created_topics = self
-            .reflect_on_units(user_id, stream_id, source_units)
-            .await?;
+        let content_hash = Self::reflection_content_hash(&source_units);
+        let hash_key = Self::reflection_hash_key(user_id);
+        let deduplicated =
+            self.system_kv().get(hash_key.as_bytes())?.as_deref() == Some(content_hash.as_bytes());
+        let created_topics = if deduplicated {
+            tracing::debug!(
+                "Skipping reflection for user {}: batch repeats the last reflection",
+                user_id
+            );
+            0
+        } else {
+            let created = self
+                .reflect_on_units(user_id, stream_id, source_units)
+                .await?;
+            self.system_kv()
+                .put(hash_key.as_bytes(), content_hash.as_bytes())?;
+            created
+        };
         Ok(ReflectionBatchOutcome {
             created_topics,
             consumed_units,
             consumed_tokens,
             next_first_event_tx_micros: next_cursor.as_ref().map(|(ts, _)| *ts),
             next_first_event_id: next_cursor.map(|(_, id)| id),
+            deduplicated,
         })
     }
 
+    fn reflection_hash_key(user_id: &str) -> String {
+        format!("reflection_hash:{}", user_id)
+    }
+
+    /// Hash of the text a reflection batch summarizes, independent of unit ids and order,
+    /// so re-ingested or replayed content does not trigger another reflection.
+    fn reflection_content_hash(units: &[MemoryUnit]) -> String {
+        let mut contents: Vec<String> = units
+            .iter()
+            .map(|unit| unit.content.trim().to_lowercase())
+            .collect();
+        contents.sort();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        contents.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
     /// Retrospective Reflection: Apply feedback to the reranker and reinforce graph associations.
     pub async fn apply_reranker_feedback(
         &self,
@@ -511,8 +542,10 @@ impl super::MemoroseEngine {
 
                 self.graph.reinforce_edge(&uid, id_a, id_b, 0.1).await?;
                 self.graph.reinforce_edge(&uid, id_b, id_a, 0.1).await?;
+                self.mark_community_nodes_dirty(&uid, &[id_a, id_b])?;
             }
         }
+        self.invalidate_graph_cache(&uid, &uuids).await;
 
         Ok(())
     }
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use memorose_common::RelationDefinition;
+use std::collections::HashMap;
+
+impl super::MemoroseEngine {
+    // ── Custom relation registry ────────────────────────────────────
+
+    fn relation_definition_key(user_id: &str, name: &str) -> String {
+        format!("relation_def:{}:{}", user_id, name)
+    }
+
+    pub fn put_relation_definition(&self, definition: &RelationDefinition) -> Result<()> {
+        let key = Self::relation_definition_key(&definition.user_id, &definition.name);
+        self.system_kv()
+            .put(key.as_bytes(), &serde_json::to_vec(definition)?)
+    }
+
+    pub fn get_relation_definition(
+        &self,
+        user_id: &str,
+        name: &str,
+    ) -> Result<Option<RelationDefinition>> {
+        let key = Self::relation_definition_key(user_id, name);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    /// The user's custom relations, by name.
+    pub fn list_relation_definitions(&self, user_id: &str) -> Result<Vec<RelationDefinition>> {
+        let prefix = format!("relation_def:{}:", user_id);
+        Ok(self
+            .system_kv()
+            .scan(prefix.as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+
+    /// Remove a custom relation. Edges that use it stay, but graph expansion stops
+    /// following them. Returns false when the user has no such relation.
+    pub fn delete_relation_definition(&self, user_id: &str, name: &str) -> Result<bool> {
+        if self.get_relation_definition(user_id, name)?.is_none() {
+            return Ok(false);
+        }
+        let key = Self::relation_definition_key(user_id, name);
+        self.system_kv().delete(key.as_bytes())?;
+        Ok(true)
+    }
+
+    pub(crate) fn relation_definitions_by_name(
+        &self,
+        user_id: &str,
+    ) -> Result<HashMap<String, RelationDefinition>> {
+        Ok(self
+            .list_relation_definitions(user_id)?
+            .into_iter()
+            .map(|definition| (definition.name.clone(), definition))
+            .collect())
+    }
+}
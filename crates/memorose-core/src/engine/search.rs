@@ -1,10 +1,25 @@
 use super::helpers::{cosine_similarity, escape_sql_string, validate_id};
-use super::types::SharedSearchHit;
+use super::types::{
+    ArbitrationExplanation, GraphExpansionProvenance, PackedContext, RerankDelta,
+    RetrievalDiagnostics, RetrievalStageTimings, RetrievalThresholds, RrfContribution,
+    SearchExplainOptions, SharedSearchHit, SharedSearchOutcome, TextHitDiagnostic,
+    VectorHitDiagnostic, VectorSearchMode,
+};
+use crate::arbitrator::ArbitrationDecision;
+use crate::storage::vector::ASSET_VECTOR_TABLE;
 use anyhow::Result;
-use memorose_common::{MemoryDomain, MemoryUnit, RelationType, TimeRange};
+use memorose_common::tokenizer::count_tokens;
+use memorose_common::{GeoRadius, MemoryDomain, MemoryUnit, RelationType, TimeRange};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// Default cutoff on reranked scores when the caller does not pass `min_score`.
+const DEFAULT_MIN_SCORE: f32 = 0.3;
+/// Results whose embeddings are closer than this are treated as duplicates.
+const DEDUP_SIMILARITY: f32 = 0.92;
+/// Arbitration only runs when the top two scores are closer than this.
+const ARBITRATION_SCORE_GAP: f32 = 0.25;
+
 impl super::MemoroseEngine {
     // ── Search ──────────────────────────────────────────────────────
 
@@ -27,11 +42,13 @@ impl super::MemoroseEngine {
     }
 
     /// Perform a BFS graph traversal to expand context from seed memories.
+    /// When `provenance` is given, records the edge that brought in each new node.
     pub(crate) async fn expand_subgraph(
         &self,
         user_id: &str,
         seeds: Vec<(MemoryUnit, f32)>,
         depth: usize,
+        mut provenance: Option<&mut Vec<GraphExpansionProvenance>>,
     ) -> Result<Vec<(MemoryUnit, f32)>> {
         if depth == 0 || seeds.is_empty() {
             return Ok(seeds);
@@ -44,6 +61,8 @@ impl super::MemoroseEngine {
 
         let mut frontier: Vec<String> = seeds.iter().map(|(u, _)| u.id.to_string()).collect();
         let mut visited: HashSet<String> = frontier.iter().cloned().collect();
+        let custom_relations = self.relation_definitions_by_name(user_id)?;
+        let now = chrono::Utc::now();
 
         for _d in 0..depth {
             if frontier.is_empty() {
@@ -95,6 +114,10 @@ impl super::MemoroseEngine {
             }
 
             let mut neighbor_ids_to_fetch = HashSet::new();
+            let mut via_edges: HashMap<String, (Uuid, RelationType, f32)> = HashMap::new();
+            // Share of the hop score carried to each neighbour: 1.0 over built-in relations,
+            // the registered traversal weight over custom ones.
+            let mut traversal_weights: HashMap<String, f32> = HashMap::new();
 
             for edge in edges_to_process {
                 let is_outgoing = visited.contains(&edge.source_id.to_string());
@@ -109,17 +132,40 @@ impl super::MemoroseEngine {
                     continue;
                 }
 
-                let is_relevant = match edge.relation {
-                    RelationType::DerivedFrom | RelationType::EvolvedTo => true,
+                let traversal_weight = match &edge.relation {
+                    RelationType::DerivedFrom | RelationType::EvolvedTo => Some(1.0),
                     RelationType::RelatedTo
-                        if edge.weight > self.auto_link_similarity_threshold =>
+                        if self.decayed_edge_weight(&edge, now)
+                            > self.auto_link_similarity_threshold =>
                     {
-                        true
+                        Some(1.0)
                     }
-                    _ => false,
+                    // Unregistered custom relations are not followed.
+                    RelationType::Custom(name) => custom_relations
+                        .get(name)
+                        .filter(|definition| {
+                            definition.traversal_weight > 0.0
+                                && (is_outgoing || !definition.directed)
+                        })
+                        .map(|definition| definition.traversal_weight),
+                    _ => None,
                 };
 
-                if is_relevant {
+                if let Some(traversal_weight) = traversal_weight {
+                    let weight = traversal_weights.entry(neighbor_str.clone()).or_insert(0.0);
+                    *weight = weight.max(traversal_weight);
+                    if provenance.is_some() {
+                        let via = if is_outgoing {
+                            edge.source_id
+                        } else {
+                            edge.target_id
+                        };
+                        via_edges.entry(neighbor_str.clone()).or_insert((
+                            via,
+                            edge.relation.clone(),
+                            edge.weight,
+                        ));
+                    }
                     neighbor_ids_to_fetch.insert(neighbor_str.clone());
                     next_frontier.insert(neighbor_str);
                 }
@@ -129,9 +175,22 @@ impl super::MemoroseEngine {
             if !ids_list.is_empty() {
                 let units = self.fetch_units(user_id, ids_list).await?;
                 for unit in units {
-                    let score = 0.8_f32.powi((_d + 1) as i32) * 0.8;
-
                     let unit_id_str = unit.id.to_string();
+                    let score = 0.8_f32.powi((_d + 1) as i32)
+                        * 0.8
+                        * traversal_weights.get(&unit_id_str).copied().unwrap_or(1.0);
+                    if let (Some(provenance), Some((via, relation, edge_weight))) =
+                        (provenance.as_deref_mut(), via_edges.remove(&unit_id_str))
+                    {
+                        provenance.push(GraphExpansionProvenance {
+                            id: unit.id,
+                            via,
+                            relation,
+                            edge_weight,
+                            hop: _d + 1,
+                            score,
+                        });
+                    }
                     results.insert(unit_id_str.clone(), (unit, score));
                     visited.insert(unit_id_str);
                 }
@@ -254,6 +313,57 @@ impl super::MemoroseEngine {
         valid_time: Option<TimeRange>,
         transaction_time: Option<TimeRange>,
         token_budget: Option<usize>,
+    ) -> Result<Vec<(MemoryUnit, f32)>> {
+        self.search_hybrid_traced(
+            user_id,
+            org_id,
+            agent_id,
+            None,
+            None,
+            None,
+            query_text,
+            vector,
+            limit,
+            enable_arbitration,
+            min_score,
+            graph_depth,
+            valid_time,
+            transaction_time,
+            token_budget,
+            0.0,
+            VectorSearchMode::Text,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Hybrid search body. When `diagnostics` is given, each stage records what it saw
+    /// and which thresholds it applied; `timings` receives how long each stage took. `app_ids` widens retrieval from a single
+    /// `agent_id` to any of the listed apps; `emotions` keeps memories tagged with any of
+    /// them; `near` keeps memories recorded within its radius; `vector_mode` picks the
+    /// vector tables.
+    pub(crate) async fn search_hybrid_traced(
+        &self,
+        user_id: &str,
+        org_id: Option<&str>,
+        agent_id: Option<&str>,
+        app_ids: Option<&[String]>,
+        emotions: Option<&[String]>,
+        near: Option<&GeoRadius>,
+        query_text: &str,
+        vector: &[f32],
+        limit: usize,
+        enable_arbitration: bool,
+        min_score: Option<f32>,
+        graph_depth: usize,
+        valid_time: Option<TimeRange>,
+        transaction_time: Option<TimeRange>,
+        token_budget: Option<usize>,
+        recency_bias: f32,
+        vector_mode: VectorSearchMode,
+        mut diagnostics: Option<&mut RetrievalDiagnostics>,
+        timings: Option<&mut RetrievalStageTimings>,
     ) -> Result<Vec<(MemoryUnit, f32)>> {
         validate_id(user_id)?;
         if let Some(oid) = org_id {
@@ -262,8 +372,34 @@ impl super::MemoroseEngine {
         if let Some(aid) = agent_id {
             validate_id(aid)?;
         }
+        for app_id in app_ids.unwrap_or_default() {
+            validate_id(app_id)?;
+        }
         let time_filter = self.build_time_filter(valid_time.clone());
-        let agent_filter = agent_id.map(|aid| format!("agent_id = '{}'", escape_sql_string(aid)));
+        let agent_filter = match app_ids {
+            Some(app_ids) => Some(format!(
+                "agent_id IN ({})",
+                app_ids
+                    .iter()
+                    .map(|app_id| format!("'{}'", escape_sql_string(app_id)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            None => agent_id.map(|aid| format!("agent_id = '{}'", escape_sql_string(aid))),
+        };
+        let in_scope = |unit: &MemoryUnit| {
+            org_id.is_none_or(|oid| unit.org_id.as_deref() == Some(oid))
+                && app_ids.is_none_or(|app_ids| {
+                    unit.agent_id
+                        .as_deref()
+                        .is_some_and(|aid| app_ids.iter().any(|app_id| app_id == aid))
+                })
+                && emotions.is_none_or(|emotions| unit.has_any_emotion(emotions))
+                && near.is_none_or(|near| {
+                    unit.location
+                        .is_some_and(|location| near.contains(&location))
+                })
+        };
         let org_filter = org_id.map(|oid| format!("org_id = '{}'", escape_sql_string(oid)));
         let mut filters = vec!["(domain = 'agent' OR domain = 'user')".to_string()];
         if let Some(filter) = time_filter {
@@ -275,6 +411,9 @@ impl super::MemoroseEngine {
         if let Some(filter) = org_filter {
             filters.push(filter);
         }
+        if let Some(near) = near {
+            filters.push(self.build_geo_filter(near));
+        }
         let extra = Some(filters.join(" AND "));
         let vec_filter = self.build_user_filter(user_id, extra);
 
@@ -284,11 +423,16 @@ impl super::MemoroseEngine {
         let tt = transaction_time.clone();
         let oid = org_id.map(|s| s.to_string());
         let uid = Some(user_id.to_string());
-        let agid = agent_id.map(|s| s.to_string());
+        // The text index filters on a single agent; app lists are filtered after fetch.
+        let agid = agent_id
+            .filter(|_| app_ids.is_none())
+            .map(|s| s.to_string());
+        let mut stage_timings = RetrievalStageTimings::default();
+        let mut stage = std::time::Instant::now();
         let text_future = tokio::task::spawn_blocking(move || {
             // Ensure reader sees latest committed segments before searching
             index.reload().ok();
-            index.search_bitemporal(
+            index.search_bitemporal_scored(
                 &q_text,
                 limit * 2,
                 vt,
@@ -300,44 +444,116 @@ impl super::MemoroseEngine {
             )
         });
 
+        let asset_filter = vec_filter.clone();
         let vector_future = async {
-            if let Some(vector_store) = &self.vector {
-                vector_store
-                    .search("memories", vector, limit * 2, vec_filter)
-                    .await
-            } else {
-                Ok(Vec::new())
+            match &self.vector {
+                Some(vector_store) if vector_mode.uses_memory_vectors() => {
+                    vector_store
+                        .search("memories", vector, limit * 2, vec_filter)
+                        .await
+                }
+                _ => Ok(Vec::new()),
+            }
+        };
+        let asset_future = async {
+            match &self.vector {
+                Some(vector_store) if vector_mode.uses_asset_vectors() => {
+                    vector_store
+                        .search_multi_vector(ASSET_VECTOR_TABLE, vector, limit * 2, asset_filter)
+                        .await
+                }
+                _ => Ok(Vec::new()),
             }
         };
 
-        let (vector_results, text_results) = tokio::join!(vector_future, text_future);
+        let (vector_results, asset_results, text_results) =
+            tokio::join!(vector_future, asset_future, text_future);
 
-        let vector_hits = match vector_results {
-            Ok(hits) => hits,
+        // A missing table is expected on a fresh node with no ingested data (or no
+        // embedded assets). Require both a table-related term AND "not found" to avoid
+        // swallowing real errors.
+        let missing_table_as_empty = |results: Result<Vec<(String, f32)>>| match results {
+            Ok(hits) => Ok(hits),
             Err(e) => {
                 let msg = e.to_string().to_lowercase();
-                // "Table 'memories' not found" is expected on a fresh node with no ingested data.
-                // Require both a table-related term AND "not found" to avoid swallowing real errors.
                 if (msg.contains("table") || msg.contains("no such")) && msg.contains("not found") {
-                    Vec::new()
+                    Ok(Vec::new())
                 } else {
-                    return Err(e);
+                    Err(e)
                 }
             }
         };
+        let vector_hits = missing_table_as_empty(vector_results)?;
+        let asset_hits = missing_table_as_empty(asset_results)?;
 
         let text_hits = text_results??;
+        stage_timings.candidates_ms = RetrievalStageTimings::lap(&mut stage);
+
+        let rrf_k = self.rrf_k();
+        if let Some(diag) = diagnostics.as_deref_mut() {
+            diag.thresholds = RetrievalThresholds {
+                rrf_k,
+                candidate_limit: limit * 3,
+                graph_depth,
+                graph_related_min_weight: self.auto_link_similarity_threshold,
+                min_score: min_score.unwrap_or(DEFAULT_MIN_SCORE),
+                dedup_similarity: DEDUP_SIMILARITY,
+                dedup_cap: (limit * 4).max(20),
+                arbitration_score_gap: ARBITRATION_SCORE_GAP,
+                recency_bias,
+                recency_half_life_hours: self.recency_half_life_hours(),
+                mmr_lambda: None,
+            };
+            diag.vector_hits = vector_hits
+                .iter()
+                .enumerate()
+                .map(|(rank, (id, similarity))| VectorHitDiagnostic {
+                    id: id.clone(),
+                    rank,
+                    // The vector store reports 1 / (1 + distance).
+                    distance: 1.0 / similarity - 1.0,
+                    similarity: *similarity,
+                })
+                .collect();
+            diag.text_hits = text_hits
+                .iter()
+                .enumerate()
+                .map(|(rank, (id, bm25))| TextHitDiagnostic {
+                    id: id.clone(),
+                    rank,
+                    bm25: *bm25,
+                })
+                .collect();
+        }
 
         // RRF Fusion on IDs
-        let k = 60.0;
         let mut rrf_scores: HashMap<String, f32> = HashMap::new();
+        let mut rrf_parts: HashMap<String, (f32, f32)> = HashMap::new();
+        let tracing_rrf = diagnostics.is_some();
 
         for (rank, (id, _sim_score)) in vector_hits.into_iter().enumerate() {
-            *rrf_scores.entry(id).or_default() += 1.0 / (k + rank as f32);
+            let contribution = 1.0 / (rrf_k + rank as f32);
+            if tracing_rrf {
+                rrf_parts.entry(id.clone()).or_default().0 += contribution;
+            }
+            *rrf_scores.entry(id).or_default() += contribution;
+        }
+
+        // Asset hits rank memories by their best-matching image in the joint space.
+        for (rank, (id, _sim_score)) in asset_hits.into_iter().enumerate() {
+            let contribution = 1.0 / (rrf_k + rank as f32);
+            if tracing_rrf {
+                rrf_parts.entry(id.clone()).or_default().0 += contribution;
+            }
+            *rrf_scores.entry(id).or_default() += contribution;
         }
 
-        for (rank, id) in text_hits.into_iter().enumerate() {
-            *rrf_scores.entry(id).or_default() += 1.0 / (k + rank as f32);
+        for (rank, (id, _bm25)) in text_hits.into_iter().enumerate() {
+            let contribution = 1.0 / (rrf_k + rank as f32);
+            if tracing_rrf {
+                rrf_parts.entry(id.clone()).or_default().1 += contribution;
+            }
+            *rrf_scores.entry(id).or_default() += contribution;
         }
 
         // Normalize RRF scores to [0, 1] range so they are compatible with reranker weights
@@ -351,6 +567,21 @@ impl super::MemoroseEngine {
         let mut sorted_ids: Vec<(String, f32)> = rrf_scores.into_iter().collect();
         sorted_ids.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
+        if let Some(diag) = diagnostics.as_deref_mut() {
+            diag.rrf = sorted_ids
+                .iter()
+                .map(|(id, fused)| {
+                    let (vector, text) = rrf_parts.get(id).copied().unwrap_or_default();
+                    RrfContribution {
+                        id: id.clone(),
+                        vector,
+                        text,
+                        fused: *fused,
+                    }
+                })
+                .collect();
+        }
+
         let candidates_to_fetch: Vec<String> = sorted_ids
             .iter()
             .take(limit * 3)
@@ -360,7 +591,7 @@ impl super::MemoroseEngine {
             .fetch_units(user_id, candidates_to_fetch)
             .await?
             .into_iter()
-            .filter(|unit| org_id.map_or(true, |oid| unit.org_id.as_deref() == Some(oid)))
+            .filter(|unit| in_scope(unit))
             .collect();
 
         let mut seeds = Vec::new();
@@ -372,31 +603,75 @@ impl super::MemoroseEngine {
                 .unwrap_or(0.0);
             seeds.push((unit, score));
         }
+        stage_timings.fusion_ms = RetrievalStageTimings::lap(&mut stage);
 
         // Graph Expansion (BFS)
-        let mut expanded_units = self.expand_subgraph(user_id, seeds, graph_depth).await?;
-        if let Some(org_id) = org_id {
-            expanded_units.retain(|(unit, _)| unit.org_id.as_deref() == Some(org_id));
-        }
+        let mut expanded_units = self
+            .expand_subgraph(
+                user_id,
+                seeds,
+                graph_depth,
+                diagnostics
+                    .as_deref_mut()
+                    .map(|diag| &mut diag.graph_expansion),
+            )
+            .await?;
+        expanded_units.retain(|(unit, _)| in_scope(unit));
+        stage_timings.graph_expansion_ms = RetrievalStageTimings::lap(&mut stage);
 
+        if recency_bias > 0.0 {
+            self.apply_recency_bias(&mut expanded_units, recency_bias);
+        }
         expanded_units.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let scores_before_rerank: HashMap<Uuid, f32> = if diagnostics.is_some() {
+            expanded_units
+                .iter()
+                .map(|(unit, score)| (unit.id, *score))
+                .collect()
+        } else {
+            HashMap::new()
+        };
 
         // Time and Importance Reranking
         let final_results = self
             .reranker
             .rerank(query_text, &self.kv_store, expanded_units)
             .await?;
+        stage_timings.rerank_ms = RetrievalStageTimings::lap(&mut stage);
 
         // Default threshold lowered: RRF scores are now normalized to [0,1], and the
         // reranker adds importance (0.2) + recency (0.1) components, so a reasonable
         // cutoff is ~0.3 to keep relevant results while filtering noise.
-        let threshold = min_score.unwrap_or(0.3);
+        let threshold = min_score.unwrap_or(DEFAULT_MIN_SCORE);
+        if let Some(diag) = diagnostics.as_deref_mut() {
+            diag.rerank = final_results
+                .iter()
+                .map(|(unit, after)| {
+                    let before = scores_before_rerank.get(&unit.id).copied().unwrap_or(0.0);
+                    RerankDelta {
+                        id: unit.id,
+                        before,
+                        after: *after,
+                        delta: after - before,
+                    }
+                })
+                .collect();
+            diag.below_min_score = final_results
+                .iter()
+                .filter(|(_, score)| *score < threshold)
+                .map(|(unit, _)| unit.id)
+                .collect();
+        }
         let mut final_results: Vec<_> = final_results
             .into_iter()
             .filter(|(_, score)| *score >= threshold)
             .collect();
 
         if final_results.is_empty() {
+            stage_timings.finalize_ms = RetrievalStageTimings::lap(&mut stage);
+            if let Some(timings) = timings {
+                *timings = stage_timings;
+            }
             return Ok(Vec::new());
         }
 
@@ -413,7 +688,7 @@ impl super::MemoroseEngine {
             let mut is_duplicate = false;
             for (existing_unit, _) in &deduped_results {
                 if let (Some(v1), Some(v2)) = (&unit.embedding, &existing_unit.embedding) {
-                    if cosine_similarity(v1, v2) > 0.92 {
+                    if cosine_similarity(v1, v2) > DEDUP_SIMILARITY {
                         is_duplicate = true;
                         break;
                     }
@@ -421,6 +696,8 @@ impl super::MemoroseEngine {
             }
             if !is_duplicate {
                 deduped_results.push((unit, score));
+            } else if let Some(diag) = diagnostics.as_deref_mut() {
+                diag.deduplicated.push(unit.id);
             }
         }
         final_results = deduped_results;
@@ -436,7 +713,7 @@ impl super::MemoroseEngine {
             let top1_score = results_for_arbitration[0].1;
             let top2_score = results_for_arbitration[1].1;
 
-            if (top1_score - top2_score).abs() < 0.25 {
+            if (top1_score - top2_score).abs() < ARBITRATION_SCORE_GAP {
                 should_arbitrate = true;
             } else {
                 tracing::info!(
@@ -446,7 +723,7 @@ impl super::MemoroseEngine {
             }
         }
 
-        if should_arbitrate {
+        let results = if should_arbitrate {
             tracing::info!(
                 "Executing LLM Arbitration for {} candidates...",
                 results_for_arbitration.len()
@@ -455,29 +732,38 @@ impl super::MemoroseEngine {
                 .iter()
                 .map(|(u, _)| u.clone())
                 .collect();
-            let arbitrated = self
-                .arbitrator
-                .arbitrate(units_to_arbitrate, Some(query_text))
-                .await?;
-
-            let mut arbitrated_results = Vec::new();
-            for unit in arbitrated {
-                if let Some((_, score)) = results_for_arbitration
-                    .iter()
-                    .find(|(u, _)| u.id == unit.id)
-                {
-                    arbitrated_results.push((unit, *score));
-                }
-            }
-            Ok(Self::apply_token_budget_to_scored_memory_units(
-                arbitrated_results,
-                token_budget,
-            ))
+            let arbitrated_results = match self
+                .arbitrate_candidates(user_id, &units_to_arbitrate, query_text, false)
+                .await?
+            {
+                Some((decision, _)) => results_for_arbitration
+                    .into_iter()
+                    .filter(|(u, _)| decision.retained_ids.contains(&u.id))
+                    .collect(),
+                None => results_for_arbitration,
+            };
+            Self::apply_token_budget_to_scored_memory_units(arbitrated_results, token_budget)
         } else {
-            Ok(Self::apply_token_budget_to_scored_memory_units(
-                results_for_arbitration,
-                token_budget,
-            ))
+            Self::apply_token_budget_to_scored_memory_units(results_for_arbitration, token_budget)
+        };
+        stage_timings.finalize_ms = RetrievalStageTimings::lap(&mut stage);
+        if let Some(timings) = timings {
+            *timings = stage_timings;
+        }
+        Ok(results)
+    }
+
+    /// Scale candidate scores by an exponential decay on memory age. `recency_bias`
+    /// blends the decay in: 0 leaves scores untouched, 1 applies it in full.
+    fn apply_recency_bias(&self, candidates: &mut [(MemoryUnit, f32)], recency_bias: f32) {
+        let bias = recency_bias.clamp(0.0, 1.0);
+        let half_life_secs = self.recency_half_life_hours().max(f64::EPSILON) * 3600.0;
+        let now = chrono::Utc::now();
+        for (unit, score) in candidates.iter_mut() {
+            let at = unit.valid_time.unwrap_or(unit.transaction_time);
+            let age_secs = now.signed_duration_since(at).num_seconds().max(0) as f64;
+            let decay = 0.5f64.powf(age_secs / half_life_secs) as f32;
+            *score *= 1.0 - bias + bias * decay;
         }
     }
 
@@ -609,24 +895,105 @@ impl super::MemoroseEngine {
         transaction_time: Option<TimeRange>,
         token_budget: Option<usize>,
     ) -> Result<Vec<(SharedSearchHit, f32)>> {
+        self.search_hybrid_with_shared_explained(
+            user_id,
+            org_id,
+            agent_id,
+            None,
+            None,
+            None,
+            query_text,
+            vector,
+            limit,
+            0,
+            enable_arbitration,
+            min_score,
+            graph_depth,
+            valid_time,
+            transaction_time,
+            token_budget,
+            0.0,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions::default(),
+        )
+        .await
+        .map(|outcome| outcome.results)
+    }
+
+    /// Like `search_hybrid_with_shared_and_token_budget`, additionally reporting why
+    /// arbitration kept what it kept and per-stage diagnostics, as requested by `explain`.
+    /// With `mmr_lambda` set, the final candidates are reordered for topical diversity.
+    pub async fn search_hybrid_with_shared_explained(
+        &self,
+        user_id: &str,
+        org_id: Option<&str>,
+        agent_id: Option<&str>,
+        app_ids: Option<&[String]>,
+        emotions: Option<&[String]>,
+        near: Option<&GeoRadius>,
+        query_text: &str,
+        vector: &[f32],
+        limit: usize,
+        offset: usize,
+        enable_arbitration: bool,
+        min_score: Option<f32>,
+        graph_depth: usize,
+        valid_time: Option<TimeRange>,
+        transaction_time: Option<TimeRange>,
+        token_budget: Option<usize>,
+        recency_bias: f32,
+        mmr_lambda: Option<f32>,
+        vector_mode: VectorSearchMode,
+        explain: SearchExplainOptions,
+    ) -> Result<SharedSearchOutcome> {
+        // Rank everything up to the end of the requested page, plus one hit to tell
+        // whether a further page exists.
+        let window = offset.saturating_add(limit);
+        let mut diagnostics = explain.diagnostics.then(RetrievalDiagnostics::default);
+        let mut timings = RetrievalStageTimings::default();
         let mut combined = self
-            .search_hybrid(
+            .search_hybrid_traced(
                 user_id,
                 org_id,
                 agent_id,
+                app_ids,
+                emotions,
+                near,
                 query_text,
                 vector,
-                limit,
+                window + 1,
                 false,
                 min_score,
                 graph_depth,
                 valid_time.clone(),
                 transaction_time,
+                None,
+                recency_bias,
+                vector_mode,
+                diagnostics.as_mut(),
+                Some(&mut timings),
             )
             .await?
             .into_iter()
             .map(|(unit, score)| (SharedSearchHit::native(unit), score))
             .collect::<Vec<_>>();
+        if let Some(diag) = diagnostics.as_mut() {
+            diag.thresholds.mmr_lambda = mmr_lambda;
+        }
+        let mut stage = std::time::Instant::now();
+
+        combined.extend(
+            self.search_granted_memories(
+                user_id,
+                query_text,
+                vector,
+                window + 1,
+                min_score,
+                valid_time.clone(),
+            )
+            .await?,
+        );
 
         if let Some(org_id) = org_id {
             let org_policy = self.get_org_share_policy(user_id, org_id)?;
@@ -638,7 +1005,7 @@ impl super::MemoroseEngine {
                         agent_id,
                         query_text,
                         vector,
-                        limit,
+                        window + 1,
                         min_score,
                         valid_time,
                     )
@@ -649,9 +1016,24 @@ impl super::MemoroseEngine {
                 combined.extend(org_results);
             }
         }
+        timings.shared_ms = RetrievalStageTimings::lap(&mut stage);
+        if let Some(emotions) = emotions {
+            combined.retain(|(hit, _)| hit.memory_unit().has_any_emotion(emotions));
+        }
+        if let Some(near) = near {
+            combined.retain(|(hit, _)| {
+                hit.memory_unit()
+                    .location
+                    .is_some_and(|location| near.contains(&location))
+            });
+        }
 
         if combined.is_empty() {
-            return Ok(Vec::new());
+            return Ok(SharedSearchOutcome {
+                diagnostics,
+                timings,
+                ..Default::default()
+            });
         }
 
         combined.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
@@ -666,7 +1048,7 @@ impl super::MemoroseEngine {
             let mut is_duplicate = false;
             for (existing, _) in &deduped {
                 if let (Some(v1), Some(v2)) = (&hit.embedding, &existing.embedding) {
-                    if cosine_similarity(v1, v2) > 0.92 {
+                    if cosine_similarity(v1, v2) > DEDUP_SIMILARITY {
                         is_duplicate = true;
                         break;
                     }
@@ -675,58 +1057,248 @@ impl super::MemoroseEngine {
 
             if !is_duplicate {
                 deduped.push((hit, score));
+            } else if let Some(diag) = diagnostics.as_mut() {
+                diag.deduplicated.push(hit.id);
             }
 
-            if deduped.len() >= limit * 2 {
+            if deduped.len() >= window * 2 {
                 break;
             }
         }
 
-        let threshold = min_score.unwrap_or(0.3);
+        let threshold = min_score.unwrap_or(DEFAULT_MIN_SCORE);
+        if let Some(diag) = diagnostics.as_mut() {
+            diag.below_min_score.extend(
+                deduped
+                    .iter()
+                    .filter(|(_, score)| *score < threshold)
+                    .map(|(hit, _)| hit.id),
+            );
+        }
         deduped.retain(|(_, score)| *score >= threshold);
         if deduped.is_empty() {
-            return Ok(Vec::new());
+            timings.finalize_ms += RetrievalStageTimings::lap(&mut stage);
+            return Ok(SharedSearchOutcome {
+                diagnostics,
+                timings,
+                ..Default::default()
+            });
         }
 
-        if deduped.len() > limit * 2 {
-            deduped.truncate(limit * 2);
+        if deduped.len() > window * 2 {
+            deduped.truncate(window * 2);
         }
 
-        let should_arbitrate =
-            enable_arbitration && deduped.len() >= 2 && (deduped[0].1 - deduped[1].1).abs() < 0.25;
+        let should_arbitrate = enable_arbitration
+            && deduped.len() >= 2
+            && (deduped[0].1 - deduped[1].1).abs() < ARBITRATION_SCORE_GAP;
+        if let Some(lambda) = mmr_lambda {
+            deduped = Self::select_by_mmr(deduped, lambda);
+        }
 
         if should_arbitrate {
-            let arbitrated = self
-                .arbitrator
-                .arbitrate(
-                    deduped
+            let candidates = deduped
+                .iter()
+                .map(|(hit, _)| hit.memory_unit().clone())
+                .collect::<Vec<_>>();
+            let outcome = self
+                .arbitrate_candidates(user_id, &candidates, query_text, explain.arbitration)
+                .await?;
+
+            let (final_results, discarded): (Vec<_>, Vec<_>) = match &outcome {
+                Some((decision, _)) => deduped
+                    .into_iter()
+                    .partition(|(hit, _)| decision.retained_ids.contains(&hit.id)),
+                None => (deduped, Vec::new()),
+            };
+            let arbitration = explain.arbitration.then(|| ArbitrationExplanation {
+                arbitrated: outcome.is_some(),
+                cached: outcome.as_ref().is_some_and(|(_, cached)| *cached),
+                reasoning: outcome
+                    .as_ref()
+                    .and_then(|(decision, _)| decision.reasoning.clone()),
+                retained_ids: final_results.iter().map(|(hit, _)| hit.id).collect(),
+                discarded_ids: discarded.iter().map(|(hit, _)| hit.id).collect(),
+            });
+            let (page, has_more) = Self::page_scored_shared_hits(final_results, offset, limit);
+            let results = Self::apply_token_budget_to_scored_shared_hits(page, token_budget);
+            timings.finalize_ms += RetrievalStageTimings::lap(&mut stage);
+            Ok(SharedSearchOutcome {
+                results,
+                arbitration,
+                diagnostics,
+                has_more,
+                timings,
+            })
+        } else {
+            let arbitration =
+                (enable_arbitration && explain.arbitration).then(|| ArbitrationExplanation {
+                    arbitrated: false,
+                    cached: false,
+                    reasoning: deduped.get(1).map(|runner_up| {
+                        format!(
+                            "Top result leads by {:.2}; arbitration was not needed",
+                            deduped[0].1 - runner_up.1
+                        )
+                    }),
+                    retained_ids: deduped
                         .iter()
-                        .map(|(hit, _)| hit.memory_unit().clone())
+                        .skip(offset)
+                        .take(limit)
+                        .map(|(hit, _)| hit.id)
                         .collect(),
-                    Some(query_text),
-                )
-                .await?;
+                    discarded_ids: Vec::new(),
+                });
+            let (page, has_more) = Self::page_scored_shared_hits(deduped, offset, limit);
+            let results = Self::apply_token_budget_to_scored_shared_hits(page, token_budget);
+            timings.finalize_ms += RetrievalStageTimings::lap(&mut stage);
+            Ok(SharedSearchOutcome {
+                results,
+                arbitration,
+                diagnostics,
+                has_more,
+                timings,
+            })
+        }
+    }
 
-            let mut final_results = Vec::new();
-            for unit in arbitrated {
-                if let Some((hit, score)) = deduped
+    /// Reorder ranked hits by Maximal Marginal Relevance: each pick maximises
+    /// `lambda * score - (1 - lambda) * (similarity to the closest hit already picked)`,
+    /// so `lambda = 1` keeps relevance order and lower values trade relevance for
+    /// topical spread. Scores are left as they were.
+    pub(crate) fn select_by_mmr(
+        mut hits: Vec<(SharedSearchHit, f32)>,
+        lambda: f32,
+    ) -> Vec<(SharedSearchHit, f32)> {
+        let lambda = lambda.clamp(0.0, 1.0);
+        let mut selected: Vec<(SharedSearchHit, f32)> = Vec::with_capacity(hits.len());
+        while !hits.is_empty() {
+            let mut best = 0;
+            let mut best_mmr = f32::NEG_INFINITY;
+            for (idx, (hit, score)) in hits.iter().enumerate() {
+                let redundancy = selected
                     .iter()
-                    .find(|(candidate, _)| candidate.id == unit.id)
-                {
-                    final_results.push((hit.clone(), *score));
+                    .filter_map(|(picked, _)| match (&hit.embedding, &picked.embedding) {
+                        (Some(v1), Some(v2)) => Some(cosine_similarity(v1, v2)),
+                        _ => None,
+                    })
+                    .fold(0.0_f32, f32::max);
+                let mmr = lambda * score - (1.0 - lambda) * redundancy;
+                if mmr > best_mmr {
+                    best = idx;
+                    best_mmr = mmr;
                 }
             }
-            Ok(Self::apply_token_budget_to_scored_shared_hits(
-                final_results,
-                token_budget,
-            ))
-        } else {
-            deduped.truncate(limit);
-            Ok(Self::apply_token_budget_to_scored_shared_hits(
-                deduped,
-                token_budget,
-            ))
+            selected.push(hits.remove(best));
+        }
+        selected
+    }
+
+    /// Cut the `offset..offset + limit` page out of ranked hits, reporting whether any
+    /// hits remain past it.
+    fn page_scored_shared_hits(
+        hits: Vec<(SharedSearchHit, f32)>,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<(SharedSearchHit, f32)>, bool) {
+        let has_more = hits.len() > offset.saturating_add(limit);
+        let page = hits.into_iter().skip(offset).take(limit).collect();
+        (page, has_more)
+    }
+
+    /// Greedily pack ranked hits into a context string of at most `max_tokens`, skipping
+    /// any hit that no longer fits. With `summarize_overflow`, whatever was left out is
+    /// summarized by the LLM into the remaining room.
+    pub async fn pack_context(
+        &self,
+        results: &[(SharedSearchHit, f32)],
+        max_tokens: usize,
+        summarize_overflow: bool,
+    ) -> Result<PackedContext> {
+        let mut lines = Vec::new();
+        let mut used_tokens = 0usize;
+        let mut packed_ids = Vec::new();
+        let mut overflow = Vec::new();
+        for (hit, _) in results {
+            let line = format!("- {}", hit.content.trim());
+            let cost = count_tokens(&line);
+            if used_tokens.saturating_add(cost) > max_tokens {
+                overflow.push(hit);
+                continue;
+            }
+            used_tokens += cost;
+            packed_ids.push(hit.id);
+            lines.push(line);
+        }
+
+        let mut overflow_summary = None;
+        let remaining = max_tokens - used_tokens;
+        if summarize_overflow && !overflow.is_empty() && remaining > 0 {
+            if let Some(client) = self.arbitrator.get_llm_client() {
+                let texts = overflow.iter().map(|hit| hit.content.clone()).collect();
+                match client.summarize_group(texts).await {
+                    Ok(response) => {
+                        let summary = truncate_to_tokens(response.data.trim(), remaining);
+                        if !summary.is_empty() {
+                            used_tokens += count_tokens(&summary);
+                            lines.push(summary.clone());
+                            overflow_summary = Some(summary);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to summarize context overflow: {:?}", e),
+                }
+            }
+        }
+
+        Ok(PackedContext {
+            text: lines.join("\n"),
+            max_tokens,
+            used_tokens,
+            packed_ids,
+            overflow_ids: overflow.iter().map(|hit| hit.id).collect(),
+            overflow_summary,
+        })
+    }
+
+    /// Arbitrate `candidates` for `query_text`, reusing a cached decision for the same
+    /// query and candidate set. The flag in the result reports a cache hit; `None`
+    /// means the arbitrator passed everything through.
+    pub(crate) async fn arbitrate_candidates(
+        &self,
+        user_id: &str,
+        candidates: &[MemoryUnit],
+        query_text: &str,
+        explain: bool,
+    ) -> Result<Option<(ArbitrationDecision, bool)>> {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        query_text.trim().hash(&mut hasher);
+        let mut candidate_ids = candidates.iter().map(|unit| unit.id).collect::<Vec<_>>();
+        candidate_ids.sort();
+        let cache_key = crate::graph::CacheKey::Arbitration {
+            user_id: user_id.to_string(),
+            query_hash: hasher.finish(),
+            candidate_ids,
+        };
+
+        if let Some(decision) = self.query_cache.get_arbitration(&cache_key).await {
+            // A decision cached without reasoning cannot answer an explain request.
+            if !explain || decision.reasoning.is_some() {
+                return Ok(Some((decision, true)));
+            }
         }
+
+        let decision = self
+            .arbitrator
+            .decide_arbitration(candidates, Some(query_text), explain)
+            .await?;
+        if let Some(decision) = &decision {
+            self.query_cache
+                .put_arbitration(cache_key, decision.clone())
+                .await;
+        }
+        Ok(decision.map(|decision| (decision, false)))
     }
 
     pub async fn search_text(
@@ -791,6 +1363,19 @@ impl super::MemoroseEngine {
                 .or_insert((SharedSearchHit::native(unit), score));
         }
 
+        for (rank, unit) in self
+            .search_granted_memories_text(user_id, query, limit, time_range.clone())
+            .await?
+            .into_iter()
+            .enumerate()
+        {
+            let score = 0.8 / (k + rank as f32);
+            combined_scores
+                .entry(unit.id)
+                .and_modify(|(_, existing_score)| *existing_score += score)
+                .or_insert((SharedSearchHit::native(unit), score));
+        }
+
         if let Some(org_id) = org_id {
             let org_policy = self.get_org_share_policy(user_id, org_id)?;
             if org_policy.consume {
@@ -859,3 +1444,20 @@ impl super::MemoroseEngine {
         self.arbitrator.consolidate(units).await
     }
 }
+
+/// Longest whitespace-delimited prefix of `text` that fits in `max_tokens`.
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let mut truncated = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if truncated.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", truncated, word)
+        };
+        if count_tokens(&candidate) > max_tokens {
+            break;
+        }
+        truncated = candidate;
+    }
+    truncated
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use crate::self_eval::SelfEvalReport;
+
+/// Self-evaluation runs kept; older ones are dropped first.
+const MAX_SELF_EVAL_REPORTS: usize = 500;
+const SELF_EVAL_PREFIX: &str = "self_eval:";
+
+impl super::MemoroseEngine {
+    // ── Retrieval self-evaluation ───────────────────────────────────
+
+    /// Reports sort newest first: the key carries the run time counted down from `u64::MAX`.
+    fn self_eval_key(report: &SelfEvalReport) -> String {
+        let millis = report.run_at.timestamp_millis().max(0) as u64;
+        format!("{}{:020}", SELF_EVAL_PREFIX, u64::MAX - millis)
+    }
+
+    /// Store a self-evaluation run on this node, dropping the oldest past the cap.
+    pub fn record_self_eval_report(&self, report: &SelfEvalReport) -> Result<()> {
+        let system_kv = self.system_kv();
+        system_kv.put(
+            Self::self_eval_key(report).as_bytes(),
+            &serde_json::to_vec(report)?,
+        )?;
+        for (key, _) in system_kv
+            .scan(SELF_EVAL_PREFIX.as_bytes())?
+            .into_iter()
+            .skip(MAX_SELF_EVAL_REPORTS)
+        {
+            system_kv.delete(&key)?;
+        }
+        Ok(())
+    }
+
+    /// The most recent self-evaluation runs, newest first.
+    pub fn list_self_eval_reports(&self, limit: usize) -> Result<Vec<SelfEvalReport>> {
+        Ok(self
+            .system_kv()
+            .scan_limited(SELF_EVAL_PREFIX.as_bytes(), limit)?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+}
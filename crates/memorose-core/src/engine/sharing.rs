@@ -0,0 +1,240 @@
+use super::types::SharedSearchHit;
+use anyhow::Result;
+use memorose_common::{
+    GroupMembershipUpdate, MemoryShareGrant, MemoryUnit, ShareGrantee, TimeRange,
+};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Shared memories rank a little below the caller's own, like organization knowledge.
+const SHARED_MEMORY_SCORE_FACTOR: f32 = 0.8;
+
+impl super::MemoroseEngine {
+    // ── Sharing / ACL ───────────────────────────────────────────────
+
+    fn share_grant_key(owner_user_id: &str, grant_id: Uuid) -> String {
+        format!("acl:grant:{}:{}", owner_user_id, grant_id)
+    }
+
+    fn share_grantee_key(grantee: &ShareGrantee, grant_id: Uuid) -> String {
+        match grantee {
+            ShareGrantee::User(user_id) => format!("acl:grantee:user:{}:{}", user_id, grant_id),
+            ShareGrantee::Group(group_id) => {
+                format!("acl:grantee:group:{}:{}", group_id, grant_id)
+            }
+        }
+    }
+
+    fn group_member_key(group_id: &str, user_id: &str) -> String {
+        format!("acl:group:{}:member:{}", group_id, user_id)
+    }
+
+    fn member_group_key(user_id: &str, group_id: &str) -> String {
+        format!("acl:member:{}:group:{}", user_id, group_id)
+    }
+
+    fn scan_grants(&self, prefix: &str) -> Result<Vec<MemoryShareGrant>> {
+        Ok(self
+            .system_kv()
+            .scan(prefix.as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+
+    /// Store a grant along with the reverse index its grantee is resolved through.
+    pub fn put_share_grant(&self, grant: &MemoryShareGrant) -> Result<()> {
+        let value = serde_json::to_vec(grant)?;
+        let kv = self.system_kv();
+        kv.put(
+            Self::share_grant_key(&grant.owner_user_id, grant.id).as_bytes(),
+            &value,
+        )?;
+        kv.put(
+            Self::share_grantee_key(&grant.grantee, grant.id).as_bytes(),
+            &value,
+        )
+    }
+
+    /// Remove a grant. Returns false when `owner_user_id` has no such grant.
+    pub fn revoke_share_grant(&self, owner_user_id: &str, grant_id: Uuid) -> Result<bool> {
+        let Some(grant) = self.get_share_grant(owner_user_id, grant_id)? else {
+            return Ok(false);
+        };
+        let kv = self.system_kv();
+        kv.delete(Self::share_grantee_key(&grant.grantee, grant_id).as_bytes())?;
+        kv.delete(Self::share_grant_key(owner_user_id, grant_id).as_bytes())?;
+        Ok(true)
+    }
+
+    pub fn get_share_grant(
+        &self,
+        owner_user_id: &str,
+        grant_id: Uuid,
+    ) -> Result<Option<MemoryShareGrant>> {
+        let key = Self::share_grant_key(owner_user_id, grant_id);
+        Ok(self
+            .system_kv()
+            .get(key.as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    /// Grants `owner_user_id` has handed out.
+    pub fn list_share_grants(&self, owner_user_id: &str) -> Result<Vec<MemoryShareGrant>> {
+        let mut grants = self.scan_grants(&format!("acl:grant:{}:", owner_user_id))?;
+        grants.sort_by_key(|grant| grant.created_at);
+        Ok(grants)
+    }
+
+    /// Grants that let `user_id` read someone else's memory, directly or through a group.
+    pub fn list_grants_visible_to(&self, user_id: &str) -> Result<Vec<MemoryShareGrant>> {
+        let mut grants = self.scan_grants(&format!("acl:grantee:user:{}:", user_id))?;
+        for group_id in self.list_user_groups(user_id)? {
+            grants.extend(self.scan_grants(&format!("acl:grantee:group:{}:", group_id))?);
+        }
+        let mut seen = HashSet::new();
+        grants.retain(|grant| grant.owner_user_id != user_id && seen.insert(grant.id));
+        grants.sort_by_key(|grant| grant.created_at);
+        Ok(grants)
+    }
+
+    pub fn apply_group_membership_update(&self, update: &GroupMembershipUpdate) -> Result<()> {
+        let kv = self.system_kv();
+        let member_key = Self::group_member_key(&update.group_id, &update.user_id);
+        let group_key = Self::member_group_key(&update.user_id, &update.group_id);
+        if update.member {
+            kv.put(member_key.as_bytes(), b"")?;
+            kv.put(group_key.as_bytes(), b"")
+        } else {
+            kv.delete(member_key.as_bytes())?;
+            kv.delete(group_key.as_bytes())
+        }
+    }
+
+    pub fn list_group_members(&self, group_id: &str) -> Result<Vec<String>> {
+        let prefix = format!("acl:group:{}:member:", group_id);
+        self.scan_key_suffixes(&prefix)
+    }
+
+    pub fn list_user_groups(&self, user_id: &str) -> Result<Vec<String>> {
+        let prefix = format!("acl:member:{}:group:", user_id);
+        self.scan_key_suffixes(&prefix)
+    }
+
+    pub(super) fn scan_key_suffixes(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .system_kv()
+            .scan(prefix.as_bytes())?
+            .into_iter()
+            .filter_map(|(key, _)| {
+                String::from_utf8(key)
+                    .ok()
+                    .and_then(|key| key.strip_prefix(prefix).map(str::to_string))
+            })
+            .collect())
+    }
+
+    /// Whether `user_id` may read `unit`: it is their own, or a grant covers it.
+    pub fn can_read_memory_unit(&self, user_id: &str, unit: &MemoryUnit) -> Result<bool> {
+        if unit.user_id == user_id {
+            return Ok(true);
+        }
+        Ok(self
+            .list_grants_visible_to(user_id)?
+            .iter()
+            .any(|grant| grant.covers(unit)))
+    }
+
+    /// Search the memories other users have shared with `user_id`. Each owner's memory
+    /// is searched on its own and only hits covered by a grant are kept.
+    pub async fn search_granted_memories(
+        &self,
+        user_id: &str,
+        query_text: &str,
+        vector: &[f32],
+        limit: usize,
+        min_score: Option<f32>,
+        valid_time: Option<TimeRange>,
+    ) -> Result<Vec<(SharedSearchHit, f32)>> {
+        let mut grants_by_owner: HashMap<String, Vec<MemoryShareGrant>> = HashMap::new();
+        for grant in self.list_grants_visible_to(user_id)? {
+            grants_by_owner
+                .entry(grant.owner_user_id.clone())
+                .or_default()
+                .push(grant);
+        }
+
+        let mut hits = Vec::new();
+        for (owner_user_id, grants) in grants_by_owner {
+            let owner_hits = self
+                .search_hybrid_traced(
+                    &owner_user_id,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    query_text,
+                    vector,
+                    limit,
+                    false,
+                    min_score,
+                    0,
+                    valid_time.clone(),
+                    None,
+                    None,
+                    0.0,
+                    super::types::VectorSearchMode::Text,
+                    None,
+                    None,
+                )
+                .await?;
+            hits.extend(
+                owner_hits
+                    .into_iter()
+                    .filter(|(unit, _)| grants.iter().any(|grant| grant.covers(unit)))
+                    .map(|(unit, score)| {
+                        (
+                            SharedSearchHit::native(unit),
+                            score * SHARED_MEMORY_SCORE_FACTOR,
+                        )
+                    }),
+            );
+        }
+        hits.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// Text-only counterpart of `search_granted_memories`.
+    pub async fn search_granted_memories_text(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+        time_range: Option<TimeRange>,
+    ) -> Result<Vec<MemoryUnit>> {
+        let mut units = Vec::new();
+        let mut owners = HashSet::new();
+        let grants = self.list_grants_visible_to(user_id)?;
+        for grant in &grants {
+            if !owners.insert(grant.owner_user_id.clone()) {
+                continue;
+            }
+            units.extend(
+                self.search_text(
+                    &grant.owner_user_id,
+                    query,
+                    limit,
+                    false,
+                    time_range.clone(),
+                )
+                .await?
+                .into_iter()
+                .filter(|unit| grants.iter().any(|grant| grant.covers(unit))),
+            );
+        }
+        units.truncate(limit);
+        Ok(units)
+    }
+}
@@ -2,9 +2,21 @@ use anyhow::Result;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use std::path::PathBuf;
+use memorose_common::config::StorageBackend;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::types::{SnapshotFileEntry, SnapshotManifest, SnapshotVerification};
+
+/// Name of the manifest entry written last into every snapshot archive.
+const SNAPSHOT_MANIFEST_PATH: &str = "MANIFEST.json";
+const SNAPSHOT_MANIFEST_VERSION: u32 = 1;
 
 impl super::MemoroseEngine {
+    /// Write the shard's stores to a gzipped tar at `output_path`, with a manifest of
+    /// per-file checksums inside and a `sha256sum`-style `<output_path>.sha256` next to it.
     pub async fn export_snapshot(&self, output_path: PathBuf) -> Result<()> {
         let engine = self.clone();
         tokio::task::spawn_blocking(move || {
@@ -30,25 +42,70 @@ impl super::MemoroseEngine {
             })?;
             let enc = GzEncoder::new(file, Compression::default());
             let mut tar = tar::Builder::new(enc);
+            let mut files = Vec::new();
 
             let root = &engine.root_path;
             tracing::info!("Root path for snapshot: {:?}", root);
 
-            if root.join("rocksdb").exists() {
+            if engine.storage_config.backend == StorageBackend::Postgres {
+                // Ship the Postgres rows as a RocksDB copy so snapshots keep one format;
+                // the receiving node imports it when it reopens its store.
+                tracing::info!("Exporting Postgres key-value rows to tar...");
+                let export_dir = tempfile::tempdir()?;
+                let export_root = export_dir.path().to_path_buf();
+                engine.kv_store.checkpoint(&export_root.join("rocksdb"))?;
+                engine.append_dir_to_tar(&mut tar, &export_root, "rocksdb", &mut files)?;
+            } else if root.join("rocksdb").exists() {
                 tracing::info!("Adding rocksdb to tar...");
-                engine.append_dir_to_tar(&mut tar, root, "rocksdb")?;
+                engine.append_dir_to_tar(&mut tar, root, "rocksdb", &mut files)?;
             }
             if root.join("lancedb").exists() {
                 tracing::info!("Adding lancedb to tar...");
-                engine.append_dir_to_tar(&mut tar, root, "lancedb")?;
+                engine.append_dir_to_tar(&mut tar, root, "lancedb", &mut files)?;
             }
             if root.join("tantivy").exists() {
                 tracing::info!("Adding tantivy to tar...");
-                engine.append_dir_to_tar(&mut tar, root, "tantivy")?;
+                engine.append_dir_to_tar(&mut tar, root, "tantivy", &mut files)?;
             }
 
-            tar.finish()
-                .map_err(|e| anyhow::anyhow!("Tar finish failed: {}", e))?;
+            let manifest = SnapshotManifest {
+                version: SNAPSHOT_MANIFEST_VERSION,
+                created_at: chrono::Utc::now(),
+                content_sha256: content_sha256(&files),
+                files,
+            };
+            let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(manifest_json.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+            tar.append_data(
+                &mut header,
+                SNAPSHOT_MANIFEST_PATH,
+                manifest_json.as_slice(),
+            )?;
+
+            let file = tar
+                .into_inner()
+                .map_err(|e| anyhow::anyhow!("Tar finish failed: {}", e))?
+                .finish()?;
+            file.sync_all()?;
+
+            let archive_sha256 = sha256_file(&output_path)?;
+            let file_name = output_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            std::fs::write(
+                checksum_path(&output_path),
+                format!("{}  {}\n", archive_sha256, file_name),
+            )?;
+            tracing::info!(
+                "Snapshot {:?} holds {} files, sha256 {}",
+                output_path,
+                manifest.files.len(),
+                archive_sha256
+            );
             Ok(())
         })
         .await?
@@ -59,6 +116,7 @@ impl super::MemoroseEngine {
         tar: &mut tar::Builder<W>,
         root: &PathBuf,
         dir_name: &str,
+        files: &mut Vec<SnapshotFileEntry>,
     ) -> Result<()> {
         let dir_path = root.join(dir_name);
         for entry in walkdir::WalkDir::new(&dir_path) {
@@ -78,7 +136,7 @@ impl super::MemoroseEngine {
             let path = entry.path();
             if path.is_file() {
                 let rel_path = path.strip_prefix(root)?;
-                let mut file = match std::fs::File::open(path) {
+                let file = match std::fs::File::open(path) {
                     Ok(f) => f,
                     Err(e) => {
                         if e.kind() == std::io::ErrorKind::NotFound {
@@ -87,12 +145,35 @@ impl super::MemoroseEngine {
                         return Err(anyhow::anyhow!("Failed to open file {:?}: {}", path, e));
                     }
                 };
-                tar.append_file(rel_path, &mut file)?;
+                let metadata = file.metadata()?;
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata(&metadata);
+                let mut reader = HashingReader::new(file.take(metadata.len()));
+                tar.append_data(&mut header, rel_path, &mut reader)?;
+                if reader.bytes != metadata.len() {
+                    return Err(anyhow::anyhow!(
+                        "File {:?} shrank while it was being archived",
+                        path
+                    ));
+                }
+                files.push(SnapshotFileEntry {
+                    path: rel_path.to_string_lossy().into_owned(),
+                    size: reader.bytes,
+                    sha256: format!("{:x}", reader.hasher.finalize()),
+                });
             }
         }
         Ok(())
     }
 
+    /// Check a snapshot archive against its manifest and, when present, the `.sha256`
+    /// file next to it.
+    pub async fn verify_snapshot(snapshot_path: PathBuf) -> Result<SnapshotVerification> {
+        tokio::task::spawn_blocking(move || verify_snapshot_file(&snapshot_path)).await?
+    }
+
+    /// Unpack a snapshot archive into `target_dir`, replacing it. Archives that fail
+    /// verification are rejected before anything is removed.
     pub async fn restore_from_snapshot(snapshot_path: PathBuf, target_dir: PathBuf) -> Result<()> {
         tracing::info!(
             "Restoring snapshot from {:?} to {:?}",
@@ -100,6 +181,21 @@ impl super::MemoroseEngine {
             target_dir
         );
 
+        let verification = Self::verify_snapshot(snapshot_path.clone()).await?;
+        if !verification.is_intact() {
+            return Err(anyhow::anyhow!(
+                "Snapshot {:?} failed verification: {}",
+                snapshot_path,
+                verification_failure(&verification)
+            ));
+        }
+        if !verification.has_manifest {
+            tracing::warn!(
+                "Snapshot {:?} has no manifest; restoring it unverified",
+                snapshot_path
+            );
+        }
+
         if target_dir.exists() {
             std::fs::remove_dir_all(&target_dir)?;
         }
@@ -110,7 +206,150 @@ impl super::MemoroseEngine {
         let mut archive = tar::Archive::new(dec);
 
         archive.unpack(&target_dir)?;
+        let _ = std::fs::remove_file(target_dir.join(SNAPSHOT_MANIFEST_PATH));
 
         Ok(())
     }
 }
+
+/// Counts and hashes everything read through it.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<R> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.bytes += read as u64;
+        Ok(read)
+    }
+}
+
+fn checksum_path(snapshot_path: &Path) -> PathBuf {
+    let mut name = snapshot_path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut reader = HashingReader::new(std::fs::File::open(path)?);
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    Ok(format!("{:x}", reader.hasher.finalize()))
+}
+
+/// One digest over the whole file list, so a manifest cannot be edited entry by entry.
+fn content_sha256(files: &[SnapshotFileEntry]) -> String {
+    let mut sorted: Vec<&SnapshotFileEntry> = files.iter().collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut hasher = Sha256::new();
+    for file in sorted {
+        hasher.update(format!("{}\0{}\0{}\n", file.path, file.size, file.sha256).as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn verify_snapshot_file(snapshot_path: &Path) -> Result<SnapshotVerification> {
+    let archive_sha256 = sha256_file(snapshot_path)?;
+    let archive_checksum_matches = match std::fs::read_to_string(checksum_path(snapshot_path)) {
+        Ok(contents) => Some(
+            contents
+                .split_whitespace()
+                .next()
+                .is_some_and(|expected| expected.eq_ignore_ascii_case(&archive_sha256)),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut archive = tar::Archive::new(GzDecoder::new(std::fs::File::open(snapshot_path)?));
+    let mut manifest: Option<SnapshotManifest> = None;
+    let mut actual: BTreeMap<String, SnapshotFileEntry> = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        if path == SNAPSHOT_MANIFEST_PATH {
+            let mut raw = Vec::new();
+            entry.read_to_end(&mut raw)?;
+            manifest = Some(serde_json::from_slice(&raw).map_err(|e| {
+                anyhow::anyhow!("Snapshot manifest in {:?} is invalid: {}", snapshot_path, e)
+            })?);
+            continue;
+        }
+        let mut reader = HashingReader::new(&mut entry);
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        actual.insert(
+            path.clone(),
+            SnapshotFileEntry {
+                path,
+                size: reader.bytes,
+                sha256: format!("{:x}", reader.hasher.finalize()),
+            },
+        );
+    }
+
+    let mut verification = SnapshotVerification {
+        archive_sha256,
+        archive_checksum_matches,
+        has_manifest: manifest.is_some(),
+        files: actual.len(),
+        bytes: actual.values().map(|file| file.size).sum(),
+        ..Default::default()
+    };
+    let Some(manifest) = manifest else {
+        return Ok(verification);
+    };
+
+    verification.content_checksum_matches =
+        content_sha256(&manifest.files) == manifest.content_sha256;
+    let mut expected: BTreeMap<&str, &SnapshotFileEntry> = BTreeMap::new();
+    for file in &manifest.files {
+        expected.insert(file.path.as_str(), file);
+        match actual.get(&file.path) {
+            Some(found) if found == file => {}
+            Some(_) => verification.mismatched.push(file.path.clone()),
+            None => verification.missing.push(file.path.clone()),
+        }
+    }
+    verification.unexpected = actual
+        .keys()
+        .filter(|path| !expected.contains_key(path.as_str()))
+        .cloned()
+        .collect();
+    Ok(verification)
+}
+
+fn verification_failure(verification: &SnapshotVerification) -> String {
+    let mut problems = Vec::new();
+    if verification.archive_checksum_matches == Some(false) {
+        problems.push("archive checksum mismatch".to_string());
+    }
+    if verification.has_manifest && !verification.content_checksum_matches {
+        problems.push("manifest checksum mismatch".to_string());
+    }
+    for (label, paths) in [
+        ("corrupted", &verification.mismatched),
+        ("missing", &verification.missing),
+        ("unexpected", &verification.unexpected),
+    ] {
+        if !paths.is_empty() {
+            problems.push(format!("{} {} files", paths.len(), label));
+        }
+    }
+    problems.join(", ")
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::{MemoryStream, StreamSegmentBoundary};
+use uuid::Uuid;
+
+/// Boundaries kept on a stream record; older ones are dropped first.
+const MAX_STREAM_SEGMENT_BOUNDARIES: usize = 200;
+
+impl super::MemoroseEngine {
+    // ── Streams ─────────────────────────────────────────────────────
+
+    fn memory_stream_key(user_id: &str, stream_id: Uuid) -> String {
+        format!("stream_meta:{}:{}", user_id, stream_id)
+    }
+
+    /// Create or replace a stream record.
+    pub fn put_memory_stream(&self, stream: &MemoryStream) -> Result<()> {
+        self.system_kv().put(
+            Self::memory_stream_key(&stream.user_id, stream.id).as_bytes(),
+            &serde_json::to_vec(stream)?,
+        )
+    }
+
+    pub fn get_memory_stream(
+        &self,
+        user_id: &str,
+        stream_id: Uuid,
+    ) -> Result<Option<MemoryStream>> {
+        Ok(self
+            .system_kv()
+            .get(Self::memory_stream_key(user_id, stream_id).as_bytes())?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+
+    /// Streams `user_id` has created, newest first. `app_id` keeps only that app's
+    /// streams; archived streams are left out unless `include_archived` is set.
+    pub fn list_memory_streams(
+        &self,
+        user_id: &str,
+        app_id: Option<&str>,
+        include_archived: bool,
+    ) -> Result<Vec<MemoryStream>> {
+        let mut streams: Vec<MemoryStream> = self
+            .system_kv()
+            .scan(format!("stream_meta:{}:", user_id).as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<MemoryStream>(&value).ok())
+            .filter(|stream| app_id.is_none() || stream.app_id.as_deref() == app_id)
+            .filter(|stream| include_archived || !stream.is_archived())
+            .collect();
+        streams.sort_by(|a, b| b.transaction_time.cmp(&a.transaction_time));
+        Ok(streams)
+    }
+
+    /// Append segment boundaries found by the worker and advance the stream's last event
+    /// time. Streams without a record get one, owned by `app_id`. Boundaries already
+    /// recorded for the same event are skipped, so a deferred batch can be segmented again.
+    pub fn record_stream_segments(
+        &self,
+        user_id: &str,
+        stream_id: Uuid,
+        app_id: Option<String>,
+        boundaries: Vec<StreamSegmentBoundary>,
+        last_event_at: DateTime<Utc>,
+    ) -> Result<MemoryStream> {
+        let mut stream = match self.get_memory_stream(user_id, stream_id)? {
+            Some(stream) => stream,
+            None => {
+                let mut stream = MemoryStream::new(user_id.to_string(), app_id, None);
+                stream.id = stream_id;
+                stream
+            }
+        };
+
+        let before = stream.segment_boundaries.len();
+        for boundary in boundaries {
+            if !stream
+                .segment_boundaries
+                .iter()
+                .any(|existing| existing.event_id == boundary.event_id)
+            {
+                stream.segment_boundaries.push(boundary);
+            }
+        }
+        let added = stream.segment_boundaries.len() != before;
+        if added {
+            stream
+                .segment_boundaries
+                .sort_by_key(|boundary| boundary.at);
+            let excess = stream
+                .segment_boundaries
+                .len()
+                .saturating_sub(MAX_STREAM_SEGMENT_BOUNDARIES);
+            stream.segment_boundaries.drain(..excess);
+        }
+
+        let advanced = stream.last_event_at.is_none_or(|at| last_event_at > at);
+        if advanced {
+            stream.last_event_at = Some(last_event_at);
+        }
+        if added || advanced {
+            stream.updated_at = Utc::now();
+            self.put_memory_stream(&stream)?;
+        }
+        Ok(stream)
+    }
+}
@@ -1,7 +1,15 @@
 use anyhow::Result;
-use memorose_common::{GraphEdge, MemoryDomain, RelationType};
+use chrono::{DateTime, Utc};
+use memorose_common::config::PromptKind;
+use memorose_common::{
+    GraphEdge, MemoryDomain, MemoryUnit, RelationType, TaskDeadlineEventKind, TaskStatus,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
 use uuid::Uuid;
 
+/// Block reason carried by auto-planned milestones until their plan is approved.
+pub(crate) const PLAN_APPROVAL_REASON: &str = "awaiting plan approval";
+
 impl super::MemoroseEngine {
     pub fn auto_plan_goal(
         &self,
@@ -16,6 +24,8 @@ impl super::MemoroseEngine {
         Box::pin(async move {
             tracing::info!("Auto-planning goal {} (depth {})", goal_id, depth);
 
+            let system_prompt =
+                self.configured_prompt(PromptKind::Decomposition, agent_id.as_deref());
             let milestones = self
                 .arbitrator
                 .decompose_goal(
@@ -24,8 +34,10 @@ impl super::MemoroseEngine {
                     agent_id.as_deref(),
                     stream_id,
                     &goal_content,
+                    system_prompt.as_deref(),
                 )
                 .await?;
+            let milestones = self.apply_auto_planner_policy(milestones);
 
             if milestones.is_empty() {
                 return Ok(());
@@ -46,13 +58,118 @@ impl super::MemoroseEngine {
                     RelationType::IsSubTaskOf,
                     1.0,
                 );
-                self.graph.add_edge(&edge).await?;
+                self.add_graph_edge(&edge).await?;
             }
 
             Ok(())
         })
     }
 
+    /// Trim a decomposition to the subtask budget, dropping dependencies on milestones
+    /// that were cut, and park the survivors when plans require approval.
+    fn apply_auto_planner_policy(
+        &self,
+        mut milestones: Vec<memorose_common::L3Task>,
+    ) -> Vec<memorose_common::L3Task> {
+        milestones.truncate(self.auto_planner_policy.max_subtasks);
+        let kept = milestones
+            .iter()
+            .map(|ms| ms.task_id)
+            .collect::<HashSet<_>>();
+        for ms in &mut milestones {
+            ms.dependencies.retain(|dep| kept.contains(dep));
+            if self.auto_planner_policy.require_approval {
+                ms.status = TaskStatus::Blocked(PLAN_APPROVAL_REASON.into());
+            }
+        }
+        milestones
+    }
+
+    /// Stored milestones of a goal, or a dry-run decomposition when none exist yet.
+    /// Returns `None` if the goal is unknown.
+    pub async fn get_goal_plan(
+        &self,
+        user_id: &str,
+        goal_id: Uuid,
+    ) -> Result<Option<super::GoalPlan>> {
+        let Some(goal) = self.get_memory_unit(user_id, goal_id).await? else {
+            return Ok(None);
+        };
+        if Self::goal_index_key(&goal).is_none() {
+            return Ok(None);
+        }
+        let planning_state = self
+            .system_kv()
+            .get(format!("planning:{}", goal_id).as_bytes())?
+            .map(|state| String::from_utf8_lossy(&state).into_owned());
+
+        let mut milestones = self
+            .get_l3_subtask_map(user_id, &[goal_id], 1)
+            .await?
+            .remove(&goal_id)
+            .unwrap_or_default();
+        let status = if milestones.is_empty() {
+            let system_prompt =
+                self.configured_prompt(PromptKind::Decomposition, goal.agent_id.as_deref());
+            milestones = self.apply_auto_planner_policy(
+                self.arbitrator
+                    .decompose_goal(
+                        goal.org_id.as_deref(),
+                        user_id,
+                        goal.agent_id.as_deref(),
+                        goal.stream_id,
+                        &goal.content,
+                        system_prompt.as_deref(),
+                    )
+                    .await?,
+            );
+            for ms in &mut milestones {
+                ms.parent_id = Some(goal_id);
+            }
+            super::GoalPlanStatus::Preview
+        } else if milestones.iter().any(Self::awaits_plan_approval) {
+            super::GoalPlanStatus::AwaitingApproval
+        } else {
+            super::GoalPlanStatus::Approved
+        };
+
+        Ok(Some(super::GoalPlan {
+            goal_id,
+            status,
+            planning_state,
+            milestones,
+        }))
+    }
+
+    fn awaits_plan_approval(task: &memorose_common::L3Task) -> bool {
+        matches!(&task.status, TaskStatus::Blocked(reason) if reason == PLAN_APPROVAL_REASON)
+    }
+
+    /// Status updates that release a goal's parked milestones back to `Pending`.
+    pub async fn goal_plan_approval_updates(
+        &self,
+        user_id: &str,
+        goal_id: Uuid,
+    ) -> Result<Vec<memorose_common::L3TaskStatusUpdate>> {
+        let now = Utc::now();
+        Ok(self
+            .get_l3_subtask_map(user_id, &[goal_id], 1)
+            .await?
+            .remove(&goal_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(Self::awaits_plan_approval)
+            .map(|task| memorose_common::L3TaskStatusUpdate {
+                user_id: user_id.to_string(),
+                task_id: task.task_id,
+                status: TaskStatus::Pending,
+                progress: None,
+                result_summary: None,
+                updated_at: now,
+            })
+            .collect())
+    }
+
     pub async fn store_l3_task(&self, task: &memorose_common::L3Task) -> Result<()> {
         let key = format!("l3:task:{}:{}", task.user_id, task.task_id);
         let val = serde_json::to_vec(task)?;
@@ -86,34 +203,536 @@ impl super::MemoroseEngine {
         Ok(tasks)
     }
 
-    /// Agent Action Driver: Get tasks that are Pending and have all dependencies Completed.
-    pub async fn get_ready_l3_tasks(&self, user_id: &str) -> Result<Vec<memorose_common::L3Task>> {
-        let all_tasks = self.list_l3_tasks(user_id).await?;
+    /// Secondary index entry for goal units: `l3_idx:{user_id}:{stream_id}:{id}`.
+    /// Returns `None` for units that are not goals.
+    pub(crate) fn goal_index_key(unit: &MemoryUnit) -> Option<String> {
+        (unit.level == 3 && !Self::is_profile_unit(unit))
+            .then(|| format!("l3_idx:{}:{}:{}", unit.user_id, unit.stream_id, unit.id))
+    }
 
-        // Build a map of task_id -> status for quick dependency checking
-        let status_map: std::collections::HashMap<Uuid, memorose_common::TaskStatus> = all_tasks
-            .iter()
-            .map(|t| (t.task_id, t.status.clone()))
+    /// Backfill the goal index once per user for data written before it existed.
+    async fn ensure_goal_index(&self, user_id: &str) -> Result<()> {
+        let marker_key = format!("l3_idx_ready:{}", user_id);
+        if self.system_kv().get(marker_key.as_bytes())?.is_some() {
+            return Ok(());
+        }
+
+        let mut batch = rocksdb::WriteBatch::default();
+        for unit in self.list_memory_units_global(Some(user_id)).await? {
+            if let Some(key) = Self::goal_index_key(&unit) {
+                batch.put(
+                    key.as_bytes(),
+                    unit.transaction_time.timestamp_micros().to_le_bytes(),
+                );
+            }
+        }
+        let kv = self.kv_store.clone();
+        tokio::task::spawn_blocking(move || kv.write_batch(batch)).await??;
+        self.system_kv().put(marker_key.as_bytes(), b"1")?;
+        Ok(())
+    }
+
+    /// List a user's goal units through the `l3_idx` index instead of scanning every unit.
+    /// Stale index entries for deleted or hidden goals are skipped on read.
+    pub async fn list_goal_units(
+        &self,
+        user_id: &str,
+        stream_id: Option<Uuid>,
+    ) -> Result<Vec<MemoryUnit>> {
+        self.ensure_goal_index(user_id).await?;
+
+        let prefix = match stream_id {
+            Some(stream_id) => format!("l3_idx:{}:{}:", user_id, stream_id),
+            None => format!("l3_idx:{}:", user_id),
+        };
+        let kv = self.kv_store.clone();
+        let pairs = tokio::task::spawn_blocking(move || kv.scan(prefix.as_bytes())).await??;
+        let ids = pairs
+            .into_iter()
+            .filter_map(|(key, _)| {
+                let key = String::from_utf8(key).ok()?;
+                key.rsplit(':').next().map(str::to_string)
+            })
             .collect();
 
-        let mut ready_tasks = Vec::new();
-        for task in all_tasks {
-            if task.status == memorose_common::TaskStatus::Pending {
-                let mut all_deps_completed = true;
-                for dep_id in &task.dependencies {
-                    if let Some(dep_status) = status_map.get(dep_id) {
-                        if *dep_status != memorose_common::TaskStatus::Completed {
-                            all_deps_completed = false;
-                            break;
+        let mut units = self.fetch_units(user_id, ids).await?;
+        units.retain(|unit| stream_id.is_none_or(|stream_id| unit.stream_id == stream_id));
+        Ok(units)
+    }
+
+    /// Walk `IsSubTaskOf` edges breadth-first from `root_ids`, returning each parent's
+    /// direct subtasks ordered by creation time.
+    pub async fn get_l3_subtask_map(
+        &self,
+        user_id: &str,
+        root_ids: &[Uuid],
+        max_depth: usize,
+    ) -> Result<HashMap<Uuid, Vec<memorose_common::L3Task>>> {
+        let mut subtasks: HashMap<Uuid, Vec<memorose_common::L3Task>> = HashMap::new();
+        let mut visited = root_ids.iter().copied().collect::<HashSet<_>>();
+        let mut frontier = root_ids.to_vec();
+
+        for _ in 0..max_depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let incoming = self
+                .graph
+                .batch_get_incoming_edges(user_id, &frontier)
+                .await?;
+            let mut next_frontier = Vec::new();
+            for parent_id in &frontier {
+                let mut children = Vec::new();
+                for edge in incoming
+                    .get(parent_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|edge| edge.relation == RelationType::IsSubTaskOf)
+                {
+                    if !visited.insert(edge.source_id) {
+                        continue;
+                    }
+                    // The stored task is authoritative in case it was re-parented.
+                    if let Some(task) = self.get_l3_task(user_id, edge.source_id).await? {
+                        if task.parent_id == Some(*parent_id) {
+                            next_frontier.push(task.task_id);
+                            children.push(task);
+                        }
+                    }
+                }
+                if !children.is_empty() {
+                    children.sort_by_key(|task| task.created_at);
+                    subtasks.insert(*parent_id, children);
+                }
+            }
+            frontier = next_frontier;
+        }
+
+        Ok(subtasks)
+    }
+
+    /// Deadline index entry: `task_due:{due_at_micros:020}:{user_id}:{id}`, ordered by due
+    /// time. Open tasks stay indexed until they finish or go overdue; recurring tasks stay
+    /// indexed until their next occurrence has been instantiated.
+    pub(crate) fn task_due_index_key(unit: &MemoryUnit) -> Option<String> {
+        let meta = unit.task_metadata.as_ref()?;
+        let due_at = meta.due_at?;
+        let tracked = meta.recurrence.is_some() || (!meta.is_terminal() && !meta.overdue);
+        tracked.then(|| {
+            format!(
+                "task_due:{:020}:{}:{}",
+                due_at.timestamp_micros().max(0),
+                unit.user_id,
+                unit.id
+            )
+        })
+    }
+
+    pub fn subscribe_task_deadline_events(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<memorose_common::TaskDeadlineEvent> {
+        self.task_deadline_events.subscribe()
+    }
+
+    /// Re-store a task unit after a deadline transition, dropping its old index entry if
+    /// the transition moved or removed it.
+    async fn store_task_unit(&self, unit: MemoryUnit, previous_key: &str) -> Result<()> {
+        if Self::task_due_index_key(&unit).as_deref() != Some(previous_key) {
+            self.kv_store.delete(previous_key.as_bytes())?;
+        }
+        // Skip `store_memory_unit` so goals are not re-planned on every transition.
+        self.store_memory_units(vec![unit]).await
+    }
+
+    /// Create the next occurrence of a recurring task and move the schedule onto it.
+    async fn instantiate_next_occurrence(
+        &self,
+        mut current: MemoryUnit,
+        index_key: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<MemoryUnit>> {
+        let Some(next_due_at) = current
+            .task_metadata
+            .as_ref()
+            .and_then(|meta| meta.next_due_at(now))
+        else {
+            return Ok(None);
+        };
+
+        let mut next = current.clone();
+        next.id = Uuid::new_v4();
+        next.transaction_time = now;
+        next.last_accessed_at = now;
+        next.access_count = 0;
+        let mut next_meta = memorose_common::TaskMetadata::new(TaskStatus::Pending, 0.0);
+        next_meta.due_at = Some(next_due_at);
+        if let Some(meta) = current.task_metadata.as_mut() {
+            next_meta.recurrence = meta.recurrence.take();
+            next_meta.priority = meta.priority;
+        }
+        next.task_metadata = Some(next_meta);
+
+        self.store_memory_units(vec![next.clone()]).await?;
+        for parent_id in &next.references {
+            let edge = GraphEdge::new(
+                next.user_id.clone(),
+                next.id,
+                *parent_id,
+                RelationType::IsSubTaskOf,
+                1.0,
+            );
+            self.add_graph_edge(&edge).await?;
+        }
+        let edge = GraphEdge::new(
+            current.user_id.clone(),
+            current.id,
+            next.id,
+            RelationType::EvolvedTo,
+            1.0,
+        );
+        self.add_graph_edge(&edge).await?;
+        self.store_task_unit(current, index_key).await?;
+        Ok(Some(next))
+    }
+
+    /// Walk the deadline index up to `now + warning`, marking overdue tasks, instantiating
+    /// recurring tasks and announcing approaching deadlines once per due date. Emitted
+    /// events are also broadcast to `subscribe_task_deadline_events` listeners.
+    pub async fn process_task_deadlines(
+        &self,
+        now: DateTime<Utc>,
+        warning: chrono::Duration,
+        limit: usize,
+    ) -> Result<Vec<memorose_common::TaskDeadlineEvent>> {
+        let horizon = (now + warning).timestamp_micros().max(0);
+        let end_key = format!("task_due:{:020}", horizon.saturating_add(1));
+        let kv = self.kv_store.clone();
+        let entries =
+            tokio::task::spawn_blocking(move || kv.scan_range(b"task_due:", end_key.as_bytes()))
+                .await??;
+
+        let mut events = Vec::new();
+        for (key, _) in entries.into_iter().take(limit) {
+            let Ok(key) = String::from_utf8(key) else {
+                continue;
+            };
+            let Some((user_id, unit_id)) = key
+                .strip_prefix("task_due:")
+                .and_then(|rest| rest.split_once(':'))
+                .and_then(|(_, rest)| rest.rsplit_once(':'))
+                .and_then(|(user_id, id)| Some((user_id.to_string(), Uuid::parse_str(id).ok()?)))
+            else {
+                continue;
+            };
+
+            let unit = self.get_memory_unit(&user_id, unit_id).await?;
+            let Some(mut unit) =
+                unit.filter(|unit| Self::task_due_index_key(unit).as_deref() == Some(key.as_str()))
+            else {
+                // Deleted, rescheduled or finished since the entry was written.
+                self.kv_store.delete(key.as_bytes())?;
+                continue;
+            };
+            let meta = unit
+                .task_metadata
+                .clone()
+                .expect("indexed units carry task metadata");
+            let due_at = meta.due_at.expect("indexed units carry a due date");
+            let event = |kind, next_unit_id| memorose_common::TaskDeadlineEvent {
+                kind,
+                user_id: user_id.clone(),
+                unit_id,
+                content: unit.content.clone(),
+                due_at,
+                next_unit_id,
+                emitted_at: now,
+            };
+
+            if due_at <= now || meta.is_terminal() {
+                if !meta.is_terminal() && !meta.overdue {
+                    events.push(event(TaskDeadlineEventKind::Overdue, None));
+                    if let Some(meta) = unit.task_metadata.as_mut() {
+                        meta.overdue = true;
+                    }
+                }
+                if meta.recurrence.is_some() {
+                    match self
+                        .instantiate_next_occurrence(unit.clone(), &key, now)
+                        .await?
+                    {
+                        Some(next) => {
+                            events.push(event(TaskDeadlineEventKind::Recurred, Some(next.id)))
+                        }
+                        None => {
+                            tracing::warn!(
+                                "Task {} has an unparseable recurrence {:?}",
+                                unit_id,
+                                meta.recurrence
+                            );
+                            if let Some(meta) = unit.task_metadata.as_mut() {
+                                meta.recurrence = None;
+                            }
+                            self.store_task_unit(unit, &key).await?;
                         }
-                    } else {
-                        // If a dependency is missing, we consider it blocked/not completed
-                        all_deps_completed = false;
-                        break;
                     }
+                } else {
+                    self.store_task_unit(unit, &key).await?;
                 }
+                continue;
+            }
+
+            let notified_key = format!("task_due_notified:{}", unit_id);
+            let due_marker = due_at.timestamp_micros().to_le_bytes();
+            if self.system_kv().get(notified_key.as_bytes())?.as_deref() != Some(&due_marker[..]) {
+                events.push(event(TaskDeadlineEventKind::Approaching, None));
+                self.system_kv().put(notified_key.as_bytes(), &due_marker)?;
+            }
+        }
+
+        for event in &events {
+            // No subscribers is not an error.
+            let _ = self.task_deadline_events.send(event.clone());
+        }
+        Ok(events)
+    }
+
+    /// Store a task and, for subtasks, link it to its parent with an `IsSubTaskOf` edge.
+    pub async fn create_l3_task(&self, task: &memorose_common::L3Task) -> Result<()> {
+        self.store_l3_task(task).await?;
+        if let Some(parent_id) = task.parent_id {
+            let edge = GraphEdge::new(
+                task.user_id.clone(),
+                task.task_id,
+                parent_id,
+                RelationType::IsSubTaskOf,
+                1.0,
+            );
+            self.add_graph_edge(&edge).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply a status transition. Returns `None` when the task does not exist.
+    pub async fn apply_l3_task_status_update(
+        &self,
+        update: &memorose_common::L3TaskStatusUpdate,
+    ) -> Result<Option<memorose_common::L3Task>> {
+        let Some(mut task) = self.get_l3_task(&update.user_id, update.task_id).await? else {
+            return Ok(None);
+        };
+        update.apply_to(&mut task);
+        self.store_l3_task(&task).await?;
+        Ok(Some(task))
+    }
+
+    /// Load a task together with its subtask tree and rolled-up progress.
+    ///
+    /// A task with subtasks reports the mean progress of its non-cancelled children;
+    /// completed tasks always count as 1.0.
+    pub async fn get_l3_task_progress(
+        &self,
+        user_id: &str,
+        task_id: Uuid,
+    ) -> Result<Option<super::types::L3TaskProgress>> {
+        let all_tasks = self.list_l3_tasks(user_id).await?;
+        let mut children: HashMap<Uuid, Vec<&memorose_common::L3Task>> = HashMap::new();
+        for task in &all_tasks {
+            if let Some(parent_id) = task.parent_id {
+                children.entry(parent_id).or_default().push(task);
+            }
+        }
+        let Some(root) = all_tasks.iter().find(|task| task.task_id == task_id) else {
+            return Ok(None);
+        };
+        let mut visited = HashSet::new();
+        Ok(Some(Self::roll_up_task_progress(
+            root,
+            &children,
+            &mut visited,
+        )))
+    }
 
-                if all_deps_completed {
+    fn roll_up_task_progress(
+        task: &memorose_common::L3Task,
+        children: &HashMap<Uuid, Vec<&memorose_common::L3Task>>,
+        visited: &mut HashSet<Uuid>,
+    ) -> super::types::L3TaskProgress {
+        visited.insert(task.task_id);
+        let mut subtasks = Vec::new();
+        for child in children.get(&task.task_id).into_iter().flatten() {
+            if !visited.contains(&child.task_id) {
+                subtasks.push(Self::roll_up_task_progress(child, children, visited));
+            }
+        }
+        subtasks.sort_by_key(|subtask| subtask.task.created_at);
+
+        let active = subtasks
+            .iter()
+            .filter(|subtask| subtask.task.status != memorose_common::TaskStatus::Cancelled)
+            .collect::<Vec<_>>();
+        let progress = if task.status == memorose_common::TaskStatus::Completed {
+            1.0
+        } else if active.is_empty() {
+            task.progress.clamp(0.0, 1.0)
+        } else {
+            active.iter().map(|subtask| subtask.progress).sum::<f32>() / active.len() as f32
+        };
+        let total_subtasks = subtasks
+            .iter()
+            .map(|subtask| 1 + subtask.total_subtasks)
+            .sum();
+        let completed_subtasks = subtasks
+            .iter()
+            .map(|subtask| {
+                usize::from(subtask.task.status == memorose_common::TaskStatus::Completed)
+                    + subtask.completed_subtasks
+            })
+            .sum();
+
+        super::types::L3TaskProgress {
+            task: task.clone(),
+            progress,
+            total_subtasks,
+            completed_subtasks,
+            subtasks,
+        }
+    }
+
+    /// Blockers of every task: explicit `dependencies` plus the sources of incoming
+    /// `Blocks` edges.
+    async fn l3_task_blockers(
+        &self,
+        user_id: &str,
+        tasks: &[memorose_common::L3Task],
+    ) -> Result<HashMap<Uuid, HashSet<Uuid>>> {
+        let task_ids = tasks.iter().map(|task| task.task_id).collect::<Vec<_>>();
+        let incoming = self
+            .graph
+            .batch_get_incoming_edges(user_id, &task_ids)
+            .await?;
+
+        let mut blockers = HashMap::new();
+        for task in tasks {
+            let mut task_blockers = task.dependencies.iter().copied().collect::<HashSet<_>>();
+            task_blockers.extend(
+                incoming
+                    .get(&task.task_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|edge| edge.relation == RelationType::Blocks)
+                    .map(|edge| edge.source_id),
+            );
+            blockers.insert(task.task_id, task_blockers);
+        }
+        Ok(blockers)
+    }
+
+    /// Build a `Blocks` edge from `blocker_id` to `blocked_id` after checking that both
+    /// tasks exist and that the dependency would not introduce a cycle.
+    pub async fn build_l3_task_dependency_edge(
+        &self,
+        user_id: &str,
+        blocker_id: Uuid,
+        blocked_id: Uuid,
+    ) -> Result<GraphEdge> {
+        if blocker_id == blocked_id {
+            return Err(anyhow::anyhow!("a task cannot block itself"));
+        }
+        let tasks = self.list_l3_tasks(user_id).await?;
+        for id in [blocker_id, blocked_id] {
+            if !tasks.iter().any(|task| task.task_id == id) {
+                return Err(anyhow::anyhow!("task {} not found", id));
+            }
+        }
+
+        // Adding the edge closes a cycle if the blocker already waits on the blocked task.
+        let blockers = self.l3_task_blockers(user_id, &tasks).await?;
+        let mut stack = vec![blocker_id];
+        let mut visited = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == blocked_id {
+                return Err(anyhow::anyhow!(
+                    "task {} already depends on task {}",
+                    blocker_id,
+                    blocked_id
+                ));
+            }
+            if visited.insert(id) {
+                stack.extend(blockers.get(&id).into_iter().flatten().copied());
+            }
+        }
+
+        Ok(GraphEdge::new(
+            user_id.to_string(),
+            blocker_id,
+            blocked_id,
+            RelationType::Blocks,
+            1.0,
+        ))
+    }
+
+    /// Agent Action Driver: Get tasks that are Pending and have all blockers Completed.
+    ///
+    /// Blockers are resolved topologically, so tasks caught in a dependency cycle or
+    /// waiting on a missing task are never returned. Ready tasks come back in
+    /// dependency order, oldest first among peers.
+    pub async fn get_ready_l3_tasks(&self, user_id: &str) -> Result<Vec<memorose_common::L3Task>> {
+        let mut all_tasks = self.list_l3_tasks(user_id).await?;
+        all_tasks.sort_by_key(|task| task.created_at);
+        let blockers = self.l3_task_blockers(user_id, &all_tasks).await?;
+
+        let status_map: HashMap<Uuid, memorose_common::TaskStatus> = all_tasks
+            .iter()
+            .map(|task| (task.task_id, task.status.clone()))
+            .collect();
+
+        // Kahn's algorithm over known tasks; anything left unresolved sits on a cycle.
+        let mut remaining: HashMap<Uuid, usize> = HashMap::new();
+        let mut dependents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for task in &all_tasks {
+            let known = blockers[&task.task_id]
+                .iter()
+                .filter(|blocker| status_map.contains_key(blocker))
+                .collect::<Vec<_>>();
+            remaining.insert(task.task_id, known.len());
+            for blocker in known {
+                dependents.entry(*blocker).or_default().push(task.task_id);
+            }
+        }
+        let mut queue = all_tasks
+            .iter()
+            .map(|task| task.task_id)
+            .filter(|id| remaining[id] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(all_tasks.len());
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for dependent in dependents.get(&id).into_iter().flatten() {
+                let count = remaining
+                    .get_mut(dependent)
+                    .expect("dependent is a known task");
+                *count -= 1;
+                if *count == 0 {
+                    queue.push_back(*dependent);
+                }
+            }
+        }
+
+        let mut tasks_by_id: HashMap<Uuid, memorose_common::L3Task> = all_tasks
+            .into_iter()
+            .map(|task| (task.task_id, task))
+            .collect();
+        let mut ready_tasks = Vec::new();
+        for id in order {
+            let is_ready = status_map[&id] == memorose_common::TaskStatus::Pending
+                && blockers[&id].iter().all(|blocker| {
+                    // A missing blocker is treated as unresolved.
+                    status_map
+                        .get(blocker)
+                        .is_some_and(|status| *status == memorose_common::TaskStatus::Completed)
+                });
+            if is_ready {
+                if let Some(task) = tasks_by_id.remove(&id) {
                     ready_tasks.push(task);
                 }
             }
@@ -6,14 +6,14 @@ use crate::fact_extraction::{
     self, MemoryFactAttribute, MemoryFactChangeType, MemoryFactDescriptor, MemoryFactSubject,
     MemoryFactValueKind, MemoryFactValuePayload,
 };
-use crate::llm::LLMClient;
+use crate::llm::{CompressionContext, LLMClient};
 use crate::reranker::Reranker;
 use anyhow::Result;
 use chrono::{DateTime, TimeZone, Utc};
 use memorose_common::{
-    Event, EventContent, ForgetMode, ForgetTargetKind, ForgettingTombstone, GraphEdge,
-    MemoryDomain, MemoryType, MemoryUnit, RelationType, SharePolicy, ShareTarget, StoredMemoryFact,
-    TimeRange,
+    Event, EventContent, ForgetMode, ForgetTargetKind, ForgettingTombstone, GeoPoint, GeoRadius,
+    GraphEdge, MemoryDomain, MemoryType, MemoryUnit, RelationType, SharePolicy, ShareTarget,
+    StoredMemoryFact, TimeRange,
 };
 use std::sync::Arc;
 use tempfile::tempdir;
@@ -21,6 +21,39 @@ use uuid::Uuid;
 
 const TEST_USER: &str = "test_user";
 
+#[tokio::test]
+async fn test_new_in_memory_uses_mock_llm_and_removes_its_directory_on_drop() -> Result<()> {
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let root = engine.root_path();
+    assert!(root.exists());
+
+    let llm = engine
+        .arbitrator
+        .get_llm_client()
+        .expect("in-memory engine should carry the mock LLM");
+    assert_eq!(
+        llm.embed("anything").await?.data.len(),
+        crate::llm::MOCK_EMBEDDING_DIM
+    );
+
+    let event = Event::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        EventContent::Text("in-memory".to_string()),
+    );
+    engine.ingest_event(event.clone()).await?;
+    assert_eq!(engine.fetch_pending_events().await?.len(), 1);
+
+    let clone = engine.clone();
+    drop(engine);
+    assert!(root.exists());
+    drop(clone);
+    assert!(!root.exists());
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_vector_disabled_skips_lancedb_and_preserves_primary_storage() -> Result<()> {
     std::env::set_var("MEMOROSE__VECTOR__ENABLED", "false");
@@ -192,6 +225,52 @@ async fn test_prune_preserves_l1_referenced_by_l2() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_pinned_memory_skips_decay_and_prune() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let mut fact = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        memorose_common::MemoryType::Factual,
+        "The user is allergic to peanuts".into(),
+        None,
+    );
+    fact.importance = 0.15;
+    let fact_id = fact.id;
+    engine.store_memory_unit(fact).await?;
+
+    let pinned = engine
+        .set_memory_unit_pinned(TEST_USER, fact_id, true)
+        .await?
+        .expect("unit exists");
+    assert!(pinned.pinned);
+    assert!(engine
+        .set_memory_unit_pinned(TEST_USER, Uuid::new_v4(), true)
+        .await?
+        .is_none());
+
+    engine.decay_importance(TEST_USER, 0.5).await?;
+    assert_eq!(engine.prune_memories(TEST_USER, 0.1).await?, 0);
+    let kept = engine
+        .get_memory_unit(TEST_USER, fact_id)
+        .await?
+        .expect("pinned unit survives pruning");
+    assert!(kept.pinned);
+    assert!((kept.importance - 0.15).abs() < 1e-6);
+
+    engine
+        .set_memory_unit_pinned(TEST_USER, fact_id, false)
+        .await?;
+    engine.decay_importance(TEST_USER, 0.5).await?;
+    assert_eq!(engine.prune_memories(TEST_USER, 0.1).await?, 1);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_auto_linking() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -438,6 +517,53 @@ async fn test_feedback_loop() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_retrieval_feedback_counts_towards_experiment_variant() -> Result<()> {
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let (shown_first, shown_second) = (Uuid::new_v4(), Uuid::new_v4());
+    let feedback = |cited: Vec<Uuid>, variant: &str| memorose_common::RetrievalFeedback {
+        user_id: TEST_USER.into(),
+        retrieved_ids: vec![shown_first, shown_second],
+        cited_ids: cited,
+        arm: Some(memorose_common::ExperimentArm {
+            experiment: "rrf-k".into(),
+            variant: variant.into(),
+        }),
+        recorded_at: Utc::now(),
+    };
+
+    engine
+        .apply_retrieval_feedback(&feedback(vec![shown_second], "flat"))
+        .await?;
+    engine
+        .apply_retrieval_feedback(&feedback(vec![], "flat"))
+        .await?;
+    engine
+        .apply_retrieval_feedback(&feedback(vec![shown_first], "control"))
+        .await?;
+    // Feedback outside an experiment only trains the reranker
+    engine
+        .apply_retrieval_feedback(&memorose_common::RetrievalFeedback {
+            arm: None,
+            ..feedback(vec![shown_first], "control")
+        })
+        .await?;
+
+    let outcomes = engine.experiment_outcomes("rrf-k")?;
+    let variants: Vec<&str> = outcomes.iter().map(|o| o.variant.as_str()).collect();
+    assert_eq!(variants, ["control", "flat"]);
+    let flat = &outcomes[1];
+    assert_eq!(
+        (flat.feedback, flat.retrieved, flat.cited, flat.useful),
+        (2, 4, 1, 1)
+    );
+    assert_eq!(flat.mean_reciprocal_rank(), 0.25);
+    assert_eq!(flat.success_rate(), 0.5);
+    assert_eq!(outcomes[0].mean_reciprocal_rank(), 1.0);
+    assert!(engine.experiment_outcomes("other")?.is_empty());
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_temporal_text_search() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -648,7 +774,7 @@ impl crate::llm::LLMClient for MockCorrectionLLM {
     async fn compress(
         &self,
         text: &str,
-        _is_agent: bool,
+        _context: &CompressionContext,
     ) -> Result<crate::llm::LLMResponse<crate::llm::CompressionOutput>> {
         Ok(crate::llm::LLMResponse {
             data: crate::llm::CompressionOutput {
@@ -725,7 +851,7 @@ impl crate::llm::LLMClient for PromptMatchingCorrectionLLM {
     async fn compress(
         &self,
         text: &str,
-        _is_agent: bool,
+        _context: &CompressionContext,
     ) -> Result<crate::llm::LLMResponse<crate::llm::CompressionOutput>> {
         Ok(crate::llm::LLMResponse {
             data: crate::llm::CompressionOutput {
@@ -787,7 +913,7 @@ impl crate::llm::LLMClient for PanicOnGenerateLLM {
     async fn compress(
         &self,
         text: &str,
-        _is_agent: bool,
+        _context: &CompressionContext,
     ) -> Result<crate::llm::LLMResponse<crate::llm::CompressionOutput>> {
         Ok(crate::llm::LLMResponse {
             data: crate::llm::CompressionOutput {
@@ -2385,6 +2511,66 @@ async fn test_resolve_fact_descriptors_compatible_matches_best_multi_fact_pair()
     Ok(())
 }
 
+#[tokio::test]
+async fn test_arbitrate_candidates_caches_decisions_and_reasoning() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let mut engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let old = new_unit("I live in Berlin");
+    let current = new_unit("I moved to Lisbon");
+    engine.arbitrator =
+        crate::arbitrator::Arbitrator::with_client(std::sync::Arc::new(MockCorrectionLLM {
+            response: format!(
+                r#"{{"retained_ids": ["{}"], "reasoning": "The Lisbon memory is newer."}}"#,
+                current.id
+            ),
+        }));
+
+    let (decision, cached) = engine
+        .arbitrate_candidates(
+            TEST_USER,
+            &[old.clone(), current.clone()],
+            "where do I live",
+            true,
+        )
+        .await?
+        .expect("arbitration ran");
+    assert!(!cached);
+    assert_eq!(decision.retained_ids, vec![current.id]);
+    assert_eq!(
+        decision.reasoning.as_deref(),
+        Some("The Lisbon memory is newer.")
+    );
+
+    // Same query and candidate set, in any order, is served from the cache.
+    engine.arbitrator =
+        crate::arbitrator::Arbitrator::with_client(std::sync::Arc::new(PanicOnGenerateLLM));
+    let (cached_decision, cached) = engine
+        .arbitrate_candidates(
+            TEST_USER,
+            &[current.clone(), old.clone()],
+            " where do I live ",
+            false,
+        )
+        .await?
+        .expect("cached decision");
+    assert!(cached);
+    assert_eq!(cached_decision, decision);
+    assert_eq!(engine.query_cache_stats().await.arbitration_cache_size, 1);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_resolve_memory_fact_descriptors_prefers_persisted_extracted_facts() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -3452,10 +3638,10 @@ async fn test_concurrency_progress_update() -> Result<()> {
         None,
     );
     parent.level = 2;
-    parent.task_metadata = Some(memorose_common::TaskMetadata {
-        status: memorose_common::TaskStatus::InProgress,
-        progress: 0.0,
-    });
+    parent.task_metadata = Some(memorose_common::TaskMetadata::new(
+        memorose_common::TaskStatus::InProgress,
+        0.0,
+    ));
     let parent_id = parent.id;
     engine.store_memory_unit(parent).await?;
 
@@ -3471,10 +3657,10 @@ async fn test_concurrency_progress_update() -> Result<()> {
             None,
         );
         child.level = 1;
-        child.task_metadata = Some(memorose_common::TaskMetadata {
-            status: memorose_common::TaskStatus::Completed,
-            progress: 1.0,
-        });
+        child.task_metadata = Some(memorose_common::TaskMetadata::new(
+            memorose_common::TaskStatus::Completed,
+            1.0,
+        ));
         child.references.push(parent_id);
         engine.store_memory_unit(child).await?;
     }
@@ -3676,6 +3862,85 @@ async fn test_fetch_pending_events_limit_zero_short_circuits() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_ingest_admission_escalates_with_pending_depth() {
+    let config = memorose_common::config::AdmissionConfig {
+        shed_linking_pending: 5,
+        throttle_pending: 10,
+        reject_pending: 20,
+        retry_after_secs: 7,
+        sample_interval_ms: 1_000,
+    };
+    assert_eq!(
+        MemoroseEngine::admission_for(&config, 9),
+        IngestAdmission::Accept
+    );
+    assert_eq!(
+        MemoroseEngine::admission_for(&config, 10),
+        IngestAdmission::Throttle {
+            retry_after_secs: 7
+        }
+    );
+    assert_eq!(
+        MemoroseEngine::admission_for(&config, 25),
+        IngestAdmission::Reject {
+            retry_after_secs: 7
+        }
+    );
+
+    let disabled = memorose_common::config::AdmissionConfig {
+        throttle_pending: 0,
+        reject_pending: 0,
+        ..config
+    };
+    assert_eq!(
+        MemoroseEngine::admission_for(&disabled, usize::MAX),
+        IngestAdmission::Accept
+    );
+}
+
+#[tokio::test]
+async fn test_pending_gauge_drives_linking_shed_and_admission() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+        .await?
+        .with_admission_config(memorose_common::config::AdmissionConfig {
+            shed_linking_pending: 1,
+            throttle_pending: 2,
+            reject_pending: 0,
+            retry_after_secs: 3,
+            sample_interval_ms: 0,
+        });
+
+    assert!(!engine.should_shed_linking().await);
+    assert_eq!(
+        engine.check_ingest_admission().await?,
+        IngestAdmission::Accept
+    );
+
+    for text in ["first", "second"] {
+        engine
+            .ingest_event_directly(Event::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                EventContent::Text(text.into()),
+            ))
+            .await?;
+    }
+
+    assert_eq!(engine.pending_events_gauge().await?, 2);
+    assert!(engine.should_shed_linking().await);
+    assert_eq!(
+        engine.check_ingest_admission().await?,
+        IngestAdmission::Throttle {
+            retry_after_secs: 3
+        }
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_fetch_pending_events_limited_respects_limit() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -3975,6 +4240,221 @@ async fn test_reflect_on_session_creates_l2_topics_and_graph_edges() -> Result<(
     Ok(())
 }
 
+#[tokio::test]
+async fn test_refresh_user_profile_links_previous_profile_with_evolved_to() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let stream_id = Uuid::new_v4();
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, false, false).await?;
+    let with_profile = |profile: &str| {
+        engine
+            .clone()
+            .with_arbitrator(crate::arbitrator::Arbitrator::with_client(Arc::new(
+                MockCorrectionLLM {
+                    response: profile.to_string(),
+                },
+            )))
+    };
+
+    assert_eq!(
+        with_profile("- Prefers tea")
+            .refresh_user_profile(TEST_USER, 10)
+            .await?,
+        None
+    );
+
+    let mut insight = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        stream_id,
+        MemoryType::Factual,
+        "Drinks green tea every morning".into(),
+        None,
+    );
+    insight.level = 2;
+    engine.store_memory_units(vec![insight.clone()]).await?;
+
+    let first_id = with_profile("- Prefers tea")
+        .refresh_user_profile(TEST_USER, 10)
+        .await?
+        .expect("profile should be synthesized from L2 insights");
+    let mut jobs = engine.fetch_due_materialization_jobs(10)?;
+    assert_eq!(jobs.len(), 1);
+    let mut first = jobs.remove(0);
+    assert_eq!(first.unit.id, first_id);
+    assert_eq!(first.unit.level, 3);
+    assert_eq!(first.unit.content, "- Prefers tea");
+    assert!(first.post_publish_edges.iter().any(|edge| {
+        edge.target_id == insight.id && edge.relation == RelationType::DerivedFrom
+    }));
+    assert!(engine.get_user_profile(TEST_USER).await?.is_none());
+
+    first.unit.visible = true;
+    first.unit.materialization_state = memorose_common::MaterializationState::Published;
+    engine.publish_materialized_memory_unit(&first.unit).await?;
+    engine.delete_materialization_job(&first)?;
+    assert_eq!(
+        engine
+            .get_user_profile(TEST_USER)
+            .await?
+            .map(|unit| unit.id),
+        Some(first_id)
+    );
+
+    // Unchanged synthesis output does not create a new revision.
+    assert_eq!(
+        with_profile("- Prefers tea")
+            .refresh_user_profile(TEST_USER, 10)
+            .await?,
+        None
+    );
+
+    let second_id = with_profile("- Prefers green tea\n- Lives in Berlin")
+        .refresh_user_profile(TEST_USER, 10)
+        .await?
+        .expect("changed profile should create a new revision");
+    let jobs = engine.fetch_due_materialization_jobs(10)?;
+    assert_eq!(jobs.len(), 1);
+    assert!(jobs[0].post_publish_edges.iter().any(|edge| {
+        edge.source_id == first_id
+            && edge.target_id == second_id
+            && edge.relation == RelationType::EvolvedTo
+    }));
+    // The previous profile keeps serving reads until the new one is published.
+    assert_eq!(
+        engine
+            .get_user_profile(TEST_USER)
+            .await?
+            .map(|unit| unit.id),
+        Some(first_id)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_digest_groups_window_by_topic_and_caches_until_memories_change() -> Result<()>
+{
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, false, false).await?;
+    let with_llm = |client: Arc<dyn LLMClient>| {
+        engine
+            .clone()
+            .with_arbitrator(crate::arbitrator::Arbitrator::with_client(client))
+    };
+    let to = Utc.with_ymd_and_hms(2026, 3, 8, 0, 0, 0).unwrap();
+    let from = to - chrono::Duration::days(7);
+    let unit_at = |content: &str, keyword: &str, days_before: i64| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        unit.keywords = vec![keyword.into()];
+        unit.transaction_time = to - chrono::Duration::days(days_before);
+        unit
+    };
+    engine
+        .store_memory_units(vec![
+            unit_at("Booked flights to Lisbon", "travel", 3),
+            unit_at("Reserved a hotel in Alfama", "Travel", 2),
+            unit_at("Shipped the billing migration", "work", 1),
+            unit_at("Started learning Portuguese", "language", 30),
+        ])
+        .await?;
+
+    let (fallback, cached) = with_llm(Arc::new(MockCorrectionLLM {
+        response: String::new(),
+    }))
+    .memory_digest(TEST_USER, from, to, false)
+    .await?;
+    assert!(!cached);
+    assert_eq!(fallback.memory_count, 3);
+    assert_eq!(fallback.topics[0].label, "travel");
+    assert_eq!(fallback.topics[0].memory_ids.len(), 2);
+    assert_eq!(fallback.digest, "- travel: 2 memories\n- work: 1 memory");
+
+    let written = Arc::new(MockCorrectionLLM {
+        response: "- Planned a Lisbon trip".into(),
+    });
+    let (digest, cached) = with_llm(written.clone())
+        .memory_digest(TEST_USER, from, to, false)
+        .await?;
+    assert!(!cached);
+    assert_eq!(digest.digest, "- Planned a Lisbon trip");
+
+    let (again, cached) = with_llm(Arc::new(PanicOnGenerateLLM))
+        .memory_digest(TEST_USER, from, to, false)
+        .await?;
+    assert!(cached);
+    assert_eq!(again, digest);
+
+    engine
+        .store_memory_units(vec![unit_at("Renewed my passport", "travel", 1)])
+        .await?;
+    let (rewritten, cached) = with_llm(written)
+        .memory_digest(TEST_USER, from, to, false)
+        .await?;
+    assert!(!cached);
+    assert_eq!(rewritten.memory_count, 4);
+    assert_eq!(rewritten.topics[0].memory_ids.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refresh_structured_profile_preserves_manual_attributes() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, false, false)
+        .await?
+        .with_arbitrator(crate::arbitrator::Arbitrator::with_client(Arc::new(
+            MockCorrectionLLM {
+                response: r#"{"language": "German", "timezone": "UTC", "diet": null}"#.into(),
+            },
+        )));
+
+    engine.patch_structured_profile(
+        TEST_USER,
+        std::collections::HashMap::from([(
+            " Timezone ".to_string(),
+            Some(memorose_common::ProfileValue::Text("Europe/Berlin".into())),
+        )]),
+    )?;
+
+    let mut memory = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        MemoryType::Factual,
+        "Speaks German at home".into(),
+        None,
+    );
+    memory.level = 1;
+    engine.store_memory_units(vec![memory]).await?;
+
+    assert!(engine.refresh_structured_profile(TEST_USER, 10).await?);
+    let profile = engine
+        .get_structured_profile(TEST_USER)?
+        .expect("profile should be stored");
+    assert_eq!(
+        profile.attributes["language"].value,
+        memorose_common::ProfileValue::Text("German".into())
+    );
+    assert_eq!(
+        profile.attributes["timezone"].value,
+        memorose_common::ProfileValue::Text("Europe/Berlin".into())
+    );
+    assert!(!profile.attributes.contains_key("diet"));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_reflect_on_user_window_batches_across_streams() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -4051,18 +4531,502 @@ async fn test_reflect_on_user_window_batches_across_streams() -> Result<()> {
 }
 
 #[test]
-fn test_reflection_and_community_markers_roundtrip() -> Result<()> {
-    let temp_dir = tempdir()?;
-    let rt = tokio::runtime::Runtime::new()?;
-    let engine = rt.block_on(MemoroseEngine::new_with_default_threshold(
-        temp_dir.path(),
-        1000,
-        true,
-        true,
-    ))?;
-
-    engine.set_needs_reflect("alice")?;
-    engine.set_needs_reflect("bob")?;
+fn test_community_record_prefers_l2_summary_and_falls_back_to_member_keywords() {
+    let unit = |level: u8, content: &str, keywords: &[&str]| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        unit.level = level;
+        unit.keywords = keywords.iter().map(|k| k.to_string()).collect();
+        unit
+    };
+    let rust = unit(1, "Rust is memory safe", &["rust", "safety"]);
+    let borrow = unit(
+        1,
+        "The borrow checker prevents data races",
+        &["rust", "borrowing"],
+    );
+    let insight = unit(2, "The user studies Rust safety", &["Rust", "ownership"]);
+    let members = vec![rust.id, borrow.id, insight.id];
+
+    let record = super::community::community_record(
+        Uuid::nil(),
+        members.clone(),
+        &[rust.clone(), borrow.clone(), insight.clone()],
+    );
+    assert_eq!(record.summary_id, Some(insight.id));
+    assert_eq!(
+        record.summary.as_deref(),
+        Some("The user studies Rust safety")
+    );
+    assert_eq!(record.keywords, vec!["Rust", "ownership"]);
+    assert_eq!(record.member_ids, vec![rust.id, borrow.id]);
+
+    let record =
+        super::community::community_record(Uuid::nil(), vec![rust.id, borrow.id], &[rust, borrow]);
+    assert_eq!(record.summary_id, None);
+    assert_eq!(record.keywords, vec!["rust", "borrowing", "safety"]);
+}
+
+#[tokio::test]
+async fn test_incremental_communities_fall_back_to_full_run_and_consume_markers() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let node = Uuid::new_v4();
+
+    engine.mark_community_nodes_dirty(TEST_USER, &[node])?;
+    assert!(engine.get_community_state(TEST_USER)?.is_none());
+    assert_eq!(
+        engine
+            .process_communities_incremental(TEST_USER, 3, 10, 0.3)
+            .await?,
+        0
+    );
+    assert_eq!(
+        engine.get_community_state(TEST_USER)?,
+        Some(CommunityState::default())
+    );
+    assert!(engine.dirty_community_nodes(TEST_USER)?.is_empty());
+
+    engine.mark_community_nodes_dirty(TEST_USER, &[node])?;
+    assert_eq!(engine.dirty_community_nodes(TEST_USER)?, vec![node]);
+    engine
+        .process_communities_incremental(TEST_USER, 3, 10, 0.3)
+        .await?;
+    assert!(engine.dirty_community_nodes(TEST_USER)?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refresh_stale_community_summaries_supersedes_drifted_insight() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let unit = |level: u8, content: &str| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        unit.level = level;
+        unit
+    };
+    let members = vec![
+        unit(1, "Alice moved to Berlin"),
+        unit(1, "Alice works at a robotics startup"),
+        unit(1, "Alice is learning German"),
+        unit(1, "Alice adopted a cat"),
+    ];
+    let stale = unit(2, "Alice lives in Berlin and builds robots");
+    for unit in members.iter().chain([&stale]) {
+        engine.write_published_memory_unit_metadata(unit).await?;
+    }
+
+    let community_id = Uuid::new_v4();
+    let mut state = CommunityState::default();
+    for unit in members.iter().chain([&stale]) {
+        state.node_to_community.insert(unit.id, community_id);
+    }
+    // The insight was generated when only the first two members existed.
+    state.summaries.insert(
+        community_id,
+        CommunitySummaryState {
+            l2_id: stale.id,
+            members: vec![members[0].id, members[1].id],
+        },
+    );
+    engine.system_kv().put(
+        format!("community_state:{}", TEST_USER).as_bytes(),
+        &serde_json::to_vec(&state)?,
+    )?;
+
+    assert_eq!(
+        engine
+            .refresh_stale_community_summaries(TEST_USER, 3, 10, 0.3)
+            .await?,
+        1
+    );
+
+    let jobs = engine.fetch_due_materialization_jobs(10)?;
+    assert_eq!(jobs.len(), 1);
+    let fresh_id = jobs[0].unit.id;
+    assert_eq!(jobs[0].unit.level, 2);
+    assert!(jobs[0]
+        .post_publish_edges
+        .iter()
+        .any(|edge| edge.source_id == stale.id
+            && edge.target_id == fresh_id
+            && edge.relation == RelationType::EvolvedTo));
+
+    let demoted = engine.get_memory_unit_raw(TEST_USER, stale.id)?.unwrap();
+    assert!(demoted.importance < stale.importance);
+
+    let refreshed = engine.get_community_state(TEST_USER)?.unwrap();
+    let summary = &refreshed.summaries[&community_id];
+    assert_eq!(summary.l2_id, fresh_id);
+    assert_eq!(summary.members.len(), 4);
+
+    // The regenerated insight matches its members exactly, so nothing is stale any more.
+    assert_eq!(
+        engine
+            .refresh_stale_community_summaries(TEST_USER, 3, 10, 0.0)
+            .await?,
+        0
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deferred_linking_queues_units_and_disabled_mode_skips_them() -> Result<()> {
+    use memorose_common::config::{LinkingConfig, LinkingMode};
+
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+        .await?
+        .with_linking_config(LinkingConfig {
+            mode: LinkingMode::Deferred,
+            ..LinkingConfig::default()
+        });
+    let mut unit = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        MemoryType::Factual,
+        "Moved to Lisbon".into(),
+        None,
+    );
+    unit.level = 1;
+    engine.write_published_memory_unit_metadata(&unit).await?;
+
+    engine.link_published_memory_unit(&unit).await;
+    assert_eq!(engine.count_pending_linking_jobs().await?, 1);
+    assert_eq!(engine.process_linking_queue(10).await?, 1);
+    assert_eq!(engine.count_pending_linking_jobs().await?, 0);
+
+    let engine = engine.with_linking_config(LinkingConfig {
+        mode: LinkingMode::Disabled,
+        ..LinkingConfig::default()
+    });
+    engine.link_published_memory_unit(&unit).await;
+    assert_eq!(engine.count_pending_linking_jobs().await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_integrity_batch_reindexes_missing_units_and_reports_orphans() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let options = IntegrityCheckOptions {
+        batch_size: 1,
+        repair: true,
+        repair_orphaned_edges: false,
+        settle_secs: INTEGRITY_SETTLE_SECS,
+    };
+
+    // Metadata landed in KV but the text-index write was lost.
+    let mut unit = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        MemoryType::Factual,
+        "Half-written memory".into(),
+        None,
+    );
+    unit.transaction_time = chrono::Utc::now() - chrono::Duration::hours(1);
+    engine.write_published_memory_unit_metadata(&unit).await?;
+    // A unit index entry whose unit record is gone.
+    let dangling = Uuid::new_v4();
+    engine.kv_store.put(
+        format!("idx:unit:{}", dangling).as_bytes(),
+        TEST_USER.as_bytes(),
+    )?;
+    // An edge from the stored unit to a node that was never stored.
+    engine
+        .add_graph_edge(&memorose_common::GraphEdge::new(
+            TEST_USER.into(),
+            unit.id,
+            Uuid::new_v4(),
+            memorose_common::RelationType::RelatedTo,
+            0.5,
+        ))
+        .await?;
+    assert!(!engine.index.contains_unit(&unit.id.to_string())?);
+
+    // Two one-unit batches cover both entries; a third finds the end and completes the pass.
+    let mut report = engine.run_integrity_batch(options).await?;
+    while report.completed_at.is_none() {
+        assert!(engine.integrity_pass_in_progress()?.is_some());
+        report = engine.run_integrity_batch(options).await?;
+    }
+
+    assert_eq!(report.scanned_units, 2);
+    assert_eq!(report.missing_text_index, 1);
+    assert_eq!(report.dangling_unit_index, 1);
+    assert_eq!(report.orphaned_edges, 1);
+    assert_eq!(report.repaired, 2);
+    assert!(engine.index.contains_unit(&unit.id.to_string())?);
+    assert!(engine
+        .kv_store
+        .get(format!("idx:unit:{}", dangling).as_bytes())?
+        .is_none());
+    assert_eq!(engine.last_integrity_report()?, Some(report));
+    assert!(engine.integrity_pass_in_progress()?.is_none());
+
+    // The next pass finds nothing left to fix except the edge, which is only reported.
+    let report = engine
+        .run_integrity_batch(IntegrityCheckOptions {
+            batch_size: 10,
+            ..options
+        })
+        .await?;
+    assert!(report.completed_at.is_some());
+    assert_eq!(report.missing_text_index, 0);
+    assert_eq!(report.orphaned_edges, 1);
+    assert_eq!(report.repaired, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_orphan_gc_dry_run_reports_and_real_run_removes_dangling_references() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let unit = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        MemoryType::Factual,
+        "Still here".into(),
+        None,
+    );
+    engine.write_published_memory_unit_metadata(&unit).await?;
+    let live_l1 = format!("l1_idx:{}:{}", TEST_USER, unit.id);
+    engine
+        .kv_store
+        .put(live_l1.as_bytes(), &0i64.to_le_bytes())?;
+
+    // References left behind by a unit whose record is gone.
+    let deleted = Uuid::new_v4();
+    let now = chrono::Utc::now().timestamp();
+    let dangling_keys = [
+        format!("idx:unit:{}", deleted),
+        format!("l1_idx:{}:{}", TEST_USER, deleted),
+        format!("link_queue:{}:{}", TEST_USER, deleted),
+        format!("task_due:{:020}:{}:{}", 1, TEST_USER, deleted),
+        format!("task_due_notified:{}", deleted),
+    ];
+    for key in &dangling_keys {
+        engine.kv_store.put(key.as_bytes(), TEST_USER.as_bytes())?;
+    }
+    let expired_dedup = format!("dedup:{}:stale", TEST_USER);
+    let fresh_dedup = format!("dedup:{}:fresh", TEST_USER);
+    engine.kv_store.put(
+        expired_dedup.as_bytes(),
+        (now - 2 * SEMANTIC_DEDUP_WINDOW_SECS)
+            .to_string()
+            .as_bytes(),
+    )?;
+    engine
+        .kv_store
+        .put(fresh_dedup.as_bytes(), now.to_string().as_bytes())?;
+    let mut edge = memorose_common::GraphEdge::new(
+        TEST_USER.into(),
+        unit.id,
+        deleted,
+        memorose_common::RelationType::RelatedTo,
+        0.5,
+    );
+    edge.transaction_time = chrono::Utc::now() - chrono::Duration::hours(1);
+    engine.add_graph_edge(&edge).await?;
+
+    let dry = engine
+        .run_orphan_gc(OrphanGcOptions {
+            dry_run: true,
+            include_edges: true,
+        })
+        .await?;
+    assert!(dry.dry_run);
+    assert_eq!(
+        dry.dangling_keys.values().sum::<usize>(),
+        dangling_keys.len()
+    );
+    assert_eq!(dry.dangling_keys.get("task_due_notified"), Some(&1));
+    assert_eq!(dry.expired_dedup_fingerprints, 1);
+    assert_eq!(dry.orphaned_edges, 1);
+    assert_eq!(dry.removed, 0);
+    for key in &dangling_keys {
+        assert!(engine.kv_store.get(key.as_bytes())?.is_some());
+    }
+
+    let report = engine.run_orphan_gc(OrphanGcOptions::default()).await?;
+    assert_eq!(report.removed, dangling_keys.len() + 1);
+    for key in &dangling_keys {
+        assert!(engine.kv_store.get(key.as_bytes())?.is_none(), "{key}");
+    }
+    assert!(engine.kv_store.get(expired_dedup.as_bytes())?.is_none());
+    assert!(engine.kv_store.get(fresh_dedup.as_bytes())?.is_some());
+    assert!(engine.kv_store.get(live_l1.as_bytes())?.is_some());
+    assert!(engine
+        .kv_store
+        .get(format!("idx:unit:{}", unit.id).as_bytes())?
+        .is_some());
+    // Edges are only reported unless the sweep is allowed to remove them.
+    assert_eq!(report.orphaned_edges, 1);
+    assert_eq!(engine.last_orphan_gc_report()?, Some(report));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relation_analysis_budget_caps_calls_per_minute() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+        .await?
+        .with_linking_config(memorose_common::config::LinkingConfig {
+            max_relation_calls_per_minute: 2,
+            ..Default::default()
+        });
+
+    assert!(engine.try_acquire_relation_call().await);
+    assert!(engine.try_acquire_relation_call().await);
+    assert!(!engine.try_acquire_relation_call().await);
+
+    // An expired window refills the budget.
+    engine.relation_call_window.lock().await.0 =
+        std::time::Instant::now() - std::time::Duration::from_secs(61);
+    assert!(engine.try_acquire_relation_call().await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reflection_batch_skips_content_identical_to_last_reflection() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let write = |content: &str| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        unit.level = 1;
+        unit
+    };
+
+    let mut first = write("Bought a road bike");
+    first.transaction_time = Utc::now() - chrono::Duration::hours(1);
+    engine.write_published_memory_unit_metadata(&first).await?;
+    let outcome = engine
+        .reflect_on_user_window_batch(TEST_USER, None, None, 10, 4096)
+        .await?;
+    assert_eq!(outcome.consumed_units, 1);
+    assert!(!outcome.deduplicated);
+
+    // The same content ingested again under a new id is not reflected on twice.
+    let replayed = write("bought a road bike ");
+    engine
+        .write_published_memory_unit_metadata(&replayed)
+        .await?;
+    let cursor = replayed.transaction_time.timestamp_micros();
+    let cursor_id = replayed.id.to_string();
+    let outcome = engine
+        .reflect_on_user_window_batch(TEST_USER, Some(cursor), Some(&cursor_id), 10, 4096)
+        .await?;
+    assert_eq!(outcome.consumed_units, 1);
+    assert!(outcome.deduplicated);
+    assert_eq!(outcome.created_topics, 0);
+    Ok(())
+}
+
+#[test]
+fn test_incremental_community_plan_keeps_ids_and_only_resummarizes_drifted_groups() {
+    let [a, b, c, d, summary, e, f, g, z, new_node]: [Uuid; 10] =
+        std::array::from_fn(|_| Uuid::new_v4());
+    let (x, y, gone) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let mut state = CommunityState::default();
+    for node in [a, b, c, d, summary] {
+        state.node_to_community.insert(node, x);
+    }
+    for node in [e, f] {
+        state.node_to_community.insert(node, y);
+    }
+    state.node_to_community.insert(z, gone);
+    state.summaries.insert(
+        x,
+        CommunitySummaryState {
+            l2_id: summary,
+            members: vec![a, b, c, d],
+        },
+    );
+
+    // `new_node` joined X and `g` joined Y; `z` lost its only edge.
+    let dirty = vec![a, new_node, e, g, z];
+    let affected = super::community::affected_community_nodes(&state, &dirty);
+    assert_eq!(affected.len(), 10);
+
+    let label_x = Uuid::new_v4();
+    let label_y = Uuid::new_v4();
+    let mut labels = std::collections::HashMap::new();
+    for node in [a, b, c, d, summary, new_node] {
+        labels.insert(node, label_x);
+    }
+    for node in [e, f, g] {
+        labels.insert(node, label_y);
+    }
+
+    let plan = super::community::plan_community_update(&state, &affected, &labels, 3, 0.3);
+    assert_eq!(plan.dropped, vec![gone]);
+    let group_x = plan.groups.iter().find(|g| g.members.contains(&a)).unwrap();
+    assert_eq!(group_x.community_id, x);
+    // One new member out of five is within the 30% drift budget.
+    assert!(!group_x.needs_summary);
+    let group_y = plan.groups.iter().find(|g| g.members.contains(&e)).unwrap();
+    assert_eq!(group_y.community_id, y);
+    // Y reached the minimum size and has never been summarized.
+    assert!(group_y.needs_summary);
+
+    let strict = super::community::plan_community_update(&state, &affected, &labels, 3, 0.1);
+    let group_x = strict
+        .groups
+        .iter()
+        .find(|g| g.members.contains(&a))
+        .unwrap();
+    assert!(group_x.needs_summary);
+}
+
+#[test]
+fn test_reflection_and_community_markers_roundtrip() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let engine = rt.block_on(MemoroseEngine::new_with_default_threshold(
+        temp_dir.path(),
+        1000,
+        true,
+        true,
+    ))?;
+
+    engine.set_needs_reflect("alice")?;
+    engine.set_needs_reflect("bob")?;
     engine.set_needs_community("alice")?;
     engine.set_needs_community("carol")?;
 
@@ -4082,6 +5046,146 @@ fn test_reflection_and_community_markers_roundtrip() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_background_jobs_are_leased_and_requeued_on_new_work() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let lease = chrono::Duration::seconds(60);
+    let now = Utc::now();
+
+    engine.set_needs_community("alice")?;
+    let claimed = engine
+        .claim_job(
+            BackgroundJobKind::Community,
+            "alice",
+            "w1",
+            0,
+            lease,
+            3,
+            now,
+        )?
+        .expect("queued job is claimable");
+    assert_eq!(claimed.attempts, 1);
+    assert!(engine
+        .claim_job(
+            BackgroundJobKind::Community,
+            "alice",
+            "w2",
+            0,
+            lease,
+            3,
+            now
+        )?
+        .is_none());
+    assert!(!engine.heartbeat_job(BackgroundJobKind::Community, "alice", "w2", lease, now)?);
+    assert!(engine.heartbeat_job(BackgroundJobKind::Community, "alice", "w1", lease, now)?);
+
+    // Work enqueued while the job ran keeps it queued past completion.
+    engine.set_needs_community("alice")?;
+    assert!(!engine.complete_job(&claimed, "w1")?);
+    let requeued = engine
+        .claim_job(
+            BackgroundJobKind::Community,
+            "alice",
+            "w2",
+            0,
+            lease,
+            3,
+            now,
+        )?
+        .expect("requeued job is claimable");
+    assert!(engine.complete_job(&requeued, "w2")?);
+    assert!(engine.get_pending_communities()?.is_empty());
+
+    // A holder that stalls loses the job once its lease runs out.
+    engine.set_needs_profile("bob")?;
+    engine.claim_job(BackgroundJobKind::Profile, "bob", "w1", 0, lease, 3, now)?;
+    let later = now + chrono::Duration::seconds(61);
+    let taken_over = engine
+        .claim_job(BackgroundJobKind::Profile, "bob", "w2", 0, lease, 3, later)?
+        .expect("expired lease is claimable");
+    assert_eq!(taken_over.attempts, 2);
+
+    // Failures back off, and the job is dropped after its last attempt.
+    let backoff = chrono::Duration::seconds(30);
+    engine.fail_job(&taken_over, "w2", "llm timeout", 3, backoff, later)?;
+    assert!(engine
+        .claim_job(BackgroundJobKind::Profile, "bob", "w1", 0, lease, 3, later)?
+        .is_none());
+    let retry_at = later + backoff;
+    let last_try = engine
+        .claim_job(
+            BackgroundJobKind::Profile,
+            "bob",
+            "w1",
+            0,
+            lease,
+            3,
+            retry_at,
+        )?
+        .expect("job is retried after the backoff");
+    engine.fail_job(&last_try, "w1", "llm timeout", 3, backoff, retry_at)?;
+    assert!(engine.get_pending_profiles()?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_legacy_markers_move_into_the_job_queue() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let marker = ReflectionMarker {
+        first_event_at_ts: 1,
+        last_event_at_ts: 2,
+        pending_units: 4,
+        pending_tokens: 90,
+        ..Default::default()
+    };
+    engine
+        .system_kv()
+        .put(b"needs_reflect:alice", &serde_json::to_vec(&marker)?)?;
+    engine
+        .system_kv()
+        .put(b"needs_community:bob", b"1700000000")?;
+    engine.set_needs_profile("carol")?;
+    engine.claim_job(
+        BackgroundJobKind::Profile,
+        "carol",
+        "gone",
+        0,
+        chrono::Duration::seconds(600),
+        3,
+        Utc::now(),
+    )?;
+    // A lease claimed through Raft is replicated state and survives the restart.
+    engine.set_needs_profile("dave")?;
+    engine.claim_job(
+        BackgroundJobKind::Profile,
+        "dave",
+        "leader",
+        1,
+        chrono::Duration::seconds(600),
+        3,
+        Utc::now(),
+    )?;
+
+    assert_eq!(engine.recover_background_jobs()?, 2);
+    assert_eq!(
+        engine.get_pending_reflection_markers()?,
+        vec![("alice".to_string(), marker)]
+    );
+    assert_eq!(engine.get_pending_communities()?, vec!["bob".to_string()]);
+    assert!(engine.system_kv().scan(b"needs_")?.is_empty());
+    assert!(engine
+        .get_job(BackgroundJobKind::Profile, "carol")?
+        .is_some_and(|job| job.lease.is_none()));
+    assert!(engine
+        .get_job(BackgroundJobKind::Profile, "dave")?
+        .is_some_and(|job| job.lease.is_some()));
+    Ok(())
+}
+
 #[test]
 fn test_reflection_marker_accumulates_pending_units_and_tokens() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -4311,8 +5415,85 @@ async fn test_export_snapshot_writes_archive() -> Result<()> {
 
     assert!(output_path.exists());
     assert!(std::fs::metadata(&output_path)?.len() > 0);
-    Ok(())
-}
+
+    let verification = MemoroseEngine::verify_snapshot(output_path.clone()).await?;
+    assert!(verification.is_verified());
+    assert_eq!(verification.archive_checksum_matches, Some(true));
+    assert!(verification.files > 0);
+
+    let checksum_path = output_dir.path().join("snapshot.tar.gz.sha256");
+    std::fs::write(
+        &checksum_path,
+        format!("{}  snapshot.tar.gz\n", "0".repeat(64)),
+    )?;
+    let verification = MemoroseEngine::verify_snapshot(output_path.clone()).await?;
+    assert_eq!(verification.archive_checksum_matches, Some(false));
+    assert!(!verification.is_intact());
+    let restore_dir = output_dir.path().join("restored");
+    assert!(
+        MemoroseEngine::restore_from_snapshot(output_path, restore_dir.clone())
+            .await
+            .is_err()
+    );
+    assert!(!restore_dir.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_from_snapshot_rejects_files_that_do_not_match_manifest() -> Result<()> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let archive_dir = tempdir()?;
+    let target_root = tempdir()?;
+    let snapshot_path = archive_dir.path().join("snapshot.tar.gz");
+    let target_dir = target_root.path().join("restore-target");
+    std::fs::create_dir_all(&target_dir)?;
+    std::fs::write(target_dir.join("keep.txt"), b"keep")?;
+
+    let manifest = serde_json::json!({
+        "version": 1,
+        "created_at": chrono::Utc::now(),
+        "files": [
+            { "path": "rocksdb/a.sst", "size": 5, "sha256": "0".repeat(64) },
+            { "path": "rocksdb/b.sst", "size": 1, "sha256": "0".repeat(64) },
+        ],
+        "content_sha256": "0".repeat(64),
+    });
+    let file = std::fs::File::create(&snapshot_path)?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (path, payload) in [
+        ("rocksdb/a.sst", b"fresh".to_vec()),
+        ("rocksdb/extra.sst", b"x".to_vec()),
+        ("MANIFEST.json", serde_json::to_vec(&manifest)?),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(payload.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, path, payload.as_slice())?;
+    }
+    tar.into_inner()?.finish()?;
+
+    let verification = MemoroseEngine::verify_snapshot(snapshot_path.clone()).await?;
+    assert!(verification.has_manifest);
+    assert_eq!(verification.archive_checksum_matches, None);
+    assert!(!verification.content_checksum_matches);
+    assert_eq!(verification.mismatched, vec!["rocksdb/a.sst".to_string()]);
+    assert_eq!(verification.missing, vec!["rocksdb/b.sst".to_string()]);
+    assert_eq!(
+        verification.unexpected,
+        vec!["rocksdb/extra.sst".to_string()]
+    );
+
+    assert!(
+        MemoroseEngine::restore_from_snapshot(snapshot_path, target_dir.clone())
+            .await
+            .is_err()
+    );
+    assert!(target_dir.join("keep.txt").exists());
+    Ok(())
+}
 
 #[tokio::test]
 async fn test_restore_from_snapshot_replaces_existing_target_dir() -> Result<()> {
@@ -4357,89 +5538,1296 @@ async fn test_bump_l1_count_tracks_threshold_crossing() -> Result<()> {
     for i in 0..4 {
         let unit = MemoryUnit::new(
             None,
-            TEST_USER.into(),
+            TEST_USER.into(),
+            None,
+            stream_id,
+            memorose_common::MemoryType::Factual,
+            format!("base {}", i),
+            None,
+        );
+        engine.store_memory_unit(unit).await?;
+    }
+
+    for i in 0..2 {
+        let unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            stream_id,
+            memorose_common::MemoryType::Factual,
+            format!("delta {}", i),
+            None,
+        );
+        engine.store_memory_unit(unit).await?;
+    }
+
+    let (before, after) = engine.bump_l1_count_and_get_range(TEST_USER, 2).await?;
+    assert_eq!((before, after), (4, 6));
+    assert!(before / 5 < after / 5);
+
+    let unit = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        stream_id,
+        memorose_common::MemoryType::Factual,
+        "delta 2".into(),
+        None,
+    );
+    engine.store_memory_unit(unit).await?;
+    let (before, after) = engine.bump_l1_count_and_get_range(TEST_USER, 1).await?;
+    assert_eq!((before, after), (6, 7));
+    assert!(!(before / 5 < after / 5));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_text_search_returns_local_memories() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let stream_id = Uuid::new_v4();
+
+    let primary = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        stream_id,
+        memorose_common::MemoryType::Factual,
+        "Cross stream retrieval phrase".into(),
+        None,
+    );
+    let secondary = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        stream_id,
+        memorose_common::MemoryType::Factual,
+        "Cross stream retrieval phrase".into(),
+        None,
+    );
+
+    engine
+        .store_memory_units(vec![primary.clone(), secondary.clone()])
+        .await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let results = engine
+        .search_text(TEST_USER, "cross stream retrieval", 10, false, None)
+        .await?;
+
+    assert!(!results.is_empty());
+    assert_eq!(results.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multimodal_search_mode_matches_asset_vectors() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            Some(vec![0.0, 1.0, 0.0, 0.0]),
+        )
+    };
+    let photo = new_unit("Offsite recap");
+    let note = new_unit("Grocery list");
+    engine
+        .store_memory_units(vec![photo.clone(), note.clone()])
+        .await?;
+    engine
+        .store_asset_vectors(&photo, vec![vec![1.0, 0.0, 0.0, 0.0]])
+        .await?;
+
+    let search = |mode: VectorSearchMode| {
+        let engine = engine.clone();
+        async move {
+            engine
+                .search_hybrid_with_shared_explained(
+                    TEST_USER,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    "beach volleyball",
+                    &[1.0, 0.0, 0.0, 0.0],
+                    5,
+                    0,
+                    false,
+                    Some(0.0),
+                    0,
+                    None,
+                    None,
+                    None,
+                    0.0,
+                    None,
+                    mode,
+                    SearchExplainOptions::default(),
+                )
+                .await
+                .map(|outcome| {
+                    outcome
+                        .results
+                        .into_iter()
+                        .map(|(hit, _)| hit.id)
+                        .collect::<Vec<_>>()
+                })
+        }
+    };
+
+    assert_eq!(search(VectorSearchMode::Multimodal).await?, vec![photo.id]);
+    let joint = search(VectorSearchMode::Joint).await?;
+    assert_eq!(joint.first(), Some(&photo.id));
+    assert!(joint.contains(&note.id));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_diagnostics_report_text_rrf_graph_and_rerank_stages() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let seed = new_unit("Quarterly budget review notes");
+    let neighbor = new_unit("Follow up with finance team");
+    engine
+        .store_memory_units(vec![seed.clone(), neighbor.clone()])
+        .await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+    engine
+        .graph()
+        .add_edge(&GraphEdge::new(
+            TEST_USER.into(),
+            seed.id,
+            neighbor.id,
+            RelationType::DerivedFrom,
+            1.0,
+        ))
+        .await?;
+
+    let outcome = engine
+        .search_hybrid_with_shared_explained(
+            TEST_USER,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "quarterly budget",
+            &[0.0; 8],
+            5,
+            0,
+            false,
+            Some(0.0),
+            1,
+            None,
+            None,
+            None,
+            0.0,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions {
+                arbitration: false,
+                diagnostics: true,
+            },
+        )
+        .await?;
+    let diag = outcome.diagnostics.expect("diagnostics requested");
+
+    assert_eq!(diag.text_hits[0].id, seed.id.to_string());
+    assert!(diag.text_hits[0].bm25.is_some_and(|score| score > 0.0));
+    assert_eq!(diag.rrf[0].id, seed.id.to_string());
+    assert!(diag.rrf[0].text > 0.0);
+    assert_eq!(diag.rrf[0].fused, 1.0);
+    assert_eq!(diag.graph_expansion.len(), 1);
+    assert_eq!(diag.graph_expansion[0].id, neighbor.id);
+    assert_eq!(diag.graph_expansion[0].via, seed.id);
+    assert_eq!(diag.graph_expansion[0].relation, RelationType::DerivedFrom);
+    assert_eq!(diag.rerank.len(), 2);
+    assert_eq!(diag.thresholds.min_score, 0.0);
+    assert!(outcome.results.iter().any(|(hit, _)| hit.id == neighbor.id));
+
+    let plain = engine
+        .search_hybrid_with_shared_explained(
+            TEST_USER,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "quarterly budget",
+            &[0.0; 8],
+            5,
+            0,
+            false,
+            Some(0.0),
+            1,
+            None,
+            None,
+            None,
+            0.0,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions::default(),
+        )
+        .await?;
+    assert!(plain.diagnostics.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_pages_through_results_with_offset() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let units = [
+        "Budget planning kickoff",
+        "Budget approval from finance",
+        "Budget retrospective notes",
+    ]
+    .into_iter()
+    .map(|content| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    })
+    .collect::<Vec<_>>();
+    engine.store_memory_units(units).await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let page = |offset| {
+        engine.search_hybrid_with_shared_explained(
+            TEST_USER,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "budget",
+            &[0.0; 8],
+            2,
+            offset,
+            false,
+            Some(0.0),
+            0,
+            None,
+            None,
+            None,
+            0.0,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions::default(),
+        )
+    };
+
+    let first = page(0).await?;
+    assert_eq!(first.results.len(), 2);
+    assert!(first.has_more);
+
+    let second = page(2).await?;
+    assert_eq!(second.results.len(), 1);
+    assert!(!second.has_more);
+
+    let mut seen = first
+        .results
+        .iter()
+        .chain(&second.results)
+        .map(|(hit, _)| hit.id)
+        .collect::<Vec<_>>();
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 3);
+
+    assert!(page(3).await?.results.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recency_bias_favours_fresh_memories() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+        .await?
+        .with_recency_half_life_hours(24.0);
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let mut stale = new_unit("Budget budget meeting with finance about the budget");
+    stale.transaction_time = Utc::now() - chrono::Duration::days(30);
+    let fresh = new_unit("Budget meeting yesterday");
+    engine
+        .store_memory_units(vec![stale.clone(), fresh.clone()])
+        .await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let search = |recency_bias| {
+        engine.search_hybrid_with_shared_explained(
+            TEST_USER,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "budget meeting",
+            &[0.0; 8],
+            5,
+            0,
+            false,
+            Some(0.0),
+            0,
+            None,
+            None,
+            None,
+            recency_bias,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions::default(),
+        )
+    };
+    let score_of = |results: &[(SharedSearchHit, f32)], id| {
+        results
+            .iter()
+            .find(|(hit, _)| hit.id == id)
+            .map(|(_, score)| *score)
+    };
+
+    let neutral = search(0.0).await?.results;
+    let biased = search(1.0).await?.results;
+
+    assert_eq!(biased[0].0.id, fresh.id);
+    let stale_neutral = score_of(&neutral, stale.id).expect("stale memory retrieved");
+    let stale_biased = score_of(&biased, stale.id).expect("stale memory retrieved");
+    assert!(stale_biased < stale_neutral);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_app_ids_span_listed_apps_only() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let units = ["notes", "mail", "calendar"]
+        .into_iter()
+        .map(|app| {
+            MemoryUnit::new(
+                None,
+                TEST_USER.into(),
+                Some(app.into()),
+                Uuid::new_v4(),
+                memorose_common::MemoryType::Factual,
+                format!("Budget reminder from {}", app),
+                None,
+            )
+        })
+        .collect::<Vec<_>>();
+    engine.store_memory_units(units.clone()).await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let search = |agent_id, app_ids| {
+        engine.search_hybrid_with_shared_explained(
+            TEST_USER,
+            None,
+            agent_id,
+            app_ids,
+            None,
+            None,
+            "budget reminder",
+            &[0.0; 8],
+            10,
+            0,
+            false,
+            Some(0.0),
+            0,
+            None,
+            None,
+            None,
+            0.0,
+            None,
+            VectorSearchMode::Text,
+            SearchExplainOptions::default(),
+        )
+    };
+    let apps_of = |outcome: SharedSearchOutcome| {
+        let mut apps = outcome
+            .results
+            .into_iter()
+            .filter_map(|(hit, _)| hit.agent_id.clone())
+            .collect::<Vec<_>>();
+        apps.sort();
+        apps
+    };
+
+    assert_eq!(apps_of(search(Some("notes"), None).await?), vec!["notes"]);
+    let listed = vec!["notes".to_string(), "mail".to_string()];
+    assert_eq!(
+        apps_of(search(None, Some(listed.as_slice())).await?),
+        vec!["mail", "notes"]
+    );
+    assert_eq!(apps_of(search(None, None).await?).len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_near_keeps_memories_within_radius() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |place: &str, location: Option<GeoPoint>| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            format!("Coffee with Sam in {}", place),
+            Some(vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        );
+        unit.location = location;
+        unit
+    };
+    let paris = new_unit("Paris", GeoPoint::new(48.8566, 2.3522));
+    let london = new_unit("London", GeoPoint::new(51.5074, -0.1278));
+    let unknown = new_unit("town", None);
+    engine
+        .store_memory_units(vec![paris.clone(), london.clone(), unknown])
+        .await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let search = |near: Option<GeoRadius>| {
+        let engine = engine.clone();
+        async move {
+            engine
+                .search_hybrid_with_shared_explained(
+                    TEST_USER,
+                    None,
+                    None,
+                    None,
+                    None,
+                    near.as_ref(),
+                    "coffee with sam",
+                    &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                    10,
+                    0,
+                    false,
+                    Some(0.0),
+                    0,
+                    None,
+                    None,
+                    None,
+                    0.0,
+                    None,
+                    VectorSearchMode::Text,
+                    SearchExplainOptions::default(),
+                )
+                .await
+                .map(|outcome| {
+                    outcome
+                        .results
+                        .into_iter()
+                        .map(|(hit, _)| hit.id)
+                        .collect::<std::collections::HashSet<_>>()
+                })
+        }
+    };
+
+    assert_eq!(search(None).await?.len(), 3);
+    let near_paris = GeoRadius {
+        lat: 48.8606,
+        lon: 2.3376,
+        radius: 5_000.0,
+    };
+    assert_eq!(
+        search(Some(near_paris)).await?,
+        std::collections::HashSet::from([paris.id])
+    );
+    let paris_and_london = GeoRadius {
+        radius: 400_000.0,
+        ..near_paris
+    };
+    assert_eq!(
+        search(Some(paris_and_london)).await?,
+        std::collections::HashSet::from([paris.id, london.id])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expand_subgraph_follows_registered_custom_relations() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let seed = new_unit("Alice leads the platform team");
+    let manager = new_unit("Bob runs engineering");
+    let report = new_unit("Carol joined the platform team");
+    engine
+        .store_memory_units(vec![seed.clone(), manager.clone(), report.clone()])
+        .await?;
+    let reports_to = RelationType::Custom("reports_to".into());
+    for (source, target) in [(seed.id, manager.id), (report.id, seed.id)] {
+        engine
+            .graph()
+            .add_edge(&GraphEdge::new(
+                TEST_USER.into(),
+                source,
+                target,
+                reports_to.clone(),
+                1.0,
+            ))
+            .await?;
+    }
+
+    async fn expanded(
+        engine: &MemoroseEngine,
+        seed: &MemoryUnit,
+    ) -> Result<std::collections::HashMap<Uuid, f32>> {
+        let units = engine
+            .expand_subgraph(TEST_USER, vec![(seed.clone(), 1.0)], 1, None)
+            .await?;
+        Ok(units
+            .into_iter()
+            .map(|(unit, score)| (unit.id, score))
+            .collect())
+    }
+
+    // Unregistered custom relations are not followed.
+    assert_eq!(expanded(&engine, &seed).await?.len(), 1);
+
+    let mut definition = memorose_common::RelationDefinition {
+        user_id: TEST_USER.into(),
+        name: "reports_to".into(),
+        directed: true,
+        traversal_weight: 0.5,
+        description: None,
+        updated_at: Utc::now(),
+    };
+    engine.put_relation_definition(&definition)?;
+    let directed = expanded(&engine, &seed).await?;
+    assert_eq!(directed.len(), 2);
+    let unweighted = 0.8_f32.powi(1) * 0.8;
+    assert!((directed[&manager.id] - unweighted * 0.5).abs() < 1e-6);
+    assert!(!directed.contains_key(&report.id));
+
+    definition.directed = false;
+    engine.put_relation_definition(&definition)?;
+    assert!(expanded(&engine, &seed).await?.contains_key(&report.id));
+
+    assert!(engine.delete_relation_definition(TEST_USER, "reports_to")?);
+    assert!(!engine.delete_relation_definition(TEST_USER, "reports_to")?);
+    assert_eq!(expanded(&engine, &seed).await?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prune_decayed_edges_removes_weak_idle_auto_links() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+        .await?
+        .with_edge_decay_config(memorose_common::config::EdgeDecayConfig {
+            enabled: true,
+            ..Default::default()
+        });
+    let hub = Uuid::new_v4();
+    let weak = GraphEdge::new(
+        TEST_USER.into(),
+        hub,
+        Uuid::new_v4(),
+        RelationType::RelatedTo,
+        0.3,
+    );
+    let strong = GraphEdge::new(
+        TEST_USER.into(),
+        hub,
+        Uuid::new_v4(),
+        RelationType::RelatedTo,
+        1.0,
+    );
+    let derived = GraphEdge::new(
+        TEST_USER.into(),
+        hub,
+        Uuid::new_v4(),
+        RelationType::DerivedFrom,
+        0.3,
+    );
+    engine
+        .graph()
+        .add_edges(&[weak.clone(), strong.clone(), derived.clone()])
+        .await?;
+
+    // Recently written edges are kept however weak they are.
+    assert_eq!(engine.prune_decayed_edges(TEST_USER, Utc::now()).await?, 0);
+
+    let later = Utc::now() + chrono::Duration::days(40);
+    assert_eq!(engine.prune_decayed_edges(TEST_USER, later).await?, 1);
+    let remaining: std::collections::HashSet<Uuid> = engine
+        .graph()
+        .get_outgoing_edges(TEST_USER, hub)
+        .await?
+        .into_iter()
+        .map(|edge| edge.target_id)
+        .collect();
+    assert!(!remaining.contains(&weak.target_id));
+    assert!(remaining.contains(&strong.target_id));
+    assert!(remaining.contains(&derived.target_id));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_graph_filters_edges_and_labels_nodes() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let new_unit = |content: &str| {
+        MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let jazz = new_unit("Loves   late-night jazz\nrecords");
+    let piano = new_unit("Took piano lessons as a kid");
+    let forgotten = new_unit("Used to live in Lisbon");
+    engine
+        .store_memory_units(vec![jazz.clone(), piano.clone(), forgotten.clone()])
+        .await?;
+    engine
+        .graph()
+        .add_edges(&[
+            GraphEdge::new(
+                TEST_USER.into(),
+                jazz.id,
+                piano.id,
+                RelationType::Supports,
+                0.9,
+            ),
+            GraphEdge::new(
+                TEST_USER.into(),
+                jazz.id,
+                piano.id,
+                RelationType::RelatedTo,
+                0.4,
+            ),
+            GraphEdge::new(
+                TEST_USER.into(),
+                piano.id,
+                forgotten.id,
+                RelationType::Supports,
+                0.8,
+            ),
+        ])
+        .await?;
+    engine
+        .soft_delete_memory_unit(TEST_USER, forgotten.id, 30)
+        .await?;
+
+    let export = engine
+        .export_graph(
+            TEST_USER,
+            &GraphExportFilter {
+                relations: vec![RelationType::Supports],
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(export.edges.len(), 1);
+    assert_eq!(export.edges[0].source, jazz.id);
+    assert_eq!(export.edges[0].relation, RelationType::Supports);
+    let ids: std::collections::HashSet<Uuid> = export.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(ids, [jazz.id, piano.id].into_iter().collect());
+    let jazz_node = export.nodes.iter().find(|node| node.id == jazz.id).unwrap();
+    assert_eq!(jazz_node.label, "Loves late-night jazz records");
+
+    let all = engine
+        .export_graph(TEST_USER, &GraphExportFilter::default())
+        .await?;
+    assert_eq!(all.edges.len(), 2);
+    let heaviest = engine
+        .export_graph(
+            TEST_USER,
+            &GraphExportFilter {
+                max_edges: 1,
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(heaviest.edges[0].weight, 0.9);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graph_diff_reports_changes_between_two_times() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let now = Utc::now();
+    let edge_at = |source, target, relation, weight, hours_ago| {
+        let mut edge = GraphEdge::new(TEST_USER.into(), source, target, relation, weight);
+        edge.transaction_time = now - chrono::Duration::hours(hours_ago);
+        edge
+    };
+    let (a, b, c, d) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let dropped = edge_at(a, c, RelationType::Supports, 0.6, 3);
+    engine
+        .graph()
+        .add_edges(&[
+            edge_at(a, b, RelationType::Supports, 0.5, 3),
+            dropped.clone(),
+        ])
+        .await?;
+    engine
+        .graph()
+        .add_edges(&[
+            edge_at(a, b, RelationType::Supports, 0.9, 1),
+            edge_at(b, d, RelationType::RelatedTo, 0.7, 1),
+        ])
+        .await?;
+    engine.graph().delete_edges(&[dropped]).await?;
+
+    let diff = engine
+        .graph_diff(
+            TEST_USER,
+            now - chrono::Duration::hours(2),
+            Utc::now() + chrono::Duration::seconds(1),
+        )
+        .await?;
+    assert_eq!(diff.edges_added.len(), 1);
+    assert_eq!(
+        (diff.edges_added[0].source_id, diff.edges_added[0].target_id),
+        (b, d)
+    );
+    assert_eq!(diff.edges_removed.len(), 1);
+    assert_eq!(
+        (
+            diff.edges_removed[0].source_id,
+            diff.edges_removed[0].target_id
+        ),
+        (a, c)
+    );
+    assert_eq!(diff.weight_changes.len(), 1);
+    assert_eq!(diff.weight_changes[0].previous_weight, 0.5);
+    assert_eq!(diff.weight_changes[0].edge.weight, 0.9);
+    assert_eq!(diff.nodes_introduced, vec![d]);
+    assert_eq!(diff.nodes_removed, vec![c]);
+
+    let earlier = engine
+        .graph_diff(
+            TEST_USER,
+            now - chrono::Duration::hours(4),
+            now - chrono::Duration::hours(2),
+        )
+        .await?;
+    assert_eq!(earlier.edges_added.len(), 2);
+    assert!(earlier.edges_removed.is_empty() && earlier.weight_changes.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_soft_delete_moves_memory_to_trash_until_restored_or_purged() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let unit = MemoryUnit::new(
+        None,
+        "alice".into(),
+        None,
+        Uuid::new_v4(),
+        memorose_common::MemoryType::Factual,
+        "Alice keeps her passport in the blue drawer".into(),
+        None,
+    );
+    engine.store_memory_units(vec![unit.clone()]).await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+    let search = || engine.search_text("alice", "passport", 10, false, None);
+
+    let entry = engine
+        .soft_delete_memory_unit("alice", unit.id, 30)
+        .await?
+        .expect("unit exists");
+    assert_eq!(
+        entry.purge_after - entry.deleted_at,
+        chrono::Duration::days(30)
+    );
+    assert!(engine.get_memory_unit("alice", unit.id).await?.is_none());
+    assert!(search().await?.is_empty());
+    assert_eq!(
+        engine.list_trashed_memory_units("alice")?,
+        vec![entry.clone()]
+    );
+    assert!(engine
+        .soft_delete_memory_unit("alice", Uuid::new_v4(), 30)
+        .await?
+        .is_none());
+
+    assert!(engine.restore_memory_unit("alice", unit.id).await?);
+    assert!(!engine.restore_memory_unit("alice", unit.id).await?);
+    assert!(engine.get_memory_unit("alice", unit.id).await?.is_some());
+    assert_eq!(search().await?.len(), 1);
+
+    let entry = engine
+        .soft_delete_memory_unit("alice", unit.id, 30)
+        .await?
+        .expect("unit exists");
+    assert_eq!(engine.purge_expired_trash(entry.deleted_at).await?, 0);
+    assert_eq!(engine.purge_expired_trash(entry.purge_after).await?, 1);
+    assert!(engine
+        .get_memory_unit_including_forgotten("alice", unit.id)?
+        .is_none());
+    assert!(engine.list_trashed_memory_units("alice")?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restoring_a_forgotten_unit_from_trash_keeps_it_forgotten() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let unit = MemoryUnit::new(
+        None,
+        "alice".into(),
+        None,
+        Uuid::new_v4(),
+        memorose_common::MemoryType::Factual,
+        "Alice used to live in Berlin".into(),
+        None,
+    );
+    engine.store_memory_units(vec![unit.clone()]).await?;
+    let forgotten = memorose_common::ForgettingTombstone {
+        user_id: "alice".into(),
+        org_id: None,
+        target_kind: memorose_common::ForgetTargetKind::MemoryUnit,
+        target_id: unit.id.to_string(),
+        reason_query: "where alice lived".into(),
+        created_at: chrono::Utc::now(),
+        preview_id: None,
+        mode: memorose_common::ForgetMode::Logical,
+    };
+    engine.mark_memory_unit_forgotten("alice", unit.id, &forgotten)?;
+
+    engine
+        .soft_delete_memory_unit("alice", unit.id, 30)
+        .await?
+        .expect("unit exists");
+    let tombstone = engine
+        .get_memory_unit_tombstone("alice", unit.id)?
+        .expect("still forgotten");
+    assert_eq!(tombstone.reason_query, "where alice lived");
+
+    assert!(engine.restore_memory_unit("alice", unit.id).await?);
+    assert!(engine.list_trashed_memory_units("alice")?.is_empty());
+    assert!(engine.is_memory_unit_forgotten("alice", unit.id)?);
+    assert!(engine.get_memory_unit("alice", unit.id).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_org_policy_enforces_event_quota_and_retention() -> Result<()> {
+    use memorose_common::OrgPolicy;
+
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let stream_id = Uuid::new_v4();
+    let org_event = |user_id: &str, text: &str| {
+        Event::new(
+            Some("acme".into()),
+            user_id.into(),
+            None,
+            stream_id,
+            EventContent::Text(text.into()),
+        )
+    };
+
+    engine.set_org_policy(
+        "acme",
+        &OrgPolicy {
+            max_events: Some(2),
+            retention_days: Some(30),
+        },
+    )?;
+    engine.ingest_event(org_event("alice", "first")).await?;
+    engine.ingest_event(org_event("bob", "second")).await?;
+    assert!(engine
+        .ingest_event(org_event("alice", "third"))
+        .await
+        .is_err());
+    // Events outside the organization are not counted against it.
+    engine
+        .ingest_event(Event::new(
+            None,
+            "alice".into(),
             None,
             stream_id,
-            memorose_common::MemoryType::Factual,
-            format!("base {}", i),
-            None,
-        );
-        engine.store_memory_unit(unit).await?;
-    }
+            EventContent::Text("personal".into()),
+        ))
+        .await?;
+    assert_eq!(engine.count_org_events("acme")?, 2);
+    let mut users = engine.list_org_users("acme")?;
+    users.sort();
+    assert_eq!(users, vec!["alice".to_string(), "bob".to_string()]);
 
-    for i in 0..2 {
-        let unit = MemoryUnit::new(
-            None,
-            TEST_USER.into(),
+    let new_unit = |org_id: Option<&str>, age_days: i64| {
+        let mut unit = MemoryUnit::new(
+            org_id.map(str::to_string),
+            "alice".into(),
             None,
             stream_id,
             memorose_common::MemoryType::Factual,
-            format!("delta {}", i),
+            format!("note aged {} days", age_days),
             None,
         );
-        engine.store_memory_unit(unit).await?;
-    }
-
-    let (before, after) = engine.bump_l1_count_and_get_range(TEST_USER, 2).await?;
-    assert_eq!((before, after), (4, 6));
-    assert!(before / 5 < after / 5);
-
-    let unit = MemoryUnit::new(
-        None,
-        TEST_USER.into(),
-        None,
-        stream_id,
-        memorose_common::MemoryType::Factual,
-        "delta 2".into(),
-        None,
-    );
-    engine.store_memory_unit(unit).await?;
-    let (before, after) = engine.bump_l1_count_and_get_range(TEST_USER, 1).await?;
-    assert_eq!((before, after), (6, 7));
-    assert!(!(before / 5 < after / 5));
+        unit.transaction_time = Utc::now() - chrono::Duration::days(age_days);
+        unit
+    };
+    let expired = new_unit(Some("acme"), 45);
+    let fresh = new_unit(Some("acme"), 5);
+    let personal = new_unit(None, 45);
+    engine
+        .store_memory_units(vec![expired.clone(), fresh.clone(), personal.clone()])
+        .await?;
 
+    assert_eq!(engine.apply_org_retention("acme").await?, 1);
+    assert!(engine.get_memory_unit("alice", expired.id).await?.is_none());
+    assert!(engine.get_memory_unit("alice", fresh.id).await?.is_some());
+    assert!(engine
+        .get_memory_unit("alice", personal.id)
+        .await?
+        .is_some());
     Ok(())
 }
 
 #[tokio::test]
-async fn test_text_search_returns_local_memories() -> Result<()> {
-    let temp_dir = tempdir()?;
+async fn test_user_key_seals_content_at_rest_and_revocation_shreds_it() -> Result<()> {
+    use base64::Engine as _;
+
     let engine =
-        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        MemoroseEngine::new_in_memory()
+            .await?
+            .with_key_manager(crate::crypto::KeyManager::new(
+                memorose_common::config::EncryptionConfig {
+                    local_master_key: Some(
+                        base64::engine::general_purpose::STANDARD.encode([9u8; 32]),
+                    ),
+                    ..Default::default()
+                },
+            ));
     let stream_id = Uuid::new_v4();
+    let record = engine.create_user_key("carol", "local:carol").await?;
+    engine.put_user_key(&record).await?;
+    assert!(engine
+        .create_user_key("carol", "local:carol")
+        .await
+        .is_err());
 
-    let primary = MemoryUnit::new(
+    let event = Event::new(
         None,
-        TEST_USER.into(),
+        "carol".into(),
         None,
         stream_id,
-        memorose_common::MemoryType::Factual,
-        "Cross stream retrieval phrase".into(),
-        None,
+        EventContent::Text("my passport number".into()),
     );
-    let secondary = MemoryUnit::new(
+    engine.ingest_event(event.clone()).await?;
+    let unit = MemoryUnit::new(
         None,
-        TEST_USER.into(),
+        "carol".into(),
         None,
         stream_id,
-        memorose_common::MemoryType::Factual,
-        "Cross stream retrieval phrase".into(),
+        MemoryType::Factual,
+        "carol's passport expires in May".into(),
         None,
     );
+    engine.store_memory_unit(unit.clone()).await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    for key in [
+        format!("u:carol:event:{}", event.id),
+        format!("u:carol:unit:{}", unit.id),
+    ] {
+        let raw = engine.kv_store.get(key.as_bytes())?.expect("stored");
+        assert!(!String::from_utf8_lossy(&raw).contains("passport"));
+    }
+    let stored_event = engine
+        .get_event("carol", &event.id.to_string())
+        .await?
+        .expect("event");
+    assert!(
+        matches!(stored_event.content, EventContent::Text(ref text) if text == "my passport number")
+    );
+    assert_eq!(
+        engine
+            .get_memory_unit("carol", unit.id)
+            .await?
+            .expect("unit")
+            .content,
+        unit.content
+    );
+    assert!(engine.index.contains_unit(&unit.id.to_string())?);
+
+    let mut revoked = record.clone();
+    revoked.wrapped_key = None;
+    revoked.revoked_at = Some(Utc::now());
+    engine.put_user_key(&revoked).await?;
+    engine.index.commit()?;
+    engine.index.reload()?;
+
+    let error = engine.get_memory_unit("carol", unit.id).await.unwrap_err();
+    assert!(crate::crypto::is_key_unavailable(&error));
+    assert!(!engine.index.contains_unit(&unit.id.to_string())?);
+    assert!(engine
+        .ingest_event(Event::new(
+            None,
+            "carol".into(),
+            None,
+            stream_id,
+            EventContent::Text("after revocation".into()),
+        ))
+        .await
+        .is_err());
+    // The shredded event can never be consolidated, so it leaves the pending queue.
+    assert!(engine.fetch_pending_events().await?.is_empty());
+    assert_eq!(engine.count_pending_events().await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_moderation_audit_lists_a_users_records_newest_first() -> Result<()> {
+    use memorose_common::{ModerationAuditRecord, ModerationOutcome, ModerationStage};
+
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let record = |user_id: &str, minutes_ago: i64, outcome| ModerationAuditRecord {
+        id: Uuid::new_v4(),
+        user_id: user_id.to_string(),
+        app_id: Some("kids-tutor".to_string()),
+        stage: ModerationStage::Ingest,
+        outcome,
+        item_id: Uuid::new_v4(),
+        categories: vec!["ssn".to_string()],
+        recorded_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+    };
+    let older = record("dave", 10, ModerationOutcome::Blocked);
+    let newer = record("dave", 1, ModerationOutcome::Stripped);
+    engine.record_moderation(&older)?;
+    engine.record_moderation(&newer)?;
+    engine.record_moderation(&record("erin", 0, ModerationOutcome::Tagged))?;
+
+    assert_eq!(
+        engine.list_moderation_audit("dave", 10)?,
+        vec![newer.clone(), older]
+    );
+    assert_eq!(engine.list_moderation_audit("dave", 1)?, vec![newer]);
+    assert!(engine.list_moderation_audit("frank", 10)?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_share_grants_expose_only_granted_memories() -> Result<()> {
+    use memorose_common::{GroupMembershipUpdate, MemoryShareGrant, ShareGrantee, ShareResource};
 
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let shared_stream = Uuid::new_v4();
+    let private_stream = Uuid::new_v4();
+    let new_unit = |stream_id, content: &str| {
+        MemoryUnit::new(
+            None,
+            "alice".into(),
+            None,
+            stream_id,
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    };
+    let team_note = new_unit(shared_stream, "Roadmap budget for the platform team");
+    let private_note = new_unit(private_stream, "Personal budget for the holidays");
     engine
-        .store_memory_units(vec![primary.clone(), secondary.clone()])
+        .store_memory_units(vec![team_note.clone(), private_note.clone()])
         .await?;
     engine.index.commit()?;
     engine.index.reload()?;
 
-    let results = engine
-        .search_text(TEST_USER, "cross stream retrieval", 10, false, None)
+    let visible_to_bob = || async {
+        let mut ids = engine
+            .search_hybrid_with_shared_explained(
+                "bob",
+                None,
+                None,
+                None,
+                None,
+                None,
+                "budget",
+                &[0.0; 8],
+                10,
+                0,
+                false,
+                Some(0.0),
+                0,
+                None,
+                None,
+                None,
+                0.0,
+                None,
+                VectorSearchMode::Text,
+                SearchExplainOptions::default(),
+            )
+            .await?
+            .results
+            .into_iter()
+            .map(|(hit, _)| hit.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        anyhow::Ok(ids)
+    };
+
+    assert!(visible_to_bob().await?.is_empty());
+
+    let stream_grant = MemoryShareGrant::new(
+        "alice".into(),
+        ShareResource::Stream(shared_stream),
+        ShareGrantee::User("bob".into()),
+    );
+    engine.put_share_grant(&stream_grant)?;
+    assert_eq!(visible_to_bob().await?, vec![team_note.id]);
+    assert!(engine.can_read_memory_unit("bob", &team_note)?);
+    assert!(!engine.can_read_memory_unit("bob", &private_note)?);
+
+    let group_grant = MemoryShareGrant::new(
+        "alice".into(),
+        ShareResource::MemoryUnit(private_note.id),
+        ShareGrantee::Group("family".into()),
+    );
+    engine.put_share_grant(&group_grant)?;
+    assert_eq!(visible_to_bob().await?, vec![team_note.id]);
+    engine.apply_group_membership_update(&GroupMembershipUpdate {
+        group_id: "family".into(),
+        user_id: "bob".into(),
+        member: true,
+    })?;
+    let mut both = vec![team_note.id, private_note.id];
+    both.sort();
+    assert_eq!(visible_to_bob().await?, both);
+    assert_eq!(
+        engine.list_group_members("family")?,
+        vec!["bob".to_string()]
+    );
+
+    let text_hits = engine
+        .search_text_with_shared("bob", None, "budget", 10, false, None)
         .await?;
+    assert_eq!(text_hits.len(), 2);
 
-    assert!(!results.is_empty());
-    assert_eq!(results.len(), 2);
+    assert!(engine.revoke_share_grant("alice", stream_grant.id)?);
+    assert!(!engine.revoke_share_grant("alice", stream_grant.id)?);
+    assert_eq!(visible_to_bob().await?, vec![private_note.id]);
+    assert_eq!(engine.list_share_grants("alice")?, vec![group_grant]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_streams_list_by_app_and_hide_archived() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let mut support = memorose_common::MemoryStream::new(
+        "alice".into(),
+        Some("support".into()),
+        Some("Refund request".into()),
+    );
+    support.transaction_time -= chrono::Duration::minutes(5);
+    let mut tutor = memorose_common::MemoryStream::new("alice".into(), Some("tutor".into()), None);
+    tutor.metadata.insert("course".into(), "algebra".into());
+    let other_user = memorose_common::MemoryStream::new("bob".into(), Some("tutor".into()), None);
+    for stream in [&support, &tutor, &other_user] {
+        engine.put_memory_stream(stream)?;
+    }
+
+    assert_eq!(
+        engine.list_memory_streams("alice", None, false)?,
+        vec![tutor.clone(), support.clone()]
+    );
+    assert_eq!(
+        engine.list_memory_streams("alice", Some("support"), false)?,
+        vec![support.clone()]
+    );
 
+    support.archived_at = Some(Utc::now());
+    engine.put_memory_stream(&support)?;
+    assert_eq!(
+        engine.list_memory_streams("alice", None, false)?,
+        vec![tutor.clone()]
+    );
+    assert_eq!(engine.list_memory_streams("alice", None, true)?.len(), 2);
+    assert_eq!(
+        engine.get_memory_stream("alice", support.id)?,
+        Some(support)
+    );
+    assert!(engine.get_memory_stream("bob", tutor.id)?.is_none());
     Ok(())
 }
 
@@ -6050,6 +8438,48 @@ async fn test_startup_reconcile_removes_persisted_org_views() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_changing_text_index_user_buckets_rebuilds_index_on_open() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, false, false).await?;
+    let unit = MemoryUnit::new(
+        None,
+        TEST_USER.into(),
+        None,
+        Uuid::new_v4(),
+        memorose_common::MemoryType::Factual,
+        "Bucketed heron sighting".to_string(),
+        None,
+    );
+    engine.store_memory_unit(unit.clone()).await?;
+    engine.index.commit()?;
+    drop(engine);
+
+    let storage_config = memorose_common::config::StorageConfig {
+        index_user_buckets: 4,
+        ..Default::default()
+    };
+    let reopened = MemoroseEngine::new_with_storage_config(
+        temp_dir.path(),
+        storage_config,
+        false,
+        false,
+        0.5,
+        768,
+    )
+    .await?;
+    assert_eq!(reopened.index.bucket_count(), 4);
+    assert!(reopened.index.contains_unit(&unit.id.to_string())?);
+
+    let hits = reopened
+        .search_text(TEST_USER, "heron", 10, false, None)
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, unit.id);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_startup_reconcile_removes_org_record_without_live_sources() -> Result<()> {
     let temp_dir = tempdir()?;
@@ -6351,62 +8781,208 @@ async fn test_batch_get_neighbors_and_multi_hop_traverse() -> Result<()> {
     ] {
         engine.graph().add_edge(&edge).await?;
     }
-    engine.graph().flush().await?;
+    engine.graph().flush().await?;
+
+    let neighbors = engine
+        .batch_get_neighbors(TEST_USER, &[node_a, node_b])
+        .await?;
+    assert_eq!(neighbors.get(&node_a).map(Vec::len), Some(1));
+    assert_eq!(neighbors.get(&node_b).map(Vec::len), Some(2));
+
+    let traversed = engine
+        .multi_hop_traverse(TEST_USER, vec![node_a], 2, Some(0.5))
+        .await?;
+    assert!(traversed.contains(&node_a));
+    assert!(traversed.contains(&node_b));
+    assert!(traversed.contains(&node_c));
+    assert!(!traversed.contains(&node_d));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_neighbors_cached_query_cache_stats_and_invalidate() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let node_a = Uuid::new_v4();
+    let node_b = Uuid::new_v4();
+    let edge = memorose_common::GraphEdge::new(
+        TEST_USER.into(),
+        node_a,
+        node_b,
+        memorose_common::RelationType::RelatedTo,
+        0.9,
+    );
+    engine.graph().add_edge(&edge).await?;
+    engine.graph().flush().await?;
+
+    let first = engine.get_neighbors_cached(TEST_USER, node_a).await?;
+    assert_eq!(first.len(), 1);
+
+    let stats = engine.query_cache_stats().await;
+    assert_eq!(stats.edge_cache_size, 1);
+
+    engine
+        .graph()
+        .delete_edges_for_node(TEST_USER, node_a)
+        .await?;
+    let cached = engine.get_neighbors_cached(TEST_USER, node_a).await?;
+    assert_eq!(cached.len(), 1);
+
+    engine.invalidate_query_cache(TEST_USER).await;
+    let stats_after_invalidate = engine.query_cache_stats().await;
+    assert_eq!(stats_after_invalidate.edge_cache_size, 0);
+    let after_invalidate = engine.get_neighbors_cached(TEST_USER, node_a).await?;
+    assert!(after_invalidate.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_warm_query_cache_preloads_most_recently_active_users() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let system_kv = engine.system_kv();
+    system_kv.put(b"active_user:cold", b"")?;
+    system_kv.put(b"active_user:warm", b"1000")?;
+    system_kv.put(b"active_user:hot", b"2000")?;
+    assert_eq!(
+        engine.recently_active_users(10)?,
+        vec!["hot".to_string(), "warm".to_string(), "cold".to_string()]
+    );
+
+    let hot_node = Uuid::new_v4();
+    for user in ["hot", "warm"] {
+        engine
+            .add_graph_edge(&memorose_common::GraphEdge::new(
+                user.into(),
+                hot_node,
+                Uuid::new_v4(),
+                memorose_common::RelationType::RelatedTo,
+                0.5,
+            ))
+            .await?;
+    }
+
+    // Only the hottest user fits the budget.
+    assert_eq!(engine.warm_query_cache(1, 8).await?, 1);
+    assert_eq!(engine.query_cache_stats().await.edge_cache_size, 1);
+    assert_eq!(engine.get_neighbors_cached("hot", hot_node).await?.len(), 1);
+    assert_eq!(engine.query_cache_stats().await.edge_cache_size, 1);
+
+    assert_eq!(engine.warm_query_cache(0, 8).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_explain_graph_query_reports_plan_estimates_and_paths() -> Result<()> {
+    use crate::graph::{GraphQueryBuilder, TraversalDirection, TraversalSpec};
+
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+    let root = Uuid::new_v4();
+    for _ in 0..3 {
+        engine
+            .add_graph_edge(&memorose_common::GraphEdge::new(
+                TEST_USER.into(),
+                root,
+                Uuid::new_v4(),
+                memorose_common::RelationType::RelatedTo,
+                0.5,
+            ))
+            .await?;
+    }
 
-    let neighbors = engine
-        .batch_get_neighbors(TEST_USER, &[node_a, node_b])
-        .await?;
-    assert_eq!(neighbors.get(&node_a).map(Vec::len), Some(1));
-    assert_eq!(neighbors.get(&node_b).map(Vec::len), Some(2));
+    let spec = TraversalSpec {
+        relation_types: vec![],
+        direction: TraversalDirection::Outgoing,
+        min_hops: 1,
+        max_hops: 1,
+        weight_threshold: None,
+    };
 
-    let traversed = engine
-        .multi_hop_traverse(TEST_USER, vec![node_a], 2, Some(0.5))
-        .await?;
-    assert!(traversed.contains(&node_a));
-    assert!(traversed.contains(&node_b));
-    assert!(traversed.contains(&node_c));
-    assert!(!traversed.contains(&node_d));
+    // Single-node, one-hop, unfiltered: the cached neighbour path.
+    let one_hop = GraphQueryBuilder::new(TEST_USER.to_string())
+        .start_from(vec![root])
+        .with_traversal(spec.clone());
+    let explained = engine.explain_graph_query(&one_hop).await?;
+    assert!(explained.uses_query_cache);
+    assert!(!explained.cache_hit);
+    assert!(!explained.uses_batch_expansion);
+    assert!(explained.uses_adjacency_index);
+    assert_eq!(explained.sampled_fanout.get("RelatedTo"), Some(&3.0));
+    assert_eq!(explained.estimated_result_rows, 3);
+
+    engine.get_neighbors_cached(TEST_USER, root).await?;
+    assert!(engine.explain_graph_query(&one_hop).await?.cache_hit);
+
+    // Two hops with a limit go through batched expansion.
+    let two_hops = GraphQueryBuilder::new(TEST_USER.to_string())
+        .start_from(vec![root])
+        .with_traversal(spec.clone())
+        .with_traversal(spec)
+        .limit(5);
+    let explained = engine.explain_graph_query(&two_hops).await?;
+    assert!(!explained.uses_query_cache);
+    assert!(explained.uses_batch_expansion);
+    assert!(explained.plan.starts_with("Limit (count=5)"));
+    assert_eq!(explained.steps.len(), 5);
+    assert_eq!(explained.steps[2].estimated_rows, 9);
+    assert_eq!(explained.estimated_result_rows, 5);
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_get_neighbors_cached_query_cache_stats_and_invalidate() -> Result<()> {
+async fn test_graph_writes_invalidate_cached_neighbourhoods() -> Result<()> {
     let temp_dir = tempdir()?;
     let engine =
         MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
 
     let node_a = Uuid::new_v4();
     let node_b = Uuid::new_v4();
-    let edge = memorose_common::GraphEdge::new(
-        TEST_USER.into(),
-        node_a,
-        node_b,
-        memorose_common::RelationType::RelatedTo,
-        0.9,
-    );
-    engine.graph().add_edge(&edge).await?;
-    engine.graph().flush().await?;
+    let node_c = Uuid::new_v4();
+    let edge = |target| {
+        memorose_common::GraphEdge::new(
+            TEST_USER.into(),
+            node_a,
+            target,
+            memorose_common::RelationType::RelatedTo,
+            0.5,
+        )
+    };
 
-    let first = engine.get_neighbors_cached(TEST_USER, node_a).await?;
-    assert_eq!(first.len(), 1);
+    engine.add_graph_edge(&edge(node_b)).await?;
+    assert_eq!(
+        engine.get_neighbors_cached(TEST_USER, node_a).await?.len(),
+        1
+    );
 
-    let stats = engine.query_cache_stats().await;
-    assert_eq!(stats.edge_cache_size, 1);
+    // A single-edge write (the path Raft-applied UpdateGraph entries take) drops the stale entry.
+    engine.add_graph_edge(&edge(node_c)).await?;
+    assert_eq!(
+        engine.get_neighbors_cached(TEST_USER, node_a).await?.len(),
+        2
+    );
 
+    // Reinforcement creates b -> c; b's cached (empty) neighbourhood must refresh.
+    assert!(engine
+        .get_neighbors_cached(TEST_USER, node_b)
+        .await?
+        .is_empty());
     engine
-        .graph()
-        .delete_edges_for_node(TEST_USER, node_a)
+        .reinforce_associations(TEST_USER, vec![node_b.to_string(), node_c.to_string()])
         .await?;
-    let cached = engine.get_neighbors_cached(TEST_USER, node_a).await?;
-    assert_eq!(cached.len(), 1);
-
-    engine.invalidate_query_cache(TEST_USER).await;
-    let stats_after_invalidate = engine.query_cache_stats().await;
-    assert_eq!(stats_after_invalidate.edge_cache_size, 1);
-    engine.query_cache.clear().await;
-    let after_invalidate = engine.get_neighbors_cached(TEST_USER, node_a).await?;
-    assert!(after_invalidate.is_empty());
+    let reinforced = engine.get_neighbors_cached(TEST_USER, node_b).await?;
+    assert_eq!(reinforced.len(), 1);
+    assert_eq!(reinforced[0].target_id, node_c);
 
     Ok(())
 }
@@ -6602,129 +9178,573 @@ async fn test_engine_l3_task_helpers_and_auto_plan_goal() -> Result<()> {
         )
         .await?;
 
-    let tasks = engine.list_l3_tasks(user_id).await?;
-    assert_eq!(tasks.len(), 2);
-
-    let plan = tasks.iter().find(|task| task.title == "Plan").unwrap();
-    let execute = tasks.iter().find(|task| task.title == "Execute").unwrap();
-    assert_eq!(plan.parent_id, Some(goal_id));
-    assert_eq!(execute.parent_id, Some(goal_id));
-    assert_eq!(execute.description, "Ship it");
-    assert_eq!(execute.dependencies, vec![plan.task_id]);
+    let tasks = engine.list_l3_tasks(user_id).await?;
+    assert_eq!(tasks.len(), 2);
+
+    let plan = tasks.iter().find(|task| task.title == "Plan").unwrap();
+    let execute = tasks.iter().find(|task| task.title == "Execute").unwrap();
+    assert_eq!(plan.parent_id, Some(goal_id));
+    assert_eq!(execute.parent_id, Some(goal_id));
+    assert_eq!(execute.description, "Ship it");
+    assert_eq!(execute.dependencies, vec![plan.task_id]);
+    assert_eq!(
+        engine
+            .get_l3_task(user_id, plan.task_id)
+            .await?
+            .map(|task| task.title),
+        Some("Plan".into())
+    );
+
+    let outgoing = engine
+        .graph()
+        .get_outgoing_edges(user_id, execute.task_id)
+        .await?;
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].target_id, goal_id);
+    assert_eq!(outgoing[0].relation, RelationType::IsSubTaskOf);
+
+    let empty_engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path().join("empty"), 1000, true, true)
+            .await?
+            .with_arbitrator(crate::arbitrator::Arbitrator::with_client(Arc::new(
+                MockCorrectionLLM {
+                    response: "[]".into(),
+                },
+            )));
+    empty_engine
+        .auto_plan_goal(
+            None,
+            "nobody".into(),
+            None,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "noop".into(),
+            0,
+        )
+        .await?;
+    assert!(empty_engine.list_l3_tasks("nobody").await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_ready_l3_tasks_filters_blocked_and_missing_dependencies() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let user_id = "task_user";
+
+    let mut completed =
+        memorose_common::L3Task::new(None, user_id.into(), None, "Done".into(), "done".into());
+    completed.status = memorose_common::TaskStatus::Completed;
+
+    let ready =
+        memorose_common::L3Task::new(None, user_id.into(), None, "Ready".into(), "ready".into());
+
+    let mut blocked_by_progress = memorose_common::L3Task::new(
+        None,
+        user_id.into(),
+        None,
+        "Waiting".into(),
+        "waiting".into(),
+    );
+    blocked_by_progress.dependencies = vec![completed.task_id];
+    blocked_by_progress.status = memorose_common::TaskStatus::InProgress;
+
+    let mut dependent_ready = memorose_common::L3Task::new(
+        None,
+        user_id.into(),
+        None,
+        "DependentReady".into(),
+        "dep ready".into(),
+    );
+    dependent_ready.dependencies = vec![completed.task_id];
+
+    let mut blocked_missing_dep = memorose_common::L3Task::new(
+        None,
+        user_id.into(),
+        None,
+        "MissingDep".into(),
+        "missing dep".into(),
+    );
+    blocked_missing_dep.dependencies = vec![Uuid::new_v4()];
+
+    let mut blocked_incomplete_dep = memorose_common::L3Task::new(
+        None,
+        user_id.into(),
+        None,
+        "BlockedIncomplete".into(),
+        "blocked incomplete".into(),
+    );
+    blocked_incomplete_dep.dependencies = vec![ready.task_id];
+
+    for task in [
+        completed.clone(),
+        ready.clone(),
+        blocked_by_progress,
+        dependent_ready.clone(),
+        blocked_missing_dep,
+        blocked_incomplete_dep,
+    ] {
+        engine.store_l3_task(&task).await?;
+    }
+
+    let mut titles = engine
+        .get_ready_l3_tasks(user_id)
+        .await?
+        .into_iter()
+        .map(|task| task.title)
+        .collect::<Vec<_>>();
+    titles.sort();
+
+    assert_eq!(
+        titles,
+        vec!["DependentReady".to_string(), "Ready".to_string()]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auto_planner_policy_caps_subtasks_and_parks_milestones_for_approval() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+            .await?
+            .with_arbitrator(crate::arbitrator::Arbitrator::with_client(Arc::new(
+                MockCorrectionLLM {
+                    response: r#"[{"summary":"Plan"},{"summary":"Build","dependencies":["Plan","Polish"]},{"summary":"Polish"}]"#.into(),
+                },
+            )))
+            .with_auto_planner_policy(AutoPlannerPolicy {
+                max_subtasks: 2,
+                max_depth: 5,
+                require_approval: true,
+            });
+
+    let user_id = "planner_user";
+    let mut goal = MemoryUnit::new(
+        None,
+        user_id.into(),
+        None,
+        Uuid::new_v4(),
+        MemoryType::Procedural,
+        "Ship the release".into(),
+        None,
+    );
+    goal.level = 3;
+    engine.store_memory_units(vec![goal.clone()]).await?;
+
+    let preview = engine
+        .get_goal_plan(user_id, goal.id)
+        .await?
+        .expect("goal exists");
+    assert_eq!(preview.status, GoalPlanStatus::Preview);
+    assert_eq!(preview.milestones.len(), 2);
+    assert!(engine.list_l3_tasks(user_id).await?.is_empty());
+    assert!(engine
+        .get_goal_plan(user_id, Uuid::new_v4())
+        .await?
+        .is_none());
+
+    engine
+        .auto_plan_goal(
+            None,
+            user_id.into(),
+            None,
+            goal.stream_id,
+            goal.id,
+            goal.content.clone(),
+            1,
+        )
+        .await?;
+    let plan = engine
+        .get_goal_plan(user_id, goal.id)
+        .await?
+        .expect("goal exists");
+    assert_eq!(plan.status, GoalPlanStatus::AwaitingApproval);
+    let titles = plan
+        .milestones
+        .iter()
+        .map(|task| task.title.as_str())
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(titles, std::collections::HashSet::from(["Plan", "Build"]));
+    let build = plan
+        .milestones
+        .iter()
+        .find(|task| task.title == "Build")
+        .unwrap();
+    assert_eq!(
+        build.dependencies.len(),
+        1,
+        "dependency on a cut milestone is dropped"
+    );
+    assert!(engine.get_ready_l3_tasks(user_id).await?.is_empty());
+
+    let updates = engine.goal_plan_approval_updates(user_id, goal.id).await?;
+    assert_eq!(updates.len(), 2);
+    for update in &updates {
+        engine.apply_l3_task_status_update(update).await?;
+    }
+    assert_eq!(
+        engine
+            .get_goal_plan(user_id, goal.id)
+            .await?
+            .expect("goal exists")
+            .status,
+        GoalPlanStatus::Approved
+    );
+    assert!(engine
+        .goal_plan_approval_updates(user_id, goal.id)
+        .await?
+        .is_empty());
+    let ready = engine.get_ready_l3_tasks(user_id).await?;
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].title, "Plan");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_ready_l3_tasks_resolves_blocks_edges_topologically() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let user_id = "task_user";
+    let new_task = |title: &str| {
+        memorose_common::L3Task::new(None, user_id.into(), None, title.into(), String::new())
+    };
+
+    let design = new_task("Design");
+    let build = new_task("Build");
+    let release = new_task("Release");
+    let loop_a = new_task("LoopA");
+    let loop_b = new_task("LoopB");
+    for task in [&design, &build, &release, &loop_a, &loop_b] {
+        engine.create_l3_task(task).await?;
+    }
+
+    for (blocker, blocked) in [
+        (design.task_id, build.task_id),
+        (build.task_id, release.task_id),
+        (loop_a.task_id, loop_b.task_id),
+    ] {
+        let edge = engine
+            .build_l3_task_dependency_edge(user_id, blocker, blocked)
+            .await?;
+        assert_eq!(edge.relation, RelationType::Blocks);
+        engine.graph().add_edge(&edge).await?;
+    }
+    assert!(engine
+        .build_l3_task_dependency_edge(user_id, release.task_id, design.task_id)
+        .await
+        .is_err());
+    assert!(engine
+        .build_l3_task_dependency_edge(user_id, design.task_id, design.task_id)
+        .await
+        .is_err());
+    assert!(engine
+        .build_l3_task_dependency_edge(user_id, Uuid::new_v4(), design.task_id)
+        .await
+        .is_err());
+
+    // A cycle written directly to the graph must never surface as ready.
+    engine
+        .graph()
+        .add_edge(&GraphEdge::new(
+            user_id.into(),
+            loop_b.task_id,
+            loop_a.task_id,
+            RelationType::Blocks,
+            1.0,
+        ))
+        .await?;
+
+    let ready_titles = |tasks: Vec<memorose_common::L3Task>| {
+        tasks.into_iter().map(|task| task.title).collect::<Vec<_>>()
+    };
     assert_eq!(
-        engine
-            .get_l3_task(user_id, plan.task_id)
-            .await?
-            .map(|task| task.title),
-        Some("Plan".into())
+        ready_titles(engine.get_ready_l3_tasks(user_id).await?),
+        vec!["Design".to_string()]
     );
 
-    let outgoing = engine
-        .graph()
-        .get_outgoing_edges(user_id, execute.task_id)
+    engine
+        .apply_l3_task_status_update(&memorose_common::L3TaskStatusUpdate {
+            user_id: user_id.into(),
+            task_id: design.task_id,
+            status: memorose_common::TaskStatus::Completed,
+            progress: None,
+            result_summary: None,
+            updated_at: Utc::now(),
+        })
         .await?;
-    assert_eq!(outgoing.len(), 1);
-    assert_eq!(outgoing[0].target_id, goal_id);
-    assert_eq!(outgoing[0].relation, RelationType::IsSubTaskOf);
+    assert_eq!(
+        ready_titles(engine.get_ready_l3_tasks(user_id).await?),
+        vec!["Build".to_string()]
+    );
+    Ok(())
+}
 
-    let empty_engine =
-        MemoroseEngine::new_with_default_threshold(temp_dir.path().join("empty"), 1000, true, true)
-            .await?
-            .with_arbitrator(crate::arbitrator::Arbitrator::with_client(Arc::new(
-                MockCorrectionLLM {
-                    response: "[]".into(),
-                },
-            )));
-    empty_engine
-        .auto_plan_goal(
+#[tokio::test]
+async fn test_list_goal_units_uses_stream_index_and_subtask_edges() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let user_id = "goal_user";
+    let (stream_a, stream_b) = (Uuid::new_v4(), Uuid::new_v4());
+    let new_unit = |stream_id: Uuid, content: &str, level: u8| {
+        let mut unit = MemoryUnit::new(
             None,
-            "nobody".into(),
+            user_id.into(),
             None,
-            Uuid::new_v4(),
-            Uuid::new_v4(),
-            "noop".into(),
-            0,
-        )
+            stream_id,
+            MemoryType::Procedural,
+            content.into(),
+            None,
+        );
+        unit.level = level;
+        unit
+    };
+    let goal_a = new_unit(stream_a, "Launch the beta", 3);
+    let goal_b = new_unit(stream_b, "Hire a designer", 3);
+    let mut profile = new_unit(stream_a, "- Prefers tea", 3);
+    profile.keywords = vec!["profile".into()];
+    let fact = new_unit(stream_a, "Beta date is fixed", 1);
+    engine
+        .store_memory_units(vec![goal_a.clone(), goal_b.clone(), profile, fact])
         .await?;
-    assert!(empty_engine.list_l3_tasks("nobody").await?.is_empty());
 
+    let mut all_goals = engine
+        .list_goal_units(user_id, None)
+        .await?
+        .into_iter()
+        .map(|unit| unit.id)
+        .collect::<Vec<_>>();
+    all_goals.sort();
+    let mut expected = vec![goal_a.id, goal_b.id];
+    expected.sort();
+    assert_eq!(all_goals, expected);
+    assert_eq!(
+        engine
+            .list_goal_units(user_id, Some(stream_a))
+            .await?
+            .into_iter()
+            .map(|unit| unit.id)
+            .collect::<Vec<_>>(),
+        vec![goal_a.id]
+    );
+
+    // Units written before the index existed are backfilled on first read.
+    let goal_key = MemoroseEngine::goal_index_key(&goal_b).expect("goal is indexed");
+    engine.kv().delete(goal_key.as_bytes())?;
+    engine
+        .system_kv()
+        .delete(format!("l3_idx_ready:{}", user_id).as_bytes())?;
+    assert_eq!(
+        engine
+            .list_goal_units(user_id, Some(stream_b))
+            .await?
+            .into_iter()
+            .map(|unit| unit.id)
+            .collect::<Vec<_>>(),
+        vec![goal_b.id]
+    );
+
+    let mut milestone =
+        memorose_common::L3Task::new(None, user_id.into(), None, "Milestone".into(), "".into());
+    milestone.parent_id = Some(goal_a.id);
+    let mut step =
+        memorose_common::L3Task::new(None, user_id.into(), None, "Step".into(), "".into());
+    step.parent_id = Some(milestone.task_id);
+    engine.create_l3_task(&milestone).await?;
+    engine.create_l3_task(&step).await?;
+
+    let subtasks = engine.get_l3_subtask_map(user_id, &[goal_a.id], 10).await?;
+    assert_eq!(subtasks[&goal_a.id][0].task_id, milestone.task_id);
+    assert_eq!(subtasks[&milestone.task_id][0].task_id, step.task_id);
+    let shallow = engine.get_l3_subtask_map(user_id, &[goal_a.id], 1).await?;
+    assert!(!shallow.contains_key(&milestone.task_id));
     Ok(())
 }
 
 #[tokio::test]
-async fn test_get_ready_l3_tasks_filters_blocked_and_missing_dependencies() -> Result<()> {
+async fn test_process_task_deadlines_marks_overdue_and_instantiates_recurrences() -> Result<()> {
+    use memorose_common::{TaskDeadlineEventKind, TaskMetadata, TaskStatus};
+
     let temp_dir = tempdir()?;
     let engine =
         MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
-    let user_id = "task_user";
-
-    let mut completed =
-        memorose_common::L3Task::new(None, user_id.into(), None, "Done".into(), "done".into());
-    completed.status = memorose_common::TaskStatus::Completed;
-
-    let ready =
-        memorose_common::L3Task::new(None, user_id.into(), None, "Ready".into(), "ready".into());
+    let user_id = "deadline_user";
+    let now = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+    let new_task = |content: &str, due_at: DateTime<Utc>, recurrence: Option<&str>| {
+        let mut unit = MemoryUnit::new(
+            None,
+            user_id.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Procedural,
+            content.into(),
+            None,
+        );
+        let mut meta = TaskMetadata::new(TaskStatus::Pending, 0.0);
+        meta.due_at = Some(due_at);
+        meta.recurrence = recurrence.map(str::to_string);
+        unit.task_metadata = Some(meta);
+        unit
+    };
+    let soon = new_task("Send report", now + chrono::Duration::minutes(30), None);
+    let late = new_task("Renew domain", now - chrono::Duration::hours(1), None);
+    let standup = new_task(
+        "Daily standup",
+        now - chrono::Duration::minutes(5),
+        Some("daily"),
+    );
+    let later = new_task("Plan offsite", now + chrono::Duration::days(3), None);
+    engine
+        .store_memory_units(vec![soon.clone(), late.clone(), standup.clone(), later])
+        .await?;
 
-    let mut blocked_by_progress = memorose_common::L3Task::new(
-        None,
-        user_id.into(),
-        None,
-        "Waiting".into(),
-        "waiting".into(),
+    let mut notifications = engine.subscribe_task_deadline_events();
+    let events = engine
+        .process_task_deadlines(now, chrono::Duration::hours(1), 100)
+        .await?;
+    let kinds = |events: &[memorose_common::TaskDeadlineEvent], id: Uuid| {
+        events
+            .iter()
+            .filter(|event| event.unit_id == id)
+            .map(|event| event.kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        kinds(&events, soon.id),
+        vec![TaskDeadlineEventKind::Approaching]
     );
-    blocked_by_progress.dependencies = vec![completed.task_id];
-    blocked_by_progress.status = memorose_common::TaskStatus::InProgress;
-
-    let mut dependent_ready = memorose_common::L3Task::new(
-        None,
-        user_id.into(),
-        None,
-        "DependentReady".into(),
-        "dep ready".into(),
+    assert_eq!(
+        kinds(&events, late.id),
+        vec![TaskDeadlineEventKind::Overdue]
     );
-    dependent_ready.dependencies = vec![completed.task_id];
-
-    let mut blocked_missing_dep = memorose_common::L3Task::new(
-        None,
-        user_id.into(),
-        None,
-        "MissingDep".into(),
-        "missing dep".into(),
+    assert_eq!(
+        kinds(&events, standup.id),
+        vec![
+            TaskDeadlineEventKind::Overdue,
+            TaskDeadlineEventKind::Recurred
+        ]
     );
-    blocked_missing_dep.dependencies = vec![Uuid::new_v4()];
+    assert_eq!(notifications.try_recv()?.unit_id, events[0].unit_id);
 
-    let mut blocked_incomplete_dep = memorose_common::L3Task::new(
-        None,
-        user_id.into(),
-        None,
-        "BlockedIncomplete".into(),
-        "blocked incomplete".into(),
+    let late_meta = engine
+        .get_memory_unit(user_id, late.id)
+        .await?
+        .and_then(|unit| unit.task_metadata)
+        .expect("task metadata");
+    assert!(late_meta.overdue);
+
+    let next_id = events
+        .iter()
+        .find_map(|event| event.next_unit_id)
+        .expect("recurrence instantiated");
+    let previous = engine
+        .get_memory_unit(user_id, standup.id)
+        .await?
+        .and_then(|unit| unit.task_metadata)
+        .expect("task metadata");
+    assert!(previous.overdue);
+    assert!(previous.recurrence.is_none());
+    let next = engine
+        .get_memory_unit(user_id, next_id)
+        .await?
+        .and_then(|unit| unit.task_metadata)
+        .expect("task metadata");
+    assert_eq!(next.status, TaskStatus::Pending);
+    assert_eq!(next.recurrence.as_deref(), Some("daily"));
+    assert_eq!(
+        next.due_at,
+        Some(now - chrono::Duration::minutes(5) + chrono::Duration::days(1))
     );
-    blocked_incomplete_dep.dependencies = vec![ready.task_id];
 
-    for task in [
-        completed.clone(),
-        ready.clone(),
-        blocked_by_progress,
-        dependent_ready.clone(),
-        blocked_missing_dep,
-        blocked_incomplete_dep,
-    ] {
-        engine.store_l3_task(&task).await?;
+    // Approaching deadlines are announced once per due date; handled tasks leave the index.
+    let again = engine
+        .process_task_deadlines(now, chrono::Duration::hours(1), 100)
+        .await?;
+    assert!(again.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_l3_task_progress_rolls_up_subtasks() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    let user_id = "task_user";
+    let new_task = |title: &str, parent_id: Option<Uuid>| {
+        let mut task =
+            memorose_common::L3Task::new(None, user_id.into(), None, title.into(), String::new());
+        task.parent_id = parent_id;
+        task
+    };
+
+    let goal = new_task("Goal", None);
+    let done = new_task("Done", Some(goal.task_id));
+    let halfway = new_task("Halfway", Some(goal.task_id));
+    let leaf = new_task("Leaf", Some(halfway.task_id));
+    let mut cancelled = new_task("Cancelled", Some(goal.task_id));
+    cancelled.status = memorose_common::TaskStatus::Cancelled;
+    for task in [&goal, &done, &halfway, &leaf, &cancelled] {
+        engine.create_l3_task(task).await?;
     }
 
-    let mut titles = engine
-        .get_ready_l3_tasks(user_id)
+    let update = |task_id: Uuid, status: memorose_common::TaskStatus, progress: Option<f32>| {
+        memorose_common::L3TaskStatusUpdate {
+            user_id: user_id.into(),
+            task_id,
+            status,
+            progress,
+            result_summary: None,
+            updated_at: Utc::now(),
+        }
+    };
+    engine
+        .apply_l3_task_status_update(&update(
+            done.task_id,
+            memorose_common::TaskStatus::Completed,
+            None,
+        ))
         .await?
-        .into_iter()
-        .map(|task| task.title)
-        .collect::<Vec<_>>();
-    titles.sort();
+        .expect("task exists");
+    engine
+        .apply_l3_task_status_update(&update(
+            leaf.task_id,
+            memorose_common::TaskStatus::InProgress,
+            Some(0.5),
+        ))
+        .await?
+        .expect("task exists");
+    assert!(engine
+        .apply_l3_task_status_update(&update(
+            Uuid::new_v4(),
+            memorose_common::TaskStatus::Completed,
+            None
+        ))
+        .await?
+        .is_none());
 
-    assert_eq!(
-        titles,
-        vec!["DependentReady".to_string(), "Ready".to_string()]
-    );
+    let rollup = engine
+        .get_l3_task_progress(user_id, goal.task_id)
+        .await?
+        .expect("goal exists");
+    assert_eq!(rollup.total_subtasks, 4);
+    assert_eq!(rollup.completed_subtasks, 1);
+    assert!((rollup.progress - 0.75).abs() < f32::EPSILON);
+    assert!(engine
+        .get_l3_task_progress(user_id, Uuid::new_v4())
+        .await?
+        .is_none());
+
+    let parent_edges = engine
+        .graph()
+        .get_outgoing_edges(user_id, leaf.task_id)
+        .await?;
+    assert!(parent_edges.iter().any(|edge| {
+        edge.target_id == halfway.task_id && edge.relation == RelationType::IsSubTaskOf
+    }));
     Ok(())
 }
 
@@ -7098,6 +10118,90 @@ fn test_apply_token_budget_to_scored_shared_hits_truncates_ranked_results() {
     assert_eq!(budgeted[0].0.id, first.id);
 }
 
+#[tokio::test]
+async fn test_pack_context_fills_budget_and_summarizes_overflow() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let mut engine =
+        MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+    engine.arbitrator =
+        crate::arbitrator::Arbitrator::with_client(std::sync::Arc::new(MockCorrectionLLM {
+            response: String::new(),
+        }));
+    let hit = |content: &str| {
+        SharedSearchHit::native(MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        ))
+    };
+    let budget = hit("Budget review");
+    let minutes = hit(&"the quarterly planning minutes ran long again ".repeat(6));
+    let hiring = hit("Hiring plan");
+    let ranked = vec![
+        (budget.clone(), 0.9),
+        (minutes.clone(), 0.8),
+        (hiring.clone(), 0.7),
+    ];
+
+    let packed = engine.pack_context(&ranked, 20, false).await?;
+    assert_eq!(packed.packed_ids, vec![budget.id, hiring.id]);
+    assert_eq!(packed.overflow_ids, vec![minutes.id]);
+    assert!(packed.overflow_summary.is_none());
+    assert_eq!(packed.text, "- Budget review\n- Hiring plan");
+
+    let summarized = engine.pack_context(&ranked, 20, true).await?;
+    let summary = summarized.overflow_summary.expect("overflow summarized");
+    assert!(summary.starts_with("the quarterly planning"));
+    assert!(summarized.text.ends_with(&summary));
+    assert!(summarized.used_tokens <= 20);
+    assert!(summarized.used_tokens > packed.used_tokens);
+    Ok(())
+}
+
+#[test]
+fn test_select_by_mmr_promotes_distinct_topics() {
+    let hit = |content: &str, embedding: Vec<f32>| {
+        let mut unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        unit.embedding = Some(embedding);
+        SharedSearchHit::native(unit)
+    };
+    let budget = hit("Budget review", vec![1.0, 0.0]);
+    let budget_again = hit("Budget review, again", vec![0.99, 0.1]);
+    let hiring = hit("Hiring plan", vec![0.0, 1.0]);
+    let ranked = vec![
+        (budget.clone(), 1.0),
+        (budget_again.clone(), 0.95),
+        (hiring.clone(), 0.8),
+    ];
+
+    let diversified = MemoroseEngine::select_by_mmr(ranked.clone(), 0.5);
+    let order = diversified
+        .iter()
+        .map(|(hit, _)| hit.id)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![budget.id, hiring.id, budget_again.id]);
+    assert_eq!(diversified[1].1, 0.8);
+
+    let relevance_only = MemoroseEngine::select_by_mmr(ranked, 1.0);
+    let order = relevance_only
+        .iter()
+        .map(|(hit, _)| hit.id)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![budget.id, budget_again.id, hiring.id]);
+}
+
 #[test]
 fn test_apply_token_budget_skips_oversized_item_and_keeps_later_fit() {
     let oversized = MemoryUnit::new(
@@ -7375,3 +10479,119 @@ mod missing_coverage_tests {
         Ok(())
     }
 }
+
+#[tokio::test]
+async fn test_recorded_retrievals_are_capped_newest_first() -> Result<()> {
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let started = Utc::now();
+    for minutes in 0..4 {
+        let record = crate::replay::RecordedRetrieval {
+            id: Uuid::new_v4(),
+            recorded_at: started + chrono::Duration::minutes(minutes),
+            user_id: TEST_USER.into(),
+            org_id: None,
+            agent_id: None,
+            app_ids: None,
+            emotions: None,
+            near: None,
+            query: format!("query {}", minutes),
+            embedding: vec![0.0; 4],
+            limit: 5,
+            offset: 0,
+            enable_arbitration: false,
+            min_score: None,
+            graph_depth: 1,
+            valid_time: None,
+            transaction_time: None,
+            token_budget: None,
+            recency_bias: 0.0,
+            mmr_lambda: None,
+            vector_mode: VectorSearchMode::default(),
+            results: Vec::new(),
+        };
+        engine.record_retrieval(&record, 3)?;
+    }
+
+    let recorded = engine.list_recorded_retrievals(10)?;
+    let queries: Vec<&str> = recorded.iter().map(|r| r.query.as_str()).collect();
+    assert_eq!(queries, ["query 3", "query 2", "query 1"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_storage_usage_accounts_bytes_per_user_and_app() -> Result<()> {
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let mut unit = MemoryUnit::new(
+        None,
+        "tenant-a:bob".into(),
+        Some("support-bot".into()),
+        Uuid::new_v4(),
+        MemoryType::Factual,
+        "Prefers window seats on long flights".into(),
+        Some(vec![0.1; crate::llm::MOCK_EMBEDDING_DIM]),
+    );
+    unit.keywords = vec!["flights".into()];
+    engine.store_memory_unit(unit).await?;
+    engine
+        .store_memory_unit(MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "Lives in Lisbon".into(),
+            None,
+        ))
+        .await?;
+
+    assert!(engine.latest_storage_usage()?.is_none());
+    let report = engine.compute_storage_usage().await?;
+    engine.record_storage_usage(&report)?;
+    assert_eq!(engine.latest_storage_usage()?, Some(report.clone()));
+
+    let bob = &report.users["tenant-a:bob"];
+    assert_eq!(bob.memory_units, 1);
+    assert_eq!(
+        bob.vector_bytes,
+        (crate::llm::MOCK_EMBEDDING_DIM * 4) as u64
+    );
+    assert!(bob.text_index_bytes >= "Prefers window seats on long flights".len() as u64);
+    assert!(bob.kv_bytes > bob.text_index_bytes);
+    assert_eq!(report.users[TEST_USER].vector_bytes, 0);
+    assert_eq!(report.apps.keys().collect::<Vec<_>>(), ["support-bot"]);
+    assert_eq!(report.apps["support-bot"].kv_bytes, bob.kv_bytes);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_worker_runs_are_capped_newest_first() -> Result<()> {
+    let engine = MemoroseEngine::new_in_memory().await?;
+    let start = Utc::now() - chrono::Duration::minutes(10);
+    for (minute, cycle) in ["consolidation", "insight", "consolidation", "decay"]
+        .into_iter()
+        .enumerate()
+    {
+        let started_at = start + chrono::Duration::minutes(minute as i64);
+        let run = WorkerRun {
+            id: Uuid::new_v4(),
+            cycle: cycle.into(),
+            started_at,
+            finished_at: started_at + chrono::Duration::seconds(2),
+            items_processed: minute + 1,
+            llm_tokens: 0,
+            error: (cycle == "insight").then(|| "provider timeout".to_string()),
+        };
+        engine.record_worker_run(&run, 3)?;
+    }
+
+    let runs = engine.list_worker_runs(10, None)?;
+    let items: Vec<usize> = runs.iter().map(|run| run.items_processed).collect();
+    assert_eq!(items, [4, 3, 2]);
+    assert_eq!(runs[2].error.as_deref(), Some("provider timeout"));
+
+    let consolidation = engine.list_worker_runs(10, Some("consolidation"))?;
+    assert_eq!(consolidation.len(), 1);
+    assert_eq!(consolidation[0].items_processed, 3);
+    assert_eq!(engine.list_worker_runs(1, None)?[0].cycle, "decay");
+    Ok(())
+}
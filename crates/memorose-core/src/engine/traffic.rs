@@ -0,0 +1,36 @@
+use anyhow::Result;
+
+use crate::replay::RecordedRetrieval;
+
+const TRAFFIC_PREFIX: &str = "traffic:";
+
+impl super::MemoroseEngine {
+    // ── Recorded retrieval traffic ──────────────────────────────────
+
+    /// Recordings sort newest first: the key carries the time counted down from `u64::MAX`.
+    fn traffic_key(record: &RecordedRetrieval) -> String {
+        let millis = record.recorded_at.timestamp_millis().max(0) as u64;
+        format!("{}{:020}:{}", TRAFFIC_PREFIX, u64::MAX - millis, record.id)
+    }
+
+    /// Store a sampled retrieval on this node, dropping the oldest past `max_records`.
+    pub fn record_retrieval(&self, record: &RecordedRetrieval, max_records: usize) -> Result<()> {
+        let system_kv = self.system_kv();
+        system_kv.put(
+            Self::traffic_key(record).as_bytes(),
+            &serde_json::to_vec(record)?,
+        )?;
+        // Recordings carry embeddings; trimming walks their keys only.
+        system_kv.trim_prefix(TRAFFIC_PREFIX.as_bytes(), max_records)
+    }
+
+    /// The most recent recorded retrievals, newest first.
+    pub fn list_recorded_retrievals(&self, limit: usize) -> Result<Vec<RecordedRetrieval>> {
+        Ok(self
+            .system_kv()
+            .scan_limited(TRAFFIC_PREFIX.as_bytes(), limit)?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect())
+    }
+}
@@ -1,7 +1,10 @@
 use crate::arbitrator::MemoryCorrectionKind;
 use chrono::{DateTime, Utc};
-use memorose_common::{GraphEdge, MaterializationState, MemoryType, MemoryUnit, RelationType};
+use memorose_common::{
+    ForgettingTombstone, GraphEdge, MaterializationState, MemoryType, MemoryUnit, RelationType,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::ops::{Deref, DerefMut};
 use uuid::Uuid;
 
@@ -19,6 +22,707 @@ pub struct ReflectionMarker {
     pub first_event_id: Option<String>,
 }
 
+/// Per-user background work kept in the job queue.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundJobKind {
+    Reflect,
+    Community,
+    Profile,
+}
+
+impl BackgroundJobKind {
+    pub const ALL: [Self; 3] = [Self::Reflect, Self::Community, Self::Profile];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reflect => "reflect",
+            Self::Community => "community",
+            Self::Profile => "profile",
+        }
+    }
+}
+
+/// The worker a claimed job belongs to until `expires_at`, which heartbeats push back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobLease {
+    pub holder: String,
+    /// Raft term of the leader whose worker claimed the job; 0 without Raft. A claim in a
+    /// later term takes the job over even while this lease runs.
+    #[serde(default)]
+    pub term: u64,
+    pub claimed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A queued background job for one user. Enqueueing work that is already queued bumps
+/// `generation` instead of adding a second job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackgroundJob {
+    pub kind: BackgroundJobKind,
+    pub user_id: String,
+    pub enqueued_at: DateTime<Utc>,
+    /// Bumped on every enqueue; completing a claim made at an older generation keeps the
+    /// job queued for the work that arrived meanwhile.
+    pub generation: u64,
+    /// Claims since the job last completed, including ones whose lease expired.
+    pub attempts: u32,
+    #[serde(default)]
+    pub lease: Option<JobLease>,
+    /// A failed job is not claimed again before this.
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Backlog of unreflected memories, for `Reflect` jobs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflection: Option<ReflectionMarker>,
+}
+
+/// An L3 task with its subtask tree and progress rolled up from the leaves.
+#[derive(Debug, Clone, Serialize)]
+pub struct L3TaskProgress {
+    pub task: memorose_common::L3Task,
+    pub progress: f32,
+    pub total_subtasks: usize,
+    pub completed_subtasks: usize,
+    pub subtasks: Vec<L3TaskProgress>,
+}
+
+/// Why arbitration kept the results it did, surfaced when a caller asks to explain it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArbitrationExplanation {
+    /// False when the top result led by enough that the LLM was not consulted.
+    pub arbitrated: bool,
+    /// True when the decision came from the arbitration cache.
+    pub cached: bool,
+    pub reasoning: Option<String>,
+    pub retained_ids: Vec<Uuid>,
+    pub discarded_ids: Vec<Uuid>,
+}
+
+/// Ranked search hits packed into a prompt-ready context string under a token cap.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackedContext {
+    pub text: String,
+    pub max_tokens: usize,
+    pub used_tokens: usize,
+    pub packed_ids: Vec<Uuid>,
+    /// Hits that did not fit, highest ranked first.
+    pub overflow_ids: Vec<Uuid>,
+    /// LLM summary of the overflow, clipped to the tokens left after packing.
+    pub overflow_summary: Option<String>,
+}
+
+/// One detected community of a user's graph, as cached for the communities API.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommunityRecord {
+    pub community_id: Uuid,
+    /// Non-summary units in the community.
+    pub member_ids: Vec<Uuid>,
+    /// The L2 insight generated for this community, if one has been published.
+    pub summary_id: Option<Uuid>,
+    pub summary: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+/// The result of the last enhanced community detection run for a user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommunitySnapshot {
+    pub user_id: String,
+    pub computed_at: DateTime<Utc>,
+    /// Modularity of the whole partition.
+    pub modularity: f64,
+    pub communities: Vec<CommunityRecord>,
+}
+
+/// Memories of a digest window that share a topic.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DigestTopic {
+    /// Set when the topic is a cached community; otherwise the memories share a keyword.
+    pub community_id: Option<Uuid>,
+    pub label: String,
+    pub keywords: Vec<String>,
+    /// Oldest first.
+    pub memory_ids: Vec<Uuid>,
+}
+
+/// An LLM-written digest of a user's L1 memories recorded in `[from, to)`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryDigest {
+    pub user_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub memory_count: usize,
+    /// Largest first.
+    pub topics: Vec<DigestTopic>,
+    pub digest: String,
+    /// Hash of the memory ids the digest was written from. The cached digest is
+    /// rewritten once memories in the window change.
+    pub source_fingerprint: String,
+}
+
+/// Which part of a user's graph `export_graph` returns. Empty lists keep everything.
+#[derive(Debug, Clone, Default)]
+pub struct GraphExportFilter {
+    pub relations: Vec<RelationType>,
+    pub levels: Vec<u8>,
+    pub min_weight: Option<f32>,
+    /// Only nodes of this cached community, and the edges between them.
+    pub community_id: Option<Uuid>,
+    /// Cap on exported edges, heaviest first; 0 keeps all of them.
+    pub max_edges: usize,
+}
+
+/// A memory in a graph export.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphExportNode {
+    pub id: Uuid,
+    /// Start of the memory's content, for display in graph tools.
+    pub label: String,
+    pub level: u8,
+    pub memory_type: MemoryType,
+    pub importance: f32,
+    /// Community from the last cached detection run.
+    pub community_id: Option<Uuid>,
+    pub transaction_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphExportEdge {
+    pub source: Uuid,
+    pub target: Uuid,
+    pub relation: RelationType,
+    pub weight: f32,
+}
+
+/// A user's memory graph, or the filtered part of it, for analysis in external tools.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphExport {
+    pub user_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub nodes: Vec<GraphExportNode>,
+    pub edges: Vec<GraphExportEdge>,
+}
+
+/// A batch of pending events reserved for one consolidation pool node until
+/// `expires_at`. Only the shard leader keeps leases.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConsolidationLease {
+    pub lease_id: Uuid,
+    /// Physical node ID of the pool node holding the batch.
+    pub holder_node_id: u32,
+    pub user_id: String,
+    pub event_ids: Vec<Uuid>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// One run of a background worker cycle that did work or failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkerRun {
+    pub id: Uuid,
+    /// Cycle name, e.g. `consolidation` or `insight`.
+    pub cycle: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Events, jobs, users or units the cycle handled; what counts depends on the cycle.
+    pub items_processed: usize,
+    pub llm_tokens: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// What reconciling and linking a replicated memory unit wrote on the leader, to be
+/// replicated to the other nodes as commands of their own.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUnitFollowUp {
+    /// Tombstones for the older units the new one superseded.
+    pub tombstones: Vec<ForgettingTombstone>,
+    /// Edges touching the new unit.
+    pub edges: Vec<GraphEdge>,
+}
+
+/// An edge present at both ends of a graph diff whose weight changed in between.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphEdgeWeightChange {
+    /// The edge as of the end of the window.
+    pub edge: GraphEdge,
+    pub previous_weight: f32,
+}
+
+/// How a user's graph changed between `from` and `to`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphDiff {
+    pub user_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Edges present at `to` but not at `from`, oldest first.
+    pub edges_added: Vec<GraphEdge>,
+    /// Edges present at `from` but not at `to`, as they were at `from`.
+    pub edges_removed: Vec<GraphEdge>,
+    pub weight_changes: Vec<GraphEdgeWeightChange>,
+    /// Memories with an edge at `to` but none at `from`.
+    pub nodes_introduced: Vec<Uuid>,
+    /// Memories with an edge at `from` but none at `to`.
+    pub nodes_removed: Vec<Uuid>,
+}
+
+/// The partition kept between community runs so later runs only revisit the nodes
+/// whose edges changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CommunityState {
+    pub node_to_community: HashMap<Uuid, Uuid>,
+    pub summaries: HashMap<Uuid, CommunitySummaryState>,
+}
+
+/// The L2 insight generated for a community and the members it was generated from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommunitySummaryState {
+    pub l2_id: Uuid,
+    pub members: Vec<Uuid>,
+}
+
+/// Admission decision for an ingest request, derived from the pending-queue depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum IngestAdmission {
+    Accept,
+    /// Queue is deep; the caller should back off and retry.
+    Throttle {
+        retry_after_secs: u64,
+    },
+    /// Queue is past the hard limit; the service is shedding ingest.
+    Reject {
+        retry_after_secs: u64,
+    },
+}
+
+/// Drift found by the consistency checker between the KV store and the derived stores
+/// (vector table, text index, graph). `repaired` counts fixes made during the same pass.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IntegrityReport {
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub scanned_units: usize,
+    /// `idx:unit:` entries whose unit record is gone.
+    pub dangling_unit_index: usize,
+    pub missing_vectors: usize,
+    pub missing_text_index: usize,
+    /// Vector rows whose unit no longer exists.
+    pub orphaned_vectors: usize,
+    /// Edges with at least one endpoint that is not a stored unit.
+    pub orphaned_edges: usize,
+    pub repaired: usize,
+    pub repair_failures: usize,
+}
+
+/// How much one consistency-check batch verifies and whether it repairs what it finds.
+#[derive(Debug, Clone, Copy)]
+pub struct IntegrityCheckOptions {
+    pub batch_size: usize,
+    pub repair: bool,
+    pub repair_orphaned_edges: bool,
+    /// Units younger than this may still be mid-write and are not checked yet.
+    pub settle_secs: i64,
+}
+
+/// What the startup recovery phase found and did before the node started serving.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RecoveryReport {
+    /// The previous process exited without closing its session.
+    pub unclean_shutdown: bool,
+    pub last_applied_index: Option<u64>,
+    /// Applied index up to which every derived store was last known to be flushed.
+    pub durable_applied_index: Option<u64>,
+    /// Applied log entries past the durable watermark that were re-checked.
+    pub checked_entries: usize,
+    /// Edges from those entries that were missing from the graph table and re-written.
+    pub replayed_edges: usize,
+    /// Consistency pass run over the derived stores after an unclean shutdown.
+    pub integrity: Option<IntegrityReport>,
+}
+
+/// Rows and files written by one warehouse export.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportReport {
+    pub units: usize,
+    pub edges: usize,
+    pub files: usize,
+}
+
+/// Outcome of one orphan garbage-collection sweep. In a dry run `removed` stays 0 and the
+/// other counters say what a real run would delete.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OrphanGcReport {
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub dry_run: bool,
+    pub scanned_keys: usize,
+    /// Index and queue entries that reference a missing unit, by key family.
+    pub dangling_keys: BTreeMap<String, usize>,
+    /// Semantic dedup fingerprints past their window, which can no longer match.
+    pub expired_dedup_fingerprints: usize,
+    /// Edges with an endpoint that is not a stored unit.
+    pub orphaned_edges: usize,
+    pub removed: usize,
+}
+
+/// What an orphan garbage-collection sweep is allowed to delete.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrphanGcOptions {
+    /// Count what would be removed without deleting anything.
+    pub dry_run: bool,
+    /// Also delete edges whose endpoints are not stored units.
+    pub include_edges: bool,
+}
+
+/// Which vector tables the vector side of a hybrid search consults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorSearchMode {
+    /// Memory embeddings only.
+    #[default]
+    Text,
+    /// Joint text+image embeddings of memory assets only.
+    Multimodal,
+    /// Memory and asset embeddings, fused by rank.
+    Joint,
+}
+
+impl VectorSearchMode {
+    pub fn uses_memory_vectors(self) -> bool {
+        matches!(self, Self::Text | Self::Joint)
+    }
+
+    pub fn uses_asset_vectors(self) -> bool {
+        matches!(self, Self::Multimodal | Self::Joint)
+    }
+}
+
+/// Optional reporting requested alongside a shared hybrid search.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchExplainOptions {
+    /// Return why arbitration kept what it kept.
+    pub arbitration: bool,
+    /// Collect per-stage `RetrievalDiagnostics`.
+    pub diagnostics: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct SharedSearchOutcome {
+    pub results: Vec<(SharedSearchHit, f32)>,
+    pub arbitration: Option<ArbitrationExplanation>,
+    pub diagnostics: Option<RetrievalDiagnostics>,
+    /// More results rank below the returned page.
+    pub has_more: bool,
+    pub timings: RetrievalStageTimings,
+}
+
+/// Wall-clock time spent in each hybrid retrieval stage, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetrievalStageTimings {
+    /// Vector, asset and full-text candidate lookups, which run concurrently.
+    pub candidates_ms: f64,
+    /// Rank fusion and loading the candidate units.
+    pub fusion_ms: f64,
+    pub graph_expansion_ms: f64,
+    pub rerank_ms: f64,
+    /// Memories shared with the user through grants or an organization.
+    pub shared_ms: f64,
+    /// Dedup, score cutoff, arbitration, paging and token budgeting.
+    pub finalize_ms: f64,
+}
+
+impl RetrievalStageTimings {
+    /// Milliseconds since `stage`, restarting it for the next stage.
+    pub fn lap(stage: &mut std::time::Instant) -> f64 {
+        let now = std::time::Instant::now();
+        let elapsed = now.duration_since(*stage).as_secs_f64() * 1000.0;
+        *stage = now;
+        elapsed
+    }
+}
+
+/// Per-stage hybrid retrieval diagnostics, collected when a search runs in debug mode.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetrievalDiagnostics {
+    pub vector_hits: Vec<VectorHitDiagnostic>,
+    pub text_hits: Vec<TextHitDiagnostic>,
+    pub rrf: Vec<RrfContribution>,
+    pub graph_expansion: Vec<GraphExpansionProvenance>,
+    pub rerank: Vec<RerankDelta>,
+    pub thresholds: RetrievalThresholds,
+    /// Candidates dropped for scoring under `thresholds.min_score`.
+    pub below_min_score: Vec<Uuid>,
+    /// Candidates dropped as near-duplicates of a higher-ranked result.
+    pub deduplicated: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorHitDiagnostic {
+    pub id: String,
+    pub rank: usize,
+    pub distance: f32,
+    pub similarity: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextHitDiagnostic {
+    pub id: String,
+    pub rank: usize,
+    /// `None` for hits served from the recent-write overlay before a Tantivy commit.
+    pub bm25: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RrfContribution {
+    pub id: String,
+    pub vector: f32,
+    pub text: f32,
+    /// Fused score after normalizing by the best candidate.
+    pub fused: f32,
+}
+
+/// The edge that pulled a node into the result set during graph expansion.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphExpansionProvenance {
+    pub id: Uuid,
+    pub via: Uuid,
+    pub relation: RelationType,
+    pub edge_weight: f32,
+    pub hop: usize,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RerankDelta {
+    pub id: Uuid,
+    pub before: f32,
+    pub after: f32,
+    pub delta: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetrievalThresholds {
+    pub rrf_k: f32,
+    pub candidate_limit: usize,
+    pub graph_depth: usize,
+    pub graph_related_min_weight: f32,
+    pub min_score: f32,
+    pub dedup_similarity: f32,
+    pub dedup_cap: usize,
+    pub arbitration_score_gap: f32,
+    pub recency_bias: f32,
+    pub recency_half_life_hours: f64,
+    pub mmr_lambda: Option<f32>,
+}
+
+/// Ranking settings pinned on one engine handle, for experiments and offline evaluation.
+/// Set values take precedence over the configured ones, live reloads included.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RankingOverrides {
+    pub rrf_k: Option<f32>,
+    pub recency_half_life_hours: Option<f64>,
+}
+
+/// Feedback collected for one variant of a ranking experiment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentVariantOutcome {
+    pub variant: String,
+    /// Feedback reports received
+    pub feedback: u64,
+    /// Memories shown across those reports
+    pub retrieved: u64,
+    /// Shown memories the client used
+    pub cited: u64,
+    /// Reports that cited at least one memory
+    pub useful: u64,
+    /// Sum of 1/rank of the first cited memory, with uncited reports adding 0
+    pub reciprocal_rank_sum: f64,
+}
+
+impl ExperimentVariantOutcome {
+    pub fn citation_rate(&self) -> f64 {
+        ratio(self.cited, self.retrieved)
+    }
+
+    pub fn success_rate(&self) -> f64 {
+        ratio(self.useful, self.feedback)
+    }
+
+    pub fn mean_reciprocal_rank(&self) -> f64 {
+        if self.feedback == 0 {
+            0.0
+        } else {
+            self.reciprocal_rank_sum / self.feedback as f64
+        }
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Logical bytes a user or app holds in each store of a shard. Sizes are taken before
+/// compression and index overhead, so they rank consumers rather than add up to disk use.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub memory_units: u64,
+    pub events: u64,
+    /// Keys and values under the owner's RocksDB prefix
+    pub kv_bytes: u64,
+    /// Embedding data of its memory units
+    pub vector_bytes: u64,
+    /// Text the full-text index holds for its memory units
+    pub text_index_bytes: u64,
+    /// Local asset files and thumbnails attached to its memory units
+    pub asset_bytes: u64,
+}
+
+impl StorageUsage {
+    pub fn total_bytes(&self) -> u64 {
+        self.kv_bytes + self.vector_bytes + self.text_index_bytes + self.asset_bytes
+    }
+
+    pub fn add(&mut self, other: &StorageUsage) {
+        self.memory_units += other.memory_units;
+        self.events += other.events;
+        self.kv_bytes += other.kv_bytes;
+        self.vector_bytes += other.vector_bytes;
+        self.text_index_bytes += other.text_index_bytes;
+        self.asset_bytes += other.asset_bytes;
+    }
+}
+
+/// On-disk size of each store under a shard's data directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoreDiskUsage {
+    pub kv_bytes: u64,
+    pub vector_bytes: u64,
+    pub text_index_bytes: u64,
+    pub asset_bytes: u64,
+}
+
+/// Storage accounting of one shard, by user and by app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageUsageReport {
+    pub computed_at: DateTime<Utc>,
+    pub disk: StoreDiskUsage,
+    pub users: BTreeMap<String, StorageUsage>,
+    /// Usage of memories and events written by each app; edges belong to no app
+    pub apps: BTreeMap<String, StorageUsage>,
+}
+
+/// Outcome of a forced text index commit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextIndexCommitReport {
+    /// Documents that were waiting for the background commit
+    pub committed_docs: usize,
+    pub segments_before: usize,
+    pub segments_after: usize,
+    pub elapsed_ms: u64,
+}
+
+/// One file in a snapshot archive, as recorded in its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFileEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// `MANIFEST.json`, the last entry of every snapshot archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<SnapshotFileEntry>,
+    /// Digest over every file entry, sorted by path
+    pub content_sha256: String,
+}
+
+/// What verifying a snapshot archive against its manifest found.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotVerification {
+    /// SHA-256 of the compressed archive
+    pub archive_sha256: String,
+    /// `None` when no `.sha256` file sits next to the archive
+    pub archive_checksum_matches: Option<bool>,
+    /// Archives written before manifests existed have none and cannot be checked
+    pub has_manifest: bool,
+    pub files: usize,
+    pub bytes: u64,
+    pub content_checksum_matches: bool,
+    pub mismatched: Vec<String>,
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+}
+
+impl SnapshotVerification {
+    /// Whether nothing in the archive contradicts its checksums.
+    pub fn is_intact(&self) -> bool {
+        self.archive_checksum_matches != Some(false)
+            && (!self.has_manifest
+                || (self.content_checksum_matches
+                    && self.mismatched.is_empty()
+                    && self.missing.is_empty()
+                    && self.unexpected.is_empty()))
+    }
+
+    /// Whether the archive has a manifest and matches it.
+    pub fn is_verified(&self) -> bool {
+        self.has_manifest && self.is_intact()
+    }
+}
+
+/// Guardrails applied whenever a goal is decomposed into milestones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoPlannerPolicy {
+    pub max_subtasks: usize,
+    pub max_depth: usize,
+    pub require_approval: bool,
+}
+
+impl AutoPlannerPolicy {
+    pub fn from_worker_config(config: &memorose_common::config::WorkerConfig) -> Self {
+        Self {
+            max_subtasks: config.auto_planner_max_subtasks,
+            max_depth: config.auto_planner_max_depth,
+            require_approval: config.auto_planner_require_approval,
+        }
+    }
+}
+
+impl Default for AutoPlannerPolicy {
+    fn default() -> Self {
+        Self::from_worker_config(&memorose_common::config::WorkerConfig::default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPlanStatus {
+    /// Nothing has been stored yet; the milestones are a dry-run decomposition.
+    Preview,
+    AwaitingApproval,
+    Approved,
+}
+
+/// The milestones planned (or that would be planned) for an L3 goal.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalPlan {
+    pub goal_id: Uuid,
+    pub status: GoalPlanStatus,
+    /// Auto-planner marker for the goal: `pending`, `done` or `failed`.
+    pub planning_state: Option<String>,
+    pub milestones: Vec<memorose_common::L3Task>,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ReflectionBatchOutcome {
     pub created_topics: usize,
@@ -26,6 +730,8 @@ pub struct ReflectionBatchOutcome {
     pub consumed_tokens: usize,
     pub next_first_event_tx_micros: Option<i64>,
     pub next_first_event_id: Option<String>,
+    /// The batch repeated the content of the last reflection, so no LLM call was made.
+    pub deduplicated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use memorose_common::{Event, MemoryUnit};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use super::types::{StorageUsage, StorageUsageReport, StoreDiskUsage};
+use crate::ingest::image::{resolve_local_asset, THUMBNAIL_KEY_METADATA_KEY};
+use crate::storage::repair::dir_size_bytes;
+
+const STORAGE_USAGE_KEY: &[u8] = b"storage_usage:latest";
+const USAGE_SCAN_PAGE: usize = 512;
+/// Key segments that follow the user id in `u:{user_id}:...` keys.
+const USER_KEY_KINDS: [&str; 3] = [":unit:", ":event:", ":edge:"];
+
+impl super::MemoroseEngine {
+    // ── Storage usage accounting ────────────────────────────────────
+
+    /// Walk every user's keys on this shard and account the bytes each user and app
+    /// holds in RocksDB, the vector and text indexes, and local assets.
+    pub async fn compute_storage_usage(&self) -> Result<StorageUsageReport> {
+        let kv = self.kv();
+        let root = self.root_path();
+        let asset_dir = self.asset_dir();
+        tokio::task::spawn_blocking(move || {
+            let mut users: BTreeMap<String, StorageUsage> = BTreeMap::new();
+            let mut apps: BTreeMap<String, StorageUsage> = BTreeMap::new();
+            let mut seen_assets = HashSet::new();
+            let mut after: Option<Vec<u8>> = None;
+            loop {
+                let page = kv.scan_prefix_after(b"u:", after.as_deref(), USAGE_SCAN_PAGE)?;
+                let Some((last_key, _)) = page.last() else {
+                    break;
+                };
+                after = Some(last_key.clone());
+                for (key, value) in &page {
+                    let Some((user_id, kind)) = split_user_key(key) else {
+                        continue;
+                    };
+                    let (usage, app_id) = match kind {
+                        ":unit:" => match crate::migration::decode_sealed_memory_unit(value) {
+                            Ok(unit) => (
+                                unit_usage(&unit, &asset_dir, &mut seen_assets),
+                                unit.agent_id,
+                            ),
+                            Err(_) => (StorageUsage::default(), None),
+                        },
+                        ":event:" => (
+                            StorageUsage {
+                                events: 1,
+                                ..Default::default()
+                            },
+                            serde_json::from_slice::<Event>(value)
+                                .ok()
+                                .and_then(|event| event.app_id().map(str::to_string)),
+                        ),
+                        _ => (StorageUsage::default(), None),
+                    };
+                    let usage = StorageUsage {
+                        kv_bytes: (key.len() + value.len()) as u64,
+                        ..usage
+                    };
+                    users.entry(user_id).or_default().add(&usage);
+                    if let Some(app_id) = app_id {
+                        apps.entry(app_id).or_default().add(&usage);
+                    }
+                }
+            }
+
+            Ok(StorageUsageReport {
+                computed_at: chrono::Utc::now(),
+                disk: StoreDiskUsage {
+                    kv_bytes: dir_size_bytes(&root.join("rocksdb"))?,
+                    vector_bytes: dir_size_bytes(&root.join("lancedb"))?,
+                    text_index_bytes: dir_size_bytes(&root.join("tantivy"))?,
+                    asset_bytes: dir_size_bytes(&asset_dir)?,
+                },
+                users,
+                apps,
+            })
+        })
+        .await?
+    }
+
+    /// Keep `report` as this node's latest accounting of the shard.
+    pub fn record_storage_usage(&self, report: &StorageUsageReport) -> Result<()> {
+        self.system_kv()
+            .put(STORAGE_USAGE_KEY, &serde_json::to_vec(report)?)
+    }
+
+    /// The last recorded storage accounting, if any has run.
+    pub fn latest_storage_usage(&self) -> Result<Option<StorageUsageReport>> {
+        Ok(self
+            .system_kv()
+            .get(STORAGE_USAGE_KEY)?
+            .and_then(|raw| serde_json::from_slice(&raw).ok()))
+    }
+}
+
+/// The user id and key kind of a `u:{user_id}:{kind}:...` key. User ids may contain
+/// `:`, so the id ends where the first kind segment starts.
+fn split_user_key(key: &[u8]) -> Option<(String, &'static str)> {
+    let rest = std::str::from_utf8(key).ok()?.strip_prefix("u:")?;
+    let (position, kind) = USER_KEY_KINDS
+        .iter()
+        .filter_map(|kind| rest.find(kind).map(|position| (position, *kind)))
+        .min_by_key(|(position, _)| *position)?;
+    Some((rest[..position].to_string(), kind))
+}
+
+/// Vector, text and asset bytes of one memory unit. Assets shared by several units
+/// count once.
+fn unit_usage(
+    unit: &MemoryUnit,
+    asset_dir: &Path,
+    seen_assets: &mut HashSet<String>,
+) -> StorageUsage {
+    let asset_keys = unit.assets.iter().flat_map(|asset| {
+        std::iter::once(asset.storage_key.as_str()).chain(
+            asset
+                .metadata
+                .get(THUMBNAIL_KEY_METADATA_KEY)
+                .map(String::as_str),
+        )
+    });
+    let mut asset_bytes = 0;
+    for storage_key in asset_keys {
+        if !seen_assets.insert(storage_key.to_string()) {
+            continue;
+        }
+        if let Some(path) = resolve_local_asset(asset_dir, storage_key) {
+            asset_bytes += std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+        }
+    }
+    StorageUsage {
+        memory_units: 1,
+        vector_bytes: unit.embedding.as_ref().map_or(0, |embedding| {
+            (embedding.len() * std::mem::size_of::<f32>()) as u64
+        }),
+        text_index_bytes: (unit.content.len()
+            + unit.keywords.iter().map(String::len).sum::<usize>())
+            as u64,
+        asset_bytes,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_user_key() {
+        let id = uuid::Uuid::new_v4();
+        assert_eq!(
+            split_user_key(format!("u:tenant-a:bob:unit:{}", id).as_bytes()),
+            Some(("tenant-a:bob".to_string(), ":unit:"))
+        );
+        assert_eq!(
+            split_user_key(format!("u:alice:edge:out:{}:{}:rel:unit:x", id, id).as_bytes()),
+            Some(("alice".to_string(), ":edge:"))
+        );
+        assert_eq!(split_user_key(b"profile:alice"), None);
+        assert_eq!(split_user_key(b"u:alice:other"), None);
+    }
+}
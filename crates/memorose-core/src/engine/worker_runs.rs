@@ -0,0 +1,40 @@
+use super::types::WorkerRun;
+use anyhow::Result;
+
+const WORKER_RUN_PREFIX: &str = "worker_run:";
+
+impl super::MemoroseEngine {
+    // ── Worker run history ──────────────────────────────────────────
+
+    /// Runs sort newest first: the key carries the start time counted down from `u64::MAX`.
+    fn worker_run_key(run: &WorkerRun) -> String {
+        let micros = run.started_at.timestamp_micros().max(0) as u64;
+        format!("{}{:020}:{}", WORKER_RUN_PREFIX, u64::MAX - micros, run.id)
+    }
+
+    /// Store a worker cycle run on this node, dropping the oldest past `max_records`.
+    pub fn record_worker_run(&self, run: &WorkerRun, max_records: usize) -> Result<()> {
+        let system_kv = self.system_kv();
+        system_kv.put(
+            Self::worker_run_key(run).as_bytes(),
+            &serde_json::to_vec(run)?,
+        )?;
+        system_kv.trim_prefix(WORKER_RUN_PREFIX.as_bytes(), max_records)
+    }
+
+    /// The most recent worker runs, newest first, optionally of one cycle only.
+    pub fn list_worker_runs(&self, limit: usize, cycle: Option<&str>) -> Result<Vec<WorkerRun>> {
+        let runs = match cycle {
+            None => self
+                .system_kv()
+                .scan_limited(WORKER_RUN_PREFIX.as_bytes(), limit)?,
+            Some(_) => self.system_kv().scan(WORKER_RUN_PREFIX.as_bytes())?,
+        };
+        Ok(runs
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<WorkerRun>(&value).ok())
+            .filter(|run| cycle.is_none_or(|cycle| run.cycle == cycle))
+            .take(limit)
+            .collect())
+    }
+}
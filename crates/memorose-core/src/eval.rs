@@ -0,0 +1,326 @@
+//! Offline retrieval evaluation against a golden dataset. Each case pairs a query with
+//! the memory ids a good retrieval should return. Every case is replayed under each
+//! ranking config, and the report gives recall@k, MRR and nDCG@k per config, so reranker
+//! and fusion settings can be compared on the same data.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::engine::{MemoroseEngine, RankingOverrides, SearchExplainOptions, VectorSearchMode};
+use crate::llm::LLMClient;
+
+/// A labeled query: who asks it and which memories should come back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub query: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// Ids of the relevant memories, in no particular order
+    pub expected: Vec<Uuid>,
+}
+
+/// Ranking parameters to evaluate. Unset overrides keep the engine's configured values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingConfig {
+    pub name: String,
+    #[serde(default)]
+    pub rrf_k: Option<f32>,
+    #[serde(default)]
+    pub recency_bias: f32,
+    #[serde(default)]
+    pub recency_half_life_hours: Option<f64>,
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    #[serde(default)]
+    pub vector_mode: VectorSearchMode,
+    #[serde(default = "default_graph_depth")]
+    pub graph_depth: usize,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub enable_arbitration: bool,
+    /// Rerank with the HTTP reranker at this endpoint instead of the engine's reranker
+    #[serde(default)]
+    pub reranker_endpoint: Option<String>,
+}
+
+fn default_graph_depth() -> usize {
+    1
+}
+
+impl RankingConfig {
+    /// The engine's configured ranking, as the search API runs it by default.
+    pub fn baseline() -> Self {
+        Self {
+            name: "baseline".to_string(),
+            rrf_k: None,
+            recency_bias: 0.0,
+            recency_half_life_hours: None,
+            mmr_lambda: None,
+            vector_mode: VectorSearchMode::default(),
+            graph_depth: default_graph_depth(),
+            min_score: None,
+            enable_arbitration: false,
+            reranker_endpoint: None,
+        }
+    }
+
+    fn apply(&self, mut engine: MemoroseEngine) -> MemoroseEngine {
+        engine = engine.with_ranking_overrides(RankingOverrides {
+            rrf_k: self.rrf_k,
+            recency_half_life_hours: self.recency_half_life_hours,
+        });
+        if let Some(endpoint) = &self.reranker_endpoint {
+            engine = engine.with_reranker(Arc::new(crate::reranker::HttpReranker::new(
+                endpoint.clone(),
+            )));
+        }
+        engine
+    }
+}
+
+/// Averaged metrics of one ranking config over the dataset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigReport {
+    pub name: String,
+    pub recall_at_k: f32,
+    pub mean_reciprocal_rank: f32,
+    pub ndcg_at_k: f32,
+    pub mean_query_ms: f64,
+    /// Queries for which none of the expected memories ranked within `k`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub k: usize,
+    pub cases: usize,
+    pub configs: Vec<ConfigReport>,
+}
+
+/// Read a dataset with one JSON `EvalCase` per line. Blank lines are skipped.
+pub fn load_cases(path: impl AsRef<Path>) -> Result<Vec<EvalCase>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read dataset {}", path.display()))?;
+    let mut cases = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let case: EvalCase = serde_json::from_str(line)
+            .with_context(|| format!("{}:{}: invalid eval case", path.display(), index + 1))?;
+        if case.expected.is_empty() {
+            return Err(anyhow!(
+                "{}:{}: eval case lists no expected memories",
+                path.display(),
+                index + 1
+            ));
+        }
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Replay every case under every config and report the metrics of each config.
+/// Queries are embedded once and shared across configs.
+pub async fn evaluate(
+    engine: &MemoroseEngine,
+    client: &dyn LLMClient,
+    cases: &[EvalCase],
+    configs: &[RankingConfig],
+    k: usize,
+) -> Result<EvalReport> {
+    let k = k.max(1);
+    let mut embeddings = Vec::with_capacity(cases.len());
+    for case in cases {
+        embeddings.push(client.embed(&case.query).await?.data);
+    }
+
+    let mut reports = Vec::with_capacity(configs.len());
+    for config in configs {
+        let ranked_engine = config.apply(engine.clone());
+        let mut totals = (0.0_f32, 0.0_f32, 0.0_f32, 0.0_f64);
+        let mut missed = Vec::new();
+        for (case, embedding) in cases.iter().zip(&embeddings) {
+            let started = std::time::Instant::now();
+            let outcome = ranked_engine
+                .search_hybrid_with_shared_explained(
+                    &case.user_id,
+                    case.org_id.as_deref(),
+                    case.agent_id.as_deref(),
+                    None,
+                    None,
+                    None,
+                    &case.query,
+                    embedding,
+                    k,
+                    0,
+                    config.enable_arbitration,
+                    config.min_score,
+                    config.graph_depth,
+                    None,
+                    None,
+                    None,
+                    config.recency_bias,
+                    config.mmr_lambda,
+                    config.vector_mode,
+                    SearchExplainOptions::default(),
+                )
+                .await
+                .with_context(|| format!("Config {:?} failed on {:?}", config.name, case.query))?;
+            totals.3 += started.elapsed().as_secs_f64() * 1000.0;
+
+            let ranked: Vec<Uuid> = outcome.results.iter().map(|(hit, _)| hit.id).collect();
+            let expected: HashSet<Uuid> = case.expected.iter().copied().collect();
+            let recall = recall_at_k(&ranked, &expected, k);
+            if recall == 0.0 {
+                missed.push(case.query.clone());
+            }
+            totals.0 += recall;
+            totals.1 += reciprocal_rank(&ranked, &expected, k);
+            totals.2 += ndcg_at_k(&ranked, &expected, k);
+        }
+
+        let count = cases.len().max(1);
+        reports.push(ConfigReport {
+            name: config.name.clone(),
+            recall_at_k: totals.0 / count as f32,
+            mean_reciprocal_rank: totals.1 / count as f32,
+            ndcg_at_k: totals.2 / count as f32,
+            mean_query_ms: totals.3 / count as f64,
+            missed,
+        });
+    }
+
+    Ok(EvalReport {
+        k,
+        cases: cases.len(),
+        configs: reports,
+    })
+}
+
+/// Share of the expected memories found in the top `k`.
+pub fn recall_at_k(ranked: &[Uuid], expected: &HashSet<Uuid>, k: usize) -> f32 {
+    if expected.is_empty() {
+        return 0.0;
+    }
+    let found = ranked
+        .iter()
+        .take(k)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .filter(|id| expected.contains(id))
+        .count();
+    found as f32 / expected.len() as f32
+}
+
+/// 1/rank of the first expected memory in the top `k`, or 0 when none made it.
+pub fn reciprocal_rank(ranked: &[Uuid], expected: &HashSet<Uuid>, k: usize) -> f32 {
+    ranked
+        .iter()
+        .take(k)
+        .position(|id| expected.contains(id))
+        .map_or(0.0, |index| 1.0 / (index + 1) as f32)
+}
+
+/// Normalized discounted cumulative gain over the top `k`, with binary relevance.
+pub fn ndcg_at_k(ranked: &[Uuid], expected: &HashSet<Uuid>, k: usize) -> f32 {
+    let discount = |index: usize| 1.0 / ((index + 2) as f32).log2();
+    let mut seen = HashSet::new();
+    let dcg: f32 = ranked
+        .iter()
+        .take(k)
+        .enumerate()
+        .filter(|(_, id)| expected.contains(id) && seen.insert(**id))
+        .map(|(index, _)| discount(index))
+        .sum();
+    let ideal: f32 = (0..expected.len().min(k)).map(discount).sum();
+    if ideal > 0.0 {
+        dcg / ideal
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLLM;
+    use memorose_common::{MemoryType, MemoryUnit};
+
+    #[test]
+    fn test_ranking_metrics() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let expected: HashSet<Uuid> = [ids[1], ids[3]].into_iter().collect();
+        let ranked = vec![ids[0], ids[1], ids[2], ids[3]];
+
+        assert_eq!(recall_at_k(&ranked, &expected, 2), 0.5);
+        assert_eq!(recall_at_k(&ranked, &expected, 4), 1.0);
+        assert_eq!(reciprocal_rank(&ranked, &expected, 4), 0.5);
+        assert_eq!(reciprocal_rank(&ranked, &expected, 1), 0.0);
+
+        // Relevant at ranks 2 and 4 against an ideal of ranks 1 and 2
+        let ideal = 1.0 + 1.0 / 3f32.log2();
+        let dcg = 1.0 / 3f32.log2() + 1.0 / 5f32.log2();
+        assert!((ndcg_at_k(&ranked, &expected, 4) - dcg / ideal).abs() < 1e-6);
+        assert_eq!(ndcg_at_k(&[ids[1], ids[3]], &expected, 4), 1.0);
+        assert_eq!(ndcg_at_k(&[], &expected, 4), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_reports_each_config() -> Result<()> {
+        let engine = MemoroseEngine::new_in_memory().await?;
+        let unit = MemoryUnit::new(
+            None,
+            "eval_user".into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "Signed the Berlin apartment lease starting in May".into(),
+            None,
+        );
+        let unit_id = unit.id;
+        engine.store_memory_unit(unit).await?;
+
+        let cases = vec![EvalCase {
+            query: "Berlin apartment lease".into(),
+            user_id: "eval_user".into(),
+            org_id: None,
+            agent_id: None,
+            expected: vec![unit_id],
+        }];
+        let configs = vec![
+            RankingConfig::baseline(),
+            RankingConfig {
+                name: "flat_rrf".into(),
+                rrf_k: Some(10.0),
+                ..RankingConfig::baseline()
+            },
+        ];
+        let client = MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        };
+
+        let report = evaluate(&engine, &client, &cases, &configs, 5).await?;
+        assert_eq!(report.cases, 1);
+        let names: Vec<&str> = report.configs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["baseline", "flat_rrf"]);
+        for config in &report.configs {
+            assert!((0.0..=1.0).contains(&config.recall_at_k));
+            assert_eq!(config.missed.is_empty(), config.recall_at_k > 0.0);
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 // Query Cache - Borrowed from lance-graph's query cache ideas
 // Caches frequently used query results to avoid redundant computations
 
+use crate::arbitrator::ArbitrationDecision;
 use memorose_common::GraphEdge;
 use moka::future::Cache;
 use std::time::Duration;
@@ -23,6 +24,12 @@ pub enum CacheKey {
     },
     /// Community detection results cache
     CommunityDetection { user_id: String, algorithm: String },
+    /// LLM arbitration decision for a query over a (sorted) candidate set
+    Arbitration {
+        user_id: String,
+        query_hash: u64,
+        candidate_ids: Vec<Uuid>,
+    },
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -37,6 +44,8 @@ pub struct QueryCache {
     edge_cache: Cache<CacheKey, Vec<GraphEdge>>,
     /// Node ID list cache
     node_list_cache: Cache<CacheKey, Vec<Uuid>>,
+    /// Arbitration decision cache
+    arbitration_cache: Cache<CacheKey, ArbitrationDecision>,
     /// Cache configuration
     config: CacheConfig,
 }
@@ -66,16 +75,25 @@ impl QueryCache {
         let edge_cache = Cache::builder()
             .time_to_live(config.ttl)
             .max_capacity(config.max_entries as u64)
+            .support_invalidation_closures()
             .build();
 
         let node_list_cache = Cache::builder()
             .time_to_live(config.ttl)
             .max_capacity(config.max_entries as u64)
+            .support_invalidation_closures()
+            .build();
+
+        let arbitration_cache = Cache::builder()
+            .time_to_live(config.ttl)
+            .max_capacity(config.max_entries as u64)
+            .support_invalidation_closures()
             .build();
 
         Self {
             edge_cache,
             node_list_cache,
+            arbitration_cache,
             config,
         }
     }
@@ -119,6 +137,22 @@ impl QueryCache {
         self.node_list_cache.insert(key, nodes).await;
     }
 
+    /// Retrieve a cached arbitration decision
+    pub async fn get_arbitration(&self, key: &CacheKey) -> Option<ArbitrationDecision> {
+        if !self.config.enabled {
+            return None;
+        }
+        self.arbitration_cache.get(key).await
+    }
+
+    /// Cache an arbitration decision
+    pub async fn put_arbitration(&self, key: CacheKey, decision: ArbitrationDecision) {
+        if !self.config.enabled {
+            return;
+        }
+        self.arbitration_cache.insert(key, decision).await;
+    }
+
     /// Invalidate all caches for a specific user (e.g., when the user adds new edges)
     pub async fn invalidate_user(&self, user_id: &str) {
         let uid = user_id.to_string();
@@ -131,14 +165,65 @@ impl QueryCache {
             .node_list_cache
             .invalidate_entries_if(move |k: &CacheKey, _v| Self::key_matches_user(k, &uid2));
 
+        let uid3 = user_id.to_string();
+        let _ = self
+            .arbitration_cache
+            .invalidate_entries_if(move |k: &CacheKey, _v| Self::key_matches_user(k, &uid3));
+
         tracing::info!("Invalidated cache for user: {}", user_id);
     }
 
+    /// Invalidate graph results that may include edges touching `node_ids`: their 1-hop
+    /// neighbourhoods are dropped directly, and the user's multi-hop and community results
+    /// are dropped because any of them may route through the changed nodes. Arbitration
+    /// decisions do not depend on edges and are kept.
+    pub async fn invalidate_nodes(&self, user_id: &str, node_ids: &[Uuid]) {
+        for node_id in node_ids {
+            for direction in [Direction::Outgoing, Direction::Incoming] {
+                self.edge_cache
+                    .invalidate(&CacheKey::OneHopNeighbors {
+                        user_id: user_id.to_string(),
+                        node_id: *node_id,
+                        direction,
+                    })
+                    .await;
+            }
+        }
+
+        let uid = user_id.to_string();
+        let _ = self
+            .edge_cache
+            .invalidate_entries_if(move |k: &CacheKey, _v| {
+                Self::key_is_derived_graph_result(k) && Self::key_matches_user(k, &uid)
+            });
+
+        let uid2 = user_id.to_string();
+        let _ = self
+            .node_list_cache
+            .invalidate_entries_if(move |k: &CacheKey, _v| {
+                Self::key_is_derived_graph_result(k) && Self::key_matches_user(k, &uid2)
+            });
+
+        tracing::debug!(
+            "Invalidated graph cache for user {} ({} nodes)",
+            user_id,
+            node_ids.len()
+        );
+    }
+
+    fn key_is_derived_graph_result(key: &CacheKey) -> bool {
+        matches!(
+            key,
+            CacheKey::MultiHopTraversal { .. } | CacheKey::CommunityDetection { .. }
+        )
+    }
+
     fn key_matches_user(key: &CacheKey, user_id: &str) -> bool {
         match key {
             CacheKey::OneHopNeighbors { user_id: uid, .. } => uid == user_id,
             CacheKey::MultiHopTraversal { user_id: uid, .. } => uid == user_id,
             CacheKey::CommunityDetection { user_id: uid, .. } => uid == user_id,
+            CacheKey::Arbitration { user_id: uid, .. } => uid == user_id,
         }
     }
 
@@ -146,10 +231,12 @@ impl QueryCache {
     pub async fn stats(&self) -> CacheStats {
         self.edge_cache.run_pending_tasks().await;
         self.node_list_cache.run_pending_tasks().await;
+        self.arbitration_cache.run_pending_tasks().await;
 
         CacheStats {
             edge_cache_size: self.edge_cache.entry_count() as usize,
             node_cache_size: self.node_list_cache.entry_count() as usize,
+            arbitration_cache_size: self.arbitration_cache.entry_count() as usize,
             max_entries: self.config.max_entries,
         }
     }
@@ -158,6 +245,7 @@ impl QueryCache {
     pub async fn clear(&self) {
         self.edge_cache.invalidate_all();
         self.node_list_cache.invalidate_all();
+        self.arbitration_cache.invalidate_all();
         tracing::info!("Cleared all query caches");
     }
 }
@@ -166,6 +254,7 @@ impl QueryCache {
 pub struct CacheStats {
     pub edge_cache_size: usize,
     pub node_cache_size: usize,
+    pub arbitration_cache_size: usize,
     pub max_entries: usize,
 }
 
@@ -259,6 +348,74 @@ mod tests {
         assert!(cache.get_edges(&key2).await.is_some());
     }
 
+    #[tokio::test]
+    async fn test_node_invalidation_drops_neighbourhoods_and_derived_results() {
+        let cache = QueryCache::new(CacheConfig::default());
+        let changed = Uuid::new_v4();
+        let untouched = Uuid::new_v4();
+        let one_hop = |user: &str, node_id, direction| CacheKey::OneHopNeighbors {
+            user_id: user.to_string(),
+            node_id,
+            direction,
+        };
+        let traversal = CacheKey::MultiHopTraversal {
+            user_id: "user1".to_string(),
+            start_nodes: vec![untouched],
+            max_hops: 2,
+        };
+        let arbitration = CacheKey::Arbitration {
+            user_id: "user1".to_string(),
+            query_hash: 7,
+            candidate_ids: vec![changed],
+        };
+
+        cache
+            .put_edges(one_hop("user1", changed, Direction::Outgoing), vec![])
+            .await;
+        cache
+            .put_edges(one_hop("user1", changed, Direction::Incoming), vec![])
+            .await;
+        cache
+            .put_edges(one_hop("user1", untouched, Direction::Outgoing), vec![])
+            .await;
+        cache
+            .put_edges(one_hop("user2", changed, Direction::Outgoing), vec![])
+            .await;
+        cache.put_node_list(traversal.clone(), vec![changed]).await;
+        cache
+            .put_arbitration(
+                arbitration.clone(),
+                ArbitrationDecision {
+                    retained_ids: vec![changed],
+                    reasoning: None,
+                },
+            )
+            .await;
+
+        cache.invalidate_nodes("user1", &[changed]).await;
+        cache.node_list_cache.run_pending_tasks().await;
+
+        assert!(cache
+            .get_edges(&one_hop("user1", changed, Direction::Outgoing))
+            .await
+            .is_none());
+        assert!(cache
+            .get_edges(&one_hop("user1", changed, Direction::Incoming))
+            .await
+            .is_none());
+        assert!(cache.get_node_list(&traversal).await.is_none());
+        assert!(cache
+            .get_edges(&one_hop("user1", untouched, Direction::Outgoing))
+            .await
+            .is_some());
+        assert!(cache
+            .get_edges(&one_hop("user2", changed, Direction::Outgoing))
+            .await
+            .is_some());
+        assert!(cache.get_arbitration(&arbitration).await.is_some());
+        assert_eq!(cache.stats().await.node_cache_size, 0);
+    }
+
     #[tokio::test]
     async fn test_node_list_stats_and_clear() {
         let cache = QueryCache::new(CacheConfig::default());
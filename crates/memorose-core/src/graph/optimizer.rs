@@ -1,6 +1,7 @@
 // Query Optimizer - Borrowing lance-graph's query optimization concepts
 // Converts declarative queries into efficient execution plans
 
+use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -55,6 +56,66 @@ impl QueryOptimizer {
         }
     }
 
+    /// Seed the optimizer with observed average fanout per relation type.
+    pub fn with_fanout_stats(mut self, avg_fanout: HashMap<String, f32>) -> Self {
+        self.stats.avg_fanout = avg_fanout;
+        self
+    }
+
+    /// Estimated output rows of every operator, outermost first (the order
+    /// [`PlanExplainer::explain`] prints them in).
+    pub fn estimate(&self, plan: &ExecutionPlan) -> Vec<PlanStepEstimate> {
+        let mut steps = Vec::new();
+        self.estimate_recursive(plan, 0, &mut steps);
+        steps
+    }
+
+    fn estimate_recursive(
+        &self,
+        plan: &ExecutionPlan,
+        depth: usize,
+        steps: &mut Vec<PlanStepEstimate>,
+    ) -> usize {
+        let index = steps.len();
+        steps.push(PlanStepEstimate {
+            depth,
+            operator: String::new(),
+            estimated_rows: 0,
+        });
+        let (operator, rows) = match plan {
+            ExecutionPlan::ScanNodes { node_ids } => ("ScanNodes", node_ids.len()),
+            ExecutionPlan::BatchExpand {
+                input, edge_filter, ..
+            } => {
+                let input_rows = self.estimate_recursive(input, depth + 1, steps);
+                let rows = (input_rows as f32 * self.estimated_fanout(edge_filter)).ceil();
+                ("BatchExpand", rows as usize)
+            }
+            ExecutionPlan::Distinct { input } => {
+                ("Distinct", self.estimate_recursive(input, depth + 1, steps))
+            }
+            ExecutionPlan::Limit { input, count } => {
+                let input_rows = self.estimate_recursive(input, depth + 1, steps);
+                ("Limit", input_rows.min(*count))
+            }
+        };
+        steps[index].operator = operator.to_string();
+        steps[index].estimated_rows = rows;
+        rows
+    }
+
+    /// Average edges followed per input node; an empty relation filter follows every type.
+    fn estimated_fanout(&self, edge_filter: &EdgeFilter) -> f32 {
+        if edge_filter.relation_types.is_empty() {
+            return self.stats.avg_fanout.values().sum();
+        }
+        edge_filter
+            .relation_types
+            .iter()
+            .filter_map(|t| self.stats.avg_fanout.get(t))
+            .sum()
+    }
+
     /// Optimize execution plan (similar to DataFusion Planner in lance-graph)
     pub fn optimize(&self, plan: ExecutionPlan) -> ExecutionPlan {
         // Optimization Rule 1: Predicate Pushdown
@@ -97,7 +158,7 @@ impl QueryOptimizer {
         }
     }
 
-    /// Rule 2: Adjust batch size based on statistics
+    /// Rule 2: Adjust batch size based on statistics (applied to every expansion in the plan)
     fn adjust_batch_sizes(&self, plan: ExecutionPlan) -> ExecutionPlan {
         match plan {
             ExecutionPlan::BatchExpand {
@@ -105,6 +166,7 @@ impl QueryOptimizer {
                 edge_filter,
                 batch_size,
             } => {
+                let input = Box::new(self.adjust_batch_sizes(*input));
                 // Dynamically adjust based on relation type fanout degree
                 let estimated_fanout = edge_filter
                     .relation_types
@@ -129,6 +191,13 @@ impl QueryOptimizer {
                     batch_size: optimal_batch,
                 }
             }
+            ExecutionPlan::Distinct { input } => ExecutionPlan::Distinct {
+                input: Box::new(self.adjust_batch_sizes(*input)),
+            },
+            ExecutionPlan::Limit { input, count } => ExecutionPlan::Limit {
+                input: Box::new(self.adjust_batch_sizes(*input)),
+                count,
+            },
             other => other,
         }
     }
@@ -140,6 +209,34 @@ impl QueryOptimizer {
     }
 }
 
+/// Estimated output of one plan operator
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanStepEstimate {
+    /// Nesting depth in the plan tree (0 = root)
+    pub depth: usize,
+    pub operator: String,
+    pub estimated_rows: usize,
+}
+
+/// EXPLAIN output for a graph query
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanExplanation {
+    /// The optimized plan, rendered by [`PlanExplainer::explain`]
+    pub plan: String,
+    pub steps: Vec<PlanStepEstimate>,
+    pub estimated_result_rows: usize,
+    /// Observed average fanout per relation type, sampled from the start nodes
+    pub sampled_fanout: HashMap<String, f32>,
+    /// Whether the query is served by the cached one-hop neighbour path
+    pub uses_query_cache: bool,
+    /// Whether that cached neighbourhood is currently present
+    pub cache_hit: bool,
+    /// Whether frontier expansion goes through the batched edge lookup
+    pub uses_batch_expansion: bool,
+    /// Whether edge lookups are answered from the RocksDB adjacency index
+    pub uses_adjacency_index: bool,
+}
+
 /// Execution Plan Explainer (for debugging)
 pub struct PlanExplainer;
 
@@ -224,6 +321,7 @@ mod tests {
 
         let explanation = PlanExplainer::explain(&plan);
         println!("Execution Plan:\n{}", explanation);
+        assert!(explanation.starts_with("Limit (count=10)"));
 
         // Should output something like:
         // Limit (count=10)
@@ -231,4 +329,53 @@ mod tests {
         //     BatchExpand (batch_size=256, filter=...)
         //       ScanNodes (count=5)
     }
+
+    #[test]
+    fn test_estimate_uses_fanout_stats_and_adjusts_nested_expansions() {
+        let expand = |input| ExecutionPlan::BatchExpand {
+            input: Box::new(input),
+            edge_filter: EdgeFilter {
+                relation_types: vec!["RELATED_TO".to_string()],
+                min_weight: None,
+                max_weight: None,
+            },
+            batch_size: 256,
+        };
+        let plan = ExecutionPlan::Limit {
+            count: 50,
+            input: Box::new(ExecutionPlan::Distinct {
+                input: Box::new(expand(expand(ExecutionPlan::ScanNodes {
+                    node_ids: vec![Uuid::new_v4(); 4],
+                }))),
+            }),
+        };
+        let optimizer = QueryOptimizer::new()
+            .with_fanout_stats(HashMap::from([("RELATED_TO".to_string(), 3.0)]));
+
+        let optimized = optimizer.optimize(plan);
+        let steps = optimizer.estimate(&optimized);
+
+        let operators: Vec<(&str, usize)> = steps
+            .iter()
+            .map(|s| (s.operator.as_str(), s.estimated_rows))
+            .collect();
+        assert_eq!(
+            operators,
+            vec![
+                ("Limit", 36),
+                ("Distinct", 36),
+                ("BatchExpand", 36),
+                ("BatchExpand", 12),
+                ("ScanNodes", 4),
+            ]
+        );
+        assert_eq!(steps[4].depth, 4);
+        // Low observed fanout widens both expansions, not just the root.
+        assert_eq!(
+            PlanExplainer::explain(&optimized)
+                .matches("batch_size=512")
+                .count(),
+            2
+        );
+    }
 }
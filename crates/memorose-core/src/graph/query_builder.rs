@@ -1,12 +1,17 @@
 // Graph Query Builder - Borrowing lance-graph's declarative concepts
 // But using pure Rust APIs, requiring no Cypher parsing
 
+use super::optimizer::{EdgeFilter, ExecutionPlan};
 use crate::storage::graph::GraphStore;
 use anyhow::Result;
 use memorose_common::{GraphEdge, RelationType};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use uuid::Uuid;
 
+/// Nodes per expansion batch before the optimizer adjusts it
+const DEFAULT_EXPAND_BATCH_SIZE: usize = 256;
+
 /// Graph traversal configuration
 #[derive(Debug, Clone)]
 pub struct TraversalSpec {
@@ -17,8 +22,10 @@ pub struct TraversalSpec {
     pub weight_threshold: Option<f32>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TraversalDirection {
+    #[default]
     Outgoing,
     Incoming,
     Both,
@@ -65,6 +72,60 @@ impl GraphQueryBuilder {
         self
     }
 
+    /// Append an already-configured traversal step.
+    pub fn with_traversal(mut self, spec: TraversalSpec) -> Self {
+        self.traversals.push(spec);
+        self
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn start_nodes(&self) -> &[Uuid] {
+        &self.start_nodes
+    }
+
+    pub fn traversals(&self) -> &[TraversalSpec] {
+        &self.traversals
+    }
+
+    /// The unoptimized plan `execute` follows: one batched expansion per traversal step,
+    /// then de-duplication and the optional limit.
+    pub fn to_execution_plan(&self) -> ExecutionPlan {
+        let mut plan = ExecutionPlan::ScanNodes {
+            node_ids: self.start_nodes.clone(),
+        };
+        for traversal in &self.traversals {
+            plan = ExecutionPlan::BatchExpand {
+                input: Box::new(plan),
+                edge_filter: EdgeFilter {
+                    relation_types: traversal
+                        .relation_types
+                        .iter()
+                        .map(|r| r.as_str().to_string())
+                        .collect(),
+                    min_weight: traversal.weight_threshold,
+                    max_weight: None,
+                },
+                batch_size: DEFAULT_EXPAND_BATCH_SIZE,
+            };
+        }
+        if self.traversals.is_empty() {
+            return plan;
+        }
+        plan = ExecutionPlan::Distinct {
+            input: Box::new(plan),
+        };
+        if let Some(count) = self.limit {
+            plan = ExecutionPlan::Limit {
+                input: Box::new(plan),
+                count,
+            };
+        }
+        plan
+    }
+
     /// Execute the optimized query plan
     pub async fn execute(self, graph: &GraphStore) -> Result<Vec<Uuid>> {
         let planner = QueryPlanner::new(graph);
@@ -269,6 +330,36 @@ mod tests {
         // Execute: let results = query.execute(&graph).await?;
     }
 
+    #[test]
+    fn test_to_execution_plan_expands_once_per_traversal_step() {
+        let spec = TraversalSpec {
+            relation_types: vec![RelationType::RelatedTo],
+            direction: TraversalDirection::Outgoing,
+            min_hops: 1,
+            max_hops: 1,
+            weight_threshold: Some(0.4),
+        };
+        let query = GraphQueryBuilder::new("user1".to_string())
+            .start_from(vec![Uuid::new_v4(), Uuid::new_v4()])
+            .with_traversal(spec.clone())
+            .with_traversal(spec)
+            .limit(5);
+
+        let explained = super::super::PlanExplainer::explain(&query.to_execution_plan());
+        let lines: Vec<&str> = explained.lines().map(str::trim).collect();
+        assert_eq!(lines[0], "Limit (count=5)");
+        assert_eq!(lines[1], "Distinct");
+        assert!(lines[2].starts_with("BatchExpand (batch_size=256"));
+        assert!(lines[3].starts_with("BatchExpand"));
+        assert_eq!(lines[4], "ScanNodes (count=2)");
+
+        let bare = GraphQueryBuilder::new("user1".to_string()).start_from(vec![Uuid::new_v4()]);
+        assert!(matches!(
+            bare.to_execution_plan(),
+            ExecutionPlan::ScanNodes { .. }
+        ));
+    }
+
     #[tokio::test]
     async fn test_execute_returns_start_nodes_when_no_traversal() -> Result<()> {
         let start = Uuid::new_v4();
@@ -0,0 +1,162 @@
+/// Phrases where the speaker asks for something to be kept. One of them is enough to
+/// score a memory as fully important.
+const EXPLICIT_PHRASES: &[&str] = &[
+    "remember",
+    "don't forget",
+    "do not forget",
+    "keep in mind",
+    "note that",
+    "make sure",
+    "important",
+    "never forget",
+];
+
+/// Phrases stating a preference, habit or constraint of the user.
+const PREFERENCE_PHRASES: &[&str] = &[
+    "prefer",
+    "favorite",
+    "favourite",
+    "i like",
+    "i love",
+    "i enjoy",
+    "i hate",
+    "i dislike",
+    "i don't like",
+    "i do not like",
+    "i want",
+    "i'd rather",
+    "i would rather",
+    "allergic",
+    "i always",
+    "i never",
+    "i usually",
+    "my goal",
+];
+
+const EMOTION_WORDS: &[&str] = &[
+    "afraid",
+    "angry",
+    "annoyed",
+    "anxious",
+    "delighted",
+    "disappointed",
+    "excited",
+    "frustrated",
+    "grateful",
+    "happy",
+    "hurt",
+    "lonely",
+    "proud",
+    "sad",
+    "scared",
+    "stressed",
+    "thrilled",
+    "upset",
+    "worried",
+];
+
+const NOVELTY_WEIGHT: f32 = 0.5;
+const PREFERENCE_WEIGHT: f32 = 0.3;
+const EMOTION_WEIGHT: f32 = 0.2;
+
+/// Content signals behind a new memory's initial importance, each between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ImportanceSignals {
+    /// How unlike the user's existing memories the content is
+    pub novelty: f32,
+    pub preference: f32,
+    pub emotion: f32,
+    /// Whether the speaker asked for this to be remembered
+    pub explicit: bool,
+}
+
+impl ImportanceSignals {
+    pub fn from_content(content: &str, novelty: f32) -> Self {
+        let text = content.to_lowercase();
+        let explicit = EXPLICIT_PHRASES.iter().any(|phrase| text.contains(phrase));
+        let preference = saturating_share(count_phrases(&text, PREFERENCE_PHRASES), 2);
+        let emotion_hits = text
+            .split(|c: char| !c.is_alphanumeric() && c != '\'')
+            .filter(|word| EMOTION_WORDS.contains(word))
+            .count()
+            + usize::from(text.contains('!'));
+        Self {
+            novelty: novelty.clamp(0.0, 1.0),
+            preference,
+            emotion: saturating_share(emotion_hits, 2),
+            explicit,
+        }
+    }
+
+    /// Weighted sum of the signals, lifted onto `[floor, 1]`. An explicit request to
+    /// remember scores 1.0.
+    pub fn score(&self, floor: f32) -> f32 {
+        let floor = floor.clamp(0.0, 1.0);
+        if self.explicit {
+            return 1.0;
+        }
+        let weighted = NOVELTY_WEIGHT * self.novelty
+            + PREFERENCE_WEIGHT * self.preference
+            + EMOTION_WEIGHT * self.emotion;
+        (floor + (1.0 - floor) * weighted).clamp(floor, 1.0)
+    }
+}
+
+/// Novelty from the best `1 / (1 + squared L2 distance)` score against the user's
+/// memories. For unit vectors an identical memory scores 1 and an unrelated one 1/3,
+/// so that range maps onto novelty 0 to 1.
+pub(crate) fn novelty_from_similarity(best_score: Option<f32>) -> f32 {
+    match best_score {
+        Some(score) => ((1.0 - score) * 1.5).clamp(0.0, 1.0),
+        None => 1.0,
+    }
+}
+
+fn count_phrases(text: &str, phrases: &[&str]) -> usize {
+    phrases
+        .iter()
+        .filter(|phrase| text.contains(*phrase))
+        .count()
+}
+
+fn saturating_share(hits: usize, saturation: usize) -> f32 {
+    (hits as f32 / saturation as f32).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_importance_ranks_preferences_and_requests_above_small_talk() {
+        let floor = 0.2;
+        let small_talk = ImportanceSignals::from_content("The weather was cloudy today.", 0.5);
+        let preference =
+            ImportanceSignals::from_content("I prefer window seats and I hate early flights.", 0.5);
+        let emotional = ImportanceSignals::from_content("I was so worried and upset!", 0.5);
+        let explicit = ImportanceSignals::from_content("Please remember my passport number.", 0.0);
+
+        assert!(preference.score(floor) > small_talk.score(floor));
+        assert!(emotional.score(floor) > small_talk.score(floor));
+        assert_eq!(explicit.score(floor), 1.0);
+        assert!(small_talk.score(floor) >= floor);
+    }
+
+    #[test]
+    fn test_importance_drops_for_repeated_content() {
+        let floor = 0.2;
+        let novel = ImportanceSignals::from_content("Alice moved to Lisbon.", 1.0);
+        let repeated = ImportanceSignals::from_content(
+            "Alice moved to Lisbon.",
+            novelty_from_similarity(Some(0.98)),
+        );
+
+        assert!(repeated.score(floor) < novel.score(floor));
+        assert_eq!(novelty_from_similarity(None), 1.0);
+        assert!(novelty_from_similarity(Some(1.0 / 3.0)) > 0.99);
+        assert_eq!(
+            ImportanceSignals::from_content("ok", 0.0).score(floor),
+            floor
+        );
+    }
+}
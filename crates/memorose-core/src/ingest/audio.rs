@@ -0,0 +1,342 @@
+use crate::llm::LLMClient;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use memorose_common::tokenizer::count_tokens;
+use std::process::Command;
+use tempfile::TempDir;
+use tracing::info;
+
+/// One utterance of a transcript, timed relative to the start of the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// Consecutive segments that are stored together as one memory unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptSection {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl TranscriptSection {
+    /// Speakers in order of first appearance.
+    pub fn speakers(&self) -> Vec<String> {
+        let mut speakers: Vec<String> = Vec::new();
+        for speaker in self.segments.iter().filter_map(|s| s.speaker.as_ref()) {
+            if !speakers.contains(speaker) {
+                speakers.push(speaker.clone());
+            }
+        }
+        speakers
+    }
+
+    /// One `[hh:mm:ss] Speaker: text` line per segment.
+    pub fn render(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match &segment.speaker {
+                Some(speaker) => format!(
+                    "[{}] {}: {}",
+                    format_timestamp(segment.start_secs),
+                    speaker,
+                    segment.text
+                ),
+                None => format!(
+                    "[{}] {}",
+                    format_timestamp(segment.start_secs),
+                    segment.text
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A slice of the recording small enough for a single transcription call.
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    pub start_secs: f64,
+    pub bytes: Vec<u8>,
+}
+
+pub fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        (total % 3600) / 60,
+        total % 60
+    )
+}
+
+/// Split a recording into `chunk_secs` long mono MP3 chunks with FFmpeg. When FFmpeg is
+/// missing or cannot decode the input, the whole recording is returned as one chunk.
+pub async fn split_audio(bytes: Vec<u8>, chunk_secs: u64) -> Result<Vec<AudioChunk>> {
+    let chunk_secs = chunk_secs.max(1);
+    tokio::task::spawn_blocking(move || -> Result<Vec<AudioChunk>> {
+        let temp_dir = TempDir::new()?;
+        let input_path = temp_dir.path().join("input");
+        std::fs::write(&input_path, &bytes)?;
+        let output_pattern = temp_dir.path().join("chunk_%05d.mp3");
+
+        info!("Splitting audio into {}s chunks...", chunk_secs);
+        let status = Command::new("ffmpeg")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-i")
+            .arg(&input_path)
+            .arg("-vn")
+            .arg("-ac")
+            .arg("1")
+            .arg("-ar")
+            .arg("16000")
+            .arg("-f")
+            .arg("segment")
+            .arg("-segment_time")
+            .arg(chunk_secs.to_string())
+            .arg(&output_pattern)
+            .status();
+        let failure = match status {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(format!("ffmpeg exited with status {}", status)),
+            Err(error) => Some(format!("ffmpeg unavailable: {}", error)),
+        };
+        if let Some(failure) = failure {
+            tracing::warn!("{}; transcribing audio as a single chunk", failure);
+            return Ok(vec![AudioChunk {
+                start_secs: 0.0,
+                bytes,
+            }]);
+        }
+
+        let mut paths: Vec<_> = std::fs::read_dir(temp_dir.path())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("chunk_"))
+            })
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| {
+                Ok(AudioChunk {
+                    start_secs: (index as u64 * chunk_secs) as f64,
+                    bytes: std::fs::read(path)?,
+                })
+            })
+            .collect()
+    })
+    .await?
+}
+
+/// Transcribe a chunk with a local command such as whisper.cpp. `{input}` in the
+/// command is replaced by the path of the chunk; the transcript is read from stdout.
+pub async fn transcribe_with_command(command: &str, chunk: &AudioChunk) -> Result<String> {
+    let command = command.to_string();
+    let bytes = chunk.bytes.clone();
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let temp_dir = TempDir::new()?;
+        let input_path = temp_dir.path().join("chunk.mp3");
+        std::fs::write(&input_path, &bytes)?;
+        let input = input_path.to_string_lossy();
+        let mut parts = command
+            .split_whitespace()
+            .map(|part| part.replace("{input}", &input));
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow!("audio transcription command is empty"))?;
+        let output = Command::new(&program)
+            .args(parts)
+            .output()
+            .with_context(|| format!("failed to run {}", program))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} exited with status {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+    .await?
+}
+
+/// Parse one chunk's transcript into segments. Lines may carry a `[mm:ss]` or
+/// `[hh:mm:ss]` timestamp relative to the chunk and a `Speaker:` label; untimed lines
+/// are spread evenly across the chunk.
+pub fn parse_transcript(text: &str, offset_secs: f64, chunk_secs: f64) -> Vec<TranscriptSegment> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let step = chunk_secs / lines.len().max(1) as f64;
+
+    let mut segments: Vec<TranscriptSegment> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let (timestamp, rest) = split_timestamp(line);
+            let (speaker, text) = split_speaker(rest);
+            TranscriptSegment {
+                start_secs: offset_secs + timestamp.unwrap_or(index as f64 * step),
+                end_secs: offset_secs + chunk_secs,
+                speaker,
+                text: text.to_string(),
+            }
+        })
+        .collect();
+    for index in 1..segments.len() {
+        let next_start = segments[index].start_secs;
+        segments[index - 1].end_secs = next_start.max(segments[index - 1].start_secs);
+    }
+    segments
+}
+
+fn split_timestamp(line: &str) -> (Option<f64>, &str) {
+    let Some(rest) = line.strip_prefix('[') else {
+        return (None, line);
+    };
+    let Some((stamp, rest)) = rest.split_once(']') else {
+        return (None, line);
+    };
+    let parts: Option<Vec<f64>> = stamp.split(':').map(|p| p.trim().parse().ok()).collect();
+    let secs = match parts.as_deref() {
+        Some([m, s]) => m * 60.0 + s,
+        Some([h, m, s]) => h * 3600.0 + m * 60.0 + s,
+        _ => return (None, line),
+    };
+    (Some(secs), rest.trim_start())
+}
+
+fn split_speaker(line: &str) -> (Option<String>, &str) {
+    if let Some((label, text)) = line.split_once(':') {
+        let label = label.trim();
+        let looks_like_label = !label.is_empty()
+            && label.len() <= 32
+            && label.split_whitespace().count() <= 3
+            && label.chars().next().is_some_and(char::is_uppercase);
+        if looks_like_label && !text.trim().is_empty() {
+            return (Some(label.to_string()), text.trim());
+        }
+    }
+    (None, line)
+}
+
+/// Transcribe `chunks` with the local `command` when set, otherwise with `llm`, and
+/// merge the results into one timeline.
+pub async fn transcribe_chunks(
+    llm: Option<&dyn LLMClient>,
+    command: Option<&str>,
+    chunks: &[AudioChunk],
+    chunk_secs: u64,
+) -> Result<Vec<TranscriptSegment>> {
+    let mut segments = Vec::new();
+    for chunk in chunks {
+        let transcript = match command {
+            Some(command) => transcribe_with_command(command, chunk).await?,
+            None => {
+                let client =
+                    llm.ok_or_else(|| anyhow!("no transcription provider is configured"))?;
+                let encoded = base64::engine::general_purpose::STANDARD.encode(&chunk.bytes);
+                client.transcribe(&encoded).await?.data
+            }
+        };
+        segments.extend(parse_transcript(
+            &transcript,
+            chunk.start_secs,
+            chunk_secs as f64,
+        ));
+    }
+    Ok(segments)
+}
+
+/// Group segments into sections of at most `max_tokens` each.
+pub fn group_segments(
+    segments: Vec<TranscriptSegment>,
+    max_tokens: usize,
+) -> Vec<TranscriptSection> {
+    let max_tokens = max_tokens.max(1);
+    let mut sections = Vec::new();
+    let mut current: Vec<TranscriptSegment> = Vec::new();
+    let mut current_tokens = 0;
+
+    for segment in segments {
+        let tokens = count_tokens(&segment.text) + 4;
+        if !current.is_empty() && current_tokens + tokens > max_tokens {
+            sections.push(section_from(std::mem::take(&mut current)));
+            current_tokens = 0;
+        }
+        current_tokens += tokens;
+        current.push(segment);
+    }
+    if !current.is_empty() {
+        sections.push(section_from(current));
+    }
+    sections
+}
+
+fn section_from(segments: Vec<TranscriptSegment>) -> TranscriptSection {
+    TranscriptSection {
+        start_secs: segments.first().map(|s| s.start_secs).unwrap_or_default(),
+        end_secs: segments.last().map(|s| s.end_secs).unwrap_or_default(),
+        segments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transcript_reads_timestamps_and_speakers() {
+        let segments = parse_transcript(
+            "[00:05] Alice: Let's start.\n[01:10] Bob: Budget is approved.\nthanks all",
+            300.0,
+            120.0,
+        );
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].start_secs, 305.0);
+        assert_eq!(segments[0].end_secs, 370.0);
+        assert_eq!(segments[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(segments[1].text, "Budget is approved.");
+        // Untimed lines are spread across the chunk.
+        assert_eq!(segments[2].start_secs, 380.0);
+        assert_eq!(segments[2].speaker, None);
+        assert_eq!(segments[2].end_secs, 420.0);
+    }
+
+    #[test]
+    fn test_group_segments_respects_token_budget_and_renders_lines() {
+        let segment = |start: f64, speaker: &str, text: &str| TranscriptSegment {
+            start_secs: start,
+            end_secs: start + 10.0,
+            speaker: Some(speaker.to_string()),
+            text: text.to_string(),
+        };
+        let sections = group_segments(
+            vec![
+                segment(0.0, "Alice", "We ship the beta next week."),
+                segment(10.0, "Bob", "QA still needs two days."),
+                segment(3725.0, "Alice", "Then we move the launch."),
+            ],
+            30,
+        );
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].speakers(), vec!["Alice", "Bob"]);
+        assert_eq!((sections[0].start_secs, sections[0].end_secs), (0.0, 20.0));
+        assert_eq!(
+            sections[1].render(),
+            "[01:02:05] Alice: Then we move the launch."
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use crate::llm::LLMClient;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+
+/// Longest side, in pixels, of the thumbnails stored next to ingested images.
+pub const THUMBNAIL_MAX_SIDE: u32 = 256;
+
+/// Asset metadata key holding the OCR text of an image.
+pub const OCR_TEXT_METADATA_KEY: &str = "ocr_text";
+/// Asset metadata key holding the storage key of an image's thumbnail.
+pub const THUMBNAIL_KEY_METADATA_KEY: &str = "thumbnail_key";
+/// Asset metadata key holding the URL an image was downloaded from.
+pub const SOURCE_URL_METADATA_KEY: &str = "source_url";
+
+/// Raw image bytes resolved from an event's URL, data URI or base64 payload.
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+/// What the vision model saw in an image.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageAnalysis {
+    pub caption: Option<String>,
+    pub ocr_text: Option<String>,
+}
+
+impl ImageAnalysis {
+    /// The searchable content stored for an image: the caption followed by any OCR text.
+    pub fn searchable_text(&self, source: &str) -> String {
+        let caption = self
+            .caption
+            .clone()
+            .unwrap_or_else(|| format!("Image at {}", source));
+        match &self.ocr_text {
+            Some(ocr_text) => format!("{}\nText in image: {}", caption, ocr_text),
+            None => caption,
+        }
+    }
+}
+
+/// Where an ingested image and its thumbnail were written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredImage {
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+}
+
+/// Caption and OCR an image. Either step may fail on its own; failures leave that
+/// field empty rather than failing the ingest.
+pub async fn analyze_image(llm: Option<&dyn LLMClient>, source: &str) -> ImageAnalysis {
+    let Some(client) = llm else {
+        return ImageAnalysis::default();
+    };
+    let caption = client
+        .describe_image(source)
+        .await
+        .map(|response| response.data.trim().to_string())
+        .ok()
+        .filter(|caption| !caption.is_empty());
+    let ocr_text = client
+        .extract_image_text(source)
+        .await
+        .map(|response| response.data.trim().to_string())
+        .ok()
+        .filter(|text| !text.is_empty());
+    ImageAnalysis { caption, ocr_text }
+}
+
+/// Resolve an image event payload to bytes: `http(s)` URLs are downloaded, `data:` URIs
+/// and bare base64 are decoded.
+pub async fn load_image(source: &str) -> Result<LoadedImage> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = reqwest::get(source).await?.error_for_status()?;
+        let header_mime = response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.bytes().await?.to_vec();
+        let mime_type = header_mime.unwrap_or_else(|| sniff_mime_type(&bytes));
+        return Ok(LoadedImage { bytes, mime_type });
+    }
+
+    let (declared_mime, payload) = match source.strip_prefix("data:") {
+        Some(rest) => {
+            let (header, payload) = rest
+                .split_once(',')
+                .ok_or_else(|| anyhow!("malformed data URI"))?;
+            let mime = header.trim_end_matches(";base64");
+            (Some(mime.to_string()), payload)
+        }
+        None => (None, source),
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(payload.trim())
+        .context("image payload is neither a URL nor base64")?;
+    let mime_type = declared_mime
+        .filter(|mime| !mime.is_empty())
+        .unwrap_or_else(|| sniff_mime_type(&bytes));
+    Ok(LoadedImage { bytes, mime_type })
+}
+
+fn sniff_mime_type(bytes: &[u8]) -> String {
+    image::guess_format(bytes)
+        .map(|format| format.to_mime_type().to_string())
+        .unwrap_or_else(|_| "image/jpeg".to_string())
+}
+
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        _ => "bin",
+    }
+}
+
+/// Downscale an image to fit in `max_side` pixels and encode it as PNG.
+pub fn make_thumbnail(bytes: &[u8], max_side: u32) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(bytes)?;
+    let thumbnail = image.thumbnail(max_side, max_side);
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    thumbnail.write_to(&mut encoded, image::ImageFormat::Png)?;
+    Ok(encoded.into_inner())
+}
+
+/// Write an image, and a thumbnail when it can be decoded, under `asset_dir`.
+/// Files are content-addressed, so storing the same image twice is a no-op.
+pub fn store_image(asset_dir: &Path, image: &LoadedImage) -> Result<StoredImage> {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    image.bytes.hash(&mut hasher);
+    let name = format!("{:016x}", hasher.finish());
+
+    let relative = format!("images/{}.{}", name, extension_for(&image.mime_type));
+    let path = asset_dir.join(&relative);
+    std::fs::create_dir_all(path.parent().expect("asset path has a parent"))?;
+    if !path.exists() {
+        std::fs::write(&path, &image.bytes)?;
+    }
+
+    let thumbnail_relative = format!("images/thumbs/{}.png", name);
+    let thumbnail_path = asset_dir.join(&thumbnail_relative);
+    let thumbnail_key = if thumbnail_path.exists() {
+        Some(format!("local://{}", thumbnail_relative))
+    } else {
+        match make_thumbnail(&image.bytes, THUMBNAIL_MAX_SIDE) {
+            Ok(thumbnail) => {
+                std::fs::create_dir_all(thumbnail_path.parent().expect("has a parent"))?;
+                std::fs::write(&thumbnail_path, thumbnail)?;
+                Some(format!("local://{}", thumbnail_relative))
+            }
+            Err(error) => {
+                tracing::warn!("Could not build a thumbnail for {}: {:?}", relative, error);
+                None
+            }
+        }
+    };
+
+    Ok(StoredImage {
+        storage_key: format!("local://{}", relative),
+        thumbnail_key,
+    })
+}
+
+/// Map a `local://` storage key back to a file under `asset_dir`. Keys that are not
+/// local, or that would escape `asset_dir`, resolve to `None`.
+pub fn resolve_local_asset(asset_dir: &Path, storage_key: &str) -> Option<PathBuf> {
+    let relative = Path::new(storage_key.strip_prefix("local://")?);
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(asset_dir.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]));
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut encoded, image::ImageFormat::Png)
+            .unwrap();
+        encoded.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_load_image_decodes_data_uri_and_bare_base64() {
+        let bytes = png_bytes(4, 4);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+
+        let from_uri = load_image(&format!("data:image/png;base64,{}", encoded))
+            .await
+            .unwrap();
+        assert_eq!(from_uri.mime_type, "image/png");
+        assert_eq!(from_uri.bytes, bytes);
+
+        let from_base64 = load_image(&encoded).await.unwrap();
+        assert_eq!(from_base64.mime_type, "image/png");
+
+        assert!(load_image("not base64 at all!").await.is_err());
+    }
+
+    #[test]
+    fn test_store_image_writes_asset_and_thumbnail() {
+        let dir = tempdir().unwrap();
+        let image = LoadedImage {
+            bytes: png_bytes(800, 400),
+            mime_type: "image/png".into(),
+        };
+
+        let stored = store_image(dir.path(), &image).unwrap();
+        assert!(stored.storage_key.starts_with("local://images/"));
+        let thumbnail_key = stored.thumbnail_key.clone().unwrap();
+        let thumbnail_path = resolve_local_asset(dir.path(), &thumbnail_key).unwrap();
+        let thumbnail = image::open(thumbnail_path).unwrap();
+        assert_eq!(
+            (thumbnail.width(), thumbnail.height()),
+            (THUMBNAIL_MAX_SIDE, 128)
+        );
+        assert_eq!(store_image(dir.path(), &image).unwrap(), stored);
+
+        let undecodable = LoadedImage {
+            bytes: b"not an image".to_vec(),
+            mime_type: "image/jpeg".into(),
+        };
+        assert!(store_image(dir.path(), &undecodable)
+            .unwrap()
+            .thumbnail_key
+            .is_none());
+    }
+
+    #[test]
+    fn test_resolve_local_asset_rejects_escapes() {
+        let dir = Path::new("/data/assets");
+        assert_eq!(
+            resolve_local_asset(dir, "local://images/a.png"),
+            Some(dir.join("images/a.png"))
+        );
+        assert!(resolve_local_asset(dir, "local://../secrets").is_none());
+        assert!(resolve_local_asset(dir, "local:///etc/passwd").is_none());
+        assert!(resolve_local_asset(dir, "https://example.com/a.png").is_none());
+    }
+
+    #[test]
+    fn test_searchable_text_combines_caption_and_ocr() {
+        let analysis = ImageAnalysis {
+            caption: Some("A whiteboard".into()),
+            ocr_text: Some("Ship v2 on Friday".into()),
+        };
+        assert_eq!(
+            analysis.searchable_text("x"),
+            "A whiteboard\nText in image: Ship v2 on Friday"
+        );
+        assert_eq!(ImageAnalysis::default().searchable_text("x"), "Image at x");
+    }
+}
@@ -1 +1,26 @@
+pub mod audio;
+pub mod image;
 pub mod video;
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+
+/// Resolve a media event payload to bytes: `http(s)` URLs are downloaded, `data:` URIs
+/// and bare base64 are decoded.
+pub async fn load_media_bytes(source: &str) -> Result<Vec<u8>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = reqwest::get(source).await?.error_for_status()?;
+        return Ok(response.bytes().await?.to_vec());
+    }
+    let payload = match source.strip_prefix("data:") {
+        Some(rest) => {
+            rest.split_once(',')
+                .ok_or_else(|| anyhow!("malformed data URI"))?
+                .1
+        }
+        None => source,
+    };
+    base64::engine::general_purpose::STANDARD
+        .decode(payload.trim())
+        .context("media payload is neither a URL nor base64")
+}
@@ -1,10 +1,15 @@
+use crate::ingest::audio::{self, TranscriptSegment};
+use crate::llm::LLMClient;
 use anyhow::{Context, Result};
+use base64::Engine as _;
 use candle_core::{Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::models::clip::{ClipConfig, ClipModel};
 use hf_hub::{api::sync::Api, Repo, RepoType};
 use image::DynamicImage;
+use memorose_common::video::{VideoClip, VideoSource};
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -203,10 +208,264 @@ impl VideoIngestor {
     }
 }
 
+/// Settings for [`analyze_video`].
+#[derive(Debug, Clone, Default)]
+pub struct VideoPipelineOptions<'a> {
+    /// Length of the segments a video is described in; one keyframe is taken per segment.
+    pub segment_secs: u64,
+    /// Length of the slices the audio track is transcribed in.
+    pub audio_chunk_secs: u64,
+    /// Optional local transcriber, see [`audio::transcribe_with_command`].
+    pub transcribe_command: Option<&'a str>,
+    /// Where keyframes and their thumbnails are stored; `None` skips storing them.
+    pub asset_dir: Option<&'a Path>,
+}
+
+/// Everything the video pipeline learned about one recording.
+#[derive(Debug, Clone)]
+pub struct VideoAnalysis {
+    pub source: VideoSource,
+    pub overview: Option<String>,
+    pub clips: Vec<VideoClip>,
+}
+
+impl VideoAnalysis {
+    /// The composite searchable content: the overview followed by one timestamped entry
+    /// per segment with its description and transcript.
+    pub fn searchable_text(&self) -> String {
+        let mut lines = vec![self
+            .overview
+            .clone()
+            .unwrap_or_else(|| format!("Video at {}", self.source.path))];
+        for clip in &self.clips {
+            if clip.summary.is_none() && clip.transcript.is_none() {
+                continue;
+            }
+            lines.push(format!(
+                "[{} - {}] {}",
+                audio::format_timestamp(clip.start_time),
+                audio::format_timestamp(clip.end_time),
+                clip.summary.as_deref().unwrap_or("(no description)")
+            ));
+            if let Some(transcript) = &clip.transcript {
+                lines.push(format!("Transcript: {}", transcript));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Take one JPEG keyframe per `segment_secs` of video with FFmpeg.
+pub fn extract_segment_keyframes(path: &Path, segment_secs: u64) -> Result<Vec<(f64, Vec<u8>)>> {
+    let segment_secs = segment_secs.max(1);
+    let temp_dir = TempDir::new()?;
+    let status = Command::new("ffmpeg")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-i")
+        .arg(path)
+        .arg("-vf")
+        .arg(format!("fps=1/{}", segment_secs))
+        .arg(temp_dir.path().join("frame_%05d.jpg"))
+        .status()
+        .context("Failed to execute ffmpeg. Ensure ffmpeg is installed and in PATH.")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("ffmpeg exited with status: {}", status));
+    }
+
+    let mut paths: Vec<_> = fs::read_dir(temp_dir.path())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| Ok(((index as u64 * segment_secs) as f64, fs::read(path)?)))
+        .collect()
+}
+
+/// Extract the audio track as 16 kHz mono MP3. Returns `None` when the video has no
+/// audio stream or FFmpeg cannot read it.
+pub fn extract_audio_track(path: &Path) -> Option<Vec<u8>> {
+    let temp_dir = TempDir::new().ok()?;
+    let output = temp_dir.path().join("audio.mp3");
+    let status = Command::new("ffmpeg")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-i")
+        .arg(path)
+        .arg("-vn")
+        .arg("-ac")
+        .arg("1")
+        .arg("-ar")
+        .arg("16000")
+        .arg(&output)
+        .status()
+        .ok()?;
+    if !status.success() {
+        return None;
+    }
+    fs::read(output).ok().filter(|bytes| !bytes.is_empty())
+}
+
+/// Attach each transcript segment to the clip it starts in.
+pub fn attach_transcript(clips: &mut [VideoClip], segments: &[TranscriptSegment]) {
+    for clip in clips.iter_mut() {
+        let lines: Vec<String> = segments
+            .iter()
+            .filter(|segment| {
+                segment.start_secs >= clip.start_time && segment.start_secs < clip.end_time
+            })
+            .map(|segment| match &segment.speaker {
+                Some(speaker) => format!("{}: {}", speaker, segment.text),
+                None => segment.text.clone(),
+            })
+            .collect();
+        if !lines.is_empty() {
+            clip.transcript = Some(lines.join(" "));
+        }
+    }
+}
+
+/// Run the video pipeline: keyframe extraction, per-segment description, audio-track
+/// transcription and an overall description. Each stage degrades to "nothing learned"
+/// rather than failing, so a video without FFmpeg support still gets its overview.
+pub async fn analyze_video(
+    llm: Option<&dyn LLMClient>,
+    source: &str,
+    bytes: Option<Vec<u8>>,
+    options: &VideoPipelineOptions<'_>,
+) -> VideoAnalysis {
+    let segment_secs = options.segment_secs.max(1);
+    let mut video = VideoSource::new(source.to_string(), 0.0);
+    let mut clips = Vec::new();
+
+    if let Some(bytes) = bytes {
+        let temp_dir = TempDir::new().ok();
+        let input_path = temp_dir.as_ref().map(|dir| dir.path().join("input"));
+        let staged = match &input_path {
+            Some(path) => fs::write(path, &bytes).is_ok(),
+            None => false,
+        };
+        if let (true, Some(path)) = (staged, input_path) {
+            let (keyframes, audio_track) = tokio::task::spawn_blocking(move || {
+                (
+                    extract_segment_keyframes(&path, segment_secs),
+                    extract_audio_track(&path),
+                )
+            })
+            .await
+            .unwrap_or_else(|error| (Err(error.into()), None));
+
+            match keyframes {
+                Ok(keyframes) => {
+                    for (start, frame) in keyframes {
+                        clips.push(
+                            describe_keyframe(llm, &video, start, segment_secs, frame, options)
+                                .await,
+                        );
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!("Keyframe extraction failed for {}: {:?}", source, error)
+                }
+            }
+
+            if let Some(track) = audio_track {
+                let chunk_secs = options.audio_chunk_secs.max(1);
+                let transcript = match audio::split_audio(track, chunk_secs).await {
+                    Ok(chunks) => {
+                        audio::transcribe_chunks(
+                            llm,
+                            options.transcribe_command,
+                            &chunks,
+                            chunk_secs,
+                        )
+                        .await
+                    }
+                    Err(error) => Err(error),
+                };
+                match transcript {
+                    Ok(segments) => {
+                        if clips.is_empty() {
+                            if let Some(end) = segments.last().map(|segment| segment.end_secs) {
+                                clips.push(VideoClip::new(video.id, 0.0, end));
+                            }
+                        }
+                        attach_transcript(&mut clips, &segments);
+                    }
+                    Err(error) => {
+                        tracing::warn!(
+                            "Audio track transcription failed for {}: {:?}",
+                            source,
+                            error
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    let overview = match llm {
+        Some(client) => client
+            .describe_video(source)
+            .await
+            .map(|response| response.data.trim().to_string())
+            .ok()
+            .filter(|overview| !overview.is_empty()),
+        None => None,
+    };
+    video.duration = clips.last().map(|clip| clip.end_time).unwrap_or_default();
+    info!("Analyzed video {} into {} segments", video.id, clips.len());
+    VideoAnalysis {
+        source: video,
+        overview,
+        clips,
+    }
+}
+
+async fn describe_keyframe(
+    llm: Option<&dyn LLMClient>,
+    video: &VideoSource,
+    start: f64,
+    segment_secs: u64,
+    frame: Vec<u8>,
+    options: &VideoPipelineOptions<'_>,
+) -> VideoClip {
+    let mut clip = VideoClip::new(video.id, start, start + segment_secs as f64);
+    if let Some(client) = llm {
+        let data_uri = format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&frame)
+        );
+        clip.summary = client
+            .describe_image(&data_uri)
+            .await
+            .map(|response| response.data.trim().to_string())
+            .ok()
+            .filter(|summary| !summary.is_empty());
+    }
+    if let Some(asset_dir) = options.asset_dir {
+        let image = crate::ingest::image::LoadedImage {
+            bytes: frame,
+            mime_type: "image/jpeg".into(),
+        };
+        match crate::ingest::image::store_image(asset_dir, &image) {
+            Ok(stored) => {
+                clip.keyframe_path = Some(stored.storage_key);
+                clip.thumbnail_path = stored.thumbnail_key;
+            }
+            Err(error) => tracing::warn!("Could not store keyframe: {:?}", error),
+        }
+    }
+    clip
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use image::{DynamicImage, Rgb, RgbImage};
+    use uuid::Uuid;
 
     #[test]
     fn test_new_initializes_without_loaded_model() {
@@ -235,4 +494,56 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_attach_transcript_assigns_segments_by_start_time() {
+        let source_id = Uuid::new_v4();
+        let mut clips = vec![
+            VideoClip::new(source_id, 0.0, 30.0),
+            VideoClip::new(source_id, 30.0, 60.0),
+        ];
+        let segment = |start: f64, speaker: Option<&str>, text: &str| TranscriptSegment {
+            start_secs: start,
+            end_secs: start + 5.0,
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+        };
+        attach_transcript(
+            &mut clips,
+            &[
+                segment(2.0, Some("Alice"), "Welcome."),
+                segment(28.0, None, "Slide one."),
+                segment(45.0, Some("Bob"), "Questions?"),
+                segment(75.0, None, "Past the end."),
+            ],
+        );
+        assert_eq!(
+            clips[0].transcript.as_deref(),
+            Some("Alice: Welcome. Slide one.")
+        );
+        assert_eq!(clips[1].transcript.as_deref(), Some("Bob: Questions?"));
+    }
+
+    #[test]
+    fn test_searchable_text_lists_segments_with_timestamps() {
+        let mut analysis = VideoAnalysis {
+            source: VideoSource::new("https://example.com/demo.mp4".into(), 60.0),
+            overview: None,
+            clips: Vec::new(),
+        };
+        assert_eq!(
+            analysis.searchable_text(),
+            "Video at https://example.com/demo.mp4"
+        );
+
+        let mut clip = VideoClip::new(analysis.source.id, 30.0, 60.0);
+        clip.summary = Some("A roadmap slide".into());
+        clip.transcript = Some("Bob: Questions?".into());
+        analysis.clips = vec![VideoClip::new(analysis.source.id, 0.0, 30.0), clip];
+        analysis.overview = Some("A product demo".into());
+        assert_eq!(
+            analysis.searchable_text(),
+            "A product demo\n[00:00:30 - 00:01:00] A roadmap slide\nTranscript: Bob: Questions?"
+        );
+    }
 }
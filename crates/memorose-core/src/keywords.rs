@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+
+/// Function words that split RAKE candidate phrases. Kept deliberately small: the goal is to
+/// break sentences into content phrases, not to model a full stopword corpus.
+const STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "also",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "itself",
+    "just",
+    "me",
+    "message",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "user",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+const MAX_PHRASE_WORDS: usize = 3;
+
+fn is_cjk_char(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xF900..=0xFAFF
+            | 0x3040..=0x30FF
+            | 0xAC00..=0xD7AF
+    )
+}
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+/// Split text into RAKE candidate phrases. Punctuation, stopwords, and script changes all act
+/// as phrase delimiters; CJK runs become their own candidates since they carry no whitespace.
+fn candidate_phrases(text: &str) -> Vec<Vec<String>> {
+    fn flush_word(words: &mut Vec<String>, phrases: &mut Vec<Vec<String>>, word: &mut String) {
+        if word.is_empty() {
+            return;
+        }
+        let lowered = std::mem::take(word).to_lowercase();
+        let is_numeric = lowered.chars().all(|ch| ch.is_numeric());
+        if is_stopword(&lowered) || is_numeric || lowered.chars().count() < 2 {
+            flush_phrase(words, phrases);
+        } else {
+            words.push(lowered);
+        }
+    }
+
+    fn flush_phrase(words: &mut Vec<String>, phrases: &mut Vec<Vec<String>>) {
+        if words.is_empty() {
+            return;
+        }
+        let phrase = std::mem::take(words);
+        if phrase.len() <= MAX_PHRASE_WORDS {
+            phrases.push(phrase);
+        } else {
+            phrases.extend(phrase.into_iter().map(|word| vec![word]));
+        }
+    }
+
+    fn flush_cjk(cjk: &mut String, phrases: &mut Vec<Vec<String>>) {
+        let run = std::mem::take(cjk);
+        let chars = run.chars().collect::<Vec<_>>();
+        match chars.len() {
+            0 | 1 => {}
+            2..=6 => phrases.push(vec![run]),
+            _ => phrases.extend(
+                chars
+                    .windows(2)
+                    .map(|window| vec![window.iter().collect::<String>()]),
+            ),
+        }
+    }
+
+    let mut phrases = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut cjk = String::new();
+
+    for ch in text.chars() {
+        if is_cjk_char(ch) {
+            flush_word(&mut words, &mut phrases, &mut word);
+            flush_phrase(&mut words, &mut phrases);
+            cjk.push(ch);
+        } else if ch.is_alphanumeric() || ch == '-' || ch == '\'' {
+            flush_cjk(&mut cjk, &mut phrases);
+            word.push(ch);
+        } else if ch.is_whitespace() {
+            flush_cjk(&mut cjk, &mut phrases);
+            flush_word(&mut words, &mut phrases, &mut word);
+        } else {
+            flush_cjk(&mut cjk, &mut phrases);
+            flush_word(&mut words, &mut phrases, &mut word);
+            flush_phrase(&mut words, &mut phrases);
+        }
+    }
+    flush_cjk(&mut cjk, &mut phrases);
+    flush_word(&mut words, &mut phrases, &mut word);
+    flush_phrase(&mut words, &mut phrases);
+
+    phrases
+        .into_iter()
+        .map(|phrase| {
+            phrase
+                .into_iter()
+                .map(|word| word.trim_matches(|c| c == '-' || c == '\'').to_string())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|phrase| !phrase.is_empty())
+        .collect()
+}
+
+/// Extract up to `limit` keywords using RAKE (Rapid Automatic Keyword Extraction).
+///
+/// Used as the deterministic fallback when no LLM is configured or the LLM call fails, so every
+/// consolidated memory still carries searchable keywords. Words are scored by degree/frequency
+/// and phrases by the sum of their word scores; ties keep first-occurrence order.
+pub(crate) fn extract_keywords(text: &str, limit: usize) -> Vec<String> {
+    if limit == 0 {
+        return Vec::new();
+    }
+
+    let phrases = candidate_phrases(text);
+    if phrases.is_empty() {
+        return Vec::new();
+    }
+
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word.as_str()).or_insert(0) += 1;
+            *degree.entry(word.as_str()).or_insert(0) += phrase.len();
+        }
+    }
+
+    let mut scored: Vec<(String, f32, usize)> = Vec::new();
+    let mut seen = HashSet::new();
+    for phrase in &phrases {
+        let joined = phrase.join(" ");
+        if !seen.insert(joined.clone()) {
+            continue;
+        }
+        let score = phrase
+            .iter()
+            .map(|word| degree[word.as_str()] as f32 / frequency[word.as_str()] as f32)
+            .sum::<f32>();
+        scored.push((joined, score, scored.len()));
+    }
+
+    scored.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.2.cmp(&b.2))
+    });
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(phrase, _, _)| phrase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopwords_are_sorted_for_binary_search() {
+        let mut sorted = STOPWORDS.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, STOPWORDS);
+    }
+
+    #[test]
+    fn test_extract_keywords_prefers_multi_word_phrases() {
+        let keywords = extract_keywords(
+            "I moved to Berlin last spring and started learning Rust programming at a startup.",
+            4,
+        );
+        assert_eq!(keywords.len(), 4);
+        assert_eq!(keywords[0], "berlin last spring");
+        assert!(keywords.iter().all(|keyword| !keyword.contains(" and ")));
+        assert!(!keywords.contains(&"i".to_string()));
+    }
+
+    #[test]
+    fn test_extract_keywords_handles_cjk_and_limits() {
+        let keywords = extract_keywords("我住在北京，喜欢喝咖啡。", 8);
+        assert!(keywords.contains(&"我住在北京".to_string()));
+        assert!(keywords.contains(&"喜欢喝咖啡".to_string()));
+
+        assert!(extract_keywords("the and of", 5).is_empty());
+        assert!(extract_keywords("Rust memory database", 0).is_empty());
+        assert_eq!(extract_keywords("alpha, beta, gamma, delta", 2).len(), 2);
+    }
+}
@@ -1,18 +1,38 @@
+pub mod answer;
 pub mod arbitrator;
 pub mod community;
+pub mod consolidation_pool;
+pub mod crypto;
+pub mod emotion;
 pub mod engine;
+pub mod eval;
 pub(crate) mod fact_extraction;
 pub mod graph;
+pub(crate) mod importance;
 pub mod ingest;
+pub(crate) mod keywords;
 pub mod llm;
+pub mod migration;
+pub mod moderation;
 pub mod raft;
+pub mod replay;
+pub mod replication;
 pub mod reranker;
+pub(crate) mod run_report;
+pub(crate) mod segmentation;
+pub mod self_eval;
 pub mod storage;
+pub mod summary_tier;
+pub mod temporal;
 pub mod worker; // 新增：图查询优化模块
 
 pub use arbitrator::Arbitrator;
 pub use community::CommunityDetector;
-pub use engine::{MemoroseEngine, OrganizationKnowledgeSearchHit, SharedSearchHit};
+pub use engine::{
+    IngestAdmission, IntegrityCheckOptions, IntegrityReport, MemoroseEngine,
+    OrganizationKnowledgeSearchHit, OrphanGcOptions, OrphanGcReport, RecoveryReport,
+    RetrievalStageTimings, SharedSearchHit,
+};
 pub use llm::{GeminiClient, LLMClient};
 pub use reranker::Reranker;
 pub use worker::BackgroundWorker;
@@ -0,0 +1,147 @@
+use super::{
+    CompressionContext, CompressionOutput, EmbedInput, LLMClient, LLMResponse, OutputSchema,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Wraps a provider client and rejects embeddings whose length differs from the
+/// configured `llm.embedding_dim`.
+///
+/// The vector store pads or truncates vectors to its table dimension, so a provider
+/// that disagrees with the config would otherwise fill the index with vectors that
+/// search as noise. Failing the embed call keeps them from ever being stored.
+pub struct DimensionCheckedClient {
+    inner: Arc<dyn LLMClient>,
+    expected: usize,
+    /// Dimension of the first embedding the provider returned; 0 until then.
+    observed: AtomicUsize,
+}
+
+impl DimensionCheckedClient {
+    pub fn new(inner: Arc<dyn LLMClient>, expected: usize) -> Self {
+        Self {
+            inner,
+            expected,
+            observed: AtomicUsize::new(0),
+        }
+    }
+
+    fn check(&self, vector: &[f32]) -> Result<()> {
+        let actual = vector.len();
+        if self
+            .observed
+            .compare_exchange(0, actual, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            if actual == self.expected {
+                tracing::info!("Embedding provider returns {} dimensions", actual);
+            } else {
+                tracing::error!(
+                    "Embedding provider returns {} dimensions but llm.embedding_dim is {}; \
+                     embeddings will be rejected until the config matches the model",
+                    actual,
+                    self.expected
+                );
+            }
+        }
+        if actual != self.expected {
+            return Err(anyhow!(
+                "embedding provider returned {} dimensions but llm.embedding_dim is {}; \
+                 set llm.embedding_dim (or llm.embedding_output_dim) to match the embedding model",
+                actual,
+                self.expected
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_all(&self, vectors: &[Vec<f32>]) -> Result<()> {
+        vectors.iter().try_for_each(|vector| self.check(vector))
+    }
+}
+
+#[async_trait]
+impl LLMClient for DimensionCheckedClient {
+    async fn embed(&self, text: &str) -> Result<LLMResponse<Vec<f32>>> {
+        let res = self.inner.embed(text).await?;
+        self.check(&res.data)?;
+        Ok(res)
+    }
+
+    async fn embed_batch(&self, texts: Vec<String>) -> Result<LLMResponse<Vec<Vec<f32>>>> {
+        let res = self.inner.embed_batch(texts).await?;
+        self.check_all(&res.data)?;
+        Ok(res)
+    }
+
+    async fn embed_content(&self, input: EmbedInput) -> Result<LLMResponse<Vec<f32>>> {
+        let res = self.inner.embed_content(input).await?;
+        self.check(&res.data)?;
+        Ok(res)
+    }
+
+    async fn embed_content_batch(
+        &self,
+        inputs: Vec<EmbedInput>,
+    ) -> Result<LLMResponse<Vec<Vec<f32>>>> {
+        let res = self.inner.embed_content_batch(inputs).await?;
+        self.check_all(&res.data)?;
+        Ok(res)
+    }
+
+    fn observed_embedding_dim(&self) -> Option<usize> {
+        match self.observed.load(Ordering::Relaxed) {
+            0 => None,
+            dim => Some(dim),
+        }
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<LLMResponse<String>> {
+        self.inner.generate(prompt).await
+    }
+
+    async fn generate_json(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        schema: &OutputSchema,
+    ) -> Result<LLMResponse<String>> {
+        self.inner
+            .generate_json(system_prompt, prompt, schema)
+            .await
+    }
+
+    fn structured_output_retries(&self) -> u32 {
+        self.inner.structured_output_retries()
+    }
+
+    async fn compress(
+        &self,
+        text: &str,
+        context: &CompressionContext,
+    ) -> Result<LLMResponse<CompressionOutput>> {
+        self.inner.compress(text, context).await
+    }
+
+    async fn summarize_group(&self, texts: Vec<String>) -> Result<LLMResponse<String>> {
+        self.inner.summarize_group(texts).await
+    }
+
+    async fn describe_image(&self, image_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.inner.describe_image(image_url_or_base64).await
+    }
+
+    async fn extract_image_text(&self, image_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.inner.extract_image_text(image_url_or_base64).await
+    }
+
+    async fn transcribe(&self, audio_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.inner.transcribe(audio_url_or_base64).await
+    }
+
+    async fn describe_video(&self, video_url: &str) -> Result<LLMResponse<String>> {
+        self.inner.describe_video(video_url).await
+    }
+}
@@ -1,4 +1,9 @@
-use super::{EmbedInput, EmbedPart, LLMClient, LANGUAGE_PRESERVATION_INSTRUCTION};
+use super::rate_limit;
+use super::structured::{self, OutputSchema};
+use super::{
+    EmbedInput, EmbedPart, LLMClient, IMAGE_TEXT_EXTRACTION_PROMPT,
+    LANGUAGE_PRESERVATION_INSTRUCTION,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
@@ -14,6 +19,7 @@ pub struct GeminiClient {
     embedding_model: String,
     output_dimensionality: Option<i32>,
     task_type: Option<String>,
+    structured_output_retries: u32,
 }
 
 impl GeminiClient {
@@ -57,8 +63,16 @@ impl GeminiClient {
             embedding_model,
             output_dimensionality,
             task_type,
+            structured_output_retries:
+                memorose_common::config::DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES,
         }
     }
+
+    /// Extra attempts when a JSON reply fails schema validation.
+    pub fn with_structured_output_retries(mut self, retries: u32) -> Self {
+        self.structured_output_retries = retries;
+        self
+    }
 }
 
 // ============== Generate API Structures ==============
@@ -68,6 +82,14 @@ struct GenerateRequest {
     contents: Vec<Content>,
     #[serde(rename = "systemInstruction", skip_serializing_if = "Option::is_none")]
     system_instruction: Option<Content>,
+    #[serde(rename = "generationConfig", skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GenerationConfig>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    response_mime_type: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -276,20 +298,36 @@ fn parse_generate_response(body: &str) -> Result<super::LLMResponse<String>> {
     })
 }
 
-fn trim_json_fence(text: &str) -> &str {
-    text.trim()
-        .trim_start_matches("```json")
-        .trim_start_matches("```")
-        .trim_end_matches("```")
-        .trim()
-}
-
 #[async_trait]
 impl LLMClient for GeminiClient {
     async fn generate(&self, prompt: &str) -> Result<super::LLMResponse<String>> {
         self.call_generate(None, prompt).await
     }
 
+    async fn generate_json(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        _schema: &OutputSchema,
+    ) -> Result<super::LLMResponse<String>> {
+        // JSON mode only: `responseSchema` takes an OpenAPI subset without type unions,
+        // so the schema is checked on our side.
+        self.call_generate_with_config(
+            Some(system_prompt),
+            vec![Part::Text {
+                text: prompt.to_string(),
+            }],
+            Some(GenerationConfig {
+                response_mime_type: "application/json".to_string(),
+            }),
+        )
+        .await
+    }
+
+    fn structured_output_retries(&self) -> u32 {
+        self.structured_output_retries
+    }
+
     async fn embed(&self, text: &str) -> Result<super::LLMResponse<Vec<f32>>> {
         let clean_model = self.embedding_model.trim_start_matches("models/");
         let model_name = format!("models/{}", clean_model);
@@ -314,10 +352,16 @@ impl LLMClient for GeminiClient {
         let response = self.client.post(&url).json(&request).send().await?;
 
         let status = response.status();
+        let retry_after = rate_limit::retry_after(response.headers());
         let body = response.text().await?;
 
         if !status.is_success() {
-            return Err(anyhow!("Gemini Embedding API error ({}): {}", status, body));
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("Gemini Embedding API error ({}): {}", status, body),
+            ));
         }
 
         parse_embed_response(&body, self.output_dimensionality)
@@ -368,13 +412,15 @@ impl LLMClient for GeminiClient {
             let response = self.client.post(&url).json(&batch_request).send().await?;
 
             let status = response.status();
+            let retry_after = rate_limit::retry_after(response.headers());
             let body = response.text().await?;
 
             if !status.is_success() {
-                return Err(anyhow!(
-                    "Gemini Batch Embedding API error ({}): {}",
+                return Err(rate_limit::provider_error(
                     status,
-                    body
+                    retry_after,
+                    &body,
+                    format!("Gemini Batch Embedding API error ({}): {}", status, body),
                 ));
             }
 
@@ -392,9 +438,9 @@ impl LLMClient for GeminiClient {
     async fn compress(
         &self,
         text: &str,
-        is_agent: bool,
+        context: &super::CompressionContext,
     ) -> Result<super::LLMResponse<super::CompressionOutput>> {
-        let system_prompt = if is_agent {
+        let base_prompt = if context.is_agent {
             // PROCEDURAL (Agent) PROMPT
             format!(
                 "You are an expert at extracting and summarizing Agent execution trajectories and experiences. \
@@ -431,23 +477,17 @@ impl LLMClient for GeminiClient {
                 LANGUAGE_PRESERVATION_INSTRUCTION
             )
         };
-
-        let response = self.call_generate(Some(&system_prompt), text).await?;
-
-        let clean_json = trim_json_fence(&response.data);
-
-        let parsed: super::CompressionOutput = serde_json::from_str(clean_json).map_err(|e| {
-            anyhow!(
-                "Failed to parse compression JSON: {} - body: {}",
-                e,
-                clean_json
-            )
-        })?;
-
-        Ok(super::LLMResponse {
-            data: parsed,
-            usage: response.usage,
-        })
+        let system_prompt = context.system_prompt(&base_prompt);
+
+        structured::generate_structured(
+            self,
+            "compress",
+            &system_prompt,
+            text,
+            &OutputSchema::compression(),
+            self.structured_output_retries,
+        )
+        .await
     }
 
     async fn summarize_group(&self, texts: Vec<String>) -> Result<super::LLMResponse<String>> {
@@ -460,38 +500,21 @@ impl LLMClient for GeminiClient {
         &self,
         image_url_or_base64: &str,
     ) -> Result<super::LLMResponse<String>> {
-        let (mime_type, data) = if image_url_or_base64.starts_with("http") {
-            let resp = self.client.get(image_url_or_base64).send().await?;
-            let headers = resp.headers();
-            let mime = headers
-                .get("content-type")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("image/jpeg")
-                .to_string();
-            let bytes = resp.bytes().await?;
-            (mime, general_purpose::STANDARD.encode(&bytes))
-        } else {
-            // Assume it's base64, default to jpeg if unknown
-            ("image/jpeg".to_string(), image_url_or_base64.to_string())
-        };
-
-        let prompt =
-            "Describe this image in detail, focusing on objects, actions, and text visible.";
-
-        self.call_generate_parts(
-            None,
-            vec![
-                Part::Text {
-                    text: prompt.to_string(),
-                },
-                Part::Inline {
-                    inline_data: InlineData { mime_type, data },
-                },
-            ],
+        self.describe_inline_image(
+            image_url_or_base64,
+            "Describe this image in detail, focusing on objects, actions, and text visible.",
         )
         .await
     }
 
+    async fn extract_image_text(
+        &self,
+        image_url_or_base64: &str,
+    ) -> Result<super::LLMResponse<String>> {
+        self.describe_inline_image(image_url_or_base64, IMAGE_TEXT_EXTRACTION_PROMPT)
+            .await
+    }
+
     async fn transcribe(&self, audio_url_or_base64: &str) -> Result<super::LLMResponse<String>> {
         let (mime_type, data) = if audio_url_or_base64.starts_with("http") {
             let resp = self.client.get(audio_url_or_base64).send().await?;
@@ -575,10 +598,16 @@ impl LLMClient for GeminiClient {
 
         let response = self.client.post(&url).json(&request).send().await?;
         let status = response.status();
+        let retry_after = rate_limit::retry_after(response.headers());
         let body = response.text().await?;
 
         if !status.is_success() {
-            return Err(anyhow!("Gemini Embedding API error ({}): {}", status, body));
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("Gemini Embedding API error ({}): {}", status, body),
+            ));
         }
 
         parse_embed_response(&body, self.output_dimensionality)
@@ -623,13 +652,15 @@ impl LLMClient for GeminiClient {
 
             let response = self.client.post(&url).json(&batch_request).send().await?;
             let status = response.status();
+            let retry_after = rate_limit::retry_after(response.headers());
             let body = response.text().await?;
 
             if !status.is_success() {
-                return Err(anyhow!(
-                    "Gemini Batch Embedding API error ({}): {}",
+                return Err(rate_limit::provider_error(
                     status,
-                    body
+                    retry_after,
+                    &body,
+                    format!("Gemini Batch Embedding API error ({}): {}", status, body),
                 ));
             }
 
@@ -646,6 +677,40 @@ impl LLMClient for GeminiClient {
 }
 
 impl GeminiClient {
+    async fn describe_inline_image(
+        &self,
+        image_url_or_base64: &str,
+        prompt: &str,
+    ) -> Result<super::LLMResponse<String>> {
+        let (mime_type, data) = if image_url_or_base64.starts_with("http") {
+            let resp = self.client.get(image_url_or_base64).send().await?;
+            let headers = resp.headers();
+            let mime = headers
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("image/jpeg")
+                .to_string();
+            let bytes = resp.bytes().await?;
+            (mime, general_purpose::STANDARD.encode(&bytes))
+        } else {
+            // Assume it's base64, default to jpeg if unknown
+            ("image/jpeg".to_string(), image_url_or_base64.to_string())
+        };
+
+        self.call_generate_parts(
+            None,
+            vec![
+                Part::Text {
+                    text: prompt.to_string(),
+                },
+                Part::Inline {
+                    inline_data: InlineData { mime_type, data },
+                },
+            ],
+        )
+        .await
+    }
+
     async fn call_generate(
         &self,
         system_prompt: Option<&str>,
@@ -664,6 +729,16 @@ impl GeminiClient {
         &self,
         system_prompt: Option<&str>,
         parts: Vec<Part>,
+    ) -> Result<super::LLMResponse<String>> {
+        self.call_generate_with_config(system_prompt, parts, None)
+            .await
+    }
+
+    async fn call_generate_with_config(
+        &self,
+        system_prompt: Option<&str>,
+        parts: Vec<Part>,
+        generation_config: Option<GenerationConfig>,
     ) -> Result<super::LLMResponse<String>> {
         let clean_model = self.model.trim_start_matches("models/");
         let url = format!(
@@ -684,6 +759,7 @@ impl GeminiClient {
                     text: s.to_string(),
                 }],
             }),
+            generation_config,
         };
 
         tracing::debug!(
@@ -700,6 +776,7 @@ impl GeminiClient {
             .await?;
 
         let status = response.status();
+        let retry_after = rate_limit::retry_after(response.headers());
         let body = response.text().await?;
 
         if !status.is_success() {
@@ -708,7 +785,12 @@ impl GeminiClient {
                 status,
                 self.api_key.is_empty()
             );
-            return Err(anyhow!("Gemini API error ({}): {}", status, body));
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("Gemini API error ({}): {}", status, body),
+            ));
         }
 
         parse_generate_response(&body)
@@ -2,9 +2,13 @@
 mod tests {
     use super::super::{
         embed_input_to_parts, map_usage_metadata, parse_batch_embed_response, parse_embed_response,
-        parse_generate_response, trim_json_fence, GeminiClient, GeminiUsageMetadata, Part,
+        parse_generate_response, GeminiClient, GeminiUsageMetadata, Part,
     };
-    use crate::llm::{EmbedInput, EmbedPart, LLMClient};
+    use crate::llm::structured::strip_code_fence;
+    use crate::llm::{
+        CompressionContext, ConversationParticipant, EmbedInput, EmbedPart, LLMClient,
+    };
+    use memorose_common::EventRole;
     use serde_json::json;
     use wiremock::matchers::{method, path, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -82,6 +86,75 @@ mod tests {
         assert!(err_msg.contains("Gemini API error (403 Forbidden)"));
     }
 
+    #[tokio::test]
+    async fn test_compress_requests_json_mode_and_retries_invalid_output() {
+        let mock_server = MockServer::start().await;
+        let reply = |text: &str| {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{ "content": { "parts": [{ "text": text }] } }]
+            }))
+        };
+
+        Mock::given(method("POST"))
+            .respond_with(reply("I live in Paris"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(reply(r#"{"content":"Lives in Paris","valid_at":null}"#))
+            .mount(&mock_server)
+            .await;
+
+        let client = GeminiClient::with_base_url(
+            "test-key".to_string(),
+            TEST_MODEL.to_string(),
+            TEST_EMBEDDING_MODEL.to_string(),
+            mock_server.uri(),
+            None,
+            None,
+        );
+        let output = client
+            .compress("I live in Paris", &CompressionContext::agent(false))
+            .await
+            .unwrap();
+        assert_eq!(output.data.content, "Lives in Paris");
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let retry: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(
+            retry["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert!(retry["contents"][0]["parts"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Your previous reply was rejected: reply is not valid JSON"));
+
+        // Every attempt invalid: the call fails instead of returning raw text
+        let strict = GeminiClient::with_base_url(
+            "test-key".to_string(),
+            TEST_MODEL.to_string(),
+            TEST_EMBEDDING_MODEL.to_string(),
+            mock_server.uri(),
+            None,
+            None,
+        )
+        .with_structured_output_retries(0);
+        mock_server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(reply(r#"{"summary":"wrong shape"}"#))
+            .mount(&mock_server)
+            .await;
+        let err = strict
+            .compress("I live in Paris", &CompressionContext::agent(false))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missing required field `content`"));
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_compress_prompts_preserve_input_language() {
         let mock_server = MockServer::start().await;
@@ -114,11 +187,17 @@ mod tests {
         );
 
         client
-            .compress("用户主要使用中文，不要翻译成英文", false)
+            .compress(
+                "用户主要使用中文，不要翻译成英文",
+                &CompressionContext::agent(false),
+            )
             .await
             .unwrap();
         client
-            .compress("Agent fixed crates/memorose-core/src/worker.rs", true)
+            .compress(
+                "Agent fixed crates/memorose-core/src/worker.rs",
+                &CompressionContext::agent(true),
+            )
             .await
             .unwrap();
 
@@ -137,6 +216,90 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_compress_prompt_attributes_multiple_speakers() {
+        let mock_server = MockServer::start().await;
+
+        let expected_response = json!({
+            "candidates": [{
+                "content": {
+                    "parts": [{ "text": r#"{"content":"summary","valid_at":null}"# }]
+                }
+            }]
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!(
+                "/v1beta/models/{}:generateContent",
+                TEST_MODEL
+            )))
+            .and(query_param("key", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(expected_response))
+            .mount(&mock_server)
+            .await;
+
+        let client = GeminiClient::with_base_url(
+            "test-key".to_string(),
+            TEST_MODEL.to_string(),
+            TEST_EMBEDDING_MODEL.to_string(),
+            mock_server.uri(),
+            None,
+            None,
+        );
+
+        let single = CompressionContext {
+            is_agent: false,
+            participants: vec![ConversationParticipant {
+                name: Some("Alice".into()),
+                role: Some(EventRole::User),
+            }],
+            ..CompressionContext::default()
+        };
+        let multi = CompressionContext {
+            is_agent: false,
+            participants: vec![
+                ConversationParticipant {
+                    name: Some("Alice".into()),
+                    role: Some(EventRole::User),
+                },
+                ConversationParticipant {
+                    name: Some("Bob".into()),
+                    role: Some(EventRole::User),
+                },
+            ],
+            ..CompressionContext::default()
+        };
+        client
+            .compress("Message 1: I live in Paris", &single)
+            .await
+            .unwrap();
+        client
+            .compress(
+                "Message 1 (Alice, user): I live in Paris\nMessage 2 (Bob, user): I live in Rome",
+                &multi,
+            )
+            .await
+            .unwrap();
+
+        let requests = mock_server
+            .received_requests()
+            .await
+            .expect("request recording should be enabled");
+        let prompts = requests
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["systemInstruction"]["parts"][0]["text"]
+                    .as_str()
+                    .expect("system prompt should be present")
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert!(!prompts[0].contains("MULTIPLE SPEAKERS"));
+        assert!(prompts[1].contains("MULTIPLE SPEAKERS"));
+        assert!(prompts[1].contains("Alice, user; Bob, user"));
+    }
+
     #[tokio::test]
     async fn test_embed_success() {
         let mock_server = MockServer::start().await;
@@ -436,12 +599,12 @@ mod tests {
     }
 
     #[test]
-    fn test_trim_json_fence_removes_markdown_wrappers() {
+    fn test_strip_code_fence_removes_markdown_wrappers() {
         assert_eq!(
-            trim_json_fence("```json\n{\"content\":\"x\"}\n```"),
+            strip_code_fence("```json\n{\"content\":\"x\"}\n```"),
             "{\"content\":\"x\"}"
         );
-        assert_eq!(trim_json_fence("  plain  "), "plain");
+        assert_eq!(strip_code_fence("  plain  "), "plain");
     }
 
     #[test]
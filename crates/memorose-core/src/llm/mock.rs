@@ -0,0 +1,88 @@
+//! Deterministic stand-in for a real provider, for tests that exercise the engine and
+//! worker without API keys or network access. Enabled with the `test-util` feature.
+
+use super::{CompressionContext, CompressionOutput, LLMClient, LLMResponse};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+/// Length of the vectors [`MockLLM::embed`] returns.
+pub const MOCK_EMBEDDING_DIM: usize = 384;
+
+/// Echoes its input back: compression returns the text unchanged, embeddings are zero
+/// vectors of [`MOCK_EMBEDDING_DIM`], and media descriptions are fixed strings.
+#[derive(Debug, Clone, Default)]
+pub struct MockLLM {
+    /// Make `compress` fail, to exercise error handling.
+    pub fail_compress: bool,
+    /// What `generate` answers; empty when unset.
+    pub generate_response: Option<String>,
+    /// Every prompt `generate` was called with, oldest first. Clones share the log.
+    pub generated_prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockLLM {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LLMClient for MockLLM {
+    async fn generate(&self, prompt: &str) -> Result<LLMResponse<String>> {
+        self.generated_prompts
+            .lock()
+            .unwrap()
+            .push(prompt.to_string());
+        Ok(LLMResponse {
+            data: self.generate_response.clone().unwrap_or_default(),
+            usage: Default::default(),
+        })
+    }
+    async fn embed(&self, _text: &str) -> Result<LLMResponse<Vec<f32>>> {
+        Ok(LLMResponse {
+            data: vec![0.0; MOCK_EMBEDDING_DIM],
+            usage: Default::default(),
+        })
+    }
+    async fn compress(
+        &self,
+        text: &str,
+        _context: &CompressionContext,
+    ) -> Result<LLMResponse<CompressionOutput>> {
+        if self.fail_compress {
+            return Err(anyhow::anyhow!("LLM Error"));
+        }
+        Ok(LLMResponse {
+            data: CompressionOutput {
+                content: text.to_string(),
+                valid_at: None,
+            },
+            usage: Default::default(),
+        })
+    }
+    async fn summarize_group(&self, _texts: Vec<String>) -> Result<LLMResponse<String>> {
+        Ok(LLMResponse {
+            data: "summary".into(),
+            usage: Default::default(),
+        })
+    }
+    async fn describe_image(&self, _url: &str) -> Result<LLMResponse<String>> {
+        Ok(LLMResponse {
+            data: "image".into(),
+            usage: Default::default(),
+        })
+    }
+    async fn describe_video(&self, _url: &str) -> Result<LLMResponse<String>> {
+        Ok(LLMResponse {
+            data: "video".into(),
+            usage: Default::default(),
+        })
+    }
+    async fn transcribe(&self, _url: &str) -> Result<LLMResponse<String>> {
+        Ok(LLMResponse {
+            data: "audio".into(),
+            usage: Default::default(),
+        })
+    }
+}
@@ -1,18 +1,32 @@
+pub mod dimension_check;
 pub mod gemini;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
 pub mod openai;
+pub mod rate_limit;
+pub mod structured;
 
+pub use dimension_check::DimensionCheckedClient;
 pub use gemini::GeminiClient;
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::{MockLLM, MOCK_EMBEDDING_DIM};
 pub use openai::OpenAIClient;
+pub use rate_limit::{RateLimitSnapshot, RateLimitedClient, Throttled};
+pub use structured::{OutputSchema, StructuredOutputMetricSnapshot};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use memorose_common::config::{LLMConfig, LLMProvider};
+use memorose_common::EventRole;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 pub const LANGUAGE_PRESERVATION_INSTRUCTION: &str =
     "LANGUAGE PRESERVATION: Respond in the dominant language of the input memories, events, or tasks. Do not translate unless the user explicitly requested translation. Preserve code, identifiers, proper nouns, file names, API names, and technical terms as written.";
 
+pub const IMAGE_TEXT_EXTRACTION_PROMPT: &str =
+    "Transcribe all text visible in this image verbatim, preserving line breaks. Reply with only the transcribed text, or an empty reply if the image contains no text.";
+
 /// Represents embedding input that can be text or multimodal content.
 #[derive(Debug, Clone)]
 pub enum EmbedInput {
@@ -55,28 +69,48 @@ impl EmbedInput {
 }
 
 pub fn create_llm_client(config: &LLMConfig) -> Option<Arc<dyn LLMClient>> {
+    let client: Arc<dyn LLMClient> = Arc::new(RateLimitedClient::new(
+        create_provider_client(config)?,
+        rate_limit::shared_limiter(config),
+    ));
+    if config.embedding_dim <= 0 {
+        return Some(client);
+    }
+    Some(Arc::new(DimensionCheckedClient::new(
+        client,
+        config.embedding_dim as usize,
+    )))
+}
+
+fn create_provider_client(config: &LLMConfig) -> Option<Arc<dyn LLMClient>> {
     match config.provider {
         LLMProvider::Gemini => {
             let api_key = config.google_api_key.clone()?;
-            Some(Arc::new(GeminiClient::with_base_url(
-                api_key,
-                config.model.clone(),
-                config.embedding_model.clone(),
-                config
-                    .get_base_url()
-                    .unwrap_or_else(|| "https://generativelanguage.googleapis.com".to_string()),
-                config.embedding_output_dim,
-                config.embedding_task_type.clone(),
-            )))
+            Some(Arc::new(
+                GeminiClient::with_base_url(
+                    api_key,
+                    config.model.clone(),
+                    config.embedding_model.clone(),
+                    config
+                        .get_base_url()
+                        .unwrap_or_else(|| "https://generativelanguage.googleapis.com".to_string()),
+                    config.embedding_output_dim,
+                    config.embedding_task_type.clone(),
+                )
+                .with_structured_output_retries(config.structured_output_retries),
+            ))
         }
         LLMProvider::OpenAI => {
             let api_key = config.openai_api_key.clone()?;
-            Some(Arc::new(OpenAIClient::new(
-                api_key,
-                config.model.clone(),
-                config.embedding_model.clone(),
-                config.get_base_url(),
-            )))
+            Some(Arc::new(
+                OpenAIClient::new(
+                    api_key,
+                    config.model.clone(),
+                    config.embedding_model.clone(),
+                    config.get_base_url(),
+                )
+                .with_structured_output_retries(config.structured_output_retries),
+            ))
         }
     }
 }
@@ -87,6 +121,90 @@ pub struct CompressionOutput {
     pub valid_at: Option<String>,
 }
 
+/// A speaker in the conversation being compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationParticipant {
+    pub name: Option<String>,
+    pub role: Option<EventRole>,
+}
+
+impl ConversationParticipant {
+    /// Label used in front of this participant's messages, e.g. `Alice, user`.
+    pub fn label(&self) -> Option<String> {
+        match (&self.name, self.role) {
+            (Some(name), Some(role)) => Some(format!("{}, {}", name, role.as_str())),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some(role)) => Some(role.as_str().to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// What `compress` knows about the events behind its text.
+#[derive(Debug, Clone, Default)]
+pub struct CompressionContext {
+    /// The events are an agent trajectory rather than the user's side of a conversation
+    pub is_agent: bool,
+    /// Distinct speakers in order of first appearance; empty when events carry no role
+    /// or speaker name
+    pub participants: Vec<ConversationParticipant>,
+    /// App the events belong to
+    pub app_id: Option<String>,
+    /// Configured replacement for the built-in system prompt
+    pub template: Option<String>,
+}
+
+impl CompressionContext {
+    pub fn agent(is_agent: bool) -> Self {
+        Self {
+            is_agent,
+            ..Self::default()
+        }
+    }
+
+    /// More than one speaker, so messages are labelled and facts must be attributed.
+    pub fn is_multi_party(&self) -> bool {
+        self.participants.len() > 1
+    }
+
+    /// Extra prompt rule for multi-party conversations; `None` for a single speaker.
+    pub fn speaker_instruction(&self) -> Option<String> {
+        if !self.is_multi_party() {
+            return None;
+        }
+        let speakers = self
+            .participants
+            .iter()
+            .filter_map(ConversationParticipant::label)
+            .collect::<Vec<_>>()
+            .join("; ");
+        Some(format!(
+            "MULTIPLE SPEAKERS: Messages are labelled with their speaker ({}). Attribute every fact, preference and decision to the speaker who stated it, by name when one is given. Never merge different people's facts into one profile, and do not treat one participant's statements as another's.",
+            speakers
+        ))
+    }
+
+    /// The configured template rendered for these events, or else the built-in prompt
+    /// with the speaker rule, if any, appended.
+    pub fn system_prompt(&self, base: &str) -> String {
+        let speakers = self.speaker_instruction();
+        match &self.template {
+            Some(template) => memorose_common::prompt_template::render(
+                template,
+                &[
+                    ("app_id", self.app_id.as_deref().unwrap_or_default()),
+                    ("language_instruction", LANGUAGE_PRESERVATION_INSTRUCTION),
+                    ("speakers", speakers.as_deref().unwrap_or_default()),
+                ],
+            ),
+            None => match speakers {
+                Some(instruction) => format!("{} {}", base, instruction),
+                None => base.to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LLMResponse<T> {
     pub data: T,
@@ -132,12 +250,49 @@ pub trait LLMClient: Send + Sync {
         self.embed_batch(texts).await
     }
 
+    /// Embedding length the provider has actually returned, once known.
+    fn observed_embedding_dim(&self) -> Option<usize> {
+        None
+    }
+
     async fn generate(&self, prompt: &str) -> Result<LLMResponse<String>>;
-    async fn compress(&self, text: &str, is_agent: bool) -> Result<LLMResponse<CompressionOutput>>;
+
+    /// Generate a reply that should be JSON matching `schema`. Providers with a JSON
+    /// mode enable it; the default sends the prompts as one `generate` call. Callers
+    /// validate the reply through [`structured::generate_structured`].
+    async fn generate_json(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        _schema: &OutputSchema,
+    ) -> Result<LLMResponse<String>> {
+        self.generate(&format!("{}\n\n{}", system_prompt, prompt))
+            .await
+    }
+
+    /// Extra attempts allowed when a JSON reply fails validation.
+    fn structured_output_retries(&self) -> u32 {
+        memorose_common::config::DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES
+    }
+
+    /// Compress packed events into one memory. `context` says whose turns they are, so
+    /// the prompt can follow an agent trajectory or attribute facts across speakers.
+    async fn compress(
+        &self,
+        text: &str,
+        context: &CompressionContext,
+    ) -> Result<LLMResponse<CompressionOutput>>;
     async fn summarize_group(&self, texts: Vec<String>) -> Result<LLMResponse<String>>;
 
     // Multi-modal placeholders
     async fn describe_image(&self, image_url_or_base64: &str) -> Result<LLMResponse<String>>;
+    /// OCR: transcribe the text visible in an image. Clients without vision support
+    /// return an error, and callers fall back to the caption alone.
+    async fn extract_image_text(&self, _image_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        Err(anyhow::anyhow!(
+            "image text extraction is not supported by this client"
+        ))
+    }
     async fn transcribe(&self, audio_url_or_base64: &str) -> Result<LLMResponse<String>>;
     async fn describe_video(&self, video_url: &str) -> Result<LLMResponse<String>>;
 }
@@ -167,7 +322,7 @@ mod tests {
         async fn compress(
             &self,
             _text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> anyhow::Result<LLMResponse<CompressionOutput>> {
             unimplemented!()
         }
@@ -222,4 +377,53 @@ mod tests {
         assert_eq!(res.data.len(), 2);
         assert_eq!(res.usage.total_tokens, 2);
     }
+
+    #[tokio::test]
+    async fn test_dimension_checked_client_rejects_mismatched_embeddings() {
+        let matching = DimensionCheckedClient::new(Arc::new(DummyLLM), 3);
+        assert_eq!(matching.observed_embedding_dim(), None);
+        assert_eq!(matching.embed("a").await.unwrap().data.len(), 3);
+        assert_eq!(matching.observed_embedding_dim(), Some(3));
+
+        let mismatched = DimensionCheckedClient::new(Arc::new(DummyLLM), 768);
+        let err = mismatched.embed("a").await.unwrap_err().to_string();
+        assert!(err.contains("returned 3 dimensions"));
+        assert!(err.contains("llm.embedding_dim is 768"));
+        assert!(mismatched
+            .embed_batch(vec!["a".into(), "b".into()])
+            .await
+            .is_err());
+        assert_eq!(mismatched.observed_embedding_dim(), Some(3));
+    }
+
+    #[test]
+    fn test_compression_context_renders_configured_template() {
+        let participants = vec![
+            ConversationParticipant {
+                name: Some("Alice".into()),
+                role: None,
+            },
+            ConversationParticipant {
+                name: Some("Bob".into()),
+                role: None,
+            },
+        ];
+        let built_in = CompressionContext {
+            participants: participants.clone(),
+            ..CompressionContext::default()
+        };
+        assert!(built_in
+            .system_prompt("Compress.")
+            .starts_with("Compress. MULTIPLE SPEAKERS"));
+
+        let templated = CompressionContext {
+            participants,
+            app_id: Some("support".into()),
+            template: Some("Summarize {{app_id}} tickets.\n{{speakers}}".into()),
+            ..CompressionContext::default()
+        };
+        let prompt = templated.system_prompt("Compress.");
+        assert!(prompt.starts_with("Summarize support tickets.\nMULTIPLE SPEAKERS"));
+        assert!(!prompt.contains("Compress."));
+    }
 }
@@ -1,4 +1,9 @@
-use super::{CompressionOutput, EmbedInput, EmbedPart, LLMClient};
+use super::rate_limit;
+use super::structured::{self, OutputSchema};
+use super::{
+    CompressionContext, CompressionOutput, EmbedInput, EmbedPart, LLMClient,
+    IMAGE_TEXT_EXTRACTION_PROMPT,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use reqwest::Client;
@@ -108,6 +113,7 @@ pub struct OpenAIClient {
     base_url: String,
     model: String,
     embedding_model: String,
+    structured_output_retries: u32,
 }
 
 impl OpenAIClient {
@@ -130,9 +136,17 @@ impl OpenAIClient {
             base_url: actual_base_url,
             model,
             embedding_model,
+            structured_output_retries:
+                memorose_common::config::DEFAULT_LLM_STRUCTURED_OUTPUT_RETRIES,
         }
     }
 
+    /// Extra attempts when a JSON reply fails schema validation.
+    pub fn with_structured_output_retries(mut self, retries: u32) -> Self {
+        self.structured_output_retries = retries;
+        self
+    }
+
     async fn call_chat_completion(
         &self,
         system_prompt: Option<&str>,
@@ -174,10 +188,16 @@ impl OpenAIClient {
             .await?;
 
         let status = res.status();
+        let retry_after = rate_limit::retry_after(res.headers());
         let body = res.text().await?;
 
         if !status.is_success() {
-            return Err(anyhow!("OpenAI API error ({}): {}", status, body));
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("OpenAI API error ({}): {}", status, body),
+            ));
         }
 
         parse_chat_response(&body, "OpenAI response")
@@ -205,14 +225,77 @@ impl OpenAIClient {
             .await?;
 
         let status = res.status();
+        let retry_after = rate_limit::retry_after(res.headers());
         let body = res.text().await?;
 
         if !status.is_success() {
-            return Err(anyhow!("OpenAI Embedding API error ({}): {}", status, body));
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("OpenAI Embedding API error ({}): {}", status, body),
+            ));
         }
 
         parse_embed_response(&body)
     }
+
+    async fn vision_chat(
+        &self,
+        image_url_or_base64: &str,
+        prompt: &str,
+    ) -> Result<super::LLMResponse<String>> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+
+        let image_url = if image_url_or_base64.starts_with("http") {
+            image_url_or_base64.to_string()
+        } else {
+            format!("data:image/jpeg;base64,{}", image_url_or_base64)
+        };
+
+        let req = MultiModalChatRequest {
+            model: self.model.clone(),
+            messages: vec![MultiModalMessage {
+                role: "user".to_string(),
+                content: MessageContent::Parts(vec![
+                    ContentPart::Text {
+                        r#type: "text".to_string(),
+                        text: prompt.to_string(),
+                    },
+                    ContentPart::ImageUrl {
+                        r#type: "image_url".to_string(),
+                        image_url: ImageUrlDetail { url: image_url },
+                    },
+                ]),
+            }],
+            temperature: 0.1,
+            max_tokens: 300,
+        };
+
+        let res = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&req)
+            .send()
+            .await?;
+
+        let status = res.status();
+        let retry_after = rate_limit::retry_after(res.headers());
+        let body = res.text().await?;
+
+        if !status.is_success() {
+            return Err(rate_limit::provider_error(
+                status,
+                retry_after,
+                &body,
+                format!("OpenAI Vision API error ({}): {}", status, body),
+            ));
+        }
+
+        parse_chat_response(&body, "OpenAI Vision response")
+    }
 }
 
 #[async_trait]
@@ -221,12 +304,28 @@ impl LLMClient for OpenAIClient {
         self.call_chat_completion(None, prompt, false).await
     }
 
+    async fn generate_json(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        _schema: &OutputSchema,
+    ) -> Result<super::LLMResponse<String>> {
+        // `json_object` rather than `json_schema`: OpenAI-compatible servers behind
+        // `base_url` widely support it, and the schema is checked on our side.
+        self.call_chat_completion(Some(system_prompt), prompt, true)
+            .await
+    }
+
+    fn structured_output_retries(&self) -> u32 {
+        self.structured_output_retries
+    }
+
     async fn compress(
         &self,
         text: &str,
-        is_agent: bool,
+        context: &CompressionContext,
     ) -> Result<super::LLMResponse<CompressionOutput>> {
-        let system_prompt = if is_agent {
+        let base_prompt = if context.is_agent {
             // PROCEDURAL (Agent) PROMPT
             "You are an expert at extracting and summarizing Agent execution trajectories and experiences. \
             Your task is to produce a comprehensive summary of the agent's actions, logic, and outcomes. \
@@ -255,23 +354,17 @@ impl LLMClient for OpenAIClient {
             Output ONLY valid JSON: \
             {\"content\": \"compressed factual summary\", \"valid_at\": \"ISO8601 timestamp or null\"}"
         };
-
-        let response = self
-            .call_chat_completion(Some(system_prompt), text, true)
-            .await?;
-
-        let parsed: CompressionOutput = serde_json::from_str(&response.data).map_err(|e| {
-            anyhow!(
-                "Failed to parse JSON compression output: {} - Output: {}",
-                e,
-                response.data
-            )
-        })?;
-
-        Ok(super::LLMResponse {
-            data: parsed,
-            usage: response.usage,
-        })
+        let system_prompt = context.system_prompt(base_prompt);
+
+        structured::generate_structured(
+            self,
+            "compress",
+            &system_prompt,
+            text,
+            &OutputSchema::compression(),
+            self.structured_output_retries,
+        )
+        .await
     }
 
     async fn summarize_group(&self, texts: Vec<String>) -> Result<super::LLMResponse<String>> {
@@ -324,50 +417,19 @@ impl LLMClient for OpenAIClient {
         &self,
         image_url_or_base64: &str,
     ) -> Result<super::LLMResponse<String>> {
-        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
-
-        let image_url = if image_url_or_base64.starts_with("http") {
-            image_url_or_base64.to_string()
-        } else {
-            format!("data:image/jpeg;base64,{}", image_url_or_base64)
-        };
-
-        let req = MultiModalChatRequest {
-            model: self.model.clone(),
-            messages: vec![MultiModalMessage {
-                role: "user".to_string(),
-                content: MessageContent::Parts(vec![
-                    ContentPart::Text {
-                        r#type: "text".to_string(),
-                        text: "Describe this image in detail, focusing on objects, actions, and text visible.".to_string(),
-                    },
-                    ContentPart::ImageUrl {
-                        r#type: "image_url".to_string(),
-                        image_url: ImageUrlDetail { url: image_url },
-                    },
-                ]),
-            }],
-            temperature: 0.1,
-            max_tokens: 300,
-        };
-
-        let res = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&req)
-            .send()
-            .await?;
-
-        let status = res.status();
-        let body = res.text().await?;
-
-        if !status.is_success() {
-            return Err(anyhow!("OpenAI Vision API error ({}): {}", status, body));
-        }
+        self.vision_chat(
+            image_url_or_base64,
+            "Describe this image in detail, focusing on objects, actions, and text visible.",
+        )
+        .await
+    }
 
-        parse_chat_response(&body, "OpenAI Vision response")
+    async fn extract_image_text(
+        &self,
+        image_url_or_base64: &str,
+    ) -> Result<super::LLMResponse<String>> {
+        self.vision_chat(image_url_or_base64, IMAGE_TEXT_EXTRACTION_PROMPT)
+            .await
     }
 
     async fn transcribe(&self, _audio_url_or_base64: &str) -> Result<super::LLMResponse<String>> {
@@ -2,7 +2,9 @@
 mod tests {
     use super::super::{map_usage, parse_chat_response, parse_embed_response};
     use crate::llm::openai::OpenAIClient;
-    use crate::llm::{EmbedInput, EmbedPart, LLMClient};
+    use crate::llm::rate_limit::rate_limit_snapshots;
+    use crate::llm::{CompressionContext, EmbedInput, EmbedPart, LLMClient, Throttled};
+    use memorose_common::config::{LLMConfig, LLMProvider};
     use memorose_common::TokenUsage;
     use serde_json::json;
     use wiremock::matchers::{header, method, path};
@@ -161,13 +163,126 @@ mod tests {
             Some(mock_server.uri()),
         );
 
-        let result = client.compress("Long text to compress", false).await;
+        let result = client
+            .compress("Long text to compress", &CompressionContext::agent(false))
+            .await;
         assert!(result.is_ok());
         let output = result.unwrap();
         assert_eq!(output.data.content, "Summarized fact");
         assert!(output.data.valid_at.is_none());
     }
 
+    #[tokio::test]
+    async fn test_compress_retries_output_that_fails_schema() {
+        let mock_server = MockServer::start().await;
+        let reply = |content: &str| {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": content } }],
+                "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+            }))
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(reply(r#"{"content": null}"#))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(reply(r#"{"content": "Summarized fact", "valid_at": null}"#))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenAIClient::new(
+            TEST_API_KEY.to_string(),
+            TEST_MODEL.to_string(),
+            TEST_EMBEDDING_MODEL.to_string(),
+            Some(mock_server.uri()),
+        );
+        let output = client
+            .compress("Long text to compress", &CompressionContext::agent(false))
+            .await
+            .unwrap();
+        assert_eq!(output.data.content, "Summarized fact");
+        // Usage covers both attempts
+        assert_eq!(output.usage.total_tokens, 30);
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let first: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(first["response_format"]["type"], "json_object");
+        let retry: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert!(retry["messages"][1]["content"]
+            .as_str()
+            .unwrap()
+            .contains("$.content should be string, got null"));
+    }
+
+    #[tokio::test]
+    async fn test_throttled_call_honors_retry_after_and_retries() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "0")
+                    .set_body_string("rate limited"),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "hello" } }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let config = LLMConfig {
+            provider: LLMProvider::OpenAI,
+            openai_api_key: Some(TEST_API_KEY.to_string()),
+            base_url: Some(mock_server.uri()),
+            embedding_dim: 0,
+            ..LLMConfig::default()
+        };
+        let client = crate::llm::create_llm_client(&config).unwrap();
+        assert_eq!(client.generate("Hello").await.unwrap().data, "hello");
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+
+        let limiter = rate_limit_snapshots()
+            .into_iter()
+            .find(|snapshot| snapshot.provider == format!("openai@{}", mock_server.uri()))
+            .unwrap();
+        assert_eq!(limiter.throttled_total, 1);
+        assert_eq!(limiter.retries_total, 1);
+        assert_eq!(limiter.concurrency_limit, config.max_concurrency / 2);
+
+        // The provider client reports the throttle with the requested delay
+        let throttling_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "3"))
+            .mount(&throttling_server)
+            .await;
+        let err = OpenAIClient::new(
+            TEST_API_KEY.to_string(),
+            TEST_MODEL.to_string(),
+            TEST_EMBEDDING_MODEL.to_string(),
+            Some(throttling_server.uri()),
+        )
+        .generate("Hello")
+        .await
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("OpenAI API error (429 Too Many Requests)"));
+        assert_eq!(
+            err.downcast_ref::<Throttled>().unwrap().retry_after,
+            Some(std::time::Duration::from_secs(3))
+        );
+    }
+
     #[test]
     fn test_map_usage_defaults_completion_tokens() {
         let usage = map_usage(Some(super::super::Usage {
@@ -0,0 +1,576 @@
+//! Provider-wide rate limiting for LLM calls.
+//!
+//! Every client built for the same provider endpoint shares one [`AdaptiveLimiter`], so the
+//! worker, the arbitrator and request-path embedding calls draw from the same budget. The
+//! limiter paces calls with an optional token bucket, caps the calls in flight with a limit
+//! that halves when the provider throttles and grows back one step per window of successes,
+//! and retries throttled calls after the provider's `Retry-After`.
+
+use super::{
+    CompressionContext, CompressionOutput, EmbedInput, LLMClient, LLMResponse, OutputSchema,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use memorose_common::config::LLMConfig;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Pause after a throttled call that gave no `Retry-After`; doubles with each throttle in a row.
+const THROTTLE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_THROTTLE_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The provider refused a call with 429 or 503.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Throttled {
+    /// Wait the provider asked for, from `Retry-After` or the error body
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Throttled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Throttled {}
+
+/// Delay from `Retry-After` (seconds or an HTTP date) or OpenAI's `retry-after-ms`.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(ms) = header("retry-after-ms").and_then(|value| value.trim().parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    let value = header("retry-after")?.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Gemini puts the delay in the error body as a `RetryInfo` detail, e.g. `"retryDelay": "30s"`.
+fn retry_delay_in_body(body: &str) -> Option<Duration> {
+    let parsed: serde_json::Value = serde_json::from_str(body).ok()?;
+    parsed
+        .pointer("/error/details")?
+        .as_array()?
+        .iter()
+        .filter_map(|detail| detail.get("retryDelay")?.as_str())
+        .find_map(|delay| Duration::try_from_secs_f64(delay.strip_suffix('s')?.parse().ok()?).ok())
+}
+
+/// Error for a failed provider response: a [`Throttled`] for 429 and 503, so the limiter
+/// backs off and retries, otherwise a plain error. `message` is the same either way.
+pub(crate) fn provider_error(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+    message: String,
+) -> anyhow::Error {
+    if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+        return Throttled {
+            retry_after: retry_after.or_else(|| retry_delay_in_body(body)),
+            message,
+        }
+        .into();
+    }
+    anyhow::anyhow!(message)
+}
+
+struct TokenBucket {
+    per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Holds up to one second of calls, so a `requests_per_minute` budget is spread over the
+    /// minute instead of spent in one burst.
+    fn new(requests_per_minute: u32, now: Instant) -> Self {
+        let per_sec = f64::from(requests_per_minute) / 60.0;
+        let capacity = per_sec.max(1.0);
+        Self {
+            per_sec,
+            capacity,
+            tokens: capacity,
+            refilled_at: now,
+        }
+    }
+
+    /// Take a token, or say how long until one is available.
+    fn take(&mut self, now: Instant) -> std::result::Result<(), Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+enum Outcome<'a> {
+    Success,
+    Throttled(&'a Throttled),
+    Failed,
+}
+
+struct LimiterState {
+    /// Calls allowed in flight right now
+    limit: usize,
+    /// Permits to retire as calls finish, after the limit dropped below the calls in flight
+    shrink: usize,
+    /// Successes since the limit last changed
+    successes: usize,
+    /// Throttled calls in a row, for the backoff when the provider names no delay
+    throttle_streak: u32,
+    paused_until: Option<Instant>,
+    bucket: Option<TokenBucket>,
+}
+
+/// Rate limit and adaptive concurrency for one provider endpoint.
+pub struct AdaptiveLimiter {
+    name: String,
+    requests_per_minute: u32,
+    max_concurrency: usize,
+    max_retries: u32,
+    permits: Arc<Semaphore>,
+    state: Mutex<LimiterState>,
+    in_flight: AtomicUsize,
+    throttled_total: AtomicUsize,
+    retries_total: AtomicUsize,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(
+        name: impl Into<String>,
+        requests_per_minute: u32,
+        max_concurrency: usize,
+        max_retries: u32,
+    ) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        Self {
+            name: name.into(),
+            requests_per_minute,
+            max_concurrency,
+            max_retries,
+            permits: Arc::new(Semaphore::new(max_concurrency)),
+            state: Mutex::new(LimiterState {
+                limit: max_concurrency,
+                shrink: 0,
+                successes: 0,
+                throttle_streak: 0,
+                paused_until: None,
+                bucket: (requests_per_minute > 0)
+                    .then(|| TokenBucket::new(requests_per_minute, Instant::now())),
+            }),
+            in_flight: AtomicUsize::new(0),
+            throttled_total: AtomicUsize::new(0),
+            retries_total: AtomicUsize::new(0),
+        }
+    }
+
+    /// Run `call` within the limits, retrying it while the provider throttles it, up to
+    /// the configured number of retries. Other errors are returned as they are.
+    pub async fn run<T, F, Fut>(&self, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.wait_for_turn().await;
+            let permit = self
+                .permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("limiter semaphore is never closed");
+            self.in_flight.fetch_add(1, Ordering::Relaxed);
+
+            let result = call().await;
+            let outcome = match &result {
+                Ok(_) => Outcome::Success,
+                Err(e) => match e.downcast_ref::<Throttled>() {
+                    Some(throttled) => Outcome::Throttled(throttled),
+                    None => Outcome::Failed,
+                },
+            };
+            let retry = matches!(outcome, Outcome::Throttled(_)) && attempt < self.max_retries;
+            self.finish(permit, outcome);
+
+            if retry {
+                attempt += 1;
+                self.retries_total.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "{} throttled an LLM call; retrying ({}/{})",
+                    self.name,
+                    attempt,
+                    self.max_retries
+                );
+                continue;
+            }
+            return result;
+        }
+    }
+
+    /// Wait out any pause the provider asked for, then for a token from the bucket.
+    async fn wait_for_turn(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                match state.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ => match state.bucket.as_mut().map(|bucket| bucket.take(now)) {
+                        Some(Err(wait)) => wait,
+                        Some(Ok(())) | None => return,
+                    },
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Adjust the limit for a finished call. Errors other than throttling leave it alone.
+    fn finish(&self, permit: OwnedSemaphorePermit, outcome: Outcome<'_>) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match outcome {
+            Outcome::Success => {
+                state.throttle_streak = 0;
+                state.successes += 1;
+                if state.successes >= state.limit && state.limit < self.max_concurrency {
+                    state.successes = 0;
+                    state.limit += 1;
+                    if state.shrink > 0 {
+                        state.shrink -= 1;
+                    } else {
+                        self.permits.add_permits(1);
+                    }
+                }
+            }
+            Outcome::Throttled(throttled) => {
+                self.throttled_total.fetch_add(1, Ordering::Relaxed);
+                let backoff = throttled.retry_after.unwrap_or_else(|| {
+                    THROTTLE_BACKOFF
+                        .saturating_mul(1 << state.throttle_streak.min(6))
+                        .min(MAX_THROTTLE_BACKOFF)
+                });
+                state.throttle_streak += 1;
+                let until = Instant::now() + backoff;
+                state.paused_until = Some(state.paused_until.map_or(until, |at| at.max(until)));
+
+                let limit = (state.limit / 2).max(1);
+                state.shrink += state.limit - limit;
+                state.limit = limit;
+                state.successes = 0;
+                // Retire idle permits now; the rest go as calls in flight finish
+                while state.shrink > 0 {
+                    let Ok(idle) = self.permits.try_acquire() else {
+                        break;
+                    };
+                    idle.forget();
+                    state.shrink -= 1;
+                }
+            }
+            Outcome::Failed => {}
+        }
+
+        if state.shrink > 0 {
+            state.shrink -= 1;
+            permit.forget();
+        }
+    }
+
+    pub fn snapshot(&self) -> RateLimitSnapshot {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        RateLimitSnapshot {
+            provider: self.name.clone(),
+            requests_per_minute: self.requests_per_minute,
+            concurrency_limit: state.limit,
+            max_concurrency: self.max_concurrency,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            throttled_total: self.throttled_total.load(Ordering::Relaxed),
+            retries_total: self.retries_total.load(Ordering::Relaxed),
+            paused_ms: state.paused_until.map_or(0, |until| {
+                until.saturating_duration_since(Instant::now()).as_millis() as u64
+            }),
+        }
+    }
+}
+
+/// State of one provider's limiter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimitSnapshot {
+    pub provider: String,
+    pub requests_per_minute: u32,
+    pub concurrency_limit: usize,
+    pub max_concurrency: usize,
+    pub in_flight: usize,
+    pub throttled_total: usize,
+    pub retries_total: usize,
+    /// Time left before calls resume after a throttle
+    pub paused_ms: u64,
+}
+
+/// Limiters by provider endpoint. The worker, arbitrator and server each build their own
+/// client from the same config, so the limiter is process-wide to give them one budget.
+fn limiters() -> &'static Mutex<HashMap<String, Arc<AdaptiveLimiter>>> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<AdaptiveLimiter>>>> = OnceLock::new();
+    LIMITERS.get_or_init(Default::default)
+}
+
+/// The limiter for `config`'s provider endpoint, created on first use.
+pub fn shared_limiter(config: &LLMConfig) -> Arc<AdaptiveLimiter> {
+    let provider = format!("{:?}", config.provider).to_lowercase();
+    let name = format!("{}@{}", provider, config.get_base_url().unwrap_or_default());
+    limiters()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(name.clone())
+        .or_insert_with(|| {
+            Arc::new(AdaptiveLimiter::new(
+                name,
+                config.requests_per_minute,
+                config.max_concurrency,
+                config.rate_limit_retries,
+            ))
+        })
+        .clone()
+}
+
+pub fn rate_limit_snapshots() -> Vec<RateLimitSnapshot> {
+    let mut snapshots: Vec<RateLimitSnapshot> = limiters()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .map(|limiter| limiter.snapshot())
+        .collect();
+    snapshots.sort_by(|a, b| a.provider.cmp(&b.provider));
+    snapshots
+}
+
+/// Runs every call of a provider client through its [`AdaptiveLimiter`].
+pub struct RateLimitedClient {
+    inner: Arc<dyn LLMClient>,
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+impl RateLimitedClient {
+    pub fn new(inner: Arc<dyn LLMClient>, limiter: Arc<AdaptiveLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    /// Run one provider call through the limiter and count its tokens toward the worker
+    /// cycle it belongs to, if any.
+    async fn call<T, F, Fut>(&self, call: F) -> Result<LLMResponse<T>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<LLMResponse<T>>>,
+    {
+        let res = self.limiter.run(call).await?;
+        crate::run_report::record_llm_usage(&res.usage);
+        Ok(res)
+    }
+}
+
+#[async_trait]
+impl LLMClient for RateLimitedClient {
+    async fn embed(&self, text: &str) -> Result<LLMResponse<Vec<f32>>> {
+        self.call(|| self.inner.embed(text)).await
+    }
+
+    async fn embed_batch(&self, texts: Vec<String>) -> Result<LLMResponse<Vec<Vec<f32>>>> {
+        self.call(|| self.inner.embed_batch(texts.clone())).await
+    }
+
+    async fn embed_content(&self, input: EmbedInput) -> Result<LLMResponse<Vec<f32>>> {
+        self.call(|| self.inner.embed_content(input.clone())).await
+    }
+
+    async fn embed_content_batch(
+        &self,
+        inputs: Vec<EmbedInput>,
+    ) -> Result<LLMResponse<Vec<Vec<f32>>>> {
+        self.call(|| self.inner.embed_content_batch(inputs.clone()))
+            .await
+    }
+
+    fn observed_embedding_dim(&self) -> Option<usize> {
+        self.inner.observed_embedding_dim()
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.generate(prompt)).await
+    }
+
+    async fn generate_json(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        schema: &OutputSchema,
+    ) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.generate_json(system_prompt, prompt, schema))
+            .await
+    }
+
+    fn structured_output_retries(&self) -> u32 {
+        self.inner.structured_output_retries()
+    }
+
+    async fn compress(
+        &self,
+        text: &str,
+        context: &CompressionContext,
+    ) -> Result<LLMResponse<CompressionOutput>> {
+        self.call(|| self.inner.compress(text, context)).await
+    }
+
+    async fn summarize_group(&self, texts: Vec<String>) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.summarize_group(texts.clone()))
+            .await
+    }
+
+    async fn describe_image(&self, image_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.describe_image(image_url_or_base64))
+            .await
+    }
+
+    async fn extract_image_text(&self, image_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.extract_image_text(image_url_or_base64))
+            .await
+    }
+
+    async fn transcribe(&self, audio_url_or_base64: &str) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.transcribe(audio_url_or_base64))
+            .await
+    }
+
+    async fn describe_video(&self, video_url: &str) -> Result<LLMResponse<String>> {
+        self.call(|| self.inner.describe_video(video_url)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttled(retry_after: Option<Duration>) -> anyhow::Error {
+        Throttled {
+            retry_after,
+            message: "429".into(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_retry_after_reads_headers_and_gemini_body() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert("retry-after-ms", "250".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(250)));
+        let mut dated = HeaderMap::new();
+        dated.insert(
+            "retry-after",
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&dated), Some(Duration::ZERO));
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+
+        let body = r#"{"error":{"code":429,"details":[{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"1.5s"}]}}"#;
+        let err = provider_error(StatusCode::TOO_MANY_REQUESTS, None, body, "busy".into());
+        let throttled = err.downcast_ref::<Throttled>().unwrap();
+        assert_eq!(throttled.retry_after, Some(Duration::from_millis(1500)));
+        assert_eq!(err.to_string(), "busy");
+        assert!(
+            provider_error(StatusCode::FORBIDDEN, None, body, "no".into())
+                .downcast_ref::<Throttled>()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_token_bucket_spaces_calls_at_the_configured_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(120, start);
+        assert!(bucket.take(start).is_ok());
+        assert!(bucket.take(start).is_ok());
+        assert_eq!(bucket.take(start), Err(Duration::from_millis(500)));
+        assert!(bucket.take(start + Duration::from_millis(500)).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_limiter_halves_concurrency_and_waits_out_retry_after() {
+        let limiter = AdaptiveLimiter::new("test", 0, 8, 2);
+        let calls = AtomicUsize::new(0);
+        let started = Instant::now();
+
+        let result = limiter
+            .run(|| {
+                let attempt = calls.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    if attempt == 0 {
+                        Err(throttled(Some(Duration::from_secs(5))))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(result, 1);
+        assert!(started.elapsed() >= Duration::from_secs(5));
+
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot.concurrency_limit, 4);
+        assert_eq!(snapshot.throttled_total, 1);
+        assert_eq!(snapshot.retries_total, 1);
+        assert_eq!(snapshot.in_flight, 0);
+        assert_eq!(limiter.permits.available_permits(), 4);
+
+        // A window of successes raises the limit one step
+        for _ in 0..4 {
+            limiter.run(|| async { Ok(()) }).await.unwrap();
+        }
+        assert_eq!(limiter.snapshot().concurrency_limit, 5);
+        assert_eq!(limiter.permits.available_permits(), 5);
+
+        // Retries run out and the throttle is returned; other errors are not retried
+        let always = limiter
+            .run(|| async { Err::<(), _>(throttled(None)) })
+            .await
+            .unwrap_err();
+        assert!(always.downcast_ref::<Throttled>().is_some());
+        assert_eq!(limiter.snapshot().retries_total, 3);
+        let calls = AtomicUsize::new(0);
+        limiter
+            .run(|| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async { Err::<(), _>(anyhow::anyhow!("bad request")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(limiter.snapshot().concurrency_limit, 1);
+    }
+}
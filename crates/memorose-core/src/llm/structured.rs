@@ -0,0 +1,360 @@
+//! Schema-checked JSON replies. Calls that expect JSON ask the provider for JSON mode,
+//! check the reply against a small JSON Schema and re-prompt a bounded number of times
+//! before giving up, instead of passing malformed output on as plain text.
+
+use super::{LLMClient, LLMResponse};
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Expected shape of a JSON reply. Supports the `type`, `properties`, `required` and
+/// `items` keywords, which is all the built-in prompts need.
+#[derive(Debug, Clone)]
+pub struct OutputSchema {
+    pub name: &'static str,
+    pub schema: Value,
+}
+
+impl OutputSchema {
+    /// `{"content": "...", "valid_at": "..." | null}` from `compress`.
+    pub fn compression() -> Self {
+        Self {
+            name: "compression",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "content": { "type": "string" },
+                    "valid_at": { "type": ["string", "null"] }
+                },
+                "required": ["content"]
+            }),
+        }
+    }
+
+    /// Named insight for a memory community.
+    pub fn community_insight() -> Self {
+        Self {
+            name: "community_insight",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "summary": { "type": "string" },
+                    "keywords": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["name", "summary"]
+            }),
+        }
+    }
+
+    /// L2 topics with the memory ids they came from.
+    pub fn topics() -> Self {
+        Self {
+            name: "topics",
+            schema: json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "summary": { "type": "string" },
+                        "source_ids": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["summary", "source_ids"]
+                }
+            }),
+        }
+    }
+
+    /// Milestones a goal is broken into.
+    pub fn milestones() -> Self {
+        Self {
+            name: "milestones",
+            schema: json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "summary": { "type": "string" },
+                        "description": { "type": "string" },
+                        "dependencies": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["summary"]
+                }
+            }),
+        }
+    }
+
+    /// Answer to a question with the numbered memories it rests on.
+    pub fn grounded_answer() -> Self {
+        Self {
+            name: "grounded_answer",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "answer": { "type": "string" },
+                    "citations": { "type": "array", "items": { "type": "integer" } },
+                    "confidence": { "type": "number" }
+                },
+                "required": ["answer", "citations", "confidence"]
+            }),
+        }
+    }
+
+    /// Questions written for numbered memories, each with the answer the memory gives.
+    pub fn qa_pairs() -> Self {
+        Self {
+            name: "qa_pairs",
+            schema: json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "memory": { "type": "integer" },
+                        "question": { "type": "string" },
+                        "answer": { "type": "string" }
+                    },
+                    "required": ["memory", "question"]
+                }
+            }),
+        }
+    }
+
+    /// Check `value` against the schema, naming the first offending path.
+    pub fn validate(&self, value: &Value) -> std::result::Result<(), String> {
+        validate_value(&self.schema, value, "$")
+    }
+
+    /// Parse a raw reply, allowing a Markdown code fence around it, and deserialize it
+    /// once it matches the schema.
+    pub fn parse<T: DeserializeOwned>(&self, raw: &str) -> std::result::Result<T, String> {
+        let value: Value = serde_json::from_str(strip_code_fence(raw))
+            .map_err(|e| format!("reply is not valid JSON: {}", e))?;
+        self.validate(&value)?;
+        serde_json::from_value(value).map_err(|e| format!("reply does not match schema: {}", e))
+    }
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str) -> std::result::Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| type_matches(name, value)) {
+            return Err(format!(
+                "{} should be {}, got {}",
+                path,
+                allowed.join(" or "),
+                type_name(value)
+            ));
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for field in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(field) {
+                    return Err(format!("{} is missing required field `{}`", path, field));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (field, field_schema) in properties {
+                if let Some(field_value) = fields.get(field) {
+                    validate_value(field_schema, field_value, &format!("{}.{}", path, field))?;
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            validate_value(item_schema, item, &format!("{}[{}]", path, index))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Drop a surrounding Markdown code fence, as models add one even in JSON mode.
+pub fn strip_code_fence(text: &str) -> &str {
+    text.trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
+}
+
+/// Ask for JSON matching `schema`, re-prompting with the validation error up to
+/// `max_retries` times. Provider errors are returned at once; only replies that fail
+/// validation are retried. `operation` labels the call in logs.
+pub async fn generate_structured<C, T>(
+    client: &C,
+    operation: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    schema: &OutputSchema,
+    max_retries: u32,
+) -> Result<LLMResponse<T>>
+where
+    C: LLMClient + ?Sized,
+    T: DeserializeOwned,
+{
+    STRUCTURED_OUTPUT_METRICS
+        .requests
+        .fetch_add(1, Ordering::Relaxed);
+    let mut usage = memorose_common::TokenUsage::default();
+    let mut prompt = user_prompt.to_string();
+    let mut attempt = 0;
+
+    loop {
+        let response = client.generate_json(system_prompt, &prompt, schema).await?;
+        usage.prompt_tokens += response.usage.prompt_tokens;
+        usage.completion_tokens += response.usage.completion_tokens;
+        usage.total_tokens += response.usage.total_tokens;
+
+        let error = match schema.parse::<T>(&response.data) {
+            Ok(data) => {
+                if attempt > 0 {
+                    STRUCTURED_OUTPUT_METRICS
+                        .recovered
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+                return Ok(LLMResponse { data, usage });
+            }
+            Err(error) => error,
+        };
+
+        STRUCTURED_OUTPUT_METRICS
+            .parse_failures
+            .fetch_add(1, Ordering::Relaxed);
+        if attempt >= max_retries {
+            STRUCTURED_OUTPUT_METRICS
+                .exhausted
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(anyhow!(
+                "{} returned invalid {} output after {} attempt(s): {} - Output: {}",
+                operation,
+                schema.name,
+                attempt + 1,
+                error,
+                response.data
+            ));
+        }
+
+        attempt += 1;
+        STRUCTURED_OUTPUT_METRICS
+            .retries
+            .fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            "{} returned invalid {} output ({}); retrying ({}/{})",
+            operation,
+            schema.name,
+            error,
+            attempt,
+            max_retries
+        );
+        prompt = format!(
+            "{}\n\nYour previous reply was rejected: {}. Reply with only a JSON value matching this schema, without commentary: {}",
+            user_prompt, error, schema.schema
+        );
+    }
+}
+
+struct StructuredOutputMetrics {
+    requests: AtomicUsize,
+    parse_failures: AtomicUsize,
+    retries: AtomicUsize,
+    recovered: AtomicUsize,
+    exhausted: AtomicUsize,
+}
+
+static STRUCTURED_OUTPUT_METRICS: StructuredOutputMetrics = StructuredOutputMetrics {
+    requests: AtomicUsize::new(0),
+    parse_failures: AtomicUsize::new(0),
+    retries: AtomicUsize::new(0),
+    recovered: AtomicUsize::new(0),
+    exhausted: AtomicUsize::new(0),
+};
+
+/// Process-wide counters for schema-checked LLM calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StructuredOutputMetricSnapshot {
+    pub requests: usize,
+    /// Replies that were not valid JSON or did not match the schema
+    pub parse_failures: usize,
+    pub retries: usize,
+    /// Calls that succeeded after at least one retry
+    pub recovered: usize,
+    /// Calls that gave up with every attempt invalid
+    pub exhausted: usize,
+}
+
+pub fn structured_output_metric_snapshot() -> StructuredOutputMetricSnapshot {
+    StructuredOutputMetricSnapshot {
+        requests: STRUCTURED_OUTPUT_METRICS.requests.load(Ordering::Relaxed),
+        parse_failures: STRUCTURED_OUTPUT_METRICS
+            .parse_failures
+            .load(Ordering::Relaxed),
+        retries: STRUCTURED_OUTPUT_METRICS.retries.load(Ordering::Relaxed),
+        recovered: STRUCTURED_OUTPUT_METRICS.recovered.load(Ordering::Relaxed),
+        exhausted: STRUCTURED_OUTPUT_METRICS.exhausted.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_schema_validates_types_and_required_fields() {
+        let schema = OutputSchema::compression();
+        let output: super::super::CompressionOutput = schema
+            .parse("```json\n{\"content\": \"Lives in Paris\", \"valid_at\": null}\n```")
+            .unwrap();
+        assert_eq!(output.content, "Lives in Paris");
+
+        let missing = schema.parse::<Value>(r#"{"valid_at": null}"#).unwrap_err();
+        assert!(missing.contains("missing required field `content`"));
+
+        let wrong_type = schema.parse::<Value>(r#"{"content": 42}"#).unwrap_err();
+        assert_eq!(wrong_type, "$.content should be string, got number");
+
+        let topics = OutputSchema::topics()
+            .parse::<Value>(r#"[{"summary": "Moved", "source_ids": ["a", 7]}]"#)
+            .unwrap_err();
+        assert_eq!(topics, "$[0].source_ids[1] should be string, got number");
+
+        assert!(schema
+            .parse::<Value>("plain text")
+            .unwrap_err()
+            .starts_with("reply is not valid JSON"));
+    }
+}
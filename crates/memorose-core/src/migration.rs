@@ -0,0 +1,84 @@
+//! Upgrades for memory units persisted by older releases.
+//!
+//! Records are upgraded in memory whenever they are decoded, so old data stays
+//! readable without downtime; the next write of a unit persists the new layout.
+//! `memorose-server repair migrate-schema` rewrites every stored unit up front.
+
+use anyhow::{anyhow, Result};
+use memorose_common::{MemoryDomain, MemoryType, MemoryUnit, MEMORY_UNIT_SCHEMA_VERSION};
+use serde_json::{Map, Value};
+
+type MigrationStep = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// `MEMORY_UNIT_MIGRATIONS[n]` upgrades a record from schema version `n` to `n + 1`.
+const MEMORY_UNIT_MIGRATIONS: &[MigrationStep] = &[memory_unit_v0_to_v1];
+
+const _: () = assert!(MEMORY_UNIT_MIGRATIONS.len() == MEMORY_UNIT_SCHEMA_VERSION as usize);
+
+/// Decode a stored memory unit, upgrading it to the current schema if needed and opening
+/// content sealed with the user's encryption key.
+pub fn decode_memory_unit(bytes: &[u8]) -> Result<MemoryUnit> {
+    let mut unit = decode_sealed_memory_unit(bytes)?;
+    crate::crypto::open_unit(&mut unit)?;
+    Ok(unit)
+}
+
+/// Like [`decode_memory_unit`], but sealed content stays as stored. For code that rewrites
+/// or ships units without reading their content.
+pub fn decode_sealed_memory_unit(bytes: &[u8]) -> Result<MemoryUnit> {
+    upgrade_memory_unit(serde_json::from_slice(bytes)?).map(|(unit, _)| unit)
+}
+
+/// Upgrade a stored memory unit to the current schema. The flag is true when any
+/// migration step ran, i.e. when the stored bytes are out of date.
+pub fn upgrade_memory_unit(value: Value) -> Result<(MemoryUnit, bool)> {
+    let Value::Object(mut record) = value else {
+        return Err(anyhow!("memory unit record is not a JSON object"));
+    };
+    let version = record
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize;
+    if version > MEMORY_UNIT_SCHEMA_VERSION as usize {
+        return Err(anyhow!(
+            "memory unit schema version {} is newer than supported version {}",
+            version,
+            MEMORY_UNIT_SCHEMA_VERSION
+        ));
+    }
+    for step in &MEMORY_UNIT_MIGRATIONS[version..] {
+        step(&mut record)?;
+    }
+    record.insert(
+        "schema_version".to_string(),
+        Value::from(MEMORY_UNIT_SCHEMA_VERSION),
+    );
+    let unit = serde_json::from_value(Value::Object(record))?;
+    Ok((unit, version < MEMORY_UNIT_SCHEMA_VERSION as usize))
+}
+
+/// Units written before the three-domain split lack `memory_type`, `domain` and
+/// `namespace_key`; derive them the way `MemoryUnit::new` does.
+fn memory_unit_v0_to_v1(record: &mut Map<String, Value>) -> Result<()> {
+    if !record.contains_key("memory_type") {
+        record.insert(
+            "memory_type".to_string(),
+            serde_json::to_value(MemoryType::default())?,
+        );
+    }
+    let memory_type: MemoryType = serde_json::from_value(record["memory_type"].clone())?;
+    let org_id = record.get("org_id").and_then(Value::as_str);
+    let user_id = record.get("user_id").and_then(Value::as_str);
+    let agent_id = record.get("agent_id").and_then(Value::as_str);
+
+    let domain = match record.get("domain") {
+        Some(domain) => serde_json::from_value::<MemoryDomain>(domain.clone())?,
+        None => MemoryUnit::infer_domain(agent_id, &memory_type),
+    };
+    if !record.contains_key("namespace_key") {
+        let namespace_key = MemoryUnit::build_namespace_key(&domain, org_id, user_id, agent_id);
+        record.insert("namespace_key".to_string(), Value::from(namespace_key));
+    }
+    record.insert("domain".to_string(), serde_json::to_value(domain)?);
+    Ok(())
+}
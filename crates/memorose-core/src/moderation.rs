@@ -0,0 +1,381 @@
+use crate::llm::LLMClient;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use memorose_common::config::{ModerationConfig, ModerationPolicy, ModerationProvider};
+use memorose_common::EventContent;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Result of checking one piece of content.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModerationVerdict {
+    pub flagged: bool,
+    /// Categories the content was flagged for, sorted
+    pub categories: Vec<String>,
+}
+
+impl ModerationVerdict {
+    fn flagged(mut categories: Vec<String>) -> Self {
+        categories.sort();
+        categories.dedup();
+        Self {
+            flagged: !categories.is_empty(),
+            categories,
+        }
+    }
+
+    /// Verdict of a provider that reports `flagged` alongside its categories. A flag
+    /// without categories is filed under `flagged`.
+    fn reported(flagged: bool, mut categories: Vec<String>) -> Self {
+        if !flagged {
+            return Self::default();
+        }
+        if categories.is_empty() {
+            categories.push("flagged".to_string());
+        }
+        Self::flagged(categories)
+    }
+}
+
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict>;
+}
+
+/// Content moderation with its per-app policies, built from `[moderation]`.
+#[derive(Clone)]
+pub struct ContentModeration {
+    moderator: Arc<dyn Moderator>,
+    config: ModerationConfig,
+}
+
+impl ContentModeration {
+    /// `None` when moderation is turned off.
+    pub fn from_config(
+        config: &ModerationConfig,
+        llm_client: Arc<dyn LLMClient>,
+    ) -> Result<Option<Self>> {
+        let moderator: Arc<dyn Moderator> = match config.provider {
+            ModerationProvider::None => return Ok(None),
+            ModerationProvider::Regex => Arc::new(RegexModerator::new(&config.patterns)?),
+            ModerationProvider::Http => {
+                let endpoint = config
+                    .endpoint
+                    .clone()
+                    .ok_or_else(|| anyhow!("moderation.endpoint is required for provider http"))?;
+                Arc::new(HttpModerator::new(
+                    endpoint,
+                    config.api_key.clone(),
+                    config.model.clone(),
+                ))
+            }
+            ModerationProvider::Llm => Arc::new(LlmModerator::new(llm_client)),
+        };
+        Ok(Some(Self::new(moderator, config.clone())))
+    }
+
+    pub fn new(moderator: Arc<dyn Moderator>, config: ModerationConfig) -> Self {
+        Self { moderator, config }
+    }
+
+    pub fn policy_for(&self, app_id: Option<&str>) -> &ModerationPolicy {
+        self.config.policy_for(app_id)
+    }
+
+    /// Check `text`. A provider failure is logged and lets the content through, so an
+    /// unreachable moderation service does not stop ingest or retrieval.
+    pub async fn check(&self, text: &str) -> ModerationVerdict {
+        if text.trim().is_empty() {
+            return ModerationVerdict::default();
+        }
+        match self.moderator.moderate(text).await {
+            Ok(verdict) => verdict,
+            Err(error) => {
+                tracing::warn!("Content moderation failed, allowing content: {:?}", error);
+                ModerationVerdict::default()
+            }
+        }
+    }
+}
+
+/// Text of an event that moderation looks at. Media events are only URLs here, so they
+/// are not checked; their descriptions are, once consolidated into memories.
+pub fn event_text(content: &EventContent) -> Option<String> {
+    match content {
+        EventContent::Text(text) => Some(text.clone()),
+        EventContent::Json(value) => Some(value.to_string()),
+        EventContent::Image(_) | EventContent::Audio(_) | EventContent::Video(_) => None,
+    }
+}
+
+// ---------------------------------------------------------
+// RegexModerator
+// ---------------------------------------------------------
+
+pub struct RegexModerator {
+    rules: Vec<(String, Regex)>,
+}
+
+impl RegexModerator {
+    pub fn new(patterns: &std::collections::BTreeMap<String, String>) -> Result<Self> {
+        if patterns.is_empty() {
+            return Err(anyhow!(
+                "moderation.patterns must not be empty for provider regex"
+            ));
+        }
+        let rules = patterns
+            .iter()
+            .map(|(category, pattern)| {
+                Regex::new(pattern)
+                    .map(|regex| (category.clone(), regex))
+                    .map_err(|error| anyhow!("invalid moderation pattern {}: {}", category, error))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+}
+
+#[async_trait]
+impl Moderator for RegexModerator {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict> {
+        Ok(ModerationVerdict::flagged(
+            self.rules
+                .iter()
+                .filter(|(_, regex)| regex.is_match(text))
+                .map(|(category, _)| category.clone())
+                .collect(),
+        ))
+    }
+}
+
+// ---------------------------------------------------------
+// HttpModerator (OpenAI-compatible moderation API)
+// ---------------------------------------------------------
+
+#[derive(Serialize)]
+struct HttpModerationRequest<'a> {
+    input: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct HttpModerationResponse {
+    results: Vec<HttpModerationResult>,
+}
+
+#[derive(Deserialize)]
+struct HttpModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: std::collections::HashMap<String, bool>,
+}
+
+pub struct HttpModerator {
+    endpoint: String,
+    api_key: Option<String>,
+    model: Option<String>,
+    client: Client,
+}
+
+impl HttpModerator {
+    pub fn new(endpoint: String, api_key: Option<String>, model: Option<String>) -> Self {
+        Self {
+            endpoint,
+            api_key,
+            model,
+            client: Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Moderator for HttpModerator {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict> {
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .json(&HttpModerationRequest {
+                input: text,
+                model: self.model.as_deref(),
+            });
+        if let Some(api_key) = self.api_key.as_deref() {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?.error_for_status()?;
+        let body: HttpModerationResponse = response.json().await?;
+
+        let mut categories = Vec::new();
+        let mut flagged = false;
+        for result in body.results {
+            flagged |= result.flagged;
+            categories.extend(
+                result
+                    .categories
+                    .into_iter()
+                    .filter(|(_, hit)| *hit)
+                    .map(|(category, _)| category),
+            );
+        }
+        Ok(ModerationVerdict::reported(flagged, categories))
+    }
+}
+
+// ---------------------------------------------------------
+// LlmModerator
+// ---------------------------------------------------------
+
+#[derive(Deserialize)]
+struct LlmModerationDTO {
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+pub struct LlmModerator {
+    client: Arc<dyn LLMClient>,
+}
+
+impl LlmModerator {
+    pub fn new(client: Arc<dyn LLMClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Moderator for LlmModerator {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict> {
+        let system_prompt = "You are a content safety classifier for a memory store. \
+            Decide whether the content contains harassment, hate, sexual content involving minors, \
+            self-harm instructions, violent threats, or instructions for serious crimes. \
+            Return ONLY a JSON object: {\"flagged\": true|false, \"categories\": [\"snake_case_category\", ...]}.";
+        let result = self
+            .client
+            .generate(&format!("{}\n\nContent:\n{}", system_prompt, text))
+            .await?
+            .data;
+
+        let clean_json = result
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim();
+        let parsed: LlmModerationDTO = serde_json::from_str(clean_json)
+            .map_err(|error| anyhow!("unparseable moderation verdict: {}", error))?;
+
+        Ok(ModerationVerdict::reported(
+            parsed.flagged,
+            parsed.categories,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::config::ModerationIngestAction;
+    use std::collections::BTreeMap;
+
+    struct FailingModerator;
+
+    #[async_trait]
+    impl Moderator for FailingModerator {
+        async fn moderate(&self, _text: &str) -> Result<ModerationVerdict> {
+            Err(anyhow!("moderation service unavailable"))
+        }
+    }
+
+    fn regex_moderator() -> RegexModerator {
+        RegexModerator::new(&BTreeMap::from([
+            ("ssn".to_string(), r"\b\d{3}-\d{2}-\d{4}\b".to_string()),
+            ("profanity".to_string(), r"(?i)\bdarn\b".to_string()),
+        ]))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_regex_moderator_flags_matching_categories() {
+        let moderator = regex_moderator();
+
+        let verdict = moderator
+            .moderate("Darn, my SSN is 123-45-6789")
+            .await
+            .unwrap();
+        assert!(verdict.flagged);
+        assert_eq!(verdict.categories, vec!["profanity", "ssn"]);
+
+        let clean = moderator.moderate("I like green tea").await.unwrap();
+        assert_eq!(clean, ModerationVerdict::default());
+    }
+
+    #[test]
+    fn test_regex_moderator_rejects_invalid_config() {
+        assert!(RegexModerator::new(&BTreeMap::new()).is_err());
+        assert!(
+            RegexModerator::new(&BTreeMap::from([("broken".to_string(), "(".to_string())]))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_content_moderation_applies_app_policy_and_fails_open() {
+        let mut config = ModerationConfig::default();
+        config.apps.insert(
+            "kids-tutor".to_string(),
+            ModerationPolicy {
+                ingest: ModerationIngestAction::Block,
+                strip_from_retrieval: true,
+            },
+        );
+
+        let moderation = ContentModeration::new(Arc::new(regex_moderator()), config.clone());
+        assert_eq!(
+            moderation.policy_for(Some("kids-tutor")).ingest,
+            ModerationIngestAction::Block
+        );
+        assert_eq!(
+            moderation.policy_for(Some("other")).ingest,
+            ModerationIngestAction::Allow
+        );
+        assert!(moderation.check("123-45-6789").await.flagged);
+        assert!(!moderation.check("   ").await.flagged);
+
+        let failing = ContentModeration::new(Arc::new(FailingModerator), config);
+        assert!(!failing.check("123-45-6789").await.flagged);
+    }
+
+    #[test]
+    fn test_reported_verdict_trusts_the_flag() {
+        assert_eq!(
+            ModerationVerdict::reported(false, vec!["hate".to_string()]),
+            ModerationVerdict::default()
+        );
+        assert_eq!(
+            ModerationVerdict::reported(true, Vec::new()).categories,
+            vec!["flagged"]
+        );
+    }
+
+    #[test]
+    fn test_event_text_skips_media_urls() {
+        assert_eq!(
+            event_text(&EventContent::Text("hello".to_string())).as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            event_text(&EventContent::Json(serde_json::json!({"a": 1}))).as_deref(),
+            Some(r#"{"a":1}"#)
+        );
+        assert!(event_text(&EventContent::Image(
+            "https://example.com/a.png".to_string()
+        ))
+        .is_none());
+    }
+}
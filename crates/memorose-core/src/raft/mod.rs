@@ -1,17 +1,41 @@
-use self::types::MemoroseTypeConfig;
+use self::types::{MemoroseNode, MemoroseTypeConfig};
+use memorose_common::config::AppConfig;
 use openraft::{Config, Raft, SnapshotPolicy};
 use std::sync::Arc;
 
 pub mod network;
+pub mod progress;
 pub mod storage;
 pub mod types;
 
 pub type MemoroseRaft = Raft<MemoroseTypeConfig>;
 
+/// Base URL of the leader's HTTP API. The address the leader advertises in Raft
+/// membership wins; nodes recorded before that existed fall back to their
+/// `sharding.nodes` entry. An unspecified bind host such as `0.0.0.0` is replaced
+/// by the host of the leader's Raft address.
+pub fn leader_base_url(
+    config: &AppConfig,
+    metrics: &openraft::RaftMetrics<u64, MemoroseNode>,
+    leader_id: u64,
+) -> Option<String> {
+    let node = metrics.membership_config.membership().get_node(&leader_id);
+    let http_addr = node
+        .map(|node| node.http_addr.as_str())
+        .filter(|addr| !addr.is_empty())
+        .or_else(|| config.http_addr_for_raft_node(leader_id))?;
+    let (host, port) = http_addr.rsplit_once(':')?;
+    let host = match host {
+        "" | "0.0.0.0" | "[::]" => node?.addr.rsplit_once(':')?.0,
+        host => host,
+    };
+    Some(format!("http://{}:{}", host, port))
+}
+
 pub async fn start_raft_node(
     node_id: u64,
     engine: crate::MemoroseEngine,
-    config: memorose_common::config::AppConfig,
+    config: AppConfig,
 ) -> Result<MemoroseRaft, openraft::error::Fatal<u64>> {
     tracing::info!(
         "Starting Raft node {} with snapshot_logs={}",
@@ -27,7 +51,15 @@ pub async fn start_raft_node(
     };
 
     let raft_config = Arc::new(raft_config);
-    let storage = storage::MemoroseRaftStorage::new(engine);
+    let mut storage = storage::MemoroseRaftStorage::new(engine);
+    let recovery = storage.recover().await?;
+    if recovery.unclean_shutdown {
+        tracing::warn!(
+            "Recovered node {} after unclean shutdown: {:?}",
+            node_id,
+            recovery
+        );
+    }
     let (log_store, state_machine) = openraft::storage::Adaptor::new(storage);
     let network = network::MemoroseNetworkFactory::default();
 
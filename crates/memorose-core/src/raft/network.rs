@@ -4,12 +4,13 @@ use openraft::raft::{
     AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
     VoteRequest, VoteResponse,
 };
-use openraft::BasicNode;
 use openraft::{RaftNetwork, RaftNetworkFactory};
+use std::sync::Arc;
 use tonic::transport::Channel;
 use tonic::Request;
 
-use super::types::MemoroseTypeConfig;
+use super::progress::CatchUpTracker;
+use super::types::{MemoroseNode, MemoroseTypeConfig};
 
 // Include the generated gRPC code
 pub mod raft_proto {
@@ -50,7 +51,7 @@ impl RaftNetwork<MemoroseTypeConfig> for MemoroseNetworkConnection {
         &mut self,
         rpc: AppendEntriesRequest<MemoroseTypeConfig>,
         _option: RPCOption,
-    ) -> Result<AppendEntriesResponse<u64>, RPCError<u64, BasicNode, RaftError<u64>>> {
+    ) -> Result<AppendEntriesResponse<u64>, RPCError<u64, MemoroseNode, RaftError<u64>>> {
         let data = serde_json::to_vec(&rpc).map_err(to_rpc_err)?;
         let request = Request::new(RaftRequest { data });
 
@@ -75,7 +76,7 @@ impl RaftNetwork<MemoroseTypeConfig> for MemoroseNetworkConnection {
         _option: RPCOption,
     ) -> Result<
         InstallSnapshotResponse<u64>,
-        RPCError<u64, BasicNode, RaftError<u64, InstallSnapshotError>>,
+        RPCError<u64, MemoroseNode, RaftError<u64, InstallSnapshotError>>,
     > {
         let data = serde_json::to_vec(&rpc).map_err(to_rpc_err_snapshot)?;
         let request = Request::new(RaftRequest { data });
@@ -99,7 +100,7 @@ impl RaftNetwork<MemoroseTypeConfig> for MemoroseNetworkConnection {
         &mut self,
         rpc: VoteRequest<u64>,
         _option: RPCOption,
-    ) -> Result<VoteResponse<u64>, RPCError<u64, BasicNode, RaftError<u64>>> {
+    ) -> Result<VoteResponse<u64>, RPCError<u64, MemoroseNode, RaftError<u64>>> {
         let data = serde_json::to_vec(&rpc).map_err(to_rpc_err)?;
         let request = Request::new(RaftRequest { data });
 
@@ -118,13 +119,13 @@ impl RaftNetwork<MemoroseTypeConfig> for MemoroseNetworkConnection {
     }
 }
 
-fn to_rpc_err<E: std::error::Error + 'static>(e: E) -> RPCError<u64, BasicNode, RaftError<u64>> {
+fn to_rpc_err<E: std::error::Error + 'static>(e: E) -> RPCError<u64, MemoroseNode, RaftError<u64>> {
     RPCError::Network(openraft::error::NetworkError::new(&e))
 }
 
 fn to_rpc_err_snapshot<E: std::error::Error + 'static>(
     e: E,
-) -> RPCError<u64, BasicNode, RaftError<u64, InstallSnapshotError>> {
+) -> RPCError<u64, MemoroseNode, RaftError<u64, InstallSnapshotError>> {
     RPCError::Network(openraft::error::NetworkError::new(&e))
 }
 
@@ -133,11 +134,12 @@ use raft_proto::RaftResponse;
 
 pub struct MemoroseRaftServer {
     raft: super::MemoroseRaft,
+    catch_up: Arc<CatchUpTracker>,
 }
 
 impl MemoroseRaftServer {
-    pub fn new(raft: super::MemoroseRaft) -> Self {
-        Self { raft }
+    pub fn new(raft: super::MemoroseRaft, catch_up: Arc<CatchUpTracker>) -> Self {
+        Self { raft, catch_up }
     }
 }
 
@@ -150,6 +152,8 @@ impl RaftService for MemoroseRaftServer {
         let req: AppendEntriesRequest<MemoroseTypeConfig> =
             serde_json::from_slice(&request.into_inner().data)
                 .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+        self.catch_up
+            .observe_leader_commit(req.leader_commit.map(|log_id| log_id.index));
 
         let res = self
             .raft
@@ -168,12 +172,15 @@ impl RaftService for MemoroseRaftServer {
         let req: InstallSnapshotRequest<MemoroseTypeConfig> =
             serde_json::from_slice(&request.into_inner().data)
                 .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+        let snapshot_index = req.meta.last_log_id.map(|log_id| log_id.index);
+        let (offset, len, done) = (req.offset, req.data.len(), req.done);
 
-        let res = self
-            .raft
-            .install_snapshot(req)
-            .await
-            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let res = self.raft.install_snapshot(req).await.map_err(|e| {
+            self.catch_up.abort_snapshot();
+            tonic::Status::internal(e.to_string())
+        })?;
+        self.catch_up
+            .observe_snapshot_chunk(snapshot_index, offset, len, done);
 
         let data = serde_json::to_vec(&res).map_err(|e| tonic::Status::internal(e.to_string()))?;
         Ok(tonic::Response::new(RaftResponse { data }))
@@ -200,8 +207,9 @@ impl RaftService for MemoroseRaftServer {
 pub async fn run_raft_server(
     addr: std::net::SocketAddr,
     raft: super::MemoroseRaft,
+    catch_up: Arc<CatchUpTracker>,
 ) -> Result<(), tonic::transport::Error> {
-    let service = MemoroseRaftServer::new(raft);
+    let service = MemoroseRaftServer::new(raft, catch_up);
     tonic::transport::Server::builder()
         .add_service(RaftServiceServer::new(service))
         .serve(addr)
@@ -214,7 +222,7 @@ pub struct MemoroseNetworkFactory {}
 impl RaftNetworkFactory<MemoroseTypeConfig> for MemoroseNetworkFactory {
     type Network = MemoroseNetworkConnection;
 
-    async fn new_client(&mut self, _target: u64, node: &BasicNode) -> Self::Network {
+    async fn new_client(&mut self, _target: u64, node: &MemoroseNode) -> Self::Network {
         let addr = format!("http://{}", node.addr);
         MemoroseNetworkConnection::new(addr)
     }
@@ -224,7 +232,7 @@ impl RaftNetworkFactory<MemoroseTypeConfig> for MemoroseNetworkFactory {
 mod tests {
     use super::*;
     use openraft::error::RPCError;
-    use openraft::{BasicNode, LeaderId, LogId, SnapshotMeta, Vote};
+    use openraft::{LeaderId, LogId, SnapshotMeta, Vote};
     use std::time::Duration;
 
     #[tokio::test]
@@ -256,7 +264,7 @@ mod tests {
     #[tokio::test]
     async fn test_factory_new_client_formats_http_addr() {
         let mut factory = MemoroseNetworkFactory::default();
-        let node = BasicNode::new("127.0.0.1:3100");
+        let node = MemoroseNode::new("127.0.0.1:3100", "127.0.0.1:3000");
 
         let connection = factory.new_client(1, &node).await;
 
@@ -321,7 +329,6 @@ mod tests {
     #[tokio::test]
     async fn test_memorose_raft_server_rejects_invalid_json() -> anyhow::Result<()> {
         use openraft::{Config, Raft};
-        use std::sync::Arc;
 
         let temp_dir = tempfile::tempdir()?;
         let engine = crate::engine::MemoroseEngine::new_with_default_threshold(
@@ -338,7 +345,7 @@ mod tests {
         let raft = Raft::new(1, config.clone(), network, log_store, state_machine)
             .await
             .unwrap();
-        let server = MemoroseRaftServer::new(raft);
+        let server = MemoroseRaftServer::new(raft, Arc::new(CatchUpTracker::default()));
 
         let req1 = tonic::Request::new(raft_proto::RaftRequest {
             data: b"invalid".to_vec(),
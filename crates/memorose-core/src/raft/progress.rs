@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// What a follower has learned from its leader's RPCs while catching up.
+///
+/// Raft metrics only describe the local log, so the leader's commit index and any
+/// in-flight snapshot transfer are recorded here by the RPC server as requests arrive.
+#[derive(Debug, Default)]
+pub struct CatchUpTracker {
+    /// Highest `leader_commit` seen in an AppendEntries request, plus one; 0 when none yet.
+    leader_commit: AtomicU64,
+    snapshot_installing: AtomicBool,
+    /// Last log index covered by the snapshot being (or last) installed.
+    snapshot_index: AtomicU64,
+    snapshot_bytes_received: AtomicU64,
+}
+
+/// Catch-up state of one shard on this node.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CatchUpProgress {
+    pub leader_commit_index: Option<u64>,
+    pub last_applied: u64,
+    /// Committed entries not yet applied here.
+    pub lag: Option<u64>,
+    /// `last_applied` as a share of the leader's commit index, 0-100.
+    pub percent: Option<f64>,
+    pub snapshot_installing: bool,
+    pub snapshot_index: Option<u64>,
+    pub snapshot_bytes_received: u64,
+}
+
+impl CatchUpTracker {
+    pub fn observe_leader_commit(&self, index: Option<u64>) {
+        if let Some(index) = index {
+            self.leader_commit.fetch_max(index + 1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record one snapshot chunk; `offset` 0 starts a new transfer.
+    pub fn observe_snapshot_chunk(&self, index: Option<u64>, offset: u64, len: usize, done: bool) {
+        if offset == 0 {
+            self.snapshot_index
+                .store(index.unwrap_or_default(), Ordering::Relaxed);
+        }
+        self.snapshot_bytes_received
+            .store(offset + len as u64, Ordering::Relaxed);
+        self.snapshot_installing.store(!done, Ordering::Relaxed);
+        if done {
+            self.observe_leader_commit(index);
+        }
+    }
+
+    /// A snapshot chunk was rejected; the leader restarts the transfer from scratch.
+    pub fn abort_snapshot(&self) {
+        self.snapshot_installing.store(false, Ordering::Relaxed);
+    }
+
+    /// Catch-up state given this node's Raft metrics. A leader is its own reference point,
+    /// so it only waits on entries it has not applied yet.
+    pub fn progress(
+        &self,
+        metrics: &openraft::RaftMetrics<u64, super::types::MemoroseNode>,
+    ) -> CatchUpProgress {
+        let last_applied = metrics.last_applied.map(|l| l.index).unwrap_or_default();
+        let leader_commit_index = if metrics.current_leader == Some(metrics.id) {
+            metrics.last_log_index
+        } else {
+            self.leader_commit.load(Ordering::Relaxed).checked_sub(1)
+        };
+        let lag = leader_commit_index.map(|commit| commit.saturating_sub(last_applied));
+        let percent = leader_commit_index.map(|commit| {
+            if commit == 0 {
+                100.0
+            } else {
+                (last_applied.min(commit) as f64 / commit as f64 * 100.0).round()
+            }
+        });
+        let snapshot_index = self.snapshot_index.load(Ordering::Relaxed);
+        CatchUpProgress {
+            leader_commit_index,
+            last_applied,
+            lag,
+            percent,
+            snapshot_installing: self.snapshot_installing.load(Ordering::Relaxed),
+            snapshot_index: (snapshot_index > 0).then_some(snapshot_index),
+            snapshot_bytes_received: self.snapshot_bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl CatchUpProgress {
+    /// Whether the shard is close enough to its leader to serve reads.
+    pub fn is_caught_up(&self, max_lag: u64) -> bool {
+        !self.snapshot_installing && self.lag.is_some_and(|lag| lag <= max_lag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(lag: Option<u64>, snapshot_installing: bool) -> CatchUpProgress {
+        CatchUpProgress {
+            leader_commit_index: lag.map(|lag| lag + 10),
+            last_applied: 10,
+            lag,
+            percent: None,
+            snapshot_installing,
+            snapshot_index: None,
+            snapshot_bytes_received: 0,
+        }
+    }
+
+    #[test]
+    fn test_is_caught_up_requires_known_lag_within_limit() {
+        assert!(progress(Some(0), false).is_caught_up(0));
+        assert!(progress(Some(5), false).is_caught_up(5));
+        assert!(!progress(Some(6), false).is_caught_up(5));
+        assert!(!progress(None, false).is_caught_up(5));
+        assert!(!progress(Some(0), true).is_caught_up(5));
+    }
+
+    #[test]
+    fn test_tracker_records_leader_commit_and_snapshot_chunks() {
+        let tracker = CatchUpTracker::default();
+        tracker.observe_leader_commit(Some(0));
+        assert_eq!(tracker.leader_commit.load(Ordering::Relaxed), 1);
+        tracker.observe_leader_commit(Some(42));
+        tracker.observe_leader_commit(Some(7));
+        assert_eq!(tracker.leader_commit.load(Ordering::Relaxed), 43);
+
+        tracker.observe_snapshot_chunk(Some(100), 0, 512, false);
+        assert!(tracker.snapshot_installing.load(Ordering::Relaxed));
+        tracker.observe_snapshot_chunk(Some(100), 512, 256, true);
+        assert!(!tracker.snapshot_installing.load(Ordering::Relaxed));
+        assert_eq!(tracker.snapshot_bytes_received.load(Ordering::Relaxed), 768);
+        assert_eq!(tracker.snapshot_index.load(Ordering::Relaxed), 100);
+        assert_eq!(tracker.leader_commit.load(Ordering::Relaxed), 101);
+    }
+}
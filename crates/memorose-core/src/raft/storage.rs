@@ -1,9 +1,9 @@
-use super::types::MemoroseTypeConfig;
-use crate::MemoroseEngine;
+use super::types::{ClientRequest, MemoroseNode, MemoroseTypeConfig};
+use crate::{MemoroseEngine, RecoveryReport};
 use openraft::storage::LogState;
 use openraft::{
-    BasicNode, Entry, LogId, RaftLogReader, RaftSnapshotBuilder, RaftStorage, Snapshot,
-    SnapshotMeta, StorageError, Vote,
+    Entry, LogId, RaftLogReader, RaftSnapshotBuilder, RaftStorage, Snapshot, SnapshotMeta,
+    StorageError, Vote,
 };
 use std::io::Cursor;
 use std::ops::RangeBounds;
@@ -28,7 +28,7 @@ fn storage_io_error(
 }
 
 struct StoredSnapshot {
-    meta: SnapshotMeta<u64, BasicNode>,
+    meta: SnapshotMeta<u64, MemoroseNode>,
     data: Vec<u8>,
 }
 
@@ -69,6 +69,463 @@ impl MemoroseRaftStorage {
             .expect("Engine missing")
             .clone()
     }
+
+    /// Startup recovery, run before the Raft node is created so nothing is served until it
+    /// succeeds. Verifies `raft:last_applied` against the log; after an unclean shutdown it
+    /// also replays graph writes of entries applied past the durable watermark (RocksDB
+    /// writes are logged, buffered Lance edges are not) and runs a repairing consistency
+    /// pass over the vector table and text index.
+    pub async fn recover(&mut self) -> Result<RecoveryReport, StorageError<u64>> {
+        let engine = self.get_engine().await;
+        let unclean_shutdown = engine.mark_session_open().map_err(|e| {
+            storage_io_error(openraft::ErrorSubject::Store, openraft::ErrorVerb::Write, e)
+        })?;
+        let (last_applied, _) = self.last_applied_state().await?;
+        let durable_applied = engine
+            .system_kv()
+            .get(b"raft:durable_applied")
+            .map_err(|e| {
+                storage_io_error(openraft::ErrorSubject::Store, openraft::ErrorVerb::Read, e)
+            })?
+            .and_then(|v| serde_json::from_slice::<LogId<u64>>(&v).ok());
+        let mut report = RecoveryReport {
+            unclean_shutdown,
+            last_applied_index: last_applied.map(|id| id.index),
+            durable_applied_index: durable_applied.map(|id| id.index),
+            ..Default::default()
+        };
+
+        if let Some(applied) = last_applied {
+            let log_state = self.get_log_state().await?;
+            let last_log_index = log_state.last_log_id.map_or(0, |id| id.index);
+            let applied_entry = self
+                .try_get_log_entries(applied.index..=applied.index)
+                .await?;
+            match applied_entry.first() {
+                Some(entry) if entry.log_id != applied => {
+                    return Err(storage_io_error(
+                        openraft::ErrorSubject::Log(applied),
+                        openraft::ErrorVerb::Read,
+                        format!(
+                            "last applied {} does not match log entry {}",
+                            applied, entry.log_id
+                        ),
+                    ));
+                }
+                None if applied.index > last_log_index => {
+                    return Err(storage_io_error(
+                        openraft::ErrorSubject::Log(applied),
+                        openraft::ErrorVerb::Read,
+                        format!(
+                            "last applied {} is ahead of the last log index {}",
+                            applied, last_log_index
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if !unclean_shutdown {
+            return Ok(report);
+        }
+
+        if let (Some(applied), Some(durable)) = (last_applied, durable_applied) {
+            if durable.index < applied.index {
+                let entries = self
+                    .try_get_log_entries(durable.index + 1..=applied.index)
+                    .await?;
+                for entry in &entries {
+                    report.checked_entries += 1;
+                    let edges = match &entry.payload {
+                        openraft::EntryPayload::Normal(
+                            crate::raft::types::ClientRequest::UpdateGraph(edge),
+                        ) => std::slice::from_ref(edge),
+                        openraft::EntryPayload::Normal(
+                            crate::raft::types::ClientRequest::UpdateGraphBatch(edges),
+                        ) => edges.as_slice(),
+                        _ => continue,
+                    };
+                    for edge in edges {
+                        let restored =
+                            engine
+                                .graph()
+                                .restore_unflushed_edge(edge)
+                                .await
+                                .map_err(|e| {
+                                    storage_io_error(
+                                        openraft::ErrorSubject::Log(entry.log_id),
+                                        openraft::ErrorVerb::Write,
+                                        e,
+                                    )
+                                })?;
+                        if restored {
+                            report.replayed_edges += 1;
+                        }
+                    }
+                }
+            }
+        } else if last_applied.is_some() {
+            tracing::warn!(
+                "No durable applied watermark recorded; skipping log replay and relying on the consistency pass"
+            );
+        }
+
+        report.integrity = Some(engine.converge_derived_stores().await.map_err(|e| {
+            storage_io_error(openraft::ErrorSubject::Store, openraft::ErrorVerb::Write, e)
+        })?);
+        Ok(report)
+    }
+}
+
+/// Apply one replicated request to the engine, returning whether it took effect. Shared by
+/// the state machine and by writes that bypass Raft, so both paths behave the same.
+pub async fn apply_client_request(engine: &MemoroseEngine, req: &ClientRequest) -> bool {
+    match req {
+        ClientRequest::ApplyReplicated {
+            source_region,
+            requests,
+        } => crate::replication::apply_replicated(engine, source_region, requests).await,
+        req => apply_single_request(engine, req).await,
+    }
+}
+
+pub(crate) async fn apply_single_request(engine: &MemoroseEngine, req: &ClientRequest) -> bool {
+    match req {
+        ClientRequest::ApplyReplicated { .. } => {
+            tracing::error!("Refusing to apply a replicated batch nested in another");
+            false
+        }
+        ClientRequest::IngestEvent(event) => {
+            match engine.ingest_event_directly(event.clone()).await {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply event: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::IngestEvents(events) => {
+            match engine.ingest_events_directly(events.clone()).await {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply batched events: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::UpdateGraph(edge) => match engine.add_graph_edge(&edge).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply graph update: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::UpdateGraphBatch(edges) => match engine.add_graph_edges(edges).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply batched graph update: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::UpsertTask(task) => match engine.create_l3_task(task).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply task upsert: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::UpdateTaskStatus(update) => {
+            match engine.apply_l3_task_status_update(update).await {
+                Ok(task) => task.is_some(),
+                Err(e) => {
+                    tracing::error!("Failed to apply task status update: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::PutShareGrant(grant) => match engine.put_share_grant(grant) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply share grant: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::RevokeShareGrant {
+            owner_user_id,
+            grant_id,
+        } => match engine.revoke_share_grant(owner_user_id, *grant_id) {
+            Ok(revoked) => revoked,
+            Err(e) => {
+                tracing::error!("Failed to revoke share grant: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::PutRelationDefinition(definition) => {
+            match engine.put_relation_definition(definition) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply relation definition: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::DeleteRelationDefinition { user_id, name } => {
+            match engine.delete_relation_definition(user_id, name) {
+                Ok(deleted) => deleted,
+                Err(e) => {
+                    tracing::error!("Failed to delete relation definition: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::UpdateGroupMembership(update) => {
+            match engine.apply_group_membership_update(update) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply group membership: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::SetOrgPolicy(update) => {
+            match engine.set_org_policy(&update.org_id, &update.policy) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply org policy: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::PutUserKey(record) => match engine.put_user_key(record).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply user encryption key: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::CreateMemoryUnit(unit) => {
+            match engine
+                .apply_replicated_memory_units(vec![unit.clone()])
+                .await
+            {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply memory unit creation: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::ForgetMemoryUnits {
+            user_id,
+            tombstones,
+        } => match engine
+            .apply_memory_unit_tombstones(user_id, tombstones)
+            .await
+        {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply memory unit tombstones: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::TrashMemoryUnit(entry) => {
+            match engine.apply_memory_unit_trash(entry).await {
+                Ok(trashed) => trashed,
+                Err(e) => {
+                    tracing::error!("Failed to apply memory unit trash: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::RestoreMemoryUnit { user_id, unit_id } => {
+            match engine.restore_memory_unit(user_id, *unit_id).await {
+                Ok(restored) => restored,
+                Err(e) => {
+                    tracing::error!("Failed to restore memory unit: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::DeleteMemoryUnit { user_id, unit_id } => {
+            match engine.get_memory_unit_including_forgotten(user_id, *unit_id) {
+                Ok(None) => false,
+                Ok(Some(_)) => match engine.delete_memory_unit_hard(user_id, *unit_id).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::error!("Failed to delete memory unit: {:?}", e);
+                        false
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("Failed to delete memory unit: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::SetMemoryUnitPinned {
+            user_id,
+            unit_id,
+            pinned,
+        } => match engine
+            .set_memory_unit_pinned(user_id, *unit_id, *pinned)
+            .await
+        {
+            Ok(unit) => unit.is_some(),
+            Err(e) => {
+                tracing::error!("Failed to apply memory unit pin: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::PurgeExpiredTrash { now } => match engine.purge_expired_trash(*now).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to purge expired trash: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::PatchStructuredProfile {
+            user_id,
+            attributes,
+            updated_at,
+        } => {
+            match engine.apply_structured_profile_patch(user_id, attributes.clone(), *updated_at) {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply profile patch: {:?}", e);
+                    false
+                }
+            }
+        }
+        ClientRequest::MergeStructuredProfile {
+            user_id,
+            attributes,
+            updated_at,
+        } => match engine.merge_structured_profile(user_id, attributes.clone(), *updated_at) {
+            Ok(changed) => changed,
+            Err(e) => {
+                tracing::error!("Failed to apply extracted profile attributes: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::SetUserProfilePointer {
+            user_id,
+            current,
+            previous,
+        } => match engine.set_user_profile_pointer(user_id, *current, *previous) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply profile pointer: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::EnqueueJob { kind, user_id } => match engine.enqueue_job(*kind, user_id) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply job enqueue: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::ClaimJob {
+            kind,
+            user_id,
+            holder,
+            term,
+            max_attempts,
+            now,
+            expires_at,
+        } => match engine.claim_job(
+            *kind,
+            user_id,
+            holder,
+            *term,
+            *expires_at - *now,
+            *max_attempts,
+            *now,
+        ) {
+            Ok(claimed) => claimed.is_some(),
+            Err(e) => {
+                tracing::error!("Failed to apply job claim: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::HeartbeatJob {
+            kind,
+            user_id,
+            holder,
+            now,
+            expires_at,
+        } => match engine.heartbeat_job(*kind, user_id, holder, *expires_at - *now, *now) {
+            Ok(held) => held,
+            Err(e) => {
+                tracing::error!("Failed to apply job heartbeat: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::CompleteJob { job, holder } => match engine.complete_job(job, holder) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply job completion: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::FailJob {
+            job,
+            holder,
+            error,
+            max_attempts,
+            now,
+            retry_at,
+        } => match engine.fail_job(job, holder, error, *max_attempts, *retry_at - *now, *now) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply job failure: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::ReleaseJob { job, holder } => match engine.release_job(job, holder) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply job release: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::ConsumeReflectionBatch {
+            user_id,
+            consumed_units,
+            consumed_tokens,
+            next_first_event_tx_micros,
+            next_first_event_id,
+        } => match engine.consume_reflection_marker_batch(
+            user_id,
+            *consumed_units,
+            *consumed_tokens,
+            *next_first_event_tx_micros,
+            next_first_event_id.clone(),
+        ) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply reflection progress: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::PutMemoryStream(stream) => match engine.put_memory_stream(stream) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to apply stream record: {:?}", e);
+                false
+            }
+        },
+        ClientRequest::RecordRetrievalFeedback(feedback) => {
+            match engine.apply_retrieval_feedback(feedback).await {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("Failed to apply retrieval feedback: {:?}", e);
+                    false
+                }
+            }
+        }
+    }
 }
 
 impl RaftLogReader<MemoroseTypeConfig> for MemoroseRaftStorage {
@@ -130,6 +587,15 @@ impl RaftSnapshotBuilder<MemoroseTypeConfig> for MemoroseRaftStorage {
         let (last_applied, _) = self.last_applied_state().await?;
         let last_log_id = last_applied.unwrap_or_default();
 
+        // Buffered graph edges would otherwise be missing from the exported Lance table.
+        engine.checkpoint_derived_stores().await.map_err(|e| {
+            storage_io_error(
+                openraft::ErrorSubject::Snapshot(None),
+                openraft::ErrorVerb::Write,
+                e,
+            )
+        })?;
+
         let temp_dir = tempfile::tempdir().map_err(|e| StorageError::IO {
             source: openraft::StorageIOError::new(
                 openraft::ErrorSubject::Snapshot(None),
@@ -457,7 +923,7 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
     ) -> Result<
         (
             Option<LogId<u64>>,
-            openraft::StoredMembership<u64, BasicNode>,
+            openraft::StoredMembership<u64, MemoroseNode>,
         ),
         StorageError<u64>,
     > {
@@ -496,7 +962,7 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
                 })?;
 
         let membership = if let Some(v) = persisted_membership {
-            serde_json::from_slice::<openraft::StoredMembership<u64, BasicNode>>(&v).unwrap()
+            serde_json::from_slice::<openraft::StoredMembership<u64, MemoroseNode>>(&v).unwrap()
         } else if last_applied.is_some() {
             // Migration: reconstruct membership from log entries
             let pairs = engine
@@ -550,38 +1016,10 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
                 openraft::EntryPayload::Blank => {
                     responses.push(crate::raft::types::ClientResponse { success: true });
                 }
-                openraft::EntryPayload::Normal(req) => match req {
-                    crate::raft::types::ClientRequest::IngestEvent(event) => {
-                        let success = match engine.ingest_event_directly(event.clone()).await {
-                            Ok(_) => true,
-                            Err(e) => {
-                                tracing::error!("Failed to apply event: {:?}", e);
-                                false
-                            }
-                        };
-                        responses.push(crate::raft::types::ClientResponse { success });
-                    }
-                    crate::raft::types::ClientRequest::IngestEvents(events) => {
-                        let success = match engine.ingest_events_directly(events.clone()).await {
-                            Ok(_) => true,
-                            Err(e) => {
-                                tracing::error!("Failed to apply batched events: {:?}", e);
-                                false
-                            }
-                        };
-                        responses.push(crate::raft::types::ClientResponse { success });
-                    }
-                    crate::raft::types::ClientRequest::UpdateGraph(edge) => {
-                        let success = match engine.graph().add_edge(&edge).await {
-                            Ok(_) => true,
-                            Err(e) => {
-                                tracing::error!("Failed to apply graph update: {:?}", e);
-                                false
-                            }
-                        };
-                        responses.push(crate::raft::types::ClientResponse { success });
-                    }
-                },
+                openraft::EntryPayload::Normal(req) => {
+                    let success = apply_client_request(&engine, req).await;
+                    responses.push(crate::raft::types::ClientResponse { success });
+                }
                 openraft::EntryPayload::Membership(membership) => {
                     // Persist the membership so it can be restored on restart
                     let stored =
@@ -631,7 +1069,7 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
 
     async fn install_snapshot(
         &mut self,
-        _meta: &SnapshotMeta<u64, BasicNode>,
+        _meta: &SnapshotMeta<u64, MemoroseNode>,
         snapshot: Box<Cursor<Vec<u8>>>,
     ) -> Result<(), StorageError<u64>> {
         let data = snapshot.into_inner();
@@ -656,13 +1094,8 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
             ),
         })?;
 
-        // 2. Close Engine and release locks
-        {
-            let mut engine_lock = self.engine.write().await;
-            *engine_lock = None;
-        }
-
-        // 3. Restore to temporary directory first
+        // 2. Verify and unpack into a temporary directory while the engine keeps
+        //    serving, so a corrupt snapshot never takes the node down
         MemoroseEngine::restore_from_snapshot(temp_tar_path.clone(), temp_extract_path.clone())
             .await
             .map_err(|e| StorageError::IO {
@@ -676,6 +1109,12 @@ impl RaftStorage<MemoroseTypeConfig> for MemoroseRaftStorage {
                 ),
             })?;
 
+        // 3. Close Engine and release locks
+        {
+            let mut engine_lock = self.engine.write().await;
+            *engine_lock = None;
+        }
+
         // 4. Atomically swap each data directory:
         //    Step 1: rename old -> old.bak  (preserves old data if step 2 fails)
         //    Step 2: rename new  -> old     (fast on same filesystem)
@@ -805,6 +1244,14 @@ mod tests {
     use tempfile::tempdir;
     use uuid::Uuid;
 
+    #[test]
+    fn test_membership_node_without_http_addr_still_deserializes() {
+        // Memberships persisted before nodes advertised an HTTP address.
+        let stored = r#"{"addr":"127.0.0.1:5001"}"#;
+        let node: MemoroseNode = serde_json::from_str(stored).unwrap();
+        assert_eq!(node, MemoroseNode::new("127.0.0.1:5001", ""));
+    }
+
     #[tokio::test]
     async fn test_save_and_read_vote() -> anyhow::Result<()> {
         let temp_dir = tempdir()?;
@@ -1087,6 +1534,66 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_recover_runs_consistency_pass_only_after_unclean_shutdown() -> anyhow::Result<()>
+    {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+        let entry = Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), 1),
+            payload: openraft::EntryPayload::Blank,
+        };
+        store.append_to_log(vec![entry.clone()]).await?;
+        store.apply_to_state_machine(&[entry.clone()]).await?;
+
+        let first = store.recover().await?;
+        assert!(!first.unclean_shutdown);
+        assert_eq!(first.last_applied_index, Some(1));
+        assert!(first.integrity.is_none());
+
+        // The session opened above was never closed.
+        let second = store.recover().await?;
+        assert!(second.unclean_shutdown);
+        assert!(second.integrity.is_some());
+        assert_eq!(
+            engine.system_kv().get(b"raft:durable_applied")?,
+            Some(serde_json::to_vec(&entry.log_id)?)
+        );
+
+        engine.close_session().await?;
+        assert!(!store.recover().await?.unclean_shutdown);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recover_rejects_last_applied_ahead_of_log() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+        store
+            .append_to_log(vec![Entry {
+                log_id: LogId::new(LeaderId::new(1, 1), 1),
+                payload: openraft::EntryPayload::Blank,
+            }])
+            .await?;
+        engine.system_kv().put(
+            b"raft:last_applied",
+            &serde_json::to_vec(&LogId::new(LeaderId::new(1, 1), 5))?,
+        )?;
+        assert!(store.recover().await.is_err());
+
+        // An entry at the applied index written under another term is rejected too.
+        engine.system_kv().put(
+            b"raft:last_applied",
+            &serde_json::to_vec(&LogId::new(LeaderId::new(2, 1), 1))?,
+        )?;
+        assert!(store.recover().await.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_last_applied_state_reconstructs_membership_from_logs() -> anyhow::Result<()> {
         let temp_dir = tempdir()?;
@@ -1094,9 +1601,9 @@ mod tests {
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
         let mut store = MemoroseRaftStorage::new(engine.clone());
 
-        let membership: openraft::Membership<u64, BasicNode> = BTreeMap::from([
-            (1, BasicNode::new("127.0.0.1:3001")),
-            (2, BasicNode::new("127.0.0.1:3002")),
+        let membership: openraft::Membership<u64, MemoroseNode> = BTreeMap::from([
+            (1, MemoroseNode::new("127.0.0.1:3001", "")),
+            (2, MemoroseNode::new("127.0.0.1:3002", "")),
         ])
         .into();
         let entry = Entry {
@@ -1140,8 +1647,8 @@ mod tests {
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
         let mut store = MemoroseRaftStorage::new(engine.clone());
 
-        let membership: openraft::Membership<u64, BasicNode> =
-            BTreeMap::from([(7, BasicNode::new("127.0.0.1:3007"))]).into();
+        let membership: openraft::Membership<u64, MemoroseNode> =
+            BTreeMap::from([(7, MemoroseNode::new("127.0.0.1:3007", ""))]).into();
         let stored =
             openraft::StoredMembership::new(Some(LogId::new(LeaderId::new(7, 1), 9)), membership);
         engine
@@ -1383,9 +1890,9 @@ mod tests {
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
         let mut store = MemoroseRaftStorage::new(engine.clone());
 
-        let membership: openraft::Membership<u64, BasicNode> = BTreeMap::from([
-            (1, BasicNode::new("127.0.0.1:3001")),
-            (2, BasicNode::new("127.0.0.1:3002")),
+        let membership: openraft::Membership<u64, MemoroseNode> = BTreeMap::from([
+            (1, MemoroseNode::new("127.0.0.1:3001", "")),
+            (2, MemoroseNode::new("127.0.0.1:3002", "")),
         ])
         .into();
         let entry = Entry {
@@ -1440,6 +1947,517 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_state_machine_update_graph_batch_stores_all_edges() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let source_id = Uuid::new_v4();
+        let edges: Vec<_> = (0..3)
+            .map(|_| {
+                memorose_common::GraphEdge::new(
+                    "test_user".to_string(),
+                    source_id,
+                    Uuid::new_v4(),
+                    memorose_common::RelationType::RelatedTo,
+                    0.5,
+                )
+            })
+            .collect();
+        let entry = Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), 4),
+            payload: openraft::EntryPayload::Normal(ClientRequest::UpdateGraphBatch(edges.clone())),
+        };
+
+        let responses = store.apply_to_state_machine(&[entry]).await?;
+        assert_eq!(responses.len(), 1);
+        assert!(responses[0].success);
+        // Batched edges are written straight to the table, without a buffer flush.
+        let outgoing = engine
+            .graph()
+            .get_outgoing_edges("test_user", source_id)
+            .await?;
+        assert_eq!(outgoing.len(), 3);
+        assert_eq!(
+            engine.dirty_community_nodes("test_user")?.len(),
+            4,
+            "every endpoint is marked for incremental community detection"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_task_commands_apply_in_order() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let task = memorose_common::L3Task::new(
+            None,
+            "test_user".into(),
+            Some("planner".into()),
+            "Ship release".into(),
+            String::new(),
+        );
+        let update = memorose_common::L3TaskStatusUpdate {
+            user_id: task.user_id.clone(),
+            task_id: task.task_id,
+            status: memorose_common::TaskStatus::Completed,
+            progress: None,
+            result_summary: Some("Tagged v1".into()),
+            updated_at: chrono::Utc::now(),
+        };
+        let missing = memorose_common::L3TaskStatusUpdate {
+            task_id: Uuid::new_v4(),
+            ..update.clone()
+        };
+        let entries = [
+            ClientRequest::UpsertTask(task.clone()),
+            ClientRequest::UpdateTaskStatus(update),
+            ClientRequest::UpdateTaskStatus(missing),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, request)| Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), index as u64 + 1),
+            payload: openraft::EntryPayload::Normal(request),
+        })
+        .collect::<Vec<_>>();
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert_eq!(
+            responses.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+        let stored = engine
+            .get_l3_task(&task.user_id, task.task_id)
+            .await?
+            .expect("task should be stored");
+        assert_eq!(stored.status, memorose_common::TaskStatus::Completed);
+        assert_eq!(stored.progress, 1.0);
+        assert_eq!(stored.result_summary.as_deref(), Some("Tagged v1"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_applies_created_memory_unit() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let mut unit = memorose_common::MemoryUnit::new(
+            None,
+            "test_user".into(),
+            Some("notes".into()),
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            "Prefers window seats".into(),
+            Some(vec![0.1; 384]),
+        );
+        unit.level = 2;
+        unit.keywords = vec!["travel".into()];
+        let entries = [Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), 1),
+            payload: openraft::EntryPayload::Normal(ClientRequest::CreateMemoryUnit(unit.clone())),
+        }];
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert!(responses[0].success);
+        let stored = engine
+            .get_memory_unit(&unit.user_id, unit.id)
+            .await?
+            .expect("memory unit should be stored");
+        assert_eq!(stored.content, "Prefers window seats");
+        assert_eq!(stored.level, 2);
+        assert_eq!(stored.keywords, vec!["travel".to_string()]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_stores_created_unit_without_linking_and_applies_tombstones(
+    ) -> anyhow::Result<()> {
+        use memorose_common::config::{LinkingConfig, LinkingMode};
+
+        let temp_dir = tempdir()?;
+        let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
+            .await?
+            .with_linking_config(LinkingConfig {
+                mode: LinkingMode::Deferred,
+                ..LinkingConfig::default()
+            });
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let mut old = memorose_common::MemoryUnit::new(
+            None,
+            "test_user".into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            "Lives in Berlin".into(),
+            Some(vec![0.1; 384]),
+        );
+        old.level = 1;
+        let mut new = old.clone();
+        new.id = Uuid::new_v4();
+        new.content = "Moved to Lisbon".into();
+        let tombstone = memorose_common::ForgettingTombstone {
+            user_id: "test_user".into(),
+            org_id: None,
+            target_kind: memorose_common::ForgetTargetKind::MemoryUnit,
+            target_id: old.id.to_string(),
+            reason_query: format!("Superseded by memory {}", new.id),
+            created_at: chrono::Utc::now(),
+            preview_id: Some(new.id.to_string()),
+            mode: memorose_common::ForgetMode::Logical,
+        };
+        let entries = [
+            Entry {
+                log_id: LogId::new(LeaderId::new(1, 1), 1),
+                payload: openraft::EntryPayload::Normal(ClientRequest::CreateMemoryUnit(
+                    old.clone(),
+                )),
+            },
+            Entry {
+                log_id: LogId::new(LeaderId::new(1, 1), 2),
+                payload: openraft::EntryPayload::Normal(ClientRequest::CreateMemoryUnit(
+                    new.clone(),
+                )),
+            },
+            Entry {
+                log_id: LogId::new(LeaderId::new(1, 1), 3),
+                payload: openraft::EntryPayload::Normal(ClientRequest::ForgetMemoryUnits {
+                    user_id: "test_user".into(),
+                    tombstones: vec![tombstone],
+                }),
+            },
+        ];
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert!(responses.iter().all(|response| response.success));
+        // Linking is left to the leader, so replicas queue nothing they would never drain.
+        assert_eq!(engine.count_pending_linking_jobs().await?, 0);
+        assert!(engine.is_memory_unit_forgotten("test_user", old.id)?);
+        assert!(!engine.is_memory_unit_forgotten("test_user", new.id)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_trash_commands_apply_the_leaders_entry() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let mut kept = memorose_common::MemoryUnit::new(
+            None,
+            "test_user".into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            "Prefers aisle seats".into(),
+            Some(vec![0.1; 384]),
+        );
+        kept.level = 1;
+        let mut purged = kept.clone();
+        purged.id = Uuid::new_v4();
+        purged.content = "Owns a bicycle".into();
+        let deleted_at = chrono::Utc::now() - chrono::Duration::days(40);
+        let trash = |unit_id| memorose_common::TrashedMemoryUnit {
+            user_id: "test_user".into(),
+            unit_id,
+            deleted_at,
+            purge_after: deleted_at + chrono::Duration::days(30),
+        };
+        let entries = [
+            ClientRequest::CreateMemoryUnit(kept.clone()),
+            ClientRequest::CreateMemoryUnit(purged.clone()),
+            ClientRequest::TrashMemoryUnit(trash(kept.id)),
+            ClientRequest::TrashMemoryUnit(trash(purged.id)),
+            ClientRequest::RestoreMemoryUnit {
+                user_id: "test_user".into(),
+                unit_id: kept.id,
+            },
+            ClientRequest::PurgeExpiredTrash {
+                now: chrono::Utc::now(),
+            },
+            ClientRequest::RestoreMemoryUnit {
+                user_id: "test_user".into(),
+                unit_id: purged.id,
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, request)| Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), index as u64 + 1),
+            payload: openraft::EntryPayload::Normal(request),
+        })
+        .collect::<Vec<_>>();
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert_eq!(
+            responses.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, true, true, true, true, true, false]
+        );
+        assert!(!engine.is_memory_unit_forgotten("test_user", kept.id)?);
+        assert!(engine
+            .get_memory_unit_including_forgotten("test_user", purged.id)?
+            .is_none());
+        assert!(engine.list_trashed_memory_units("test_user")?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_applies_memory_unit_pin() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let mut unit = memorose_common::MemoryUnit::new(
+            None,
+            "test_user".into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            "Allergic to peanuts".into(),
+            Some(vec![0.1; 384]),
+        );
+        unit.level = 2;
+        let entries = [
+            ClientRequest::CreateMemoryUnit(unit.clone()),
+            ClientRequest::SetMemoryUnitPinned {
+                user_id: "test_user".into(),
+                unit_id: unit.id,
+                pinned: true,
+            },
+            ClientRequest::SetMemoryUnitPinned {
+                user_id: "test_user".into(),
+                unit_id: Uuid::new_v4(),
+                pinned: true,
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, request)| Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), index as u64 + 1),
+            payload: openraft::EntryPayload::Normal(request),
+        })
+        .collect::<Vec<_>>();
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert_eq!(
+            responses.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+        let stored = engine
+            .get_memory_unit("test_user", unit.id)
+            .await?
+            .expect("memory unit should be stored");
+        assert!(stored.pinned);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_moves_claimed_job_to_the_next_leader() -> anyhow::Result<()> {
+        use crate::engine::types::BackgroundJobKind;
+
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+        let now = chrono::Utc::now();
+        let claim = |holder: &str, term: u64| ClientRequest::ClaimJob {
+            kind: BackgroundJobKind::Community,
+            user_id: "test_user".into(),
+            holder: holder.into(),
+            term,
+            max_attempts: 3,
+            now,
+            expires_at: now + chrono::Duration::seconds(600),
+        };
+        let entries_at = |leader: u64, first_index: u64, requests: Vec<ClientRequest>| {
+            requests
+                .into_iter()
+                .enumerate()
+                .map(|(offset, request)| Entry {
+                    log_id: LogId::new(LeaderId::new(leader, leader), first_index + offset as u64),
+                    payload: openraft::EntryPayload::Normal(request),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let responses = store
+            .apply_to_state_machine(&entries_at(
+                1,
+                1,
+                vec![
+                    ClientRequest::EnqueueJob {
+                        kind: BackgroundJobKind::Community,
+                        user_id: "test_user".into(),
+                    },
+                    claim("node-1", 1),
+                ],
+            ))
+            .await?;
+        assert!(responses.iter().all(|r| r.success));
+        let first_claim = engine
+            .get_job(BackgroundJobKind::Community, "test_user")?
+            .expect("job should be queued");
+
+        // Node 2 wins the election while node 1 still holds an unexpired lease. It takes the
+        // job over once; another claim in its term and node 1's late writes are refused.
+        let responses = store
+            .apply_to_state_machine(&entries_at(
+                2,
+                3,
+                vec![
+                    claim("node-2", 2),
+                    claim("node-3", 2),
+                    claim("node-1", 1),
+                    ClientRequest::CompleteJob {
+                        job: first_claim,
+                        holder: "node-1".into(),
+                    },
+                ],
+            ))
+            .await?;
+        assert_eq!(
+            responses.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, false, false, true]
+        );
+        let taken_over = engine
+            .get_job(BackgroundJobKind::Community, "test_user")?
+            .expect("the old leader's completion should not remove the job");
+        let lease = taken_over.lease.clone().expect("job should be leased");
+        assert_eq!((lease.holder.as_str(), lease.term), ("node-2", 2));
+        assert_eq!(taken_over.attempts, 2);
+
+        let responses = store
+            .apply_to_state_machine(&entries_at(
+                2,
+                7,
+                vec![ClientRequest::CompleteJob {
+                    job: taken_over,
+                    holder: "node-2".into(),
+                }],
+            ))
+            .await?;
+        assert!(responses[0].success);
+        assert!(engine
+            .get_job(BackgroundJobKind::Community, "test_user")?
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_profile_commands_keep_manual_values() -> anyhow::Result<()> {
+        use memorose_common::{ProfileValue, ProfileValueSource};
+        use std::collections::HashMap;
+
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let patched_at = chrono::Utc::now();
+        let profile_id = Uuid::new_v4();
+        let entries = [
+            ClientRequest::PatchStructuredProfile {
+                user_id: "test_user".into(),
+                attributes: HashMap::from([(
+                    "timezone".to_string(),
+                    Some(ProfileValue::Text("Europe/Berlin".into())),
+                )]),
+                updated_at: patched_at,
+            },
+            ClientRequest::MergeStructuredProfile {
+                user_id: "test_user".into(),
+                attributes: HashMap::from([
+                    ("timezone".to_string(), ProfileValue::Text("UTC".into())),
+                    ("language".to_string(), ProfileValue::Text("German".into())),
+                ]),
+                updated_at: patched_at + chrono::Duration::seconds(1),
+            },
+            ClientRequest::SetUserProfilePointer {
+                user_id: "test_user".into(),
+                current: profile_id,
+                previous: None,
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, request)| Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), index as u64 + 1),
+            payload: openraft::EntryPayload::Normal(request),
+        })
+        .collect::<Vec<_>>();
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert!(responses.iter().all(|response| response.success));
+        let profile = engine
+            .get_structured_profile("test_user")?
+            .expect("profile should be stored");
+        assert_eq!(
+            profile.attributes["timezone"].value,
+            ProfileValue::Text("Europe/Berlin".into())
+        );
+        assert_eq!(profile.attributes["timezone"].updated_at, patched_at);
+        assert_eq!(
+            profile.attributes["language"].source,
+            ProfileValueSource::Extracted
+        );
+        assert_eq!(
+            engine.user_profile_pointer("test_user")?,
+            (Some(profile_id), None)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_machine_share_commands_apply_in_order() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut store = MemoroseRaftStorage::new(engine.clone());
+
+        let grant = memorose_common::MemoryShareGrant::new(
+            "alice".into(),
+            memorose_common::ShareResource::Stream(Uuid::new_v4()),
+            memorose_common::ShareGrantee::Group("team".into()),
+        );
+        let entries = [
+            ClientRequest::PutShareGrant(grant.clone()),
+            ClientRequest::UpdateGroupMembership(memorose_common::GroupMembershipUpdate {
+                group_id: "team".into(),
+                user_id: "bob".into(),
+                member: true,
+            }),
+            ClientRequest::RevokeShareGrant {
+                owner_user_id: "alice".into(),
+                grant_id: Uuid::new_v4(),
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, request)| Entry {
+            log_id: LogId::new(LeaderId::new(1, 1), index as u64 + 1),
+            payload: openraft::EntryPayload::Normal(request),
+        })
+        .collect::<Vec<_>>();
+
+        let responses = store.apply_to_state_machine(&entries).await?;
+        assert_eq!(
+            responses.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+        assert_eq!(engine.list_grants_visible_to("bob")?, vec![grant]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_snapshot_build_and_install() -> anyhow::Result<()> {
         let temp_dir_src = tempdir()?;
@@ -1,5 +1,4 @@
 use memorose_common::Event;
-use openraft::BasicNode;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::Cursor;
@@ -13,6 +12,138 @@ pub enum ClientRequest {
     IngestEvents(Vec<Event>),
     /// Update or add an edge in the knowledge graph.
     UpdateGraph(memorose_common::GraphEdge),
+    /// Add many knowledge-graph edges in one raft entry.
+    UpdateGraphBatch(Vec<memorose_common::GraphEdge>),
+    /// Create or replace an L3 task (goal or subtask).
+    UpsertTask(memorose_common::L3Task),
+    /// Transition the status of an existing L3 task.
+    UpdateTaskStatus(memorose_common::L3TaskStatusUpdate),
+    /// Create or replace a memory share grant.
+    PutShareGrant(memorose_common::MemoryShareGrant),
+    /// Remove a memory share grant.
+    RevokeShareGrant {
+        owner_user_id: String,
+        grant_id: uuid::Uuid,
+    },
+    /// Add a user to, or remove them from, a sharing group.
+    UpdateGroupMembership(memorose_common::GroupMembershipUpdate),
+    /// Replace an organization's quota and retention policy.
+    SetOrgPolicy(memorose_common::OrgPolicyUpdate),
+    /// Register, replace or revoke a user's encryption key.
+    PutUserKey(memorose_common::UserKeyRecord),
+    /// Store a memory unit written directly by a client, bypassing consolidation. Replicas
+    /// only store it; the leader reconciles and links it and replicates the results.
+    CreateMemoryUnit(memorose_common::MemoryUnit),
+    /// Forget a user's memory units with the given tombstones, e.g. those superseded when
+    /// the leader reconciled a newly created unit.
+    ForgetMemoryUnits {
+        user_id: String,
+        tombstones: Vec<memorose_common::ForgettingTombstone>,
+    },
+    /// Move a memory unit to the trash. The leader picks the restore window, so every
+    /// replica purges it at the same time.
+    TrashMemoryUnit(memorose_common::TrashedMemoryUnit),
+    /// Take a memory unit back out of the trash.
+    RestoreMemoryUnit {
+        user_id: String,
+        unit_id: uuid::Uuid,
+    },
+    /// Permanently delete a memory unit, whether or not it is in the trash.
+    DeleteMemoryUnit {
+        user_id: String,
+        unit_id: uuid::Uuid,
+    },
+    /// Pin or unpin a memory unit.
+    SetMemoryUnitPinned {
+        user_id: String,
+        unit_id: uuid::Uuid,
+        pinned: bool,
+    },
+    /// Permanently delete trashed memory units whose restore window ended before `now`.
+    PurgeExpiredTrash { now: chrono::DateTime<chrono::Utc> },
+    /// Apply manual edits to a user's structured profile; `None` removes an attribute.
+    PatchStructuredProfile {
+        user_id: String,
+        attributes: std::collections::HashMap<String, Option<memorose_common::ProfileValue>>,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// Merge attributes the leader extracted into a user's structured profile.
+    MergeStructuredProfile {
+        user_id: String,
+        attributes: std::collections::HashMap<String, memorose_common::ProfileValue>,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// Point a user's L3 profile at a newly synthesized one.
+    SetUserProfilePointer {
+        user_id: String,
+        current: uuid::Uuid,
+        previous: Option<uuid::Uuid>,
+    },
+    /// Queue background work for a user, or fold it into the job already queued.
+    EnqueueJob {
+        kind: crate::engine::types::BackgroundJobKind,
+        user_id: String,
+    },
+    /// Lease a user's background job to the leader's worker `holder` until `expires_at`.
+    ClaimJob {
+        kind: crate::engine::types::BackgroundJobKind,
+        user_id: String,
+        holder: String,
+        term: u64,
+        max_attempts: u32,
+        now: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// Push `holder`'s lease on a job back to `expires_at`.
+    HeartbeatJob {
+        kind: crate::engine::types::BackgroundJobKind,
+        user_id: String,
+        holder: String,
+        now: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// Finish `holder`'s claim of a job.
+    CompleteJob {
+        job: crate::engine::types::BackgroundJob,
+        holder: String,
+    },
+    /// Give up `holder`'s claim of a job after `error`, to be retried at `retry_at`.
+    FailJob {
+        job: crate::engine::types::BackgroundJob,
+        holder: String,
+        error: String,
+        max_attempts: u32,
+        now: chrono::DateTime<chrono::Utc>,
+        retry_at: chrono::DateTime<chrono::Utc>,
+    },
+    /// Hand `holder`'s unfinished claim of a job back to the queue.
+    ReleaseJob {
+        job: crate::engine::types::BackgroundJob,
+        holder: String,
+    },
+    /// Record a reflection batch the leader finished, so the user's reflect job resumes
+    /// after it.
+    ConsumeReflectionBatch {
+        user_id: String,
+        consumed_units: usize,
+        consumed_tokens: usize,
+        next_first_event_tx_micros: Option<i64>,
+        next_first_event_id: Option<String>,
+    },
+    /// Create, update or archive a stream record.
+    PutMemoryStream(memorose_common::MemoryStream),
+    /// Record which retrieved memories a client used.
+    RecordRetrievalFeedback(memorose_common::RetrievalFeedback),
+    /// Register or replace a user's custom graph relation.
+    PutRelationDefinition(memorose_common::RelationDefinition),
+    /// Remove a user's custom graph relation.
+    DeleteRelationDefinition { user_id: String, name: String },
+    /// Writes shipped from a cluster in another region. Each is dropped when this cluster
+    /// already holds a newer version, and none are shipped onward again.
+    ApplyReplicated {
+        source_region: String,
+        requests: Vec<ClientRequest>,
+    },
     // Future: etc.
 }
 
@@ -23,6 +154,31 @@ pub struct ClientResponse {
     // Future: return IDs, error messages, etc.
 }
 
+/// A Raft member: the gRPC address peers replicate to and the HTTP address it
+/// advertises for client traffic, so forwarding and routing need not guess ports.
+/// `http_addr` is empty for members recorded before it existed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoroseNode {
+    pub addr: String,
+    #[serde(default)]
+    pub http_addr: String,
+}
+
+impl MemoroseNode {
+    pub fn new(addr: impl Into<String>, http_addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            http_addr: http_addr.into(),
+        }
+    }
+}
+
+impl fmt::Display for MemoroseNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{raft: {}, http: {}}}", self.addr, self.http_addr)
+    }
+}
+
 /// The implementation of the `RaftTypeConfig` trait for Memorose.
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Default, PartialOrd, Ord, Serialize, Deserialize, Hash,
@@ -39,7 +195,7 @@ impl openraft::RaftTypeConfig for MemoroseTypeConfig {
     type D = ClientRequest;
     type R = ClientResponse;
     type NodeId = u64;
-    type Node = BasicNode;
+    type Node = MemoroseNode;
     type Entry = openraft::Entry<MemoroseTypeConfig>;
     type SnapshotData = Cursor<Vec<u8>>;
     type AsyncRuntime = openraft::TokioRuntime;
@@ -0,0 +1,276 @@
+//! Shadow traffic replay. Sampled retrieve requests are recorded with the results they
+//! got, then re-run against a candidate build or config. The report shows how far the
+//! candidate's results drift from the recorded ones, so engine upgrades can be checked on
+//! real traffic before they ship.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::{GeoRadius, TimeRange};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::engine::{MemoroseEngine, SearchExplainOptions, VectorSearchMode};
+use crate::llm::LLMClient;
+
+/// Changed retrievals listed in a replay report; the rest are only counted.
+const MAX_REPORTED_CHANGES: usize = 50;
+/// Score differences below this are rounding noise.
+const SCORE_TOLERANCE: f32 = 1e-4;
+
+/// A retrieve request as the engine ran it, with the ranked ids and scores it returned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRetrieval {
+    pub id: Uuid,
+    pub recorded_at: DateTime<Utc>,
+    pub user_id: String,
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub app_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub emotions: Option<Vec<String>>,
+    #[serde(default)]
+    pub near: Option<GeoRadius>,
+    /// The search text, with any image caption already fused in
+    pub query: String,
+    pub embedding: Vec<f32>,
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub enable_arbitration: bool,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    pub graph_depth: usize,
+    #[serde(default)]
+    pub valid_time: Option<TimeRange>,
+    #[serde(default)]
+    pub transaction_time: Option<TimeRange>,
+    #[serde(default)]
+    pub token_budget: Option<usize>,
+    #[serde(default)]
+    pub recency_bias: f32,
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    #[serde(default)]
+    pub vector_mode: VectorSearchMode,
+    pub results: Vec<(Uuid, f32)>,
+}
+
+/// How a replayed retrieval differs from the recorded one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalDiff {
+    /// Same ids in the same order with the same scores
+    pub identical: bool,
+    /// Shared ids over the larger result list; 1 when both are empty
+    pub overlap: f32,
+    /// The first result differs
+    pub top_changed: bool,
+    /// Ids only the candidate returned
+    pub added: usize,
+    /// Ids only the recording returned
+    pub removed: usize,
+    /// Mean score change over the shared ids
+    pub mean_abs_score_delta: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayChange {
+    pub id: Uuid,
+    pub user_id: String,
+    pub query: String,
+    pub diff: RetrievalDiff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub identical: usize,
+    pub top_changed: usize,
+    pub failed: usize,
+    pub mean_overlap: f32,
+    pub mean_replay_ms: f64,
+    /// The least similar retrievals, worst first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<ReplayChange>,
+}
+
+/// Compare a candidate's ranked results with the recorded ones.
+pub fn diff_results(baseline: &[(Uuid, f32)], candidate: &[(Uuid, f32)]) -> RetrievalDiff {
+    let baseline_ids: HashSet<Uuid> = baseline.iter().map(|(id, _)| *id).collect();
+    let candidate_ids: HashSet<Uuid> = candidate.iter().map(|(id, _)| *id).collect();
+    let shared = baseline_ids.intersection(&candidate_ids).count();
+    let larger = baseline_ids.len().max(candidate_ids.len());
+    let overlap = if larger == 0 {
+        1.0
+    } else {
+        shared as f32 / larger as f32
+    };
+
+    let deltas: Vec<f32> = baseline
+        .iter()
+        .filter_map(|(id, score)| {
+            candidate
+                .iter()
+                .find(|(candidate_id, _)| candidate_id == id)
+                .map(|(_, candidate_score)| (candidate_score - score).abs())
+        })
+        .collect();
+    let mean_abs_score_delta = if deltas.is_empty() {
+        0.0
+    } else {
+        deltas.iter().sum::<f32>() / deltas.len() as f32
+    };
+
+    let identical = baseline.len() == candidate.len()
+        && baseline
+            .iter()
+            .zip(candidate)
+            .all(|((a, a_score), (b, b_score))| {
+                a == b && (a_score - b_score).abs() < SCORE_TOLERANCE
+            });
+
+    RetrievalDiff {
+        identical,
+        overlap,
+        top_changed: baseline.first().map(|(id, _)| id) != candidate.first().map(|(id, _)| id),
+        added: candidate_ids.difference(&baseline_ids).count(),
+        removed: baseline_ids.difference(&candidate_ids).count(),
+        mean_abs_score_delta,
+    }
+}
+
+/// Re-run each recording on `engine` and diff the results. With a client, queries are
+/// re-embedded so an embedding model change is part of the comparison; otherwise the
+/// recorded embeddings are reused.
+pub async fn replay(
+    engine: &MemoroseEngine,
+    records: &[RecordedRetrieval],
+    client: Option<&dyn LLMClient>,
+) -> Result<ReplayReport> {
+    let mut report = ReplayReport {
+        replayed: 0,
+        identical: 0,
+        top_changed: 0,
+        failed: 0,
+        mean_overlap: 0.0,
+        mean_replay_ms: 0.0,
+        changed: Vec::new(),
+    };
+    let mut overlap_sum = 0.0_f32;
+    let mut replay_ms = 0.0_f64;
+
+    for record in records {
+        let embedding = match client {
+            Some(client) => match client.embed(&record.query).await {
+                Ok(response) => response.data,
+                Err(e) => {
+                    tracing::warn!("Failed to re-embed recorded query {}: {:?}", record.id, e);
+                    report.failed += 1;
+                    continue;
+                }
+            },
+            None => record.embedding.clone(),
+        };
+        let started = std::time::Instant::now();
+        let outcome = engine
+            .search_hybrid_with_shared_explained(
+                &record.user_id,
+                record.org_id.as_deref(),
+                record.agent_id.as_deref(),
+                record.app_ids.as_deref(),
+                record.emotions.as_deref(),
+                record.near.as_ref(),
+                &record.query,
+                &embedding,
+                record.limit,
+                record.offset,
+                record.enable_arbitration,
+                record.min_score,
+                record.graph_depth,
+                record.valid_time.clone(),
+                record.transaction_time.clone(),
+                record.token_budget,
+                record.recency_bias,
+                record.mmr_lambda,
+                record.vector_mode,
+                SearchExplainOptions::default(),
+            )
+            .await;
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::warn!("Replay of recorded retrieval {} failed: {:?}", record.id, e);
+                report.failed += 1;
+                continue;
+            }
+        };
+        replay_ms += started.elapsed().as_secs_f64() * 1000.0;
+
+        let candidate: Vec<(Uuid, f32)> = outcome
+            .results
+            .iter()
+            .map(|(hit, score)| (hit.id, *score))
+            .collect();
+        let diff = diff_results(&record.results, &candidate);
+        report.replayed += 1;
+        overlap_sum += diff.overlap;
+        if diff.identical {
+            report.identical += 1;
+            continue;
+        }
+        if diff.top_changed {
+            report.top_changed += 1;
+        }
+        report.changed.push(ReplayChange {
+            id: record.id,
+            user_id: record.user_id.clone(),
+            query: record.query.clone(),
+            diff,
+        });
+    }
+
+    if report.replayed > 0 {
+        report.mean_overlap = overlap_sum / report.replayed as f32;
+        report.mean_replay_ms = replay_ms / report.replayed as f64;
+    }
+    report.changed.sort_by(|a, b| {
+        a.diff
+            .overlap
+            .partial_cmp(&b.diff.overlap)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    report.changed.truncate(MAX_REPORTED_CHANGES);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_results() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let baseline = vec![(ids[0], 0.9), (ids[1], 0.8), (ids[2], 0.7)];
+
+        let same = diff_results(&baseline, &baseline);
+        assert!(same.identical);
+        assert_eq!(same.overlap, 1.0);
+        assert!(!same.top_changed);
+
+        let candidate = vec![(ids[1], 0.85), (ids[0], 0.9), (ids[3], 0.6)];
+        let diff = diff_results(&baseline, &candidate);
+        assert!(!diff.identical);
+        assert!(diff.top_changed);
+        assert!((diff.overlap - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!((diff.added, diff.removed), (1, 1));
+        assert!((diff.mean_abs_score_delta - 0.025).abs() < 1e-6);
+
+        assert_eq!(diff_results(&[], &[]).overlap, 1.0);
+        assert_eq!(diff_results(&baseline, &[]).removed, 3);
+    }
+}
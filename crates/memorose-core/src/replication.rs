@@ -0,0 +1,527 @@
+//! Asynchronous cross-region replication.
+//!
+//! The leader of each shard ships the requests it has applied to a Memorose cluster in
+//! another region, which applies them as one [`ClientRequest::ApplyReplicated`] entry.
+//! Conflicts are settled by the writes' own timestamps: a shipped write only replaces
+//! what the receiving cluster holds when it is strictly newer, so re-shipping after a
+//! leader change or a failed request is harmless.
+
+use crate::engine::MemoroseEngine;
+use crate::raft::storage::apply_single_request;
+use crate::raft::types::{ClientRequest, MemoroseTypeConfig};
+use crate::raft::MemoroseRaft;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use memorose_common::config::{LiveConfig, ReplicationConfig};
+use openraft::{Entry, EntryPayload};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Path on the receiving cluster that accepts [`ReplicationBatch`]es.
+pub const APPLY_PATH: &str = "/v1/replication/apply";
+
+/// Highest local log index already accepted by the sink.
+const CURSOR_KEY: &[u8] = b"replication:cursor";
+/// Newest replicated timestamp per conflict key, so stale writes stay dropped even after
+/// the data they would have replaced is gone.
+const WATERMARK_PREFIX: &str = "replication:lww:";
+
+/// Applied writes of one shard, in log order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationBatch {
+    pub source_region: String,
+    pub shard_id: u32,
+    /// Log index of the last entry covered by this batch on the source shard.
+    pub last_log_index: u64,
+    pub requests: Vec<ClientRequest>,
+}
+
+/// Where a shipped write has to be applied on the receiving cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationTarget {
+    /// The shard owning this user.
+    User(String),
+    /// Every shard, like group membership and org policy writes.
+    AllShards,
+}
+
+/// Split shipped requests into single writes and the shard each belongs to. The receiving
+/// cluster may shard users differently, so batched writes are taken apart.
+pub fn route_requests(requests: Vec<ClientRequest>) -> Vec<(ReplicationTarget, ClientRequest)> {
+    let mut routed = Vec::new();
+    for request in requests {
+        match request {
+            ClientRequest::IngestEvents(events) => routed.extend(events.into_iter().map(|e| {
+                (
+                    ReplicationTarget::User(e.user_id.clone()),
+                    ClientRequest::IngestEvent(e),
+                )
+            })),
+            ClientRequest::UpdateGraphBatch(edges) => routed.extend(edges.into_iter().map(|e| {
+                (
+                    ReplicationTarget::User(e.user_id.clone()),
+                    ClientRequest::UpdateGraph(e),
+                )
+            })),
+            ClientRequest::ApplyReplicated { requests, .. } => {
+                routed.extend(route_requests(requests))
+            }
+            request => {
+                let target = match &request {
+                    ClientRequest::IngestEvent(event) => {
+                        ReplicationTarget::User(event.user_id.clone())
+                    }
+                    ClientRequest::UpdateGraph(edge) => {
+                        ReplicationTarget::User(edge.user_id.clone())
+                    }
+                    ClientRequest::UpsertTask(task) => {
+                        ReplicationTarget::User(task.user_id.clone())
+                    }
+                    ClientRequest::UpdateTaskStatus(update) => {
+                        ReplicationTarget::User(update.user_id.clone())
+                    }
+                    ClientRequest::PutShareGrant(grant) => {
+                        ReplicationTarget::User(grant.owner_user_id.clone())
+                    }
+                    ClientRequest::RevokeShareGrant { owner_user_id, .. } => {
+                        ReplicationTarget::User(owner_user_id.clone())
+                    }
+                    ClientRequest::PutUserKey(record) => {
+                        ReplicationTarget::User(record.user_id.clone())
+                    }
+                    ClientRequest::CreateMemoryUnit(unit) => {
+                        ReplicationTarget::User(unit.user_id.clone())
+                    }
+                    ClientRequest::ForgetMemoryUnits { user_id, .. } => {
+                        ReplicationTarget::User(user_id.clone())
+                    }
+                    ClientRequest::TrashMemoryUnit(entry) => {
+                        ReplicationTarget::User(entry.user_id.clone())
+                    }
+                    ClientRequest::RestoreMemoryUnit { user_id, .. }
+                    | ClientRequest::DeleteMemoryUnit { user_id, .. }
+                    | ClientRequest::SetMemoryUnitPinned { user_id, .. }
+                    | ClientRequest::PatchStructuredProfile { user_id, .. }
+                    | ClientRequest::MergeStructuredProfile { user_id, .. }
+                    | ClientRequest::SetUserProfilePointer { user_id, .. } => {
+                        ReplicationTarget::User(user_id.clone())
+                    }
+                    ClientRequest::PutMemoryStream(stream) => {
+                        ReplicationTarget::User(stream.user_id.clone())
+                    }
+                    ClientRequest::RecordRetrievalFeedback(feedback) => {
+                        ReplicationTarget::User(feedback.user_id.clone())
+                    }
+                    ClientRequest::PutRelationDefinition(definition) => {
+                        ReplicationTarget::User(definition.user_id.clone())
+                    }
+                    ClientRequest::DeleteRelationDefinition { user_id, .. } => {
+                        ReplicationTarget::User(user_id.clone())
+                    }
+                    _ => ReplicationTarget::AllShards,
+                };
+                routed.push((target, request));
+            }
+        }
+    }
+    routed
+}
+
+/// Conflict key and timestamp of a write, for writes that carry one. Writes without a
+/// timestamp (revocations, group membership, org policy) always apply.
+fn conflict_version(request: &ClientRequest) -> Option<(String, DateTime<Utc>)> {
+    match request {
+        ClientRequest::IngestEvent(event) => Some((
+            format!("event:{}:{}", event.user_id, event.id),
+            event.transaction_time,
+        )),
+        ClientRequest::UpdateGraph(edge) => Some((
+            format!(
+                "edge:{}:{}:{}:{:?}",
+                edge.user_id, edge.source_id, edge.target_id, edge.relation
+            ),
+            edge.transaction_time,
+        )),
+        ClientRequest::UpsertTask(task) => Some((
+            format!("task:{}:{}", task.user_id, task.task_id),
+            task.updated_at,
+        )),
+        ClientRequest::UpdateTaskStatus(update) => Some((
+            format!("task:{}:{}", update.user_id, update.task_id),
+            update.updated_at,
+        )),
+        ClientRequest::PutShareGrant(grant) => Some((
+            format!("grant:{}:{}", grant.owner_user_id, grant.id),
+            grant.created_at,
+        )),
+        ClientRequest::CreateMemoryUnit(unit) => Some((
+            format!("unit:{}:{}", unit.user_id, unit.id),
+            unit.transaction_time,
+        )),
+        ClientRequest::PutMemoryStream(stream) => Some((
+            format!("stream:{}:{}", stream.user_id, stream.id),
+            stream.updated_at,
+        )),
+        ClientRequest::PutRelationDefinition(definition) => Some((
+            format!("relation_def:{}:{}", definition.user_id, definition.name),
+            definition.updated_at,
+        )),
+        _ => None,
+    }
+}
+
+/// Timestamp of the version this cluster already holds for `request`, if any.
+async fn local_version(
+    engine: &MemoroseEngine,
+    request: &ClientRequest,
+) -> Result<Option<DateTime<Utc>>> {
+    Ok(match request {
+        ClientRequest::IngestEvent(event) => engine
+            .get_event_raw(&event.user_id, &event.id.to_string())?
+            .map(|e| e.transaction_time),
+        ClientRequest::UpsertTask(task) => engine
+            .get_l3_task(&task.user_id, task.task_id)
+            .await?
+            .map(|t| t.updated_at),
+        ClientRequest::UpdateTaskStatus(update) => engine
+            .get_l3_task(&update.user_id, update.task_id)
+            .await?
+            .map(|t| t.updated_at),
+        ClientRequest::PutShareGrant(grant) => engine
+            .get_share_grant(&grant.owner_user_id, grant.id)?
+            .map(|g| g.created_at),
+        ClientRequest::CreateMemoryUnit(unit) => engine
+            .get_memory_unit_including_forgotten(&unit.user_id, unit.id)?
+            .map(|u| u.transaction_time),
+        ClientRequest::PutMemoryStream(stream) => engine
+            .get_memory_stream(&stream.user_id, stream.id)?
+            .map(|s| s.updated_at),
+        ClientRequest::PutRelationDefinition(definition) => engine
+            .get_relation_definition(&definition.user_id, &definition.name)?
+            .map(|d| d.updated_at),
+        _ => None,
+    })
+}
+
+fn watermark_key(key: &str) -> String {
+    format!("{}{}", WATERMARK_PREFIX, key)
+}
+
+/// Whether this cluster already holds a version of `request` at least as new.
+async fn is_stale(engine: &MemoroseEngine, request: &ClientRequest) -> Result<bool> {
+    let Some((key, incoming)) = conflict_version(request) else {
+        return Ok(false);
+    };
+    let watermark = engine
+        .system_kv()
+        .get(watermark_key(&key).as_bytes())?
+        .and_then(|raw| serde_json::from_slice::<DateTime<Utc>>(&raw).ok());
+    let current = watermark.max(local_version(engine, request).await?);
+    Ok(current.is_some_and(|current| current >= incoming))
+}
+
+fn record_version(engine: &MemoroseEngine, request: &ClientRequest) -> Result<()> {
+    if let Some((key, incoming)) = conflict_version(request) {
+        engine.system_kv().put(
+            watermark_key(&key).as_bytes(),
+            &serde_json::to_vec(&incoming)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Apply writes shipped from `source_region`, skipping any this cluster already holds a
+/// newer version of. Runs inside the state machine, so every replica decides alike.
+pub async fn apply_replicated(
+    engine: &MemoroseEngine,
+    source_region: &str,
+    requests: &[ClientRequest],
+) -> bool {
+    let mut success = true;
+    let mut stale = 0usize;
+    for request in requests {
+        match is_stale(engine, request).await {
+            Ok(true) => stale += 1,
+            Ok(false) => {
+                if !apply_single_request(engine, request).await {
+                    success = false;
+                } else if let Err(e) = record_version(engine, request) {
+                    tracing::error!("Failed to record replicated write version: {:?}", e);
+                    success = false;
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to resolve replicated write from {}: {:?}",
+                    source_region,
+                    e
+                );
+                success = false;
+            }
+        }
+    }
+    if stale > 0 {
+        tracing::debug!(
+            "Dropped {} of {} replicated writes from {} as older than local data",
+            stale,
+            requests.len(),
+            source_region
+        );
+    }
+    success
+}
+
+/// Requests worth shipping out of applied log entries: membership changes and the job
+/// queue are local to this cluster and writes that arrived from another region are not sent back.
+fn shippable_requests(entries: &[Entry<MemoroseTypeConfig>]) -> Vec<ClientRequest> {
+    entries
+        .iter()
+        .filter_map(|entry| match &entry.payload {
+            EntryPayload::Normal(
+                ClientRequest::ApplyReplicated { .. }
+                | ClientRequest::EnqueueJob { .. }
+                | ClientRequest::ClaimJob { .. }
+                | ClientRequest::HeartbeatJob { .. }
+                | ClientRequest::CompleteJob { .. }
+                | ClientRequest::FailJob { .. }
+                | ClientRequest::ReleaseJob { .. }
+                | ClientRequest::ConsumeReflectionBatch { .. },
+            ) => None,
+            EntryPayload::Normal(request) => Some(request.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Ships one shard's applied log to the sink configured under `[replication]`. Only the
+/// shard leader ships; the cursor is kept locally, so a new leader may re-ship entries,
+/// which the receiving side drops as not newer.
+pub struct ReplicationShipper {
+    engine: MemoroseEngine,
+    raft: MemoroseRaft,
+    shard_id: u32,
+    live_config: LiveConfig,
+    client: reqwest::Client,
+}
+
+impl ReplicationShipper {
+    pub fn new(
+        engine: MemoroseEngine,
+        raft: MemoroseRaft,
+        shard_id: u32,
+        live_config: LiveConfig,
+    ) -> Self {
+        Self {
+            engine,
+            raft,
+            shard_id,
+            live_config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn run(self) {
+        loop {
+            let config = self.live_config.load();
+            let replication = &config.replication;
+            if let Some(sink) = replication.active_sink() {
+                match self.ship_once(replication, sink).await {
+                    // More may be waiting; ship it without pausing.
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(
+                        "Replication of shard {} to {} failed: {:?}",
+                        self.shard_id,
+                        sink,
+                        e
+                    ),
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(replication.interval_ms.max(1))).await;
+        }
+    }
+
+    /// Highest log index the sink has accepted, 0 before the first batch.
+    pub fn cursor(&self) -> Result<u64> {
+        Ok(self
+            .engine
+            .system_kv()
+            .get(CURSOR_KEY)?
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default())
+    }
+
+    fn set_cursor(&self, index: u64) -> Result<()> {
+        self.engine
+            .system_kv()
+            .put(CURSOR_KEY, &serde_json::to_vec(&index)?)
+    }
+
+    /// Ship the next batch of applied entries; returns whether the cursor moved.
+    async fn ship_once(&self, config: &ReplicationConfig, sink: &str) -> Result<bool> {
+        let (is_leader, last_applied) = {
+            let metrics = self.raft.metrics();
+            let metrics = metrics.borrow();
+            (
+                metrics.current_leader == Some(metrics.id),
+                metrics.last_applied.map(|l| l.index).unwrap_or_default(),
+            )
+        };
+        let cursor = self.cursor()?;
+        if !is_leader || cursor >= last_applied {
+            return Ok(false);
+        }
+
+        let end = last_applied.min(cursor.saturating_add(config.batch_size.max(1) as u64));
+        let start_key = format!("raft:log:{:020}", cursor + 1);
+        let end_key = format!("raft:log:{:020}", end + 1);
+        let entries = self
+            .engine
+            .system_kv()
+            .scan_range(start_key.as_bytes(), end_key.as_bytes())?
+            .into_iter()
+            .map(|(_, raw)| serde_json::from_slice::<Entry<MemoroseTypeConfig>>(&raw))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let first = entries.first().map(|e| e.log_id.index);
+        if first != Some(cursor + 1) {
+            tracing::warn!(
+                "Replication of shard {} fell behind log compaction; entries {}..{} were purged before they were shipped",
+                self.shard_id,
+                cursor + 1,
+                first.unwrap_or(end + 1)
+            );
+        }
+        let last_log_index = entries.last().map(|e| e.log_id.index).unwrap_or(end);
+        let requests = shippable_requests(&entries);
+
+        if !requests.is_empty() {
+            let batch = ReplicationBatch {
+                source_region: config.region.clone(),
+                shard_id: self.shard_id,
+                last_log_index,
+                requests,
+            };
+            let mut request = self
+                .client
+                .post(format!("{}{}", sink.trim_end_matches('/'), APPLY_PATH))
+                .json(&batch);
+            if let Some(api_key) = &config.sink_api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(anyhow!("sink answered {}: {}", status, body));
+            }
+        }
+
+        self.set_cursor(last_log_index)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use memorose_common::{Event, EventContent, GroupMembershipUpdate};
+    use openraft::{LeaderId, LogId};
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    fn event(user_id: &str, text: &str) -> Event {
+        Event::new(
+            None,
+            user_id.to_string(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text(text.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_route_requests_splits_batches_by_user() {
+        let membership = GroupMembershipUpdate {
+            group_id: "g".to_string(),
+            user_id: "u1".to_string(),
+            member: true,
+        };
+        let routed = route_requests(vec![
+            ClientRequest::IngestEvents(vec![event("u1", "a"), event("u2", "b")]),
+            ClientRequest::UpdateGroupMembership(membership),
+        ]);
+        let targets: Vec<_> = routed.iter().map(|(target, _)| target.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                ReplicationTarget::User("u1".to_string()),
+                ReplicationTarget::User("u2".to_string()),
+                ReplicationTarget::AllShards,
+            ]
+        );
+        assert!(matches!(routed[0].1, ClientRequest::IngestEvent(_)));
+    }
+
+    #[test]
+    fn test_shippable_requests_skip_membership_and_replicated_entries() {
+        let log_id = |index| LogId::new(LeaderId::new(1, 1), index);
+        let entries = vec![
+            Entry::<MemoroseTypeConfig> {
+                log_id: log_id(1),
+                payload: EntryPayload::Blank,
+            },
+            Entry {
+                log_id: log_id(2),
+                payload: EntryPayload::Normal(ClientRequest::IngestEvent(event("u1", "local"))),
+            },
+            Entry {
+                log_id: log_id(3),
+                payload: EntryPayload::Normal(ClientRequest::ApplyReplicated {
+                    source_region: "eu".to_string(),
+                    requests: vec![ClientRequest::IngestEvent(event("u1", "remote"))],
+                }),
+            },
+        ];
+        let requests = shippable_requests(&entries);
+        assert_eq!(requests.len(), 1);
+        assert!(matches!(requests[0], ClientRequest::IngestEvent(_)));
+    }
+
+    #[tokio::test]
+    async fn test_apply_replicated_keeps_the_newest_write() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let original = event("u1", "first");
+        assert!(
+            apply_replicated(
+                &engine,
+                "eu",
+                &[ClientRequest::IngestEvent(original.clone())]
+            )
+            .await
+        );
+
+        let mut older = original.clone();
+        older.content = EventContent::Text("older".to_string());
+        older.transaction_time = original.transaction_time - ChronoDuration::seconds(5);
+        assert!(apply_replicated(&engine, "eu", &[ClientRequest::IngestEvent(older)]).await);
+        let stored = engine
+            .get_event_raw("u1", &original.id.to_string())?
+            .expect("event stored");
+        assert!(matches!(stored.content, EventContent::Text(ref t) if t == "first"));
+
+        let mut newer = original.clone();
+        newer.content = EventContent::Text("newer".to_string());
+        newer.transaction_time = original.transaction_time + ChronoDuration::seconds(5);
+        assert!(apply_replicated(&engine, "eu", &[ClientRequest::IngestEvent(newer)]).await);
+        let stored = engine
+            .get_event_raw("u1", &original.id.to_string())?
+            .expect("event stored");
+        assert!(matches!(stored.content, EventContent::Text(ref t) if t == "newer"));
+        Ok(())
+    }
+}
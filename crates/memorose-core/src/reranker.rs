@@ -5,6 +5,18 @@ use memorose_common::MemoryUnit;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+/// Added to the final score of pinned memories, so they outrank unpinned memories of
+/// similar relevance.
+pub const PINNED_RETRIEVAL_BOOST: f32 = 0.2;
+
+fn pin_boost(unit: &MemoryUnit) -> f32 {
+    if unit.pinned {
+        PINNED_RETRIEVAL_BOOST
+    } else {
+        0.0
+    }
+}
+
 #[async_trait]
 pub trait Reranker: Send + Sync {
     async fn rerank(
@@ -75,7 +87,8 @@ impl Reranker for WeightedReranker {
             let recency = self.calculate_recency(&unit);
             let final_score = sim_score * weights.similarity_weight
                 + unit.importance * weights.importance_weight
-                + recency * weights.recency_weight;
+                + recency * weights.recency_weight
+                + pin_boost(&unit);
 
             reranked.push((unit, final_score));
         }
@@ -143,6 +156,8 @@ struct HttpCandidate {
     id: String,
     text: String,
     base_score: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
 }
 
 #[derive(Deserialize)]
@@ -172,6 +187,11 @@ impl HttpReranker {
                 .unwrap_or_default(),
         }
     }
+
+    /// Share an existing client, and its connection pool, instead of building one.
+    pub fn with_client(endpoint: String, client: Client) -> Self {
+        Self { endpoint, client }
+    }
 }
 
 #[cfg(test)]
@@ -246,6 +266,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_weighted_reranker_boosts_pinned_memories() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let store = KvStore::open(temp_dir.path())?;
+        let reranker = WeightedReranker::new();
+
+        let unpinned = build_memory("unpinned", 0.5, 1);
+        let mut pinned = build_memory("pinned", 0.5, 1);
+        pinned.pinned = true;
+
+        let reranked = reranker
+            .rerank("query", &store, vec![(unpinned, 0.7), (pinned, 0.6)])
+            .await?;
+
+        assert_eq!(reranked[0].0.content, "pinned");
+        assert!(reranked[0].1 > reranked[1].1);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_weighted_reranker_apply_feedback_updates_and_clamps_weights() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -346,6 +385,7 @@ impl Reranker for HttpReranker {
                 id: u.id.to_string(),
                 text: u.content.clone(),
                 base_score: *s,
+                pinned: u.pinned,
             })
             .collect();
 
@@ -369,7 +409,8 @@ impl Reranker for HttpReranker {
 
         let mut reranked = Vec::new();
         for (unit, base_score) in candidates {
-            let final_score = *score_map.get(&unit.id.to_string()).unwrap_or(&base_score);
+            let final_score =
+                *score_map.get(&unit.id.to_string()).unwrap_or(&base_score) + pin_boost(&unit);
             reranked.push((unit, final_score));
         }
 
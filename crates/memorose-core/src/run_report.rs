@@ -0,0 +1,80 @@
+//! Counters for the worker cycle that is running on the current task.
+//!
+//! The worker runs each cycle inside [`metered`]. Code under it reports the items it
+//! processed with [`record_items`], and every LLM call made through a
+//! [`RateLimitedClient`](crate::llm::RateLimitedClient) adds its token usage. Work moved
+//! to a spawned task is not counted, since task-locals do not follow it.
+
+use memorose_common::TokenUsage;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+tokio::task_local! {
+    static CURRENT_RUN: Arc<RunMeter>;
+}
+
+#[derive(Debug, Default)]
+struct RunMeter {
+    items: AtomicUsize,
+    llm_tokens: AtomicU64,
+}
+
+/// What a metered cycle did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RunCounts {
+    pub items: usize,
+    pub llm_tokens: u64,
+}
+
+/// Run `future` with fresh counters and return its output with what it counted.
+pub(crate) async fn metered<T>(future: impl Future<Output = T>) -> (T, RunCounts) {
+    let meter = Arc::new(RunMeter::default());
+    let output = CURRENT_RUN.scope(meter.clone(), future).await;
+    let counts = RunCounts {
+        items: meter.items.load(Ordering::Relaxed),
+        llm_tokens: meter.llm_tokens.load(Ordering::Relaxed),
+    };
+    (output, counts)
+}
+
+/// Count `items` as processed by the running cycle. Outside a cycle this does nothing.
+pub(crate) fn record_items(items: usize) {
+    let _ = CURRENT_RUN.try_with(|meter| meter.items.fetch_add(items, Ordering::Relaxed));
+}
+
+/// Add an LLM call's tokens to the running cycle. Outside a cycle this does nothing.
+pub(crate) fn record_llm_usage(usage: &TokenUsage) {
+    let _ = CURRENT_RUN.try_with(|meter| {
+        meter
+            .llm_tokens
+            .fetch_add(u64::from(usage.total_tokens), Ordering::Relaxed)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metered_counts_only_its_own_task() {
+        record_items(100);
+        let ((), counts) = metered(async {
+            record_items(2);
+            record_items(3);
+            record_llm_usage(&TokenUsage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+            });
+        })
+        .await;
+        assert_eq!(
+            counts,
+            RunCounts {
+                items: 5,
+                llm_tokens: 15
+            }
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use memorose_common::{Event, EventContent, StreamSegmentBoundary, StreamSegmentReason};
+use uuid::Uuid;
+
+/// Keywords taken from each event when comparing it with the current session.
+const SEGMENT_KEYWORDS_PER_EVENT: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SegmentationOptions {
+    /// Silence that starts a new session
+    pub gap: Duration,
+    /// Share of an event's keywords already seen in the session below which it starts a
+    /// new one; 0 disables topic detection
+    pub topic_overlap: f32,
+    /// Events a session must hold before a topic shift can close it
+    pub min_events: usize,
+}
+
+/// Split one stream's events into sessions. `events` must be sorted by transaction time;
+/// `last_event_at` is the latest event consolidated from the stream before this batch, and
+/// `known` holds boundaries already recorded for it, which are kept as they are.
+pub(crate) fn find_segment_boundaries(
+    events: &[&Event],
+    last_event_at: Option<DateTime<Utc>>,
+    known: &[StreamSegmentBoundary],
+    options: SegmentationOptions,
+) -> Vec<StreamSegmentBoundary> {
+    let known: HashMap<Uuid, StreamSegmentReason> = known
+        .iter()
+        .map(|boundary| (boundary.event_id, boundary.reason))
+        .collect();
+    let mut boundaries = Vec::new();
+    let mut previous_at = last_event_at;
+    let mut session_terms: HashSet<String> = HashSet::new();
+    let mut session_len = 0usize;
+
+    for event in events {
+        let terms = event_terms(event);
+        let reason = if let Some(reason) = known.get(&event.id) {
+            Some(*reason)
+        } else if previous_at.is_some_and(|at| event.transaction_time - at > options.gap) {
+            Some(StreamSegmentReason::TimeGap)
+        } else if options.topic_overlap > 0.0
+            && session_len >= options.min_events.max(1)
+            && !terms.is_empty()
+            && overlap(&terms, &session_terms) < options.topic_overlap
+        {
+            Some(StreamSegmentReason::TopicShift)
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            boundaries.push(StreamSegmentBoundary {
+                event_id: event.id,
+                at: event.transaction_time,
+                reason,
+            });
+            session_terms.clear();
+            session_len = 0;
+        }
+
+        session_terms.extend(terms);
+        session_len += 1;
+        previous_at =
+            Some(previous_at.map_or(event.transaction_time, |at| at.max(event.transaction_time)));
+    }
+
+    boundaries
+}
+
+/// Number every event by the session it falls in. Events before the first boundary are in
+/// session 0.
+pub(crate) fn assign_segments(
+    events: &[&Event],
+    boundaries: &[StreamSegmentBoundary],
+) -> HashMap<Uuid, usize> {
+    let starts: HashSet<Uuid> = boundaries
+        .iter()
+        .map(|boundary| boundary.event_id)
+        .collect();
+    let mut segment = 0usize;
+    events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            if index > 0 && starts.contains(&event.id) {
+                segment += 1;
+            }
+            (event.id, segment)
+        })
+        .collect()
+}
+
+fn event_terms(event: &Event) -> HashSet<String> {
+    let text = match &event.content {
+        EventContent::Text(text) => text.clone(),
+        EventContent::Json(value) => value.to_string(),
+        EventContent::Image(_) | EventContent::Audio(_) | EventContent::Video(_) => {
+            return HashSet::new()
+        }
+    };
+    crate::keywords::extract_keywords(&text, SEGMENT_KEYWORDS_PER_EVENT)
+        .iter()
+        .flat_map(|phrase| phrase.split_whitespace().map(str::to_string))
+        .collect()
+}
+
+fn overlap(terms: &HashSet<String>, session_terms: &HashSet<String>) -> f32 {
+    terms.intersection(session_terms).count() as f32 / terms.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_at(text: &str, minutes: i64) -> Event {
+        let mut event = Event::new(
+            None,
+            "u1".to_string(),
+            None,
+            Uuid::nil(),
+            EventContent::Text(text.to_string()),
+        );
+        event.transaction_time =
+            DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::minutes(minutes);
+        event
+    }
+
+    fn options() -> SegmentationOptions {
+        SegmentationOptions {
+            gap: Duration::minutes(30),
+            topic_overlap: 0.2,
+            min_events: 2,
+        }
+    }
+
+    #[test]
+    fn test_time_gap_starts_new_segment() {
+        let events = [
+            event_at("planning the garden beds", 0),
+            event_at("garden beds need compost", 5),
+            event_at("garden beds need watering", 90),
+        ];
+        let refs: Vec<&Event> = events.iter().collect();
+        let boundaries = find_segment_boundaries(&refs, None, &[], options());
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].event_id, events[2].id);
+        assert_eq!(boundaries[0].reason, StreamSegmentReason::TimeGap);
+
+        let segments = assign_segments(&refs, &boundaries);
+        assert_eq!(segments[&events[1].id], 0);
+        assert_eq!(segments[&events[2].id], 1);
+
+        // A gap since the previous cycle is recorded too
+        let boundaries = find_segment_boundaries(
+            &refs[..1],
+            Some(events[0].transaction_time - Duration::hours(2)),
+            &[],
+            options(),
+        );
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].event_id, events[0].id);
+    }
+
+    #[test]
+    fn test_topic_shift_waits_for_min_events() {
+        let events = [
+            event_at("garden beds need compost", 0),
+            // Too early in the session to count as a shift
+            event_at("tax return deadline approaching", 1),
+            event_at("garden compost smells", 2),
+            event_at("quarterly invoice overdue", 3),
+        ];
+        let refs: Vec<&Event> = events.iter().collect();
+        let boundaries = find_segment_boundaries(&refs, None, &[], options());
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].event_id, events[3].id);
+        assert_eq!(boundaries[0].reason, StreamSegmentReason::TopicShift);
+
+        let disabled = SegmentationOptions {
+            topic_overlap: 0.0,
+            ..options()
+        };
+        assert!(find_segment_boundaries(&refs, None, &[], disabled).is_empty());
+    }
+
+    #[test]
+    fn test_known_boundaries_are_kept() {
+        let events = [
+            event_at("garden beds need compost", 0),
+            event_at("garden beds need watering", 1),
+        ];
+        let refs: Vec<&Event> = events.iter().collect();
+        let known = [StreamSegmentBoundary {
+            event_id: events[1].id,
+            at: events[1].transaction_time,
+            reason: StreamSegmentReason::TimeGap,
+        }];
+        // The stream has already moved past these events, as when a batch was deferred
+        let boundaries = find_segment_boundaries(
+            &refs,
+            Some(events[1].transaction_time + Duration::hours(1)),
+            &known,
+            options(),
+        );
+        assert_eq!(boundaries, known.to_vec());
+    }
+}
@@ -0,0 +1,245 @@
+//! Retrieval self-evaluation. A run samples recent L1 memories, has the LLM write a
+//! question each one answers, replays the questions through hybrid search and counts how
+//! often the source memory comes back in the top results. Runs are kept so a drop in
+//! retrieval quality shows up over time.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use memorose_common::MemoryUnit;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::engine::MemoroseEngine;
+use crate::llm::structured::{generate_structured, OutputSchema};
+use crate::llm::LLMClient;
+
+/// Recent L1 memories read per user when building the sample.
+const RECENT_UNITS_PER_USER: usize = 10;
+/// Missed questions kept on a report, for a look at what retrieval got wrong.
+const MAX_REPORTED_MISSES: usize = 10;
+
+const QA_GENERATION_PROMPT: &str = "For each numbered memory below, write one question a user \
+might ask that this memory, and not general knowledge, answers. Phrase it the way the user \
+would ask, without copying the memory's wording. Reply with a JSON array of \
+{\"memory\": number, \"question\": string, \"answer\": string}, one item per memory.";
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SelfEvalOptions {
+    pub sample_size: usize,
+    pub top_k: usize,
+}
+
+/// A generated question whose memory did not come back in the top results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfEvalMiss {
+    pub user_id: String,
+    pub unit_id: Uuid,
+    pub question: String,
+}
+
+/// Outcome of one self-evaluation run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfEvalReport {
+    pub run_at: DateTime<Utc>,
+    /// Memories sampled
+    pub sampled: usize,
+    /// Questions the LLM wrote and retrieval replayed
+    pub questions: usize,
+    pub hits: usize,
+    pub misses: usize,
+    /// Share of questions whose memory ranked within `top_k`
+    pub hit_rate: f32,
+    /// Mean of 1/rank over all questions, with misses counted as 0
+    pub mean_reciprocal_rank: f32,
+    pub top_k: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missed: Vec<SelfEvalMiss>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QaPair {
+    memory: usize,
+    question: String,
+}
+
+/// Run one self-evaluation over the recent memories of active users. Returns `None`
+/// when there is nothing to sample or the LLM wrote no usable question.
+pub(crate) async fn run_self_eval(
+    engine: &MemoroseEngine,
+    client: &dyn LLMClient,
+    options: SelfEvalOptions,
+) -> Result<Option<SelfEvalReport>> {
+    let sample = sample_recent_units(engine, options.sample_size).await?;
+    if sample.is_empty() {
+        return Ok(None);
+    }
+
+    let mut prompt = String::from("Memories:\n");
+    for (index, unit) in sample.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {}\n", index + 1, unit.content.trim()));
+    }
+    let pairs: Vec<QaPair> = generate_structured(
+        client,
+        "self_eval",
+        QA_GENERATION_PROMPT,
+        &prompt,
+        &OutputSchema::qa_pairs(),
+        client.structured_output_retries(),
+    )
+    .await?
+    .data;
+
+    let mut ranks = Vec::new();
+    let mut missed = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for pair in pairs {
+        let question = pair.question.trim();
+        let Some(unit) = pair
+            .memory
+            .checked_sub(1)
+            .and_then(|index| sample.get(index))
+        else {
+            continue;
+        };
+        if question.is_empty() || !seen.insert(unit.id) {
+            continue;
+        }
+        let rank = replay(engine, client, unit, question, options.top_k).await?;
+        if rank.is_none() && missed.len() < MAX_REPORTED_MISSES {
+            missed.push(SelfEvalMiss {
+                user_id: unit.user_id.clone(),
+                unit_id: unit.id,
+                question: question.to_string(),
+            });
+        }
+        ranks.push(rank);
+    }
+    if ranks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut report = score(&ranks, options.top_k);
+    report.sampled = sample.len();
+    report.missed = missed;
+    Ok(Some(report))
+}
+
+/// Up to `sample_size` memories picked at random from the recent L1 memories of active
+/// users. Organization knowledge is left out, since it is searched on another path.
+async fn sample_recent_units(
+    engine: &MemoroseEngine,
+    sample_size: usize,
+) -> Result<Vec<MemoryUnit>> {
+    if sample_size == 0 {
+        return Ok(Vec::new());
+    }
+    let mut user_ids: Vec<String> = engine
+        .system_kv()
+        .scan(b"active_user:")?
+        .into_iter()
+        .filter_map(|(key, _)| {
+            String::from_utf8(key)
+                .ok()?
+                .strip_prefix("active_user:")
+                .map(str::to_string)
+        })
+        .collect();
+    user_ids.shuffle(&mut rand::thread_rng());
+
+    let mut pool = Vec::new();
+    for user_id in user_ids {
+        if pool.len() >= sample_size * 2 {
+            break;
+        }
+        pool.extend(
+            engine
+                .fetch_recent_l1_units(&user_id, RECENT_UNITS_PER_USER)
+                .await?
+                .into_iter()
+                .filter(|unit| {
+                    unit.domain != memorose_common::MemoryDomain::Organization
+                        && !unit.content.trim().is_empty()
+                }),
+        );
+    }
+    pool.shuffle(&mut rand::thread_rng());
+    pool.truncate(sample_size);
+    Ok(pool)
+}
+
+/// Search for `question` as the memory's owner would and return the memory's 1-based
+/// rank, if it is within `top_k`.
+async fn replay(
+    engine: &MemoroseEngine,
+    client: &dyn LLMClient,
+    unit: &MemoryUnit,
+    question: &str,
+    top_k: usize,
+) -> Result<Option<usize>> {
+    let embedding = client.embed(question).await?.data;
+    let results = engine
+        .search_hybrid(
+            &unit.user_id,
+            unit.org_id.as_deref(),
+            unit.agent_id.as_deref(),
+            question,
+            &embedding,
+            top_k.max(1),
+            false,
+            None,
+            1,
+            None,
+            None,
+        )
+        .await?;
+    Ok(results
+        .iter()
+        .position(|(result, _)| result.id == unit.id)
+        .map(|index| index + 1))
+}
+
+/// Hit rate and mean reciprocal rank over the replayed questions' ranks.
+fn score(ranks: &[Option<usize>], top_k: usize) -> SelfEvalReport {
+    let hits = ranks.iter().filter(|rank| rank.is_some()).count();
+    let questions = ranks.len();
+    let reciprocal: f32 = ranks.iter().flatten().map(|&rank| 1.0 / rank as f32).sum();
+    SelfEvalReport {
+        run_at: Utc::now(),
+        sampled: questions,
+        questions,
+        hits,
+        misses: questions - hits,
+        hit_rate: if questions == 0 {
+            0.0
+        } else {
+            hits as f32 / questions as f32
+        },
+        mean_reciprocal_rank: if questions == 0 {
+            0.0
+        } else {
+            reciprocal / questions as f32
+        },
+        top_k,
+        missed: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_counts_hits_and_reciprocal_rank() {
+        let report = score(&[Some(1), Some(2), None, Some(4)], 5);
+        assert_eq!(report.questions, 4);
+        assert_eq!(report.hits, 3);
+        assert_eq!(report.misses, 1);
+        assert_eq!(report.hit_rate, 0.75);
+        assert!((report.mean_reciprocal_rank - (1.0 + 0.5 + 0.25) / 4.0).abs() < 1e-6);
+
+        let empty = score(&[], 5);
+        assert_eq!(empty.hit_rate, 0.0);
+        assert_eq!(empty.mean_reciprocal_rank, 0.0);
+    }
+}
@@ -0,0 +1,311 @@
+use super::index::{TextIndex, TextIndexConfig, TextIndexMetricSnapshot};
+use anyhow::Result;
+use memorose_common::{MemoryUnit, TimeRange};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// File in the text index directory recording how many user buckets it is split into.
+/// It is written only once the index holds every unit, so a directory without it, or one
+/// left by an interrupted rebuild, is rebuilt.
+const BUCKETS_MARKER: &str = "BUCKETS";
+
+/// The text index of a shard, split into buckets by user id so a search only reads the
+/// segments of the searching user's bucket. With one bucket this is a single index in
+/// the directory itself, as before buckets existed.
+#[derive(Clone)]
+pub struct BucketedTextIndex {
+    buckets: Arc<Vec<TextIndex>>,
+    marker_path: PathBuf,
+    rebuild_required: bool,
+}
+
+impl BucketedTextIndex {
+    /// Open `bucket_count` indexes under `path`. An index laid out for another bucket
+    /// count, or one without a layout marker, is removed; [`Self::rebuild_required`] then
+    /// tells the caller to re-index and call [`Self::mark_rebuilt`] once it has committed.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        config: TextIndexConfig,
+        bucket_count: usize,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let bucket_count = bucket_count.max(1);
+        let marker_path = path.join(BUCKETS_MARKER);
+        let existing_count = match std::fs::read_to_string(&marker_path) {
+            Ok(raw) => raw.trim().parse::<usize>().ok(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let rebuild_required = existing_count != Some(bucket_count);
+        if rebuild_required && path.exists() {
+            if std::fs::read_dir(path)?.next().is_some() {
+                tracing::warn!(
+                    "Text index at {:?} has {} user buckets, {} configured; rebuilding it",
+                    path,
+                    existing_count.map_or_else(|| "unknown".to_string(), |n| n.to_string()),
+                    bucket_count
+                );
+            }
+            std::fs::remove_dir_all(path)?;
+        }
+
+        let buckets = if bucket_count == 1 {
+            vec![TextIndex::with_config(path, config)?]
+        } else {
+            (0..bucket_count)
+                .map(|bucket| {
+                    TextIndex::with_config(path.join(format!("bucket-{}", bucket)), config.clone())
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        Ok(Self {
+            buckets: Arc::new(buckets),
+            marker_path,
+            rebuild_required,
+        })
+    }
+
+    /// Record the bucket layout once a rebuild has committed every unit.
+    pub fn mark_rebuilt(&self) -> Result<()> {
+        std::fs::write(&self.marker_path, self.buckets.len().to_string())?;
+        Ok(())
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// The index was missing its layout marker or laid out for another bucket count, and
+    /// was recreated empty.
+    pub fn rebuild_required(&self) -> bool {
+        self.rebuild_required
+    }
+
+    fn bucket(&self, user_id: &str) -> &TextIndex {
+        &self.buckets[user_bucket(user_id, self.buckets.len())]
+    }
+
+    /// The bucket holding `user_id`'s documents, or every bucket without a user.
+    fn buckets_for(&self, user_id: Option<&str>) -> Vec<&TextIndex> {
+        match user_id {
+            Some(user_id) => vec![self.bucket(user_id)],
+            None => self.buckets.iter().collect(),
+        }
+    }
+
+    pub fn index_unit(&self, unit: &MemoryUnit) -> Result<()> {
+        self.bucket(&unit.user_id).index_unit(unit)
+    }
+
+    /// Deletes go to every bucket since only the unit id is known.
+    pub fn delete_unit(&self, id: &str) -> Result<()> {
+        for bucket in self.buckets.iter() {
+            bucket.delete_unit(id)?;
+        }
+        Ok(())
+    }
+
+    pub fn contains_unit(&self, id: &str) -> Result<bool> {
+        for bucket in self.buckets.iter() {
+            if bucket.contains_unit(id)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn commit(&self) -> Result<()> {
+        for bucket in self.buckets.iter() {
+            bucket.commit()?;
+        }
+        Ok(())
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        for bucket in self.buckets.iter() {
+            bucket.reload()?;
+        }
+        Ok(())
+    }
+
+    /// [`TextIndex::sync_pending`] on `user_id`'s bucket, or on every bucket.
+    pub fn sync_pending(&self, user_id: Option<&str>, min_interval: Duration) -> Result<bool> {
+        let mut committed = false;
+        for bucket in self.buckets_for(user_id) {
+            committed |= bucket.sync_pending(min_interval)?;
+        }
+        Ok(committed)
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.buckets.iter().map(TextIndex::segment_count).sum()
+    }
+
+    pub fn merge_segments(&self) -> Result<()> {
+        for bucket in self.buckets.iter() {
+            bucket.merge_segments()?;
+        }
+        Ok(())
+    }
+
+    pub fn metrics_snapshot(&self) -> TextIndexMetricSnapshot {
+        let mut snapshot = TextIndexMetricSnapshot::default();
+        for bucket in self.buckets.iter() {
+            snapshot.merge(&bucket.metrics_snapshot());
+        }
+        snapshot
+    }
+
+    pub fn search(
+        &self,
+        query_str: &str,
+        limit: usize,
+        time_range: Option<TimeRange>,
+        org_id: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Result<Vec<String>> {
+        Ok(self
+            .search_bitemporal_scored(
+                query_str, limit, time_range, None, org_id, user_id, None, None,
+            )?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_bitemporal_scored(
+        &self,
+        query_str: &str,
+        limit: usize,
+        valid_time: Option<TimeRange>,
+        transaction_time: Option<TimeRange>,
+        org_id: Option<&str>,
+        user_id: Option<&str>,
+        agent_id: Option<&str>,
+        domain: Option<&str>,
+    ) -> Result<Vec<(String, Option<f32>)>> {
+        let buckets = self.buckets_for(user_id);
+        if let [bucket] = buckets.as_slice() {
+            return bucket.search_bitemporal_scored(
+                query_str,
+                limit,
+                valid_time,
+                transaction_time,
+                org_id,
+                user_id,
+                agent_id,
+                domain,
+            );
+        }
+
+        let mut hits = Vec::new();
+        for bucket in buckets {
+            hits.extend(bucket.search_bitemporal_scored(
+                query_str,
+                limit,
+                valid_time.clone(),
+                transaction_time.clone(),
+                org_id,
+                user_id,
+                agent_id,
+                domain,
+            )?);
+        }
+        // Uncommitted overlay hits carry no score and rank first, as within one index.
+        hits.sort_by(|a, b| {
+            let score = |hit: &(String, Option<f32>)| hit.1.unwrap_or(f32::INFINITY);
+            score(b).total_cmp(&score(a))
+        });
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+/// Bucket of `user_id` among `bucket_count`. Uses other hash bytes than shard routing,
+/// so the users of one shard still spread over all its buckets.
+fn user_bucket(user_id: &str, bucket_count: usize) -> usize {
+    if bucket_count <= 1 {
+        return 0;
+    }
+    let hash = Sha256::digest(user_id.as_bytes());
+    u32::from_le_bytes([hash[4], hash[5], hash[6], hash[7]]) as usize % bucket_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    fn unit(user_id: &str, content: &str) -> MemoryUnit {
+        MemoryUnit::new(
+            None,
+            user_id.into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::MemoryType::Factual,
+            content.into(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_bucketed_text_index_routes_users_and_rebuilds_on_layout_change() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let path = temp_dir.path().join("tantivy");
+            let users: Vec<String> = (0..16).map(|i| format!("user-{}", i)).collect();
+            let buckets: std::collections::HashSet<usize> =
+                users.iter().map(|user| user_bucket(user, 4)).collect();
+            assert!(buckets.len() > 1);
+
+            let index = BucketedTextIndex::open(&path, TextIndexConfig::legacy(60_000), 1)?;
+            assert!(index.rebuild_required());
+            index.index_unit(&unit("user-0", "single index document"))?;
+            index.commit()?;
+            index.mark_rebuilt()?;
+            drop(index);
+
+            let index = BucketedTextIndex::open(&path, TextIndexConfig::legacy(60_000), 4)?;
+            assert!(index.rebuild_required());
+            assert_eq!(index.bucket_count(), 4);
+            index.index_unit(&unit("user-0", "interrupted document"))?;
+            index.commit()?;
+            drop(index);
+
+            // The rebuild never finished, so it starts over.
+            let index = BucketedTextIndex::open(&path, TextIndexConfig::legacy(60_000), 4)?;
+            assert!(index.rebuild_required());
+            for user in &users {
+                index.index_unit(&unit(user, "bucketed document"))?;
+            }
+            index.commit()?;
+            index.reload()?;
+            index.mark_rebuilt()?;
+
+            let hits = index.search("bucketed", 10, None, None, Some("user-3"))?;
+            assert_eq!(hits.len(), 1);
+            assert_eq!(index.search("bucketed", 100, None, None, None)?.len(), 16);
+            assert!(index.search("single", 10, None, None, None)?.is_empty());
+            assert!(index
+                .search("interrupted", 10, None, None, None)?
+                .is_empty());
+            drop(index);
+
+            let index = BucketedTextIndex::open(&path, TextIndexConfig::legacy(60_000), 4)?;
+            assert!(!index.rebuild_required());
+            drop(index);
+
+            let index = BucketedTextIndex::open(&path, TextIndexConfig::legacy(60_000), 1)?;
+            assert!(index.rebuild_required());
+            assert!(index.search("bucketed", 100, None, None, None)?.is_empty());
+            Ok(())
+        })
+    }
+}
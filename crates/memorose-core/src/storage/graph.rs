@@ -1,7 +1,8 @@
+use crate::storage::kv::KvStore;
 use anyhow::{Context, Result};
 use arrow_array::{Float32Array, RecordBatch, StringArray, TimestampMicrosecondArray};
 use arrow_schema::{DataType, Field, Schema, TimeUnit};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
 use lancedb::query::{ExecutableQuery, QueryBase};
 use lancedb::Connection;
@@ -11,6 +12,9 @@ use std::time::Duration;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// Set once the adjacency index has been backfilled from the LanceDB edge table.
+const ADJACENCY_BUILT_KEY: &[u8] = b"graph_adjacency_built";
+
 fn create_graph_schema() -> Arc<Schema> {
     Arc::new(Schema::new(vec![
         Field::new("user_id", DataType::Utf8, false),
@@ -30,11 +34,22 @@ fn create_graph_schema() -> Arc<Schema> {
     ]))
 }
 
+/// A version of an edge that was deleted, or replaced by a newer write of the same edge.
+/// Kept so the graph can be reconstructed as of an earlier time.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct RemovedGraphEdge {
+    pub edge: GraphEdge,
+    pub removed_at: DateTime<Utc>,
+}
+
 #[derive(Clone)]
 pub struct GraphStore {
     db: Option<Arc<Connection>>,
     buffer: Arc<Mutex<Vec<GraphEdge>>>,
     table_name: String,
+    /// RocksDB mirror of the edge table keyed by endpoint, so neighbour lookups are prefix
+    /// scans instead of LanceDB filter queries. Analytical scans still read LanceDB.
+    adjacency: Option<KvStore>,
     _shutdown: Option<Arc<tokio::sync::Notify>>,
     _flush_task: Option<Arc<tokio::task::JoinHandle<()>>>,
 }
@@ -83,6 +98,7 @@ impl GraphStore {
             db: Some(db),
             buffer,
             table_name,
+            adjacency: None,
             _shutdown: Some(shutdown),
             _flush_task: Some(Arc::new(flush_task)),
         };
@@ -95,6 +111,7 @@ impl GraphStore {
             db: None,
             buffer: Arc::new(Mutex::new(Vec::new())),
             table_name: "relationships".to_string(),
+            adjacency: None,
             _shutdown: None,
             _flush_task: None,
         }
@@ -104,6 +121,152 @@ impl GraphStore {
         self.db.as_ref()
     }
 
+    /// Serve neighbour lookups from an adjacency index in `kv`, building it from the
+    /// LanceDB table the first time.
+    pub async fn with_adjacency_index(mut self, kv: KvStore) -> Result<Self> {
+        if self.db().is_some() && kv.get(ADJACENCY_BUILT_KEY)?.is_none() {
+            let edges = self.scan_all_edges().await?;
+            Self::index_edges(&kv, &edges)?;
+            kv.put(ADJACENCY_BUILT_KEY, b"1")?;
+            tracing::info!("Built graph adjacency index from {} edges", edges.len());
+        }
+        self.adjacency = Some(kv);
+        Ok(self)
+    }
+
+    fn adjacency_edge_suffix(edge: &GraphEdge) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            edge.relation.as_str(),
+            edge.edge_kind.as_str(),
+            edge.namespace_key,
+            edge.source_namespace_key,
+            edge.target_namespace_key
+        )
+    }
+
+    fn outgoing_prefix(user_id: &str, source_id: Uuid) -> String {
+        format!("u:{}:edge:out:{}:", user_id, source_id)
+    }
+
+    fn incoming_prefix(user_id: &str, target_id: Uuid) -> String {
+        format!("u:{}:edge:in:{}:", user_id, target_id)
+    }
+
+    fn removed_prefix(user_id: &str) -> String {
+        format!("u:{}:edgelog:", user_id)
+    }
+
+    /// Ordered by removal time, so the log reads oldest first.
+    fn removed_key(edge: &GraphEdge, removed_at: DateTime<Utc>) -> String {
+        format!(
+            "{}{:020}:{}:{}:{}:{}",
+            Self::removed_prefix(&edge.user_id),
+            removed_at.timestamp_micros(),
+            edge.source_id,
+            edge.target_id,
+            edge.transaction_time.timestamp_micros(),
+            Self::adjacency_edge_suffix(edge)
+        )
+    }
+
+    fn log_removed(batch: &mut rocksdb::WriteBatch, edge: &GraphEdge, removed_at: DateTime<Utc>) {
+        let record = RemovedGraphEdge {
+            edge: edge.clone(),
+            removed_at,
+        };
+        if let Ok(value) = serde_json::to_vec(&record) {
+            batch.put(Self::removed_key(edge, removed_at).as_bytes(), &value);
+        }
+    }
+
+    fn adjacency_keys(edge: &GraphEdge) -> [String; 2] {
+        let suffix = Self::adjacency_edge_suffix(edge);
+        [
+            format!(
+                "{}{}:{}",
+                Self::outgoing_prefix(&edge.user_id, edge.source_id),
+                edge.target_id,
+                suffix
+            ),
+            format!(
+                "{}{}:{}",
+                Self::incoming_prefix(&edge.user_id, edge.target_id),
+                edge.source_id,
+                suffix
+            ),
+        ]
+    }
+
+    /// Mirror `edges` into the adjacency index, keeping the same winner as `dedup_edges`
+    /// when a key is already present.
+    fn index_edges(kv: &KvStore, edges: &[GraphEdge]) -> Result<()> {
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut staged: std::collections::HashMap<String, GraphEdge> =
+            std::collections::HashMap::new();
+        for edge in edges {
+            let [out_key, in_key] = Self::adjacency_keys(edge);
+            let existing = match staged.get(&out_key) {
+                Some(edge) => Some(edge.clone()),
+                None => kv
+                    .get(out_key.as_bytes())?
+                    .and_then(|bytes| serde_json::from_slice::<GraphEdge>(&bytes).ok()),
+            };
+            if let Some(existing) = existing {
+                let newer = edge.transaction_time > existing.transaction_time
+                    || (edge.transaction_time == existing.transaction_time
+                        && edge.weight > existing.weight);
+                if !newer {
+                    continue;
+                }
+                Self::log_removed(&mut batch, &existing, edge.transaction_time);
+            }
+            let value = serde_json::to_vec(edge)?;
+            batch.put(out_key.as_bytes(), &value);
+            batch.put(in_key.as_bytes(), &value);
+            staged.insert(out_key, edge.clone());
+        }
+        kv.write_batch(batch)
+    }
+
+    fn read_adjacency(kv: &KvStore, prefix: &str) -> Result<Vec<GraphEdge>> {
+        Ok(kv
+            .scan(prefix.as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<GraphEdge>(&value).ok())
+            .collect())
+    }
+
+    fn unindex_edges(kv: &KvStore, edges: &[GraphEdge]) -> Result<()> {
+        let mut batch = rocksdb::WriteBatch::default();
+        let removed_at = Utc::now();
+        for edge in edges {
+            for key in Self::adjacency_keys(edge) {
+                batch.delete(key.as_bytes());
+            }
+            Self::log_removed(&mut batch, edge, removed_at);
+        }
+        kv.write_batch(batch)
+    }
+
+    /// Edge versions of `user_id` deleted or replaced after `since`, oldest removal first.
+    /// Empty without an adjacency index, which holds the log.
+    pub fn removed_edges_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<RemovedGraphEdge>> {
+        let Some(kv) = &self.adjacency else {
+            return Ok(Vec::new());
+        };
+        Ok(kv
+            .scan(Self::removed_prefix(user_id).as_bytes())?
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_slice::<RemovedGraphEdge>(&value).ok())
+            .filter(|removed| removed.removed_at > since)
+            .collect())
+    }
+
     async fn init(&self) -> Result<()> {
         let Some(db) = self.db() else {
             return Ok(());
@@ -144,10 +307,18 @@ impl GraphStore {
         Ok(())
     }
 
+    /// Whether neighbour lookups are served from the RocksDB adjacency index.
+    pub fn has_adjacency_index(&self) -> bool {
+        self.adjacency.is_some()
+    }
+
     pub async fn add_edge(&self, edge: &GraphEdge) -> Result<()> {
         if self.db().is_none() {
             return Ok(());
         }
+        if let Some(kv) = &self.adjacency {
+            Self::index_edges(kv, std::slice::from_ref(edge))?;
+        }
 
         let should_flush = {
             let mut buf = self.buffer.lock().await;
@@ -161,27 +332,24 @@ impl GraphStore {
         Ok(())
     }
 
-    pub async fn flush(&self) -> Result<()> {
+    /// Write `edges` as a single Arrow batch, bypassing the flush buffer.
+    pub async fn add_edges(&self, edges: &[GraphEdge]) -> Result<()> {
         let Some(db) = self.db() else {
             return Ok(());
         };
-        Self::flush_with_refs(db, &self.buffer, &self.table_name).await
+        if edges.is_empty() {
+            return Ok(());
+        }
+        let batch = Self::edges_to_record_batch(edges)?;
+        let table = db.open_table(&self.table_name).execute().await?;
+        table.add(vec![batch]).execute().await?;
+        if let Some(kv) = &self.adjacency {
+            Self::index_edges(kv, edges)?;
+        }
+        Ok(())
     }
 
-    async fn flush_with_refs(
-        db: &Arc<Connection>,
-        buffer: &Arc<Mutex<Vec<GraphEdge>>>,
-        table_name: &str,
-    ) -> Result<()> {
-        // Atomically drain the buffer. New edges can be added concurrently while we write.
-        let edges = {
-            let mut buf = buffer.lock().await;
-            if buf.is_empty() {
-                return Ok(());
-            }
-            std::mem::take(&mut *buf)
-        };
-
+    fn edges_to_record_batch(edges: &[GraphEdge]) -> Result<RecordBatch> {
         let schema = create_graph_schema();
 
         let user_ids: Vec<String> = edges.iter().map(|e| e.user_id.clone()).collect();
@@ -210,8 +378,8 @@ impl GraphStore {
             .map(|e| e.transaction_time.timestamp_micros())
             .collect();
 
-        let batch = RecordBatch::try_new(
-            schema.clone(),
+        Ok(RecordBatch::try_new(
+            schema,
             vec![
                 Arc::new(StringArray::from(user_ids)),
                 Arc::new(StringArray::from(namespace_keys)),
@@ -224,7 +392,31 @@ impl GraphStore {
                 Arc::new(Float32Array::from(weights)),
                 Arc::new(TimestampMicrosecondArray::from(times).with_timezone("UTC")),
             ],
-        );
+        )?)
+    }
+
+    pub async fn flush(&self) -> Result<()> {
+        let Some(db) = self.db() else {
+            return Ok(());
+        };
+        Self::flush_with_refs(db, &self.buffer, &self.table_name).await
+    }
+
+    async fn flush_with_refs(
+        db: &Arc<Connection>,
+        buffer: &Arc<Mutex<Vec<GraphEdge>>>,
+        table_name: &str,
+    ) -> Result<()> {
+        // Atomically drain the buffer. New edges can be added concurrently while we write.
+        let edges = {
+            let mut buf = buffer.lock().await;
+            if buf.is_empty() {
+                return Ok(());
+            }
+            std::mem::take(&mut *buf)
+        };
+
+        let batch = Self::edges_to_record_batch(&edges);
 
         let write_result = async {
             let batch = batch?;
@@ -330,6 +522,9 @@ impl GraphStore {
         let Some(db) = self.db() else {
             return Ok(Vec::new());
         };
+        if let Some(kv) = &self.adjacency {
+            return Self::read_adjacency(kv, &Self::outgoing_prefix(user_id, source_id));
+        }
 
         let mut edges = {
             let buf = self.buffer.lock().await;
@@ -364,6 +559,9 @@ impl GraphStore {
         let Some(db) = self.db() else {
             return Ok(Vec::new());
         };
+        if let Some(kv) = &self.adjacency {
+            return Self::read_adjacency(kv, &Self::incoming_prefix(user_id, target_id));
+        }
 
         let mut edges = {
             let buf = self.buffer.lock().await;
@@ -457,6 +655,62 @@ impl GraphStore {
         Ok(edges)
     }
 
+    /// Re-buffer an applied edge that never reached the Lance table, e.g. because the
+    /// process crashed before the buffer was flushed. The adjacency index is durable, so it
+    /// decides which version to restore; edges it no longer holds were deleted since and stay
+    /// gone. Returns whether the edge was restored.
+    pub async fn restore_unflushed_edge(&self, edge: &GraphEdge) -> Result<bool> {
+        let Some(db) = self.db() else {
+            return Ok(false);
+        };
+        let same = |e: &GraphEdge| {
+            e.user_id == edge.user_id
+                && e.source_id == edge.source_id
+                && e.target_id == edge.target_id
+                && e.relation == edge.relation
+        };
+        let current = match &self.adjacency {
+            Some(kv) => {
+                match Self::read_adjacency(
+                    kv,
+                    &Self::outgoing_prefix(&edge.user_id, edge.source_id),
+                )?
+                .into_iter()
+                .find(|e| same(e))
+                {
+                    Some(current) => current,
+                    None => return Ok(false),
+                }
+            }
+            None => edge.clone(),
+        };
+        if self.buffer.lock().await.iter().any(same) {
+            return Ok(false);
+        }
+
+        let table = db.open_table(&self.table_name).execute().await?;
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .only_if(format!(
+                "user_id = '{}' AND source_id = '{}' AND target_id = '{}' AND relation = '{}'",
+                edge.user_id.replace('\'', "''"),
+                edge.source_id,
+                edge.target_id,
+                edge.relation.as_str().replace('\'', "''")
+            ))
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        if batches.iter().any(|batch| batch.num_rows() > 0) {
+            return Ok(false);
+        }
+
+        self.buffer.lock().await.push(current);
+        Ok(true)
+    }
+
     pub async fn scan_all_edges(&self) -> Result<Vec<GraphEdge>> {
         let Some(db) = self.db() else {
             return Ok(Vec::new());
@@ -533,13 +787,70 @@ impl GraphStore {
             .await?
             .try_collect::<Vec<RecordBatch>>()
             .await?;
-        let deleted_in_store = self.batches_to_edges(existing)?.len();
+        let existing = self.batches_to_edges(existing)?;
+        let deleted_in_store = existing.len();
 
         table.delete(&filter).await?;
+        if let Some(kv) = &self.adjacency {
+            let mut indexed = Self::read_adjacency(kv, &Self::outgoing_prefix(user_id, node_id))?;
+            indexed.extend(Self::read_adjacency(
+                kv,
+                &Self::incoming_prefix(user_id, node_id),
+            )?);
+            indexed.extend(existing);
+            Self::unindex_edges(kv, &indexed)?;
+        }
 
         Ok(deleted_in_buffer + deleted_in_store)
     }
 
+    /// Delete exactly these edges, matched by user, namespace, endpoints and relation.
+    /// Returns how many were removed.
+    pub async fn delete_edges(&self, edges: &[GraphEdge]) -> Result<usize> {
+        let Some(db) = self.db() else {
+            return Ok(0);
+        };
+        if edges.is_empty() {
+            return Ok(0);
+        }
+        let same = |a: &GraphEdge, b: &GraphEdge| {
+            a.user_id == b.user_id
+                && a.namespace_key == b.namespace_key
+                && a.source_id == b.source_id
+                && a.target_id == b.target_id
+                && a.relation == b.relation
+        };
+
+        {
+            let mut buf = self.buffer.lock().await;
+            buf.retain(|buffered| !edges.iter().any(|edge| same(buffered, edge)));
+        }
+
+        let table = db.open_table(&self.table_name).execute().await?;
+        for chunk in edges.chunks(100) {
+            let filter = chunk
+                .iter()
+                .map(|edge| {
+                    format!(
+                        "(user_id = '{}' AND namespace_key = '{}' AND source_id = '{}' AND target_id = '{}' AND relation = '{}')",
+                        edge.user_id.replace('\'', "''"),
+                        edge.namespace_key.replace('\'', "''"),
+                        edge.source_id,
+                        edge.target_id,
+                        edge.relation.as_str().replace('\'', "''")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            table.delete(&filter).await?;
+        }
+        if let Some(kv) = &self.adjacency {
+            Self::unindex_edges(kv, edges)?;
+        }
+
+        Ok(edges.len())
+    }
+
     /// 批量查询多个节点的出边（使用 SQL IN 子句，性能优化版本）
     pub async fn batch_get_outgoing_edges(
         &self,
@@ -554,6 +865,16 @@ impl GraphStore {
         let Some(db) = self.db() else {
             return Ok(HashMap::new());
         };
+        if let Some(kv) = &self.adjacency {
+            let mut result = HashMap::new();
+            for &source_id in source_ids {
+                let edges = Self::read_adjacency(kv, &Self::outgoing_prefix(user_id, source_id))?;
+                if !edges.is_empty() {
+                    result.insert(source_id, edges);
+                }
+            }
+            return Ok(result);
+        }
 
         // 先检查缓冲区
         let mut result: HashMap<Uuid, Vec<GraphEdge>> = HashMap::new();
@@ -623,6 +944,16 @@ impl GraphStore {
         let Some(db) = self.db() else {
             return Ok(HashMap::new());
         };
+        if let Some(kv) = &self.adjacency {
+            let mut result = HashMap::new();
+            for &target_id in target_ids {
+                let edges = Self::read_adjacency(kv, &Self::incoming_prefix(user_id, target_id))?;
+                if !edges.is_empty() {
+                    result.insert(target_id, edges);
+                }
+            }
+            return Ok(result);
+        }
 
         // 先检查缓冲区
         let mut result: HashMap<Uuid, Vec<GraphEdge>> = HashMap::new();
@@ -788,19 +1119,7 @@ impl GraphStore {
                 let target = Uuid::parse_str(target_col.value(i)).unwrap_or_default();
                 let edge_kind = EdgeKind::from_str(edge_kind_col.value(i));
                 let rel_str = rel_col.value(i);
-                let relation = match rel_str {
-                    "Next" => RelationType::Next,
-                    "IsSubTaskOf" => RelationType::IsSubTaskOf,
-                    "Contradicts" => RelationType::Contradicts,
-                    "DerivedFrom" => RelationType::DerivedFrom,
-                    "EvolvedTo" => RelationType::EvolvedTo,
-                    "Supports" => RelationType::Supports,
-                    "Abstracts" => RelationType::Abstracts,
-                    "CausedBy" => RelationType::CausedBy,
-                    "Blocks" => RelationType::Blocks,
-                    "Accomplishes" => RelationType::Accomplishes,
-                    _ => RelationType::RelatedTo,
-                };
+                let relation = RelationType::from_str(rel_str);
                 let ts_micros = time_col.value(i);
                 // Use Euclidean division so the nanosecond remainder is always non-negative,
                 // which correctly handles timestamps before the Unix epoch.
@@ -967,6 +1286,75 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_adjacency_index_backfills_and_serves_neighbour_lookups() -> Result<()> {
+        let kv_dir = tempfile::tempdir()?;
+        let kv = KvStore::open(kv_dir.path())?;
+        let store = test_store().await?;
+        let node_a = Uuid::new_v4();
+        let node_b = Uuid::new_v4();
+        let node_c = Uuid::new_v4();
+
+        // Edges that predate the index are picked up by the one-time backfill.
+        let edge_ab = scoped_edge("user1", node_a, node_b, RelationType::RelatedTo, 0.4, "ns");
+        store.add_edge(&edge_ab).await?;
+        let store = store.with_adjacency_index(kv.clone()).await?;
+        assert!(kv.get(ADJACENCY_BUILT_KEY)?.is_some());
+
+        let edge_cb = scoped_edge("user1", node_c, node_b, RelationType::Supports, 0.6, "ns");
+        store.add_edge(&edge_cb).await?;
+        let mut stale = edge_ab.clone();
+        stale.weight = 0.1;
+        stale.transaction_time = edge_ab.transaction_time - Duration::seconds(10);
+        store.add_edge(&stale).await?;
+
+        let outgoing = store.get_outgoing_edges("user1", node_a).await?;
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(
+            outgoing[0].weight, 0.4,
+            "an older write never replaces a newer one"
+        );
+        assert_eq!(store.get_incoming_edges("user1", node_b).await?.len(), 2);
+        assert!(store.get_outgoing_edges("user2", node_a).await?.is_empty());
+
+        let batch = store
+            .batch_get_incoming_edges("user1", &[node_b, node_c])
+            .await?;
+        assert_eq!(batch.get(&node_b).map(Vec::len), Some(2));
+        assert!(!batch.contains_key(&node_c));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_unflushed_edge_rebuffers_only_indexed_edges() -> Result<()> {
+        let kv_dir = tempfile::tempdir()?;
+        let kv = KvStore::open(kv_dir.path())?;
+        let store = test_store().await?.with_adjacency_index(kv).await?;
+        let node_a = Uuid::new_v4();
+        let node_b = Uuid::new_v4();
+
+        let edge = scoped_edge("user1", node_a, node_b, RelationType::RelatedTo, 0.4, "ns");
+        let mut heavier = edge.clone();
+        heavier.weight = 0.8;
+        heavier.transaction_time = edge.transaction_time + Duration::seconds(1);
+        store.add_edge(&edge).await?;
+        store.add_edge(&heavier).await?;
+        // A crash loses the unflushed buffer but not the adjacency index.
+        store.buffer.lock().await.clear();
+
+        assert!(store.restore_unflushed_edge(&edge).await?);
+        assert!(!store.restore_unflushed_edge(&edge).await?);
+        {
+            let buffer = store.buffer.lock().await;
+            assert_eq!(buffer.len(), 1);
+            assert_eq!(buffer[0].weight, 0.8, "the indexed version is restored");
+        }
+
+        let deleted = scoped_edge("user1", node_b, node_a, RelationType::RelatedTo, 0.4, "ns");
+        assert!(!store.restore_unflushed_edge(&deleted).await?);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_reinforce_edge_and_delete_edges_for_node() -> Result<()> {
         let store = test_store().await?;
@@ -1,21 +1,67 @@
 use anyhow::Result;
 use memorose_common::{MemoryUnit, TimeRange};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::Bound;
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tantivy::indexer::{LogMergePolicy, MergePolicy, NoMergePolicy};
 use tantivy::schema::*;
 use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy};
 
+/// Shortest poll of the commit loop when an app commit interval asks for quick commits.
+const MIN_APP_COMMIT_POLL_MS: u64 = 50;
+
+/// Settings of Tantivy's log merge policy.
+#[derive(Debug, Clone)]
+pub struct TextIndexMergePolicy {
+    pub enabled: bool,
+    pub min_num_segments: usize,
+    pub max_docs_before_merge: usize,
+    pub min_layer_size: u32,
+    pub level_log_size: f64,
+    pub del_docs_ratio: f32,
+}
+
+impl Default for TextIndexMergePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_num_segments: 8,
+            max_docs_before_merge: 10_000_000,
+            min_layer_size: 10_000,
+            level_log_size: 0.75,
+            del_docs_ratio: 1.0,
+        }
+    }
+}
+
+impl TextIndexMergePolicy {
+    fn build(&self) -> Box<dyn MergePolicy> {
+        if !self.enabled {
+            return Box::new(NoMergePolicy);
+        }
+        let mut policy = LogMergePolicy::default();
+        policy.set_min_num_segments(self.min_num_segments.max(2));
+        policy.set_max_docs_before_merge(self.max_docs_before_merge.max(1));
+        policy.set_min_layer_size(self.min_layer_size);
+        policy.set_level_log_size(self.level_log_size);
+        policy.set_del_docs_ratio_before_merge(self.del_docs_ratio.clamp(f32::EPSILON, 1.0));
+        Box::new(policy)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TextIndexConfig {
     pub commit_min_interval_ms: u64,
     pub commit_max_interval_ms: u64,
     pub commit_docs_threshold: usize,
     pub commit_bytes_threshold: u64,
+    /// Longest a document of these apps waits for a commit, by app id
+    pub app_commit_interval_ms: BTreeMap<String, u64>,
+    pub merge_policy: TextIndexMergePolicy,
     pub recent_overlay_enabled: bool,
     pub recent_overlay_ttl_secs: u64,
     pub recent_overlay_per_user_max_docs: usize,
@@ -32,6 +78,8 @@ impl TextIndexConfig {
             commit_max_interval_ms: interval_ms,
             commit_docs_threshold: usize::MAX,
             commit_bytes_threshold: u64::MAX,
+            app_commit_interval_ms: BTreeMap::new(),
+            merge_policy: TextIndexMergePolicy::default(),
             recent_overlay_enabled: true,
             recent_overlay_ttl_secs: 120,
             recent_overlay_per_user_max_docs: 1000,
@@ -53,6 +101,15 @@ impl TextIndexConfig {
             commit_max_interval_ms,
             commit_docs_threshold: storage.index_commit_docs_threshold.max(1),
             commit_bytes_threshold: storage.index_commit_bytes_threshold.max(1),
+            app_commit_interval_ms: storage.index_app_commit_interval_ms.clone(),
+            merge_policy: TextIndexMergePolicy {
+                enabled: storage.index_merge_enabled,
+                min_num_segments: storage.index_merge_min_num_segments,
+                max_docs_before_merge: storage.index_merge_max_docs_before_merge,
+                min_layer_size: storage.index_merge_min_layer_size,
+                level_log_size: storage.index_merge_level_log_size,
+                del_docs_ratio: storage.index_merge_del_docs_ratio,
+            },
             recent_overlay_enabled: storage.recent_overlay_enabled,
             recent_overlay_ttl_secs: storage.recent_overlay_ttl_secs.max(1),
             recent_overlay_per_user_max_docs: storage.recent_overlay_per_user_max_docs.max(1),
@@ -63,7 +120,18 @@ impl TextIndexConfig {
     }
 
     fn poll_interval(&self) -> Duration {
-        Duration::from_millis(self.commit_min_interval_ms.min(1000).max(1))
+        let app_interval_ms = self
+            .app_commit_interval_ms
+            .values()
+            .min()
+            .map_or(u64::MAX, |interval_ms| {
+                (*interval_ms).max(MIN_APP_COMMIT_POLL_MS)
+            });
+        Duration::from_millis(
+            self.commit_min_interval_ms
+                .min(app_interval_ms)
+                .clamp(1, 1000),
+        )
     }
 
     fn commit_min_interval(&self) -> Duration {
@@ -128,6 +196,8 @@ struct PendingCommitState {
     dirty_bytes: u64,
     last_commit_at: Instant,
     current_commit_seq: u64,
+    /// Earliest commit deadline set by an app commit interval
+    app_commit_deadline: Option<Instant>,
 }
 
 impl PendingCommitState {
@@ -137,8 +207,35 @@ impl PendingCommitState {
             dirty_bytes: 0,
             last_commit_at: Instant::now(),
             current_commit_seq: 0,
+            app_commit_deadline: None,
         }
     }
+
+    fn is_commit_due(&self, config: &TextIndexConfig) -> bool {
+        if self.dirty_docs == 0 {
+            return false;
+        }
+        if self
+            .app_commit_deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            return true;
+        }
+        let elapsed = self.last_commit_at.elapsed();
+        elapsed >= config.commit_min_interval()
+            && (self.dirty_docs >= config.commit_docs_threshold
+                || self.dirty_bytes >= config.commit_bytes_threshold
+                || elapsed >= config.commit_max_interval())
+    }
+
+    fn mark_committed(&mut self) -> u64 {
+        self.dirty_docs = 0;
+        self.dirty_bytes = 0;
+        self.app_commit_deadline = None;
+        self.last_commit_at = Instant::now();
+        self.current_commit_seq += 1;
+        self.current_commit_seq
+    }
 }
 
 #[derive(Clone)]
@@ -149,6 +246,7 @@ struct RecentDoc {
     agent_id: Option<String>,
     domain: String,
     content: String,
+    keywords: String,
     transaction_time_micros: i64,
     valid_time_micros: Option<i64>,
     inserted_at: Instant,
@@ -296,7 +394,7 @@ impl RecentOverlay {
                     continue;
                 }
 
-                let score = overlay_match_score(&doc.content, &terms);
+                let score = overlay_match_score(&doc.content, &doc.keywords, &terms);
                 if score == 0 {
                     continue;
                 }
@@ -430,6 +528,8 @@ pub struct TextIndex {
     reader: IndexReader,
     config: TextIndexConfig,
     commit_state: Arc<Mutex<PendingCommitState>>,
+    /// Serializes commits forced by strong-consistency reads
+    sync_lock: Arc<Mutex<()>>,
     overlay: Arc<Mutex<RecentOverlay>>,
     metrics: Arc<TextIndexRuntimeMetrics>,
     _shutdown: Arc<tokio::sync::Notify>,
@@ -461,6 +561,7 @@ impl TextIndex {
         schema_builder.add_text_field("domain", STRING | STORED);
         schema_builder.add_text_field("namespace_key", STRING | STORED);
         schema_builder.add_text_field("content", TEXT | STORED);
+        schema_builder.add_text_field("keywords", TEXT);
         schema_builder.add_text_field("stream_id", STRING);
         schema_builder.add_u64_field("level", INDEXED | STORED);
         schema_builder.add_i64_field("transaction_time", INDEXED | STORED | FAST);
@@ -484,6 +585,7 @@ impl TextIndex {
         };
 
         let writer = index.writer(50_000_000)?;
+        writer.set_merge_policy(config.merge_policy.build());
         let reader = index
             .reader_builder()
             .reload_policy(ReloadPolicy::Manual)
@@ -519,23 +621,10 @@ impl TextIndex {
                         }
 
                         let should_commit = match commit_state_clone.lock() {
-                            Ok(state) => {
-                                let elapsed = state.last_commit_at.elapsed();
-                                state.dirty_docs > 0
-                                    && elapsed >= commit_config.commit_min_interval()
-                                    && (state.dirty_docs >= commit_config.commit_docs_threshold
-                                        || state.dirty_bytes >= commit_config.commit_bytes_threshold
-                                        || elapsed >= commit_config.commit_max_interval())
-                            }
+                            Ok(state) => state.is_commit_due(&commit_config),
                             Err(e) => {
                                 tracing::warn!("TextIndex commit state mutex was poisoned; recovering");
-                                let state = e.into_inner();
-                                let elapsed = state.last_commit_at.elapsed();
-                                state.dirty_docs > 0
-                                    && elapsed >= commit_config.commit_min_interval()
-                                    && (state.dirty_docs >= commit_config.commit_docs_threshold
-                                        || state.dirty_bytes >= commit_config.commit_bytes_threshold
-                                        || elapsed >= commit_config.commit_max_interval())
+                                e.into_inner().is_commit_due(&commit_config)
                             }
                         };
 
@@ -586,21 +675,10 @@ impl TextIndex {
                                 }
 
                                 let committed_seq = match commit_state_clone.lock() {
-                                    Ok(mut state) => {
-                                        state.dirty_docs = 0;
-                                        state.dirty_bytes = 0;
-                                        state.last_commit_at = Instant::now();
-                                        state.current_commit_seq += 1;
-                                        state.current_commit_seq
-                                    }
+                                    Ok(mut state) => state.mark_committed(),
                                     Err(e) => {
                                         tracing::warn!("TextIndex commit state mutex was poisoned after commit; recovering");
-                                        let mut state = e.into_inner();
-                                        state.dirty_docs = 0;
-                                        state.dirty_bytes = 0;
-                                        state.last_commit_at = Instant::now();
-                                        state.current_commit_seq += 1;
-                                        state.current_commit_seq
+                                        e.into_inner().mark_committed()
                                     }
                                 };
 
@@ -630,6 +708,7 @@ impl TextIndex {
             reader,
             config,
             commit_state,
+            sync_lock: Arc::new(Mutex::new(())),
             overlay,
             metrics,
             _shutdown: shutdown,
@@ -646,6 +725,7 @@ impl TextIndex {
         let domain_field = schema.get_field("domain").unwrap();
         let namespace_key_field = schema.get_field("namespace_key").unwrap();
         let content_field = schema.get_field("content").unwrap();
+        let keywords_field = schema.get_field("keywords").unwrap();
         let stream_field = schema.get_field("stream_id").unwrap();
         let level_field = schema.get_field("level").unwrap();
         let tx_time_field = schema.get_field("transaction_time").unwrap();
@@ -659,6 +739,9 @@ impl TextIndex {
         doc.add_text(domain_field, unit.domain.as_str());
         doc.add_text(namespace_key_field, &unit.namespace_key);
         doc.add_text(content_field, &unit.content);
+        for keyword in &unit.keywords {
+            doc.add_text(keywords_field, keyword);
+        }
         doc.add_text(stream_field, &unit.stream_id.to_string());
         doc.add_u64(level_field, unit.level as u64);
         doc.add_i64(tx_time_field, unit.transaction_time.timestamp_micros());
@@ -681,6 +764,18 @@ impl TextIndex {
             });
             state.dirty_docs += 1;
             state.dirty_bytes = state.dirty_bytes.saturating_add(estimated_bytes as u64);
+            if let Some(interval_ms) = unit
+                .agent_id
+                .as_ref()
+                .and_then(|app_id| self.config.app_commit_interval_ms.get(app_id))
+            {
+                let deadline = Instant::now() + Duration::from_millis(*interval_ms);
+                state.app_commit_deadline = Some(
+                    state
+                        .app_commit_deadline
+                        .map_or(deadline, |current| current.min(deadline)),
+                );
+            }
             state.current_commit_seq + 1
         };
 
@@ -691,6 +786,7 @@ impl TextIndex {
             agent_id: unit.agent_id.clone(),
             domain: unit.domain.as_str().to_string(),
             content: unit.content.clone(),
+            keywords: unit.keywords.join(" "),
             transaction_time_micros: unit.transaction_time.timestamp_micros(),
             valid_time_micros: unit.valid_time.map(|t| t.timestamp_micros()),
             inserted_at: Instant::now(),
@@ -737,6 +833,34 @@ impl TextIndex {
         Ok(())
     }
 
+    /// Whether a document for `id` is indexed, counting uncommitted overlay documents.
+    pub fn contains_unit(&self, id: &str) -> Result<bool> {
+        {
+            let overlay = self.overlay.lock().unwrap_or_else(|e| {
+                tracing::warn!("TextIndex overlay mutex was poisoned; recovering");
+                e.into_inner()
+            });
+            if overlay
+                .users
+                .values()
+                .any(|buffer| buffer.docs.iter().any(|doc| doc.id == id))
+            {
+                return Ok(true);
+            }
+        }
+
+        let id_field = self.index.schema().get_field("id").unwrap();
+        let query = tantivy::query::TermQuery::new(
+            tantivy::Term::from_field_text(id_field, id),
+            tantivy::schema::IndexRecordOption::Basic,
+        );
+        let hits = self
+            .reader
+            .searcher()
+            .search(&query, &tantivy::collector::Count)?;
+        Ok(hits > 0)
+    }
+
     pub fn commit(&self) -> Result<()> {
         let had_dirty = {
             let state = self.commit_state.lock().unwrap_or_else(|e| {
@@ -764,11 +888,7 @@ impl TextIndex {
                     tracing::warn!("TextIndex commit state mutex was poisoned; recovering");
                     e.into_inner()
                 });
-                state.dirty_docs = 0;
-                state.dirty_bytes = 0;
-                state.last_commit_at = Instant::now();
-                state.current_commit_seq += 1;
-                state.current_commit_seq
+                state.mark_committed()
             };
 
             let mut overlay = self.overlay.lock().unwrap_or_else(|e| {
@@ -791,6 +911,74 @@ impl TextIndex {
         Ok(())
     }
 
+    /// Make every document indexed before this call searchable. Commits forced this way
+    /// are at least `min_interval` apart; callers inside the interval wait for its end,
+    /// and return early if another commit covered their documents meanwhile. Returns
+    /// whether this call committed.
+    pub fn sync_pending(&self, min_interval: Duration) -> Result<bool> {
+        let target_seq = {
+            let state = self.commit_state.lock().unwrap_or_else(|e| {
+                tracing::warn!("TextIndex commit state mutex was poisoned; recovering");
+                e.into_inner()
+            });
+            if state.dirty_docs == 0 {
+                return Ok(false);
+            }
+            state.current_commit_seq + 1
+        };
+
+        let _sync = self.sync_lock.lock().unwrap_or_else(|e| {
+            tracing::warn!("TextIndex sync mutex was poisoned; recovering");
+            e.into_inner()
+        });
+        let since_last_commit = {
+            let state = self.commit_state.lock().unwrap_or_else(|e| {
+                tracing::warn!("TextIndex commit state mutex was poisoned; recovering");
+                e.into_inner()
+            });
+            if state.current_commit_seq >= target_seq {
+                return Ok(false);
+            }
+            state.last_commit_at.elapsed()
+        };
+        if since_last_commit < min_interval {
+            std::thread::sleep(min_interval - since_last_commit);
+            let state = self.commit_state.lock().unwrap_or_else(|e| {
+                tracing::warn!("TextIndex commit state mutex was poisoned; recovering");
+                e.into_inner()
+            });
+            if state.current_commit_seq >= target_seq {
+                return Ok(false);
+            }
+        }
+        self.commit()?;
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// Number of segments searchers currently see.
+    pub fn segment_count(&self) -> usize {
+        self.reader.searcher().segment_readers().len()
+    }
+
+    /// Merge every committed segment into one and wait for the merge to land.
+    pub fn merge_segments(&self) -> Result<()> {
+        let segment_ids = self.index.searchable_segment_ids()?;
+        if segment_ids.len() < 2 {
+            return Ok(());
+        }
+        let merge = {
+            let mut writer = self.writer.lock().unwrap_or_else(|e| {
+                tracing::warn!("TextIndex writer mutex was poisoned; recovering");
+                e.into_inner()
+            });
+            writer.merge(&segment_ids)
+        };
+        merge.wait()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
     pub fn search(
         &self,
         query_str: &str,
@@ -815,6 +1003,35 @@ impl TextIndex {
         agent_id: Option<&str>,
         domain: Option<&str>,
     ) -> Result<Vec<String>> {
+        Ok(self
+            .search_bitemporal_scored(
+                query_str,
+                limit,
+                valid_time,
+                transaction_time,
+                org_id,
+                user_id,
+                agent_id,
+                domain,
+            )?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    /// Same as `search_bitemporal`, keeping each hit's BM25 score. Hits served from the
+    /// recent-write overlay have not reached Tantivy yet and carry no score.
+    pub fn search_bitemporal_scored(
+        &self,
+        query_str: &str,
+        limit: usize,
+        valid_time: Option<TimeRange>,
+        transaction_time: Option<TimeRange>,
+        org_id: Option<&str>,
+        user_id: Option<&str>,
+        agent_id: Option<&str>,
+        domain: Option<&str>,
+    ) -> Result<Vec<(String, Option<f32>)>> {
         if limit == 0 {
             return Ok(Vec::new());
         }
@@ -822,9 +1039,13 @@ impl TextIndex {
         let searcher = self.reader.searcher();
         let schema = self.index.schema();
         let content_field = schema.get_field("content").unwrap();
+        let keywords_field = schema.get_field("keywords").unwrap();
         let id_field = schema.get_field("id").unwrap();
 
-        let query_parser = tantivy::query::QueryParser::for_index(&self.index, vec![content_field]);
+        let query_parser = tantivy::query::QueryParser::for_index(
+            &self.index,
+            vec![content_field, keywords_field],
+        );
         let base_query = match query_parser.parse_query(query_str) {
             Ok(q) => q,
             Err(_) => {
@@ -893,13 +1114,13 @@ impl TextIndex {
 
         let mut tantivy_results = Vec::new();
         let mut seen = HashSet::new();
-        for (_score, doc_address) in top_docs {
+        for (score, doc_address) in top_docs {
             let retrieved_doc: tantivy::TantivyDocument = searcher.doc(doc_address)?;
             if let Some(val) = retrieved_doc.get_first(id_field) {
                 if let Some(s) = val.as_str() {
                     let id = s.to_string();
                     if seen.insert(id.clone()) {
-                        tantivy_results.push(id);
+                        tantivy_results.push((id, score));
                     }
                 }
             }
@@ -948,16 +1169,16 @@ impl TextIndex {
                 self.metrics
                     .overlay_merge_total
                     .fetch_add(1, Ordering::Relaxed);
-                results.push(id);
+                results.push((id, None));
                 if results.len() >= limit {
                     return Ok(results);
                 }
             }
         }
 
-        for id in tantivy_results {
+        for (id, score) in tantivy_results {
             if seen.insert(id.clone()) {
-                results.push(id);
+                results.push((id, Some(score)));
                 if results.len() >= limit {
                     break;
                 }
@@ -1011,6 +1232,7 @@ impl TextIndex {
 
 fn estimate_doc_bytes(unit: &MemoryUnit) -> usize {
     unit.content.len()
+        + unit.keywords.iter().map(String::len).sum::<usize>()
         + unit.user_id.len()
         + unit.org_id.as_deref().map_or(0, str::len)
         + unit.agent_id.as_deref().map_or(0, str::len)
@@ -1055,13 +1277,17 @@ fn i64_range_query(field: Field, range: &TimeRange) -> tantivy::query::RangeQuer
     tantivy::query::RangeQuery::new(lower, upper)
 }
 
-fn overlay_match_score(content: &str, terms: &[String]) -> usize {
+fn overlay_match_score(content: &str, keywords: &str, terms: &[String]) -> usize {
     if terms.is_empty() {
         return 1;
     }
 
     let lowered = content.to_lowercase();
-    terms.iter().filter(|term| lowered.contains(*term)).count()
+    let lowered_keywords = keywords.to_lowercase();
+    terms
+        .iter()
+        .filter(|term| lowered.contains(*term) || lowered_keywords.contains(*term))
+        .count()
 }
 
 fn matches_filters(
@@ -1169,6 +1395,36 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_text_index_matches_keywords_in_overlay_and_committed_docs() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let index = TextIndex::new(temp_dir.path(), 60_000)?;
+
+            let mut unit = MemoryUnit::new(
+                None,
+                "u1".into(),
+                None,
+                Uuid::new_v4(),
+                memorose_common::MemoryType::Factual,
+                "Spent the weekend hiking near the lake".to_string(),
+                None,
+            );
+            unit.keywords = vec!["outdoors".into(), "mountain trail".into()];
+
+            index.index_unit(&unit)?;
+            let overlay_results = index.search("outdoors", 10, None, None, Some("u1"))?;
+            assert_eq!(overlay_results, vec![unit.id.to_string()]);
+
+            index.commit()?;
+            index.reload()?;
+            let committed_results = index.search("trail", 10, None, None, Some("u1"))?;
+            assert_eq!(committed_results, vec![unit.id.to_string()]);
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_text_index_recent_overlay_is_merged_even_when_tantivy_limit_is_full() -> Result<()> {
         let rt = tokio::runtime::Runtime::new()?;
@@ -1303,6 +1559,144 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_text_index_contains_unit_sees_overlay_committed_and_deleted_docs() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let index = TextIndex::new(temp_dir.path(), 60_000)?;
+            let unit = MemoryUnit::new(
+                None,
+                "u1".into(),
+                None,
+                Uuid::new_v4(),
+                memorose_common::MemoryType::Factual,
+                "integrity probe".to_string(),
+                None,
+            );
+            let id = unit.id.to_string();
+
+            assert!(!index.contains_unit(&id)?);
+            index.index_unit(&unit)?;
+            assert!(index.contains_unit(&id)?);
+
+            index.commit()?;
+            index.reload()?;
+            assert!(index.contains_unit(&id)?);
+
+            index.delete_unit(&id)?;
+            index.commit()?;
+            index.reload()?;
+            assert!(!index.contains_unit(&id)?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_text_index_app_commit_interval_commits_early() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let mut config = TextIndexConfig::legacy(60_000);
+            config
+                .app_commit_interval_ms
+                .insert("fast_app".to_string(), 50);
+            let index = TextIndex::with_config(temp_dir.path(), config)?;
+
+            let unit = |app_id: &str| {
+                MemoryUnit::new(
+                    None,
+                    "commit_user".into(),
+                    Some(app_id.into()),
+                    Uuid::new_v4(),
+                    memorose_common::MemoryType::Factual,
+                    "committed on the app interval".into(),
+                    None,
+                )
+            };
+            index.index_unit(&unit("slow_app"))?;
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            assert_eq!(index.metrics_snapshot().commit_total, 0);
+
+            let fast = unit("fast_app");
+            index.index_unit(&fast)?;
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let metrics = index.metrics_snapshot();
+            assert_eq!(metrics.commit_total, 1);
+            assert_eq!(metrics.dirty_docs, 0);
+            assert!(index.contains_unit(&fast.id.to_string())?);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_text_index_sync_pending_commits_at_most_once_per_interval() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let index = TextIndex::with_config(temp_dir.path(), TextIndexConfig::legacy(60_000))?;
+            let unit = || {
+                MemoryUnit::new(
+                    None,
+                    "sync_user".into(),
+                    None,
+                    Uuid::new_v4(),
+                    memorose_common::MemoryType::Factual,
+                    "visible to strong reads".into(),
+                    None,
+                )
+            };
+
+            assert!(!index.sync_pending(Duration::ZERO)?);
+
+            let first = unit();
+            index.index_unit(&first)?;
+            assert!(index.sync_pending(Duration::ZERO)?);
+            assert!(index.contains_unit(&first.id.to_string())?);
+            assert!(!index.sync_pending(Duration::ZERO)?);
+
+            let second = unit();
+            index.index_unit(&second)?;
+            let started_at = Instant::now();
+            assert!(index.sync_pending(Duration::from_millis(200))?);
+            assert!(started_at.elapsed() >= Duration::from_millis(150));
+            assert_eq!(index.metrics_snapshot().commit_total, 2);
+            assert_eq!(index.search("strong reads", 10, None, None, None)?.len(), 2);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_text_index_merge_segments_leaves_one_segment() -> Result<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async {
+            let temp_dir = tempdir()?;
+            let mut config = TextIndexConfig::legacy(60_000);
+            config.merge_policy.enabled = false;
+            let index = TextIndex::with_config(temp_dir.path(), config)?;
+
+            for content in ["first segment", "second segment", "third segment"] {
+                index.index_unit(&MemoryUnit::new(
+                    None,
+                    "merge_user".into(),
+                    None,
+                    Uuid::new_v4(),
+                    memorose_common::MemoryType::Factual,
+                    content.into(),
+                    None,
+                ))?;
+                index.commit()?;
+            }
+            index.reload()?;
+            assert_eq!(index.segment_count(), 3);
+
+            index.merge_segments()?;
+            assert_eq!(index.segment_count(), 1);
+            assert_eq!(index.search("segment", 10, None, None, None)?.len(), 3);
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_text_index_overlay_returns_uncommitted_documents() -> Result<()> {
         let rt = tokio::runtime::Runtime::new()?;
@@ -1313,6 +1707,8 @@ mod tests {
                 commit_max_interval_ms: 5_000,
                 commit_docs_threshold: 10_000,
                 commit_bytes_threshold: 1_000_000_000,
+                app_commit_interval_ms: BTreeMap::new(),
+                merge_policy: TextIndexMergePolicy::default(),
                 recent_overlay_enabled: true,
                 recent_overlay_ttl_secs: 120,
                 recent_overlay_per_user_max_docs: 1000,
@@ -1360,6 +1756,8 @@ mod tests {
                 commit_max_interval_ms: 5_000,
                 commit_docs_threshold: 10_000,
                 commit_bytes_threshold: 1_000_000_000,
+                app_commit_interval_ms: BTreeMap::new(),
+                merge_policy: TextIndexMergePolicy::default(),
                 recent_overlay_enabled: true,
                 recent_overlay_ttl_secs: 120,
                 recent_overlay_per_user_max_docs: 1000,
@@ -1,33 +1,177 @@
 use anyhow::Result;
+use memorose_common::config::{StorageBackend, StorageConfig};
 use rocksdb::{Options, DB};
 use std::path::Path;
 use std::sync::Arc;
 
+/// Ordered byte-key store holding events, units, markers and the raft log.
+///
+/// Writes are grouped in a `rocksdb::WriteBatch` whatever the backend; other
+/// backends replay the batch through `WriteBatch::iterate`.
+pub trait KvBackend: Send + Sync {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>>;
+    fn delete(&self, key: &[u8]) -> Result<()>;
+    /// Apply every put and delete of `batch` atomically.
+    fn write_batch(&self, batch: rocksdb::WriteBatch) -> Result<()>;
+    fn flush(&self) -> Result<()>;
+    /// Write a consistent RocksDB copy of the whole store to `path`.
+    fn checkpoint(&self, path: &Path) -> Result<()>;
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    fn scan_limited(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    fn scan_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    fn scan_keys_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>>;
+    fn count_prefix(&self, prefix: &[u8]) -> Result<usize>;
+    fn scan_range(
+        &self,
+        start_key: &[u8],
+        end_key_exclusive: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+}
+
 #[derive(Clone)]
 pub struct KvStore {
-    db: Arc<DB>,
+    backend: Arc<dyn KvBackend>,
 }
 
 impl KvStore {
+    /// Open a RocksDB store at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::from_backend(RocksKvStore::open(path)?))
+    }
+
+    /// Open the backend selected by `storage.backend`. `path` is the local RocksDB
+    /// directory; the Postgres backend uses it as its namespace and imports any
+    /// RocksDB data found there (e.g. from an installed raft snapshot).
+    pub fn open_with_config<P: AsRef<Path>>(path: P, config: &StorageConfig) -> Result<Self> {
+        match config.backend {
+            StorageBackend::Rocksdb => Self::open(path),
+            StorageBackend::Postgres => Ok(Self::from_backend(
+                super::postgres_kv::PostgresKvStore::open(path.as_ref(), config)?,
+            )),
+        }
+    }
+
+    pub fn from_backend(backend: impl KvBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.backend.put(key, value)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.backend.get(key)
+    }
+
+    pub fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        self.backend.multi_get(keys)
+    }
+
+    pub fn delete(&self, key: &[u8]) -> Result<()> {
+        self.backend.delete(key)
+    }
+
+    pub fn write_batch(&self, batch: rocksdb::WriteBatch) -> Result<()> {
+        self.backend.write_batch(batch)
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.backend.flush()
+    }
+
+    pub fn checkpoint(&self, path: &std::path::Path) -> Result<()> {
+        self.backend.checkpoint(path)
+    }
+
+    pub fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.backend.scan(prefix)
+    }
+
+    /// Scan keys with the given prefix, returning at most `limit` key-value pairs.
+    /// More efficient than `scan()` when only a subset is needed, as it stops
+    /// iterating once the limit is reached.
+    pub fn scan_limited(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.backend.scan_limited(prefix, limit)
+    }
+
+    /// Scan a bounded page of keys with the given prefix after an exclusive key.
+    /// This keeps repair/rebuild jobs from materializing an entire prefix at once.
+    pub fn scan_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.backend.scan_prefix_after(prefix, after, limit)
+    }
+
+    /// Scan a bounded page of keys with the given prefix after an exclusive key,
+    /// without reading values.
+    pub fn scan_keys_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        self.backend.scan_keys_prefix_after(prefix, after, limit)
+    }
+
+    /// Count the number of keys with the given prefix without loading values.
+    /// Avoids the deserialization cost of `scan` when only the count is needed.
+    pub fn count_prefix(&self, prefix: &[u8]) -> Result<usize> {
+        self.backend.count_prefix(prefix)
+    }
+
+    /// Scan keys in the range [start_key, end_key_exclusive).
+    pub fn scan_range(
+        &self,
+        start_key: &[u8],
+        end_key_exclusive: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.backend.scan_range(start_key, end_key_exclusive)
+    }
+}
+
+pub struct RocksKvStore {
+    db: DB,
+}
+
+impl RocksKvStore {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         // Optimize for L0/WAL behavior if needed, but defaults are fine for now
         let db = DB::open(&opts, path)?;
-        Ok(Self { db: Arc::new(db) })
+        Ok(Self { db })
     }
+}
 
-    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+impl KvBackend for RocksKvStore {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
         self.db.put(key, value)?;
         Ok(())
     }
 
-    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         let val = self.db.get(key)?;
         Ok(val)
     }
 
-    pub fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+    fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
         let results = self.db.multi_get(keys);
         let mut final_res = Vec::new();
         for res in results {
@@ -36,28 +180,28 @@ impl KvStore {
         Ok(final_res)
     }
 
-    pub fn delete(&self, key: &[u8]) -> Result<()> {
+    fn delete(&self, key: &[u8]) -> Result<()> {
         self.db.delete(key)?;
         Ok(())
     }
 
-    pub fn write_batch(&self, batch: rocksdb::WriteBatch) -> Result<()> {
+    fn write_batch(&self, batch: rocksdb::WriteBatch) -> Result<()> {
         self.db.write(batch)?;
         Ok(())
     }
 
-    pub fn flush(&self) -> Result<()> {
+    fn flush(&self) -> Result<()> {
         self.db.flush()?;
         Ok(())
     }
 
-    pub fn checkpoint(&self, path: &std::path::Path) -> Result<()> {
+    fn checkpoint(&self, path: &std::path::Path) -> Result<()> {
         let checkpoint = rocksdb::checkpoint::Checkpoint::new(&self.db)?;
         checkpoint.create_checkpoint(path)?;
         Ok(())
     }
 
-    pub fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         // Use an explicit seek iterator instead of prefix_iterator: prefix_iterator
         // requires a configured SliceTransform prefix extractor; without one its
         // behaviour is undefined and bloom filters are bypassed.
@@ -79,7 +223,7 @@ impl KvStore {
     /// Scan keys with the given prefix, returning at most `limit` key-value pairs.
     /// More efficient than `scan()` when only a subset is needed, as it stops
     /// iterating once the limit is reached.
-    pub fn scan_limited(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    fn scan_limited(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         use rocksdb::{Direction, IteratorMode};
         let iter = self
             .db
@@ -100,7 +244,7 @@ impl KvStore {
 
     /// Scan a bounded page of keys with the given prefix after an exclusive key.
     /// This keeps repair/rebuild jobs from materializing an entire prefix at once.
-    pub fn scan_prefix_after(
+    fn scan_prefix_after(
         &self,
         prefix: &[u8],
         after: Option<&[u8]>,
@@ -136,7 +280,7 @@ impl KvStore {
 
     /// Scan a bounded page of keys with the given prefix after an exclusive key.
     /// Uses a raw iterator so values are not copied into memory.
-    pub fn scan_keys_prefix_after(
+    fn scan_keys_prefix_after(
         &self,
         prefix: &[u8],
         after: Option<&[u8]>,
@@ -176,7 +320,7 @@ impl KvStore {
 
     /// Count the number of keys with the given prefix without loading values.
     /// Avoids the deserialization cost of `scan` when only the count is needed.
-    pub fn count_prefix(&self, prefix: &[u8]) -> Result<usize> {
+    fn count_prefix(&self, prefix: &[u8]) -> Result<usize> {
         use rocksdb::{Direction, IteratorMode};
         let iter = self
             .db
@@ -194,7 +338,7 @@ impl KvStore {
 
     /// Scan keys in the range [start_key, end_key_exclusive) using a RocksDB seek.
     /// This is O(result_size) instead of O(total_keys_with_prefix).
-    pub fn scan_range(
+    fn scan_range(
         &self,
         start_key: &[u8],
         end_key_exclusive: &[u8],
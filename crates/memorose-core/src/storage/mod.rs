@@ -1,7 +1,10 @@
 pub mod blob;
+pub mod bucketed_index;
 pub mod graph;
 pub mod index;
 pub mod kv;
+pub mod pgvector;
+pub mod postgres_kv;
 pub mod repair;
 pub mod system_kv;
 pub mod vector;
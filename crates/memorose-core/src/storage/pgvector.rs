@@ -0,0 +1,434 @@
+use super::vector::{VectorBackend, VectorOptimizeReport, VectorStore, VECTOR_SCHEMA_VERSION};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use memorose_common::config::VectorConfig;
+use memorose_common::MemoryUnit;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{Client, NoTls};
+
+/// Vector index kept in Postgres with the pgvector extension, one table per vector
+/// table name. Rows carry a `namespace` (the local LanceDB path) so every shard and
+/// node sharing the database keeps its own rows.
+///
+/// Columns mirror the LanceDB schema, with times stored as microseconds, so the
+/// engine's filters run unchanged as `WHERE` clauses.
+pub struct PgVectorStore {
+    url: String,
+    client: RwLock<Arc<Client>>,
+    table_prefix: String,
+    namespace: String,
+    dim: i32,
+}
+
+impl PgVectorStore {
+    pub async fn connect(config: &VectorConfig, namespace: &str, dim: i32) -> Result<Self> {
+        let url = config
+            .pgvector_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| anyhow!("vector.backend = \"pgvector\" requires vector.pgvector_url"))?;
+        let table_prefix = config.pgvector_table_prefix.clone();
+        if !table_prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(anyhow!(
+                "invalid vector.pgvector_table_prefix: {:?}",
+                table_prefix
+            ));
+        }
+        let client = connect(&url).await?;
+        client
+            .batch_execute("CREATE EXTENSION IF NOT EXISTS vector")
+            .await?;
+        Ok(Self {
+            url,
+            client: RwLock::new(Arc::new(client)),
+            table_prefix,
+            namespace: namespace.to_string(),
+            dim,
+        })
+    }
+
+    async fn client(&self) -> Result<Arc<Client>> {
+        let current = self.client.read().await.clone();
+        if !current.is_closed() {
+            return Ok(current);
+        }
+        let mut client = self.client.write().await;
+        if client.is_closed() {
+            *client = Arc::new(connect(&self.url).await?);
+        }
+        Ok(client.clone())
+    }
+
+    fn table(&self, table_name: &str) -> Result<String> {
+        if !table_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(anyhow!("invalid vector table name: {:?}", table_name));
+        }
+        Ok(format!("{}{}", self.table_prefix, table_name))
+    }
+
+    fn where_clause(filter: Option<String>) -> String {
+        match filter {
+            Some(filter) => format!("namespace = $1 AND ({})", filter),
+            None => "namespace = $1".to_string(),
+        }
+    }
+
+    /// pgvector's text form, padded or truncated to the table dimension.
+    fn vector_literal(&self, vector: &[f32]) -> String {
+        let mut vector = vector.to_vec();
+        vector.resize(self.dim as usize, 0.0);
+        let values: Vec<String> = vector.iter().map(f32::to_string).collect();
+        format!("[{}]", values.join(","))
+    }
+}
+
+#[async_trait]
+impl VectorBackend for PgVectorStore {
+    async fn table_schema_status(
+        &self,
+        table_name: &str,
+    ) -> Result<(Vec<String>, Option<u32>, String)> {
+        let table = self.table(table_name)?;
+        let rows = self
+            .client()
+            .await?
+            .query(
+                "SELECT column_name::TEXT FROM information_schema.columns
+                 WHERE table_schema = current_schema() AND table_name = $1
+                 ORDER BY ordinal_position",
+                &[&table],
+            )
+            .await?;
+        if rows.is_empty() {
+            return Err(anyhow!("vector table {} not found", table));
+        }
+        let actual_columns: Vec<String> = rows
+            .into_iter()
+            .map(|row| row.get::<_, String>(0))
+            .filter(|column| column != "namespace")
+            .collect();
+        let expected_columns: Vec<String> = VectorStore::expected_columns()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+        let (version, status) = if actual_columns == expected_columns {
+            (Some(VECTOR_SCHEMA_VERSION), "current".to_string())
+        } else {
+            (None, "mismatch".to_string())
+        };
+        Ok((actual_columns, version, status))
+    }
+
+    async fn ensure_table(&self, table_name: &str) -> Result<()> {
+        let table = self.table(table_name)?;
+        self.client()
+            .await?
+            .batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    namespace TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    user_id TEXT NOT NULL,
+                    org_id TEXT,
+                    agent_id TEXT,
+                    domain TEXT NOT NULL,
+                    namespace_key TEXT NOT NULL,
+                    level SMALLINT NOT NULL,
+                    transaction_time BIGINT NOT NULL,
+                    valid_time BIGINT,
+                    \"vector\" vector({dim}) NOT NULL,
+                    lat DOUBLE PRECISION,
+                    lon DOUBLE PRECISION
+                );
+                ALTER TABLE {table} ADD COLUMN IF NOT EXISTS lat DOUBLE PRECISION;
+                ALTER TABLE {table} ADD COLUMN IF NOT EXISTS lon DOUBLE PRECISION;
+                CREATE INDEX IF NOT EXISTS {table}_id_idx ON {table} (namespace, id);
+                CREATE INDEX IF NOT EXISTS {table}_vector_idx
+                    ON {table} USING hnsw (\"vector\" vector_l2_ops);",
+                dim = self.dim
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn add_vectors(&self, table_name: &str, rows: Vec<(MemoryUnit, Vec<f32>)>) -> Result<()> {
+        let table = self.table(table_name)?;
+        let mut ids = Vec::new();
+        let mut user_ids = Vec::new();
+        let mut org_ids: Vec<Option<String>> = Vec::new();
+        let mut agent_ids: Vec<Option<String>> = Vec::new();
+        let mut domains = Vec::new();
+        let mut namespace_keys = Vec::new();
+        let mut levels: Vec<i16> = Vec::new();
+        let mut transaction_times = Vec::new();
+        let mut valid_times: Vec<Option<i64>> = Vec::new();
+        let mut vectors = Vec::new();
+        let mut lats: Vec<Option<f64>> = Vec::new();
+        let mut lons: Vec<Option<f64>> = Vec::new();
+
+        for (unit, vector) in &rows {
+            ids.push(unit.id.to_string());
+            user_ids.push(unit.user_id.clone());
+            org_ids.push(unit.org_id.clone());
+            agent_ids.push(unit.agent_id.clone());
+            domains.push(unit.domain.as_str().to_string());
+            namespace_keys.push(unit.namespace_key.clone());
+            levels.push(unit.level as i16);
+            transaction_times.push(unit.transaction_time.timestamp_micros());
+            valid_times.push(unit.valid_time.map(|t| t.timestamp_micros()));
+            vectors.push(self.vector_literal(vector));
+            lats.push(unit.location.map(|location| location.lat));
+            lons.push(unit.location.map(|location| location.lon));
+        }
+
+        // One statement, so a batch is written entirely or not at all.
+        self.client()
+            .await?
+            .execute(
+                &format!(
+                    "INSERT INTO {table} (namespace, id, user_id, org_id, agent_id, domain,
+                        namespace_key, level, transaction_time, valid_time, \"vector\", lat, lon)
+                     SELECT $1, id, user_id, org_id, agent_id, domain, namespace_key, level,
+                        transaction_time, valid_time, v::vector, lat, lon
+                     FROM UNNEST($2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[],
+                        $7::TEXT[], $8::SMALLINT[], $9::BIGINT[], $10::BIGINT[], $11::TEXT[],
+                        $12::FLOAT8[], $13::FLOAT8[])
+                     AS t(id, user_id, org_id, agent_id, domain, namespace_key, level,
+                        transaction_time, valid_time, v, lat, lon)"
+                ),
+                &[
+                    &self.namespace,
+                    &ids,
+                    &user_ids,
+                    &org_ids,
+                    &agent_ids,
+                    &domains,
+                    &namespace_keys,
+                    &levels,
+                    &transaction_times,
+                    &valid_times,
+                    &vectors,
+                    &lats,
+                    &lons,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn optimize_table(&self, table_name: &str) -> Result<VectorOptimizeReport> {
+        // Postgres compacts on its own through autovacuum; refresh planner stats only.
+        let table = self.table(table_name)?;
+        match self
+            .client()
+            .await?
+            .batch_execute(&format!("ANALYZE {table}"))
+            .await
+        {
+            Ok(()) => {}
+            Err(error) if is_undefined_table(&error) => {}
+            Err(error) => return Err(error.into()),
+        }
+        Ok(VectorOptimizeReport {
+            compaction_ran: false,
+            prune_ran: false,
+        })
+    }
+
+    async fn delete_by_id(&self, table_name: &str, id: &str) -> Result<()> {
+        let table = self.table(table_name)?;
+        match self
+            .client()
+            .await?
+            .execute(
+                &format!("DELETE FROM {table} WHERE namespace = $1 AND id = $2"),
+                &[&self.namespace, &id],
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(error) if is_undefined_table(&error) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Only this store's rows are removed; the table is shared with other namespaces.
+    async fn delete_table(&self, table_name: &str) -> Result<()> {
+        let table = self.table(table_name)?;
+        match self
+            .client()
+            .await?
+            .execute(
+                &format!("DELETE FROM {table} WHERE namespace = $1"),
+                &[&self.namespace],
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(error) if is_undefined_table(&error) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn scan_ids(&self, table_name: &str, filter: Option<String>) -> Result<Vec<String>> {
+        let table = self.table(table_name)?;
+        let rows = match self
+            .client()
+            .await?
+            .query(
+                &format!(
+                    "SELECT id FROM {table} WHERE {}",
+                    Self::where_clause(filter)
+                ),
+                &[&self.namespace],
+            )
+            .await
+        {
+            Ok(rows) => rows,
+            Err(error) if is_undefined_table(&error) => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn count_rows(&self, table_name: &str) -> Result<usize> {
+        let table = self.table(table_name)?;
+        let row = self
+            .client()
+            .await?
+            .query_one(
+                &format!("SELECT COUNT(*) FROM {table} WHERE namespace = $1"),
+                &[&self.namespace],
+            )
+            .await?;
+        Ok(row.get::<_, i64>(0) as usize)
+    }
+
+    async fn search(
+        &self,
+        table_name: &str,
+        query_vector: &[f32],
+        limit: usize,
+        filter: Option<String>,
+    ) -> Result<Vec<(String, f32)>> {
+        let table = self.table(table_name)?;
+        let query = self.vector_literal(query_vector);
+        let limit = limit as i64;
+        let rows = self
+            .client()
+            .await?
+            .query(
+                &format!(
+                    "SELECT id, (\"vector\" <-> $2::TEXT::vector)::REAL AS distance
+                     FROM {table} WHERE {}
+                     ORDER BY \"vector\" <-> $2::TEXT::vector
+                     LIMIT $3",
+                    Self::where_clause(filter)
+                ),
+                &[&self.namespace, &query, &limit],
+            )
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let distance: f32 = row.get(1);
+                // `<->` is the plain L2 distance; LanceDB scores on the squared one.
+                (row.get(0), 1.0 / (1.0 + distance * distance))
+            })
+            .collect())
+    }
+}
+
+async fn connect(url: &str) -> Result<Client> {
+    let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            tracing::warn!("pgvector connection closed: {}", error);
+        }
+    });
+    Ok(client)
+}
+
+fn is_undefined_table(error: &tokio_postgres::Error) -> bool {
+    error.code() == Some(&SqlState::UNDEFINED_TABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::config::VectorBackendKind;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_pgvector_store_round_trip() -> Result<()> {
+        let Ok(url) = std::env::var("MEMOROSE_TEST_POSTGRES_URL") else {
+            return Ok(());
+        };
+        let config = VectorConfig {
+            backend: VectorBackendKind::Pgvector,
+            pgvector_url: Some(url),
+            ..Default::default()
+        };
+        let namespace = format!("test-{}", Uuid::new_v4());
+        let store =
+            VectorStore::from_backend(PgVectorStore::connect(&config, &namespace, 4).await?);
+        store.ensure_table("memories").await?;
+
+        let unit = |content: &str, user_id: &str| {
+            MemoryUnit::new(
+                None,
+                user_id.into(),
+                None,
+                Uuid::new_v4(),
+                memorose_common::MemoryType::Factual,
+                content.into(),
+                None,
+            )
+        };
+        let near = unit("near", "u1");
+        let far = unit("far", "u1");
+        let other_user = unit("other", "u2");
+        store
+            .add_vectors(
+                "memories",
+                vec![
+                    (near.clone(), vec![1.0, 0.0, 0.0, 0.0]),
+                    (far.clone(), vec![0.0, 1.0, 0.0, 0.0]),
+                    (other_user.clone(), vec![1.0, 0.0, 0.0, 0.0]),
+                ],
+            )
+            .await?;
+        assert_eq!(store.count_rows("memories").await?, 3);
+
+        let results = store
+            .search(
+                "memories",
+                &[1.0, 0.0, 0.0, 0.0],
+                5,
+                Some("user_id = 'u1'".into()),
+            )
+            .await?;
+        let ids: Vec<String> = results.iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ids, vec![near.id.to_string(), far.id.to_string()]);
+        assert!(results[0].1 > 0.99);
+        assert!((results[1].1 - 1.0 / 3.0).abs() < 1e-4);
+
+        store.delete_by_id("memories", &near.id.to_string()).await?;
+        assert_eq!(store.scan_ids("memories", None).await?.len(), 2);
+
+        let (columns, version, _) = store.table_schema_status("memories").await?;
+        assert_eq!(columns, VectorStore::expected_columns());
+        assert_eq!(version, Some(VECTOR_SCHEMA_VERSION));
+
+        store.delete_table("memories").await?;
+        assert_eq!(store.count_rows("memories").await?, 0);
+        Ok(())
+    }
+}
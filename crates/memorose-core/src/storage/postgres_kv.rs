@@ -0,0 +1,538 @@
+use super::kv::{KvBackend, RocksKvStore};
+use anyhow::{anyhow, Context, Result};
+use memorose_common::config::StorageConfig;
+use postgres::config::SslMode;
+use postgres::{Client, Config, IsolationLevel, NoTls};
+use postgres_native_tls::MakeTlsConnector;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+const IMPORT_BATCH_SIZE: usize = 1_000;
+
+type PgJob = Box<dyn FnOnce(&mut Client) + Send>;
+
+/// Key-value store kept in one Postgres table, `(namespace, key, value)`, where the
+/// namespace is `storage.postgres_namespace` plus the store's shard and directory name,
+/// so every shard and node gets its own rows.
+///
+/// The synchronous `postgres` client cannot run on a Tokio worker, so queries are
+/// handed to a small pool of dedicated threads that each own a connection.
+pub struct PostgresKvStore {
+    jobs: Sender<PgJob>,
+    table: Arc<str>,
+    namespace: Arc<str>,
+}
+
+impl PostgresKvStore {
+    pub fn open(path: &Path, config: &StorageConfig) -> Result<Self> {
+        let url = config
+            .postgres_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow!("storage.backend = \"postgres\" requires storage.postgres_url")
+            })?;
+        let namespace = config
+            .postgres_namespace
+            .clone()
+            .filter(|namespace| !namespace.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow!("storage.backend = \"postgres\" requires storage.postgres_namespace")
+            })?;
+        let table = config.postgres_table.clone();
+        if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow!("invalid storage.postgres_table: {:?}", table));
+        }
+
+        let connector = Connector::new(&url)?;
+        let (jobs, receiver) = mpsc::channel::<PgJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for worker in 0..config.postgres_pool_size.max(1) {
+            let client = connector
+                .connect()
+                .with_context(|| format!("failed to connect to Postgres ({})", worker))?;
+            let receiver = receiver.clone();
+            let connector = connector.clone();
+            std::thread::Builder::new()
+                .name(format!("memorose-pg-{}", worker))
+                .spawn(move || run_worker(client, &connector, receiver))?;
+        }
+
+        let store = Self {
+            jobs,
+            table: table.into(),
+            namespace: namespace_for(&namespace, path).into(),
+        };
+        store.ensure_table()?;
+        store.import_local_rocksdb(path)?;
+        Ok(store)
+    }
+
+    fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Client, &str, &str) -> Result<T> + Send + 'static,
+    {
+        let (reply, result) = mpsc::sync_channel(1);
+        let table = self.table.clone();
+        let namespace = self.namespace.clone();
+        self.jobs
+            .send(Box::new(move |client| {
+                let _ = reply.send(job(client, &table, &namespace));
+            }))
+            .map_err(|_| anyhow!("Postgres workers have stopped"))?;
+        result
+            .recv()
+            .map_err(|_| anyhow!("Postgres worker dropped the request"))?
+    }
+
+    fn ensure_table(&self) -> Result<()> {
+        self.run(|client, table, _| {
+            client.batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    namespace TEXT NOT NULL,
+                    key BYTEA NOT NULL,
+                    value BYTEA NOT NULL,
+                    PRIMARY KEY (namespace, key)
+                )"
+            ))?;
+            Ok(())
+        })
+    }
+
+    /// Replace this namespace with the contents of a RocksDB directory left at
+    /// `path`, then move the directory aside so it is only imported once.
+    fn import_local_rocksdb(&self, path: &Path) -> Result<()> {
+        if !path.join("CURRENT").exists() {
+            return Ok(());
+        }
+        let local = RocksKvStore::open(path)?;
+        self.run(|client, table, namespace| {
+            client.execute(
+                &format!("DELETE FROM {table} WHERE namespace = $1"),
+                &[&namespace],
+            )?;
+            Ok(())
+        })?;
+        let mut imported = 0usize;
+        let mut after: Option<Vec<u8>> = None;
+        loop {
+            let page = local.scan_prefix_after(b"", after.as_deref(), IMPORT_BATCH_SIZE)?;
+            let Some((last_key, _)) = page.last() else {
+                break;
+            };
+            after = Some(last_key.clone());
+            imported += page.len();
+            self.run(move |client, table, namespace| {
+                let mut tx = client.transaction()?;
+                let statement = tx.prepare(&upsert_sql(table))?;
+                for (key, value) in &page {
+                    tx.execute(&statement, &[&namespace, key, value])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })?;
+        }
+        drop(local);
+
+        let imported_path = PathBuf::from(format!("{}.imported", path.display()));
+        if imported_path.exists() {
+            std::fs::remove_dir_all(&imported_path)?;
+        }
+        std::fs::rename(path, &imported_path)?;
+        tracing::info!(
+            "Imported {} keys from {:?} into Postgres; the RocksDB copy was moved to {:?}",
+            imported,
+            path,
+            imported_path
+        );
+        Ok(())
+    }
+
+    /// Rows with keys in `[start, end)` (no upper bound when `end` is None) and
+    /// strictly after `after`, in key order.
+    fn select_range(
+        &self,
+        start: Vec<u8>,
+        end: Option<Vec<u8>>,
+        after: Option<Vec<u8>>,
+        limit: Option<usize>,
+        with_values: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.run(move |client, table, namespace| {
+            let columns = if with_values { "key, value" } else { "key" };
+            let limit = limit.map(|limit| limit as i64);
+            let rows = client.query(
+                &format!(
+                    "SELECT {columns} FROM {table}
+                     WHERE namespace = $1 AND key >= $2
+                       AND ($3::BYTEA IS NULL OR key < $3)
+                       AND ($4::BYTEA IS NULL OR key > $4)
+                     ORDER BY key
+                     LIMIT $5"
+                ),
+                &[&namespace, &start, &end, &after, &limit],
+            )?;
+            Ok(rows
+                .into_iter()
+                .map(|row| {
+                    let value = if with_values { row.get(1) } else { Vec::new() };
+                    (row.get(0), value)
+                })
+                .collect())
+        })
+    }
+}
+
+impl KvBackend for PostgresKvStore {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let (key, value) = (key.to_vec(), value.to_vec());
+        self.run(move |client, table, namespace| {
+            client.execute(&upsert_sql(table), &[&namespace, &key, &value])?;
+            Ok(())
+        })
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let key = key.to_vec();
+        self.run(move |client, table, namespace| {
+            let row = client.query_opt(
+                &format!("SELECT value FROM {table} WHERE namespace = $1 AND key = $2"),
+                &[&namespace, &key],
+            )?;
+            Ok(row.map(|row| row.get(0)))
+        })
+    }
+
+    fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        let keys: Vec<Vec<u8>> = keys.iter().map(|key| key.to_vec()).collect();
+        self.run(move |client, table, namespace| {
+            let rows = client.query(
+                &format!("SELECT key, value FROM {table} WHERE namespace = $1 AND key = ANY($2)"),
+                &[&namespace, &keys],
+            )?;
+            let mut found: std::collections::HashMap<Vec<u8>, Vec<u8>> = rows
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect();
+            Ok(keys.iter().map(|key| found.remove(key)).collect())
+        })
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        let key = key.to_vec();
+        self.run(move |client, table, namespace| {
+            client.execute(
+                &format!("DELETE FROM {table} WHERE namespace = $1 AND key = $2"),
+                &[&namespace, &key],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn write_batch(&self, batch: rocksdb::WriteBatch) -> Result<()> {
+        let mut ops = BatchOps::default();
+        batch.iterate(&mut ops);
+        let ops = ops.0;
+        self.run(move |client, table, namespace| {
+            let mut tx = client.transaction()?;
+            let upsert = tx.prepare(&upsert_sql(table))?;
+            let delete = tx.prepare(&format!(
+                "DELETE FROM {table} WHERE namespace = $1 AND key = $2"
+            ))?;
+            for (key, value) in &ops {
+                match value {
+                    Some(value) => tx.execute(&upsert, &[&namespace, key, value])?,
+                    None => tx.execute(&delete, &[&namespace, key])?,
+                };
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        // Every statement is committed before it returns.
+        Ok(())
+    }
+
+    /// Pages are read in one read-only REPEATABLE READ transaction, so the copy is a
+    /// single snapshot of the namespace even while writes continue.
+    fn checkpoint(&self, path: &Path) -> Result<()> {
+        let target = RocksKvStore::open(path)?;
+        self.run(move |client, table, namespace| {
+            let mut tx = client
+                .build_transaction()
+                .isolation_level(IsolationLevel::RepeatableRead)
+                .read_only(true)
+                .start()?;
+            let select_page = tx.prepare(&format!(
+                "SELECT key, value FROM {table}
+                 WHERE namespace = $1 AND ($2::BYTEA IS NULL OR key > $2)
+                 ORDER BY key
+                 LIMIT $3"
+            ))?;
+            let limit = IMPORT_BATCH_SIZE as i64;
+            let mut after: Option<Vec<u8>> = None;
+            loop {
+                let rows = tx.query(&select_page, &[&namespace, &after, &limit])?;
+                let Some(last) = rows.last() else {
+                    break;
+                };
+                after = Some(last.get(0));
+                let mut batch = rocksdb::WriteBatch::default();
+                for row in &rows {
+                    batch.put(row.get::<_, Vec<u8>>(0), row.get::<_, Vec<u8>>(1));
+                }
+                target.write_batch(batch)?;
+            }
+            tx.commit()?;
+            target.flush()
+        })
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.select_range(prefix.to_vec(), prefix_end(prefix), None, None, true)
+    }
+
+    fn scan_limited(&self, prefix: &[u8], limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.select_range(prefix.to_vec(), prefix_end(prefix), None, Some(limit), true)
+    }
+
+    fn scan_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        self.select_range(
+            prefix.to_vec(),
+            prefix_end(prefix),
+            after.map(<[u8]>::to_vec),
+            Some(limit),
+            true,
+        )
+    }
+
+    fn scan_keys_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .select_range(
+                prefix.to_vec(),
+                prefix_end(prefix),
+                after.map(<[u8]>::to_vec),
+                Some(limit),
+                false,
+            )?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    fn count_prefix(&self, prefix: &[u8]) -> Result<usize> {
+        let (start, end) = (prefix.to_vec(), prefix_end(prefix));
+        self.run(move |client, table, namespace| {
+            let row = client.query_one(
+                &format!(
+                    "SELECT COUNT(*) FROM {table}
+                     WHERE namespace = $1 AND key >= $2 AND ($3::BYTEA IS NULL OR key < $3)"
+                ),
+                &[&namespace, &start, &end],
+            )?;
+            Ok(row.get::<_, i64>(0) as usize)
+        })
+    }
+
+    fn scan_range(
+        &self,
+        start_key: &[u8],
+        end_key_exclusive: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.select_range(
+            start_key.to_vec(),
+            Some(end_key_exclusive.to_vec()),
+            None,
+            None,
+            true,
+        )
+    }
+}
+
+/// Opens connections with the settings of `storage.postgres_url`. Its `sslmode` decides
+/// TLS: `disable` connects in plain text, `prefer` (the default) and `require` use TLS
+/// verified against the system roots, `prefer` falling back to plain text only when the
+/// server does not offer TLS.
+#[derive(Clone)]
+struct Connector {
+    config: Config,
+    tls: Option<MakeTlsConnector>,
+}
+
+impl Connector {
+    fn new(url: &str) -> Result<Self> {
+        let config: Config = url.parse().context("invalid storage.postgres_url")?;
+        let tls = match config.get_ssl_mode() {
+            SslMode::Disable => None,
+            _ => Some(MakeTlsConnector::new(
+                native_tls::TlsConnector::new().context("failed to set up TLS for Postgres")?,
+            )),
+        };
+        Ok(Self { config, tls })
+    }
+
+    fn connect(&self) -> std::result::Result<Client, postgres::Error> {
+        match &self.tls {
+            Some(tls) => self.config.connect(tls.clone()),
+            None => self.config.connect(NoTls),
+        }
+    }
+}
+
+fn run_worker(mut client: Client, connector: &Connector, jobs: Arc<Mutex<Receiver<PgJob>>>) {
+    loop {
+        let job = {
+            let jobs = jobs.lock().unwrap_or_else(|e| e.into_inner());
+            match jobs.recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            }
+        };
+        if client.is_closed() {
+            match connector.connect() {
+                Ok(reconnected) => client = reconnected,
+                Err(error) => tracing::error!("Postgres reconnect failed: {}", error),
+            }
+        }
+        job(&mut client);
+    }
+}
+
+fn upsert_sql(table: &str) -> String {
+    format!(
+        "INSERT INTO {table} (namespace, key, value) VALUES ($1, $2, $3)
+         ON CONFLICT (namespace, key) DO UPDATE SET value = EXCLUDED.value"
+    )
+}
+
+/// `prefix` plus the last two components of the store's directory, e.g.
+/// `node-1:shard_0/rocksdb`, so the rows stay put when the data directory moves.
+fn namespace_for(prefix: &str, path: &Path) -> String {
+    let mut names = path
+        .iter()
+        .rev()
+        .take(2)
+        .map(|name| name.to_string_lossy())
+        .collect::<Vec<_>>();
+    names.reverse();
+    format!("{}:{}", prefix, names.join("/"))
+}
+
+/// Smallest key greater than every key starting with `prefix`, or None when no
+/// such key exists (empty prefix or all `0xFF`). BYTEA compares bytewise, like RocksDB.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+#[derive(Default)]
+struct BatchOps(Vec<(Vec<u8>, Option<Vec<u8>>)>);
+
+impl rocksdb::WriteBatchIterator for BatchOps {
+    fn put(&mut self, key: &[u8], value: &[u8]) {
+        self.0.push((key.to_vec(), Some(value.to_vec())));
+    }
+
+    fn delete(&mut self, key: &[u8]) {
+        self.0.push((key.to_vec(), None));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::kv::KvStore;
+    use memorose_common::config::StorageBackend;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_prefix_end_increments_last_non_max_byte() {
+        assert_eq!(prefix_end(b"u:"), Some(b"u;".to_vec()));
+        assert_eq!(prefix_end(&[0x01, 0xFF]), Some(vec![0x02]));
+        assert_eq!(prefix_end(&[0xFF, 0xFF]), None);
+        assert_eq!(prefix_end(b""), None);
+    }
+
+    #[test]
+    fn test_namespace_for_uses_prefix_shard_and_store_name() {
+        assert_eq!(
+            namespace_for("node-1", Path::new("/var/lib/memorose/shard_0/rocksdb")),
+            "node-1:shard_0/rocksdb"
+        );
+        assert_eq!(
+            namespace_for("node-1", Path::new("rocksdb")),
+            "node-1:rocksdb"
+        );
+    }
+
+    #[test]
+    fn test_postgres_backend_matches_rocksdb_semantics() -> Result<()> {
+        let Ok(url) = std::env::var("MEMOROSE_TEST_POSTGRES_URL") else {
+            return Ok(());
+        };
+        let temp_dir = tempdir()?;
+        let kv_path = temp_dir.path().join("rocksdb");
+        let seeded = KvStore::open(&kv_path)?;
+        seeded.put(b"seed:1", b"from-rocksdb")?;
+        drop(seeded);
+
+        let config = StorageConfig {
+            backend: StorageBackend::Postgres,
+            postgres_url: Some(url),
+            postgres_namespace: Some(format!("test-{}", uuid::Uuid::new_v4())),
+            ..Default::default()
+        };
+        let kv = KvStore::open_with_config(&kv_path, &config)?;
+        assert_eq!(kv.get(b"seed:1")?, Some(b"from-rocksdb".to_vec()));
+        assert!(!kv_path.exists());
+
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put(b"ns:1", b"one");
+        batch.put(b"ns:2", b"two");
+        batch.put(b"ns:3", b"three");
+        batch.delete(b"seed:1");
+        kv.write_batch(batch)?;
+
+        assert_eq!(kv.get(b"seed:1")?, None);
+        assert_eq!(kv.count_prefix(b"ns:")?, 3);
+        assert_eq!(
+            kv.multi_get(&[b"ns:1", b"missing", b"ns:3"])?,
+            vec![Some(b"one".to_vec()), None, Some(b"three".to_vec())]
+        );
+        let page = kv.scan_prefix_after(b"ns:", Some(b"ns:1"), 1)?;
+        assert_eq!(page, vec![(b"ns:2".to_vec(), b"two".to_vec())]);
+        assert_eq!(kv.scan_range(b"ns:1", b"ns:3")?.len(), 2);
+
+        let checkpoint_dir = temp_dir.path().join("checkpoint");
+        kv.checkpoint(&checkpoint_dir)?;
+        let restored = KvStore::open(&checkpoint_dir)?;
+        assert_eq!(restored.scan(b"ns:")?.len(), 3);
+        Ok(())
+    }
+}
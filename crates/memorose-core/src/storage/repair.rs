@@ -1,7 +1,7 @@
 use crate::storage::kv::KvStore;
 use crate::storage::vector::{VectorStore, VECTOR_SCHEMA_VERSION};
 use anyhow::{anyhow, Context, Result};
-use memorose_common::MemoryUnit;
+use memorose_common::MEMORY_UNIT_SCHEMA_VERSION;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 
@@ -200,7 +200,7 @@ pub async fn rebuild_vector_index(options: VectorRebuildOptions) -> Result<Vecto
                 continue;
             }
             report.scanned_units += 1;
-            match serde_json::from_slice::<MemoryUnit>(value) {
+            match crate::migration::decode_sealed_memory_unit(value) {
                 Ok(unit) if unit.embedding.is_some() => {
                     vector_batch.push(unit);
                     if vector_batch.len() >= batch_size {
@@ -249,6 +249,76 @@ pub async fn rebuild_vector_index(options: VectorRebuildOptions) -> Result<Vecto
     Ok(report)
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaMigrationReport {
+    pub data_dir: PathBuf,
+    pub target_schema_version: u32,
+    pub dry_run: bool,
+    pub scanned_units: usize,
+    pub migrated_units: usize,
+    pub decode_errors: usize,
+}
+
+/// Rewrite every stored memory unit older than the current schema version. With
+/// `dry_run` the units are only counted. Must run while the server is stopped.
+pub fn migrate_memory_units(
+    data_dir: impl AsRef<Path>,
+    dry_run: bool,
+) -> Result<SchemaMigrationReport> {
+    let data_dir = data_dir.as_ref().to_path_buf();
+    let rocksdb_path = data_dir.join("rocksdb");
+    if !rocksdb_path.exists() {
+        return Err(anyhow!(
+            "RocksDB directory does not exist: {}",
+            rocksdb_path.display()
+        ));
+    }
+    let kv = KvStore::open(&rocksdb_path)?;
+    let mut report = SchemaMigrationReport {
+        data_dir,
+        target_schema_version: MEMORY_UNIT_SCHEMA_VERSION,
+        dry_run,
+        scanned_units: 0,
+        migrated_units: 0,
+        decode_errors: 0,
+    };
+
+    let mut after: Option<Vec<u8>> = None;
+    loop {
+        let page =
+            kv.scan_prefix_after(MEMORY_SCAN_PREFIX, after.as_deref(), REPAIR_SCAN_BATCH_SIZE)?;
+        if page.is_empty() {
+            break;
+        }
+        let mut batch = rocksdb::WriteBatch::default();
+        for (key, value) in &page {
+            if !is_memory_unit_key(key) {
+                continue;
+            }
+            report.scanned_units += 1;
+            let upgraded = serde_json::from_slice(value)
+                .map_err(anyhow::Error::from)
+                .and_then(crate::migration::upgrade_memory_unit);
+            match upgraded {
+                Ok((unit, true)) => {
+                    report.migrated_units += 1;
+                    batch.put(key, serde_json::to_vec(&unit)?);
+                }
+                Ok((_, false)) => {}
+                Err(_) => report.decode_errors += 1,
+            }
+        }
+        if !dry_run && !batch.is_empty() {
+            kv.write_batch(batch)?;
+        }
+        after = page.last().map(|(key, _)| key.clone());
+    }
+    if !dry_run {
+        kv.flush()?;
+    }
+    Ok(report)
+}
+
 #[derive(Default)]
 struct MemoryScanCounts {
     memory_units_total: usize,
@@ -270,7 +340,7 @@ fn scan_memory_counts(kv: &KvStore) -> Result<MemoryScanCounts> {
                 continue;
             }
             counts.memory_units_total += 1;
-            match serde_json::from_slice::<MemoryUnit>(value) {
+            match crate::migration::decode_sealed_memory_unit(value) {
                 Ok(unit) if unit.embedding.is_some() => counts.memory_units_with_embeddings += 1,
                 Ok(_) => {}
                 Err(_) => counts.decode_errors += 1,
@@ -288,7 +358,7 @@ fn is_memory_unit_key(key: &[u8]) -> bool {
             .unwrap_or(false)
 }
 
-fn dir_size_bytes(path: &Path) -> Result<u64> {
+pub(crate) fn dir_size_bytes(path: &Path) -> Result<u64> {
     if !path.exists() {
         return Ok(0);
     }
@@ -393,6 +463,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_migrate_memory_units_upgrades_legacy_records_once() -> anyhow::Result<()> {
+        let temp = tempdir()?;
+        let data_dir = temp.path();
+        let kv = KvStore::open(data_dir.join("rocksdb"))?;
+        let current = test_unit("u1", "already current", None);
+        put_unit(&kv, &current)?;
+
+        let mut legacy = serde_json::to_value(test_unit("u1", "legacy", None))?;
+        let record = legacy
+            .as_object_mut()
+            .expect("unit serializes to an object");
+        for field in ["schema_version", "memory_type", "domain", "namespace_key"] {
+            record.remove(field);
+        }
+        let legacy_id = record["id"].as_str().expect("id").to_string();
+        let legacy_key = format!("u:u1:unit:{}", legacy_id);
+        kv.put(legacy_key.as_bytes(), &serde_json::to_vec(&legacy)?)?;
+        drop(kv);
+
+        let dry_run = migrate_memory_units(data_dir, true)?;
+        assert_eq!(dry_run.scanned_units, 2);
+        assert_eq!(dry_run.migrated_units, 1);
+
+        let report = migrate_memory_units(data_dir, false)?;
+        assert_eq!(report.migrated_units, 1);
+        assert_eq!(report.decode_errors, 0);
+
+        let kv = KvStore::open(data_dir.join("rocksdb"))?;
+        let stored: serde_json::Value =
+            serde_json::from_slice(&kv.get(legacy_key.as_bytes())?.expect("legacy unit"))?;
+        assert_eq!(stored["schema_version"], MEMORY_UNIT_SCHEMA_VERSION);
+        assert_eq!(stored["domain"], "user");
+        assert_eq!(stored["namespace_key"], "user:_global:u1");
+        drop(kv);
+
+        assert_eq!(migrate_memory_units(data_dir, false)?.migrated_units, 0);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_vector_status_recommends_rebuild_when_size_exceeds_limit() -> anyhow::Result<()> {
         let temp = tempdir()?;
@@ -452,7 +562,7 @@ mod tests {
 
         let status = vector_status(data_dir, true).await?;
         assert_eq!(status.vector_rows, Some(2));
-        assert_eq!(status.vector_schema_version, Some(2));
+        assert_eq!(status.vector_schema_version, Some(VECTOR_SCHEMA_VERSION));
         assert_eq!(status.vector_schema_status.as_deref(), Some("current"));
         Ok(())
     }
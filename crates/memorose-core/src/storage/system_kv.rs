@@ -1,6 +1,9 @@
 use super::kv::KvStore;
 use anyhow::Result;
 
+/// Keys read per page by [`SystemKvStore::trim_prefix`].
+const TRIM_PAGE: usize = 1000;
+
 #[derive(Clone)]
 pub struct SystemKvStore {
     inner: KvStore,
@@ -34,6 +37,17 @@ impl SystemKvStore {
         self.inner.scan_limited(prefix, limit)
     }
 
+    /// Scan a bounded page of keys with the given prefix after an exclusive key,
+    /// without reading values.
+    pub fn scan_keys_prefix_after(
+        &self,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        self.inner.scan_keys_prefix_after(prefix, after, limit)
+    }
+
     pub fn scan_range(
         &self,
         start_key: &[u8],
@@ -46,6 +60,30 @@ impl SystemKvStore {
         self.inner.count_prefix(prefix)
     }
 
+    /// Delete every key under `prefix` after the first `keep` in key order, walking keys
+    /// only so large values are never loaded.
+    pub fn trim_prefix(&self, prefix: &[u8], keep: usize) -> Result<()> {
+        if self.count_prefix(prefix)? <= keep {
+            return Ok(());
+        }
+        let mut seen = 0;
+        let mut after: Option<Vec<u8>> = None;
+        loop {
+            let keys = self.scan_keys_prefix_after(prefix, after.as_deref(), TRIM_PAGE)?;
+            let Some(last) = keys.last().cloned() else {
+                break;
+            };
+            for key in keys {
+                seen += 1;
+                if seen > keep {
+                    self.delete(&key)?;
+                }
+            }
+            after = Some(last);
+        }
+        Ok(())
+    }
+
     pub fn multi_get(&self, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
         self.inner.multi_get(keys)
     }
@@ -89,6 +127,13 @@ mod tests {
         store.delete(b"sys:b")?;
         assert_eq!(store.get(b"sys:b")?, None);
 
+        for key in [b"log:3", b"log:1", b"log:2"] {
+            store.put(key, b"x")?;
+        }
+        store.trim_prefix(b"log:", 2)?;
+        assert_eq!(store.get(b"log:3")?, None);
+        assert_eq!(store.count_prefix(b"log:")?, 2);
+
         store.checkpoint(&checkpoint_dir)?;
         let restored = KvStore::open(&checkpoint_dir)?;
         assert_eq!(restored.get(b"sys:a")?, Some(b"1".to_vec()));
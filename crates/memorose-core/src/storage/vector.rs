@@ -1,8 +1,10 @@
 use anyhow::Result;
 use arrow_array::{
-    Array, FixedSizeListArray, Float32Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+    Array, FixedSizeListArray, Float32Array, Float64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
 };
 use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use async_trait::async_trait;
 use futures::StreamExt;
 use lancedb::query::{ExecutableQuery, QueryBase};
 use lancedb::table::OptimizeAction;
@@ -10,12 +12,43 @@ use lancedb::{connect, Connection};
 use memorose_common::MemoryUnit;
 use std::sync::Arc;
 
-pub const VECTOR_SCHEMA_VERSION: u32 = 2;
+pub const VECTOR_SCHEMA_VERSION: u32 = 3;
+
+/// Table of joint text+image embeddings, one row per embedded asset. Rows carry the id
+/// of the memory unit that owns the asset, so a unit may have several vectors.
+pub const ASSET_VECTOR_TABLE: &str = "memory_assets";
+
+/// Nearest-neighbour index over memory and asset embeddings.
+///
+/// Filters are the SQL predicates the engine builds over the columns listed in
+/// `VectorStore::expected_columns`, with times as microseconds since the epoch.
+#[async_trait]
+pub trait VectorBackend: Send + Sync {
+    /// Returns `(columns, schema version, status)` for `table_name`.
+    async fn table_schema_status(
+        &self,
+        table_name: &str,
+    ) -> Result<(Vec<String>, Option<u32>, String)>;
+    async fn ensure_table(&self, table_name: &str) -> Result<()>;
+    async fn add_vectors(&self, table_name: &str, rows: Vec<(MemoryUnit, Vec<f32>)>) -> Result<()>;
+    async fn optimize_table(&self, table_name: &str) -> Result<VectorOptimizeReport>;
+    async fn delete_by_id(&self, table_name: &str, id: &str) -> Result<()>;
+    async fn delete_table(&self, table_name: &str) -> Result<()>;
+    async fn scan_ids(&self, table_name: &str, filter: Option<String>) -> Result<Vec<String>>;
+    async fn count_rows(&self, table_name: &str) -> Result<usize>;
+    /// Ids with a `1 / (1 + squared L2 distance)` score, best first.
+    async fn search(
+        &self,
+        table_name: &str,
+        query_vector: &[f32],
+        limit: usize,
+        filter: Option<String>,
+    ) -> Result<Vec<(String, f32)>>;
+}
 
 #[derive(Clone)]
 pub struct VectorStore {
-    conn: Connection,
-    dim: i32,
+    backend: Arc<dyn VectorBackend>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,6 +58,17 @@ pub struct VectorOptimizeReport {
 }
 
 impl VectorStore {
+    /// Open a LanceDB store at `path`.
+    pub async fn new(path: &str, dim: i32) -> Result<Self> {
+        Ok(Self::from_backend(LanceVectorStore::new(path, dim).await?))
+    }
+
+    pub fn from_backend(backend: impl VectorBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
     pub fn expected_columns() -> Vec<&'static str> {
         vec![
             "id",
@@ -37,15 +81,126 @@ impl VectorStore {
             "transaction_time",
             "valid_time",
             "vector",
+            "lat",
+            "lon",
         ]
     }
 
+    pub async fn table_schema_status(
+        &self,
+        table_name: &str,
+    ) -> Result<(Vec<String>, Option<u32>, String)> {
+        self.backend.table_schema_status(table_name).await
+    }
+
+    pub async fn ensure_table(&self, table_name: &str) -> Result<()> {
+        self.backend.ensure_table(table_name).await
+    }
+
+    pub async fn add(&self, table_name: &str, units: Vec<MemoryUnit>) -> Result<()> {
+        let rows = units
+            .into_iter()
+            .map(|unit| {
+                let vector = unit.embedding.clone().unwrap_or_default();
+                (unit, vector)
+            })
+            .collect();
+        self.add_vectors(table_name, rows).await
+    }
+
+    /// Add rows with explicit vectors rather than each unit's own embedding. The same
+    /// unit may appear several times to index more than one vector for it.
+    pub async fn add_vectors(
+        &self,
+        table_name: &str,
+        rows: Vec<(MemoryUnit, Vec<f32>)>,
+    ) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.backend.add_vectors(table_name, rows).await
+    }
+
+    pub async fn optimize_table(&self, table_name: &str) -> Result<VectorOptimizeReport> {
+        self.backend.optimize_table(table_name).await
+    }
+
+    pub async fn compact_files(&self, table_name: &str) -> Result<()> {
+        let stats = self.optimize_table(table_name).await?;
+        tracing::info!(
+            compaction_ran = stats.compaction_ran,
+            prune_ran = stats.prune_ran,
+            "Vector table optimized"
+        );
+        Ok(())
+    }
+
+    /// Delete a single memory unit from the vector table by its ID.
+    pub async fn delete_by_id(&self, table_name: &str, id: &str) -> Result<()> {
+        self.backend.delete_by_id(table_name, id).await
+    }
+
+    pub async fn delete_table(&self, table_name: &str) -> Result<()> {
+        self.backend.delete_table(table_name).await
+    }
+
+    /// Ids of the rows in `table_name` matching `filter` (all rows when `None`), reading
+    /// only the id column. A missing table has no ids.
+    pub async fn scan_ids(&self, table_name: &str, filter: Option<String>) -> Result<Vec<String>> {
+        self.backend.scan_ids(table_name, filter).await
+    }
+
+    pub async fn count_rows(&self, table_name: &str) -> Result<usize> {
+        self.backend.count_rows(table_name).await
+    }
+
+    pub async fn search(
+        &self,
+        table_name: &str,
+        query_vector: &[f32],
+        limit: usize,
+        filter: Option<String>,
+    ) -> Result<Vec<(String, f32)>> {
+        self.backend
+            .search(table_name, query_vector, limit, filter)
+            .await
+    }
+
+    /// Search a table that may hold several vectors per id, keeping each id's best hit.
+    pub async fn search_multi_vector(
+        &self,
+        table_name: &str,
+        query_vector: &[f32],
+        limit: usize,
+        filter: Option<String>,
+    ) -> Result<Vec<(String, f32)>> {
+        let hits = self
+            .search(table_name, query_vector, limit.saturating_mul(4), filter)
+            .await?;
+        let mut seen = std::collections::HashSet::new();
+        Ok(hits
+            .into_iter()
+            .filter(|(id, _)| seen.insert(id.clone()))
+            .take(limit)
+            .collect())
+    }
+}
+
+pub struct LanceVectorStore {
+    conn: Connection,
+    dim: i32,
+}
+
+impl LanceVectorStore {
     pub async fn new(path: &str, dim: i32) -> Result<Self> {
         let conn = connect(path).execute().await?;
         Ok(Self { conn, dim })
     }
+}
 
-    pub async fn table_schema_status(
+#[async_trait]
+impl VectorBackend for LanceVectorStore {
+    async fn table_schema_status(
         &self,
         table_name: &str,
     ) -> Result<(Vec<String>, Option<u32>, String)> {
@@ -56,7 +211,7 @@ impl VectorStore {
             .iter()
             .map(|field| field.name().to_string())
             .collect();
-        let expected_columns: Vec<String> = Self::expected_columns()
+        let expected_columns: Vec<String> = VectorStore::expected_columns()
             .into_iter()
             .map(|name| name.to_string())
             .collect();
@@ -76,7 +231,7 @@ impl VectorStore {
         Ok((actual_columns, version, status))
     }
 
-    pub async fn ensure_table(&self, table_name: &str) -> Result<()> {
+    async fn ensure_table(&self, table_name: &str) -> Result<()> {
         let tables = self.conn.table_names().execute().await?;
         if tables.contains(&table_name.to_string()) {
             let table = self.conn.open_table(table_name).execute().await?;
@@ -86,7 +241,7 @@ impl VectorStore {
                 .iter()
                 .map(|field| field.name().to_string())
                 .collect();
-            let expected_columns: Vec<String> = Self::expected_columns()
+            let expected_columns: Vec<String> = VectorStore::expected_columns()
                 .into_iter()
                 .map(|name| name.to_string())
                 .collect();
@@ -129,6 +284,8 @@ impl VectorStore {
                 ),
                 false,
             ),
+            Field::new("lat", DataType::Float64, true),
+            Field::new("lon", DataType::Float64, true),
         ]));
 
         self.conn
@@ -138,11 +295,7 @@ impl VectorStore {
         Ok(())
     }
 
-    pub async fn add(&self, table_name: &str, units: Vec<MemoryUnit>) -> Result<()> {
-        if units.is_empty() {
-            return Ok(());
-        }
-
+    async fn add_vectors(&self, table_name: &str, rows: Vec<(MemoryUnit, Vec<f32>)>) -> Result<()> {
         let table = self.conn.open_table(table_name).execute().await?;
 
         let mut ids = Vec::new();
@@ -155,8 +308,10 @@ impl VectorStore {
         let mut transaction_times = Vec::new();
         let mut valid_ats = Vec::new();
         let mut vectors_flat = Vec::new();
+        let mut lats = Vec::new();
+        let mut lons = Vec::new();
 
-        for unit in &units {
+        for (unit, vector) in &rows {
             ids.push(unit.id.to_string());
             user_ids.push(unit.user_id.clone());
             org_ids.push(unit.org_id.clone());
@@ -167,17 +322,15 @@ impl VectorStore {
             levels.push(unit.level);
             transaction_times.push(unit.transaction_time.timestamp_micros());
             valid_ats.push(unit.valid_time.map(|t| t.timestamp_micros()));
+            lats.push(unit.location.map(|location| location.lat));
+            lons.push(unit.location.map(|location| location.lon));
 
-            if let Some(emb) = &unit.embedding {
-                if emb.len() != self.dim as usize {
-                    let mut e = emb.clone();
-                    e.resize(self.dim as usize, 0.0);
-                    vectors_flat.extend(e);
-                } else {
-                    vectors_flat.extend(emb);
-                }
+            if vector.len() != self.dim as usize {
+                let mut e = vector.clone();
+                e.resize(self.dim as usize, 0.0);
+                vectors_flat.extend(e);
             } else {
-                vectors_flat.extend(vec![0.0; self.dim as usize]);
+                vectors_flat.extend(vector);
             }
         }
 
@@ -196,6 +349,8 @@ impl VectorStore {
         let field = Arc::new(Field::new("item", DataType::Float32, true));
         let values = Arc::new(Float32Array::from(vectors_flat));
         let vector_array = Arc::new(FixedSizeListArray::new(field, self.dim, values, None));
+        let lat_array = Arc::new(Float64Array::from(lats));
+        let lon_array = Arc::new(Float64Array::from(lons));
 
         let schema = table.schema().await?;
         let batch = RecordBatch::try_new(
@@ -211,6 +366,8 @@ impl VectorStore {
                 transaction_time_array as Arc<dyn Array>,
                 valid_time_array as Arc<dyn Array>,
                 vector_array as Arc<dyn Array>,
+                lat_array as Arc<dyn Array>,
+                lon_array as Arc<dyn Array>,
             ],
         )?;
 
@@ -219,7 +376,7 @@ impl VectorStore {
         Ok(())
     }
 
-    pub async fn optimize_table(&self, table_name: &str) -> Result<VectorOptimizeReport> {
+    async fn optimize_table(&self, table_name: &str) -> Result<VectorOptimizeReport> {
         let table = match self.conn.open_table(table_name).execute().await {
             Ok(table) => table,
             Err(error) if error.to_string().to_lowercase().contains("not found") => {
@@ -237,18 +394,7 @@ impl VectorStore {
         })
     }
 
-    pub async fn compact_files(&self, table_name: &str) -> Result<()> {
-        let stats = self.optimize_table(table_name).await?;
-        tracing::info!(
-            compaction_ran = stats.compaction_ran,
-            prune_ran = stats.prune_ran,
-            "LanceDB table optimized"
-        );
-        Ok(())
-    }
-
-    /// Delete a single memory unit from the vector table by its ID.
-    pub async fn delete_by_id(&self, table_name: &str, id: &str) -> Result<()> {
+    async fn delete_by_id(&self, table_name: &str, id: &str) -> Result<()> {
         let table = match self.conn.open_table(table_name).execute().await {
             Ok(t) => t,
             Err(e) if e.to_string().to_lowercase().contains("not found") => return Ok(()),
@@ -259,7 +405,7 @@ impl VectorStore {
         Ok(())
     }
 
-    pub async fn delete_table(&self, table_name: &str) -> Result<()> {
+    async fn delete_table(&self, table_name: &str) -> Result<()> {
         match self.conn.drop_table(table_name, &[]).await {
             Ok(_) => Ok(()),
             Err(e) => {
@@ -273,12 +419,40 @@ impl VectorStore {
         }
     }
 
-    pub async fn count_rows(&self, table_name: &str) -> Result<usize> {
+    async fn scan_ids(&self, table_name: &str, filter: Option<String>) -> Result<Vec<String>> {
+        let table = match self.conn.open_table(table_name).execute().await {
+            Ok(t) => t,
+            Err(e) if e.to_string().to_lowercase().contains("not found") => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut query = table
+            .query()
+            .select(lancedb::query::Select::columns(&["id"]));
+        if let Some(f) = filter {
+            query = query.only_if(f);
+        }
+
+        let mut stream = query.execute().await?;
+        let mut ids = Vec::new();
+        while let Some(batch_res) = stream.next().await {
+            let batch: RecordBatch = batch_res?;
+            let id_col = batch
+                .column_by_name("id")
+                .ok_or_else(|| anyhow::anyhow!("id column not found"))?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow::anyhow!("failed to downcast id column"))?;
+            ids.extend((0..id_col.len()).map(|i| id_col.value(i).to_string()));
+        }
+        Ok(ids)
+    }
+
+    async fn count_rows(&self, table_name: &str) -> Result<usize> {
         let table = self.conn.open_table(table_name).execute().await?;
         Ok(table.count_rows(None).await?)
     }
 
-    pub async fn search(
+    async fn search(
         &self,
         table_name: &str,
         query_vector: &[f32],
@@ -369,6 +543,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_multi_vector_search_keeps_best_hit_per_unit() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let store = VectorStore::new(temp_dir.path().to_str().unwrap(), 4).await?;
+        store.ensure_table(ASSET_VECTOR_TABLE).await?;
+
+        let unit = |content: &str| {
+            MemoryUnit::new(
+                None,
+                "u1".into(),
+                None,
+                Uuid::new_v4(),
+                memorose_common::MemoryType::Factual,
+                content.into(),
+                None,
+            )
+        };
+        let album = unit("two photos");
+        let other = unit("one photo");
+        store
+            .add_vectors(
+                ASSET_VECTOR_TABLE,
+                vec![
+                    (album.clone(), vec![1.0, 0.0, 0.0, 0.0]),
+                    (album.clone(), vec![0.9, 0.1, 0.0, 0.0]),
+                    (other.clone(), vec![0.0, 1.0, 0.0, 0.0]),
+                ],
+            )
+            .await?;
+        assert_eq!(store.count_rows(ASSET_VECTOR_TABLE).await?, 3);
+
+        let results = store
+            .search_multi_vector(ASSET_VECTOR_TABLE, &[1.0, 0.0, 0.0, 0.0], 5, None)
+            .await?;
+        let ids: Vec<String> = results.iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ids, vec![album.id.to_string(), other.id.to_string()]);
+        assert!(results[0].1 > 0.99);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_vector_store_uses_slim_schema() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -377,13 +592,8 @@ mod tests {
         let store = VectorStore::new(db_path, 384).await?;
         store.ensure_table("memories").await?;
 
-        let table = store.conn.open_table("memories").execute().await?;
-        let schema = table.schema().await?;
-        let columns: Vec<&str> = schema
-            .fields()
-            .iter()
-            .map(|field| field.name().as_str())
-            .collect();
+        let (columns, _, _) = store.table_schema_status("memories").await?;
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
 
         assert_eq!(
             columns,
@@ -398,6 +608,8 @@ mod tests {
                 "transaction_time",
                 "valid_time",
                 "vector",
+                "lat",
+                "lon",
             ]
         );
         assert!(!columns.contains(&"content"));
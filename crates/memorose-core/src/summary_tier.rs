@@ -0,0 +1,346 @@
+//! Two-tier consolidation for cost-sensitive deployments. Routine batches are summarized
+//! locally, by a cheap model when one is configured and extractively otherwise, while long,
+//! dense or multimodal batches, and batches the local tier fails on, go to the main LLM.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::llm::{CompressionContext, CompressionOutput, LLMClient};
+
+/// Sentences an extractive summary keeps.
+const EXTRACTIVE_MAX_SENTENCES: usize = 5;
+/// Keywords that decide which sentences an extractive summary keeps.
+const EXTRACTIVE_KEYWORDS: usize = 12;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TierOptions {
+    /// Batches longer than this many characters are escalated
+    pub max_chars: usize,
+    /// Batches whose character entropy in bits exceeds this are escalated
+    pub max_entropy: f32,
+}
+
+/// Why a batch went to the main LLM instead of the local tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escalation {
+    Length,
+    Entropy,
+    Multimodal,
+    LocalFailed,
+}
+
+/// One event's text in a batch, with the participant it came from when that matters.
+#[derive(Debug, Clone)]
+pub(crate) struct Passage {
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct LocalSummarizer {
+    pub client: Option<Arc<dyn LLMClient>>,
+    pub options: TierOptions,
+}
+
+impl LocalSummarizer {
+    /// Summarize a batch on the local tier, or say why it belongs to the main LLM.
+    /// `combined_text` is what the main LLM would be sent.
+    pub(crate) async fn summarize(
+        &self,
+        combined_text: &str,
+        passages: &[Passage],
+        multimodal: bool,
+        context: &CompressionContext,
+    ) -> Result<CompressionOutput, Escalation> {
+        let result = match escalation(combined_text, multimodal, self.options) {
+            Some(reason) => Err(reason),
+            None => match &self.client {
+                Some(client) => match client.compress(combined_text, context).await {
+                    Ok(out) if !out.data.content.trim().is_empty() => Ok(out.data),
+                    Ok(_) => {
+                        tracing::warn!("Local summarizer returned an empty summary; escalating");
+                        Err(Escalation::LocalFailed)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Local summarizer failed ({:?}); escalating", e);
+                        Err(Escalation::LocalFailed)
+                    }
+                },
+                None => {
+                    let content = extractive_summary(passages, EXTRACTIVE_MAX_SENTENCES);
+                    if content.is_empty() {
+                        Err(Escalation::LocalFailed)
+                    } else {
+                        Ok(CompressionOutput {
+                            content,
+                            valid_at: None,
+                        })
+                    }
+                }
+            },
+        };
+        SUMMARY_TIER_METRICS.record(&result);
+        result
+    }
+}
+
+/// The reason a batch should skip the local tier, if any.
+pub(crate) fn escalation(text: &str, multimodal: bool, options: TierOptions) -> Option<Escalation> {
+    if multimodal {
+        Some(Escalation::Multimodal)
+    } else if text.chars().count() > options.max_chars {
+        Some(Escalation::Length)
+    } else if char_entropy(text) > options.max_entropy {
+        Some(Escalation::Entropy)
+    } else {
+        None
+    }
+}
+
+/// Shannon entropy of the character distribution, in bits per character. English prose
+/// sits around 4.1-4.5; identifiers, encoded data and CJK text score higher.
+pub(crate) fn char_entropy(text: &str) -> f32 {
+    let mut counts = std::collections::HashMap::new();
+    let mut total = 0usize;
+    for ch in text.chars() {
+        *counts.entry(ch).or_insert(0usize) += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>() as f32
+}
+
+/// Keep the `max_sentences` sentences that cover the most batch keywords, in their
+/// original order. Batches with no more sentences than that are kept whole.
+pub(crate) fn extractive_summary(passages: &[Passage], max_sentences: usize) -> String {
+    let sentences: Vec<(Option<&str>, String)> = passages
+        .iter()
+        .flat_map(|passage| {
+            split_sentences(&passage.text)
+                .into_iter()
+                .map(|sentence| (passage.speaker.as_deref(), sentence))
+        })
+        .collect();
+
+    let keep: HashSet<usize> = if sentences.len() <= max_sentences {
+        (0..sentences.len()).collect()
+    } else {
+        let all_text = passages
+            .iter()
+            .map(|passage| passage.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let terms: HashSet<String> =
+            crate::keywords::extract_keywords(&all_text, EXTRACTIVE_KEYWORDS)
+                .iter()
+                .flat_map(|phrase| phrase.split_whitespace().map(str::to_string))
+                .collect();
+        let mut scored: Vec<(usize, usize)> = sentences
+            .iter()
+            .enumerate()
+            .map(|(index, (_, sentence))| {
+                let lowered = sentence.to_lowercase();
+                let score = terms
+                    .iter()
+                    .filter(|term| lowered.contains(term.as_str()))
+                    .count();
+                (index, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored
+            .into_iter()
+            .take(max_sentences)
+            .map(|(index, _)| index)
+            .collect()
+    };
+
+    sentences
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| keep.contains(index))
+        .map(|(_, (speaker, sentence))| match speaker {
+            Some(speaker) => format!("{}: {}", speaker, sentence),
+            None => sentence.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split at line breaks, CJK full stops, and `.`, `!` or `?` followed by whitespace, so
+/// decimals and file names stay whole.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let ends = match ch {
+            '\n' => true,
+            '。' | '！' | '？' => {
+                current.push(ch);
+                true
+            }
+            '.' | '!' | '?' => {
+                current.push(ch);
+                chars.peek().is_none_or(|next| next.is_whitespace())
+            }
+            _ => {
+                current.push(ch);
+                false
+            }
+        };
+        if ends {
+            let sentence = current.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            current.clear();
+        }
+    }
+    let sentence = current.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+    sentences
+}
+
+struct SummaryTierMetrics {
+    local: AtomicUsize,
+    escalated_length: AtomicUsize,
+    escalated_entropy: AtomicUsize,
+    escalated_multimodal: AtomicUsize,
+    escalated_local_failed: AtomicUsize,
+}
+
+impl SummaryTierMetrics {
+    fn record(&self, result: &Result<CompressionOutput, Escalation>) {
+        let counter = match result {
+            Ok(_) => &self.local,
+            Err(Escalation::Length) => &self.escalated_length,
+            Err(Escalation::Entropy) => &self.escalated_entropy,
+            Err(Escalation::Multimodal) => &self.escalated_multimodal,
+            Err(Escalation::LocalFailed) => &self.escalated_local_failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static SUMMARY_TIER_METRICS: SummaryTierMetrics = SummaryTierMetrics {
+    local: AtomicUsize::new(0),
+    escalated_length: AtomicUsize::new(0),
+    escalated_entropy: AtomicUsize::new(0),
+    escalated_multimodal: AtomicUsize::new(0),
+    escalated_local_failed: AtomicUsize::new(0),
+};
+
+/// Process-wide counters for two-tier consolidation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SummaryTierMetricSnapshot {
+    /// Batches summarized on the local tier
+    pub local: usize,
+    /// Batches sent to the main LLM, the sum of the escalation counters
+    pub remote: usize,
+    pub escalated_length: usize,
+    pub escalated_entropy: usize,
+    pub escalated_multimodal: usize,
+    /// Batches the local tier failed on or returned an empty summary for
+    pub escalated_local_failed: usize,
+}
+
+pub fn summary_tier_metric_snapshot() -> SummaryTierMetricSnapshot {
+    let metrics = &SUMMARY_TIER_METRICS;
+    let escalated_length = metrics.escalated_length.load(Ordering::Relaxed);
+    let escalated_entropy = metrics.escalated_entropy.load(Ordering::Relaxed);
+    let escalated_multimodal = metrics.escalated_multimodal.load(Ordering::Relaxed);
+    let escalated_local_failed = metrics.escalated_local_failed.load(Ordering::Relaxed);
+    SummaryTierMetricSnapshot {
+        local: metrics.local.load(Ordering::Relaxed),
+        remote: escalated_length
+            + escalated_entropy
+            + escalated_multimodal
+            + escalated_local_failed,
+        escalated_length,
+        escalated_entropy,
+        escalated_multimodal,
+        escalated_local_failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passage(speaker: Option<&str>, text: &str) -> Passage {
+        Passage {
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+        }
+    }
+
+    fn options() -> TierOptions {
+        TierOptions {
+            max_chars: 200,
+            max_entropy: 4.8,
+        }
+    }
+
+    #[test]
+    fn test_escalation_by_length_entropy_and_modality() {
+        let routine = "Booked the dentist for Tuesday at noon.";
+        assert_eq!(escalation(routine, false, options()), None);
+        assert_eq!(
+            escalation(routine, true, options()),
+            Some(Escalation::Multimodal)
+        );
+        assert_eq!(
+            escalation(&routine.repeat(6), false, options()),
+            Some(Escalation::Length)
+        );
+        let dense = "id=7f3a9c2e-11b4-4d2a-9e8f-0a1b2c3d4e5f token=AbX9zQ2LmN8pR4tY";
+        assert!(char_entropy(dense) > 4.8);
+        assert_eq!(
+            escalation(dense, false, options()),
+            Some(Escalation::Entropy)
+        );
+        assert_eq!(char_entropy(""), 0.0);
+    }
+
+    #[test]
+    fn test_extractive_summary_keeps_keyword_dense_sentences_in_order() {
+        let passages = [
+            passage(
+                Some("Alice"),
+                "Hi there. I moved to Berlin for a Rust programming job. The weather is fine.",
+            ),
+            passage(
+                Some("Bob"),
+                "Nice! Rust programming in Berlin sounds great.\nVersion 1.5 shipped today.",
+            ),
+        ];
+        assert_eq!(
+            extractive_summary(&passages, 2),
+            "Alice: I moved to Berlin for a Rust programming job. Bob: Rust programming in Berlin sounds great."
+        );
+
+        // Short batches are kept whole
+        let short = [passage(
+            None,
+            "Booked the dentist. Bring the x-ray 2.0 file.",
+        )];
+        assert_eq!(
+            extractive_summary(&short, 5),
+            "Booked the dentist. Bring the x-ray 2.0 file."
+        );
+    }
+}
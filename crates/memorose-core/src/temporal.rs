@@ -0,0 +1,696 @@
+//! Rule-based parsing of time expressions in retrieval queries, such as "last Tuesday"
+//! or "上周二", into the valid-time range they refer to.
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Utc, Weekday};
+use serde::Serialize;
+
+/// Language the time expressions of a query are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemporalLocale {
+    En,
+    Zh,
+}
+
+impl TemporalLocale {
+    /// Locale for a language tag such as `en`, `en-US` or `zh-CN`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag
+            .trim()
+            .to_lowercase()
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+        {
+            "en" => Some(Self::En),
+            "zh" => Some(Self::Zh),
+            _ => None,
+        }
+    }
+
+    /// Chinese when the query contains CJK ideographs, English otherwise.
+    pub fn detect(query: &str) -> Self {
+        if query
+            .chars()
+            .any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+        {
+            Self::Zh
+        } else {
+            Self::En
+        }
+    }
+}
+
+/// A time range found in a query. `end` is inclusive, like a retrieve's `end_time`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedTimeRange {
+    /// The matched text, lowercased
+    pub expression: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// What an expression covers before it is placed on the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Span {
+    /// Local days from the first date up to, not including, the second.
+    Days(NaiveDate, NaiveDate),
+    /// The last stretch of time up to now.
+    Rolling(Duration),
+}
+
+/// A match at byte `at` of the lowercased query, `len` bytes long.
+struct Match {
+    at: usize,
+    len: usize,
+    span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Find the first time expression in `query`. Days and weeks (starting on Monday) are
+/// counted in `offset` local time relative to `now`. When two expressions start at the
+/// same place the longer one wins, so "last Tuesday" is read before "Tuesday".
+pub fn parse_time_range(
+    query: &str,
+    now: DateTime<Utc>,
+    offset: FixedOffset,
+    locale: TemporalLocale,
+) -> Option<ParsedTimeRange> {
+    let text = query.to_lowercase();
+    let today = now.with_timezone(&offset).date_naive();
+    let mut matches = iso_date_matches(&text);
+    match locale {
+        TemporalLocale::En => matches.extend(english_matches(&text, today)),
+        TemporalLocale::Zh => matches.extend(chinese_matches(&text, today)),
+    }
+    let best = matches
+        .into_iter()
+        .min_by(|a, b| a.at.cmp(&b.at).then(b.len.cmp(&a.len)))?;
+
+    let (start, end) = match best.span {
+        Span::Days(from, to) => (
+            local_midnight(from, offset)?,
+            local_midnight(to, offset)? - Duration::microseconds(1),
+        ),
+        Span::Rolling(length) => (now - length, now),
+    };
+    Some(ParsedTimeRange {
+        expression: text[best.at..best.at + best.len].to_string(),
+        start,
+        end,
+    })
+}
+
+fn local_midnight(date: NaiveDate, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    offset
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .single()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn day(date: NaiveDate) -> Span {
+    Span::Days(date, date + Duration::days(1))
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday().into())
+}
+
+fn week_of(date: NaiveDate) -> Span {
+    let start = monday_of(date);
+    Span::Days(start, start + Duration::days(7))
+}
+
+fn month_of(year: i32, month: u32) -> Option<Span> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let end = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    Some(Span::Days(start, end))
+}
+
+fn year_of(year: i32) -> Option<Span> {
+    Some(Span::Days(
+        NaiveDate::from_ymd_opt(year, 1, 1)?,
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+    ))
+}
+
+/// `months` months before the month of `date`.
+fn months_back(date: NaiveDate, months: u32) -> Option<Span> {
+    let index = date.year() * 12 + date.month0() as i32 - months as i32;
+    month_of(index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+/// The calendar unit `count` units before `today`.
+fn units_ago(today: NaiveDate, count: u32, unit: Unit) -> Option<Span> {
+    match unit {
+        Unit::Hour => None,
+        Unit::Day => Some(day(today - Duration::days(count.into()))),
+        Unit::Week => Some(week_of(today - Duration::weeks(count.into()))),
+        Unit::Month => months_back(today, count),
+        Unit::Year => year_of(today.year() - count as i32),
+    }
+}
+
+fn rolling(count: u32, unit: Unit) -> Span {
+    let count = i64::from(count);
+    Span::Rolling(match unit {
+        Unit::Hour => Duration::hours(count),
+        Unit::Day => Duration::days(count),
+        Unit::Week => Duration::weeks(count),
+        Unit::Month => Duration::days(30 * count),
+        Unit::Year => Duration::days(365 * count),
+    })
+}
+
+/// The latest `weekday` before `today`, or on it when `include_today` is set.
+fn previous_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let back = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    let back = if back == 0 && !include_today { 7 } else { back };
+    today - Duration::days(back.into())
+}
+
+/// The latest `month`/`day` on or before `today` when no year is given.
+fn latest_date(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(today.year(), month, day)
+        .filter(|date| *date <= today)
+        .or_else(|| NaiveDate::from_ymd_opt(today.year() - 1, month, day))
+}
+
+fn latest_month(today: NaiveDate, month: u32) -> Option<Span> {
+    let year = if month > today.month() {
+        today.year() - 1
+    } else {
+        today.year()
+    };
+    month_of(year, month)
+}
+
+/// `yyyy-mm-dd` dates, in any locale.
+fn iso_date_matches(text: &str) -> Vec<Match> {
+    let bytes = text.as_bytes();
+    let mut matches = Vec::new();
+    for at in 0..bytes.len().saturating_sub(9) {
+        let candidate = &bytes[at..at + 10];
+        let shape = candidate.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+        let bounded = (at == 0 || !bytes[at - 1].is_ascii_digit())
+            && bytes.get(at + 10).is_none_or(|b| !b.is_ascii_digit());
+        if !shape || !bounded {
+            continue;
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&text[at..at + 10], "%Y-%m-%d") {
+            matches.push(Match {
+                at,
+                len: 10,
+                span: day(date),
+            });
+        }
+    }
+    matches
+}
+
+// ── English ─────────────────────────────────────────────────────────
+
+fn english_weekday(word: &str) -> Option<Weekday> {
+    Some(match word {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thur" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn english_month(word: &str) -> Option<u32> {
+    Some(match word {
+        "january" | "jan" => 1,
+        "february" | "feb" => 2,
+        "march" | "mar" => 3,
+        "april" | "apr" => 4,
+        "may" => 5,
+        "june" | "jun" => 6,
+        "july" | "jul" => 7,
+        "august" | "aug" => 8,
+        "september" | "sep" | "sept" => 9,
+        "october" | "oct" => 10,
+        "november" | "nov" => 11,
+        "december" | "dec" => 12,
+        _ => return None,
+    })
+}
+
+fn english_unit(word: &str) -> Option<Unit> {
+    Some(match word {
+        "hour" | "hours" => Unit::Hour,
+        "day" | "days" => Unit::Day,
+        "week" | "weeks" => Unit::Week,
+        "month" | "months" => Unit::Month,
+        "year" | "years" => Unit::Year,
+        _ => return None,
+    })
+}
+
+fn english_number(word: &str) -> Option<u32> {
+    const WORDS: &[&str] = &[
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven",
+        "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ if word.len() <= 3 && word.bytes().all(|b| b.is_ascii_digit()) => word.parse().ok(),
+        _ => WORDS
+            .iter()
+            .position(|number| *number == word)
+            .map(|i| i as u32 + 1),
+    }
+}
+
+/// Day of the month, also written as an ordinal ("5th").
+fn english_day_of_month(word: &str) -> Option<u32> {
+    let digits = word
+        .strip_suffix("st")
+        .or_else(|| word.strip_suffix("nd"))
+        .or_else(|| word.strip_suffix("rd"))
+        .or_else(|| word.strip_suffix("th"))
+        .unwrap_or(word);
+    if digits.is_empty() || digits.len() > 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn english_year(word: &str) -> Option<i32> {
+    (word.len() == 4 && word.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| word.parse().ok())
+        .flatten()
+}
+
+fn english_matches(text: &str, today: NaiveDate) -> Vec<Match> {
+    let words: Vec<(usize, &str)> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(|word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+        .collect();
+    let word = |i: usize| words.get(i).map(|(_, word)| *word).unwrap_or_default();
+    let mut matches = Vec::new();
+
+    for i in 0..words.len() {
+        let mut found = |span: Option<Span>, count: usize| {
+            if let Some(span) = span {
+                let (at, _) = words[i];
+                let (last_at, last) = words[i + count - 1];
+                matches.push(Match {
+                    at,
+                    len: last_at + last.len() - at,
+                    span,
+                });
+            }
+        };
+        match word(i) {
+            "today" => found(Some(day(today)), 1),
+            "yesterday" => found(Some(day(today - Duration::days(1))), 1),
+            "day" if word(i + 1) == "before" && word(i + 2) == "yesterday" => {
+                found(Some(day(today - Duration::days(2))), 3)
+            }
+            first @ ("this" | "last" | "past" | "previous" | "on") => {
+                let next = word(i + 1);
+                let previous = first != "this";
+                match next {
+                    "week" if first != "on" => found(
+                        Some(match first {
+                            "past" => rolling(1, Unit::Week),
+                            _ => week_of(today - Duration::weeks(previous.into())),
+                        }),
+                        2,
+                    ),
+                    "month" if first != "on" => found(
+                        match first {
+                            "past" => Some(rolling(1, Unit::Month)),
+                            _ => months_back(today, previous.into()),
+                        },
+                        2,
+                    ),
+                    "year" if first != "on" => found(
+                        match first {
+                            "past" => Some(rolling(1, Unit::Year)),
+                            _ => year_of(today.year() - i32::from(previous)),
+                        },
+                        2,
+                    ),
+                    _ => {
+                        if let Some(weekday) = english_weekday(next) {
+                            let date = match first {
+                                "this" => {
+                                    monday_of(today)
+                                        + Duration::days(weekday.num_days_from_monday().into())
+                                }
+                                "on" => previous_weekday(today, weekday, true),
+                                _ => previous_weekday(today, weekday, false),
+                            };
+                            found(Some(day(date)), 2);
+                        } else if matches!(first, "last" | "past" | "previous") {
+                            if let (Some(count), Some(unit)) =
+                                (english_number(next), english_unit(word(i + 2)))
+                            {
+                                found(Some(rolling(count, unit)), 3);
+                            }
+                        }
+                    }
+                }
+            }
+            "in" | "during" => {
+                if let Some(month) = english_month(word(i + 1)) {
+                    match english_year(word(i + 2)) {
+                        Some(year) => found(month_of(year, month), 3),
+                        None => found(latest_month(today, month), 2),
+                    }
+                }
+            }
+            current => {
+                if let (Some(count), Some(unit), "ago") = (
+                    english_number(current),
+                    english_unit(word(i + 1)),
+                    word(i + 2),
+                ) {
+                    found(units_ago(today, count, unit), 3);
+                } else if let (Some(month), Some(day_of_month)) =
+                    (english_month(current), english_day_of_month(word(i + 1)))
+                {
+                    match english_year(word(i + 2)) {
+                        Some(year) => found(
+                            NaiveDate::from_ymd_opt(year, month, day_of_month).map(day),
+                            3,
+                        ),
+                        None => found(latest_date(today, month, day_of_month).map(day), 2),
+                    }
+                }
+            }
+        }
+    }
+    matches
+}
+
+// ── Chinese ─────────────────────────────────────────────────────────
+
+const ZH_LAST_WEEK: &[&str] = &["上个星期", "上个礼拜", "上星期", "上礼拜", "上周"];
+const ZH_THIS_WEEK: &[&str] = &["这个星期", "这个礼拜", "这星期", "本星期", "这周", "本周"];
+const ZH_WEEK: &[&str] = &["星期", "礼拜", "周"];
+const ZH_RECENT: &[&str] = &["最近", "过去", "近"];
+
+fn zh_weekday(c: char) -> Option<Weekday> {
+    Some(match c {
+        '一' => Weekday::Mon,
+        '二' => Weekday::Tue,
+        '三' => Weekday::Wed,
+        '四' => Weekday::Thu,
+        '五' => Weekday::Fri,
+        '六' => Weekday::Sat,
+        '日' | '天' => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// A number written in digits or as a Chinese numeral up to twelve, with its byte length.
+fn zh_number(rest: &str) -> Option<(u32, usize)> {
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 {
+        return rest[..digits.min(4)]
+            .parse()
+            .ok()
+            .filter(|_| digits <= 4)
+            .map(|n| (n, digits));
+    }
+    for (numeral, value) in [("十一", 11), ("十二", 12)] {
+        if rest.starts_with(numeral) {
+            return Some((value, numeral.len()));
+        }
+    }
+    let c = rest.chars().next()?;
+    let value = match c {
+        '一' => 1,
+        '二' | '两' => 2,
+        '三' => 3,
+        '四' => 4,
+        '五' => 5,
+        '六' => 6,
+        '七' => 7,
+        '八' => 8,
+        '九' => 9,
+        '十' => 10,
+        _ => return None,
+    };
+    Some((value, c.len_utf8()))
+}
+
+fn zh_unit(rest: &str) -> Option<(Unit, usize)> {
+    [
+        ("个小时", Unit::Hour),
+        ("小时", Unit::Hour),
+        ("天", Unit::Day),
+        ("个星期", Unit::Week),
+        ("个礼拜", Unit::Week),
+        ("星期", Unit::Week),
+        ("礼拜", Unit::Week),
+        ("周", Unit::Week),
+        ("个月", Unit::Month),
+        ("年", Unit::Year),
+    ]
+    .into_iter()
+    .find(|(word, _)| rest.starts_with(word))
+    .map(|(word, unit)| (unit, word.len()))
+}
+
+fn prefix_of<'a>(rest: &str, prefixes: &[&'a str]) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .copied()
+        .find(|prefix| rest.starts_with(prefix))
+}
+
+fn chinese_matches(text: &str, today: NaiveDate) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for (at, _) in text.char_indices() {
+        let rest = &text[at..];
+        let mut found = |span: Option<Span>, len: usize| {
+            if let Some(span) = span {
+                matches.push(Match { at, len, span });
+            }
+        };
+        let weekday_after = |prefix: &str| rest[prefix.len()..].chars().next().and_then(zh_weekday);
+
+        for (word, days_back) in [("前天", 2), ("昨天", 1), ("今天", 0)] {
+            if rest.starts_with(word) {
+                found(Some(day(today - Duration::days(days_back))), word.len());
+            }
+        }
+        for (prefixes, weeks_back) in [(ZH_LAST_WEEK, 1), (ZH_THIS_WEEK, 0)] {
+            if let Some(prefix) = prefix_of(rest, prefixes) {
+                let monday = monday_of(today - Duration::weeks(weeks_back));
+                match weekday_after(prefix) {
+                    Some(weekday) => found(
+                        Some(day(
+                            monday + Duration::days(weekday.num_days_from_monday().into())
+                        )),
+                        prefix.len() + '一'.len_utf8(),
+                    ),
+                    None => found(Some(week_of(monday)), prefix.len()),
+                }
+            }
+        }
+        if let Some(prefix) = prefix_of(rest, ZH_WEEK) {
+            if let Some(weekday) = weekday_after(prefix) {
+                found(
+                    Some(day(previous_weekday(today, weekday, true))),
+                    prefix.len() + '一'.len_utf8(),
+                );
+            }
+        }
+        for (word, months) in [("上个月", 1), ("上月", 1), ("这个月", 0), ("本月", 0)] {
+            if rest.starts_with(word) {
+                found(months_back(today, months), word.len());
+            }
+        }
+        for (word, years) in [("今年", 0), ("去年", 1), ("前年", 2)] {
+            if rest.starts_with(word) {
+                found(year_of(today.year() - years), word.len());
+            }
+        }
+        if let Some(prefix) = prefix_of(rest, ZH_RECENT) {
+            let after = &rest[prefix.len()..];
+            if let Some((count, number_len)) = zh_number(after) {
+                if let Some((unit, unit_len)) = zh_unit(&after[number_len..]) {
+                    found(
+                        Some(rolling(count, unit)),
+                        prefix.len() + number_len + unit_len,
+                    );
+                }
+            }
+        }
+
+        // Numbers only start where no digit precedes them.
+        if text[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_digit())
+        {
+            continue;
+        }
+        let Some((count, number_len)) = zh_number(rest) else {
+            continue;
+        };
+        let after = &rest[number_len..];
+        if let Some((unit, unit_len)) = zh_unit(after) {
+            let tail = &after[unit_len..];
+            let ago = ["以前", "之前", "前"]
+                .into_iter()
+                .find(|word| tail.starts_with(word));
+            if let Some(ago) = ago {
+                found(
+                    units_ago(today, count, unit),
+                    number_len + unit_len + ago.len(),
+                );
+                continue;
+            }
+        }
+        // "2025年3月5日", "3月5号", "3月".
+        let (year, month_at) = match after.strip_prefix('年') {
+            Some(month_part) if count >= 1000 => match zh_number(month_part) {
+                Some((month, len)) if month_part[len..].starts_with('月') => (
+                    Some(count as i32),
+                    (month, number_len + '年'.len_utf8() + len),
+                ),
+                _ => continue,
+            },
+            _ => (None, (count, number_len)),
+        };
+        let (month, month_len) = month_at;
+        let Some(after_month) = rest[month_len..].strip_prefix('月') else {
+            continue;
+        };
+        if !(1..=12).contains(&month) {
+            continue;
+        }
+        let month_end = month_len + '月'.len_utf8();
+        let day_of_month = zh_number(after_month).and_then(|(value, len)| {
+            let suffix = prefix_of(&after_month[len..], &["日", "号"])?;
+            Some((value, len + suffix.len()))
+        });
+        match (year, day_of_month) {
+            (Some(year), Some((value, len))) => found(
+                NaiveDate::from_ymd_opt(year, month, value).map(day),
+                month_end + len,
+            ),
+            (Some(year), None) => found(month_of(year, month), month_end),
+            (None, Some((value, len))) => {
+                found(latest_date(today, month, value).map(day), month_end + len)
+            }
+            (None, None) => found(latest_month(today, month), month_end),
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday 2026-03-11, 15:00 UTC.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 11, 15, 0, 0).unwrap()
+    }
+
+    fn days(query: &str, offset_hours: i32) -> Option<(String, NaiveDate, NaiveDate)> {
+        let offset = FixedOffset::east_opt(offset_hours * 3600).unwrap();
+        let range = parse_time_range(query, now(), offset, TemporalLocale::detect(query))?;
+        Some((
+            range.expression,
+            range.start.with_timezone(&offset).date_naive(),
+            range.end.with_timezone(&offset).date_naive(),
+        ))
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_english_time_expressions() {
+        assert_eq!(
+            days("What did I decide last Tuesday?", 0),
+            Some(("last tuesday".into(), date(3, 10), date(3, 10)))
+        );
+        assert_eq!(
+            days("notes from last week", 0),
+            Some(("last week".into(), date(3, 2), date(3, 8)))
+        );
+        assert_eq!(
+            days("what happened 2 days ago", 0).map(|(_, start, _)| start),
+            Some(date(3, 9))
+        );
+        assert_eq!(
+            days("trips in December", 0),
+            Some((
+                "in december".into(),
+                NaiveDate::from_ymd_opt(2025, 12, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()
+            ))
+        );
+        assert_eq!(
+            days("the call on 2026-02-27", 0).map(|(_, start, end)| (start, end)),
+            Some((date(2, 27), date(2, 27)))
+        );
+        let rolling = parse_time_range(
+            "emails in the past 3 days",
+            now(),
+            FixedOffset::east_opt(0).unwrap(),
+            TemporalLocale::En,
+        )
+        .unwrap();
+        assert_eq!(rolling.start, now() - Duration::days(3));
+        assert_eq!(rolling.end, now());
+        assert_eq!(days("What is my favourite tea?", 0), None);
+    }
+
+    #[test]
+    fn test_parse_chinese_time_expressions_in_local_time() {
+        assert_eq!(
+            days("我上周二决定了什么", 0),
+            Some(("上周二".into(), date(3, 3), date(3, 3)))
+        );
+        assert_eq!(
+            days("昨天的会议", 0).map(|(_, start, _)| start),
+            Some(date(3, 10))
+        );
+        assert_eq!(
+            days("3天前买的书", 0).map(|(_, start, _)| start),
+            Some(date(3, 8))
+        );
+        assert_eq!(
+            days("2月14号的晚餐", 0).map(|(_, start, end)| (start, end)),
+            Some((date(2, 14), date(2, 14)))
+        );
+        // 15:00 UTC is already Thursday in UTC+10, so yesterday is Wednesday there.
+        assert_eq!(
+            days("昨天的会议", 10).map(|(_, start, _)| start),
+            Some(date(3, 11))
+        );
+        assert_eq!(TemporalLocale::from_tag("zh-CN"), Some(TemporalLocale::Zh));
+        assert_eq!(TemporalLocale::from_tag("fr"), None);
+    }
+}
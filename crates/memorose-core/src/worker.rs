@@ -1,8 +1,16 @@
-use crate::llm::{EmbedInput, EmbedPart, LLMClient, LANGUAGE_PRESERVATION_INSTRUCTION};
+use crate::consolidation_pool::{ConsolidatedGroup, ConsolidationClaim};
+use crate::engine::{BackgroundJob, BackgroundJobKind, ConsolidationLease, WorkerRun};
+use crate::llm::{
+    CompressionContext, EmbedInput, EmbedPart, LLMClient, LANGUAGE_PRESERVATION_INSTRUCTION,
+};
+use crate::summary_tier::{LocalSummarizer, Passage, TierOptions};
 use crate::MemoroseEngine;
 use anyhow::Result;
+use futures::StreamExt;
 use memorose_common::{
-    config::AppConfig, tokenizer::count_tokens, Asset, Event, EventContent, GraphEdge, MemoryUnit,
+    config::{AppConfig, PromptKind},
+    tokenizer::count_tokens,
+    Asset, Event, EventContent, GraphEdge, MemoryUnit,
 };
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
@@ -15,6 +23,22 @@ use tokio::time::Duration;
 
 type PackedGroupKey = (String, uuid::Uuid, Option<String>);
 
+/// A compressed group ready to become a memory unit: source event ids, user, stream,
+/// summary, valid time, assets, metadata and the optional multimodal embed input.
+type PipelineItem = (
+    Vec<uuid::Uuid>,
+    String,
+    uuid::Uuid,
+    String,
+    Option<String>,
+    Vec<Asset>,
+    serde_json::Value,
+    Option<EmbedInput>,
+);
+
+/// Upper bound on deadline index entries handled per cycle.
+const TASK_DEADLINE_BATCH_LIMIT: usize = 500;
+
 #[derive(Debug, Clone)]
 struct PackedEventGroup {
     key: PackedGroupKey,
@@ -22,6 +46,15 @@ struct PackedEventGroup {
     events: Vec<Event>,
 }
 
+/// Per-cycle settings for turning a packed group into its summary.
+#[derive(Clone)]
+struct CompressOptions {
+    local: Option<LocalSummarizer>,
+    /// Tokens sent in one compress call; longer text is compressed in chunks
+    max_tokens: usize,
+    overlap_tokens: usize,
+}
+
 struct ProducedBatch {
     key: PackedGroupKey,
     seq_no: u64,
@@ -53,20 +86,72 @@ impl Drop for RunningFlagGuard {
     }
 }
 
+/// Hides events from the background consolidation cycle while they are consolidated
+/// inline, and hands back whatever is still pending once the inline run ends.
+struct SyncConsolidationGuard {
+    in_flight: Arc<std::sync::Mutex<HashSet<uuid::Uuid>>>,
+    ids: Vec<uuid::Uuid>,
+}
+
+impl SyncConsolidationGuard {
+    fn register(in_flight: Arc<std::sync::Mutex<HashSet<uuid::Uuid>>>, events: &[Event]) -> Self {
+        let ids = events.iter().map(|event| event.id).collect::<Vec<_>>();
+        in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(ids.iter().copied());
+        Self { in_flight, ids }
+    }
+}
+
+impl Drop for SyncConsolidationGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        for id in &self.ids {
+            in_flight.remove(id);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct BackgroundWorker {
     engine: MemoroseEngine,
     llm_client: Option<Arc<dyn LLMClient>>,
+    /// Cheap model for routine batches when local summarization is on
+    local_llm_client: Option<Arc<dyn LLMClient>>,
     config: memorose_common::config::WorkerConfig,
+    prompts: Arc<memorose_common::config::PromptsConfig>,
     last_decay: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_retention: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_edge_decay: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_integrity_check: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_orphan_gc: Arc<tokio::sync::Mutex<std::time::Instant>>,
     last_compaction: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_export: Arc<tokio::sync::Mutex<std::time::Instant>>,
     last_consolidation: Arc<tokio::sync::Mutex<std::time::Instant>>,
     last_insight: Arc<tokio::sync::Mutex<std::time::Instant>>,
     last_community: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_l2_refresh: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_profile: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_task_deadline: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_self_eval: Arc<tokio::sync::Mutex<std::time::Instant>>,
+    last_storage_usage: Arc<tokio::sync::Mutex<std::time::Instant>>,
     consolidation_running: Arc<AtomicBool>,
     materialization_running: Arc<AtomicBool>,
     insight_running: Arc<AtomicBool>,
+    linking_running: Arc<AtomicBool>,
+    /// User that headed the previous consolidation rotation.
+    consolidation_user_cursor: Arc<tokio::sync::Mutex<Option<String>>>,
+    /// Pending events being consolidated inline, which the background cycle skips.
+    sync_consolidating: Arc<std::sync::Mutex<HashSet<uuid::Uuid>>>,
     raft: Option<crate::raft::MemoroseRaft>,
+    /// When set, every tick picks up the current worker settings from here.
+    live_config: Option<memorose_common::config::LiveConfig>,
+    /// Text consolidation is leased to a consolidation pool instead of run here; media
+    /// and pre-summarized events stay local.
+    consolidation_pooled: bool,
+    /// Names this worker on the leases of the background jobs it claims.
+    job_holder: String,
 }
 
 impl BackgroundWorker {
@@ -83,6 +168,13 @@ impl BackgroundWorker {
             return true;
         }
 
+        // Only the age trigger is left; it must not spend a reflection on a trivial window.
+        if marker.pending_units < self.config.insight_min_reflect_l1
+            && marker.pending_tokens < self.config.insight_min_reflect_tokens
+        {
+            return false;
+        }
+
         let max_delay_ms = self
             .config
             .insight_max_delay_ms
@@ -94,9 +186,7 @@ impl BackgroundWorker {
     }
 
     fn packed_event_key(event: &Event) -> PackedGroupKey {
-        let is_agent = event.metadata.get("role").and_then(|v| v.as_str()) == Some("assistant")
-            || event.metadata.get("agent_id").is_some();
-        let agent_id = if is_agent {
+        let agent_id = if event.is_agent_event() {
             event
                 .metadata
                 .get("agent_id")
@@ -120,11 +210,92 @@ impl BackgroundWorker {
         content_tokens + 4
     }
 
-    fn pack_events_for_consolidation(&self, events: Vec<Event>) -> Vec<PackedEventGroup> {
+    /// Split each stream in `events` into sessions at long time gaps or topic shifts and
+    /// record the boundaries on the stream. Returns the session number of every event;
+    /// empty when segmentation is disabled.
+    fn detect_stream_segments(&self, events: &[Event]) -> HashMap<uuid::Uuid, usize> {
+        let mut segments = HashMap::new();
+        if !self.config.stream_segmentation_enabled {
+            return segments;
+        }
+        let options = crate::segmentation::SegmentationOptions {
+            gap: chrono::Duration::seconds(
+                self.config.stream_segment_gap_secs.min(i64::MAX as u64) as i64,
+            ),
+            topic_overlap: self.config.stream_segment_topic_overlap,
+            min_events: self.config.stream_segment_min_events,
+        };
+
+        let mut by_stream: HashMap<(&str, uuid::Uuid), Vec<&Event>> = HashMap::new();
+        for event in events {
+            by_stream
+                .entry((event.user_id.as_str(), event.stream_id))
+                .or_default()
+                .push(event);
+        }
+
+        for ((user_id, stream_id), mut stream_events) in by_stream {
+            stream_events.sort_by_key(|event| event.transaction_time);
+            let stream = match self.engine.get_memory_stream(user_id, stream_id) {
+                Ok(stream) => stream,
+                Err(error) => {
+                    tracing::warn!(
+                        "Failed to load stream {} for segmentation: {:?}",
+                        stream_id,
+                        error
+                    );
+                    continue;
+                }
+            };
+            let boundaries = crate::segmentation::find_segment_boundaries(
+                &stream_events,
+                stream.as_ref().and_then(|stream| stream.last_event_at),
+                stream
+                    .as_ref()
+                    .map_or(&[][..], |stream| stream.segment_boundaries.as_slice()),
+                options,
+            );
+            segments.extend(crate::segmentation::assign_segments(
+                &stream_events,
+                &boundaries,
+            ));
+
+            let Some(last_event_at) = stream_events.last().map(|event| event.transaction_time)
+            else {
+                continue;
+            };
+            let app_id = stream_events
+                .iter()
+                .find_map(|event| event.agent_id.clone());
+            if let Err(error) = self.engine.record_stream_segments(
+                user_id,
+                stream_id,
+                app_id,
+                boundaries,
+                last_event_at,
+            ) {
+                tracing::warn!(
+                    "Failed to record segments for stream {}: {:?}",
+                    stream_id,
+                    error
+                );
+            }
+        }
+        segments
+    }
+
+    /// Group sorted events into prompt-sized packs. A pack never spans two streams, agents
+    /// or stream sessions from `segments`.
+    fn pack_events_for_consolidation(
+        &self,
+        events: Vec<Event>,
+        segments: &HashMap<uuid::Uuid, usize>,
+    ) -> Vec<PackedEventGroup> {
         let mut packed_batches = Vec::new();
         let mut current_batch = Vec::new();
         let mut current_key: Option<PackedGroupKey> = None;
         let mut current_tokens = 0usize;
+        let mut current_segment = 0usize;
         let mut next_seq_by_key: HashMap<PackedGroupKey, u64> = HashMap::new();
         let target_tokens = self.config.consolidation_target_tokens.max(1);
         let max_events_per_pack = self.config.consolidation_max_events_per_pack.max(1);
@@ -132,7 +303,9 @@ impl BackgroundWorker {
         for event in events {
             let key = Self::packed_event_key(&event);
             let event_tokens = Self::estimate_event_pack_tokens(&event).max(1);
+            let segment = segments.get(&event.id).copied().unwrap_or_default();
             let should_flush = Some(&key) != current_key.as_ref()
+                || segment != current_segment
                 || current_batch.len() >= max_events_per_pack
                 || (!current_batch.is_empty() && current_tokens + event_tokens > target_tokens);
 
@@ -153,6 +326,7 @@ impl BackgroundWorker {
                     });
                 }
                 current_key = Some(key);
+                current_segment = segment;
                 current_tokens = 0;
             }
 
@@ -237,6 +411,63 @@ impl BackgroundWorker {
         selected
     }
 
+    /// Split a cycle's events into per-user queues and deal them out round-robin until
+    /// `event_budget` is spent, capping each user at `max_events_per_user` (0 = no cap).
+    /// Users are visited in id order starting after `start_after`, so the head of the
+    /// rotation moves on every cycle. Each user's events keep their fetch order.
+    fn select_user_batches_fairly(
+        events: Vec<Event>,
+        event_budget: usize,
+        max_events_per_user: usize,
+        start_after: Option<&str>,
+    ) -> Vec<(String, Vec<Event>)> {
+        let mut queues: std::collections::BTreeMap<String, VecDeque<Event>> =
+            std::collections::BTreeMap::new();
+        for event in events {
+            queues
+                .entry(event.user_id.clone())
+                .or_default()
+                .push_back(event);
+        }
+
+        let mut users: Vec<String> = queues.keys().cloned().collect();
+        if let Some(start_after) = start_after {
+            let pivot = users.partition_point(|user| user.as_str() <= start_after);
+            users.rotate_left(pivot);
+        }
+
+        let per_user_cap = if max_events_per_user == 0 {
+            usize::MAX
+        } else {
+            max_events_per_user
+        };
+        let mut selected: Vec<(String, Vec<Event>)> = users
+            .iter()
+            .map(|user| (user.clone(), Vec::new()))
+            .collect();
+        let mut remaining = event_budget.max(1);
+        let mut progressed = true;
+        while remaining > 0 && progressed {
+            progressed = false;
+            for (user, batch) in selected.iter_mut() {
+                if remaining == 0 {
+                    break;
+                }
+                if batch.len() >= per_user_cap {
+                    continue;
+                }
+                if let Some(event) = queues.get_mut(user).and_then(|queue| queue.pop_front()) {
+                    batch.push(event);
+                    remaining -= 1;
+                    progressed = true;
+                }
+            }
+        }
+
+        selected.retain(|(_, batch)| !batch.is_empty());
+        selected
+    }
+
     fn normalize_asset_storage_key(asset_type: &str, storage_key: &str) -> String {
         let trimmed = storage_key.trim();
         if trimmed.starts_with("http://")
@@ -269,6 +500,65 @@ impl BackgroundWorker {
         }
     }
 
+    async fn hydrate_keywords(&self, unit: &mut MemoryUnit) {
+        let limit = self.config.keyword_max_per_memory;
+        if !self.config.keyword_extraction_enabled
+            || limit == 0
+            || unit.level != 1
+            || !unit.keywords.is_empty()
+        {
+            return;
+        }
+
+        let mut keywords = Vec::new();
+        if let Some(client) = self.llm_client.clone() {
+            let arbitrator = crate::arbitrator::Arbitrator::with_client(client);
+            match arbitrator.extract_keywords(unit, limit).await {
+                Ok(extracted) => keywords = extracted,
+                Err(error) => {
+                    tracing::warn!(
+                        "Keyword extraction during worker hydration failed for {}: {:?}",
+                        unit.id,
+                        error
+                    );
+                }
+            }
+        }
+
+        if keywords.is_empty() {
+            keywords = crate::keywords::extract_keywords(&unit.content, limit);
+        }
+
+        unit.keywords = crate::fact_extraction::normalize_memory_keywords(&keywords, limit);
+    }
+
+    async fn hydrate_emotions(&self, unit: &mut MemoryUnit) {
+        if !self.config.emotion_tagging_enabled || unit.level != 1 || !unit.emotions.is_empty() {
+            return;
+        }
+
+        let mut emotions = Vec::new();
+        if let Some(client) = self.llm_client.clone() {
+            let arbitrator = crate::arbitrator::Arbitrator::with_client(client);
+            match arbitrator.classify_emotions(unit).await {
+                Ok(classified) => emotions = classified,
+                Err(error) => {
+                    tracing::warn!(
+                        "Emotion classification during worker hydration failed for {}: {:?}",
+                        unit.id,
+                        error
+                    );
+                }
+            }
+        }
+
+        if emotions.is_empty() {
+            emotions = crate::emotion::classify_emotions(&unit.content);
+        }
+
+        unit.emotions = emotions;
+    }
+
     async fn hydrate_extracted_facts(&self, unit: &mut MemoryUnit) {
         if unit.level != 1 || unit.memory_type != memorose_common::MemoryType::Factual {
             return;
@@ -340,20 +630,44 @@ impl BackgroundWorker {
             tracing::warn!("BackgroundWorker starting without API Key. Summary and Insight features will be disabled/degraded.");
         }
 
+        let local_llm_client = config
+            .local_llm
+            .as_ref()
+            .and_then(crate::llm::create_llm_client);
+
+        let consolidation_pooled = config.consolidation_is_pooled();
         let now = std::time::Instant::now();
         Self {
             engine,
             llm_client,
+            local_llm_client,
+            prompts: Arc::new(config.prompts),
             config: config.worker,
             last_decay: Arc::new(tokio::sync::Mutex::new(now)),
+            last_retention: Arc::new(tokio::sync::Mutex::new(now)),
+            last_edge_decay: Arc::new(tokio::sync::Mutex::new(now)),
+            last_integrity_check: Arc::new(tokio::sync::Mutex::new(now)),
+            last_orphan_gc: Arc::new(tokio::sync::Mutex::new(now)),
             last_compaction: Arc::new(tokio::sync::Mutex::new(now)),
+            last_export: Arc::new(tokio::sync::Mutex::new(now)),
             last_consolidation: Arc::new(tokio::sync::Mutex::new(now)),
             last_insight: Arc::new(tokio::sync::Mutex::new(now)),
             last_community: Arc::new(tokio::sync::Mutex::new(now)),
+            last_l2_refresh: Arc::new(tokio::sync::Mutex::new(now)),
+            last_profile: Arc::new(tokio::sync::Mutex::new(now)),
+            last_task_deadline: Arc::new(tokio::sync::Mutex::new(now)),
+            last_self_eval: Arc::new(tokio::sync::Mutex::new(now)),
+            last_storage_usage: Arc::new(tokio::sync::Mutex::new(now)),
             consolidation_running: Arc::new(AtomicBool::new(false)),
             materialization_running: Arc::new(AtomicBool::new(false)),
             insight_running: Arc::new(AtomicBool::new(false)),
+            linking_running: Arc::new(AtomicBool::new(false)),
+            consolidation_user_cursor: Arc::new(tokio::sync::Mutex::new(None)),
+            sync_consolidating: Arc::new(std::sync::Mutex::new(HashSet::new())),
             raft: None,
+            live_config: None,
+            consolidation_pooled,
+            job_holder: uuid::Uuid::new_v4().to_string(),
         }
     }
 
@@ -361,6 +675,27 @@ impl BackgroundWorker {
         self.raft = Some(raft);
     }
 
+    pub fn set_live_config(&mut self, live_config: memorose_common::config::LiveConfig) {
+        let config = live_config.load();
+        self.config = config.worker.clone();
+        self.prompts = Arc::new(config.prompts.clone());
+        self.consolidation_pooled = config.consolidation_is_pooled();
+        self.live_config = Some(live_config);
+    }
+
+    /// This worker with the worker settings of the latest config reload. Cycle timestamps
+    /// and running flags are shared, so the copy can stand in for `self` for one tick.
+    fn refreshed(&self) -> Self {
+        let mut worker = self.clone();
+        if let Some(live_config) = &self.live_config {
+            let config = live_config.load();
+            worker.config = config.worker.clone();
+            worker.prompts = Arc::new(config.prompts.clone());
+            worker.consolidation_pooled = config.consolidation_is_pooled();
+        }
+        worker
+    }
+
     pub async fn is_leader(&self) -> bool {
         if let Some(raft) = &self.raft {
             let metrics = raft.metrics().borrow().clone();
@@ -370,6 +705,119 @@ impl BackgroundWorker {
         }
     }
 
+    /// Write `request` through Raft when this worker is part of a cluster, so every replica
+    /// applies it, or straight to the engine otherwise. Returns whether it took effect.
+    async fn replicate(&self, request: crate::raft::types::ClientRequest) -> Result<bool> {
+        match &self.raft {
+            Some(raft) => raft
+                .client_write(request)
+                .await
+                .map(|response| response.data.success)
+                .map_err(|e| anyhow::anyhow!("Raft write error: {:?}", e)),
+            None => Ok(crate::raft::storage::apply_client_request(&self.engine, &request).await),
+        }
+    }
+
+    /// Run one `cycle` and keep a record of it in the run history. Runs that did nothing
+    /// are not recorded, since most ticks find no work.
+    async fn tracked<T>(
+        &self,
+        cycle: &'static str,
+        run: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let started_at = chrono::Utc::now();
+        let (result, counts) = crate::run_report::metered(run).await;
+        let did_work = counts.items > 0 || counts.llm_tokens > 0 || result.is_err();
+        if did_work && self.config.run_history_max_records > 0 {
+            let record = WorkerRun {
+                id: uuid::Uuid::new_v4(),
+                cycle: cycle.to_string(),
+                started_at,
+                finished_at: chrono::Utc::now(),
+                items_processed: counts.items,
+                llm_tokens: counts.llm_tokens,
+                error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            };
+            if let Err(e) = self
+                .engine
+                .record_worker_run(&record, self.config.run_history_max_records)
+            {
+                tracing::warn!("Failed to record {} run: {:?}", cycle, e);
+            }
+        }
+        result
+    }
+
+    /// Consolidate just-ingested events now instead of on the next consolidation cycle:
+    /// compress, embed and publish their memory units before returning the unit ids.
+    /// Events stay pending until their units are staged, so anything left unfinished,
+    /// including a run abandoned by its caller, falls back to the background cycle.
+    /// Audio and video always take the background path.
+    pub async fn consolidate_events_now(&self, mut events: Vec<Event>) -> Result<Vec<uuid::Uuid>> {
+        events.retain(|event| {
+            !matches!(
+                event.content,
+                EventContent::Audio(_) | EventContent::Video(_)
+            )
+        });
+        if events.is_empty() {
+            return Ok(Vec::new());
+        }
+        let _guard = SyncConsolidationGuard::register(self.sync_consolidating.clone(), &events);
+
+        let (uncompressed_events, mut events): (Vec<_>, Vec<_>) = events
+            .into_iter()
+            .partition(|event| self.skips_compression(event));
+        let mut batch = Vec::new();
+        for event in &uncompressed_events {
+            batch.push(self.uncompressed_pipeline_item(event).await);
+        }
+
+        events.sort_by_key(|event| (Self::packed_event_key(event), event.transaction_time));
+        let segments = self.detect_stream_segments(&events);
+        for group in self.pack_events_for_consolidation(events, &segments) {
+            let PackedEventGroup {
+                key,
+                seq_no,
+                events,
+            } = group;
+            let produced = Self::compress_packed_group(
+                self.llm_client.as_deref(),
+                &self.compress_options(),
+                &self.engine,
+                &self.prompts,
+                key,
+                seq_no,
+                events,
+            )
+            .await;
+            batch.push((
+                produced.event_ids,
+                produced.user_id,
+                produced.stream_id,
+                produced.summary,
+                produced.valid_at,
+                produced.assets,
+                produced.metadata,
+                produced.embed_input,
+            ));
+        }
+
+        let (processed_ids, mut jobs) = self.stage_pipeline_batch(batch).await?;
+        // Queue the jobs durably, but out of the background loop's reach for as long as
+        // the inline publish below is expected to take.
+        let grace_micros = (self.config.sync_consolidation_budget_ms as i64).saturating_mul(1000);
+        for job in &mut jobs {
+            job.next_attempt_at_micros = job.next_attempt_at_micros.saturating_add(grace_micros);
+        }
+        self.engine.enqueue_materialization_jobs(jobs.clone())?;
+        for eid in &processed_ids {
+            self.engine.mark_event_processed(eid).await?;
+        }
+
+        self.materialize_jobs(jobs).await
+    }
+
     pub async fn run(&self) {
         let tick_ms = self.config.tick_interval_ms.max(10);
         let consolidation_interval_ms = self
@@ -410,31 +858,89 @@ impl BackgroundWorker {
             });
         }
 
+        if self.linking_is_deferred() {
+            let linking_worker = self.clone();
+            loop_tasks.spawn(async move {
+                linking_worker.run_linking_loop().await;
+                "linking"
+            });
+        }
+
+        self.spawn_cache_warmup("startup");
+        let mut was_leader = false;
+
         let mut interval = tokio::time::interval(Duration::from_millis(tick_ms));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                    if !self.is_leader().await {
+                    let worker = self.refreshed();
+                    let leader = worker.is_leader().await;
+                    if leader && !was_leader && worker.raft.is_some() {
+                        worker.spawn_cache_warmup("leader change");
+                    }
+                    was_leader = leader;
+
+                    // Every replica keeps its own derived indexes, so followers check too.
+                    if let Err(e) = worker.tracked("integrity", worker.run_integrity_cycle()).await {
+                        tracing::error!("Integrity check cycle failed: {:?}", e);
+                    }
+                    if let Err(e) = worker.tracked("orphan_gc", worker.run_orphan_gc_cycle()).await {
+                        tracing::error!("Orphan GC cycle failed: {:?}", e);
+                    }
+
+                    if !leader {
                         continue;
                     }
 
-                    if let Err(e) = self.run_decay_cycle().await {
+                    if let Err(e) = worker.tracked("decay", worker.run_decay_cycle()).await {
                         tracing::error!("Decay cycle failed: {:?}", e);
                     }
 
-                    if let Err(e) = self.run_l3_task_cycle().await {
+                    if let Err(e) = worker.tracked("retention", worker.run_retention_cycle()).await {
+                        tracing::error!("Retention cycle failed: {:?}", e);
+                    }
+
+                    if let Err(e) = worker.tracked("edge_decay", worker.run_edge_decay_cycle()).await {
+                        tracing::error!("Edge decay cycle failed: {:?}", e);
+                    }
+
+                    if let Err(e) = worker.tracked("l3_task", worker.run_l3_task_cycle()).await {
                         tracing::error!("L3 Task cycle failed: {:?}", e);
                     }
 
-                    if let Err(e) = self.run_compaction_cycle().await {
+                    if let Err(e) = worker.tracked("task_deadline", worker.run_task_deadline_cycle()).await {
+                        tracing::error!("Task deadline cycle failed: {:?}", e);
+                    }
+
+                    if let Err(e) = worker.tracked("compaction", worker.run_compaction_cycle()).await {
                         tracing::error!("Compaction cycle failed: {:?}", e);
                     }
 
-                    if self.llm_client.is_some() {
-                        if let Err(e) = self.run_community_cycle().await {
+                    if let Err(e) = worker.tracked("export", worker.run_export_cycle()).await {
+                        tracing::error!("Export cycle failed: {:?}", e);
+                    }
+
+                    if let Err(e) = worker.tracked("storage_usage", worker.run_storage_usage_cycle()).await {
+                        tracing::error!("Storage usage cycle failed: {:?}", e);
+                    }
+
+                    if worker.llm_client.is_some() {
+                        if let Err(e) = worker.tracked("community", worker.run_community_cycle()).await {
                             tracing::error!("Community cycle failed: {:?}", e);
                         }
+
+                        if let Err(e) = worker.tracked("l2_refresh", worker.run_l2_refresh_cycle()).await {
+                            tracing::error!("L2 refresh cycle failed: {:?}", e);
+                        }
+
+                        if let Err(e) = worker.tracked("profile", worker.run_profile_cycle()).await {
+                            tracing::error!("Profile cycle failed: {:?}", e);
+                        }
+
+                        if let Err(e) = worker.tracked("self_eval", worker.run_self_eval_cycle()).await {
+                            tracing::error!("Self-evaluation cycle failed: {:?}", e);
+                        }
                     }
                 }
                 Some(result) = loop_tasks.join_next() => {
@@ -470,11 +976,40 @@ impl BackgroundWorker {
                             "insight"
                         });
                     }
+
+                    if self.linking_is_deferred() {
+                        let linking_worker = self.clone();
+                        loop_tasks.spawn(async move {
+                            linking_worker.run_linking_loop().await;
+                            "linking"
+                        });
+                    }
                 }
             }
         }
     }
 
+    /// Preload the query cache for the most recently active users in the background so
+    /// the first reads after a restart or failover do not all miss.
+    fn spawn_cache_warmup(&self, reason: &'static str) {
+        let max_users = self.config.cache_warmup_users;
+        if max_users == 0 {
+            return;
+        }
+        let nodes_per_user = self.config.cache_warmup_nodes_per_user;
+        let engine = self.engine.clone();
+        tokio::spawn(async move {
+            match engine.warm_query_cache(max_users, nodes_per_user).await {
+                Ok(warmed) => tracing::info!(
+                    "Query cache warm-up ({}) preloaded {} neighbourhoods",
+                    reason,
+                    warmed
+                ),
+                Err(e) => tracing::warn!("Query cache warm-up ({}) failed: {:?}", reason, e),
+            }
+        });
+    }
+
     async fn run_consolidation_loop(self) {
         let tick_ms = self.config.tick_interval_ms.max(10);
         tracing::info!(
@@ -495,6 +1030,7 @@ impl BackgroundWorker {
                 continue;
             }
 
+            let worker = self.refreshed();
             let Some(_running_guard) =
                 RunningFlagGuard::try_acquire(self.consolidation_running.clone())
             else {
@@ -502,7 +1038,10 @@ impl BackgroundWorker {
                 continue;
             };
 
-            if let Err(error) = self.run_consolidation_cycle().await {
+            if let Err(error) = worker
+                .tracked("consolidation", worker.run_consolidation_cycle())
+                .await
+            {
                 tracing::error!("Consolidation loop failed: {:?}", error);
             }
         }
@@ -522,6 +1061,7 @@ impl BackgroundWorker {
                 continue;
             }
 
+            let worker = self.refreshed();
             let Some(_running_guard) =
                 RunningFlagGuard::try_acquire(self.materialization_running.clone())
             else {
@@ -529,7 +1069,10 @@ impl BackgroundWorker {
                 continue;
             };
 
-            if let Err(error) = self.run_materialization_cycle().await {
+            if let Err(error) = worker
+                .tracked("materialization", worker.run_materialization_cycle())
+                .await
+            {
                 tracing::error!("Materialization loop failed: {:?}", error);
             }
         }
@@ -560,18 +1103,62 @@ impl BackgroundWorker {
                 continue;
             }
 
+            let worker = self.refreshed();
             let Some(_running_guard) = RunningFlagGuard::try_acquire(self.insight_running.clone())
             else {
                 tracing::debug!("Insight loop is still busy; skipping this tick.");
                 continue;
             };
 
-            if let Err(error) = self.run_insight_cycle().await {
+            if let Err(error) = worker.tracked("insight", worker.run_insight_cycle()).await {
                 tracing::error!("Insight loop failed: {:?}", error);
             }
         }
     }
 
+    fn linking_is_deferred(&self) -> bool {
+        self.engine.linking_config().mode == memorose_common::config::LinkingMode::Deferred
+    }
+
+    /// Drains the queue of units whose linking was deferred out of the storage path.
+    async fn run_linking_loop(self) {
+        let tick_ms = self.config.tick_interval_ms.max(10);
+        let batch_size = self.engine.linking_config().deferred_batch_size.max(1);
+        tracing::info!(
+            "Linking loop started (poll={}ms, batch_size={}).",
+            tick_ms,
+            batch_size
+        );
+
+        let mut interval = tokio::time::interval(Duration::from_millis(tick_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            if !self.is_leader().await {
+                continue;
+            }
+
+            let Some(_running_guard) = RunningFlagGuard::try_acquire(self.linking_running.clone())
+            else {
+                tracing::debug!("Linking loop is still busy; skipping this tick.");
+                continue;
+            };
+
+            let linking = async {
+                let linked = self.engine.process_linking_queue(batch_size).await?;
+                crate::run_report::record_items(linked);
+                anyhow::Ok(linked)
+            };
+            match self.tracked("linking", linking).await {
+                Ok(0) => {}
+                Ok(linked) => tracing::debug!("Linked {} deferred memory units", linked),
+                Err(error) => tracing::error!("Linking loop failed: {:?}", error),
+            }
+        }
+    }
+
     async fn run_compaction_cycle(&self) -> Result<()> {
         let compaction_interval = Duration::from_secs(self.config.compaction_interval_secs.max(1));
         let should_compact = {
@@ -582,6 +1169,7 @@ impl BackgroundWorker {
         if should_compact {
             tracing::info!("Running LanceDB compaction...");
             self.engine.compact_vector_store().await?;
+            crate::run_report::record_items(1);
             let mut last = self.last_compaction.lock().await;
             *last = std::time::Instant::now();
         }
@@ -660,6 +1248,7 @@ impl BackgroundWorker {
                     )),
                 );
                 let _ = self.engine.ingest_event(event).await;
+                crate::run_report::record_items(1);
             }
         }
         Ok(())
@@ -690,6 +1279,7 @@ impl BackgroundWorker {
                     self.engine
                         .decay_importance(user_id, self.config.decay_factor)
                         .await?;
+                    crate::run_report::record_items(1);
 
                     let pruned = self
                         .engine
@@ -711,48 +1301,319 @@ impl BackgroundWorker {
         Ok(())
     }
 
-    fn parse_metadata_embedding(metadata: &serde_json::Value) -> Option<Option<Vec<f32>>> {
-        metadata
-            .get("embedding")
-            .and_then(|v| v.as_array())
-            .map(|values| {
-                values
-                    .iter()
-                    .map(|v| v.as_f64().map(|f| f as f32))
-                    .collect::<Option<Vec<f32>>>()
-                    .filter(|vec| !vec.is_empty())
-            })
-    }
+    /// Prune graph edges of active users that have decayed below their relation's
+    /// threshold, so auto-links that are never reinforced stop growing the graph.
+    async fn run_edge_decay_cycle(&self) -> Result<()> {
+        let edge_decay = &self.engine.edge_decay;
+        if !edge_decay.enabled {
+            return Ok(());
+        }
+        let interval = Duration::from_secs(edge_decay.interval_secs.max(1));
+        {
+            let last = self.last_edge_decay.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
 
-    fn pending_input_from_embed_input(
-        input: EmbedInput,
-    ) -> crate::engine::PendingMaterializationInput {
-        match input {
-            EmbedInput::Text(text) => crate::engine::PendingMaterializationInput::Text(text),
-            EmbedInput::Multimodal { parts } => {
-                crate::engine::PendingMaterializationInput::Multimodal {
-                    parts: parts
-                        .into_iter()
-                        .map(|part| match part {
-                            EmbedPart::Text(text) => {
-                                crate::engine::PendingMaterializationPart::Text { text }
-                            }
-                            EmbedPart::InlineData { mime_type, data } => {
-                                crate::engine::PendingMaterializationPart::InlineData {
-                                    mime_type,
-                                    data,
-                                }
-                            }
-                        })
-                        .collect(),
+        let skv = self.engine.system_kv();
+        let active_pairs = tokio::task::spawn_blocking(move || skv.scan(b"active_user:")).await??;
+        let now = chrono::Utc::now();
+        for (key, _) in active_pairs {
+            let key_str = String::from_utf8(key)?;
+            if let Some(user_id) = key_str.strip_prefix("active_user:") {
+                let pruned = self.engine.prune_decayed_edges(user_id, now).await?;
+                crate::run_report::record_items(pruned);
+                if pruned > 0 {
+                    tracing::info!("Pruned {} decayed graph edges for user {}", pruned, user_id);
                 }
             }
         }
+
+        let mut last = self.last_edge_decay.lock().await;
+        *last = std::time::Instant::now();
+        Ok(())
     }
 
-    fn embed_input_from_pending_input(
-        input: crate::engine::PendingMaterializationInput,
-    ) -> EmbedInput {
+    /// Verify one batch of units against the vector table, text index and graph, repairing
+    /// drift when `integrity_repair` is set.
+    async fn run_integrity_cycle(&self) -> Result<()> {
+        if self.config.integrity_check_interval_secs == 0 {
+            return Ok(());
+        }
+        let interval = Duration::from_secs(self.config.integrity_check_interval_secs);
+        {
+            let last = self.last_integrity_check.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+
+        let report = self
+            .engine
+            .run_integrity_batch(crate::engine::IntegrityCheckOptions {
+                batch_size: self.config.integrity_batch_size,
+                repair: self.config.integrity_repair,
+                repair_orphaned_edges: self.config.integrity_repair_orphaned_edges,
+                settle_secs: crate::engine::INTEGRITY_SETTLE_SECS,
+            })
+            .await?;
+        crate::run_report::record_items(report.scanned_units);
+        if report.completed_at.is_some() {
+            let drift = report.dangling_unit_index
+                + report.missing_vectors
+                + report.missing_text_index
+                + report.orphaned_vectors
+                + report.orphaned_edges;
+            if drift > 0 {
+                tracing::warn!(
+                    "Integrity pass found drift over {} units: {:?}",
+                    report.scanned_units,
+                    report
+                );
+            } else {
+                tracing::info!(
+                    "Integrity pass found no drift over {} units",
+                    report.scanned_units
+                );
+            }
+        }
+
+        *self.last_integrity_check.lock().await = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Remove index, queue and dedup entries that reference deleted units. With
+    /// `orphan_gc_dry_run` the sweep only reports what it would remove.
+    async fn run_orphan_gc_cycle(&self) -> Result<()> {
+        if self.config.orphan_gc_interval_secs == 0 {
+            return Ok(());
+        }
+        let interval = Duration::from_secs(self.config.orphan_gc_interval_secs);
+        {
+            let last = self.last_orphan_gc.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+
+        let report = self
+            .engine
+            .run_orphan_gc(crate::engine::OrphanGcOptions {
+                dry_run: self.config.orphan_gc_dry_run,
+                include_edges: self.config.orphan_gc_edges,
+            })
+            .await?;
+        crate::run_report::record_items(report.removed);
+        let found = report.dangling_keys.values().sum::<usize>()
+            + report.expired_dedup_fingerprints
+            + report.orphaned_edges;
+        if found > 0 {
+            tracing::info!(
+                "Orphan GC{} found {} stale references, removed {}: {:?}",
+                if report.dry_run { " (dry run)" } else { "" },
+                found,
+                report.removed,
+                report
+            );
+        }
+
+        *self.last_orphan_gc.lock().await = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Replay LLM-written questions about recent memories through retrieval and record
+    /// how often each memory comes back.
+    async fn run_self_eval_cycle(&self) -> Result<()> {
+        if self.config.self_eval_interval_secs == 0 {
+            return Ok(());
+        }
+        let Some(client) = self.llm_client.as_ref() else {
+            return Ok(());
+        };
+        let interval = Duration::from_secs(self.config.self_eval_interval_secs);
+        {
+            let last = self.last_self_eval.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+        // Wait a full interval before retrying a failed run.
+        *self.last_self_eval.lock().await = std::time::Instant::now();
+
+        let Some(report) = crate::self_eval::run_self_eval(
+            &self.engine,
+            client.as_ref(),
+            crate::self_eval::SelfEvalOptions {
+                sample_size: self.config.self_eval_sample_size,
+                top_k: self.config.self_eval_top_k,
+            },
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+        crate::run_report::record_items(report.questions);
+        tracing::info!(
+            "Retrieval self-evaluation: {}/{} questions hit the top {} (MRR {:.3})",
+            report.hits,
+            report.questions,
+            report.top_k,
+            report.mean_reciprocal_rank
+        );
+        self.engine.record_self_eval_report(&report)
+    }
+
+    /// Account the bytes each user and app holds on this shard, for the storage
+    /// usage dashboard.
+    async fn run_storage_usage_cycle(&self) -> Result<()> {
+        if self.config.storage_usage_interval_secs == 0 {
+            return Ok(());
+        }
+        let interval = Duration::from_secs(self.config.storage_usage_interval_secs);
+        {
+            let last = self.last_storage_usage.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+        *self.last_storage_usage.lock().await = std::time::Instant::now();
+
+        let report = self.engine.compute_storage_usage().await?;
+        crate::run_report::record_items(report.users.len());
+        tracing::info!(
+            "Storage usage: {} users, {} apps",
+            report.users.len(),
+            report.apps.len()
+        );
+        self.engine.record_storage_usage(&report)
+    }
+
+    /// Export units and edges that appeared since the last run to `worker.export_url`.
+    async fn run_export_cycle(&self) -> Result<()> {
+        let Some(url) = self
+            .config
+            .export_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+        else {
+            return Ok(());
+        };
+        let interval = Duration::from_secs(self.config.export_interval_secs.max(1));
+        {
+            let last = self.last_export.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+        // Wait a full interval before retrying a failed export.
+        *self.last_export.lock().await = std::time::Instant::now();
+
+        let report = self
+            .engine
+            .export_to_warehouse(url, self.config.export_max_rows_per_file)
+            .await?;
+        crate::run_report::record_items(report.units + report.edges);
+        if report.files > 0 {
+            tracing::info!(
+                "Exported {} memory units and {} edges in {} Parquet files to {}",
+                report.units,
+                report.edges,
+                report.files,
+                url
+            );
+        }
+        Ok(())
+    }
+
+    /// Enforce organization retention windows and purge expired trash. Runs on the
+    /// decay interval but independently of `forgetting_enabled`, since both are
+    /// explicit policies.
+    async fn run_retention_cycle(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.config.decay_interval_secs.max(1));
+        {
+            let last = self.last_retention.lock().await;
+            if last.elapsed() <= interval {
+                return Ok(());
+            }
+        }
+
+        for (org_id, policy) in self.engine.list_org_policies()? {
+            if policy.retention_days.is_none() {
+                continue;
+            }
+            let removed = self.engine.apply_org_retention(&org_id).await?;
+            crate::run_report::record_items(removed);
+            if removed > 0 {
+                tracing::info!(
+                    "Removed {} memories past the retention window of org {}",
+                    removed,
+                    org_id
+                );
+            }
+        }
+
+        let now = chrono::Utc::now();
+        let purged = self.engine.list_expired_trash(now)?.len();
+        if purged > 0
+            && !self
+                .replicate(crate::raft::types::ClientRequest::PurgeExpiredTrash { now })
+                .await?
+        {
+            return Err(anyhow::anyhow!("Failed to purge expired trash"));
+        }
+        crate::run_report::record_items(purged);
+        if purged > 0 {
+            tracing::info!("Purged {} memories from the trash", purged);
+        }
+
+        let mut last = self.last_retention.lock().await;
+        *last = std::time::Instant::now();
+        Ok(())
+    }
+
+    fn parse_metadata_embedding(metadata: &serde_json::Value) -> Option<Option<Vec<f32>>> {
+        metadata
+            .get("embedding")
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_f64().map(|f| f as f32))
+                    .collect::<Option<Vec<f32>>>()
+                    .filter(|vec| !vec.is_empty())
+            })
+    }
+
+    fn pending_input_from_embed_input(
+        input: EmbedInput,
+    ) -> crate::engine::PendingMaterializationInput {
+        match input {
+            EmbedInput::Text(text) => crate::engine::PendingMaterializationInput::Text(text),
+            EmbedInput::Multimodal { parts } => {
+                crate::engine::PendingMaterializationInput::Multimodal {
+                    parts: parts
+                        .into_iter()
+                        .map(|part| match part {
+                            EmbedPart::Text(text) => {
+                                crate::engine::PendingMaterializationPart::Text { text }
+                            }
+                            EmbedPart::InlineData { mime_type, data } => {
+                                crate::engine::PendingMaterializationPart::InlineData {
+                                    mime_type,
+                                    data,
+                                }
+                            }
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    fn embed_input_from_pending_input(
+        input: crate::engine::PendingMaterializationInput,
+    ) -> EmbedInput {
         match input {
             crate::engine::PendingMaterializationInput::Text(text) => EmbedInput::Text(text),
             crate::engine::PendingMaterializationInput::Multimodal { parts } => {
@@ -793,6 +1654,7 @@ impl BackgroundWorker {
             }
         }
 
+        self.score_initial_importance(&mut job.unit).await;
         job.unit.visible = true;
         job.unit.materialization_state = memorose_common::MaterializationState::Published;
         job.unit.materialized_at = Some(chrono::Utc::now());
@@ -800,6 +1662,13 @@ impl BackgroundWorker {
         self.engine
             .publish_materialized_memory_unit(&job.unit)
             .await?;
+        if let Err(error) = self.index_asset_vectors(&job.unit).await {
+            tracing::warn!(
+                "Failed to embed assets of unit {}: {:?}",
+                job.unit.id,
+                error
+            );
+        }
 
         self.run_post_publish_hooks_once(&job.unit, &job.post_publish_edges)
             .await?;
@@ -807,6 +1676,81 @@ impl BackgroundWorker {
         Ok(true)
     }
 
+    /// Replace the default importance of a new L1 memory with a score from its content
+    /// and from how close it is to the user's existing memories.
+    async fn score_initial_importance(&self, unit: &mut MemoryUnit) {
+        if !self.config.importance_scoring_enabled || unit.level != 1 {
+            return;
+        }
+        let mut best_score = None;
+        if let Some(embedding) = unit.embedding.as_ref() {
+            let filter = self.engine.build_user_filter(
+                &unit.user_id,
+                Some("(domain = 'agent' OR domain = 'user')".to_string()),
+            );
+            match self
+                .engine
+                .search_similar(&unit.user_id, embedding, 2, filter)
+                .await
+            {
+                Ok(similar) => {
+                    best_score = similar
+                        .into_iter()
+                        .filter(|(peer, _)| peer.id != unit.id)
+                        .map(|(_, score)| score)
+                        .reduce(f32::max);
+                }
+                Err(error) => {
+                    tracing::warn!("Novelty lookup for unit {} failed: {:?}", unit.id, error);
+                }
+            }
+        }
+        let signals = crate::importance::ImportanceSignals::from_content(
+            &unit.content,
+            crate::importance::novelty_from_similarity(best_score),
+        );
+        unit.importance = signals.score(self.config.importance_floor);
+    }
+
+    /// Embed a unit's locally stored images into the joint text+image space, so text
+    /// queries in multimodal search mode match them without a caption round trip.
+    async fn index_asset_vectors(&self, unit: &MemoryUnit) -> Result<()> {
+        if !self.config.asset_embeddings_enabled {
+            return Ok(());
+        }
+        let Some(client) = &self.llm_client else {
+            return Ok(());
+        };
+
+        let asset_dir = self.engine.asset_dir();
+        let mut inputs = Vec::new();
+        for asset in &unit.assets {
+            let Some(path) =
+                crate::ingest::image::resolve_local_asset(&asset_dir, &asset.storage_key)
+            else {
+                continue;
+            };
+            let Ok(format) = image::ImageFormat::from_path(&path) else {
+                continue;
+            };
+            let Ok(bytes) = tokio::fs::read(&path).await else {
+                continue;
+            };
+            inputs.push(EmbedInput::Multimodal {
+                parts: vec![EmbedPart::InlineData {
+                    mime_type: format.to_mime_type().to_string(),
+                    data: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes),
+                }],
+            });
+        }
+        if inputs.is_empty() {
+            return Ok(());
+        }
+
+        let vectors = client.embed_content_batch(inputs).await?.data;
+        self.engine.store_asset_vectors(unit, vectors).await
+    }
+
     async fn run_post_publish_hooks_once(
         &self,
         unit: &MemoryUnit,
@@ -835,7 +1779,7 @@ impl BackgroundWorker {
         }
 
         for edge in staged_edges {
-            self.engine.graph().add_edge(edge).await?;
+            self.engine.add_graph_edge(edge).await?;
         }
 
         let mut l1_increase_by_user: HashMap<String, usize> = HashMap::new();
@@ -845,6 +1789,18 @@ impl BackgroundWorker {
             }
         }
 
+        let profile_users: HashSet<&str> = units
+            .iter()
+            .filter(|unit| {
+                (unit.level == 1 || unit.level == 2)
+                    && MemoroseEngine::is_local_domain(&unit.domain)
+            })
+            .map(|unit| unit.user_id.as_str())
+            .collect();
+        for user_id in profile_users {
+            let _ = self.enqueue_job(BackgroundJobKind::Profile, user_id).await;
+        }
+
         let community_step = self.config.community_trigger_l1_step.max(1);
         for (user_id, delta) in l1_increase_by_user {
             if let Ok((before, after)) = self
@@ -853,7 +1809,9 @@ impl BackgroundWorker {
                 .await
             {
                 if before / community_step < after / community_step && after >= community_step {
-                    let _ = self.engine.set_needs_community(&user_id);
+                    let _ = self
+                        .enqueue_job(BackgroundJobKind::Community, &user_id)
+                        .await;
                 }
             }
         }
@@ -886,12 +1844,22 @@ impl BackgroundWorker {
 
     async fn run_materialization_cycle(&self) -> Result<bool> {
         let limit = self.config.consolidation_store_batch_size.max(1);
-        let mut jobs = self.engine.fetch_due_materialization_jobs(limit)?;
+        let jobs = self.engine.fetch_due_materialization_jobs(limit)?;
         if jobs.is_empty() {
             return Ok(false);
         }
+        let published = self.materialize_jobs(jobs).await?;
+        crate::run_report::record_items(published.len());
+        Ok(!published.is_empty())
+    }
 
-        let mut any_published = false;
+    /// Embed and publish queued materialization jobs. Failed jobs are rescheduled or
+    /// failed in the queue; returns the ids of the units that were published.
+    async fn materialize_jobs(
+        &self,
+        mut jobs: Vec<crate::engine::PendingMaterializationJob>,
+    ) -> Result<Vec<uuid::Uuid>> {
+        let mut published_ids = Vec::new();
         let mut ready_jobs = Vec::new();
         let mut jobs_needing_embedding = Vec::new();
 
@@ -918,7 +1886,11 @@ impl BackgroundWorker {
 
         for mut job in ready_jobs {
             match self.publish_materialization_job(job.clone()).await {
-                Ok(published) => any_published |= published,
+                Ok(published) => {
+                    if published {
+                        published_ids.push(job.unit.id);
+                    }
+                }
                 Err(error) => {
                     let error_message = format!("Materialization publish failed: {:?}", error);
                     if job.attempts >= self.config.consolidation_max_retries {
@@ -933,7 +1905,7 @@ impl BackgroundWorker {
         }
 
         if jobs_needing_embedding.is_empty() {
-            return Ok(any_published);
+            return Ok(published_ids);
         }
 
         let Some(client) = self.llm_client.as_ref() else {
@@ -947,7 +1919,7 @@ impl BackgroundWorker {
                         .reschedule_materialization_job(&mut job, error)?;
                 }
             }
-            return Ok(any_published);
+            return Ok(published_ids);
         };
 
         let inputs_to_embed = jobs_needing_embedding
@@ -979,7 +1951,11 @@ impl BackgroundWorker {
 
                     job.unit.embedding = Some(embedding);
                     match self.publish_materialization_job(job.clone()).await {
-                        Ok(published) => any_published |= published,
+                        Ok(published) => {
+                            if published {
+                                published_ids.push(job.unit.id);
+                            }
+                        }
                         Err(error) => {
                             let error_message = format!(
                                 "Materialization publish after embedding failed: {:?}",
@@ -1027,7 +2003,7 @@ impl BackgroundWorker {
             }
         }
 
-        Ok(any_published)
+        Ok(published_ids)
     }
 
     /// Generates a semantic fingerprint by stripping numbers, punctuation, and converting to lowercase.
@@ -1048,71 +2024,79 @@ impl BackgroundWorker {
         hasher.finish()
     }
 
+    /// Turn an event into searchable text, an embedding input and its assets. Images are
+    /// captioned and OCR'd, and written under `asset_dir` when one is given.
     async fn extract_text_and_embed_input(
         event: &memorose_common::Event,
         llm: Option<&dyn crate::llm::LLMClient>,
+        asset_dir: Option<&std::path::Path>,
     ) -> (String, EmbedInput, Vec<Asset>) {
         match &event.content {
             memorose_common::EventContent::Text(t) => {
                 (t.clone(), EmbedInput::Text(t.clone()), vec![])
             }
             memorose_common::EventContent::Image(url) => {
-                // For native multimodal embedding, try to fetch bytes for inline embedding
-                let text_description = if let Some(client) = llm {
-                    client
-                        .describe_image(url)
-                        .await
-                        .map(|r| r.data)
-                        .unwrap_or_else(|_| format!("Image at {}", url))
-                } else {
-                    format!("Image at {}", url)
-                };
+                let analysis = crate::ingest::image::analyze_image(llm, url).await;
+                let text_description = analysis.searchable_text(url);
+                let loaded = crate::ingest::image::load_image(url).await;
 
-                let embed_input = if url.starts_with("http") {
-                    // URL-based: fetch the bytes for inline embedding
-                    match reqwest::get(url).await {
-                        Ok(resp) => {
-                            let mime = resp
-                                .headers()
-                                .get("content-type")
-                                .and_then(|v| v.to_str().ok())
-                                .unwrap_or("image/jpeg")
-                                .to_string();
-                            match resp.bytes().await {
-                                Ok(bytes) => EmbedInput::Multimodal {
-                                    parts: vec![EmbedPart::InlineData {
-                                        mime_type: mime,
-                                        data: base64::Engine::encode(
-                                            &base64::engine::general_purpose::STANDARD,
-                                            &bytes,
-                                        ),
-                                    }],
-                                },
-                                Err(_) => EmbedInput::Text(text_description.clone()),
-                            }
-                        }
-                        Err(_) => EmbedInput::Text(text_description.clone()),
-                    }
-                } else {
-                    // Already base64
-                    EmbedInput::Multimodal {
+                // Prefer native multimodal embedding of the image bytes
+                let embed_input = match &loaded {
+                    Ok(image) => EmbedInput::Multimodal {
+                        parts: vec![EmbedPart::InlineData {
+                            mime_type: image.mime_type.clone(),
+                            data: base64::Engine::encode(
+                                &base64::engine::general_purpose::STANDARD,
+                                &image.bytes,
+                            ),
+                        }],
+                    },
+                    Err(_) if !url.starts_with("http") => EmbedInput::Multimodal {
                         parts: vec![EmbedPart::InlineData {
                             mime_type: "image/jpeg".to_string(),
                             data: url.clone(),
                         }],
-                    }
+                    },
+                    Err(_) => EmbedInput::Text(text_description.clone()),
                 };
 
-                (
-                    text_description.clone(),
-                    embed_input,
-                    vec![Self::build_asset(
-                        url.clone(),
-                        "image",
-                        "image",
-                        Some(text_description),
-                    )],
-                )
+                let mut asset = Self::build_asset(
+                    url.clone(),
+                    "image",
+                    "image",
+                    Some(analysis.caption.clone().unwrap_or(text_description.clone())),
+                );
+                if let Some(ocr_text) = &analysis.ocr_text {
+                    asset.metadata.insert(
+                        crate::ingest::image::OCR_TEXT_METADATA_KEY.to_string(),
+                        ocr_text.clone(),
+                    );
+                }
+                if let (Some(asset_dir), Ok(image)) = (asset_dir, &loaded) {
+                    match crate::ingest::image::store_image(asset_dir, image) {
+                        Ok(stored) => {
+                            if url.starts_with("http") {
+                                asset.metadata.insert(
+                                    crate::ingest::image::SOURCE_URL_METADATA_KEY.to_string(),
+                                    url.clone(),
+                                );
+                            }
+                            if let Some(thumbnail_key) = stored.thumbnail_key {
+                                asset.metadata.insert(
+                                    crate::ingest::image::THUMBNAIL_KEY_METADATA_KEY.to_string(),
+                                    thumbnail_key,
+                                );
+                            }
+                            asset.storage_key = stored.storage_key;
+                            asset.asset_type = image.mime_type.clone();
+                        }
+                        Err(error) => {
+                            tracing::warn!("Failed to store image asset: {:?}", error);
+                        }
+                    }
+                }
+
+                (text_description, embed_input, vec![asset])
             }
             memorose_common::EventContent::Audio(url) => {
                 let text_description = if let Some(client) = llm {
@@ -1248,10 +2232,17 @@ impl BackgroundWorker {
         let batch_size = self.config.consolidation_batch_size.max(1);
         let fetch_limit =
             batch_size.saturating_mul(self.config.consolidation_fetch_multiplier.max(1));
-        let events = self
+        let mut events = self
             .engine
             .fetch_pending_events_limited(fetch_limit)
             .await?;
+        {
+            let in_flight = self
+                .sync_consolidating
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            events.retain(|event| !in_flight.contains(&event.id));
+        }
         if events.is_empty() {
             return Ok(false);
         }
@@ -1294,18 +2285,460 @@ impl BackgroundWorker {
             }
         }
 
+        if self.consolidation_pooled {
+            let leased = self.engine.leased_event_ids(chrono::Utc::now())?;
+            valid_events.retain(|event| !leased.contains(&event.id) && !self.is_pool_event(event));
+        }
         if valid_events.is_empty() {
             return Ok(false);
         }
+        crate::run_report::record_items(valid_events.len());
+
+        // Recordings skip prompt packing: audio is transcribed chunk by chunk into its own
+        // chain of memory units, video runs through the segment pipeline.
+        let mut any_media = false;
+        if self.llm_client.is_some() || self.config.audio_transcribe_command.is_some() {
+            let (media_events, other_events): (Vec<_>, Vec<_>) =
+                valid_events.into_iter().partition(|event| {
+                    matches!(
+                        event.content,
+                        EventContent::Audio(_) | EventContent::Video(_)
+                    )
+                });
+            valid_events = other_events;
+            for event in media_events {
+                let result = match event.content {
+                    EventContent::Video(_) => self.consolidate_video_event(&event).await,
+                    _ => self.consolidate_audio_event(&event).await,
+                };
+                match result {
+                    Ok(()) => any_media = true,
+                    Err(error) => {
+                        tracing::error!(
+                            "Media consolidation failed for event {}: {:?}",
+                            event.id,
+                            error
+                        );
+                        let _ = self
+                            .engine
+                            .increment_retry_count_if_pending(&event.id.to_string())
+                            .await;
+                    }
+                }
+            }
+            if valid_events.is_empty() {
+                *self.last_consolidation.lock().await = std::time::Instant::now();
+                return Ok(any_media);
+            }
+        }
 
-        valid_events.sort_by_key(|event| (Self::packed_event_key(event), event.transaction_time));
-
-        // 1.5 Batching / Prompt Packing with overfetch + fair selection
-        let pending_valid_count = valid_events.len();
-        let packed_batches = self.pack_events_for_consolidation(valid_events);
+        // Pre-summarized events skip prompt packing and compression; they are only embedded.
+        let (uncompressed_events, packable_events): (Vec<_>, Vec<_>) = valid_events
+            .into_iter()
+            .partition(|event| self.skips_compression(event));
+        valid_events = packable_events;
+        let any_uncompressed = self.store_uncompressed_events(uncompressed_events).await;
+        let any_media = any_media || any_uncompressed;
+        if valid_events.is_empty() {
+            *self.last_consolidation.lock().await = std::time::Instant::now();
+            return Ok(any_media);
+        }
+
+        // 1.5 Per-user queues: round-robin across users so one chatty user cannot crowd
+        // out the rest of the cycle, then run a bounded pool of user pipelines.
+        let pending_valid_count = valid_events.len();
+        let start_after = self.consolidation_user_cursor.lock().await.clone();
+        let user_batches = Self::select_user_batches_fairly(
+            valid_events,
+            batch_size,
+            self.config.consolidation_max_events_per_user,
+            start_after.as_deref(),
+        );
+        if let Some((first_user, _)) = user_batches.first() {
+            *self.consolidation_user_cursor.lock().await = Some(first_user.clone());
+        }
+        let selected_event_count = user_batches
+            .iter()
+            .map(|(_, events)| events.len())
+            .sum::<usize>();
+        let user_workers = self.config.consolidation_user_workers.max(1);
+
+        tracing::info!(
+            "Consolidation cycle: {} users, selected_events={}, deferred_events={}, user_workers={}, llm_concurrency={}, fetch_limit={}",
+            user_batches.len(),
+            selected_event_count,
+            pending_valid_count.saturating_sub(selected_event_count),
+            user_workers,
+            self.config.llm_concurrency,
+            fetch_limit
+        );
+
+        let llm_permits = Arc::new(tokio::sync::Semaphore::new(
+            self.config.llm_concurrency.max(1),
+        ));
+        let mut join_set = tokio::task::JoinSet::new();
+        let mut any_processed = false;
+        for (user_id, events) in user_batches {
+            if join_set.len() >= user_workers {
+                if let Some(res) = join_set.join_next().await {
+                    match res {
+                        Ok(processed) => any_processed |= processed,
+                        Err(e) => tracing::error!("User consolidation task panicked: {:?}", e),
+                    }
+                }
+            }
+            let worker = self.clone();
+            let llm_permits = llm_permits.clone();
+            join_set.spawn(async move {
+                worker
+                    .consolidate_user_events(user_id, events, llm_permits)
+                    .await
+            });
+        }
+        while let Some(res) = join_set.join_next().await {
+            match res {
+                Ok(processed) => any_processed |= processed,
+                Err(e) => tracing::error!("User consolidation task panicked: {:?}", e),
+            }
+        }
+
+        *self.last_consolidation.lock().await = std::time::Instant::now();
+        Ok(any_processed || any_media)
+    }
+
+    fn turn_participant(event: &Event) -> crate::llm::ConversationParticipant {
+        crate::llm::ConversationParticipant {
+            name: event.speaker_name().map(str::to_string),
+            role: event.role(),
+        }
+    }
+
+    /// Speakers in a packed group, in order of first appearance, whether it is an agent
+    /// trajectory, and the app's prompt template. The group key keeps agent and user turns
+    /// apart, so the first event decides the rest.
+    fn compression_context(
+        events: &[Event],
+        prompts: &memorose_common::config::PromptsConfig,
+    ) -> CompressionContext {
+        let first = events.first();
+        let mut context =
+            CompressionContext::agent(first.is_some_and(|event| event.is_agent_event()));
+        context.app_id = first.and_then(|event| event.app_id()).map(str::to_string);
+        let kind = if context.is_agent {
+            PromptKind::AgentCompression
+        } else {
+            PromptKind::Compression
+        };
+        context.template = prompts
+            .template_for(kind, context.app_id.as_deref())
+            .map(str::to_string);
+        for event in events {
+            let participant = Self::turn_participant(event);
+            if participant.label().is_some() && !context.participants.contains(&participant) {
+                context.participants.push(participant);
+            }
+        }
+        context
+    }
+
+    fn compress_options(&self) -> CompressOptions {
+        // The local tier of two-tier consolidation, when it is switched on
+        let local = self
+            .config
+            .local_summarization_enabled
+            .then(|| LocalSummarizer {
+                client: self.local_llm_client.clone(),
+                options: TierOptions {
+                    max_chars: self.config.local_summarization_max_chars,
+                    max_entropy: self.config.local_summarization_max_entropy,
+                },
+            });
+        CompressOptions {
+            local,
+            max_tokens: self.config.consolidation_target_tokens.max(1),
+            overlap_tokens: self.config.consolidation_chunk_overlap_tokens,
+        }
+    }
+
+    /// Compress `text` in one call, or chunk by chunk when it is over the per-call token
+    /// budget, joining the chunk summaries in order.
+    async fn compress_in_chunks(
+        client: &dyn LLMClient,
+        text: &str,
+        context: &CompressionContext,
+        options: &CompressOptions,
+    ) -> Result<crate::llm::CompressionOutput> {
+        let chunks = memorose_common::tokenizer::split_by_tokens(
+            text,
+            options.max_tokens,
+            options.overlap_tokens,
+        );
+        if chunks.len() <= 1 {
+            return Ok(client.compress(text, context).await?.data);
+        }
+
+        let mut summaries = Vec::with_capacity(chunks.len());
+        let mut valid_at = None;
+        for chunk in chunks {
+            let out = client.compress(chunk, context).await?.data;
+            valid_at = valid_at.or(out.valid_at);
+            summaries.push(out.content);
+        }
+        Ok(crate::llm::CompressionOutput {
+            content: summaries.join("\n"),
+            valid_at,
+        })
+    }
+
+    /// Compress one packed group of events into the summary its memory unit is built from.
+    async fn compress_packed_group(
+        llm: Option<&dyn LLMClient>,
+        options: &CompressOptions,
+        engine: &MemoroseEngine,
+        prompts: &memorose_common::config::PromptsConfig,
+        key: PackedGroupKey,
+        seq_no: u64,
+        mut events: Vec<Event>,
+    ) -> ProducedBatch {
+        let asset_dir = engine.asset_dir();
+        // Clients that number their turns know the conversation order better than arrival time
+        if events.iter().all(|event| event.turn_index().is_some()) {
+            events.sort_by_key(|event| event.turn_index());
+        }
+        let context = Self::compression_context(&events, prompts);
+        let speaker = |event: &Event| {
+            if context.is_multi_party() || event.speaker_name().is_some() {
+                Self::turn_participant(event).label()
+            } else {
+                None
+            }
+        };
+        let label = |event: &Event| {
+            speaker(event)
+                .map(|label| format!(" ({})", label))
+                .unwrap_or_default()
+        };
+        let has_media = events
+            .iter()
+            .any(|event| !matches!(event.content, EventContent::Text(_) | EventContent::Json(_)));
+        let mut events_iter = events.into_iter();
+        let first_event = events_iter
+            .next()
+            .expect("packed group must contain at least one event");
+        let (first_text, first_embed_input, mut assets) =
+            Self::extract_text_and_embed_input(&first_event, llm, Some(&asset_dir)).await;
+        let mut combined_text = format!("Message 1{}: {}", label(&first_event), first_text);
+        let mut passages = vec![Passage {
+            speaker: speaker(&first_event),
+            text: first_text,
+        }];
+        let embed_input = if first_embed_input.has_multimodal_parts() {
+            Some(first_embed_input)
+        } else {
+            None
+        };
+
+        let metadata = first_event.metadata.clone();
+        let user_id = first_event.user_id.clone();
+        let stream_id = first_event.stream_id;
+        let mut event_ids = vec![first_event.id];
+
+        for (index, evt) in events_iter.enumerate() {
+            let (evt_text, _evt_embed_input, evt_assets) =
+                Self::extract_text_and_embed_input(&evt, llm, Some(&asset_dir)).await;
+            combined_text.push_str(&format!(
+                "\nMessage {}{}: {}",
+                index + 2,
+                label(&evt),
+                evt_text
+            ));
+            passages.push(Passage {
+                speaker: speaker(&evt),
+                text: evt_text,
+            });
+            event_ids.push(evt.id);
+            assets.extend(evt_assets);
+        }
+
+        // Semantic Deduplication Check
+        let fingerprint = Self::generate_semantic_fingerprint(&combined_text);
+        let dedup_key = format!("dedup:{}:{}", user_id, fingerprint);
+
+        let is_duplicate =
+            if let Ok(Some(last_seen_bytes)) = engine.system_kv().get(dedup_key.as_bytes()) {
+                if let Some(last_seen) = String::from_utf8(last_seen_bytes)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                {
+                    let now = chrono::Utc::now().timestamp();
+                    // Deduplicate if seen within the dedup window (1 hour).
+                    // Use saturating_sub so clock-skew or a future stored timestamp
+                    // never causes underflow (which would bypass deduplication).
+                    now.saturating_sub(last_seen) < crate::engine::SEMANTIC_DEDUP_WINDOW_SECS
+                } else {
+                    false
+                }
+            } else {
+                false
+            };
+
+        let (summary, valid_at) = if is_duplicate {
+            tracing::debug!(
+                "Semantic deduplication triggered for fingerprint {}. Skipping LLM compression.",
+                fingerprint
+            );
+            // Update timestamp for LRU-like rolling window
+            let _ = engine.system_kv().put(
+                dedup_key.as_bytes(),
+                chrono::Utc::now().timestamp().to_string().as_bytes(),
+            );
+            (combined_text, None)
+        } else {
+            // Routine batches stay on the local tier when two-tier consolidation is on
+            let local_output = match &options.local {
+                Some(local) => local
+                    .summarize(
+                        &combined_text,
+                        &passages,
+                        has_media || !assets.is_empty(),
+                        &context,
+                    )
+                    .await
+                    .ok(),
+                None => None,
+            };
+
+            // Compression
+            let (compressed, valid) = match (local_output, llm) {
+                (Some(out), _) => (out.content, out.valid_at),
+                (None, Some(client)) => {
+                    match Self::compress_in_chunks(client, &combined_text, &context, options).await
+                    {
+                        Ok(out) => (out.content, out.valid_at),
+                        Err(e) => {
+                            tracing::warn!(
+                                "Packed compression failed for {}: {:?}",
+                                event_ids[0],
+                                e
+                            );
+                            (combined_text, None)
+                        }
+                    }
+                }
+                (None, None) => (combined_text, None),
+            };
+
+            // Save fingerprint
+            let _ = engine.system_kv().put(
+                dedup_key.as_bytes(),
+                chrono::Utc::now().timestamp().to_string().as_bytes(),
+            );
+
+            (compressed, valid)
+        };
+
+        ProducedBatch {
+            key,
+            seq_no,
+            event_ids,
+            user_id,
+            stream_id,
+            summary,
+            valid_at,
+            assets,
+            metadata,
+            embed_input,
+        }
+    }
+
+    /// Whether an event is stored as written instead of compressed. A client-supplied
+    /// embedding describes the event's own text, so it always does; otherwise the
+    /// event's `skip_compression` flag wins, then its app's `skip_compression_apps` entry.
+    fn skips_compression(&self, event: &Event) -> bool {
+        if let Some(Some(_)) = Self::parse_metadata_embedding(&event.metadata) {
+            return true;
+        }
+        if let Some(skip) = event
+            .metadata
+            .get("skip_compression")
+            .and_then(|v| v.as_bool())
+        {
+            return skip;
+        }
+        let app = event
+            .agent_id
+            .as_deref()
+            .or_else(|| event.metadata.get("agent_id").and_then(|v| v.as_str()));
+        app.is_some_and(|app| {
+            self.config
+                .skip_compression_apps
+                .iter()
+                .any(|skip_app| skip_app == app)
+        })
+    }
+
+    /// A pipeline item holding one event's content as written.
+    async fn uncompressed_pipeline_item(&self, event: &Event) -> PipelineItem {
+        let asset_dir = self.engine.asset_dir();
+        let (text, embed_input, assets) =
+            Self::extract_text_and_embed_input(event, self.llm_client.as_deref(), Some(&asset_dir))
+                .await;
+        let embed_input = embed_input.has_multimodal_parts().then_some(embed_input);
+        (
+            vec![event.id],
+            event.user_id.clone(),
+            event.stream_id,
+            text,
+            None,
+            assets,
+            event.metadata.clone(),
+            embed_input,
+        )
+    }
+
+    /// Turn each event into its own memory unit without LLM compression, in store-sized
+    /// batches. Returns whether anything was stored; failures get their retry count bumped.
+    async fn store_uncompressed_events(&self, events: Vec<Event>) -> bool {
+        let mut any_processed = false;
+        let batch_size = self.config.consolidation_store_batch_size.max(1);
+        for chunk in events.chunks(batch_size) {
+            let mut batch = Vec::with_capacity(chunk.len());
+            for event in chunk {
+                batch.push(self.uncompressed_pipeline_item(event).await);
+            }
+            match self.process_pipeline_batch(batch).await {
+                Ok(ids) => any_processed |= !ids.is_empty(),
+                Err(error) => {
+                    tracing::error!("Uncompressed event batch failed: {:?}", error);
+                    for event in chunk {
+                        let _ = self
+                            .engine
+                            .increment_retry_count_if_pending(&event.id.to_string())
+                            .await;
+                    }
+                }
+            }
+        }
+        any_processed
+    }
+
+    /// Pack, compress, embed and store one user's slice of a consolidation cycle.
+    /// Returns whether anything was stored; unprocessed events get their retry count bumped.
+    async fn consolidate_user_events(
+        &self,
+        user_id: String,
+        mut events: Vec<Event>,
+        llm_permits: Arc<tokio::sync::Semaphore>,
+    ) -> bool {
+        let event_budget = events.len();
+        events.sort_by_key(|event| (Self::packed_event_key(event), event.transaction_time));
+
+        // Prompt packing, interleaved across this user's streams and agents
+        let segments = self.detect_stream_segments(&events);
+        let packed_batches = self.pack_events_for_consolidation(events, &segments);
         let scheduled_batches = self.schedule_packed_groups_fairly(packed_batches);
         let scheduled_batches =
-            self.limit_scheduled_groups_by_event_budget(scheduled_batches, batch_size);
+            self.limit_scheduled_groups_by_event_budget(scheduled_batches, event_budget);
         let distinct_keys = scheduled_batches
             .iter()
             .map(|group| group.key.clone())
@@ -1321,17 +2754,16 @@ impl BackgroundWorker {
             .collect();
 
         if scheduled_batches.is_empty() {
-            return Ok(false);
+            return false;
         }
 
         tracing::info!(
-            "Consolidating {} packed event groups via pipeline (selected_events={}, deferred_events={}, keys={}, concurrency={}, fetch_limit={}, target_tokens={}, max_events_per_pack={}, store_batch_size={})...",
+            "Consolidating {} packed event groups for user {} via pipeline (selected_events={}, keys={}, concurrency={}, target_tokens={}, max_events_per_pack={}, store_batch_size={})...",
             scheduled_batches.len(),
+            user_id,
             selected_event_count,
-            pending_valid_count.saturating_sub(selected_event_count),
             distinct_keys,
             self.config.llm_concurrency,
-            fetch_limit,
             self.config.consolidation_target_tokens,
             self.config.consolidation_max_events_per_pack,
             self.config.consolidation_store_batch_size
@@ -1342,6 +2774,8 @@ impl BackgroundWorker {
         let llm_client_clone = self.llm_client.clone();
         let concurrency_limit = self.config.llm_concurrency;
         let engine_clone = self.engine.clone();
+        let prompts_clone = self.prompts.clone();
+        let compress_options = self.compress_options();
 
         // Spawn Producer — keep the handle so we can detect panics after the consumer drains.
         let producer_handle = tokio::spawn(async move {
@@ -1359,6 +2793,9 @@ impl BackgroundWorker {
                 }
                 let llm = llm_client_clone.clone();
                 let engine = engine_clone.clone();
+                let prompts = prompts_clone.clone();
+                let options = compress_options.clone();
+                let llm_permits = llm_permits.clone();
 
                 // Limit concurrency
                 if join_set.len() >= concurrency_limit {
@@ -1375,89 +2812,19 @@ impl BackgroundWorker {
                 }
 
                 join_set.spawn(async move {
-                    let mut events_iter = events.into_iter();
-                    let first_event = events_iter
-                        .next()
-                        .expect("packed group must contain at least one event");
-                    let (first_text, first_embed_input, mut assets) =
-                        Self::extract_text_and_embed_input(&first_event, llm.as_deref()).await;
-                    let mut combined_text = format!("Message 1: {}", first_text);
-                    let embed_input = if first_embed_input.has_multimodal_parts() {
-                        Some(first_embed_input)
-                    } else {
-                        None
-                    };
-
-                    let metadata = first_event.metadata.clone();
-                    let user_id = first_event.user_id.clone();
-                    let stream_id = first_event.stream_id;
-                    let is_agent =
-                        metadata.get("role").and_then(|v| v.as_str()) == Some("assistant")
-                            || metadata.get("agent_id").is_some();
-                    let mut event_ids = vec![first_event.id];
-
-                    for (index, evt) in events_iter.enumerate() {
-                        let (evt_text, _evt_embed_input, evt_assets) =
-                            Self::extract_text_and_embed_input(&evt, llm.as_deref()).await;
-                        combined_text.push_str(&format!("\nMessage {}: {}", index + 2, evt_text));
-                        event_ids.push(evt.id);
-                        assets.extend(evt_assets);
-                    }
-
-                    // Semantic Deduplication Check
-                    let fingerprint = Self::generate_semantic_fingerprint(&combined_text);
-                    let dedup_key = format!("dedup:{}:{}", user_id, fingerprint);
-
-                    let is_duplicate = if let Ok(Some(last_seen_bytes)) = engine.system_kv().get(dedup_key.as_bytes()) {
-                        if let Some(last_seen) = String::from_utf8(last_seen_bytes).ok().and_then(|s| s.parse::<i64>().ok()) {
-                            let now = chrono::Utc::now().timestamp();
-                            // Deduplicate if seen within the last 1 hour (3600 seconds).
-                            // Use saturating_sub so clock-skew or a future stored timestamp
-                            // never causes underflow (which would bypass deduplication).
-                            now.saturating_sub(last_seen) < 3600
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
-
-                    let (summary, valid_at) = if is_duplicate {
-                        tracing::debug!("Semantic deduplication triggered for fingerprint {}. Skipping LLM compression.", fingerprint);
-                        // Update timestamp for LRU-like rolling window
-                        let _ = engine.system_kv().put(dedup_key.as_bytes(), chrono::Utc::now().timestamp().to_string().as_bytes());
-                        (combined_text, None)
-                    } else {
-                        // Compression
-                        let (compressed, valid) = match llm.as_ref() {
-                            Some(client) => match client.compress(&combined_text, is_agent).await {
-                                Ok(out) => (out.data.content, out.data.valid_at),
-                                Err(e) => {
-                                    tracing::warn!("Packed compression failed for {}: {:?}", event_ids[0], e);
-                                    (combined_text, None)
-                                }
-                            },
-                            None => (combined_text, None),
-                        };
-
-                        // Save fingerprint
-                        let _ = engine.system_kv().put(dedup_key.as_bytes(), chrono::Utc::now().timestamp().to_string().as_bytes());
-
-                        (compressed, valid)
-                    };
-
-                    ProducedBatch {
+                    // Shared across every user pipeline in the cycle, so LLM load stays bounded
+                    // no matter how many users consolidate at once.
+                    let _permit = llm_permits.acquire_owned().await;
+                    Self::compress_packed_group(
+                        llm.as_deref(),
+                        &options,
+                        &engine,
+                        &prompts,
                         key,
                         seq_no,
-                        event_ids,
-                        user_id,
-                        stream_id,
-                        summary,
-                        valid_at,
-                        assets,
-                        metadata,
-                        embed_input,
-                    }
+                        events,
+                    )
+                    .await
                 });
             }
 
@@ -1544,7 +2911,7 @@ impl BackgroundWorker {
         // 4. Retry Logic: Check which IDs were NOT processed
         for id in all_fetched_ids {
             // If ID is not in processed_ids and not in failed_events (already handled), increment retry
-            // Note: failed_events logic handled above. We only care about valid_events that failed in pipeline.
+            // Note: failed_events logic handled above. We only care about events that failed in pipeline.
             // But 'id' here includes all initial fetch.
             // Simplified: Try to increment retry for anything that wasn't successfully marked processed.
             // Mark_event_processed deletes the pending key, so increment_retry_count_if_pending works safely.
@@ -1554,8 +2921,367 @@ impl BackgroundWorker {
             }
         }
 
-        *self.last_consolidation.lock().await = std::time::Instant::now();
-        Ok(any_processed)
+        any_processed
+    }
+
+    /// Whether a consolidation pool compresses this event: text and JSON that are not
+    /// stored as written. Media needs the leader's asset store, so it stays local.
+    fn is_pool_event(&self, event: &Event) -> bool {
+        matches!(event.content, EventContent::Text(_) | EventContent::Json(_))
+            && !self.skips_compression(event)
+    }
+
+    /// Lease the next batch of pool events to pool node `holder_node_id` for `lease_for`.
+    /// A batch holds one user's events, taking users in turn like the local cycle. Every
+    /// claim counts as a consolidation attempt, so events whose holders keep failing are
+    /// eventually marked failed by the local cycle.
+    pub async fn claim_consolidation_batch(
+        &self,
+        holder_node_id: u32,
+        lease_for: Duration,
+    ) -> Result<Option<ConsolidationClaim>> {
+        let batch_size = self.config.consolidation_batch_size.max(1);
+        let fetch_limit =
+            batch_size.saturating_mul(self.config.consolidation_fetch_multiplier.max(1));
+        let now = chrono::Utc::now();
+        let leased = self.engine.leased_event_ids(now)?;
+        let mut events = self
+            .engine
+            .fetch_pending_events_limited(fetch_limit)
+            .await?;
+        {
+            let in_flight = self
+                .sync_consolidating
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            events.retain(|event| {
+                !in_flight.contains(&event.id)
+                    && !leased.contains(&event.id)
+                    && self.is_pool_event(event)
+            });
+        }
+        let mut claimable = Vec::new();
+        for event in events {
+            let retry_count = self
+                .engine
+                .get_retry_count(&event.id.to_string())
+                .await
+                .unwrap_or(0);
+            if retry_count < self.config.consolidation_max_retries {
+                claimable.push(event);
+            }
+        }
+
+        let start_after = self.consolidation_user_cursor.lock().await.clone();
+        let Some((user_id, mut events)) = Self::select_user_batches_fairly(
+            claimable,
+            usize::MAX,
+            self.config.consolidation_max_events_per_user,
+            start_after.as_deref(),
+        )
+        .into_iter()
+        .next() else {
+            return Ok(None);
+        };
+        events.truncate(batch_size);
+        *self.consolidation_user_cursor.lock().await = Some(user_id.clone());
+
+        for event in &events {
+            self.engine
+                .increment_retry_count_if_pending(&event.id.to_string())
+                .await?;
+        }
+        let lease = ConsolidationLease {
+            lease_id: uuid::Uuid::new_v4(),
+            holder_node_id,
+            user_id,
+            event_ids: events.iter().map(|event| event.id).collect(),
+            expires_at: now + chrono::Duration::from_std(lease_for)?,
+        };
+        self.engine.put_consolidation_lease(&lease)?;
+        Ok(Some(ConsolidationClaim { lease, events }))
+    }
+
+    /// Compress a claimed batch on a pool node. Nothing is stored here; the groups come
+    /// back in commit order for the leader to store.
+    pub async fn compress_claimed_events(&self, mut events: Vec<Event>) -> Vec<ConsolidatedGroup> {
+        events.sort_by_key(|event| (Self::packed_event_key(event), event.transaction_time));
+        let segments = self.detect_stream_segments(&events);
+        let groups = self.pack_events_for_consolidation(events, &segments);
+        let options = self.compress_options();
+        futures::stream::iter(groups)
+            .map(|group| {
+                let options = &options;
+                async move {
+                    Self::compress_packed_group(
+                        self.llm_client.as_deref(),
+                        options,
+                        &self.engine,
+                        &self.prompts,
+                        group.key,
+                        group.seq_no,
+                        group.events,
+                    )
+                    .await
+                }
+            })
+            .buffered(self.config.llm_concurrency.max(1))
+            .map(|produced| ConsolidatedGroup {
+                event_ids: produced.event_ids,
+                user_id: produced.user_id,
+                stream_id: produced.stream_id,
+                summary: produced.summary,
+                valid_at: produced.valid_at,
+                assets: produced.assets,
+                metadata: produced.metadata,
+                embed_input: produced
+                    .embed_input
+                    .map(Self::pending_input_from_embed_input),
+            })
+            .collect()
+            .await
+    }
+
+    /// Store a batch compressed by pool node `holder_node_id`. Returns `None` when the
+    /// lease has expired or belongs to another node; groups naming events outside the
+    /// lease are dropped. Otherwise returns the events now processed.
+    pub async fn commit_consolidation(
+        &self,
+        lease_id: uuid::Uuid,
+        holder_node_id: u32,
+        groups: Vec<ConsolidatedGroup>,
+    ) -> Result<Option<Vec<String>>> {
+        let Some(lease) = self
+            .engine
+            .get_consolidation_lease(lease_id)?
+            .filter(|lease| {
+                lease.holder_node_id == holder_node_id && lease.expires_at > chrono::Utc::now()
+            })
+        else {
+            return Ok(None);
+        };
+        let leased: HashSet<uuid::Uuid> = lease.event_ids.iter().copied().collect();
+        let batch: Vec<PipelineItem> = groups
+            .into_iter()
+            .filter(|group| {
+                group.user_id == lease.user_id
+                    && !group.event_ids.is_empty()
+                    && group.event_ids.iter().all(|id| leased.contains(id))
+            })
+            .map(|group| {
+                (
+                    group.event_ids,
+                    group.user_id,
+                    group.stream_id,
+                    group.summary,
+                    group.valid_at,
+                    group.assets,
+                    group.metadata,
+                    group.embed_input.map(Self::embed_input_from_pending_input),
+                )
+            })
+            .collect();
+        let processed = if batch.is_empty() {
+            Vec::new()
+        } else {
+            self.process_pipeline_batch(batch).await?
+        };
+        self.engine.delete_consolidation_lease(lease_id)?;
+        Ok(Some(processed))
+    }
+
+    /// Run the video pipeline over a video event and store the result as one composite
+    /// memory carrying an asset per described segment.
+    async fn consolidate_video_event(&self, event: &Event) -> Result<()> {
+        let EventContent::Video(source) = &event.content else {
+            return Ok(());
+        };
+        let bytes = match crate::ingest::load_media_bytes(source).await {
+            Ok(bytes) => Some(bytes),
+            Err(error) => {
+                tracing::warn!("Could not load video for event {}: {:?}", event.id, error);
+                None
+            }
+        };
+        let asset_dir = self.engine.asset_dir();
+        let options = crate::ingest::video::VideoPipelineOptions {
+            segment_secs: self.config.video_segment_secs,
+            audio_chunk_secs: self.config.audio_chunk_secs,
+            transcribe_command: self.config.audio_transcribe_command.as_deref(),
+            asset_dir: Some(&asset_dir),
+        };
+        let analysis = crate::ingest::video::analyze_video(
+            self.llm_client.as_deref(),
+            source,
+            bytes,
+            &options,
+        )
+        .await;
+
+        let mut unit = MemoryUnit::new(
+            event.org_id.clone(),
+            event.user_id.clone(),
+            event.agent_id.clone(),
+            event.stream_id,
+            memorose_common::MemoryType::Factual,
+            analysis.searchable_text(),
+            None,
+        );
+        unit.valid_time = event.valid_time;
+        unit.location = event.location();
+        unit.references.push(event.id);
+        unit.assets.push(Self::build_asset(
+            source.clone(),
+            "video",
+            "video",
+            analysis.overview.clone(),
+        ));
+        for clip in &analysis.clips {
+            let mut asset = Self::build_asset(
+                clip.keyframe_path.clone().unwrap_or_else(|| source.clone()),
+                "keyframe",
+                "video_segment",
+                clip.summary.clone(),
+            );
+            asset
+                .metadata
+                .insert("start_secs".into(), clip.start_time.to_string());
+            asset
+                .metadata
+                .insert("end_secs".into(), clip.end_time.to_string());
+            if let Some(transcript) = &clip.transcript {
+                asset
+                    .metadata
+                    .insert("transcript".into(), transcript.clone());
+            }
+            if let Some(thumbnail) = &clip.thumbnail_path {
+                asset.metadata.insert(
+                    crate::ingest::image::THUMBNAIL_KEY_METADATA_KEY.into(),
+                    thumbnail.clone(),
+                );
+            }
+            unit.assets.push(asset);
+        }
+
+        self.hydrate_keywords(&mut unit).await;
+
+        self.hydrate_emotions(&mut unit).await;
+        self.hydrate_extracted_facts(&mut unit).await;
+        let pending_input =
+            Self::pending_input_from_embed_input(EmbedInput::Text(unit.content.clone()));
+        self.engine.enqueue_materialization_jobs(vec![
+            crate::engine::PendingMaterializationJob::new(unit, Vec::new(), Some(pending_input)),
+        ])?;
+        self.engine
+            .mark_event_processed(&event.id.to_string())
+            .await
+    }
+
+    /// Transcribe an audio event in chunks and store the merged, timestamped transcript
+    /// as one or more memory units chained by `Next` edges.
+    async fn consolidate_audio_event(&self, event: &Event) -> Result<()> {
+        let EventContent::Audio(source) = &event.content else {
+            return Ok(());
+        };
+        let chunk_secs = self.config.audio_chunk_secs.max(1);
+        let bytes = crate::ingest::load_media_bytes(source).await?;
+        let chunks = crate::ingest::audio::split_audio(bytes, chunk_secs).await?;
+        let segments = crate::ingest::audio::transcribe_chunks(
+            self.llm_client.as_deref(),
+            self.config.audio_transcribe_command.as_deref(),
+            &chunks,
+            chunk_secs,
+        )
+        .await?;
+        let sections =
+            crate::ingest::audio::group_segments(segments, self.config.consolidation_target_tokens);
+        if sections.is_empty() {
+            return Err(anyhow::anyhow!(
+                "transcript of audio event {} is empty",
+                event.id
+            ));
+        }
+        tracing::info!(
+            "Transcribed audio event {} ({} chunks) into {} memories",
+            event.id,
+            chunks.len(),
+            sections.len()
+        );
+
+        let mut units = Vec::with_capacity(sections.len());
+        for (index, section) in sections.iter().enumerate() {
+            let mut unit = MemoryUnit::new(
+                event.org_id.clone(),
+                event.user_id.clone(),
+                event.agent_id.clone(),
+                event.stream_id,
+                memorose_common::MemoryType::Factual,
+                section.render(),
+                None,
+            );
+            unit.valid_time = event.valid_time;
+            unit.location = event.location();
+            unit.references.push(event.id);
+
+            let mut asset = Self::build_asset(
+                source.clone(),
+                "audio",
+                "audio",
+                Some(format!(
+                    "Transcript part {} of {} ({} - {})",
+                    index + 1,
+                    sections.len(),
+                    crate::ingest::audio::format_timestamp(section.start_secs),
+                    crate::ingest::audio::format_timestamp(section.end_secs)
+                )),
+            );
+            asset
+                .metadata
+                .insert("start_secs".into(), section.start_secs.to_string());
+            asset
+                .metadata
+                .insert("end_secs".into(), section.end_secs.to_string());
+            let speakers = section.speakers();
+            if !speakers.is_empty() {
+                asset
+                    .metadata
+                    .insert("speakers".into(), speakers.join(", "));
+            }
+            unit.assets.push(asset);
+
+            self.hydrate_keywords(&mut unit).await;
+
+            self.hydrate_emotions(&mut unit).await;
+            self.hydrate_extracted_facts(&mut unit).await;
+            units.push(unit);
+        }
+
+        let next_ids = units.iter().skip(1).map(|unit| unit.id).collect::<Vec<_>>();
+        let jobs = units
+            .into_iter()
+            .enumerate()
+            .map(|(index, unit)| {
+                let edges = next_ids
+                    .get(index)
+                    .map(|next_id| {
+                        vec![GraphEdge::new(
+                            unit.user_id.clone(),
+                            unit.id,
+                            *next_id,
+                            memorose_common::RelationType::Next,
+                            1.0,
+                        )]
+                    })
+                    .unwrap_or_default();
+                let pending_input =
+                    Self::pending_input_from_embed_input(EmbedInput::Text(unit.content.clone()));
+                crate::engine::PendingMaterializationJob::new(unit, edges, Some(pending_input))
+            })
+            .collect::<Vec<_>>();
+        self.engine.enqueue_materialization_jobs(jobs)?;
+        self.engine
+            .mark_event_processed(&event.id.to_string())
+            .await
     }
 
     /// Helper for pipeline batch processing
@@ -1719,24 +3445,31 @@ impl BackgroundWorker {
         Ok(staged_edges)
     }
 
-    async fn process_pipeline_batch(
-        &self,
-        batch: Vec<(
-            Vec<uuid::Uuid>,
-            String,
-            uuid::Uuid,
-            String,
-            Option<String>,
-            Vec<Asset>,
-            serde_json::Value,
-            Option<EmbedInput>,
-        )>,
-    ) -> Result<Vec<String>> {
-        if batch.is_empty() {
-            return Ok(Vec::new());
+    async fn process_pipeline_batch(&self, batch: Vec<PipelineItem>) -> Result<Vec<String>> {
+        let (processed_ids, jobs) = self.stage_pipeline_batch(batch).await?;
+        if !jobs.is_empty() {
+            self.engine.enqueue_materialization_jobs(jobs)?;
         }
 
-        let mut staged_units = Vec::new();
+        // Mark processed
+        for eid in &processed_ids {
+            self.engine.mark_event_processed(eid).await?;
+        }
+
+        Ok(processed_ids)
+    }
+
+    /// Build memory units for a batch and the materialization jobs that will publish
+    /// them. Returns the source event ids covered; nothing is persisted yet.
+    async fn stage_pipeline_batch(
+        &self,
+        batch: Vec<PipelineItem>,
+    ) -> Result<(Vec<String>, Vec<crate::engine::PendingMaterializationJob>)> {
+        if batch.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let mut staged_units = Vec::new();
         let mut processed_ids = Vec::new();
 
         for (
@@ -1749,8 +3482,7 @@ impl BackgroundWorker {
                 Some(None) | None => None,
             };
 
-            let is_agent = metadata.get("role").and_then(|v| v.as_str()) == Some("assistant")
-                || metadata.get("agent_id").is_some();
+            let is_agent = memorose_common::is_agent_metadata(&metadata);
 
             let agent_id = metadata
                 .get("agent_id")
@@ -1777,6 +3509,7 @@ impl BackgroundWorker {
                     .ok()
                     .map(|d| d.with_timezone(&chrono::Utc))
             });
+            unit.location = memorose_common::GeoPoint::from_metadata(&metadata);
             unit.assets = assets;
 
             // Link to all source events
@@ -1801,16 +3534,33 @@ impl BackgroundWorker {
                         ),
                         _ => memorose_common::TaskStatus::Pending,
                     };
-                    unit.task_metadata = Some(memorose_common::TaskMetadata {
+                    let mut task_metadata = memorose_common::TaskMetadata::new(
                         status,
-                        progress: metadata
+                        metadata
                             .get("task_progress")
                             .and_then(|v| v.as_f64())
                             .unwrap_or(0.0) as f32,
-                    });
+                    );
+                    task_metadata.due_at = metadata
+                        .get("task_due_at")
+                        .and_then(|v| v.as_str())
+                        .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+                        .map(|v| v.with_timezone(&chrono::Utc));
+                    task_metadata.recurrence = metadata
+                        .get("task_recurrence")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string);
+                    task_metadata.priority = metadata
+                        .get("task_priority")
+                        .and_then(|v| v.as_str())
+                        .and_then(memorose_common::TaskPriority::parse);
+                    unit.task_metadata = Some(task_metadata);
                 }
             }
 
+            self.hydrate_keywords(&mut unit).await;
+
+            self.hydrate_emotions(&mut unit).await;
             self.hydrate_extracted_facts(&mut unit).await;
             let pending_input = if unit.embedding.is_some() {
                 None
@@ -1825,6 +3575,7 @@ impl BackgroundWorker {
             }
         }
 
+        let mut jobs = Vec::new();
         if !staged_units.is_empty() {
             let mut pending_input_by_unit = staged_units
                 .iter()
@@ -1864,7 +3615,7 @@ impl BackgroundWorker {
                     .push(edge);
             }
 
-            let jobs = units_to_stage
+            jobs = units_to_stage
                 .into_iter()
                 .map(|unit| {
                     let unit_id = unit.id;
@@ -1875,15 +3626,9 @@ impl BackgroundWorker {
                     )
                 })
                 .collect::<Vec<_>>();
-            self.engine.enqueue_materialization_jobs(jobs)?;
-        }
-
-        // Mark processed
-        for eid in &processed_ids {
-            self.engine.mark_event_processed(eid).await?;
         }
 
-        Ok(processed_ids)
+        Ok((processed_ids, jobs))
     }
 
     async fn run_community_cycle(&self) -> Result<()> {
@@ -1900,8 +3645,8 @@ impl BackgroundWorker {
             return Ok(());
         }
 
-        let user_ids = self.engine.get_pending_communities()?;
-        if user_ids.is_empty() {
+        let jobs = self.engine.list_jobs(BackgroundJobKind::Community)?;
+        if jobs.is_empty() {
             return Ok(());
         }
 
@@ -1912,25 +3657,47 @@ impl BackgroundWorker {
         tracing::info!(
             "Running L2 Graph Community Detection for up to {} users (queued={})...",
             max_users,
-            user_ids.len()
+            jobs.len()
         );
 
-        for user_id in user_ids.into_iter().take(max_users) {
+        let mut claimed_users = 0;
+        for job in jobs {
+            if claimed_users >= max_users {
+                break;
+            }
+            let Some(job) = self
+                .claim_job(BackgroundJobKind::Community, &job.user_id)
+                .await?
+            else {
+                continue;
+            };
+            claimed_users += 1;
             match self
                 .engine
-                .process_communities_with_limits(&user_id, min_members, max_groups)
+                .process_communities_incremental(
+                    &job.user_id,
+                    min_members,
+                    max_groups,
+                    self.config.community_resummarize_threshold,
+                )
                 .await
             {
                 Ok(created) => {
                     tracing::debug!(
                         "Community processing finished for user {} (created_l2={})",
-                        user_id,
+                        job.user_id,
                         created
                     );
-                    self.engine.clear_community_marker(&user_id)?;
+                    self.complete_job(&job).await?;
+                    crate::run_report::record_items(1);
                 }
                 Err(e) => {
-                    tracing::warn!("Community processing failed for user {}: {:?}", user_id, e);
+                    tracing::warn!(
+                        "Community processing failed for user {}: {:?}",
+                        job.user_id,
+                        e
+                    );
+                    self.fail_job(&job, &e).await?;
                 }
             }
         }
@@ -1938,6 +3705,222 @@ impl BackgroundWorker {
         Ok(())
     }
 
+    async fn run_l2_refresh_cycle(&self) -> Result<()> {
+        let refresh_interval = Duration::from_millis(
+            self.config
+                .l2_refresh_interval_ms
+                .max(self.config.tick_interval_ms),
+        );
+        {
+            let last = self.last_l2_refresh.lock().await;
+            if last.elapsed() <= refresh_interval {
+                return Ok(());
+            }
+        }
+
+        let min_members = self.config.community_min_members.max(1);
+        let max_groups = self.config.community_max_groups_per_user.max(1);
+        for user_id in self.engine.list_community_state_users()? {
+            match self
+                .engine
+                .refresh_stale_community_summaries(
+                    &user_id,
+                    min_members,
+                    max_groups,
+                    self.config.community_resummarize_threshold,
+                )
+                .await
+            {
+                Ok(0) => {}
+                Ok(refreshed) => {
+                    crate::run_report::record_items(refreshed);
+                    tracing::info!(
+                        "Regenerated {} stale L2 insights for user {}",
+                        refreshed,
+                        user_id
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!("L2 refresh failed for user {}: {:?}", user_id, e);
+                }
+            }
+        }
+
+        *self.last_l2_refresh.lock().await = std::time::Instant::now();
+        Ok(())
+    }
+
+    async fn run_task_deadline_cycle(&self) -> Result<()> {
+        let deadline_interval = Duration::from_millis(
+            self.config
+                .task_deadline_interval_ms
+                .max(self.config.tick_interval_ms),
+        );
+        let should_run = {
+            let last = self.last_task_deadline.lock().await;
+            last.elapsed() > deadline_interval
+        };
+        if !should_run {
+            return Ok(());
+        }
+
+        let warning = chrono::Duration::milliseconds(
+            i64::try_from(self.config.task_deadline_warning_ms).unwrap_or(i64::MAX),
+        );
+        let events = self
+            .engine
+            .process_task_deadlines(chrono::Utc::now(), warning, TASK_DEADLINE_BATCH_LIMIT)
+            .await?;
+        crate::run_report::record_items(events.len());
+        if !events.is_empty() {
+            tracing::info!("Task deadline cycle emitted {} notifications", events.len());
+        }
+
+        if let Some(url) = self.config.task_deadline_webhook_url.as_deref() {
+            let client = reqwest::Client::new();
+            for event in &events {
+                let result = client
+                    .post(url)
+                    .timeout(Duration::from_secs(10))
+                    .json(event)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    tracing::warn!(
+                        "Task deadline webhook failed for task {}: {:?}",
+                        event.unit_id,
+                        e
+                    );
+                }
+            }
+        }
+
+        *self.last_task_deadline.lock().await = std::time::Instant::now();
+        Ok(())
+    }
+
+    async fn run_profile_cycle(&self) -> Result<()> {
+        let profile_interval = Duration::from_millis(
+            self.config
+                .profile_interval_ms
+                .max(self.config.tick_interval_ms),
+        );
+        let should_run = {
+            let last = self.last_profile.lock().await;
+            last.elapsed() > profile_interval
+        };
+        if !should_run {
+            return Ok(());
+        }
+
+        let jobs = self.engine.list_jobs(BackgroundJobKind::Profile)?;
+        if jobs.is_empty() {
+            return Ok(());
+        }
+
+        let max_users = self.config.profile_max_users_per_cycle.max(1);
+        let max_insights = self.config.profile_max_insights.max(1);
+        let max_memories = self.config.profile_max_memories.max(1);
+
+        tracing::info!(
+            "Running L3 profile synthesis for up to {} users (queued={})...",
+            max_users,
+            jobs.len()
+        );
+
+        let mut claimed_users = 0;
+        for job in jobs {
+            if claimed_users >= max_users {
+                break;
+            }
+            let Some(job) = self
+                .claim_job(BackgroundJobKind::Profile, &job.user_id)
+                .await?
+            else {
+                continue;
+            };
+            claimed_users += 1;
+            if let Err(e) = self
+                .refresh_structured_profile(&job.user_id, max_memories)
+                .await
+            {
+                tracing::warn!(
+                    "Structured profile extraction failed for user {}: {:?}",
+                    job.user_id,
+                    e
+                );
+            }
+
+            match self
+                .engine
+                .refresh_user_profile(&job.user_id, max_insights)
+                .await
+            {
+                Ok(profile_id) => {
+                    if profile_id.is_some() {
+                        if let Err(e) = self.replicate_user_profile_pointer(&job.user_id).await {
+                            tracing::warn!(
+                                "Failed to replicate the profile of user {}: {:?}",
+                                job.user_id,
+                                e
+                            );
+                        }
+                    }
+                    tracing::debug!(
+                        "Profile synthesis finished for user {} (profile={:?})",
+                        job.user_id,
+                        profile_id
+                    );
+                    self.complete_job(&job).await?;
+                    crate::run_report::record_items(1);
+                }
+                Err(e) => {
+                    tracing::warn!("Profile synthesis failed for user {}: {:?}", job.user_id, e);
+                    self.fail_job(&job, &e).await?;
+                }
+            }
+        }
+        *self.last_profile.lock().await = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Extract profile attributes here, on the leader, and replicate the merge so replicas
+    /// store the same profile without repeating the LLM call.
+    async fn refresh_structured_profile(&self, user_id: &str, max_memories: usize) -> Result<bool> {
+        let attributes = self
+            .engine
+            .extract_structured_profile(user_id, max_memories)
+            .await?;
+        if attributes.is_empty() {
+            return Ok(false);
+        }
+        self.replicate(crate::raft::types::ClientRequest::MergeStructuredProfile {
+            user_id: user_id.to_string(),
+            attributes,
+            updated_at: chrono::Utc::now(),
+        })
+        .await
+    }
+
+    /// Replicate the L3 profile pointer the leader just moved, so a new leader resolves
+    /// the same profile.
+    async fn replicate_user_profile_pointer(&self, user_id: &str) -> Result<()> {
+        if self.raft.is_none() {
+            return Ok(());
+        }
+        let (Some(current), previous) = self.engine.user_profile_pointer(user_id)? else {
+            return Ok(());
+        };
+        self.replicate(crate::raft::types::ClientRequest::SetUserProfilePointer {
+            user_id: user_id.to_string(),
+            current,
+            previous,
+        })
+        .await?;
+        Ok(())
+    }
+
     async fn run_insight_cycle(&self) -> Result<()> {
         if self.llm_client.is_none() {
             return Ok(());
@@ -1958,22 +3941,48 @@ impl BackgroundWorker {
 
         let engine = self.engine.clone();
 
-        // Marker-driven: only process users with needs_reflect markers
-        let pending_markers = engine.get_pending_reflection_markers()?;
-        if pending_markers.is_empty() {
+        // Job-driven: only process users with a queued reflect job
+        let pending_jobs = engine.list_jobs(BackgroundJobKind::Reflect)?;
+        if pending_jobs.is_empty() {
             return Ok(());
         }
 
-        for (user_id, marker) in pending_markers {
-            if !self.should_process_reflection_marker(&marker) {
+        let mut llm_budget = self.config.insight_max_llm_calls_per_cycle.max(1);
+        for job in pending_jobs {
+            if llm_budget == 0 {
+                tracing::info!("Reflection LLM budget exhausted; deferring remaining users");
+                break;
+            }
+            if !self.should_process_reflection_marker(&job.reflection.unwrap_or_default()) {
                 continue;
             }
-            let mut remaining_marker = marker.clone();
+            let Some(job) = self
+                .claim_job(BackgroundJobKind::Reflect, &job.user_id)
+                .await?
+            else {
+                continue;
+            };
+            let user_id = job.user_id.clone();
+            let mut remaining_marker = job.reflection.clone().unwrap_or_default();
             let max_batches = self.config.insight_max_batches_per_cycle.max(1);
+            let mut settled = false;
 
             for batch_no in 0..max_batches {
                 if remaining_marker.pending_units == 0 {
-                    engine.clear_reflection_marker(&user_id)?;
+                    self.complete_job(&job).await?;
+                    settled = true;
+                    break;
+                }
+                if llm_budget == 0 {
+                    break;
+                }
+                if batch_no > 0
+                    && !self
+                        .heartbeat_job(BackgroundJobKind::Reflect, &user_id)
+                        .await?
+                {
+                    tracing::warn!("Lost the reflect job lease for user {}", user_id);
+                    settled = true;
                     break;
                 }
 
@@ -2003,23 +4012,29 @@ impl BackgroundWorker {
                     .await
                 {
                     Ok(outcome) if outcome.consumed_units == 0 => {
-                        engine.clear_reflection_marker(&user_id)?;
+                        self.complete_job(&job).await?;
+                        settled = true;
                         break;
                     }
                     Ok(outcome) => {
+                        if !outcome.deduplicated {
+                            llm_budget -= 1;
+                        }
                         tracing::debug!(
                             "User-window reflection batch completed for user {} with {} topics from {} units",
                             user_id,
                             outcome.created_topics,
                             outcome.consumed_units
                         );
-                        engine.consume_reflection_marker_batch(
-                            &user_id,
-                            outcome.consumed_units,
-                            outcome.consumed_tokens,
-                            outcome.next_first_event_tx_micros,
-                            outcome.next_first_event_id.clone(),
-                        )?;
+                        crate::run_report::record_items(outcome.consumed_units);
+                        self.replicate(crate::raft::types::ClientRequest::ConsumeReflectionBatch {
+                            user_id: user_id.clone(),
+                            consumed_units: outcome.consumed_units,
+                            consumed_tokens: outcome.consumed_tokens,
+                            next_first_event_tx_micros: outcome.next_first_event_tx_micros,
+                            next_first_event_id: outcome.next_first_event_id.clone(),
+                        })
+                        .await?;
 
                         remaining_marker.pending_units = remaining_marker
                             .pending_units
@@ -2034,25 +4049,117 @@ impl BackgroundWorker {
                         if remaining_marker.pending_units == 0
                             || remaining_marker.first_event_tx_micros <= 0
                         {
+                            self.complete_job(&job).await?;
+                            settled = true;
                             break;
                         }
                     }
                     Err(e) => {
+                        llm_budget -= 1;
                         tracing::warn!(
                             "User-window reflection failed for user {}: {:?}",
                             user_id,
                             e
                         );
+                        self.fail_job(&job, &e).await?;
+                        settled = true;
                         break;
                     }
                 }
             }
+
+            if !settled {
+                // Out of budget or batches with work left; the next cycle picks it up.
+                self.replicate(crate::raft::types::ClientRequest::ReleaseJob {
+                    job,
+                    holder: self.job_holder.clone(),
+                })
+                .await?;
+            }
         }
 
         *self.last_insight.lock().await = std::time::Instant::now();
         Ok(())
     }
 
+    fn job_duration(secs: u64) -> chrono::Duration {
+        chrono::Duration::seconds(i64::from(u32::try_from(secs).unwrap_or(u32::MAX)))
+    }
+
+    /// Raft term this worker claims jobs in; 0 without Raft.
+    fn current_term(&self) -> u64 {
+        self.raft
+            .as_ref()
+            .map_or(0, |raft| raft.metrics().borrow().current_term)
+    }
+
+    async fn enqueue_job(&self, kind: BackgroundJobKind, user_id: &str) -> Result<bool> {
+        self.replicate(crate::raft::types::ClientRequest::EnqueueJob {
+            kind,
+            user_id: user_id.to_string(),
+        })
+        .await
+    }
+
+    async fn claim_job(
+        &self,
+        kind: BackgroundJobKind,
+        user_id: &str,
+    ) -> Result<Option<BackgroundJob>> {
+        let now = chrono::Utc::now();
+        let claimed = self
+            .replicate(crate::raft::types::ClientRequest::ClaimJob {
+                kind,
+                user_id: user_id.to_string(),
+                holder: self.job_holder.clone(),
+                term: self.current_term(),
+                max_attempts: self.config.job_max_attempts,
+                now,
+                expires_at: now + Self::job_duration(self.config.job_lease_secs),
+            })
+            .await?;
+        if !claimed {
+            return Ok(None);
+        }
+        self.engine.get_job(kind, user_id)
+    }
+
+    /// Extend this worker's lease on the job. Returns false once the lease was lost.
+    async fn heartbeat_job(&self, kind: BackgroundJobKind, user_id: &str) -> Result<bool> {
+        let now = chrono::Utc::now();
+        self.replicate(crate::raft::types::ClientRequest::HeartbeatJob {
+            kind,
+            user_id: user_id.to_string(),
+            holder: self.job_holder.clone(),
+            now,
+            expires_at: now + Self::job_duration(self.config.job_lease_secs),
+        })
+        .await
+    }
+
+    async fn complete_job(&self, job: &BackgroundJob) -> Result<()> {
+        self.replicate(crate::raft::types::ClientRequest::CompleteJob {
+            job: job.clone(),
+            holder: self.job_holder.clone(),
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn fail_job(&self, job: &BackgroundJob, error: &anyhow::Error) -> Result<()> {
+        let now = chrono::Utc::now();
+        self.replicate(crate::raft::types::ClientRequest::FailJob {
+            job: job.clone(),
+            holder: self.job_holder.clone(),
+            error: error.to_string(),
+            max_attempts: self.config.job_max_attempts,
+            now,
+            retry_at: now + Self::job_duration(self.config.job_retry_backoff_secs),
+        })
+        .await?;
+        Ok(())
+    }
+
     pub async fn update_parent_progress(&self, user_id: &str, parent_id: uuid::Uuid) -> Result<()> {
         // Atomic locking per task to prevent race conditions during Read-Modify-Write.
         let lock = {
@@ -2098,10 +4205,10 @@ impl BackgroundWorker {
                         parent
                             .task_metadata
                             .clone()
-                            .unwrap_or(memorose_common::TaskMetadata {
-                                status: memorose_common::TaskStatus::InProgress,
-                                progress: 0.0,
-                            });
+                            .unwrap_or(memorose_common::TaskMetadata::new(
+                                memorose_common::TaskStatus::InProgress,
+                                0.0,
+                            ));
 
                     if (meta.progress - progress).abs() > 0.001 {
                         meta.progress = progress;
@@ -2129,7 +4236,7 @@ impl BackgroundWorker {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::llm::CompressionOutput;
+    use crate::llm::{CompressionOutput, MockLLM};
     use async_trait::async_trait;
     use chrono::Utc;
     use memorose_common::{Event, EventContent, L3Task, MemoryType, TaskStatus};
@@ -2140,11 +4247,6 @@ mod tests {
 
     const TEST_USER: &str = "test_user";
 
-    struct MockLLM {
-        fail_compress: bool,
-        generate_response: Option<String>,
-    }
-
     struct PromptCaptureGenerateLLM {
         response: String,
         prompts: Arc<Mutex<Vec<String>>>,
@@ -2171,65 +4273,6 @@ mod tests {
         max_describes: AtomicUsize,
     }
 
-    #[async_trait]
-    impl crate::llm::LLMClient for MockLLM {
-        async fn generate(&self, _prompt: &str) -> Result<crate::llm::LLMResponse<String>> {
-            Ok(crate::llm::LLMResponse {
-                data: self.generate_response.clone().unwrap_or_default(),
-                usage: Default::default(),
-            })
-        }
-        async fn embed(&self, _text: &str) -> Result<crate::llm::LLMResponse<Vec<f32>>> {
-            Ok(crate::llm::LLMResponse {
-                data: vec![0.0; 384],
-                usage: Default::default(),
-            })
-        }
-        async fn compress(
-            &self,
-            text: &str,
-            _is_agent: bool,
-        ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
-            if self.fail_compress {
-                return Err(anyhow::anyhow!("LLM Error"));
-            }
-            Ok(crate::llm::LLMResponse {
-                data: CompressionOutput {
-                    content: text.to_string(),
-                    valid_at: None,
-                },
-                usage: Default::default(),
-            })
-        }
-        async fn summarize_group(
-            &self,
-            _texts: Vec<String>,
-        ) -> Result<crate::llm::LLMResponse<String>> {
-            Ok(crate::llm::LLMResponse {
-                data: "summary".into(),
-                usage: Default::default(),
-            })
-        }
-        async fn describe_image(&self, _url: &str) -> Result<crate::llm::LLMResponse<String>> {
-            Ok(crate::llm::LLMResponse {
-                data: "image".into(),
-                usage: Default::default(),
-            })
-        }
-        async fn describe_video(&self, _url: &str) -> Result<crate::llm::LLMResponse<String>> {
-            Ok(crate::llm::LLMResponse {
-                data: "video".into(),
-                usage: Default::default(),
-            })
-        }
-        async fn transcribe(&self, _url: &str) -> Result<crate::llm::LLMResponse<String>> {
-            Ok(crate::llm::LLMResponse {
-                data: "audio".into(),
-                usage: Default::default(),
-            })
-        }
-    }
-
     #[async_trait]
     impl crate::llm::LLMClient for PromptCaptureGenerateLLM {
         async fn generate(&self, prompt: &str) -> Result<crate::llm::LLMResponse<String>> {
@@ -2248,7 +4291,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2330,7 +4373,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2415,7 +4458,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             if text.contains("I live in Shanghai") {
                 tokio::time::sleep(Duration::from_millis(80)).await;
@@ -2483,7 +4526,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2573,7 +4616,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2632,7 +4675,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2694,7 +4737,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2768,7 +4811,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2840,7 +4883,7 @@ mod tests {
         async fn compress(
             &self,
             text: &str,
-            _is_agent: bool,
+            _context: &CompressionContext,
         ) -> Result<crate::llm::LLMResponse<CompressionOutput>> {
             Ok(crate::llm::LLMResponse {
                 data: CompressionOutput {
@@ -2893,6 +4936,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: true,
             generate_response: None,
+            ..Default::default()
         }));
         *worker.last_consolidation.lock().await =
             std::time::Instant::now() - Duration::from_secs(1);
@@ -2970,6 +5014,7 @@ mod tests {
         let llm = MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         };
 
         let text_event = Event::new(
@@ -2980,7 +5025,7 @@ mod tests {
             EventContent::Text("Hello".into()),
         );
         let (text, text_input, text_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&text_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&text_event, Some(&llm), None).await;
         assert_eq!(text, "Hello");
         assert!(matches!(text_input, EmbedInput::Text(ref v) if v == "Hello"));
         assert!(text_assets.is_empty());
@@ -2993,7 +5038,7 @@ mod tests {
             EventContent::Json(serde_json::json!({"kind":"demo"})),
         );
         let (json_text, json_input, json_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&json_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&json_event, Some(&llm), None).await;
         assert!(json_text.contains("\"kind\":\"demo\""));
         assert!(matches!(json_input, EmbedInput::Text(_)));
         assert!(json_assets.is_empty());
@@ -3006,7 +5051,7 @@ mod tests {
             EventContent::Image("YmFzZTY0aW1hZ2U=".into()),
         );
         let (image_text, image_input, image_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&image_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&image_event, Some(&llm), None).await;
         assert_eq!(image_text, "image");
         assert!(matches!(image_input, EmbedInput::Multimodal { .. }));
         assert_eq!(image_assets.len(), 1);
@@ -3019,7 +5064,7 @@ mod tests {
             EventContent::Audio("YmFzZTY0YXVkaW8=".into()),
         );
         let (_, audio_input, audio_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&audio_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&audio_event, Some(&llm), None).await;
         assert!(matches!(audio_input, EmbedInput::Multimodal { .. }));
         assert_eq!(audio_assets.len(), 1);
 
@@ -3031,13 +5076,55 @@ mod tests {
             EventContent::Video("YmFzZTY0dmlkZW8=".into()),
         );
         let (_, video_input, video_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&video_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&video_event, Some(&llm), None).await;
         assert!(matches!(video_input, EmbedInput::Multimodal { .. }));
         assert_eq!(video_assets.len(), 1);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_extract_text_and_embed_input_stores_image_with_thumbnail() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let llm = MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        };
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(512, 512, image::Rgb([10, 120, 200]))
+            .write_to(&mut png, image::ImageFormat::Png)?;
+        let encoded =
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png.get_ref());
+        let image_event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Image(format!("data:image/png;base64,{}", encoded)),
+        );
+
+        let (_, image_input, assets) = BackgroundWorker::extract_text_and_embed_input(
+            &image_event,
+            Some(&llm),
+            Some(temp_dir.path()),
+        )
+        .await;
+        assert!(matches!(
+            image_input,
+            EmbedInput::Multimodal { ref parts }
+                if matches!(&parts[0], EmbedPart::InlineData { mime_type, data }
+                    if mime_type == "image/png" && data == &encoded)
+        ));
+        assert_eq!(assets[0].asset_type, "image/png");
+        assert!(assets[0].storage_key.starts_with("local://images/"));
+        let thumbnail_key = &assets[0].metadata[crate::ingest::image::THUMBNAIL_KEY_METADATA_KEY];
+        let thumbnail_path =
+            crate::ingest::image::resolve_local_asset(temp_dir.path(), thumbnail_key).unwrap();
+        assert!(thumbnail_path.exists());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_extract_text_and_embed_input_without_llm_uses_plain_fallbacks() -> Result<()> {
         let stream_id = Uuid::new_v4();
@@ -3050,7 +5137,7 @@ mod tests {
             EventContent::Image("ZmFrZS1pbWFnZQ==".into()),
         );
         let (image_text, _, _) =
-            BackgroundWorker::extract_text_and_embed_input(&image_event, None).await;
+            BackgroundWorker::extract_text_and_embed_input(&image_event, None, None).await;
         assert_eq!(image_text, "Image at ZmFrZS1pbWFnZQ==");
 
         let audio_event = Event::new(
@@ -3061,7 +5148,7 @@ mod tests {
             EventContent::Audio("ZmFrZS1hdWRpbw==".into()),
         );
         let (audio_text, _, _) =
-            BackgroundWorker::extract_text_and_embed_input(&audio_event, None).await;
+            BackgroundWorker::extract_text_and_embed_input(&audio_event, None, None).await;
         assert_eq!(audio_text, "Audio at ZmFrZS1hdWRpbw==");
 
         let video_event = Event::new(
@@ -3072,7 +5159,7 @@ mod tests {
             EventContent::Video("ZmFrZS12aWRlbw==".into()),
         );
         let (video_text, _, _) =
-            BackgroundWorker::extract_text_and_embed_input(&video_event, None).await;
+            BackgroundWorker::extract_text_and_embed_input(&video_event, None, None).await;
         assert_eq!(video_text, "Video at ZmFrZS12aWRlbw==");
 
         Ok(())
@@ -3092,7 +5179,7 @@ mod tests {
             EventContent::Image("aW1hZ2UtYnl0ZXM=".into()),
         );
         let (image_text, image_input, image_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&image_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&image_event, Some(&llm), None).await;
         assert_eq!(image_text, "Image at aW1hZ2UtYnl0ZXM=");
         assert!(matches!(image_input, EmbedInput::Multimodal { .. }));
         assert!(image_assets[0].storage_key.starts_with("inline://image/"));
@@ -3105,7 +5192,7 @@ mod tests {
             EventContent::Audio("YXVkaW8tYnl0ZXM=".into()),
         );
         let (audio_text, audio_input, audio_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&audio_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&audio_event, Some(&llm), None).await;
         assert_eq!(audio_text, "Audio at YXVkaW8tYnl0ZXM=");
         assert!(matches!(audio_input, EmbedInput::Multimodal { .. }));
         assert!(audio_assets[0].storage_key.starts_with("inline://audio/"));
@@ -3118,7 +5205,7 @@ mod tests {
             EventContent::Video("dmlkZW8tYnl0ZXM=".into()),
         );
         let (video_text, video_input, video_assets) =
-            BackgroundWorker::extract_text_and_embed_input(&video_event, Some(&llm)).await;
+            BackgroundWorker::extract_text_and_embed_input(&video_event, Some(&llm), None).await;
         assert_eq!(video_text, "Video at dmlkZW8tYnl0ZXM=");
         assert!(matches!(video_input, EmbedInput::Multimodal { .. }));
         assert!(video_assets[0].storage_key.starts_with("inline://video/"));
@@ -3127,16 +5214,124 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_hydrate_extracted_facts_skips_non_l1_and_falls_back_on_llm_error() -> Result<()> {
+    async fn test_hydrate_keywords_uses_llm_then_falls_back_to_rake() -> Result<()> {
         let temp_dir = tempdir()?;
         let engine =
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
         let stream_id = Uuid::new_v4();
+        let content = "I moved to Berlin last spring and started learning Rust programming.";
 
         let mut worker = BackgroundWorker::new(engine);
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
-            generate_response: None,
+            generate_response: Some(
+                "```json\n[\"Berlin\", \" Rust \", \"Berlin\", \"\"]\n```".into(),
+            ),
+            ..Default::default()
+        }));
+        let mut llm_unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            stream_id,
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        worker.hydrate_keywords(&mut llm_unit).await;
+        assert_eq!(
+            llm_unit.keywords,
+            vec!["Berlin".to_string(), "Rust".to_string()]
+        );
+
+        worker.llm_client = None;
+        let mut rake_unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            stream_id,
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        worker.hydrate_keywords(&mut rake_unit).await;
+        assert!(rake_unit
+            .keywords
+            .contains(&"berlin last spring".to_string()));
+        assert!(rake_unit.keywords.len() <= worker.config.keyword_max_per_memory);
+
+        let mut l2_unit = MemoryUnit::new(
+            None,
+            TEST_USER.into(),
+            None,
+            stream_id,
+            MemoryType::Factual,
+            content.into(),
+            None,
+        );
+        l2_unit.level = 2;
+        worker.hydrate_keywords(&mut l2_unit).await;
+        assert!(l2_unit.keywords.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hydrate_emotions_uses_llm_then_falls_back_to_lexicon() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let content = "The sync keeps failing and I am really frustrated with it.";
+        let new_unit = || {
+            MemoryUnit::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                MemoryType::Factual,
+                content.into(),
+                None,
+            )
+        };
+
+        let mut worker = BackgroundWorker::new(engine);
+        worker.config.emotion_tagging_enabled = true;
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: Some("```json\n[\"Angry\", \"bored\", \"frustrated\"]\n```".into()),
+            ..Default::default()
+        }));
+        let mut llm_unit = new_unit();
+        worker.hydrate_emotions(&mut llm_unit).await;
+        assert_eq!(
+            llm_unit.emotions,
+            vec!["angry".to_string(), "frustrated".to_string()]
+        );
+
+        worker.llm_client = None;
+        let mut lexicon_unit = new_unit();
+        worker.hydrate_emotions(&mut lexicon_unit).await;
+        assert_eq!(lexicon_unit.emotions, vec!["frustrated".to_string()]);
+
+        worker.config.emotion_tagging_enabled = false;
+        let mut disabled_unit = new_unit();
+        worker.hydrate_emotions(&mut disabled_unit).await;
+        assert!(disabled_unit.emotions.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hydrate_extracted_facts_skips_non_l1_and_falls_back_on_llm_error() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let stream_id = Uuid::new_v4();
+
+        let mut worker = BackgroundWorker::new(engine);
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
         }));
 
         let mut non_l1 = MemoryUnit::new(
@@ -3237,6 +5432,55 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_run_self_eval_cycle_replays_generated_questions() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        engine
+            .store_memory_unit(MemoryUnit::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                MemoryType::Factual,
+                "Signed the Berlin apartment lease starting in May".into(),
+                None,
+            ))
+            .await?;
+        engine
+            .system_kv()
+            .put(format!("active_user:{TEST_USER}").as_bytes(), b"1")?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            // Memory 2 does not exist and is ignored
+            generate_response: Some(
+                r#"[{"memory": 1, "question": "When does my Berlin lease start?"},
+                    {"memory": 2, "question": "What is my cat called?"}]"#
+                    .into(),
+            ),
+            ..Default::default()
+        }));
+
+        // Disabled by default
+        worker.run_self_eval_cycle().await?;
+        assert!(engine.list_self_eval_reports(10)?.is_empty());
+
+        worker.config.self_eval_interval_secs = 1;
+        *worker.last_self_eval.lock().await = std::time::Instant::now() - Duration::from_secs(2);
+        worker.run_self_eval_cycle().await?;
+
+        let reports = engine.list_self_eval_reports(10)?;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].sampled, 1);
+        assert_eq!(reports[0].questions, 1);
+        assert_eq!(reports[0].hits + reports[0].misses, 1);
+        assert_eq!(reports[0].top_k, worker.config.self_eval_top_k);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_run_decay_cycle_skips_when_forgetting_disabled() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -3331,6 +5575,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: Some("LLM summary".into()),
+            ..Default::default()
         }));
 
         worker.run_l3_task_cycle().await?;
@@ -3431,47 +5676,311 @@ mod tests {
         let engine =
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
 
-        let mut worker = BackgroundWorker::new(engine);
-        let processed = worker.run_consolidation_cycle().await?;
+        let mut worker = BackgroundWorker::new(engine);
+        let processed = worker.run_consolidation_cycle().await?;
+
+        assert!(!processed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_cycle_marks_exhausted_retries_failed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let worker = BackgroundWorker::new(engine.clone());
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Retry me".into()),
+        );
+        let event_id = event.id.to_string();
+        engine.ingest_event_directly(event).await?;
+
+        for _ in 0..worker.config.consolidation_max_retries {
+            engine.increment_retry_count_if_pending(&event_id).await?;
+        }
+
+        let processed = worker.run_consolidation_cycle().await?;
+        assert!(!processed);
+        assert_eq!(engine.fetch_pending_events().await?.len(), 0);
+        assert_eq!(engine.get_retry_count(&event_id).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pooled_consolidation_claims_and_commits_under_lease() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Pooled".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        let claim = worker
+            .claim_consolidation_batch(7, Duration::from_secs(60))
+            .await?
+            .expect("a pending text event is claimable");
+        assert_eq!(claim.lease.event_ids, vec![event.id]);
+        // A leased event is not handed out twice.
+        assert!(worker
+            .claim_consolidation_batch(8, Duration::from_secs(60))
+            .await?
+            .is_none());
+
+        let groups = worker.compress_claimed_events(claim.events).await;
+        assert!(worker
+            .commit_consolidation(claim.lease.lease_id, 8, groups.clone())
+            .await?
+            .is_none());
+        let processed = worker
+            .commit_consolidation(claim.lease.lease_id, 7, groups.clone())
+            .await?
+            .expect("the holder commits within its lease");
+        assert_eq!(processed, vec![event.id.to_string()]);
+        assert_eq!(engine.fetch_recent_l1_units(TEST_USER, 10).await?.len(), 1);
+        assert!(worker
+            .commit_consolidation(claim.lease.lease_id, 7, groups)
+            .await?
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_cycle_success() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Success".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        worker.run_consolidation_cycle().await?;
+
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        assert_eq!(l1s.len(), 1);
+        assert_eq!(l1s[0].content, "Message 1: Success");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidate_events_now_skips_pending_queue() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Inline".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        worker.consolidate_events_now(vec![event]).await?;
+
+        assert!(engine.fetch_pending_events().await?.is_empty());
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        assert_eq!(l1s.len(), 1);
+        assert_eq!(l1s[0].content, "Message 1: Inline");
+        assert!(worker.sync_consolidating.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_scores_initial_importance_from_content() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+
+        let mut importance = Vec::new();
+        for text in ["Lunch was fine", "Please remember the gate code"] {
+            let event = Event::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                EventContent::Text(text.into()),
+            );
+            engine.ingest_event_directly(event.clone()).await?;
+            let ids = worker.consolidate_events_now(vec![event]).await?;
+            assert_eq!(ids.len(), 1);
+            let unit = engine
+                .get_memory_unit(TEST_USER, ids[0])
+                .await?
+                .expect("consolidated unit");
+            importance.push(unit.importance);
+        }
+
+        assert!(importance[0] >= worker.config.importance_floor);
+        assert!(importance[0] < 1.0);
+        assert_eq!(importance[1], 1.0);
+
+        worker.config.importance_scoring_enabled = false;
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Lunch was fine again".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+        let ids = worker.consolidate_events_now(vec![event]).await?;
+        let unit = engine
+            .get_memory_unit(TEST_USER, ids[0])
+            .await?
+            .expect("consolidated unit");
+        assert_eq!(unit.importance, 1.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_cycle_leaves_sync_consolidating_events_alone() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("In flight".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        let guard = SyncConsolidationGuard::register(
+            worker.sync_consolidating.clone(),
+            std::slice::from_ref(&event),
+        );
+        assert!(!worker.run_consolidation_cycle().await?);
+        assert_eq!(engine.fetch_pending_events().await?.len(), 1);
+
+        // An abandoned inline run hands the event back to the background cycle.
+        drop(guard);
+        worker.run_consolidation_cycle().await?;
+        assert!(engine.fetch_pending_events().await?.is_empty());
 
-        assert!(!processed);
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_consolidation_cycle_marks_exhausted_retries_failed() -> Result<()> {
+    async fn test_consolidation_stores_skip_compression_events_as_written() -> Result<()> {
         let temp_dir = tempdir()?;
         let engine =
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
 
-        let worker = BackgroundWorker::new(engine.clone());
-        *worker.last_consolidation.lock().await =
-            std::time::Instant::now() - Duration::from_secs(1);
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        worker.config.skip_compression_apps = vec!["summarizer".into()];
 
-        let event = Event::new(
+        let mut flagged = Event::new(
             None,
             TEST_USER.into(),
             None,
             Uuid::new_v4(),
-            EventContent::Text("Retry me".into()),
+            EventContent::Text("Already a summary".into()),
         );
-        let event_id = event.id.to_string();
-        engine.ingest_event_directly(event).await?;
+        flagged.metadata = serde_json::json!({ "skip_compression": true });
+        let from_app = Event::new(
+            None,
+            TEST_USER.into(),
+            Some("summarizer".into()),
+            Uuid::new_v4(),
+            EventContent::Text("App summary".into()),
+        );
+        let mut opted_back_in = Event::new(
+            None,
+            TEST_USER.into(),
+            Some("summarizer".into()),
+            Uuid::new_v4(),
+            EventContent::Text("Raw turn".into()),
+        );
+        opted_back_in.metadata = serde_json::json!({ "skip_compression": false });
 
-        for _ in 0..worker.config.consolidation_max_retries {
-            engine.increment_retry_count_if_pending(&event_id).await?;
-        }
+        engine.ingest_event_directly(flagged).await?;
+        engine.ingest_event_directly(from_app).await?;
+        engine.ingest_event_directly(opted_back_in).await?;
 
-        let processed = worker.run_consolidation_cycle().await?;
-        assert!(!processed);
-        assert_eq!(engine.fetch_pending_events().await?.len(), 0);
-        assert_eq!(engine.get_retry_count(&event_id).await?, 0);
+        worker.run_consolidation_cycle().await?;
+
+        assert!(engine.fetch_pending_events().await?.is_empty());
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        let contents: Vec<&str> = l1s.iter().map(|u| u.content.as_str()).collect();
+        assert_eq!(contents.len(), 3);
+        assert!(contents.contains(&"Already a summary"));
+        assert!(contents.contains(&"App summary"));
+        assert!(contents.contains(&"Message 1: Raw turn"));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_consolidation_cycle_success() -> Result<()> {
+    async fn test_local_summarization_escalates_long_batches_and_local_failures() -> Result<()> {
         let temp_dir = tempdir()?;
         let engine =
             MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
@@ -3480,24 +5989,67 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
-        *worker.last_consolidation.lock().await =
-            std::time::Instant::now() - Duration::from_secs(1);
+        worker.config.local_summarization_enabled = true;
+        worker.config.local_summarization_max_chars = 120;
 
-        let event = Event::new(
+        let routine = Event::new(
             None,
             TEST_USER.into(),
             None,
             Uuid::new_v4(),
-            EventContent::Text("Success".into()),
+            EventContent::Text("Booked the dentist for Tuesday.".into()),
         );
-        engine.ingest_event_directly(event.clone()).await?;
+        let long = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("Planning the garden beds this weekend. ".repeat(4)),
+        );
+        engine.ingest_event_directly(routine).await?;
+        engine.ingest_event_directly(long).await?;
+        let before = crate::summary_tier::summary_tier_metric_snapshot();
 
         worker.run_consolidation_cycle().await?;
 
         let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
-        assert_eq!(l1s.len(), 1);
-        assert_eq!(l1s[0].content, "Message 1: Success");
+        let contents: Vec<&str> = l1s.iter().map(|u| u.content.as_str()).collect();
+        // The routine batch is summarized extractively, the long one by the main LLM
+        assert!(contents.contains(&"Booked the dentist for Tuesday."));
+        assert!(contents
+            .iter()
+            .any(|content| content.starts_with("Message 1: Planning the garden beds")));
+        let after = crate::summary_tier::summary_tier_metric_snapshot();
+        assert!(after.local > before.local);
+        assert!(after.escalated_length > before.escalated_length);
+
+        // A local model that fails hands the batch to the main LLM
+        worker.local_llm_client = Some(Arc::new(MockLLM {
+            fail_compress: true,
+            generate_response: None,
+            ..Default::default()
+        }));
+        engine
+            .ingest_event_directly(Event::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                EventContent::Text("Renewed the passport.".into()),
+            ))
+            .await?;
+        worker.run_consolidation_cycle().await?;
+
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        assert!(l1s
+            .iter()
+            .any(|u| u.content == "Message 1: Renewed the passport."));
+        assert!(
+            crate::summary_tier::summary_tier_metric_snapshot().escalated_local_failed
+                > after.escalated_local_failed
+        );
 
         Ok(())
     }
@@ -3512,6 +6064,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         *worker.last_consolidation.lock().await =
             std::time::Instant::now() - Duration::from_secs(1);
@@ -3557,6 +6110,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.consolidation_interval_ms = 1;
         worker.config.tick_interval_ms = 1;
@@ -3588,6 +6142,55 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_consolidation_compresses_oversized_events_in_overlapping_chunks() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        worker.config.consolidation_target_tokens = 16;
+        worker.config.consolidation_chunk_overlap_tokens = 3;
+
+        let text = (0..40)
+            .map(|index| format!("w{index}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        engine
+            .ingest_event_directly(Event::new(
+                None,
+                TEST_USER.into(),
+                None,
+                Uuid::new_v4(),
+                EventContent::Text(text),
+            ))
+            .await?;
+
+        worker.run_consolidation_cycle().await?;
+
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        assert_eq!(l1s.len(), 1);
+        // The mock echoes its input, so each line is one chunk
+        let chunks: Vec<&str> = l1s[0].content.lines().collect();
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|chunk| count_tokens(chunk) <= 16));
+        assert!(chunks[0].starts_with("Message 1: w0 "));
+        assert!(chunks.last().unwrap().ends_with("w39"));
+        // Each chunk repeats the last three one-token words of the one before
+        for pair in chunks.windows(2) {
+            let words: Vec<&str> = pair[0].split(' ').collect();
+            let tail = words[words.len() - 3..].join(" ");
+            assert!(pair[1].starts_with(&format!("{} ", tail)));
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_consolidation_cycle_preserves_same_stream_commit_order_when_compression_finishes_out_of_order(
     ) -> Result<()> {
@@ -3657,6 +6260,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.consolidation_interval_ms = 1;
         worker.config.tick_interval_ms = 1;
@@ -3766,6 +6370,106 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_consolidation_cycle_transcribes_audio_into_linked_memories() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        // The "recording" is the transcript itself, echoed back by the local command.
+        worker.config.audio_transcribe_command = Some("cat {input}".into());
+        worker.config.consolidation_target_tokens = 30;
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        let transcript = "[00:05] Alice: We ship the beta next week.\n\
+                          [00:40] Bob: QA still needs two days.\n\
+                          [01:30] Alice: Then we move the launch to Friday.";
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Audio(base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                transcript,
+            )),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        assert!(worker.run_consolidation_cycle().await?);
+        assert!(engine.fetch_pending_events().await?.is_empty());
+
+        let mut jobs = engine.fetch_due_materialization_jobs(10)?;
+        jobs.sort_by(|a, b| a.unit.content.cmp(&b.unit.content));
+        assert_eq!(jobs.len(), 2);
+        let (first, second) = (&jobs[0].unit, &jobs[1].unit);
+        assert_eq!(
+            first.content,
+            "[00:00:05] Alice: We ship the beta next week.\n[00:00:40] Bob: QA still needs two days."
+        );
+        assert_eq!(
+            second.content,
+            "[00:01:30] Alice: Then we move the launch to Friday."
+        );
+        assert!(jobs.iter().all(|job| job.unit.references == vec![event.id]));
+        assert_eq!(
+            first.assets[0].metadata.get("speakers").map(String::as_str),
+            Some("Alice, Bob")
+        );
+        assert_eq!(jobs[0].post_publish_edges.len(), 1);
+        assert_eq!(jobs[0].post_publish_edges[0].target_id, second.id);
+        assert_eq!(
+            jobs[0].post_publish_edges[0].relation,
+            memorose_common::RelationType::Next
+        );
+        assert!(jobs[1].post_publish_edges.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_cycle_keeps_video_overview_when_segments_are_unavailable(
+    ) -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        // Not a decodable video, so neither keyframes nor an audio track can be extracted.
+        let event = Event::new(
+            None,
+            TEST_USER.into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Video("bm90LWEtdmlkZW8=".into()),
+        );
+        engine.ingest_event_directly(event.clone()).await?;
+
+        assert!(worker.run_consolidation_cycle().await?);
+        assert!(engine.fetch_pending_events().await?.is_empty());
+
+        let jobs = engine.fetch_due_materialization_jobs(10)?;
+        assert_eq!(jobs.len(), 1);
+        let unit = &jobs[0].unit;
+        assert_eq!(unit.content, "video");
+        assert_eq!(unit.references, vec![event.id]);
+        assert_eq!(unit.assets.len(), 1);
+        assert_eq!(unit.assets[0].asset_type, "video");
+        assert_eq!(unit.assets[0].description.as_deref(), Some("video"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_pack_events_for_consolidation_respects_token_budget() {
         let rt = tokio::runtime::Runtime::new().expect("runtime");
@@ -3801,12 +6505,66 @@ mod tests {
             ),
         ];
 
-        let packed = worker.pack_events_for_consolidation(events);
+        let packed = worker.pack_events_for_consolidation(events, &HashMap::new());
         assert_eq!(packed.len(), 2);
         assert_eq!(packed[0].events.len(), 1);
         assert_eq!(packed[1].events.len(), 1);
     }
 
+    #[test]
+    fn test_stream_segmentation_splits_packs_at_time_gaps() {
+        let rt = tokio::runtime::Runtime::new().expect("runtime");
+        let temp_dir = tempdir().expect("tempdir");
+        let engine = rt
+            .block_on(MemoroseEngine::new_with_default_threshold(
+                temp_dir.path(),
+                1000,
+                true,
+                true,
+            ))
+            .expect("engine");
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.config.stream_segmentation_enabled = true;
+        worker.config.stream_segment_gap_secs = 1800;
+
+        let stream_id = Uuid::new_v4();
+        let start = Utc::now() - chrono::Duration::hours(3);
+        let events = ["morning run", "run felt easy", "evening run plans"]
+            .iter()
+            .zip([0, 10, 120])
+            .map(|(text, minutes)| {
+                let mut event = Event::new(
+                    None,
+                    TEST_USER.into(),
+                    None,
+                    stream_id,
+                    EventContent::Text((*text).into()),
+                );
+                event.transaction_time = start + chrono::Duration::minutes(minutes);
+                event
+            })
+            .collect::<Vec<_>>();
+        let late_event_id = events[2].id;
+
+        let segments = worker.detect_stream_segments(&events);
+        let packed = worker.pack_events_for_consolidation(events, &segments);
+        assert_eq!(packed.len(), 2);
+        assert_eq!(packed[0].events.len(), 2);
+        assert_eq!(packed[1].events[0].id, late_event_id);
+
+        let stream = engine
+            .get_memory_stream(TEST_USER, stream_id)
+            .expect("load stream")
+            .expect("stream record");
+        assert_eq!(stream.segment_boundaries.len(), 1);
+        assert_eq!(stream.segment_boundaries[0].event_id, late_event_id);
+        assert_eq!(
+            stream.last_event_at,
+            Some(start + chrono::Duration::minutes(120))
+        );
+    }
+
     #[test]
     fn test_schedule_packed_groups_fairly_round_robins_keys_while_preserving_per_key_order() {
         let rt = tokio::runtime::Runtime::new().expect("runtime");
@@ -3876,6 +6634,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_user_batches_fairly_round_robins_users_and_rotates_head() {
+        let stream_id = Uuid::new_v4();
+        let mk_events = |user: &str, count: usize| -> Vec<Event> {
+            (0..count)
+                .map(|i| {
+                    Event::new(
+                        None,
+                        user.into(),
+                        None,
+                        stream_id,
+                        EventContent::Text(format!("{} {}", user, i)),
+                    )
+                })
+                .collect()
+        };
+        let mut events = mk_events("chatty", 50);
+        events.extend(mk_events("alice", 2));
+        events.extend(mk_events("bob", 3));
+        let chatty_head = events[0].id;
+
+        let selected = BackgroundWorker::select_user_batches_fairly(events.clone(), 9, 0, None);
+        let summary: Vec<(String, usize)> = selected
+            .iter()
+            .map(|(user, batch)| (user.clone(), batch.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("alice".to_string(), 2),
+                ("bob".to_string(), 3),
+                ("chatty".to_string(), 4),
+            ]
+        );
+        assert_eq!(selected[2].1[0].id, chatty_head);
+
+        let capped = BackgroundWorker::select_user_batches_fairly(events.clone(), 100, 3, None);
+        assert!(capped.iter().all(|(_, batch)| batch.len() <= 3));
+        assert_eq!(
+            capped.iter().map(|(_, batch)| batch.len()).sum::<usize>(),
+            8
+        );
+
+        let rotated = BackgroundWorker::select_user_batches_fairly(events, 9, 0, Some("alice"));
+        let users: Vec<&str> = rotated.iter().map(|(user, _)| user.as_str()).collect();
+        assert_eq!(users, vec!["bob", "chatty", "alice"]);
+    }
+
     #[test]
     fn test_limit_scheduled_groups_by_event_budget_keeps_prefix_without_splitting_groups() {
         let rt = tokio::runtime::Runtime::new().expect("runtime");
@@ -3947,6 +6753,7 @@ mod tests {
                 r#"[{{"target_id":"{}","action":"OBSOLETE","reason":"Residence updated","confidence":0.96}}]"#,
                 old_id
             )),
+            ..Default::default()
         });
 
         let engine = MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true)
@@ -3999,6 +6806,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
 
         let processed = worker
@@ -4051,6 +6859,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
 
         let processed = worker
@@ -4103,6 +6912,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
 
         let processed = worker
@@ -4153,6 +6963,7 @@ mod tests {
                 r#"{"facts":[{"subject":"user","subject_ref":"user:self","attribute":"residence","value":"Beijing","change_type":"update","temporal_status":"current","polarity":"positive","evidence_span":"I now live in Beijing","confidence":0.93}]}"#
                     .into(),
             ),
+            ..Default::default()
         }));
 
         let processed = worker
@@ -4200,6 +7011,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
 
         let processed = worker
@@ -4350,6 +7162,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.community_trigger_l1_step = 1;
 
@@ -4535,6 +7348,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.consolidation_interval_ms = 1;
         worker.config.tick_interval_ms = 1;
@@ -4585,6 +7399,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.consolidation_interval_ms = 1;
         worker.config.tick_interval_ms = 1;
@@ -4611,6 +7426,59 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_consolidation_labels_speakers_in_multi_party_groups() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+
+        let mut worker = BackgroundWorker::new(engine.clone());
+        worker.llm_client = Some(Arc::new(MockLLM {
+            fail_compress: false,
+            generate_response: None,
+            ..Default::default()
+        }));
+        worker.config.consolidation_interval_ms = 1;
+        worker.config.tick_interval_ms = 1;
+        *worker.last_consolidation.lock().await =
+            std::time::Instant::now() - Duration::from_secs(1);
+
+        let stream_id = Uuid::new_v4();
+        // Bob's turn arrives first, but Alice spoke first
+        for (text, speaker, turn) in [
+            ("I live in Rome", "Bob", 2),
+            ("I live in Paris", "Alice", 1),
+        ] {
+            let mut event = Event::new(
+                None,
+                TEST_USER.into(),
+                None,
+                stream_id,
+                EventContent::Text(text.into()),
+            );
+            event.set_conversation_turn(
+                Some(memorose_common::EventRole::User),
+                Some(speaker.into()),
+                Some(turn),
+            );
+            engine.ingest_event_directly(event).await?;
+        }
+
+        assert!(worker.run_consolidation_cycle().await?);
+
+        let l1s = engine.fetch_recent_l1_units(TEST_USER, 10).await?;
+        assert_eq!(l1s.len(), 1);
+        assert_eq!(
+            l1s[0].content,
+            "Message 1 (Alice, user): I live in Paris\nMessage 2 (Bob, user): I live in Rome"
+        );
+
+        let context = BackgroundWorker::compression_context(&[], &Default::default());
+        assert!(!context.is_agent && !context.is_multi_party());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_is_leader_without_raft_returns_true() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -4723,6 +7591,7 @@ mod tests {
         worker.llm_client = Some(Arc::new(MockLLM {
             fail_compress: false,
             generate_response: None,
+            ..Default::default()
         }));
         worker.config.insight_interval_ms = 1;
         worker.config.insight_min_pending_l1 = 1;
@@ -4772,7 +7641,7 @@ mod tests {
         let all_units = kv.scan(prefix.as_bytes())?;
         let topics = all_units
             .into_iter()
-            .filter_map(|(_, value)| serde_json::from_slice::<MemoryUnit>(&value).ok())
+            .filter_map(|(_, value)| crate::migration::decode_memory_unit(&value).ok())
             .filter(|unit| unit.level == 2 && unit.content == "Topic summary")
             .collect::<Vec<_>>();
         assert_eq!(topics.len(), 1);
@@ -4834,7 +7703,7 @@ mod tests {
         let all_units = kv.scan(prefix.as_bytes())?;
         let topics = all_units
             .into_iter()
-            .filter_map(|(_, value)| serde_json::from_slice::<MemoryUnit>(&value).ok())
+            .filter_map(|(_, value)| crate::migration::decode_memory_unit(&value).ok())
             .filter(|unit| unit.level == 2 && unit.content == "Topic summary")
             .collect::<Vec<_>>();
         assert_eq!(topics.len(), 2);
@@ -4908,7 +7777,7 @@ mod tests {
         let all_units = kv.scan(prefix.as_bytes())?;
         let topics = all_units
             .into_iter()
-            .filter_map(|(_, value)| serde_json::from_slice::<MemoryUnit>(&value).ok())
+            .filter_map(|(_, value)| crate::migration::decode_memory_unit(&value).ok())
             .filter(|unit| unit.level == 2 && unit.content == "Topic summary")
             .collect::<Vec<_>>();
         assert_eq!(topics.len(), 3);
@@ -4956,13 +7825,43 @@ mod tests {
         let all_units = kv.scan(prefix.as_bytes())?;
         let topics = all_units
             .into_iter()
-            .filter_map(|(_, value)| serde_json::from_slice::<MemoryUnit>(&value).ok())
+            .filter_map(|(_, value)| crate::migration::decode_memory_unit(&value).ok())
             .filter(|unit| unit.level == 2 && unit.content == "Topic summary")
             .collect::<Vec<_>>();
         assert!(topics.is_empty());
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_expired_reflection_marker_is_skipped_when_window_is_trivial() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let engine =
+            MemoroseEngine::new_with_default_threshold(temp_dir.path(), 1000, true, true).await?;
+        let mut worker = BackgroundWorker::new(engine);
+        worker.config.insight_min_pending_l1 = 8;
+        worker.config.insight_min_pending_tokens = 1000;
+        worker.config.insight_max_delay_ms = 1;
+        worker.config.insight_min_reflect_l1 = 2;
+        worker.config.insight_min_reflect_tokens = 64;
+
+        let mut marker = crate::engine::ReflectionMarker {
+            first_event_at_ts: chrono::Utc::now().timestamp_millis() - 60_000,
+            last_event_at_ts: chrono::Utc::now().timestamp_millis(),
+            pending_units: 1,
+            pending_tokens: 5,
+            ..Default::default()
+        };
+        assert!(!worker.should_process_reflection_marker(&marker));
+
+        marker.pending_tokens = 200;
+        assert!(worker.should_process_reflection_marker(&marker));
+
+        marker.pending_tokens = 5;
+        marker.pending_units = 2;
+        assert!(worker.should_process_reflection_marker(&marker));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_run_community_cycle_clears_marker_when_no_edges_exist() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -185,12 +185,14 @@ use axum::{
     extract::{Request, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
+    routing::get,
     Json, Router,
 };
 use bytes::Bytes;
 use memorose_common::sharding::{decode_raft_node_id, user_id_to_shard};
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -199,9 +201,82 @@ struct AppState {
     shard_count: u32,
     /// Maps physical_node_id -> HTTP address
     node_addresses: HashMap<u32, String>,
-    /// Maps shard_id -> (leader physical_node_id, insertion time) (cached with 30s TTL)
-    shard_leaders: RwLock<HashMap<u32, (u32, Instant)>>,
+    /// Maps shard_id -> (leader HTTP base URL, insertion time) (cached with 30s TTL)
+    shard_leaders: RwLock<HashMap<u32, (String, Instant)>>,
     http_client: reqwest::Client,
+    /// Delay after which an idempotent read is duplicated to another node; `None` disables hedging
+    hedge_delay: Option<Duration>,
+    hedge_stats: HedgeStats,
+    /// Round-robin position among the nodes a request can be hedged to
+    hedge_cursor: AtomicUsize,
+    /// HTTP address of the analytics replica that serves heavy reads; `None` routes them normally
+    analytics_addr: Option<String>,
+    analytics_stats: AnalyticsStats,
+}
+
+#[derive(Default)]
+struct HedgeStats {
+    /// Requests that outlived the hedge delay and were duplicated
+    issued: AtomicU64,
+    /// Hedged requests answered first by the duplicate
+    wins: AtomicU64,
+}
+
+#[derive(Default)]
+struct AnalyticsStats {
+    /// Heavy reads answered by the analytics replica
+    routed: AtomicU64,
+    /// Heavy reads sent back to the normal route because the replica failed
+    fallbacks: AtomicU64,
+}
+
+/// Analytics replica from `ANALYTICS_NODE`, a physical node ID listed in `NODES`.
+fn analytics_addr(node_addresses: &HashMap<u32, String>) -> Option<String> {
+    let node_id = std::env::var("ANALYTICS_NODE").ok()?.parse::<u32>().ok()?;
+    let addr = node_addresses.get(&node_id).cloned();
+    if addr.is_none() {
+        tracing::warn!(
+            "ANALYTICS_NODE {} is not listed in NODES; ignoring it",
+            node_id
+        );
+    }
+    addr
+}
+
+/// Heavy, read-only queries that the analytics replica serves from its local copy of
+/// every shard: dashboard scans and statistics, and graph exports.
+fn is_analytics_query(method: &axum::http::Method, path: &str) -> bool {
+    const DASHBOARD_SCANS: &[&str] = &[
+        "v1/dashboard/stats",
+        "v1/dashboard/memories",
+        "v1/dashboard/graph",
+        "v1/dashboard/usage/storage",
+        "v1/dashboard/emotions",
+        "v1/dashboard/map",
+    ];
+    *method == axum::http::Method::GET
+        && (DASHBOARD_SCANS.contains(&path)
+            || (extract_routing_key(path).is_some()
+                && (path.ends_with("/graph/export") || path.ends_with("/graph/diff"))))
+}
+
+/// Hedge delay from `GATEWAY_HEDGE_DELAY_MS`; set it to the backend's P95 read latency.
+fn hedge_delay() -> Option<Duration> {
+    std::env::var("GATEWAY_HEDGE_DELAY_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&ms| ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// Reads that are safe to send twice: plain GETs. Retrieve calls are not, since each one
+/// records usage, slow-query and experiment exposure entries on the node serving it.
+fn is_hedgeable(method: &axum::http::Method) -> bool {
+    *method == axum::http::Method::GET
+}
+
+fn is_success(res: &reqwest::Result<reqwest::Response>) -> bool {
+    res.as_ref().is_ok_and(|resp| resp.status().is_success())
 }
 
 fn max_body_bytes() -> usize {
@@ -222,19 +297,119 @@ impl AppState {
             cache.get(&shard_id).cloned()
         };
 
-        if let Some((leader_node, inserted_at)) = leader {
+        if let Some((leader_addr, inserted_at)) = leader {
             if Instant::now().duration_since(inserted_at) > LEADER_CACHE_TTL {
                 // Stale entry — evict it
                 let mut cache = self.shard_leaders.write().await;
                 cache.remove(&shard_id);
-            } else if let Some(addr) = self.node_addresses.get(&leader_node) {
-                return Some(addr.clone());
+            } else {
+                return Some(leader_addr);
             }
         }
 
         // Fallback: pick any node
         self.node_addresses.values().next().cloned()
     }
+
+    /// Send a request to `primary`. When hedging is enabled and the primary has not
+    /// answered within the hedge delay, the same request goes to another node and the
+    /// first successful response is returned. When neither succeeds, the primary's
+    /// answer is returned, so a "Not Leader" redirect still reaches the retry loop.
+    async fn send_hedged(
+        &self,
+        hedgeable: bool,
+        primary: &str,
+        build: impl Fn(&str) -> reqwest::RequestBuilder,
+    ) -> reqwest::Result<reqwest::Response> {
+        let primary_req = build(primary).send();
+        let hedge_target = self
+            .hedge_delay
+            .filter(|_| hedgeable)
+            .zip(self.next_hedge_target(primary));
+        let Some((delay, hedge_addr)) = hedge_target else {
+            return primary_req.await;
+        };
+
+        tokio::pin!(primary_req);
+        tokio::select! {
+            res = &mut primary_req => return res,
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        tracing::debug!("Hedging request to {} after {:?}", hedge_addr, delay);
+        self.hedge_stats.issued.fetch_add(1, Ordering::Relaxed);
+        let hedge_req = build(&hedge_addr).send();
+        tokio::pin!(hedge_req);
+
+        tokio::select! {
+            res = &mut primary_req => {
+                if is_success(&res) {
+                    return res;
+                }
+                let hedged = hedge_req.await;
+                if is_success(&hedged) {
+                    self.hedge_stats.wins.fetch_add(1, Ordering::Relaxed);
+                    return hedged;
+                }
+                res
+            }
+            hedged = &mut hedge_req => {
+                if is_success(&hedged) {
+                    self.hedge_stats.wins.fetch_add(1, Ordering::Relaxed);
+                    return hedged;
+                }
+                primary_req.await
+            }
+        }
+    }
+
+    /// The node to hedge a request for `primary` to, rotating through the others so
+    /// duplicated load is spread over the cluster.
+    fn next_hedge_target(&self, primary: &str) -> Option<String> {
+        let mut candidates = self
+            .node_addresses
+            .iter()
+            .filter(|(_, addr)| addr.as_str() != primary)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        candidates.sort_by_key(|(node_id, _)| **node_id);
+        let index = self.hedge_cursor.fetch_add(1, Ordering::Relaxed) % candidates.len();
+        Some(candidates[index].1.clone())
+    }
+
+    /// Resolve the leader address from a "Not Leader" response. The HTTP address
+    /// the leader advertises in Raft membership is authoritative; the node IDs are
+    /// only mapped through `NODES` for servers that do not report one.
+    fn leader_addr_from_not_leader(&self, json: &serde_json::Value) -> Option<String> {
+        if let Some(addr) = json["leader_http_addr"].as_str().filter(|a| !a.is_empty()) {
+            return Some(normalize_node_addr(addr));
+        }
+        // Try leader_physical_node first (sharded response),
+        // but only trust it when > 0 (0 means leader unknown).
+        if let Some(leader_node) = json["leader_physical_node"].as_u64().filter(|&n| n > 0) {
+            if let Some(addr) = self.node_addresses.get(&(leader_node as u32)) {
+                return Some(addr.clone());
+            }
+        }
+        // Fallback: current_leader is a raw Raft node ID,
+        // decode it to extract the physical_node_id.
+        let (_leader_shard, physical_node_id) =
+            decode_raft_node_id(json["current_leader"].as_u64()?);
+        if physical_node_id > 0 {
+            return self.node_addresses.get(&physical_node_id).cloned();
+        }
+        None
+    }
+}
+
+fn normalize_node_addr(addr: &str) -> String {
+    if addr.starts_with("http") {
+        addr.to_string()
+    } else {
+        format!("http://{}", addr)
+    }
 }
 
 #[tokio::main]
@@ -255,12 +430,7 @@ async fn main() {
             let parts: Vec<&str> = entry.trim().splitn(2, '=').collect();
             if parts.len() == 2 {
                 let id: u32 = parts[0].parse().ok()?;
-                let addr = if parts[1].starts_with("http") {
-                    parts[1].to_string()
-                } else {
-                    format!("http://{}", parts[1])
-                };
-                Some((id, addr))
+                Some((id, normalize_node_addr(parts[1])))
             } else {
                 None
             }
@@ -274,6 +444,7 @@ async fn main() {
         node_addresses
     );
 
+    let analytics_addr = analytics_addr(&node_addresses);
     let state = Arc::new(AppState {
         shard_count,
         node_addresses,
@@ -283,9 +454,23 @@ async fn main() {
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .expect("Failed to build gateway HTTP client"),
+        hedge_delay: hedge_delay(),
+        hedge_stats: HedgeStats::default(),
+        hedge_cursor: AtomicUsize::new(0),
+        analytics_addr,
+        analytics_stats: AnalyticsStats::default(),
     });
+    if let Some(delay) = state.hedge_delay {
+        tracing::info!("Hedging idempotent reads after {:?}", delay);
+    }
+    if let Some(addr) = &state.analytics_addr {
+        tracing::info!("Routing heavy reads to the analytics replica at {}", addr);
+    }
 
-    let app = Router::new().fallback(proxy_handler).with_state(state);
+    let app = Router::new()
+        .route("/gateway/metrics", get(gateway_metrics))
+        .fallback(proxy_handler)
+        .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
     tracing::info!(
@@ -297,6 +482,30 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
+async fn gateway_metrics(State(state): State<Arc<AppState>>) -> Json<Value> {
+    let issued = state.hedge_stats.issued.load(Ordering::Relaxed);
+    let wins = state.hedge_stats.wins.load(Ordering::Relaxed);
+    let win_rate = if issued > 0 {
+        wins as f64 / issued as f64
+    } else {
+        0.0
+    };
+    Json(serde_json::json!({
+        "hedging": {
+            "enabled": state.hedge_delay.is_some(),
+            "delay_ms": state.hedge_delay.map(|d| d.as_millis() as u64),
+            "issued": issued,
+            "wins": wins,
+            "win_rate": win_rate,
+        },
+        "analytics": {
+            "enabled": state.analytics_addr.is_some(),
+            "routed": state.analytics_stats.routed.load(Ordering::Relaxed),
+            "fallbacks": state.analytics_stats.fallbacks.load(Ordering::Relaxed),
+        }
+    }))
+}
+
 async fn proxy_handler(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -339,6 +548,76 @@ fn extract_routing_key(path: &str) -> Option<&str> {
     }
 }
 
+/// Stream a backend response back to the client with its status and headers.
+fn forward_response(resp: reqwest::Response) -> Response {
+    let status = resp.status();
+    let res_headers = resp.headers().clone();
+    let res_body = axum::body::Body::from_stream(resp.bytes_stream());
+    let mut response = res_body.into_response();
+    *response.status_mut() = status;
+    for (k, v) in res_headers {
+        if let Some(k) = k {
+            response.headers_mut().insert(k, v);
+        }
+    }
+    response
+}
+
+/// Send a heavy read to the analytics replica. Returns `None` when the request is not
+/// one, or when the replica is unreachable or fails, so it takes the normal route.
+async fn try_analytics_replica(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &axum::http::Method,
+    path: &str,
+    query: Option<&str>,
+) -> Option<Response> {
+    let addr = state.analytics_addr.as_deref()?;
+    if !is_analytics_query(method, path) {
+        return None;
+    }
+    let url = match query {
+        Some(q) => format!("{}/{}?{}", addr, path, q),
+        None => format!("{}/{}", addr, path),
+    };
+    let mut builder = state.http_client.request(method.clone(), url);
+    for (key, value) in headers {
+        if key.as_str() != "host" {
+            builder = builder.header(key, value);
+        }
+    }
+    match builder.send().await {
+        Ok(resp) if !resp.status().is_server_error() => {
+            state.analytics_stats.routed.fetch_add(1, Ordering::Relaxed);
+            Some(forward_response(resp))
+        }
+        Ok(resp) => {
+            tracing::warn!(
+                "Analytics replica answered '{}' with {}; using the normal route",
+                path,
+                resp.status()
+            );
+            state
+                .analytics_stats
+                .fallbacks
+                .fetch_add(1, Ordering::Relaxed);
+            None
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Analytics replica unreachable for '{}': {}; using the normal route",
+                path,
+                e
+            );
+            state
+                .analytics_stats
+                .fallbacks
+                .fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
 async fn proxy_request_with_retry(
     state: Arc<AppState>,
     headers: HeaderMap,
@@ -347,6 +626,12 @@ async fn proxy_request_with_retry(
     query: Option<String>,
     body: Option<Bytes>,
 ) -> Response {
+    if let Some(response) =
+        try_analytics_replica(&state, &headers, &method, path, query.as_deref()).await
+    {
+        return response;
+    }
+
     // Route based on user_id hash
     let routing_key = extract_routing_key(path);
     if routing_key.is_none() && method == axum::http::Method::GET {
@@ -361,6 +646,7 @@ async fn proxy_request_with_retry(
     let mut target_addr: Option<String> = state.resolve_shard_addr(shard_id).await;
 
     let client = &state.http_client;
+    let hedgeable = is_hedgeable(&method);
     let max_retries = 3;
 
     for attempt in 0..max_retries {
@@ -378,11 +664,9 @@ async fn proxy_request_with_retry(
             },
         };
 
-        let target_url = format!("{}/{}", addr, path);
-        let target_uri_string = if let Some(ref q) = query {
-            format!("{}?{}", target_url, q)
-        } else {
-            target_url
+        let target_uri = |base: &str| match query {
+            Some(ref q) => format!("{}/{}?{}", base, path, q),
+            None => format!("{}/{}", base, path),
         };
 
         tracing::info!(
@@ -390,36 +674,29 @@ async fn proxy_request_with_retry(
             attempt + 1,
             path,
             shard_id,
-            target_uri_string
+            target_uri(&addr)
         );
 
-        let mut builder = client.request(method.clone(), &target_uri_string);
-        for (key, value) in &headers {
-            if key.as_str() != "host" && key.as_str() != "content-length" {
-                builder = builder.header(key, value);
+        let build = |base: &str| {
+            let mut builder = client.request(method.clone(), target_uri(base));
+            for (key, value) in &headers {
+                if key.as_str() != "host" && key.as_str() != "content-length" {
+                    builder = builder.header(key, value);
+                }
             }
-        }
-
-        if let Some(ref bytes) = body {
-            builder = builder.body(bytes.clone());
-        }
+            if let Some(ref bytes) = body {
+                builder = builder.body(bytes.clone());
+            }
+            builder
+        };
 
-        match builder.send().await {
+        match state.send_hedged(hedgeable, &addr, build).await {
             Ok(resp) => {
                 let status = resp.status();
 
                 // Stop retrying on client errors (4xx) - return immediately
                 if status.is_client_error() {
-                    let res_headers = resp.headers().clone();
-                    let res_body = axum::body::Body::from_stream(resp.bytes_stream());
-                    let mut response = res_body.into_response();
-                    *response.status_mut() = status;
-                    for (k, v) in res_headers {
-                        if let Some(k) = k {
-                            response.headers_mut().insert(k, v);
-                        }
-                    }
-                    return response;
+                    return forward_response(resp);
                 }
 
                 // RAFT REDIRECTION LOGIC
@@ -427,36 +704,13 @@ async fn proxy_request_with_retry(
                     let res_bytes = resp.bytes().await.unwrap_or_default();
                     if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&res_bytes) {
                         if json["error"] == "Not Leader" {
-                            // Try leader_physical_node first (sharded response),
-                            // but only trust it when > 0 (0 means leader unknown) and the
-                            // node actually exists in our config.
-                            if let Some(leader_node) = json["leader_physical_node"].as_u64() {
-                                let leader_node = leader_node as u32;
-                                if leader_node > 0
-                                    && state.node_addresses.contains_key(&leader_node)
-                                {
-                                    let mut cache = state.shard_leaders.write().await;
-                                    cache.insert(shard_id, (leader_node, Instant::now()));
-                                    target_addr = state.node_addresses.get(&leader_node).cloned();
-                                    continue;
-                                }
-                            }
-                            // Fallback: current_leader is a raw Raft node ID,
-                            // decode it to extract the physical_node_id.
-                            if let Some(raft_leader_id) = json["current_leader"].as_u64() {
-                                let (_leader_shard, physical_node_id) =
-                                    decode_raft_node_id(raft_leader_id);
-                                if physical_node_id > 0
-                                    && state.node_addresses.contains_key(&physical_node_id)
-                                {
-                                    let mut cache = state.shard_leaders.write().await;
-                                    cache.insert(shard_id, (physical_node_id, Instant::now()));
-                                    target_addr =
-                                        state.node_addresses.get(&physical_node_id).cloned();
-                                    continue;
-                                }
+                            if let Some(leader_addr) = state.leader_addr_from_not_leader(&json) {
+                                let mut cache = state.shard_leaders.write().await;
+                                cache.insert(shard_id, (leader_addr.clone(), Instant::now()));
+                                target_addr = Some(leader_addr);
+                                continue;
                             }
-                            // Leader unknown or not in our node list - clear stale cache and retry
+                            // Leader unknown or not resolvable - clear stale cache and retry
                             tracing::warn!(
                                 "Shard {} has no known leader, clearing cache and retrying",
                                 shard_id
@@ -487,16 +741,7 @@ async fn proxy_request_with_retry(
                 }
 
                 // SUCCESS or other server error: Return to client
-                let res_headers = resp.headers().clone();
-                let res_body = axum::body::Body::from_stream(resp.bytes_stream());
-                let mut response = res_body.into_response();
-                *response.status_mut() = status;
-                for (k, v) in res_headers {
-                    if let Some(k) = k {
-                        response.headers_mut().insert(k, v);
-                    }
-                }
-                return response;
+                return forward_response(resp);
             }
             Err(e) => {
                 tracing::error!("Proxy attempt {} failed: {}", attempt + 1, e);
@@ -540,6 +785,243 @@ mod tests {
         assert_eq!(extract_routing_key("invalid/path"), None);
     }
 
+    #[test]
+    fn test_leader_addr_prefers_advertised_http_addr() {
+        let state = AppState {
+            shard_count: 2,
+            node_addresses: HashMap::from([
+                (1, "http://10.0.0.1:3000".to_string()),
+                (2, "http://10.0.0.2:3000".to_string()),
+            ]),
+            shard_leaders: RwLock::new(HashMap::new()),
+            http_client: reqwest::Client::new(),
+            hedge_delay: None,
+            hedge_stats: HedgeStats::default(),
+            hedge_cursor: AtomicUsize::new(0),
+            analytics_addr: None,
+            analytics_stats: AnalyticsStats::default(),
+        };
+
+        let moved = serde_json::json!({
+            "error": "Not Leader",
+            "current_leader": 1002,
+            "leader_physical_node": 2,
+            "leader_http_addr": "http://10.0.0.9:3000",
+        });
+        assert_eq!(
+            state.leader_addr_from_not_leader(&moved).as_deref(),
+            Some("http://10.0.0.9:3000")
+        );
+
+        let legacy = serde_json::json!({
+            "error": "Not Leader",
+            "current_leader": 1002,
+            "leader_physical_node": 2,
+        });
+        assert_eq!(
+            state.leader_addr_from_not_leader(&legacy).as_deref(),
+            Some("http://10.0.0.2:3000")
+        );
+
+        let unknown = serde_json::json!({
+            "error": "Not Leader",
+            "current_leader": null,
+            "leader_physical_node": 0,
+            "leader_http_addr": null,
+        });
+        assert_eq!(state.leader_addr_from_not_leader(&unknown), None);
+    }
+
+    #[test]
+    fn test_is_hedgeable_only_for_reads() {
+        use axum::http::Method;
+        assert!(is_hedgeable(&Method::GET));
+        // Retrieval records usage and experiment exposures, so it is not sent twice.
+        assert!(!is_hedgeable(&Method::POST));
+        assert!(!is_hedgeable(&Method::DELETE));
+    }
+
+    async fn spawn_backend(name: &'static str, delay: Duration) -> String {
+        spawn_backend_with_status(name, delay, StatusCode::OK).await
+    }
+
+    async fn spawn_backend_with_status(
+        name: &'static str,
+        delay: Duration,
+        status: StatusCode,
+    ) -> String {
+        let app = Router::new().fallback(move || async move {
+            tokio::time::sleep(delay).await;
+            (status, name)
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_send_hedged_returns_first_response() {
+        let slow = spawn_backend("slow", Duration::from_secs(5)).await;
+        let fast = spawn_backend("fast", Duration::ZERO).await;
+        let state = AppState {
+            shard_count: 1,
+            node_addresses: HashMap::from([(1, slow.clone()), (2, fast)]),
+            shard_leaders: RwLock::new(HashMap::new()),
+            http_client: reqwest::Client::builder().no_proxy().build().unwrap(),
+            hedge_delay: Some(Duration::from_millis(50)),
+            hedge_stats: HedgeStats::default(),
+            hedge_cursor: AtomicUsize::new(0),
+            analytics_addr: None,
+            analytics_stats: AnalyticsStats::default(),
+        };
+        let build = |base: &str| state.http_client.get(format!("{}/v1/stats", base));
+
+        let resp = state.send_hedged(true, &slow, build).await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "fast");
+        assert_eq!(state.hedge_stats.issued.load(Ordering::Relaxed), 1);
+        assert_eq!(state.hedge_stats.wins.load(Ordering::Relaxed), 1);
+
+        // Writes are never duplicated, so the slow primary is awaited.
+        let timed_out = tokio::time::timeout(
+            Duration::from_millis(200),
+            state.send_hedged(false, &slow, build),
+        )
+        .await;
+        assert!(timed_out.is_err());
+        assert_eq!(state.hedge_stats.issued.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_hedged_waits_for_a_success_status() {
+        let failing = spawn_backend_with_status(
+            "failing",
+            Duration::from_millis(100),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+        .await;
+        let erroring =
+            spawn_backend_with_status("erroring", Duration::ZERO, StatusCode::BAD_GATEWAY).await;
+        let healthy = spawn_backend("healthy", Duration::from_millis(300)).await;
+        let mut state = AppState {
+            shard_count: 1,
+            node_addresses: HashMap::from([(1, failing.clone()), (2, healthy)]),
+            shard_leaders: RwLock::new(HashMap::new()),
+            http_client: reqwest::Client::builder().no_proxy().build().unwrap(),
+            hedge_delay: Some(Duration::from_millis(50)),
+            hedge_stats: HedgeStats::default(),
+            hedge_cursor: AtomicUsize::new(0),
+            analytics_addr: None,
+            analytics_stats: AnalyticsStats::default(),
+        };
+        let build = |base: &str| reqwest::Client::new().get(format!("{}/v1/stats", base));
+
+        // The primary fails first, so the slower but healthy hedge is awaited.
+        let resp = state.send_hedged(true, &failing, build).await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "healthy");
+        assert_eq!(state.hedge_stats.wins.load(Ordering::Relaxed), 1);
+
+        // A failed hedge does not win either; the primary's answer is returned.
+        state.node_addresses = HashMap::from([(1, failing.clone()), (2, erroring)]);
+        let resp = state.send_hedged(true, &failing, build).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(resp.text().await.unwrap(), "failing");
+        assert_eq!(state.hedge_stats.wins.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_hedge_target_rotates_over_other_nodes() {
+        let state = AppState {
+            shard_count: 1,
+            node_addresses: HashMap::from([
+                (1, "http://10.0.0.1:3000".to_string()),
+                (2, "http://10.0.0.2:3000".to_string()),
+                (3, "http://10.0.0.3:3000".to_string()),
+            ]),
+            shard_leaders: RwLock::new(HashMap::new()),
+            http_client: reqwest::Client::new(),
+            hedge_delay: Some(Duration::from_millis(50)),
+            hedge_stats: HedgeStats::default(),
+            hedge_cursor: AtomicUsize::new(0),
+            analytics_addr: None,
+            analytics_stats: AnalyticsStats::default(),
+        };
+        let targets = (0..4)
+            .map(|_| state.next_hedge_target("http://10.0.0.1:3000").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![
+                "http://10.0.0.2:3000",
+                "http://10.0.0.3:3000",
+                "http://10.0.0.2:3000",
+                "http://10.0.0.3:3000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_analytics_query_only_for_heavy_reads() {
+        use axum::http::Method;
+        assert!(is_analytics_query(&Method::GET, "v1/dashboard/stats"));
+        assert!(is_analytics_query(
+            &Method::GET,
+            "v1/users/alice/graph/export"
+        ));
+        assert!(is_analytics_query(
+            &Method::GET,
+            "v1/users/alice/graph/diff"
+        ));
+        assert!(!is_analytics_query(
+            &Method::GET,
+            "v1/dashboard/cluster/status"
+        ));
+        assert!(!is_analytics_query(
+            &Method::GET,
+            "v1/users/alice/communities"
+        ));
+        assert!(!is_analytics_query(&Method::POST, "v1/dashboard/stats"));
+    }
+
+    #[tokio::test]
+    async fn test_analytics_replica_serves_heavy_reads_and_falls_back() {
+        let replica = spawn_backend("replica", Duration::ZERO).await;
+        let mut state = AppState {
+            shard_count: 1,
+            node_addresses: HashMap::new(),
+            shard_leaders: RwLock::new(HashMap::new()),
+            http_client: reqwest::Client::builder().no_proxy().build().unwrap(),
+            hedge_delay: None,
+            hedge_stats: HedgeStats::default(),
+            hedge_cursor: AtomicUsize::new(0),
+            analytics_addr: Some(replica),
+            analytics_stats: AnalyticsStats::default(),
+        };
+        let headers = HeaderMap::new();
+        let get = axum::http::Method::GET;
+
+        let served = try_analytics_replica(&state, &headers, &get, "v1/dashboard/stats", None)
+            .await
+            .unwrap();
+        let body = to_bytes(served.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"replica");
+        assert!(
+            try_analytics_replica(&state, &headers, &get, "v1/users/alice/tasks/tree", None)
+                .await
+                .is_none()
+        );
+        assert_eq!(state.analytics_stats.routed.load(Ordering::Relaxed), 1);
+
+        // An unreachable replica hands the request back to the normal route.
+        state.analytics_addr = Some("http://127.0.0.1:1".to_string());
+        assert!(
+            try_analytics_replica(&state, &headers, &get, "v1/dashboard/stats", None)
+                .await
+                .is_none()
+        );
+        assert_eq!(state.analytics_stats.fallbacks.load(Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_shard_routing_determinism() {
         let shard_count = 3;
@@ -0,0 +1,567 @@
+//! Node.js bindings for the Memorose HTTP API.
+//!
+//! `napi build` turns the `#[napi]` items below into a native addon plus an
+//! `index.d.ts`, so TypeScript agents get typed request and response objects.
+//! Field names follow JavaScript conventions (`contentType`, `tokenBudget`, ...).
+//! The objects are converted to and from the `memorose_types::client` structs, which
+//! own the wire format, so the bindings cannot drift from the Rust client.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use memorose_types::client;
+use memorose_types::TaskPriority;
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const DEFAULT_TIMEOUT_MS: u32 = 30_000;
+
+#[napi(object)]
+pub struct ClientOptions {
+    /// Server root, e.g. `http://localhost:3000`.
+    pub base_url: String,
+    /// Sent as `Authorization: Bearer <api_key>` when set.
+    pub api_key: Option<String>,
+    pub timeout_ms: Option<u32>,
+}
+
+#[napi(string_enum = "snake_case")]
+#[derive(Debug, PartialEq)]
+pub enum MemoryType {
+    Factual,
+    Procedural,
+}
+
+impl From<memorose_types::MemoryType> for MemoryType {
+    fn from(memory_type: memorose_types::MemoryType) -> Self {
+        match memory_type {
+            memorose_types::MemoryType::Factual => Self::Factual,
+            memorose_types::MemoryType::Procedural => Self::Procedural,
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct IngestEventRequest {
+    pub content: String,
+    /// `text` (default), `image`, `audio`, `video` or `json`.
+    pub content_type: Option<String>,
+    pub org_id: Option<String>,
+    pub level: Option<u8>,
+    pub parent_id: Option<String>,
+    pub task_status: Option<String>,
+    pub task_progress: Option<f64>,
+    /// RFC 3339 timestamp.
+    pub task_due_at: Option<String>,
+    /// Cron-ish schedule such as `daily` or `every 2h`.
+    pub task_recurrence: Option<String>,
+    /// `low`, `normal`, `high` or `urgent`.
+    pub task_priority: Option<String>,
+}
+
+impl TryFrom<IngestEventRequest> for client::IngestEventRequest {
+    type Error = Error;
+
+    fn try_from(request: IngestEventRequest) -> Result<Self> {
+        let task_priority = request
+            .task_priority
+            .as_deref()
+            .map(|priority| {
+                TaskPriority::parse(priority)
+                    .ok_or_else(|| Error::from_reason(format!("Invalid task priority: {priority}")))
+            })
+            .transpose()?;
+        Ok(Self {
+            content_type: request.content_type.unwrap_or_else(|| "text".to_string()),
+            org_id: request.org_id,
+            level: request.level,
+            parent_id: request.parent_id,
+            task_status: request.task_status,
+            task_progress: request.task_progress.map(|progress| progress as f32),
+            task_due_at: parse_timestamp("taskDueAt", request.task_due_at)?,
+            task_recurrence: request.task_recurrence,
+            task_priority,
+            ..Self::text(request.content)
+        })
+    }
+}
+
+#[napi(object)]
+pub struct IngestEventResponse {
+    pub status: String,
+    pub event_id: String,
+}
+
+impl From<client::IngestEventResponse> for IngestEventResponse {
+    fn from(response: client::IngestEventResponse) -> Self {
+        Self {
+            status: response.status,
+            event_id: response.event_id.to_string(),
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct RetrieveRequest {
+    pub query: String,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub enable_arbitration: Option<bool>,
+    pub min_score: Option<f64>,
+    pub token_budget: Option<u32>,
+    pub graph_depth: Option<u32>,
+    pub recency_bias: Option<f64>,
+    pub mmr_lambda: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub as_of: Option<String>,
+    pub org_id: Option<String>,
+    pub agent_id: Option<String>,
+}
+
+impl TryFrom<RetrieveRequest> for client::RetrieveRequest {
+    type Error = Error;
+
+    fn try_from(request: RetrieveRequest) -> Result<Self> {
+        let defaults = Self::new(request.query);
+        Ok(Self {
+            limit: request.limit.map_or(defaults.limit, |limit| limit as usize),
+            offset: request.offset.map(|offset| offset as usize),
+            enable_arbitration: request.enable_arbitration,
+            min_score: request.min_score.map(|score| score as f32),
+            token_budget: request.token_budget.map(|budget| budget as usize),
+            graph_depth: request.graph_depth.map(|depth| depth as usize),
+            recency_bias: request.recency_bias.map(|bias| bias as f32),
+            mmr_lambda: request.mmr_lambda.map(|lambda| lambda as f32),
+            max_tokens: request.max_tokens.map(|tokens| tokens as usize),
+            start_time: parse_timestamp("startTime", request.start_time)?,
+            end_time: parse_timestamp("endTime", request.end_time)?,
+            as_of: parse_timestamp("asOf", request.as_of)?,
+            org_id: request.org_id,
+            agent_id: request.agent_id,
+            ..defaults
+        })
+    }
+}
+
+#[napi(object)]
+pub struct RetrievedMemory {
+    pub id: String,
+    pub memory_type: MemoryType,
+    pub content: String,
+    pub keywords: Vec<String>,
+    pub level: u8,
+}
+
+impl From<client::RetrievedMemory> for RetrievedMemory {
+    fn from(memory: client::RetrievedMemory) -> Self {
+        Self {
+            id: memory.id.to_string(),
+            memory_type: memory.memory_type.into(),
+            content: memory.content,
+            keywords: memory.keywords,
+            level: memory.level,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct RetrieveResultItem {
+    pub unit: RetrievedMemory,
+    pub score: f64,
+}
+
+impl From<client::RetrieveResultItem> for RetrieveResultItem {
+    fn from(item: client::RetrieveResultItem) -> Self {
+        Self {
+            unit: item.unit.into(),
+            score: item.score as f64,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct RetrieveResponse {
+    pub stream_id: String,
+    pub query: String,
+    pub results: Vec<RetrieveResultItem>,
+    pub next_offset: Option<u32>,
+    pub query_time_ms: f64,
+}
+
+impl From<client::RetrieveResponse> for RetrieveResponse {
+    fn from(response: client::RetrieveResponse) -> Self {
+        Self {
+            stream_id: response.stream_id.to_string(),
+            query: response.query,
+            results: response.results.into_iter().map(Into::into).collect(),
+            next_offset: response.next_offset.map(|offset| offset as u32),
+            query_time_ms: response.query_time_ms as f64,
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct MemoryContextRequest {
+    pub user_id: String,
+    pub query: String,
+    pub limit: Option<u32>,
+    pub enable_arbitration: Option<bool>,
+    pub min_score: Option<f64>,
+    pub token_budget: Option<u32>,
+    pub graph_depth: Option<u32>,
+    pub org_id: Option<String>,
+    pub agent_id: Option<String>,
+    /// `text` (default) or `xml`.
+    pub format: Option<String>,
+}
+
+impl From<MemoryContextRequest> for client::MemoryContextRequest {
+    fn from(request: MemoryContextRequest) -> Self {
+        Self {
+            user_id: request.user_id,
+            query: request.query,
+            limit: request.limit.map(|limit| limit as usize),
+            enable_arbitration: request.enable_arbitration,
+            min_score: request.min_score.map(|score| score as f32),
+            token_budget: request.token_budget.map(|budget| budget as usize),
+            graph_depth: request.graph_depth.map(|depth| depth as usize),
+            org_id: request.org_id,
+            agent_id: request.agent_id,
+            format: request.format,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct MemoryContextHit {
+    pub id: String,
+    pub level: u8,
+    pub memory_type: MemoryType,
+    pub domain: String,
+    pub score: f64,
+}
+
+impl From<client::MemoryContextHit> for MemoryContextHit {
+    fn from(hit: client::MemoryContextHit) -> Self {
+        Self {
+            id: hit.id.to_string(),
+            level: hit.level,
+            memory_type: hit.memory_type.into(),
+            domain: hit.domain,
+            score: hit.score as f64,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct MemoryContextResponse {
+    pub query: String,
+    pub format: String,
+    pub strategy: String,
+    pub token_budget: u32,
+    pub used_token_estimate: u32,
+    pub matched_count: u32,
+    pub included_count: u32,
+    pub truncated: bool,
+    pub context: String,
+    pub hits: Vec<MemoryContextHit>,
+    pub query_time_ms: f64,
+}
+
+impl From<client::MemoryContextResponse> for MemoryContextResponse {
+    fn from(response: client::MemoryContextResponse) -> Self {
+        Self {
+            query: response.query,
+            format: response.format,
+            strategy: response.strategy,
+            token_budget: response.token_budget as u32,
+            used_token_estimate: response.used_token_estimate as u32,
+            matched_count: response.matched_count as u32,
+            included_count: response.included_count as u32,
+            truncated: response.truncated,
+            context: response.context,
+            hits: response.hits.into_iter().map(Into::into).collect(),
+            query_time_ms: response.query_time_ms as f64,
+        }
+    }
+}
+
+/// Client for a Memorose server (or the gateway in front of a cluster).
+#[napi]
+pub struct MemoroseClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+#[napi]
+impl MemoroseClient {
+    #[napi(constructor)]
+    pub fn new(options: ClientOptions) -> Result<Self> {
+        let timeout =
+            Duration::from_millis(options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS) as u64);
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(to_napi_error)?;
+        Ok(Self {
+            http,
+            base_url: options.base_url.trim_end_matches('/').to_string(),
+            api_key: options.api_key.filter(|key| !key.is_empty()),
+        })
+    }
+
+    /// Append an event to a stream; consolidation into memories happens asynchronously.
+    #[napi]
+    pub async fn ingest_event(
+        &self,
+        user_id: String,
+        stream_id: String,
+        request: IngestEventRequest,
+    ) -> Result<IngestEventResponse> {
+        let request = client::IngestEventRequest::try_from(request)?;
+        let path = format!("/v1/users/{user_id}/streams/{stream_id}/events");
+        let response: client::IngestEventResponse = self.post_json(&path, &request).await?;
+        Ok(response.into())
+    }
+
+    #[napi]
+    pub async fn retrieve(
+        &self,
+        user_id: String,
+        stream_id: String,
+        request: RetrieveRequest,
+    ) -> Result<RetrieveResponse> {
+        let request = client::RetrieveRequest::try_from(request)?;
+        let path = format!("/v1/users/{user_id}/streams/{stream_id}/retrieve");
+        let response: client::RetrieveResponse = self.post_json(&path, &request).await?;
+        Ok(response.into())
+    }
+
+    /// Retrieve and render memories as a prompt-ready context block.
+    #[napi]
+    pub async fn build_context(
+        &self,
+        request: MemoryContextRequest,
+    ) -> Result<MemoryContextResponse> {
+        let request = client::MemoryContextRequest::from(request);
+        let response: client::MemoryContextResponse =
+            self.post_json("/v1/memory/context", &request).await?;
+        Ok(response.into())
+    }
+
+    /// Move a memory to the trash; returns the server's JSON response.
+    #[napi]
+    pub async fn delete_memory(
+        &self,
+        user_id: String,
+        memory_id: String,
+    ) -> Result<serde_json::Value> {
+        let path = format!("/v1/users/{user_id}/memories/{memory_id}");
+        self.send(self.http.delete(self.url(&path))).await
+    }
+}
+
+impl MemoroseClient {
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn post_json<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.http.post(self.url(path)).json(body)).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let request = match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        };
+        let response = request.send().await.map_err(to_napi_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::from_reason(format!(
+                "Memorose request failed with {status}: {body}"
+            )));
+        }
+        response.json().await.map_err(to_napi_error)
+    }
+}
+
+fn to_napi_error(error: reqwest::Error) -> Error {
+    Error::from_reason(error.to_string())
+}
+
+/// Parse an optional RFC 3339 timestamp passed from JavaScript as `field`.
+fn parse_timestamp(field: &str, value: Option<String>) -> Result<Option<DateTime<Utc>>> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| Error::from_reason(format!("Invalid {field} {value:?}: {e}")))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The wire body of `request`, checked to decode back into the same shared struct.
+    fn wire_body<T>(request: &T) -> serde_json::Value
+    where
+        T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let body = serde_json::to_value(request).unwrap();
+        let decoded: T = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(&decoded, request);
+        body
+    }
+
+    #[test]
+    fn test_ingest_request_round_trips_through_the_shared_struct() {
+        let request = client::IngestEventRequest::try_from(IngestEventRequest {
+            content: "Ship the release".into(),
+            level: Some(3),
+            task_due_at: Some("2026-05-01T09:00:00+02:00".into()),
+            task_priority: Some("critical".into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            wire_body(&request),
+            json!({
+                "content": "Ship the release",
+                "content_type": "text",
+                "level": 3,
+                "task_due_at": "2026-05-01T07:00:00Z",
+                "task_priority": "urgent",
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_priority_and_timestamp_are_rejected() {
+        let priority = client::IngestEventRequest::try_from(IngestEventRequest {
+            content: "x".into(),
+            task_priority: Some("someday".into()),
+            ..Default::default()
+        });
+        assert!(priority.is_err());
+
+        let as_of = client::RetrieveRequest::try_from(RetrieveRequest {
+            query: "x".into(),
+            as_of: Some("yesterday".into()),
+            ..Default::default()
+        });
+        assert!(as_of.is_err());
+    }
+
+    #[test]
+    fn test_retrieve_and_context_requests_round_trip_through_the_shared_structs() {
+        let retrieve = client::RetrieveRequest::try_from(RetrieveRequest {
+            query: "travel plans".into(),
+            offset: Some(10),
+            graph_depth: Some(2),
+            start_time: Some("2026-01-01T00:00:00Z".into()),
+            agent_id: Some("planner".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            wire_body(&retrieve),
+            json!({
+                "query": "travel plans",
+                "limit": 10,
+                "offset": 10,
+                "graph_depth": 2,
+                "start_time": "2026-01-01T00:00:00Z",
+                "agent_id": "planner",
+            })
+        );
+
+        let context = client::MemoryContextRequest::from(MemoryContextRequest {
+            user_id: "alice".into(),
+            query: "travel plans".into(),
+            token_budget: Some(400),
+            format: Some("xml".into()),
+            ..Default::default()
+        });
+        assert_eq!(
+            wire_body(&context),
+            json!({
+                "user_id": "alice",
+                "query": "travel plans",
+                "token_budget": 400,
+                "format": "xml",
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_responses_decode_into_the_bindings() {
+        let memory_id = "6f1c1f0e-4a52-4c38-9a43-0f5fb0c2f6d1";
+        let retrieve: client::RetrieveResponse = serde_json::from_value(json!({
+            "stream_id": "0d7c3b9e-93a4-4a43-8f0e-9c7d1b2a3e4f",
+            "query": "travel plans",
+            "results": [{
+                "unit": {
+                    "id": memory_id,
+                    "memory_type": "procedural",
+                    "content": "Books trains over flights",
+                    "keywords": ["travel"],
+                    "level": 1,
+                },
+                "score": 0.5,
+            }],
+            "next_offset": 1,
+            "query_time_ms": 12,
+        }))
+        .unwrap();
+        let retrieve = RetrieveResponse::from(retrieve);
+        assert_eq!(retrieve.results[0].unit.id, memory_id);
+        assert_eq!(retrieve.results[0].unit.memory_type, MemoryType::Procedural);
+        assert_eq!(retrieve.results[0].score, 0.5);
+        assert_eq!(retrieve.next_offset, Some(1));
+        assert_eq!(retrieve.query_time_ms, 12.0);
+
+        let context: client::MemoryContextResponse = serde_json::from_value(json!({
+            "query": "travel plans",
+            "format": "text",
+            "strategy": "ranked",
+            "token_budget": 400,
+            "used_token_estimate": 120,
+            "matched_count": 1,
+            "included_count": 1,
+            "truncated": false,
+            "context": "- Books trains over flights",
+            "hits": [{
+                "id": memory_id,
+                "level": 1,
+                "memory_type": "factual",
+                "domain": "user",
+                "score": 0.25,
+            }],
+            "query_time_ms": 7,
+        }))
+        .unwrap();
+        let context = MemoryContextResponse::from(context);
+        assert_eq!(context.hits.len(), 1);
+        assert_eq!(context.hits[0].memory_type, MemoryType::Factual);
+        assert_eq!(context.hits[0].domain, "user");
+        assert_eq!(context.used_token_estimate, 120);
+    }
+}
@@ -0,0 +1,457 @@
+use async_trait::async_trait;
+use memorose_common::config::{CacheBackend, CacheConfig};
+use memorose_core::storage::kv::KvStore;
+use redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const PERSIST_QUEUE_CAPACITY: usize = 1_024;
+const PERSIST_BATCH_SIZE: usize = 256;
+const ENTRY_PREFIX: &str = "e:";
+const WRITTEN_AT_PREFIX: &str = "t:";
+
+/// Best-effort key-value cache. Backend failures read as misses and drop writes so a
+/// cache outage never fails the request that touched it.
+#[async_trait]
+pub trait Cache<V>: Send + Sync {
+    async fn get(&self, key: &str) -> Option<V>;
+    async fn insert(&self, key: String, value: V);
+}
+
+pub struct LocalCache<V: Clone + Send + Sync + 'static> {
+    inner: moka::future::Cache<String, V>,
+}
+
+impl<V: Clone + Send + Sync + 'static> LocalCache<V> {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        Self {
+            inner: moka::future::Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(capacity)
+                .build(),
+        }
+    }
+}
+
+#[async_trait]
+impl<V: Clone + Send + Sync + 'static> Cache<V> for LocalCache<V> {
+    async fn get(&self, key: &str) -> Option<V> {
+        self.inner.get(key).await
+    }
+
+    async fn insert(&self, key: String, value: V) {
+        self.inner.insert(key, value).await;
+    }
+}
+
+/// Values are stored as JSON under `{prefix}{name}:{key}` and expire after `ttl`.
+pub struct RedisCache<V> {
+    conn: redis::aio::ConnectionManager,
+    key_prefix: String,
+    ttl_secs: u64,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<V> RedisCache<V> {
+    pub async fn connect(url: &str, key_prefix: String, ttl: Duration) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        let conn = redis::aio::ConnectionManager::new(client).await?;
+        Ok(Self {
+            conn,
+            key_prefix,
+            ttl_secs: ttl.as_secs().max(1),
+            _value: PhantomData,
+        })
+    }
+}
+
+#[async_trait]
+impl<V: Serialize + DeserializeOwned + Send + Sync + 'static> Cache<V> for RedisCache<V> {
+    async fn get(&self, key: &str) -> Option<V> {
+        let mut conn = self.conn.clone();
+        let key = format!("{}{}", self.key_prefix, key);
+        match conn.get::<_, Option<Vec<u8>>>(&key).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).ok(),
+            Ok(None) => None,
+            Err(error) => {
+                tracing::warn!("Redis cache read failed for {}: {}", key, error);
+                None
+            }
+        }
+    }
+
+    async fn insert(&self, key: String, value: V) {
+        let Ok(bytes) = serde_json::to_vec(&value) else {
+            return;
+        };
+        let mut conn = self.conn.clone();
+        let key = format!("{}{}", self.key_prefix, key);
+        if let Err(error) = conn.set_ex::<_, _, ()>(&key, bytes, self.ttl_secs).await {
+            tracing::warn!("Redis cache write failed for {}: {}", key, error);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry<V> {
+    written_at: i64,
+    value: V,
+}
+
+/// In-memory cache backed by a local RocksDB directory that survives restarts.
+///
+/// Misses fall through to disk and promote what they find; inserts are written
+/// behind by a background thread, which also drops expired entries and the oldest
+/// ones beyond `max_entries`. Entries are kept under `e:{key}` with a `t:{written_at}:{key}`
+/// marker ordering them by age.
+pub struct PersistentCache<V: Clone + Send + Sync + 'static> {
+    memory: LocalCache<V>,
+    store: KvStore,
+    ttl: Duration,
+    writes: mpsc::Sender<(String, V)>,
+}
+
+impl<V> PersistentCache<V>
+where
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub fn open(
+        path: &Path,
+        capacity: u64,
+        ttl: Duration,
+        max_entries: usize,
+    ) -> anyhow::Result<Self> {
+        let store = KvStore::open(path)?;
+        let (writes, queue) = mpsc::channel(PERSIST_QUEUE_CAPACITY);
+        let writer = PersistWriter {
+            store: store.clone(),
+            ttl,
+            max_entries: max_entries.max(1),
+            entries: AtomicUsize::new(store.count_prefix(ENTRY_PREFIX.as_bytes())?),
+        };
+        std::thread::Builder::new()
+            .name("memorose-cache-persist".into())
+            .spawn(move || writer.run::<V>(queue))?;
+        Ok(Self {
+            memory: LocalCache::new(capacity, ttl),
+            store,
+            ttl,
+            writes,
+        })
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<Option<V>> {
+        let entry_key = format!("{}{}", ENTRY_PREFIX, key);
+        let Some(bytes) = self.store.get(entry_key.as_bytes())? else {
+            return Ok(None);
+        };
+        let entry: PersistedEntry<V> = serde_json::from_slice(&bytes)?;
+        if is_expired(entry.written_at, self.ttl) {
+            return Ok(None);
+        }
+        Ok(Some(entry.value))
+    }
+}
+
+#[async_trait]
+impl<V> Cache<V> for PersistentCache<V>
+where
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn get(&self, key: &str) -> Option<V> {
+        if let Some(value) = self.memory.get(key).await {
+            return Some(value);
+        }
+        match self.load(key) {
+            Ok(Some(value)) => {
+                self.memory.insert(key.to_string(), value.clone()).await;
+                Some(value)
+            }
+            Ok(None) => None,
+            Err(error) => {
+                tracing::warn!("Persisted cache read failed for {}: {}", key, error);
+                None
+            }
+        }
+    }
+
+    async fn insert(&self, key: String, value: V) {
+        self.memory.insert(key.clone(), value.clone()).await;
+        if self.writes.try_send((key, value)).is_err() {
+            tracing::debug!("Persisted cache queue full; entry kept in memory only");
+        }
+    }
+}
+
+struct PersistWriter {
+    store: KvStore,
+    ttl: Duration,
+    max_entries: usize,
+    entries: AtomicUsize,
+}
+
+impl PersistWriter {
+    fn run<V: Serialize>(self, mut queue: mpsc::Receiver<(String, V)>) {
+        while let Some(first) = queue.blocking_recv() {
+            let mut pending = vec![first];
+            while pending.len() < PERSIST_BATCH_SIZE {
+                match queue.try_recv() {
+                    Ok(item) => pending.push(item),
+                    Err(_) => break,
+                }
+            }
+            if let Err(error) = self.write(pending).and_then(|_| self.evict()) {
+                tracing::warn!("Persisted cache write failed: {}", error);
+            }
+        }
+    }
+
+    fn write<V: Serialize>(&self, pending: Vec<(String, V)>) -> anyhow::Result<()> {
+        let written_at = chrono::Utc::now().timestamp();
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut added = 0;
+        // Later inserts of the same key win.
+        let pending: HashMap<String, V> = pending.into_iter().collect();
+        for (key, value) in pending {
+            let entry_key = format!("{}{}", ENTRY_PREFIX, key);
+            match self.store.get(entry_key.as_bytes())? {
+                Some(previous) => {
+                    if let Ok(previous) =
+                        serde_json::from_slice::<PersistedEntry<serde::de::IgnoredAny>>(&previous)
+                    {
+                        batch.delete(written_at_key(previous.written_at, &key));
+                    }
+                }
+                None => added += 1,
+            }
+            let entry = PersistedEntry { written_at, value };
+            batch.put(entry_key.as_bytes(), serde_json::to_vec(&entry)?);
+            batch.put(written_at_key(written_at, &key), b"");
+        }
+        self.store.write_batch(batch)?;
+        self.entries.fetch_add(added, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Drop entries past their TTL, then the oldest ones beyond `max_entries`.
+    fn evict(&self) -> anyhow::Result<()> {
+        let over_limit = self
+            .entries
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.max_entries);
+        let oldest = self.store.scan_limited(
+            WRITTEN_AT_PREFIX.as_bytes(),
+            over_limit + PERSIST_BATCH_SIZE,
+        )?;
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut removed = 0;
+        for (marker, _) in oldest {
+            let Some((written_at, key)) = parse_written_at_key(&marker) else {
+                continue;
+            };
+            if removed >= over_limit && !is_expired(written_at, self.ttl) {
+                break;
+            }
+            batch.delete(&marker);
+            batch.delete(format!("{}{}", ENTRY_PREFIX, key).as_bytes());
+            removed += 1;
+        }
+        if removed > 0 {
+            self.store.write_batch(batch)?;
+            self.entries.fetch_sub(removed, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+fn is_expired(written_at: i64, ttl: Duration) -> bool {
+    chrono::Utc::now().timestamp() - written_at > ttl.as_secs() as i64
+}
+
+/// Zero-padded so markers sort by write time.
+fn written_at_key(written_at: i64, key: &str) -> Vec<u8> {
+    format!("{}{:020}:{}", WRITTEN_AT_PREFIX, written_at, key).into_bytes()
+}
+
+fn parse_written_at_key(marker: &[u8]) -> Option<(i64, String)> {
+    let marker = std::str::from_utf8(marker).ok()?;
+    let (written_at, key) = marker.strip_prefix(WRITTEN_AT_PREFIX)?.split_once(':')?;
+    Some((written_at.parse().ok()?, key.to_string()))
+}
+
+/// Build the cache called `name` for the configured backend. `capacity` bounds the
+/// in-memory backend only; Redis relies on its own eviction policy.
+pub async fn build<V>(
+    config: &CacheConfig,
+    name: &str,
+    capacity: u64,
+    ttl: Duration,
+) -> anyhow::Result<Arc<dyn Cache<V>>>
+where
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    match config.backend {
+        CacheBackend::Memory => Ok(Arc::new(LocalCache::new(capacity, ttl))),
+        CacheBackend::Redis => {
+            let url = config
+                .redis_url
+                .as_deref()
+                .filter(|url| !url.trim().is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("cache.backend = \"redis\" requires cache.redis_url")
+                })?;
+            let key_prefix = format!("{}{}:", config.redis_key_prefix, name);
+            Ok(Arc::new(RedisCache::connect(url, key_prefix, ttl).await?))
+        }
+    }
+}
+
+/// Like [`build`], but with `cache.persist_embeddings` the in-memory backend is backed
+/// by a RocksDB directory `{name}_cache` under `root_dir`. Redis already outlives restarts.
+pub async fn build_persistent<V>(
+    config: &CacheConfig,
+    name: &str,
+    capacity: u64,
+    ttl: Duration,
+    root_dir: &Path,
+) -> anyhow::Result<Arc<dyn Cache<V>>>
+where
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    if config.backend != CacheBackend::Memory || !config.persist_embeddings {
+        return build(config, name, capacity, ttl).await;
+    }
+    let path = root_dir.join(format!("{}_cache", name));
+    let max_entries = config.persisted_embedding_max_entries;
+    let cache = tokio::task::spawn_blocking(move || {
+        PersistentCache::open(&path, capacity, ttl, max_entries)
+    })
+    .await??;
+    Ok(Arc::new(cache))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_cache_round_trip() {
+        let cache = build::<Vec<f32>>(
+            &CacheConfig::default(),
+            "embedding",
+            10,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cache.get("query").await, None);
+        cache.insert("query".into(), vec![0.5, 1.0]).await;
+        assert_eq!(cache.get("query").await, Some(vec![0.5, 1.0]));
+    }
+
+    #[tokio::test]
+    async fn test_persistent_cache_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embedding_cache");
+        let ttl = Duration::from_secs(60);
+        {
+            let cache = PersistentCache::<Vec<f32>>::open(&path, 10, ttl, 100).unwrap();
+            cache.insert("query".into(), vec![0.25, 0.75]).await;
+            // Wait for the write-behind thread to reach disk.
+            for _ in 0..100 {
+                if cache.load("query").unwrap().is_some() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+
+        // The writer thread releases the RocksDB lock once it sees the queue close.
+        let mut reopened = PersistentCache::<Vec<f32>>::open(&path, 10, ttl, 100);
+        for _ in 0..100 {
+            if reopened.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            reopened = PersistentCache::open(&path, 10, ttl, 100);
+        }
+        let reopened = reopened.unwrap();
+        assert_eq!(reopened.get("query").await, Some(vec![0.25, 0.75]));
+        assert_eq!(reopened.get("other").await, None);
+    }
+
+    #[test]
+    fn test_persist_writer_evicts_oldest_beyond_max_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let writer = PersistWriter {
+            store: store.clone(),
+            ttl: Duration::from_secs(3_600),
+            max_entries: 2,
+            entries: AtomicUsize::new(0),
+        };
+        for key in ["a", "b", "c"] {
+            writer.write(vec![(key.to_string(), vec![1.0f32])]).unwrap();
+        }
+        writer.write(vec![("b".to_string(), vec![2.0f32])]).unwrap();
+        assert_eq!(writer.entries.load(Ordering::Relaxed), 3);
+
+        writer.evict().unwrap();
+        assert_eq!(store.count_prefix(ENTRY_PREFIX.as_bytes()).unwrap(), 2);
+        assert_eq!(store.count_prefix(WRITTEN_AT_PREFIX.as_bytes()).unwrap(), 2);
+        assert_eq!(writer.entries.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_written_at_key_round_trips_keys_with_colons() {
+        let marker = written_at_key(1_700_000_000, "what is 10:30?");
+        assert_eq!(
+            parse_written_at_key(&marker),
+            Some((1_700_000_000, "what is 10:30?".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redis_cache_requires_url() {
+        let config = CacheConfig {
+            backend: CacheBackend::Redis,
+            ..Default::default()
+        };
+        let result = build::<serde_json::Value>(&config, "dashboard", 10, Duration::from_secs(60));
+        assert!(result.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redis_cache_is_shared_between_instances() {
+        let Ok(url) = std::env::var("MEMOROSE_TEST_REDIS_URL") else {
+            return;
+        };
+        let config = CacheConfig {
+            backend: CacheBackend::Redis,
+            redis_url: Some(url),
+            redis_key_prefix: format!("memorose-test-{}:", uuid::Uuid::new_v4()),
+            ..Default::default()
+        };
+        let ttl = Duration::from_secs(60);
+        let node_a = build::<serde_json::Value>(&config, "dashboard", 10, ttl)
+            .await
+            .unwrap();
+        let node_b = build::<serde_json::Value>(&config, "dashboard", 10, ttl)
+            .await
+            .unwrap();
+
+        node_a
+            .insert("agents:list".into(), serde_json::json!({"total_count": 2}))
+            .await;
+        assert_eq!(
+            node_b.get("agents:list").await,
+            Some(serde_json::json!({"total_count": 2}))
+        );
+    }
+}
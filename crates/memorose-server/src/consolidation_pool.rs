@@ -0,0 +1,91 @@
+//! Leader side of the consolidation pool: leases batches to pool nodes and stores the
+//! batches they send back.
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use memorose_core::consolidation_pool::{ClaimRequest, CommitRequest};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::shard_manager::ShardState;
+use crate::{not_leader_response, AppState};
+
+fn error_response(status: StatusCode, error: String) -> axum::response::Response {
+    (status, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+/// The shard a pool request names, when `node_id` is in the pool and this node leads
+/// the shard.
+fn leader_shard(
+    state: &AppState,
+    shard_id: u32,
+    node_id: u32,
+) -> Result<&ShardState, axum::response::Response> {
+    let config = state.config.load();
+    if !config.consolidation_is_pooled() || !config.consolidation_pool.node_ids.contains(&node_id) {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            format!("Node {} is not in the consolidation pool", node_id),
+        ));
+    }
+    let Some(shard) = state.shard_manager.shard(shard_id) else {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            format!("Unknown shard {}", shard_id),
+        ));
+    };
+    if let Some(raft) = &shard.raft {
+        let metrics = raft.metrics().borrow().clone();
+        if metrics.current_leader != Some(metrics.id) {
+            return Err(not_leader_response(&config, &metrics));
+        }
+    }
+    Ok(shard)
+}
+
+pub(crate) async fn claim_consolidation_batch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ClaimRequest>,
+) -> axum::response::Response {
+    let shard = match leader_shard(&state, request.shard_id, request.node_id) {
+        Ok(shard) => shard,
+        Err(r) => return r,
+    };
+    let lease_for = Duration::from_secs(state.config.load().consolidation_pool.lease_secs);
+    match shard
+        .worker
+        .claim_consolidation_batch(request.node_id, lease_for)
+        .await
+    {
+        Ok(claim) => Json(claim).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub(crate) async fn commit_consolidation(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CommitRequest>,
+) -> axum::response::Response {
+    let shard = match leader_shard(&state, request.shard_id, request.node_id) {
+        Ok(shard) => shard,
+        Err(r) => return r,
+    };
+    match shard
+        .worker
+        .commit_consolidation(request.lease_id, request.node_id, request.groups)
+        .await
+    {
+        Ok(Some(processed)) => Json(serde_json::json!({
+            "lease_id": request.lease_id,
+            "processed": processed.len(),
+        }))
+        .into_response(),
+        Ok(None) => error_response(
+            StatusCode::CONFLICT,
+            format!(
+                "Lease {} has expired or is held by another node",
+                request.lease_id
+            ),
+        ),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
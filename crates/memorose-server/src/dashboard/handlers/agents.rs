@@ -1,5 +1,5 @@
 use axum::{extract::State, response::IntoResponse, Json};
-use memorose_common::{MemoryDomain, MemoryUnit};
+use memorose_common::MemoryDomain;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -35,10 +35,11 @@ pub async fn list_agents(State(state): State<Arc<crate::AppState>>) -> axum::res
 
                 let all_pairs = kv.scan(b"u:")?;
 
-                // Scan memory units grouped by agent_id
+                // Scan memory units grouped by agent_id. Only metadata is read, so sealed
+                // content is left sealed.
                 for (k, val) in &all_pairs {
                     if k.windows(6).any(|w| w == b":unit:") {
-                        if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(val) {
+                        if let Ok(unit) = memorose_core::migration::decode_sealed_memory_unit(val) {
                             if unit.domain != MemoryDomain::Agent {
                                 continue;
                             }
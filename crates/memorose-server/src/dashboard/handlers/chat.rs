@@ -1,13 +1,31 @@
 use axum::{
-    extract::State,
-    response::sse::{Event, Sse},
-    Json,
+    extract::{Path, State},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
 };
-use futures_util::stream::Stream;
+use memorose_common::{ChatTurnRecord, EventContent, EventRole, MemoryStream};
 use serde::Deserialize;
 use std::sync::Arc;
+use uuid::Uuid;
 
 use super::types::{append_context_with_budget, format_memory_unit_context};
+use crate::dashboard::registry::ApiKeyScope;
+
+const DEFAULT_CHAT_SYSTEM_PROMPT: &str =
+    "You are a helpful AI assistant with access to the user's memory system. \
+    Use the provided memory context when it is relevant, especially multimodal descriptions and source references. \
+    If the memory context is insufficient, answer honestly and do not invent remembered facts.";
+
+const CITATION_INSTRUCTION: &str =
+    "Memories are numbered; cite the ones your answer relies on as [1], [2] and so on.";
+
+/// Turns a chat session keeps on its stream; older ones are dropped first.
+const MAX_CHAT_SESSION_TURNS: usize = 100;
+/// Characters of earlier turns a prompt may carry.
+const CHAT_HISTORY_MAX_CHARS: usize = 4000;
 
 #[derive(Deserialize)]
 pub struct ChatRequest {
@@ -15,22 +33,287 @@ pub struct ChatRequest {
     user_id: String,
     #[serde(default)]
     org_id: Option<String>,
+    /// Restrict the memory context to this app
+    #[serde(default)]
+    agent_id: Option<String>,
+    #[serde(default = "default_chat_limit")]
+    context_limit: usize,
+    /// Chat session to continue and record the exchange in; stateless when unset
+    #[serde(default)]
+    stream_id: Option<Uuid>,
+    /// Earlier turns of the session included in the prompt
+    #[serde(default = "default_history_limit")]
+    history_limit: usize,
+}
+
+#[derive(Deserialize)]
+pub struct AppChatRequest {
+    message: String,
+    #[serde(default)]
+    org_id: Option<String>,
+    /// Chat session to continue; a new one is started when unset
+    #[serde(default)]
+    stream_id: Option<Uuid>,
     #[serde(default = "default_chat_limit")]
     context_limit: usize,
+    /// Earlier turns of the session included in the prompt
+    #[serde(default = "default_history_limit")]
+    history_limit: usize,
+    /// Replaces the app's `[prompts]` chat template for this request
+    #[serde(default)]
+    system_prompt: Option<String>,
+    /// Record the user message and the reply as events of the app
+    #[serde(default = "default_chat_ingest")]
+    ingest: bool,
 }
 
 fn default_chat_limit() -> usize {
     5
 }
 
+fn default_chat_ingest() -> bool {
+    true
+}
+
+fn default_history_limit() -> usize {
+    10
+}
+
+/// One chat turn: what it searches, how it answers and where the exchange is recorded.
+struct ChatTurn {
+    user_id: String,
+    org_id: Option<String>,
+    agent_id: Option<String>,
+    message: String,
+    context_limit: usize,
+    system_prompt: String,
+    /// Number the memories in the context and stream them as a `citations` event
+    cite: bool,
+    /// Earlier turns of the session, oldest first
+    history: Vec<ChatTurnRecord>,
+    /// Session stream to record the exchange in
+    ingest_stream: Option<MemoryStream>,
+}
+
 pub async fn chat(
     State(state): State<Arc<crate::AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
     Json(payload): Json<ChatRequest>,
-) -> Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>> {
-    let user_id = payload.user_id.clone();
-    let org_id = payload.org_id.clone();
-    let message = payload.message.clone();
-    let context_limit = payload.context_limit;
+) -> axum::response::Response {
+    let requested_apps = payload.agent_id.as_deref().map(|agent_id| vec![agent_id]);
+    if let Err(response) =
+        crate::check_key_scope(&key_scope, &payload.user_id, requested_apps.as_deref())
+    {
+        return response;
+    }
+
+    let session = match payload.stream_id {
+        Some(stream_id) => {
+            if let Err(r) =
+                check_chat_ingest(&state, &payload.user_id, payload.org_id.as_deref()).await
+            {
+                return r;
+            }
+            match load_chat_session(
+                &state,
+                &payload.user_id,
+                payload.agent_id.as_deref(),
+                Some(stream_id),
+                &payload.message,
+            ) {
+                Ok(session) => Some(session),
+                Err(r) => return r,
+            }
+        }
+        None => None,
+    };
+
+    chat_stream(
+        state,
+        ChatTurn {
+            user_id: payload.user_id,
+            org_id: payload.org_id,
+            agent_id: payload.agent_id,
+            message: payload.message,
+            context_limit: payload.context_limit,
+            system_prompt: DEFAULT_CHAT_SYSTEM_PROMPT.to_string(),
+            cite: false,
+            history: session_history(session.as_ref(), payload.history_limit),
+            ingest_stream: session,
+        },
+    )
+}
+
+/// Retrieval-augmented chat for one app of a user, streamed as server-sent events:
+/// `citations` with the memories in the context, `message` chunks of the reply, then
+/// `done` with the stream and events the exchange was recorded as.
+pub async fn app_chat(
+    State(state): State<Arc<crate::AppState>>,
+    Path((user_id, app_id)): Path<(String, String)>,
+    Json(payload): Json<AppChatRequest>,
+) -> axum::response::Response {
+    if let Err(r) = crate::validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Err(r) = crate::validate_id(&app_id, "app_id") {
+        return r;
+    }
+    if let Some(org_id) = payload.org_id.as_deref() {
+        if let Err(r) = crate::validate_id(org_id, "org_id") {
+            return r;
+        }
+    }
+    if payload.message.trim().is_empty() {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "message must not be empty" })),
+        )
+            .into_response();
+    }
+
+    if payload.ingest {
+        if let Err(r) = check_chat_ingest(&state, &user_id, payload.org_id.as_deref()).await {
+            return r;
+        }
+    }
+    // Without ingestion an existing session still lends its history, but is left unchanged
+    let session = if payload.ingest || payload.stream_id.is_some() {
+        match load_chat_session(
+            &state,
+            &user_id,
+            Some(&app_id),
+            payload.stream_id,
+            &payload.message,
+        ) {
+            Ok(session) => Some(session),
+            Err(r) => return r,
+        }
+    } else {
+        None
+    };
+    let history = session_history(session.as_ref(), payload.history_limit);
+
+    let system_prompt = match payload.system_prompt {
+        Some(system_prompt) => system_prompt,
+        None => {
+            let config = state.config.load();
+            match config
+                .prompts
+                .template_for(memorose_common::config::PromptKind::Chat, Some(&app_id))
+            {
+                Some(template) => memorose_common::prompt_template::render(
+                    template,
+                    &[
+                        ("app_id", app_id.as_str()),
+                        (
+                            "language_instruction",
+                            memorose_core::llm::LANGUAGE_PRESERVATION_INSTRUCTION,
+                        ),
+                    ],
+                ),
+                None => DEFAULT_CHAT_SYSTEM_PROMPT.to_string(),
+            }
+        }
+    };
+
+    chat_stream(
+        state,
+        ChatTurn {
+            user_id,
+            org_id: payload.org_id,
+            agent_id: Some(app_id),
+            message: payload.message,
+            context_limit: payload.context_limit,
+            system_prompt,
+            cite: true,
+            history,
+            ingest_stream: session.filter(|_| payload.ingest),
+        },
+    )
+}
+
+/// The disk, leadership, quota, key and admission checks an exchange must pass before it
+/// is recorded.
+async fn check_chat_ingest(
+    state: &crate::AppState,
+    user_id: &str,
+    org_id: Option<&str>,
+) -> Result<(), axum::response::Response> {
+    let shard = state.shard_manager.shard_for_user(user_id);
+    crate::check_disk_space(state)?;
+    crate::check_shard_leader(state, shard)?;
+    crate::check_org_event_quota(state, org_id, 2).await?;
+    crate::check_user_key(&shard.engine, user_id)?;
+    crate::check_ingest_admission(&shard.engine).await
+}
+
+/// The chat session recorded in `stream_id`, or a new one for `app_id` titled after the
+/// first message. A stream id without a record starts a session with that id; one owned by
+/// another app is not found.
+fn load_chat_session(
+    state: &crate::AppState,
+    user_id: &str,
+    app_id: Option<&str>,
+    stream_id: Option<Uuid>,
+    message: &str,
+) -> Result<MemoryStream, axum::response::Response> {
+    let shard = state.shard_manager.shard_for_user(user_id);
+    let existing = match stream_id {
+        Some(stream_id) => match shard.engine.get_memory_stream(user_id, stream_id) {
+            Ok(stream) => stream,
+            Err(e) => {
+                return Err((
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response())
+            }
+        },
+        None => None,
+    };
+    match existing {
+        Some(stream) if stream.app_id.as_deref() == app_id => Ok(stream),
+        Some(_) => Err((
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Stream not found" })),
+        )
+            .into_response()),
+        None => {
+            let mut stream = MemoryStream::new(
+                user_id.to_string(),
+                app_id.map(str::to_string),
+                Some(crate::build_content_preview(message, 60)),
+            );
+            if let Some(stream_id) = stream_id {
+                stream.id = stream_id;
+            }
+            Ok(stream)
+        }
+    }
+}
+
+fn session_history(session: Option<&MemoryStream>, history_limit: usize) -> Vec<ChatTurnRecord> {
+    session
+        .map(|stream| {
+            stream
+                .chat_window(history_limit, CHAT_HISTORY_MAX_CHARS)
+                .to_vec()
+        })
+        .unwrap_or_default()
+}
+
+fn chat_stream(state: Arc<crate::AppState>, turn: ChatTurn) -> axum::response::Response {
+    let ChatTurn {
+        user_id,
+        org_id,
+        agent_id,
+        message,
+        context_limit,
+        system_prompt,
+        cite,
+        history,
+        ingest_stream,
+    } = turn;
 
     let stream = async_stream::stream! {
         // Step 1: Search for relevant context using hybrid search
@@ -41,7 +324,7 @@ pub async fn chat(
                 match shard.engine.search_hybrid_with_shared(
                     &user_id,
                     org_id.as_deref(),
-                    None,
+                    agent_id.as_deref(),
                     &message,
                     &embedding.data,
                     context_limit,
@@ -66,52 +349,353 @@ pub async fn chat(
 
         // Step 2: Build context from search results
         let mut context_text = String::new();
+        let mut citations = Vec::new();
         let context_budget = context_limit.clamp(1, 10) * 500;
+        match shard.engine.get_structured_profile(&user_id) {
+            Ok(Some(profile)) if !profile.attributes.is_empty() => {
+                context_text.push_str("## User Profile:\n");
+                context_text.push_str(&profile.render());
+                context_text.push_str("\n\n");
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to load user profile for chat: {:?}", e),
+        }
         if !context_results.is_empty() {
             context_text.push_str("## Relevant Context from Memory:\n");
-            for (unit, _score) in &context_results {
-                if !append_context_with_budget(
-                    &mut context_text,
-                    &format_memory_unit_context(unit.memory_unit()),
-                    context_budget,
-                ) {
+            for (unit, score) in &context_results {
+                let unit = unit.memory_unit();
+                let mut block = format_memory_unit_context(unit);
+                if cite {
+                    block = format!("[{}] {}", citations.len() + 1, block.trim_start_matches("- "));
+                }
+                if !append_context_with_budget(&mut context_text, &block, context_budget) {
                     break;
                 }
+                citations.push(serde_json::json!({
+                    "index": citations.len() + 1,
+                    "id": unit.id,
+                    "level": unit.level,
+                    "score": score,
+                    "content": crate::build_content_preview(&unit.content, 200),
+                }));
             }
             context_text.push_str("\n");
         }
+        if cite {
+            yield Ok(Event::default().event("citations").data(serde_json::json!(citations).to_string()));
+        }
 
         // Step 3: Build prompt
-        let system_prompt = format!(
-            "You are a helpful AI assistant with access to the user's memory system. \
-    Use the provided memory context when it is relevant, especially multimodal descriptions and source references. \
-    If the memory context is insufficient, answer honestly and do not invent remembered facts.\n\n{}",
-            context_text
-        );
-
-        // Step 4: Generate response using LLM
-        let full_prompt = format!("{}\nUser: {}", system_prompt, message);
-        match state.llm_client.generate(&full_prompt).await {
-            Ok(response) => {
-                // Stream the response word by word for better UX
-                let words: Vec<&str> = response.data.split_whitespace().collect();
-                for (i, word) in words.iter().enumerate() {
-                    let text = if i == words.len() - 1 {
-                        word.to_string()
-                    } else {
-                        format!("{} ", word)
-                    };
-                    yield Ok(Event::default().event("message").data(text));
-                    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-                }
+        let system_prompt = if cite && !citations.is_empty() {
+            format!("{}\n{}\n\n{}", system_prompt, CITATION_INSTRUCTION, context_text)
+        } else {
+            format!("{}\n\n{}", system_prompt, context_text)
+        };
 
-                yield Ok(Event::default().event("done").data(""));
+        // Step 4: Generate response using LLM, continuing the session's earlier turns
+        let mut full_prompt = system_prompt;
+        if !history.is_empty() {
+            full_prompt.push_str("## Conversation so far:\n");
+            for turn in &history {
+                let speaker = match turn.role {
+                    EventRole::Assistant => "Assistant",
+                    _ => "User",
+                };
+                full_prompt.push_str(&format!("{}: {}\n", speaker, turn.content));
             }
+        }
+        full_prompt.push_str(&format!("\nUser: {}", message));
+        let reply = match state.llm_client.generate(&full_prompt).await {
+            Ok(response) => response.data,
             Err(e) => {
                 yield Ok(Event::default().event("error").data(format!("Generation failed: {}", e)));
+                return;
+            }
+        };
+
+        // Stream the response word by word for better UX
+        let words: Vec<&str> = reply.split_whitespace().collect();
+        for (i, word) in words.iter().enumerate() {
+            let text = if i == words.len() - 1 {
+                word.to_string()
+            } else {
+                format!("{} ", word)
+            };
+            yield Ok(Event::default().event("message").data(text));
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        }
+
+        // Step 5: Record the exchange so later turns can remember it
+        let Some(session) = ingest_stream else {
+            yield Ok(Event::default().event("done").data(""));
+            return;
+        };
+        let stream_id = session.id;
+        let mut events = Vec::new();
+        for (role, text) in [(EventRole::User, message), (EventRole::Assistant, reply)] {
+            let mut event = memorose_common::Event::new(
+                org_id.clone(),
+                user_id.clone(),
+                agent_id.clone(),
+                stream_id,
+                EventContent::Text(text),
+            );
+            event.set_conversation_turn(Some(role), None, None);
+            match crate::moderate_event(&state, &shard.engine, event).await {
+                Ok(event) => events.push(event),
+                Err(categories) => tracing::info!(
+                    "Chat turn for user {} not recorded: flagged as {}",
+                    user_id,
+                    categories.join(", ")
+                ),
+            }
+        }
+        let event_ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+        let turns: Vec<ChatTurnRecord> = events
+            .iter()
+            .filter_map(|event| match &event.content {
+                EventContent::Text(text) => Some(ChatTurnRecord {
+                    role: event.role()?,
+                    content: text.clone(),
+                    at: event.transaction_time,
+                    event_id: Some(event.id),
+                }),
+                _ => None,
+            })
+            .collect();
+        let written = if events.is_empty() {
+            Ok(true)
+        } else if state.is_standalone_mode() {
+            shard.engine.ingest_events_directly(events).await.map(|_| true)
+        } else {
+            crate::replicate_command(
+                shard,
+                memorose_core::raft::types::ClientRequest::IngestEvents(events),
+            )
+            .await
+        };
+        match written {
+            Ok(true) => {}
+            Ok(false) => {
+                yield Ok(Event::default().event("error").data("Recording the exchange was not applied"));
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Recording chat exchange failed: {:?}", e);
+                yield Ok(Event::default().event("error").data(format!("Recording the exchange failed: {}", e)));
+                return;
             }
         }
+
+        // Keep the turns on the session so the next message sees them. Reload first, in
+        // case another turn was recorded while this one was generating.
+        let mut session = match shard.engine.get_memory_stream(&user_id, stream_id) {
+            Ok(Some(stream)) => stream,
+            _ => session,
+        };
+        session.push_chat_turns(turns, MAX_CHAT_SESSION_TURNS);
+        if let Err(e) = crate::put_memory_stream(&state, shard, &session).await {
+            tracing::error!("Saving chat session {} failed: {:?}", stream_id, e);
+        }
+        yield Ok(Event::default().event("done").data(
+            serde_json::json!({ "stream_id": stream_id, "event_ids": event_ids }).to_string(),
+        ));
     };
 
-    Sse::new(stream)
+    Sse::new(stream).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::{MemoryType, MemoryUnit};
+    use memorose_core::llm::{MockLLM, MOCK_EMBEDDING_DIM};
+    use tempfile::tempdir;
+
+    fn llm() -> MockLLM {
+        MockLLM {
+            generate_response: Some("Bees.".into()),
+            ..Default::default()
+        }
+    }
+
+    async fn call_app_chat(
+        state: &Arc<crate::AppState>,
+        app_id: &str,
+        request: serde_json::Value,
+    ) -> anyhow::Result<String> {
+        let response = app_chat(
+            State(state.clone()),
+            Path(("alice".to_string(), app_id.to_string())),
+            Json(serde_json::from_value(request)?),
+        )
+        .await;
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok(String::from_utf8(body.to_vec())?)
+    }
+
+    /// The data of the first server-sent event named `name`.
+    fn event_data(body: &str, name: &str) -> Option<String> {
+        let mut lines = body.lines();
+        while let Some(line) = lines.next() {
+            if line == format!("event: {}", name) {
+                return lines
+                    .next()
+                    .and_then(|line| line.strip_prefix("data: "))
+                    .map(str::to_string)
+                    .or(Some(String::new()));
+            }
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn test_app_chat_numbers_citations_in_event_and_prompt() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let llm = llm();
+        let state = crate::tests::test_state(temp_dir.path(), llm.clone()).await;
+        let engine = &state.shard_manager.shard_for_user("alice").engine;
+        let mut stored = Vec::new();
+        for content in [
+            "Alice keeps bees in the garden",
+            "The bees made honey in June",
+        ] {
+            let unit = MemoryUnit::new(
+                None,
+                "alice".into(),
+                Some("notes".into()),
+                Uuid::new_v4(),
+                MemoryType::Factual,
+                content.into(),
+                Some(vec![1.0; MOCK_EMBEDDING_DIM]),
+            );
+            stored.push(unit.id);
+            engine.store_memory_unit(unit).await?;
+        }
+        engine.commit_text_index(false).await?;
+
+        let body = call_app_chat(
+            &state,
+            "notes",
+            serde_json::json!({ "message": "bees", "ingest": false }),
+        )
+        .await?;
+
+        let citations: Vec<serde_json::Value> =
+            serde_json::from_str(&event_data(&body, "citations").expect("citations event"))?;
+        assert_eq!(citations.len(), 2);
+        for (i, citation) in citations.iter().enumerate() {
+            assert_eq!(citation["index"], i + 1);
+            let id: Uuid = serde_json::from_value(citation["id"].clone())?;
+            assert!(stored.contains(&id));
+        }
+        let prompts = llm.generated_prompts.lock().unwrap().clone();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains(CITATION_INSTRUCTION));
+        for citation in &citations {
+            let marker = format!("[{}] ", citation["index"]);
+            let content = citation["content"].as_str().unwrap();
+            assert!(prompts[0]
+                .lines()
+                .any(|line| line.starts_with(&marker) && line.contains(content)));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_app_chat_system_prompt_overrides_configured_template() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let llm = llm();
+        let state = crate::tests::test_state(temp_dir.path(), llm.clone()).await;
+        let mut config = (*state.config.load()).clone();
+        config.prompts.apps.insert(
+            "notes".into(),
+            memorose_common::config::PromptTemplates {
+                chat: Some("Configured assistant for {{app_id}}.".into()),
+                ..Default::default()
+            },
+        );
+        state.config.store(config);
+
+        call_app_chat(
+            &state,
+            "notes",
+            serde_json::json!({ "message": "hello", "ingest": false }),
+        )
+        .await?;
+        call_app_chat(
+            &state,
+            "notes",
+            serde_json::json!({
+                "message": "hello",
+                "system_prompt": "Override assistant.",
+                "ingest": false,
+            }),
+        )
+        .await?;
+        call_app_chat(
+            &state,
+            "other",
+            serde_json::json!({ "message": "hello", "ingest": false }),
+        )
+        .await?;
+
+        let prompts = llm.generated_prompts.lock().unwrap().clone();
+        assert_eq!(prompts.len(), 3);
+        assert!(prompts[0].starts_with("Configured assistant for notes."));
+        assert!(prompts[1].starts_with("Override assistant."));
+        assert!(!prompts[1].contains("Configured assistant"));
+        assert!(prompts[2].starts_with(DEFAULT_CHAT_SYSTEM_PROMPT));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_app_chat_records_both_turns_only_when_ingesting() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let state = crate::tests::test_state(temp_dir.path(), llm()).await;
+        let engine = state.shard_manager.shard_for_user("alice").engine.clone();
+
+        let body = call_app_chat(
+            &state,
+            "notes",
+            serde_json::json!({ "message": "hello", "ingest": false }),
+        )
+        .await?;
+        assert_eq!(event_data(&body, "done").as_deref(), Some(""));
+        assert_eq!(engine.count_pending_events().await?, 0);
+        assert!(engine.list_memory_streams("alice", None, true)?.is_empty());
+
+        let body =
+            call_app_chat(&state, "notes", serde_json::json!({ "message": "hello" })).await?;
+        let done: serde_json::Value =
+            serde_json::from_str(&event_data(&body, "done").expect("done event"))?;
+        let stream_id: Uuid = serde_json::from_value(done["stream_id"].clone())?;
+        let event_ids: Vec<Uuid> = serde_json::from_value(done["event_ids"].clone())?;
+        assert_eq!(event_ids.len(), 2);
+        assert_eq!(engine.count_pending_events().await?, 2);
+
+        let session = engine
+            .get_memory_stream("alice", stream_id)?
+            .expect("chat session");
+        assert_eq!(session.app_id.as_deref(), Some("notes"));
+        let turns: Vec<(EventRole, &str)> = session
+            .chat_turns
+            .iter()
+            .map(|turn| (turn.role, turn.content.as_str()))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![(EventRole::User, "hello"), (EventRole::Assistant, "Bees.")]
+        );
+        for (turn, event_id) in session.chat_turns.iter().zip(&event_ids) {
+            assert_eq!(turn.event_id, Some(*event_id));
+            let event = engine
+                .get_event("alice", &event_id.to_string())
+                .await?
+                .expect("recorded event");
+            assert_eq!(event.stream_id, stream_id);
+        }
+        Ok(())
+    }
 }
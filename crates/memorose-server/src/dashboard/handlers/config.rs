@@ -4,7 +4,7 @@ use std::sync::Arc;
 // ── Config ────────────────────────────────────────────────────────
 
 pub async fn get_config(State(state): State<Arc<crate::AppState>>) -> Json<serde_json::Value> {
-    let config = &state.config;
+    let config = state.config.load();
 
     let mut result = serde_json::json!({
         "raft": {
@@ -25,6 +25,10 @@ pub async fn get_config(State(state): State<Arc<crate::AppState>>) -> Json<serde
             "provider": format!("{:?}", config.llm.provider),
             "model": config.llm.model,
             "embedding_model": config.llm.embedding_model,
+            "embedding_dim": config.llm.embedding_dim,
+            "observed_embedding_dim": state.llm_client.observed_embedding_dim(),
+            "requests_per_minute": config.llm.requests_per_minute,
+            "max_concurrency": config.llm.max_concurrency,
         },
         "storage": {
             "root_dir": config.storage.root_dir,
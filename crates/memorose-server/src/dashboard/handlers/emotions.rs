@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use memorose_common::MemoryUnit;
+use memorose_core::emotion::{sentiment_of, Sentiment};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// ── Emotions ──────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct EmotionStatsQuery {
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    agent_id: Option<String>,
+    /// Only memories created in the last this many days; 0 counts all of them
+    #[serde(default = "default_emotion_stats_days")]
+    days: u32,
+}
+
+fn default_emotion_stats_days() -> u32 {
+    30
+}
+
+#[derive(Debug, Default, Serialize)]
+struct EmotionStats {
+    tagged_memories: usize,
+    untagged_memories: usize,
+    emotions: BTreeMap<String, usize>,
+    sentiment: BTreeMap<Sentiment, usize>,
+    /// Emotion counts per day the memories were created
+    daily: BTreeMap<NaiveDate, BTreeMap<String, usize>>,
+}
+
+impl EmotionStats {
+    fn add(&mut self, unit: &MemoryUnit) {
+        if unit.emotions.is_empty() {
+            self.untagged_memories += 1;
+            return;
+        }
+        self.tagged_memories += 1;
+        let day = self
+            .daily
+            .entry(unit.transaction_time.date_naive())
+            .or_default();
+        for emotion in &unit.emotions {
+            *self.emotions.entry(emotion.clone()).or_default() += 1;
+            *self.sentiment.entry(sentiment_of(emotion)).or_default() += 1;
+            *day.entry(emotion.clone()).or_default() += 1;
+        }
+    }
+}
+
+fn counts_emotions_of(
+    unit: &MemoryUnit,
+    agent_id: Option<&str>,
+    since: Option<DateTime<Utc>>,
+) -> bool {
+    unit.level == 1
+        && agent_id.is_none_or(|agent_id| unit.agent_id.as_deref() == Some(agent_id))
+        && since.is_none_or(|since| unit.transaction_time >= since)
+}
+
+/// Emotion tags of L1 memories on this node, with their sentiment split and a daily
+/// breakdown. Memories are tagged when `worker.emotion_tagging_enabled` is on.
+pub async fn emotion_stats(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<EmotionStatsQuery>,
+) -> axum::response::Response {
+    let cache_key = format!(
+        "emotions:{}:{}:{}",
+        params.user_id.as_deref().unwrap_or_default(),
+        params.agent_id.as_deref().unwrap_or_default(),
+        params.days
+    );
+    if let Some(cached) = state.dashboard_cache.get(&cache_key).await {
+        return Json(cached).into_response();
+    }
+
+    let since = (params.days > 0).then(|| Utc::now() - chrono::Duration::days(params.days.into()));
+    let mut stats = EmotionStats::default();
+    let user_shard = params.user_id.as_deref().map(|user_id| {
+        memorose_common::sharding::user_id_to_shard(user_id, state.shard_manager.shard_count())
+    });
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        if user_shard.is_some_and(|user_shard| user_shard != shard_id) {
+            continue;
+        }
+        match shard
+            .engine
+            .list_memory_units_global(params.user_id.as_deref())
+            .await
+        {
+            Ok(units) => {
+                for unit in units
+                    .iter()
+                    .filter(|unit| counts_emotions_of(unit, params.agent_id.as_deref(), since))
+                {
+                    stats.add(unit);
+                }
+            }
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    let result = serde_json::json!({
+        "days": params.days,
+        "tagging_enabled": state.config.load().worker.emotion_tagging_enabled,
+        "stats": stats,
+    });
+    state
+        .dashboard_cache
+        .insert(cache_key, result.clone())
+        .await;
+    Json(result).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::MemoryType;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_emotion_stats_counts_labels_sentiment_and_days() {
+        let unit = |emotions: &[&str]| {
+            let mut unit = MemoryUnit::new(
+                None,
+                "u1".into(),
+                Some("support-bot".into()),
+                Uuid::new_v4(),
+                MemoryType::Factual,
+                "content".into(),
+                None,
+            );
+            unit.emotions = emotions.iter().map(|e| e.to_string()).collect();
+            unit
+        };
+        let mut stats = EmotionStats::default();
+        for emotions in [&["frustrated", "angry"][..], &["grateful"], &[]] {
+            stats.add(&unit(emotions));
+        }
+
+        assert_eq!(stats.tagged_memories, 2);
+        assert_eq!(stats.untagged_memories, 1);
+        assert_eq!(stats.emotions["frustrated"], 1);
+        assert_eq!(stats.sentiment[&Sentiment::Negative], 2);
+        assert_eq!(stats.sentiment[&Sentiment::Positive], 1);
+        assert_eq!(
+            stats.daily.values().next().unwrap().values().sum::<usize>(),
+            3
+        );
+
+        let old = {
+            let mut old = unit(&["sad"]);
+            old.transaction_time = Utc::now() - chrono::Duration::days(40);
+            old
+        };
+        let since = Some(Utc::now() - chrono::Duration::days(30));
+        assert!(!counts_emotions_of(&old, None, since));
+        assert!(counts_emotions_of(&old, Some("support-bot"), None));
+        assert!(!counts_emotions_of(&old, Some("other-app"), None));
+    }
+}
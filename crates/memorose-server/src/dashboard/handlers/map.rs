@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use memorose_common::MemoryUnit;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::types::dashboard_build_content_preview;
+
+// ── Map ───────────────────────────────────────────────────────────
+
+const MAP_CONTENT_PREVIEW_CHARS: usize = 120;
+const MAX_MAP_POINTS: usize = 5000;
+
+#[derive(Deserialize)]
+pub struct MemoryMapQuery {
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    agent_id: Option<String>,
+    /// Only memories created in the last this many days; 0 maps all of them
+    #[serde(default = "default_memory_map_days")]
+    days: u32,
+    #[serde(default = "default_memory_map_limit")]
+    limit: usize,
+}
+
+fn default_memory_map_days() -> u32 {
+    30
+}
+
+fn default_memory_map_limit() -> usize {
+    1000
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryMapPoint {
+    id: Uuid,
+    user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_id: Option<String>,
+    lat: f64,
+    lon: f64,
+    level: u8,
+    content: String,
+    transaction_time: DateTime<Utc>,
+}
+
+/// The map pin for `unit`, when it carries a location and passes the filters.
+fn map_point_of(
+    unit: &MemoryUnit,
+    agent_id: Option<&str>,
+    since: Option<DateTime<Utc>>,
+) -> Option<MemoryMapPoint> {
+    let location = unit.location?;
+    let in_scope = matches!(unit.level, 1 | 2)
+        && agent_id.is_none_or(|agent_id| unit.agent_id.as_deref() == Some(agent_id))
+        && since.is_none_or(|since| unit.transaction_time >= since);
+    in_scope.then(|| MemoryMapPoint {
+        id: unit.id,
+        user_id: unit.user_id.clone(),
+        agent_id: unit.agent_id.clone(),
+        lat: location.lat,
+        lon: location.lon,
+        level: unit.level,
+        content: dashboard_build_content_preview(&unit.content, MAP_CONTENT_PREVIEW_CHARS),
+        transaction_time: unit.transaction_time,
+    })
+}
+
+/// Geo-tagged L1 and L2 memories on this node, newest first, for the dashboard map.
+pub async fn memory_map(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<MemoryMapQuery>,
+) -> axum::response::Response {
+    let limit = params.limit.clamp(1, MAX_MAP_POINTS);
+    let cache_key = format!(
+        "map:{}:{}:{}:{}",
+        params.user_id.as_deref().unwrap_or_default(),
+        params.agent_id.as_deref().unwrap_or_default(),
+        params.days,
+        limit
+    );
+    if let Some(cached) = state.dashboard_cache.get(&cache_key).await {
+        return Json(cached).into_response();
+    }
+
+    let since = (params.days > 0).then(|| Utc::now() - chrono::Duration::days(params.days.into()));
+    let mut points = Vec::new();
+    let user_shard = params.user_id.as_deref().map(|user_id| {
+        memorose_common::sharding::user_id_to_shard(user_id, state.shard_manager.shard_count())
+    });
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        if user_shard.is_some_and(|user_shard| user_shard != shard_id) {
+            continue;
+        }
+        match shard
+            .engine
+            .list_memory_units_global(params.user_id.as_deref())
+            .await
+        {
+            Ok(units) => points.extend(
+                units
+                    .iter()
+                    .filter_map(|unit| map_point_of(unit, params.agent_id.as_deref(), since)),
+            ),
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+    points.sort_by(|a, b| b.transaction_time.cmp(&a.transaction_time));
+    let total = points.len();
+    points.truncate(limit);
+
+    let result = serde_json::json!({
+        "days": params.days,
+        "total": total,
+        "points": points,
+    });
+    state
+        .dashboard_cache
+        .insert(cache_key, result.clone())
+        .await;
+    Json(result).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::{GeoPoint, MemoryType};
+
+    #[test]
+    fn test_map_point_keeps_geo_tagged_memories_in_scope() {
+        let mut unit = MemoryUnit::new(
+            None,
+            "u1".into(),
+            Some("travel-bot".into()),
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "Dinner   at the harbour".into(),
+            None,
+        );
+        assert!(map_point_of(&unit, None, None).is_none());
+
+        unit.location = GeoPoint::new(59.3293, 18.0686);
+        let point = map_point_of(&unit, Some("travel-bot"), None).unwrap();
+        assert_eq!((point.lat, point.lon), (59.3293, 18.0686));
+        assert_eq!(point.content, "Dinner at the harbour");
+        assert!(map_point_of(&unit, Some("other-app"), None).is_none());
+
+        unit.transaction_time = Utc::now() - chrono::Duration::days(40);
+        let since = Some(Utc::now() - chrono::Duration::days(30));
+        assert!(map_point_of(&unit, None, since).is_none());
+    }
+}
@@ -3,8 +3,9 @@ use axum::{
     response::IntoResponse,
     Json,
 };
-use memorose_common::Event as MemoryEvent;
+use memorose_common::{Event as MemoryEvent, MemoryStream};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::types::*;
@@ -83,6 +84,7 @@ pub async fn list_memories(
                             }
                             .to_string();
                             let (user_id, agent_id) = display_identity_for_memory(&u);
+                            let stream_id = (!user_id.is_empty()).then_some(u.stream_id);
 
                             DashboardMemoryRow {
                                 id: u.id.to_string(),
@@ -97,6 +99,7 @@ pub async fn list_memories(
                                 reference_count: u.references.len(),
                                 item_type: "memory",
                                 memory_type: Some(memory_type_str),
+                                stream_id,
                             }
                         })
                         .take(10_000)
@@ -132,7 +135,12 @@ pub async fn list_memories(
                                 true
                             }
                         })
-                        .filter_map(|(_, val)| serde_json::from_slice::<MemoryEvent>(&val).ok())
+                        .filter_map(|(_, val)| {
+                            let mut event = serde_json::from_slice::<MemoryEvent>(&val).ok()?;
+                            // Events under a revoked key are left out.
+                            memorose_core::crypto::open_event(&mut event).ok()?;
+                            Some(event)
+                        })
                         .filter(|event| {
                             !engine
                                 .is_event_forgotten(&event.user_id, &event.id.to_string())
@@ -156,6 +164,7 @@ pub async fn list_memories(
                                 reference_count: 0,
                                 item_type: "event",
                                 memory_type: None,
+                                stream_id: Some(event.stream_id),
                             }
                         })
                         .take(10_000)
@@ -192,11 +201,31 @@ pub async fn list_memories(
     let limit = params.limit.min(100);
     let offset = (page - 1) * limit;
 
+    // Resolve stream records for the page only; most rows of a page share a stream.
+    let mut streams: HashMap<(String, uuid::Uuid), Option<MemoryStream>> = HashMap::new();
     let items = rows
         .into_iter()
         .skip(offset)
         .take(limit)
-        .map(DashboardMemoryListItemView::from)
+        .map(|row| {
+            let stream = row.stream_id.and_then(|stream_id| {
+                streams
+                    .entry((row.user_id.clone(), stream_id))
+                    .or_insert_with(|| {
+                        state
+                            .shard_manager
+                            .shard_for_user(&row.user_id)
+                            .engine
+                            .get_memory_stream(&row.user_id, stream_id)
+                            .ok()
+                            .flatten()
+                    })
+                    .clone()
+            });
+            let mut item = DashboardMemoryListItemView::from(row);
+            item.stream = stream;
+            item
+        })
         .collect();
 
     Json(DashboardMemoryListResponse {
@@ -250,7 +279,15 @@ pub async fn get_memory(
         match shard.engine.get_native_memory_unit_by_index(uuid).await {
             Ok(Some(mut unit)) => {
                 unit.embedding = None;
-                return Json(dashboard_memory_detail_view(&unit, None)).into_response();
+                let mut view = dashboard_memory_detail_view(&unit, None);
+                view.stream = shard
+                    .engine
+                    .get_memory_stream(&unit.user_id, unit.stream_id)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Failed to load stream {}: {}", unit.stream_id, e);
+                        None
+                    });
+                return Json(view).into_response();
             }
             Ok(None) => continue,
             Err(e) => {
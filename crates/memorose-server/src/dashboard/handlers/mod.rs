@@ -5,25 +5,33 @@ mod auth;
 mod chat;
 mod config;
 mod corrections;
+mod emotions;
 mod forget;
 mod graph;
+mod map;
 mod memories;
 mod organizations;
 mod search;
+mod self_eval;
+mod slow_queries;
 mod stats;
+mod storage_usage;
+mod worker_runs;
 
 // Re-export all public handler functions so main.rs paths don't change
 pub use agents::list_agents;
 pub use auth::{change_password, login};
-pub use chat::chat;
+pub use chat::{app_chat, chat};
 pub use config::get_config;
 pub use corrections::{
     apply_manual_correction, approve_rac_review, list_rac_reviews, reject_rac_review,
     semantic_memory_execute, semantic_memory_preview, user_semantic_memory_execute,
     user_semantic_memory_preview,
 };
+pub use emotions::emotion_stats;
 pub use forget::{forget_execute, forget_preview};
 pub use graph::graph_data;
+pub use map::memory_map;
 pub use memories::{get_memory, list_memories};
 pub use organizations::{
     create_api_key, create_organization, get_organization_knowledge,
@@ -31,4 +39,8 @@ pub use organizations::{
     list_organizations, revoke_api_key,
 };
 pub use search::search;
+pub use self_eval::self_eval_reports;
+pub use slow_queries::slow_queries;
 pub use stats::{cluster_status, stats};
+pub use storage_usage::storage_usage;
+pub use worker_runs::worker_runs;
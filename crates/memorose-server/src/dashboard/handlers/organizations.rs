@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use super::types::*;
+use crate::dashboard::registry::ApiKeyScope;
 
 #[derive(Deserialize)]
 pub struct CreateOrganizationRequest {
@@ -20,6 +21,12 @@ pub struct CreateApiKeyRequest {
     org_id: String,
     #[serde(default)]
     name: Option<String>,
+    /// Apps the key is bound to; empty allows every app
+    #[serde(default)]
+    app_ids: Vec<String>,
+    /// User id patterns (`*` wildcard) the key may act for; empty allows every user
+    #[serde(default)]
+    user_patterns: Vec<String>,
 }
 
 pub async fn list_organizations(
@@ -89,9 +96,24 @@ pub async fn create_api_key(
         return response;
     }
 
+    for app_id in &payload.app_ids {
+        if let Err(response) = validate_registry_id(app_id, "app_id") {
+            return response;
+        }
+    }
+    for pattern in &payload.user_patterns {
+        if let Err(response) = validate_registry_id(pattern, "user_pattern") {
+            return response;
+        }
+    }
+    let scope = ApiKeyScope {
+        app_ids: payload.app_ids,
+        user_patterns: payload.user_patterns,
+    };
+
     match state
         .management_registry
-        .create_api_key(payload.org_id.trim(), payload.name)
+        .create_api_key(payload.org_id.trim(), payload.name, scope)
         .await
     {
         Ok(record) => Json(record).into_response(),
@@ -1,8 +1,9 @@
-use axum::{extract::State, response::IntoResponse, Json};
+use axum::{extract::State, response::IntoResponse, Extension, Json};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use super::types::DashboardSearchMemoryUnitView;
+use crate::dashboard::registry::ApiKeyScope;
 
 // ── Search ────────────────────────────────────────────────────────
 
@@ -32,6 +33,7 @@ fn default_search_limit() -> usize {
 
 pub async fn search(
     State(state): State<Arc<crate::AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
     Json(payload): Json<SearchRequest>,
 ) -> axum::response::Response {
     let limit = payload.limit.min(100);
@@ -46,6 +48,15 @@ pub async fn search(
     let org_id = payload.org_id.as_deref();
     let agent_id = payload.agent_id.as_deref();
 
+    // Only hybrid search filters by app; the other modes read across every app
+    let requested_apps = match payload.mode.as_str() {
+        "text_local" | "text" | "text_shared" | "vector" => None,
+        _ => agent_id.map(|agent_id| vec![agent_id]),
+    };
+    if let Err(response) = crate::check_key_scope(&key_scope, user_id, requested_apps.as_deref()) {
+        return response;
+    }
+
     // Route to the correct shard for this user
     let shard = state.shard_manager.shard_for_user(user_id);
 
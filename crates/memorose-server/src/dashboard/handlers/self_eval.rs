@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// ── Retrieval self-evaluation ─────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SelfEvalQuery {
+    #[serde(default = "default_self_eval_limit")]
+    limit: usize,
+}
+
+fn default_self_eval_limit() -> usize {
+    50
+}
+
+/// Recent retrieval self-evaluation runs across shards, newest first.
+pub async fn self_eval_reports(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<SelfEvalQuery>,
+) -> axum::response::Response {
+    let limit = params.limit.clamp(1, 500);
+    let mut reports = Vec::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        match shard.engine.list_self_eval_reports(limit) {
+            Ok(shard_reports) => {
+                reports.extend(shard_reports.into_iter().map(|report| (shard_id, report)))
+            }
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+    reports.sort_by(|a, b| b.1.run_at.cmp(&a.1.run_at));
+    reports.truncate(limit);
+    let runs: Vec<serde_json::Value> = reports
+        .into_iter()
+        .map(|(shard_id, report)| serde_json::json!({ "shard_id": shard_id, "report": report }))
+        .collect();
+
+    let config = state.config.load();
+    Json(serde_json::json!({
+        "interval_secs": config.worker.self_eval_interval_secs,
+        "top_k": config.worker.self_eval_top_k,
+        "runs": runs,
+    }))
+    .into_response()
+}
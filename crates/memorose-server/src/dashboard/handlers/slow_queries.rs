@@ -0,0 +1,31 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// ── Slow queries ──────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SlowQueriesQuery {
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Retrievals that exceeded `slow_query.threshold_ms`, newest first.
+pub async fn slow_queries(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<SlowQueriesQuery>,
+) -> Json<serde_json::Value> {
+    let capacity = state.slow_queries.capacity();
+    let limit = params.limit.unwrap_or(capacity).min(capacity);
+    let queries = state.slow_queries.recent(params.user_id.as_deref(), limit);
+    Json(serde_json::json!({
+        "threshold_ms": state.config.load().slow_query.threshold_ms,
+        "capacity": capacity,
+        "queries": queries,
+    }))
+}
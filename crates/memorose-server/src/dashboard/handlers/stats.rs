@@ -3,7 +3,7 @@ use axum::{
     response::IntoResponse,
     Json,
 };
-use memorose_common::{Event as MemoryEvent, MemoryDomain, MemoryUnit};
+use memorose_common::{Event as MemoryEvent, MemoryDomain};
 use memorose_core::engine::{
     DerivedIndexStatus, RacDecisionRecord, RacMetricHistoryPoint, RacMetricSnapshot,
 };
@@ -16,6 +16,7 @@ use super::types::{matches_dashboard_org_scope, MemoryAggregate};
 // ── Cluster Status ────────────────────────────────────────────────
 
 pub async fn cluster_status(State(state): State<Arc<crate::AppState>>) -> Json<serde_json::Value> {
+    let config = state.config.load();
     let mut shard_statuses = Vec::new();
 
     for (shard_id, shard) in state.shard_manager.all_shards() {
@@ -41,6 +42,21 @@ pub async fn cluster_status(State(state): State<Arc<crate::AppState>>) -> Json<s
                 .membership()
                 .learner_ids()
                 .collect();
+            let nodes: serde_json::Map<String, serde_json::Value> = metrics
+                .membership_config
+                .membership()
+                .nodes()
+                .map(|(id, node)| {
+                    (
+                        id.to_string(),
+                        serde_json::json!({
+                            "raft_addr": node.addr,
+                            "http_addr": node.http_addr,
+                        }),
+                    )
+                })
+                .collect();
+            let catch_up = shard.catch_up.progress(&metrics);
 
             shard_statuses.push(serde_json::json!({
                 "shard_id": shard_id,
@@ -52,6 +68,9 @@ pub async fn cluster_status(State(state): State<Arc<crate::AppState>>) -> Json<s
                 "replication_lag": last_log_index.saturating_sub(last_applied),
                 "voters": voters,
                 "learners": learners,
+                "nodes": nodes,
+                "catch_up": catch_up,
+                "ready": catch_up.is_caught_up(config.raft.ready_max_lag),
                 "storage": storage_status,
                 "text_index_metrics": index_metrics,
             }));
@@ -81,24 +100,25 @@ pub async fn cluster_status(State(state): State<Arc<crate::AppState>>) -> Json<s
         if let Some(first) = shard_statuses.first() {
             let mut result = first.clone();
             result["node_id"] = serde_json::json!(state.shard_manager.physical_node_id());
-            result["snapshot_policy_logs"] = serde_json::json!(state.config.raft.snapshot_logs);
+            result["snapshot_policy_logs"] = serde_json::json!(config.raft.snapshot_logs);
             result["runtime_mode"] = serde_json::json!(if state.is_standalone_mode() {
                 "standalone"
             } else {
                 "cluster"
             });
             result["write_path"] = serde_json::json!(state.write_path_name());
+            result["analytics_node"] = serde_json::json!(config.analytics_node_id());
             result["config"] = serde_json::json!({
-                "heartbeat_interval_ms": state.config.raft.heartbeat_interval_ms,
-                "election_timeout_min_ms": state.config.raft.election_timeout_min_ms,
+                "heartbeat_interval_ms": config.raft.heartbeat_interval_ms,
+                "election_timeout_min_ms": config.raft.election_timeout_min_ms,
                 "worker": {
-                    "insight_interval_ms": state.config.worker.insight_interval_ms,
-                    "insight_min_pending_tokens": state.config.worker.insight_min_pending_tokens,
-                    "insight_min_pending_l1": state.config.worker.insight_min_pending_l1,
-                    "insight_max_delay_ms": state.config.worker.insight_max_delay_ms,
-                    "insight_batch_target_tokens": state.config.worker.insight_batch_target_tokens,
-                    "insight_max_l1_per_batch": state.config.worker.insight_max_l1_per_batch,
-                    "insight_max_batches_per_cycle": state.config.worker.insight_max_batches_per_cycle,
+                    "insight_interval_ms": config.worker.insight_interval_ms,
+                    "insight_min_pending_tokens": config.worker.insight_min_pending_tokens,
+                    "insight_min_pending_l1": config.worker.insight_min_pending_l1,
+                    "insight_max_delay_ms": config.worker.insight_max_delay_ms,
+                    "insight_batch_target_tokens": config.worker.insight_batch_target_tokens,
+                    "insight_max_l1_per_batch": config.worker.insight_max_l1_per_batch,
+                    "insight_max_batches_per_cycle": config.worker.insight_max_batches_per_cycle,
                 }
             });
             return Json(result);
@@ -110,18 +130,19 @@ pub async fn cluster_status(State(state): State<Arc<crate::AppState>>) -> Json<s
         "shard_count": state.shard_manager.shard_count(),
         "runtime_mode": if state.is_standalone_mode() { "standalone" } else { "cluster" },
         "write_path": state.write_path_name(),
+        "analytics_node": config.analytics_node_id(),
         "shards": shard_statuses,
         "config": {
-            "heartbeat_interval_ms": state.config.raft.heartbeat_interval_ms,
-            "election_timeout_min_ms": state.config.raft.election_timeout_min_ms,
+            "heartbeat_interval_ms": config.raft.heartbeat_interval_ms,
+            "election_timeout_min_ms": config.raft.election_timeout_min_ms,
             "worker": {
-                "insight_interval_ms": state.config.worker.insight_interval_ms,
-                "insight_min_pending_tokens": state.config.worker.insight_min_pending_tokens,
-                "insight_min_pending_l1": state.config.worker.insight_min_pending_l1,
-                "insight_max_delay_ms": state.config.worker.insight_max_delay_ms,
-                "insight_batch_target_tokens": state.config.worker.insight_batch_target_tokens,
-                "insight_max_l1_per_batch": state.config.worker.insight_max_l1_per_batch,
-                "insight_max_batches_per_cycle": state.config.worker.insight_max_batches_per_cycle,
+                "insight_interval_ms": config.worker.insight_interval_ms,
+                "insight_min_pending_tokens": config.worker.insight_min_pending_tokens,
+                "insight_min_pending_l1": config.worker.insight_min_pending_l1,
+                "insight_max_delay_ms": config.worker.insight_max_delay_ms,
+                "insight_batch_target_tokens": config.worker.insight_batch_target_tokens,
+                "insight_max_l1_per_batch": config.worker.insight_max_l1_per_batch,
+                "insight_max_batches_per_cycle": config.worker.insight_max_batches_per_cycle,
             }
         }
     }))
@@ -266,8 +287,10 @@ pub async fn stats(
                 let unit_prefix = format!("u:{}:unit:", uid);
                 let unit_pairs = kv.scan(unit_prefix.as_bytes())?;
                 let mut memory = MemoryAggregate::default();
+                // Stored units go through schema migration; the counts read no content,
+                // so units sealed with a revoked key are still counted.
                 for (_, val) in &unit_pairs {
-                    if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(val) {
+                    if let Ok(unit) = memorose_core::migration::decode_sealed_memory_unit(val) {
                         if unit.domain != MemoryDomain::Organization
                             && matches_dashboard_org_scope(
                                 unit.org_id.as_deref(),
@@ -298,7 +321,7 @@ pub async fn stats(
                             }
                         }
                     } else if k.windows(6).any(|w| w == b":unit:") {
-                        if let Ok(unit) = serde_json::from_slice::<MemoryUnit>(val) {
+                        if let Ok(unit) = memorose_core::migration::decode_sealed_memory_unit(val) {
                             if unit.domain != MemoryDomain::Organization
                                 && matches_dashboard_org_scope(
                                     unit.org_id.as_deref(),
@@ -395,6 +418,9 @@ pub async fn stats(
             "shared": shared_levels,
         },
         "text_index_metrics": text_index_metrics,
+        "llm_output_metrics": memorose_core::llm::structured::structured_output_metric_snapshot(),
+        "llm_rate_limits": memorose_core::llm::rate_limit::rate_limit_snapshots(),
+        "summary_tier_metrics": memorose_core::summary_tier::summary_tier_metric_snapshot(),
         "rac_metrics": rac_metrics,
         "rac_metrics_history": rac_history.into_values().collect::<Vec<_>>(),
         "rac_recent_decisions": rac_recent_decisions,
@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use memorose_core::engine::{StorageUsage, StoreDiskUsage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// ── Storage usage ─────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageUsageSort {
+    #[default]
+    Total,
+    Kv,
+    Vector,
+    TextIndex,
+    Assets,
+}
+
+impl StorageUsageSort {
+    fn bytes(self, usage: &StorageUsage) -> u64 {
+        match self {
+            Self::Total => usage.total_bytes(),
+            Self::Kv => usage.kv_bytes,
+            Self::Vector => usage.vector_bytes,
+            Self::TextIndex => usage.text_index_bytes,
+            Self::Assets => usage.asset_bytes,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StorageUsageQuery {
+    #[serde(default = "default_storage_usage_limit")]
+    limit: usize,
+    #[serde(default)]
+    sort: StorageUsageSort,
+    /// Account the shards now instead of reading the last worker run
+    #[serde(default)]
+    refresh: bool,
+}
+
+fn default_storage_usage_limit() -> usize {
+    20
+}
+
+#[derive(Serialize)]
+struct ShardStorageUsage {
+    shard_id: u32,
+    computed_at: Option<chrono::DateTime<chrono::Utc>>,
+    disk: StoreDiskUsage,
+    users: usize,
+    apps: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageEntry {
+    /// User or app id
+    id: String,
+    #[serde(flatten)]
+    usage: StorageUsage,
+    total_bytes: u64,
+}
+
+/// The biggest consumers first, at most `limit` of them.
+fn top_usage(
+    usage: impl IntoIterator<Item = (String, StorageUsage)>,
+    sort: StorageUsageSort,
+    limit: usize,
+) -> Vec<UsageEntry> {
+    let mut entries: Vec<UsageEntry> = usage
+        .into_iter()
+        .map(|(id, usage)| UsageEntry {
+            id,
+            total_bytes: usage.total_bytes(),
+            usage,
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        sort.bytes(&b.usage)
+            .cmp(&sort.bytes(&a.usage))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    entries.truncate(limit);
+    entries
+}
+
+/// Bytes per user and per app across this node's shards, biggest first. Figures come
+/// from the worker's last accounting run unless `refresh` is set.
+pub async fn storage_usage(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<StorageUsageQuery>,
+) -> axum::response::Response {
+    let limit = params.limit.clamp(1, 1000);
+    let mut shards = Vec::new();
+    let mut users = Vec::new();
+    let mut apps: BTreeMap<String, StorageUsage> = BTreeMap::new();
+    let mut totals = StorageUsage::default();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        let report = if params.refresh {
+            match shard.engine.compute_storage_usage().await {
+                Ok(report) => shard
+                    .engine
+                    .record_storage_usage(&report)
+                    .map(|_| Some(report)),
+                Err(e) => Err(e),
+            }
+        } else {
+            shard.engine.latest_storage_usage()
+        };
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        };
+        let Some(report) = report else {
+            shards.push(ShardStorageUsage {
+                shard_id,
+                computed_at: None,
+                disk: StoreDiskUsage::default(),
+                users: 0,
+                apps: 0,
+            });
+            continue;
+        };
+        shards.push(ShardStorageUsage {
+            shard_id,
+            computed_at: Some(report.computed_at),
+            disk: report.disk,
+            users: report.users.len(),
+            apps: report.apps.len(),
+        });
+        for usage in report.users.values() {
+            totals.add(usage);
+        }
+        users.extend(report.users);
+        for (app_id, usage) in report.apps {
+            apps.entry(app_id).or_default().add(&usage);
+        }
+    }
+
+    Json(serde_json::json!({
+        "interval_secs": state.config.load().worker.storage_usage_interval_secs,
+        "sort": params.sort,
+        "shards": shards,
+        "totals": totals,
+        "users": top_usage(users, params.sort, limit),
+        "apps": top_usage(apps, params.sort, limit),
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_usage_sorts_by_the_requested_store() {
+        let usage = |kv_bytes, asset_bytes| StorageUsage {
+            kv_bytes,
+            asset_bytes,
+            ..Default::default()
+        };
+        let users = vec![
+            ("alice".to_string(), usage(300, 0)),
+            ("bob".to_string(), usage(100, 500)),
+            ("carol".to_string(), usage(200, 0)),
+        ];
+
+        let names = |entries: Vec<UsageEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.id).collect()
+        };
+        assert_eq!(
+            names(top_usage(users.clone(), StorageUsageSort::Total, 10)),
+            ["bob", "alice", "carol"]
+        );
+        assert_eq!(
+            names(top_usage(users.clone(), StorageUsageSort::Kv, 2)),
+            ["alice", "carol"]
+        );
+        assert_eq!(
+            top_usage(users, StorageUsageSort::Total, 1)[0].total_bytes,
+            600
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{response::IntoResponse, Json};
-use memorose_common::{Asset, EventContent, MemoryDomain, MemoryType, MemoryUnit};
+use memorose_common::{Asset, EventContent, MemoryDomain, MemoryStream, MemoryType, MemoryUnit};
 use memorose_core::engine::{
     OrganizationAutomationCounterSnapshot, OrganizationKnowledgeContributionRecord,
     OrganizationKnowledgeContributionStatus, OrganizationKnowledgeDetailRecord,
@@ -181,6 +181,7 @@ pub struct DashboardMemoryDetailUnitView {
     pub keywords: Vec<String>,
     pub importance: f32,
     pub level: u8,
+    pub pinned: bool,
     pub transaction_time: chrono::DateTime<chrono::Utc>,
     pub assets: Vec<DashboardAssetView>,
 }
@@ -195,6 +196,7 @@ impl From<&MemoryUnit> for DashboardMemoryDetailUnitView {
             keywords: unit.keywords.clone(),
             importance: unit.importance,
             level: unit.level,
+            pinned: unit.pinned,
             transaction_time: unit.transaction_time,
             assets: unit.assets.iter().map(DashboardAssetView::from).collect(),
         }
@@ -207,6 +209,9 @@ pub struct DashboardMemoryDetailResponse {
     pub unit: DashboardMemoryDetailUnitView,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub organization_knowledge: Option<DashboardOrganizationKnowledgeView>,
+    /// Record of the stream the memory came from, when one was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<MemoryStream>,
 }
 
 #[derive(Clone, Serialize)]
@@ -570,6 +575,7 @@ pub struct DashboardMemoryRow {
     pub reference_count: usize,
     pub item_type: &'static str,
     pub memory_type: Option<String>,
+    pub stream_id: Option<uuid::Uuid>,
 }
 
 #[derive(Serialize)]
@@ -585,6 +591,8 @@ pub struct DashboardMemoryListItemView {
     pub reference_count: usize,
     pub item_type: &'static str,
     pub memory_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<MemoryStream>,
 }
 
 impl From<DashboardMemoryRow> for DashboardMemoryListItemView {
@@ -601,6 +609,7 @@ impl From<DashboardMemoryRow> for DashboardMemoryListItemView {
             reference_count: row.reference_count,
             item_type: row.item_type,
             memory_type: row.memory_type,
+            stream: None,
         }
     }
 }
@@ -762,6 +771,7 @@ pub fn dashboard_memory_detail_view(
     DashboardMemoryDetailResponse {
         unit: DashboardMemoryDetailUnitView::from(unit),
         organization_knowledge,
+        stream: None,
     }
 }
 
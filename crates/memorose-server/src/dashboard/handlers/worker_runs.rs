@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// ── Worker run history ────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct WorkerRunsQuery {
+    #[serde(default = "default_worker_runs_limit")]
+    limit: usize,
+    cycle: Option<String>,
+}
+
+fn default_worker_runs_limit() -> usize {
+    50
+}
+
+/// Recent worker cycle runs across shards, newest first, with each shard's consolidation
+/// backlog so it is visible whether consolidation keeps up.
+pub async fn worker_runs(
+    State(state): State<Arc<crate::AppState>>,
+    Query(params): Query<WorkerRunsQuery>,
+) -> axum::response::Response {
+    let limit = params.limit.clamp(1, 500);
+    let cycle = params.cycle.as_deref().filter(|cycle| !cycle.is_empty());
+    let mut runs = Vec::new();
+    let mut pending_events = serde_json::Map::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        let shard_runs = match shard.engine.list_worker_runs(limit, cycle) {
+            Ok(shard_runs) => shard_runs,
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        };
+        runs.extend(shard_runs.into_iter().map(|run| (shard_id, run)));
+        if let Ok(pending) = shard.engine.count_pending_events().await {
+            pending_events.insert(shard_id.to_string(), pending.into());
+        }
+    }
+    runs.sort_by(|a, b| b.1.started_at.cmp(&a.1.started_at));
+    runs.truncate(limit);
+    let runs: Vec<serde_json::Value> = runs
+        .into_iter()
+        .map(|(shard_id, run)| serde_json::json!({ "shard_id": shard_id, "run": run }))
+        .collect();
+
+    Json(serde_json::json!({
+        "pending_events": pending_events,
+        "runs": runs,
+    }))
+    .into_response()
+}
@@ -3,6 +3,7 @@ use chrono::{DateTime, Utc};
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use tokio::sync::Mutex;
@@ -28,10 +29,137 @@ struct ApiKeyRecord {
     key_hash: String,
     created_at: DateTime<Utc>,
     revoked_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    scope: ApiKeyScope,
+}
+
+/// What an API key may read and write. Empty lists leave that dimension unrestricted,
+/// so keys created before scoping existed keep full access.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyScope {
+    /// App ids the key is bound to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub app_ids: Vec<String>,
+    /// User id patterns the key may act for; `*` matches any run of characters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_patterns: Vec<String>,
+}
+
+/// Routes outside `/v1/users/...` that a restricted key may call. The handlers behind
+/// them check the user and app named in the request body against the scope.
+const BODY_SCOPED_ROUTES: &[&str] = &[
+    "/v1/memory/context",
+    "/v1/dashboard/search",
+    "/v1/dashboard/chat",
+];
+
+/// Per-user routes without an `:app_id` segment that an app-bound key may call. Their
+/// handlers check the app of the stream, memory unit or request body against the scope;
+/// every other such route is refused to app-bound keys.
+const APP_CHECKED_USER_ROUTES: &[&str] = &[
+    "/v1/users/:user_id/streams/:stream_id/events",
+    "/v1/users/:user_id/streams/:stream_id/events/batch",
+    "/v1/users/:user_id/streams/:stream_id/retrieve",
+    "/v1/users/:user_id/ask",
+    "/v1/users/:user_id/feedback",
+    "/v1/users/:user_id/streams",
+    "/v1/users/:user_id/streams/:stream_id",
+    "/v1/users/:user_id/streams/:stream_id/archive",
+    "/v1/users/:user_id/memories/:id",
+    "/v1/users/:user_id/memories/:id/restore",
+    "/v1/users/:user_id/memories/:id/pin",
+];
+
+impl ApiKeyScope {
+    pub fn is_unrestricted(&self) -> bool {
+        self.app_ids.is_empty() && self.user_patterns.is_empty()
+    }
+
+    pub fn is_app_restricted(&self) -> bool {
+        !self.app_ids.is_empty()
+    }
+
+    pub fn allows_user(&self, user_id: &str) -> bool {
+        self.user_patterns.is_empty()
+            || self
+                .user_patterns
+                .iter()
+                .any(|pattern| glob_matches(pattern, user_id))
+    }
+
+    pub fn allows_app(&self, app_id: &str) -> bool {
+        self.app_ids.is_empty() || self.app_ids.iter().any(|allowed| allowed == app_id)
+    }
+
+    /// Check a request against the scope given its matched route and path parameters.
+    /// Restricted keys reach per-user routes for the users and apps they are bound to,
+    /// the status routes, and the routes in `BODY_SCOPED_ROUTES`; everything else
+    /// (groups, admin, organization management) is refused. App-bound keys only reach
+    /// per-user routes that name the app in the path or are in `APP_CHECKED_USER_ROUTES`.
+    pub fn authorize_route(
+        &self,
+        route: &str,
+        params: &HashMap<String, String>,
+    ) -> std::result::Result<(), String> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+
+        if route.starts_with("/v1/users/") {
+            if let Some(user_id) = params.get("user_id") {
+                if !self.allows_user(user_id) {
+                    return Err(format!("API key is not allowed to access user {}", user_id));
+                }
+                match params.get("app_id") {
+                    Some(app_id) if !self.allows_app(app_id) => {
+                        return Err(format!("API key is not allowed to access app {}", app_id));
+                    }
+                    None if self.is_app_restricted()
+                        && !APP_CHECKED_USER_ROUTES.contains(&route) =>
+                    {
+                        return Err(
+                            "API key is bound to specific apps and this endpoint is not app-scoped"
+                                .to_string(),
+                        );
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+        }
+
+        if route.starts_with("/v1/status/") || BODY_SCOPED_ROUTES.contains(&route) {
+            return Ok(());
+        }
+
+        Err("API key scope does not cover this endpoint".to_string())
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == value;
+    };
+    let Some(mut remaining) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        match remaining.find(part) {
+            Some(position) => remaining = &remaining[position + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= last.len() && remaining.ends_with(last)
 }
 
 #[derive(Debug, Clone)]
-pub struct AuthenticatedApiKey;
+pub struct AuthenticatedApiKey {
+    pub key_id: String,
+    pub scope: ApiKeyScope,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKeySummary {
@@ -42,6 +170,7 @@ pub struct ApiKeySummary {
     pub created_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub active: bool,
+    pub scope: ApiKeyScope,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +181,7 @@ pub struct CreatedApiKey {
     pub key_prefix: String,
     pub key: String,
     pub created_at: DateTime<Utc>,
+    pub scope: ApiKeyScope,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -149,6 +279,7 @@ impl ManagementRegistry {
         &self,
         org_id: &str,
         name: Option<String>,
+        scope: ApiKeyScope,
     ) -> Result<CreatedApiKey> {
         let _lock = self.file_lock.lock().await;
         let mut data = self.read_data()?;
@@ -157,6 +288,14 @@ impl ManagementRegistry {
         if !data.organizations.iter().any(|org| org.org_id == org_id) {
             return Err(anyhow!("organization does not exist"));
         }
+        if scope
+            .app_ids
+            .iter()
+            .chain(&scope.user_patterns)
+            .any(|value| value.trim().is_empty())
+        {
+            return Err(anyhow!("scope entries must not be empty"));
+        }
 
         let raw_key = generate_api_key();
         let created_at = Utc::now();
@@ -168,6 +307,7 @@ impl ManagementRegistry {
             key_hash: hash_api_key(&raw_key),
             created_at,
             revoked_at: None,
+            scope,
         };
 
         data.api_keys.push(record.clone());
@@ -180,6 +320,7 @@ impl ManagementRegistry {
             key_prefix: record.key_prefix,
             key: raw_key,
             created_at,
+            scope: record.scope,
         })
     }
 
@@ -212,7 +353,10 @@ impl ManagementRegistry {
 
         Ok(data.api_keys.into_iter().find_map(|record| {
             if record.revoked_at.is_none() && record.key_hash == hashed {
-                Some(AuthenticatedApiKey)
+                Some(AuthenticatedApiKey {
+                    key_id: record.key_id,
+                    scope: record.scope,
+                })
             } else {
                 None
             }
@@ -281,6 +425,7 @@ fn api_key_summary_from_record(record: &ApiKeyRecord) -> ApiKeySummary {
         created_at: record.created_at,
         revoked_at: record.revoked_at,
         active: record.revoked_at.is_none(),
+        scope: record.scope.clone(),
     }
 }
 
@@ -322,7 +467,11 @@ mod tests {
         let registry = ManagementRegistry::new(dir.path()).unwrap();
 
         let created = registry
-            .create_api_key("default", Some("My Key".to_string()))
+            .create_api_key(
+                "default",
+                Some("My Key".to_string()),
+                ApiKeyScope::default(),
+            )
             .await
             .unwrap();
         assert_eq!(created.org_id, "default");
@@ -356,4 +505,149 @@ mod tests {
             .unwrap()
             .is_none());
     }
+
+    #[tokio::test]
+    async fn test_registry_api_key_scope_round_trips() {
+        let dir = tempdir().unwrap();
+        let registry = ManagementRegistry::new(dir.path()).unwrap();
+        let scope = ApiKeyScope {
+            app_ids: vec!["support-bot".to_string()],
+            user_patterns: vec!["tenant-a:*".to_string()],
+        };
+
+        let created = registry
+            .create_api_key("default", None, scope.clone())
+            .await
+            .unwrap();
+        assert_eq!(created.scope, scope);
+
+        let auth = registry
+            .authenticate_api_key(&created.key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth.key_id, created.key_id);
+        assert_eq!(auth.scope, scope);
+
+        let empty = ApiKeyScope {
+            app_ids: vec![" ".to_string()],
+            user_patterns: Vec::new(),
+        };
+        assert!(registry
+            .create_api_key("default", None, empty)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_api_key_scope_matches_users_and_apps() {
+        let scope = ApiKeyScope {
+            app_ids: vec!["support-bot".to_string()],
+            user_patterns: vec!["tenant-a:*".to_string(), "admin".to_string()],
+        };
+        assert!(scope.allows_user("tenant-a:42"));
+        assert!(scope.allows_user("admin"));
+        assert!(!scope.allows_user("tenant-b:42"));
+        assert!(!scope.allows_user("admin2"));
+        assert!(scope.allows_app("support-bot"));
+        assert!(!scope.allows_app("billing"));
+
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "a-b-c"));
+        assert!(glob_matches("a*a", "aa"));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(!glob_matches("a*b", "a-c"));
+
+        let unrestricted = ApiKeyScope::default();
+        assert!(unrestricted.is_unrestricted());
+        assert!(unrestricted.allows_user("anyone"));
+        assert!(unrestricted.allows_app("any-app"));
+    }
+
+    #[test]
+    fn test_api_key_scope_authorizes_routes() {
+        let scope = ApiKeyScope {
+            app_ids: vec!["support-bot".to_string()],
+            user_patterns: vec!["tenant-a:*".to_string()],
+        };
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        assert!(scope
+            .authorize_route(
+                "/v1/users/:user_id/streams/:stream_id/events",
+                &params(&[("user_id", "tenant-a:1"), ("stream_id", "s")])
+            )
+            .is_ok());
+        assert!(scope
+            .authorize_route(
+                "/v1/users/:user_id/streams/:stream_id/events",
+                &params(&[("user_id", "tenant-b:1"), ("stream_id", "s")])
+            )
+            .is_err());
+        assert!(scope
+            .authorize_route(
+                "/v1/users/:user_id/apps/:app_id/memories",
+                &params(&[("user_id", "tenant-a:1"), ("app_id", "support-bot")])
+            )
+            .is_ok());
+        assert!(scope
+            .authorize_route(
+                "/v1/users/:user_id/apps/:app_id/tasks/tree",
+                &params(&[("user_id", "tenant-a:1"), ("app_id", "billing")])
+            )
+            .is_err());
+        assert!(scope
+            .authorize_route("/v1/memory/context", &HashMap::new())
+            .is_ok());
+        assert!(scope
+            .authorize_route("/v1/status/pending", &HashMap::new())
+            .is_ok());
+        assert!(scope
+            .authorize_route(
+                "/v1/groups/:group_id/members/:user_id",
+                &params(&[("group_id", "g"), ("user_id", "tenant-a:1")])
+            )
+            .is_err());
+        assert!(ApiKeyScope::default()
+            .authorize_route("/v1/admin/anything", &HashMap::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_app_bound_key_is_refused_on_routes_that_do_not_check_the_app() {
+        let scope = ApiKeyScope {
+            app_ids: vec!["support-bot".to_string()],
+            user_patterns: Vec::new(),
+        };
+        let user = HashMap::from([("user_id".to_string(), "alice".to_string())]);
+        for route in [
+            "/v1/users/:user_id/shares",
+            "/v1/users/:user_id/shares/:grant_id",
+            "/v1/users/:user_id/encryption-key",
+            "/v1/users/:user_id/graph/export",
+            "/v1/users/:user_id/graph/diff",
+            "/v1/users/:user_id/digest",
+            "/v1/users/:user_id/tasks/tree",
+            "/v1/users/:user_id/trash",
+        ] {
+            assert!(scope.authorize_route(route, &user).is_err(), "{}", route);
+        }
+        for route in APP_CHECKED_USER_ROUTES {
+            assert!(scope.authorize_route(route, &user).is_ok(), "{}", route);
+        }
+
+        // A key restricted to users only keeps every app of those users.
+        let users_only = ApiKeyScope {
+            app_ids: Vec::new(),
+            user_patterns: vec!["alice".to_string()],
+        };
+        assert!(users_only
+            .authorize_route("/v1/users/:user_id/encryption-key", &user)
+            .is_ok());
+    }
 }
@@ -0,0 +1,198 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use memorose_common::config::DiskWatchdogConfig;
+use serde::Serialize;
+
+use crate::AppState;
+
+/// How close the data volume is to full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLevel {
+    #[default]
+    Ok,
+    /// Free space is below `warn_free_percent`
+    Warning,
+    /// Free space fell below `read_only_free_percent`; writes are rejected
+    ReadOnly,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskStatus {
+    pub path: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub free_percent: f64,
+    pub level: DiskLevel,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Last free-space reading of the data volume, and whether it put the node in
+/// read-only mode.
+#[derive(Default)]
+pub struct DiskWatchdog {
+    read_only: AtomicBool,
+    status: RwLock<Option<DiskStatus>>,
+}
+
+impl DiskWatchdog {
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    #[cfg(test)]
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> Option<DiskStatus> {
+        self.status.read().ok().and_then(|status| status.clone())
+    }
+
+    /// The level for `free_percent`. A read-only node stays read-only until free space
+    /// recovers past the warning threshold, so it does not flap around the limit.
+    pub fn classify(
+        config: &DiskWatchdogConfig,
+        previous: DiskLevel,
+        free_percent: f64,
+    ) -> DiskLevel {
+        if free_percent < config.read_only_free_percent {
+            DiskLevel::ReadOnly
+        } else if free_percent < config.warn_free_percent {
+            if previous == DiskLevel::ReadOnly {
+                DiskLevel::ReadOnly
+            } else {
+                DiskLevel::Warning
+            }
+        } else {
+            DiskLevel::Ok
+        }
+    }
+
+    /// Read free space under `path` and update the level. Returns the new status and
+    /// whether the level changed.
+    fn check(
+        &self,
+        config: &DiskWatchdogConfig,
+        path: &Path,
+    ) -> std::io::Result<(DiskStatus, bool)> {
+        let stats = fs4::statvfs(path)?;
+        let total_bytes = stats.total_space();
+        let available_bytes = stats.available_space();
+        let free_percent = if total_bytes == 0 {
+            100.0
+        } else {
+            available_bytes as f64 * 100.0 / total_bytes as f64
+        };
+        let previous = self.status().map(|status| status.level).unwrap_or_default();
+        let level = Self::classify(config, previous, free_percent);
+        let status = DiskStatus {
+            path: path.display().to_string(),
+            available_bytes,
+            total_bytes,
+            free_percent,
+            level,
+            checked_at: Utc::now(),
+        };
+        self.read_only
+            .store(level == DiskLevel::ReadOnly, Ordering::Relaxed);
+        if let Ok(mut current) = self.status.write() {
+            *current = Some(status.clone());
+        }
+        Ok((status, level != previous))
+    }
+}
+
+/// Check free space every `disk_watchdog.check_interval_secs`, log and post level
+/// changes to `disk_watchdog.webhook_url`.
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            let config = state.config.load();
+            let watchdog = &config.disk_watchdog;
+            if watchdog.check_interval_secs == 0 {
+                state
+                    .disk_watchdog
+                    .read_only
+                    .store(false, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+            let path = std::path::PathBuf::from(&config.storage.root_dir);
+            match state.disk_watchdog.check(watchdog, &path) {
+                Ok((status, true)) => {
+                    match status.level {
+                        DiskLevel::Ok => tracing::info!(
+                            "Disk space recovered: {:.1}% free under {}",
+                            status.free_percent,
+                            status.path
+                        ),
+                        DiskLevel::Warning => tracing::warn!(
+                            "Disk space low: {:.1}% free under {}",
+                            status.free_percent,
+                            status.path
+                        ),
+                        DiskLevel::ReadOnly => tracing::error!(
+                            "Disk space critical: {:.1}% free under {}; rejecting ingest",
+                            status.free_percent,
+                            status.path
+                        ),
+                    }
+                    if let Some(url) = watchdog.webhook_url.as_deref() {
+                        let payload = serde_json::json!({
+                            "node_id": config.raft.node_id,
+                            "disk": status,
+                        });
+                        let result = state
+                            .http_client
+                            .post(url)
+                            .json(&payload)
+                            .send()
+                            .await
+                            .and_then(|response| response.error_for_status());
+                        if let Err(e) = result {
+                            tracing::warn!("Disk watchdog webhook failed: {:?}", e);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to read free space under {:?}: {:?}", path, e),
+            }
+            tokio::time::sleep(Duration::from_secs(watchdog.check_interval_secs)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_holds_read_only_until_space_recovers() {
+        let config = DiskWatchdogConfig::default();
+        assert_eq!(
+            DiskWatchdog::classify(&config, DiskLevel::Ok, 50.0),
+            DiskLevel::Ok
+        );
+        assert_eq!(
+            DiskWatchdog::classify(&config, DiskLevel::Ok, 8.0),
+            DiskLevel::Warning
+        );
+        assert_eq!(
+            DiskWatchdog::classify(&config, DiskLevel::Warning, 4.0),
+            DiskLevel::ReadOnly
+        );
+        assert_eq!(
+            DiskWatchdog::classify(&config, DiskLevel::ReadOnly, 8.0),
+            DiskLevel::ReadOnly
+        );
+        assert_eq!(
+            DiskWatchdog::classify(&config, DiskLevel::ReadOnly, 12.0),
+            DiskLevel::Ok
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Context, Result};
+use memorose_common::config::AppConfig;
+use memorose_core::eval::{evaluate, load_cases, RankingConfig};
+use memorose_core::llm::create_llm_client;
+use memorose_core::MemoroseEngine;
+use std::path::PathBuf;
+
+const DEFAULT_EVAL_K: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalCommand {
+    dataset: PathBuf,
+    configs: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    k: usize,
+}
+
+pub async fn run_from_env_if_requested(config: &AppConfig) -> Result<bool> {
+    let Some(command) = parse_eval_command(std::env::args()).map_err(|error| anyhow!(error))?
+    else {
+        return Ok(false);
+    };
+    run_eval_command(command, config).await?;
+    Ok(true)
+}
+
+pub fn parse_eval_command<I, S>(args: I) -> std::result::Result<Option<EvalCommand>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+    if args.len() <= 1 {
+        return Ok(None);
+    }
+    args.remove(0);
+    if args.first().map(String::as_str) != Some("eval") {
+        return Ok(None);
+    }
+    args.remove(0);
+
+    let mut dataset = None;
+    let mut configs = None;
+    let mut data_dir = None;
+    let mut k = DEFAULT_EVAL_K;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dataset" => dataset = iter.next().map(PathBuf::from),
+            "--configs" => configs = iter.next().map(PathBuf::from),
+            "--data-dir" => data_dir = iter.next().map(PathBuf::from),
+            "--k" => {
+                let Some(value) = iter.next() else {
+                    return Err(eval_usage());
+                };
+                k = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|k| *k > 0)
+                    .ok_or_else(|| "invalid --k value".to_string())?;
+            }
+            _ => return Err(eval_usage()),
+        }
+    }
+    let Some(dataset) = dataset else {
+        return Err(eval_usage());
+    };
+    Ok(Some(EvalCommand {
+        dataset,
+        configs,
+        data_dir,
+        k,
+    }))
+}
+
+async fn run_eval_command(command: EvalCommand, config: &AppConfig) -> Result<()> {
+    let cases = load_cases(&command.dataset)?;
+    let configs: Vec<RankingConfig> = match &command.configs {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read ranking configs {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("Invalid ranking configs {}", path.display()))?
+        }
+        None => vec![RankingConfig::baseline()],
+    };
+    if configs.is_empty() {
+        return Err(anyhow!("No ranking configs to evaluate"));
+    }
+    let client = create_llm_client(&config.llm)
+        .ok_or_else(|| anyhow!("An LLM provider is required to embed eval queries"))?;
+
+    let engine = MemoroseEngine::new_with_storage_config(
+        command
+            .data_dir
+            .unwrap_or_else(|| PathBuf::from(&config.storage.root_dir)),
+        config.storage.clone(),
+        false,
+        false,
+        config.worker.auto_link_similarity_threshold,
+        config.llm.embedding_dim,
+    )
+    .await?;
+
+    let report = evaluate(&engine, client.as_ref(), &cases, &configs, command.k).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn eval_usage() -> String {
+    [
+        "Usage:",
+        "  memorose-server eval --dataset <FILE> [--configs <FILE>] [--data-dir <DIR>] [--k <N>]",
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eval_command() {
+        let command = parse_eval_command([
+            "memorose-server",
+            "eval",
+            "--dataset",
+            "golden.jsonl",
+            "--configs",
+            "configs.json",
+            "--k",
+            "5",
+        ])
+        .expect("eval command should parse")
+        .expect("eval command should be detected");
+
+        assert_eq!(
+            command,
+            EvalCommand {
+                dataset: "golden.jsonl".into(),
+                configs: Some("configs.json".into()),
+                data_dir: None,
+                k: 5,
+            }
+        );
+        assert_eq!(
+            parse_eval_command(["memorose-server", "repair", "vector-status"]),
+            Ok(None)
+        );
+        assert!(parse_eval_command(["memorose-server", "eval", "--k", "5"]).is_err());
+        assert!(
+            parse_eval_command(["memorose-server", "eval", "--dataset", "a", "--k", "0"]).is_err()
+        );
+    }
+}
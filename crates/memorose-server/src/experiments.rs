@@ -0,0 +1,248 @@
+//! Ranking experiments: which variant ranks a retrieval, the feedback that scores it,
+//! and the per-variant results.
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use memorose_common::config::RankingVariantConfig;
+use memorose_common::{ExperimentArm, RetrievalFeedback};
+use memorose_core::engine::{ExperimentVariantOutcome, RankingOverrides};
+use memorose_core::MemoroseEngine;
+use std::sync::Arc;
+
+use crate::dashboard::registry::ApiKeyScope;
+use crate::types::{ExperimentResultsResponse, ExperimentVariantResults, RetrievalFeedbackRequest};
+use crate::AppState;
+
+/// Feedback naming more memories than a retrieval can return is refused.
+const MAX_FEEDBACK_IDS: usize = 1000;
+
+/// The variant that ranks `user_id`'s next retrieval, while an experiment runs.
+pub(crate) fn assign_variant(
+    state: &AppState,
+    user_id: &str,
+) -> Option<(ExperimentArm, RankingVariantConfig)> {
+    let config = state.config.load();
+    let experiment = config.experiment.as_ref()?;
+    let variant = experiment.assign(user_id)?;
+    Some((
+        ExperimentArm {
+            experiment: experiment.name.clone(),
+            variant: variant.name.clone(),
+        },
+        variant.clone(),
+    ))
+}
+
+/// A handle on `engine` with the variant's engine-level ranking, when it changes any.
+pub(crate) fn variant_engine(
+    state: &AppState,
+    engine: &MemoroseEngine,
+    variant: &RankingVariantConfig,
+) -> Option<MemoroseEngine> {
+    if variant.rrf_k.is_none()
+        && variant.recency_half_life_hours.is_none()
+        && variant.reranker_endpoint.is_none()
+    {
+        return None;
+    }
+    let mut engine = engine.clone().with_ranking_overrides(RankingOverrides {
+        rrf_k: variant.rrf_k,
+        recency_half_life_hours: variant.recency_half_life_hours,
+    });
+    if let Some(endpoint) = &variant.reranker_endpoint {
+        engine = engine.with_reranker(Arc::new(
+            memorose_core::reranker::HttpReranker::with_client(
+                endpoint.clone(),
+                state.http_client.clone(),
+            ),
+        ));
+    }
+    Some(engine)
+}
+
+/// Record which retrieved memories the client used. The reranker learns from it, and
+/// it counts towards the experiment variant that served the retrieval.
+pub(crate) async fn record_feedback(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Json(payload): Json<RetrievalFeedbackRequest>,
+) -> axum::response::Response {
+    let bad_request = |message: String| {
+        (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response()
+    };
+    if let Err(r) = crate::validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Some(agent_id) = payload.agent_id.as_deref() {
+        if let Err(r) = crate::validate_id(agent_id, "agent_id") {
+            return r;
+        }
+    }
+    let requested_apps = payload.agent_id.as_deref().map(|agent_id| vec![agent_id]);
+    if let Err(r) = crate::check_key_scope(&key_scope, &user_id, requested_apps.as_deref()) {
+        return r;
+    }
+    if payload.retrieved_ids.is_empty() {
+        return bad_request("retrieved_ids must list the results shown".to_string());
+    }
+    if payload.retrieved_ids.len() > MAX_FEEDBACK_IDS || payload.cited_ids.len() > MAX_FEEDBACK_IDS
+    {
+        return bad_request(format!(
+            "retrieved_ids and cited_ids may list at most {} ids",
+            MAX_FEEDBACK_IDS
+        ));
+    }
+    if let Some(arm) = &payload.experiment {
+        let config = state.config.load();
+        let known = config
+            .experiment
+            .as_ref()
+            .filter(|experiment| experiment.name == arm.experiment)
+            .and_then(|experiment| experiment.variant(&arm.variant))
+            .is_some();
+        if !known {
+            return bad_request(format!(
+                "experiment {:?} has no running variant {:?}",
+                arm.experiment, arm.variant
+            ));
+        }
+    }
+
+    let feedback = RetrievalFeedback {
+        user_id: user_id.clone(),
+        retrieved_ids: payload.retrieved_ids,
+        cited_ids: payload.cited_ids,
+        arm: payload.experiment,
+        recorded_at: chrono::Utc::now(),
+    };
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let applied = if state.is_standalone_mode() {
+        shard
+            .engine
+            .apply_retrieval_feedback(&feedback)
+            .await
+            .map(|_| true)
+    } else {
+        crate::replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::RecordRetrievalFeedback(feedback),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => Json(serde_json::json!({ "status": "recorded" })).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Feedback was not applied" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Retrieval feedback error: {:?}", e);
+            crate::replication_error_response(&state, &e)
+        }
+    }
+}
+
+/// Per-variant results of an experiment, summed over this node's shards. Configured
+/// variants come first, in config order, then any that have since been removed.
+pub(crate) async fn experiment_results(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = crate::validate_id(&name, "experiment") {
+        return r;
+    }
+    let mut totals: Vec<ExperimentVariantOutcome> = Vec::new();
+    for (_, shard) in state.shard_manager.all_shards() {
+        let outcomes = match shard.engine.experiment_outcomes(&name) {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        };
+        for outcome in outcomes {
+            match totals
+                .iter_mut()
+                .find(|total| total.variant == outcome.variant)
+            {
+                Some(total) => {
+                    total.feedback += outcome.feedback;
+                    total.retrieved += outcome.retrieved;
+                    total.cited += outcome.cited;
+                    total.useful += outcome.useful;
+                    total.reciprocal_rank_sum += outcome.reciprocal_rank_sum;
+                }
+                None => totals.push(outcome),
+            }
+        }
+    }
+
+    let config = state.config.load();
+    let experiment = config
+        .experiment
+        .as_ref()
+        .filter(|experiment| experiment.name == name);
+    let mut variants: Vec<ExperimentVariantResults> = experiment
+        .map(|experiment| {
+            experiment
+                .variants
+                .iter()
+                .map(|variant| {
+                    let outcome = totals
+                        .iter()
+                        .find(|total| total.variant == variant.name)
+                        .cloned()
+                        .unwrap_or_else(|| ExperimentVariantOutcome {
+                            variant: variant.name.clone(),
+                            ..Default::default()
+                        });
+                    variant_results(&outcome, Some(variant.weight))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    for outcome in &totals {
+        if !variants
+            .iter()
+            .any(|variant| variant.variant == outcome.variant)
+        {
+            variants.push(variant_results(outcome, None));
+        }
+    }
+
+    Json(ExperimentResultsResponse {
+        experiment: name,
+        active: experiment.is_some(),
+        variants,
+    })
+    .into_response()
+}
+
+fn variant_results(
+    outcome: &ExperimentVariantOutcome,
+    weight: Option<u32>,
+) -> ExperimentVariantResults {
+    ExperimentVariantResults {
+        variant: outcome.variant.clone(),
+        weight,
+        feedback: outcome.feedback,
+        retrieved: outcome.retrieved,
+        cited: outcome.cited,
+        useful: outcome.useful,
+        citation_rate: outcome.citation_rate(),
+        success_rate: outcome.success_rate(),
+        mean_reciprocal_rank: outcome.mean_reciprocal_rank(),
+    }
+}
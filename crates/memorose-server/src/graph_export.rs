@@ -0,0 +1,334 @@
+//! Export of a user's memory graph as GraphML, DOT or JSON for external graph tools.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use memorose_common::RelationType;
+use memorose_core::engine::{GraphExport, GraphExportFilter};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::types::GraphExportQuery;
+use crate::{validate_id, xml_escape, AppState};
+
+/// Most edges one export returns.
+const MAX_GRAPH_EXPORT_EDGES: usize = 50_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GraphExportFormat {
+    GraphMl,
+    Dot,
+    Json,
+}
+
+impl GraphExportFormat {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw
+            .map(|value| value.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("json") => Some(Self::Json),
+            Some("graphml") => Some(Self::GraphMl),
+            Some("dot") => Some(Self::Dot),
+            _ => None,
+        }
+    }
+}
+
+fn bad_request(error: String) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({ "error": error })),
+    )
+        .into_response()
+}
+
+fn parse_list(raw: Option<&str>) -> impl Iterator<Item = &str> {
+    raw.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+pub(crate) async fn export_graph(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<GraphExportQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let Some(format) = GraphExportFormat::parse(query.format.as_deref()) else {
+        return bad_request("format must be graphml, dot or json".to_string());
+    };
+    let relations: Vec<RelationType> = parse_list(query.relations.as_deref())
+        .map(RelationType::from_str)
+        .collect();
+    if let Some(invalid) = relations.iter().find(|relation| {
+        relation.is_custom() && !RelationType::is_valid_custom_name(relation.as_str())
+    }) {
+        return bad_request(format!("Invalid relation '{}'", invalid.as_str()));
+    }
+    let mut levels = Vec::new();
+    for level in parse_list(query.levels.as_deref()) {
+        match level.parse::<u8>() {
+            Ok(level) => levels.push(level),
+            Err(_) => return bad_request(format!("Invalid level '{}'", level)),
+        }
+    }
+    let filter = GraphExportFilter {
+        relations,
+        levels,
+        min_weight: query.min_weight,
+        community_id: query.community_id,
+        max_edges: query
+            .max_edges
+            .unwrap_or(MAX_GRAPH_EXPORT_EDGES)
+            .clamp(1, MAX_GRAPH_EXPORT_EDGES),
+    };
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let export = match shard.engine.export_graph(&user_id, &filter).await {
+        Ok(export) => export,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let (body, content_type, extension) = match format {
+        GraphExportFormat::Json => return Json(export).into_response(),
+        GraphExportFormat::GraphMl => (
+            render_graphml(&export),
+            "application/graphml+xml",
+            "graphml",
+        ),
+        GraphExportFormat::Dot => (render_dot(&export), "text/vnd.graphviz", "dot"),
+    };
+    let disposition = format!(
+        "attachment; filename=\"memorose-graph-{}.{}\"",
+        user_id, extension
+    );
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// GraphML, which Gephi, Cytoscape and NetworkX read with node and edge attributes.
+fn render_graphml(export: &GraphExport) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+         \x20 <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"level\" for=\"node\" attr.name=\"level\" attr.type=\"int\"/>\n\
+         \x20 <key id=\"memory_type\" for=\"node\" attr.name=\"memory_type\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"importance\" for=\"node\" attr.name=\"importance\" attr.type=\"double\"/>\n\
+         \x20 <key id=\"community\" for=\"node\" attr.name=\"community\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+    );
+    let _ = writeln!(
+        out,
+        "  <graph id=\"{}\" edgedefault=\"directed\">",
+        xml_escape(&export.user_id)
+    );
+    for node in &export.nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", node.id);
+        let _ = writeln!(
+            out,
+            "      <data key=\"label\">{}</data>",
+            xml_escape(&node.label)
+        );
+        let _ = writeln!(out, "      <data key=\"level\">{}</data>", node.level);
+        let _ = writeln!(
+            out,
+            "      <data key=\"memory_type\">{}</data>",
+            memory_type_name(node)
+        );
+        let _ = writeln!(
+            out,
+            "      <data key=\"importance\">{}</data>",
+            node.importance
+        );
+        if let Some(community_id) = node.community_id {
+            let _ = writeln!(out, "      <data key=\"community\">{}</data>", community_id);
+        }
+        out.push_str("    </node>\n");
+    }
+    for (index, edge) in export.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">",
+            index, edge.source, edge.target
+        );
+        let _ = writeln!(
+            out,
+            "      <data key=\"relation\">{}</data>",
+            xml_escape(edge.relation.as_str())
+        );
+        let _ = writeln!(out, "      <data key=\"weight\">{}</data>", edge.weight);
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// Graphviz DOT, with each community drawn as a cluster.
+fn render_dot(export: &GraphExport) -> String {
+    let mut out = String::from("digraph memories {\n");
+    let mut communities: Vec<_> = export
+        .nodes
+        .iter()
+        .filter_map(|node| node.community_id)
+        .collect();
+    communities.sort();
+    communities.dedup();
+    for community_id in &communities {
+        let _ = writeln!(out, "  subgraph \"cluster_{}\" {{", community_id);
+        let _ = writeln!(out, "    label=\"{}\";", community_id);
+        for node in export
+            .nodes
+            .iter()
+            .filter(|node| node.community_id == Some(*community_id))
+        {
+            let _ = writeln!(out, "    {}", dot_node(node));
+        }
+        out.push_str("  }\n");
+    }
+    for node in export
+        .nodes
+        .iter()
+        .filter(|node| node.community_id.is_none())
+    {
+        let _ = writeln!(out, "  {}", dot_node(node));
+    }
+    for edge in &export.edges {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [label=\"{}\", weight={}];",
+            edge.source,
+            edge.target,
+            dot_escape(edge.relation.as_str()),
+            edge.weight
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_node(node: &memorose_core::engine::GraphExportNode) -> String {
+    format!(
+        "\"{}\" [label=\"{}\", level={}, memory_type=\"{}\", importance={}];",
+        node.id,
+        dot_escape(&node.label),
+        node.level,
+        memory_type_name(node),
+        node.importance
+    )
+}
+
+fn dot_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn memory_type_name(node: &memorose_core::engine::GraphExportNode) -> String {
+    serde_json::to_value(&node.memory_type)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memorose_common::MemoryType;
+    use memorose_core::engine::{GraphExportEdge, GraphExportNode};
+    use uuid::Uuid;
+
+    fn sample_export() -> GraphExport {
+        let community = Uuid::new_v4();
+        let node = |label: &str, community_id| GraphExportNode {
+            id: Uuid::new_v4(),
+            label: label.to_string(),
+            level: 1,
+            memory_type: MemoryType::Factual,
+            importance: 0.5,
+            community_id,
+            transaction_time: chrono::Utc::now(),
+        };
+        let a = node("Likes \"jazz\" & <blues>", Some(community));
+        let b = node("Plays piano", None);
+        GraphExport {
+            user_id: "u1".into(),
+            exported_at: chrono::Utc::now(),
+            edges: vec![GraphExportEdge {
+                source: a.id,
+                target: b.id,
+                relation: RelationType::Custom("inspired_by".into()),
+                weight: 0.75,
+            }],
+            nodes: vec![a, b],
+        }
+    }
+
+    #[test]
+    fn test_graph_export_format_parses_known_names() {
+        assert_eq!(
+            GraphExportFormat::parse(None),
+            Some(GraphExportFormat::Json)
+        );
+        assert_eq!(
+            GraphExportFormat::parse(Some("GraphML")),
+            Some(GraphExportFormat::GraphMl)
+        );
+        assert_eq!(
+            GraphExportFormat::parse(Some("dot")),
+            Some(GraphExportFormat::Dot)
+        );
+        assert_eq!(GraphExportFormat::parse(Some("gexf")), None);
+    }
+
+    #[test]
+    fn test_render_graphml_escapes_labels_and_keeps_attributes() {
+        let export = sample_export();
+        let graphml = render_graphml(&export);
+        assert!(graphml.contains("Likes &quot;jazz&quot; &amp; &lt;blues&gt;"));
+        assert!(graphml.contains(&format!(
+            "source=\"{}\" target=\"{}\"",
+            export.edges[0].source, export.edges[0].target
+        )));
+        assert!(graphml.contains("<data key=\"relation\">inspired_by</data>"));
+        assert!(graphml.contains("<data key=\"memory_type\">factual</data>"));
+        assert!(graphml.contains(&format!(
+            "<data key=\"community\">{}</data>",
+            export.nodes[0].community_id.unwrap()
+        )));
+    }
+
+    #[test]
+    fn test_render_dot_clusters_communities() {
+        let export = sample_export();
+        let dot = render_dot(&export);
+        assert!(dot.starts_with("digraph memories {"));
+        assert!(dot.contains(&format!(
+            "subgraph \"cluster_{}\"",
+            export.nodes[0].community_id.unwrap()
+        )));
+        assert!(dot.contains("label=\"Likes \\\"jazz\\\" & <blues>\""));
+        assert!(dot.contains("[label=\"inspired_by\", weight=0.75]"));
+    }
+}
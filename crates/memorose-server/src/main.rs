@@ -1,17 +1,30 @@
 use axum::{
-    extract::{OriginalUri, Path, State},
+    extract::{
+        ws::{Message, WebSocketUpgrade},
+        MatchedPath, OriginalUri, Path, Query, State,
+    },
     http::HeaderMap,
     middleware as axum_middleware,
     response::{IntoResponse, Redirect},
     routing::{delete, get, post, put},
-    Json, Router,
+    Extension, Json, Router,
 };
+use futures_util::StreamExt;
 use memorose_common::sharding::decode_raft_node_id;
 use memorose_common::{
-    config::AppConfig, tokenizer::count_tokens, Asset, Event, EventContent, GraphEdge, MemoryType,
-    MemoryUnit, TimeRange,
+    config::{
+        AppConfig, ConfigReloadReport, LiveConfig, ModerationIngestAction, NodeRole, PromptKind,
+    },
+    tokenizer::count_tokens,
+    Asset, Event, EventContent, GraphEdge, MemoryType, MemoryUnit, ModerationAuditRecord,
+    ModerationOutcome, ModerationStage, RelationType, TimeRange,
+};
+use memorose_core::moderation::ContentModeration;
+use memorose_core::raft::leader_base_url;
+use memorose_core::raft::types::MemoroseNode;
+use memorose_core::{
+    IngestAdmission, LLMClient, MemoroseEngine, RetrievalStageTimings, SharedSearchHit,
 };
-use memorose_core::{LLMClient, MemoroseEngine, SharedSearchHit};
 use moka::future::Cache;
 use std::cmp::Ordering;
 use std::net::SocketAddr;
@@ -20,19 +33,36 @@ use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
+mod cache;
+mod consolidation_pool;
 mod dashboard;
+mod disk_watchdog;
+mod eval_cli;
+mod experiments;
+mod graph_export;
 mod repair_cli;
+mod replay_cli;
 mod shard_manager;
+mod slow_query;
 pub mod types;
+mod verify_cli;
 
 use types::{
-    default_context_token_budget, public_asset_storage_key, AddEdgeRequest, BatchIngestRequest,
-    ContextCompressionTier, ContextFormat, GoalMemoryUnitView, GoalTree, IngestRequest,
-    JoinRequest, L3TaskTree, MemoryContextHitView, MemoryContextRequest, MemoryContextResponse,
-    RenderedMemoryContext, RetrievalMemoryUnitView, RetrieveRequest, RetrieveResponse,
-    RetrieveResultItem, UpdateTaskStatusRequest,
+    default_context_token_budget, public_asset_storage_key, AddEdgeRequest,
+    AddTaskDependencyRequest, AskRequest, AskResponse, AskSupportingItem, AssetQuery,
+    BatchAddEdgesRequest, BatchIngestRequest, CommunitiesQuery, CommunitiesResponse, CommunityView,
+    ContextCompressionTier, ContextFormat, CreateMemoryRequest, CreateShareGrantRequest,
+    CreateStreamRequest, CreateTaskRequest, DeleteMemoryQuery, DigestQuery, DigestResponse,
+    GoalMemoryUnitView, GoalTree, GraphDiffQuery, GraphQueryExplainRequest, IndexCommitQuery,
+    IngestMode, IngestQuery, IngestRequest, JoinRequest, L3TaskTree, ListStreamsQuery,
+    MemoryContextHitView, MemoryContextRequest, MemoryContextResponse, ModerationAuditQuery,
+    PatchUserProfileRequest, PutRelationDefinitionRequest, QueryAssetRef, ReadConsistency,
+    ReadYourWrites, RegisterUserKeyRequest, RenderedMemoryContext, RetrievalMemoryUnitView,
+    RetrievalScope, RetrieveRequest, RetrieveResponse, RetrieveResultItem, UpdateTaskStatusRequest,
+    UserProfileResponse, ValidatePromptRequest,
 };
 
+use dashboard::registry::ApiKeyScope;
 use shard_manager::ShardManager;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -44,16 +74,21 @@ enum RuntimeMode {
 struct AppState {
     shard_manager: ShardManager,
     llm_client: Arc<dyn LLMClient>,
-    embedding_cache: Cache<String, Vec<f32>>,
-    config: AppConfig,
+    embedding_cache: Arc<dyn cache::Cache<Vec<f32>>>,
+    config: LiveConfig,
     runtime_mode: RuntimeMode,
     start_time: std::time::Instant,
     dashboard_auth: dashboard::auth::DashboardAuth,
     management_registry: dashboard::registry::ManagementRegistry,
     login_limiter: Cache<String, u32>,
-    dashboard_cache: Cache<String, serde_json::Value>,
+    dashboard_cache: Arc<dyn cache::Cache<serde_json::Value>>,
     /// Shared HTTP client for leader-forwarding; reusing it preserves connection pools.
     http_client: reqwest::Client,
+    slow_queries: slow_query::SlowQueryLog,
+    /// Content safety checks; `None` when `moderation.provider` is `none`.
+    moderation: Option<ContentModeration>,
+    /// Free space on the data volume; a nearly full volume makes the node read-only.
+    disk_watchdog: disk_watchdog::DiskWatchdog,
 }
 
 impl AppState {
@@ -73,6 +108,45 @@ impl AppState {
     }
 }
 
+#[cfg(test)]
+impl AppState {
+    /// A standalone single-shard node over `config.storage.root_dir`, for handler tests.
+    async fn for_tests(config: AppConfig, llm_client: Arc<dyn LLMClient>) -> Arc<Self> {
+        let data_dir = std::path::PathBuf::from(&config.storage.root_dir);
+        let live_config = LiveConfig::new(config.clone());
+        Arc::new(Self {
+            shard_manager: ShardManager::new_single_shard(&live_config).await.unwrap(),
+            llm_client,
+            embedding_cache: cache::build(
+                &config.cache,
+                "embedding",
+                100,
+                std::time::Duration::from_secs(60),
+            )
+            .await
+            .unwrap(),
+            config: live_config,
+            runtime_mode: RuntimeMode::Standalone,
+            start_time: std::time::Instant::now(),
+            dashboard_auth: dashboard::auth::DashboardAuth::new(&data_dir).unwrap(),
+            management_registry: dashboard::registry::ManagementRegistry::new(&data_dir).unwrap(),
+            login_limiter: Cache::builder().build(),
+            dashboard_cache: cache::build(
+                &config.cache,
+                "dashboard",
+                100,
+                std::time::Duration::from_secs(60),
+            )
+            .await
+            .unwrap(),
+            http_client: reqwest::Client::new(),
+            slow_queries: slow_query::SlowQueryLog::new(config.slow_query.capacity),
+            moderation: None,
+            disk_watchdog: disk_watchdog::DiskWatchdog::default(),
+        })
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
@@ -101,6 +175,30 @@ async fn main() {
             std::process::exit(2);
         }
     }
+    match eval_cli::run_from_env_if_requested(&config).await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(error) => {
+            eprintln!("eval command failed: {error:?}");
+            std::process::exit(2);
+        }
+    }
+    match replay_cli::run_from_env_if_requested(&config).await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(error) => {
+            eprintln!("replay command failed: {error:?}");
+            std::process::exit(2);
+        }
+    }
+    match verify_cli::run_from_env_if_requested().await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(error) => {
+            eprintln!("verify command failed: {error:?}");
+            std::process::exit(2);
+        }
+    }
 
     let runtime_mode = if config.is_standalone_mode() {
         RuntimeMode::Standalone
@@ -120,6 +218,7 @@ async fn main() {
     );
 
     let data_dir = config.storage.root_dir.clone();
+    let live_config = LiveConfig::new(config.clone());
 
     // Initialize shard manager (handles engine, raft, workers for all shards)
     let shard_manager = if config.is_sharded() {
@@ -128,7 +227,7 @@ async fn main() {
             config.shard_count(),
             config.physical_node_id()
         );
-        ShardManager::new(&config)
+        ShardManager::new(&live_config)
             .await
             .expect("Failed to start ShardManager")
     } else {
@@ -144,7 +243,7 @@ async fn main() {
                 config.raft.node_id
             );
         }
-        ShardManager::new_single_shard(&config)
+        ShardManager::new_single_shard(&live_config)
             .await
             .expect("Failed to start single-shard ShardManager")
     };
@@ -159,7 +258,15 @@ async fn main() {
         config.get_embedding_model_name()
     );
 
-    let embedding_cache = Cache::new(10_000);
+    let embedding_cache = cache::build_persistent(
+        &config.cache,
+        "embedding",
+        10_000,
+        std::time::Duration::from_secs(config.cache.embedding_ttl_secs),
+        std::path::Path::new(&data_dir),
+    )
+    .await
+    .expect("Failed to initialize embedding cache");
 
     // Initialize dashboard auth
     let auth_dir = std::path::Path::new(&data_dir);
@@ -173,16 +280,29 @@ async fn main() {
         .max_capacity(10_000)
         .build();
 
-    let dashboard_cache = Cache::builder()
-        .time_to_live(std::time::Duration::from_secs(300))
-        .max_capacity(100)
-        .build();
+    let dashboard_cache = cache::build(
+        &config.cache,
+        "dashboard",
+        100,
+        std::time::Duration::from_secs(config.cache.dashboard_ttl_secs),
+    )
+    .await
+    .expect("Failed to initialize dashboard cache");
+
+    let moderation = ContentModeration::from_config(&config.moderation, llm_client.clone())
+        .expect("Invalid [moderation] configuration");
+    if moderation.is_some() {
+        tracing::info!(
+            "Content moderation enabled ({:?})",
+            config.moderation.provider
+        );
+    }
 
     let state = Arc::new(AppState {
         shard_manager,
         llm_client,
         embedding_cache,
-        config: config.clone(),
+        config: live_config.clone(),
         runtime_mode,
         start_time: std::time::Instant::now(),
         dashboard_auth,
@@ -193,7 +313,29 @@ async fn main() {
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .expect("Failed to build HTTP client"),
+        slow_queries: slow_query::SlowQueryLog::new(config.slow_query.capacity),
+        moderation,
+        disk_watchdog: disk_watchdog::DiskWatchdog::default(),
     });
+    disk_watchdog::spawn(state.clone());
+
+    #[cfg(unix)]
+    {
+        let live_config = live_config.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangup = match signal(SignalKind::hangup()) {
+                Ok(hangup) => hangup,
+                Err(e) => {
+                    tracing::warn!("Failed to install SIGHUP handler: {:?}", e);
+                    return;
+                }
+            };
+            while hangup.recv().await.is_some() {
+                let _ = reload_live_config(&live_config, "SIGHUP");
+            }
+        });
+    }
 
     // Dashboard API routes (auth-protected)
     let dashboard_protected = Router::new()
@@ -203,7 +345,6 @@ async fn main() {
         .route("/memories", get(dashboard::handlers::list_memories))
         .route("/memories/:id", get(dashboard::handlers::get_memory))
         .route("/graph", get(dashboard::handlers::graph_data))
-        .route("/search", post(dashboard::handlers::search))
         .route("/forget/preview", post(dashboard::handlers::forget_preview))
         .route("/forget/execute", post(dashboard::handlers::forget_execute))
         .route(
@@ -230,7 +371,6 @@ async fn main() {
             "/corrections/reviews/:review_id/reject",
             post(dashboard::handlers::reject_rac_review),
         )
-        .route("/chat", post(dashboard::handlers::chat))
         .route("/config", get(dashboard::handlers::get_config))
         .route(
             "/organizations",
@@ -258,6 +398,12 @@ async fn main() {
             get(dashboard::handlers::get_organization_knowledge_metrics),
         )
         .route("/agents", get(dashboard::handlers::list_agents))
+        .route("/slow-queries", get(dashboard::handlers::slow_queries))
+        .route("/self-eval", get(dashboard::handlers::self_eval_reports))
+        .route("/usage/storage", get(dashboard::handlers::storage_usage))
+        .route("/worker/runs", get(dashboard::handlers::worker_runs))
+        .route("/emotions", get(dashboard::handlers::emotion_stats))
+        .route("/map", get(dashboard::handlers::memory_map))
         .layer(axum_middleware::from_fn_with_state(
             state.clone(),
             dashboard::auth::auth_middleware,
@@ -284,10 +430,35 @@ async fn main() {
             "/v1/users/:user_id/streams/:stream_id/retrieve",
             post(retrieve_memory),
         )
+        .route("/v1/users/:user_id/ask", post(ask_memory))
+        .route(
+            "/v1/users/:user_id/feedback",
+            post(experiments::record_feedback),
+        )
         .route("/v1/memory/context", post(build_memory_context))
+        // Dashboard search and chat also accept API keys, within their scope
+        .route("/v1/dashboard/search", post(dashboard::handlers::search))
+        .route("/v1/dashboard/chat", post(dashboard::handlers::chat))
+        .route(
+            "/v1/users/:user_id/apps/:app_id/chat",
+            post(dashboard::handlers::app_chat),
+        )
         .route(
             "/v1/users/:user_id/memories/:id",
-            delete(delete_memory_unit_hard),
+            delete(delete_memory_unit),
+        )
+        .route(
+            "/v1/users/:user_id/memories/:id/restore",
+            post(restore_memory_unit),
+        )
+        .route("/v1/users/:user_id/trash", get(list_trashed_memory_units))
+        .route(
+            "/v1/users/:user_id/memories/:id/pin",
+            put(pin_memory_unit).delete(unpin_memory_unit),
+        )
+        .route(
+            "/v1/users/:user_id/memories/:id/assets/:index",
+            get(get_memory_asset),
         )
         .route(
             "/v1/users/:user_id/memories/semantic/preview",
@@ -303,12 +474,105 @@ async fn main() {
         )
         .route("/v1/users/:user_id/tasks/tree", get(get_all_task_trees))
         .route("/v1/users/:user_id/tasks/ready", get(get_ready_tasks))
+        .route(
+            "/v1/users/:user_id/tasks/deadlines/ws",
+            get(task_deadline_notifications_ws),
+        )
+        .route("/v1/users/:user_id/apps/:app_id/tasks", post(create_task))
+        .route(
+            "/v1/users/:user_id/apps/:app_id/streams",
+            post(create_memory_stream),
+        )
+        .route("/v1/users/:user_id/streams", get(list_memory_streams))
+        .route(
+            "/v1/users/:user_id/streams/:stream_id",
+            get(get_memory_stream),
+        )
+        .route(
+            "/v1/users/:user_id/streams/:stream_id/archive",
+            post(archive_memory_stream),
+        )
+        .route(
+            "/v1/users/:user_id/apps/:app_id/memories",
+            post(create_memory_unit),
+        )
+        .route("/v1/users/:user_id/goals/:goal_id/plan", get(get_goal_plan))
+        .route(
+            "/v1/users/:user_id/goals/:goal_id/plan/approve",
+            post(approve_goal_plan),
+        )
+        .route(
+            "/v1/users/:user_id/shares",
+            get(list_share_grants).post(create_share_grant),
+        )
+        .route(
+            "/v1/users/:user_id/shares/received",
+            get(list_received_share_grants),
+        )
+        .route(
+            "/v1/users/:user_id/shares/:grant_id",
+            delete(revoke_share_grant),
+        )
+        .route("/v1/groups/:group_id/members", get(list_group_members))
+        .route(
+            "/v1/groups/:group_id/members/:user_id",
+            put(add_group_member).delete(remove_group_member),
+        )
+        .route(
+            "/v1/users/:user_id/encryption-key",
+            get(get_user_key)
+                .put(register_user_key)
+                .delete(revoke_user_key),
+        )
+        .route(
+            "/v1/users/:user_id/moderation/audit",
+            get(list_moderation_audit),
+        )
+        .route(
+            "/v1/orgs/:org_id/policy",
+            get(get_org_policy).put(set_org_policy),
+        )
+        .route("/v1/orgs/:org_id/usage", get(get_org_usage))
+        .route("/v1/users/:user_id/tasks/:task_id", get(get_task))
+        .route(
+            "/v1/users/:user_id/tasks/:task_id/dependencies",
+            post(add_task_dependency),
+        )
         .route(
             "/v1/users/:user_id/tasks/:task_id/status",
-            put(update_task_status),
+            put(update_task_status).patch(update_task_status),
+        )
+        .route(
+            "/v1/users/:user_id/profile",
+            get(get_user_profile).patch(patch_user_profile),
         )
         .route("/v1/users/:user_id/graph/edges", post(add_edge))
+        .route(
+            "/v1/users/:user_id/graph/edges/batch",
+            post(add_edges_batch),
+        )
+        .route(
+            "/v1/users/:user_id/graph/query/explain",
+            post(explain_graph_query),
+        )
+        .route(
+            "/v1/users/:user_id/graph/export",
+            get(graph_export::export_graph),
+        )
+        .route("/v1/users/:user_id/graph/diff", get(graph_diff))
+        .route(
+            "/v1/users/:user_id/graph/relations",
+            get(list_relation_definitions),
+        )
+        .route(
+            "/v1/users/:user_id/graph/relations/:name",
+            put(put_relation_definition).delete(delete_relation_definition),
+        )
+        .route("/v1/users/:user_id/communities", get(list_communities))
+        .route("/v1/users/:user_id/digest", get(get_memory_digest))
         .route("/v1/status/pending", get(pending_count))
+        .route("/v1/status/integrity", get(integrity_status))
+        .route("/v1/status/disk", get(disk_status))
         .route(
             "/v1/organizations/:org_id/knowledge",
             get(dashboard::handlers::list_organization_knowledge),
@@ -324,6 +588,30 @@ async fn main() {
         .route("/v1/cluster/initialize", post(initialize_cluster))
         .route("/v1/cluster/join", post(join_cluster))
         .route("/v1/cluster/nodes/:node_id", delete(leave_cluster))
+        .route("/v1/admin/config/reload", post(reload_config))
+        .route("/v1/admin/index/commit", post(commit_text_index))
+        .route("/v1/admin/prompts", get(list_prompt_templates))
+        .route("/v1/admin/prompts/validate", post(validate_prompt_template))
+        .route(
+            "/v1/admin/experiments/:name",
+            get(experiments::experiment_results),
+        )
+        .route(
+            memorose_core::replication::APPLY_PATH,
+            post(apply_replication_batch),
+        )
+        .route(
+            memorose_core::consolidation_pool::CLAIM_PATH,
+            post(consolidation_pool::claim_consolidation_batch),
+        )
+        .route(
+            memorose_core::consolidation_pool::COMMIT_PATH,
+            post(consolidation_pool::commit_consolidation),
+        )
+        .route_layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            disk_space_guard,
+        ))
         .route_layer(axum_middleware::from_fn_with_state(
             state.clone(),
             api_key_auth,
@@ -331,6 +619,8 @@ async fn main() {
 
     let app = Router::new()
         .route("/", get(root))
+        .route("/version", get(version))
+        .route("/readyz", get(readyz))
         .merge(v1_routes)
         .nest("/v1/dashboard", dashboard_routes)
         .route("/dashboard", get(redirect_dashboard_ui))
@@ -343,21 +633,11 @@ async fn main() {
         .with_state(state.clone());
 
     // Determine HTTP listen address
-    let http_addr: SocketAddr = if config.is_sharded() {
-        let sharding = config.sharding.as_ref().unwrap();
-        let this_node = sharding
-            .nodes
-            .iter()
-            .find(|n| n.id == sharding.physical_node_id)
-            .expect("Physical node not found in sharding.nodes");
-        this_node
-            .http_addr
-            .parse()
-            .expect("Invalid http_addr in sharding config")
-    } else {
-        let http_port = 3000 + (config.raft.node_id as u16 - 1);
-        SocketAddr::from(([0, 0, 0, 0], http_port))
-    };
+    let http_addr: SocketAddr = config
+        .local_http_addr()
+        .expect("Physical node not found in sharding.nodes")
+        .parse()
+        .expect("Invalid http_addr in sharding config");
 
     tracing::info!("HTTP API listening on {}", http_addr);
     tracing::info!(
@@ -374,6 +654,14 @@ async fn main() {
         );
     }
 
+    if config.is_analytics_replica() {
+        tracing::info!(
+            "Node {} is the analytics replica: it replicates every shard as a learner and \
+             serves heavy reads, but never runs worker cycles.",
+            config.physical_node_id()
+        );
+    }
+
     if config.should_auto_initialize_raft() {
         let bootstrap_seed = config
             .raft
@@ -442,10 +730,14 @@ async fn redirect_dashboard_ui(uri: OriginalUri) -> Redirect {
 }
 
 /// Middleware: allow dashboard JWTs for internal UI calls, otherwise require a
-/// valid API key on `/v1/` routes.
+/// valid API key on `/v1/` routes. The key's scope is checked against the route's
+/// path parameters here and stored in the request extensions for handlers that name
+/// the user or app in their body; dashboard JWTs get an unrestricted scope.
 async fn api_key_auth(
     State(state): State<Arc<AppState>>,
-    req: axum::extract::Request,
+    matched_path: Option<MatchedPath>,
+    path_params: Option<Path<std::collections::HashMap<String, String>>>,
+    mut req: axum::extract::Request,
     next: axum_middleware::Next,
 ) -> axum::response::Response {
     if let Some(token) = req
@@ -455,6 +747,7 @@ async fn api_key_auth(
         .and_then(|header| header.strip_prefix("Bearer "))
     {
         if state.dashboard_auth.verify_token(token).is_ok() {
+            req.extensions_mut().insert(ApiKeyScope::default());
             return next.run(req).await;
         }
     }
@@ -472,7 +765,20 @@ async fn api_key_auth(
         .authenticate_api_key(raw_key)
         .await
     {
-        Ok(Some(_)) => next.run(req).await,
+        Ok(Some(key)) => {
+            let route = matched_path.as_ref().map(MatchedPath::as_str).unwrap_or("");
+            let params = path_params.map(|Path(params)| params).unwrap_or_default();
+            if let Err(message) = key.scope.authorize_route(route, &params) {
+                tracing::debug!("API key {} refused on {}: {}", key.key_id, route, message);
+                return (
+                    axum::http::StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({ "error": message })),
+                )
+                    .into_response();
+            }
+            req.extensions_mut().insert(key.scope);
+            next.run(req).await
+        }
         Ok(None) => (
             axum::http::StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({ "error": "Invalid API key" })),
@@ -486,6 +792,86 @@ async fn api_key_auth(
     }
 }
 
+/// Check the user and apps a request names in its body against the caller's API key
+/// scope. `apps` is `None` when the request reads across every app of the user.
+fn check_key_scope(
+    scope: &ApiKeyScope,
+    user_id: &str,
+    apps: Option<&[&str]>,
+) -> Result<(), axum::response::Response> {
+    let forbidden = |message: String| {
+        (
+            axum::http::StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response()
+    };
+    if !scope.allows_user(user_id) {
+        return Err(forbidden(format!(
+            "API key is not allowed to access user {}",
+            user_id
+        )));
+    }
+    if !scope.is_app_restricted() {
+        return Ok(());
+    }
+    let Some(apps) = apps else {
+        return Err(forbidden(
+            "API key is bound to specific apps; name them with agent_id or scope.apps".to_string(),
+        ));
+    };
+    match apps.iter().find(|app_id| !scope.allows_app(app_id)) {
+        Some(app_id) => Err(forbidden(format!(
+            "API key is not allowed to access app {}",
+            app_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check a memory unit's app against an app-bound key. Keys without app restrictions
+/// pass without a lookup; trashed and forgotten units are checked too.
+fn check_unit_key_scope(
+    engine: &MemoroseEngine,
+    scope: &ApiKeyScope,
+    user_id: &str,
+    unit_id: Uuid,
+) -> Result<(), axum::response::Response> {
+    if !scope.is_app_restricted() {
+        return Ok(());
+    }
+    match engine.get_memory_unit_including_forgotten(user_id, unit_id) {
+        Ok(Some(unit)) => {
+            let apps: Option<Vec<&str>> = unit.agent_id.as_deref().map(|app_id| vec![app_id]);
+            check_key_scope(scope, user_id, apps.as_deref())
+        }
+        Ok(None) => Err((
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Memory not found" })),
+        )
+            .into_response()),
+        Err(e) => Err((
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response()),
+    }
+}
+
+/// App-bound keys may only write to streams created for one of their apps with
+/// `POST /v1/users/:uid/apps/:app_id/streams`.
+fn check_stream_key_scope(
+    shard: &shard_manager::ShardState,
+    scope: &ApiKeyScope,
+    user_id: &str,
+    stream_id: Uuid,
+) -> Result<(), axum::response::Response> {
+    if !scope.is_app_restricted() {
+        return Ok(());
+    }
+    load_memory_stream(shard, scope, user_id, stream_id).map(|_| ())
+}
+
 /// Validate that a path-supplied identifier is within acceptable bounds.
 /// Returns an error response if the value is too long or contains characters that
 /// would break the internal RocksDB key scheme.
@@ -511,9 +897,58 @@ fn validate_id(value: &str, field: &str) -> Result<(), axum::response::Response>
     Ok(())
 }
 
+/// A client-supplied embedding must match the configured `llm.embedding_dim`,
+/// otherwise it would be stored or searched against the wrong vector space.
+fn validate_client_embedding(
+    embedding: &[f32],
+    embedding_dim: i32,
+) -> Result<(), axum::response::Response> {
+    if embedding.len() != embedding_dim.max(0) as usize {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "embedding has {} dimensions, expected {}",
+                    embedding.len(),
+                    embedding_dim
+                )
+            })),
+        )
+            .into_response());
+    }
+    if embedding.iter().any(|value| !value.is_finite()) {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "embedding values must be finite" })),
+        )
+            .into_response());
+    }
+    Ok(())
+}
+
+/// Reject a location whose coordinates are out of range.
+fn validate_location(
+    location: Option<&memorose_common::GeoPoint>,
+) -> Result<(), axum::response::Response> {
+    match location {
+        Some(point) if memorose_common::GeoPoint::new(point.lat, point.lon).is_none() => Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "location lat must be within [-90, 90] and lon within [-180, 180]"
+            })),
+        )
+            .into_response()),
+        _ => Ok(()),
+    }
+}
+
+type RaftMetrics = openraft::RaftMetrics<u64, MemoroseNode>;
+
 /// Build a "Not Leader" response with shard info when applicable.
-fn not_leader_response(current_leader: Option<u64>, is_sharded: bool) -> axum::response::Response {
-    if is_sharded {
+fn not_leader_response(config: &AppConfig, metrics: &RaftMetrics) -> axum::response::Response {
+    let current_leader = metrics.current_leader;
+    let leader_http_addr = current_leader.and_then(|id| leader_base_url(config, metrics, id));
+    if config.is_sharded() {
         let (shard_id, leader_physical_node) = current_leader
             .map(|id| decode_raft_node_id(id))
             .unwrap_or((0, 0));
@@ -524,18 +959,23 @@ fn not_leader_response(current_leader: Option<u64>, is_sharded: bool) -> axum::r
                 "current_leader": current_leader,
                 "shard_id": shard_id,
                 "leader_physical_node": leader_physical_node,
+                "leader_http_addr": leader_http_addr,
             })),
         )
             .into_response()
     } else {
         let hint = current_leader
-            .map(|id| format!("Node {} (Try Port {})", id, 3000 + id - 1))
+            .map(|id| match &leader_http_addr {
+                Some(url) => format!("Node {} ({})", id, url),
+                None => format!("Node {}", id),
+            })
             .unwrap_or_else(|| "Unknown".to_string());
         (
             axum::http::StatusCode::SERVICE_UNAVAILABLE,
             Json(serde_json::json!({
                 "error": "Not Leader",
                 "current_leader": current_leader,
+                "leader_http_addr": leader_http_addr,
                 "hint": hint,
             })),
         )
@@ -543,16 +983,137 @@ fn not_leader_response(current_leader: Option<u64>, is_sharded: bool) -> axum::r
     }
 }
 
+/// Wait until this node has applied the shard's Raft entry at `min_applied_index`, so
+/// a read sees the event or record that write carried. Only the entry itself is covered:
+/// consolidation of events into memories and text-index commits run after it applies,
+/// so the returned [`ReadYourWrites`] tells the client how many events are still
+/// pending. `None` when the read did not ask for its writes.
+/// Bounded by `raft.read_index_wait_ms`.
+async fn wait_for_event_applied(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    min_applied_index: Option<u64>,
+) -> Result<Option<ReadYourWrites>, axum::response::Response> {
+    let Some(index) = min_applied_index else {
+        return Ok(None);
+    };
+    let applied_index = match shard.raft.as_ref() {
+        // Standalone writes are applied before they are acknowledged.
+        None => None,
+        Some(raft) => {
+            let timeout =
+                std::time::Duration::from_millis(state.config.load().raft.read_index_wait_ms);
+            match raft
+                .wait(Some(timeout))
+                .applied_index_at_least(Some(index), "read-your-writes")
+                .await
+            {
+                Ok(metrics) => metrics.last_applied.map(|log_id| log_id.index),
+                Err(e) => {
+                    let applied_index = raft.metrics().borrow().last_applied.map(|l| l.index);
+                    tracing::warn!(
+                        "Read waited for log index {} but only {:?} is applied: {}",
+                        index,
+                        applied_index,
+                        e
+                    );
+                    return Err((
+                        axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                        Json(serde_json::json!({
+                            "error": "Event at read index not yet applied",
+                            "min_applied_index": index,
+                            "applied_index": applied_index,
+                        })),
+                    )
+                        .into_response());
+                }
+            }
+        }
+    };
+    match shard.engine.count_pending_events().await {
+        Ok(pending_events) => Ok(Some(ReadYourWrites {
+            applied_index,
+            pending_events,
+        })),
+        Err(e) => Err((
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response()),
+    }
+}
+
 /// Forward request to leader node
 async fn forward_to_leader<T: serde::Serialize>(
     state: &AppState,
+    metrics: &RaftMetrics,
     leader_id: u64,
     path: &str,
     payload: &T,
 ) -> Result<axum::response::Response, axum::response::Response> {
-    // Calculate leader address
-    let leader_port = 3000 + leader_id - 1;
-    let leader_url = format!("http://localhost:{}{}", leader_port, path);
+    forward_method_to_leader(
+        state,
+        metrics,
+        leader_id,
+        axum::http::Method::POST,
+        path,
+        payload,
+    )
+    .await
+}
+
+/// In cluster mode, answer a write that reached a follower of `shard`: forward it to the
+/// leader when one is known, or return "Not Leader". `None` when this node leads the
+/// shard, or runs standalone, and should handle the write itself.
+async fn forward_write_if_follower<T: serde::Serialize>(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    method: axum::http::Method,
+    path: &str,
+    payload: &T,
+) -> Option<axum::response::Response> {
+    if !state.is_cluster_mode() {
+        return None;
+    }
+    let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+    let metrics = raft.metrics().borrow().clone();
+    let node_id = metrics.id;
+    if metrics.current_leader == Some(node_id) {
+        return None;
+    }
+    if let Some(leader_id) = metrics.current_leader {
+        tracing::info!(
+            "Not leader (I'm {}, leader is {}), forwarding request",
+            node_id,
+            leader_id
+        );
+        return Some(
+            match forward_method_to_leader(state, &metrics, leader_id, method, path, payload).await
+            {
+                Ok(response) => response,
+                Err(err_response) => err_response,
+            },
+        );
+    }
+    Some(not_leader_response(&state.config.load(), &metrics))
+}
+
+/// Forward a request to the leader node with `method`. A body is only sent when the
+/// method carries one.
+async fn forward_method_to_leader<T: serde::Serialize>(
+    state: &AppState,
+    metrics: &RaftMetrics,
+    leader_id: u64,
+    method: axum::http::Method,
+    path: &str,
+    payload: &T,
+) -> Result<axum::response::Response, axum::response::Response> {
+    let config = state.config.load();
+    let Some(base_url) = leader_base_url(&config, metrics, leader_id) else {
+        tracing::warn!("No HTTP address known for leader node {}", leader_id);
+        return Err(not_leader_response(&config, metrics));
+    };
+    let leader_url = format!("{}{}", base_url, path);
 
     tracing::info!(
         "Forwarding request to leader node {} at {}",
@@ -561,24 +1122,22 @@ async fn forward_to_leader<T: serde::Serialize>(
     );
 
     // Reuse the shared HTTP client — avoids creating a new connection pool per request.
-    let response = state
-        .http_client
-        .post(&leader_url)
-        .json(payload)
-        .send()
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to forward to leader: {}", e);
-            (
-                axum::http::StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": "Failed to forward to leader",
-                    "leader_id": leader_id,
-                    "details": e.to_string()
-                })),
-            )
-                .into_response()
-        })?;
+    let mut request = state.http_client.request(method.clone(), &leader_url);
+    if !matches!(method, axum::http::Method::GET | axum::http::Method::DELETE) {
+        request = request.json(payload);
+    }
+    let response = request.send().await.map_err(|e| {
+        tracing::error!("Failed to forward to leader: {}", e);
+        (
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": "Failed to forward to leader",
+                "leader_id": leader_id,
+                "details": e.to_string()
+            })),
+        )
+            .into_response()
+    })?;
 
     // Convert response
     let status = response.status();
@@ -619,11 +1178,28 @@ async fn forward_to_leader<T: serde::Serialize>(
         })
 }
 
+/// Custom relation names end up in graph filters and keys, so malformed ones are refused.
+fn validate_relation(relation: &RelationType) -> Result<(), axum::response::Response> {
+    match relation {
+        RelationType::Custom(name) if !RelationType::is_valid_custom_name(name) => Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Invalid custom relation name '{}'", name)
+            })),
+        )
+            .into_response()),
+        _ => Ok(()),
+    }
+}
+
 async fn add_edge(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
     Json(payload): Json<AddEdgeRequest>,
 ) -> axum::response::Response {
+    if let Err(r) = validate_relation(&payload.relation) {
+        return r;
+    }
     let shard = state.shard_manager.shard_for_user(&user_id);
     if state.is_cluster_mode() {
         let raft = shard.raft.as_ref().expect("cluster mode requires raft");
@@ -641,13 +1217,13 @@ async fn add_edge(
                     leader_id
                 );
 
-                match forward_to_leader(&state, leader_id, &path, &payload).await {
+                match forward_to_leader(&state, &metrics, leader_id, &path, &payload).await {
                     Ok(response) => return response,
                     Err(err_response) => return err_response,
                 }
             }
 
-            return not_leader_response(current_leader, state.config.is_sharded());
+            return not_leader_response(&state.config.load(), &metrics);
         }
     }
 
@@ -660,7 +1236,7 @@ async fn add_edge(
     );
 
     if state.is_standalone_mode() {
-        return match shard.engine.graph().add_edge(&edge).await {
+        return match shard.engine.add_graph_edge(&edge).await {
             Ok(_) => Json(serde_json::json!({
                 "status": "accepted",
                 "write_path": state.write_path_name(),
@@ -696,185 +1272,199 @@ async fn add_edge(
     }
 }
 
-async fn root() -> &'static str {
-    "Memorose is running."
-}
+/// Deepest traversal the explain endpoint will plan.
+const MAX_EXPLAIN_HOPS: usize = 8;
 
-/// Returns the number of pending (un-consolidated) events across all shards.
-/// Useful for benchmarks to poll until consolidation is complete.
-async fn pending_count(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    let mut total_pending: usize = 0;
-    for (_shard_id, shard) in state.shard_manager.all_shards() {
-        if let Ok(n) = shard.engine.count_pending_events().await {
-            total_pending += n;
-        }
+async fn explain_graph_query(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<GraphQueryExplainRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if payload.start_nodes.is_empty() {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "start_nodes must not be empty" })),
+        )
+            .into_response();
+    }
+    if payload.max_hops == 0 || payload.max_hops > MAX_EXPLAIN_HOPS {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("max_hops must be between 1 and {}", MAX_EXPLAIN_HOPS)
+            })),
+        )
+            .into_response();
     }
-    Json(serde_json::json!({
-        "pending": total_pending,
-        "ready": total_pending == 0,
-    }))
-}
 
-fn parse_ingest_content(
-    content_type: &str,
-    raw_content: String,
-) -> std::result::Result<EventContent, String> {
-    match content_type.to_lowercase().as_str() {
-        "image" => Ok(EventContent::Image(raw_content)),
-        "audio" => Ok(EventContent::Audio(raw_content)),
-        "video" => Ok(EventContent::Video(raw_content)),
-        "json" => serde_json::from_str(&raw_content)
-            .map(EventContent::Json)
-            .map_err(|e| format!("invalid json payload: {}", e)),
-        _ => Ok(EventContent::Text(raw_content)),
+    let spec = memorose_core::graph::TraversalSpec {
+        relation_types: payload.relations,
+        direction: payload.direction,
+        min_hops: 1,
+        max_hops: 1,
+        weight_threshold: payload.min_weight,
+    };
+    let mut query = memorose_core::graph::GraphQueryBuilder::new(user_id.clone())
+        .start_from(payload.start_nodes);
+    for _ in 0..payload.max_hops {
+        query = query.with_traversal(spec.clone());
+    }
+    if let Some(limit) = payload.limit {
+        query = query.limit(limit);
+    }
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.explain_graph_query(&query).await {
+        Ok(explanation) => Json(explanation).into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
     }
 }
 
-async fn ingest_event(
+async fn graph_diff(
     State(state): State<Arc<AppState>>,
-    Path((user_id, stream_id)): Path<(String, Uuid)>,
-    Json(payload): Json<IngestRequest>,
+    Path(user_id): Path<String>,
+    Query(query): Query<GraphDiffQuery>,
 ) -> axum::response::Response {
     if let Err(r) = validate_id(&user_id, "user_id") {
         return r;
     }
-    if let Some(org_id) = payload.org_id.as_deref() {
-        if let Err(r) = validate_id(org_id, "org_id") {
-            return r;
-        }
+    let to = query.to.unwrap_or_else(chrono::Utc::now);
+    if query.from >= to {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "from must be before to" })),
+        )
+            .into_response();
     }
     let shard = state.shard_manager.shard_for_user(&user_id);
-    if state.is_cluster_mode() {
-        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
-        let metrics = raft.metrics().borrow().clone();
-        let current_leader = metrics.current_leader;
-        let node_id = metrics.id;
-
-        if current_leader != Some(node_id) {
-            if let Some(leader_id) = current_leader {
-                let path = format!("/v1/users/{}/streams/{}/events", user_id, stream_id);
-
-                tracing::info!(
-                    "Not leader (I'm {}, leader is {}), forwarding request",
-                    node_id,
-                    leader_id
-                );
-
-                match forward_to_leader(&state, leader_id, &path, &payload).await {
-                    Ok(response) => return response,
-                    Err(err_response) => return err_response,
-                }
-            }
-
-            return not_leader_response(current_leader, state.config.is_sharded());
-        }
+    match shard.engine.graph_diff(&user_id, query.from, to).await {
+        Ok(diff) => Json(diff).into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
     }
+}
 
-    let content = match parse_ingest_content(&payload.content_type, payload.content) {
-        Ok(content) => content,
-        Err(message) => {
-            return (
-                axum::http::StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "status": "error",
-                    "message": message
-                })),
-            )
-                .into_response();
-        }
-    };
-    let mut event = Event::new(
-        payload.org_id.clone(),
-        user_id.clone(),
-        None,
-        stream_id,
-        content,
-    );
-    if let Some(l) = payload.level {
-        event.metadata["target_level"] = serde_json::json!(l);
+async fn list_relation_definitions(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
     }
-    if let Some(ref p) = payload.parent_id {
-        event.metadata["parent_id"] = serde_json::json!(p);
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.list_relation_definitions(&user_id) {
+        Ok(relations) => Json(serde_json::json!({ "relations": relations })).into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
     }
-    if let Some(ref s) = payload.task_status {
-        event.metadata["task_status"] = serde_json::json!(s);
+}
+
+async fn put_relation_definition(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, name)): Path<(String, String)>,
+    Json(payload): Json<PutRelationDefinitionRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
     }
-    if let Some(p) = payload.task_progress {
-        event.metadata["task_progress"] = serde_json::json!(p);
+    if let Err(r) = validate_relation(&RelationType::Custom(name.clone())) {
+        return r;
     }
-    let event_id = event.id;
-    if state.is_standalone_mode() {
-        return match shard.engine.ingest_event_directly(event).await {
-            Ok(_) => Json(serde_json::json!({
-                "status": "accepted",
-                "event_id": event_id,
-                "write_path": state.write_path_name(),
-            }))
-            .into_response(),
-            Err(e) => {
-                tracing::error!("Direct write error (event): {:?}", e);
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "status": "error",
-                        "message": e.to_string()
-                    })),
-                )
-                    .into_response()
-            }
-        };
+    if !(0.0..=1.0).contains(&payload.traversal_weight) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "traversal_weight must be between 0.0 and 1.0" })),
+        )
+            .into_response();
     }
 
-    match shard
-        .raft
-        .as_ref()
-        .expect("cluster mode requires raft")
-        .client_write(memorose_core::raft::types::ClientRequest::IngestEvent(
-            event,
-        ))
+    let definition = memorose_common::RelationDefinition {
+        user_id,
+        name,
+        directed: payload.directed,
+        traversal_weight: payload.traversal_weight,
+        description: payload.description,
+        updated_at: chrono::Utc::now(),
+    };
+    let shard = state.shard_manager.shard_for_user(&definition.user_id);
+    let applied = if state.is_standalone_mode() {
+        shard
+            .engine
+            .put_relation_definition(&definition)
+            .map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::PutRelationDefinition(definition.clone()),
+        )
         .await
-    {
-        Ok(_) => Json(serde_json::json!({
-            "status": "accepted",
-            "event_id": event_id
-        }))
-        .into_response(),
-        Err(e) => {
-            tracing::error!("Raft write error: {:?}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "status": "error",
-                    "message": e.to_string()
-                })),
-            )
-                .into_response()
-        }
+    };
+    match applied {
+        Ok(true) => Json(definition).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Relation definition was not applied" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
     }
 }
 
-async fn ingest_events_batch(
+async fn delete_relation_definition(
     State(state): State<Arc<AppState>>,
-    Path((user_id, stream_id)): Path<(String, Uuid)>,
-    Json(payload): Json<BatchIngestRequest>,
+    Path((user_id, name)): Path<(String, String)>,
 ) -> axum::response::Response {
     if let Err(r) = validate_id(&user_id, "user_id") {
         return r;
     }
-    if payload.events.is_empty() {
-        return Json(serde_json::json!({
-            "status": "accepted",
-            "event_ids": Vec::<String>::new(),
-            "count": 0
-        }))
-        .into_response();
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let deleted = if state.is_standalone_mode() {
+        shard.engine.delete_relation_definition(&user_id, &name)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::DeleteRelationDefinition { user_id, name },
+        )
+        .await
+    };
+    match deleted {
+        Ok(true) => axum::http::StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Relation not found" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
     }
+}
 
-    for event in &payload.events {
-        if let Some(org_id) = event.org_id.as_deref() {
-            if let Err(r) = validate_id(org_id, "org_id") {
-                return r;
-            }
+async fn add_edges_batch(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<BatchAddEdgesRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if payload.edges.is_empty() {
+        return Json(serde_json::json!({ "status": "accepted", "count": 0 })).into_response();
+    }
+    for edge in &payload.edges {
+        if let Err(r) = validate_relation(&edge.relation) {
+            return r;
         }
     }
     let shard = state.shard_manager.shard_for_user(&user_id);
@@ -886,73 +1476,451 @@ async fn ingest_events_batch(
 
         if current_leader != Some(node_id) {
             if let Some(leader_id) = current_leader {
-                let path = format!("/v1/users/{}/streams/{}/events/batch", user_id, stream_id);
+                let path = format!("/v1/users/{}/graph/edges/batch", user_id);
                 tracing::info!(
-                    "Not leader (I'm {}, leader is {}), forwarding batch request",
+                    "Not leader (I'm {}, leader is {}), forwarding batch edge request",
                     node_id,
                     leader_id
                 );
-                match forward_to_leader(&state, leader_id, &path, &payload).await {
+                match forward_to_leader(&state, &metrics, leader_id, &path, &payload).await {
                     Ok(response) => return response,
                     Err(err_response) => return err_response,
                 }
             }
 
-            return not_leader_response(current_leader, state.config.is_sharded());
+            return not_leader_response(&state.config.load(), &metrics);
         }
     }
 
-    let mut events = Vec::with_capacity(payload.events.len());
-    let mut event_ids = Vec::with_capacity(payload.events.len());
-    for item in payload.events {
-        let content = match parse_ingest_content(&item.content_type, item.content) {
-            Ok(content) => content,
-            Err(message) => {
-                return (
-                    axum::http::StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({
-                        "status": "error",
-                        "message": message
-                    })),
-                )
-                    .into_response();
-            }
-        };
+    let edges: Vec<GraphEdge> = payload
+        .edges
+        .into_iter()
+        .map(|edge| {
+            GraphEdge::new(
+                user_id.clone(),
+                edge.source_id,
+                edge.target_id,
+                edge.relation,
+                edge.weight.unwrap_or(1.0),
+            )
+        })
+        .collect();
+    let count = edges.len();
 
-        let mut event = Event::new(
-            item.org_id.clone(),
-            user_id.clone(),
-            None,
-            stream_id,
-            content,
-        );
-        if let Some(level) = item.level {
-            event.metadata["target_level"] = serde_json::json!(level);
-        }
-        if let Some(parent_id) = item.parent_id {
-            event.metadata["parent_id"] = serde_json::json!(parent_id);
+    let applied = if state.is_standalone_mode() {
+        shard.engine.add_graph_edges(&edges).await.map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::UpdateGraphBatch(edges),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => Json(serde_json::json!({
+            "status": "accepted",
+            "count": count,
+            "write_path": state.write_path_name(),
+        }))
+        .into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Edge batch was not applied" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Batch edge write error: {:?}", e);
+            replication_error_response(&state, &e)
         }
-        if let Some(task_status) = item.task_status {
-            event.metadata["task_status"] = serde_json::json!(task_status);
+    }
+}
+
+async fn root() -> &'static str {
+    "Memorose is running."
+}
+
+/// Build version and the on-disk schema versions this binary reads and writes.
+async fn version() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "schema_versions": {
+            "memory_unit": memorose_common::MEMORY_UNIT_SCHEMA_VERSION,
+            "vector": memorose_core::storage::vector::VECTOR_SCHEMA_VERSION,
+        },
+    }))
+}
+
+/// Readiness probe: 503 while any shard is installing a snapshot or has applied the
+/// log to more than `raft.ready_max_lag` entries behind its leader.
+async fn readyz(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    let max_lag = state.config.load().raft.ready_max_lag;
+    let mut lagging = Vec::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        let Some(raft) = shard.raft.as_ref() else {
+            continue;
+        };
+        let progress = shard.catch_up.progress(&raft.metrics().borrow());
+        if !progress.is_caught_up(max_lag) {
+            lagging.push(serde_json::json!({
+                "shard_id": shard_id,
+                "catch_up": progress,
+            }));
         }
-        if let Some(task_progress) = item.task_progress {
-            event.metadata["task_progress"] = serde_json::json!(task_progress);
+    }
+
+    if lagging.is_empty() {
+        return Json(serde_json::json!({ "status": "ready" })).into_response();
+    }
+    (
+        axum::http::StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({
+            "status": "catching_up",
+            "max_lag": max_lag,
+            "shards": lagging,
+        })),
+    )
+        .into_response()
+}
+
+/// Returns the number of pending (un-consolidated) events across all shards.
+/// Useful for benchmarks to poll until consolidation is complete.
+async fn pending_count(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let mut total_pending: usize = 0;
+    let mut shards = Vec::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        if let Ok(n) = shard.engine.count_pending_events().await {
+            total_pending += n;
+            let admission = MemoroseEngine::admission_for(shard.engine.admission_config(), n);
+            shards.push(serde_json::json!({
+                "shard_id": shard_id,
+                "pending": n,
+                "admission": admission,
+            }));
         }
-        event_ids.push(event.id.to_string());
-        events.push(event);
     }
+    let config = state.config.load();
+    let admission = &config.admission;
+    Json(serde_json::json!({
+        "pending": total_pending,
+        "ready": total_pending == 0,
+        "shards": shards,
+        "admission_thresholds": {
+            "shed_linking_pending": admission.shed_linking_pending,
+            "throttle_pending": admission.throttle_pending,
+            "reject_pending": admission.reject_pending,
+        },
+    }))
+}
 
-    if state.is_standalone_mode() {
-        return match shard.engine.ingest_events_directly(events).await {
-            Ok(_) => Json(serde_json::json!({
-                "status": "accepted",
-                "event_ids": event_ids,
-                "count": event_ids.len(),
-                "write_path": state.write_path_name(),
-            }))
+/// Last completed consistency pass and the one in progress, per shard.
+async fn integrity_status(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    let mut shards = Vec::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        let reports = shard.engine.last_integrity_report().and_then(|last| {
+            Ok((
+                last,
+                shard.engine.integrity_pass_in_progress()?,
+                shard.engine.last_orphan_gc_report()?,
+            ))
+        });
+        match reports {
+            Ok((last_completed, in_progress, last_orphan_gc)) => shards.push(serde_json::json!({
+                "shard_id": shard_id,
+                "last_completed": last_completed,
+                "in_progress": in_progress,
+                "last_orphan_gc": last_orphan_gc,
+            })),
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+    Json(serde_json::json!({ "shards": shards })).into_response()
+}
+
+/// Free space on the data volume as of the watchdog's last check.
+async fn disk_status(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let config = state.config.load();
+    Json(serde_json::json!({
+        "read_only": state.disk_watchdog.is_read_only(),
+        "disk": state.disk_watchdog.status(),
+        "thresholds": {
+            "warn_free_percent": config.disk_watchdog.warn_free_percent,
+            "read_only_free_percent": config.disk_watchdog.read_only_free_percent,
+        },
+    }))
+}
+
+/// Re-read the configuration and swap it in for the workers and handlers.
+fn reload_live_config(
+    live_config: &LiveConfig,
+    trigger: &str,
+) -> anyhow::Result<ConfigReloadReport> {
+    match live_config.reload() {
+        Ok(report) => {
+            tracing::info!(
+                "Configuration reloaded ({}): changed={:?}, restart_required={:?}",
+                trigger,
+                report.changed,
+                report.restart_required
+            );
+            Ok(report)
+        }
+        Err(e) => {
+            tracing::error!(
+                "Configuration reload ({}) rejected, keeping the current config: {}",
+                trigger,
+                e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+async fn reload_config(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    match reload_live_config(&state.config, "admin API") {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
             .into_response(),
+    }
+}
+
+/// Commit every local shard's text index now so pending writes become searchable, and
+/// merge its segments when asked.
+async fn commit_text_index(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<IndexCommitQuery>,
+) -> axum::response::Response {
+    let mut shards = Vec::new();
+    for (shard_id, shard) in state.shard_manager.all_shards() {
+        match shard.engine.commit_text_index(params.merge).await {
+            Ok(report) => shards.push(serde_json::json!({
+                "shard_id": shard_id,
+                "report": report,
+            })),
             Err(e) => {
-                tracing::error!("Direct batch write error: {:?}", e);
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": format!("Index commit failed on shard {}: {}", shard_id, e),
+                    })),
+                )
+                    .into_response()
+            }
+        }
+    }
+    Json(serde_json::json!({ "merge": params.merge, "shards": shards })).into_response()
+}
+
+/// The `[prompts]` templates in effect, with the variables each kind accepts.
+async fn list_prompt_templates(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    let config = state.config.load();
+    let variables: serde_json::Map<String, serde_json::Value> = PromptKind::ALL
+        .iter()
+        .map(|kind| {
+            (
+                kind.as_str().to_string(),
+                serde_json::json!(kind.variables()),
+            )
+        })
+        .collect();
+    Json(serde_json::json!({
+        "prompts": config.prompts,
+        "variables": variables,
+    }))
+    .into_response()
+}
+
+/// Check a template before putting it in the config; reloads reject invalid templates.
+async fn validate_prompt_template(
+    Json(payload): Json<ValidatePromptRequest>,
+) -> axum::response::Response {
+    let variables = payload.kind.variables();
+    match memorose_common::prompt_template::validate(&payload.template, variables) {
+        Ok(()) => Json(serde_json::json!({
+            "valid": true,
+            "kind": payload.kind,
+            "variables": variables,
+        }))
+        .into_response(),
+        Err(error) => (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "valid": false,
+                "kind": payload.kind,
+                "error": error,
+                "variables": variables,
+            })),
+        )
+            .into_response(),
+    }
+}
+
+fn parse_ingest_content(
+    content_type: &str,
+    raw_content: String,
+) -> std::result::Result<EventContent, String> {
+    match content_type.to_lowercase().as_str() {
+        "image" => Ok(EventContent::Image(raw_content)),
+        "audio" => Ok(EventContent::Audio(raw_content)),
+        "video" => Ok(EventContent::Video(raw_content)),
+        "json" => serde_json::from_str(&raw_content)
+            .map(EventContent::Json)
+            .map_err(|e| format!("invalid json payload: {}", e)),
+        _ => Ok(EventContent::Text(raw_content)),
+    }
+}
+
+async fn ingest_event(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, stream_id)): Path<(String, Uuid)>,
+    Query(query): Query<IngestQuery>,
+    Json(payload): Json<IngestRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Some(org_id) = payload.org_id.as_deref() {
+        if let Err(r) = validate_id(org_id, "org_id") {
+            return r;
+        }
+    }
+    if let Some(embedding) = payload.embedding.as_deref() {
+        if let Err(r) = validate_client_embedding(embedding, state.config.load().llm.embedding_dim)
+        {
+            return r;
+        }
+    }
+    if let Err(r) = validate_location(payload.location.as_ref()) {
+        return r;
+    }
+    let shard_id = state.shard_manager.shard_id_for_user(&user_id);
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_stream_key_scope(shard, &key_scope, &user_id, stream_id) {
+        return r;
+    }
+    if state.is_cluster_mode() {
+        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+        let metrics = raft.metrics().borrow().clone();
+        let current_leader = metrics.current_leader;
+        let node_id = metrics.id;
+
+        if current_leader != Some(node_id) {
+            if let Some(leader_id) = current_leader {
+                let mut path = format!("/v1/users/{}/streams/{}/events", user_id, stream_id);
+                if query.mode == IngestMode::Sync {
+                    path.push_str("?mode=sync");
+                }
+
+                tracing::info!(
+                    "Not leader (I'm {}, leader is {}), forwarding request",
+                    node_id,
+                    leader_id
+                );
+
+                match forward_to_leader(&state, &metrics, leader_id, &path, &payload).await {
+                    Ok(response) => return response,
+                    Err(err_response) => return err_response,
+                }
+            }
+
+            return not_leader_response(&state.config.load(), &metrics);
+        }
+    }
+
+    let content = match parse_ingest_content(&payload.content_type, payload.content) {
+        Ok(content) => content,
+        Err(message) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "status": "error",
+                    "message": message
+                })),
+            )
+                .into_response();
+        }
+    };
+    let mut event = Event::new(
+        payload.org_id.clone(),
+        user_id.clone(),
+        None,
+        stream_id,
+        content,
+    );
+    if let Some(l) = payload.level {
+        event.metadata["target_level"] = serde_json::json!(l);
+    }
+    if let Some(ref p) = payload.parent_id {
+        event.metadata["parent_id"] = serde_json::json!(p);
+    }
+    if let Some(ref s) = payload.task_status {
+        event.metadata["task_status"] = serde_json::json!(s);
+    }
+    if let Some(p) = payload.task_progress {
+        event.metadata["task_progress"] = serde_json::json!(p);
+    }
+    if let Some(due_at) = payload.task_due_at {
+        event.metadata["task_due_at"] = serde_json::json!(due_at.to_rfc3339());
+    }
+    if let Some(ref recurrence) = payload.task_recurrence {
+        event.metadata["task_recurrence"] = serde_json::json!(recurrence);
+    }
+    if let Some(priority) = payload.task_priority {
+        event.metadata["task_priority"] = serde_json::json!(priority);
+    }
+    if let Some(skip) = payload.skip_compression {
+        event.metadata["skip_compression"] = serde_json::json!(skip);
+    }
+    if let Some(ref embedding) = payload.embedding {
+        event.metadata["embedding"] = serde_json::json!(embedding);
+    }
+    event.set_conversation_turn(
+        payload.role,
+        payload.speaker_name.clone(),
+        payload.turn_index,
+    );
+    if let Some(location) = payload.location {
+        event.set_location(location);
+    }
+    if let Err(r) = check_org_event_quota(&state, event.org_id.as_deref(), 1).await {
+        return r;
+    }
+    if let Err(r) = check_user_key(&shard.engine, &user_id) {
+        return r;
+    }
+    if let Err(r) = check_ingest_admission(&shard.engine).await {
+        return r;
+    }
+    let event = match moderate_event(&state, &shard.engine, event).await {
+        Ok(event) => event,
+        Err(categories) => return moderation_blocked_response(&categories),
+    };
+    let event_id = event.id;
+    let sync_event = (query.mode == IngestMode::Sync).then(|| event.clone());
+    if state.is_standalone_mode() {
+        return match shard.engine.ingest_event_directly(event).await {
+            Ok(_) => {
+                let mut body = serde_json::json!({
+                    "status": "accepted",
+                    "event_id": event_id,
+                    "write_path": state.write_path_name(),
+                });
+                if let Some(event) = sync_event {
+                    consolidate_inline(&state, shard, event, &mut body).await;
+                }
+                Json(body).into_response()
+            }
+            Err(e) => {
+                tracing::error!("Direct write error (event): {:?}", e);
                 (
                     axum::http::StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({
@@ -969,19 +1937,25 @@ async fn ingest_events_batch(
         .raft
         .as_ref()
         .expect("cluster mode requires raft")
-        .client_write(memorose_core::raft::types::ClientRequest::IngestEvents(
-            events,
+        .client_write(memorose_core::raft::types::ClientRequest::IngestEvent(
+            event,
         ))
         .await
     {
-        Ok(_) => Json(serde_json::json!({
-            "status": "accepted",
-            "event_ids": event_ids,
-            "count": event_ids.len()
-        }))
-        .into_response(),
+        Ok(resp) => {
+            let mut body = serde_json::json!({
+                "status": "accepted",
+                "event_id": event_id,
+                "shard_id": shard_id,
+                "log_index": resp.log_id.index,
+            });
+            if let Some(event) = sync_event {
+                consolidate_inline(&state, shard, event, &mut body).await;
+            }
+            Json(body).into_response()
+        }
         Err(e) => {
-            tracing::error!("Raft batch write error: {:?}", e);
+            tracing::error!("Raft write error: {:?}", e);
             (
                 axum::http::StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
@@ -994,13 +1968,436 @@ async fn ingest_events_batch(
     }
 }
 
-fn memory_budget_from_headers(
-    headers: &HeaderMap,
-) -> Result<Option<usize>, axum::response::Response> {
-    let Some(raw_budget) = headers.get("x-memory-budget") else {
-        return Ok(None);
-    };
-    let value = raw_budget.to_str().map_err(|_| {
+/// Consolidate a just-written event before answering a `?mode=sync` ingest. Past the
+/// `worker.sync_consolidation_budget_ms` budget the response falls back to async while
+/// the inline run carries on; anything it leaves unfinished stays with the background
+/// worker.
+async fn consolidate_inline(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    event: Event,
+    body: &mut serde_json::Value,
+) {
+    let budget =
+        std::time::Duration::from_millis(state.config.load().worker.sync_consolidation_budget_ms);
+    let worker = shard.worker.clone();
+    let task = tokio::spawn(async move { worker.consolidate_events_now(vec![event]).await });
+    let fallback_reason = match tokio::time::timeout(budget, task).await {
+        Ok(Ok(Ok(memory_ids))) if !memory_ids.is_empty() => {
+            body["mode"] = serde_json::json!("sync");
+            body["memory_ids"] = serde_json::json!(memory_ids);
+            return;
+        }
+        Ok(Ok(Ok(_))) => "not materialized inline".to_string(),
+        Ok(Ok(Err(e))) => {
+            tracing::warn!("Inline consolidation failed: {:?}", e);
+            e.to_string()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Inline consolidation task panicked: {:?}", e);
+            "inline consolidation panicked".to_string()
+        }
+        Err(_) => "sync consolidation budget exceeded".to_string(),
+    };
+    body["mode"] = serde_json::json!("async");
+    body["fallback_reason"] = serde_json::json!(fallback_reason);
+}
+
+async fn ingest_events_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, stream_id)): Path<(String, Uuid)>,
+    Json(payload): Json<BatchIngestRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if payload.events.is_empty() {
+        return Json(serde_json::json!({
+            "status": "accepted",
+            "event_ids": Vec::<String>::new(),
+            "count": 0
+        }))
+        .into_response();
+    }
+
+    for event in &payload.events {
+        if let Some(org_id) = event.org_id.as_deref() {
+            if let Err(r) = validate_id(org_id, "org_id") {
+                return r;
+            }
+        }
+        if let Err(r) = validate_location(event.location.as_ref()) {
+            return r;
+        }
+    }
+    let shard_id = state.shard_manager.shard_id_for_user(&user_id);
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_stream_key_scope(shard, &key_scope, &user_id, stream_id) {
+        return r;
+    }
+    if state.is_cluster_mode() {
+        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+        let metrics = raft.metrics().borrow().clone();
+        let current_leader = metrics.current_leader;
+        let node_id = metrics.id;
+
+        if current_leader != Some(node_id) {
+            if let Some(leader_id) = current_leader {
+                let path = format!("/v1/users/{}/streams/{}/events/batch", user_id, stream_id);
+                tracing::info!(
+                    "Not leader (I'm {}, leader is {}), forwarding batch request",
+                    node_id,
+                    leader_id
+                );
+                match forward_to_leader(&state, &metrics, leader_id, &path, &payload).await {
+                    Ok(response) => return response,
+                    Err(err_response) => return err_response,
+                }
+            }
+
+            return not_leader_response(&state.config.load(), &metrics);
+        }
+    }
+
+    let embedding_dim = state.config.load().llm.embedding_dim;
+    let mut events = Vec::with_capacity(payload.events.len());
+    let mut event_ids = Vec::with_capacity(payload.events.len());
+    for item in payload.events {
+        if let Some(embedding) = item.embedding.as_deref() {
+            if let Err(r) = validate_client_embedding(embedding, embedding_dim) {
+                return r;
+            }
+        }
+        let content = match parse_ingest_content(&item.content_type, item.content) {
+            Ok(content) => content,
+            Err(message) => {
+                return (
+                    axum::http::StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "status": "error",
+                        "message": message
+                    })),
+                )
+                    .into_response();
+            }
+        };
+
+        let mut event = Event::new(
+            item.org_id.clone(),
+            user_id.clone(),
+            None,
+            stream_id,
+            content,
+        );
+        if let Some(level) = item.level {
+            event.metadata["target_level"] = serde_json::json!(level);
+        }
+        if let Some(parent_id) = item.parent_id {
+            event.metadata["parent_id"] = serde_json::json!(parent_id);
+        }
+        if let Some(task_status) = item.task_status {
+            event.metadata["task_status"] = serde_json::json!(task_status);
+        }
+        if let Some(task_progress) = item.task_progress {
+            event.metadata["task_progress"] = serde_json::json!(task_progress);
+        }
+        if let Some(task_due_at) = item.task_due_at {
+            event.metadata["task_due_at"] = serde_json::json!(task_due_at.to_rfc3339());
+        }
+        if let Some(task_recurrence) = item.task_recurrence {
+            event.metadata["task_recurrence"] = serde_json::json!(task_recurrence);
+        }
+        if let Some(task_priority) = item.task_priority {
+            event.metadata["task_priority"] = serde_json::json!(task_priority);
+        }
+        if let Some(skip) = item.skip_compression {
+            event.metadata["skip_compression"] = serde_json::json!(skip);
+        }
+        if let Some(embedding) = item.embedding {
+            event.metadata["embedding"] = serde_json::json!(embedding);
+        }
+        event.set_conversation_turn(item.role, item.speaker_name, item.turn_index);
+        if let Some(location) = item.location {
+            event.set_location(location);
+        }
+        event_ids.push(event.id.to_string());
+        events.push(event);
+    }
+
+    let mut org_counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for event in &events {
+        if let Some(org_id) = event.org_id.as_deref() {
+            *org_counts.entry(org_id).or_default() += 1;
+        }
+    }
+    for (org_id, count) in org_counts {
+        if let Err(r) = check_org_event_quota(&state, Some(org_id), count).await {
+            return r;
+        }
+    }
+    if let Err(r) = check_user_key(&shard.engine, &user_id) {
+        return r;
+    }
+    if let Err(r) = check_ingest_admission(&shard.engine).await {
+        return r;
+    }
+
+    // Blocked events are dropped from the batch instead of failing it
+    let mut blocked_event_ids = Vec::new();
+    if state.moderation.is_some() {
+        let moderated: Vec<_> = futures_util::stream::iter(events)
+            .map(|event| {
+                let event_id = event.id.to_string();
+                let state = &state;
+                async move { (event_id, moderate_event(state, &shard.engine, event).await) }
+            })
+            .buffered(MODERATION_CONCURRENCY)
+            .collect()
+            .await;
+        events = Vec::with_capacity(moderated.len());
+        for (event_id, result) in moderated {
+            match result {
+                Ok(event) => events.push(event),
+                Err(_) => blocked_event_ids.push(event_id),
+            }
+        }
+        event_ids.retain(|event_id| !blocked_event_ids.contains(event_id));
+        if events.is_empty() {
+            return Json(serde_json::json!({
+                "status": "accepted",
+                "event_ids": event_ids,
+                "count": 0,
+                "blocked_event_ids": blocked_event_ids,
+            }))
+            .into_response();
+        }
+    }
+    let with_blocked = |mut body: serde_json::Value| {
+        if !blocked_event_ids.is_empty() {
+            body["blocked_event_ids"] = serde_json::json!(blocked_event_ids);
+        }
+        Json(body).into_response()
+    };
+
+    if state.is_standalone_mode() {
+        return match shard.engine.ingest_events_directly(events).await {
+            Ok(_) => with_blocked(serde_json::json!({
+                "status": "accepted",
+                "event_ids": event_ids,
+                "count": event_ids.len(),
+                "write_path": state.write_path_name(),
+            })),
+            Err(e) => {
+                tracing::error!("Direct batch write error: {:?}", e);
+                (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "status": "error",
+                        "message": e.to_string()
+                    })),
+                )
+                    .into_response()
+            }
+        };
+    }
+
+    match shard
+        .raft
+        .as_ref()
+        .expect("cluster mode requires raft")
+        .client_write(memorose_core::raft::types::ClientRequest::IngestEvents(
+            events,
+        ))
+        .await
+    {
+        Ok(resp) => with_blocked(serde_json::json!({
+            "status": "accepted",
+            "event_ids": event_ids,
+            "count": event_ids.len(),
+            "shard_id": shard_id,
+            "log_index": resp.log_id.index,
+        })),
+        Err(e) => {
+            tracing::error!("Raft batch write error: {:?}", e);
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "status": "error",
+                    "message": e.to_string()
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Moderation checks in flight at once for one batch.
+const MODERATION_CONCURRENCY: usize = 8;
+
+/// Check an event against its app's ingest policy, auditing anything flagged. A tagged
+/// event carries the verdict under `metadata.moderation`; `Err` holds the categories of
+/// an event the policy blocks.
+async fn moderate_event(
+    state: &AppState,
+    engine: &MemoroseEngine,
+    mut event: Event,
+) -> Result<Event, Vec<String>> {
+    let Some(moderation) = state.moderation.as_ref() else {
+        return Ok(event);
+    };
+    let app_id = event.agent_id.clone().or_else(|| {
+        event
+            .metadata
+            .get("agent_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    });
+    let action = moderation.policy_for(app_id.as_deref()).ingest;
+    if action == ModerationIngestAction::Allow {
+        return Ok(event);
+    }
+    let Some(text) = memorose_core::moderation::event_text(&event.content) else {
+        return Ok(event);
+    };
+    let verdict = moderation.check(&text).await;
+    if !verdict.flagged {
+        return Ok(event);
+    }
+
+    let outcome = match action {
+        ModerationIngestAction::Block => ModerationOutcome::Blocked,
+        _ => ModerationOutcome::Tagged,
+    };
+    record_moderation(
+        engine,
+        &event.user_id,
+        app_id,
+        ModerationStage::Ingest,
+        outcome,
+        event.id,
+        verdict.categories.clone(),
+    );
+    if outcome == ModerationOutcome::Blocked {
+        return Err(verdict.categories);
+    }
+    event.metadata["moderation"] = serde_json::json!({
+        "flagged": true,
+        "categories": verdict.categories,
+    });
+    Ok(event)
+}
+
+/// Categories that block a directly written memory under its app's ingest policy. Tagging
+/// has nowhere to go on a memory unit, so only blocking policies are checked.
+async fn moderate_memory_content(
+    state: &AppState,
+    engine: &MemoroseEngine,
+    unit: &MemoryUnit,
+) -> Option<Vec<String>> {
+    let moderation = state.moderation.as_ref()?;
+    if moderation.policy_for(unit.agent_id.as_deref()).ingest != ModerationIngestAction::Block {
+        return None;
+    }
+    let verdict = moderation.check(&unit.content).await;
+    if !verdict.flagged {
+        return None;
+    }
+    record_moderation(
+        engine,
+        &unit.user_id,
+        unit.agent_id.clone(),
+        ModerationStage::Ingest,
+        ModerationOutcome::Blocked,
+        unit.id,
+        verdict.categories.clone(),
+    );
+    Some(verdict.categories)
+}
+
+/// Drop the hits that the requesting app's policy strips as flagged, auditing each one.
+async fn strip_moderated_hits(
+    state: &AppState,
+    engine: &MemoroseEngine,
+    user_id: &str,
+    app_id: Option<&str>,
+    hits: Vec<(SharedSearchHit, f32)>,
+) -> Vec<(SharedSearchHit, f32)> {
+    let Some(moderation) = state.moderation.as_ref() else {
+        return hits;
+    };
+    if !moderation.policy_for(app_id).strip_from_retrieval {
+        return hits;
+    }
+
+    let verdicts: Vec<_> = futures_util::stream::iter(&hits)
+        .map(|(hit, _)| moderation.check(&hit.memory_unit().content))
+        .buffered(MODERATION_CONCURRENCY)
+        .collect()
+        .await;
+    hits.into_iter()
+        .zip(verdicts)
+        .filter_map(|(hit, verdict)| {
+            if !verdict.flagged {
+                return Some(hit);
+            }
+            record_moderation(
+                engine,
+                user_id,
+                app_id.map(str::to_string),
+                ModerationStage::Retrieval,
+                ModerationOutcome::Stripped,
+                hit.0.memory_unit().id,
+                verdict.categories,
+            );
+            None
+        })
+        .collect()
+}
+
+fn record_moderation(
+    engine: &MemoroseEngine,
+    user_id: &str,
+    app_id: Option<String>,
+    stage: ModerationStage,
+    outcome: ModerationOutcome,
+    item_id: Uuid,
+    categories: Vec<String>,
+) {
+    let record = ModerationAuditRecord {
+        id: Uuid::new_v4(),
+        user_id: user_id.to_string(),
+        app_id,
+        stage,
+        outcome,
+        item_id,
+        categories,
+        recorded_at: chrono::Utc::now(),
+    };
+    if let Err(e) = engine.record_moderation(&record) {
+        tracing::warn!("Failed to record moderation audit for {}: {:?}", user_id, e);
+    }
+}
+
+fn moderation_blocked_response(categories: &[String]) -> axum::response::Response {
+    (
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+        Json(serde_json::json!({
+            "status": "error",
+            "message": "content blocked by moderation",
+            "categories": categories,
+        })),
+    )
+        .into_response()
+}
+
+fn memory_budget_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<usize>, axum::response::Response> {
+    let Some(raw_budget) = headers.get("x-memory-budget") else {
+        return Ok(None);
+    };
+    let value = raw_budget.to_str().map_err(|_| {
         (
             axum::http::StatusCode::BAD_REQUEST,
             Json(serde_json::json!({ "error": "X-Memory-Budget must be a valid UTF-8 integer" })),
@@ -1041,20 +2438,117 @@ fn validate_payload_token_budget(
     }
 }
 
-async fn embed_query_with_optional_multimodal(
+const MAX_RETRIEVE_LIMIT: usize = 100;
+const MAX_RETRIEVE_OFFSET: usize = 1000;
+
+fn validate_retrieve_offset(offset: usize) -> Result<(), axum::response::Response> {
+    if offset > MAX_RETRIEVE_OFFSET {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("offset must not exceed {}", MAX_RETRIEVE_OFFSET)
+            })),
+        )
+            .into_response());
+    }
+    Ok(())
+}
+
+/// Query image resolved to base64, with the vision model's caption of it.
+struct QueryImage {
+    data: String,
+    caption: Option<String>,
+}
+
+/// Resolve a request's inline or stored query image and caption it, so an image query
+/// matches memories by embedding and, through the caption, by full text.
+async fn resolve_query_image(
     state: &Arc<AppState>,
-    query: &str,
+    shard: &shard_manager::ShardState,
+    user_id: &str,
     image: Option<&str>,
-    audio: Option<&str>,
-    video: Option<&str>,
-) -> Result<Vec<f32>, String> {
-    let has_multimodal = image.is_some() || audio.is_some() || video.is_some();
-    let query_key = query.to_string();
-
-    if has_multimodal {
-        use memorose_core::llm::{EmbedInput, EmbedPart};
-        let mut parts = vec![EmbedPart::Text(query.to_string())];
-        if let Some(img) = image {
+    image_asset: Option<QueryAssetRef>,
+) -> Result<Option<QueryImage>, axum::response::Response> {
+    let data =
+        match (image, image_asset) {
+            (Some(_), Some(_)) => return Err((
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(
+                    serde_json::json!({ "error": "Provide either image or image_asset, not both" }),
+                ),
+            )
+                .into_response()),
+            (Some(image), None) => image.to_string(),
+            (None, Some(asset_ref)) => {
+                let asset = match shard
+                    .engine
+                    .get_memory_unit(user_id, asset_ref.memory_id)
+                    .await
+                {
+                    Ok(unit) => unit.and_then(|unit| unit.assets.get(asset_ref.index).cloned()),
+                    Err(error) => {
+                        return Err((
+                            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({ "error": error.to_string() })),
+                        )
+                            .into_response())
+                    }
+                };
+                let path = asset.and_then(|asset| {
+                    memorose_core::ingest::image::resolve_local_asset(
+                        &shard.engine.asset_dir(),
+                        &asset.storage_key,
+                    )
+                });
+                let bytes = match path {
+                    Some(path) => tokio::fs::read(path).await.ok(),
+                    None => None,
+                };
+                let Some(bytes) = bytes else {
+                    return Err((
+                        axum::http::StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({ "error": "Image asset not found" })),
+                    )
+                        .into_response());
+                };
+                base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes)
+            }
+            (None, None) => return Ok(None),
+        };
+
+    let caption = match state.llm_client.describe_image(&data).await {
+        Ok(response) => Some(response.data.trim().to_string()).filter(|c| !c.is_empty()),
+        Err(error) => {
+            tracing::warn!("Failed to caption query image: {:?}", error);
+            None
+        }
+    };
+    Ok(Some(QueryImage { data, caption }))
+}
+
+/// The text side of an image query: the user's words followed by the image caption.
+fn fuse_query_with_caption(query: &str, caption: Option<&str>) -> String {
+    match caption {
+        Some(caption) if query.trim().is_empty() => caption.to_string(),
+        Some(caption) => format!("{} {}", query.trim(), caption),
+        None => query.to_string(),
+    }
+}
+
+async fn embed_query_with_optional_multimodal(
+    state: &Arc<AppState>,
+    query: &str,
+    image: Option<&str>,
+    audio: Option<&str>,
+    video: Option<&str>,
+) -> Result<Vec<f32>, String> {
+    let has_multimodal = image.is_some() || audio.is_some() || video.is_some();
+    let query_key = query.to_string();
+
+    if has_multimodal {
+        use memorose_core::llm::{EmbedInput, EmbedPart};
+        let mut parts = vec![EmbedPart::Text(query.to_string())];
+        if let Some(img) = image {
             parts.push(EmbedPart::InlineData {
                 mime_type: "image/jpeg".to_string(),
                 data: img.to_string(),
@@ -1380,10 +2874,52 @@ fn format_memory_xml_block(unit: &MemoryUnit, tier: ContextCompressionTier) -> S
     xml
 }
 
+#[cfg(test)]
 fn render_memory_context(
     results: &[(SharedSearchHit, f32)],
     token_budget: usize,
     format: ContextFormat,
+) -> RenderedMemoryContext {
+    render_memory_context_with_profile(results, None, token_budget, format)
+}
+
+fn format_profile_context_block(
+    profile: &memorose_common::UserProfile,
+    format: ContextFormat,
+) -> String {
+    match format {
+        ContextFormat::Text => {
+            let lines = profile
+                .attributes
+                .iter()
+                .map(|(key, attribute)| format!("- {}: {}", key, attribute.value.render()))
+                .collect::<Vec<_>>();
+            format!("User profile:\n{}", lines.join("\n"))
+        }
+        ContextFormat::Xml => {
+            let attributes = profile
+                .attributes
+                .iter()
+                .map(|(key, attribute)| {
+                    format!(
+                        "<attribute key=\"{}\">{}</attribute>",
+                        xml_escape(key),
+                        xml_escape(&attribute.value.render())
+                    )
+                })
+                .collect::<String>();
+            format!("<user_profile>{}</user_profile>", attributes)
+        }
+    }
+}
+
+/// Render retrieval hits into a budgeted context, prefixed by the user's structured
+/// profile when one exists and fits the budget.
+fn render_memory_context_with_profile(
+    results: &[(SharedSearchHit, f32)],
+    profile: Option<&memorose_common::UserProfile>,
+    token_budget: usize,
+    format: ContextFormat,
 ) -> RenderedMemoryContext {
     let tier = context_compression_tier(token_budget);
     let mut ordered = results.iter().collect::<Vec<_>>();
@@ -1399,6 +2935,12 @@ fn render_memory_context(
         ContextFormat::Text => String::new(),
         ContextFormat::Xml => "<memory_context>".to_string(),
     };
+    if let Some(profile) = profile.filter(|profile| !profile.attributes.is_empty()) {
+        let candidate = format!("{context}{}", format_profile_context_block(profile, format));
+        if count_tokens(&candidate) <= token_budget {
+            context = candidate;
+        }
+    }
     let mut hits = Vec::new();
     let mut truncated = false;
 
@@ -1409,9 +2951,7 @@ fn render_memory_context(
             ContextFormat::Xml => format_memory_xml_block(unit, tier),
         };
 
-        let separator = if context.is_empty() || context == "<memory_context>" {
-            ""
-        } else if format == ContextFormat::Text {
+        let separator = if format == ContextFormat::Text && !context.is_empty() {
             "\n"
         } else {
             ""
@@ -1474,9 +3014,64 @@ fn render_memory_context(
     }
 }
 
+/// Time range named in a retrieve's query, when temporal parsing is on and the request
+/// sets no valid-time bounds of its own.
+fn parse_query_time_range(
+    config: &memorose_common::config::TemporalQueryConfig,
+    payload: &RetrieveRequest,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Option<memorose_core::temporal::ParsedTimeRange>, axum::response::Response> {
+    use memorose_common::config::{
+        MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES, MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES,
+    };
+    use memorose_core::temporal::{parse_time_range, TemporalLocale};
+
+    let bad_request = |error: String| {
+        (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response()
+    };
+    let locale = match payload.locale.as_deref() {
+        Some(tag) => match TemporalLocale::from_tag(tag) {
+            Some(locale) => locale,
+            None => {
+                return Err(bad_request(format!(
+                    "unsupported locale {:?}; expected en or zh",
+                    tag
+                )))
+            }
+        },
+        None => TemporalLocale::detect(&payload.query),
+    };
+    let offset_minutes = payload
+        .utc_offset_minutes
+        .unwrap_or(config.utc_offset_minutes);
+    if !(MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES..=MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES)
+        .contains(&offset_minutes)
+    {
+        return Err(bad_request(format!(
+            "utc_offset_minutes must be between {} and {}",
+            MIN_TEMPORAL_QUERY_UTC_OFFSET_MINUTES, MAX_TEMPORAL_QUERY_UTC_OFFSET_MINUTES
+        )));
+    }
+    if !payload.parse_time.unwrap_or(config.enabled)
+        || payload.start_time.is_some()
+        || payload.end_time.is_some()
+    {
+        return Ok(None);
+    }
+    let Some(offset) = chrono::FixedOffset::east_opt(offset_minutes * 60) else {
+        return Ok(None);
+    };
+    Ok(parse_time_range(&payload.query, now, offset, locale))
+}
+
 async fn retrieve_memory(
     State(state): State<Arc<AppState>>,
     Path((user_id, stream_id)): Path<(String, Uuid)>,
+    Extension(key_scope): Extension<ApiKeyScope>,
     headers: HeaderMap,
     Json(payload): Json<RetrieveRequest>,
 ) -> axum::response::Response {
@@ -1489,7 +3084,58 @@ async fn retrieve_memory(
             return r;
         }
     }
+    let (agent_id, app_ids) = match &payload.scope {
+        RetrievalScope::App => (payload.agent_id.as_deref(), None),
+        RetrievalScope::User => (None, None),
+        RetrievalScope::Apps(app_ids) => {
+            if app_ids.is_empty() {
+                return (
+                    axum::http::StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": "scope.apps must list at least one app" })),
+                )
+                    .into_response();
+            }
+            for app_id in app_ids {
+                if let Err(r) = validate_id(app_id, "app_id") {
+                    return r;
+                }
+            }
+            (None, Some(app_ids.as_slice()))
+        }
+    };
+    let requested_apps: Option<Vec<&str>> = match (agent_id, app_ids) {
+        (Some(agent_id), _) => Some(vec![agent_id]),
+        (None, Some(app_ids)) => Some(app_ids.iter().map(String::as_str).collect()),
+        (None, None) => None,
+    };
+    if let Err(r) = check_key_scope(&key_scope, &user_id, requested_apps.as_deref()) {
+        return r;
+    }
+    if let Some(embedding) = payload.embedding.as_deref() {
+        if let Err(r) = validate_client_embedding(embedding, state.config.load().llm.embedding_dim)
+        {
+            return r;
+        }
+    }
     let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_user_key(&shard.engine, &user_id) {
+        return r;
+    }
+    let read_your_writes =
+        match wait_for_event_applied(&state, shard, payload.min_applied_index).await {
+            Ok(read_your_writes) => read_your_writes,
+            Err(r) => return r,
+        };
+    if payload.consistency == ReadConsistency::Strong {
+        if let Err(e) = shard.engine.sync_text_index(&user_id).await {
+            tracing::error!("Text index sync for strong read failed: {:?}", e);
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("Text index sync failed: {}", e) })),
+            )
+                .into_response();
+        }
+    }
     let header_token_budget = match memory_budget_from_headers(&headers) {
         Ok(budget) => budget,
         Err(response) => return response,
@@ -1499,15 +3145,122 @@ async fn retrieve_memory(
         Err(response) => return response,
     };
     let token_budget = payload_token_budget.or(header_token_budget);
-
-    let embedding_f32 = embed_query_with_optional_multimodal(
+    if let Err(r) = validate_retrieve_offset(payload.offset) {
+        return r;
+    }
+    if !(0.0..=1.0).contains(&payload.recency_bias) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "recency_bias must be between 0.0 and 1.0" })),
+        )
+            .into_response();
+    }
+    if payload.max_tokens == Some(0) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(
+                serde_json::json!({ "error": "max_tokens must be a positive integer greater than zero" }),
+            ),
+        )
+            .into_response();
+    }
+    if payload
+        .mmr_lambda
+        .is_some_and(|lambda| !(0.0..=1.0).contains(&lambda))
+    {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "mmr_lambda must be between 0.0 and 1.0" })),
+        )
+            .into_response();
+    }
+    if let Some(unknown) = payload.emotions.iter().flatten().find(|emotion| {
+        !memorose_core::emotion::EMOTION_LABELS.contains(&emotion.trim().to_lowercase().as_str())
+    }) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "unknown emotion {:?}; expected one of {}",
+                    unknown,
+                    memorose_core::emotion::EMOTION_LABELS.join(", ")
+                )
+            })),
+        )
+            .into_response();
+    }
+    if payload.near.is_some_and(|near| !near.is_valid()) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "near needs lat within [-90, 90], lon within [-180, 180] and a positive radius in meters"
+            })),
+        )
+            .into_response();
+    }
+    let query_time_range = match parse_query_time_range(
+        &state.config.load().temporal_query,
+        &payload,
+        chrono::Utc::now(),
+    ) {
+        Ok(range) => range,
+        Err(r) => return r,
+    };
+    // A running ranking experiment overrides the request's ranking settings.
+    let experiment = experiments::assign_variant(&state, &user_id);
+    let variant = experiment.as_ref().map(|(_, variant)| variant);
+    let variant_engine =
+        variant.and_then(|variant| experiments::variant_engine(&state, &shard.engine, variant));
+    let engine = variant_engine.as_ref().unwrap_or(&shard.engine);
+    let min_score = variant
+        .and_then(|variant| variant.min_score)
+        .or(payload.min_score);
+    let graph_depth = variant
+        .and_then(|variant| variant.graph_depth)
+        .unwrap_or(payload.graph_depth);
+    let recency_bias = variant
+        .and_then(|variant| variant.recency_bias)
+        .unwrap_or(payload.recency_bias);
+    let mmr_lambda = variant
+        .and_then(|variant| variant.mmr_lambda)
+        .or(payload.mmr_lambda);
+    let limit = payload.limit.min(MAX_RETRIEVE_LIMIT);
+    let emotions = payload
+        .emotions
+        .as_deref()
+        .filter(|emotions| !emotions.is_empty());
+    let mut timings = slow_query::RetrieveTimings::default();
+    let mut stage = std::time::Instant::now();
+
+    let query_image = match resolve_query_image(
         &state,
-        &payload.query,
+        shard,
+        &user_id,
         payload.image.as_deref(),
-        payload.audio.as_deref(),
-        payload.video.as_deref(),
+        payload.image_asset,
     )
-    .await;
+    .await
+    {
+        Ok(image) => image,
+        Err(r) => return r,
+    };
+    let image_caption = query_image.as_ref().and_then(|image| image.caption.clone());
+    let search_query = fuse_query_with_caption(&payload.query, image_caption.as_deref());
+
+    let embedding_f32 = match payload.embedding.clone() {
+        Some(embedding) => Ok(embedding),
+        None => {
+            embed_query_with_optional_multimodal(
+                &state,
+                &payload.query,
+                query_image.as_ref().map(|image| image.data.as_str()),
+                payload.audio.as_deref(),
+                payload.video.as_deref(),
+            )
+            .await
+        }
+    };
+    timings.embedding_ms = RetrievalStageTimings::lap(&mut stage);
 
     match embedding_f32 {
         Ok(embedding_f32) => {
@@ -1517,7 +3270,10 @@ async fn retrieve_memory(
                     end: payload.end_time,
                 })
             } else {
-                None
+                query_time_range.as_ref().map(|range| TimeRange {
+                    start: Some(range.start),
+                    end: Some(range.end),
+                })
             };
 
             let tx_range = payload.as_of.map(|t| TimeRange {
@@ -1525,37 +3281,205 @@ async fn retrieve_memory(
                 end: Some(t),
             });
 
-            match shard
-                .engine
-                .search_hybrid_with_shared_and_token_budget(
+            match engine
+                .search_hybrid_with_shared_explained(
                     &user_id,
                     payload.org_id.as_deref(),
-                    payload.agent_id.as_deref(),
-                    &payload.query,
+                    agent_id,
+                    app_ids,
+                    emotions,
+                    payload.near.as_ref(),
+                    &search_query,
                     &embedding_f32,
-                    payload.limit.min(100),
+                    limit,
+                    payload.offset,
                     payload.enable_arbitration,
-                    payload.min_score,
-                    payload.graph_depth,
-                    valid_range,
-                    tx_range,
+                    min_score,
+                    graph_depth,
+                    valid_range.clone(),
+                    tx_range.clone(),
                     token_budget,
+                    recency_bias,
+                    mmr_lambda,
+                    payload.vector_mode,
+                    memorose_core::engine::SearchExplainOptions {
+                        arbitration: payload.explain_arbitration,
+                        diagnostics: payload.debug,
+                    },
                 )
                 .await
             {
-                Ok(units) => {
-                    let processed_units = units
-                        .into_iter()
-                        .map(|(u, score)| RetrieveResultItem {
-                            unit: RetrievalMemoryUnitView::from(u.memory_unit()),
-                            score,
+                Ok(outcome) => {
+                    timings.search = outcome.timings;
+                    // Sample traffic for replay. Experiment variants rank differently from
+                    // the configured engine, so only requests outside one are recorded.
+                    let recording = state.config.load().traffic_recording.clone();
+                    if experiment.is_none()
+                        && recording.sample_rate > 0.0
+                        && rand::random::<f64>() < recording.sample_rate
+                    {
+                        let record = memorose_core::replay::RecordedRetrieval {
+                            id: Uuid::new_v4(),
+                            recorded_at: chrono::Utc::now(),
+                            user_id: user_id.clone(),
+                            org_id: payload.org_id.clone(),
+                            agent_id: agent_id.map(str::to_string),
+                            app_ids: app_ids.map(<[String]>::to_vec),
+                            emotions: emotions.map(<[String]>::to_vec),
+                            near: payload.near,
+                            query: search_query.clone(),
+                            embedding: embedding_f32.clone(),
+                            limit,
+                            offset: payload.offset,
+                            enable_arbitration: payload.enable_arbitration,
+                            min_score,
+                            graph_depth,
+                            valid_time: valid_range.clone(),
+                            transaction_time: tx_range,
+                            token_budget,
+                            recency_bias,
+                            mmr_lambda,
+                            vector_mode: payload.vector_mode,
+                            results: outcome
+                                .results
+                                .iter()
+                                .map(|(hit, score)| (hit.id, *score))
+                                .collect(),
+                        };
+                        if let Err(e) = shard
+                            .engine
+                            .record_retrieval(&record, recording.max_records)
+                        {
+                            tracing::warn!("Failed to record retrieval for replay: {:?}", e);
+                        }
+                    }
+                    RetrievalStageTimings::lap(&mut stage);
+                    let mut units = outcome.results;
+                    // The owning shard only sees grants for memories it stores; on the
+                    // first page, fold in what other shards share with this user.
+                    if payload.offset == 0 {
+                        match search_granted_on_other_shards(
+                            &state,
+                            shard,
+                            &user_id,
+                            &search_query,
+                            &embedding_f32,
+                            limit,
+                            min_score,
+                            valid_range,
+                        )
+                        .await
+                        {
+                            Ok(mut granted) if !granted.is_empty() => {
+                                if let Some(emotions) = emotions {
+                                    granted.retain(|(hit, _)| {
+                                        hit.memory_unit().has_any_emotion(emotions)
+                                    });
+                                }
+                                if let Some(near) = payload.near {
+                                    granted.retain(|(hit, _)| {
+                                        hit.memory_unit()
+                                            .location
+                                            .is_some_and(|location| near.contains(&location))
+                                    });
+                                }
+                                units.extend(granted);
+                                units.sort_by(|a, b| {
+                                    b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
+                                });
+                                units.truncate(limit);
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                tracing::warn!("Failed to search shared memories: {:?}", e)
+                            }
+                        }
+                    }
+                    let units =
+                        strip_moderated_hits(&state, &shard.engine, &user_id, agent_id, units)
+                            .await;
+                    timings.cross_shard_ms = RetrievalStageTimings::lap(&mut stage);
+                    let context = match payload.max_tokens {
+                        Some(max_tokens) => match shard
+                            .engine
+                            .pack_context(&units, max_tokens, payload.summarize_overflow)
+                            .await
+                        {
+                            Ok(context) => Some(context),
+                            Err(e) => {
+                                tracing::error!("Context packing error: {:?}", e);
+                                return (
+                                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                                    Json(serde_json::json!({ "error": e.to_string() })),
+                                )
+                                    .into_response();
+                            }
+                        },
+                        None => None,
+                    };
+                    timings.packing_ms = RetrievalStageTimings::lap(&mut stage);
+                    let next_offset = outcome
+                        .has_more
+                        .then_some(payload.offset + limit)
+                        .filter(|next| *next <= MAX_RETRIEVE_OFFSET);
+                    // The profile heads the first page only.
+                    let profile = if payload.include_profile && payload.offset == 0 {
+                        match shard.engine.get_user_profile(&user_id).await {
+                            Ok(profile) => profile,
+                            Err(e) => {
+                                tracing::warn!("Failed to load profile for {}: {:?}", user_id, e);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    timings.profile_ms = RetrievalStageTimings::lap(&mut stage);
+                    let profile_id = profile.as_ref().map(|unit| unit.id);
+                    let processed_units = profile
+                        .iter()
+                        .map(|unit| RetrieveResultItem {
+                            unit: RetrievalMemoryUnitView::from(unit),
+                            score: 1.0,
                         })
-                        .collect();
+                        .chain(
+                            units
+                                .into_iter()
+                                .filter(|(u, _)| Some(u.id) != profile_id)
+                                .map(|(u, score)| RetrieveResultItem {
+                                    unit: RetrievalMemoryUnitView::from(u.memory_unit()),
+                                    score,
+                                }),
+                        )
+                        .collect::<Vec<_>>();
+
+                    timings.total_ms = start.elapsed().as_secs_f64() * 1000.0;
+                    if slow_query::SlowQueryLog::is_slow(
+                        state.config.load().slow_query.threshold_ms,
+                        timings.total_ms,
+                    ) {
+                        state.slow_queries.record(slow_query::SlowQueryEntry {
+                            recorded_at: chrono::Utc::now(),
+                            user_id: user_id.clone(),
+                            stream_id,
+                            query: payload.query.clone(),
+                            result_count: processed_units.len(),
+                            timings,
+                        });
+                    }
 
                     Json(RetrieveResponse {
                         stream_id,
                         query: payload.query,
                         results: processed_units,
+                        arbitration: outcome.arbitration,
+                        debug: outcome.diagnostics,
+                        next_offset,
+                        context,
+                        image_caption,
+                        time_range: query_time_range,
+                        experiment: experiment.map(|(arm, _)| arm),
+                        read_your_writes,
                         query_time_ms: start.elapsed().as_millis(),
                     })
                     .into_response()
@@ -1583,8 +3507,169 @@ async fn retrieve_memory(
     }
 }
 
+async fn search_granted_on_other_shards(
+    state: &AppState,
+    home: &shard_manager::ShardState,
+    user_id: &str,
+    query: &str,
+    embedding: &[f32],
+    limit: usize,
+    min_score: Option<f32>,
+    valid_range: Option<TimeRange>,
+) -> anyhow::Result<Vec<(memorose_core::engine::SharedSearchHit, f32)>> {
+    let mut hits = Vec::new();
+    for (_, shard) in state.shard_manager.all_shards() {
+        if std::ptr::eq(shard, home) {
+            continue;
+        }
+        hits.extend(
+            shard
+                .engine
+                .search_granted_memories(
+                    user_id,
+                    query,
+                    embedding,
+                    limit,
+                    min_score,
+                    valid_range.clone(),
+                )
+                .await?,
+        );
+    }
+    Ok(hits)
+}
+
+/// Answer one question strictly from the user's memories, citing the ones it rests on.
+async fn ask_memory(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Json(payload): Json<AskRequest>,
+) -> axum::response::Response {
+    let start = std::time::Instant::now();
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Some(org_id) = payload.org_id.as_deref() {
+        if let Err(r) = validate_id(org_id, "org_id") {
+            return r;
+        }
+    }
+    let requested_apps = payload.agent_id.as_deref().map(|agent_id| vec![agent_id]);
+    if let Err(r) = check_key_scope(&key_scope, &user_id, requested_apps.as_deref()) {
+        return r;
+    }
+    let question = payload.question.trim();
+    if question.is_empty() {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "question must not be empty" })),
+        )
+            .into_response();
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_user_key(&shard.engine, &user_id) {
+        return r;
+    }
+    let read_your_writes =
+        match wait_for_event_applied(&state, shard, payload.min_applied_index).await {
+            Ok(read_your_writes) => read_your_writes,
+            Err(r) => return r,
+        };
+
+    let embedding =
+        match embed_query_with_optional_multimodal(&state, question, None, None, None).await {
+            Ok(embedding) => embedding,
+            Err(e) => return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(
+                    serde_json::json!({ "error": format!("Failed to generate embedding: {}", e) }),
+                ),
+            )
+                .into_response(),
+        };
+    let hits = match shard
+        .engine
+        .search_hybrid_with_shared(
+            &user_id,
+            payload.org_id.as_deref(),
+            payload.agent_id.as_deref(),
+            question,
+            &embedding,
+            payload.limit.clamp(1, 20),
+            false,
+            payload.min_score,
+            payload.graph_depth,
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(hits) => hits,
+        Err(e) => {
+            tracing::error!("Search error: {:?}", e);
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    };
+    let hits = strip_moderated_hits(
+        &state,
+        &shard.engine,
+        &user_id,
+        payload.agent_id.as_deref(),
+        hits,
+    )
+    .await;
+
+    let units: Vec<&MemoryUnit> = hits.iter().map(|(hit, _)| hit.memory_unit()).collect();
+    let answer = match memorose_core::answer::answer_from_memories(
+        state.llm_client.as_ref(),
+        question,
+        &units,
+    )
+    .await
+    {
+        Ok(response) => response.data,
+        Err(e) => {
+            tracing::error!("Answer generation failed: {:?}", e);
+            return (
+                axum::http::StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({ "error": format!("Answer generation failed: {}", e) })),
+            )
+                .into_response();
+        }
+    };
+    let supporting = answer
+        .citations
+        .iter()
+        .map(|&citation| {
+            let (hit, score) = &hits[citation - 1];
+            AskSupportingItem {
+                citation,
+                unit: RetrievalMemoryUnitView::from(hit.memory_unit()),
+                score: *score,
+            }
+        })
+        .collect();
+
+    Json(AskResponse {
+        question: question.to_string(),
+        answer: answer.answer,
+        confidence: answer.confidence,
+        supporting,
+        considered: hits.len(),
+        read_your_writes,
+        query_time_ms: start.elapsed().as_millis(),
+    })
+    .into_response()
+}
+
 async fn build_memory_context(
     State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
     headers: HeaderMap,
     Json(payload): Json<MemoryContextRequest>,
 ) -> axum::response::Response {
@@ -1592,6 +3677,10 @@ async fn build_memory_context(
     if let Err(r) = validate_id(&payload.user_id, "user_id") {
         return r;
     }
+    let requested_apps = payload.agent_id.as_deref().map(|agent_id| vec![agent_id]);
+    if let Err(r) = check_key_scope(&key_scope, &payload.user_id, requested_apps.as_deref()) {
+        return r;
+    }
     if let Some(org_id) = payload.org_id.as_deref() {
         if let Err(r) = validate_id(org_id, "org_id") {
             return r;
@@ -1614,11 +3703,35 @@ async fn build_memory_context(
     let compression_tier = context_compression_tier(token_budget);
     let search_limit = context_search_limit(payload.limit, compression_tier);
     let shard = state.shard_manager.shard_for_user(&payload.user_id);
+    let read_your_writes =
+        match wait_for_event_applied(&state, shard, payload.min_applied_index).await {
+            Ok(read_your_writes) => read_your_writes,
+            Err(r) => return r,
+        };
+
+    let query_image = match resolve_query_image(
+        &state,
+        shard,
+        &payload.user_id,
+        payload.image.as_deref(),
+        payload.image_asset,
+    )
+    .await
+    {
+        Ok(image) => image,
+        Err(r) => return r,
+    };
+    let search_query = fuse_query_with_caption(
+        &payload.query,
+        query_image
+            .as_ref()
+            .and_then(|image| image.caption.as_deref()),
+    );
 
     let embedding_f32 = embed_query_with_optional_multimodal(
         &state,
         &payload.query,
-        payload.image.as_deref(),
+        query_image.as_ref().map(|image| image.data.as_str()),
         payload.audio.as_deref(),
         payload.video.as_deref(),
     )
@@ -1645,7 +3758,7 @@ async fn build_memory_context(
                     &payload.user_id,
                     payload.org_id.as_deref(),
                     payload.agent_id.as_deref(),
-                    &payload.query,
+                    &search_query,
                     &embedding_f32,
                     search_limit,
                     payload.enable_arbitration,
@@ -1658,7 +3771,27 @@ async fn build_memory_context(
                 .await
             {
                 Ok(results) => {
-                    let rendered = render_memory_context(&results, token_budget, format);
+                    let results = strip_moderated_hits(
+                        &state,
+                        &shard.engine,
+                        &payload.user_id,
+                        payload.agent_id.as_deref(),
+                        results,
+                    )
+                    .await;
+                    let profile = shard
+                        .engine
+                        .get_structured_profile(&payload.user_id)
+                        .unwrap_or_else(|e| {
+                            tracing::warn!("Failed to load user profile for context: {:?}", e);
+                            None
+                        });
+                    let rendered = render_memory_context_with_profile(
+                        &results,
+                        profile.as_ref(),
+                        token_budget,
+                        format,
+                    );
                     Json(MemoryContextResponse {
                         query: payload.query,
                         format: format.as_str().to_string(),
@@ -1670,6 +3803,7 @@ async fn build_memory_context(
                         truncated: rendered.truncated,
                         context: rendered.context,
                         hits: rendered.hits,
+                        read_your_writes,
                         query_time_ms: start.elapsed().as_millis(),
                     })
                     .into_response()
@@ -1684,280 +3818,2256 @@ async fn build_memory_context(
                 }
             }
         }
-        Err(e) => {
-            tracing::error!("Context embedding error: {:?}", e);
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({ "error": format!("Failed to generate embedding: {}", e) }),
-                ),
+        Err(e) => {
+            tracing::error!("Context embedding error: {:?}", e);
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(
+                    serde_json::json!({ "error": format!("Failed to generate embedding: {}", e) }),
+                ),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn hard_delete_memory_unit_for_user(
+    engine: &MemoroseEngine,
+    user_id: &str,
+    unit_id: Uuid,
+) -> anyhow::Result<bool> {
+    if engine
+        .get_memory_unit_including_forgotten(user_id, unit_id)?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    engine.delete_memory_unit_hard(user_id, unit_id).await?;
+    Ok(true)
+}
+
+fn parse_memory_unit_id(id: &str) -> std::result::Result<Uuid, axum::response::Response> {
+    Uuid::parse_str(id).map_err(|_| {
+        (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "Invalid memory ID format" })),
+        )
+            .into_response()
+    })
+}
+
+async fn delete_memory_unit(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, id)): Path<(String, String)>,
+    Query(query): Query<DeleteMemoryQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let unit_id = match parse_memory_unit_id(&id) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_shard_leader(&state, shard) {
+        return r;
+    }
+    if let Err(r) = check_unit_key_scope(&shard.engine, &key_scope, &user_id, unit_id) {
+        return r;
+    }
+    if !query.hard {
+        let retention_days = state.config.load().worker.trash_retention_days;
+        return match trash_memory_unit(&state, shard, &user_id, unit_id, retention_days).await {
+            Ok(Some(entry)) => Json(serde_json::json!({
+                "status": "deleted",
+                "memory_id": unit_id,
+                "mode": "soft",
+                "purge_after": entry.purge_after,
+            }))
+            .into_response(),
+            Ok(None) => (
+                axum::http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Memory not found" })),
+            )
+                .into_response(),
+            Err(error) => replication_error_response(&state, &error),
+        };
+    }
+
+    let deleted = if state.is_standalone_mode() {
+        hard_delete_memory_unit_for_user(&shard.engine, &user_id, unit_id).await
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::DeleteMemoryUnit {
+                user_id: user_id.clone(),
+                unit_id,
+            },
+        )
+        .await
+    };
+    match deleted {
+        Ok(true) => Json(serde_json::json!({
+            "status": "deleted",
+            "memory_id": unit_id,
+            "mode": "hard"
+        }))
+        .into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Memory not found" })),
+        )
+            .into_response(),
+        Err(error) => replication_error_response(&state, &error),
+    }
+}
+
+/// Move a memory unit to the trash. In cluster mode the leader picks the restore window
+/// and replicates the entry, so every replica purges the unit at the same time.
+async fn trash_memory_unit(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    user_id: &str,
+    unit_id: Uuid,
+    retention_days: u32,
+) -> anyhow::Result<Option<memorose_common::TrashedMemoryUnit>> {
+    if state.is_standalone_mode() {
+        return shard
+            .engine
+            .soft_delete_memory_unit(user_id, unit_id, retention_days)
+            .await;
+    }
+    let Some(entry) = shard
+        .engine
+        .memory_unit_trash_entry(user_id, unit_id, retention_days)?
+    else {
+        return Ok(None);
+    };
+    let trashed = replicate_command(
+        shard,
+        memorose_core::raft::types::ClientRequest::TrashMemoryUnit(entry.clone()),
+    )
+    .await?;
+    Ok(trashed.then_some(entry))
+}
+
+/// Serve an asset of a memory unit from local storage. Assets that were never
+/// downloaded (remote URLs that failed to fetch) are reported with their source instead.
+async fn get_memory_asset(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, id, index)): Path<(String, String, usize)>,
+    Query(query): Query<AssetQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let unit_id = match parse_memory_unit_id(&id) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let unit = match shard.engine.get_memory_unit(&user_id, unit_id).await {
+        Ok(Some(unit)) => unit,
+        Ok(None) => {
+            return (
+                axum::http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Memory not found" })),
+            )
+                .into_response()
+        }
+        Err(error) if memorose_core::crypto::is_key_unavailable(&error) => {
+            return (
+                axum::http::StatusCode::GONE,
+                Json(serde_json::json!({ "error": error.to_string() })),
+            )
+                .into_response()
+        }
+        Err(error) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": error.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    let Some(asset) = unit.assets.get(index) else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Asset not found" })),
+        )
+            .into_response();
+    };
+
+    let (storage_key, content_type) = if query.thumbnail {
+        match asset
+            .metadata
+            .get(memorose_core::ingest::image::THUMBNAIL_KEY_METADATA_KEY)
+        {
+            Some(key) => (key.clone(), "image/png".to_string()),
+            None => {
+                return (
+                    axum::http::StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({ "error": "Asset has no thumbnail" })),
+                )
+                    .into_response()
+            }
+        }
+    } else {
+        (asset.storage_key.clone(), asset.asset_type.clone())
+    };
+
+    let asset_dir = shard.engine.asset_dir();
+    let Some(path) = memorose_core::ingest::image::resolve_local_asset(&asset_dir, &storage_key)
+    else {
+        return (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "Asset is not stored locally",
+                "storage_key": public_asset_storage_key(asset),
+            })),
+        )
+            .into_response();
+    };
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => ([(axum::http::header::CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(_) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Asset file is missing" })),
+        )
+            .into_response(),
+    }
+}
+
+async fn restore_memory_unit(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let unit_id = match parse_memory_unit_id(&id) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_shard_leader(&state, shard) {
+        return r;
+    }
+    if let Err(r) = check_unit_key_scope(&shard.engine, &key_scope, &user_id, unit_id) {
+        return r;
+    }
+    let restored = if state.is_standalone_mode() {
+        shard.engine.restore_memory_unit(&user_id, unit_id).await
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::RestoreMemoryUnit {
+                user_id: user_id.clone(),
+                unit_id,
+            },
+        )
+        .await
+    };
+    match restored {
+        Ok(true) => Json(serde_json::json!({
+            "status": "restored",
+            "memory_id": unit_id,
+        }))
+        .into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Memory is not in the trash" })),
+        )
+            .into_response(),
+        Err(error) => replication_error_response(&state, &error),
+    }
+}
+
+async fn pin_memory_unit(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> axum::response::Response {
+    set_memory_unit_pinned(&state, &key_scope, &user_id, &id, true).await
+}
+
+async fn unpin_memory_unit(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> axum::response::Response {
+    set_memory_unit_pinned(&state, &key_scope, &user_id, &id, false).await
+}
+
+async fn set_memory_unit_pinned(
+    state: &AppState,
+    key_scope: &ApiKeyScope,
+    user_id: &str,
+    id: &str,
+    pinned: bool,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(user_id, "user_id") {
+        return r;
+    }
+    let unit_id = match parse_memory_unit_id(id) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let shard = state.shard_manager.shard_for_user(user_id);
+    if let Err(r) = check_shard_leader(state, shard) {
+        return r;
+    }
+    if let Err(r) = check_unit_key_scope(&shard.engine, key_scope, user_id, unit_id) {
+        return r;
+    }
+    let updated = if state.is_standalone_mode() {
+        shard
+            .engine
+            .set_memory_unit_pinned(user_id, unit_id, pinned)
+            .await
+            .map(|unit| unit.is_some())
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::SetMemoryUnitPinned {
+                user_id: user_id.to_string(),
+                unit_id,
+                pinned,
+            },
+        )
+        .await
+    };
+    match updated {
+        Ok(true) => Json(serde_json::json!({
+            "status": if pinned { "pinned" } else { "unpinned" },
+            "memory_id": unit_id,
+        }))
+        .into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Memory not found" })),
+        )
+            .into_response(),
+        Err(error) => replication_error_response(state, &error),
+    }
+}
+
+async fn list_trashed_memory_units(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_shard_leader(&state, shard) {
+        return r;
+    }
+    match shard.engine.list_trashed_memory_units(&user_id) {
+        Ok(items) => {
+            Json(serde_json::json!({ "user_id": user_id, "items": items })).into_response()
+        }
+        Err(error) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn list_communities(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<CommunitiesQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let snapshot = match shard.engine.list_communities(&user_id, query.refresh).await {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": error.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let mut communities = Vec::with_capacity(snapshot.communities.len());
+    for community in snapshot.communities {
+        let member_ids = community
+            .member_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        // Members forgotten since the snapshot was taken are left out.
+        let members = match shard.engine.fetch_units(&user_id, member_ids).await {
+            Ok(units) => units.iter().map(RetrievalMemoryUnitView::from).collect(),
+            Err(error) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": error.to_string() })),
+                )
+                    .into_response()
+            }
+        };
+        communities.push(CommunityView {
+            community_id: community.community_id,
+            members,
+            summary_id: community.summary_id,
+            summary: community.summary,
+            keywords: community.keywords,
+        });
+    }
+
+    Json(CommunitiesResponse {
+        user_id,
+        computed_at: snapshot.computed_at,
+        modularity: snapshot.modularity,
+        communities,
+    })
+    .into_response()
+}
+
+/// Default end of a digest window: now rounded up to the next full hour.
+fn default_digest_end(now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+    let hour = now.timestamp().div_euclid(3600) + 1;
+    chrono::DateTime::from_timestamp(hour * 3600, 0).unwrap_or(now)
+}
+
+async fn get_memory_digest(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<DigestQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let to = query
+        .to
+        .unwrap_or_else(|| default_digest_end(chrono::Utc::now()));
+    let from = query.from.unwrap_or(to - chrono::Duration::days(7));
+    if from >= to {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "from must be before to" })),
+        )
+            .into_response();
+    }
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard
+        .engine
+        .memory_digest(&user_id, from, to, query.refresh)
+        .await
+    {
+        Ok((digest, cached)) => Json(DigestResponse { digest, cached }).into_response(),
+        Err(error) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn initialize_cluster(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    if state.is_standalone_mode() {
+        return Json(serde_json::json!({
+            "status": "skipped",
+            "message": "Cluster initialization is disabled in standalone mode",
+            "write_path": state.write_path_name(),
+        }));
+    }
+    let results = state
+        .shard_manager
+        .initialize_all(&state.config.load())
+        .await;
+    Json(serde_json::json!({
+        "status": "initialized",
+        "shards": results,
+    }))
+}
+
+async fn join_cluster(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<JoinRequest>,
+) -> Json<serde_json::Value> {
+    if state.is_standalone_mode() {
+        return Json(serde_json::json!({
+            "error": "join_cluster is disabled in standalone mode"
+        }));
+    }
+    let config = state.config.load();
+    let role = payload
+        .role
+        .unwrap_or_else(|| config.node_role(payload.node_id));
+    if config.is_sharded() {
+        // Multi-shard: join all raft groups
+        let results = state
+            .shard_manager
+            .join_all(payload.node_id, role, &config)
+            .await;
+        Json(serde_json::json!({
+            "status": "joined",
+            "node_id": payload.node_id,
+            "role": role,
+            "shards": results,
+        }))
+    } else {
+        // Single-shard: join the local raft group using the provided node address
+        let shard = state.shard_manager.shard(0).unwrap();
+        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+        let node_id = payload.node_id as u64;
+
+        let http_addr = if payload.http_addr.is_empty() {
+            config
+                .http_addr_for_raft_node(node_id)
+                .unwrap_or_default()
+                .to_string()
+        } else {
+            payload.http_addr.clone()
+        };
+        let node = MemoroseNode::new(payload.address.clone(), http_addr);
+
+        // Check if already a voter — idempotent on restart, but pick up new addresses
+        let metrics = raft.metrics().borrow().clone();
+        let membership = metrics.membership_config.membership();
+        if let Some(current) = membership.get_node(&node_id) {
+            if *current != node {
+                let update = std::collections::BTreeMap::from([(node_id, node.clone())]);
+                if let Err(e) = raft
+                    .change_membership(openraft::ChangeMembers::SetNodes(update), false)
+                    .await
+                {
+                    return Json(serde_json::json!({
+                        "error": format!("Update node address failed: {:?}", e)
+                    }));
+                }
+            }
+            if membership.voter_ids().any(|id| id == node_id) {
+                return Json(serde_json::json!({
+                    "status": "already_joined",
+                    "node_id": node_id,
+                    "role": "voter"
+                }));
+            }
+            if role == NodeRole::Learner {
+                return Json(serde_json::json!({
+                    "status": "already_joined",
+                    "node_id": node_id,
+                    "role": "learner"
+                }));
+            }
+        }
+
+        // Wait for leader election if needed (up to 10s)
+        let mut leader = metrics.current_leader;
+        if leader.is_none() {
+            for _ in 0..20 {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                leader = raft.metrics().borrow().current_leader;
+                if leader.is_some() {
+                    break;
+                }
+            }
+        }
+        if leader.is_none() {
+            return Json(serde_json::json!({
+                "error": "No leader elected yet, try again later"
+            }));
+        }
+
+        // Only a node about to be promoted needs to catch up before the call returns
+        match raft
+            .add_learner(node_id, node, role == NodeRole::Voter)
+            .await
+        {
+            Ok(_) => {}
+            Err(e) => {
+                return Json(
+                    serde_json::json!({ "error": format!("Add learner failed: {:?}", e) }),
+                );
+            }
+        }
+        if role == NodeRole::Learner {
+            return Json(serde_json::json!({
+                "status": "joined",
+                "node_id": node_id,
+                "role": "learner"
+            }));
+        }
+
+        tokio::task::yield_now().await;
+
+        let metrics = raft.metrics().borrow().clone();
+        let mut members: std::collections::BTreeSet<u64> =
+            metrics.membership_config.membership().voter_ids().collect();
+        members.insert(node_id);
+
+        match raft.change_membership(members, false).await {
+            Ok(_) => Json(serde_json::json!({
+                "status": "joined",
+                "node_id": node_id,
+                "role": "voter"
+            })),
+            Err(e) => Json(serde_json::json!({
+                "error": format!("Change membership failed: {:?}", e)
+            })),
+        }
+    }
+}
+
+async fn leave_cluster(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<u32>,
+) -> Json<serde_json::Value> {
+    if state.is_standalone_mode() {
+        return Json(serde_json::json!({
+            "error": "leave_cluster is disabled in standalone mode"
+        }));
+    }
+    if state.config.load().is_sharded() {
+        let results = state.shard_manager.leave_all(node_id).await;
+        Json(serde_json::json!({
+            "status": "left",
+            "node_id": node_id,
+            "shards": results,
+        }))
+    } else {
+        let shard = state.shard_manager.shard(0).unwrap();
+        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+        let node_id_u64 = node_id as u64;
+        let metrics = raft.metrics().borrow().clone();
+        let membership = metrics.membership_config.membership();
+        let change = if membership.voter_ids().any(|id| id == node_id_u64) {
+            let mut members: std::collections::BTreeSet<u64> = membership.voter_ids().collect();
+            members.remove(&node_id_u64);
+            openraft::ChangeMembers::ReplaceAllVoters(members)
+        } else if membership.learner_ids().any(|id| id == node_id_u64) {
+            openraft::ChangeMembers::RemoveNodes(std::collections::BTreeSet::from([node_id_u64]))
+        } else {
+            return Json(serde_json::json!({ "error": "Node not found in cluster" }));
+        };
+
+        match raft.change_membership(change, false).await {
+            Ok(_) => Json(serde_json::json!({
+                "status": "left",
+                "node_id": node_id
+            })),
+            Err(e) => Json(serde_json::json!({
+                "error": format!("Remove node failed: {:?}", e)
+            })),
+        }
+    }
+}
+
+async fn get_ready_tasks(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let engine = &shard.engine;
+
+    match engine.get_ready_l3_tasks(&user_id).await {
+        Ok(tasks) => axum::response::Json(tasks).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Streams approaching/overdue/recurred deadline events for one user as JSON text frames.
+/// Events are produced by the deadline worker cycle, which runs on the shard leader.
+async fn task_deadline_notifications_ws(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let mut events = state
+        .shard_manager
+        .shard_for_user(&user_id)
+        .engine
+        .subscribe_task_deadline_events();
+
+    ws.on_upgrade(move |mut socket| async move {
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) if event.user_id == user_id => {
+                        let Ok(payload) = serde_json::to_string(&event) else {
+                            continue;
+                        };
+                        if socket.send(Message::Text(payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            "Deadline notification stream for {} lagged by {} events",
+                            user_id,
+                            skipped
+                        );
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    })
+}
+
+async fn add_task_dependency(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+    Json(payload): Json<AddTaskDependencyRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let edge = match shard
+        .engine
+        .build_l3_task_dependency_edge(&user_id, payload.blocked_by, task_id)
+        .await
+    {
+        Ok(edge) => edge,
+        Err(e) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let applied = if state.is_standalone_mode() {
+        shard.engine.add_graph_edge(&edge).await.map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::UpdateGraph(edge),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => Json(serde_json::json!({ "status": "accepted" })).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Task dependency was not applied" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Task dependency error: {:?}", e);
+            replication_error_response(&state, &e)
+        }
+    }
+}
+
+/// A replicated write refused because this node does not lead the shard's Raft group.
+#[derive(Debug)]
+struct NotLeader(Box<RaftMetrics>);
+
+impl std::fmt::Display for NotLeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not Leader (current leader: {:?})",
+            self.0.current_leader
+        )
+    }
+}
+
+impl std::error::Error for NotLeader {}
+
+/// Replicate a command through Raft, returning whether the state machine applied it.
+/// Only the shard leader can write; on a follower this fails with [`NotLeader`] instead
+/// of a Raft forwarding error, so handlers can answer with [`replication_error_response`].
+async fn replicate_command(
+    shard: &shard_manager::ShardState,
+    request: memorose_core::raft::types::ClientRequest,
+) -> anyhow::Result<bool> {
+    let raft = shard.raft.as_ref().expect("cluster mode requires raft");
+    let metrics = raft.metrics().borrow().clone();
+    if metrics.current_leader != Some(metrics.id) {
+        return Err(NotLeader(Box::new(metrics)).into());
+    }
+    match raft.client_write(request).await {
+        Ok(response) => Ok(response.data.success),
+        // Leadership moved between the check and the write.
+        Err(e) if e.forward_to_leader::<MemoroseNode>().is_some() => {
+            Err(NotLeader(Box::new(raft.metrics().borrow().clone())).into())
+        }
+        Err(e) => Err(anyhow::anyhow!("Raft write error: {:?}", e)),
+    }
+}
+
+/// The response to a failed replicated write: "Not Leader", which the gateway retries on
+/// the leader, when this node does not lead the shard, and a server error otherwise.
+fn replication_error_response(state: &AppState, error: &anyhow::Error) -> axum::response::Response {
+    match error.downcast_ref::<NotLeader>() {
+        Some(NotLeader(metrics)) => not_leader_response(&state.config.load(), metrics),
+        None => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Refuse a write that must be replicated when this node does not lead `shard`, before
+/// work that cannot be undone, such as a streamed chat reply, has started.
+fn check_shard_leader(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+) -> std::result::Result<(), axum::response::Response> {
+    let Some(raft) = shard.raft.as_ref().filter(|_| state.is_cluster_mode()) else {
+        return Ok(());
+    };
+    let metrics = raft.metrics().borrow().clone();
+    if metrics.current_leader == Some(metrics.id) {
+        return Ok(());
+    }
+    Err(not_leader_response(&state.config.load(), &metrics))
+}
+
+/// Reconcile and link a committed memory unit once, here on the leader, then replicate the
+/// tombstones and edges that wrote so replicas converge without repeating the LLM calls.
+/// Failures are logged: the unit itself is already stored everywhere.
+async fn replicate_memory_unit_follow_up(shard: &shard_manager::ShardState, unit: &MemoryUnit) {
+    let follow_up = match shard.engine.follow_up_replicated_memory_unit(unit).await {
+        Ok(follow_up) => follow_up,
+        Err(e) => {
+            tracing::error!("Follow-up for memory unit {} failed: {:?}", unit.id, e);
+            return;
+        }
+    };
+    if !follow_up.tombstones.is_empty() {
+        let request = memorose_core::raft::types::ClientRequest::ForgetMemoryUnits {
+            user_id: unit.user_id.clone(),
+            tombstones: follow_up.tombstones,
+        };
+        if let Err(e) = replicate_command(shard, request).await {
+            tracing::error!(
+                "Failed to replicate tombstones for unit {}: {:?}",
+                unit.id,
+                e
+            );
+        }
+    }
+    if !follow_up.edges.is_empty() {
+        let request = memorose_core::raft::types::ClientRequest::UpdateGraphBatch(follow_up.edges);
+        if let Err(e) = replicate_command(shard, request).await {
+            tracing::error!("Failed to replicate edges for unit {}: {:?}", unit.id, e);
+        }
+    }
+}
+
+async fn create_task(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, app_id)): Path<(String, String)>,
+    Json(payload): Json<CreateTaskRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Err(r) = validate_id(&app_id, "app_id") {
+        return r;
+    }
+    if payload.title.trim().is_empty() {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "title must not be empty" })),
+        )
+            .into_response();
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let path = format!("/v1/users/{}/apps/{}/tasks", user_id, app_id);
+    if let Some(response) =
+        forward_write_if_follower(&state, shard, axum::http::Method::POST, &path, &payload).await
+    {
+        return response;
+    }
+
+    if let Some(parent_id) = payload.parent_id {
+        match shard.engine.get_l3_task(&user_id, parent_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return (
+                    axum::http::StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({ "error": "Parent task not found" })),
+                )
+                    .into_response()
+            }
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    // The app that owns the task is recorded as its agent.
+    let mut task = memorose_common::L3Task::new(
+        payload.org_id,
+        user_id,
+        Some(app_id),
+        payload.title.trim().to_string(),
+        payload.description,
+    );
+    task.parent_id = payload.parent_id;
+    task.dependencies = payload.dependencies;
+    task.context_refs = payload.context_refs;
+
+    let applied = if state.is_standalone_mode() {
+        shard.engine.create_l3_task(&task).await.map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::UpsertTask(task.clone()),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => (axum::http::StatusCode::CREATED, Json(task)).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Task creation was not applied" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Task creation error: {:?}", e);
+            replication_error_response(&state, &e)
+        }
+    }
+}
+
+async fn create_memory_unit(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, app_id)): Path<(String, String)>,
+    Json(payload): Json<CreateMemoryRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Err(r) = validate_id(&app_id, "app_id") {
+        return r;
+    }
+    if let Some(org_id) = payload.org_id.as_deref() {
+        if let Err(r) = validate_id(org_id, "org_id") {
+            return r;
+        }
+    }
+    if let Err(r) = validate_location(payload.location.as_ref()) {
+        return r;
+    }
+    let content = payload.content.trim();
+    let invalid = if content.is_empty() {
+        Some("content must not be empty")
+    } else if !matches!(payload.level, 1 | 2) {
+        Some("level must be 1 or 2")
+    } else if !(0.0..=1.0).contains(&payload.importance) {
+        Some("importance must be between 0.0 and 1.0")
+    } else {
+        None
+    };
+    if let Some(message) = invalid {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response();
+    }
+    if let Some(embedding) = payload.embedding.as_deref() {
+        if let Err(r) = validate_client_embedding(embedding, state.config.load().llm.embedding_dim)
+        {
+            return r;
+        }
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    if let Err(r) = check_user_key(&shard.engine, &user_id) {
+        return r;
+    }
+
+    // The app writing the memory is recorded as its agent.
+    let mut unit = MemoryUnit::new(
+        payload.org_id,
+        user_id.clone(),
+        Some(app_id.clone()),
+        payload.stream_id.unwrap_or_else(Uuid::new_v4),
+        payload.memory_type,
+        content.to_string(),
+        None,
+    );
+    if let Some(categories) = moderate_memory_content(&state, &shard.engine, &unit).await {
+        return moderation_blocked_response(&categories);
+    }
+
+    unit.embedding = match payload.embedding {
+        Some(embedding) => Some(embedding),
+        None => match state.llm_client.embed(&unit.content).await {
+            Ok(embedding) => Some(embedding.data),
+            Err(e) => {
+                tracing::error!("Embedding for manual memory failed: {:?}", e);
+                return (
+                    axum::http::StatusCode::BAD_GATEWAY,
+                    Json(serde_json::json!({ "error": format!("embedding failed: {}", e) })),
+                )
+                    .into_response();
+            }
+        },
+    };
+    unit.keywords = payload
+        .keywords
+        .iter()
+        .map(|keyword| keyword.trim())
+        .filter(|keyword| !keyword.is_empty())
+        .map(str::to_string)
+        .collect();
+    unit.importance = payload.importance;
+    unit.level = payload.level;
+    unit.valid_time = payload.valid_time;
+    unit.location = payload.location;
+    unit.references = payload.references;
+
+    let applied = if state.is_standalone_mode() {
+        shard
+            .engine
+            .store_memory_units(vec![unit.clone()])
+            .await
+            .map(|_| true)
+    } else {
+        let applied = replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::CreateMemoryUnit(unit.clone()),
+        )
+        .await;
+        if matches!(applied, Ok(true)) {
+            replicate_memory_unit_follow_up(shard, &unit).await;
+        }
+        applied
+    };
+    match applied {
+        Ok(true) => (
+            axum::http::StatusCode::CREATED,
+            Json(serde_json::json!({
+                "status": "created",
+                "memory_id": unit.id,
+                "level": unit.level,
+            })),
+        )
+            .into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Memory creation was not applied" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Memory creation error: {:?}", e);
+            replication_error_response(&state, &e)
+        }
+    }
+}
+
+async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.get_l3_task_progress(&user_id, task_id).await {
+        Ok(Some(progress)) => Json(progress).into_response(),
+        Ok(None) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Task not found" })),
+        )
+            .into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Returns the goal's stored milestones, or a dry-run decomposition if it has none yet.
+async fn get_goal_plan(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, goal_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.get_goal_plan(&user_id, goal_id).await {
+        Ok(Some(plan)) => Json(plan).into_response(),
+        Ok(None) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Goal not found" })),
+        )
+            .into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Releases milestones parked by the auto-planner's approval mode.
+async fn approve_goal_plan(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, goal_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let updates = match shard
+        .engine
+        .goal_plan_approval_updates(&user_id, goal_id)
+        .await
+    {
+        Ok(updates) if updates.is_empty() => {
+            return (
+                axum::http::StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "No milestones awaiting approval" })),
+            )
+                .into_response()
+        }
+        Ok(updates) => updates,
+        Err(e) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let mut approved = Vec::with_capacity(updates.len());
+    for update in updates {
+        let task_id = update.task_id;
+        let applied = if state.is_standalone_mode() {
+            shard
+                .engine
+                .apply_l3_task_status_update(&update)
+                .await
+                .map(|task| task.is_some())
+        } else {
+            replicate_command(
+                shard,
+                memorose_core::raft::types::ClientRequest::UpdateTaskStatus(update),
+            )
+            .await
+        };
+        match applied {
+            Ok(true) => approved.push(task_id),
+            Ok(false) => {}
+            Err(e) if e.is::<NotLeader>() => return replication_error_response(&state, &e),
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string(), "approved": approved })),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    Json(serde_json::json!({ "goal_id": goal_id, "approved": approved })).into_response()
+}
+
+async fn update_task_status(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+    method: axum::http::Method,
+    axum::Json(req): axum::Json<UpdateTaskStatusRequest>,
+) -> axum::response::Response {
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let engine = &shard.engine;
+    let path = format!("/v1/users/{}/tasks/{}/status", user_id, task_id);
+    if let Some(response) = forward_write_if_follower(&state, shard, method, &path, &req).await {
+        return response;
+    }
+
+    match engine.get_l3_task(&user_id, task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return (axum::http::StatusCode::NOT_FOUND, "Task not found").into_response(),
+        Err(e) => {
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+
+    let update = memorose_common::L3TaskStatusUpdate {
+        user_id: user_id.clone(),
+        task_id,
+        status: req.status,
+        progress: req.progress,
+        result_summary: req.result_summary,
+        updated_at: chrono::Utc::now(),
+    };
+    let applied = if state.is_standalone_mode() {
+        engine
+            .apply_l3_task_status_update(&update)
+            .await
+            .map(|task| task.is_some())
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::UpdateTaskStatus(update),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => {}
+        Ok(false) => return (axum::http::StatusCode::NOT_FOUND, "Task not found").into_response(),
+        Err(e) if e.is::<NotLeader>() => return replication_error_response(&state, &e),
+        Err(e) => {
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+
+    match engine.get_l3_task(&user_id, task_id).await {
+        Ok(Some(task)) => {
+            // Downward Sedimentation: If completed, log it to L0
+            if task.status == memorose_common::TaskStatus::Completed {
+                let event_content = format!(
+                    "Agent completed milestone '{}'. Summary: {}",
+                    task.title,
+                    task.result_summary.as_deref().unwrap_or("None")
+                );
+
+                // Assuming stream_id can be derived or ignored for pure system tasks,
+                // generating a dummy one here, or it should be passed via task model.
+                let event = memorose_common::Event::new(
+                    task.org_id.clone(),
+                    task.user_id.clone(),
+                    task.agent_id.clone(),
+                    Uuid::new_v4(), // Need stream context actually
+                    memorose_common::EventContent::Text(event_content),
+                );
+                if let Err(e) = engine.ingest_event(event).await {
+                    tracing::warn!("Failed to sediment L3 task completion to L0: {}", e);
+                }
+            }
+
+            axum::response::Json(task).into_response()
+        }
+        Ok(None) => (axum::http::StatusCode::NOT_FOUND, "Task not found").into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn create_share_grant(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<CreateShareGrantRequest>,
+) -> axum::response::Response {
+    use memorose_common::{ShareGrantee, ShareResource};
+
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let grantee_check = match &payload.grantee {
+        ShareGrantee::User(grantee) if grantee == &user_id => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "Cannot share memory with yourself" })),
+            )
+                .into_response()
+        }
+        ShareGrantee::User(grantee) => validate_id(grantee, "grantee.id"),
+        ShareGrantee::Group(group_id) => validate_id(group_id, "grantee.id"),
+    };
+    if let Err(r) = grantee_check {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+
+    if let ShareResource::MemoryUnit(unit_id) = payload.resource {
+        match shard.engine.get_memory_unit(&user_id, unit_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return (
+                    axum::http::StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({ "error": "Memory unit not found" })),
+                )
+                    .into_response()
+            }
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    let grant = memorose_common::MemoryShareGrant::new(user_id, payload.resource, payload.grantee);
+    let applied = if state.is_standalone_mode() {
+        shard.engine.put_share_grant(&grant).map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::PutShareGrant(grant.clone()),
+        )
+        .await
+    };
+    match applied {
+        Ok(true) => (axum::http::StatusCode::CREATED, Json(grant)).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Share grant was not applied" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
+    }
+}
+
+async fn list_share_grants(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.list_share_grants(&user_id) {
+        Ok(grants) => Json(serde_json::json!({ "grants": grants })).into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Grants live with the owner's memory, so every shard is asked.
+async fn list_received_share_grants(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let mut grants = Vec::new();
+    for (_, shard) in state.shard_manager.all_shards() {
+        match shard.engine.list_grants_visible_to(&user_id) {
+            Ok(found) => grants.extend(found),
+            Err(e) => {
+                return (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
+            }
+        }
+    }
+    grants.sort_by_key(|grant| grant.created_at);
+    Json(serde_json::json!({ "grants": grants })).into_response()
+}
+
+async fn revoke_share_grant(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, grant_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let revoked = if state.is_standalone_mode() {
+        shard.engine.revoke_share_grant(&user_id, grant_id)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::RevokeShareGrant {
+                owner_user_id: user_id,
+                grant_id,
+            },
+        )
+        .await
+    };
+    match revoked {
+        Ok(true) => axum::http::StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Share grant not found" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
+    }
+}
+
+async fn add_group_member(
+    State(state): State<Arc<AppState>>,
+    Path((group_id, user_id)): Path<(String, String)>,
+) -> axum::response::Response {
+    update_group_membership(&state, group_id, user_id, true).await
+}
+
+async fn remove_group_member(
+    State(state): State<Arc<AppState>>,
+    Path((group_id, user_id)): Path<(String, String)>,
+) -> axum::response::Response {
+    update_group_membership(&state, group_id, user_id, false).await
+}
+
+/// Group membership is written to every shard: grants to a group are resolved on
+/// the owner's shard, which may not be the member's.
+async fn update_group_membership(
+    state: &AppState,
+    group_id: String,
+    user_id: String,
+    member: bool,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&group_id, "group_id") {
+        return r;
+    }
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let update = memorose_common::GroupMembershipUpdate {
+        group_id,
+        user_id,
+        member,
+    };
+    // Refuse before writing any shard, rather than leave the update on some of them.
+    for (_, shard) in state.shard_manager.all_shards() {
+        if let Err(r) = check_shard_leader(state, shard) {
+            return r;
+        }
+    }
+    for (_, shard) in state.shard_manager.all_shards() {
+        let applied = if state.is_standalone_mode() {
+            shard
+                .engine
+                .apply_group_membership_update(&update)
+                .map(|_| true)
+        } else {
+            replicate_command(
+                shard,
+                memorose_core::raft::types::ClientRequest::UpdateGroupMembership(update.clone()),
+            )
+            .await
+        };
+        let error = match applied {
+            Ok(true) => continue,
+            Ok(false) => "Group membership update was not applied".to_string(),
+            Err(e) => return replication_error_response(&state, &e),
+        };
+        return (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response();
+    }
+    Json(update).into_response()
+}
+
+async fn list_group_members(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&group_id, "group_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&group_id);
+    match shard.engine.list_group_members(&group_id) {
+        Ok(members) => {
+            Json(serde_json::json!({ "group_id": group_id, "members": members })).into_response()
+        }
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Store a stream record, through Raft in cluster mode.
+async fn put_memory_stream(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    stream: &memorose_common::MemoryStream,
+) -> anyhow::Result<bool> {
+    if state.is_standalone_mode() {
+        shard.engine.put_memory_stream(stream).map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::PutMemoryStream(stream.clone()),
+        )
+        .await
+    }
+}
+
+/// Load a stream and check that the API key may see the app that owns it.
+fn load_memory_stream(
+    shard: &shard_manager::ShardState,
+    key_scope: &ApiKeyScope,
+    user_id: &str,
+    stream_id: Uuid,
+) -> Result<memorose_common::MemoryStream, axum::response::Response> {
+    let stream = match shard.engine.get_memory_stream(user_id, stream_id) {
+        Ok(Some(stream)) => stream,
+        Ok(None) => {
+            return Err((
+                axum::http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Stream not found" })),
+            )
+                .into_response())
+        }
+        Err(e) => {
+            return Err((
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response())
+        }
+    };
+    let apps: Option<Vec<&str>> = stream.app_id.as_deref().map(|app_id| vec![app_id]);
+    check_key_scope(key_scope, user_id, apps.as_deref())?;
+    Ok(stream)
+}
+
+async fn create_memory_stream(
+    State(state): State<Arc<AppState>>,
+    Path((user_id, app_id)): Path<(String, String)>,
+    Json(payload): Json<CreateStreamRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Err(r) = validate_id(&app_id, "app_id") {
+        return r;
+    }
+    let title = payload
+        .title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+    let mut stream = memorose_common::MemoryStream::new(user_id.clone(), Some(app_id), title);
+    stream.metadata = payload.metadata;
+
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match put_memory_stream(&state, shard, &stream).await {
+        Ok(true) => (axum::http::StatusCode::CREATED, Json(stream)).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Stream creation was not applied" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
+    }
+}
+
+async fn list_memory_streams(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path(user_id): Path<String>,
+    Query(query): Query<ListStreamsQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Some(app_id) = query.app_id.as_deref() {
+        if let Err(r) = check_key_scope(&key_scope, &user_id, Some(&[app_id])) {
+            return r;
+        }
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.list_memory_streams(
+        &user_id,
+        query.app_id.as_deref(),
+        query.include_archived,
+    ) {
+        Ok(mut streams) => {
+            // An app-bound key only sees the streams of its apps.
+            if key_scope.is_app_restricted() {
+                streams.retain(|stream| {
+                    stream
+                        .app_id
+                        .as_deref()
+                        .is_some_and(|app_id| key_scope.allows_app(app_id))
+                });
+            }
+            Json(serde_json::json!({
+                "streams": streams,
+                "total_count": streams.len(),
+            }))
+            .into_response()
+        }
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn get_memory_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, stream_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match load_memory_stream(shard, &key_scope, &user_id, stream_id) {
+        Ok(stream) => Json(stream).into_response(),
+        Err(r) => r,
+    }
+}
+
+async fn archive_memory_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(key_scope): Extension<ApiKeyScope>,
+    Path((user_id, stream_id)): Path<(String, Uuid)>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let mut stream = match load_memory_stream(shard, &key_scope, &user_id, stream_id) {
+        Ok(stream) => stream,
+        Err(r) => return r,
+    };
+    if stream.is_archived() {
+        return Json(stream).into_response();
+    }
+    let now = chrono::Utc::now();
+    stream.archived_at = Some(now);
+    stream.updated_at = now;
+
+    match put_memory_stream(&state, shard, &stream).await {
+        Ok(true) => Json(stream).into_response(),
+        Ok(false) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Stream archive was not applied" })),
+        )
+            .into_response(),
+        Err(e) => replication_error_response(&state, &e),
+    }
+}
+
+/// Items this node's content moderation blocked, tagged or stripped for the user.
+async fn list_moderation_audit(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<ModerationAuditQuery>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard
+        .engine
+        .list_moderation_audit(&user_id, query.limit.clamp(1, 500))
+    {
+        Ok(records) => Json(serde_json::json!({
+            "records": records,
+            "total_count": records.len(),
+        }))
+        .into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn get_user_key(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.get_user_key(&user_id) {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => (
+            axum::http::StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "User has no encryption key" })),
+        )
+            .into_response(),
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Content written for the user from now on is encrypted with a data key wrapped by
+/// `key_ref`; earlier content stays as it was written.
+async fn register_user_key(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<RegisterUserKeyRequest>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    match shard.engine.get_user_key(&user_id) {
+        Ok(Some(record)) if !record.is_revoked() => {
+            return (
+                axum::http::StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "User already has an active encryption key" })),
+            )
+                .into_response()
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+    let record = match shard
+        .engine
+        .create_user_key(&user_id, payload.key_ref.trim())
+        .await
+    {
+        Ok(record) => record,
+        Err(e) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    put_user_key(&state, shard, record).await
+}
+
+/// Crypto-shred the user's encrypted content: the wrapped data key is dropped from the
+/// registry on every replica, after which that content can no longer be read.
+async fn revoke_user_key(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let mut record = match shard.engine.get_user_key(&user_id) {
+        Ok(Some(record)) if !record.is_revoked() => record,
+        Ok(_) => {
+            return (
+                axum::http::StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "User has no active encryption key" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    record.wrapped_key = None;
+    record.revoked_at = Some(chrono::Utc::now());
+    put_user_key(&state, shard, record).await
+}
+
+async fn put_user_key(
+    state: &AppState,
+    shard: &shard_manager::ShardState,
+    record: memorose_common::UserKeyRecord,
+) -> axum::response::Response {
+    let applied = if state.is_standalone_mode() {
+        shard.engine.put_user_key(&record).await.map(|_| true)
+    } else {
+        replicate_command(
+            shard,
+            memorose_core::raft::types::ClientRequest::PutUserKey(record.clone()),
+        )
+        .await
+    };
+    let error = match applied {
+        Ok(true) => return Json(record).into_response(),
+        Ok(false) => "Encryption key update was not applied".to_string(),
+        Err(e) => return replication_error_response(&state, &e),
+    };
+    (
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({ "error": error })),
+    )
+        .into_response()
+}
+
+/// Org policy is written to every shard, since an organization's users are spread
+/// across all of them.
+async fn get_org_policy(
+    State(state): State<Arc<AppState>>,
+    Path(org_id): Path<String>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&org_id, "org_id") {
+        return r;
+    }
+    let shard = state.shard_manager.shard_for_user(&org_id);
+    match shard.engine.get_org_policy(&org_id) {
+        Ok(policy) => {
+            Json(serde_json::json!({ "org_id": org_id, "policy": policy })).into_response()
+        }
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn set_org_policy(
+    State(state): State<Arc<AppState>>,
+    Path(org_id): Path<String>,
+    Json(policy): Json<memorose_common::OrgPolicy>,
+) -> axum::response::Response {
+    if let Err(r) = validate_id(&org_id, "org_id") {
+        return r;
+    }
+    if policy.retention_days == Some(0) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "retention_days must be at least 1" })),
+        )
+            .into_response();
+    }
+    let update = memorose_common::OrgPolicyUpdate { org_id, policy };
+    // Refuse before writing any shard, rather than leave the policy on some of them.
+    for (_, shard) in state.shard_manager.all_shards() {
+        if let Err(r) = check_shard_leader(&state, shard) {
+            return r;
+        }
+    }
+    for (_, shard) in state.shard_manager.all_shards() {
+        let applied = if state.is_standalone_mode() {
+            shard
+                .engine
+                .set_org_policy(&update.org_id, &update.policy)
+                .map(|_| true)
+        } else {
+            replicate_command(
+                shard,
+                memorose_core::raft::types::ClientRequest::SetOrgPolicy(update.clone()),
             )
-                .into_response()
-        }
+            .await
+        };
+        let error = match applied {
+            Ok(true) => continue,
+            Ok(false) => "Org policy update was not applied".to_string(),
+            Err(e) => return replication_error_response(&state, &e),
+        };
+        return (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response();
     }
+    Json(update).into_response()
 }
 
-async fn hard_delete_memory_unit_for_user(
-    engine: &MemoroseEngine,
-    user_id: &str,
-    unit_id: Uuid,
-) -> anyhow::Result<bool> {
-    if engine
-        .get_memory_unit_including_forgotten(user_id, unit_id)?
-        .is_none()
-    {
-        return Ok(false);
+/// Apply writes shipped from a cluster in another region. Each write goes to the shard
+/// that owns it here; writes older than what this cluster holds are dropped while applying.
+async fn apply_replication_batch(
+    State(state): State<Arc<AppState>>,
+    Json(batch): Json<memorose_core::replication::ReplicationBatch>,
+) -> axum::response::Response {
+    use memorose_core::replication::{route_requests, ReplicationTarget};
+
+    let shard_ids: Vec<u32> = state.shard_manager.all_shards().map(|(id, _)| id).collect();
+    let received = batch.requests.len();
+    let mut per_shard: std::collections::BTreeMap<u32, Vec<_>> = Default::default();
+    for (target, request) in route_requests(batch.requests) {
+        match target {
+            ReplicationTarget::User(user_id) => per_shard
+                .entry(state.shard_manager.shard_id_for_user(&user_id))
+                .or_default()
+                .push(request),
+            ReplicationTarget::AllShards => {
+                for shard_id in &shard_ids {
+                    per_shard
+                        .entry(*shard_id)
+                        .or_default()
+                        .push(request.clone());
+                }
+            }
+        }
     }
 
-    engine.delete_memory_unit_hard(user_id, unit_id).await?;
-    Ok(true)
+    for (shard_id, requests) in per_shard {
+        let Some(shard) = state.shard_manager.shard(shard_id) else {
+            continue;
+        };
+        let request = memorose_core::raft::types::ClientRequest::ApplyReplicated {
+            source_region: batch.source_region.clone(),
+            requests,
+        };
+        let applied = if state.is_standalone_mode() {
+            Ok(memorose_core::raft::storage::apply_client_request(&shard.engine, &request).await)
+        } else {
+            replicate_command(shard, request).await
+        };
+        let error = match applied {
+            Ok(true) => continue,
+            Ok(false) => format!(
+                "Replicated writes were not all applied on shard {}",
+                shard_id
+            ),
+            Err(e) => return replication_error_response(&state, &e),
+        };
+        return (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response();
+    }
+
+    Json(serde_json::json!({
+        "source_region": batch.source_region,
+        "source_shard_id": batch.shard_id,
+        "last_log_index": batch.last_log_index,
+        "received": received,
+    }))
+    .into_response()
 }
 
-async fn delete_memory_unit_hard(
+/// Events ingested and users seen under an organization, summed over all shards.
+async fn org_event_usage(state: &AppState, org_id: &str) -> anyhow::Result<(usize, usize)> {
+    let mut events = 0;
+    let mut users = 0;
+    for (_, shard) in state.shard_manager.all_shards() {
+        events += shard.engine.count_org_events(org_id)?;
+        users += shard.engine.list_org_users(org_id)?.len();
+    }
+    Ok((events, users))
+}
+
+async fn get_org_usage(
     State(state): State<Arc<AppState>>,
-    Path((user_id, id)): Path<(String, String)>,
+    Path(org_id): Path<String>,
 ) -> axum::response::Response {
-    if let Err(r) = validate_id(&user_id, "user_id") {
+    if let Err(r) = validate_id(&org_id, "org_id") {
         return r;
     }
-
-    let unit_id = match Uuid::parse_str(&id) {
-        Ok(id) => id,
-        Err(_) => {
+    let policy = match state
+        .shard_manager
+        .shard_for_user(&org_id)
+        .engine
+        .get_org_policy(&org_id)
+    {
+        Ok(policy) => policy,
+        Err(e) => {
             return (
-                axum::http::StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid memory ID format" })),
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
             )
-                .into_response();
+                .into_response()
         }
     };
-
-    let shard = state.shard_manager.shard_for_user(&user_id);
-    match hard_delete_memory_unit_for_user(&shard.engine, &user_id, unit_id).await {
-        Ok(true) => Json(serde_json::json!({
-            "status": "deleted",
-            "memory_id": unit_id,
-            "mode": "hard"
+    match org_event_usage(&state, &org_id).await {
+        Ok((events, users)) => Json(serde_json::json!({
+            "org_id": org_id,
+            "events": events,
+            "users": users,
+            "max_events": policy.max_events,
+            "retention_days": policy.retention_days,
         }))
         .into_response(),
-        Ok(false) => (
-            axum::http::StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Memory not found" })),
-        )
-            .into_response(),
-        Err(error) => (
+        Err(e) => (
             axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": error.to_string() })),
+            Json(serde_json::json!({ "error": e.to_string() })),
         )
             .into_response(),
     }
 }
 
-async fn initialize_cluster(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    if state.is_standalone_mode() {
-        return Json(serde_json::json!({
-            "status": "skipped",
-            "message": "Cluster initialization is disabled in standalone mode",
-            "write_path": state.write_path_name(),
-        }));
-    }
-    let results = state.shard_manager.initialize_all(&state.config).await;
-    Json(serde_json::json!({
-        "status": "initialized",
-        "shards": results,
-    }))
-}
-
-async fn join_cluster(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<JoinRequest>,
-) -> Json<serde_json::Value> {
-    if state.is_standalone_mode() {
-        return Json(serde_json::json!({
-            "error": "join_cluster is disabled in standalone mode"
-        }));
-    }
-    if state.config.is_sharded() {
-        // Multi-shard: join all raft groups
-        let results = state
-            .shard_manager
-            .join_all(payload.node_id, &state.config)
-            .await;
-        Json(serde_json::json!({
-            "status": "joined",
-            "node_id": payload.node_id,
-            "shards": results,
-        }))
-    } else {
-        // Single-shard: join the local raft group using the provided node address
-        let shard = state.shard_manager.shard(0).unwrap();
-        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
-        let node_id = payload.node_id as u64;
-
-        // Check if already a voter — idempotent on restart
-        let metrics = raft.metrics().borrow().clone();
-        let existing_voters: std::collections::BTreeSet<u64> =
-            metrics.membership_config.membership().voter_ids().collect();
-        if existing_voters.contains(&node_id) {
-            return Json(serde_json::json!({
-                "status": "already_joined",
-                "node_id": node_id,
-                "role": "voter"
-            }));
-        }
-
-        // Wait for leader election if needed (up to 10s)
-        let mut leader = metrics.current_leader;
-        if leader.is_none() {
-            for _ in 0..20 {
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                leader = raft.metrics().borrow().current_leader;
-                if leader.is_some() {
-                    break;
-                }
-            }
+/// Reject ingestion that would take an organization past its event quota. Engines
+/// only see their own shard's events, so the quota is checked against the cluster-wide total.
+async fn check_org_event_quota(
+    state: &AppState,
+    org_id: Option<&str>,
+    incoming: usize,
+) -> std::result::Result<(), axum::response::Response> {
+    let Some(org_id) = org_id else {
+        return Ok(());
+    };
+    let shard = state.shard_manager.shard_for_user(org_id);
+    let max_events = match shard.engine.get_org_policy(org_id) {
+        Ok(policy) => policy.max_events,
+        Err(e) => {
+            tracing::warn!("Failed to load org policy for {}: {:?}", org_id, e);
+            None
         }
-        if leader.is_none() {
-            return Json(serde_json::json!({
-                "error": "No leader elected yet, try again later"
-            }));
+    };
+    let Some(max_events) = max_events else {
+        return Ok(());
+    };
+    let used = match org_event_usage(state, org_id).await {
+        Ok((events, _)) => events as u64,
+        Err(e) => {
+            tracing::warn!("Failed to count events for org {}: {:?}", org_id, e);
+            return Ok(());
         }
+    };
+    if used + incoming as u64 <= max_events {
+        return Ok(());
+    }
+    Err((
+        axum::http::StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({
+            "status": "error",
+            "message": format!(
+                "organization {} exceeded its event quota ({} of {} used)",
+                org_id, used, max_events
+            ),
+        })),
+    )
+        .into_response())
+}
 
-        let node = openraft::BasicNode {
-            addr: payload.address.clone(),
-        };
-
-        match raft.add_learner(node_id, node, true).await {
-            Ok(_) => {}
-            Err(e) => {
-                return Json(
-                    serde_json::json!({ "error": format!("Add learner failed: {:?}", e) }),
-                );
-            }
+/// Push back on ingestion while the shard's pending queue is deep: 429 past the throttle
+/// threshold, 503 past the reject threshold, both with `Retry-After`.
+/// Refuse reads and writes for a user whose encryption key was revoked: their content has
+/// been crypto-shredded, so answering with partial or empty results would mislead.
+fn check_user_key(
+    engine: &MemoroseEngine,
+    user_id: &str,
+) -> std::result::Result<(), axum::response::Response> {
+    match engine.get_user_key(user_id) {
+        Ok(Some(record)) if record.is_revoked() => Err(user_key_revoked_response(user_id)),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::warn!("Failed to load encryption key of user {}: {:?}", user_id, e);
+            Ok(())
         }
+    }
+}
 
-        tokio::task::yield_now().await;
-
-        let metrics = raft.metrics().borrow().clone();
-        let mut members: std::collections::BTreeSet<u64> =
-            metrics.membership_config.membership().voter_ids().collect();
-        members.insert(node_id);
+fn user_key_revoked_response(user_id: &str) -> axum::response::Response {
+    (
+        axum::http::StatusCode::GONE,
+        Json(serde_json::json!({
+            "status": "error",
+            "message": format!("the encryption key of user {} has been revoked", user_id),
+        })),
+    )
+        .into_response()
+}
 
-        match raft.change_membership(members, false).await {
-            Ok(_) => Json(serde_json::json!({
-                "status": "joined",
-                "node_id": node_id,
-                "role": "voter"
-            })),
-            Err(e) => Json(serde_json::json!({
-                "error": format!("Change membership failed: {:?}", e)
-            })),
+/// `/v1/` routes taking a body that only read, or that an operator needs to recover a full
+/// node. The chat routes check disk space themselves when they record the exchange.
+const DISK_GUARD_EXEMPT_ROUTES: &[&str] = &[
+    "/v1/users/:user_id/streams/:stream_id/retrieve",
+    "/v1/users/:user_id/ask",
+    "/v1/memory/context",
+    "/v1/dashboard/search",
+    "/v1/dashboard/chat",
+    "/v1/users/:user_id/apps/:app_id/chat",
+    "/v1/users/:user_id/memories/semantic/preview",
+    "/v1/users/:user_id/graph/query/explain",
+    "/v1/cluster/initialize",
+    "/v1/cluster/join",
+    "/v1/cluster/nodes/:node_id",
+    "/v1/admin/config/reload",
+    "/v1/admin/index/commit",
+    "/v1/admin/prompts/validate",
+];
+
+/// Refuse every mutating `/v1/` request while the disk watchdog holds the node read-only.
+async fn disk_space_guard(
+    State(state): State<Arc<AppState>>,
+    matched_path: Option<MatchedPath>,
+    req: axum::extract::Request,
+    next: axum_middleware::Next,
+) -> axum::response::Response {
+    let mutating = !matches!(
+        *req.method(),
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    );
+    let exempt = matched_path
+        .as_ref()
+        .is_some_and(|path| DISK_GUARD_EXEMPT_ROUTES.contains(&path.as_str()));
+    if mutating && !exempt {
+        if let Err(r) = check_disk_space(&state) {
+            return r;
         }
     }
+    next.run(req).await
 }
 
-async fn leave_cluster(
-    State(state): State<Arc<AppState>>,
-    Path(node_id): Path<u32>,
-) -> Json<serde_json::Value> {
-    if state.is_standalone_mode() {
-        return Json(serde_json::json!({
-            "error": "leave_cluster is disabled in standalone mode"
-        }));
+/// Reject a write while the disk watchdog holds the node read-only, before a full volume
+/// fails writes halfway through applying them.
+fn check_disk_space(state: &AppState) -> std::result::Result<(), axum::response::Response> {
+    if !state.disk_watchdog.is_read_only() {
+        return Ok(());
     }
-    if state.config.is_sharded() {
-        let results = state.shard_manager.leave_all(node_id).await;
+    let free_percent = state
+        .disk_watchdog
+        .status()
+        .map(|status| status.free_percent);
+    Err((
+        axum::http::StatusCode::INSUFFICIENT_STORAGE,
         Json(serde_json::json!({
-            "status": "left",
-            "node_id": node_id,
-            "shards": results,
-        }))
-    } else {
-        let shard = state.shard_manager.shard(0).unwrap();
-        let raft = shard.raft.as_ref().expect("cluster mode requires raft");
-        let metrics = raft.metrics().borrow().clone();
-        let mut members: std::collections::BTreeSet<u64> =
-            metrics.membership_config.membership().voter_ids().collect();
-
-        if !members.remove(&(node_id as u64)) {
-            return Json(serde_json::json!({ "error": "Node not found in cluster" }));
-        }
+            "status": "error",
+            "message": "the data volume is nearly full; this node is read-only until space is freed",
+            "free_percent": free_percent,
+        })),
+    )
+        .into_response())
+}
 
-        match raft.change_membership(members, false).await {
-            Ok(_) => Json(serde_json::json!({
-                "status": "left",
-                "node_id": node_id
-            })),
-            Err(e) => Json(serde_json::json!({
-                "error": format!("Remove node failed: {:?}", e)
-            })),
+async fn check_ingest_admission(
+    engine: &MemoroseEngine,
+) -> std::result::Result<(), axum::response::Response> {
+    let (status, retry_after_secs, message) = match engine.check_ingest_admission().await {
+        Ok(IngestAdmission::Accept) => return Ok(()),
+        Ok(IngestAdmission::Throttle { retry_after_secs }) => (
+            axum::http::StatusCode::TOO_MANY_REQUESTS,
+            retry_after_secs,
+            "pending queue is deep; retry later",
+        ),
+        Ok(IngestAdmission::Reject { retry_after_secs }) => (
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            retry_after_secs,
+            "pending queue is over capacity; ingest is temporarily rejected",
+        ),
+        Err(e) => {
+            tracing::warn!("Failed to sample pending queue for admission: {:?}", e);
+            return Ok(());
         }
-    }
+    };
+    Err((
+        status,
+        [(
+            axum::http::header::RETRY_AFTER,
+            retry_after_secs.to_string(),
+        )],
+        Json(serde_json::json!({
+            "status": "error",
+            "message": message,
+            "retry_after_secs": retry_after_secs,
+        })),
+    )
+        .into_response())
 }
 
-async fn get_ready_tasks(
+async fn get_user_profile(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
 ) -> axum::response::Response {
-    let shard = state.shard_manager.shard_for_user(&user_id);
-    let engine = &shard.engine;
-
-    match engine.get_ready_l3_tasks(&user_id).await {
-        Ok(tasks) => axum::response::Json(tasks).into_response(),
-        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
     }
+    let shard = state.shard_manager.shard_for_user(&user_id);
+    let profile = match shard.engine.get_structured_profile(&user_id) {
+        Ok(profile) => {
+            profile.unwrap_or_else(|| memorose_common::UserProfile::new(user_id.clone()))
+        }
+        Err(e) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    let summary = match shard.engine.get_user_profile(&user_id).await {
+        Ok(unit) => unit.map(|unit| unit.content),
+        Err(e) => {
+            tracing::warn!("Failed to load L3 profile for {}: {:?}", user_id, e);
+            None
+        }
+    };
+    Json(UserProfileResponse { profile, summary }).into_response()
 }
 
-async fn update_task_status(
+async fn patch_user_profile(
     State(state): State<Arc<AppState>>,
-    Path((user_id, task_id)): Path<(String, Uuid)>,
-    axum::Json(req): axum::Json<UpdateTaskStatusRequest>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<PatchUserProfileRequest>,
 ) -> axum::response::Response {
+    if let Err(r) = validate_id(&user_id, "user_id") {
+        return r;
+    }
+    if let Some(key) = payload
+        .attributes
+        .keys()
+        .find(|key| memorose_common::UserProfile::normalize_key(key).is_none())
+    {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("invalid profile key: {:?}", key) })),
+        )
+            .into_response();
+    }
     let shard = state.shard_manager.shard_for_user(&user_id);
-    let engine = &shard.engine;
-
-    match engine.get_l3_task(&user_id, task_id).await {
-        Ok(Some(mut task)) => {
-            task.status = req.status.clone();
-            if let Some(p) = req.progress {
-                task.progress = p;
-            }
-            if let Some(summary) = req.result_summary {
-                task.result_summary = Some(summary);
-            }
-            task.updated_at = chrono::Utc::now();
-
-            if let Err(e) = engine.store_l3_task(&task).await {
-                return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-                    .into_response();
-            }
-
-            // Downward Sedimentation: If completed, log it to L0
-            if task.status == memorose_common::TaskStatus::Completed {
-                let event_content = format!(
-                    "Agent completed milestone '{}'. Summary: {}",
-                    task.title,
-                    task.result_summary.as_deref().unwrap_or("None")
-                );
-
-                // Assuming stream_id can be derived or ignored for pure system tasks,
-                // generating a dummy one here, or it should be passed via task model.
-                let event = memorose_common::Event::new(
-                    task.org_id.clone(),
-                    task.user_id.clone(),
-                    task.agent_id.clone(),
-                    Uuid::new_v4(), // Need stream context actually
-                    memorose_common::EventContent::Text(event_content),
-                );
-                if let Err(e) = engine.ingest_event(event).await {
-                    tracing::warn!("Failed to sediment L3 task completion to L0: {}", e);
-                }
-            }
+    if state.is_standalone_mode() {
+        return match shard
+            .engine
+            .patch_structured_profile(&user_id, payload.attributes)
+        {
+            Ok(profile) => Json(profile).into_response(),
+            Err(e) => (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response(),
+        };
+    }
 
-            axum::response::Json(task).into_response()
+    let request = memorose_core::raft::types::ClientRequest::PatchStructuredProfile {
+        user_id: user_id.clone(),
+        attributes: payload.attributes,
+        updated_at: chrono::Utc::now(),
+    };
+    match replicate_command(shard, request).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to apply profile patch" })),
+            )
+                .into_response()
         }
-        Ok(None) => (axum::http::StatusCode::NOT_FOUND, "Task not found").into_response(),
-        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => return replication_error_response(&state, &e),
+    }
+    match shard.engine.get_structured_profile(&user_id) {
+        Ok(profile) => {
+            Json(profile.unwrap_or_else(|| memorose_common::UserProfile::new(user_id.clone())))
+                .into_response()
+        }
+        Err(e) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
     }
 }
 
@@ -1966,36 +6076,13 @@ async fn get_all_task_trees(
     Path(user_id): Path<String>,
 ) -> axum::response::Response {
     let shard = state.shard_manager.shard_for_user(&user_id);
-    let kv = shard.engine.kv();
-    let prefix = format!("u:{}:unit:", user_id);
-    let pairs = match kv.scan(prefix.as_bytes()) {
-        Ok(p) => p,
-        Err(e) => {
-            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-        }
-    };
-
-    let all_units: Vec<MemoryUnit> = pairs
-        .into_iter()
-        .filter_map(|(_, v)| serde_json::from_slice::<MemoryUnit>(&v).ok())
-        .collect();
-
-    let all_tasks = match shard.engine.list_l3_tasks(&user_id).await {
-        Ok(t) => t,
+    let mut root_nodes = match build_goal_trees(&shard.engine, &user_id, None).await {
+        Ok(trees) => trees,
         Err(e) => {
             return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
         }
     };
 
-    let mut root_nodes = Vec::new();
-    for unit in all_units.into_iter().filter(|u| u.level == 3) {
-        let tasks = build_l3_task_tree(unit.id, &all_tasks, &shard.engine, &user_id, 0).await;
-        root_nodes.push(GoalTree {
-            goal: GoalMemoryUnitView::from(&unit),
-            tasks,
-        });
-    }
-
     // Sort by transaction time descending
     root_nodes.sort_by(|a, b| b.goal.transaction_time.cmp(&a.goal.transaction_time));
 
@@ -2007,74 +6094,53 @@ async fn get_task_tree(
     Path((user_id, stream_id)): Path<(String, Uuid)>,
 ) -> axum::response::Response {
     let shard = state.shard_manager.shard_for_user(&user_id);
-    let kv = shard.engine.kv();
-    let prefix = format!("u:{}:unit:", user_id);
-    let pairs = match kv.scan(prefix.as_bytes()) {
-        Ok(p) => p,
-        Err(e) => {
-            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-        }
-    };
-
-    let all_units: Vec<MemoryUnit> = pairs
-        .into_iter()
-        .filter_map(|(_, v)| serde_json::from_slice::<MemoryUnit>(&v).ok())
-        .filter(|u| u.stream_id == stream_id)
-        .collect();
-
-    let all_tasks = match shard.engine.list_l3_tasks(&user_id).await {
-        Ok(t) => t,
-        Err(e) => {
-            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-        }
-    };
-
-    let mut root_nodes = Vec::new();
-    for unit in all_units.into_iter().filter(|u| u.level == 3) {
-        let tasks = build_l3_task_tree(unit.id, &all_tasks, &shard.engine, &user_id, 0).await;
-        root_nodes.push(GoalTree {
-            goal: GoalMemoryUnitView::from(&unit),
-            tasks,
-        });
+    match build_goal_trees(&shard.engine, &user_id, Some(stream_id)).await {
+        Ok(root_nodes) => Json(root_nodes).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
-
-    Json(root_nodes).into_response()
 }
 
-use async_recursion::async_recursion;
-
 const MAX_TASK_DEPTH: usize = 10;
 
-#[async_recursion]
-async fn build_l3_task_tree(
-    parent_id: Uuid,
-    all_tasks: &[memorose_common::L3Task],
+async fn build_goal_trees(
     engine: &MemoroseEngine,
     user_id: &str,
-    depth: usize,
-) -> Vec<L3TaskTree> {
-    let mut children = Vec::new();
-
-    if depth < MAX_TASK_DEPTH {
-        for task in all_tasks {
-            if task.parent_id == Some(parent_id) {
-                let task_children =
-                    build_l3_task_tree(task.task_id, all_tasks, engine, user_id, depth + 1).await;
-                children.push(L3TaskTree {
-                    task: task.clone(),
-                    children: task_children,
-                });
-            }
-        }
-    }
+    stream_id: Option<Uuid>,
+) -> anyhow::Result<Vec<GoalTree>> {
+    let goals = engine.list_goal_units(user_id, stream_id).await?;
+    let goal_ids = goals.iter().map(|goal| goal.id).collect::<Vec<_>>();
+    let mut subtasks = engine
+        .get_l3_subtask_map(user_id, &goal_ids, MAX_TASK_DEPTH)
+        .await?;
+
+    Ok(goals
+        .iter()
+        .map(|goal| GoalTree {
+            goal: GoalMemoryUnitView::from(goal),
+            tasks: build_l3_task_tree(goal.id, &mut subtasks),
+        })
+        .collect())
+}
 
-    children
+fn build_l3_task_tree(
+    parent_id: Uuid,
+    subtasks: &mut std::collections::HashMap<Uuid, Vec<memorose_common::L3Task>>,
+) -> Vec<L3TaskTree> {
+    subtasks
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|task| {
+            let children = build_l3_task_tree(task.task_id, subtasks);
+            L3TaskTree { task, children }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
     use memorose_common::{ForgetMode, ForgetTargetKind, ForgettingTombstone, MemoryType};
     use tempfile::tempdir;
 
@@ -2145,6 +6211,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_reload_live_config_picks_up_environment_changes() {
+        let live_config = LiveConfig::new(AppConfig::load().expect("config should load"));
+        let interval = live_config.load().worker.orphan_gc_interval_secs + 1;
+
+        std::env::set_var(
+            "MEMOROSE__WORKER__ORPHAN_GC_INTERVAL_SECS",
+            interval.to_string(),
+        );
+        let report = reload_live_config(&live_config, "test");
+        std::env::remove_var("MEMOROSE__WORKER__ORPHAN_GC_INTERVAL_SECS");
+
+        let report = report.expect("reload should succeed");
+        assert_eq!(report.changed, vec!["worker.orphan_gc_interval_secs"]);
+        assert!(report.restart_required.is_empty());
+        assert_eq!(live_config.load().worker.orphan_gc_interval_secs, interval);
+    }
+
     #[test]
     fn test_bootstrap_initialize_errors_collects_failures() {
         let results = vec![
@@ -2186,6 +6270,223 @@ mod tests {
         assert_eq!(response.status(), axum::http::StatusCode::OK);
     }
 
+    /// A standalone node over `dir` whose LLM calls go to `llm`.
+    pub(crate) async fn test_state(
+        dir: &std::path::Path,
+        llm: memorose_core::llm::MockLLM,
+    ) -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.storage.root_dir = dir.to_str().unwrap().to_string();
+        config.llm.embedding_dim = memorose_core::llm::MOCK_EMBEDDING_DIM as i32;
+        AppState::for_tests(config, Arc::new(llm)).await
+    }
+
+    #[tokio::test]
+    async fn test_read_your_writes_reports_unconsolidated_events() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let state = test_state(temp_dir.path(), Default::default()).await;
+        let shard = state.shard_manager.shard_for_user("test-user");
+        assert_eq!(
+            wait_for_event_applied(&state, shard, None).await.ok(),
+            Some(None)
+        );
+
+        let event = memorose_common::Event::new(
+            None,
+            "test-user".into(),
+            None,
+            Uuid::new_v4(),
+            memorose_common::EventContent::Text("Booked a flight to Lisbon".into()),
+        );
+        shard.engine.ingest_event_directly(event).await?;
+        assert_eq!(
+            wait_for_event_applied(&state, shard, Some(1)).await.ok(),
+            Some(Some(ReadYourWrites {
+                applied_index: None,
+                pending_events: 1,
+            }))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_only_disk_refuses_writes_but_not_reads() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::http::{Method, Request, StatusCode};
+        use tower::ServiceExt;
+
+        let temp_dir = tempdir()?;
+        let state = test_state(temp_dir.path(), Default::default()).await;
+        let app = Router::new()
+            .route(
+                "/v1/users/:user_id/graph/edges",
+                axum::routing::post(|| async { "ok" }),
+            )
+            .route(
+                "/v1/users/:user_id/apps/:app_id/memories",
+                axum::routing::post(|| async { "ok" }),
+            )
+            .route(
+                "/v1/users/:user_id/shares/:grant_id",
+                axum::routing::delete(|| async { "ok" }),
+            )
+            .route(
+                "/v1/users/:user_id/ask",
+                axum::routing::post(|| async { "ok" }),
+            )
+            .route(
+                "/v1/users/:user_id/trash",
+                axum::routing::get(|| async { "ok" }),
+            )
+            .route_layer(axum_middleware::from_fn_with_state(
+                state.clone(),
+                disk_space_guard,
+            ))
+            .with_state(state.clone());
+        let call = |method: Method, uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        assert_eq!(
+            call(Method::POST, "/v1/users/alice/graph/edges").await,
+            StatusCode::OK
+        );
+
+        state.disk_watchdog.set_read_only(true);
+        for (method, uri) in [
+            (Method::POST, "/v1/users/alice/graph/edges"),
+            (Method::POST, "/v1/users/alice/apps/notes/memories"),
+            (Method::DELETE, "/v1/users/alice/shares/grant-1"),
+        ] {
+            assert_eq!(call(method, uri).await, StatusCode::INSUFFICIENT_STORAGE);
+        }
+        assert_eq!(
+            call(Method::POST, "/v1/users/alice/ask").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(Method::GET, "/v1/users/alice/trash").await,
+            StatusCode::OK
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_app_bound_key_is_refused_on_other_apps_data() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::http::{Method, Request, StatusCode};
+        use tower::ServiceExt;
+
+        let temp_dir = tempdir()?;
+        let state = test_state(temp_dir.path(), Default::default()).await;
+        let engine = &state.shard_manager.shard_for_user("alice").engine;
+        let mut unit_ids = Vec::new();
+        for app_id in ["support-bot", "billing"] {
+            let unit = MemoryUnit::new(
+                None,
+                "alice".into(),
+                Some(app_id.into()),
+                Uuid::new_v4(),
+                MemoryType::Factual,
+                format!("Remembered by {}", app_id),
+                None,
+            );
+            unit_ids.push(unit.id);
+            engine.store_memory_unit(unit).await?;
+        }
+        let key = state
+            .management_registry
+            .create_api_key(
+                dashboard::registry::DEFAULT_ORG_ID,
+                None,
+                ApiKeyScope {
+                    app_ids: vec!["support-bot".into()],
+                    user_patterns: Vec::new(),
+                },
+            )
+            .await?;
+
+        let app = Router::new()
+            .route(
+                "/v1/users/:user_id/memories/:id",
+                axum::routing::delete(delete_memory_unit),
+            )
+            .route(
+                "/v1/users/:user_id/shares",
+                axum::routing::post(create_share_grant),
+            )
+            .route(
+                "/v1/users/:user_id/encryption-key",
+                axum::routing::delete(revoke_user_key),
+            )
+            .route_layer(axum_middleware::from_fn_with_state(
+                state.clone(),
+                api_key_auth,
+            ))
+            .with_state(state.clone());
+        let call = |method: Method, uri: String| {
+            let app = app.clone();
+            let key = key.key.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("x-api-key", key)
+                        .header("content-type", "application/json")
+                        .body(Body::from("{}"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        let billing_unit = format!("/v1/users/alice/memories/{}", unit_ids[1]);
+        assert_eq!(
+            call(Method::DELETE, billing_unit).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call(Method::POST, "/v1/users/alice/shares".into()).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call(Method::DELETE, "/v1/users/alice/encryption-key".into()).await,
+            StatusCode::FORBIDDEN
+        );
+        assert!(engine
+            .get_memory_unit_including_forgotten("alice", unit_ids[1])?
+            .is_some());
+
+        let own_unit = format!("/v1/users/alice/memories/{}", unit_ids[0]);
+        assert_eq!(call(Method::DELETE, own_unit).await, StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_version_reports_schema_versions() {
+        let Json(body) = version().await;
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            body["schema_versions"]["memory_unit"],
+            memorose_common::MEMORY_UNIT_SCHEMA_VERSION
+        );
+    }
+
     fn test_memory_unit(content: &str, level: u8, memory_type: MemoryType) -> MemoryUnit {
         let mut unit = MemoryUnit::new(
             None,
@@ -2262,6 +6563,34 @@ mod tests {
         assert!(count_tokens(&rendered.context) <= 128);
     }
 
+    #[test]
+    fn test_render_memory_context_prepends_user_profile() {
+        let unit = test_memory_unit("User is planning a trip to Lisbon.", 1, MemoryType::Factual);
+        let results = vec![(SharedSearchHit::native(unit), 0.9)];
+        let mut profile = memorose_common::UserProfile::new("test-user".into());
+        profile.merge_extracted(
+            std::collections::HashMap::from([(
+                "language".to_string(),
+                memorose_common::ProfileValue::Text("Portuguese".into()),
+            )]),
+            chrono::Utc::now(),
+        );
+
+        let rendered =
+            render_memory_context_with_profile(&results, Some(&profile), 256, ContextFormat::Text);
+        assert!(rendered
+            .context
+            .starts_with("User profile:\n- language: Portuguese"));
+        assert_eq!(rendered.included_count, 1);
+
+        let rendered =
+            render_memory_context_with_profile(&results, Some(&profile), 256, ContextFormat::Xml);
+        assert!(rendered.context.starts_with(
+            "<memory_context><user_profile><attribute key=\"language\">Portuguese</attribute>"
+        ));
+        assert!(rendered.context.ends_with("</memory_context>"));
+    }
+
     #[test]
     fn test_memory_budget_from_headers_rejects_zero() {
         let mut headers = HeaderMap::new();
@@ -2276,4 +6605,138 @@ mod tests {
         let response = validate_payload_token_budget(Some(0)).unwrap_err();
         assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
     }
+
+    #[test]
+    fn test_validate_client_embedding_checks_dimension_and_values() {
+        assert!(validate_client_embedding(&[0.1, 0.2, 0.3], 3).is_ok());
+
+        let response = validate_client_embedding(&[0.1, 0.2], 3).unwrap_err();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+
+        let response = validate_client_embedding(&[0.1, f32::NAN, 0.3], 3).unwrap_err();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+    #[test]
+    fn test_graph_query_explain_request_defaults_to_one_outgoing_hop() {
+        let node = Uuid::new_v4();
+        let request: GraphQueryExplainRequest =
+            serde_json::from_value(serde_json::json!({ "start_nodes": [node] })).unwrap();
+        assert_eq!(request.start_nodes, vec![node]);
+        assert_eq!(request.max_hops, 1);
+        assert_eq!(
+            request.direction,
+            memorose_core::graph::TraversalDirection::Outgoing
+        );
+        assert!(request.relations.is_empty());
+
+        let request: GraphQueryExplainRequest = serde_json::from_value(serde_json::json!({
+            "start_nodes": [node],
+            "direction": "both",
+            "max_hops": 3,
+        }))
+        .unwrap();
+        assert_eq!(
+            request.direction,
+            memorose_core::graph::TraversalDirection::Both
+        );
+        assert_eq!(request.max_hops, 3);
+    }
+
+    #[test]
+    fn test_retrieve_request_scope_defaults_to_app() {
+        let parse = |body: serde_json::Value| {
+            serde_json::from_value::<RetrieveRequest>(body)
+                .unwrap()
+                .scope
+        };
+
+        assert_eq!(
+            parse(serde_json::json!({ "query": "q" })),
+            RetrievalScope::App
+        );
+        assert_eq!(
+            parse(serde_json::json!({ "query": "q", "scope": "user" })),
+            RetrievalScope::User
+        );
+        assert_eq!(
+            parse(serde_json::json!({ "query": "q", "scope": { "apps": ["notes", "mail"] } })),
+            RetrievalScope::Apps(vec!["notes".into(), "mail".into()])
+        );
+    }
+
+    #[test]
+    fn test_retrieve_query_time_range_yields_to_explicit_bounds() {
+        let config = memorose_common::config::TemporalQueryConfig::default();
+        let now = Utc.with_ymd_and_hms(2026, 3, 11, 15, 0, 0).unwrap();
+        let parse = |body: serde_json::Value| {
+            let payload = serde_json::from_value::<RetrieveRequest>(body).unwrap();
+            parse_query_time_range(&config, &payload, now)
+        };
+
+        let range = parse(serde_json::json!({ "query": "what did I decide yesterday" }))
+            .unwrap()
+            .expect("yesterday is parsed");
+        assert_eq!(
+            range.start,
+            Utc.with_ymd_and_hms(2026, 3, 10, 0, 0, 0).unwrap()
+        );
+        let shifted = parse(serde_json::json!({
+            "query": "what did I decide yesterday",
+            "utc_offset_minutes": 600
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            shifted.start,
+            Utc.with_ymd_and_hms(2026, 3, 10, 14, 0, 0).unwrap()
+        );
+
+        assert!(parse(serde_json::json!({
+            "query": "what did I decide yesterday",
+            "start_time": "2026-01-01T00:00:00Z"
+        }))
+        .unwrap()
+        .is_none());
+        assert!(parse(serde_json::json!({
+            "query": "what did I decide yesterday",
+            "parse_time": false
+        }))
+        .unwrap()
+        .is_none());
+        assert!(parse(serde_json::json!({ "query": "q", "locale": "fr" })).is_err());
+    }
+
+    #[test]
+    fn test_default_digest_end_rounds_up_to_the_hour() {
+        let at = |h, m| Utc.with_ymd_and_hms(2026, 3, 8, h, m, 0).unwrap();
+        assert_eq!(default_digest_end(at(9, 15)), at(10, 0));
+        assert_eq!(default_digest_end(at(9, 0)), at(10, 0));
+    }
+
+    #[test]
+    fn test_image_query_caption_extends_text_query() {
+        assert_eq!(
+            fuse_query_with_caption("deploy error", Some("A terminal showing a 502")),
+            "deploy error A terminal showing a 502"
+        );
+        assert_eq!(
+            fuse_query_with_caption("  ", Some("A whiteboard")),
+            "A whiteboard"
+        );
+        assert_eq!(fuse_query_with_caption("plain", None), "plain");
+
+        let memory_id = Uuid::new_v4();
+        let request = serde_json::from_value::<RetrieveRequest>(serde_json::json!({
+            "query": "",
+            "image_asset": { "memory_id": memory_id, "index": 2 }
+        }))
+        .unwrap();
+        assert_eq!(
+            request.image_asset,
+            Some(QueryAssetRef {
+                memory_id,
+                index: 2
+            })
+        );
+    }
 }
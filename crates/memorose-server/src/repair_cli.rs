@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use memorose_common::config::AppConfig;
 use memorose_core::storage::repair::{
-    rebuild_vector_index, vector_status_with_limits, VectorRebuildOptions,
+    migrate_memory_units, rebuild_vector_index, vector_status_with_limits, VectorRebuildOptions,
 };
 use std::path::PathBuf;
 
@@ -17,6 +17,10 @@ pub enum RepairCommand {
         batch_size: Option<usize>,
         force: bool,
     },
+    MigrateSchema {
+        data_dir: PathBuf,
+        dry_run: bool,
+    },
 }
 
 pub async fn run_from_env_if_requested(config: &AppConfig) -> Result<bool> {
@@ -51,6 +55,7 @@ where
     match subcommand.as_str() {
         "vector-status" => parse_vector_status(args).map(Some),
         "vector-rebuild" => parse_vector_rebuild(args).map(Some),
+        "migrate-schema" => parse_migrate_schema(args).map(Some),
         _ => Err(repair_usage()),
     }
 }
@@ -81,6 +86,10 @@ async fn run_repair_command(command: RepairCommand, config: &AppConfig) -> Resul
             .await?;
             println!("{}", serde_json::to_string_pretty(&report)?);
         }
+        RepairCommand::MigrateSchema { data_dir, dry_run } => {
+            let report = migrate_memory_units(data_dir, dry_run)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
     }
     Ok(())
 }
@@ -149,11 +158,29 @@ fn parse_vector_rebuild(args: Vec<String>) -> std::result::Result<RepairCommand,
     })
 }
 
+fn parse_migrate_schema(args: Vec<String>) -> std::result::Result<RepairCommand, String> {
+    let mut data_dir = None;
+    let mut dry_run = false;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--data-dir" => data_dir = iter.next().map(PathBuf::from),
+            "--dry-run" => dry_run = true,
+            _ => return Err(repair_usage()),
+        }
+    }
+    let Some(data_dir) = data_dir else {
+        return Err(repair_usage());
+    };
+    Ok(RepairCommand::MigrateSchema { data_dir, dry_run })
+}
+
 fn repair_usage() -> String {
     [
         "Usage:",
         "  memorose-server repair vector-status --data-dir <DIR> [--open-lancedb]",
         "  memorose-server repair vector-rebuild --data-dir <DIR> [--embedding-dim <N>] [--batch-size <N>] [--force]",
+        "  memorose-server repair migrate-schema --data-dir <DIR> [--dry-run]",
     ]
     .join("\n")
 }
@@ -210,4 +237,26 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_migrate_schema_command() {
+        let command = parse_repair_command([
+            "memorose-server",
+            "repair",
+            "migrate-schema",
+            "--data-dir",
+            "/app/data",
+            "--dry-run",
+        ])
+        .expect("migrate command should parse")
+        .expect("repair command should be detected");
+
+        assert_eq!(
+            command,
+            RepairCommand::MigrateSchema {
+                data_dir: "/app/data".into(),
+                dry_run: true,
+            }
+        );
+    }
 }
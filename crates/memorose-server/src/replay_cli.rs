@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+use memorose_common::config::AppConfig;
+use memorose_core::llm::create_llm_client;
+use memorose_core::replay::replay;
+use memorose_core::MemoroseEngine;
+use std::path::PathBuf;
+
+const DEFAULT_REPLAY_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayCommand {
+    data_dir: Option<PathBuf>,
+    limit: usize,
+    reembed: bool,
+}
+
+pub async fn run_from_env_if_requested(config: &AppConfig) -> Result<bool> {
+    let Some(command) = parse_replay_command(std::env::args()).map_err(|error| anyhow!(error))?
+    else {
+        return Ok(false);
+    };
+    run_replay_command(command, config).await?;
+    Ok(true)
+}
+
+pub fn parse_replay_command<I, S>(args: I) -> std::result::Result<Option<ReplayCommand>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+    if args.len() <= 1 {
+        return Ok(None);
+    }
+    args.remove(0);
+    if args.first().map(String::as_str) != Some("replay") {
+        return Ok(None);
+    }
+    args.remove(0);
+
+    let mut data_dir = None;
+    let mut limit = DEFAULT_REPLAY_LIMIT;
+    let mut reembed = false;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--data-dir" => data_dir = iter.next().map(PathBuf::from),
+            "--limit" => {
+                let Some(value) = iter.next() else {
+                    return Err(replay_usage());
+                };
+                limit = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .ok_or_else(|| "invalid --limit value".to_string())?;
+            }
+            "--reembed" => reembed = true,
+            _ => return Err(replay_usage()),
+        }
+    }
+    Ok(Some(ReplayCommand {
+        data_dir,
+        limit,
+        reembed,
+    }))
+}
+
+async fn run_replay_command(command: ReplayCommand, config: &AppConfig) -> Result<()> {
+    let client = if command.reembed {
+        Some(
+            create_llm_client(&config.llm)
+                .ok_or_else(|| anyhow!("An LLM provider is required to re-embed queries"))?,
+        )
+    } else {
+        None
+    };
+
+    let engine = MemoroseEngine::new_with_storage_config(
+        command
+            .data_dir
+            .unwrap_or_else(|| PathBuf::from(&config.storage.root_dir)),
+        config.storage.clone(),
+        false,
+        false,
+        config.worker.auto_link_similarity_threshold,
+        config.llm.embedding_dim,
+    )
+    .await?;
+
+    let records = engine.list_recorded_retrievals(command.limit)?;
+    if records.is_empty() {
+        return Err(anyhow!("No recorded retrievals to replay"));
+    }
+    let report = replay(&engine, &records, client.as_deref()).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn replay_usage() -> String {
+    [
+        "Usage:",
+        "  memorose-server replay [--data-dir <DIR>] [--limit <N>] [--reembed]",
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_replay_command() {
+        let command = parse_replay_command([
+            "memorose-server",
+            "replay",
+            "--data-dir",
+            "data/shard_0",
+            "--limit",
+            "200",
+            "--reembed",
+        ])
+        .expect("replay command should parse")
+        .expect("replay command should be detected");
+
+        assert_eq!(
+            command,
+            ReplayCommand {
+                data_dir: Some("data/shard_0".into()),
+                limit: 200,
+                reembed: true,
+            }
+        );
+        assert_eq!(
+            parse_replay_command(["memorose-server", "replay"]),
+            Ok(Some(ReplayCommand {
+                data_dir: None,
+                limit: DEFAULT_REPLAY_LIMIT,
+                reembed: false,
+            }))
+        );
+        assert_eq!(
+            parse_replay_command(["memorose-server", "eval", "--dataset", "a"]),
+            Ok(None)
+        );
+        assert!(parse_replay_command(["memorose-server", "replay", "--limit", "0"]).is_err());
+    }
+}
@@ -1,17 +1,26 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::net::SocketAddr;
+use std::sync::Arc;
 
-use memorose_common::config::AppConfig;
+use memorose_common::config::{AppConfig, LiveConfig, NodeRole};
 use memorose_common::sharding::{encode_raft_node_id, raft_addr_for_shard, user_id_to_shard};
+use memorose_core::consolidation_pool::ConsolidationPoolMember;
 use memorose_core::raft::network::run_raft_server;
+use memorose_core::raft::progress::CatchUpTracker;
 use memorose_core::raft::start_raft_node;
+use memorose_core::raft::types::MemoroseNode;
 use memorose_core::raft::MemoroseRaft;
+use memorose_core::replication::ReplicationShipper;
 use memorose_core::{BackgroundWorker, MemoroseEngine};
-use openraft::BasicNode;
+use openraft::ChangeMembers;
 
 pub struct ShardState {
     pub engine: MemoroseEngine,
     pub raft: Option<MemoroseRaft>,
+    /// Handle on the shard's background worker for inline consolidation.
+    pub worker: BackgroundWorker,
+    /// What this node has heard from the shard leader while catching up.
+    pub catch_up: Arc<CatchUpTracker>,
 }
 
 pub struct ShardManager {
@@ -22,7 +31,8 @@ pub struct ShardManager {
 
 impl ShardManager {
     /// Create a multi-shard manager from sharding config.
-    pub async fn new(config: &AppConfig) -> anyhow::Result<Self> {
+    pub async fn new(live_config: &LiveConfig) -> anyhow::Result<Self> {
+        let config = &*live_config.load();
         let sharding = config
             .sharding
             .as_ref()
@@ -67,7 +77,11 @@ impl ShardManager {
                 config.worker.auto_link_similarity_threshold,
                 config.llm.embedding_dim,
             )
-            .await?;
+            .await?
+            .with_admission_config(config.admission.clone())
+            .with_linking_config(config.linking.clone())
+            .with_edge_decay_config(config.edge_decay.clone())
+            .with_live_config(live_config.clone());
 
             // Override raft config for this shard
             let mut shard_config = config.clone();
@@ -83,20 +97,44 @@ impl ShardManager {
             // Start background worker for this shard
             let mut worker = BackgroundWorker::with_config(engine.clone(), shard_config);
             worker.set_raft(raft.clone());
+            worker.set_live_config(live_config.clone());
+            let background = worker.clone();
             tokio::spawn(async move {
-                worker.run().await;
+                background.run().await;
             });
+            tokio::spawn(
+                ReplicationShipper::new(
+                    engine.clone(),
+                    raft.clone(),
+                    shard_id,
+                    live_config.clone(),
+                )
+                .run(),
+            );
+            tokio::spawn(
+                ConsolidationPoolMember::new(
+                    worker.clone(),
+                    raft.clone(),
+                    shard_id,
+                    live_config.clone(),
+                )
+                .run(),
+            );
 
             // Start raft gRPC server for this shard
             let raft_addr: SocketAddr = raft_addr_str.parse()?;
             let raft_for_server = raft.clone();
+            let catch_up = Arc::new(CatchUpTracker::default());
+            let catch_up_for_server = catch_up.clone();
             tokio::spawn(async move {
                 tracing::info!(
                     "Raft gRPC server for shard {} listening on {}",
                     shard_id,
                     raft_addr
                 );
-                if let Err(e) = run_raft_server(raft_addr, raft_for_server).await {
+                if let Err(e) =
+                    run_raft_server(raft_addr, raft_for_server, catch_up_for_server).await
+                {
                     tracing::error!("Raft server error for shard {}: {:?}", shard_id, e);
                 }
             });
@@ -106,6 +144,8 @@ impl ShardManager {
                 ShardState {
                     engine,
                     raft: Some(raft),
+                    worker,
+                    catch_up,
                 },
             );
         }
@@ -118,7 +158,8 @@ impl ShardManager {
     }
 
     /// Create a single-shard manager (backward compatible, no sharding config needed).
-    pub async fn new_single_shard(config: &AppConfig) -> anyhow::Result<Self> {
+    pub async fn new_single_shard(live_config: &LiveConfig) -> anyhow::Result<Self> {
+        let config = &*live_config.load();
         let data_dir = &config.storage.root_dir;
         let node_id = config.raft.node_id;
 
@@ -130,37 +171,67 @@ impl ShardManager {
             config.worker.auto_link_similarity_threshold,
             config.llm.embedding_dim,
         )
-        .await?;
+        .await?
+        .with_admission_config(config.admission.clone())
+        .with_linking_config(config.linking.clone())
+        .with_edge_decay_config(config.edge_decay.clone())
+        .with_live_config(live_config.clone());
 
         // Start background worker
         let mut worker = BackgroundWorker::with_config(engine.clone(), config.clone());
+        worker.set_live_config(live_config.clone());
+        let catch_up = Arc::new(CatchUpTracker::default());
         let raft = if config.is_cluster_mode() {
             let raft_addr_str = config.raft.raft_addr.clone();
             let raft = start_raft_node(node_id, engine.clone(), config.clone())
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to start raft: {:?}", e))?;
             worker.set_raft(raft.clone());
+            tokio::spawn(
+                ReplicationShipper::new(engine.clone(), raft.clone(), 0, live_config.clone()).run(),
+            );
+            tokio::spawn(
+                ConsolidationPoolMember::new(worker.clone(), raft.clone(), 0, live_config.clone())
+                    .run(),
+            );
 
             let raft_addr: SocketAddr = raft_addr_str.parse()?;
             let raft_for_server = raft.clone();
+            let catch_up_for_server = catch_up.clone();
             tokio::spawn(async move {
                 tracing::info!("Raft gRPC server listening on {}", raft_addr);
-                if let Err(e) = run_raft_server(raft_addr, raft_for_server).await {
+                if let Err(e) =
+                    run_raft_server(raft_addr, raft_for_server, catch_up_for_server).await
+                {
                     tracing::error!("Raft server error: {:?}", e);
                 }
             });
             Some(raft)
         } else {
             tracing::info!("Standalone mode: skipping raft node/server startup");
+            // Without a Raft log there is nothing to replay; converge the derived stores.
+            if engine.mark_session_open()? {
+                let report = engine.converge_derived_stores().await?;
+                tracing::warn!("Recovered after unclean shutdown: {:?}", report);
+            }
             None
         };
 
+        let background = worker.clone();
         tokio::spawn(async move {
-            worker.run().await;
+            background.run().await;
         });
 
         let mut shards = HashMap::new();
-        shards.insert(0, ShardState { engine, raft });
+        shards.insert(
+            0,
+            ShardState {
+                engine,
+                raft,
+                worker,
+                catch_up,
+            },
+        );
 
         Ok(Self {
             shards,
@@ -169,9 +240,14 @@ impl ShardManager {
         })
     }
 
+    /// The shard a user_id routes to.
+    pub fn shard_id_for_user(&self, user_id: &str) -> u32 {
+        user_id_to_shard(user_id, self.shard_count)
+    }
+
     /// Route a user_id to the appropriate shard.
     pub fn shard_for_user(&self, user_id: &str) -> &ShardState {
-        let shard_id = user_id_to_shard(user_id, self.shard_count);
+        let shard_id = self.shard_id_for_user(user_id);
         self.shards
             .get(&shard_id)
             .expect("shard_for_user: shard missing from map")
@@ -236,9 +312,10 @@ impl ShardManager {
             } else {
                 config.raft.raft_addr.clone()
             };
+            let http_addr = config.local_http_addr().unwrap_or_default();
 
             let mut nodes = BTreeMap::new();
-            nodes.insert(raft_node_id, BasicNode { addr: raft_addr });
+            nodes.insert(raft_node_id, MemoroseNode::new(raft_addr, http_addr));
 
             match raft.initialize(nodes).await {
                 Ok(_) => {
@@ -273,10 +350,12 @@ impl ShardManager {
         results
     }
 
-    /// Add a joining node to all Raft groups.
+    /// Add a joining node to all Raft groups. Voters are promoted once added; learners
+    /// stay non-voting members that only replicate.
     pub async fn join_all(
         &self,
         joining_physical_node_id: u32,
+        role: NodeRole,
         config: &AppConfig,
     ) -> Vec<serde_json::Value> {
         let mut results = Vec::new();
@@ -296,30 +375,66 @@ impl ShardManager {
                 joining_physical_node_id as u64
             };
 
-            let joining_addr = if let Some(sc) = sharding {
-                let node = sc.nodes.iter().find(|n| n.id == joining_physical_node_id);
-                node.map(|n| {
+            let joining_node = sharding
+                .and_then(|sc| sc.nodes.iter().find(|n| n.id == joining_physical_node_id))
+                .map(|n| {
                     let host = n.http_addr.split(':').next().unwrap_or("127.0.0.1");
-                    raft_addr_for_shard(host, n.raft_base_port, shard_id)
-                })
-                .unwrap_or_default()
-            } else {
-                // Single-shard mode: expect address to be passed separately
-                String::new()
-            };
-
-            if joining_addr.is_empty() {
+                    MemoroseNode::new(
+                        raft_addr_for_shard(host, n.raft_base_port, shard_id),
+                        n.http_addr.clone(),
+                    )
+                });
+
+            // Single-shard mode expects the address to be passed separately
+            let Some(node) = joining_node else {
                 results.push(serde_json::json!({
                     "shard_id": shard_id,
                     "error": "Cannot resolve joining node address"
                 }));
                 continue;
-            }
+            };
 
-            let node = BasicNode { addr: joining_addr };
+            // A known member that moved hosts only needs its addresses replaced.
+            let metrics = raft.metrics().borrow().clone();
+            if let Some(current) = metrics
+                .membership_config
+                .membership()
+                .get_node(&joining_raft_id)
+            {
+                if *current != node {
+                    let update = BTreeMap::from([(joining_raft_id, node)]);
+                    if let Err(e) = raft
+                        .change_membership(ChangeMembers::SetNodes(update), false)
+                        .await
+                    {
+                        results.push(serde_json::json!({
+                            "shard_id": shard_id,
+                            "error": format!("update node address failed: {:?}", e)
+                        }));
+                        continue;
+                    }
+                }
+                let is_voter = metrics
+                    .membership_config
+                    .membership()
+                    .voter_ids()
+                    .any(|id| id == joining_raft_id);
+                if is_voter || role == NodeRole::Learner {
+                    results.push(serde_json::json!({
+                        "shard_id": shard_id,
+                        "status": "already_joined",
+                        "raft_node_id": joining_raft_id,
+                        "role": if is_voter { NodeRole::Voter } else { NodeRole::Learner },
+                    }));
+                    continue;
+                }
+            }
 
-            // Add as learner
-            match raft.add_learner(joining_raft_id, node, true).await {
+            // Add as learner; only a node about to be promoted needs to catch up first
+            match raft
+                .add_learner(joining_raft_id, node, role == NodeRole::Voter)
+                .await
+            {
                 Ok(_) => {}
                 Err(e) => {
                     results.push(serde_json::json!({
@@ -329,6 +444,15 @@ impl ShardManager {
                     continue;
                 }
             }
+            if role == NodeRole::Learner {
+                results.push(serde_json::json!({
+                    "shard_id": shard_id,
+                    "status": "joined",
+                    "raft_node_id": joining_raft_id,
+                    "role": NodeRole::Learner,
+                }));
+                continue;
+            }
 
             tokio::task::yield_now().await;
 
@@ -343,7 +467,8 @@ impl ShardManager {
                     results.push(serde_json::json!({
                         "shard_id": shard_id,
                         "status": "joined",
-                        "raft_node_id": joining_raft_id
+                        "raft_node_id": joining_raft_id,
+                        "role": NodeRole::Voter,
                     }));
                 }
                 Err(e) => {
@@ -376,18 +501,22 @@ impl ShardManager {
             };
 
             let metrics = raft.metrics().borrow().clone();
-            let mut members: BTreeSet<u64> =
-                metrics.membership_config.membership().voter_ids().collect();
-
-            if !members.remove(&leaving_raft_id) {
+            let membership = metrics.membership_config.membership();
+            let change = if membership.voter_ids().any(|id| id == leaving_raft_id) {
+                let mut members: BTreeSet<u64> = membership.voter_ids().collect();
+                members.remove(&leaving_raft_id);
+                ChangeMembers::ReplaceAllVoters(members)
+            } else if membership.learner_ids().any(|id| id == leaving_raft_id) {
+                ChangeMembers::RemoveNodes(BTreeSet::from([leaving_raft_id]))
+            } else {
                 results.push(serde_json::json!({
                     "shard_id": shard_id,
                     "error": "Node not found in cluster"
                 }));
                 continue;
-            }
+            };
 
-            match raft.change_membership(members, false).await {
+            match raft.change_membership(change, false).await {
                 Ok(_) => {
                     results.push(serde_json::json!({
                         "shard_id": shard_id,
@@ -406,17 +535,18 @@ impl ShardManager {
         results
     }
 
-    /// Gracefully shut down all Raft groups.
+    /// Gracefully shut down all Raft groups, then flush and close each shard's stores so the
+    /// next start skips recovery.
     pub async fn shutdown_all(&self) {
         for (&shard_id, shard) in &self.shards {
-            if let Err(e) = shard.engine.graph().flush().await {
-                tracing::error!("Graph flush error for shard {}: {:?}", shard_id, e);
-            }
             if let Some(raft) = shard.raft.as_ref() {
                 if let Err(e) = raft.shutdown().await {
                     tracing::error!("Raft shutdown error for shard {}: {:?}", shard_id, e);
                 }
             }
+            if let Err(e) = shard.engine.close_session().await {
+                tracing::error!("Store close error for shard {}: {:?}", shard_id, e);
+            }
         }
     }
 }
@@ -437,18 +567,20 @@ mod tests {
             },
             sharding: Some(memorose_common::config::ShardingConfig {
                 enabled: true,
+                analytics_node_id: None,
                 shard_count: 2,
                 physical_node_id: 1,
                 nodes: vec![memorose_common::config::ShardNodeConfig {
                     id: 1,
                     http_addr: "127.0.0.1:3000".into(),
                     raft_base_port: 5000,
+                    role: NodeRole::Voter,
                 }],
             }),
             ..AppConfig::default()
         };
 
-        let manager = ShardManager::new(&config).await.unwrap();
+        let manager = ShardManager::new(&LiveConfig::new(config)).await.unwrap();
         assert_eq!(manager.shard_count(), 2);
         assert_eq!(manager.physical_node_id(), 1);
 
@@ -478,6 +610,7 @@ mod tests {
             },
             sharding: Some(memorose_common::config::ShardingConfig {
                 enabled: true,
+                analytics_node_id: None,
                 shard_count: 1,
                 physical_node_id: 2,
                 nodes: vec![
@@ -485,19 +618,23 @@ mod tests {
                         id: 1,
                         http_addr: server.uri().replace("http://", ""),
                         raft_base_port: 5000,
+                        role: NodeRole::Voter,
                     },
                     memorose_common::config::ShardNodeConfig {
                         id: 2,
                         http_addr: "127.0.0.1:3001".into(),
                         raft_base_port: 5100,
+                        role: NodeRole::Voter,
                     },
                 ],
             }),
             ..AppConfig::default()
         };
 
-        let manager = ShardManager::new(&config).await.unwrap();
-        let results = manager.join_all(2, &config).await;
+        let manager = ShardManager::new(&LiveConfig::new(config.clone()))
+            .await
+            .unwrap();
+        let results = manager.join_all(2, NodeRole::Voter, &config).await;
 
         assert_eq!(results.len(), 1);
         if let Some(err_val) = results[0].get("error") {
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use memorose_core::RetrievalStageTimings;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Where the time of one `/retrieve` call went, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RetrieveTimings {
+    pub total_ms: f64,
+    /// Query image resolution and embedding.
+    pub embedding_ms: f64,
+    /// Hybrid search on the owning shard, broken down by stage.
+    pub search: RetrievalStageTimings,
+    /// Grants searched on the other shards.
+    pub cross_shard_ms: f64,
+    pub packing_ms: f64,
+    pub profile_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQueryEntry {
+    pub recorded_at: DateTime<Utc>,
+    pub user_id: String,
+    pub stream_id: Uuid,
+    pub query: String,
+    pub result_count: usize,
+    pub timings: RetrieveTimings,
+}
+
+/// Ring buffer of the most recent retrievals that exceeded the slow-query threshold.
+pub struct SlowQueryLog {
+    entries: Mutex<VecDeque<SlowQueryEntry>>,
+    capacity: usize,
+}
+
+impl SlowQueryLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Whether a retrieval taking `total_ms` counts as slow. A threshold of 0 never does.
+    pub fn is_slow(threshold_ms: u64, total_ms: f64) -> bool {
+        threshold_ms > 0 && total_ms >= threshold_ms as f64
+    }
+
+    /// Log `entry` with its stage timings and keep it, evicting the oldest when full.
+    pub fn record(&self, entry: SlowQueryEntry) {
+        tracing::warn!(
+            user_id = %entry.user_id,
+            stream_id = %entry.stream_id,
+            total_ms = entry.timings.total_ms,
+            timings = %serde_json::to_string(&entry.timings).unwrap_or_default(),
+            "Slow retrieval: {:?}",
+            entry.query
+        );
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Up to `limit` entries, newest first, optionally only those of one user.
+    pub fn recent(&self, user_id: Option<&str>, limit: usize) -> Vec<SlowQueryEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .filter(|entry| user_id.is_none_or(|user_id| entry.user_id == user_id))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: &str, query: &str, total_ms: f64) -> SlowQueryEntry {
+        SlowQueryEntry {
+            recorded_at: Utc::now(),
+            user_id: user_id.to_string(),
+            stream_id: Uuid::nil(),
+            query: query.to_string(),
+            result_count: 0,
+            timings: RetrieveTimings {
+                total_ms,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_slow_query_log_evicts_oldest_and_filters_by_user() {
+        assert!(!SlowQueryLog::is_slow(100, 20.0));
+        assert!(!SlowQueryLog::is_slow(0, 5_000.0));
+        assert!(SlowQueryLog::is_slow(100, 100.0));
+
+        let log = SlowQueryLog::new(2);
+        log.record(entry("alice", "first", 150.0));
+        log.record(entry("bob", "second", 300.0));
+        log.record(entry("alice", "third", 120.0));
+
+        let queries: Vec<_> = log
+            .recent(None, 10)
+            .into_iter()
+            .map(|entry| entry.query)
+            .collect();
+        assert_eq!(queries, vec!["third", "second"]);
+
+        let alice = log.recent(Some("alice"), 10);
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].query, "third");
+        assert_eq!(log.recent(None, 1).len(), 1);
+    }
+}
@@ -27,6 +27,14 @@ pub fn default_context_token_budget() -> usize {
     800
 }
 
+pub fn default_memory_importance() -> f32 {
+    1.0
+}
+
+pub fn default_memory_level() -> u8 {
+    1
+}
+
 // ---------------------------------------------------------------------------
 // Asset / Unit views
 // ---------------------------------------------------------------------------
@@ -61,15 +69,23 @@ pub struct RetrievalAssetView {
     pub asset_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ocr_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_key: Option<String>,
 }
 
 impl From<&Asset> for RetrievalAssetView {
     fn from(asset: &Asset) -> Self {
+        use memorose_core::ingest::image::{OCR_TEXT_METADATA_KEY, THUMBNAIL_KEY_METADATA_KEY};
+
         Self {
             storage_key: public_asset_storage_key(asset),
             original_name: asset.original_name.clone(),
             asset_type: asset.asset_type.clone(),
             description: asset.description.clone(),
+            ocr_text: asset.metadata.get(OCR_TEXT_METADATA_KEY).cloned(),
+            thumbnail_key: asset.metadata.get(THUMBNAIL_KEY_METADATA_KEY).cloned(),
         }
     }
 }
@@ -81,6 +97,12 @@ pub struct RetrievalMemoryUnitView {
     pub content: String,
     pub keywords: Vec<String>,
     pub level: u8,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub emotions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<memorose_common::GeoPoint>,
     pub assets: Vec<RetrievalAssetView>,
 }
 
@@ -92,6 +114,9 @@ impl From<&MemoryUnit> for RetrievalMemoryUnitView {
             content: unit.content.clone(),
             keywords: unit.keywords.clone(),
             level: unit.level,
+            pinned: unit.pinned,
+            emotions: unit.emotions.clone(),
+            location: unit.location,
             assets: unit.assets.iter().map(RetrievalAssetView::from).collect(),
         }
     }
@@ -127,10 +152,131 @@ pub struct AddEdgeRequest {
     pub weight: Option<f32>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct BatchAddEdgesRequest {
+    pub edges: Vec<AddEdgeRequest>,
+}
+
+/// `PUT /v1/users/:user_id/graph/relations/:name` registers a custom relation.
+#[derive(Deserialize, Serialize)]
+pub struct PutRelationDefinitionRequest {
+    #[serde(default = "default_relation_directed")]
+    pub directed: bool,
+    pub traversal_weight: f32,
+    pub description: Option<String>,
+}
+
+fn default_relation_directed() -> bool {
+    true
+}
+
+/// `POST /v1/users/:user_id/graph/query/explain` describes how a traversal would run.
+#[derive(Deserialize)]
+pub struct GraphQueryExplainRequest {
+    pub start_nodes: Vec<Uuid>,
+    /// Relation types to follow; empty follows every type
+    #[serde(default)]
+    pub relations: Vec<RelationType>,
+    #[serde(default)]
+    pub direction: memorose_core::graph::TraversalDirection,
+    #[serde(default = "default_explain_max_hops")]
+    pub max_hops: usize,
+    pub min_weight: Option<f32>,
+    pub limit: Option<usize>,
+}
+
+fn default_explain_max_hops() -> usize {
+    1
+}
+
+/// `GET /v1/users/:user_id/graph/export`. List filters are comma-separated.
+#[derive(Deserialize, Default)]
+pub struct GraphExportQuery {
+    /// `graphml`, `dot` or `json`
+    pub format: Option<String>,
+    pub relations: Option<String>,
+    pub levels: Option<String>,
+    pub min_weight: Option<f32>,
+    pub community_id: Option<Uuid>,
+    pub max_edges: Option<usize>,
+}
+
+/// `GET /v1/users/:user_id/graph/diff` compares the graph at `from` with the graph at
+/// `to`, which defaults to now.
+#[derive(Deserialize)]
+pub struct GraphDiffQuery {
+    pub from: DateTime<Utc>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// `GET /v1/users/:user_id/communities` re-runs detection instead of serving the
+/// cached snapshot when `refresh` is set.
+#[derive(Deserialize, Default)]
+pub struct CommunitiesQuery {
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Serialize)]
+pub struct CommunityView {
+    pub community_id: Uuid,
+    pub members: Vec<RetrievalMemoryUnitView>,
+    pub summary_id: Option<Uuid>,
+    pub summary: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct CommunitiesResponse {
+    pub user_id: String,
+    pub computed_at: DateTime<Utc>,
+    pub modularity: f64,
+    pub communities: Vec<CommunityView>,
+}
+
+/// `GET /v1/users/:user_id/digest` covers `[from, to)`. `to` defaults to now rounded up
+/// to the hour and `from` to seven days before `to`, so repeated calls share a cache entry.
+#[derive(Deserialize, Default)]
+pub struct DigestQuery {
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Serialize)]
+pub struct DigestResponse {
+    #[serde(flatten)]
+    pub digest: memorose_core::engine::MemoryDigest,
+    pub cached: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Ingest
 // ---------------------------------------------------------------------------
 
+/// How an ingested event reaches memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestMode {
+    /// Queue the event for the background consolidation cycle.
+    #[default]
+    Async,
+    /// Consolidate the event before responding, falling back to `Async` when the
+    /// `worker.sync_consolidation_budget_ms` budget runs out.
+    Sync,
+}
+
+/// `POST /v1/users/:user_id/streams/:stream_id/events?mode=sync`
+#[derive(Deserialize, Default)]
+pub struct IngestQuery {
+    #[serde(default)]
+    pub mode: IngestMode,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct IngestRequest {
     pub content: String,
@@ -146,6 +292,33 @@ pub struct IngestRequest {
     pub task_status: Option<String>,
     #[serde(default)]
     pub task_progress: Option<f32>,
+    #[serde(default)]
+    pub task_due_at: Option<DateTime<Utc>>,
+    /// Cron-ish schedule such as `daily` or `every 2h`
+    #[serde(default)]
+    pub task_recurrence: Option<String>,
+    #[serde(default)]
+    pub task_priority: Option<memorose_common::TaskPriority>,
+    /// Store the content as written and only embed it, skipping LLM compression.
+    /// Unset falls back to `worker.skip_compression_apps`
+    #[serde(default)]
+    pub skip_compression: Option<bool>,
+    /// Precomputed embedding of `content` with `llm.embedding_dim` dimensions; the
+    /// event is stored as written and never sent to the server's LLM for embedding
+    #[serde(default)]
+    pub embedding: Option<Vec<f32>>,
+    /// Who produced this turn: `user`, `assistant`, `system` or `tool`
+    #[serde(default)]
+    pub role: Option<memorose_common::EventRole>,
+    /// Display name of the speaker, so multi-party conversations keep facts apart
+    #[serde(default)]
+    pub speaker_name: Option<String>,
+    /// Position of the turn in its conversation; orders turns that arrive together
+    #[serde(default)]
+    pub turn_index: Option<u64>,
+    /// Where the turn happened, carried onto the memories consolidated from it
+    #[serde(default)]
+    pub location: Option<memorose_common::GeoPoint>,
 }
 // PLACEHOLDER_CHUNK3
 
@@ -158,11 +331,27 @@ pub struct BatchIngestRequest {
 // Retrieve
 // ---------------------------------------------------------------------------
 
+/// Which of the user's apps a retrieval may draw from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetrievalScope {
+    /// Only the app named by `agent_id`.
+    #[default]
+    App,
+    /// The user's whole memory, across every app.
+    User,
+    /// Any of the listed apps.
+    Apps(Vec<String>),
+}
+
 #[derive(Deserialize)]
 pub struct RetrieveRequest {
     pub query: String,
     #[serde(default = "default_retrieve_limit")]
     pub limit: usize,
+    /// Number of ranked results to skip, for paging past the first `limit`
+    #[serde(default)]
+    pub offset: usize,
     #[serde(default)]
     pub enable_arbitration: bool,
     #[serde(default)]
@@ -171,25 +360,99 @@ pub struct RetrieveRequest {
     pub token_budget: Option<usize>,
     #[serde(default = "default_graph_depth")]
     pub graph_depth: usize,
+    /// Weight (0.0-1.0) of exponential time decay applied to candidate scores,
+    /// favouring recent memories without an explicit time range
+    #[serde(default)]
+    pub recency_bias: f32,
+    /// Maximal Marginal Relevance lambda (0.0-1.0); lower values favour topically
+    /// distinct results over raw relevance. Diversification is off when unset
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    /// Pack the ranked results into a context string of at most this many tokens
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Summarize results that did not fit into `max_tokens` into the leftover room
+    #[serde(default)]
+    pub summarize_overflow: bool,
     #[serde(default)]
     pub start_time: Option<DateTime<Utc>>,
     #[serde(default)]
     pub end_time: Option<DateTime<Utc>>,
     #[serde(default)]
     pub as_of: Option<DateTime<Utc>>,
+    /// Read a time expression in `query`, such as "last Tuesday", as the valid-time range
+    /// when neither `start_time` nor `end_time` is set. Defaults to `temporal_query.enabled`
+    #[serde(default)]
+    pub parse_time: Option<bool>,
+    /// Language of the query's time expressions (`en`, `zh`); detected from the query
+    /// when unset
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// The user's offset from UTC in minutes, for counting days and weeks. Defaults to
+    /// `temporal_query.utc_offset_minutes`
+    #[serde(default)]
+    pub utc_offset_minutes: Option<i32>,
     #[serde(default)]
     pub org_id: Option<String>,
     #[serde(default)]
     pub agent_id: Option<String>,
+    /// `"app"` (default), `"user"`, or `{"apps": [...]}`
+    #[serde(default)]
+    pub scope: RetrievalScope,
+    /// Keep only memories tagged with at least one of these emotions
+    #[serde(default)]
+    pub emotions: Option<Vec<String>>,
+    /// Keep only memories recorded within `radius` meters of `lat`/`lon`
+    #[serde(default)]
+    pub near: Option<memorose_common::GeoRadius>,
     /// Base64-encoded image for cross-modal retrieval
     #[serde(default)]
     pub image: Option<String>,
+    /// A stored image asset to query with instead of an inline `image`
+    #[serde(default)]
+    pub image_asset: Option<QueryAssetRef>,
+    /// `"text"` (default) searches memory embeddings, `"multimodal"` the joint
+    /// text+image embeddings of memory assets, `"joint"` both
+    #[serde(default)]
+    pub vector_mode: memorose_core::engine::VectorSearchMode,
     /// Base64-encoded audio for cross-modal retrieval
     #[serde(default)]
     pub audio: Option<String>,
     /// Base64-encoded video for cross-modal retrieval
     #[serde(default)]
     pub video: Option<String>,
+    /// Prepend the user's synthesized L3 profile to the results
+    #[serde(default)]
+    pub include_profile: bool,
+    /// `log_index` returned by an earlier write; the read waits until this node has
+    /// applied that event. Consolidating it into memories is not waited for, so the
+    /// response's `read_your_writes` reports the events still pending
+    #[serde(default)]
+    pub min_applied_index: Option<u64>,
+    /// `"strong"` makes memories indexed before the request searchable first, at the
+    /// cost of a text index commit
+    #[serde(default)]
+    pub consistency: ReadConsistency,
+    /// Return the arbitrator's reasoning for which memories won
+    #[serde(default)]
+    pub explain_arbitration: bool,
+    /// Return per-stage retrieval diagnostics for tuning
+    #[serde(default)]
+    pub debug: bool,
+    /// Precomputed query embedding with `llm.embedding_dim` dimensions, used instead
+    /// of embedding `query` (and any image, audio or video) on the server
+    #[serde(default)]
+    pub embedding: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadConsistency {
+    /// Search what the text index has committed plus its recent-write overlay
+    #[default]
+    Eventual,
+    /// Commit pending text index writes before searching
+    Strong,
 }
 
 #[derive(Serialize)]
@@ -203,8 +466,91 @@ pub struct RetrieveResponse {
     pub stream_id: Uuid,
     pub query: String,
     pub results: Vec<RetrieveResultItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arbitration: Option<memorose_core::engine::ArbitrationExplanation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug: Option<memorose_core::engine::RetrievalDiagnostics>,
+    /// Offset of the next page, present while more results remain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<memorose_core::engine::PackedContext>,
+    /// Caption of the query image, added to the full-text side of the search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_caption: Option<String>,
+    /// Time range read from the query and applied as the valid-time filter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_range: Option<memorose_core::temporal::ParsedTimeRange>,
+    /// Ranking experiment variant that served this retrieval; echo it in feedback
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<memorose_common::ExperimentArm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_your_writes: Option<ReadYourWrites>,
     pub query_time_ms: u128,
 }
+
+/// What a read that passed `min_applied_index` covered. The write's event is applied on
+/// the serving node, but memories are only searchable once it is consolidated, which is
+/// not waited for.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadYourWrites {
+    /// Log index this node had applied when the read ran; absent in standalone mode,
+    /// which applies writes before acknowledging them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_index: Option<u64>,
+    /// Events on this shard still waiting to be consolidated. While it is non-zero the
+    /// results may miss memories of the write; ingest with `?mode=sync` to avoid that
+    pub pending_events: usize,
+}
+
+/// Points at asset `index` of one of the caller's memories, as served by
+/// `GET /v1/users/:user_id/memories/:id/assets/:index`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryAssetRef {
+    pub memory_id: Uuid,
+    pub index: usize,
+}
+
+/// Which memories of a retrieval the client went on to use.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetrievalFeedbackRequest {
+    /// Ids of the results shown, in their ranked order
+    pub retrieved_ids: Vec<Uuid>,
+    /// Ids of the results that were used
+    #[serde(default)]
+    pub cited_ids: Vec<Uuid>,
+    /// The `experiment` of the retrieval response, when it had one
+    #[serde(default)]
+    pub experiment: Option<memorose_common::ExperimentArm>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+}
+
+/// Feedback counted for one variant of a ranking experiment, on this node's shards.
+#[derive(Serialize, Debug, Clone)]
+pub struct ExperimentVariantResults {
+    pub variant: String,
+    /// Configured share of traffic; absent once the variant is no longer configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+    pub feedback: u64,
+    pub retrieved: u64,
+    pub cited: u64,
+    pub useful: u64,
+    /// Share of shown memories that were used
+    pub citation_rate: f64,
+    /// Share of feedback reports that used at least one memory
+    pub success_rate: f64,
+    pub mean_reciprocal_rank: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExperimentResultsResponse {
+    pub experiment: String,
+    /// Whether this is the experiment currently configured
+    pub active: bool,
+    pub variants: Vec<ExperimentVariantResults>,
+}
 // PLACEHOLDER_CHUNK4
 
 // ---------------------------------------------------------------------------
@@ -215,6 +561,9 @@ pub struct RetrieveResponse {
 pub struct MemoryContextRequest {
     pub user_id: String,
     pub query: String,
+    /// `log_index` returned by an earlier write; see `RetrieveRequest`
+    #[serde(default)]
+    pub min_applied_index: Option<u64>,
     #[serde(default = "default_context_limit")]
     pub limit: usize,
     #[serde(default)]
@@ -240,6 +589,8 @@ pub struct MemoryContextRequest {
     #[serde(default)]
     pub image: Option<String>,
     #[serde(default)]
+    pub image_asset: Option<QueryAssetRef>,
+    #[serde(default)]
     pub audio: Option<String>,
     #[serde(default)]
     pub video: Option<String>,
@@ -306,6 +657,8 @@ pub struct MemoryContextResponse {
     pub truncated: bool,
     pub context: String,
     pub hits: Vec<MemoryContextHitView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_your_writes: Option<ReadYourWrites>,
     pub query_time_ms: u128,
 }
 
@@ -328,6 +681,12 @@ pub struct JoinRequest {
     pub node_id: u32,
     #[serde(default)]
     pub address: String,
+    /// HTTP address the node advertises for forwarded client traffic.
+    #[serde(default)]
+    pub http_addr: String,
+    /// `"voter"` or `"learner"`; defaults to the node's `sharding.nodes` role
+    #[serde(default)]
+    pub role: Option<memorose_common::config::NodeRole>,
 }
 
 // ---------------------------------------------------------------------------
@@ -346,9 +705,218 @@ pub struct L3TaskTree {
     pub children: Vec<L3TaskTree>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CreateTaskRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Parent goal or task; omit to create a top-level goal
+    pub parent_id: Option<Uuid>,
+    pub org_id: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Uuid>,
+    #[serde(default)]
+    pub context_refs: Vec<Uuid>,
+}
+
+/// A memory the client already distilled, stored without going through consolidation.
 #[derive(serde::Deserialize)]
+pub struct CreateMemoryRequest {
+    pub content: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default = "default_memory_importance")]
+    pub importance: f32,
+    /// 1 for a fact (L1), 2 for an insight (L2)
+    #[serde(default = "default_memory_level")]
+    pub level: u8,
+    #[serde(default)]
+    pub memory_type: MemoryType,
+    pub valid_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub location: Option<memorose_common::GeoPoint>,
+    /// Source events or memory units the memory was distilled from
+    #[serde(default)]
+    pub references: Vec<Uuid>,
+    pub org_id: Option<String>,
+    pub stream_id: Option<Uuid>,
+    /// Precomputed embedding of `content`; generated server-side when omitted
+    pub embedding: Option<Vec<f32>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct AddTaskDependencyRequest {
+    /// Task that must complete before this one becomes ready
+    pub blocked_by: Uuid,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct UpdateTaskStatusRequest {
     pub status: memorose_common::TaskStatus,
     pub progress: Option<f32>,
     pub result_summary: Option<String>,
 }
+
+// ---------------------------------------------------------------------------
+// Profile
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+pub struct UserProfileResponse {
+    #[serde(flatten)]
+    pub profile: memorose_common::UserProfile,
+    /// Narrative L3 profile synthesized from L2 insights, when available
+    pub summary: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PatchUserProfileRequest {
+    /// Attributes to set; a `null` value removes the attribute
+    pub attributes: std::collections::HashMap<String, Option<memorose_common::ProfileValue>>,
+}
+
+// ---------------------------------------------------------------------------
+// Assets
+// ---------------------------------------------------------------------------
+
+/// `GET /v1/users/:user_id/memories/:id/assets/:index` serves the thumbnail instead of
+/// the original when `thumbnail` is set.
+#[derive(Deserialize, Default)]
+pub struct AssetQuery {
+    #[serde(default)]
+    pub thumbnail: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Trash
+// ---------------------------------------------------------------------------
+
+/// `DELETE /v1/users/:user_id/memories/:id` moves the memory to the trash unless
+/// `hard` is set.
+#[derive(Deserialize, Default)]
+pub struct DeleteMemoryQuery {
+    #[serde(default)]
+    pub hard: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Sharing
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct CreateShareGrantRequest {
+    pub resource: memorose_common::ShareResource,
+    pub grantee: memorose_common::ShareGrantee,
+}
+
+// ---------------------------------------------------------------------------
+// Encryption keys
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct RegisterUserKeyRequest {
+    /// The user's key in their KMS, e.g. `vault:acme-user-42`
+    pub key_ref: String,
+}
+
+// ---------------------------------------------------------------------------
+// Content moderation
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct ModerationAuditQuery {
+    #[serde(default = "default_moderation_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_moderation_audit_limit() -> usize {
+    50
+}
+
+// ---------------------------------------------------------------------------
+// Ask
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct AskRequest {
+    pub question: String,
+    /// `log_index` returned by an earlier write; see `RetrieveRequest`
+    #[serde(default)]
+    pub min_applied_index: Option<u64>,
+    /// Memories the answer may draw on
+    #[serde(default = "default_ask_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default = "default_graph_depth")]
+    pub graph_depth: usize,
+    #[serde(default)]
+    pub org_id: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+}
+
+fn default_ask_limit() -> usize {
+    8
+}
+
+/// A memory the answer cites, under the number it is cited by.
+#[derive(Serialize)]
+pub struct AskSupportingItem {
+    pub citation: usize,
+    pub unit: RetrievalMemoryUnitView,
+    pub score: f32,
+}
+
+#[derive(Serialize)]
+pub struct AskResponse {
+    pub question: String,
+    pub answer: String,
+    /// 0.0-1.0, how fully the cited memories support the answer
+    pub confidence: f32,
+    pub supporting: Vec<AskSupportingItem>,
+    /// Memories retrieved and shown to the model, cited or not
+    pub considered: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_your_writes: Option<ReadYourWrites>,
+    pub query_time_ms: u128,
+}
+
+// ---------------------------------------------------------------------------
+// Streams
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct CreateStreamRequest {
+    pub title: Option<String>,
+    #[serde(default)]
+    pub metadata: std::collections::HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct ListStreamsQuery {
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Prompt templates
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct ValidatePromptRequest {
+    pub kind: memorose_common::config::PromptKind,
+    pub template: String,
+}
+
+// ---------------------------------------------------------------------------
+// Text index
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct IndexCommitQuery {
+    /// Also merge each shard's segments into one
+    #[serde(default)]
+    pub merge: bool,
+}
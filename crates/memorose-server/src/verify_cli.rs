@@ -0,0 +1,80 @@
+use anyhow::{anyhow, Result};
+use memorose_core::MemoroseEngine;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyCommand {
+    snapshot: PathBuf,
+}
+
+pub async fn run_from_env_if_requested() -> Result<bool> {
+    let Some(command) = parse_verify_command(std::env::args()).map_err(|error| anyhow!(error))?
+    else {
+        return Ok(false);
+    };
+    run_verify_command(command).await?;
+    Ok(true)
+}
+
+pub fn parse_verify_command<I, S>(args: I) -> std::result::Result<Option<VerifyCommand>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+    if args.len() <= 1 {
+        return Ok(None);
+    }
+    args.remove(0);
+    if args.first().map(String::as_str) != Some("verify") {
+        return Ok(None);
+    }
+    args.remove(0);
+
+    match args.as_slice() {
+        [snapshot] if !snapshot.starts_with("--") => Ok(Some(VerifyCommand {
+            snapshot: PathBuf::from(snapshot),
+        })),
+        _ => Err(verify_usage()),
+    }
+}
+
+async fn run_verify_command(command: VerifyCommand) -> Result<()> {
+    let verification = MemoroseEngine::verify_snapshot(command.snapshot.clone()).await?;
+    println!("{}", serde_json::to_string_pretty(&verification)?);
+    if !verification.has_manifest {
+        return Err(anyhow!(
+            "Snapshot {:?} has no manifest and cannot be verified",
+            command.snapshot
+        ));
+    }
+    if !verification.is_verified() {
+        return Err(anyhow!("Snapshot {:?} is corrupted", command.snapshot));
+    }
+    Ok(())
+}
+
+fn verify_usage() -> String {
+    ["Usage:", "  memorose-server verify <SNAPSHOT.tar.gz>"].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verify_command() {
+        assert_eq!(
+            parse_verify_command(["memorose-server", "verify", "backups/shard_0.tar.gz"]),
+            Ok(Some(VerifyCommand {
+                snapshot: "backups/shard_0.tar.gz".into(),
+            }))
+        );
+        assert_eq!(
+            parse_verify_command(["memorose-server", "replay", "--limit", "5"]),
+            Ok(None)
+        );
+        assert!(parse_verify_command(["memorose-server", "verify"]).is_err());
+        assert!(parse_verify_command(["memorose-server", "verify", "a", "b"]).is_err());
+    }
+}
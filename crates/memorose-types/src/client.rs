@@ -0,0 +1,274 @@
+//! Thin client for the Memorose HTTP API.
+//!
+//! Covers the calls a frontend needs (ingest, retrieve, context, delete) and
+//! decodes responses into the shared types, so the dashboard and the server
+//! cannot drift apart on field names.
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{MemoryType, TaskPriority};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("server returned {status}: {body}")]
+    Status { status: u16, body: String },
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IngestEventRequest {
+    pub content: String,
+    pub content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_progress: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_due_at: Option<DateTime<Utc>>,
+    /// Cron-ish schedule such as `daily` or `every 2h`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_recurrence: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_priority: Option<TaskPriority>,
+}
+
+impl IngestEventRequest {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            content_type: "text".to_string(),
+            org_id: None,
+            level: None,
+            parent_id: None,
+            task_status: None,
+            task_progress: None,
+            task_due_at: None,
+            task_recurrence: None,
+            task_priority: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IngestEventResponse {
+    pub status: String,
+    pub event_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetrieveRequest {
+    pub query: String,
+    pub limit: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_arbitration: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph_depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recency_bias: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mmr_lambda: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+}
+
+impl RetrieveRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            limit: 10,
+            offset: None,
+            enable_arbitration: None,
+            min_score: None,
+            token_budget: None,
+            graph_depth: None,
+            recency_bias: None,
+            mmr_lambda: None,
+            max_tokens: None,
+            start_time: None,
+            end_time: None,
+            as_of: None,
+            agent_id: None,
+            org_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetrievedMemory {
+    pub id: Uuid,
+    pub memory_type: MemoryType,
+    pub content: String,
+    pub keywords: Vec<String>,
+    pub level: u8,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetrieveResultItem {
+    pub unit: RetrievedMemory,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetrieveResponse {
+    pub stream_id: Uuid,
+    pub query: String,
+    pub results: Vec<RetrieveResultItem>,
+    #[serde(default)]
+    pub next_offset: Option<usize>,
+    pub query_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryContextRequest {
+    pub user_id: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_arbitration: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph_depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// `text` (default) or `xml`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemoryContextHit {
+    pub id: Uuid,
+    pub level: u8,
+    pub memory_type: MemoryType,
+    pub domain: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemoryContextResponse {
+    pub query: String,
+    pub format: String,
+    pub strategy: String,
+    pub token_budget: usize,
+    pub used_token_estimate: usize,
+    pub matched_count: usize,
+    pub included_count: usize,
+    pub truncated: bool,
+    pub context: String,
+    #[serde(default)]
+    pub hits: Vec<MemoryContextHit>,
+    pub query_time_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoroseClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl MemoroseClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Send `Authorization: Bearer <api_key>` with every request.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub async fn ingest_event(
+        &self,
+        user_id: &str,
+        stream_id: Uuid,
+        request: &IngestEventRequest,
+    ) -> ClientResult<IngestEventResponse> {
+        let url = self.url(&format!("/v1/users/{user_id}/streams/{stream_id}/events"));
+        self.send(self.http.post(url).json(request)).await
+    }
+
+    pub async fn retrieve(
+        &self,
+        user_id: &str,
+        stream_id: Uuid,
+        request: &RetrieveRequest,
+    ) -> ClientResult<RetrieveResponse> {
+        let url = self.url(&format!("/v1/users/{user_id}/streams/{stream_id}/retrieve"));
+        self.send(self.http.post(url).json(request)).await
+    }
+
+    pub async fn build_context(
+        &self,
+        request: &MemoryContextRequest,
+    ) -> ClientResult<MemoryContextResponse> {
+        let url = self.url("/v1/memory/context");
+        self.send(self.http.post(url).json(request)).await
+    }
+
+    /// Move a memory to the trash.
+    pub async fn delete_memory(&self, user_id: &str, memory_id: Uuid) -> ClientResult<()> {
+        let url = self.url(&format!("/v1/users/{user_id}/memories/{memory_id}"));
+        self.send::<serde_json::Value>(self.http.delete(url))
+            .await
+            .map(|_| ())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> ClientResult<T> {
+        let request = match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        };
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ClientError::Status {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(response.json().await?)
+    }
+}
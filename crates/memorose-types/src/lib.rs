@@ -0,0 +1,1785 @@
+//! Plain data types shared by the Memorose server, its clients and the dashboard.
+//!
+//! Without features this crate only depends on serde, chrono and uuid, so it builds for
+//! `wasm32-unknown-unknown`. Enable the `client` feature for a small HTTP client
+//! that uses the browser's `fetch` on wasm32.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// A conversation or session that events are ingested into. Streams are addressed by id
+/// alone; a record only exists for streams created through the stream API or segmented by
+/// the worker.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryStream {
+    pub id: Uuid,
+    pub user_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub transaction_time: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Set once the stream is archived; archived streams are left out of listings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Session boundaries found by the worker's stream segmentation, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segment_boundaries: Vec<StreamSegmentBoundary>,
+    /// Time of the latest consolidated event, used to spot a gap at the start of the next cycle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Recent turns when the stream is a chat session, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chat_turns: Vec<ChatTurnRecord>,
+}
+
+/// One message of a chat session, kept on the stream so later turns can see it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatTurnRecord {
+    pub role: EventRole,
+    pub content: String,
+    pub at: DateTime<Utc>,
+    /// The event the message was recorded as, when it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<Uuid>,
+}
+
+/// Why a stream segment was started.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamSegmentReason {
+    TimeGap,
+    TopicShift,
+}
+
+/// The first event of a logical session within a stream.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamSegmentBoundary {
+    pub event_id: Uuid,
+    pub at: DateTime<Utc>,
+    pub reason: StreamSegmentReason,
+}
+
+impl MemoryStream {
+    pub fn new(user_id: String, app_id: Option<String>, title: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            app_id,
+            title,
+            transaction_time: now,
+            updated_at: now,
+            metadata: HashMap::new(),
+            archived_at: None,
+            segment_boundaries: Vec::new(),
+            last_event_at: None,
+            chat_turns: Vec::new(),
+        }
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Append chat turns, dropping the oldest beyond `max_turns`.
+    pub fn push_chat_turns(&mut self, turns: Vec<ChatTurnRecord>, max_turns: usize) {
+        self.chat_turns.extend(turns);
+        let excess = self.chat_turns.len().saturating_sub(max_turns);
+        self.chat_turns.drain(..excess);
+        self.updated_at = Utc::now();
+    }
+
+    /// The latest turns whose content fits in `max_chars`, oldest first, at most `max_turns`.
+    pub fn chat_window(&self, max_turns: usize, max_chars: usize) -> &[ChatTurnRecord] {
+        let mut start = self.chat_turns.len();
+        let mut used = 0;
+        for turn in self.chat_turns.iter().rev().take(max_turns) {
+            used += turn.content.chars().count();
+            if used > max_chars {
+                break;
+            }
+            start -= 1;
+        }
+        &self.chat_turns[start..]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum EventContent {
+    Text(String),
+    Image(String), // URL
+    Audio(String), // URL
+    Video(String), // URL
+    Json(serde_json::Value),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: Uuid,
+    pub org_id: Option<String>,
+    pub user_id: String,
+    pub agent_id: Option<String>,
+    pub stream_id: Uuid,
+    pub content: EventContent,
+    pub transaction_time: DateTime<Utc>,
+    pub valid_time: Option<DateTime<Utc>>,
+    pub metadata: serde_json::Value,
+}
+
+impl Event {
+    pub fn new(
+        org_id: Option<String>,
+        user_id: String,
+        agent_id: Option<String>,
+        stream_id: Uuid,
+        content: EventContent,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            org_id,
+            user_id,
+            agent_id,
+            stream_id,
+            content,
+            transaction_time: Utc::now(),
+            valid_time: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    /// App the event was written for: `agent_id`, else `metadata.agent_id`.
+    pub fn app_id(&self) -> Option<&str> {
+        self.agent_id.as_deref().or_else(|| {
+            self.metadata
+                .get("agent_id")
+                .and_then(|value| value.as_str())
+        })
+    }
+
+    /// Who produced this conversation turn, from `metadata.role`.
+    pub fn role(&self) -> Option<EventRole> {
+        self.metadata
+            .get("role")
+            .and_then(|value| value.as_str())
+            .and_then(EventRole::parse)
+    }
+
+    /// Display name of the participant who produced this turn, from `metadata.speaker_name`.
+    pub fn speaker_name(&self) -> Option<&str> {
+        self.metadata
+            .get("speaker_name")
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Position of this turn in its conversation, from `metadata.turn_index`.
+    pub fn turn_index(&self) -> Option<u64> {
+        self.metadata
+            .get("turn_index")
+            .and_then(|value| value.as_u64())
+    }
+
+    /// Record who said this turn and where it falls in the conversation. `None` leaves
+    /// the existing value in place.
+    pub fn set_conversation_turn(
+        &mut self,
+        role: Option<EventRole>,
+        speaker_name: Option<String>,
+        turn_index: Option<u64>,
+    ) {
+        if let Some(role) = role {
+            self.metadata["role"] = serde_json::json!(role);
+        }
+        if let Some(speaker_name) = speaker_name {
+            self.metadata["speaker_name"] = serde_json::json!(speaker_name);
+        }
+        if let Some(turn_index) = turn_index {
+            self.metadata["turn_index"] = serde_json::json!(turn_index);
+        }
+    }
+
+    /// Where the event happened, from `metadata.location`.
+    pub fn location(&self) -> Option<GeoPoint> {
+        GeoPoint::from_metadata(&self.metadata)
+    }
+
+    pub fn set_location(&mut self, location: GeoPoint) {
+        self.metadata["location"] = serde_json::json!(location);
+    }
+
+    /// Whether this event belongs to an agent trajectory rather than the user's side
+    /// of the conversation.
+    pub fn is_agent_event(&self) -> bool {
+        is_agent_metadata(&self.metadata)
+    }
+}
+
+/// Whether event metadata marks an agent turn: an assistant or tool role, or an
+/// `agent_id` key.
+pub fn is_agent_metadata(metadata: &serde_json::Value) -> bool {
+    metadata
+        .get("role")
+        .and_then(|value| value.as_str())
+        .and_then(EventRole::parse)
+        .is_some_and(EventRole::is_agent)
+        || metadata.get("agent_id").is_some()
+}
+
+/// The participant type behind a conversation turn.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventRole {
+    User,
+    Assistant,
+    System,
+    Tool,
+}
+
+impl EventRole {
+    /// Parse a role name case-insensitively.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "user" => Some(Self::User),
+            "assistant" => Some(Self::Assistant),
+            "system" => Some(Self::System),
+            "tool" => Some(Self::Tool),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Assistant => "assistant",
+            Self::System => "system",
+            Self::Tool => "tool",
+        }
+    }
+
+    /// Assistant and tool turns are part of an agent trajectory.
+    pub fn is_agent(self) -> bool {
+        matches!(self, Self::Assistant | Self::Tool)
+    }
+}
+
+/// Kind of a graph edge. Serialized as its name; a name that is not built in is a
+/// `Custom` relation, whose semantics a user can register as a `RelationDefinition`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelationType {
+    Next, // Temporal sequence
+    RelatedTo,
+    Contradicts,
+    Supports,
+    Abstracts,
+    DerivedFrom,
+    CausedBy,
+    EvolvedTo,
+    IsSubTaskOf,  // Vertical hierarchy
+    Blocks,       // Horizontal dependency
+    Accomplishes, // Goal fulfillment
+    Custom(String),
+}
+
+/// Longest name a custom relation may have.
+pub const MAX_CUSTOM_RELATION_NAME_LEN: usize = 64;
+
+impl RelationType {
+    const BUILT_IN: [RelationType; 11] = [
+        RelationType::Next,
+        RelationType::RelatedTo,
+        RelationType::Contradicts,
+        RelationType::Supports,
+        RelationType::Abstracts,
+        RelationType::DerivedFrom,
+        RelationType::CausedBy,
+        RelationType::EvolvedTo,
+        RelationType::IsSubTaskOf,
+        RelationType::Blocks,
+        RelationType::Accomplishes,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            RelationType::Next => "Next",
+            RelationType::RelatedTo => "RelatedTo",
+            RelationType::Contradicts => "Contradicts",
+            RelationType::Supports => "Supports",
+            RelationType::Abstracts => "Abstracts",
+            RelationType::DerivedFrom => "DerivedFrom",
+            RelationType::CausedBy => "CausedBy",
+            RelationType::EvolvedTo => "EvolvedTo",
+            RelationType::IsSubTaskOf => "IsSubTaskOf",
+            RelationType::Blocks => "Blocks",
+            RelationType::Accomplishes => "Accomplishes",
+            RelationType::Custom(name) => name,
+        }
+    }
+
+    /// The built-in relation named `s`, else a custom one. An empty name is `RelatedTo`.
+    pub fn from_str(s: &str) -> Self {
+        if s.is_empty() {
+            return RelationType::RelatedTo;
+        }
+        Self::BUILT_IN
+            .into_iter()
+            .find(|relation| relation.as_str() == s)
+            .unwrap_or_else(|| RelationType::Custom(s.to_string()))
+    }
+
+    pub fn is_custom(&self) -> bool {
+        matches!(self, RelationType::Custom(_))
+    }
+
+    /// Custom names start with a letter, hold only ASCII letters, digits, `_` and `-`,
+    /// and do not shadow a built-in relation.
+    pub fn is_valid_custom_name(name: &str) -> bool {
+        name.len() <= MAX_CUSTOM_RELATION_NAME_LEN
+            && name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            && !Self::BUILT_IN
+                .iter()
+                .any(|relation| relation.as_str() == name)
+    }
+}
+
+impl Serialize for RelationType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for RelationType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if name.is_empty() {
+            return Err(serde::de::Error::custom("relation name must not be empty"));
+        }
+        Ok(Self::from_str(&name))
+    }
+}
+
+/// How graph expansion treats a user's custom relation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelationDefinition {
+    pub user_id: String,
+    pub name: String,
+    /// Directed relations are only followed from source to target; undirected ones
+    /// both ways
+    #[serde(default = "default_relation_directed")]
+    pub directed: bool,
+    /// Share of a neighbour's expansion score carried across the edge, 0.0-1.0; 0.0
+    /// keeps the relation out of expansion
+    pub traversal_weight: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_relation_directed() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryDomain {
+    Agent,
+    #[default]
+    User,
+    Organization,
+}
+
+impl MemoryDomain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryDomain::Agent => "agent",
+            MemoryDomain::User => "user",
+            MemoryDomain::Organization => "organization",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    #[default]
+    Native,
+    Projected,
+    Derived,
+}
+
+impl EdgeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeKind::Native => "native",
+            EdgeKind::Projected => "projected",
+            EdgeKind::Derived => "derived",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Self {
+        match value {
+            "projected" => EdgeKind::Projected,
+            "derived" => EdgeKind::Derived,
+            _ => EdgeKind::Native,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareTarget {
+    Organization,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SharePolicy {
+    #[serde(default)]
+    pub contribute: bool,
+    #[serde(default)]
+    pub consume: bool,
+    #[serde(default)]
+    pub include_history: bool,
+    #[serde(default)]
+    pub targets: Vec<ShareTarget>,
+}
+
+/// A memory unit, or a whole stream, that its owner can share with others.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum ShareResource {
+    MemoryUnit(Uuid),
+    Stream(Uuid),
+}
+
+/// Who a share grant makes a resource visible to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum ShareGrantee {
+    User(String),
+    Group(String),
+}
+
+/// An ACL entry letting `grantee` read `resource` out of `owner_user_id`'s memory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryShareGrant {
+    pub id: Uuid,
+    pub owner_user_id: String,
+    pub resource: ShareResource,
+    pub grantee: ShareGrantee,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MemoryShareGrant {
+    pub fn new(owner_user_id: String, resource: ShareResource, grantee: ShareGrantee) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            owner_user_id,
+            resource,
+            grantee,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether `unit` falls under this grant.
+    pub fn covers(&self, unit: &MemoryUnit) -> bool {
+        unit.user_id == self.owner_user_id
+            && match self.resource {
+                ShareResource::MemoryUnit(id) => unit.id == id,
+                ShareResource::Stream(stream_id) => unit.stream_id == stream_id,
+            }
+    }
+}
+
+/// Adds `user_id` to, or removes it from, a sharing group.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GroupMembershipUpdate {
+    pub group_id: String,
+    pub user_id: String,
+    pub member: bool,
+}
+
+/// Limits an organization applies to every user and app beneath it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OrgPolicy {
+    /// Maximum number of events the organization may ingest. `None` is unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_events: Option<u64>,
+    /// Memory units older than this many days are deleted. `None` keeps them forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<u32>,
+}
+
+/// Replaces the policy of `org_id`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OrgPolicyUpdate {
+    pub org_id: String,
+    pub policy: OrgPolicy,
+}
+
+/// A user's entry in the bring-your-own-key registry. Content written for the user is
+/// encrypted with a data key that only the user's KMS key can unwrap.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserKeyRecord {
+    pub user_id: String,
+    /// Identifies the data key; recorded on every value sealed with it
+    pub key_id: String,
+    /// The user's key in their KMS, e.g. `vault:acme-user-42`
+    pub key_ref: String,
+    /// The data key as wrapped by the KMS. Dropped on revocation, which leaves everything
+    /// sealed with it unreadable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapped_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl UserKeyRecord {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some() || self.wrapped_key.is_none()
+    }
+}
+
+/// The ranking experiment variant that served a retrieval.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExperimentArm {
+    pub experiment: String,
+    pub variant: String,
+}
+
+/// Which retrieved memories a client went on to use. Order of `retrieved_ids` is the
+/// ranking the client was shown.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetrievalFeedback {
+    pub user_id: String,
+    pub retrieved_ids: Vec<Uuid>,
+    pub cited_ids: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arm: Option<ExperimentArm>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl RetrievalFeedback {
+    /// 1-based rank of the first cited memory among those retrieved.
+    pub fn first_cited_rank(&self) -> Option<usize> {
+        self.retrieved_ids
+            .iter()
+            .position(|id| self.cited_ids.contains(id))
+            .map(|index| index + 1)
+    }
+}
+
+/// Where content moderation acted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStage {
+    Ingest,
+    Retrieval,
+}
+
+/// What content moderation did with a flagged item.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationOutcome {
+    /// The event was refused at ingest
+    Blocked,
+    /// The event was stored with the verdict in its metadata
+    Tagged,
+    /// The memory was left out of a retrieval result
+    Stripped,
+}
+
+/// Audit entry for one item that content moderation blocked, tagged or stripped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModerationAuditRecord {
+    pub id: Uuid,
+    pub user_id: String,
+    /// App whose policy applied; `None` for the default policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    pub stage: ModerationStage,
+    pub outcome: ModerationOutcome,
+    /// The event (ingest) or memory unit (retrieval)
+    pub item_id: Uuid,
+    pub categories: Vec<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TaskStatus {
+    Pending,
+    InProgress,
+    Blocked(String), // Reason for being blocked
+    Completed,
+    Failed(String), // Reason for failure
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L3Task {
+    pub task_id: Uuid,
+    pub org_id: Option<String>,
+    pub user_id: String,
+    pub agent_id: Option<String>,
+    pub parent_id: Option<Uuid>, // Hierarchy support
+
+    pub title: String,
+    pub description: String,
+    pub status: TaskStatus,
+    pub progress: f32, // 0.0 - 1.0
+
+    pub dependencies: Vec<Uuid>, // Pre-requisites
+    pub context_refs: Vec<Uuid>, // Links to L1/L2 MemoryUnit IDs
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_summary: Option<String>,
+}
+
+impl L3Task {
+    pub fn new(
+        org_id: Option<String>,
+        user_id: String,
+        agent_id: Option<String>,
+        title: String,
+        description: String,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            task_id: Uuid::new_v4(),
+            org_id,
+            user_id,
+            agent_id,
+            parent_id: None,
+            title,
+            description,
+            status: TaskStatus::Pending,
+            progress: 0.0,
+            dependencies: Vec::new(),
+            context_refs: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            result_summary: None,
+        }
+    }
+}
+
+/// A status transition for an L3 task, replicated verbatim so every node applies
+/// the same timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L3TaskStatusUpdate {
+    pub user_id: String,
+    pub task_id: Uuid,
+    pub status: TaskStatus,
+    pub progress: Option<f32>,
+    pub result_summary: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl L3TaskStatusUpdate {
+    /// Apply this transition to `task`. Completing a task pins its progress to 1.0.
+    pub fn apply_to(&self, task: &mut L3Task) {
+        task.status = self.status.clone();
+        if let Some(progress) = self.progress {
+            task.progress = progress.clamp(0.0, 1.0);
+        }
+        if task.status == TaskStatus::Completed {
+            task.progress = 1.0;
+        }
+        if let Some(summary) = &self.result_summary {
+            task.result_summary = Some(summary.clone());
+        }
+        task.updated_at = self.updated_at;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl TaskPriority {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(TaskPriority::Low),
+            "normal" | "medium" => Some(TaskPriority::Normal),
+            "high" => Some(TaskPriority::High),
+            "urgent" | "critical" => Some(TaskPriority::Urgent),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskMetadata {
+    pub status: TaskStatus,
+    pub progress: f32, // 0.0 - 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Cron-ish schedule: `hourly`, `daily`, `weekly`, `monthly` or `every <n>{m,h,d,w}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
+    /// Set by the worker once `due_at` passes without the task finishing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub overdue: bool,
+}
+
+enum RecurrenceStep {
+    Minutes(i64),
+    Months(u32),
+}
+
+impl RecurrenceStep {
+    fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim().to_ascii_lowercase();
+        match spec.as_str() {
+            "hourly" => return Some(RecurrenceStep::Minutes(60)),
+            "daily" => return Some(RecurrenceStep::Minutes(24 * 60)),
+            "weekly" => return Some(RecurrenceStep::Minutes(7 * 24 * 60)),
+            "monthly" => return Some(RecurrenceStep::Months(1)),
+            _ => {}
+        }
+        let interval = spec.strip_prefix("every")?.trim().replace(' ', "");
+        let unit_start = interval.find(|c: char| !c.is_ascii_digit())?;
+        let count: i64 = interval[..unit_start].parse().ok().filter(|n| *n > 0)?;
+        let minutes = match &interval[unit_start..] {
+            "m" | "min" | "mins" | "minute" | "minutes" => 1,
+            "h" | "hour" | "hours" => 60,
+            "d" | "day" | "days" => 24 * 60,
+            "w" | "week" | "weeks" => 7 * 24 * 60,
+            _ => return None,
+        };
+        Some(RecurrenceStep::Minutes(count.checked_mul(minutes)?))
+    }
+}
+
+impl TaskMetadata {
+    pub fn new(status: TaskStatus, progress: f32) -> Self {
+        Self {
+            status,
+            progress,
+            due_at: None,
+            recurrence: None,
+            priority: None,
+            overdue: false,
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.status,
+            TaskStatus::Completed | TaskStatus::Cancelled | TaskStatus::Failed(_)
+        )
+    }
+
+    /// The first occurrence of the recurrence schedule, stepping from `due_at`, that
+    /// falls strictly after `after`. `None` when the task is not recurring or has no
+    /// due date.
+    pub fn next_due_at(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let due_at = self.due_at?;
+        match RecurrenceStep::parse(self.recurrence.as_deref()?)? {
+            RecurrenceStep::Minutes(minutes) => {
+                let step = chrono::Duration::minutes(minutes);
+                let elapsed = (after - due_at).num_minutes().max(0) / minutes;
+                let mut next = due_at + chrono::Duration::minutes(elapsed * minutes);
+                while next <= after {
+                    next += step;
+                }
+                Some(next)
+            }
+            RecurrenceStep::Months(months) => {
+                let mut next = due_at;
+                while next <= after {
+                    next = next.checked_add_months(chrono::Months::new(months))?;
+                }
+                Some(next)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskDeadlineEventKind {
+    Approaching,
+    Overdue,
+    Recurred,
+}
+
+/// Deadline notification emitted by the worker's task deadline cycle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDeadlineEvent {
+    pub kind: TaskDeadlineEventKind,
+    pub user_id: String,
+    pub unit_id: Uuid,
+    pub content: String,
+    pub due_at: DateTime<Utc>,
+    /// The next instance created for a recurring task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_unit_id: Option<Uuid>,
+    pub emitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source_id: Uuid,
+    pub target_id: Uuid,
+    pub user_id: String,
+    pub namespace_key: String,
+    pub source_namespace_key: String,
+    pub target_namespace_key: String,
+    pub edge_kind: EdgeKind,
+    pub relation: RelationType,
+    pub weight: f32,
+    pub transaction_time: DateTime<Utc>,
+}
+
+impl GraphEdge {
+    pub fn new(
+        user_id: String,
+        source: Uuid,
+        target: Uuid,
+        relation: RelationType,
+        weight: f32,
+    ) -> Self {
+        let namespace_key =
+            MemoryUnit::build_namespace_key(&MemoryDomain::User, None, Some(&user_id), None);
+        Self::new_scoped(
+            user_id,
+            source,
+            target,
+            relation,
+            weight,
+            namespace_key,
+            None,
+            None,
+            EdgeKind::Native,
+        )
+    }
+
+    pub fn new_scoped(
+        user_id: String,
+        source: Uuid,
+        target: Uuid,
+        relation: RelationType,
+        weight: f32,
+        namespace_key: String,
+        source_namespace_key: Option<String>,
+        target_namespace_key: Option<String>,
+        edge_kind: EdgeKind,
+    ) -> Self {
+        Self {
+            source_id: source,
+            target_id: target,
+            user_id,
+            namespace_key: namespace_key.clone(),
+            source_namespace_key: source_namespace_key.unwrap_or_else(|| namespace_key.clone()),
+            target_namespace_key: target_namespace_key.unwrap_or(namespace_key.clone()),
+            edge_kind,
+            relation,
+            weight,
+            transaction_time: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// Mean Earth radius used for great-circle distances.
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// A WGS84 coordinate in decimal degrees.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// `None` unless `lat` is within [-90, 90] and `lon` within [-180, 180].
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon))
+            .then_some(Self { lat, lon })
+    }
+
+    /// Read `metadata.location = {lat, lon}`; out-of-range coordinates are ignored.
+    pub fn from_metadata(metadata: &serde_json::Value) -> Option<Self> {
+        let location = metadata.get("location")?;
+        Self::new(
+            location.get("lat")?.as_f64()?,
+            location.get("lon")?.as_f64()?,
+        )
+    }
+
+    /// Great-circle (haversine) distance to `other`, in meters.
+    pub fn distance_meters(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+    }
+}
+
+/// A circle around a point, `radius` in meters; the `near` retrieval filter.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GeoRadius {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius: f64,
+}
+
+impl GeoRadius {
+    pub fn center(&self) -> GeoPoint {
+        GeoPoint {
+            lat: self.lat,
+            lon: self.lon,
+        }
+    }
+
+    /// Whether the center is a valid coordinate and the radius positive and finite.
+    pub fn is_valid(&self) -> bool {
+        GeoPoint::new(self.lat, self.lon).is_some() && self.radius.is_finite() && self.radius > 0.0
+    }
+
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        self.center().distance_meters(point) <= self.radius
+    }
+
+    /// `(min_lat, max_lat, min_lon, max_lon)` enclosing the circle, for index prefilters.
+    /// Longitude spans the full range when the circle reaches a pole or crosses the
+    /// antimeridian.
+    pub fn bounding_box(&self) -> (f64, f64, f64, f64) {
+        let d_lat = (self.radius / EARTH_RADIUS_METERS).to_degrees();
+        let min_lat = (self.lat - d_lat).max(-90.0);
+        let max_lat = (self.lat + d_lat).min(90.0);
+        if min_lat <= -90.0 || max_lat >= 90.0 {
+            return (min_lat, max_lat, -180.0, 180.0);
+        }
+        let d_lon = (self.radius / (EARTH_RADIUS_METERS * self.lat.to_radians().cos()))
+            .min(1.0)
+            .asin()
+            .to_degrees();
+        let (min_lon, max_lon) = (self.lon - d_lon, self.lon + d_lon);
+        if min_lon < -180.0 || max_lon > 180.0 {
+            return (min_lat, max_lat, -180.0, 180.0);
+        }
+        (min_lat, max_lat, min_lon, max_lon)
+    }
+}
+
+/// Metadata for multimodal assets (images, audio, etc.)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Asset {
+    pub storage_key: String, // The internal key (e.g., s3://bucket/uuid.png or local://uuid.png)
+    pub original_name: String,
+    pub asset_type: String, // e.g., "image/png", "audio/mpeg"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgetTargetKind {
+    MemoryUnit,
+    Event,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgetMode {
+    Logical,
+    Hard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgettingTombstone {
+    pub user_id: String,
+    pub org_id: Option<String>,
+    pub target_kind: ForgetTargetKind,
+    pub target_id: String,
+    pub reason_query: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_id: Option<String>,
+    pub mode: ForgetMode,
+}
+
+/// A memory unit moved to the trash. It stays restorable until `purge_after`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashedMemoryUnit {
+    pub user_id: String,
+    pub unit_id: Uuid,
+    pub deleted_at: DateTime<Utc>,
+    pub purge_after: DateTime<Utc>,
+}
+
+/// A typed value in a structured user profile.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ProfileValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    List(Vec<String>),
+}
+
+impl ProfileValue {
+    pub fn render(&self) -> String {
+        match self {
+            ProfileValue::Bool(value) => value.to_string(),
+            ProfileValue::Number(value) => value.to_string(),
+            ProfileValue::Text(value) => value.clone(),
+            ProfileValue::List(values) => values.join(", "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileValueSource {
+    #[default]
+    Extracted,
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProfileAttribute {
+    pub value: ProfileValue,
+    #[serde(default)]
+    pub source: ProfileValueSource,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Structured key-value profile (language, timezone, dietary preferences, ...) for one user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UserProfile {
+    pub user_id: String,
+    #[serde(default)]
+    pub attributes: std::collections::BTreeMap<String, ProfileAttribute>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserProfile {
+    pub fn new(user_id: String) -> Self {
+        Self {
+            user_id,
+            attributes: std::collections::BTreeMap::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Normalize a profile key to snake_case so extracted and manual keys collide.
+    pub fn normalize_key(key: &str) -> Option<String> {
+        let mut normalized = String::with_capacity(key.len());
+        for ch in key.trim().chars() {
+            if ch.is_alphanumeric() {
+                normalized.extend(ch.to_lowercase());
+            } else if !normalized.ends_with('_') {
+                normalized.push('_');
+            }
+        }
+        let normalized = normalized.trim_matches('_').to_string();
+        (!normalized.is_empty()).then_some(normalized)
+    }
+
+    /// Merge attributes extracted at `now` without overwriting manually set ones.
+    /// Returns true when any attribute changed.
+    pub fn merge_extracted(
+        &mut self,
+        extracted: HashMap<String, ProfileValue>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let mut changed = false;
+        for (key, value) in extracted {
+            let Some(key) = Self::normalize_key(&key) else {
+                continue;
+            };
+            match self.attributes.get(&key) {
+                Some(existing)
+                    if existing.source == ProfileValueSource::Manual || existing.value == value => {
+                }
+                _ => {
+                    self.attributes.insert(
+                        key,
+                        ProfileAttribute {
+                            value,
+                            source: ProfileValueSource::Extracted,
+                            updated_at: now,
+                        },
+                    );
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.updated_at = now;
+        }
+        changed
+    }
+
+    /// Render the profile as `key: value` lines for prompt/context injection.
+    pub fn render(&self) -> String {
+        self.attributes
+            .iter()
+            .map(|(key, attribute)| format!("{}: {}", key, attribute.value.render()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Represents a consolidated memory unit (L1/L2).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryType {
+    Factual,    // User facts and preferences
+    Procedural, // Agent experiences, reflections, and tool usage paths
+}
+
+impl Default for MemoryType {
+    fn default() -> Self {
+        Self::Factual
+    }
+}
+
+fn default_stored_fact_confidence() -> f32 {
+    0.5
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredMemoryFact {
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_name: Option<String>,
+    pub attribute: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_value: Option<String>,
+    pub change_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temporal_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polarity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence_span: Option<String>,
+    #[serde(default = "default_stored_fact_confidence")]
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterializationState {
+    Pending,
+    RetryScheduled,
+    Failed,
+    #[default]
+    Published,
+}
+
+fn default_memory_visibility() -> bool {
+    true
+}
+
+/// Layout version stamped on newly written [`MemoryUnit`] records. Bump it together
+/// with a new step in `memorose_core::migration` whenever stored units change shape.
+pub const MEMORY_UNIT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryUnit {
+    /// Layout version of the stored record; 0 for records written before versioning
+    #[serde(default)]
+    pub schema_version: u32,
+    pub id: Uuid,
+    pub org_id: Option<String>,
+    pub user_id: String,
+    pub agent_id: Option<String>,
+    pub stream_id: Uuid,
+    pub memory_type: MemoryType,
+    pub domain: MemoryDomain,
+    pub namespace_key: String,
+    #[serde(default)]
+    pub share_policy: SharePolicy,
+
+    /// Semantic content (compressed/summarized text)
+    pub content: String,
+
+    /// Vector embedding for retrieval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+
+    #[serde(default = "default_memory_visibility")]
+    pub visible: bool,
+
+    #[serde(default)]
+    pub materialization_state: MaterializationState,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub materialized_at: Option<DateTime<Utc>>,
+
+    /// Keywords for text indexing
+    pub keywords: Vec<String>,
+
+    /// Importance score (0.0 - 1.0) for forgetting mechanism
+    pub importance: f32,
+
+    /// Memory level (1: L1 Consolidated, 2: L2 Insight, etc.)
+    pub level: u8,
+
+    /// Pinned memories are exempt from importance decay and pruning, and rank higher
+    /// in retrieval
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+
+    pub transaction_time: DateTime<Utc>,
+    pub valid_time: Option<DateTime<Utc>>,
+    pub last_accessed_at: DateTime<Utc>,
+    pub access_count: u64,
+
+    /// Links to source Events or other MemoryUnits (Graph edges)
+    pub references: Vec<Uuid>,
+
+    /// Multimodal assets associated with this memory
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+
+    /// Cached structured facts extracted from this memory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extracted_facts: Vec<StoredMemoryFact>,
+
+    /// Emotions expressed in the memory, lowercase labels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emotions: Vec<String>,
+
+    /// Where the memory was recorded, taken from its source event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<GeoPoint>,
+
+    /// Task-specific metadata (status, progress)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_metadata: Option<TaskMetadata>,
+}
+
+impl MemoryUnit {
+    pub fn new(
+        org_id: Option<String>,
+        user_id: String,
+        agent_id: Option<String>,
+        stream_id: Uuid,
+        memory_type: MemoryType,
+        content: String,
+        embedding: Option<Vec<f32>>,
+    ) -> Self {
+        let domain = Self::infer_domain(agent_id.as_deref(), &memory_type);
+        Self::new_with_domain(
+            org_id,
+            user_id,
+            agent_id,
+            stream_id,
+            memory_type,
+            domain,
+            content,
+            embedding,
+        )
+    }
+
+    pub fn new_with_domain(
+        org_id: Option<String>,
+        user_id: String,
+        agent_id: Option<String>,
+        stream_id: Uuid,
+        memory_type: MemoryType,
+        domain: MemoryDomain,
+        content: String,
+        embedding: Option<Vec<f32>>,
+    ) -> Self {
+        let now = Utc::now();
+        let namespace_key = Self::build_namespace_key(
+            &domain,
+            org_id.as_deref(),
+            Some(&user_id),
+            agent_id.as_deref(),
+        );
+        Self {
+            schema_version: MEMORY_UNIT_SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            org_id,
+            user_id,
+            agent_id,
+            stream_id,
+            memory_type,
+            domain,
+            namespace_key,
+            share_policy: SharePolicy::default(),
+            content,
+            embedding,
+            visible: true,
+            materialization_state: MaterializationState::Published,
+            materialized_at: Some(now),
+            keywords: Vec::new(),
+            importance: 1.0, // Start with high importance
+            level: 1,        // Default to L1
+            pinned: false,
+            transaction_time: now,
+            valid_time: None,
+            last_accessed_at: now,
+            access_count: 0,
+            references: Vec::new(),
+            assets: Vec::new(),
+            extracted_facts: Vec::new(),
+            emotions: Vec::new(),
+            location: None,
+            task_metadata: None,
+        }
+    }
+
+    /// Whether the memory is tagged with any of `emotions`, compared case-insensitively.
+    pub fn has_any_emotion(&self, emotions: &[String]) -> bool {
+        emotions.iter().any(|wanted| {
+            self.emotions
+                .iter()
+                .any(|emotion| emotion.eq_ignore_ascii_case(wanted.trim()))
+        })
+    }
+
+    pub fn infer_domain(agent_id: Option<&str>, memory_type: &MemoryType) -> MemoryDomain {
+        if matches!(memory_type, MemoryType::Procedural) && agent_id.is_some() {
+            MemoryDomain::Agent
+        } else {
+            MemoryDomain::User
+        }
+    }
+
+    pub fn build_namespace_key(
+        domain: &MemoryDomain,
+        org_id: Option<&str>,
+        user_id: Option<&str>,
+        agent_id: Option<&str>,
+    ) -> String {
+        match domain {
+            // Three-domain direction:
+            // - Agent memory is scoped to the agent itself (optionally under an org)
+            // - User memory is scoped to the user itself (optionally under an org)
+            MemoryDomain::Agent => format!(
+                "agent:{}:{}",
+                org_id.unwrap_or("_global"),
+                agent_id.unwrap_or("_agent")
+            ),
+            MemoryDomain::User => format!(
+                "user:{}:{}",
+                org_id.unwrap_or("_global"),
+                user_id.unwrap_or("_anonymous")
+            ),
+            MemoryDomain::Organization => {
+                format!("org:{}", org_id.unwrap_or("_global"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let stream_id = Uuid::new_v4();
+        let content = EventContent::Text("Hello World".to_string());
+        let event = Event::new(None, "user1".into(), None, stream_id, content);
+
+        let json = serde_json::to_string(&event).expect("Failed to serialize");
+        let deserialized: Event = serde_json::from_str(&json).expect("Failed to deserialize");
+
+        assert_eq!(event.id, deserialized.id);
+        assert_eq!(event.stream_id, deserialized.stream_id);
+        assert_eq!(deserialized.user_id, "user1");
+    }
+
+    #[test]
+    fn test_event_conversation_turn_accessors() {
+        let mut event = Event::new(
+            None,
+            "u1".into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("hi".into()),
+        );
+        assert_eq!(event.role(), None);
+        assert!(!event.is_agent_event());
+
+        event.set_conversation_turn(Some(EventRole::Tool), Some("search".into()), Some(3));
+        assert_eq!(event.role(), Some(EventRole::Tool));
+        assert_eq!(event.speaker_name(), Some("search"));
+        assert_eq!(event.turn_index(), Some(3));
+        assert!(event.is_agent_event());
+
+        event.metadata["role"] = serde_json::json!("User");
+        assert_eq!(event.role(), Some(EventRole::User));
+        assert!(!event.is_agent_event());
+        event.metadata["agent_id"] = serde_json::json!("bot");
+        assert!(event.is_agent_event());
+    }
+
+    #[test]
+    fn test_geo_radius_contains_points_within_distance() {
+        assert!(GeoPoint::new(91.0, 0.0).is_none());
+        assert!(GeoPoint::new(0.0, -181.0).is_none());
+
+        let paris = GeoPoint::new(48.8566, 2.3522).unwrap();
+        let london = GeoPoint::new(51.5074, -0.1278).unwrap();
+        let distance = paris.distance_meters(&london);
+        assert!((340_000.0..345_000.0).contains(&distance), "{distance}");
+
+        let near_paris = GeoRadius {
+            lat: paris.lat,
+            lon: paris.lon,
+            radius: 10_000.0,
+        };
+        assert!(near_paris.is_valid());
+        assert!(near_paris.contains(&GeoPoint::new(48.8606, 2.3376).unwrap()));
+        assert!(!near_paris.contains(&london));
+        let (min_lat, max_lat, min_lon, max_lon) = near_paris.bounding_box();
+        assert!(min_lat < paris.lat && paris.lat < max_lat);
+        assert!(min_lon < paris.lon && paris.lon < max_lon);
+        assert!(!(min_lat..=max_lat).contains(&london.lat));
+
+        let mut event = Event::new(
+            None,
+            "u1".into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("hi".into()),
+        );
+        assert_eq!(event.location(), None);
+        event.set_location(paris);
+        assert_eq!(event.location(), Some(paris));
+        event.metadata["location"] = serde_json::json!({"lat": 120.0, "lon": 0.0});
+        assert_eq!(event.location(), None);
+    }
+
+    #[test]
+    fn test_memory_stream_chat_window_keeps_latest_turns_within_budget() {
+        let mut stream = MemoryStream::new("u1".into(), Some("app".into()), None);
+        let turn = |role, content: &str| ChatTurnRecord {
+            role,
+            content: content.to_string(),
+            at: Utc::now(),
+            event_id: None,
+        };
+        stream.push_chat_turns(
+            vec![
+                turn(EventRole::User, "first question"),
+                turn(EventRole::Assistant, "first answer"),
+                turn(EventRole::User, "second"),
+                turn(EventRole::Assistant, "reply"),
+            ],
+            3,
+        );
+        assert_eq!(stream.chat_turns.len(), 3);
+        assert_eq!(stream.chat_turns[0].content, "first answer");
+
+        let window: Vec<&str> = stream
+            .chat_window(10, 100)
+            .iter()
+            .map(|turn| turn.content.as_str())
+            .collect();
+        assert_eq!(window, ["first answer", "second", "reply"]);
+        assert_eq!(stream.chat_window(1, 100).len(), 1);
+        // "reply" and "second" fit in 11 characters, "first answer" does not
+        assert_eq!(stream.chat_window(10, 11).len(), 2);
+        assert!(stream.chat_window(10, 0).is_empty());
+    }
+
+    #[test]
+    fn test_bitemporal_fields() {
+        let now = Utc::now();
+        let valid_time = now - chrono::Duration::days(7);
+
+        let mut unit = MemoryUnit::new(
+            None,
+            "u1".into(),
+            None,
+            Uuid::new_v4(),
+            MemoryType::Factual,
+            "text".into(),
+            None,
+        );
+        unit.valid_time = Some(valid_time);
+
+        assert_eq!(unit.valid_time, Some(valid_time));
+
+        let mut event = Event::new(
+            None,
+            "u1".into(),
+            None,
+            Uuid::new_v4(),
+            EventContent::Text("test".into()),
+        );
+        event.valid_time = Some(valid_time);
+        assert_eq!(event.valid_time, Some(valid_time));
+    }
+
+    #[test]
+    fn test_memory_unit_new_infers_agent_domain() {
+        let unit = MemoryUnit::new(
+            Some("org1".into()),
+            "u1".into(),
+            Some("agent1".into()),
+            Uuid::new_v4(),
+            MemoryType::Procedural,
+            "tool trace".into(),
+            None,
+        );
+
+        assert_eq!(unit.domain, MemoryDomain::Agent);
+        assert_eq!(unit.namespace_key, "agent:org1:agent1");
+    }
+
+    #[test]
+    fn test_relation_type_to_str() {
+        assert_eq!(RelationType::Next.as_str(), "Next");
+        assert_eq!(RelationType::IsSubTaskOf.as_str(), "IsSubTaskOf");
+        assert_eq!(RelationType::Contradicts.as_str(), "Contradicts");
+        assert_eq!(RelationType::DerivedFrom.as_str(), "DerivedFrom");
+        assert_eq!(RelationType::EvolvedTo.as_str(), "EvolvedTo");
+        assert_eq!(RelationType::Supports.as_str(), "Supports");
+        assert_eq!(RelationType::Abstracts.as_str(), "Abstracts");
+        assert_eq!(RelationType::CausedBy.as_str(), "CausedBy");
+        assert_eq!(RelationType::Blocks.as_str(), "Blocks");
+        assert_eq!(RelationType::Accomplishes.as_str(), "Accomplishes");
+        assert_eq!(RelationType::RelatedTo.as_str(), "RelatedTo");
+    }
+
+    #[test]
+    fn test_relation_type_from_str() {
+        assert_eq!(RelationType::from_str("Next"), RelationType::Next);
+        assert_eq!(
+            RelationType::from_str("IsSubTaskOf"),
+            RelationType::IsSubTaskOf
+        );
+        assert_eq!(
+            RelationType::from_str("Contradicts"),
+            RelationType::Contradicts
+        );
+        assert_eq!(
+            RelationType::from_str("DerivedFrom"),
+            RelationType::DerivedFrom
+        );
+        assert_eq!(RelationType::from_str("EvolvedTo"), RelationType::EvolvedTo);
+        assert_eq!(RelationType::from_str("Supports"), RelationType::Supports);
+        assert_eq!(RelationType::from_str("Abstracts"), RelationType::Abstracts);
+        assert_eq!(RelationType::from_str("CausedBy"), RelationType::CausedBy);
+        assert_eq!(RelationType::from_str("Blocks"), RelationType::Blocks);
+        assert_eq!(
+            RelationType::from_str("Accomplishes"),
+            RelationType::Accomplishes
+        );
+        assert_eq!(RelationType::from_str("RelatedTo"), RelationType::RelatedTo);
+        assert_eq!(
+            RelationType::from_str("UnknownString"),
+            RelationType::Custom("UnknownString".into())
+        );
+        assert_eq!(RelationType::from_str(""), RelationType::RelatedTo);
+    }
+
+    #[test]
+    fn test_custom_relation_serializes_as_its_name() {
+        let custom = RelationType::Custom("mentors".into());
+        assert_eq!(serde_json::to_string(&custom).unwrap(), "\"mentors\"");
+        assert_eq!(
+            serde_json::to_string(&RelationType::DerivedFrom).unwrap(),
+            "\"DerivedFrom\""
+        );
+        assert_eq!(
+            serde_json::from_str::<RelationType>("\"mentors\"").unwrap(),
+            custom
+        );
+        assert_eq!(
+            serde_json::from_str::<RelationType>("\"Blocks\"").unwrap(),
+            RelationType::Blocks
+        );
+        assert!(serde_json::from_str::<RelationType>("\"\"").is_err());
+
+        assert!(RelationType::is_valid_custom_name("reports-to"));
+        assert!(!RelationType::is_valid_custom_name("Supports"));
+        assert!(!RelationType::is_valid_custom_name("2nd_degree"));
+        assert!(!RelationType::is_valid_custom_name("knows' OR 1=1"));
+        assert!(!RelationType::is_valid_custom_name(""));
+    }
+
+    #[test]
+    fn test_memory_type_default() {
+        assert_eq!(MemoryType::default(), MemoryType::Factual);
+    }
+
+    #[test]
+    fn test_forgetting_tombstone() {
+        let tombstone = ForgettingTombstone {
+            user_id: "u1".into(),
+            org_id: None,
+            target_kind: ForgetTargetKind::Event,
+            target_id: "evt1".into(),
+            reason_query: "forget about xyz".into(),
+            created_at: Utc::now(),
+            preview_id: None,
+            mode: ForgetMode::Logical,
+        };
+        assert_eq!(tombstone.target_kind, ForgetTargetKind::Event);
+        assert_eq!(tombstone.mode, ForgetMode::Logical);
+        assert_eq!(tombstone.user_id, "u1");
+    }
+
+    #[test]
+    fn test_asset() {
+        let asset = Asset {
+            storage_key: "local://uuid.png".into(),
+            original_name: "test.png".into(),
+            asset_type: "image/png".into(),
+            description: None,
+            metadata: std::collections::HashMap::new(),
+        };
+        assert_eq!(asset.asset_type, "image/png");
+        assert_eq!(asset.storage_key, "local://uuid.png");
+    }
+
+    #[test]
+    fn test_l3_task() {
+        let task = L3Task {
+            task_id: Uuid::new_v4(),
+            org_id: None,
+            user_id: "user1".into(),
+            agent_id: None,
+            parent_id: None,
+            title: "Task".into(),
+            description: "Task desc".into(),
+            status: TaskStatus::Pending,
+            progress: 0.0,
+            dependencies: vec![],
+            context_refs: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            result_summary: None,
+        };
+        assert_eq!(task.user_id, "user1");
+        assert!(matches!(task.status, TaskStatus::Pending));
+    }
+
+    #[test]
+    fn test_user_profile_merge_keeps_manual_values() {
+        let mut profile = UserProfile::new("user1".into());
+        profile.attributes.insert(
+            "timezone".into(),
+            ProfileAttribute {
+                value: ProfileValue::Text("Europe/Berlin".into()),
+                source: ProfileValueSource::Manual,
+                updated_at: Utc::now(),
+            },
+        );
+
+        let changed = profile.merge_extracted(
+            HashMap::from([
+                ("Time Zone".to_string(), ProfileValue::Text("UTC".into())),
+                ("timezone".to_string(), ProfileValue::Text("UTC".into())),
+                (
+                    "dietary-preferences".to_string(),
+                    ProfileValue::List(vec!["vegetarian".into()]),
+                ),
+            ]),
+            Utc::now(),
+        );
+
+        assert!(changed);
+        assert_eq!(
+            profile.attributes["timezone"].value,
+            ProfileValue::Text("Europe/Berlin".into())
+        );
+        assert_eq!(
+            profile.attributes["time_zone"].source,
+            ProfileValueSource::Extracted
+        );
+        assert_eq!(
+            profile.attributes["dietary_preferences"].value.render(),
+            "vegetarian"
+        );
+        assert!(!profile.merge_extracted(
+            HashMap::from([(
+                "dietary_preferences".to_string(),
+                ProfileValue::List(vec!["vegetarian".into()]),
+            )]),
+            Utc::now()
+        ));
+
+        let json: serde_json::Value =
+            serde_json::to_value(&profile.attributes["timezone"]).unwrap();
+        assert_eq!(json["value"], "Europe/Berlin");
+        assert_eq!(json["source"], "manual");
+    }
+
+    #[test]
+    fn test_task_metadata_next_due_at_steps_past_reference_time() {
+        use chrono::TimeZone;
+
+        let due_at = Utc.with_ymd_and_hms(2026, 1, 31, 9, 0, 0).unwrap();
+        let mut meta = TaskMetadata::new(TaskStatus::Completed, 1.0);
+        meta.due_at = Some(due_at);
+        assert_eq!(meta.next_due_at(due_at), None);
+
+        meta.recurrence = Some("every 2h".into());
+        assert_eq!(
+            meta.next_due_at(due_at + chrono::Duration::minutes(150)),
+            Some(due_at + chrono::Duration::hours(4))
+        );
+        meta.recurrence = Some("daily".into());
+        assert_eq!(
+            meta.next_due_at(due_at - chrono::Duration::days(3)),
+            Some(due_at)
+        );
+        meta.recurrence = Some("monthly".into());
+        assert_eq!(
+            meta.next_due_at(due_at),
+            Some(Utc.with_ymd_and_hms(2026, 2, 28, 9, 0, 0).unwrap())
+        );
+        meta.recurrence = Some("every fortnight".into());
+        assert_eq!(meta.next_due_at(due_at), None);
+
+        assert_eq!(TaskPriority::parse(" HIGH "), Some(TaskPriority::High));
+        assert!(TaskPriority::Urgent > TaskPriority::Low);
+        let json = serde_json::to_value(TaskMetadata::new(TaskStatus::Pending, 0.0)).unwrap();
+        assert!(json.get("overdue").is_none());
+    }
+}
@@ -29,7 +29,7 @@ import { api } from "@/lib/api";
 import { useOrgScope } from "@/lib/org-scope";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Skeleton } from "@/components/ui/skeleton";
-import type { GraphData } from "@/lib/types";
+import type { GraphData, SelfEvalResponse } from "@/lib/types";
 import { motion } from "framer-motion";
 import { useTranslations } from "next-intl";
 import { DashboardHero } from "@/components/dashboard-chrome";
@@ -170,6 +170,89 @@ function ImportanceHistogram({
   );
 }
 
+function SelfEvalTrend({ className = "" }: { className?: string }) {
+  const t = useTranslations("Metrics");
+  const [data, setData] = useState<SelfEvalResponse | null>(null);
+
+  useEffect(() => {
+    api.selfEval().then(setData).catch(() => {
+      // ignore
+    });
+  }, []);
+
+  // Oldest first, so the chart reads left to right
+  const points = (data?.runs ?? [])
+    .map(({ report }) => ({
+      run: new Date(report.run_at).toLocaleString(),
+      hitRate: Math.round(report.hit_rate * 1000) / 10,
+      mrr: Math.round(report.mean_reciprocal_rank * 1000) / 1000,
+    }))
+    .reverse();
+  const latest = data?.runs[0]?.report;
+
+  return (
+    <Card className={`glass-card flex flex-col border-white/[0.04] ${className}`}>
+      <CardHeader className="pb-0 flex-shrink-0">
+        <CardTitle className="label-xs">{t("selfEval.title", { topK: data?.top_k ?? 0 })}</CardTitle>
+      </CardHeader>
+      <CardContent className="flex-1 pt-6">
+        {!latest ? (
+          <p className="label-xs">
+            {data && data.interval_secs === 0 ? t("selfEval.disabled") : t("selfEval.empty")}
+          </p>
+        ) : (
+          <div className="grid h-full grid-cols-1 gap-4 md:grid-cols-[1fr_220px]">
+            <div className="h-full min-h-[180px] w-full">
+              <ResponsiveContainer width="100%" height="100%">
+                <AreaChart data={points} margin={{ top: 10, right: 10, left: -20, bottom: 0 }}>
+                  <defs>
+                    <linearGradient id="colorHitRate" x1="0" y1="0" x2="0" y2="1">
+                      <stop offset="5%" stopColor="hsl(142 70% 50%)" stopOpacity={0.3} />
+                      <stop offset="95%" stopColor="hsl(142 70% 50%)" stopOpacity={0} />
+                    </linearGradient>
+                  </defs>
+                  <XAxis dataKey="run" hide />
+                  <YAxis domain={[0, 100]} hide />
+                  <Tooltip
+                    contentStyle={CHART_TOOLTIP_STYLE}
+                    itemStyle={{ ...CHART_TOOLTIP_ITEM_STYLE, fontFamily: "monospace" }}
+                    cursor={{ stroke: 'rgba(255,255,255,0.1)', strokeWidth: 1 }}
+                  />
+                  <Area
+                    type="monotone"
+                    dataKey="hitRate"
+                    name={t("selfEval.hitRate")}
+                    unit="%"
+                    stroke="hsl(142 70% 50%)"
+                    strokeWidth={2}
+                    fillOpacity={1}
+                    fill="url(#colorHitRate)"
+                    activeDot={{ r: 4, strokeWidth: 0, fill: "hsl(142 70% 55%)" }}
+                  />
+                </AreaChart>
+              </ResponsiveContainer>
+            </div>
+            <div className="space-y-3">
+              {[
+                { label: t("selfEval.hitRate"), value: `${(latest.hit_rate * 100).toFixed(1)}%` },
+                { label: t("selfEval.mrr"), value: latest.mean_reciprocal_rank.toFixed(3) },
+                { label: t("selfEval.questions"), value: formatNumber(latest.questions) },
+                { label: t("selfEval.misses"), value: formatNumber(latest.misses) },
+                { label: t("selfEval.runs"), value: formatNumber(data?.runs.length ?? 0) },
+              ].map((row) => (
+                <div key={row.label} className="flex items-center justify-between gap-4">
+                  <span className="text-sm text-muted-foreground">{row.label}</span>
+                  <span className="font-mono text-sm text-foreground/80">{row.value}</span>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
+
 function WorkerStatus({ config, className = "" }: { config: NonNullable<ReturnType<typeof useClusterStatus>["data"]>, className?: string }) {
   const t = useTranslations("Metrics");
   // eslint-disable-next-line @typescript-eslint/no-explicit-any
@@ -314,6 +397,8 @@ export default function MetricsPage() {
             <RelationDistribution graphData={graphData} className="min-h-[280px]" />
           </div>
 
+          <SelfEvalTrend className="min-h-[260px]" />
+
           {textIndexMetrics && (
             <div className="space-y-3">
               <div className="grid grid-cols-1 gap-2 sm:grid-cols-2 xl:grid-cols-4">
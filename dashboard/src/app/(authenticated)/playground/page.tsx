@@ -106,6 +106,8 @@ function ChatPanel() {
   const { orgId } = useOrgScope();
   const scrollRef = useRef<HTMLDivElement>(null);
   const streamingMessageRef = useRef<string>("");
+  // One chat session per panel, so follow-up messages see the earlier turns
+  const sessionIdRef = useRef<string>(crypto.randomUUID());
   const scopedOrgId = orgId.trim();
 
   useEffect(() => {
@@ -134,15 +136,6 @@ function ChatPanel() {
     streamingMessageRef.current = "";
 
     try {
-      await api.ingestEvent({
-        user_id: currentUserId,
-        stream_id: "chat",
-        content: {
-          type: "text",
-          data: messageContent,
-        },
-      });
-
       const response = await fetch("/v1/dashboard/chat", {
         method: "POST",
         headers: {
@@ -154,6 +147,7 @@ function ChatPanel() {
           user_id: currentUserId,
           ...(scopedOrgId ? { org_id: scopedOrgId } : {}),
           context_limit: 5,
+          stream_id: sessionIdRef.current,
         }),
       });
 
@@ -355,6 +355,9 @@ export const api = {
       `/organizations/${encodeURIComponent(orgId)}/knowledge/metrics`
     ),
 
+  selfEval: (limit = 50) =>
+    fetchAPI<import("./types").SelfEvalResponse>(`/self-eval?limit=${limit}`),
+
   // Fixed: was using fetchAPI which adds /v1/dashboard prefix incorrectly
   getTaskTree: (user_id: string) =>
     fetchRaw<import("./types").GoalTree[]>(`/v1/users/${user_id}/tasks/tree`),
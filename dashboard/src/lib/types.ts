@@ -31,6 +31,58 @@ export interface TextIndexMetrics {
   commit_latency_total_ms: number;
 }
 
+export interface LlmOutputMetrics {
+  requests: number;
+  parse_failures: number;
+  retries: number;
+  recovered: number;
+  exhausted: number;
+}
+
+export interface LlmRateLimit {
+  provider: string;
+  requests_per_minute: number;
+  concurrency_limit: number;
+  max_concurrency: number;
+  in_flight: number;
+  throttled_total: number;
+  retries_total: number;
+  paused_ms: number;
+}
+
+export interface SummaryTierMetrics {
+  local: number;
+  remote: number;
+  escalated_length: number;
+  escalated_entropy: number;
+  escalated_multimodal: number;
+  escalated_local_failed: number;
+}
+
+export interface SelfEvalMiss {
+  user_id: string;
+  unit_id: string;
+  question: string;
+}
+
+export interface SelfEvalReport {
+  run_at: string;
+  sampled: number;
+  questions: number;
+  hits: number;
+  misses: number;
+  hit_rate: number;
+  mean_reciprocal_rank: number;
+  top_k: number;
+  missed?: SelfEvalMiss[];
+}
+
+export interface SelfEvalResponse {
+  interval_secs: number;
+  top_k: number;
+  runs: Array<{ shard_id: number; report: SelfEvalReport }>;
+}
+
 export interface WorkerInsightConfig {
   insight_interval_ms: number;
   insight_min_pending_tokens: number;
@@ -91,6 +143,9 @@ export interface Stats {
   total_memory_units: number;
   total_edges: number;
   text_index_metrics?: TextIndexMetrics;
+  llm_output_metrics?: LlmOutputMetrics;
+  llm_rate_limits?: LlmRateLimit[];
+  summary_tier_metrics?: SummaryTierMetrics;
   rac_metrics?: {
     fact_extraction_attempt_total: number;
     fact_extraction_success_total: number;
@@ -581,6 +581,7 @@ initialize_single_node() {
 join_cluster_node() {
     local joiner_id="$1"
     local joiner_addr="$2"
+    local joiner_http_addr="$3"
     local response
 
     log_info "Joining node ${joiner_id} (${joiner_addr})..."
@@ -589,7 +590,7 @@ join_cluster_node() {
             "${CLUSTER_AUTH_ARGS[@]}" \
             -X POST "http://127.0.0.1:3000/v1/cluster/join" \
             -H "Content-Type: application/json" \
-            -d "{\"node_id\": ${joiner_id}, \"address\": \"${joiner_addr}\"}" 2>&1 || true
+            -d "{\"node_id\": ${joiner_id}, \"address\": \"${joiner_addr}\", \"http_addr\": \"${joiner_http_addr}\"}" 2>&1 || true
     )"
     log_info "Join response for node ${joiner_id}: ${response}"
 }
@@ -633,9 +634,9 @@ start_cluster() {
         log_info "Bootstrapping fresh cluster..."
         initialize_single_node
         sleep 3
-        join_cluster_node 2 "127.0.0.1:5002"
+        join_cluster_node 2 "127.0.0.1:5002" "127.0.0.1:3001"
         sleep 1
-        join_cluster_node 3 "127.0.0.1:5003"
+        join_cluster_node 3 "127.0.0.1:5003" "127.0.0.1:3002"
         sleep 1
     fi
 